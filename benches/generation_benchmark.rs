@@ -6,6 +6,7 @@ use rust_bert::gpt2::{
     Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
 use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::generation_utils::NoRepeatNgramScope;
 use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
 use rust_bert::resources::RemoteResource;
 use std::time::{Duration, Instant};
@@ -33,10 +34,19 @@ fn create_text_generation_model() -> TextGenerationModel {
         repetition_penalty: 1.0,
         length_penalty: 1.0,
         no_repeat_ngram_size: 3,
+        no_repeat_ngram_scope: NoRepeatNgramScope::FullSequence,
         num_beam_groups: None,
         diversity_penalty: None,
+        penalty_alpha: None,
+        typical_p: None,
+        eta_cutoff: None,
+        epsilon_cutoff: None,
         num_return_sequences: 5,
         device: Device::cuda_if_available(),
+        kind: None,
+        stop_sequences: Vec::new(),
+        bad_word_ids: Vec::new(),
+        sequence_bias: Vec::new(),
     };
     TextGenerationModel::new(config).unwrap()
 }
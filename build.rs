@@ -24,6 +24,13 @@ fn main() {
             println!("cargo:rustc-link-arg=-Wl,--copy-dt-needed-entries");
             println!("cargo:rustc-link-arg=-ltorch");
         }
+        "macos" => {
+            // Node.js addons resolve the N-API symbols when loaded by the Node.js process
+            if std::env::var_os("CARGO_FEATURE_NODE").is_some() {
+                println!("cargo:rustc-cdylib-link-arg=-undefined");
+                println!("cargo:rustc-cdylib-link-arg=dynamic_lookup");
+            }
+        }
         _ => {}
     }
 }
@@ -54,11 +54,13 @@ pub enum Activation {
     gelu,
     /// Rectified Linear Unit
     relu,
-    /// Swish ([Ramachandran, 2017](https://arxiv.org/abs/1710.05941))
+    /// Swish ([Ramachandran, 2017](https://arxiv.org/abs/1710.05941)), also known as SiLU
+    #[serde(alias = "silu")]
     swish,
     /// Mish ([Misra, 2019](https://arxiv.org/abs/1908.08681))
     mish,
     /// Gaussian Error Linear Unit (New) ([Hendrycks et al., 2016,](https://arxiv.org/abs/1606.08415))
+    #[serde(alias = "gelu_pytorch_tanh")]
     gelu_new,
     /// Tanh
     tanh,
@@ -0,0 +1,421 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Shared attention implementations
+//! The attention layers of the models project their inputs to queries, keys and values (with their own weights,
+//! caches and position encodings) and delegate the attention itself to an implementation of the `Attention` trait:
+//! - `DenseAttention`: every query attends to all keys, optionally with a causal mask (BERT, BART, GPT-Neo global layers)
+//! - `LocalAttention`: causal attention over a sliding window of the previous keys (GPT-Neo local layers)
+//! - `StridedSparseAttention`: fixed sparse pattern where queries attend to the keys of their block and to the last key
+//!   of every block (summary positions, following [Child et al.](https://arxiv.org/abs/1904.10509))
+//!
+//! Optimizations of the attention computation therefore apply to all models using these implementations. In
+//! particular, the fused scaled dot-product attention kernel of Torch (using flash or memory-efficient attention
+//! when available) can be enabled for inference. It is only used when the attention weights are not returned, outside
+//! of training and when FP32 accumulation of half precision attention is disabled.
+//!
+//! ```no_run
+//! use rust_bert::attention::set_fused_attention;
+//! # use rust_bert::pipelines::text_generation::TextGenerationModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let model = TextGenerationModel::new(Default::default())?;
+//! set_fused_attention(true);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::dropout::Dropout;
+use crate::common::kind::get_min;
+use crate::common::precision::{accumulation_kind, attention_output, attention_scores};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tch::{Device, Kind, Scalar, Tensor};
+
+static FUSED_ATTENTION: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the fused scaled dot-product attention kernel for inference (disabled by default)
+///
+/// # Arguments
+///
+/// * `enabled` - flag indicating if the fused attention kernel should be used when possible
+pub fn set_fused_attention(enabled: bool) {
+    FUSED_ATTENTION.store(enabled, Ordering::Relaxed);
+}
+
+/// Checks if the fused scaled dot-product attention kernel is used for inference
+pub fn fused_attention() -> bool {
+    FUSED_ATTENTION.load(Ordering::Relaxed)
+}
+
+/// # Attention over projected queries, keys and values
+pub trait Attention: Send + Sync {
+    /// Boolean mask of the keys each query may attend to (true for allowed positions), of shape
+    /// (*query_length*, *key_length*), or `None` if all keys are allowed. Queries are aligned with the last keys
+    /// (the previous keys being cached during generation).
+    ///
+    /// # Arguments
+    ///
+    /// * `query_length` - number of queries
+    /// * `key_length` - number of keys
+    /// * `device` - device to create the mask on
+    fn pattern_mask(&self, query_length: i64, key_length: i64, device: Device) -> Option<Tensor>;
+
+    /// Forward pass through the attention
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query of shape (*batch size*, *num_heads*, *query_length*, *head_dim*)
+    /// * `key` - key of shape (*batch size*, *num_heads*, *key_length*, *head_dim*)
+    /// * `value` - value of shape (*batch size*, *num_heads*, *key_length*, *head_dim*)
+    /// * `attention_mask` - optional additive mask broadcastable to the attention scores (e.g. padding mask)
+    /// * `output_attentions` - flag indicating if the attention weights should be returned
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` attention output of shape (*batch size*, *num_heads*, *query_length*, *head_dim*)
+    /// * `Option<Tensor>` attention weights of shape (*batch size*, *num_heads*, *query_length*, *key_length*) if `output_attentions` is true
+    fn forward_t(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        value: &Tensor,
+        attention_mask: Option<&Tensor>,
+        output_attentions: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>);
+}
+
+/// Value of the attention scores masked by the attention pattern. Kept finite so that queries with no allowed key
+/// once padding is masked (e.g. left-padded positions) do not produce NaN values.
+fn pattern_mask_value(kind: Kind) -> Scalar {
+    match kind {
+        Kind::Float | Kind::Double => Scalar::float(-1e9),
+        _ => get_min(kind).unwrap_or_else(|_| Scalar::float(-1e4)),
+    }
+}
+
+/// Shifts the pattern masks so that queries are aligned with the last keys
+fn query_key_positions(query_length: i64, key_length: i64, device: Device) -> (Tensor, Tensor) {
+    let query_positions =
+        Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+            .unsqueeze(-1);
+    let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+    (query_positions, key_positions)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn scaled_dot_product(
+    query: &Tensor,
+    key: &Tensor,
+    value: &Tensor,
+    scale: Option<f64>,
+    pattern_mask: Option<Tensor>,
+    attention_mask: Option<&Tensor>,
+    dropout: &Dropout,
+    output_attentions: bool,
+    train: bool,
+) -> (Tensor, Option<Tensor>) {
+    let kind = query.kind();
+    if fused_attention()
+        && !output_attentions
+        && !train
+        && (key.kind() == kind)
+        && (value.kind() == kind)
+        && accumulation_kind(kind).is_none()
+    {
+        let mask = match (pattern_mask, attention_mask) {
+            (None, None) => None,
+            (Some(pattern_mask), None) => Some(pattern_mask),
+            (None, Some(attention_mask)) => Some(attention_mask.to_kind(kind)),
+            (Some(pattern_mask), Some(attention_mask)) => Some(
+                attention_mask.to_kind(kind)
+                    + Tensor::zeros(pattern_mask.size(), (kind, query.device()))
+                        .masked_fill(&pattern_mask.logical_not(), pattern_mask_value(kind)),
+            ),
+        };
+        // The fused kernel scales the scores by the inverse square root of the head dimension
+        let head_dim = query.size()[3] as f64;
+        let query = query * (scale.unwrap_or(1.0) * head_dim.sqrt());
+        let output = query.scaled_dot_product_attention(key, value, mask, 0.0, false);
+        return (output, None);
+    }
+
+    let mut scores = attention_scores(query, &key.transpose(-1, -2));
+    if let Some(scale) = scale {
+        scores *= scale;
+    }
+    if let Some(pattern_mask) = pattern_mask {
+        let mask_value = pattern_mask_value(scores.kind());
+        scores = scores.masked_fill(&pattern_mask.logical_not(), mask_value);
+    }
+    if let Some(attention_mask) = attention_mask {
+        scores = scores + attention_mask;
+    }
+    let weights = scores.softmax(-1, scores.kind());
+    let weights = match accumulation_kind(value.kind()) {
+        Some(_) => weights,
+        None => weights.to_kind(value.kind()),
+    };
+    let output = attention_output(&weights.apply_t(dropout, train), value);
+    (
+        output,
+        if output_attentions {
+            Some(weights)
+        } else {
+            None
+        },
+    )
+}
+
+#[derive(Debug)]
+/// # Dense attention
+/// Every query attends to all keys (or to the previous keys if causal).
+pub struct DenseAttention {
+    scale: Option<f64>,
+    causal: bool,
+    dropout: Dropout,
+}
+
+impl DenseAttention {
+    /// Creates a dense attention
+    ///
+    /// # Arguments
+    ///
+    /// * `dropout_prob` - dropout probability of the attention weights
+    /// * `scale` - optional factor applied to the attention scores (models scaling their queries beforehand use `None`)
+    /// * `causal` - flag indicating if queries only attend to the previous keys
+    pub fn new(dropout_prob: f64, scale: Option<f64>, causal: bool) -> DenseAttention {
+        DenseAttention {
+            scale,
+            causal,
+            dropout: Dropout::new(dropout_prob),
+        }
+    }
+}
+
+impl Attention for DenseAttention {
+    fn pattern_mask(&self, query_length: i64, key_length: i64, device: Device) -> Option<Tensor> {
+        if self.causal {
+            let (query_positions, key_positions) =
+                query_key_positions(query_length, key_length, device);
+            Some(key_positions.le_tensor(&query_positions))
+        } else {
+            None
+        }
+    }
+
+    fn forward_t(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        value: &Tensor,
+        attention_mask: Option<&Tensor>,
+        output_attentions: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let pattern_mask = self.pattern_mask(query.size()[2], key.size()[2], query.device());
+        scaled_dot_product(
+            query,
+            key,
+            value,
+            self.scale,
+            pattern_mask,
+            attention_mask,
+            &self.dropout,
+            output_attentions,
+            train,
+        )
+    }
+}
+
+#[derive(Debug)]
+/// # Local attention
+/// Causal attention where every query attends to the `window_size` previous keys (including its own position).
+pub struct LocalAttention {
+    window_size: i64,
+    scale: Option<f64>,
+    dropout: Dropout,
+}
+
+impl LocalAttention {
+    /// Creates a local attention
+    ///
+    /// # Arguments
+    ///
+    /// * `window_size` - number of keys each query attends to
+    /// * `dropout_prob` - dropout probability of the attention weights
+    /// * `scale` - optional factor applied to the attention scores
+    pub fn new(window_size: i64, dropout_prob: f64, scale: Option<f64>) -> LocalAttention {
+        LocalAttention {
+            window_size,
+            scale,
+            dropout: Dropout::new(dropout_prob),
+        }
+    }
+}
+
+impl Attention for LocalAttention {
+    fn pattern_mask(&self, query_length: i64, key_length: i64, device: Device) -> Option<Tensor> {
+        let (query_positions, key_positions) =
+            query_key_positions(query_length, key_length, device);
+        let distance = &query_positions - &key_positions;
+        Some(distance.ge(0).logical_and(&distance.lt(self.window_size)))
+    }
+
+    fn forward_t(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        value: &Tensor,
+        attention_mask: Option<&Tensor>,
+        output_attentions: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let pattern_mask = self.pattern_mask(query.size()[2], key.size()[2], query.device());
+        scaled_dot_product(
+            query,
+            key,
+            value,
+            self.scale,
+            pattern_mask,
+            attention_mask,
+            &self.dropout,
+            output_attentions,
+            train,
+        )
+    }
+}
+
+#[derive(Debug)]
+/// # Strided sparse attention
+/// Fixed sparse pattern splitting the sequence in blocks: every query attends to the keys of its own block and to the
+/// last key of every block (optionally restricted to the previous keys if causal). The pattern is applied as a mask
+/// over the dense attention scores.
+pub struct StridedSparseAttention {
+    block_size: i64,
+    causal: bool,
+    scale: Option<f64>,
+    dropout: Dropout,
+}
+
+impl StridedSparseAttention {
+    /// Creates a strided sparse attention
+    ///
+    /// # Arguments
+    ///
+    /// * `block_size` - size of the blocks of the sequence
+    /// * `causal` - flag indicating if queries only attend to the previous keys
+    /// * `dropout_prob` - dropout probability of the attention weights
+    /// * `scale` - optional factor applied to the attention scores
+    pub fn new(
+        block_size: i64,
+        causal: bool,
+        dropout_prob: f64,
+        scale: Option<f64>,
+    ) -> StridedSparseAttention {
+        StridedSparseAttention {
+            block_size,
+            causal,
+            scale,
+            dropout: Dropout::new(dropout_prob),
+        }
+    }
+}
+
+impl Attention for StridedSparseAttention {
+    fn pattern_mask(&self, query_length: i64, key_length: i64, device: Device) -> Option<Tensor> {
+        let (query_positions, key_positions) =
+            query_key_positions(query_length, key_length, device);
+        let same_block = query_positions
+            .divide_scalar_mode(self.block_size, "floor")
+            .eq_tensor(&key_positions.divide_scalar_mode(self.block_size, "floor"));
+        let summary = key_positions
+            .remainder(self.block_size)
+            .eq(self.block_size - 1);
+        let mask = same_block.logical_or(&summary);
+        Some(if self.causal {
+            mask.logical_and(&key_positions.le_tensor(&query_positions))
+        } else {
+            mask
+        })
+    }
+
+    fn forward_t(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        value: &Tensor,
+        attention_mask: Option<&Tensor>,
+        output_attentions: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let pattern_mask = self.pattern_mask(query.size()[2], key.size()[2], query.device());
+        scaled_dot_product(
+            query,
+            key,
+            value,
+            self.scale,
+            pattern_mask,
+            attention_mask,
+            &self.dropout,
+            output_attentions,
+            train,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_attention_window() {
+        let attention = LocalAttention::new(2, 0.0, None);
+        let mask = attention.pattern_mask(3, 3, Device::Cpu).unwrap();
+        let expected =
+            Tensor::from_slice(&[true, false, false, true, true, false, false, true, true])
+                .view([3, 3]);
+        assert!(mask.equal(&expected));
+
+        // Cached keys: the query is aligned with the last key
+        let mask = attention.pattern_mask(1, 3, Device::Cpu).unwrap();
+        assert!(mask.equal(&Tensor::from_slice(&[false, true, true]).view([1, 3])));
+    }
+
+    #[test]
+    fn strided_sparse_attention_pattern() {
+        let attention = StridedSparseAttention::new(2, false, 0.0, None);
+        let mask = attention.pattern_mask(4, 4, Device::Cpu).unwrap();
+        let expected = Tensor::from_slice(&[
+            true, true, false, true, //
+            true, true, false, true, //
+            false, true, true, true, //
+            false, true, true, true,
+        ])
+        .view([4, 4]);
+        assert!(mask.equal(&expected));
+    }
+
+    #[test]
+    fn dense_attention_masking() {
+        let attention = DenseAttention::new(0.0, None, true);
+        let query = Tensor::ones([1, 1, 2, 4], (Kind::Float, Device::Cpu));
+        let value = Tensor::from_slice(&[1f32, 3f32]).view([1, 1, 2, 1]);
+        let (output, weights) = attention.forward_t(&query, &query, &value, None, true, false);
+        assert!(output.allclose(
+            &Tensor::from_slice(&[1f32, 2f32]).view([1, 1, 2, 1]),
+            1e-5,
+            1e-8,
+            false
+        ));
+        assert!(weights.is_some());
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Layer sharding across devices
+//! Partitions the transformer blocks of a model into groups of consecutive blocks placed on different devices, for
+//! models that do not fit on a single device. The embeddings, final layer normalization and language model head are
+//! placed on the device of the embeddings (the first device of the map unless specified otherwise). During the
+//! forward pass, the hidden states are moved to the device of each block before it is applied, and the cached keys
+//! and values of each block remain on its device.
+//!
+//! Each group of blocks, as well as the embeddings, can also be stored in its own floating point precision (e.g. the
+//! embeddings in single precision on the CPU and the top blocks in half precision on a GPU) for a finer control of
+//! the memory usage. The hidden states are cast to the precision of each block, and back to the precision of the
+//! embeddings after the last block. Integer weights are obtained with quantization (see the `quantization` module)
+//! and cannot be set in a device map.
+//!
+//! Unlike tensor parallelism (see the `tensor_parallel` module), the devices compute one after another, but only the
+//! hidden states are transferred between devices, once per group of blocks. The model is loaded on the `device` of
+//! the pipeline configuration before being dispatched: loading on the CPU keeps the full model in RAM only, so that
+//! each device only receives its share of the weights.
+//!
+//! Layer sharding is available for GPT-Neo and GPT-J through the `device_map` of the `TextGenerationConfig`:
+//!
+//! ```no_run
+//! use rust_bert::device_map::{DeviceMap, LayerPlacement};
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use tch::{Device, Kind};
+//! # fn main() -> anyhow::Result<()> {
+//! // 32 blocks split evenly across two GPUs
+//! let device_map = DeviceMap::new(vec![Device::Cuda(0), Device::Cuda(1)]);
+//!
+//! // Embeddings and first 8 blocks on the CPU, last 24 blocks on a GPU in half precision
+//! let device_map = DeviceMap::from_layer_ranges(
+//!     LayerPlacement::new(Device::Cpu),
+//!     vec![
+//!         (0..8, LayerPlacement::new(Device::Cpu)),
+//!         (8..32, LayerPlacement::with_kind(Device::Cuda(0), Kind::Half)),
+//!     ],
+//! )?;
+//!
+//! let generate_config = TextGenerationConfig {
+//!     model_type: ModelType::GPTJ,
+//!     device: Device::Cpu,
+//!     device_map: Some(device_map),
+//!     ..Default::default()
+//! };
+//! let model = TextGenerationModel::new(generate_config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::kind::get_min;
+use crate::common::precision::{check_model_kind, is_half_precision};
+use crate::RustBertError;
+use std::ops::Range;
+use tch::nn::VarStore;
+use tch::{Device, Kind, Tensor};
+
+/// # Device and precision of a part of a model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerPlacement {
+    /// Device the weights are stored on
+    pub device: Device,
+    /// Floating point precision the weights are cast to. The precision of the loaded model is kept if `None`.
+    pub kind: Option<Kind>,
+}
+
+impl LayerPlacement {
+    /// Create a new `LayerPlacement` on a device, keeping the precision of the loaded model
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - device to store the weights on
+    pub fn new(device: Device) -> LayerPlacement {
+        LayerPlacement { device, kind: None }
+    }
+
+    /// Create a new `LayerPlacement` on a device and in a given precision
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - device to store the weights on
+    /// * `kind` - floating point precision of the weights (`Kind::Float`, `Kind::Double`, `Kind::Half` or `Kind::BFloat16`)
+    pub fn with_kind(device: Device, kind: Kind) -> LayerPlacement {
+        LayerPlacement {
+            device,
+            kind: Some(kind),
+        }
+    }
+
+    fn validate(&self) -> Result<(), RustBertError> {
+        match self.kind {
+            Some(kind) => check_model_kind(kind),
+            None => Ok(()),
+        }
+    }
+
+    /// Moves a tensor to the device and precision of the placement
+    fn place(&self, tensor: &Tensor) -> Tensor {
+        let tensor = match self.kind {
+            Some(kind) if tensor.is_floating_point() => tensor.to_kind(kind),
+            _ => tensor.shallow_clone(),
+        };
+        tensor.to_device(self.device)
+    }
+}
+
+impl From<Device> for LayerPlacement {
+    fn from(device: Device) -> LayerPlacement {
+        LayerPlacement::new(device)
+    }
+}
+
+/// # Placement of the parts of a model across devices
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceMap {
+    /// Placement of the embeddings, final layer normalization and language model head. Its device becomes the
+    /// device of the model.
+    pub embeddings: LayerPlacement,
+    /// Placements of consecutive groups of transformer blocks, in the order of the blocks
+    pub groups: Vec<LayerPlacement>,
+    /// Number of blocks in each group. The blocks are split as evenly as possible across the groups if `None`, the
+    /// first groups receiving the remainder (default: None)
+    pub layers_per_group: Option<Vec<usize>>,
+}
+
+impl DeviceMap {
+    /// Create a new `DeviceMap` splitting the transformer blocks evenly across the given devices. The embeddings are
+    /// placed on the first device.
+    ///
+    /// # Arguments
+    ///
+    /// * `devices` - devices to place the blocks on, in the order of the blocks
+    pub fn new(devices: Vec<Device>) -> DeviceMap {
+        DeviceMap {
+            embeddings: LayerPlacement::new(devices.first().copied().unwrap_or(Device::Cpu)),
+            groups: devices.into_iter().map(LayerPlacement::new).collect(),
+            layers_per_group: None,
+        }
+    }
+
+    /// Create a new `DeviceMap` placing a given number of consecutive transformer blocks on each device, for
+    /// example to put fewer blocks on a device that also stores the embeddings and the language model head. The
+    /// embeddings are placed on the first device.
+    ///
+    /// # Arguments
+    ///
+    /// * `layers_per_device` - devices and number of blocks placed on them, in the order of the blocks
+    pub fn from_layer_counts(layers_per_device: Vec<(Device, usize)>) -> DeviceMap {
+        let (devices, counts): (Vec<Device>, Vec<usize>) = layers_per_device.into_iter().unzip();
+        DeviceMap {
+            layers_per_group: Some(counts),
+            ..DeviceMap::new(devices)
+        }
+    }
+
+    /// Create a new `DeviceMap` from the placements of the embeddings and of ranges of transformer blocks. The
+    /// ranges must follow each other, starting from the first block (e.g. `0..8` and `8..24`).
+    ///
+    /// # Arguments
+    ///
+    /// * `embeddings` - placement of the embeddings, final layer normalization and language model head
+    /// * `layer_ranges` - ranges of block indices and their placement, in the order of the blocks
+    pub fn from_layer_ranges(
+        embeddings: LayerPlacement,
+        layer_ranges: Vec<(Range<usize>, LayerPlacement)>,
+    ) -> Result<DeviceMap, RustBertError> {
+        let mut next_start = 0;
+        for (range, _) in &layer_ranges {
+            if range.start != next_start || range.is_empty() {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Invalid layer range {range:?}, expected a non-empty range starting at layer {next_start}"
+                )));
+            }
+            next_start = range.end;
+        }
+        let (counts, groups) = layer_ranges
+            .into_iter()
+            .map(|(range, placement)| (range.len(), placement))
+            .unzip();
+        Ok(DeviceMap {
+            embeddings,
+            groups,
+            layers_per_group: Some(counts),
+        })
+    }
+
+    /// Create a new `DeviceMap` splitting the transformer blocks evenly across all available CUDA devices
+    pub fn all_cuda_devices() -> DeviceMap {
+        DeviceMap::new(
+            (0..tch::Cuda::device_count() as usize)
+                .map(Device::Cuda)
+                .collect(),
+        )
+    }
+
+    /// Places the embeddings, final layer normalization and language model head on a device and in a precision
+    ///
+    /// # Arguments
+    ///
+    /// * `embeddings` - placement of the embeddings, final layer normalization and language model head
+    pub fn with_embeddings(mut self, embeddings: LayerPlacement) -> DeviceMap {
+        self.embeddings = embeddings;
+        self
+    }
+
+    /// Device of the model (device of the embeddings and language model head)
+    pub fn main_device(&self) -> Device {
+        self.embeddings.device
+    }
+
+    /// Checks if a part of the model is stored in half precision (requiring the FP32 accumulation of the attention)
+    pub(crate) fn has_half_precision(&self) -> bool {
+        std::iter::once(&self.embeddings)
+            .chain(&self.groups)
+            .filter_map(|placement| placement.kind)
+            .any(is_half_precision)
+    }
+
+    /// Returns the placement of each of the `num_layers` transformer blocks of a model
+    ///
+    /// # Arguments
+    ///
+    /// * `num_layers` - number of transformer blocks of the model
+    pub fn layer_placements(
+        &self,
+        num_layers: usize,
+    ) -> Result<Vec<LayerPlacement>, RustBertError> {
+        if self.groups.is_empty() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "At least one group of layers is required for the device map".to_string(),
+            ));
+        }
+        self.embeddings.validate()?;
+        for placement in &self.groups {
+            placement.validate()?;
+        }
+        let counts = match &self.layers_per_group {
+            Some(counts) => {
+                if counts.len() != self.groups.len() {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "The device map has {} groups of layers but {} layer counts",
+                        self.groups.len(),
+                        counts.len()
+                    )));
+                }
+                let total = counts.iter().sum::<usize>();
+                if total != num_layers {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "The device map places {total} layers but the model has {num_layers} layers"
+                    )));
+                }
+                counts.clone()
+            }
+            None => {
+                let num_groups = self.groups.len();
+                if num_layers < num_groups {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Cannot split {num_layers} layers across {num_groups} groups"
+                    )));
+                }
+                (0..num_groups)
+                    .map(|index| {
+                        num_layers / num_groups + usize::from(index < num_layers % num_groups)
+                    })
+                    .collect()
+            }
+        };
+        Ok(self
+            .groups
+            .iter()
+            .zip(counts)
+            .flat_map(|(placement, count)| std::iter::repeat(*placement).take(count))
+            .collect())
+    }
+}
+
+/// Moves the hidden states and the additive attention mask to the device and precision of a transformer block. The
+/// masked positions are clamped to the range of the precision of the block.
+pub(crate) fn to_layer_placement(
+    hidden_states: &Tensor,
+    attention_mask: Option<&Tensor>,
+    placement: &LayerPlacement,
+) -> Result<(Tensor, Option<Tensor>), RustBertError> {
+    let attention_mask = match (attention_mask, placement.kind) {
+        (Some(mask), Some(kind)) if mask.kind() != kind && mask.is_floating_point() => {
+            Some(placement.place(&mask.clamp_min(get_min(kind)?)))
+        }
+        (Some(mask), _) => Some(placement.place(mask)),
+        (None, _) => None,
+    };
+    Ok((placement.place(hidden_states), attention_mask))
+}
+
+/// Index of the transformer block a variable belongs to, for blocks stored under `{layer_prefix}{index}.`
+fn layer_index(name: &str, layer_prefix: &str) -> Option<usize> {
+    name.strip_prefix(layer_prefix)?
+        .split('.')
+        .next()?
+        .parse::<usize>()
+        .ok()
+}
+
+/// Moves the variables of the transformer blocks (stored under `{layer_prefix}{index}.`) to their placement and the
+/// other variables to the placement of the embeddings, whose device becomes the device of the variable store. The
+/// variables of the blocks are set aside while the variable store is moved, so that the weights are transferred
+/// once to their device.
+pub(crate) fn dispatch_var_store(
+    var_store: &mut VarStore,
+    layer_prefix: &str,
+    layer_placements: &[LayerPlacement],
+    embeddings: &LayerPlacement,
+) -> Result<(), RustBertError> {
+    let _guard = tch::no_grad_guard();
+    let mut layer_variables = {
+        let mut variables = var_store.variables_.lock().unwrap();
+        let names = variables
+            .named_variables
+            .keys()
+            .filter(|name| layer_index(name, layer_prefix).is_some())
+            .cloned()
+            .collect::<Vec<String>>();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let variable = variables.named_variables.remove(&name)?;
+                Some((name, variable))
+            })
+            .collect::<Vec<(String, Tensor)>>()
+    };
+
+    let mut result = Ok(());
+    for (name, variable) in layer_variables.iter_mut() {
+        match layer_placements.get(layer_index(name, layer_prefix).unwrap()) {
+            Some(placement) => {
+                let placed_variable = placement.place(variable);
+                variable.set_data(&placed_variable);
+            }
+            None => {
+                result = Err(RustBertError::InvalidConfigurationError(format!(
+                    "No device is assigned to the layer of variable {name}"
+                )));
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        if let Some(kind) = embeddings.kind {
+            var_store.set_kind(kind);
+        }
+        var_store.set_device(embeddings.device);
+    }
+    var_store
+        .variables_
+        .lock()
+        .unwrap()
+        .named_variables
+        .extend(layer_variables);
+    result
+}
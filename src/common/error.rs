@@ -35,8 +35,56 @@ pub enum RustBertError {
     #[cfg(feature = "onnx")]
     NdArrayError(String),
 
+    #[error("Candle tensor error: {0}")]
+    #[cfg(feature = "candle")]
+    CandleError(String),
+
     #[error("Unsupported operation")]
     UnsupportedError,
+
+    #[error("Invalid input: {0}")]
+    InputError(#[from] InputError),
+}
+
+/// # Invalid pipeline input
+/// Returned by the pipelines when validating their inputs, before running the model. The message of each
+/// variant describes how the input can be fixed.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    #[error("no input provided, at least one input text is required")]
+    EmptyInput,
+
+    #[error("no candidate labels provided, at least one label is required to classify the inputs")]
+    NoCandidateLabels,
+
+    #[error(
+        "unsupported language {language}, the languages supported by the model are: {}. \
+        Use a model trained for this language, or a multilingual model",
+        supported.join(", ")
+    )]
+    UnsupportedLanguage {
+        language: String,
+        supported: Vec<String>,
+    },
+
+    #[error(
+        "the model configuration defines {num_labels} labels for ids up to {max_label_id}. \
+        The `id2label` mapping of the configuration file should contain every id from 0 to the number of labels - 1"
+    )]
+    LabelCountMismatch {
+        num_labels: usize,
+        max_label_id: i64,
+    },
+
+    #[error(
+        "input {index} has {length} tokens, exceeding the context window of the model ({max_length} tokens). \
+        Shorten the input, split it into chunks, or use a model with a longer context window"
+    )]
+    ContextTooLong {
+        index: usize,
+        length: usize,
+        max_length: usize,
+    },
 }
 
 impl From<std::io::Error> for RustBertError {
@@ -69,3 +117,10 @@ impl From<ShapeError> for RustBertError {
         RustBertError::NdArrayError(error.to_string())
     }
 }
+
+#[cfg(feature = "candle")]
+impl From<candle_core::Error> for RustBertError {
+    fn from(error: candle_core::Error) -> Self {
+        RustBertError::CandleError(error.to_string())
+    }
+}
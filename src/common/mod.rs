@@ -1,12 +1,27 @@
 pub(crate) mod activations;
+pub mod attention;
 pub mod config;
+pub mod device_map;
 pub(crate) mod dropout;
 pub(crate) mod embeddings;
 pub mod error;
 pub(crate) mod kind;
 pub(crate) mod linear;
+pub(crate) mod mps;
+pub mod placement;
+pub mod position_embeddings;
+pub mod precision;
+pub mod pruning;
+pub mod quantization;
 pub mod resources;
+pub mod scratch;
+pub mod settings;
+pub mod snapshot;
+#[cfg(feature = "xlnet")]
 pub(crate) mod summary;
+pub mod tensor_parallel;
+pub mod vocabulary;
+pub mod weight_tying;
 
 pub use activations::Activation;
 pub use config::Config;
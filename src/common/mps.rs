@@ -0,0 +1,102 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # CPU fallbacks for Apple Silicon (MPS) devices
+//! Some operations are not supported by the Metal Performance Shaders backend of Torch:
+//! - double precision tensors,
+//! - cumulative sums of 64 bits integers (used to compute the position ids from the attention mask),
+//! - top-k selections of more than 16 elements (used by the top-k filtering and the beam search).
+//!
+//! On MPS devices, these operations are computed on the CPU and their outputs are moved back to the device. They are
+//! left unchanged on the other devices.
+
+use tch::{Device, Kind, Tensor};
+
+/// Largest number of elements selected by `topk` on MPS devices
+const MPS_MAX_TOP_K: i64 = 16;
+
+fn is_mps(tensor: &Tensor) -> bool {
+    tensor.device() == Device::Mps
+}
+
+/// Cumulative sum of the elements of a tensor along a dimension, in the precision `kind`
+pub(crate) fn cumsum(tensor: &Tensor, dim: i64, kind: Kind) -> Tensor {
+    if is_mps(tensor) && kind == Kind::Int64 {
+        tensor
+            .to_device(Device::Cpu)
+            .cumsum(dim, kind)
+            .to_device(Device::Mps)
+    } else {
+        tensor.cumsum(dim, kind)
+    }
+}
+
+/// Values and indices of the `k` largest (or smallest) elements of a tensor along a dimension
+pub(crate) fn topk(
+    tensor: &Tensor,
+    k: i64,
+    dim: i64,
+    largest: bool,
+    sorted: bool,
+) -> (Tensor, Tensor) {
+    if is_mps(tensor) && k > MPS_MAX_TOP_K {
+        let (values, indices) = tensor.to_device(Device::Cpu).topk(k, dim, largest, sorted);
+        (
+            values.to_device(Device::Mps),
+            indices.to_device(Device::Mps),
+        )
+    } else {
+        tensor.topk(k, dim, largest, sorted)
+    }
+}
+
+/// Converts a tensor to double precision. Tensors on MPS devices are moved to the CPU, the returned tensor is
+/// meant to be read (e.g. converted to a `Vec<f64>`).
+pub(crate) fn to_double(tensor: &Tensor) -> Tensor {
+    if is_mps(tensor) {
+        tensor.to_device(Device::Cpu).to_kind(Kind::Double)
+    } else {
+        tensor.to_kind(Kind::Double)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn cpu_operations_unchanged() {
+        let tensor = Tensor::from_slice(&[1i64, 0, 1, 1]);
+        assert_eq!(
+            Vec::<i64>::try_from(cumsum(&tensor, 0, Kind::Int64)).unwrap(),
+            [1, 1, 2, 3]
+        );
+        let (values, indices) = topk(&Tensor::from_slice(&[0.5f32, 2.0, 1.0]), 2, 0, true, true);
+        assert_eq!(Vec::<f32>::try_from(values).unwrap(), [2.0, 1.0]);
+        assert_eq!(Vec::<i64>::try_from(indices).unwrap(), [1, 2]);
+        assert_eq!(to_double(&tensor).kind(), Kind::Double);
+    }
+
+    #[test]
+    #[ignore] // requires an MPS device
+    fn mps_fallbacks() {
+        let values = (0..64).map(|value| value as f32).collect::<Vec<f32>>();
+        let tensor = Tensor::from_slice(&values).to_device(Device::Mps);
+        let (top_values, top_indices) = topk(&tensor, 32, 0, true, true);
+        assert_eq!(top_values.device(), Device::Mps);
+        assert_eq!(top_indices.int64_value(&[0]), 63);
+        let positions = cumsum(&tensor.ones_like().to_kind(Kind::Int64), 0, Kind::Int64);
+        assert_eq!(positions.device(), Device::Mps);
+        assert_eq!(positions.int64_value(&[63]), 64);
+        assert_eq!(to_double(&tensor).device(), Device::Cpu);
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Automatic device and precision selection
+//! Picks the device, precision and loading strategy of a model from the size of its checkpoint and the memory
+//! available on the CUDA devices and in RAM, instead of failing with an out-of-memory error when loading the model:
+//! - the model is placed on the CUDA device with the most free memory if it fits in the precision of the checkpoint,
+//! - otherwise it is converted to half precision if it then fits on a CUDA device. The weights are loaded and
+//!   converted in RAM before being moved to the device, so that the full precision weights never reside on the device,
+//! - otherwise the model is kept on the CPU if it fits in RAM.
+//!
+//! The free memory of the CUDA devices is queried using `nvidia-smi` (honouring `CUDA_VISIBLE_DEVICES`) and the
+//! available RAM is read from `/proc/meminfo` on Linux. Memory that cannot be determined is assumed to be sufficient.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::placement::{AutoPlacementConfig, Placement};
+//! # fn main() -> anyhow::Result<()> {
+//! let generate_config = TextGenerationConfig::default();
+//! let placement = Placement::auto(&generate_config.model_resource, &AutoPlacementConfig::default())?;
+//! let model = TextGenerationModel::new_with_placement(generate_config, &placement)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::pipelines::common::ModelResource;
+use crate::RustBertError;
+use std::fmt;
+use std::process::Command;
+use tch::{Device, Kind};
+
+/// # Configuration for the automatic placement of a model
+#[derive(Debug, Clone)]
+pub struct AutoPlacementConfig {
+    /// Precision the weights are stored in in the checkpoint (default: Float)
+    pub checkpoint_kind: Kind,
+    /// Fraction of the weights memory reserved for the activations and the generation cache (default: 0.2)
+    pub memory_headroom: f64,
+    /// Allow converting the model to half precision to fit on a CUDA device (default: true)
+    pub allow_half: bool,
+    /// Allow placing the model on the CPU if it does not fit on any CUDA device (default: true)
+    pub allow_cpu: bool,
+}
+
+impl Default for AutoPlacementConfig {
+    fn default() -> Self {
+        AutoPlacementConfig {
+            checkpoint_kind: Kind::Float,
+            memory_headroom: 0.2,
+            allow_half: true,
+            allow_cpu: true,
+        }
+    }
+}
+
+/// # Free memory of a CUDA device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceMemory {
+    /// Index of the CUDA device
+    pub index: usize,
+    /// Free memory in bytes, `None` if it could not be determined
+    pub free_bytes: Option<u64>,
+}
+
+/// # Memory available for loading a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryInfo {
+    /// Free memory of the available CUDA devices
+    pub cuda_devices: Vec<DeviceMemory>,
+    /// Available RAM in bytes, `None` if it could not be determined
+    pub available_ram_bytes: Option<u64>,
+}
+
+impl MemoryInfo {
+    /// Queries the free memory of the CUDA devices and the available RAM
+    pub fn detect() -> MemoryInfo {
+        let cuda_devices = if tch::Cuda::is_available() {
+            let free_bytes = cuda_free_bytes();
+            (0..tch::Cuda::device_count() as usize)
+                .map(|index| DeviceMemory {
+                    index,
+                    free_bytes: free_bytes
+                        .as_ref()
+                        .and_then(|free_bytes| free_bytes.get(index).copied()),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        MemoryInfo {
+            cuda_devices,
+            available_ram_bytes: available_ram_bytes(),
+        }
+    }
+}
+
+/// Free memory of the visible CUDA devices, indexed as seen by libtorch
+fn cuda_free_bytes() -> Option<Vec<u64>> {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=memory.free", "--format=csv,noheader,nounits"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let free_bytes = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            line.trim()
+                .parse::<u64>()
+                .ok()
+                .map(|free_mib| free_mib * 1024 * 1024)
+        })
+        .collect::<Option<Vec<u64>>>()?;
+    match std::env::var("CUDA_VISIBLE_DEVICES") {
+        Ok(visible_devices) if !visible_devices.trim().is_empty() => visible_devices
+            .split(',')
+            .map(|index| {
+                index
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| free_bytes.get(index).copied())
+            })
+            .collect(),
+        _ => Some(free_bytes),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn available_ram_bytes() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|available_kib| available_kib.parse::<u64>().ok())
+        .map(|available_kib| available_kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_ram_bytes() -> Option<u64> {
+    None
+}
+
+/// # Strategy used to load the weights of a model on its device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffloadStrategy {
+    /// The weights are loaded directly on the device
+    None,
+    /// The weights are loaded and converted on the CPU, then moved to the device
+    StageOnCpu,
+    /// The model does not fit on any CUDA device and is kept on the CPU
+    Cpu,
+}
+
+/// # Device, precision and loading strategy selected for a model
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// Device the model is placed on
+    pub device: Device,
+    /// Precision of the model weights
+    pub kind: Kind,
+    /// Strategy used to load the weights
+    pub offload: OffloadStrategy,
+    /// Estimated memory required by the model on its device, in bytes
+    pub required_bytes: u64,
+}
+
+fn to_gib(bytes: u64) -> f64 {
+    bytes as f64 / (1024 * 1024 * 1024) as f64
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strategy = match self.offload {
+            OffloadStrategy::None => "loaded directly on the device",
+            OffloadStrategy::StageOnCpu => "loaded and converted on the CPU before transfer",
+            OffloadStrategy::Cpu => "kept on the CPU: no CUDA device with sufficient memory",
+        };
+        write!(
+            f,
+            "{:?} in {:?} precision ({:.2} GiB required), {}",
+            self.device,
+            self.kind,
+            to_gib(self.required_bytes),
+            strategy
+        )
+    }
+}
+
+impl Placement {
+    /// Selects the placement of a model from the size of its checkpoint and the memory available on the system.
+    /// The returned `Placement` implements `Display` to describe the decision.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `ModelResource` pointing to the Torch checkpoint of the model
+    /// * `config` - `AutoPlacementConfig` defining the allowed conversions
+    pub fn auto(
+        model_resource: &ModelResource,
+        config: &AutoPlacementConfig,
+    ) -> Result<Placement, RustBertError> {
+        let checkpoint_path = model_resource.get_torch_local_path()?;
+        let checkpoint_bytes = std::fs::metadata(checkpoint_path)?.len();
+        let memory_info = MemoryInfo::detect();
+        Placement::select(checkpoint_bytes, &memory_info, config)
+    }
+
+    /// Selects the placement of a model given the memory available. The CUDA device with the most free memory is
+    /// preferred, in the precision of the checkpoint if possible and in half precision otherwise (if allowed).
+    /// The model is placed on the CPU if it does not fit on any CUDA device.
+    ///
+    /// # Arguments
+    ///
+    /// * `checkpoint_bytes` - size of the model checkpoint in bytes
+    /// * `memory_info` - `MemoryInfo` memory available on the CUDA devices and in RAM
+    /// * `config` - `AutoPlacementConfig` defining the allowed conversions
+    pub fn select(
+        checkpoint_bytes: u64,
+        memory_info: &MemoryInfo,
+        config: &AutoPlacementConfig,
+    ) -> Result<Placement, RustBertError> {
+        if config.memory_headroom < 0.0 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The memory headroom must be positive, got {}",
+                config.memory_headroom
+            )));
+        }
+        let required_bytes = |kind: Kind| -> u64 {
+            let weight_bytes = checkpoint_bytes as f64 * kind.elt_size_in_bytes() as f64
+                / config.checkpoint_kind.elt_size_in_bytes() as f64;
+            (weight_bytes * (1.0 + config.memory_headroom)).ceil() as u64
+        };
+        let fits_in_ram = |bytes: u64| {
+            memory_info
+                .available_ram_bytes
+                .map_or(true, |available_bytes| bytes <= available_bytes)
+        };
+
+        let mut candidate_kinds = vec![config.checkpoint_kind];
+        if config.allow_half
+            && config.checkpoint_kind.elt_size_in_bytes() > Kind::Half.elt_size_in_bytes()
+        {
+            candidate_kinds.push(Kind::Half);
+        }
+        // Devices with unknown free memory are tried first, then by decreasing free memory
+        let mut cuda_devices = memory_info.cuda_devices.clone();
+        cuda_devices.sort_by_key(|device| std::cmp::Reverse(device.free_bytes.unwrap_or(u64::MAX)));
+
+        for kind in candidate_kinds {
+            let required = required_bytes(kind);
+            let offload = if kind == config.checkpoint_kind {
+                OffloadStrategy::None
+            } else if fits_in_ram(checkpoint_bytes) {
+                OffloadStrategy::StageOnCpu
+            } else {
+                continue;
+            };
+            let device = cuda_devices.iter().find(|device| {
+                device
+                    .free_bytes
+                    .map_or(true, |free_bytes| required <= free_bytes)
+            });
+            if let Some(device) = device {
+                return Ok(Placement {
+                    device: Device::Cuda(device.index),
+                    kind,
+                    offload,
+                    required_bytes: required,
+                });
+            }
+        }
+
+        let required = required_bytes(config.checkpoint_kind);
+        if config.allow_cpu && fits_in_ram(required) {
+            return Ok(Placement {
+                device: Device::Cpu,
+                kind: config.checkpoint_kind,
+                offload: OffloadStrategy::Cpu,
+                required_bytes: required,
+            });
+        }
+        Err(RustBertError::InvalidConfigurationError(format!(
+            "Insufficient memory to load the model ({:.2} GiB required): {} CUDA device(s) available with at most \
+            {:.2} GiB free, {} RAM available",
+            to_gib(required),
+            memory_info.cuda_devices.len(),
+            to_gib(
+                memory_info
+                    .cuda_devices
+                    .iter()
+                    .filter_map(|device| device.free_bytes)
+                    .max()
+                    .unwrap_or(0)
+            ),
+            memory_info
+                .available_ram_bytes
+                .map_or("unknown".to_string(), |bytes| format!(
+                    "{:.2} GiB",
+                    to_gib(bytes)
+                ))
+        )))
+    }
+
+    /// Device the weights should be loaded on before being converted and moved to the target device
+    pub fn load_device(&self) -> Device {
+        match self.offload {
+            OffloadStrategy::StageOnCpu => Device::Cpu,
+            OffloadStrategy::None | OffloadStrategy::Cpu => self.device,
+        }
+    }
+}
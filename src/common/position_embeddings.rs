@@ -0,0 +1,638 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Position embeddings
+//! Shared implementations of the position encoding schemes used by the transformer architectures of the crate:
+//! - absolute sinusoidal ([Vaswani et al., 2017](https://arxiv.org/abs/1706.03762)) and learned position embeddings,
+//!   added to the input embeddings,
+//! - rotary position embeddings ([Su et al., 2021](https://arxiv.org/abs/2104.09864)), rotating the queries and keys,
+//! - ALiBi ([Press et al., 2021](https://arxiv.org/abs/2108.12409)) and bucketed relative positions
+//!   ([Raffel et al., 2019](https://arxiv.org/abs/1910.10683)), biasing the attention scores.
+//!
+//! All schemes implement the `PositionEmbedding` trait, and may be created from a serializable
+//! `PositionEmbeddingConfig` to swap the scheme used by a custom architecture.
+//!
+//! ```no_run
+//! use rust_bert::position_embeddings::{PositionEmbedding, PositionEmbeddingConfig};
+//! use tch::{nn, Device, Kind, Tensor};
+//!
+//! let vs = nn::VarStore::new(Device::Cpu);
+//! let position_embedding = PositionEmbeddingConfig::Rotary {
+//!     rotary_dim: 64,
+//!     base: 10000.0,
+//!     interleaved: false,
+//! }
+//! .build(vs.root() / "rotary");
+//!
+//! let query = Tensor::rand([1, 12, 8, 64], (Kind::Float, Device::Cpu));
+//! let key = Tensor::rand([1, 12, 8, 64], (Kind::Float, Device::Cpu));
+//! let position_ids = Tensor::arange(8, (Kind::Int64, Device::Cpu)).unsqueeze(0);
+//! let (query, key) = position_embedding.apply_query_key(&query, &key, &position_ids);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use tch::nn::{embedding, EmbeddingConfig};
+use tch::{nn, Device, Kind, Tensor};
+
+/// # Common interface of the position encoding schemes
+/// A scheme encodes the positions at one (or several) of the following points of a transformer:
+/// the input embeddings, the queries and keys of the attention, or the attention scores. The default implementation
+/// of each method leaves the corresponding step unchanged.
+pub trait PositionEmbedding: Send {
+    /// Position embeddings to add to the input embeddings, of shape (*batch size*, *sequence_length*, *embedding_dim*)
+    /// for position ids of shape (*batch size*, *sequence_length*). Returns `None` for schemes not encoding the
+    /// positions in the input embeddings.
+    fn input_embeddings(&self, _position_ids: &Tensor) -> Option<Tensor> {
+        None
+    }
+
+    /// Applies the position encoding to the queries and keys of shape
+    /// (*batch size*, *num_heads*, *sequence_length*, *head_dim*) for position ids of shape
+    /// (*batch size*, *sequence_length*)
+    fn apply_query_key(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        _position_ids: &Tensor,
+    ) -> (Tensor, Tensor) {
+        (query.shallow_clone(), key.shallow_clone())
+    }
+
+    /// Bias to add to the attention scores, of shape (1, *num_heads*, *query_length*, *key_length*). The queries are
+    /// aligned with the last keys (i.e. the query at position *i* corresponds to the key at position
+    /// *i + key_length - query_length*). Returns `None` for schemes not biasing the attention scores.
+    fn attention_bias(
+        &self,
+        _query_length: i64,
+        _key_length: i64,
+        _device: Device,
+    ) -> Option<Tensor> {
+        None
+    }
+}
+
+/// # Configuration of a position encoding scheme
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PositionEmbeddingConfig {
+    /// Fixed sinusoidal position embeddings
+    Sinusoidal {
+        max_position_embeddings: i64,
+        embedding_dim: i64,
+        layout: SinusoidalLayout,
+    },
+    /// Learned position embeddings, with an optional offset added to the position ids (e.g. 2 for BART)
+    Learned {
+        max_position_embeddings: i64,
+        embedding_dim: i64,
+        #[serde(default)]
+        offset: i64,
+    },
+    /// Rotary position embeddings applied to the first `rotary_dim` dimensions of each head
+    Rotary {
+        rotary_dim: i64,
+        base: f64,
+        #[serde(default)]
+        interleaved: bool,
+    },
+    /// Linear biases of the attention scores proportional to the distance between positions
+    Alibi { num_heads: i64 },
+    /// Learned biases of the attention scores for buckets of relative positions
+    RelativeBuckets {
+        num_heads: i64,
+        num_buckets: i64,
+        max_distance: i64,
+        bidirectional: bool,
+    },
+}
+
+impl PositionEmbeddingConfig {
+    /// Builds the position encoding scheme. Learned parameters are registered under the variable store path provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the position embeddings
+    pub fn build<'p, P>(&self, p: P) -> Box<dyn PositionEmbedding>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        match *self {
+            PositionEmbeddingConfig::Sinusoidal {
+                max_position_embeddings,
+                embedding_dim,
+                layout,
+            } => Box::new(SinusoidalPositionEmbedding::new(
+                max_position_embeddings,
+                embedding_dim,
+                layout,
+                p.device(),
+            )),
+            PositionEmbeddingConfig::Learned {
+                max_position_embeddings,
+                embedding_dim,
+                offset,
+            } => Box::new(LearnedPositionEmbedding::new(
+                p,
+                max_position_embeddings,
+                embedding_dim,
+                offset,
+            )),
+            PositionEmbeddingConfig::Rotary {
+                rotary_dim,
+                base,
+                interleaved,
+            } => {
+                let rotary_embedding = RotaryEmbedding::new(rotary_dim, base, p.device());
+                Box::new(if interleaved {
+                    rotary_embedding.interleaved()
+                } else {
+                    rotary_embedding
+                })
+            }
+            PositionEmbeddingConfig::Alibi { num_heads } => Box::new(AlibiBias::new(num_heads)),
+            PositionEmbeddingConfig::RelativeBuckets {
+                num_heads,
+                num_buckets,
+                max_distance,
+                bidirectional,
+            } => Box::new(RelativePositionBias::new(
+                p,
+                num_heads,
+                num_buckets,
+                max_distance,
+                bidirectional,
+            )),
+        }
+    }
+}
+
+/// # Layout of the sinusoidal position embeddings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SinusoidalLayout {
+    /// Sine and cosine values alternate over the embedding dimensions (e.g. DistilBERT)
+    Interleaved,
+    /// Sine values fill the first half of the embedding dimensions and cosine values the second half (e.g. Marian, Pegasus)
+    Concatenated,
+}
+
+/// Computes the sinusoidal position embeddings table of shape (*num_positions*, *embedding_dim*). The angle of the
+/// embedding dimensions *2i* and *2i + 1* at position *pos* is `pos / 10000^(2i / embedding_dim)`. The table is
+/// computed in double precision on the CPU (double precision is not supported on MPS devices) before being moved to
+/// `device`.
+///
+/// # Arguments
+///
+/// * `num_positions` - number of positions of the table
+/// * `embedding_dim` - dimension of the embeddings
+/// * `layout` - `SinusoidalLayout` of the sine and cosine values over the embedding dimensions
+/// * `device` - device to create the table on
+pub fn sinusoidal_table(
+    num_positions: i64,
+    embedding_dim: i64,
+    layout: SinusoidalLayout,
+    device: Device,
+) -> Tensor {
+    let positions = Tensor::arange(num_positions, (Kind::Double, Device::Cpu)).unsqueeze(1);
+    let inv_freq = (Tensor::arange_start_step(0, embedding_dim, 2, (Kind::Double, Device::Cpu))
+        * (-(10000f64.ln()) / embedding_dim as f64))
+        .exp();
+    let angles = positions * inv_freq.unsqueeze(0);
+    let sin = angles.sin();
+    let cos = angles.cos().narrow(1, 0, embedding_dim / 2);
+    let table = match layout {
+        SinusoidalLayout::Concatenated => Tensor::cat(&[sin, cos], 1),
+        SinusoidalLayout::Interleaved => {
+            let cos = if embedding_dim % 2 == 1 {
+                Tensor::cat(
+                    &[
+                        cos,
+                        Tensor::zeros([num_positions, 1], (Kind::Double, Device::Cpu)),
+                    ],
+                    1,
+                )
+            } else {
+                cos
+            };
+            Tensor::stack(&[sin, cos], 2)
+                .view([num_positions, -1])
+                .narrow(1, 0, embedding_dim)
+        }
+    };
+    table.to_kind(Kind::Float).to_device(device)
+}
+
+#[derive(Debug)]
+/// # Fixed sinusoidal position embeddings
+pub struct SinusoidalPositionEmbedding {
+    table: Tensor,
+}
+
+impl SinusoidalPositionEmbedding {
+    /// Creates the sinusoidal position embeddings for positions up to `max_position_embeddings`
+    pub fn new(
+        max_position_embeddings: i64,
+        embedding_dim: i64,
+        layout: SinusoidalLayout,
+        device: Device,
+    ) -> SinusoidalPositionEmbedding {
+        let table = sinusoidal_table(max_position_embeddings, embedding_dim, layout, device);
+        SinusoidalPositionEmbedding { table }
+    }
+}
+
+impl PositionEmbedding for SinusoidalPositionEmbedding {
+    fn input_embeddings(&self, position_ids: &Tensor) -> Option<Tensor> {
+        Some(Tensor::embedding(
+            &self.table,
+            position_ids,
+            -1,
+            false,
+            false,
+        ))
+    }
+}
+
+#[derive(Debug)]
+/// # Learned position embeddings
+pub struct LearnedPositionEmbedding {
+    embedding: nn::Embedding,
+    offset: i64,
+}
+
+impl LearnedPositionEmbedding {
+    /// Creates learned position embeddings (variable `weight` of shape
+    /// (*max_position_embeddings + offset*, *embedding_dim*) under the path provided). The offset is added to the
+    /// position ids before the lookup.
+    pub fn new<'p, P>(
+        p: P,
+        max_position_embeddings: i64,
+        embedding_dim: i64,
+        offset: i64,
+    ) -> LearnedPositionEmbedding
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let embedding = embedding(
+            p.borrow(),
+            max_position_embeddings + offset,
+            embedding_dim,
+            EmbeddingConfig::default(),
+        );
+        LearnedPositionEmbedding { embedding, offset }
+    }
+}
+
+impl PositionEmbedding for LearnedPositionEmbedding {
+    fn input_embeddings(&self, position_ids: &Tensor) -> Option<Tensor> {
+        Some((position_ids + self.offset).apply(&self.embedding))
+    }
+}
+
+fn rotate_half(x: &Tensor) -> Tensor {
+    let half_dim = x.size()[x.dim() - 1] / 2;
+    let x1 = x.narrow(-1, 0, half_dim);
+    let x2 = x.narrow(-1, half_dim, half_dim);
+    Tensor::cat(&[-x2, x1], -1)
+}
+
+fn rotate_every_two(x: &Tensor) -> Tensor {
+    let mut pair_shape = x.size();
+    let last_dim = pair_shape.pop().unwrap();
+    pair_shape.extend_from_slice(&[last_dim / 2, 2]);
+    let pairs = x.view(pair_shape.as_slice());
+    Tensor::stack(&[-pairs.select(-1, 1), pairs.select(-1, 0)], -1).view(x.size().as_slice())
+}
+
+/// Applies rotary position embeddings (non-interleaved layout) to the first dimensions of a tensor of shape
+/// (*batch size*, *num_heads*, *sequence_length*, *head_dim*), the number of rotated dimensions being given by the
+/// last dimension of the cosine and sine tables. Remaining dimensions pass through unchanged.
+pub fn apply_rotary_pos_emb(x: &Tensor, cos: &Tensor, sin: &Tensor) -> Tensor {
+    rotate(x, cos, sin, false)
+}
+
+fn rotate(x: &Tensor, cos: &Tensor, sin: &Tensor, interleaved: bool) -> Tensor {
+    let rotary_dim = *cos.size().last().unwrap();
+    let head_dim = *x.size().last().unwrap();
+    let x_rot = x.narrow(-1, 0, rotary_dim);
+    let rotated = if interleaved {
+        rotate_every_two(&x_rot)
+    } else {
+        rotate_half(&x_rot)
+    };
+    let x_rot = &x_rot * cos + rotated * sin;
+    if rotary_dim < head_dim {
+        let x_pass = x.narrow(-1, rotary_dim, head_dim - rotary_dim);
+        Tensor::cat(&[x_rot, x_pass], -1)
+    } else {
+        x_rot
+    }
+}
+
+#[derive(Debug)]
+/// # Rotary position embeddings
+/// Computes the cosine and sine tables of the rotary position embeddings for the positions provided. The rotated
+/// dimensions are split in two halves by default (GPT-NeoX layout), or in pairs of consecutive dimensions with the
+/// interleaved layout (GPT-J layout).
+pub struct RotaryEmbedding {
+    inv_freq: Tensor,
+    interleaved: bool,
+}
+
+impl RotaryEmbedding {
+    /// Creates rotary position embeddings for `rotary_dim` dimensions (non-interleaved layout)
+    ///
+    /// # Arguments
+    ///
+    /// * `rotary_dim` - number of rotated dimensions of each attention head
+    /// * `base` - base of the geometric sequence of the rotation frequencies (e.g. 10000)
+    /// * `device` - device to create the frequencies on
+    pub fn new(rotary_dim: i64, base: f64, device: Device) -> RotaryEmbedding {
+        let inv_freq = 1.0
+            / Tensor::pow_scalar(
+                base,
+                &(Tensor::arange_start_step(0, rotary_dim, 2, (Kind::Float, device)) / rotary_dim),
+            );
+        RotaryEmbedding {
+            inv_freq,
+            interleaved: false,
+        }
+    }
+
+    /// Switches to the interleaved layout (rotation of pairs of consecutive dimensions)
+    pub fn interleaved(self) -> RotaryEmbedding {
+        RotaryEmbedding {
+            interleaved: true,
+            ..self
+        }
+    }
+
+    /// Returns the cosine and sine tables for position ids of shape (*batch size*, *sequence_length*), of shape
+    /// (*batch size*, *1*, *sequence_length*, *rotary_dim*). Position ids of shape (*sequence_length*) return
+    /// tables of shape (*sequence_length*, *rotary_dim*).
+    pub fn forward(&self, position_ids: &Tensor, kind: Kind) -> (Tensor, Tensor) {
+        let freqs = position_ids.to_kind(Kind::Float).unsqueeze(-1)
+            * self.inv_freq.to_device(position_ids.device());
+        let embeddings = if self.interleaved {
+            freqs.repeat_interleave_self_int(2, -1, None::<i64>)
+        } else {
+            Tensor::cat(&[&freqs, &freqs], -1)
+        };
+        let embeddings = if position_ids.dim() > 1 {
+            embeddings.unsqueeze(1)
+        } else {
+            embeddings
+        };
+        (
+            embeddings.cos().to_kind(kind),
+            embeddings.sin().to_kind(kind),
+        )
+    }
+
+    /// Rotates a tensor of shape (*batch size*, *num_heads*, *sequence_length*, *head_dim*) with cosine and sine
+    /// tables returned by `forward`
+    pub fn rotate(&self, x: &Tensor, cos: &Tensor, sin: &Tensor) -> Tensor {
+        rotate(x, cos, sin, self.interleaved)
+    }
+}
+
+impl PositionEmbedding for RotaryEmbedding {
+    fn apply_query_key(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        position_ids: &Tensor,
+    ) -> (Tensor, Tensor) {
+        let (cos, sin) = self.forward(position_ids, query.kind());
+        (self.rotate(query, &cos, &sin), self.rotate(key, &cos, &sin))
+    }
+}
+
+/// Computes the ALiBi head slopes following the geometric sequence from
+/// [Press et al., 2021](https://arxiv.org/abs/2108.12409).
+/// Numbers of heads that are not a power of two are handled by interleaving the slopes of the
+/// closest power of two.
+pub fn alibi_head_slopes(num_heads: i64) -> Vec<f64> {
+    fn get_slopes_power_of_2(n: i64) -> Vec<f64> {
+        let start = 2f64.powf(-(2f64.powf(-((n as f64).log2() - 3f64))));
+        (0..n).map(|i| start * start.powi(i as i32)).collect()
+    }
+
+    if (num_heads as f64).log2().fract() == 0f64 {
+        get_slopes_power_of_2(num_heads)
+    } else {
+        let closest_power_of_2 = 2i64.pow((num_heads as f64).log2().floor() as u32);
+        let mut slopes = get_slopes_power_of_2(closest_power_of_2);
+        slopes.extend(
+            alibi_head_slopes(2 * closest_power_of_2)
+                .into_iter()
+                .step_by(2)
+                .take((num_heads - closest_power_of_2) as usize),
+        );
+        slopes
+    }
+}
+
+#[derive(Debug)]
+/// # ALiBi attention biases
+/// The bias added to the attention score between positions *i* and *j* is `-slope * |i - j|`, with a slope per head.
+/// For causal attention, the masked future positions make it equivalent to the causal formulation of the biases.
+pub struct AlibiBias {
+    slopes: Vec<f64>,
+}
+
+impl AlibiBias {
+    /// Creates the ALiBi biases for `num_heads` attention heads
+    pub fn new(num_heads: i64) -> AlibiBias {
+        AlibiBias {
+            slopes: alibi_head_slopes(num_heads),
+        }
+    }
+
+    /// Builds the attention bias of shape (1, *num_heads*, *query_length*, *key_length*)
+    pub fn forward(&self, query_length: i64, key_length: i64, device: Device) -> Tensor {
+        let query_positions =
+            Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device));
+        let key_positions = Tensor::arange(key_length, (Kind::Int64, device));
+        let relative_positions = (key_positions.unsqueeze(0) - query_positions.unsqueeze(-1))
+            .abs()
+            .to_kind(Kind::Float);
+        let num_heads = self.slopes.len() as i64;
+        let slopes = Tensor::from_slice(&self.slopes)
+            .to_kind(Kind::Float)
+            .to_device(device)
+            * -1.0;
+        (slopes.view([num_heads, 1, 1]) * relative_positions.unsqueeze(0)).unsqueeze(0)
+    }
+}
+
+impl PositionEmbedding for AlibiBias {
+    fn attention_bias(&self, query_length: i64, key_length: i64, device: Device) -> Option<Tensor> {
+        Some(self.forward(query_length, key_length, device))
+    }
+}
+
+/// Maps relative positions (*key position - query position*) to buckets: small distances get a bucket each, larger
+/// distances share logarithmically sized buckets up to `max_distance`. Bidirectional buckets distinguish the sign
+/// of the relative position (splitting the buckets between positive and negative positions).
+///
+/// # Arguments
+///
+/// * `relative_position` - `Tensor` of relative positions (Int64)
+/// * `bidirectional` - flag indicating if the buckets distinguish positive and negative positions
+/// * `num_buckets` - number of buckets
+/// * `max_distance` - distance beyond which all positions share the last bucket
+pub fn relative_position_bucket(
+    relative_position: &Tensor,
+    bidirectional: bool,
+    num_buckets: i64,
+    max_distance: i64,
+) -> Tensor {
+    let n = -relative_position;
+    let mut num_buckets = num_buckets;
+    let mut ret = n.zeros_like();
+    let n = if bidirectional {
+        num_buckets /= 2;
+        ret += n.lt(0).to_kind(Kind::Int64) * num_buckets;
+        n.abs()
+    } else {
+        n.max_other(&n.zeros_like())
+    };
+
+    let max_exact = num_buckets / 2;
+    let is_small = n.lt(max_exact);
+
+    let value_if_large: Tensor = ((n.to_kind(Kind::Float) / max_exact as f64).log2()
+        / (max_distance as f64 / max_exact as f64).log2()
+        * (num_buckets - max_exact) as f64)
+        .to_kind(Kind::Int64)
+        + max_exact;
+
+    let value_if_large = value_if_large.min_other(&value_if_large.full_like(num_buckets - 1));
+    ret += n.where_self(&is_small, &value_if_large);
+    ret
+}
+
+#[derive(Debug)]
+/// # Bucketed relative position biases
+/// Learned bias of the attention scores for each bucket of relative positions and head (T5 formulation).
+pub struct RelativePositionBias {
+    embedding: nn::Embedding,
+    num_buckets: i64,
+    max_distance: i64,
+    bidirectional: bool,
+}
+
+impl RelativePositionBias {
+    /// Creates the relative position biases (variable `weight` of shape (*num_buckets*, *num_heads*) under the path
+    /// provided)
+    pub fn new<'p, P>(
+        p: P,
+        num_heads: i64,
+        num_buckets: i64,
+        max_distance: i64,
+        bidirectional: bool,
+    ) -> RelativePositionBias
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let embedding = embedding(
+            p.borrow(),
+            num_buckets,
+            num_heads,
+            EmbeddingConfig::default(),
+        );
+        RelativePositionBias {
+            embedding,
+            num_buckets,
+            max_distance,
+            bidirectional,
+        }
+    }
+
+    /// Builds the attention bias of shape (1, *num_heads*, *query_length*, *key_length*)
+    pub fn forward(&self, query_length: i64, key_length: i64, device: Device) -> Tensor {
+        let query_positions =
+            Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+                .unsqueeze(1);
+        let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+        relative_position_bucket(
+            &(key_positions - query_positions),
+            self.bidirectional,
+            self.num_buckets,
+            self.max_distance,
+        )
+        .apply(&self.embedding)
+        .permute([2, 0, 1])
+        .unsqueeze(0)
+    }
+}
+
+impl PositionEmbedding for RelativePositionBias {
+    fn attention_bias(&self, query_length: i64, key_length: i64, device: Device) -> Option<Tensor> {
+        Some(self.forward(query_length, key_length, device))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn alibi_slopes_power_of_2() {
+        let slopes = alibi_head_slopes(8);
+        let expected = (1..=8).map(|i| 2f64.powi(-i)).collect::<Vec<f64>>();
+        for (slope, expected) in slopes.iter().zip(expected.iter()) {
+            assert!((slope - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn alibi_slopes_non_power_of_2() {
+        let slopes = alibi_head_slopes(12);
+        assert_eq!(slopes.len(), 12);
+        assert!((slopes[0] - 0.5).abs() < 1e-9);
+        assert!((slopes[8] - 2f64.powf(-0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rotary_identity_at_position_zero() {
+        let x = Tensor::rand([1, 2, 3, 8], (Kind::Float, Device::Cpu));
+        for rotary_embedding in [
+            RotaryEmbedding::new(8, 1000.0, Device::Cpu),
+            RotaryEmbedding::new(8, 1000.0, Device::Cpu).interleaved(),
+        ]
+        .iter()
+        {
+            let (cos, sin) =
+                rotary_embedding.forward(&Tensor::arange(3, (Kind::Int64, Device::Cpu)), x.kind());
+            let rotated = rotary_embedding.rotate(&x, &cos, &sin);
+            let diff = (rotated.narrow(2, 0, 1) - x.narrow(2, 0, 1))
+                .abs()
+                .max()
+                .double_value(&[]);
+            assert!(diff < 1e-6);
+        }
+    }
+
+    #[test]
+    fn sinusoidal_layouts() {
+        let interleaved = sinusoidal_table(4, 6, SinusoidalLayout::Interleaved, Device::Cpu);
+        let concatenated = sinusoidal_table(4, 6, SinusoidalLayout::Concatenated, Device::Cpu);
+        let angle = 3f64 / 10000f64.powf(2.0 / 6.0);
+        assert!((interleaved.double_value(&[3, 2]) - angle.sin()).abs() < 1e-6);
+        assert!((interleaved.double_value(&[3, 3]) - angle.cos()).abs() < 1e-6);
+        assert!((concatenated.double_value(&[3, 1]) - angle.sin()).abs() < 1e-6);
+        assert!((concatenated.double_value(&[3, 4]) - angle.cos()).abs() < 1e-6);
+    }
+}
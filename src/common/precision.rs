@@ -0,0 +1,185 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Numerical precision of the attention
+//! Half precision (`Kind::Half` or `Kind::BFloat16`) models accumulate the attention matrix multiplications in half
+//! precision, which may lead to a drift of the outputs for long inputs (the attention scores and weighted sums
+//! accumulate over the whole sequence). When FP32 accumulation is enabled, the attention scores are computed, masked
+//! and normalized in single precision, and the weighted sum of the values is accumulated in single precision before
+//! being cast back to the precision of the model. The weights of the models are kept in their precision.
+//!
+//! The accumulation is enabled for the forward passes run within `with_attention_fp32_accumulation`, on the current
+//! thread only: other models of the process are not affected. The pipelines loading their weights in half precision
+//! (through the `kind` field of their configuration, for example `TextGenerationConfig` or `QuestionAnsweringConfig`)
+//! run their own forward passes with FP32 accumulation. Layer normalizations do not require any setting: libtorch
+//! computes their statistics in single precision for half precision inputs.
+//!
+//! ```no_run
+//! use rust_bert::precision::with_attention_fp32_accumulation;
+//! # use rust_bert::bert::{BertConfig, BertModel, BertEmbeddings};
+//! # use rust_bert::Config;
+//! # use tch::{nn, no_grad, Device, Kind, Tensor};
+//! # fn main() -> anyhow::Result<()> {
+//! # let config = BertConfig::from_file("path/to/config.json");
+//! let mut vs = nn::VarStore::new(Device::cuda_if_available());
+//! let model = BertModel::<BertEmbeddings>::new(vs.root(), &config);
+//! vs.set_kind(Kind::Half);
+//! # let input_ids = Tensor::ones([1, 512], (Kind::Int64, vs.device()));
+//! let output = with_attention_fp32_accumulation(true, || {
+//!     no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, None, None, false))
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::RustBertError;
+use std::cell::Cell;
+use tch::nn::VarStore;
+use tch::{Kind, Tensor};
+
+thread_local! {
+    static ATTENTION_FP32_ACCUMULATION: Cell<bool> = Cell::new(false);
+}
+
+/// Restores the accumulation setting of the thread once a closure completes (or panics)
+struct AccumulationGuard {
+    previous: bool,
+}
+
+impl Drop for AccumulationGuard {
+    fn drop(&mut self) {
+        ATTENTION_FP32_ACCUMULATION.with(|enabled| enabled.set(self.previous));
+    }
+}
+
+/// Runs a closure with the accumulation of the attention matrix multiplications of half precision models in single
+/// precision enabled or disabled. The setting applies to the current thread for the duration of the closure, and is
+/// restored afterwards.
+///
+/// # Arguments
+///
+/// * `enabled` - flag indicating if the attention should be accumulated in single precision
+/// * `f` - closure running the forward passes of the model
+pub fn with_attention_fp32_accumulation<T, F>(enabled: bool, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let previous = ATTENTION_FP32_ACCUMULATION.with(|current| current.replace(enabled));
+    let _guard = AccumulationGuard { previous };
+    f()
+}
+
+/// Checks if the attention matrix multiplications of half precision models are accumulated in single precision on
+/// the current thread (see `with_attention_fp32_accumulation`)
+pub fn attention_fp32_accumulation() -> bool {
+    ATTENTION_FP32_ACCUMULATION.with(Cell::get)
+}
+
+/// Checks if a precision requires the FP32 accumulation of the attention (half precision kinds)
+pub(crate) fn is_half_precision(kind: Kind) -> bool {
+    matches!(kind, Kind::Half | Kind::BFloat16)
+}
+
+/// Precision used to accumulate attention matrix multiplications of tensors of a given kind
+pub(crate) fn accumulation_kind(kind: Kind) -> Option<Kind> {
+    match kind {
+        Kind::Half | Kind::BFloat16 if attention_fp32_accumulation() => Some(Kind::Float),
+        _ => None,
+    }
+}
+
+/// Attention scores (`query x key`, `key` being already transposed). The scores are returned in single precision
+/// when FP32 accumulation is enabled, so that the masking and softmax are also computed in single precision.
+pub(crate) fn attention_scores(query: &Tensor, key: &Tensor) -> Tensor {
+    match accumulation_kind(query.kind()) {
+        Some(kind) => query.to_kind(kind).matmul(&key.to_kind(kind)),
+        None => query.matmul(key),
+    }
+}
+
+/// Attention output (`weights x value`), returned in the precision of the values
+pub(crate) fn attention_output(weights: &Tensor, value: &Tensor) -> Tensor {
+    match accumulation_kind(value.kind()) {
+        Some(kind) => weights
+            .to_kind(kind)
+            .matmul(&value.to_kind(kind))
+            .to_kind(value.kind()),
+        None => weights.matmul(value),
+    }
+}
+
+/// Checks that a precision is supported for the weights of a model
+pub(crate) fn check_model_kind(kind: Kind) -> Result<(), RustBertError> {
+    match kind {
+        Kind::Float | Kind::Double | Kind::Half | Kind::BFloat16 => Ok(()),
+        _ => Err(RustBertError::InvalidConfigurationError(format!(
+            "Invalid model precision {kind:?}, expected one of Float, Double, Half or BFloat16"
+        ))),
+    }
+}
+
+/// Casts the floating point variables of a var store to the precision requested by a pipeline configuration
+pub(crate) fn set_var_store_kind(
+    var_store: &mut VarStore,
+    kind: Kind,
+) -> Result<(), RustBertError> {
+    check_model_kind(kind)?;
+    var_store.set_kind(kind);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tch::Device;
+
+    fn attention(query: &Tensor, key: &Tensor, value: &Tensor) -> Tensor {
+        let scores =
+            attention_scores(query, &key.transpose(-1, -2)) / (query.size()[2] as f64).sqrt();
+        let weights = scores.softmax(-1, scores.kind());
+        attention_output(&weights, value)
+    }
+
+    #[test]
+    fn half_precision_attention_accumulation() {
+        tch::manual_seed(0);
+        let (query, key, value) = (
+            Tensor::randn([2, 1024, 64], (Kind::Float, Device::Cpu)),
+            Tensor::randn([2, 1024, 64], (Kind::Float, Device::Cpu)),
+            Tensor::randn([2, 1024, 64], (Kind::Float, Device::Cpu)),
+        );
+        let reference = attention(&query, &key, &value);
+        let (half_query, half_key, half_value) = (
+            query.to_kind(Kind::BFloat16),
+            key.to_kind(Kind::BFloat16),
+            value.to_kind(Kind::BFloat16),
+        );
+
+        let accumulated = with_attention_fp32_accumulation(true, || {
+            assert!(attention_fp32_accumulation());
+            attention(&half_query, &half_key, &half_value)
+        });
+        assert!(!attention_fp32_accumulation());
+        assert_eq!(accumulated.kind(), Kind::BFloat16);
+        let accumulated_error = (accumulated.to_kind(Kind::Float) - &reference)
+            .abs()
+            .max()
+            .double_value(&[]);
+        assert!(accumulated_error < 2e-2);
+
+        let half_error = (attention(&half_query, &half_key, &half_value).to_kind(Kind::Float)
+            - &reference)
+            .abs()
+            .max()
+            .double_value(&[]);
+        assert!(accumulated_error <= half_error);
+    }
+}
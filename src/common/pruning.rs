@@ -0,0 +1,158 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Structured pruning
+//! Removes attention heads and intermediate (feed-forward) neurons from the layers of a loaded encoder, for example
+//! the least important ones identified by an importance analysis. The weight matrices of the pruned layers are
+//! rewritten in place: the pruned model is smaller and faster, and its outputs are identical to the outputs of the
+//! original model with the corresponding heads and neurons masked.
+//!
+//! Pruning is supported by the BERT models (`BertModel` and the BERT task-specific models). The variables of the
+//! `VarStore` are resized: to reload a saved pruned model, build the model from the original configuration and apply
+//! the same `PruningConfig` before loading the pruned weights.
+//!
+//! ```no_run
+//! use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+//! use rust_bert::pruning::PruningConfig;
+//! use rust_bert::Config;
+//! use std::collections::HashMap;
+//! use std::path::Path;
+//! use tch::{nn, Device};
+//! # fn main() -> anyhow::Result<()> {
+//! let mut vs = nn::VarStore::new(Device::Cpu);
+//! let config = BertConfig::from_file(Path::new("path/to/config.json"));
+//! let mut model = BertForSequenceClassification::new(vs.root(), &config)?;
+//! vs.load("path/to/model.ot")?;
+//!
+//! let pruning_config = PruningConfig {
+//!     heads: HashMap::from([(0, vec![2, 5]), (11, vec![0])]),
+//!     intermediate_neurons: HashMap::from([(3, (0..512).collect())]),
+//! };
+//! model.prune(&pruning_config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tch::{nn, no_grad, Device, Tensor};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// # Configuration for structured pruning
+/// Attention heads and intermediate neurons to remove, indexed by the layer index. Indices refer to the current heads
+/// and neurons of the layer (after previous pruning, if any).
+pub struct PruningConfig {
+    /// Attention heads to remove for each layer
+    pub heads: HashMap<usize, Vec<i64>>,
+    /// Intermediate (feed-forward) neurons to remove for each layer
+    pub intermediate_neurons: HashMap<usize, Vec<i64>>,
+}
+
+impl PruningConfig {
+    /// Checks that the pruned layers exist in a model with `num_layers` layers
+    pub(crate) fn validate(&self, num_layers: usize) -> Result<(), RustBertError> {
+        match self
+            .heads
+            .keys()
+            .chain(self.intermediate_neurons.keys())
+            .find(|layer| **layer >= num_layers)
+        {
+            Some(layer) => Err(RustBertError::ValueError(format!(
+                "Cannot prune layer {layer}: the model has {num_layers} layers"
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Indices of the rows kept when removing groups of `group_size` contiguous rows (e.g. attention heads) out of
+/// `num_groups` groups. Returns the number of groups kept and the indices of the rows kept.
+pub(crate) fn kept_indices(
+    num_groups: i64,
+    group_size: i64,
+    pruned_groups: &[i64],
+    group_name: &str,
+    device: Device,
+) -> Result<(i64, Tensor), RustBertError> {
+    if let Some(group) = pruned_groups
+        .iter()
+        .find(|group| (**group < 0) | (**group >= num_groups))
+    {
+        return Err(RustBertError::ValueError(format!(
+            "Cannot prune {group_name} {group}: the layer has {num_groups} {group_name}s"
+        )));
+    }
+    let kept_groups = (0..num_groups)
+        .filter(|group| !pruned_groups.contains(group))
+        .collect::<Vec<i64>>();
+    if kept_groups.is_empty() {
+        return Err(RustBertError::ValueError(format!(
+            "Cannot prune all {group_name}s of a layer"
+        )));
+    }
+    let indices = kept_groups
+        .iter()
+        .flat_map(|group| group * group_size..(group + 1) * group_size)
+        .collect::<Vec<i64>>();
+    Ok((
+        kept_groups.len() as i64,
+        Tensor::from_slice(&indices).to_device(device),
+    ))
+}
+
+/// Keeps the output features (`dim` = 0, including the bias) or input features (`dim` = 1) of a linear layer at the
+/// given indices, resizing its variables in place
+pub(crate) fn prune_linear(linear: &mut nn::Linear, indices: &Tensor, dim: i64) {
+    no_grad(|| {
+        let ws = linear.ws.index_select(dim, indices).contiguous();
+        linear.ws.set_data(&ws);
+        if dim == 0 {
+            if let Some(bs) = &mut linear.bs {
+                let pruned_bs = bs.index_select(0, indices).contiguous();
+                bs.set_data(&pruned_bs);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn kept_head_indices() {
+        let (num_heads, indices) = kept_indices(3, 2, &[1], "attention head", Device::Cpu).unwrap();
+        assert_eq!(num_heads, 2);
+        assert_eq!(Vec::<i64>::try_from(indices).unwrap(), vec![0, 1, 4, 5]);
+        assert!(kept_indices(3, 2, &[3], "attention head", Device::Cpu).is_err());
+        assert!(kept_indices(2, 2, &[0, 1], "attention head", Device::Cpu).is_err());
+    }
+
+    #[test]
+    fn pruned_linear_layer() {
+        let vs = nn::VarStore::new(Device::Cpu);
+        let mut linear = nn::linear(vs.root(), 4, 3, Default::default());
+        let input = Tensor::rand([2, 4], (tch::Kind::Float, Device::Cpu));
+        let output = input.apply(&linear);
+
+        prune_linear(&mut linear, &Tensor::from_slice(&[0i64, 2]), 0);
+        assert_eq!(linear.ws.size(), vec![2, 4]);
+        assert_eq!(linear.bs.as_ref().unwrap().size(), vec![2]);
+        assert!(input.apply(&linear).allclose(
+            &output.index_select(1, &Tensor::from_slice(&[0i64, 2])),
+            1e-5,
+            1e-8,
+            false
+        ));
+    }
+}
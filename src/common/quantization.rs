@@ -0,0 +1,471 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Weight quantization
+//! ## Int4 weight-only quantization
+//! Compresses the linear layer weights of a loaded model in memory, without requiring pre-quantized
+//! (e.g. GPTQ or AWQ) checkpoints. The weights of each output row are split in groups of `group_size`
+//! consecutive input features, and each group is quantized to 4-bit integers with its own scale and minimum
+//! (asymmetric quantization). Two 4-bit values are packed in each byte.
+//!
+//! Activations are not quantized: the weights are de-quantized to the original precision on the fly
+//! when a quantized layer is applied, trading some compute for a ~4x memory reduction compared to half precision.
+//!
+//! Quantization is available for decoder models through `TextGenerationModel::quantize_int4`:
+//!
+//! ```no_run
+//! use rust_bert::gpt_j::{
+//!     GptJConfigResources, GptJMergesResources, GptJModelResources, GptJVocabResources,
+//! };
+//! use rust_bert::pipelines::common::{ModelResource, ModelType};
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::quantization::Int4QuantizationConfig;
+//! use rust_bert::resources::RemoteResource;
+//! # fn main() -> anyhow::Result<()> {
+//! let generate_config = TextGenerationConfig {
+//!     model_type: ModelType::GPTJ,
+//!     model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+//!         GptJModelResources::GPT_J_TINY_RANDOM,
+//!     ))),
+//!     config_resource: Box::new(RemoteResource::from_pretrained(
+//!         GptJConfigResources::GPT_J_TINY_RANDOM,
+//!     )),
+//!     vocab_resource: Box::new(RemoteResource::from_pretrained(
+//!         GptJVocabResources::GPT_J_TINY_RANDOM,
+//!     )),
+//!     merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+//!         GptJMergesResources::GPT_J_TINY_RANDOM,
+//!     ))),
+//!     ..Default::default()
+//! };
+//! let mut model = TextGenerationModel::new(generate_config)?;
+//! model.quantize_int4(&Int4QuantizationConfig::default())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Dynamic int8 quantization
+//! Converts the linear layer weights of a loaded model to 8-bit integers, with a scale per output channel, for CPU
+//! inference. The activations are quantized on the fly (per batch) and the matrix multiplications are computed by the
+//! int8 kernels of FBGEMM, which requires an x86 CPU and a libtorch build including FBGEMM (the default for the
+//! official x86 builds). Compared to full precision, the weights are ~4x smaller and the linear layers typically run
+//! 2-3x faster. The embeddings, layer normalizations and task heads are kept in full precision.
+//!
+//! Dynamic quantization is available for the BERT, DistilBERT and RoBERTa models and the sequence classification,
+//! token classification and question answering pipelines built on them (e.g.
+//! `SequenceClassificationModel::quantize_dynamic`, the default sentiment model being a DistilBERT model). The other
+//! architectures return an error.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let mut model = SequenceClassificationModel::new(Default::default())?;
+//! model.quantize_dynamic()?;
+//! let output = model.predict(["This is a quantized model"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::tensor_parallel::{ColumnParallelLinear, RowParallelLinear};
+use crate::RustBertError;
+use tch::nn::{Linear, Module};
+use tch::{Device, Kind, Tensor};
+
+/// # Configuration for int4 weight-only quantization
+#[derive(Debug, Clone)]
+pub struct Int4QuantizationConfig {
+    /// Number of consecutive input features sharing a scale and minimum. Must be even and divide the input dimension
+    /// of the quantized layers. Smaller groups reduce the quantization error at the cost of a larger memory footprint.
+    pub group_size: i64,
+    /// Quantize the language model head projecting the hidden states to the vocabulary. It is often kept in full
+    /// precision as it is more sensitive to quantization errors.
+    pub quantize_lm_head: bool,
+}
+
+impl Default for Int4QuantizationConfig {
+    fn default() -> Self {
+        Int4QuantizationConfig {
+            group_size: 128,
+            quantize_lm_head: false,
+        }
+    }
+}
+
+/// # Group-wise int4 quantized weight matrix
+#[derive(Debug)]
+pub struct Int4Weight {
+    /// Packed 4-bit values of shape (`out_dim`, `in_dim / 2`), the even input features in the lower bits
+    pub packed: Tensor,
+    /// Quantization step of each group, of shape (`out_dim`, `in_dim / group_size`)
+    pub scales: Tensor,
+    /// Minimum value of each group, of shape (`out_dim`, `in_dim / group_size`)
+    pub minimums: Tensor,
+    /// Number of input features per group
+    pub group_size: i64,
+    /// Precision of the original weight, restored when de-quantizing
+    pub kind: Kind,
+}
+
+impl Int4Weight {
+    /// Quantize a 2-dimensional weight matrix of shape (`out_dim`, `in_dim`)
+    ///
+    /// # Arguments
+    ///
+    /// * `weight` - weight tensor to quantize
+    /// * `group_size` - number of consecutive input features sharing a scale and minimum
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Int4Weight, RustBertError>` quantized weight
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::quantization::Int4Weight;
+    /// use tch::{Device, Kind, Tensor};
+    ///
+    /// let weight = Tensor::randn([64, 256], (Kind::Float, Device::Cpu));
+    /// let quantized = Int4Weight::quantize(&weight, 128)?;
+    /// let reconstructed = quantized.dequantize();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn quantize(weight: &Tensor, group_size: i64) -> Result<Int4Weight, RustBertError> {
+        let (out_dim, in_dim) = weight.size2()?;
+        if group_size <= 0 || group_size % 2 != 0 || in_dim % group_size != 0 {
+            return Err(RustBertError::ValueError(format!(
+                "The group size ({group_size}) must be even and divide the input dimension ({in_dim})"
+            )));
+        }
+        let kind = weight.kind();
+        let _guard = tch::no_grad_guard();
+        let grouped = weight
+            .to_kind(Kind::Float)
+            .view([out_dim, in_dim / group_size, group_size]);
+        let (minimums, _) = grouped.min_dim(-1, true);
+        let (maximums, _) = grouped.max_dim(-1, true);
+        let scales = ((&maximums - &minimums) / 15.0).clamp_min(1e-8);
+        let quantized = ((&grouped - &minimums) / &scales)
+            .round()
+            .clamp(0.0, 15.0)
+            .view([out_dim, in_dim / 2, 2]);
+        let packed = (quantized.select(2, 0) + quantized.select(2, 1) * 16.0).to_kind(Kind::Uint8);
+
+        Ok(Int4Weight {
+            packed,
+            scales: scales.squeeze_dim(-1).to_kind(kind),
+            minimums: minimums.squeeze_dim(-1).to_kind(kind),
+            group_size,
+            kind,
+        })
+    }
+
+    /// Returns the de-quantized weight, of shape (`out_dim`, `in_dim`) and of the original precision
+    pub fn dequantize(&self) -> Tensor {
+        let out_dim = self.packed.size()[0];
+        let packed = self.packed.to_kind(Kind::Float);
+        let high = (&packed / 16.0).floor();
+        let low = &packed - &high * 16.0;
+        let quantized = Tensor::stack(&[low, high], -1).view([out_dim, -1, self.group_size]);
+        (quantized * self.scales.to_kind(Kind::Float).unsqueeze(-1)
+            + self.minimums.to_kind(Kind::Float).unsqueeze(-1))
+        .view([out_dim, -1])
+        .to_kind(self.kind)
+    }
+
+    /// Returns the memory used by the quantized weight, in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        let packed_size = self.packed.numel();
+        let parameters_size =
+            (self.scales.numel() + self.minimums.numel()) * self.kind.elt_size_in_bytes();
+        packed_size + parameters_size
+    }
+}
+
+/// # Linear layer with an int4 quantized weight
+#[derive(Debug)]
+pub struct Int4Linear {
+    /// Quantized weight
+    pub weight: Int4Weight,
+    /// Optional full precision bias
+    pub bias: Option<Tensor>,
+}
+
+impl Int4Linear {
+    /// Quantize a full precision linear layer. The storage of the original weight is released, including for the
+    /// variable store it was loaded from: the variable store can no longer be used to reload or save the model weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `linear` - full precision linear layer
+    /// * `group_size` - number of consecutive input features sharing a scale and minimum
+    pub fn from_linear(mut linear: Linear, group_size: i64) -> Result<Int4Linear, RustBertError> {
+        let weight = Int4Weight::quantize(&linear.ws, group_size)?;
+        let _guard = tch::no_grad_guard();
+        let empty = Tensor::empty([0], (linear.ws.kind(), linear.ws.device()));
+        linear.ws.set_data(&empty);
+        Ok(Int4Linear {
+            weight,
+            bias: linear.bs,
+        })
+    }
+}
+
+impl Module for Int4Linear {
+    fn forward(&self, xs: &Tensor) -> Tensor {
+        let output = xs.matmul(&self.weight.dequantize().tr());
+        match &self.bias {
+            Some(bias) => output + bias,
+            None => output,
+        }
+    }
+}
+
+/// # Linear layer with an int8 quantized weight
+/// The rows of the weight are normalized by their maximum absolute value before being quantized, the normalization
+/// factors being applied to the outputs of the layer: the weight is effectively quantized with a scale per output
+/// channel. The inputs are quantized dynamically by the FBGEMM kernel.
+#[derive(Debug)]
+pub struct Int8Linear {
+    weight: Tensor,
+    packed_weight: Tensor,
+    column_offsets: Tensor,
+    weight_scale: f64,
+    weight_zero_point: i64,
+    channel_scales: Tensor,
+    zero_bias: Tensor,
+    bias: Option<Tensor>,
+}
+
+impl Int8Linear {
+    /// Quantize a full precision linear layer placed on the CPU. The storage of the original weight is released,
+    /// including for the variable store it was loaded from: the variable store can no longer be used to reload or
+    /// save the model weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `linear` - full precision linear layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::quantization::Int8Linear;
+    /// use tch::nn::{self, Module};
+    /// use tch::{Device, Kind, Tensor};
+    ///
+    /// let vs = nn::VarStore::new(Device::Cpu);
+    /// let linear = nn::linear(vs.root(), 256, 64, Default::default());
+    /// let quantized = Int8Linear::from_linear(linear)?;
+    /// let output = quantized.forward(&Tensor::randn([8, 256], (Kind::Float, Device::Cpu)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_linear(mut linear: Linear) -> Result<Int8Linear, RustBertError> {
+        if linear.ws.device() != Device::Cpu {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Dynamic int8 quantization is only available for models placed on the CPU"
+                    .to_string(),
+            ));
+        }
+        let _guard = tch::no_grad_guard();
+        let weight = linear.ws.to_kind(Kind::Float);
+        let channel_scales = weight.abs().amax([1].as_slice(), false).clamp_min(1e-8);
+        let normalized_weight = &weight / channel_scales.unsqueeze(1);
+        let (weight, column_offsets, weight_scale, weight_zero_point) =
+            normalized_weight.fbgemm_linear_quantize_weight();
+        let packed_weight = weight.fbgemm_pack_quantized_matrix();
+        let zero_bias = channel_scales.zeros_like();
+        let bias = linear.bs.as_ref().map(|bias| bias.to_kind(Kind::Float));
+
+        let empty = Tensor::empty([0], (linear.ws.kind(), linear.ws.device()));
+        linear.ws.set_data(&empty);
+        Ok(Int8Linear {
+            weight,
+            packed_weight,
+            column_offsets,
+            weight_scale,
+            weight_zero_point,
+            channel_scales,
+            zero_bias,
+            bias,
+        })
+    }
+
+    /// Returns the memory used by the quantized weight (including its packed copy used by the kernel), in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        let float_parameters = self.channel_scales.numel()
+            + self.zero_bias.numel()
+            + self.bias.as_ref().map_or(0, Tensor::numel);
+        self.weight.numel()
+            + self.packed_weight.numel()
+            + self.column_offsets.numel() * Kind::Int.elt_size_in_bytes()
+            + float_parameters * Kind::Float.elt_size_in_bytes()
+    }
+}
+
+impl Module for Int8Linear {
+    fn forward(&self, xs: &Tensor) -> Tensor {
+        let output = xs
+            .to_kind(Kind::Float)
+            .fbgemm_linear_int8_weight_fp32_activation(
+                &self.weight,
+                &self.packed_weight,
+                &self.column_offsets,
+                self.weight_scale,
+                self.weight_zero_point as f64,
+                &self.zero_bias,
+            )
+            * &self.channel_scales;
+        let output = match &self.bias {
+            Some(bias) => output + bias,
+            None => output,
+        };
+        output.to_kind(xs.kind())
+    }
+}
+
+/// # Linear layer that can be quantized or split across devices in place
+#[derive(Debug)]
+pub(crate) enum QuantizableLinear {
+    Full(Linear),
+    Int4(Int4Linear),
+    Int8(Int8Linear),
+    ColumnParallel(ColumnParallelLinear),
+    RowParallel(RowParallelLinear),
+}
+
+impl From<Linear> for QuantizableLinear {
+    fn from(linear: Linear) -> Self {
+        QuantizableLinear::Full(linear)
+    }
+}
+
+impl QuantizableLinear {
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        match self {
+            QuantizableLinear::Full(linear) => {
+                let linear = shallow_clone_linear(linear);
+                *self =
+                    QuantizableLinear::Int4(Int4Linear::from_linear(linear, config.group_size)?);
+                Ok(())
+            }
+            QuantizableLinear::Int4(_) => Ok(()),
+            QuantizableLinear::Int8(_) => Err(already_quantized()),
+            QuantizableLinear::ColumnParallel(_) | QuantizableLinear::RowParallel(_) => {
+                Err(split_not_quantizable())
+            }
+        }
+    }
+
+    /// Quantize the layer to int8 with a dynamic quantization of its inputs (see `Int8Linear`)
+    pub(crate) fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        match self {
+            QuantizableLinear::Full(linear) => {
+                let linear = shallow_clone_linear(linear);
+                *self = QuantizableLinear::Int8(Int8Linear::from_linear(linear)?);
+                Ok(())
+            }
+            QuantizableLinear::Int8(_) => Ok(()),
+            QuantizableLinear::Int4(_) => Err(already_quantized()),
+            QuantizableLinear::ColumnParallel(_) | QuantizableLinear::RowParallel(_) => {
+                Err(split_not_quantizable())
+            }
+        }
+    }
+
+    /// Returns the full precision layer, e.g. to rewrite its weights in place when pruning
+    pub(crate) fn full_mut(&mut self) -> Result<&mut Linear, RustBertError> {
+        match self {
+            QuantizableLinear::Full(linear) => Ok(linear),
+            _ => Err(RustBertError::InvalidConfigurationError(
+                "Quantized or split layers cannot be modified in place".to_string(),
+            )),
+        }
+    }
+
+    /// Split the layer along its output dimension across devices (see `ColumnParallelLinear`)
+    pub(crate) fn split_columns(
+        &mut self,
+        devices: &[Device],
+        alignment: i64,
+    ) -> Result<(), RustBertError> {
+        match self {
+            QuantizableLinear::Full(linear) => {
+                let linear = shallow_clone_linear(linear);
+                *self = QuantizableLinear::ColumnParallel(ColumnParallelLinear::from_linear(
+                    linear, devices, alignment,
+                )?);
+                Ok(())
+            }
+            _ => Err(unsupported_split()),
+        }
+    }
+
+    /// Split the layer along its input dimension across devices (see `RowParallelLinear`)
+    pub(crate) fn split_rows(
+        &mut self,
+        devices: &[Device],
+        alignment: i64,
+    ) -> Result<(), RustBertError> {
+        match self {
+            QuantizableLinear::Full(linear) => {
+                let linear = shallow_clone_linear(linear);
+                *self = QuantizableLinear::RowParallel(RowParallelLinear::from_linear(
+                    linear, devices, alignment,
+                )?);
+                Ok(())
+            }
+            _ => Err(unsupported_split()),
+        }
+    }
+}
+
+fn shallow_clone_linear(linear: &Linear) -> Linear {
+    Linear {
+        ws: linear.ws.shallow_clone(),
+        bs: linear.bs.as_ref().map(Tensor::shallow_clone),
+    }
+}
+
+fn already_quantized() -> RustBertError {
+    RustBertError::InvalidConfigurationError(
+        "The layer is already quantized with a different scheme".to_string(),
+    )
+}
+
+fn split_not_quantizable() -> RustBertError {
+    RustBertError::InvalidConfigurationError(
+        "Layers split across devices cannot be quantized".to_string(),
+    )
+}
+
+fn unsupported_split() -> RustBertError {
+    RustBertError::InvalidConfigurationError(
+        "Only full precision layers that are not yet split can be split across devices".to_string(),
+    )
+}
+
+impl Module for QuantizableLinear {
+    fn forward(&self, xs: &Tensor) -> Tensor {
+        match self {
+            QuantizableLinear::Full(linear) => linear.forward(xs),
+            QuantizableLinear::Int4(linear) => linear.forward(xs),
+            QuantizableLinear::Int8(linear) => linear.forward(xs),
+            QuantizableLinear::ColumnParallel(linear) => linear.forward(xs),
+            QuantizableLinear::RowParallel(linear) => linear.forward(xs),
+        }
+    }
+}
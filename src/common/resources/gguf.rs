@@ -0,0 +1,507 @@
+use crate::common::error::RustBertError;
+use half::f16;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tch::nn::VarStore;
+use tch::{Kind, Tensor};
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+// Number of values of the quantization blocks of the Q4_0, Q4_1 and Q8_0 formats
+const QUANTIZATION_BLOCK_SIZE: usize = 32;
+
+// Arrays and strings larger than this are rejected to avoid allocating arbitrary amounts of memory for corrupted files
+const MAX_METADATA_LENGTH: u64 = 100 * 1024 * 1024;
+
+/// Returns true if the data starts with the magic number of GGUF files
+pub(crate) fn is_gguf(prefix: &[u8]) -> bool {
+    prefix.starts_with(GGUF_MAGIC)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Value of a GGUF metadata entry
+pub enum GgufValue {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    String(String),
+    Array(Vec<GgufValue>),
+}
+
+impl GgufValue {
+    /// Returns the value as an unsigned integer, `None` for negative or non-integer values
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            GgufValue::U8(value) => Some(value as u64),
+            GgufValue::U16(value) => Some(value as u64),
+            GgufValue::U32(value) => Some(value as u64),
+            GgufValue::U64(value) => Some(value),
+            GgufValue::I8(value) if value >= 0 => Some(value as u64),
+            GgufValue::I16(value) if value >= 0 => Some(value as u64),
+            GgufValue::I32(value) if value >= 0 => Some(value as u64),
+            GgufValue::I64(value) if value >= 0 => Some(value as u64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float, `None` for non-numeric values
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            GgufValue::F32(value) => Some(value as f64),
+            GgufValue::F64(value) => Some(value),
+            _ => self.as_u64().map(|value| value as f64),
+        }
+    }
+
+    /// Returns the value as a string slice, `None` for non-string values
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            GgufValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of an array, `None` for non-array values
+    pub fn as_array(&self) -> Option<&[GgufValue]> {
+        match self {
+            GgufValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// # Data type of a GGUF tensor
+pub enum GgmlType {
+    /// Single precision
+    F32,
+    /// Half precision
+    F16,
+    /// Brain floating point
+    BF16,
+    /// Blocks of 32 4-bit values sharing a half precision scale (symmetric quantization)
+    Q4_0,
+    /// Blocks of 32 4-bit values sharing a half precision scale and minimum (asymmetric quantization)
+    Q4_1,
+    /// Blocks of 32 8-bit values sharing a half precision scale
+    Q8_0,
+    /// Data type without de-quantization support (e.g. k-quants), identified by its GGML type id
+    Unsupported(u32),
+}
+
+impl GgmlType {
+    fn from_id(id: u32) -> GgmlType {
+        match id {
+            0 => GgmlType::F32,
+            1 => GgmlType::F16,
+            2 => GgmlType::Q4_0,
+            3 => GgmlType::Q4_1,
+            8 => GgmlType::Q8_0,
+            30 => GgmlType::BF16,
+            _ => GgmlType::Unsupported(id),
+        }
+    }
+
+    /// Returns the number of bytes used to store `num_elements` values, `None` for unsupported types or for a
+    /// number of elements that is not a multiple of the quantization block size
+    fn size_in_bytes(&self, num_elements: usize) -> Option<usize> {
+        let block_bytes = match self {
+            GgmlType::F32 => return Some(4 * num_elements),
+            GgmlType::F16 | GgmlType::BF16 => return Some(2 * num_elements),
+            GgmlType::Q4_0 => 18,
+            GgmlType::Q4_1 => 20,
+            GgmlType::Q8_0 => 34,
+            GgmlType::Unsupported(_) => return None,
+        };
+        if num_elements % QUANTIZATION_BLOCK_SIZE == 0 {
+            Some(num_elements / QUANTIZATION_BLOCK_SIZE * block_bytes)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// # Information on a tensor stored in a GGUF file
+pub struct GgufTensorInfo {
+    /// Name of the tensor (GGUF naming conventions, e.g. `blk.0.attn_q.weight`)
+    pub name: String,
+    /// Shape of the tensor, in the order of the Torch dimensions (the reverse of the GGML dimensions)
+    pub shape: Vec<i64>,
+    /// Data type of the tensor
+    pub ggml_type: GgmlType,
+    /// Offset of the tensor data from the start of the data section
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone)]
+/// # Header of a GGUF file
+/// Contains the metadata (architecture, hyper-parameters, tokenizer...) and the tensors information of a GGUF
+/// checkpoint, as produced by the llama.cpp conversion and quantization tools. Versions 2 and 3 of the format are
+/// supported.
+pub struct GgufFile {
+    /// Version of the GGUF format
+    pub version: u32,
+    /// Metadata entries
+    pub metadata: HashMap<String, GgufValue>,
+    /// Tensors information, in the order of the file
+    pub tensors: Vec<GgufTensorInfo>,
+    data_start: u64,
+}
+
+impl GgufFile {
+    /// Reads the header of a GGUF file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the GGUF file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::resources::GgufFile;
+    ///
+    /// let gguf_file = GgufFile::open("path/to/model-q4_0.gguf")?;
+    /// let architecture = gguf_file.architecture();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<GgufFile, RustBertError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|error| {
+            RustBertError::IOError(format!(
+                "Could not open the GGUF file {}: {error}",
+                path.display()
+            ))
+        })?;
+        GgufFile::read(&mut BufReader::new(file))
+    }
+
+    /// Reads the header of a GGUF file from a reader positioned at the start of the file
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<GgufFile, RustBertError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if !is_gguf(&magic) {
+            return Err(invalid_file("missing GGUF magic number"));
+        }
+        let version = read_u32(reader)?;
+        if !(2..=3).contains(&version) {
+            return Err(invalid_file(&format!(
+                "unsupported GGUF version {version} (versions 2 and 3 are supported)"
+            )));
+        }
+        let tensor_count = read_length(reader)?;
+        let metadata_count = read_length(reader)?;
+
+        let mut metadata = HashMap::new();
+        for _ in 0..metadata_count {
+            let key = read_string(reader)?;
+            let value_type = read_u32(reader)?;
+            let value = read_value(reader, value_type)?;
+            metadata.insert(key, value);
+        }
+
+        let mut tensors = Vec::new();
+        for _ in 0..tensor_count {
+            let name = read_string(reader)?;
+            let num_dimensions = read_u32(reader)?;
+            let mut shape = (0..num_dimensions)
+                .map(|_| read_u64(reader).map(|dimension| dimension as i64))
+                .collect::<Result<Vec<i64>, RustBertError>>()?;
+            shape.reverse();
+            let ggml_type = GgmlType::from_id(read_u32(reader)?);
+            let offset = read_u64(reader)?;
+            tensors.push(GgufTensorInfo {
+                name,
+                shape,
+                ggml_type,
+                offset,
+            });
+        }
+
+        let alignment = metadata
+            .get("general.alignment")
+            .and_then(GgufValue::as_u64)
+            .unwrap_or(32)
+            .max(1);
+        let position = reader.stream_position()?;
+        let data_start = match position % alignment {
+            0 => position,
+            remainder => position + alignment - remainder,
+        };
+        Ok(GgufFile {
+            version,
+            metadata,
+            tensors,
+            data_start,
+        })
+    }
+
+    /// Returns the architecture of the model (`general.architecture` metadata entry, e.g. `llama`)
+    pub fn architecture(&self) -> Option<&str> {
+        self.metadata
+            .get("general.architecture")
+            .and_then(GgufValue::as_str)
+    }
+
+    /// Returns a metadata entry specific to the architecture of the model, e.g. `llama.block_count` for the key
+    /// `block_count` of a LLaMA model
+    pub fn architecture_value(&self, key: &str) -> Option<&GgufValue> {
+        self.metadata
+            .get(&format!("{}.{key}", self.architecture()?))
+    }
+
+    /// Returns the information of a tensor, `None` if the file does not contain the tensor
+    pub fn tensor_info(&self, name: &str) -> Option<&GgufTensorInfo> {
+        self.tensors.iter().find(|tensor| tensor.name == name)
+    }
+
+    /// Reads a tensor from the file. Half precision tensors are returned in their original precision, quantized
+    /// tensors are de-quantized to single precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reader of the GGUF file the header was read from
+    /// * `tensor_info` - Information of the tensor to read
+    pub fn read_tensor<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        tensor_info: &GgufTensorInfo,
+    ) -> Result<Tensor, RustBertError> {
+        let num_elements = tensor_info.shape.iter().product::<i64>() as usize;
+        let num_bytes = tensor_info
+            .ggml_type
+            .size_in_bytes(num_elements)
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(format!(
+                    "Unsupported data type {:?} for the GGUF tensor {} of shape {:?}",
+                    tensor_info.ggml_type, tensor_info.name, tensor_info.shape
+                ))
+            })?;
+        let mut data = vec![0u8; num_bytes];
+        reader.seek(SeekFrom::Start(self.data_start + tensor_info.offset))?;
+        reader.read_exact(&mut data)?;
+
+        let shape = &tensor_info.shape;
+        Ok(match tensor_info.ggml_type {
+            GgmlType::F32 => Tensor::f_from_data_size(&data, shape, Kind::Float)?,
+            GgmlType::F16 => Tensor::f_from_data_size(&data, shape, Kind::Half)?,
+            GgmlType::BF16 => Tensor::f_from_data_size(&data, shape, Kind::BFloat16)?,
+            _ => Tensor::f_from_slice(&dequantize(&data, tensor_info.ggml_type))?
+                .f_view(&shape[..])?,
+        })
+    }
+}
+
+/// De-quantizes blocks of 32 Q4_0, Q4_1 or Q8_0 values to single precision
+fn dequantize(data: &[u8], ggml_type: GgmlType) -> Vec<f32> {
+    let block_bytes = match ggml_type {
+        GgmlType::Q4_0 => 18,
+        GgmlType::Q4_1 => 20,
+        _ => 34,
+    };
+    let mut values = Vec::with_capacity(data.len() / block_bytes * QUANTIZATION_BLOCK_SIZE);
+    for block in data.chunks_exact(block_bytes) {
+        let scale = f16::from_le_bytes([block[0], block[1]]).to_f32();
+        match ggml_type {
+            GgmlType::Q4_0 | GgmlType::Q4_1 => {
+                let (minimum, quants) = match ggml_type {
+                    GgmlType::Q4_0 => (-8.0 * scale, &block[2..]),
+                    _ => (
+                        f16::from_le_bytes([block[2], block[3]]).to_f32(),
+                        &block[4..],
+                    ),
+                };
+                // The lower bits store the first half of the block, the upper bits the second half
+                values.extend(quants.iter().map(|q| (q & 0x0F) as f32 * scale + minimum));
+                values.extend(quants.iter().map(|q| (q >> 4) as f32 * scale + minimum));
+            }
+            _ => values.extend(block[2..].iter().map(|&q| q as i8 as f32 * scale)),
+        }
+    }
+    values
+}
+
+/// Converts the name of a tensor of a LLaMA-architecture GGUF checkpoint (LLaMA, Mistral...) to the name of the
+/// corresponding variable of `LlamaForCausalLM`
+pub(super) fn llama_variable_name(tensor_name: &str) -> Option<String> {
+    let name = match tensor_name {
+        "token_embd.weight" => "model.embed_tokens.weight".to_string(),
+        "output_norm.weight" => "model.norm.weight".to_string(),
+        "output.weight" => "lm_head.weight".to_string(),
+        _ => {
+            let mut parts = tensor_name.strip_prefix("blk.")?.splitn(3, '.');
+            let (layer, module, parameter) = (parts.next()?, parts.next()?, parts.next()?);
+            let module = match module {
+                "attn_q" => "self_attn.q_proj",
+                "attn_k" => "self_attn.k_proj",
+                "attn_v" => "self_attn.v_proj",
+                "attn_output" => "self_attn.o_proj",
+                "attn_norm" => "input_layernorm",
+                "ffn_norm" => "post_attention_layernorm",
+                "ffn_gate" => "mlp.gate_proj",
+                "ffn_up" => "mlp.up_proj",
+                "ffn_down" => "mlp.down_proj",
+                _ => return None,
+            };
+            format!("model.layers.{layer}.{module}.{parameter}")
+        }
+    };
+    Some(name)
+}
+
+/// Reverts the permutation of the query and key projections applied by the llama.cpp conversion, which interleaves
+/// the two halves of the rotary dimensions of each head
+fn unpermute_rotary(tensor: &Tensor, num_heads: i64) -> Tensor {
+    let size = tensor.size();
+    let mut shape = vec![num_heads, 2, size[0] / num_heads / 2];
+    shape.extend_from_slice(&size[1..]);
+    let mut permuted_shape = shape.clone();
+    permuted_shape.swap(1, 2);
+    tensor
+        .view(&permuted_shape[..])
+        .transpose(1, 2)
+        .reshape(&size[..])
+}
+
+/// Loads the variables of a `VarStore` from a GGUF checkpoint of the LLaMA architecture. The tensors are
+/// de-quantized and converted to the precision and device of the variables. Tensors of the file without a matching
+/// variable are ignored, and all the variables must be found in the file.
+pub(crate) fn load_gguf<R: Read + Seek>(
+    var_store: &mut VarStore,
+    mut reader: R,
+) -> Result<(), RustBertError> {
+    let gguf_file = GgufFile::read(&mut reader)?;
+    match gguf_file.architecture() {
+        Some("llama") => {}
+        architecture => {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Unsupported GGUF architecture {}: only the LLaMA architecture (LLaMA, Mistral...) can be loaded",
+                architecture.unwrap_or("(missing)")
+            )));
+        }
+    }
+    let num_heads = gguf_file
+        .architecture_value("attention.head_count")
+        .and_then(GgufValue::as_u64)
+        .ok_or_else(|| invalid_file("missing llama.attention.head_count"))?
+        as i64;
+    let num_kv_heads = gguf_file
+        .architecture_value("attention.head_count_kv")
+        .and_then(GgufValue::as_u64)
+        .map_or(num_heads, |value| value as i64);
+
+    let tensors = gguf_file
+        .tensors
+        .iter()
+        .filter_map(|tensor_info| Some((llama_variable_name(&tensor_info.name)?, tensor_info)))
+        .collect::<HashMap<String, &GgufTensorInfo>>();
+
+    let mut assignments = Vec::new();
+    let mut missing_variables = Vec::new();
+    for (name, variable) in var_store.variables() {
+        match tensors.get(&name) {
+            Some(tensor_info) => assignments.push((name, variable, *tensor_info)),
+            None => missing_variables.push(name),
+        }
+    }
+    if !missing_variables.is_empty() {
+        missing_variables.sort();
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The GGUF checkpoint does not contain the variables: {}",
+            missing_variables.join(", ")
+        )));
+    }
+    // Reading the tensors in the order of the file
+    assignments.sort_by_key(|(_, _, tensor_info)| tensor_info.offset);
+
+    let _guard = tch::no_grad_guard();
+    for (name, mut variable, tensor_info) in assignments {
+        if tensor_info.shape != variable.size() {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Shape mismatch for {name}: the model expects {:?}, the checkpoint contains {:?}",
+                variable.size(),
+                tensor_info.shape
+            )));
+        }
+        let mut tensor = gguf_file.read_tensor(&mut reader, tensor_info)?;
+        if name.ends_with("q_proj.weight") || name.ends_with("q_proj.bias") {
+            tensor = unpermute_rotary(&tensor, num_heads);
+        } else if name.ends_with("k_proj.weight") || name.ends_with("k_proj.bias") {
+            tensor = unpermute_rotary(&tensor, num_kv_heads);
+        }
+        variable.f_copy_(&tensor)?;
+    }
+    Ok(())
+}
+
+fn invalid_file(message: &str) -> RustBertError {
+    RustBertError::InvalidConfigurationError(format!("Invalid GGUF file: {message}"))
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], RustBertError> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, RustBertError> {
+    Ok(u32::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, RustBertError> {
+    Ok(u64::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_length<R: Read>(reader: &mut R) -> Result<u64, RustBertError> {
+    let length = read_u64(reader)?;
+    if length > MAX_METADATA_LENGTH {
+        return Err(invalid_file(&format!("length {length} too large")));
+    }
+    Ok(length)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, RustBertError> {
+    let mut bytes = vec![0u8; read_length(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|error| invalid_file(&error.to_string()))
+}
+
+fn read_value<R: Read>(reader: &mut R, value_type: u32) -> Result<GgufValue, RustBertError> {
+    Ok(match value_type {
+        0 => GgufValue::U8(u8::from_le_bytes(read_bytes(reader)?)),
+        1 => GgufValue::I8(i8::from_le_bytes(read_bytes(reader)?)),
+        2 => GgufValue::U16(u16::from_le_bytes(read_bytes(reader)?)),
+        3 => GgufValue::I16(i16::from_le_bytes(read_bytes(reader)?)),
+        4 => GgufValue::U32(u32::from_le_bytes(read_bytes(reader)?)),
+        5 => GgufValue::I32(i32::from_le_bytes(read_bytes(reader)?)),
+        6 => GgufValue::F32(f32::from_le_bytes(read_bytes(reader)?)),
+        7 => GgufValue::Bool(u8::from_le_bytes(read_bytes(reader)?) != 0),
+        8 => GgufValue::String(read_string(reader)?),
+        9 => {
+            let element_type = read_u32(reader)?;
+            let length = read_length(reader)?;
+            GgufValue::Array(
+                (0..length)
+                    .map(|_| read_value(reader, element_type))
+                    .collect::<Result<Vec<GgufValue>, RustBertError>>()?,
+            )
+        }
+        10 => GgufValue::U64(u64::from_le_bytes(read_bytes(reader)?)),
+        11 => GgufValue::I64(i64::from_le_bytes(read_bytes(reader)?)),
+        12 => GgufValue::F64(f64::from_le_bytes(read_bytes(reader)?)),
+        _ => return Err(invalid_file(&format!("unknown value type {value_type}"))),
+    })
+}
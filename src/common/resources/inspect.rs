@@ -0,0 +1,312 @@
+use crate::common::error::RustBertError;
+use crate::common::resources::gguf::{is_gguf, llama_variable_name, GgmlType, GgufFile};
+use crate::common::resources::safetensors::{
+    find_tensor, is_safetensors, read_header, SAFETENSORS_PREFIX_BYTES,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tch::nn::VarStore;
+use tch::{Kind, Tensor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// # Format of a checkpoint file
+pub enum CheckpointFormat {
+    /// Torch `.ot` checkpoint (converted with `utils/convert_model.py`)
+    Torch,
+    /// Safetensors checkpoint
+    Safetensors,
+    /// GGUF checkpoint
+    Gguf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Tensor stored in a checkpoint
+pub struct CheckpointTensor {
+    /// Name of the tensor, converted to the names of the variables as done when loading the checkpoint (legacy
+    /// `gamma` and `beta` names of safetensors checkpoints, GGUF names of the LLaMA architecture)
+    pub name: String,
+    /// Shape of the tensor
+    pub shape: Vec<i64>,
+    /// Data type of the tensor (e.g. `F32`, `BF16`, or the GGML type of GGUF tensors such as `Q4_0`)
+    pub dtype: String,
+}
+
+impl CheckpointTensor {
+    /// Number of elements of the tensor
+    pub fn num_elements(&self) -> i64 {
+        self.shape.iter().product()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Variable of a model with a different shape in a checkpoint
+pub struct ShapeMismatch {
+    /// Name of the variable
+    pub name: String,
+    /// Shape of the variable of the model
+    pub expected_shape: Vec<i64>,
+    /// Shape of the tensor of the checkpoint
+    pub checkpoint_shape: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// # Compatibility of a checkpoint with the variables of a model
+pub struct CompatibilityReport {
+    /// Variables of the model not found in the checkpoint, sorted by name
+    pub missing: Vec<String>,
+    /// Tensors of the checkpoint not matching any variable of the model (ignored when loading), sorted by name
+    pub unexpected: Vec<String>,
+    /// Variables of the model with a different shape in the checkpoint, sorted by name
+    pub mismatched: Vec<ShapeMismatch>,
+}
+
+impl CompatibilityReport {
+    /// Returns true if the checkpoint can be loaded in the model: all the variables are found with their shape
+    pub fn is_compatible(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.missing.is_empty() && self.unexpected.is_empty() && self.mismatched.is_empty() {
+            return write!(f, "The checkpoint matches the variables of the model");
+        }
+        let mut lines = Vec::new();
+        if !self.missing.is_empty() {
+            lines.push(format!(
+                "Missing variables ({}): {}",
+                self.missing.len(),
+                self.missing.join(", ")
+            ));
+        }
+        if !self.unexpected.is_empty() {
+            lines.push(format!(
+                "Unexpected tensors ({}): {}",
+                self.unexpected.len(),
+                self.unexpected.join(", ")
+            ));
+        }
+        if !self.mismatched.is_empty() {
+            let mismatches = self
+                .mismatched
+                .iter()
+                .map(|mismatch| {
+                    format!(
+                        "{} (model: {:?}, checkpoint: {:?})",
+                        mismatch.name, mismatch.expected_shape, mismatch.checkpoint_shape
+                    )
+                })
+                .collect::<Vec<String>>();
+            lines.push(format!(
+                "Shape mismatches ({}): {}",
+                mismatches.len(),
+                mismatches.join(", ")
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// # Content of a checkpoint
+/// Tensors of a checkpoint file, read with `inspect_checkpoint`
+pub struct CheckpointInfo {
+    /// Format of the checkpoint
+    pub format: CheckpointFormat,
+    /// Tensors of the checkpoint, sorted by name
+    pub tensors: Vec<CheckpointTensor>,
+}
+
+impl CheckpointInfo {
+    /// Total number of elements of the tensors of the checkpoint
+    pub fn num_parameters(&self) -> i64 {
+        self.tensors
+            .iter()
+            .map(CheckpointTensor::num_elements)
+            .sum()
+    }
+
+    /// Returns the tensor of the checkpoint with the given name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the tensor
+    pub fn tensor(&self, name: &str) -> Option<&CheckpointTensor> {
+        self.tensors.iter().find(|tensor| tensor.name == name)
+    }
+
+    /// Compares the tensors of the checkpoint with the variables of a model, matching them as done when loading
+    /// the weights: safetensors checkpoints saved with a model head can be matched with the base model (a unique
+    /// tensor whose name ends with `.{variable name}` is used) and checkpoints of the base model with a model head,
+    /// the other formats require the exact names.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_store` - `VarStore` holding the variables of the model (created for the architecture and
+    ///   configuration of the checkpoint, without loading the weights)
+    ///
+    /// # Returns
+    ///
+    /// * `CompatibilityReport` listing the missing, unexpected and mismatched entries
+    pub fn compatibility_report(&self, var_store: &VarStore) -> CompatibilityReport {
+        let tensor_indices = self
+            .tensors
+            .iter()
+            .enumerate()
+            .map(|(index, tensor)| (tensor.name.clone(), index))
+            .collect::<HashMap<String, usize>>();
+
+        let mut report = CompatibilityReport::default();
+        let mut matched_tensors = HashSet::new();
+        for (name, variable) in var_store.variables() {
+            let index = match self.format {
+                CheckpointFormat::Safetensors => find_tensor(&tensor_indices, &name),
+                CheckpointFormat::Torch | CheckpointFormat::Gguf => tensor_indices.get(&name),
+            };
+            match index {
+                Some(&index) => {
+                    matched_tensors.insert(index);
+                    let tensor = &self.tensors[index];
+                    if tensor.shape != variable.size() {
+                        report.mismatched.push(ShapeMismatch {
+                            name,
+                            expected_shape: variable.size(),
+                            checkpoint_shape: tensor.shape.clone(),
+                        });
+                    }
+                }
+                None => report.missing.push(name),
+            }
+        }
+        report.unexpected = self
+            .tensors
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !matched_tensors.contains(index))
+            .map(|(_, tensor)| tensor.name.clone())
+            .collect();
+        report.missing.sort();
+        report
+            .mismatched
+            .sort_by(|mismatch, other| mismatch.name.cmp(&other.name));
+        report
+    }
+}
+
+/// Name of a Torch precision, following the safetensors data types naming
+fn dtype_name(kind: Kind) -> String {
+    match kind {
+        Kind::Double => "F64".to_string(),
+        Kind::Float => "F32".to_string(),
+        Kind::Half => "F16".to_string(),
+        Kind::BFloat16 => "BF16".to_string(),
+        Kind::Int64 => "I64".to_string(),
+        Kind::Int => "I32".to_string(),
+        Kind::Int16 => "I16".to_string(),
+        Kind::Int8 => "I8".to_string(),
+        Kind::Uint8 => "U8".to_string(),
+        Kind::Bool => "BOOL".to_string(),
+        _ => format!("{kind:?}"),
+    }
+}
+
+fn ggml_type_name(ggml_type: GgmlType) -> String {
+    match ggml_type {
+        GgmlType::Unsupported(id) => format!("GGML type {id}"),
+        _ => format!("{ggml_type:?}"),
+    }
+}
+
+/// Lists the tensors of a checkpoint in the `.ot`, safetensors or GGUF format, without loading it in a model. The
+/// format is detected from the content of the file. Only the headers of safetensors and GGUF checkpoints are read,
+/// `.ot` checkpoints are read entirely (the format does not provide a header).
+///
+/// The returned `CheckpointInfo` can be compared with the variables of a model with
+/// `CheckpointInfo::compatibility_report` before attempting to load the checkpoint.
+///
+/// # Arguments
+///
+/// * `path` - path of the checkpoint file
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+/// use rust_bert::resources::inspect_checkpoint;
+/// use rust_bert::Config;
+/// use tch::{nn, Device};
+///
+/// let checkpoint = inspect_checkpoint("path/to/model.safetensors")?;
+/// for tensor in &checkpoint.tensors {
+///     println!("{} {:?} {}", tensor.name, tensor.shape, tensor.dtype);
+/// }
+///
+/// let config = BertConfig::from_file("path/to/config.json");
+/// let vs = nn::VarStore::new(Device::Cpu);
+/// let _ = BertForSequenceClassification::new(vs.root(), &config)?;
+/// let report = checkpoint.compatibility_report(&vs);
+/// if !report.is_compatible() {
+///     println!("{report}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect_checkpoint<P: AsRef<Path>>(path: P) -> Result<CheckpointInfo, RustBertError> {
+    let path = path.as_ref();
+    let mut file = File::open(path).map_err(|error| {
+        RustBertError::IOError(format!(
+            "Could not open the weights file {}: {error}",
+            path.display()
+        ))
+    })?;
+    let mut prefix = [0u8; SAFETENSORS_PREFIX_BYTES];
+    let has_prefix = file.read_exact(&mut prefix).is_ok();
+    file.seek(SeekFrom::Start(0))?;
+
+    let (format, mut tensors) = if has_prefix && is_safetensors(&prefix) {
+        let (tensors, _) = read_header(&mut BufReader::new(file))?;
+        let tensors = tensors
+            .into_iter()
+            .map(|(name, tensor_info)| CheckpointTensor {
+                name,
+                shape: tensor_info.shape,
+                dtype: tensor_info.dtype,
+            })
+            .collect::<Vec<CheckpointTensor>>();
+        (CheckpointFormat::Safetensors, tensors)
+    } else if has_prefix && is_gguf(&prefix) {
+        let gguf_file = GgufFile::read(&mut BufReader::new(file))?;
+        let is_llama = gguf_file.architecture() == Some("llama");
+        let tensors = gguf_file
+            .tensors
+            .into_iter()
+            .map(|tensor_info| CheckpointTensor {
+                name: if is_llama {
+                    llama_variable_name(&tensor_info.name).unwrap_or(tensor_info.name)
+                } else {
+                    tensor_info.name
+                },
+                shape: tensor_info.shape,
+                dtype: ggml_type_name(tensor_info.ggml_type),
+            })
+            .collect::<Vec<CheckpointTensor>>();
+        (CheckpointFormat::Gguf, tensors)
+    } else {
+        let tensors = Tensor::load_multi(path)?
+            .into_iter()
+            .map(|(name, tensor)| CheckpointTensor {
+                name,
+                shape: tensor.size(),
+                dtype: dtype_name(tensor.kind()),
+            })
+            .collect::<Vec<CheckpointTensor>>();
+        (CheckpointFormat::Torch, tensors)
+    };
+    tensors.sort_by(|tensor, other| tensor.name.cmp(&other.name));
+    Ok(CheckpointInfo { format, tensors })
+}
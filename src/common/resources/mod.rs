@@ -18,18 +18,47 @@
 //! `get_local_path`, allowing to reference the resource file location regardless if it is a remote
 //! or local resource. Default implementations for a number of `RemoteResources` are available as
 //! pre-trained models in each model module.
+//!
+//! Model weights can be provided in the `.ot` format (converted from the Python checkpoints with
+//! `utils/convert_model.py`) or directly as [safetensors](https://github.com/huggingface/safetensors) checkpoints
+//! (e.g. `model.safetensors` files from the Hugging Face Hub). The format is detected from the content of the
+//! resource when loading the weights with `load_weights` or `load_weights_from_file`.
+//!
+//! Quantized [GGUF](https://github.com/ggerganov/ggml/blob/master/docs/gguf.md) checkpoints of the LLaMA
+//! architecture (LLaMA, Mistral...) produced by llama.cpp can also be loaded: the tensors stored in the `F32`, `F16`,
+//! `BF16`, `Q4_0`, `Q4_1` and `Q8_0` formats are de-quantized to the precision of the model when loading. The header
+//! of a GGUF file (metadata and tensors information) can be read with `GgufFile`.
+//!
+//! The tensors of a checkpoint (names, shapes and data types) can be listed with `inspect_checkpoint` without
+//! loading it, and compared with the variables of a model with `CheckpointInfo::compatibility_report` to find the
+//! missing, unexpected and mismatched entries before attempting a full load.
+//!
+//! The weights of models placed on Apple Silicon (MPS) devices are loaded on the CPU before being moved to the device.
 
 mod buffer;
+mod gguf;
+mod inspect;
 mod local;
+mod safetensors;
 
 use crate::common::error::RustBertError;
 pub use buffer::BufferResource;
+use gguf::{is_gguf, load_gguf};
+pub use gguf::{GgmlType, GgufFile, GgufTensorInfo, GgufValue};
+pub use inspect::{
+    inspect_checkpoint, CheckpointFormat, CheckpointInfo, CheckpointTensor, CompatibilityReport,
+    ShapeMismatch,
+};
 pub use local::LocalResource;
+use safetensors::{is_safetensors, load_safetensors, SAFETENSORS_PREFIX_BYTES};
 use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
 use std::ops::DerefMut;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLockWriteGuard;
 use tch::nn::VarStore;
+use tch::Device;
 
 pub enum Resource<'a> {
     PathBuf(PathBuf),
@@ -80,18 +109,147 @@ impl<T: ResourceProvider + ?Sized> ResourceProvider for Box<T> {
     }
 }
 
-/// Load the provided `VarStore` with model weights from the provided `ResourceProvider`
+/// Load the provided `VarStore` with model weights from the provided `ResourceProvider`.
+/// The weights can be stored in the `.ot`, safetensors or GGUF format.
 pub fn load_weights(
     rp: &(impl ResourceProvider + ?Sized),
     vs: &mut VarStore,
 ) -> Result<(), RustBertError> {
+    load_weights_partial(rp, vs, &[]).map(|_| ())
+}
+
+/// Loads the weights like `load_weights`, the `optional` variables being allowed to be missing from the checkpoint
+/// (e.g. an LM head tied to the input embeddings, not saved in the checkpoint). Returns the optional variables not
+/// found in the checkpoint, which are left unchanged. GGUF checkpoints must contain all the variables.
+pub(crate) fn load_weights_partial(
+    rp: &(impl ResourceProvider + ?Sized),
+    vs: &mut VarStore,
+    optional: &[&str],
+) -> Result<Vec<String>, RustBertError> {
     match rp.get_resource()? {
-        Resource::Buffer(mut data) => {
-            vs.load_from_stream(std::io::Cursor::new(data.deref_mut()))?;
-            Ok(())
+        Resource::Buffer(mut data) => load_on_cpu_for_mps(vs, |vs| {
+            if is_safetensors(&data) {
+                load_safetensors(vs, Cursor::new(&data[..]), optional)
+            } else if is_gguf(&data) {
+                load_gguf(vs, Cursor::new(&data[..]))?;
+                Ok(Vec::new())
+            } else if optional.is_empty() {
+                vs.load_from_stream(Cursor::new(data.deref_mut()))?;
+                Ok(Vec::new())
+            } else {
+                let missing_variables =
+                    vs.load_partial_from_stream(Cursor::new(data.deref_mut()))?;
+                check_missing_variables(missing_variables, optional)
+            }
+        }),
+        Resource::PathBuf(path) => load_weights_from_file_partial(path, vs, optional),
+    }
+}
+
+/// Load the provided `VarStore` with model weights from a local file in the `.ot`, safetensors or GGUF format.
+/// Safetensors and GGUF checkpoints are detected from the content of the file, regardless of its extension (remote
+/// resources are cached without their extension).
+///
+/// For safetensors checkpoints, the tensors are converted to the precision of the `VarStore`, and a checkpoint
+/// saved with a model head (e.g. `bert.encoder.layer.0...`) can be loaded in the base model (`encoder.layer.0...`),
+/// and a checkpoint of the base model (e.g. `h.0...` for BLOOM) in a model with a head (`transformer.h.0...`).
+/// The weights names `gamma` and `beta` are renamed to `weight` and `bias`, as done by `utils/convert_model.py`.
+///
+/// GGUF checkpoints must be of the LLaMA architecture: the llama.cpp tensor names are mapped to the variables of
+/// `LlamaForCausalLM`, and the quantized tensors are de-quantized and converted to the precision of the `VarStore`.
+///
+/// # Arguments
+///
+/// * `path` - path of the weights file
+/// * `vs` - `VarStore` holding the variables of the model to load
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+/// use rust_bert::resources::load_weights_from_file;
+/// use rust_bert::Config;
+/// use tch::{nn, Device};
+///
+/// let config = BertConfig::from_file("path/to/config.json");
+/// let mut vs = nn::VarStore::new(Device::cuda_if_available());
+/// let model = BertForSequenceClassification::new(vs.root(), &config)?;
+/// load_weights_from_file("path/to/model.safetensors", &mut vs)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_weights_from_file<P: AsRef<Path>>(
+    path: P,
+    vs: &mut VarStore,
+) -> Result<(), RustBertError> {
+    load_weights_from_file_partial(path, vs, &[]).map(|_| ())
+}
+
+fn load_weights_from_file_partial<P: AsRef<Path>>(
+    path: P,
+    vs: &mut VarStore,
+    optional: &[&str],
+) -> Result<Vec<String>, RustBertError> {
+    let path = path.as_ref();
+    let mut file = File::open(path).map_err(|error| {
+        RustBertError::IOError(format!(
+            "Could not open the weights file {}: {error}",
+            path.display()
+        ))
+    })?;
+    let mut prefix = [0u8; SAFETENSORS_PREFIX_BYTES];
+    let has_prefix = file.read_exact(&mut prefix).is_ok();
+    load_on_cpu_for_mps(vs, |vs| {
+        if has_prefix && is_safetensors(&prefix) {
+            file.seek(SeekFrom::Start(0))?;
+            load_safetensors(vs, BufReader::new(file), optional)
+        } else if has_prefix && is_gguf(&prefix) {
+            file.seek(SeekFrom::Start(0))?;
+            load_gguf(vs, BufReader::new(file))?;
+            Ok(Vec::new())
+        } else if optional.is_empty() {
+            vs.load(path)?;
+            Ok(Vec::new())
+        } else {
+            check_missing_variables(vs.load_partial(path)?, optional)
         }
-        Resource::PathBuf(path) => Ok(vs.load(path)?),
+    })
+}
+
+/// Returns the optional variables missing from a `.ot` checkpoint, and an error if other variables are missing
+fn check_missing_variables(
+    missing_variables: Vec<String>,
+    optional: &[&str],
+) -> Result<Vec<String>, RustBertError> {
+    let (missing_optional_variables, mut missing_variables): (Vec<String>, Vec<String>) =
+        missing_variables
+            .into_iter()
+            .partition(|name| optional.contains(&name.as_str()));
+    if !missing_variables.is_empty() {
+        missing_variables.sort();
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The checkpoint does not contain the variables: {}",
+            missing_variables.join(", ")
+        )));
+    }
+    Ok(missing_optional_variables)
+}
+
+/// Loads the weights of a `VarStore` placed on an MPS device on the CPU before moving them to the device: the
+/// checkpoints are read and converted to the precision of the model with operations (e.g. double precision
+/// conversions) that are not all supported on MPS devices.
+pub(crate) fn load_on_cpu_for_mps<T, F>(vs: &mut VarStore, load: F) -> Result<T, RustBertError>
+where
+    F: FnOnce(&mut VarStore) -> Result<T, RustBertError>,
+{
+    if vs.device() != Device::Mps {
+        return load(vs);
     }
+    vs.set_device(Device::Cpu);
+    let result = load(vs);
+    vs.set_device(Device::Mps);
+    result
 }
 
 #[cfg(feature = "remote")]
@@ -1,8 +1,9 @@
 use super::*;
 use crate::common::error::RustBertError;
+use crate::common::settings::Settings;
 use cached_path::{Cache, Options, ProgressBar};
-use dirs::cache_dir;
 use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use std::path::PathBuf;
 
 /// # Remote resource that will be downloaded and cached locally on demand
@@ -89,7 +90,10 @@ impl ResourceProvider for RemoteResource {
     /// let config_path = config_resource.get_local_path();
     /// ```
     fn get_local_path(&self) -> Result<PathBuf, RustBertError> {
-        let cached_path = CACHE
+        let cache = CACHE
+            .as_ref()
+            .map_err(|error| RustBertError::InvalidConfigurationError(error.clone()))?;
+        let cached_path = cache
             .cached_path_with_options(&self.url, &Options::default().subdir(&self.cache_subdir))?;
         Ok(cached_path)
     }
@@ -115,22 +119,33 @@ impl ResourceProvider for RemoteResource {
 lazy_static! {
     #[derive(Copy, Clone, Debug)]
 /// # Global cache directory
-/// If the environment variable `RUSTBERT_CACHE` is set, will save the cache model files at that
-/// location. Otherwise defaults to `$XDG_CACHE_HOME/.rustbert`, or corresponding user cache for
-/// the current system.
-    pub static ref CACHE: Cache = Cache::builder()
-        .dir(_get_cache_directory())
-        .progress_bar(Some(ProgressBar::Light))
-        .build().unwrap();
+/// The cache directory, offline mode and Hugging Face Hub token are resolved by the [settings](crate::settings)
+/// when the first remote resource is loaded. The cache directory is set by the `RUSTBERT_CACHE` environment
+/// variable if set, and otherwise defaults to `$XDG_CACHE_HOME/.rustbert`, or corresponding user cache for
+/// the current system. Invalid settings are reported by the resources loaded through the cache.
+    pub static ref CACHE: Result<Cache, String> = Settings::resolve()
+        .and_then(|settings| build_cache(&settings))
+        .map_err(|error| format!("Failed to create the resources cache: {error}"));
 }
 
-fn _get_cache_directory() -> PathBuf {
-    match std::env::var("RUSTBERT_CACHE") {
-        Ok(value) => PathBuf::from(value),
-        Err(_) => {
-            let mut home = cache_dir().unwrap();
-            home.push(".rustbert");
-            home
-        }
+fn build_cache(settings: &Settings) -> Result<Cache, RustBertError> {
+    let mut client_builder = reqwest::blocking::ClientBuilder::new();
+    if let Some(token) = &settings.hf_token {
+        let mut headers = HeaderMap::new();
+        let mut authorization =
+            HeaderValue::from_str(&format!("Bearer {token}")).map_err(|_| {
+                RustBertError::InvalidConfigurationError(
+                    "The Hugging Face Hub token contains invalid characters".to_string(),
+                )
+            })?;
+        authorization.set_sensitive(true);
+        headers.insert(AUTHORIZATION, authorization);
+        client_builder = client_builder.default_headers(headers);
     }
+    Ok(Cache::builder()
+        .dir(settings.cache_dir.clone())
+        .offline(settings.offline)
+        .client_builder(client_builder)
+        .progress_bar(Some(ProgressBar::Light))
+        .build()?)
 }
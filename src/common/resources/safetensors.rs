@@ -0,0 +1,170 @@
+use crate::common::error::RustBertError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use tch::nn::VarStore;
+use tch::{Kind, Tensor};
+
+// Headers larger than this are rejected to avoid allocating arbitrary amounts of memory for corrupted files
+const MAX_HEADER_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Number of bytes read to detect a safetensors file (header length and opening brace of the JSON header)
+pub(crate) const SAFETENSORS_PREFIX_BYTES: usize = 9;
+
+#[derive(Debug, Deserialize)]
+pub(super) struct TensorInfo {
+    pub(super) dtype: String,
+    pub(super) shape: Vec<i64>,
+    data_offsets: (u64, u64),
+}
+
+/// Returns true if the data starts with a safetensors header: the little-endian length of the header followed by
+/// a JSON object. Checkpoints in the `.ot` format start with a zip or pickle signature.
+pub(crate) fn is_safetensors(prefix: &[u8]) -> bool {
+    if prefix.len() < SAFETENSORS_PREFIX_BYTES {
+        return false;
+    }
+    let mut header_length = [0u8; 8];
+    header_length.copy_from_slice(&prefix[..8]);
+    let header_length = u64::from_le_bytes(header_length);
+    (2..=MAX_HEADER_BYTES).contains(&header_length) && prefix[8] == b'{'
+}
+
+fn kind_from_dtype(dtype: &str) -> Result<Kind, RustBertError> {
+    Ok(match dtype {
+        "F64" => Kind::Double,
+        "F32" => Kind::Float,
+        "F16" => Kind::Half,
+        "BF16" => Kind::BFloat16,
+        "I64" => Kind::Int64,
+        "I32" => Kind::Int,
+        "I16" => Kind::Int16,
+        "I8" => Kind::Int8,
+        "U8" => Kind::Uint8,
+        "BOOL" => Kind::Bool,
+        _ => {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Unsupported safetensors data type {dtype}"
+            )));
+        }
+    })
+}
+
+/// Reads the header of a safetensors file, returning the tensors information (with names following the
+/// conventions of the `.ot` checkpoints) and the position of the start of the tensors data.
+pub(super) fn read_header<R: Read>(
+    reader: &mut R,
+) -> Result<(HashMap<String, TensorInfo>, u64), RustBertError> {
+    let mut header_length = [0u8; 8];
+    reader.read_exact(&mut header_length)?;
+    let header_length = u64::from_le_bytes(header_length);
+    if header_length > MAX_HEADER_BYTES {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Invalid safetensors file: header too large ({header_length} bytes)"
+        )));
+    }
+    let mut header = vec![0u8; header_length as usize];
+    reader.read_exact(&mut header)?;
+    let mut header: HashMap<String, serde_json::Value> =
+        serde_json::from_slice(&header).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!("Invalid safetensors file: {error}"))
+        })?;
+    header.remove("__metadata__");
+
+    let tensors = header
+        .into_iter()
+        .map(|(name, tensor_info)| {
+            let tensor_info =
+                serde_json::from_value::<TensorInfo>(tensor_info).map_err(|error| {
+                    RustBertError::InvalidConfigurationError(format!(
+                        "Invalid safetensors header for tensor {name}: {error}"
+                    ))
+                })?;
+            // Same renaming as the conversion to the `.ot` format (`utils/convert_model.py`)
+            let name = name.replace("gamma", "weight").replace("beta", "bias");
+            Ok((name, tensor_info))
+        })
+        .collect::<Result<HashMap<String, TensorInfo>, RustBertError>>()?;
+    Ok((tensors, 8 + header_length))
+}
+
+/// Finds the tensor of a variable. Checkpoints of a model with a head can be loaded in the base model: if no tensor
+/// has the name of the variable, a unique tensor whose name ends with `.{variable name}` is used. Conversely, checkpoints
+/// saved from the base model (e.g. `h.0...` for BLOOM or `decoder.layers.0...` for OPT) can be loaded in a model with a
+/// head: the variable name without its first segment (`transformer.`, `model.`) is looked up as a last resort.
+pub(super) fn find_tensor<'a, T>(tensors: &'a HashMap<String, T>, name: &str) -> Option<&'a T> {
+    tensors
+        .get(name)
+        .or_else(|| {
+            let suffix = format!(".{name}");
+            let mut candidates = tensors
+                .iter()
+                .filter(|(tensor_name, _)| tensor_name.ends_with(&suffix));
+            match (candidates.next(), candidates.next()) {
+                (Some((_, tensor_info)), None) => Some(tensor_info),
+                _ => None,
+            }
+        })
+        .or_else(|| {
+            name.split_once('.')
+                .and_then(|(_, base_model_name)| tensors.get(base_model_name))
+        })
+}
+
+/// Loads the variables of a `VarStore` from safetensors data, reading one tensor at a time. The tensors are
+/// converted to the precision and device of the variables. Tensors of the file without a matching variable are
+/// ignored, and all the variables must be found in the file except the `optional` ones. Returns the optional
+/// variables not found, which are left unchanged.
+pub(crate) fn load_safetensors<R: Read + Seek>(
+    var_store: &mut VarStore,
+    mut reader: R,
+    optional: &[&str],
+) -> Result<Vec<String>, RustBertError> {
+    let (tensors, data_start) = read_header(&mut reader)?;
+
+    let mut assignments = Vec::new();
+    let mut missing_variables = Vec::new();
+    let mut missing_optional_variables = Vec::new();
+    for (name, variable) in var_store.variables() {
+        match find_tensor(&tensors, &name) {
+            Some(tensor_info) => assignments.push((name, variable, tensor_info)),
+            None if optional.contains(&name.as_str()) => missing_optional_variables.push(name),
+            None => missing_variables.push(name),
+        }
+    }
+    if !missing_variables.is_empty() {
+        missing_variables.sort();
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The safetensors checkpoint does not contain the variables: {}",
+            missing_variables.join(", ")
+        )));
+    }
+    // Reading the tensors in the order of the file
+    assignments.sort_by_key(|(_, _, tensor_info)| tensor_info.data_offsets.0);
+
+    let _guard = tch::no_grad_guard();
+    let mut buffer = Vec::new();
+    for (name, mut variable, tensor_info) in assignments {
+        if tensor_info.shape != variable.size() {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Shape mismatch for {name}: the model expects {:?}, the checkpoint contains {:?}",
+                variable.size(),
+                tensor_info.shape
+            )));
+        }
+        let kind = kind_from_dtype(&tensor_info.dtype)?;
+        let (begin, end) = tensor_info.data_offsets;
+        let num_elements = tensor_info.shape.iter().product::<i64>() as u64;
+        if end < begin || end - begin != num_elements * kind.elt_size_in_bytes() as u64 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Invalid safetensors data offsets for {name}: [{begin}, {end}]"
+            )));
+        }
+        buffer.resize((end - begin) as usize, 0);
+        reader.seek(SeekFrom::Start(data_start + begin))?;
+        reader.read_exact(&mut buffer)?;
+        let tensor = Tensor::f_from_data_size(&buffer, &tensor_info.shape, kind)?;
+        variable.f_copy_(&tensor)?;
+    }
+    Ok(missing_optional_variables)
+}
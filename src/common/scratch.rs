@@ -0,0 +1,197 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Scratch buffers for intermediate activations
+//! The attention layers of the decoder models (GPT-Neo and GPT-J) write the attention scores, attention output and
+//! merged heads into scratch buffers that are reused across layers and decoding steps, instead of allocating
+//! new tensors at every step. This reduces the pressure on the (CUDA) caching allocator when many generation
+//! requests are processed concurrently.
+//!
+//! The buffers are owned by the calling thread: concurrent requests processed on different threads never share
+//! buffers. Buffers grow geometrically so that the increasing sequence length during decoding does not trigger
+//! a reallocation at every step. Buffers larger than the configured limit are not retained (e.g. for long prompts):
+//! the corresponding activations are allocated as usual.
+//!
+//! Scratch buffers are only used for inference: they are bypassed in training mode, when attention weights are
+//! returned, or when gradients are tracked.
+//!
+//! ```no_run
+//! use rust_bert::scratch;
+//!
+//! // Only retain buffers up to 16MB
+//! scratch::set_max_buffer_bytes(16 * 1024 * 1024);
+//! // ... run generation ...
+//! println!("Scratch memory: {} bytes", scratch::allocated_bytes());
+//! // Release the buffers owned by the current thread
+//! scratch::clear();
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tch::{Device, Kind, Scalar, Tensor};
+
+/// Default maximum size of a retained scratch buffer (64MB)
+pub const DEFAULT_MAX_BUFFER_BYTES: usize = 64 * 1024 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static MAX_BUFFER_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_BUFFER_BYTES);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ScratchSlot {
+    AttentionScores,
+    AttentionOutput,
+    MergedHeads,
+}
+
+thread_local! {
+    static BUFFERS: RefCell<HashMap<ScratchSlot, Tensor>> = RefCell::new(HashMap::new());
+}
+
+/// Enables or disables the use of scratch buffers (enabled by default)
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns true if scratch buffers are enabled
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Sets the maximum size in bytes of a retained scratch buffer. Larger activations are allocated as usual.
+pub fn set_max_buffer_bytes(max_buffer_bytes: usize) {
+    MAX_BUFFER_BYTES.store(max_buffer_bytes, Ordering::Relaxed);
+}
+
+/// Returns the memory in bytes held by the scratch buffers of the current thread
+pub fn allocated_bytes() -> usize {
+    BUFFERS.with(|buffers| {
+        buffers
+            .borrow()
+            .values()
+            .map(|buffer| buffer.numel() * buffer.kind().elt_size_in_bytes())
+            .sum()
+    })
+}
+
+/// Releases the scratch buffers owned by the current thread
+pub fn clear() {
+    BUFFERS.with(|buffers| buffers.borrow_mut().clear());
+}
+
+/// Returns a tensor of the requested shape backed by the scratch buffer of the slot, growing the buffer if needed.
+/// The content of the tensor is undefined and is overwritten by the next request for the same slot.
+/// Returns `None` if scratch buffers are disabled or the requested size exceeds the limit.
+pub(crate) fn buffer(
+    slot: ScratchSlot,
+    size: &[i64],
+    kind: Kind,
+    device: Device,
+) -> Option<Tensor> {
+    if !is_enabled() {
+        return None;
+    }
+    let numel = size.iter().product::<i64>();
+    let max_bytes = MAX_BUFFER_BYTES.load(Ordering::Relaxed);
+    if numel as usize * kind.elt_size_in_bytes() > max_bytes {
+        return None;
+    }
+    BUFFERS.with(|buffers| {
+        let mut buffers = buffers.borrow_mut();
+        let reusable = buffers.get(&slot).filter(|buffer| {
+            buffer.kind() == kind && buffer.device() == device && buffer.numel() as i64 >= numel
+        });
+        let flat_buffer = match reusable {
+            Some(buffer) => buffer.shallow_clone(),
+            None => {
+                let max_numel = (max_bytes / kind.elt_size_in_bytes()) as i64;
+                let capacity = (numel.max(1) as u64).next_power_of_two() as i64;
+                let buffer = Tensor::empty([capacity.min(max_numel)], (kind, device));
+                buffers.insert(slot, buffer.shallow_clone());
+                buffer
+            }
+        };
+        Some(flat_buffer.narrow(0, 0, numel).view(size))
+    })
+}
+
+/// Causal self-attention over query, key and value of shape (*batch size*, *num_heads*, *sequence_length*, *head_dim*)
+/// computed in scratch buffers, returning the merged heads of shape (*batch size*, *query_length*, *num_heads x head_dim*).
+/// The returned tensor is backed by a scratch buffer and must be consumed before the next attention layer.
+/// Returns `None` if the scratch buffers cannot be used (disabled, too large or gradient tracking required).
+///
+/// # Arguments
+///
+/// * `query` - query of shape (*batch size*, *num_heads*, *query_length*, *head_dim*), in single precision
+/// * `key` - key of shape (*batch size*, *num_heads*, *key_length*, *head_dim*), in single precision
+/// * `value` - value of shape (*batch size*, *num_heads*, *key_length*, *head_dim*)
+/// * `causal_mask` - boolean tensor broadcastable to the attention scores, false for masked positions
+/// * `mask_value` - value of the masked attention scores before scaling
+/// * `scale` - optional divisor applied to the attention scores after masking
+/// * `attention_mask` - optional additive mask broadcastable to the attention scores
+pub(crate) fn causal_attention(
+    query: &Tensor,
+    key: &Tensor,
+    value: &Tensor,
+    causal_mask: &Tensor,
+    mask_value: Scalar,
+    scale: Option<f64>,
+    attention_mask: Option<&Tensor>,
+) -> Option<Tensor> {
+    if query.requires_grad() || key.requires_grad() || value.requires_grad() {
+        return None;
+    }
+    let (batch_size, num_heads, query_length, head_dim) = query.size4().ok()?;
+    let key_length = key.size()[2];
+    let device = query.device();
+
+    let mut scores = buffer(
+        ScratchSlot::AttentionScores,
+        &[batch_size, num_heads, query_length, key_length],
+        query.kind(),
+        device,
+    )?;
+    let attention_output = buffer(
+        ScratchSlot::AttentionOutput,
+        &[batch_size, num_heads, query_length, head_dim],
+        value.kind(),
+        device,
+    )?;
+    let merged_heads = buffer(
+        ScratchSlot::MergedHeads,
+        &[batch_size, query_length, num_heads * head_dim],
+        value.kind(),
+        device,
+    )?;
+
+    let _ = query.matmul_out(&scores, &key.transpose(-1, -2));
+    let _ = scores.masked_fill_(&causal_mask.logical_not(), mask_value);
+    if let Some(scale) = scale {
+        scores /= scale;
+    }
+    if let Some(attention_mask) = attention_mask {
+        scores += attention_mask;
+    }
+    // In-place softmax over the key dimension
+    let max_scores = scores.amax([-1].as_slice(), true);
+    scores -= &max_scores;
+    let _ = scores.exp_();
+    let normalization = scores.sum_dim_intlist([-1].as_slice(), true, scores.kind());
+    scores /= &normalization;
+
+    let _ = scores
+        .to_kind(value.kind())
+        .matmul_out(&attention_output, value);
+    let _ = merged_heads
+        .view([batch_size, query_length, num_heads, head_dim])
+        .copy_(&attention_output.permute([0, 2, 1, 3]));
+    Some(merged_heads)
+}
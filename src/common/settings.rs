@@ -0,0 +1,318 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Library settings
+//! Resolves the settings shared by all pipelines: the cache directory of the downloaded resources, the default
+//! device of the pipelines, the offline mode, the number of threads used by Torch and the Hugging Face Hub token
+//! used to download gated models.
+//!
+//! Each setting is resolved from the following sources, by decreasing precedence:
+//! 1. Programmatic overrides set with [`set_overrides`]
+//! 2. Environment variables:
+//!     - `RUSTBERT_CACHE`: cache directory
+//!     - `RUSTBERT_DEVICE`: default device (`cpu`, `cuda`, `cuda:<index>`, `mps` or `auto`)
+//!     - `RUSTBERT_OFFLINE` (or `HF_HUB_OFFLINE`): offline mode (`1`/`true` or `0`/`false`)
+//!     - `RUSTBERT_NUM_THREADS`: number of threads used by Torch
+//!     - `HF_TOKEN` (or `HUGGING_FACE_HUB_TOKEN`): Hugging Face Hub token
+//! 3. The JSON configuration file located at `RUSTBERT_CONFIG` if set, or at `$XDG_CONFIG_HOME/rustbert/config.json`
+//!    (defaulting to `~/.config/rustbert/config.json`). The file is optional and uses the field names of [`SettingsLayer`].
+//! 4. The defaults: `$XDG_CACHE_HOME/.rustbert` (or the corresponding user cache for the current system) as cache
+//!    directory, CUDA if available as device, online mode, the Torch default number of threads and no token.
+//!
+//! ```json
+//! {
+//!     "cache_dir": "/data/rustbert",
+//!     "device": "cpu",
+//!     "num_threads": 4
+//! }
+//! ```
+//!
+//! The resources cache is created on the first download: the cache directory, offline mode and token must be set
+//! before any remote resource is loaded.
+//!
+//! ```no_run
+//! use rust_bert::settings::{set_overrides, Settings, SettingsLayer};
+//! # fn main() -> anyhow::Result<()> {
+//! set_overrides(SettingsLayer {
+//!     device: Some("cpu".to_string()),
+//!     offline: Some(true),
+//!     ..Default::default()
+//! });
+//! let settings = Settings::resolve()?;
+//! settings.apply_num_threads();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tch::Device;
+
+/// Name of the configuration file in the `rustbert` configuration directory
+pub const SETTINGS_FILE: &str = "config.json";
+
+static OVERRIDES: RwLock<SettingsLayer> = RwLock::new(SettingsLayer {
+    cache_dir: None,
+    device: None,
+    offline: None,
+    num_threads: None,
+    hf_token: None,
+});
+
+/// # Partial settings
+/// A source of settings (overrides, environment variables or configuration file). Settings left to `None` are
+/// resolved from the sources with a lower precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsLayer {
+    /// Directory of the downloaded resources
+    pub cache_dir: Option<PathBuf>,
+    /// Default device of the pipelines (`cpu`, `cuda`, `cuda:<index>`, `mps` or `auto`)
+    pub device: Option<String>,
+    /// Only use the resources already in the cache, without network access
+    pub offline: Option<bool>,
+    /// Number of threads used by Torch
+    pub num_threads: Option<usize>,
+    /// Hugging Face Hub token, sent with the download requests
+    pub hf_token: Option<String>,
+}
+
+impl SettingsLayer {
+    /// Reads settings from a JSON configuration file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the configuration file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SettingsLayer, RustBertError> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!(
+                "Invalid settings file {}: {error}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Reads settings from the environment variables
+    pub fn from_env() -> Result<SettingsLayer, RustBertError> {
+        let offline = env_var(&["RUSTBERT_OFFLINE", "HF_HUB_OFFLINE"])
+            .map(|(name, value)| parse_bool(&name, &value))
+            .transpose()?;
+        let num_threads = env_var(&["RUSTBERT_NUM_THREADS"])
+            .map(|(name, value)| {
+                value.trim().parse::<usize>().map_err(|_| {
+                    RustBertError::InvalidConfigurationError(format!(
+                        "Invalid value for {name}: expected a number of threads, got {value}"
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(SettingsLayer {
+            cache_dir: env_var(&["RUSTBERT_CACHE"]).map(|(_, value)| PathBuf::from(value)),
+            device: env_var(&["RUSTBERT_DEVICE"]).map(|(_, value)| value),
+            offline,
+            num_threads,
+            hf_token: env_var(&["HF_TOKEN", "HUGGING_FACE_HUB_TOKEN"]).map(|(_, value)| value),
+        })
+    }
+
+    /// Reads the configuration file, if it exists
+    pub fn from_config_file() -> Result<SettingsLayer, RustBertError> {
+        match config_file_path() {
+            Some(path) if path.is_file() => SettingsLayer::from_file(path),
+            _ => Ok(SettingsLayer::default()),
+        }
+    }
+
+    /// Completes the settings missing from this layer with the settings of a layer with a lower precedence
+    fn or(self, other: SettingsLayer) -> SettingsLayer {
+        SettingsLayer {
+            cache_dir: self.cache_dir.or(other.cache_dir),
+            device: self.device.or(other.device),
+            offline: self.offline.or(other.offline),
+            num_threads: self.num_threads.or(other.num_threads),
+            hf_token: self.hf_token.or(other.hf_token),
+        }
+    }
+}
+
+/// # Resolved settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Directory of the downloaded resources
+    pub cache_dir: PathBuf,
+    /// Default device of the pipelines
+    pub device: Device,
+    /// Only use the resources already in the cache, without network access
+    pub offline: bool,
+    /// Number of threads used by Torch (`None` for the Torch default)
+    pub num_threads: Option<usize>,
+    /// Hugging Face Hub token, sent with the download requests
+    pub hf_token: Option<String>,
+}
+
+impl Settings {
+    /// Resolves the settings from the overrides, environment variables, configuration file and defaults
+    /// (by decreasing precedence).
+    pub fn resolve() -> Result<Settings, RustBertError> {
+        let overrides = OVERRIDES
+            .read()
+            .map(|overrides| overrides.clone())
+            .unwrap_or_default();
+        Settings::from_layers(vec![
+            overrides,
+            SettingsLayer::from_env()?,
+            SettingsLayer::from_config_file()?,
+        ])
+    }
+
+    /// Resolves the settings from layers ordered by decreasing precedence, using the defaults for the settings
+    /// missing from all layers.
+    ///
+    /// # Arguments
+    ///
+    /// * `layers` - settings layers, ordered by decreasing precedence
+    pub fn from_layers(layers: Vec<SettingsLayer>) -> Result<Settings, RustBertError> {
+        let settings = layers
+            .into_iter()
+            .fold(SettingsLayer::default(), SettingsLayer::or);
+        let device = match settings.device {
+            Some(device) => parse_device(&device)?,
+            None => Device::cuda_if_available(),
+        };
+        if settings.num_threads == Some(0) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The number of threads must be positive".to_string(),
+            ));
+        }
+        Ok(Settings {
+            cache_dir: settings.cache_dir.unwrap_or_else(default_cache_dir),
+            device,
+            offline: settings.offline.unwrap_or(false),
+            num_threads: settings.num_threads,
+            hf_token: settings.hf_token.filter(|token| !token.is_empty()),
+        })
+    }
+
+    /// Sets the number of threads used by Torch, if configured
+    pub fn apply_num_threads(&self) {
+        if let Some(num_threads) = self.num_threads {
+            tch::set_num_threads(num_threads as i32);
+        }
+    }
+}
+
+/// Sets programmatic overrides, taking precedence over the environment variables and configuration file.
+/// Replaces the previous overrides.
+///
+/// # Arguments
+///
+/// * `overrides` - settings to override
+pub fn set_overrides(overrides: SettingsLayer) {
+    if let Ok(mut current) = OVERRIDES.write() {
+        *current = overrides;
+    }
+}
+
+/// Removes the programmatic overrides
+pub fn clear_overrides() {
+    set_overrides(SettingsLayer::default());
+}
+
+/// Returns the default device of the pipelines. Falls back to CUDA if available when the settings are invalid,
+/// use [`Settings::resolve`] to validate them.
+pub fn default_device() -> Device {
+    Settings::resolve()
+        .map(|settings| settings.device)
+        .unwrap_or_else(|_| Device::cuda_if_available())
+}
+
+/// Returns the path of the configuration file: `RUSTBERT_CONFIG` if set, or `$XDG_CONFIG_HOME/rustbert/config.json`
+pub fn config_file_path() -> Option<PathBuf> {
+    if let Some((_, path)) = env_var(&["RUSTBERT_CONFIG"]) {
+        return Some(PathBuf::from(path));
+    }
+    xdg_directory("XDG_CONFIG_HOME", ".config").map(|mut path| {
+        path.push("rustbert");
+        path.push(SETTINGS_FILE);
+        path
+    })
+}
+
+/// Parses a device name: `cpu`, `cuda`, `cuda:<index>`, `mps` or `auto` (CUDA if available)
+///
+/// # Arguments
+///
+/// * `device` - device name
+pub fn parse_device(device: &str) -> Result<Device, RustBertError> {
+    let device = device.trim().to_lowercase();
+    match device.as_str() {
+        "auto" => Ok(Device::cuda_if_available()),
+        "cpu" => Ok(Device::Cpu),
+        "cuda" => Ok(Device::Cuda(0)),
+        "mps" => Ok(Device::Mps),
+        _ => device
+            .strip_prefix("cuda:")
+            .and_then(|index| index.parse::<usize>().ok())
+            .map(Device::Cuda)
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(format!(
+                    "Invalid device {device}, expected \"cpu\", \"cuda\", \"cuda:<index>\", \"mps\" or \"auto\""
+                ))
+            }),
+    }
+}
+
+/// Returns the first environment variable set (and not empty) among the names given
+fn env_var(names: &[&str]) -> Option<(String, String)> {
+    names.iter().find_map(|name| match std::env::var(name) {
+        Ok(value) if !value.is_empty() => Some((name.to_string(), value)),
+        _ => None,
+    })
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, RustBertError> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(RustBertError::InvalidConfigurationError(format!(
+            "Invalid value for {name}: expected a boolean (1/true or 0/false), got {value}"
+        ))),
+    }
+}
+
+/// XDG base directory from its environment variable, defaulting to a subdirectory of the home directory
+fn xdg_directory(variable: &str, home_subdir: &str) -> Option<PathBuf> {
+    match env_var(&[variable]) {
+        Some((_, path)) => Some(PathBuf::from(path)),
+        None => env_var(&["HOME", "USERPROFILE"]).map(|(_, home)| {
+            let mut path = PathBuf::from(home);
+            path.push(home_subdir);
+            path
+        }),
+    }
+}
+
+fn default_cache_dir() -> PathBuf {
+    #[cfg(feature = "remote")]
+    let cache_dir = dirs::cache_dir();
+    #[cfg(not(feature = "remote"))]
+    let cache_dir = xdg_directory("XDG_CACHE_HOME", ".cache");
+
+    let mut cache_dir = cache_dir.unwrap_or_default();
+    cache_dir.push(".rustbert");
+    cache_dir
+}
@@ -0,0 +1,178 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Weights snapshots for fast restarts
+//! Saves the weights of an initialized model (e.g. after conversion to half precision) to a single file that can be
+//! restored without repeating the conversion, for example when starting new workers of an autoscaled service.
+//! Snapshots are stored in the [safetensors](https://github.com/huggingface/safetensors) format: the tensors are
+//! laid out contiguously after a small JSON header and the file can be memory-mapped. The precision of the weights is
+//! preserved and restored when loading the snapshot.
+//!
+//! Snapshots contain the variables of the model: models split with tensor parallelism or quantized to int4 must be
+//! snapshotted before the conversion, which is then applied after restoring the snapshot.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! # fn main() -> anyhow::Result<()> {
+//! // Initialize and convert the model once
+//! let mut model = TextGenerationModel::new(TextGenerationConfig::default())?;
+//! model.half()?;
+//! model.save_snapshot("path/to/snapshot.safetensors")?;
+//!
+//! // Restore the converted model on new workers
+//! let model = TextGenerationModel::from_snapshot(
+//!     TextGenerationConfig::default(),
+//!     "path/to/snapshot.safetensors",
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::resources::load_on_cpu_for_mps;
+use crate::RustBertError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tch::nn::VarStore;
+use tch::{Device, Kind, Tensor};
+
+/// Extension of the snapshot files
+pub const SNAPSHOT_EXTENSION: &str = "safetensors";
+
+// Headers larger than this are rejected to avoid allocating arbitrary amounts of memory for corrupted files
+const MAX_HEADER_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct TensorHeader {
+    dtype: String,
+}
+
+fn check_extension(path: &Path) -> Result<(), RustBertError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(SNAPSHOT_EXTENSION) => Ok(()),
+        _ => Err(RustBertError::InvalidConfigurationError(format!(
+            "Snapshot files must have a .{SNAPSHOT_EXTENSION} extension, got {}",
+            path.display()
+        ))),
+    }
+}
+
+fn kind_from_dtype(dtype: &str) -> Option<Kind> {
+    match dtype {
+        "F64" => Some(Kind::Double),
+        "F32" => Some(Kind::Float),
+        "F16" => Some(Kind::Half),
+        "BF16" => Some(Kind::BFloat16),
+        _ => None,
+    }
+}
+
+/// Saves the variables of a `VarStore` to a snapshot file, preserving their precision.
+/// The file must have a `.safetensors` extension.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` holding the variables of the initialized model
+/// * `path` - path of the snapshot file to write
+pub fn save_snapshot<P: AsRef<Path>>(var_store: &VarStore, path: P) -> Result<(), RustBertError> {
+    let path = path.as_ref();
+    check_extension(path)?;
+    let variables = var_store.variables();
+    if let Some((name, _)) = variables
+        .iter()
+        .find(|(_, variable)| variable.numel() == 0 && !variable.size().is_empty())
+    {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Cannot snapshot the variable {name}: its weights were released by a conversion (e.g. tensor \
+            parallelism or quantization). Snapshots should be saved before these conversions"
+        )));
+    }
+    let _guard = tch::no_grad_guard();
+    let mut named_tensors = variables
+        .into_iter()
+        .map(|(name, variable)| (name, variable.to_device(Device::Cpu).contiguous()))
+        .collect::<Vec<(String, Tensor)>>();
+    named_tensors.sort_by(|(name_a, _), (name_b, _)| name_a.cmp(name_b));
+    Tensor::write_safetensors(&named_tensors, path)?;
+    Ok(())
+}
+
+/// Returns the precision of the floating point weights stored in a snapshot file. Returns an error if the
+/// snapshot mixes several floating point precisions or does not contain floating point weights.
+///
+/// # Arguments
+///
+/// * `path` - path of the snapshot file
+pub fn snapshot_kind<P: AsRef<Path>>(path: P) -> Result<Kind, RustBertError> {
+    let path = path.as_ref();
+    check_extension(path)?;
+    let mut file = File::open(path)?;
+    let mut header_length = [0u8; 8];
+    file.read_exact(&mut header_length)?;
+    let header_length = u64::from_le_bytes(header_length);
+    if header_length > MAX_HEADER_BYTES {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Invalid snapshot file {}: header too large ({header_length} bytes)",
+            path.display()
+        )));
+    }
+    let mut header = vec![0u8; header_length as usize];
+    file.read_exact(&mut header)?;
+    let mut header: HashMap<String, serde_json::Value> =
+        serde_json::from_slice(&header).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!(
+                "Invalid snapshot file {}: {error}",
+                path.display()
+            ))
+        })?;
+    header.remove("__metadata__");
+
+    let mut kinds: Vec<Kind> = Vec::new();
+    for kind in header
+        .into_values()
+        .filter_map(|tensor_header| serde_json::from_value::<TensorHeader>(tensor_header).ok())
+        .filter_map(|tensor_header| kind_from_dtype(&tensor_header.dtype))
+    {
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    match kinds.as_slice() {
+        [kind] => Ok(*kind),
+        [] => Err(RustBertError::InvalidConfigurationError(format!(
+            "The snapshot file {} does not contain floating point weights",
+            path.display()
+        ))),
+        _ => Err(RustBertError::InvalidConfigurationError(format!(
+            "The snapshot file {} contains weights with different precisions: {kinds:?}",
+            path.display()
+        ))),
+    }
+}
+
+/// Restores the variables of a `VarStore` from a snapshot file, converting the `VarStore` to the precision
+/// of the snapshot. All the variables of the `VarStore` must be present in the snapshot.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` holding the variables of the model, created with the same configuration as the snapshotted model
+/// * `path` - path of the snapshot file
+pub fn load_snapshot<P: AsRef<Path>>(
+    var_store: &mut VarStore,
+    path: P,
+) -> Result<(), RustBertError> {
+    let path = path.as_ref();
+    let kind = snapshot_kind(path)?;
+    var_store.set_kind(kind);
+    load_on_cpu_for_mps(var_store, |var_store| Ok(var_store.load(path)?))
+}
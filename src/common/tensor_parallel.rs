@@ -0,0 +1,316 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Tensor parallelism
+//! Splits the linear layers of the transformer blocks of a loaded model across several devices (Megatron-style):
+//! - the query, key and value projections and the first MLP layer are split along their output dimension
+//!   (column-parallel). The attention projections are split on attention head boundaries.
+//! - the attention output projection and the second MLP layer are split along their input dimension (row-parallel).
+//!   The partial outputs computed on each device are summed on the device of the model (all-reduce).
+//!
+//! The intermediate MLP activations stay on their shard device, so that each MLP only requires a single transfer
+//! of its input to the devices and of the partial outputs back to the model device. The embeddings, layer
+//! normalizations, attention scores computation and the language model head remain on the device of the model.
+//! Since the libtorch kernels are launched asynchronously, the computation of the shards overlaps across devices.
+//!
+//! Tensor parallelism is available for decoder models through `TextGenerationModel::tensor_parallelize`:
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::tensor_parallel::TensorParallelConfig;
+//! use tch::Device;
+//! # fn main() -> anyhow::Result<()> {
+//! let generate_config = TextGenerationConfig {
+//!     model_type: ModelType::GPTJ,
+//!     device: Device::Cuda(0),
+//!     ..Default::default()
+//! };
+//! let mut model = TextGenerationModel::new(generate_config)?;
+//! model.tensor_parallelize(&TensorParallelConfig::new(vec![
+//!     Device::Cuda(0),
+//!     Device::Cuda(1),
+//! ]))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::quantization::QuantizableLinear;
+use crate::RustBertError;
+use tch::nn::{Linear, Module};
+use tch::{Device, Tensor};
+
+/// # Configuration for tensor parallelism
+#[derive(Debug, Clone)]
+pub struct TensorParallelConfig {
+    /// Devices the layers are split across. The first device should be the device the model is loaded on.
+    pub devices: Vec<Device>,
+}
+
+impl TensorParallelConfig {
+    /// Create a new `TensorParallelConfig` splitting the layers across the given devices
+    ///
+    /// # Arguments
+    ///
+    /// * `devices` - devices to split the layers across
+    pub fn new(devices: Vec<Device>) -> TensorParallelConfig {
+        TensorParallelConfig { devices }
+    }
+
+    /// Create a new `TensorParallelConfig` splitting the layers across all available CUDA devices
+    pub fn all_cuda_devices() -> TensorParallelConfig {
+        TensorParallelConfig {
+            devices: (0..tch::Cuda::device_count() as usize)
+                .map(Device::Cuda)
+                .collect(),
+        }
+    }
+
+    /// Checks that a layer made of `num_units` units (e.g. attention heads) can be split across the devices
+    pub(crate) fn validate(&self, num_units: i64, units_name: &str) -> Result<(), RustBertError> {
+        if self.devices.is_empty() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "At least one device is required for tensor parallelism".to_string(),
+            ));
+        }
+        if num_units < self.devices.len() as i64 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Cannot split {num_units} {units_name} across {} devices",
+                self.devices.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Sizes of the shards splitting `dim` features made of groups of `alignment` consecutive features.
+/// Groups are distributed as evenly as possible, the first devices receiving the remainder.
+fn shard_sizes(dim: i64, alignment: i64, num_shards: usize) -> Result<Vec<i64>, RustBertError> {
+    if alignment <= 0 || dim % alignment != 0 {
+        return Err(RustBertError::ValueError(format!(
+            "The layer dimension ({dim}) must be a multiple of the shard alignment ({alignment})"
+        )));
+    }
+    let num_groups = dim / alignment;
+    let num_shards = num_shards as i64;
+    if num_groups < num_shards {
+        return Err(RustBertError::ValueError(format!(
+            "Cannot split {num_groups} groups of features across {num_shards} devices"
+        )));
+    }
+    Ok((0..num_shards)
+        .map(|shard| {
+            let extra = i64::from(shard < num_groups % num_shards);
+            (num_groups / num_shards + extra) * alignment
+        })
+        .collect())
+}
+
+fn copy_to_device(tensor: &Tensor, device: Device) -> Tensor {
+    if tensor.device() == device {
+        tensor.copy()
+    } else {
+        tensor.to_device(device)
+    }
+}
+
+fn release(linear: &mut Linear) {
+    let empty = Tensor::empty([0], (linear.ws.kind(), linear.ws.device()));
+    linear.ws.set_data(&empty);
+}
+
+/// # Linear layer split along its output dimension across devices
+#[derive(Debug)]
+pub struct ColumnParallelLinear {
+    /// Linear layers computing consecutive slices of the output, each stored on its device
+    pub shards: Vec<Linear>,
+}
+
+impl ColumnParallelLinear {
+    /// Split a linear layer along its output dimension. The storage of the original weight is released.
+    ///
+    /// # Arguments
+    ///
+    /// * `linear` - full linear layer
+    /// * `devices` - devices to split the layer across
+    /// * `alignment` - each shard contains a multiple of `alignment` output features (e.g. the attention head dimension)
+    pub fn from_linear(
+        mut linear: Linear,
+        devices: &[Device],
+        alignment: i64,
+    ) -> Result<ColumnParallelLinear, RustBertError> {
+        let sizes = shard_sizes(linear.ws.size2()?.0, alignment, devices.len())?;
+        let _guard = tch::no_grad_guard();
+        let weights = linear.ws.split_with_sizes(sizes.as_slice(), 0);
+        let biases = linear
+            .bs
+            .as_ref()
+            .map(|bias| bias.split_with_sizes(sizes.as_slice(), 0));
+        let shards = weights
+            .iter()
+            .zip(devices)
+            .enumerate()
+            .map(|(index, (weight, device))| Linear {
+                ws: copy_to_device(weight, *device),
+                bs: biases
+                    .as_ref()
+                    .map(|biases| copy_to_device(&biases[index], *device)),
+            })
+            .collect();
+        release(&mut linear);
+        Ok(ColumnParallelLinear { shards })
+    }
+
+    /// Computes the output slices, each slice remaining on the device of its shard
+    pub fn forward_shards(&self, xs: &Tensor) -> Vec<Tensor> {
+        self.shards
+            .iter()
+            .map(|shard| xs.to_device(shard.ws.device()).apply(shard))
+            .collect()
+    }
+}
+
+impl Module for ColumnParallelLinear {
+    fn forward(&self, xs: &Tensor) -> Tensor {
+        let device = xs.device();
+        let outputs = self
+            .forward_shards(xs)
+            .iter()
+            .map(|output| output.to_device(device))
+            .collect::<Vec<Tensor>>();
+        Tensor::cat(&outputs, -1)
+    }
+}
+
+/// # Linear layer split along its input dimension across devices
+#[derive(Debug)]
+pub struct RowParallelLinear {
+    /// Weights applied to consecutive slices of the input features, each stored on its device
+    pub shards: Vec<Tensor>,
+    /// Optional bias, stored on the output device
+    pub bias: Option<Tensor>,
+    /// Device the partial outputs are summed on
+    pub device: Device,
+}
+
+impl RowParallelLinear {
+    /// Split a linear layer along its input dimension. The partial outputs are summed on the device of the
+    /// original layer. The storage of the original weight is released.
+    ///
+    /// # Arguments
+    ///
+    /// * `linear` - full linear layer
+    /// * `devices` - devices to split the layer across
+    /// * `alignment` - each shard contains a multiple of `alignment` input features (e.g. the attention head dimension)
+    pub fn from_linear(
+        mut linear: Linear,
+        devices: &[Device],
+        alignment: i64,
+    ) -> Result<RowParallelLinear, RustBertError> {
+        let sizes = shard_sizes(linear.ws.size2()?.1, alignment, devices.len())?;
+        let device = linear.ws.device();
+        let _guard = tch::no_grad_guard();
+        let shards = linear
+            .ws
+            .split_with_sizes(sizes.as_slice(), 1)
+            .iter()
+            .zip(devices)
+            .map(|(weight, device)| copy_to_device(&weight.contiguous(), *device))
+            .collect();
+        let bias = linear.bs.as_ref().map(Tensor::shallow_clone);
+        release(&mut linear);
+        Ok(RowParallelLinear {
+            shards,
+            bias,
+            device,
+        })
+    }
+
+    /// Applies the shards to input slices already split across the devices (as returned by
+    /// `ColumnParallelLinear::forward_shards`) and sums the partial outputs on the output device
+    pub fn forward_shards(&self, inputs: &[Tensor]) -> Tensor {
+        let mut output = self
+            .shards
+            .iter()
+            .zip(inputs)
+            .map(|(weight, input)| {
+                input
+                    .to_device(weight.device())
+                    .matmul(&weight.tr())
+                    .to_device(self.device)
+            })
+            .reduce(|accumulated, partial| accumulated + partial)
+            .unwrap();
+        if let Some(bias) = &self.bias {
+            output += bias;
+        }
+        output
+    }
+}
+
+impl Module for RowParallelLinear {
+    fn forward(&self, xs: &Tensor) -> Tensor {
+        let sizes = self
+            .shards
+            .iter()
+            .map(|weight| weight.size()[1])
+            .collect::<Vec<i64>>();
+        self.forward_shards(&xs.split_with_sizes(sizes.as_slice(), -1))
+    }
+}
+
+/// Forward pass through a two-layer MLP. If the layers are split across devices (column-parallel followed by
+/// row-parallel), the activation is applied on the shard devices without gathering the intermediate features.
+pub(crate) fn mlp_forward(
+    fc_in: &QuantizableLinear,
+    fc_out: &QuantizableLinear,
+    activation: &fn(&Tensor) -> Tensor,
+    xs: &Tensor,
+) -> Tensor {
+    match (fc_in, fc_out) {
+        (QuantizableLinear::ColumnParallel(fc_in), QuantizableLinear::RowParallel(fc_out)) => {
+            let intermediate = fc_in
+                .forward_shards(xs)
+                .iter()
+                .map(activation)
+                .collect::<Vec<Tensor>>();
+            fc_out.forward_shards(&intermediate)
+        }
+        _ => activation(&xs.apply(fc_in)).apply(fc_out),
+    }
+}
+
+/// Forward pass through a gated MLP (e.g. SwiGLU), computing `fc_out(activation(gate(x)) * up(x))`. If the layers are
+/// split across devices, the gating is applied on the shard devices without gathering the intermediate features.
+pub(crate) fn gated_mlp_forward(
+    gate: &QuantizableLinear,
+    up: &QuantizableLinear,
+    fc_out: &QuantizableLinear,
+    activation: &fn(&Tensor) -> Tensor,
+    xs: &Tensor,
+) -> Tensor {
+    match (gate, up, fc_out) {
+        (
+            QuantizableLinear::ColumnParallel(gate),
+            QuantizableLinear::ColumnParallel(up),
+            QuantizableLinear::RowParallel(fc_out),
+        ) => {
+            let intermediate = gate
+                .forward_shards(xs)
+                .iter()
+                .zip(up.forward_shards(xs))
+                .map(|(gate, up)| activation(gate) * up)
+                .collect::<Vec<Tensor>>();
+            fc_out.forward_shards(&intermediate)
+        }
+        _ => (activation(&xs.apply(gate)) * xs.apply(up)).apply(fc_out),
+    }
+}
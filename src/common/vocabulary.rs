@@ -0,0 +1,316 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Vocabulary adaptation
+//! Extends the vocabulary of a pretrained tokenizer with domain-specific tokens learned from a corpus sample. Jargon
+//! (e.g. medical or legal terms) is often split into many subwords by general-purpose tokenizers: adding the most
+//! frequent of these words as single tokens shortens the encoded sequences and frees up context for the model.
+//!
+//! The adaptation has three steps:
+//! - `VocabularyLearner::learn` counts the words of the corpus that the tokenizer splits into several subwords, and
+//!   ranks them by the number of tokens their addition would save (frequency × (number of subwords - 1)),
+//! - `add_tokens` appends the selected words to the tokenizer vocabulary,
+//! - `resize_token_embeddings` resizes the embedding matrices (and any other vocabulary-sized variable, such as
+//!   language model heads and output biases) of a loaded model. The rows of a new token are initialized to the mean of
+//!   the rows of the subwords it replaces, so that the model behaves sensibly before any fine-tuning.
+//!
+//! New tokens are added as whole strings that are matched before the model tokenization, including inside longer
+//! words. The configuration of the model should be updated with the new vocabulary size before saving it.
+//!
+//! ```no_run
+//! use rust_bert::bert::{BertConfig, BertForMaskedLM};
+//! use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+//! use rust_bert::vocabulary::{add_tokens, resize_token_embeddings, VocabularyLearner};
+//! use rust_bert::Config;
+//! use std::path::Path;
+//! use tch::{nn, Device};
+//! # fn main() -> anyhow::Result<()> {
+//! let mut vs = nn::VarStore::new(Device::Cpu);
+//! let mut config = BertConfig::from_file(Path::new("path/to/config.json"));
+//! let _model = BertForMaskedLM::new(vs.root(), &config);
+//! vs.load("path/to/model.ot")?;
+//! let mut tokenizer = TokenizerOption::from_file(
+//!     ModelType::Bert,
+//!     "path/to/vocab.txt",
+//!     None,
+//!     false,
+//!     None,
+//!     None,
+//! )?;
+//!
+//! let corpus = std::fs::read_to_string("path/to/domain_corpus.txt")?;
+//! let corpus = corpus.lines().collect::<Vec<&str>>();
+//! let candidates = VocabularyLearner::default().learn(&tokenizer, &corpus);
+//! let added_tokens = add_tokens(&mut tokenizer, &candidates)?;
+//! config.vocab_size = resize_token_embeddings(&mut vs, config.vocab_size, &added_tokens)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::pipelines::common::TokenizerOption;
+use crate::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tch::nn::VarStore;
+use tch::Tensor;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Configuration for the vocabulary learning
+pub struct VocabularyLearnerConfig {
+    /// Maximum number of tokens to add to the vocabulary
+    pub max_new_tokens: usize,
+    /// Minimum number of occurrences of a word in the corpus
+    pub min_frequency: usize,
+    /// Minimum number of subwords the current tokenizer splits a word into
+    pub min_subwords: usize,
+    /// Minimum length of a word (in characters)
+    pub min_length: usize,
+}
+
+impl Default for VocabularyLearnerConfig {
+    fn default() -> VocabularyLearnerConfig {
+        VocabularyLearnerConfig {
+            max_new_tokens: 1000,
+            min_frequency: 10,
+            min_subwords: 3,
+            min_length: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Candidate token learned from a corpus
+pub struct TokenCandidate {
+    /// Word to add to the vocabulary
+    pub token: String,
+    /// Number of occurrences of the word in the corpus
+    pub frequency: usize,
+    /// Ids of the subwords the word is currently split into
+    pub subword_ids: Vec<i64>,
+}
+
+impl TokenCandidate {
+    /// Number of tokens saved on the corpus by adding the candidate to the vocabulary
+    pub fn savings(&self) -> usize {
+        self.frequency * (self.subword_ids.len() - 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Token added to a tokenizer vocabulary
+pub struct AddedToken {
+    /// Added token
+    pub token: String,
+    /// Id of the token in the adapted vocabulary
+    pub id: i64,
+    /// Ids of the subwords the token was split into before the adaptation, used to initialize its embeddings
+    pub subword_ids: Vec<i64>,
+}
+
+/// # Vocabulary learner
+/// Selects the words of a corpus sample that benefit the most from being added to the vocabulary of a tokenizer.
+#[derive(Debug, Clone, Default)]
+pub struct VocabularyLearner {
+    config: VocabularyLearnerConfig,
+}
+
+impl VocabularyLearner {
+    /// Creates a new `VocabularyLearner`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `VocabularyLearnerConfig` with the selection criteria of the new tokens
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::vocabulary::{VocabularyLearner, VocabularyLearnerConfig};
+    ///
+    /// let learner = VocabularyLearner::new(VocabularyLearnerConfig {
+    ///     max_new_tokens: 200,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn new(config: VocabularyLearnerConfig) -> VocabularyLearner {
+        VocabularyLearner { config }
+    }
+
+    /// Learns the tokens to add to a tokenizer from a corpus sample. Words are separated by whitespace, with leading
+    /// and trailing punctuation removed. The candidates are sorted by decreasing savings.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokenizer` - tokenizer to adapt
+    /// * `corpus` - sample of documents from the target domain
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<TokenCandidate>` - at most `max_new_tokens` candidates
+    pub fn learn<S: AsRef<str>>(
+        &self,
+        tokenizer: &TokenizerOption,
+        corpus: &[S],
+    ) -> Vec<TokenCandidate> {
+        let mut frequencies: HashMap<&str, usize> = HashMap::new();
+        for document in corpus {
+            for word in document.as_ref().split_whitespace() {
+                let word = word.trim_matches(|character: char| !character.is_alphanumeric());
+                if word.chars().count() >= self.config.min_length
+                    && word.chars().any(char::is_alphabetic)
+                {
+                    *frequencies.entry(word).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let min_subwords = self.config.min_subwords.max(2);
+        let mut candidates = frequencies
+            .into_iter()
+            .filter(|(_, frequency)| *frequency >= self.config.min_frequency)
+            .filter_map(|(word, frequency)| {
+                let subwords = tokenizer.tokenize(word);
+                if subwords.len() < min_subwords {
+                    return None;
+                }
+                Some(TokenCandidate {
+                    token: word.to_string(),
+                    frequency,
+                    subword_ids: tokenizer.convert_tokens_to_ids(&subwords),
+                })
+            })
+            .collect::<Vec<TokenCandidate>>();
+        candidates.sort_by(|candidate_1, candidate_2| {
+            candidate_2
+                .savings()
+                .cmp(&candidate_1.savings())
+                .then_with(|| candidate_1.token.cmp(&candidate_2.token))
+        });
+        candidates.truncate(self.config.max_new_tokens);
+        candidates
+    }
+}
+
+/// Adds learned tokens to the vocabulary of a tokenizer. Tokens already in the vocabulary are skipped.
+///
+/// # Arguments
+///
+/// * `tokenizer` - tokenizer to adapt
+/// * `candidates` - tokens to add, typically returned by `VocabularyLearner::learn`
+///
+/// # Returns
+///
+/// * `Vec<AddedToken>` - added tokens with their id in the adapted vocabulary
+pub fn add_tokens(
+    tokenizer: &mut TokenizerOption,
+    candidates: &[TokenCandidate],
+) -> Result<Vec<AddedToken>, RustBertError> {
+    let candidates = candidates
+        .iter()
+        .filter(|candidate| tokenizer.tokenize(&candidate.token).len() > 1)
+        .collect::<Vec<&TokenCandidate>>();
+    tokenizer.add_tokens(
+        &candidates
+            .iter()
+            .map(|candidate| candidate.token.as_str())
+            .collect::<Vec<&str>>(),
+    );
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            if tokenizer.tokenize(&candidate.token) != [candidate.token.as_str()] {
+                return Err(RustBertError::ValueError(format!(
+                    "The token {} could not be added to the vocabulary",
+                    candidate.token
+                )));
+            }
+            Ok(AddedToken {
+                token: candidate.token.clone(),
+                id: tokenizer.convert_tokens_to_ids(&[candidate.token.as_str()])[0],
+                subword_ids: candidate.subword_ids.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Resizes the vocabulary-sized variables of a model (the variables with a dimension of size `vocab_size`: word
+/// embeddings, language model heads and their biases) in place for the added tokens. The rows of an added token are
+/// initialized to the mean of the rows of its subwords. If the ids of the added tokens exceed the current vocabulary
+/// size, the variables are extended (intermediate rows, if any, are initialized to the mean of all rows). Tokens
+/// mapped to existing rows (models with a padded vocabulary) overwrite these rows.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` of the loaded model
+/// * `vocab_size` - current vocabulary size of the model
+/// * `added_tokens` - tokens added to the tokenizer vocabulary, returned by `add_tokens`
+///
+/// # Returns
+///
+/// * `i64` - new vocabulary size of the model
+pub fn resize_token_embeddings(
+    var_store: &mut VarStore,
+    vocab_size: i64,
+    added_tokens: &[AddedToken],
+) -> Result<i64, RustBertError> {
+    if let Some(token) = added_tokens.iter().find(|token| {
+        token.id < 0
+            || token.subword_ids.is_empty()
+            || token
+                .subword_ids
+                .iter()
+                .any(|id| (*id < 0) | (*id >= vocab_size))
+    }) {
+        return Err(RustBertError::ValueError(format!(
+            "Invalid ids for the added token {}: the model vocabulary has {vocab_size} tokens",
+            token.token
+        )));
+    }
+    let new_vocab_size = added_tokens
+        .iter()
+        .map(|token| token.id + 1)
+        .max()
+        .unwrap_or(vocab_size)
+        .max(vocab_size);
+
+    let _guard = tch::no_grad_guard();
+    let mut num_resized = 0;
+    for (_, mut variable) in var_store.variables() {
+        let dim = match variable.size().iter().position(|size| *size == vocab_size) {
+            Some(dim) => dim as i64,
+            None => continue,
+        };
+        let mut resized = if new_vocab_size > vocab_size {
+            let mut extension_shape = variable.size();
+            extension_shape[dim as usize] = new_vocab_size - vocab_size;
+            let mean = variable.mean_dim([dim].as_slice(), true, variable.kind());
+            Tensor::cat(&[&variable, &mean.expand(&extension_shape, false)], dim)
+        } else {
+            variable.copy()
+        };
+        for token in added_tokens {
+            let subword_ids = Tensor::from_slice(&token.subword_ids).to_device(variable.device());
+            let mean = variable.index_select(dim, &subword_ids).mean_dim(
+                [dim].as_slice(),
+                true,
+                variable.kind(),
+            );
+            resized.narrow(dim, token.id, 1).copy_(&mean);
+        }
+        variable.set_data(&resized);
+        num_resized += 1;
+    }
+    if num_resized == 0 {
+        return Err(RustBertError::ValueError(format!(
+            "The model has no variable with a dimension of size {vocab_size}"
+        )));
+    }
+    Ok(new_vocab_size)
+}
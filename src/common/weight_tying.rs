@@ -0,0 +1,300 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Weight tying verification
+//! Most language models share the weights of their input embeddings with their language modeling head. Models using
+//! the embeddings tensor as LM head (e.g. GPT-2, GPT-Neo, BART, MobileBERT) are tied by construction, but models
+//! storing the LM head in its own variable load it from the checkpoint: the OpenAI GPT, XLNet and ProphetNet
+//! generators and the masked language models (BERT, RoBERTa, DistilBERT, ALBERT, DeBERTa, FNet, Longformer, ELECTRA,
+//! ModernBERT). Custom checkpoints where the head is not kept in sync with the embeddings would silently produce
+//! degraded predictions, and checkpoints saved without the duplicated head (such as the safetensors checkpoints of
+//! the Hugging Face Hub) would fail to load. The tied weights of these models are available as the `TIED_WEIGHTS`
+//! constant of the model (e.g. `BertForMaskedLM::TIED_WEIGHTS`).
+//!
+//! The generators and the masked language pipeline verify the tying when loading their weights:
+//! - an LM head missing from the checkpoint is tied to the input embeddings,
+//! - an LM head differing from the input embeddings is handled following the `WeightTyingPolicy` set with
+//!   `set_weight_tying_policy`: the head of the checkpoint is kept and reported as `WeightTyingStatus::Untied`
+//!   (default), the head is tied to the embeddings or an error is returned.
+//!
+//! The tying verified when loading the weights is available from `get_weight_tying_status` on the OpenAI GPT, XLNet
+//! and ProphetNet generators and on the masked language pipeline.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::weight_tying::{set_weight_tying_policy, WeightTyingPolicy};
+//! # fn main() -> anyhow::Result<()> {
+//! # let generate_config = TextGenerationConfig::default();
+//! set_weight_tying_policy(WeightTyingPolicy::Repair);
+//! let model = TextGenerationModel::new(generate_config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::resources::{load_weights_partial, ResourceProvider};
+use crate::RustBertError;
+use std::sync::RwLock;
+use tch::nn::{Init, VarStore};
+use tch::Tensor;
+
+/// Largest absolute difference between the weights of an LM head and of the input embeddings for them to be tied
+const TYING_TOLERANCE: f64 = 1e-6;
+
+static WEIGHT_TYING_POLICY: RwLock<WeightTyingPolicy> = RwLock::new(WeightTyingPolicy::Warn);
+
+/// # Handling of the LM heads differing from the input embeddings they are expected to be tied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeightTyingPolicy {
+    /// Keeps the LM head of the checkpoint and reports it as `WeightTyingStatus::Untied` in the load result
+    #[default]
+    Warn,
+    /// Ties the LM head to the input embeddings
+    Repair,
+    /// Returns an error when loading the model
+    Error,
+}
+
+/// Sets the handling of the LM heads differing from the input embeddings, applied to the models loaded afterwards
+///
+/// # Arguments
+///
+/// * `policy` - `WeightTyingPolicy` applied to the untied LM heads
+pub fn set_weight_tying_policy(policy: WeightTyingPolicy) {
+    if let Ok(mut current) = WEIGHT_TYING_POLICY.write() {
+        *current = policy;
+    }
+}
+
+/// Returns the handling of the LM heads differing from the input embeddings (default: `WeightTyingPolicy::Warn`)
+pub fn weight_tying_policy() -> WeightTyingPolicy {
+    WEIGHT_TYING_POLICY
+        .read()
+        .map(|policy| *policy)
+        .unwrap_or_default()
+}
+
+/// # LM head expected to be tied to the input embeddings of a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TiedWeights {
+    /// Name of the input embeddings variable (e.g. `transformer.word_embedding.weight`)
+    pub input_embeddings: &'static str,
+    /// Name of the LM head variable (e.g. `lm_loss.weight`)
+    pub lm_head: &'static str,
+    /// Names of the bias of the LM head variable and of the checkpoint tensor it is tied to, for heads whose bias is
+    /// stored separately from the decoder in the checkpoints (e.g. `predictions.decoder.bias` and `predictions.bias`)
+    pub lm_head_bias: Option<(&'static str, &'static str)>,
+}
+
+impl TiedWeights {
+    /// Creates the tying of an LM head with the input embeddings
+    ///
+    /// # Arguments
+    ///
+    /// * `input_embeddings` - name of the input embeddings variable
+    /// * `lm_head` - name of the LM head variable, of the same shape as the input embeddings
+    pub const fn new(input_embeddings: &'static str, lm_head: &'static str) -> TiedWeights {
+        TiedWeights {
+            input_embeddings,
+            lm_head,
+            lm_head_bias: None,
+        }
+    }
+
+    /// Adds the tying of the bias of the LM head with a tensor of the checkpoint. The bias is loaded from this tensor
+    /// when it is missing from the checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `lm_head_bias` - name of the bias variable of the LM head (e.g. `predictions.decoder.bias`)
+    /// * `checkpoint_bias` - name of the checkpoint tensor tied to the bias (e.g. `predictions.bias`)
+    pub const fn with_bias(
+        self,
+        lm_head_bias: &'static str,
+        checkpoint_bias: &'static str,
+    ) -> TiedWeights {
+        TiedWeights {
+            lm_head_bias: Some((lm_head_bias, checkpoint_bias)),
+            ..self
+        }
+    }
+}
+
+/// # Tying of an LM head after loading a checkpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightTyingStatus {
+    /// The LM head of the checkpoint is tied to the input embeddings
+    Tied,
+    /// The LM head is missing from the checkpoint and was tied to the input embeddings
+    MissingLmHead,
+    /// The LM head of the checkpoint differs from the input embeddings, it was tied to them if `repaired`
+    Untied {
+        /// Largest absolute difference between the LM head and the input embeddings of the checkpoint
+        max_difference: f64,
+        /// Flag indicating if the LM head was tied to the input embeddings
+        repaired: bool,
+    },
+}
+
+/// Loads the weights of a model from a resource (see `load_weights`) and verifies the tying of its LM heads with the
+/// input embeddings. The LM heads may be missing from the checkpoint, in which case they are tied to the input
+/// embeddings (and their bias loaded from the tensor it is tied to). LM heads differing from the input embeddings are
+/// handled according to `policy`.
+///
+/// # Arguments
+///
+/// * `rp` - resource of the weights
+/// * `vs` - `VarStore` holding the variables of the model to load
+/// * `tied_weights` - LM heads of the model expected to be tied to the input embeddings
+/// * `policy` - `WeightTyingPolicy` applied to the LM heads differing from the input embeddings
+///
+/// # Returns
+///
+/// * `Vec<WeightTyingStatus>` status of each of the `tied_weights`
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::bert::{BertConfig, BertForMaskedLM};
+/// use rust_bert::resources::LocalResource;
+/// use rust_bert::weight_tying::{load_weights_with_tying, WeightTyingPolicy};
+/// use rust_bert::Config;
+/// use std::path::PathBuf;
+/// use tch::{nn, Device};
+///
+/// let config = BertConfig::from_file("path/to/config.json");
+/// let mut vs = nn::VarStore::new(Device::Cpu);
+/// let model = BertForMaskedLM::new(vs.root(), &config);
+/// let status = load_weights_with_tying(
+///     &LocalResource::from(PathBuf::from("path/to/model.safetensors")),
+///     &mut vs,
+///     &[BertForMaskedLM::TIED_WEIGHTS],
+///     WeightTyingPolicy::Repair,
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_weights_with_tying(
+    rp: &(impl ResourceProvider + ?Sized),
+    vs: &mut VarStore,
+    tied_weights: &[TiedWeights],
+    policy: WeightTyingPolicy,
+) -> Result<Vec<WeightTyingStatus>, RustBertError> {
+    let lm_heads = tied_weights
+        .iter()
+        .flat_map(|tied_weights| {
+            std::iter::once(tied_weights.lm_head).chain(
+                tied_weights
+                    .lm_head_bias
+                    .map(|(lm_head_bias, _)| lm_head_bias),
+            )
+        })
+        .collect::<Vec<&str>>();
+    let missing_lm_heads = load_weights_partial(rp, vs, &lm_heads)?;
+
+    let variables = vs.variables();
+    let _guard = tch::no_grad_guard();
+    for (lm_head_bias, checkpoint_bias) in tied_weights
+        .iter()
+        .filter_map(|tied_weights| tied_weights.lm_head_bias)
+        .filter(|(lm_head_bias, _)| missing_lm_heads.iter().any(|name| name == lm_head_bias))
+    {
+        let mut bias = match variables.get(lm_head_bias) {
+            Some(bias) => bias.shallow_clone(),
+            None => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "The model does not contain the LM head bias {lm_head_bias}"
+                )));
+            }
+        };
+        bias.f_copy_(&load_checkpoint_tensor(rp, vs, checkpoint_bias, &bias)?)?;
+    }
+    tied_weights
+        .iter()
+        .map(|tied_weights| {
+            let (input_embeddings, mut lm_head) = match (
+                variables.get(tied_weights.input_embeddings),
+                variables.get(tied_weights.lm_head),
+            ) {
+                (Some(input_embeddings), Some(lm_head)) => {
+                    (input_embeddings, lm_head.shallow_clone())
+                }
+                _ => {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "The model does not contain the tied weights {} and {}",
+                        tied_weights.input_embeddings, tied_weights.lm_head
+                    )));
+                }
+            };
+            if input_embeddings.size() != lm_head.size() {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "The LM head {} of shape {:?} cannot be tied to the input embeddings {} of shape {:?}",
+                    tied_weights.lm_head,
+                    lm_head.size(),
+                    tied_weights.input_embeddings,
+                    input_embeddings.size()
+                )));
+            }
+            if missing_lm_heads
+                .iter()
+                .any(|name| name == tied_weights.lm_head)
+            {
+                lm_head.f_copy_(input_embeddings)?;
+                return Ok(WeightTyingStatus::MissingLmHead);
+            }
+
+            let max_difference = (&lm_head - input_embeddings)
+                .abs()
+                .max()
+                .double_value(&[]);
+            if max_difference <= TYING_TOLERANCE {
+                return Ok(WeightTyingStatus::Tied);
+            }
+            match policy {
+                WeightTyingPolicy::Warn => Ok(WeightTyingStatus::Untied {
+                    max_difference,
+                    repaired: false,
+                }),
+                WeightTyingPolicy::Repair => {
+                    lm_head.f_copy_(input_embeddings)?;
+                    Ok(WeightTyingStatus::Untied {
+                        max_difference,
+                        repaired: true,
+                    })
+                }
+                WeightTyingPolicy::Error => Err(RustBertError::InvalidConfigurationError(format!(
+                    "The LM head {} of the checkpoint is not tied to the input embeddings {} (maximum difference: \
+                    {max_difference:.3e})",
+                    tied_weights.lm_head, tied_weights.input_embeddings
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Loads a tensor of the checkpoint that is not a variable of the model, with the shape and precision of `like`
+fn load_checkpoint_tensor(
+    rp: &(impl ResourceProvider + ?Sized),
+    vs: &VarStore,
+    name: &str,
+    like: &Tensor,
+) -> Result<Tensor, RustBertError> {
+    let mut checkpoint_vs = VarStore::new(vs.device());
+    let mut path = checkpoint_vs.root();
+    let mut segments = name.split('.').collect::<Vec<&str>>();
+    let variable_name = segments.pop().unwrap_or(name);
+    for segment in segments {
+        path = path.sub(segment);
+    }
+    let tensor = path.var(variable_name, &like.size(), Init::Const(0.));
+    load_weights_partial(rp, &mut checkpoint_vs, &[])?;
+    Ok(tensor.to_kind(like.kind()))
+}
@@ -39,8 +39,10 @@
 //! - Named Entity Recognition
 //! - Part of Speech tagging
 //! - Question-Answering
+//! - Table Question-Answering
 //! - Language Generation
 //! - Sentence Embeddings
+//! - Reranking
 //! - Masked Language Model
 //! - Keywords extraction
 //!
@@ -63,6 +65,12 @@
 //!GPT2| | | |✅ | | | |  |
 //!GPT-Neo| | | |✅ | | | | |
 //!GPT-J| | | |✅ | | | | |
+//!StarCoder2| | | |✅ | | | | |
+//!LLaMA / Mistral| | | |✅ | | | | |
+//!Falcon| | | |✅ | | | | |
+//!BLOOM| | | |✅ | | | | |
+//!OPT| | | |✅ | | | | |
+//!Phi| | | |✅ | | | | |
 //!BART|✅| | |✅ |✅| | | |
 //!Marian| | | |  | |✅| |  |
 //!MBart|✅| | |✅ | | | |  |
@@ -76,7 +84,12 @@
 //!Reformer|✅| |✅|✅ | | |✅|  |
 //!ProphetNet| | | |✅ |✅ | | |  |
 //!Longformer|✅|✅|✅| | | |✅|  |
+//!BigBird|✅| |✅| | | | |  |
+//!TAPAS| | |✅| | | | |  |
 //!Pegasus| | | | |✅| | |  |
+//!Jina BERT| | | | | | | | ✅ |
+//!Nomic BERT| | | | | | | | ✅ |
+//!ModernBERT|✅|✅| | | | |✅|  |
 //! </details>
 //!
 //! # Getting started
@@ -88,6 +101,8 @@
 //! Furthermore, this library relies on a cache folder for downloading pre-trained models.
 //! This cache location defaults to `~/.cache/.rustbert`, but can be changed by setting the `RUSTBERT_CACHE` environment variable. Note that the language models used by this library are in the order of the 100s of MBs to GBs.
 //!
+//! The cache location, default device, offline mode, number of threads and Hugging Face Hub token can also be set with environment variables (`RUSTBERT_CACHE`, `RUSTBERT_DEVICE`, `RUSTBERT_OFFLINE`, `RUSTBERT_NUM_THREADS`, `HF_TOKEN`), a configuration file (`~/.config/rustbert/config.json`) or programmatically, as documented in the `settings` module.
+//!
 //! ### Manual installation (recommended)
 //!
 //! 1. Download `libtorch` from <https://pytorch.org/get-started/locally/>. This package requires `v2.0`: if this version is no longer available on the "get started" page,
@@ -112,6 +127,14 @@
 //! The CPU version of libtorch will be downloaded by default. To download a CUDA version, please set the environment variable `TORCH_CUDA_VERSION` to `cu118`.
 //! Note that the libtorch library is large (order of several GBs for the CUDA-enabled version) and the first build may therefore take several minutes to complete.
 //!
+//! ## Model architecture selection (Optional)
+//!
+//! All model architectures are compiled by default through the `all-models` feature. Applications using a small subset of the supported models can reduce compilation times and binary sizes by disabling the default features and enabling only the architectures they need (e.g. `bert`, `distilbert`, `t5`, `gpt2` or `marian`), for example:
+//! ```toml
+//! rust-bert = { version = "0.21.0", default-features = false, features = ["remote", "default-tls", "bert", "t5"] }
+//! ```
+//! Architectures building on another model enable it automatically (e.g. `roberta` enables `bert`). Pipelines only support the architectures that are compiled in: creating a pipeline for a disabled architecture returns an `InvalidConfigurationError`, and the default pipeline configurations are only available if the architecture of their default model is enabled.
+//!
 //! ## ONNX Support (Optional)
 //!
 //! ONNX support can be enabled via the optional `onnx` feature. This crate then leverages the [ort](https://github.com/pykeio/ort) crate with bindings to the onnxruntime C++ library. We refer the user to this page project for further installation instructions/support.
@@ -138,6 +161,14 @@
 //! token classification (including named entity recognition and part-of-speech tagging), question answering, text generation, summarization and translation.
 //! These models use the same configuration and tokenizer files as their Pytorch counterparts when used in a pipeline. Examples leveraging ONNX models are given in the `./examples` directory. More information on these can be found in the [`onnx` module](./pipelines/onnx/index.html)
 //!
+//! ## Python bindings (Optional)
+//!
+//! The main pipelines (text generation, sentence embeddings, named entity recognition, question answering and summarization) can be exposed to Python through the optional `python` feature, based on [PyO3](https://pyo3.rs). The Python extension module is built with [maturin](https://www.maturin.rs) from the root of the repository, using the settings of `pyproject.toml`. More information can be found in the [`python` module](./python/index.html)
+//! ```bash
+//! pip install maturin
+//! maturin develop --release
+//! ```
+//!
 //! # Ready-to-use pipelines
 //!
 //! Based on Hugging Face's pipelines, ready to use end-to-end NLP pipelines are available as part of this crate. More information on these can be found in the [`pipelines` module](./pipelines/index.html)
@@ -694,6 +725,10 @@
 //! A conversion utility script is included in `./utils` to convert Pytorch weights to a set of weights compatible with this library. This script requires Python and `torch` to be set-up, and can be used as follows:
 //! `python ./utils/convert_model.py path/to/pytorch_model.bin` where `path/to/pytorch_model.bin` is the location of the original Pytorch weights.
 //!
+//! Checkpoints in the [safetensors](https://github.com/huggingface/safetensors) format (`model.safetensors`) can also be loaded directly, without conversion: the weights resource of a pipeline can point to a `.safetensors` file (local or remote), and `resources::load_weights_from_file` loads such a file in a variables store.
+//!
+//! Quantized GGUF checkpoints of the LLaMA architecture (e.g. LLaMA, Mistral or TinyLlama files produced by llama.cpp in the `Q4_0`, `Q4_1` or `Q8_0` formats) are loaded in the same way and de-quantized to the precision of the model. The model configuration can be read from the GGUF metadata with `LlamaConfig::from_gguf`.
+//!
 //!
 //! ## Async execution
 //!
@@ -728,14 +763,154 @@
 extern crate core;
 
 mod common;
+#[cfg(feature = "mobile")]
+pub mod mobile;
 pub mod models;
+#[cfg(feature = "node")]
+pub mod node;
 pub mod pipelines;
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "mobile")]
+uniffi::setup_scaffolding!();
 
-pub use common::error::RustBertError;
+pub use common::attention;
+pub use common::device_map;
+pub use common::error::{InputError, RustBertError};
+pub use common::placement;
+pub use common::position_embeddings;
+pub use common::precision;
+pub use common::pruning;
+pub use common::quantization;
 pub use common::resources;
+pub use common::scratch;
+pub use common::settings;
+pub use common::snapshot;
+pub use common::tensor_parallel;
+pub use common::vocabulary;
+pub use common::weight_tying;
 pub use common::{Activation, Config};
-pub use models::{
-    albert, bart, bert, deberta, deberta_v2, distilbert, electra, fnet, gpt2, gpt_j, gpt_neo,
-    longformer, longt5, m2m_100, marian, mbart, mobilebert, nllb, openai_gpt, pegasus, prophetnet,
-    reformer, roberta, t5, xlnet,
-};
+#[cfg(feature = "albert")]
+pub use models::albert;
+#[cfg(feature = "bart")]
+pub use models::bart;
+#[cfg(feature = "bert")]
+pub use models::bert;
+#[cfg(feature = "bigbird")]
+pub use models::bigbird;
+#[cfg(feature = "bloom")]
+pub use models::bloom;
+#[cfg(feature = "deberta")]
+pub use models::deberta;
+#[cfg(feature = "deberta-v2")]
+pub use models::deberta_v2;
+#[cfg(feature = "distilbert")]
+pub use models::distilbert;
+#[cfg(feature = "donut")]
+pub use models::donut;
+#[cfg(feature = "electra")]
+pub use models::electra;
+#[cfg(feature = "falcon")]
+pub use models::falcon;
+#[cfg(feature = "fnet")]
+pub use models::fnet;
+#[cfg(feature = "gpt2")]
+pub use models::gpt2;
+#[cfg(feature = "gpt-j")]
+pub use models::gpt_j;
+#[cfg(feature = "gpt-neo")]
+pub use models::gpt_neo;
+#[cfg(feature = "jina-bert")]
+pub use models::jina_bert;
+#[cfg(feature = "llama")]
+pub use models::llama;
+#[cfg(feature = "longformer")]
+pub use models::longformer;
+#[cfg(feature = "longt5")]
+pub use models::longt5;
+#[cfg(feature = "m2m-100")]
+pub use models::m2m_100;
+#[cfg(feature = "marian")]
+pub use models::marian;
+#[cfg(feature = "mbart")]
+pub use models::mbart;
+#[cfg(feature = "mobilebert")]
+pub use models::mobilebert;
+#[cfg(feature = "modernbert")]
+pub use models::modernbert;
+#[cfg(feature = "nllb")]
+pub use models::nllb;
+#[cfg(feature = "nomic-bert")]
+pub use models::nomic_bert;
+#[cfg(feature = "openai-gpt")]
+pub use models::openai_gpt;
+#[cfg(feature = "opt")]
+pub use models::opt;
+#[cfg(feature = "pegasus")]
+pub use models::pegasus;
+#[cfg(feature = "phi")]
+pub use models::phi;
+#[cfg(feature = "prophetnet")]
+pub use models::prophetnet;
+#[cfg(feature = "reformer")]
+pub use models::reformer;
+#[cfg(feature = "roberta")]
+pub use models::roberta;
+#[cfg(feature = "siglip")]
+pub use models::siglip;
+#[cfg(feature = "starcoder2")]
+pub use models::starcoder2;
+#[cfg(feature = "t5")]
+pub use models::t5;
+#[cfg(feature = "tapas")]
+pub use models::tapas;
+#[cfg(feature = "whisper")]
+pub use models::whisper;
+#[cfg(feature = "xlnet")]
+pub use models::xlnet;
+
+#[cfg(not(any(
+    feature = "albert",
+    feature = "bart",
+    feature = "bert",
+    feature = "bigbird",
+    feature = "bloom",
+    feature = "deberta",
+    feature = "deberta-v2",
+    feature = "distilbert",
+    feature = "donut",
+    feature = "electra",
+    feature = "falcon",
+    feature = "fnet",
+    feature = "gpt-j",
+    feature = "gpt-neo",
+    feature = "gpt2",
+    feature = "jina-bert",
+    feature = "llama",
+    feature = "longformer",
+    feature = "longt5",
+    feature = "m2m-100",
+    feature = "marian",
+    feature = "mbart",
+    feature = "mobilebert",
+    feature = "modernbert",
+    feature = "nllb",
+    feature = "nomic-bert",
+    feature = "openai-gpt",
+    feature = "opt",
+    feature = "pegasus",
+    feature = "phi",
+    feature = "prophetnet",
+    feature = "reformer",
+    feature = "roberta",
+    feature = "siglip",
+    feature = "starcoder2",
+    feature = "t5",
+    feature = "tapas",
+    feature = "whisper",
+    feature = "xlnet"
+)))]
+compile_error!(
+    "At least one model architecture feature (e.g. `bert`, `t5` or `all-models`) must be enabled"
+);
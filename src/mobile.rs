@@ -0,0 +1,409 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Mobile bindings
+//! Exposes the compact pipelines (sequence classification, sentence embeddings and keyword extraction) to Swift and
+//! Kotlin through [UniFFI](https://mozilla.github.io/uniffi-rs/), behind the `mobile` feature, for on-device
+//! inference on iOS and Android. The models are loaded from local files shipped with the application or downloaded
+//! by it: the bindings never download models themselves.
+//!
+//! The pipelines run on the CPU and enforce `MobileLimits` guarding against models and inputs exceeding the memory
+//! and latency budget of a mobile device:
+//! - models whose weights exceed `max_model_bytes` are rejected before being loaded,
+//! - batches larger than `max_batch_size` and texts longer than `max_input_chars` are rejected,
+//! - the number of intra-op threads used by libtorch is capped by `num_threads`.
+//!
+//! The default limits (`default_mobile_limits()`) accept models up to 300MB (e.g. DistilBERT or all-MiniLM-L12-v2
+//! in single precision). Larger models should be distilled or quantized before being shipped to devices.
+//!
+//! The library is compiled for the mobile targets as a static (iOS) or dynamic (Android) library, from which the
+//! Swift and Kotlin bindings are generated with `uniffi-bindgen`:
+//!
+//! ```bash
+//! cargo rustc --release --features mobile --target aarch64-apple-ios --crate-type staticlib
+//! cargo rustc --release --features mobile --target aarch64-linux-android --crate-type cdylib
+//! uniffi-bindgen generate --library target/aarch64-linux-android/release/librust_bert.so --language kotlin --out-dir bindings
+//! ```
+//!
+//! ```swift
+//! let classifier = try SequenceClassifier(files: files, limits: defaultMobileLimits())
+//! let labels = try classifier.predict(texts: ["This app is great"])
+//! ```
+//!
+//! The libtorch libraries built for the mobile target must be linked with the application.
+
+use crate::pipelines::common::{ModelResource, ModelType};
+use crate::pipelines::keywords_extraction::{
+    Keyword, KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType,
+};
+use crate::pipelines::sentence_embeddings::{SentenceEmbeddingsBuilder, SentenceEmbeddingsModel};
+use crate::pipelines::sequence_classification::{
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::resources::LocalResource;
+use crate::RustBertError;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tch::Device;
+
+/// Default maximum size of the model weights accepted by the mobile pipelines (300MB)
+pub const DEFAULT_MAX_MODEL_BYTES: u64 = 300 * 1024 * 1024;
+
+/// # Error returned by the mobile pipelines
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum MobileError {
+    /// The weights of the model exceed the size limit
+    #[error("The model weights ({model_bytes} bytes) exceed the limit of {max_model_bytes} bytes: use a distilled or quantized model")]
+    ModelTooLarge {
+        model_bytes: u64,
+        max_model_bytes: u64,
+    },
+    /// The input exceeds the batch size or length limits
+    #[error("{message}")]
+    InvalidInput { message: String },
+    /// The model files are invalid or missing
+    #[error("{message}")]
+    InvalidModel { message: String },
+    /// An error occurred when running the model
+    #[error("{message}")]
+    InferenceError { message: String },
+}
+
+impl From<RustBertError> for MobileError {
+    fn from(error: RustBertError) -> Self {
+        let message = error.to_string();
+        match error {
+            RustBertError::ValueError(_) | RustBertError::InputError(_) => {
+                MobileError::InvalidInput { message }
+            }
+            RustBertError::IOError(_) | RustBertError::InvalidConfigurationError(_) => {
+                MobileError::InvalidModel { message }
+            }
+            _ => MobileError::InferenceError { message },
+        }
+    }
+}
+
+/// # Resource limits enforced by the mobile pipelines
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileLimits {
+    /// Maximum size of the model weights in bytes
+    pub max_model_bytes: u64,
+    /// Maximum number of texts processed in a single call
+    pub max_batch_size: u32,
+    /// Maximum length of a text in characters
+    pub max_input_chars: u32,
+    /// Maximum number of threads used by libtorch
+    pub num_threads: u32,
+}
+
+/// Default limits, suitable for compact models on recent devices
+#[uniffi::export]
+pub fn default_mobile_limits() -> MobileLimits {
+    MobileLimits {
+        max_model_bytes: DEFAULT_MAX_MODEL_BYTES,
+        max_batch_size: 16,
+        max_input_chars: 4096,
+        num_threads: 2,
+    }
+}
+
+/// Returns the size in bytes of the model weights (`.ot` files) at the path, which can be a weights file or a
+/// model directory (weights in sub-directories, e.g. dense layers, are included)
+#[uniffi::export]
+pub fn model_weights_bytes(path: String) -> Result<u64, MobileError> {
+    Ok(weights_bytes(Path::new(&path))?)
+}
+
+fn weights_bytes(path: &Path) -> Result<u64, RustBertError> {
+    if path.is_dir() {
+        let mut total_bytes = 0;
+        for entry in std::fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() || entry_path.extension().map_or(false, |ext| ext == "ot") {
+                total_bytes += weights_bytes(&entry_path)?;
+            }
+        }
+        Ok(total_bytes)
+    } else {
+        Ok(std::fs::metadata(path)?.len())
+    }
+}
+
+impl MobileLimits {
+    fn check_model(&self, path: &Path) -> Result<(), MobileError> {
+        let model_bytes = weights_bytes(path)?;
+        if model_bytes > self.max_model_bytes {
+            return Err(MobileError::ModelTooLarge {
+                model_bytes,
+                max_model_bytes: self.max_model_bytes,
+            });
+        }
+        if self.num_threads > 0 {
+            tch::set_num_threads(self.num_threads as i32);
+        }
+        Ok(())
+    }
+
+    fn check_inputs(&self, texts: &[String]) -> Result<(), MobileError> {
+        if texts.len() > self.max_batch_size as usize {
+            return Err(MobileError::InvalidInput {
+                message: format!(
+                    "Got {} texts, the maximum batch size is {}",
+                    texts.len(),
+                    self.max_batch_size
+                ),
+            });
+        }
+        if let Some((index, text)) = texts
+            .iter()
+            .enumerate()
+            .find(|(_, text)| text.chars().count() > self.max_input_chars as usize)
+        {
+            return Err(MobileError::InvalidInput {
+                message: format!(
+                    "Text {index} has {} characters, the maximum length is {}",
+                    text.chars().count(),
+                    self.max_input_chars
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn lock<T>(model: &Mutex<T>) -> Result<MutexGuard<'_, T>, MobileError> {
+    model.lock().map_err(|_| MobileError::InferenceError {
+        message: "The model is unusable after a failed prediction".to_string(),
+    })
+}
+
+/// # Architecture of a compact classification model
+#[derive(Debug, Clone, Copy, uniffi::Enum)]
+pub enum MobileModelType {
+    Bert,
+    DistilBert,
+    MobileBert,
+    Albert,
+    Roberta,
+}
+
+impl From<MobileModelType> for ModelType {
+    fn from(model_type: MobileModelType) -> Self {
+        match model_type {
+            MobileModelType::Bert => ModelType::Bert,
+            MobileModelType::DistilBert => ModelType::DistilBert,
+            MobileModelType::MobileBert => ModelType::MobileBert,
+            MobileModelType::Albert => ModelType::Albert,
+            MobileModelType::Roberta => ModelType::Roberta,
+        }
+    }
+}
+
+/// # Local files of a classification model
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ClassificationModelFiles {
+    /// Architecture of the model
+    pub model_type: MobileModelType,
+    /// Path to the weights (`rust_model.ot`)
+    pub weights_path: String,
+    /// Path to the configuration (`config.json`)
+    pub config_path: String,
+    /// Path to the vocabulary (`vocab.txt`, `vocab.json` or `spiece.model`)
+    pub vocab_path: String,
+    /// Path to the merges (`merges.txt`), for RoBERTa models
+    pub merges_path: Option<String>,
+    /// Lower-case the input before tokenization
+    pub lower_case: bool,
+}
+
+/// # Label predicted by a `SequenceClassifier`
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileLabel {
+    /// Label text
+    pub text: String,
+    /// Confidence score
+    pub score: f64,
+    /// Label ID
+    pub id: i64,
+}
+
+impl From<Label> for MobileLabel {
+    fn from(label: Label) -> Self {
+        MobileLabel {
+            text: label.text,
+            score: label.score,
+            id: label.id,
+        }
+    }
+}
+
+/// # Sequence classification pipeline (e.g. sentiment analysis)
+#[derive(uniffi::Object)]
+pub struct SequenceClassifier {
+    model: Mutex<SequenceClassificationModel>,
+    limits: MobileLimits,
+}
+
+#[uniffi::export]
+impl SequenceClassifier {
+    /// Loads a classification model from local files, rejecting models exceeding the limits
+    #[uniffi::constructor]
+    pub fn new(
+        files: ClassificationModelFiles,
+        limits: MobileLimits,
+    ) -> Result<Arc<Self>, MobileError> {
+        let weights_path = PathBuf::from(&files.weights_path);
+        limits.check_model(&weights_path)?;
+        let mut config = SequenceClassificationConfig::new(
+            files.model_type.into(),
+            ModelResource::Torch(Box::new(LocalResource::from(weights_path))),
+            LocalResource::from(PathBuf::from(files.config_path)),
+            LocalResource::from(PathBuf::from(files.vocab_path)),
+            files
+                .merges_path
+                .map(|merges_path| LocalResource::from(PathBuf::from(merges_path))),
+            files.lower_case,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        Ok(Arc::new(SequenceClassifier {
+            model: Mutex::new(SequenceClassificationModel::new(&config)?),
+            limits,
+        }))
+    }
+
+    /// Predicts the label of each text
+    pub fn predict(&self, texts: Vec<String>) -> Result<Vec<MobileLabel>, MobileError> {
+        self.limits.check_inputs(&texts)?;
+        let inputs = texts.iter().map(String::as_str).collect::<Vec<&str>>();
+        Ok(lock(&self.model)?
+            .predict(inputs.as_slice())
+            .into_iter()
+            .map(MobileLabel::from)
+            .collect())
+    }
+}
+
+/// # Sentence embeddings pipeline
+#[derive(uniffi::Object)]
+pub struct SentenceEmbedder {
+    model: Mutex<SentenceEmbeddingsModel>,
+    limits: MobileLimits,
+}
+
+#[uniffi::export]
+impl SentenceEmbedder {
+    /// Loads a sentence embeddings model from a local directory (as created by sentence-transformers),
+    /// rejecting models exceeding the limits
+    #[uniffi::constructor]
+    pub fn new(model_dir: String, limits: MobileLimits) -> Result<Arc<Self>, MobileError> {
+        limits.check_model(Path::new(&model_dir))?;
+        let model = SentenceEmbeddingsBuilder::local(model_dir)
+            .with_device(Device::Cpu)
+            .create_model()?;
+        Ok(Arc::new(SentenceEmbedder {
+            model: Mutex::new(model),
+            limits,
+        }))
+    }
+
+    /// Computes the embedding of each text
+    pub fn encode(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, MobileError> {
+        self.limits.check_inputs(&texts)?;
+        Ok(lock(&self.model)?.encode(&texts)?)
+    }
+
+    /// Dimension of the embeddings
+    pub fn embedding_dim(&self) -> Result<i64, MobileError> {
+        Ok(lock(&self.model)?.get_embedding_dim()?)
+    }
+}
+
+/// # Keyword extracted by a `KeywordExtractor`
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileKeyword {
+    /// Keyword text
+    pub text: String,
+    /// Similarity of the keyword with the document
+    pub score: f32,
+    /// Start character offsets of the occurrences of the keyword
+    pub offsets: Vec<u32>,
+}
+
+impl From<Keyword> for MobileKeyword {
+    fn from(keyword: Keyword) -> Self {
+        MobileKeyword {
+            text: keyword.text,
+            score: keyword.score,
+            offsets: keyword
+                .offsets
+                .into_iter()
+                .map(|offset| offset.begin)
+                .collect(),
+        }
+    }
+}
+
+/// # Keyword extraction pipeline
+#[derive(uniffi::Object)]
+pub struct KeywordExtractor {
+    model: Mutex<KeywordExtractionModel<'static>>,
+    limits: MobileLimits,
+}
+
+#[uniffi::export]
+impl KeywordExtractor {
+    /// Loads a keyword extraction model based on a local sentence embeddings model directory, returning
+    /// `num_keywords` keywords of 1 to `max_ngram_length` words per text. Models exceeding the limits are rejected.
+    #[uniffi::constructor]
+    pub fn new(
+        model_dir: String,
+        num_keywords: u32,
+        max_ngram_length: u32,
+        limits: MobileLimits,
+    ) -> Result<Arc<Self>, MobileError> {
+        limits.check_model(Path::new(&model_dir))?;
+        let sentence_embeddings_config = SentenceEmbeddingsBuilder::local(model_dir)
+            .with_device(Device::Cpu)
+            .into_config()?;
+        let config = KeywordExtractionConfig {
+            sentence_embeddings_config,
+            tokenizer_stopwords: None,
+            tokenizer_pattern: None,
+            tokenizer_forbidden_ngram_chars: None,
+            scorer_type: KeywordScorerType::CosineSimilarity,
+            ngram_range: (1, max_ngram_length.max(1) as usize),
+            num_keywords: num_keywords as usize,
+            diversity: None,
+            max_sum_candidates: None,
+            pos_config: None,
+            candidate_pos_pattern: None,
+            seed_keywords: None,
+            seed_keywords_weight: None,
+        };
+        Ok(Arc::new(KeywordExtractor {
+            model: Mutex::new(KeywordExtractionModel::new(config)?),
+            limits,
+        }))
+    }
+
+    /// Extracts the keywords of each text
+    pub fn predict(&self, texts: Vec<String>) -> Result<Vec<Vec<MobileKeyword>>, MobileError> {
+        self.limits.check_inputs(&texts)?;
+        Ok(lock(&self.model)?
+            .predict(&texts)?
+            .into_iter()
+            .map(|keywords| keywords.into_iter().map(MobileKeyword::from).collect())
+            .collect())
+    }
+}
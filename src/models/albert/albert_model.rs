@@ -15,6 +15,7 @@ use crate::albert::encoder::AlbertTransformer;
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::weight_tying::TiedWeights;
 use crate::{albert::embeddings::AlbertEmbeddings, common::activations::TensorFunction};
 use crate::{Config, RustBertError};
 use serde::{Deserialize, Serialize};
@@ -358,6 +359,13 @@ pub struct AlbertForMaskedLM {
 }
 
 impl AlbertForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "albert.embeddings.word_embeddings.weight",
+        "predictions.decoder.weight",
+    )
+    .with_bias("predictions.decoder.bias", "predictions.bias");
+
     /// Build a new `AlbertForMaskedLM`
     ///
     /// # Arguments
@@ -13,6 +13,7 @@
 
 use crate::albert::AlbertConfig;
 use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
 use std::borrow::Borrow;
 use tch::{nn, Tensor};
 
@@ -113,16 +114,18 @@ impl AlbertSelfAttention {
         let query_layer: Tensor = query_layer / (self.attention_head_size as f64).sqrt();
 
         let scores = if let Some(mask) = mask {
-            query_layer.matmul(&key_layer.transpose(-1, -2)) + mask
+            attention_scores(&query_layer, &key_layer.transpose(-1, -2)) + mask
         } else {
-            query_layer.matmul(&key_layer.transpose(-1, -2))
+            attention_scores(&query_layer, &key_layer.transpose(-1, -2))
         };
 
         let weights = scores
             .softmax(-1, scores.kind())
             .apply_t(&self.dropout, train);
 
-        let context = weights.matmul(&value_layer).transpose(1, 2).contiguous();
+        let context = attention_output(&weights, &value_layer)
+            .transpose(1, 2)
+            .contiguous();
 
         let w = self.dense.ws.transpose(0, 1).view((
             self.num_attention_heads,
@@ -11,7 +11,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::dropout::Dropout;
+use crate::common::attention::{Attention, DenseAttention};
 use std::borrow::Borrow;
 use tch::{nn, Tensor};
 
@@ -45,7 +45,7 @@ impl LayerState {
 pub struct BartAttention {
     num_heads: i64,
     head_dim: i64,
-    dropout: Dropout,
+    attention: DenseAttention,
     scaling: f64,
     encoder_decoder_attention: bool,
     output_attentions: bool,
@@ -78,12 +78,12 @@ impl BartAttention {
 
         let head_dim = embed_dim / num_heads;
         let scaling = (head_dim as f64).powf(-0.5);
-        let dropout = Dropout::new(dropout);
+        let attention = DenseAttention::new(dropout, None, false);
 
         BartAttention {
             num_heads,
             head_dim,
-            dropout,
+            attention,
             scaling,
             encoder_decoder_attention,
             output_attentions,
@@ -145,36 +145,17 @@ impl BartAttention {
             None
         };
 
-        let proj_shape = [bs * self.num_heads, -1, self.head_dim];
-        let query_states = self
-            ._shape(query_states, target_length, bs)
-            .view(proj_shape);
-        let key_states = key_states.view(proj_shape);
-        let value_states = value_states.view(proj_shape);
-
-        let source_length = key_states.size()[1];
-        let mut attention_weights = query_states.bmm(&key_states.transpose(1, 2));
-
-        if let Some(attention_mask_value) = attention_mask {
-            attention_weights =
-                attention_weights.view([bs, self.num_heads, target_length, source_length])
-                    + attention_mask_value;
-            attention_weights =
-                attention_weights.view([bs * self.num_heads, target_length, source_length]);
-        };
-
-        attention_weights = attention_weights.softmax(-1, attention_weights.kind());
-
-        let saved_attention_weights = if self.output_attentions {
-            Some(attention_weights.view((bs, self.num_heads, target_length, source_length)))
-        } else {
-            None
-        };
-
-        let attention_probas = attention_weights.apply_t(&self.dropout, train);
-        let attention_output = attention_probas
-            .bmm(&value_states)
-            .view([bs, self.num_heads, target_length, self.head_dim])
+        let query_states = self._shape(query_states, target_length, bs);
+        let (attention_output, saved_attention_weights) = self.attention.forward_t(
+            &query_states,
+            &key_states,
+            &value_states,
+            attention_mask,
+            self.output_attentions,
+            train,
+        );
+
+        let attention_output = attention_output
             .transpose(1, 2)
             .reshape([bs, target_length, embed_dim])
             .apply(&self.out_proj);
@@ -13,7 +13,11 @@
 
 use crate::bert::bert_model::BertConfig;
 use crate::common::activations::TensorFunction;
+use crate::common::attention::{Attention, DenseAttention};
 use crate::common::dropout::Dropout;
+use crate::common::pruning::{kept_indices, prune_linear};
+use crate::common::quantization::QuantizableLinear;
+use crate::RustBertError;
 use std::borrow::Borrow;
 use tch::{nn, Tensor};
 
@@ -21,11 +25,11 @@ use tch::{nn, Tensor};
 pub struct BertSelfAttention {
     num_attention_heads: i64,
     attention_head_size: i64,
-    dropout: Dropout,
+    attention: DenseAttention,
     output_attentions: bool,
-    query: nn::Linear,
-    key: nn::Linear,
-    value: nn::Linear,
+    query: QuantizableLinear,
+    key: QuantizableLinear,
+    value: QuantizableLinear,
 }
 
 impl BertSelfAttention {
@@ -45,28 +49,31 @@ impl BertSelfAttention {
             config.hidden_size,
             config.hidden_size,
             Default::default(),
-        );
+        )
+        .into();
         let key = nn::linear(
             p / "key",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
-        );
+        )
+        .into();
         let value = nn::linear(
             p / "value",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
-        );
+        )
+        .into();
 
-        let dropout = Dropout::new(config.attention_probs_dropout_prob);
+        let attention = DenseAttention::new(config.attention_probs_dropout_prob, None, false);
         let attention_head_size = config.hidden_size / config.num_attention_heads;
         let output_attentions = config.output_attentions.unwrap_or(false);
 
         BertSelfAttention {
             num_attention_heads: config.num_attention_heads,
             attention_head_size,
-            dropout,
+            attention,
             output_attentions,
             query,
             key,
@@ -117,28 +124,23 @@ impl BertSelfAttention {
         let value_layer = self.split_heads(value_layer, bs, self.attention_head_size);
         let query_layer: Tensor = query_layer / (self.attention_head_size as f64).sqrt();
 
-        let scores = if let Some(mask) = mask {
-            query_layer.matmul(&key_layer.transpose(-1, -2)) + mask
-        } else {
-            query_layer.matmul(&key_layer.transpose(-1, -2))
-        };
-
-        let weights = scores
-            .softmax(-1, scores.kind())
-            .apply_t(&self.dropout, train);
-        let context = self.flatten(weights.matmul(&value_layer), bs, self.attention_head_size);
+        let (context, weights) = self.attention.forward_t(
+            &query_layer,
+            &key_layer,
+            &value_layer,
+            mask,
+            self.output_attentions,
+            train,
+        );
+        let context = self.flatten(context, bs, self.attention_head_size);
 
-        if !self.output_attentions {
-            (context, None)
-        } else {
-            (context, Some(weights))
-        }
+        (context, weights)
     }
 }
 
 #[derive(Debug)]
 pub struct BertSelfOutput {
-    linear: nn::Linear,
+    linear: QuantizableLinear,
     layer_norm: nn::LayerNorm,
     dropout: Dropout,
 }
@@ -155,7 +157,8 @@ impl BertSelfOutput {
             config.hidden_size,
             config.hidden_size,
             Default::default(),
-        );
+        )
+        .into();
         let layer_norm_config = nn::LayerNormConfig {
             eps: 1e-12,
             ..Default::default()
@@ -198,6 +201,30 @@ impl BertAttention {
         BertAttention { _self, output }
     }
 
+    pub(crate) fn prune_heads(&mut self, heads: &[i64]) -> Result<(), RustBertError> {
+        let self_attention = &mut self._self;
+        let (num_heads, indices) = kept_indices(
+            self_attention.num_attention_heads,
+            self_attention.attention_head_size,
+            heads,
+            "attention head",
+            self_attention.query.full_mut()?.ws.device(),
+        )?;
+        prune_linear(self_attention.query.full_mut()?, &indices, 0);
+        prune_linear(self_attention.key.full_mut()?, &indices, 0);
+        prune_linear(self_attention.value.full_mut()?, &indices, 0);
+        self_attention.num_attention_heads = num_heads;
+        prune_linear(self.output.linear.full_mut()?, &indices, 1);
+        Ok(())
+    }
+
+    pub(crate) fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self._self.query.quantize_dynamic()?;
+        self._self.key.quantize_dynamic()?;
+        self._self.value.quantize_dynamic()?;
+        self.output.linear.quantize_dynamic()
+    }
+
     pub fn forward_t(
         &self,
         hidden_states: &Tensor,
@@ -220,7 +247,7 @@ impl BertAttention {
 }
 
 pub struct BertIntermediate {
-    lin: nn::Linear,
+    lin: QuantizableLinear,
     activation: TensorFunction,
 }
 
@@ -236,7 +263,8 @@ impl BertIntermediate {
             config.hidden_size,
             config.intermediate_size,
             Default::default(),
-        );
+        )
+        .into();
         let activation = config.hidden_act.get_function();
         BertIntermediate { lin, activation }
     }
@@ -244,10 +272,28 @@ impl BertIntermediate {
     pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
         (self.activation.get_fn())(&hidden_states.apply(&self.lin))
     }
+
+    /// Removes intermediate neurons, returning the indices of the neurons kept
+    pub(crate) fn prune_neurons(&mut self, neurons: &[i64]) -> Result<Tensor, RustBertError> {
+        let lin = self.lin.full_mut()?;
+        let (_, indices) = kept_indices(
+            lin.ws.size()[0],
+            1,
+            neurons,
+            "intermediate neuron",
+            lin.ws.device(),
+        )?;
+        prune_linear(lin, &indices, 0);
+        Ok(indices)
+    }
+
+    pub(crate) fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.lin.quantize_dynamic()
+    }
 }
 
 pub struct BertOutput {
-    lin: nn::Linear,
+    lin: QuantizableLinear,
     layer_norm: nn::LayerNorm,
     dropout: Dropout,
 }
@@ -264,7 +310,8 @@ impl BertOutput {
             config.intermediate_size,
             config.hidden_size,
             Default::default(),
-        );
+        )
+        .into();
         let layer_norm_config = nn::LayerNormConfig {
             eps: 1e-12,
             ..Default::default()
@@ -285,4 +332,14 @@ impl BertOutput {
             input_tensor + hidden_states.apply(&self.lin).apply_t(&self.dropout, train);
         hidden_states.apply(&self.layer_norm)
     }
+
+    /// Keeps the inputs of the output layer matching the intermediate neurons kept
+    pub(crate) fn prune_inputs(&mut self, indices: &Tensor) -> Result<(), RustBertError> {
+        prune_linear(self.lin.full_mut()?, indices, 1);
+        Ok(())
+    }
+
+    pub(crate) fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.lin.quantize_dynamic()
+    }
 }
@@ -16,6 +16,8 @@ use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
 use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::pruning::PruningConfig;
+use crate::common::weight_tying::TiedWeights;
 use crate::{
     bert::embeddings::{BertEmbedding, BertEmbeddings},
     common::activations::TensorFunction,
@@ -67,6 +69,31 @@ impl BertModelResources {
         "all-mini-lm-l6-v2/model",
         "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/rust_model.ot",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2>.
+    pub const MS_MARCO_MINI_LM_L6_V2: (&'static str, &'static str) = (
+        "ms-marco-minilm-l6-v2/model",
+        "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-12-v2>.
+    pub const MS_MARCO_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "ms-marco-minilm-l12-v2/model",
+        "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-12-v2/resolve/main/model.safetensors",
+    );
+    /// Shared under CC-BY-4.0 license by deepset at <https://huggingface.co/deepset/minilm-uncased-squad2>.
+    pub const MINI_LM_SQUAD2: (&'static str, &'static str) = (
+        "minilm-uncased-squad2/model",
+        "https://huggingface.co/deepset/minilm-uncased-squad2/resolve/main/model.safetensors",
+    );
+    /// Shared under MIT license at <https://huggingface.co/philschmid/MiniLM-L6-H384-uncased-sst2>.
+    pub const MINI_LM_L6_SST2: (&'static str, &'static str) = (
+        "minilm-l6-h384-uncased-sst2/model",
+        "https://huggingface.co/philschmid/MiniLM-L6-H384-uncased-sst2/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by Unitary at <https://huggingface.co/unitary/toxic-bert>.
+    pub const TOXIC_BERT: (&'static str, &'static str) = (
+        "toxic-bert/model",
+        "https://huggingface.co/unitary/toxic-bert/resolve/main/model.safetensors",
+    );
 }
 
 impl BertConfigResources {
@@ -100,6 +127,31 @@ impl BertConfigResources {
         "all-mini-lm-l6-v2/config",
         "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2>.
+    pub const MS_MARCO_MINI_LM_L6_V2: (&'static str, &'static str) = (
+        "ms-marco-minilm-l6-v2/config",
+        "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-12-v2>.
+    pub const MS_MARCO_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "ms-marco-minilm-l12-v2/config",
+        "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-12-v2/resolve/main/config.json",
+    );
+    /// Shared under CC-BY-4.0 license by deepset at <https://huggingface.co/deepset/minilm-uncased-squad2>.
+    pub const MINI_LM_SQUAD2: (&'static str, &'static str) = (
+        "minilm-uncased-squad2/config",
+        "https://huggingface.co/deepset/minilm-uncased-squad2/resolve/main/config.json",
+    );
+    /// Shared under MIT license at <https://huggingface.co/philschmid/MiniLM-L6-H384-uncased-sst2>.
+    pub const MINI_LM_L6_SST2: (&'static str, &'static str) = (
+        "minilm-l6-h384-uncased-sst2/config",
+        "https://huggingface.co/philschmid/MiniLM-L6-H384-uncased-sst2/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by Unitary at <https://huggingface.co/unitary/toxic-bert>.
+    pub const TOXIC_BERT: (&'static str, &'static str) = (
+        "toxic-bert/config",
+        "https://huggingface.co/unitary/toxic-bert/resolve/main/config.json",
+    );
 }
 
 impl BertVocabResources {
@@ -133,6 +185,31 @@ impl BertVocabResources {
         "all-mini-lm-l6-v2/vocab",
         "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/vocab.txt",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2>.
+    pub const MS_MARCO_MINI_LM_L6_V2: (&'static str, &'static str) = (
+        "ms-marco-minilm-l6-v2/vocab",
+        "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2/resolve/main/vocab.txt",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-12-v2>.
+    pub const MS_MARCO_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "ms-marco-minilm-l12-v2/vocab",
+        "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-12-v2/resolve/main/vocab.txt",
+    );
+    /// Shared under CC-BY-4.0 license by deepset at <https://huggingface.co/deepset/minilm-uncased-squad2>.
+    pub const MINI_LM_SQUAD2: (&'static str, &'static str) = (
+        "minilm-uncased-squad2/vocab",
+        "https://huggingface.co/deepset/minilm-uncased-squad2/resolve/main/vocab.txt",
+    );
+    /// Shared under MIT license at <https://huggingface.co/philschmid/MiniLM-L6-H384-uncased-sst2>.
+    pub const MINI_LM_L6_SST2: (&'static str, &'static str) = (
+        "minilm-l6-h384-uncased-sst2/vocab",
+        "https://huggingface.co/philschmid/MiniLM-L6-H384-uncased-sst2/resolve/main/vocab.txt",
+    );
+    /// Shared under Apache 2.0 license by Unitary at <https://huggingface.co/unitary/toxic-bert>.
+    pub const TOXIC_BERT: (&'static str, &'static str) = (
+        "toxic-bert/vocab",
+        "https://huggingface.co/unitary/toxic-bert/resolve/main/vocab.txt",
+    );
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -447,6 +524,27 @@ impl<T: BertEmbedding> BertModel<T> {
             all_attentions: encoder_output.all_attentions,
         })
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the encoder, rewriting the weights in place.
+    /// See the `pruning` module for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        self.encoder.prune(config)
+    }
+
+    /// Quantizes the linear layers of the encoder to int8, with a dynamic quantization of the activations. The
+    /// embeddings and the pooler are kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.encoder.quantize_dynamic()
+    }
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    pub(crate) fn get_word_embeddings(&self) -> &Tensor {
+        self.embeddings.get_word_embeddings()
+    }
 }
 
 pub struct BertPredictionHeadTransform {
@@ -532,6 +630,12 @@ pub struct BertForMaskedLM {
 }
 
 impl BertForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "bert.embeddings.word_embeddings.weight",
+        "cls.predictions.decoder.weight",
+    );
+
     /// Build a new `BertForMaskedLM`
     ///
     /// # Arguments
@@ -649,6 +753,22 @@ impl BertForMaskedLM {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the BERT encoder, rewriting the weights in
+    /// place. See the `pruning` module for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        self.bert.prune(config)
+    }
+
+    /// Quantizes the linear layers of the BERT encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.bert.quantize_dynamic()
+    }
 }
 
 /// # BERT for sequence classification
@@ -800,6 +920,27 @@ impl BertForSequenceClassification {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the BERT encoder, rewriting the weights in
+    /// place. See the `pruning` module for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        self.bert.prune(config)
+    }
+
+    /// Quantizes the linear layers of the BERT encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.bert.quantize_dynamic()
+    }
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    pub(crate) fn get_word_embeddings(&self) -> &Tensor {
+        self.bert.get_word_embeddings()
+    }
 }
 
 /// # BERT for multiple choices
@@ -944,6 +1085,22 @@ impl BertForMultipleChoice {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the BERT encoder, rewriting the weights in
+    /// place. See the `pruning` module for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        self.bert.prune(config)
+    }
+
+    /// Quantizes the linear layers of the BERT encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.bert.quantize_dynamic()
+    }
 }
 
 /// # BERT for token classification (e.g. NER, POS)
@@ -1096,6 +1253,22 @@ impl BertForTokenClassification {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the BERT encoder, rewriting the weights in
+    /// place. See the `pruning` module for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        self.bert.prune(config)
+    }
+
+    /// Quantizes the linear layers of the BERT encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.bert.quantize_dynamic()
+    }
 }
 
 /// # BERT for question answering
@@ -1236,6 +1409,22 @@ impl BertForQuestionAnswering {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the BERT encoder, rewriting the weights in
+    /// place. See the `pruning` module for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        self.bert.prune(config)
+    }
+
+    /// Quantizes the linear layers of the BERT encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.bert.quantize_dynamic()
+    }
 }
 
 /// # BERT for sentence embeddings
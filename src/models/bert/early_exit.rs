@@ -0,0 +1,359 @@
+// Copyright 2020 Ji Xin, Raphael Tang, Jaejun Lee, Yaoliang Yu and Jimmy Lin (DeeBERT)
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bert::embeddings::{BertEmbedding, BertEmbeddings};
+use crate::bert::encoder::{BertLayer, BertPooler};
+use crate::bert::BertConfig;
+use crate::common::dropout::Dropout;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Kind, Tensor};
+
+/// # Early exit classification head
+/// Intermediate ("highway") classifier attached to the output of an encoder layer.
+/// It is made of a pooler applied to the first sequence element followed by a linear classifier.
+pub struct BertEarlyExitHead {
+    pooler: BertPooler,
+    dropout: Dropout,
+    classifier: nn::Linear,
+}
+
+impl BertEarlyExitHead {
+    /// Build a new `BertEarlyExitHead`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the exit head
+    /// * `config` - `BertConfig` object defining the model architecture
+    /// * `num_labels` - number of output classes
+    pub fn new<'p, P>(p: P, config: &BertConfig, num_labels: i64) -> BertEarlyExitHead
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let pooler = BertPooler::new(p / "pooler", config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let classifier = nn::linear(
+            p / "classifier",
+            config.hidden_size,
+            num_labels,
+            Default::default(),
+        );
+
+        BertEarlyExitHead {
+            pooler,
+            dropout,
+            classifier,
+        }
+    }
+
+    /// Forward pass through the exit head
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - layer output of shape (*batch size*, *sequence_length*, *hidden_size*).
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *num_labels*)
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        self.pooler
+            .forward(hidden_states)
+            .apply_t(&self.dropout, train)
+            .apply(&self.classifier)
+    }
+}
+
+/// # BERT for sequence classification with early exit
+/// BERT classification model with an additional classifier head after every encoder layer (DeeBERT, <https://arxiv.org/abs/2004.12993>).
+/// At inference, an input leaves the model at the first layer whose head predicts a distribution with an entropy
+/// below `entropy_threshold`: easy inputs skip the upper layers, reducing the average latency. Inputs exiting early
+/// are removed from the batch, the remaining ones proceed through the following layers.
+/// It is made of the following blocks:
+/// - `embeddings`: BERT embeddings
+/// - `layers`: BERT encoder layers
+/// - `exit_heads`: intermediate classifiers for all encoder layers but the last one
+/// - `pooler` and `classifier`: final classification head, identical to `BertForSequenceClassification`
+///
+/// The weights are compatible with DeeBERT checkpoints (exit heads stored under `bert.encoder.highway`).
+/// Loading the weights of a `BertForSequenceClassification` model leaves the exit heads randomly initialized:
+/// they need to be fine-tuned (with the base model frozen) before being used with a positive threshold.
+pub struct BertForEarlyExitSequenceClassification {
+    embeddings: BertEmbeddings,
+    layers: Vec<BertLayer>,
+    exit_heads: Vec<BertEarlyExitHead>,
+    pooler: BertPooler,
+    dropout: Dropout,
+    classifier: nn::Linear,
+    entropy_threshold: f64,
+}
+
+impl BertForEarlyExitSequenceClassification {
+    /// Build a new `BertForEarlyExitSequenceClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the model
+    /// * `config` - `BertConfig` object defining the model architecture and number of classes
+    /// * `entropy_threshold` - entropy (in nats) of the predicted distribution below which an input exits the model.
+    ///   A threshold of 0 disables early exit, the maximum entropy for `n` classes is `ln(n)`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bert::{BertConfig, BertForEarlyExitSequenceClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BertConfig::from_file(config_path);
+    /// let bert = BertForEarlyExitSequenceClassification::new(p.root(), &config, 0.3).unwrap();
+    /// ```
+    pub fn new<'p, P>(
+        p: P,
+        config: &BertConfig,
+        entropy_threshold: f64,
+    ) -> Result<BertForEarlyExitSequenceClassification, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        if config.is_decoder.unwrap_or(false) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Early exit is only supported for encoder models".to_string(),
+            ));
+        }
+        if entropy_threshold < 0.0 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The entropy threshold must be positive, got {entropy_threshold}"
+            )));
+        }
+        let num_labels = config
+            .id2label
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "num_labels not provided in configuration".to_string(),
+                )
+            })?
+            .len() as i64;
+
+        let embeddings = BertEmbeddings::new(p / "bert" / "embeddings", config);
+        let p_layers = p / "bert" / "encoder" / "layer";
+        let p_exit_heads = p / "bert" / "encoder" / "highway";
+        let mut layers: Vec<BertLayer> = vec![];
+        let mut exit_heads: Vec<BertEarlyExitHead> = vec![];
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(BertLayer::new(&p_layers / layer_index, config));
+            if layer_index < config.num_hidden_layers - 1 {
+                exit_heads.push(BertEarlyExitHead::new(
+                    &p_exit_heads / layer_index,
+                    config,
+                    num_labels,
+                ));
+            }
+        }
+        let pooler = BertPooler::new(p / "bert" / "pooler", config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let classifier = nn::linear(
+            p / "classifier",
+            config.hidden_size,
+            num_labels,
+            Default::default(),
+        );
+
+        Ok(BertForEarlyExitSequenceClassification {
+            embeddings,
+            layers,
+            exit_heads,
+            pooler,
+            dropout,
+            classifier,
+            entropy_threshold,
+        })
+    }
+
+    /// Returns the entropy threshold below which inputs exit the model
+    pub fn get_entropy_threshold(&self) -> f64 {
+        self.entropy_threshold
+    }
+
+    /// Sets the entropy threshold below which inputs exit the model. A threshold of 0 disables early exit.
+    pub fn set_entropy_threshold(&mut self, entropy_threshold: f64) {
+        self.entropy_threshold = entropy_threshold.max(0.0);
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. In training mode all layers are always
+    ///   evaluated and the logits of every exit head are returned.
+    ///
+    /// # Returns
+    ///
+    /// * `BertEarlyExitOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*), predicted by the exit head of each input
+    ///   - `exit_layers` - `Vec<usize>` of length *batch size* with the number of layers evaluated for each input
+    ///   - `all_exit_logits` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_labels*), only returned in training mode
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::bert::{BertForEarlyExitSequenceClassification, BertConfig};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BertConfig::from_file(config_path);
+    /// # let bert_model = BertForEarlyExitSequenceClassification::new(vs.root(), &config, 0.3).unwrap();
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bert_model.forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BertEarlyExitOutput, RustBertError> {
+        let mut hidden_state = self.embeddings.forward_t(
+            input_ids,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+        let (batch_size, sequence_length, _) = hidden_state.size3()?;
+        let device = hidden_state.device();
+
+        let mut extended_attention_mask = match mask {
+            Some(mask) if mask.dim() == 2 => mask.unsqueeze(1).unsqueeze(1),
+            Some(_) => {
+                return Err(RustBertError::ValueError(
+                    "Invalid attention mask dimension, must be 2".into(),
+                ));
+            }
+            None => Tensor::ones([batch_size, 1, 1, sequence_length], (Kind::Int8, device)),
+        };
+        extended_attention_mask = ((extended_attention_mask.ones_like() - extended_attention_mask)
+            * -10000.0)
+            .to_kind(hidden_state.kind());
+
+        let num_layers = self.layers.len();
+        let mut all_exit_logits: Option<Vec<Tensor>> = if train { Some(vec![]) } else { None };
+        let mut exit_layers = vec![num_layers; batch_size as usize];
+        let mut logits: Option<Tensor> = None;
+        // Positions in the original batch of the inputs that have not exited yet
+        let mut active_indices = Tensor::arange(batch_size, (Kind::Int64, device));
+
+        for (layer_index, layer) in self.layers.iter().enumerate() {
+            hidden_state = layer
+                .forward_t(
+                    &hidden_state,
+                    Some(&extended_attention_mask),
+                    None,
+                    None,
+                    train,
+                )
+                .hidden_state;
+
+            let layer_logits = match self.exit_heads.get(layer_index) {
+                Some(exit_head) => exit_head.forward_t(&hidden_state, train),
+                None => self
+                    .pooler
+                    .forward(&hidden_state)
+                    .apply_t(&self.dropout, train)
+                    .apply(&self.classifier),
+            };
+            let output_logits = logits.get_or_insert_with(|| layer_logits.zeros_like());
+
+            if let Some(all_exit_logits) = all_exit_logits.as_mut() {
+                if layer_index == num_layers - 1 {
+                    *output_logits = layer_logits.shallow_clone();
+                }
+                all_exit_logits.push(layer_logits);
+                continue;
+            }
+
+            let exiting = if layer_index == num_layers - 1 {
+                layer_logits.ones_like().select(1, 0).to_kind(Kind::Bool)
+            } else {
+                let log_probabilities = layer_logits.log_softmax(-1, Kind::Float);
+                let entropy = -(log_probabilities.exp() * &log_probabilities).sum_dim_intlist(
+                    [-1].as_slice(),
+                    false,
+                    Kind::Float,
+                );
+                entropy.lt(self.entropy_threshold)
+            };
+
+            let exiting_indices = exiting.nonzero().squeeze_dim(-1);
+            if exiting_indices.numel() > 0 {
+                let exiting_positions = active_indices.index_select(0, &exiting_indices);
+                let _ = output_logits.index_copy_(
+                    0,
+                    &exiting_positions,
+                    &layer_logits.index_select(0, &exiting_indices),
+                );
+                for position in Vec::<i64>::try_from(&exiting_positions)? {
+                    exit_layers[position as usize] = layer_index + 1;
+                }
+
+                let remaining_indices = exiting.logical_not().nonzero().squeeze_dim(-1);
+                if remaining_indices.numel() == 0 {
+                    break;
+                }
+                active_indices = active_indices.index_select(0, &remaining_indices);
+                hidden_state = hidden_state.index_select(0, &remaining_indices);
+                extended_attention_mask =
+                    extended_attention_mask.index_select(0, &remaining_indices);
+            }
+        }
+
+        Ok(BertEarlyExitOutput {
+            logits: logits.unwrap(),
+            exit_layers,
+            all_exit_logits,
+        })
+    }
+}
+
+/// Container for the BERT early exit classification model output.
+pub struct BertEarlyExitOutput {
+    /// Logits for each input (shape *batch size*, *num_labels*), predicted by the head at which the input exited
+    pub logits: Tensor,
+    /// Number of encoder layers evaluated for each input
+    pub exit_layers: Vec<usize>,
+    /// Logits of all exit heads, last one being the final classifier (returned in training mode only)
+    pub all_exit_logits: Option<Vec<Tensor>>,
+}
@@ -34,6 +34,9 @@ pub trait BertEmbedding {
         input_embeds: Option<&Tensor>,
         train: bool,
     ) -> Result<Tensor, RustBertError>;
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    fn get_word_embeddings(&self) -> &Tensor;
 }
 
 #[derive(Debug)]
@@ -210,4 +213,8 @@ impl BertEmbedding for BertEmbeddings {
             .apply(&self.layer_norm)
             .apply_t(&self.dropout, train))
     }
+
+    fn get_word_embeddings(&self) -> &Tensor {
+        &self.word_embeddings.ws
+    }
 }
@@ -13,6 +13,8 @@
 
 use crate::bert::attention::{BertAttention, BertIntermediate, BertOutput};
 use crate::bert::bert_model::BertConfig;
+use crate::common::pruning::PruningConfig;
+use crate::RustBertError;
 use std::borrow::{Borrow, BorrowMut};
 use tch::{nn, Tensor};
 
@@ -161,6 +163,36 @@ impl BertLayer {
             cross_attention_weights: cross_attention_scores,
         }
     }
+
+    /// Removes self-attention heads from the layer (cross-attention heads are kept)
+    ///
+    /// # Arguments
+    ///
+    /// * `heads` - indices of the attention heads to remove
+    pub fn prune_heads(&mut self, heads: &[i64]) -> Result<(), RustBertError> {
+        self.attention.prune_heads(heads)
+    }
+
+    /// Removes intermediate (feed-forward) neurons from the layer
+    ///
+    /// # Arguments
+    ///
+    /// * `neurons` - indices of the intermediate neurons to remove
+    pub fn prune_intermediate_neurons(&mut self, neurons: &[i64]) -> Result<(), RustBertError> {
+        let indices = self.intermediate.prune_neurons(neurons)?;
+        self.output.prune_inputs(&indices)
+    }
+
+    /// Quantizes the linear layers of the attention and feed-forward blocks to int8 (see the `quantization` module).
+    /// The cross-attention layers of decoders are also quantized.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.attention.quantize_dynamic()?;
+        if let Some(cross_attention) = &mut self.cross_attention {
+            cross_attention.quantize_dynamic()?;
+        }
+        self.intermediate.quantize_dynamic()?;
+        self.output.quantize_dynamic()
+    }
 }
 
 /// # BERT Encoder
@@ -305,6 +337,31 @@ impl BertEncoder {
             all_attentions,
         }
     }
+
+    /// Removes attention heads and intermediate neurons from the layers of the encoder, rewriting the weights in place
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `PruningConfig` listing the heads and neurons to remove for each layer
+    pub fn prune(&mut self, config: &PruningConfig) -> Result<(), RustBertError> {
+        config.validate(self.layers.len())?;
+        for (layer_index, heads) in config.heads.iter() {
+            self.layers[*layer_index].prune_heads(heads)?;
+        }
+        for (layer_index, neurons) in config.intermediate_neurons.iter() {
+            self.layers[*layer_index].prune_intermediate_neurons(neurons)?;
+        }
+        Ok(())
+    }
+
+    /// Quantizes the linear layers of the encoder layers to int8, with a dynamic quantization of the activations.
+    /// See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_dynamic()?;
+        }
+        Ok(())
+    }
 }
 
 /// # BERT Pooler
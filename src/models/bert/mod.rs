@@ -54,6 +54,7 @@
 
 mod attention;
 mod bert_model;
+mod early_exit;
 mod embeddings;
 pub(crate) mod encoder;
 
@@ -64,5 +65,8 @@ pub use bert_model::{
     BertQuestionAnsweringOutput, BertSequenceClassificationOutput, BertTokenClassificationOutput,
     BertVocabResources,
 };
+pub use early_exit::{
+    BertEarlyExitHead, BertEarlyExitOutput, BertForEarlyExitSequenceClassification,
+};
 pub use embeddings::{BertEmbedding, BertEmbeddings};
 pub use encoder::{BertEncoder, BertEncoderOutput, BertLayer, BertLayerOutput, BertPooler};
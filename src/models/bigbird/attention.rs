@@ -0,0 +1,433 @@
+// Copyright 2021 Google Research and The HuggingFace Inc. team.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bigbird::BigBirdConfig;
+use crate::common::dropout::Dropout;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+/// Number of key blocks attended by the middle query blocks in addition to the random blocks: the sliding
+/// window (previous, current and next blocks) and the first and last (global) blocks.
+const NUM_STRUCTURED_BLOCKS: i64 = 5;
+
+#[derive(Debug)]
+/// # BigBird self-attention
+/// Multi-head self-attention layer supporting the full (`original_full`) and the block-sparse (`block_sparse`)
+/// attention patterns. With block-sparse attention, the sequence is split in blocks of `block_size` tokens:
+/// - the first and last query blocks (global blocks) attend to the full sequence,
+/// - every other query block attends to the first and last key blocks, to a sliding window of 3 blocks
+///   centered on itself and to `num_random_blocks` random blocks.
+///
+/// Random blocks are only sampled during training. At inference, they point to the first (global) block,
+/// reproducing the behaviour of the reference implementation for consistency with pre-trained checkpoints.
+pub struct BigBirdSelfAttention {
+    num_attention_heads: i64,
+    attention_head_size: i64,
+    block_size: i64,
+    num_random_blocks: i64,
+    dropout: Dropout,
+    output_attentions: bool,
+    query: nn::Linear,
+    key: nn::Linear,
+    value: nn::Linear,
+}
+
+impl BigBirdSelfAttention {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdSelfAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Hidden size not a multiple of the number of attention heads"
+        );
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.use_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let query = nn::linear(
+            p / "query",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let key = nn::linear(
+            p / "key",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let value = nn::linear(
+            p / "value",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let dropout = Dropout::new(config.attention_probs_dropout_prob);
+        let attention_head_size = config.hidden_size / config.num_attention_heads;
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        BigBirdSelfAttention {
+            num_attention_heads: config.num_attention_heads,
+            attention_head_size,
+            block_size: config.block_size,
+            num_random_blocks: config.num_random_blocks,
+            dropout,
+            output_attentions,
+            query,
+            key,
+            value,
+        }
+    }
+
+    fn split_heads(&self, x: Tensor, bs: i64) -> Tensor {
+        x.view((bs, -1, self.num_attention_heads, self.attention_head_size))
+            .transpose(1, 2)
+    }
+
+    fn flatten(&self, x: Tensor, bs: i64) -> Tensor {
+        x.transpose(1, 2).contiguous().view((
+            bs,
+            -1,
+            self.num_attention_heads * self.attention_head_size,
+        ))
+    }
+
+    /// Forward pass through the self-attention layer
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - input tensor of shape (*batch size*, *sequence_length*, *hidden_size*). For block-sparse attention, the sequence length must be a multiple of the block size.
+    /// * `mask` - attention mask of shape (*batch size*, *sequence_length*), with value 1 for tokens to attend to and 0 for padding tokens.
+    /// * `block_sparse` - use the block-sparse attention pattern if true, full attention otherwise
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `context` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    /// * `attention_weights` - Optional `Tensor` of shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*). Key positions outside of the sparse pattern have a weight of 0.
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        mask: &Tensor,
+        block_sparse: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let bs = hidden_states.size()[0];
+
+        let query_layer = self.split_heads(hidden_states.apply(&self.query), bs);
+        let key_layer = self.split_heads(hidden_states.apply(&self.key), bs);
+        let value_layer = self.split_heads(hidden_states.apply(&self.value), bs);
+
+        let (context, attention_weights) = if block_sparse {
+            self.block_sparse_attention(&query_layer, &key_layer, &value_layer, mask, train)
+        } else {
+            let mask = additive_mask(mask, hidden_states.kind())
+                .unsqueeze(1)
+                .unsqueeze(1);
+            self.full_attention(&query_layer, &key_layer, &value_layer, &mask, train)
+        };
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+        (self.flatten(context, bs), attention_weights)
+    }
+
+    fn full_attention(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        value_layer: &Tensor,
+        mask: &Tensor,
+        train: bool,
+    ) -> (Tensor, Tensor) {
+        let scores = query_layer.matmul(&key_layer.transpose(-1, -2))
+            / (self.attention_head_size as f64).sqrt()
+            + mask;
+        let weights = scores
+            .softmax(-1, scores.kind())
+            .apply_t(&self.dropout, train);
+        (weights.matmul(value_layer), weights)
+    }
+
+    /// Indices of the key blocks attended by each middle query block, of shape (*num_heads*, *num_blocks - 2*,
+    /// *5 + num_random_blocks*): first block, sliding window, last block and random blocks.
+    fn attended_blocks(&self, num_blocks: i64, train: bool, device: Device) -> Tensor {
+        let num_middle_blocks = num_blocks - 2;
+        let block_positions = Tensor::arange_start(1, num_blocks - 1, (Kind::Int64, device));
+        let structured_blocks = Tensor::stack(
+            &[
+                block_positions.zeros_like(),
+                &block_positions - 1,
+                block_positions.shallow_clone(),
+                &block_positions + 1,
+                block_positions.full_like(num_blocks - 1),
+            ],
+            1,
+        )
+        .unsqueeze(0)
+        .expand([self.num_attention_heads, num_middle_blocks, -1], true);
+
+        let random_blocks = if train && self.num_random_blocks > 0 {
+            // Random blocks are sampled without replacement among the blocks not already attended
+            let candidates =
+                Tensor::arange(num_blocks, (Kind::Int64, device)).view([1, num_blocks]);
+            let excluded = candidates
+                .le(0)
+                .logical_or(&candidates.ge(num_blocks - 1))
+                .logical_or(
+                    &(&candidates - block_positions.view([num_middle_blocks, 1]))
+                        .abs()
+                        .le(1),
+                );
+            Tensor::rand(
+                [self.num_attention_heads, num_middle_blocks, num_blocks],
+                (Kind::Float, device),
+            )
+            .masked_fill(&excluded, -1.0)
+            .topk(self.num_random_blocks, -1, true, false)
+            .1
+        } else {
+            Tensor::zeros(
+                [
+                    self.num_attention_heads,
+                    num_middle_blocks,
+                    self.num_random_blocks,
+                ],
+                (Kind::Int64, device),
+            )
+        };
+        Tensor::cat(&[structured_blocks, random_blocks], -1)
+    }
+
+    fn block_sparse_attention(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        value_layer: &Tensor,
+        mask: &Tensor,
+        train: bool,
+    ) -> (Tensor, Tensor) {
+        let (bs, num_heads, seq_len, head_dim) = query_layer.size4().unwrap();
+        let block_size = self.block_size;
+        let num_blocks = seq_len / block_size;
+        let num_middle_blocks = num_blocks - 2;
+        let num_attended_blocks = NUM_STRUCTURED_BLOCKS + self.num_random_blocks;
+        let kind = query_layer.kind();
+        let device = query_layer.device();
+        let additive_mask = additive_mask(mask, kind);
+
+        // Following the reference implementation, dropout is not applied to the block-sparse attention weights.
+        // Global query blocks attend to the full sequence
+        let global_query = Tensor::cat(
+            &[
+                query_layer.narrow(2, 0, block_size),
+                query_layer.narrow(2, seq_len - block_size, block_size),
+            ],
+            2,
+        );
+        let (global_context, global_weights) = self.full_attention(
+            &global_query,
+            key_layer,
+            value_layer,
+            &additive_mask.unsqueeze(1).unsqueeze(1),
+            false,
+        );
+
+        // Middle query blocks attend to a gathered set of key blocks
+        let attended_blocks = self.attended_blocks(num_blocks, train, device);
+        let head_offsets =
+            Tensor::arange(num_heads, (Kind::Int64, device)).view([num_heads, 1, 1]) * num_blocks;
+        let flat_indices = (&attended_blocks + head_offsets).view([-1]);
+        let gather_blocks = |layer: &Tensor| {
+            layer
+                .reshape([bs, num_heads * num_blocks, block_size, head_dim])
+                .index_select(1, &flat_indices)
+                .view([
+                    bs,
+                    num_heads,
+                    num_middle_blocks,
+                    num_attended_blocks * block_size,
+                    head_dim,
+                ])
+        };
+        let gathered_keys = gather_blocks(key_layer);
+        let gathered_values = gather_blocks(value_layer);
+        let middle_query = query_layer
+            .narrow(2, block_size, num_middle_blocks * block_size)
+            .reshape([bs, num_heads, num_middle_blocks, block_size, head_dim]);
+
+        let gathered_mask = additive_mask
+            .view([bs, num_blocks, block_size])
+            .index_select(1, &attended_blocks.view([-1]))
+            .view([
+                bs,
+                num_heads,
+                num_middle_blocks,
+                1,
+                num_attended_blocks * block_size,
+            ]);
+        // The sliding window of the second and second-to-last blocks overlaps with the global blocks
+        let duplicate_mask =
+            Tensor::zeros([num_middle_blocks, num_attended_blocks], (kind, device));
+        let _ = duplicate_mask.get(0).get(1).fill_(-10000.0);
+        let _ = duplicate_mask
+            .get(num_middle_blocks - 1)
+            .get(3)
+            .fill_(-10000.0);
+        let duplicate_mask = duplicate_mask
+            .unsqueeze(-1)
+            .expand([num_middle_blocks, num_attended_blocks, block_size], true)
+            .reshape([1, 1, num_middle_blocks, 1, num_attended_blocks * block_size]);
+
+        let middle_scores = middle_query.matmul(&gathered_keys.transpose(-1, -2))
+            / (head_dim as f64).sqrt()
+            + gathered_mask
+            + duplicate_mask;
+        let middle_weights = middle_scores.softmax(-1, kind);
+        let middle_context = middle_weights.matmul(&gathered_values).view([
+            bs,
+            num_heads,
+            num_middle_blocks * block_size,
+            head_dim,
+        ]);
+
+        let context = Tensor::cat(
+            &[
+                global_context.narrow(2, 0, block_size),
+                middle_context,
+                global_context.narrow(2, block_size, block_size),
+            ],
+            2,
+        );
+
+        let attention_weights = if self.output_attentions {
+            // Scatter the weights of the middle blocks back to their key positions
+            let key_positions = (attended_blocks.unsqueeze(-1) * block_size
+                + Tensor::arange(block_size, (Kind::Int64, device)))
+            .view([1, num_heads, num_middle_blocks, 1, -1])
+            .expand(middle_weights.size(), true);
+            let middle_weights = Tensor::zeros(
+                [bs, num_heads, num_middle_blocks, block_size, seq_len],
+                (kind, device),
+            )
+            .scatter_add(-1, &key_positions, &middle_weights)
+            .view([bs, num_heads, num_middle_blocks * block_size, seq_len]);
+            Tensor::cat(
+                &[
+                    global_weights.narrow(2, 0, block_size),
+                    middle_weights,
+                    global_weights.narrow(2, block_size, block_size),
+                ],
+                2,
+            )
+        } else {
+            global_weights
+        };
+
+        (context, attention_weights)
+    }
+}
+
+/// Converts an attention mask of shape (*batch size*, *sequence_length*) with value 1 for tokens to attend to into
+/// an additive mask
+fn additive_mask(mask: &Tensor, kind: Kind) -> Tensor {
+    ((mask.ones_like() - mask) * -10000.0).to_kind(kind)
+}
+
+#[derive(Debug)]
+/// # BigBird attention output
+/// Linear projection of the attention context, followed by a residual connection and layer normalization
+pub struct BigBirdSelfOutput {
+    dense: nn::Linear,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl BigBirdSelfOutput {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdSelfOutput
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm =
+            nn::layer_norm(p / "LayerNorm", vec![config.hidden_size], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        BigBirdSelfOutput {
+            dense,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, input_tensor: &Tensor, train: bool) -> Tensor {
+        (input_tensor
+            + hidden_states
+                .apply(&self.dense)
+                .apply_t(&self.dropout, train))
+        .apply(&self.layer_norm)
+    }
+}
+
+#[derive(Debug)]
+/// # BigBird attention block
+/// Self-attention layer followed by its output projection
+pub struct BigBirdAttention {
+    _self: BigBirdSelfAttention,
+    output: BigBirdSelfOutput,
+}
+
+impl BigBirdAttention {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let _self = BigBirdSelfAttention::new(p / "self", config);
+        let output = BigBirdSelfOutput::new(p / "output", config);
+        BigBirdAttention { _self, output }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        mask: &Tensor,
+        block_sparse: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (self_output, attention_weights) =
+            self._self
+                .forward_t(hidden_states, mask, block_sparse, train);
+        let self_output = self.output.forward_t(&self_output, hidden_states, train);
+        (self_output, attention_weights)
+    }
+}
@@ -0,0 +1,1127 @@
+// Copyright 2021 Google Research and The HuggingFace Inc. team.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bigbird::attention::BigBirdAttention;
+use crate::common::activations::{Activation, TensorFunction};
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use std::collections::HashMap;
+use tch::nn::EmbeddingConfig;
+use tch::{nn, Kind, Tensor};
+
+/// # BigBird Pretrained model weight files
+pub struct BigBirdModelResources;
+
+/// # BigBird Pretrained model config files
+pub struct BigBirdConfigResources;
+
+/// # BigBird Pretrained model vocab files
+pub struct BigBirdVocabResources;
+
+impl BigBirdModelResources {
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/bigbird-roberta-base>.
+    pub const BIGBIRD_ROBERTA_BASE: (&'static str, &'static str) = (
+        "bigbird-roberta-base/model",
+        "https://huggingface.co/google/bigbird-roberta-base/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/bigbird-base-trivia-itc>.
+    pub const BIGBIRD_BASE_TRIVIA_ITC: (&'static str, &'static str) = (
+        "bigbird-base-trivia-itc/model",
+        "https://huggingface.co/google/bigbird-base-trivia-itc/resolve/main/model.safetensors",
+    );
+}
+
+impl BigBirdConfigResources {
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/bigbird-roberta-base>.
+    pub const BIGBIRD_ROBERTA_BASE: (&'static str, &'static str) = (
+        "bigbird-roberta-base/config",
+        "https://huggingface.co/google/bigbird-roberta-base/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/bigbird-base-trivia-itc>.
+    pub const BIGBIRD_BASE_TRIVIA_ITC: (&'static str, &'static str) = (
+        "bigbird-base-trivia-itc/config",
+        "https://huggingface.co/google/bigbird-base-trivia-itc/resolve/main/config.json",
+    );
+}
+
+impl BigBirdVocabResources {
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/bigbird-roberta-base>.
+    pub const BIGBIRD_ROBERTA_BASE: (&'static str, &'static str) = (
+        "bigbird-roberta-base/spiece",
+        "https://huggingface.co/google/bigbird-roberta-base/resolve/main/spiece.model",
+    );
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/bigbird-base-trivia-itc>.
+    pub const BIGBIRD_BASE_TRIVIA_ITC: (&'static str, &'static str) = (
+        "bigbird-base-trivia-itc/spiece",
+        "https://huggingface.co/google/bigbird-base-trivia-itc/resolve/main/spiece.model",
+    );
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, Copy, PartialEq, Eq)]
+/// # Attention pattern used by BigBird models
+pub enum BigBirdAttentionType {
+    /// Full (quadratic) self-attention
+    original_full,
+    /// Block-sparse self-attention combining global, sliding window and random blocks
+    block_sparse,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # BigBird model configuration
+/// Defines the BigBird model architecture (e.g. number of layers, hidden layer size, attention pattern, label mapping...)
+pub struct BigBirdConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub intermediate_size: i64,
+    pub hidden_act: Activation,
+    pub hidden_dropout_prob: f64,
+    pub attention_probs_dropout_prob: f64,
+    pub max_position_embeddings: i64,
+    pub type_vocab_size: i64,
+    pub layer_norm_eps: Option<f64>,
+    pub pad_token_id: Option<i64>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub sep_token_id: Option<i64>,
+    pub attention_type: BigBirdAttentionType,
+    pub use_bias: Option<bool>,
+    pub rescale_embeddings: Option<bool>,
+    pub block_size: i64,
+    pub num_random_blocks: i64,
+    pub classifier_dropout: Option<f64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+}
+
+impl Config for BigBirdConfig {}
+
+impl Default for BigBirdConfig {
+    fn default() -> Self {
+        BigBirdConfig {
+            vocab_size: 50358,
+            hidden_size: 768,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            intermediate_size: 3072,
+            hidden_act: Activation::gelu_new,
+            hidden_dropout_prob: 0.1,
+            attention_probs_dropout_prob: 0.1,
+            max_position_embeddings: 4096,
+            type_vocab_size: 2,
+            layer_norm_eps: Some(1e-12),
+            pad_token_id: Some(0),
+            bos_token_id: Some(1),
+            eos_token_id: Some(2),
+            sep_token_id: Some(66),
+            attention_type: BigBirdAttentionType::block_sparse,
+            use_bias: Some(true),
+            rescale_embeddings: Some(false),
+            block_size: 64,
+            num_random_blocks: 3,
+            classifier_dropout: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            id2label: None,
+            label2id: None,
+        }
+    }
+}
+
+impl BigBirdConfig {
+    fn get_num_labels(&self) -> Result<i64, RustBertError> {
+        Ok(self
+            .id2label
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "num_labels not provided in configuration".to_string(),
+                )
+            })?
+            .len() as i64)
+    }
+
+    /// Maximum sequence length processed with full attention when the block-sparse attention pattern is selected.
+    /// Shorter sequences would attend to all blocks with the sparse pattern, and use full attention instead.
+    pub fn max_full_attention_length(&self) -> i64 {
+        (5 + 2 * self.num_random_blocks) * self.block_size
+    }
+}
+
+fn layer_norm(p: nn::Path, config: &BigBirdConfig) -> nn::LayerNorm {
+    let layer_norm_config = nn::LayerNormConfig {
+        eps: config.layer_norm_eps.unwrap_or(1e-12),
+        ..Default::default()
+    };
+    nn::layer_norm(p, vec![config.hidden_size], layer_norm_config)
+}
+
+#[derive(Debug)]
+/// # BigBird embeddings
+/// Sum of the word, position and token type (segment) embeddings, followed by a normalization layer
+pub struct BigBirdEmbeddings {
+    word_embeddings: nn::Embedding,
+    position_embeddings: nn::Embedding,
+    token_type_embeddings: nn::Embedding,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+    scale: Option<f64>,
+}
+
+impl BigBirdEmbeddings {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embedding_config = EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(0),
+            ..Default::default()
+        };
+        let word_embeddings = nn::embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            embedding_config,
+        );
+        let position_embeddings = nn::embedding(
+            p / "position_embeddings",
+            config.max_position_embeddings,
+            config.hidden_size,
+            Default::default(),
+        );
+        let token_type_embeddings = nn::embedding(
+            p / "token_type_embeddings",
+            config.type_vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm = layer_norm(p / "LayerNorm", config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let scale = if config.rescale_embeddings.unwrap_or(false) {
+            Some((config.hidden_size as f64).sqrt())
+        } else {
+            None
+        };
+
+        BigBirdEmbeddings {
+            word_embeddings,
+            position_embeddings,
+            token_type_embeddings,
+            layer_norm,
+            dropout,
+            scale,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let input_embeddings = match self.scale {
+            Some(scale) => input_embeddings * scale,
+            None => input_embeddings.shallow_clone(),
+        };
+
+        let calc_position_ids = Tensor::arange(input_shape[1], (Kind::Int64, device))
+            .unsqueeze(0)
+            .expand(&input_shape, true);
+        let position_ids = position_ids.unwrap_or(&calc_position_ids);
+        let calc_token_type_ids = Tensor::zeros(&input_shape, (Kind::Int64, device));
+        let token_type_ids = token_type_ids.unwrap_or(&calc_token_type_ids);
+
+        let embeddings = input_embeddings
+            + position_ids.apply(&self.position_embeddings)
+            + token_type_ids.apply(&self.token_type_embeddings);
+        Ok(embeddings
+            .apply(&self.layer_norm)
+            .apply_t(&self.dropout, train))
+    }
+}
+
+/// # BigBird intermediate layer
+/// Linear layer and activation of the feed-forward block
+pub struct BigBirdIntermediate {
+    dense: nn::Linear,
+    activation: TensorFunction,
+}
+
+impl BigBirdIntermediate {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdIntermediate
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let activation = config.hidden_act.get_function();
+        BigBirdIntermediate { dense, activation }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (self.activation.get_fn())(&hidden_states.apply(&self.dense))
+    }
+}
+
+/// # BigBird output layer
+/// Output projection of the feed-forward block, followed by a residual connection and layer normalization
+pub struct BigBirdOutput {
+    dense: nn::Linear,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl BigBirdOutput {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdOutput
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense = nn::linear(
+            p / "dense",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm = layer_norm(p / "LayerNorm", config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        BigBirdOutput {
+            dense,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, input_tensor: &Tensor, train: bool) -> Tensor {
+        (input_tensor
+            + hidden_states
+                .apply(&self.dense)
+                .apply_t(&self.dropout, train))
+        .apply(&self.layer_norm)
+    }
+}
+
+/// # BigBird Layer
+/// Post-normalization transformer layer made of a (full or block-sparse) self-attention block, an intermediate
+/// layer and an output layer
+pub struct BigBirdLayer {
+    attention: BigBirdAttention,
+    intermediate: BigBirdIntermediate,
+    output: BigBirdOutput,
+}
+
+impl BigBirdLayer {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let attention = BigBirdAttention::new(p / "attention", config);
+        let intermediate = BigBirdIntermediate::new(p / "intermediate", config);
+        let output = BigBirdOutput::new(p / "output", config);
+
+        BigBirdLayer {
+            attention,
+            intermediate,
+            output,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        mask: &Tensor,
+        block_sparse: bool,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (attention_output, attention_weights) =
+            self.attention
+                .forward_t(hidden_states, mask, block_sparse, train);
+        let intermediate_output = self.intermediate.forward(&attention_output);
+        let output = self
+            .output
+            .forward_t(&intermediate_output, &attention_output, train);
+        (output, attention_weights)
+    }
+}
+
+/// # BigBird Encoder
+/// Stack of `BigBirdLayer`
+pub struct BigBirdEncoder {
+    output_attentions: bool,
+    output_hidden_states: bool,
+    layers: Vec<BigBirdLayer>,
+}
+
+impl BigBirdEncoder {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "layer";
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        let mut layers: Vec<BigBirdLayer> = vec![];
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(BigBirdLayer::new(&p / layer_index, config));
+        }
+
+        BigBirdEncoder {
+            output_attentions,
+            output_hidden_states,
+            layers,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input: &Tensor,
+        mask: &Tensor,
+        block_sparse: bool,
+        train: bool,
+    ) -> BigBirdModelOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let mut hidden_state = input.copy();
+        for layer in &self.layers {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let (layer_output, attention_weights) =
+                layer.forward_t(&hidden_state, mask, block_sparse, train);
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(hidden_state.copy());
+        };
+
+        BigBirdModelOutput {
+            hidden_state,
+            pooled_output: None,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// # BigBird pooler
+/// Linear layer and tanh activation applied to the hidden state of the first token
+pub struct BigBirdPooler {
+    dense: nn::Linear,
+}
+
+impl BigBirdPooler {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdPooler
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let dense = nn::linear(
+            p.borrow() / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        BigBirdPooler { dense }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        hidden_states.select(1, 0).apply(&self.dense).tanh()
+    }
+}
+
+/// # BigBird Base model
+/// Base architecture for BigBird models. Task-specific models will be built from this common base model.
+/// It is made of the following blocks:
+/// - `embeddings`: `token`, `position` and `segment_id` embeddings
+/// - `encoder`: Encoder (transformer) made of a vector of layers. Each layer is made of a (full or block-sparse) self-attention layer, an intermediate (linear) and output (linear + layer norm) layers
+/// - `pooler`: Optional linear layer applied to the first element of the sequence
+///
+/// With block-sparse attention, the inputs are padded to a multiple of the block size and the outputs truncated back
+/// to the input sequence length. Sequences shorter than `BigBirdConfig::max_full_attention_length` use full attention.
+pub struct BigBirdModel {
+    embeddings: BigBirdEmbeddings,
+    encoder: BigBirdEncoder,
+    pooler: Option<BigBirdPooler>,
+    attention_type: BigBirdAttentionType,
+    block_size: i64,
+    max_full_attention_length: i64,
+    pad_token_id: i64,
+}
+
+impl BigBirdModel {
+    /// Build a new `BigBirdModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BigBird model
+    /// * `config` - `BigBirdConfig` object defining the model architecture
+    /// * `add_pooling_layer` - boolean flag indicating if a pooling layer should be added after the encoder
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bigbird::{BigBirdConfig, BigBirdModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BigBirdConfig::from_file(config_path);
+    /// let bigbird = BigBirdModel::new(&p.root() / "bert", &config, true);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig, add_pooling_layer: bool) -> BigBirdModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = BigBirdEmbeddings::new(p / "embeddings", config);
+        let encoder = BigBirdEncoder::new(p / "encoder", config);
+        let pooler = if add_pooling_layer {
+            Some(BigBirdPooler::new(p / "pooler", config))
+        } else {
+            None
+        };
+
+        BigBirdModel {
+            embeddings,
+            encoder,
+            pooler,
+            attention_type: config.attention_type,
+            block_size: config.block_size,
+            max_full_attention_length: config.max_full_attention_length(),
+            pad_token_id: config.pad_token_id.unwrap_or(0),
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `BigBirdModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `pooled_output` - Optional `Tensor` of shape (*batch size*, *hidden_size*) if the model has a pooling layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::bigbird::{BigBirdConfig, BigBirdModel};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BigBirdConfig::from_file(config_path);
+    /// # let bigbird_model = BigBirdModel::new(&vs.root(), &config, true);
+    /// let (batch_size, sequence_length) = (2, 4096);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bigbird_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BigBirdModelOutput, RustBertError> {
+        let (input_shape, device) = match (input_ids, input_embeds) {
+            (Some(input_ids), None) => (input_ids.size(), input_ids.device()),
+            (None, Some(input_embeds)) => {
+                (input_embeds.size()[..2].to_vec(), input_embeds.device())
+            }
+            (Some(_), Some(_)) => {
+                return Err(RustBertError::ValueError(
+                    "Only one of input ids or input embeddings may be set".into(),
+                ));
+            }
+            (None, None) => {
+                return Err(RustBertError::ValueError(
+                    "At least one of input ids or input embeddings must be set".into(),
+                ));
+            }
+        };
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+        let calc_mask = Tensor::ones(&input_shape, (Kind::Int64, device));
+        let mask = mask.unwrap_or(&calc_mask);
+        if mask.dim() != 2 {
+            return Err(RustBertError::ValueError(
+                "Invalid attention mask dimension, must be 2".into(),
+            ));
+        }
+
+        let block_sparse = self.attention_type == BigBirdAttentionType::block_sparse
+            && sequence_length > self.max_full_attention_length;
+        let padding_length = if block_sparse {
+            (self.block_size - sequence_length % self.block_size) % self.block_size
+        } else {
+            0
+        };
+
+        let embedding_output = if padding_length > 0 {
+            let pad = |tensor: Option<&Tensor>, value: i64| {
+                tensor.map(|tensor| {
+                    Tensor::cat(
+                        &[
+                            tensor.shallow_clone(),
+                            Tensor::full(
+                                [batch_size, padding_length],
+                                value,
+                                (tensor.kind(), device),
+                            ),
+                        ],
+                        1,
+                    )
+                })
+            };
+            let input_ids = pad(input_ids, self.pad_token_id);
+            let token_type_ids = pad(token_type_ids, 0);
+            let position_ids = position_ids.map(|position_ids| {
+                let last_position = position_ids.narrow(1, sequence_length - 1, 1);
+                Tensor::cat(
+                    &[
+                        position_ids.shallow_clone(),
+                        last_position
+                            + Tensor::arange_start(1, padding_length + 1, (Kind::Int64, device)),
+                    ],
+                    1,
+                )
+            });
+            let input_embeds = match (input_embeds, input_ids.as_ref()) {
+                (Some(input_embeds), None) => {
+                    let padding_embeds = Tensor::full(
+                        [batch_size, padding_length],
+                        self.pad_token_id,
+                        (Kind::Int64, device),
+                    )
+                    .apply(&self.embeddings.word_embeddings);
+                    Some(Tensor::cat(&[input_embeds, &padding_embeds], 1))
+                }
+                _ => None,
+            };
+            self.embeddings.forward_t(
+                input_ids.as_ref(),
+                token_type_ids.as_ref(),
+                position_ids.as_ref(),
+                input_embeds.as_ref(),
+                train,
+            )?
+        } else {
+            self.embeddings.forward_t(
+                input_ids,
+                token_type_ids,
+                position_ids,
+                input_embeds,
+                train,
+            )?
+        };
+        let mask = if padding_length > 0 {
+            Tensor::cat(
+                &[
+                    mask.shallow_clone(),
+                    Tensor::zeros([batch_size, padding_length], (mask.kind(), device)),
+                ],
+                1,
+            )
+        } else {
+            mask.shallow_clone()
+        };
+
+        let mut encoder_output =
+            self.encoder
+                .forward_t(&embedding_output, &mask, block_sparse, train);
+        if padding_length > 0 {
+            encoder_output.hidden_state = encoder_output.hidden_state.narrow(1, 0, sequence_length);
+            encoder_output.all_hidden_states = encoder_output.all_hidden_states.map(|states| {
+                states
+                    .into_iter()
+                    .map(|state| state.narrow(1, 0, sequence_length))
+                    .collect()
+            });
+            encoder_output.all_attentions = encoder_output.all_attentions.map(|attentions| {
+                attentions
+                    .into_iter()
+                    .map(|attention| {
+                        attention
+                            .narrow(2, 0, sequence_length)
+                            .narrow(3, 0, sequence_length)
+                    })
+                    .collect()
+            });
+        }
+        encoder_output.pooled_output = self
+            .pooler
+            .as_ref()
+            .map(|pooler| pooler.forward(&encoder_output.hidden_state));
+
+        Ok(encoder_output)
+    }
+}
+
+/// # BigBird classification head
+/// Dense layer, activation and output projection applied to the hidden state of the first token
+pub struct BigBirdClassificationHead {
+    dense: nn::Linear,
+    activation: TensorFunction,
+    dropout: Dropout,
+    out_proj: nn::Linear,
+}
+
+impl BigBirdClassificationHead {
+    pub fn new<'p, P>(
+        p: P,
+        config: &BigBirdConfig,
+    ) -> Result<BigBirdClassificationHead, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let activation = config.hidden_act.get_function();
+        let dropout = Dropout::new(
+            config
+                .classifier_dropout
+                .unwrap_or(config.hidden_dropout_prob),
+        );
+        let out_proj = nn::linear(
+            p / "out_proj",
+            config.hidden_size,
+            config.get_num_labels()?,
+            Default::default(),
+        );
+
+        Ok(BigBirdClassificationHead {
+            dense,
+            activation,
+            dropout,
+            out_proj,
+        })
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let hidden_states = hidden_states
+            .select(1, 0)
+            .apply_t(&self.dropout, train)
+            .apply(&self.dense);
+        (self.activation.get_fn())(&hidden_states)
+            .apply_t(&self.dropout, train)
+            .apply(&self.out_proj)
+    }
+}
+
+/// # BigBird for sequence classification
+/// Base BigBird model with a classifier head to perform sentence or document-level classification
+/// It is made of the following blocks:
+/// - `bert`: Base BigBird model
+/// - `classifier`: BigBird classification head applied to the first token of the sequence
+pub struct BigBirdForSequenceClassification {
+    bert: BigBirdModel,
+    classifier: BigBirdClassificationHead,
+}
+
+impl BigBirdForSequenceClassification {
+    /// Build a new `BigBirdForSequenceClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BigBird model
+    /// * `config` - `BigBirdConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bigbird::{BigBirdConfig, BigBirdForSequenceClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BigBirdConfig::from_file(config_path);
+    /// let bigbird = BigBirdForSequenceClassification::new(&p.root(), &config).unwrap();
+    /// ```
+    pub fn new<'p, P>(
+        p: P,
+        config: &BigBirdConfig,
+    ) -> Result<BigBirdForSequenceClassification, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let bert = BigBirdModel::new(p / "bert", config, true);
+        let classifier = BigBirdClassificationHead::new(p / "classifier", config)?;
+
+        Ok(BigBirdForSequenceClassification { bert, classifier })
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `BigBirdSequenceClassificationOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::bigbird::{BigBirdConfig, BigBirdForSequenceClassification};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BigBirdConfig::from_file(config_path);
+    /// # let bigbird_model = BigBirdForSequenceClassification::new(&vs.root(), &config).unwrap();
+    /// let (batch_size, sequence_length) = (2, 4096);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bigbird_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BigBirdSequenceClassificationOutput, RustBertError> {
+        let base_model_output = self.bert.forward_t(
+            input_ids,
+            mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+
+        let logits = self
+            .classifier
+            .forward_t(&base_model_output.hidden_state, train);
+
+        Ok(BigBirdSequenceClassificationOutput {
+            logits,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+/// # BigBird question answering head
+/// Feed-forward block (intermediate and output layers with a residual connection) followed by a linear layer
+/// predicting the start and end logits
+pub struct BigBirdQuestionAnsweringHead {
+    dropout: Dropout,
+    intermediate: BigBirdIntermediate,
+    output: BigBirdOutput,
+    qa_outputs: nn::Linear,
+}
+
+impl BigBirdQuestionAnsweringHead {
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdQuestionAnsweringHead
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let intermediate = BigBirdIntermediate::new(p / "intermediate", config);
+        let output = BigBirdOutput::new(p / "output", config);
+        let qa_outputs = nn::linear(p / "qa_outputs", config.hidden_size, 2, Default::default());
+
+        BigBirdQuestionAnsweringHead {
+            dropout,
+            intermediate,
+            output,
+            qa_outputs,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let intermediate_output = self
+            .intermediate
+            .forward(&hidden_states.apply_t(&self.dropout, train));
+        self.output
+            .forward_t(&intermediate_output, hidden_states, train)
+            .apply(&self.qa_outputs)
+    }
+}
+
+/// # BigBird for question answering
+/// Extractive question-answering model based on a BigBird language model. Identifies the segment of a context that answers a provided question.
+/// The logits of the question tokens (up to the first separator token, except for the first token) are masked, so that answers are only extracted from the context.
+/// It is made of the following blocks:
+/// - `bert`: Base BigBird model
+/// - `qa_classifier`: BigBird question answering head
+pub struct BigBirdForQuestionAnswering {
+    bert: BigBirdModel,
+    qa_classifier: BigBirdQuestionAnsweringHead,
+    sep_token_id: i64,
+}
+
+impl BigBirdForQuestionAnswering {
+    /// Build a new `BigBirdForQuestionAnswering`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BigBird model
+    /// * `config` - `BigBirdConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bigbird::{BigBirdConfig, BigBirdForQuestionAnswering};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BigBirdConfig::from_file(config_path);
+    /// let bigbird = BigBirdForQuestionAnswering::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BigBirdConfig) -> BigBirdForQuestionAnswering
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let bert = BigBirdModel::new(p / "bert", config, false);
+        let qa_classifier = BigBirdQuestionAnsweringHead::new(p / "qa_classifier", config);
+
+        BigBirdForQuestionAnswering {
+            bert,
+            qa_classifier,
+            sep_token_id: config.sep_token_id.unwrap_or(66),
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None, set to 0 for the question tokens and 1 for the context tokens when `input_ids` are provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `BigBirdQuestionAnsweringOutput` containing:
+    ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
+    ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::bigbird::{BigBirdConfig, BigBirdForQuestionAnswering};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BigBirdConfig::from_file(config_path);
+    /// # let bigbird_model = BigBirdForQuestionAnswering::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (2, 4096);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bigbird_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BigBirdQuestionAnsweringOutput, RustBertError> {
+        // Question tokens span up to (and including) the first separator token
+        let (question_mask, logits_mask) = match input_ids {
+            Some(input_ids) => {
+                let question_lengths = input_ids
+                    .eq(self.sep_token_id)
+                    .to_kind(Kind::Int64)
+                    .argmax(-1, true)
+                    + 1;
+                let positions =
+                    Tensor::arange(input_ids.size()[1], (Kind::Int64, input_ids.device()))
+                        .unsqueeze(0);
+                let question_mask = positions.lt_tensor(&question_lengths);
+                // The first token is not masked and can be used to predict that there is no answer
+                let logits_mask = question_mask.logical_and(&positions.gt(0));
+                (Some(question_mask), Some(logits_mask))
+            }
+            None => (None, None),
+        };
+        let calc_token_type_ids = match (token_type_ids, question_mask.as_ref()) {
+            (None, Some(question_mask)) => Some(question_mask.logical_not().to_kind(Kind::Int64)),
+            _ => None,
+        };
+        let token_type_ids = token_type_ids.or(calc_token_type_ids.as_ref());
+
+        let base_model_output = self.bert.forward_t(
+            input_ids,
+            mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+
+        let logits = self
+            .qa_classifier
+            .forward_t(&base_model_output.hidden_state, train);
+        let logits = match logits_mask {
+            Some(logits_mask) => logits.masked_fill(&logits_mask.unsqueeze(-1), -1e6),
+            None => logits,
+        };
+        let logits = logits.split(1, -1);
+        let (start_logits, end_logits) = (&logits[0], &logits[1]);
+
+        Ok(BigBirdQuestionAnsweringOutput {
+            start_logits: start_logits.squeeze_dim(-1),
+            end_logits: end_logits.squeeze_dim(-1),
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+/// Container for the BigBird model output.
+pub struct BigBirdModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Pooled output (hidden state of the first token after the pooling layer)
+    pub pooled_output: Option<Tensor>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the BigBird sequence classification model output.
+pub struct BigBirdSequenceClassificationOutput {
+    /// Logits for each input (sequence) for each target class
+    pub logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the BigBird question answering model output.
+pub struct BigBirdQuestionAnsweringOutput {
+    /// Logits for the start position for token of each input sequence
+    pub start_logits: Tensor,
+    /// Logits for the end position for token of each input sequence
+    pub end_logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
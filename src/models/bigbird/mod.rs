@@ -0,0 +1,64 @@
+//! # BigBird (Zaheer et al.)
+//!
+//! Implementation of the BigBird language model ([Big Bird: Transformers for Longer Sequences](https://arxiv.org/abs/2007.14062) Zaheer, Guruganesh, Dubey, Ainslie, Alberti, Ontanon, Pham, Ravula, Wang, Yang, Ahmed, 2020).
+//! BigBird replaces the full self-attention of BERT by a block-sparse attention pattern combining global, sliding window and random blocks,
+//! with a cost growing linearly with the sequence length. This allows processing sequences of up to 4096 tokens.
+//! Sequences shorter than `BigBirdConfig::max_full_attention_length` (704 tokens for the default configuration) are processed with full attention.
+//! The base model is implemented in the `bigbird_model::BigBirdModel` struct. Several language model heads have also been implemented, including:
+//! - Sequence classification: `bigbird_model::BigBirdForSequenceClassification`
+//! - Question answering: `bigbird_model::BigBirdForQuestionAnswering`
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). The pretrained safetensors checkpoints are loaded directly without conversion. Alternatively, the Python utility scripts convert the `.bin` weights to the `.ot` format.
+//! - `AlbertTokenizer` using a `spiece.model` SentencePiece model file
+//! Pretrained models are available and can be downloaded using RemoteResources.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::bigbird::{BigBirdConfig, BigBirdForQuestionAnswering};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::AlbertTokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/spiece.model"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: AlbertTokenizer =
+//!     AlbertTokenizer::from_file(vocab_path.to_str().unwrap(), false, false)?;
+//! let config = BigBirdConfig::from_file(config_path);
+//! let bigbird_model = BigBirdForQuestionAnswering::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod bigbird_model;
+
+pub use attention::{BigBirdAttention, BigBirdSelfAttention, BigBirdSelfOutput};
+pub use bigbird_model::{
+    BigBirdAttentionType, BigBirdClassificationHead, BigBirdConfig, BigBirdConfigResources,
+    BigBirdEmbeddings, BigBirdEncoder, BigBirdForQuestionAnswering,
+    BigBirdForSequenceClassification, BigBirdIntermediate, BigBirdLayer, BigBirdModel,
+    BigBirdModelOutput, BigBirdModelResources, BigBirdOutput, BigBirdPooler,
+    BigBirdQuestionAnsweringHead, BigBirdQuestionAnsweringOutput,
+    BigBirdSequenceClassificationOutput, BigBirdVocabResources,
+};
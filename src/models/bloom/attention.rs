@@ -0,0 +1,180 @@
+// Copyright 2022 the BigScience Workshop and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bloom::bloom_model::BloomConfig;
+use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for BLOOM attention layers
+/// Stores the cached value of key and value
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self.prev_value.narrow(-2, 0, length),
+        }
+    }
+}
+
+/// Builds the boolean mask of the future key positions each query may not attend to, of shape
+/// (*query_length*, *key_length*). Queries are assumed to be the last `query_length` positions of the keys.
+pub(crate) fn build_causal_mask(query_length: i64, key_length: i64, device: Device) -> Tensor {
+    let query_positions =
+        Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+            .unsqueeze(-1);
+    let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+    (query_positions - key_positions).lt(0)
+}
+
+/// # BLOOM self-attention
+/// Causal multi-head attention with a fused query, key and value projection. BLOOM does not use position embeddings:
+/// ALiBi biases, proportional to the distance between the query and key positions, are added to the attention scores.
+pub struct BloomAttention {
+    query_key_value: QuantizableLinear,
+    dense: QuantizableLinear,
+    attention_dropout: Dropout,
+    num_heads: i64,
+    head_dim: i64,
+    use_cache: bool,
+    output_attentions: bool,
+}
+
+impl BloomAttention {
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let query_key_value = nn::linear(
+            p / "query_key_value",
+            config.hidden_size,
+            3 * config.hidden_size,
+            Default::default(),
+        );
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let attention_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+
+        BloomAttention {
+            query_key_value: query_key_value.into(),
+            dense: dense.into(),
+            attention_dropout,
+            num_heads: config.n_head,
+            head_dim: config.hidden_size / config.n_head,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.query_key_value.quantize_int4(config)?;
+        self.dense.quantize_int4(config)
+    }
+
+    /// Splits the fused projection (interleaved query, key and value for each head) into query, key and value
+    /// tensors of shape (*batch size*, *num heads*, *sequence_length*, *head_dim*)
+    fn split_heads(&self, fused_qkv: &Tensor) -> (Tensor, Tensor, Tensor) {
+        let (batch_size, sequence_length, _) = fused_qkv.size3().unwrap();
+        let qkv = fused_qkv.view([
+            batch_size,
+            sequence_length,
+            self.num_heads,
+            3,
+            self.head_dim,
+        ]);
+        (
+            qkv.select(3, 0).transpose(1, 2),
+            qkv.select(3, 1).transpose(1, 2),
+            qkv.select(3, 2).transpose(1, 2),
+        )
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        alibi: &Tensor,
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (batch_size, sequence_length, _) = hidden_states.size3().unwrap();
+
+        let (query, mut key, mut value) =
+            self.split_heads(&hidden_states.apply(&self.query_key_value));
+
+        if let Some(layer_past) = layer_past {
+            key = Tensor::cat(&[&layer_past.prev_key, &key], -2);
+            value = Tensor::cat(&[&layer_past.prev_value, &value], -2);
+        }
+
+        let present = self.use_cache.then(|| LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        });
+
+        let mut attention_scores = attention_scores(&query, &key.transpose(-1, -2))
+            / (self.head_dim as f64).sqrt()
+            + alibi;
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores + attention_mask;
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_output(&attention_weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.dense);
+
+        let attention_weights = self.output_attentions.then_some(attention_weights);
+
+        (attention_output, present, attention_weights)
+    }
+}
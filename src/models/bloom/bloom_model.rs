@@ -0,0 +1,728 @@
+// Copyright 2022 the BigScience Workshop and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bloom::attention::{build_causal_mask, LayerState};
+use crate::bloom::transformer::BloomBlock;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::kind::get_min;
+use crate::common::position_embeddings::AlibiBias;
+use crate::common::quantization::Int4QuantizationConfig;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, LanguageGenerator};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Device, Tensor};
+
+/// # BLOOM Pretrained model weight files
+pub struct BloomModelResources;
+
+/// # BLOOM Pretrained model config files
+pub struct BloomConfigResources;
+
+/// # BLOOM Pretrained model vocab files
+pub struct BloomVocabResources;
+
+/// # BLOOM Pretrained model special tokens map files
+pub struct BloomSpecialMap;
+
+impl BloomModelResources {
+    /// Shared under BigScience RAIL License v1.0 by the BigScience Workshop at <https://huggingface.co/bigscience/bloomz-560m>.
+    pub const BLOOMZ_560M: (&'static str, &'static str) = (
+        "bloomz-560m/model",
+        "https://huggingface.co/bigscience/bloomz-560m/resolve/main/model.safetensors",
+    );
+}
+
+impl BloomConfigResources {
+    /// Shared under BigScience RAIL License v1.0 by the BigScience Workshop at <https://huggingface.co/bigscience/bloomz-560m>.
+    pub const BLOOMZ_560M: (&'static str, &'static str) = (
+        "bloomz-560m/config",
+        "https://huggingface.co/bigscience/bloomz-560m/resolve/main/config.json",
+    );
+}
+
+impl BloomVocabResources {
+    /// Shared under BigScience RAIL License v1.0 by the BigScience Workshop at <https://huggingface.co/bigscience/bloomz-560m>.
+    pub const BLOOMZ_560M: (&'static str, &'static str) = (
+        "bloomz-560m/tokenizer",
+        "https://huggingface.co/bigscience/bloomz-560m/resolve/main/tokenizer.json",
+    );
+}
+
+impl BloomSpecialMap {
+    /// Shared under BigScience RAIL License v1.0 by the BigScience Workshop at <https://huggingface.co/bigscience/bloomz-560m>.
+    pub const BLOOMZ_560M: (&'static str, &'static str) = (
+        "bloomz-560m/special",
+        "https://huggingface.co/bigscience/bloomz-560m/resolve/main/special_tokens_map.json",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # BLOOM model configuration
+/// Defines the BLOOM model architecture (e.g. number of layers, hidden layer size, number of attention heads...)
+pub struct BloomConfig {
+    pub vocab_size: i64,
+    #[serde(alias = "n_embed")]
+    pub hidden_size: i64,
+    #[serde(alias = "num_hidden_layers")]
+    pub n_layer: i64,
+    #[serde(alias = "num_attention_heads")]
+    pub n_head: i64,
+    #[serde(alias = "seq_length")]
+    pub max_position_embeddings: Option<i64>,
+    pub layer_norm_epsilon: Option<f64>,
+    pub initializer_range: Option<f64>,
+    pub apply_residual_connection_post_layernorm: Option<bool>,
+    pub hidden_dropout: Option<f64>,
+    pub attention_dropout: Option<f64>,
+    pub use_cache: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    pub forced_bos_token_id: Option<i64>,
+    pub forced_eos_token_id: Option<i64>,
+}
+
+impl Config for BloomConfig {}
+
+impl Default for BloomConfig {
+    fn default() -> Self {
+        BloomConfig {
+            vocab_size: 250880,
+            hidden_size: 64,
+            n_layer: 2,
+            n_head: 8,
+            max_position_embeddings: None,
+            layer_norm_epsilon: Some(1e-5),
+            initializer_range: Some(0.02),
+            apply_residual_connection_post_layernorm: Some(false),
+            hidden_dropout: Some(0.0),
+            attention_dropout: Some(0.0),
+            use_cache: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            bos_token_id: Some(1),
+            eos_token_id: Some(2),
+            pad_token_id: Some(3),
+            decoder_start_token_id: None,
+            forced_bos_token_id: None,
+            forced_eos_token_id: None,
+        }
+    }
+}
+
+/// # BLOOM Base model
+/// Base architecture for BLOOM models. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `word_embeddings`: `token` embeddings, followed by a layer normalization (`word_embeddings_layernorm`)
+/// - `h`: Decoder made of a vector of blocks. Each block is made of a multi-head attention layer with ALiBi biases,
+///   layer normalizations and an MLP.
+/// - `ln_f`: Final layer normalization
+pub struct BloomModel {
+    word_embeddings: nn::Embedding,
+    word_embeddings_layernorm: nn::LayerNorm,
+    h: Vec<BloomBlock>,
+    ln_f: nn::LayerNorm,
+    alibi: AlibiBias,
+    use_cache: bool,
+    output_hidden_states: bool,
+    output_attentions: bool,
+}
+
+impl BloomModel {
+    /// Build a new `BloomModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BLOOM model
+    /// * `config` - `BloomConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bloom::{BloomConfig, BloomModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BloomConfig::from_file(config_path);
+    /// let bloom: BloomModel = BloomModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "transformer";
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon.unwrap_or(1e-5),
+            ..Default::default()
+        };
+
+        let word_embeddings = embedding(
+            &p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let word_embeddings_layernorm = nn::layer_norm(
+            &p / "word_embeddings_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        let mut h: Vec<BloomBlock> = vec![];
+        let layers_path = &p / "h";
+        for layer_index in 0..config.n_layer {
+            h.push(BloomBlock::new(&layers_path / layer_index, config));
+        }
+
+        let ln_f = nn::layer_norm(&p / "ln_f", vec![config.hidden_size], layer_norm_config);
+
+        BloomModel {
+            word_embeddings,
+            word_embeddings_layernorm,
+            h,
+            ln_f,
+            alibi: AlibiBias::new(config.n_head),
+            use_cache: config.use_cache.unwrap_or(true),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the decoder blocks to int4 in place.
+    /// The embeddings and normalization layers are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.h.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - Optional vector of length *n_layer* containing the past keys and values of each layer of shape (*batch size*, *number of heads*, *past_sequence_length*, *hidden size per head*). When provided, these are concatenated with the current input keys and values.
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `BloomModelOutput` containing:
+    ///   - `output` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* with shape (*batch size*, *number of heads*, *sequence_length*, *past_sequence_length + sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::bloom::{BloomConfig, BloomModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BloomConfig::from_file(config_path);
+    /// # let bloom_model: BloomModel = BloomModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bloom_model
+    ///         .forward_t(Some(&input_tensor), None, Some(&attention_mask), None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BloomModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+
+        let (layer_past, past_length) = match layer_past {
+            Some(value) => {
+                if value.len() != self.h.len() {
+                    return Err(RustBertError::ValueError(format!(
+                        "Past activations vector length ({}) must be equal to the number of layers ({})",
+                        value.len(),
+                        self.h.len()
+                    )));
+                } else {
+                    let past_length = value
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|layer_state| layer_state.prev_key.size()[2])
+                        .unwrap_or(0);
+                    (value, past_length)
+                }
+            }
+            None => {
+                let mut out = Vec::with_capacity(self.h.len());
+                out.resize_with(self.h.len(), || None);
+                (out, 0)
+            }
+        };
+        let key_length = past_length + sequence_length;
+
+        let mut masked_positions = build_causal_mask(sequence_length, key_length, device).view([
+            1,
+            1,
+            sequence_length,
+            key_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            masked_positions =
+                masked_positions.logical_or(&attention_mask.view([batch_size, 1, 1, -1]).eq(0));
+        }
+        let kind = input_embeddings.kind();
+        let attention_mask = Tensor::zeros(masked_positions.size(), (kind, device))
+            .masked_fill(&masked_positions, get_min(kind)?);
+        let alibi = self
+            .alibi
+            .forward(sequence_length, key_length, device)
+            .to_kind(kind);
+
+        let mut hidden_state = input_embeddings.apply(&self.word_embeddings_layernorm);
+
+        let mut all_presents: Option<Vec<Option<LayerState>>> = self.use_cache.then(Vec::new);
+        let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
+        let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
+
+        for (layer, past) in self.h.iter().zip(layer_past) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let temp = layer.forward_t(
+                &hidden_state,
+                Some(&attention_mask),
+                &alibi,
+                past.as_ref(),
+                train,
+            );
+            hidden_state = temp.0;
+            if let Some(presents) = all_presents.borrow_mut() {
+                presents.push(temp.1);
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(temp.2.unwrap());
+            };
+        }
+
+        let output = hidden_state.apply(&self.ln_f);
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(output.copy());
+        };
+
+        Ok(BloomModelOutput {
+            output,
+            cache: all_presents,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # BLOOM Language Modeling head
+/// BLOOM model with a decoding head (linear layer without bias). The weights of the linear layer are tied to the word embeddings.
+/// It is made of the following blocks:
+/// - `transformer`: Base BloomModel
+pub struct BloomForCausalLM {
+    transformer: BloomModel,
+}
+
+impl BloomForCausalLM {
+    /// Build a new `BloomForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BLOOM model
+    /// * `config` - `BloomConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bloom::{BloomConfig, BloomForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BloomConfig::from_file(config_path);
+    /// let bloom: BloomForCausalLM = BloomForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let transformer = BloomModel::new(p, config);
+
+        BloomForCausalLM { transformer }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place (see `BloomModel::quantize_int4`).
+    /// The tied language model head is kept in its original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.transformer.quantize_int4(config)
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - `Cache` containing the past keys and values of each layer (`Cache::BloomCache` or `Cache::None`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `cache` - `Cache::BloomCache` containing the past keys and values of each layer
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::BloomCache(layer_past) => self.transformer.forward_t(
+                input_ids,
+                layer_past,
+                attention_mask,
+                input_embeds,
+                train,
+            ),
+            Cache::None => {
+                self.transformer
+                    .forward_t(input_ids, None, attention_mask, input_embeds, train)
+            }
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with BLOOM Model".into(),
+                ));
+            }
+        }?;
+
+        let lm_logits = base_model_output
+            .output
+            .linear::<Tensor>(&self.transformer.word_embeddings.ws, None);
+
+        Ok(LMModelOutput {
+            lm_logits,
+            cache: Cache::BloomCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
+        })
+    }
+}
+
+/// Container for the BLOOM model output.
+pub struct BloomModelOutput {
+    /// Hidden state of the last layer of the decoder
+    pub output: Tensor,
+    /// Cached attention layers keys and values if the model is used for generation
+    pub cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the BLOOM architecture (including BLOOMZ)
+pub struct BloomGenerator {
+    model: BloomForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: Option<i64>,
+}
+
+impl BloomGenerator {
+    /// Build a new `BloomGenerator`. BLOOM tokenizers are not available from the `rust_tokenizers` crate: the
+    /// tokenizer should be loaded from a `tokenizer.json` file (see `TokenizerOption::from_hf_tokenizer_file`)
+    /// and the generator created with `new_with_tokenizer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    pub fn new(generate_config: GenerateConfig) -> Result<BloomGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bloom,
+            vocab_path.to_str().unwrap(),
+            None,
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    /// Build a new `BloomGenerator` with a tokenizer
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer, usually loaded from a `tokenizer.json` file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::bloom::{
+    ///     BloomConfigResources, BloomGenerator, BloomSpecialMap, BloomVocabResources,
+    /// };
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::{LocalResource, RemoteResource, ResourceProvider};
+    /// use std::path::PathBuf;
+    ///
+    /// let vocab_resource = RemoteResource::from_pretrained(BloomVocabResources::BLOOMZ_560M);
+    /// let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+    ///     vocab_resource.get_local_path()?,
+    ///     RemoteResource::from_pretrained(BloomSpecialMap::BLOOMZ_560M).get_local_path()?,
+    /// )?;
+    /// let generate_config = GenerateConfig {
+    ///     model_type: ModelType::Bloom,
+    ///     model_resource: ModelResource::Torch(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     })),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(
+    ///         BloomConfigResources::BLOOMZ_560M,
+    ///     )),
+    ///     vocab_resource: Box::new(vocab_resource),
+    ///     merges_resource: None,
+    ///     max_length: Some(128),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..Default::default()
+    /// };
+    /// let bloom_generator = BloomGenerator::new_with_tokenizer(generate_config, tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<BloomGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate()?;
+        let mut var_store = nn::VarStore::new(device);
+
+        let config = BloomConfig::from_file(config_path);
+        let model = BloomForCausalLM::new(var_store.root(), &config);
+        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+
+        let bos_token_id = tokenizer.get_bos_id().or(config.bos_token_id);
+        let eos_token_ids = tokenizer
+            .get_eos_id()
+            .or(config.eos_token_id)
+            .map(|id| vec![id]);
+        let pad_token_id = tokenizer.get_pad_id().or(config.pad_token_id);
+        let max_position_embeddings = config.max_position_embeddings;
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = config.decoder_start_token_id;
+
+        Ok(BloomGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+
+    /// Quantize the weights of the model to int4 in place (see `BloomForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+}
+
+impl PrivateLanguageGenerator for BloomGenerator {
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn _get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        &mut self.tokenizer
+    }
+    fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+    fn get_var_store_mut(&mut self) -> Result<&mut nn::VarStore, RustBertError> {
+        Ok(&mut self.var_store)
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> Option<i64> {
+        self.max_position_embeddings
+    }
+
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        _position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        self.model
+            .forward_t(input_ids, layer_past, attention_mask, input_embeds, train)
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        match past {
+            Cache::BloomCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: None,
+                        prepared_past: Cache::BloomCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: None,
+                        prepared_past: Cache::BloomCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: None,
+                prepared_past: Cache::BloomCache(None),
+            },
+            _ => panic!("Cache type incompatible with BLOOM"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::BloomCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for BLOOM model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator for BloomGenerator {}
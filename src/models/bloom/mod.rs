@@ -0,0 +1,66 @@
+//! # BLOOM (BigScience Workshop)
+//!
+//! Implementation of the BLOOM decoder architecture ([BLOOM: A 176B-Parameter Open-Access Multilingual Language Model](https://arxiv.org/abs/2211.05100) BigScience Workshop, 2022).
+//! BLOOM is a decoder-only model trained on 46 natural languages and 13 programming languages. It does not use position
+//! embeddings: ALiBi biases ([Press et al., 2021](https://arxiv.org/abs/2108.12409)) proportional to the distance between tokens are
+//! added to the attention scores, and the word embeddings are followed by a layer normalization. BLOOMZ checkpoints
+//! ([Muennighoff et al., 2022](https://arxiv.org/abs/2211.01786)) are fine-tuned versions following instructions, sharing the architecture.
+//! The base model is implemented in the `bloom_model::BloomModel` struct, the language modeling head in `bloom_model::BloomForCausalLM`
+//! and the text generation utilities in `bloom_model::BloomGenerator`, also available through the `TextGenerationModel` and
+//! `ConversationModel` pipelines with `ModelType::Bloom`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights to the `.ot` format, for example with `python utils/convert_model.py path/to/bloom/model.safetensors`.
+//! - The BPE tokenizer should be loaded from a `tokenizer.json` file and a special tokens map using the `hf-tokenizers` feature
+//!   (see `TokenizerOption::from_hf_tokenizer_file`).
+//!
+//! Pretrained resources for BLOOMZ-560m are available in `BloomModelResources`, `BloomConfigResources`,
+//! `BloomVocabResources` and `BloomSpecialMap`.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::bloom::{BloomConfig, BloomForCausalLM};
+//! use rust_bert::pipelines::common::TokenizerOption;
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+//!     "path/to/tokenizer.json",
+//!     "path/to/special_tokens_map.json",
+//! )?;
+//! let config = BloomConfig::from_file(config_path);
+//! let bloom_model = BloomForCausalLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod bloom_model;
+mod transformer;
+
+pub use attention::{BloomAttention, LayerState};
+pub use bloom_model::{
+    BloomConfig, BloomConfigResources, BloomForCausalLM, BloomGenerator, BloomModel,
+    BloomModelOutput, BloomModelResources, BloomSpecialMap, BloomVocabResources,
+};
+pub use transformer::{BloomBlock, BloomMLP};
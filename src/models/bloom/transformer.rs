@@ -0,0 +1,164 @@
+// Copyright 2022 the BigScience Workshop and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bloom::attention::{BloomAttention, LayerState};
+use crate::bloom::bloom_model::BloomConfig;
+use crate::common::activations::{Activation, TensorFunction};
+use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+/// # BLOOM feed-forward block
+/// Two-layer MLP with a tanh approximation of the GELU activation: `dense_4h_to_h(gelu(dense_h_to_4h(x)))`
+pub struct BloomMLP {
+    dense_h_to_4h: QuantizableLinear,
+    dense_4h_to_h: QuantizableLinear,
+    activation: TensorFunction,
+}
+
+impl BloomMLP {
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense_h_to_4h = nn::linear(
+            p / "dense_h_to_4h",
+            config.hidden_size,
+            4 * config.hidden_size,
+            Default::default(),
+        );
+        let dense_4h_to_h = nn::linear(
+            p / "dense_4h_to_h",
+            4 * config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        BloomMLP {
+            dense_h_to_4h: dense_h_to_4h.into(),
+            dense_4h_to_h: dense_4h_to_h.into(),
+            activation: Activation::gelu_new.get_function(),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.dense_h_to_4h.quantize_int4(config)?;
+        self.dense_4h_to_h.quantize_int4(config)
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (self.activation.get_fn())(&hidden_states.apply(&self.dense_h_to_4h))
+            .apply(&self.dense_4h_to_h)
+    }
+}
+
+/// # BLOOM decoder block
+/// Pre-normalization layer made of a self-attention block and an MLP. The residual connections start from the
+/// normalized hidden states if `apply_residual_connection_post_layernorm` is set in the configuration.
+pub struct BloomBlock {
+    input_layernorm: nn::LayerNorm,
+    self_attention: BloomAttention,
+    post_attention_layernorm: nn::LayerNorm,
+    mlp: BloomMLP,
+    hidden_dropout: Dropout,
+    residual_post_layernorm: bool,
+}
+
+impl BloomBlock {
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomBlock
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let input_layernorm = nn::layer_norm(
+            p / "input_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let self_attention = BloomAttention::new(p / "self_attention", config);
+        let post_attention_layernorm = nn::layer_norm(
+            p / "post_attention_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let mlp = BloomMLP::new(p / "mlp", config);
+        let hidden_dropout = Dropout::new(config.hidden_dropout.unwrap_or(0.0));
+
+        BloomBlock {
+            input_layernorm,
+            self_attention,
+            post_attention_layernorm,
+            mlp,
+            hidden_dropout,
+            residual_post_layernorm: config
+                .apply_residual_connection_post_layernorm
+                .unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attention.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        alibi: &Tensor,
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let layernorm_output = hidden_states.apply(&self.input_layernorm);
+        let (attention_output, present, attention_weights) = self.self_attention.forward_t(
+            &layernorm_output,
+            attention_mask,
+            alibi,
+            layer_past,
+            train,
+        );
+        let residual = if self.residual_post_layernorm {
+            &layernorm_output
+        } else {
+            hidden_states
+        };
+        let hidden_states = residual + attention_output.apply_t(&self.hidden_dropout, train);
+
+        let layernorm_output = hidden_states.apply(&self.post_attention_layernorm);
+        let mlp_output = self
+            .mlp
+            .forward(&layernorm_output)
+            .apply_t(&self.hidden_dropout, train);
+        let hidden_states = if self.residual_post_layernorm {
+            layernorm_output + mlp_output
+        } else {
+            hidden_states + mlp_output
+        };
+
+        (hidden_states, present, attention_weights)
+    }
+}
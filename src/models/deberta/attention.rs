@@ -11,6 +11,7 @@
 // limitations under the License.
 
 use crate::common::dropout::XDropout;
+use crate::common::precision::{attention_output, attention_scores};
 use crate::deberta::deberta_model::{x_softmax, PositionAttentionType, PositionAttentionTypes};
 use crate::deberta::{BaseDebertaLayerNorm, DebertaConfig};
 use crate::RustBertError;
@@ -393,7 +394,7 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
         let scale_factor = 1.0 + self.pos_att_type.len() as f64;
         let scale = (*query_layer.size().last().unwrap() as f64 * scale_factor).sqrt();
         let query_layer = query_layer / scale;
-        let mut attention_scores = query_layer.matmul(&key_layer.transpose(-1, -2));
+        let mut attention_scores = attention_scores(&query_layer, &key_layer.transpose(-1, -2));
 
         if let Some(relative_embeddings) = relative_embeddings {
             let relative_embeddings =
@@ -425,8 +426,7 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
                 .permute([0, 3, 1, 2]);
         }
 
-        let context_layer = attention_probs
-            .matmul(&value_layer)
+        let context_layer = attention_output(&attention_probs, &value_layer)
             .permute([0, 2, 1, 3])
             .contiguous();
 
@@ -17,6 +17,7 @@ use crate::common::activations::TensorFunction;
 use crate::common::dropout::{Dropout, XDropout};
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
 use crate::common::kind::get_min;
+use crate::common::weight_tying::TiedWeights;
 use crate::deberta::embeddings::DebertaEmbeddings;
 use crate::deberta::encoder::{DebertaEncoder, DebertaEncoderOutput};
 use crate::{Activation, Config, RustBertError};
@@ -543,6 +544,13 @@ pub struct DebertaForMaskedLM {
 }
 
 impl DebertaForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "deberta.embeddings.word_embeddings.weight",
+        "cls.predictions.decoder.weight",
+    )
+    .with_bias("cls.predictions.decoder.bias", "cls.predictions.bias");
+
     /// Build a new `DebertaForMaskedLM`
     ///
     /// # Arguments
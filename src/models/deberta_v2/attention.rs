@@ -11,6 +11,7 @@
 // limitations under the License.
 
 use crate::common::dropout::XDropout;
+use crate::common::precision::{attention_output, attention_scores};
 use crate::deberta::{
     x_softmax, DebertaConfig, DisentangledSelfAttention, PositionAttentionType,
     PositionAttentionTypes,
@@ -357,7 +358,8 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
             scale_factor += 1;
         }
         let scale = ((query_layer.size().last().unwrap() * scale_factor) as f64).sqrt();
-        let mut attention_scores = query_layer.bmm(&key_layer.transpose(-1, -2)) / scale;
+        let mut attention_scores =
+            attention_scores(&query_layer, &key_layer.transpose(-1, -2)) / scale;
 
         if let (Some(pos_dropout), Some(rel_embeddings)) = (&self.pos_dropout, relative_embeddings)
         {
@@ -385,13 +387,14 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
 
         let mut reverse_attention_probs_size = attention_probs.size();
         reverse_attention_probs_size.reverse();
-        let context_layer = attention_probs
-            .view([
+        let context_layer = attention_output(
+            &attention_probs.view([
                 -1,
                 reverse_attention_probs_size[1],
                 reverse_attention_probs_size[0],
-            ])
-            .bmm(&value_layer);
+            ]),
+            &value_layer,
+        );
 
         let mut reverse_context_layer_size = context_layer.size();
         reverse_context_layer_size.reverse();
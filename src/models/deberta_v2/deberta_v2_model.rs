@@ -12,6 +12,7 @@
 
 use crate::common::dropout::{Dropout, XDropout};
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::weight_tying::TiedWeights;
 use crate::deberta::{
     deserialize_attention_type, ContextPooler, DebertaConfig, DebertaLMPredictionHead,
     DebertaMaskedLMOutput, DebertaModelOutput, DebertaQuestionAnsweringOutput,
@@ -43,6 +44,11 @@ impl DebertaV2ModelResources {
         "deberta-v3-base/model",
         "https://huggingface.co/microsoft/deberta-v3-base/resolve/main/rust_model.ot",
     );
+    /// Shared under Apache 2.0 license by Protect AI at <https://huggingface.co/protectai/deberta-v3-base-prompt-injection-v2>.
+    pub const DEBERTA_V3_BASE_PROMPT_INJECTION: (&'static str, &'static str) = (
+        "deberta-v3-base-prompt-injection-v2/model",
+        "https://huggingface.co/protectai/deberta-v3-base-prompt-injection-v2/resolve/main/model.safetensors",
+    );
 }
 
 impl DebertaV2ConfigResources {
@@ -51,6 +57,11 @@ impl DebertaV2ConfigResources {
         "deberta-v3-base/config",
         "https://huggingface.co/microsoft/deberta-v3-base/resolve/main/config.json",
     );
+    /// Shared under Apache 2.0 license by Protect AI at <https://huggingface.co/protectai/deberta-v3-base-prompt-injection-v2>.
+    pub const DEBERTA_V3_BASE_PROMPT_INJECTION: (&'static str, &'static str) = (
+        "deberta-v3-base-prompt-injection-v2/config",
+        "https://huggingface.co/protectai/deberta-v3-base-prompt-injection-v2/resolve/main/config.json",
+    );
 }
 
 impl DebertaV2VocabResources {
@@ -59,6 +70,11 @@ impl DebertaV2VocabResources {
         "deberta-v3-base/vocab",
         "https://huggingface.co/microsoft/deberta-v3-base/resolve/main/spm.model",
     );
+    /// Shared under Apache 2.0 license by Protect AI at <https://huggingface.co/protectai/deberta-v3-base-prompt-injection-v2>.
+    pub const DEBERTA_V3_BASE_PROMPT_INJECTION: (&'static str, &'static str) = (
+        "deberta-v3-base-prompt-injection-v2/vocab",
+        "https://huggingface.co/protectai/deberta-v3-base-prompt-injection-v2/resolve/main/spm.model",
+    );
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -452,6 +468,13 @@ pub struct DebertaV2ForMaskedLM {
 }
 
 impl DebertaV2ForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "deberta.embeddings.word_embeddings.weight",
+        "cls.predictions.decoder.weight",
+    )
+    .with_bias("cls.predictions.decoder.bias", "cls.predictions.bias");
+
     /// Build a new `DebertaV2ForMaskedLM`
     ///
     /// # Arguments
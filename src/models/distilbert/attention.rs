@@ -11,7 +11,10 @@
 // limitations under the License.
 
 use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::common::quantization::QuantizableLinear;
 use crate::distilbert::distilbert_model::DistilBertConfig;
+use crate::RustBertError;
 use std::borrow::Borrow;
 use tch::{nn, Tensor};
 
@@ -21,10 +24,10 @@ pub struct MultiHeadSelfAttention {
     dim_per_head: i64,
     dropout: Dropout,
     output_attentions: bool,
-    q_lin: nn::Linear,
-    k_lin: nn::Linear,
-    v_lin: nn::Linear,
-    out_lin: nn::Linear,
+    q_lin: QuantizableLinear,
+    k_lin: QuantizableLinear,
+    v_lin: QuantizableLinear,
+    out_lin: QuantizableLinear,
 }
 
 impl MultiHeadSelfAttention {
@@ -33,10 +36,10 @@ impl MultiHeadSelfAttention {
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
-        let q_lin = nn::linear(p / "q_lin", config.dim, config.dim, Default::default());
-        let k_lin = nn::linear(p / "k_lin", config.dim, config.dim, Default::default());
-        let v_lin = nn::linear(p / "v_lin", config.dim, config.dim, Default::default());
-        let out_lin = nn::linear(p / "out_lin", config.dim, config.dim, Default::default());
+        let q_lin = nn::linear(p / "q_lin", config.dim, config.dim, Default::default()).into();
+        let k_lin = nn::linear(p / "k_lin", config.dim, config.dim, Default::default()).into();
+        let v_lin = nn::linear(p / "v_lin", config.dim, config.dim, Default::default()).into();
+        let out_lin = nn::linear(p / "out_lin", config.dim, config.dim, Default::default()).into();
 
         let dropout = Dropout::new(config.attention_dropout);
         let output_attentions = config.output_attentions.unwrap_or(false);
@@ -53,6 +56,13 @@ impl MultiHeadSelfAttention {
         }
     }
 
+    pub(crate) fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.q_lin.quantize_dynamic()?;
+        self.k_lin.quantize_dynamic()?;
+        self.v_lin.quantize_dynamic()?;
+        self.out_lin.quantize_dynamic()
+    }
+
     fn split_heads(&self, x: Tensor, bs: i64, dim_per_head: i64) -> Tensor {
         x.view((bs, -1, self.n_heads, dim_per_head)).transpose(1, 2)
     }
@@ -80,21 +90,21 @@ impl MultiHeadSelfAttention {
         let q: Tensor = q / (self.dim_per_head as f64).sqrt();
 
         let scores = if let Some(mask) = mask {
-            let unmasked_scores = q.matmul(&k.transpose(2, 3));
+            let unmasked_scores = attention_scores(&q, &k.transpose(2, 3));
             let mask = mask
                 .le_tensor(&(mask.zeros_like() + 0.1))
                 .view((bs, 1i64, 1i64, k_length))
                 .expand_as(&unmasked_scores);
             unmasked_scores.masked_fill(&mask, f64::NEG_INFINITY)
         } else {
-            q.matmul(&k.transpose(2, 3))
+            attention_scores(&q, &k.transpose(2, 3))
         };
 
         let weights = scores
             .softmax(-1, scores.kind())
             .apply_t(&self.dropout, train);
         let context = self
-            .flatten(weights.matmul(&v), bs, self.dim_per_head)
+            .flatten(attention_output(&weights, &v), bs, self.dim_per_head)
             .apply(&self.out_lin);
 
         if !self.output_attentions {
@@ -15,6 +15,7 @@ extern crate tch;
 use self::tch::{nn, Tensor};
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
+use crate::common::weight_tying::TiedWeights;
 use crate::distilbert::embeddings::DistilBertEmbedding;
 use crate::distilbert::transformer::{DistilBertTransformerOutput, Transformer};
 use crate::{Config, RustBertError};
@@ -251,6 +252,17 @@ impl DistilBertModel {
         let transformer_output = self.transformer.forward_t(&input_embeddings, mask, train);
         Ok(transformer_output)
     }
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    pub(crate) fn get_word_embeddings(&self) -> &Tensor {
+        self.embeddings.get_word_embeddings()
+    }
+
+    /// Quantizes the linear layers of the transformer to int8, with a dynamic quantization of the activations. The
+    /// embeddings are kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.transformer.quantize_dynamic()
+    }
 }
 
 /// # DistilBERT for sequence classification
@@ -394,6 +406,17 @@ impl DistilBertModelClassifier {
             all_attentions: base_model_output.all_attentions,
         })
     }
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    pub(crate) fn get_word_embeddings(&self) -> &Tensor {
+        self.distil_bert_model.get_word_embeddings()
+    }
+
+    /// Quantizes the linear layers of the DistilBERT transformer to int8, with a dynamic quantization of the
+    /// activations. The task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.distil_bert_model.quantize_dynamic()
+    }
 }
 
 /// # DistilBERT for masked language model
@@ -411,6 +434,12 @@ pub struct DistilBertModelMaskedLM {
 }
 
 impl DistilBertModelMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "distilbert.embeddings.word_embeddings.weight",
+        "vocab_projector.weight",
+    );
+
     /// Build a new `DistilBertModelMaskedLM` for sequence classification
     ///
     /// # Arguments
@@ -530,6 +559,12 @@ impl DistilBertModelMaskedLM {
             all_attentions: base_model_output.all_attentions,
         })
     }
+
+    /// Quantizes the linear layers of the DistilBERT transformer to int8, with a dynamic quantization of the
+    /// activations. The task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.distil_bert_model.quantize_dynamic()
+    }
 }
 
 /// # DistilBERT for question answering
@@ -653,6 +688,12 @@ impl DistilBertForQuestionAnswering {
             all_attentions: base_model_output.all_attentions,
         })
     }
+
+    /// Quantizes the linear layers of the DistilBERT transformer to int8, with a dynamic quantization of the
+    /// activations. The task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.distil_bert_model.quantize_dynamic()
+    }
 }
 
 /// # DistilBERT for token classification (e.g. NER, POS)
@@ -783,6 +824,12 @@ impl DistilBertForTokenClassification {
             all_attentions: base_model_output.all_attentions,
         })
     }
+
+    /// Quantizes the linear layers of the DistilBERT transformer to int8, with a dynamic quantization of the
+    /// activations. The task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.distil_bert_model.quantize_dynamic()
+    }
 }
 
 /// # DistilBERT for sentence embeddings
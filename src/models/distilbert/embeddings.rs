@@ -154,4 +154,9 @@ impl DistilBertEmbedding {
             .apply(&self.layer_norm)
             .apply_t(&self.dropout, train))
     }
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    pub(crate) fn get_word_embeddings(&self) -> &Tensor {
+        &self.word_embeddings.ws
+    }
 }
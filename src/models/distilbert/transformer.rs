@@ -12,15 +12,17 @@
 
 use crate::common::activations::TensorFunction;
 use crate::common::dropout::Dropout;
+use crate::common::quantization::QuantizableLinear;
 use crate::distilbert::attention::MultiHeadSelfAttention;
 use crate::distilbert::distilbert_model::DistilBertConfig;
+use crate::RustBertError;
 use std::borrow::{Borrow, BorrowMut};
 use tch::nn::LayerNorm;
 use tch::{nn, Tensor};
 
 pub struct FeedForwardNetwork {
-    lin1: nn::Linear,
-    lin2: nn::Linear,
+    lin1: QuantizableLinear,
+    lin2: QuantizableLinear,
     dropout: Dropout,
     activation: TensorFunction,
 }
@@ -36,13 +38,15 @@ impl FeedForwardNetwork {
             config.dim,
             config.hidden_dim,
             Default::default(),
-        );
+        )
+        .into();
         let lin2 = nn::linear(
             p / "lin2",
             config.hidden_dim,
             config.dim,
             Default::default(),
-        );
+        )
+        .into();
         let dropout = Dropout::new(config.dropout);
         let activation = config.activation.get_function();
         FeedForwardNetwork {
@@ -53,6 +57,11 @@ impl FeedForwardNetwork {
         }
     }
 
+    pub(crate) fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.lin1.quantize_dynamic()?;
+        self.lin2.quantize_dynamic()
+    }
+
     pub fn forward_t(&self, input: &Tensor, train: bool) -> Tensor {
         (self.activation.get_fn())(&input.apply(&self.lin1))
             .apply(&self.lin2)
@@ -93,6 +102,13 @@ impl TransformerBlock {
         }
     }
 
+    /// Quantizes the linear layers of the block to int8, with a dynamic quantization of the activations.
+    /// See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.attention.quantize_dynamic()?;
+        self.ffn.quantize_dynamic()
+    }
+
     pub fn forward_t(
         &self,
         input: &Tensor,
@@ -133,6 +149,15 @@ impl Transformer {
         }
     }
 
+    /// Quantizes the linear layers of the transformer blocks to int8, with a dynamic quantization of the activations.
+    /// See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_dynamic()?;
+        }
+        Ok(())
+    }
+
     pub fn forward_t(
         &self,
         input: &Tensor,
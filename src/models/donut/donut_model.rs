@@ -0,0 +1,585 @@
+// Copyright 2022 NAVER Corp., The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::donut::swin::DonutSwinModel;
+use crate::mbart::{LayerState, MBartConfig, MBartDecoder};
+use crate::{Config, RustBertError};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::borrow::Borrow;
+use tch::nn::embedding;
+use tch::{nn, Kind, Tensor};
+
+/// # Donut Pretrained model config files
+pub struct DonutConfigResources;
+
+/// # Donut Pretrained model vocab files
+pub struct DonutVocabResources;
+
+/// # Donut Pretrained model special tokens map files
+pub struct DonutSpecialMap;
+
+impl DonutConfigResources {
+    /// Shared under MIT license by NAVER Clova at <https://huggingface.co/naver-clova-ix/donut-base-finetuned-cord-v2>.
+    pub const DONUT_BASE_CORD_V2: (&'static str, &'static str) = (
+        "donut-base-finetuned-cord-v2/config",
+        "https://huggingface.co/naver-clova-ix/donut-base-finetuned-cord-v2/resolve/main/config.json",
+    );
+    /// Shared under MIT license by NAVER Clova at <https://huggingface.co/naver-clova-ix/donut-base-finetuned-docvqa>.
+    pub const DONUT_BASE_DOCVQA: (&'static str, &'static str) = (
+        "donut-base-finetuned-docvqa/config",
+        "https://huggingface.co/naver-clova-ix/donut-base-finetuned-docvqa/resolve/main/config.json",
+    );
+}
+
+impl DonutVocabResources {
+    /// Shared under MIT license by NAVER Clova at <https://huggingface.co/naver-clova-ix/donut-base-finetuned-cord-v2>.
+    pub const DONUT_BASE_CORD_V2: (&'static str, &'static str) = (
+        "donut-base-finetuned-cord-v2/tokenizer",
+        "https://huggingface.co/naver-clova-ix/donut-base-finetuned-cord-v2/resolve/main/tokenizer.json",
+    );
+    /// Shared under MIT license by NAVER Clova at <https://huggingface.co/naver-clova-ix/donut-base-finetuned-docvqa>.
+    pub const DONUT_BASE_DOCVQA: (&'static str, &'static str) = (
+        "donut-base-finetuned-docvqa/tokenizer",
+        "https://huggingface.co/naver-clova-ix/donut-base-finetuned-docvqa/resolve/main/tokenizer.json",
+    );
+}
+
+impl DonutSpecialMap {
+    /// Shared under MIT license by NAVER Clova at <https://huggingface.co/naver-clova-ix/donut-base-finetuned-cord-v2>.
+    pub const DONUT_BASE_CORD_V2: (&'static str, &'static str) = (
+        "donut-base-finetuned-cord-v2/special",
+        "https://huggingface.co/naver-clova-ix/donut-base-finetuned-cord-v2/resolve/main/special_tokens_map.json",
+    );
+    /// Shared under MIT license by NAVER Clova at <https://huggingface.co/naver-clova-ix/donut-base-finetuned-docvqa>.
+    pub const DONUT_BASE_DOCVQA: (&'static str, &'static str) = (
+        "donut-base-finetuned-docvqa/special",
+        "https://huggingface.co/naver-clova-ix/donut-base-finetuned-docvqa/resolve/main/special_tokens_map.json",
+    );
+}
+
+/// Task prompt of the Donut model fine-tuned for receipt parsing on the CORD dataset
+pub const CORD_V2_TASK_PROMPT: &str = "<s_cord-v2>";
+
+/// Builds the task prompt of the Donut model fine-tuned for document visual question answering on DocVQA
+pub fn build_docvqa_task_prompt(question: &str) -> String {
+    format!("<s_docvqa><s_question>{question}</s_question><s_answer>")
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Donut Swin encoder configuration
+/// Defines the Swin image encoder architecture (e.g. number of stages, window size, embedding dimension...)
+pub struct DonutSwinConfig {
+    /// Input image size (*height*, *width*)
+    pub image_size: Vec<i64>,
+    pub patch_size: i64,
+    pub num_channels: i64,
+    pub embed_dim: i64,
+    /// Number of blocks for each stage
+    pub depths: Vec<i64>,
+    /// Number of attention heads for each stage
+    pub num_heads: Vec<i64>,
+    pub window_size: i64,
+    pub mlp_ratio: f64,
+    pub qkv_bias: Option<bool>,
+    pub hidden_dropout_prob: f64,
+    pub attention_probs_dropout_prob: f64,
+    pub drop_path_rate: f64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: Option<f64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for DonutSwinConfig {}
+
+impl Default for DonutSwinConfig {
+    fn default() -> Self {
+        DonutSwinConfig {
+            image_size: vec![2560, 1920],
+            patch_size: 4,
+            num_channels: 3,
+            embed_dim: 128,
+            depths: vec![2, 2, 14, 2],
+            num_heads: vec![4, 8, 16, 32],
+            window_size: 10,
+            mlp_ratio: 4.0,
+            qkv_bias: Some(true),
+            hidden_dropout_prob: 0.0,
+            attention_probs_dropout_prob: 0.0,
+            drop_path_rate: 0.1,
+            hidden_act: Activation::gelu,
+            layer_norm_eps: Some(1e-5),
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Donut model configuration
+/// Vision encoder-decoder configuration combining a Swin image encoder and a MBART text decoder.
+pub struct DonutConfig {
+    pub encoder: DonutSwinConfig,
+    pub decoder: MBartConfig,
+    pub decoder_start_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+}
+
+impl Config for DonutConfig {}
+
+impl Default for DonutConfig {
+    fn default() -> Self {
+        DonutConfig {
+            encoder: DonutSwinConfig::default(),
+            decoder: MBartConfig {
+                vocab_size: 57525,
+                max_position_embeddings: 1536,
+                decoder_layers: 4,
+                scale_embedding: Some(true),
+                forced_eos_token_id: None,
+                ..Default::default()
+            },
+            decoder_start_token_id: None,
+            pad_token_id: Some(1),
+            eos_token_id: Some(2),
+        }
+    }
+}
+
+/// # Donut model for document understanding
+/// OCR-free document understanding model generating a structured token sequence directly from a document image.
+/// It is made of the following blocks:
+/// - `encoder`: `DonutSwinModel` Swin transformer image encoder
+/// - `decoder`: `MBartDecoder` text decoder attending to the image features, with output projection tied to the token embeddings
+pub struct DonutModel {
+    encoder: DonutSwinModel,
+    embed_tokens: nn::Embedding,
+    decoder: MBartDecoder,
+    pad_token_id: i64,
+    eos_token_id: i64,
+}
+
+impl DonutModel {
+    /// Build a new `DonutModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Donut model
+    /// * `config` - `DonutConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::donut::{DonutConfig, DonutModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = DonutConfig::from_file(config_path);
+    /// let donut_model = DonutModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &DonutConfig) -> DonutModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let encoder = DonutSwinModel::new(p / "encoder", &config.encoder);
+
+        let p_decoder = p / "decoder" / "model" / "decoder";
+        let embed_tokens = embedding(
+            &p_decoder / "embed_tokens",
+            config.decoder.vocab_size,
+            config.decoder.d_model,
+            Default::default(),
+        );
+        let decoder = MBartDecoder::new(&p_decoder, &config.decoder);
+
+        let pad_token_id = config
+            .pad_token_id
+            .or(config.decoder.pad_token_id)
+            .unwrap_or(1);
+        let eos_token_id = config
+            .eos_token_id
+            .or(config.decoder.eos_token_id)
+            .unwrap_or(2);
+
+        DonutModel {
+            encoder,
+            embed_tokens,
+            decoder,
+            pad_token_id,
+            eos_token_id,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel_values` - Optional normalized images of shape (*batch size*, *num channels*, *height*, *width*) (see `prepare_image`). Must be provided if `encoder_outputs` is not.
+    /// * `encoder_outputs` - Optional pre-computed image features of shape (*batch size*, *num patches*, *hidden_size*), skipping the image encoding.
+    /// * `decoder_input_ids` - Decoder input tokens of shape (*batch size*, *target_sequence_length*), starting with the task prompt.
+    /// * `old_layer_states` - Optional cached key/value states of the decoder from a previous step.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `DonutModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *target_sequence_length*, *vocab_size*)
+    ///   - `encoder_hidden_state` - `Option<Tensor>` image features if they were calculated
+    ///   - `cache` - `Option<Vec<(Option<LayerState>, Option<LayerState>)>>` of length *n_layer* containing the decoder past keys and values
+    ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use rust_bert::donut::{DonutConfig, DonutModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = DonutConfig::from_file(config_path);
+    /// # let donut_model = DonutModel::new(&vs.root(), &config);
+    /// let pixel_values = Tensor::rand(&[1, 3, 2560, 1920], (Kind::Float, device));
+    /// let decoder_input_ids = Tensor::from_slice(&[0i64]).unsqueeze(0);
+    ///
+    /// let model_output = no_grad(|| {
+    ///     donut_model.forward_t(Some(&pixel_values), None, &decoder_input_ids, None, false)
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        pixel_values: Option<&Tensor>,
+        encoder_outputs: Option<&Tensor>,
+        decoder_input_ids: &Tensor,
+        old_layer_states: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
+        train: bool,
+    ) -> Result<DonutModelOutput, RustBertError> {
+        let calculated_encoder_output = match (encoder_outputs, pixel_values) {
+            (Some(_), _) => None,
+            (None, Some(pixel_values)) => Some(self.encoder.forward_t(pixel_values, train)),
+            (None, None) => {
+                return Err(RustBertError::ValueError(
+                    "At least one of pixel_values or encoder_outputs must be provided".into(),
+                ));
+            }
+        };
+        let encoder_hidden_states = match encoder_outputs {
+            Some(encoder_outputs) => encoder_outputs,
+            None => &calculated_encoder_output.as_ref().unwrap().hidden_state,
+        };
+
+        let decoder_output = self.decoder.forward_t(
+            decoder_input_ids,
+            encoder_hidden_states,
+            None,
+            None,
+            &self.embed_tokens,
+            old_layer_states,
+            train,
+        );
+        let lm_logits = decoder_output
+            .hidden_state
+            .linear::<Tensor>(&self.embed_tokens.ws, None);
+
+        Ok(DonutModelOutput {
+            lm_logits,
+            encoder_hidden_state: calculated_encoder_output.map(|output| output.hidden_state),
+            cache: decoder_output.next_decoder_cache,
+            all_decoder_hidden_states: decoder_output.all_hidden_states,
+            all_decoder_attentions: decoder_output.all_attentions,
+        })
+    }
+
+    /// Generates the output token sequences for a batch of images using greedy decoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel_values` - Normalized images of shape (*batch size*, *num channels*, *height*, *width*) (see `prepare_image`)
+    /// * `decoder_input_ids` - Task prompt tokens of shape (1, *prompt_length*) shared by all images, or (*batch size*, *prompt_length*)
+    /// * `max_length` - Maximum length of the output sequences (including the prompt)
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *sequence_length*) containing the prompt followed by the generated tokens,
+    ///   padded after the end of sequence token. The token sequences can be decoded (keeping special tokens) and
+    ///   converted to JSON using `parse_donut_output`.
+    pub fn generate(
+        &self,
+        pixel_values: &Tensor,
+        decoder_input_ids: &Tensor,
+        max_length: i64,
+    ) -> Result<Tensor, RustBertError> {
+        tch::no_grad(|| {
+            let batch_size = pixel_values.size()[0];
+            let prompt_length = decoder_input_ids.size()[1];
+            let encoder_hidden_states = self.encoder.forward_t(pixel_values, false).hidden_state;
+
+            let mut output_ids = decoder_input_ids
+                .expand([batch_size, prompt_length], false)
+                .contiguous();
+            let mut step_input_ids = output_ids.shallow_clone();
+            let mut unfinished_sequences =
+                Tensor::ones([batch_size], (Kind::Int64, output_ids.device()));
+            let mut cache = None;
+
+            while output_ids.size()[1] < max_length {
+                let output = self.forward_t(
+                    None,
+                    Some(&encoder_hidden_states),
+                    &step_input_ids,
+                    cache,
+                    false,
+                )?;
+                cache = output.cache;
+
+                let next_tokens = output.lm_logits.select(1, -1).argmax(-1, false);
+                let next_tokens = &next_tokens * &unfinished_sequences
+                    + (unfinished_sequences.ones_like() - &unfinished_sequences)
+                        * self.pad_token_id;
+                output_ids = Tensor::cat(&[output_ids, next_tokens.unsqueeze(-1)], -1);
+
+                unfinished_sequences =
+                    unfinished_sequences * next_tokens.ne(self.eos_token_id).to_kind(Kind::Int64);
+                if unfinished_sequences.max().int64_value(&[]) == 0 {
+                    break;
+                }
+                step_input_ids = next_tokens.unsqueeze(-1);
+            }
+            Ok(output_ids)
+        })
+    }
+}
+
+/// Container for the Donut model output.
+pub struct DonutModelOutput {
+    /// Logits for the vocabulary items at each sequence position
+    pub lm_logits: Tensor,
+    /// Image features of the encoder if they were calculated (not provided as an input)
+    pub encoder_hidden_state: Option<Tensor>,
+    /// Cached decoder attention layers keys and values
+    pub cache: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
+    /// Hidden states for all layers of the decoder
+    pub all_decoder_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all layers of the decoder
+    pub all_decoder_attentions: Option<Vec<Tensor>>,
+}
+
+/// Prepares an image for the Donut encoder.
+/// The image is resized to fit the target size while preserving its aspect ratio, padded (centered) to the target size
+/// and normalized to the [-1, 1] range.
+///
+/// # Arguments
+///
+/// * `image` - Image tensor of shape (*num channels*, *height*, *width*) with values in [0, 255] (e.g. loaded with `tch::vision::image::load`)
+/// * `image_size` - Target (*height*, *width*) of the encoder (`DonutSwinConfig::image_size`)
+///
+/// # Returns
+///
+/// * `Tensor` of shape (*num channels*, *target height*, *target width*)
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::donut::{prepare_image, DonutSwinConfig};
+///
+/// let image = tch::vision::image::load("path/to/receipt.png")?;
+/// let pixel_values = prepare_image(&image, &DonutSwinConfig::default().image_size).unsqueeze(0);
+/// # Ok(())
+/// # }
+/// ```
+pub fn prepare_image(image: &Tensor, image_size: &[i64]) -> Tensor {
+    let (_, height, width) = image.size3().unwrap();
+    let (target_height, target_width) = (image_size[0], image_size[1]);
+
+    // Resize the shortest edge to the smallest target dimension, then shrink the image to fit the target size
+    let scale = (target_height.min(target_width) as f64 / height.min(width) as f64)
+        .min(target_height as f64 / height as f64)
+        .min(target_width as f64 / width as f64);
+    let new_height = ((height as f64 * scale).round() as i64).clamp(1, target_height);
+    let new_width = ((width as f64 * scale).round() as i64).clamp(1, target_width);
+
+    let resized = image
+        .to_kind(Kind::Float)
+        .unsqueeze(0)
+        .upsample_bilinear2d([new_height, new_width], false, None::<f64>, None::<f64>)
+        .squeeze_dim(0)
+        .clamp(0.0, 255.0);
+
+    let pad_top = (target_height - new_height) / 2;
+    let pad_left = (target_width - new_width) / 2;
+    let padded = resized.pad(
+        [
+            pad_left,
+            target_width - new_width - pad_left,
+            pad_top,
+            target_height - new_height - pad_top,
+        ],
+        "constant",
+        0.0,
+    );
+
+    (padded / 255.0 - 0.5) / 0.5
+}
+
+/// Converts a Donut output sequence to JSON.
+/// The sequence is expected to be decoded without skipping the special tokens: the end of sequence and padding
+/// tokens are removed, as well as the task prompt start token. The structure is then read from the
+/// `<s_{key}>...</s_{key}>` field tokens, with lists of values or objects separated by `<sep/>` tokens.
+/// Sequences without fields are returned as `{"text_sequence": sequence}`.
+///
+/// # Arguments
+///
+/// * `sequence` - Donut output sequence
+///
+/// # Returns
+///
+/// * `Value` JSON object extracted from the sequence
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::donut::parse_donut_output;
+///
+/// let sequence = "<s_cord-v2><s_menu><s_nm>Latte</s_nm><s_price>4.50</s_price></s_menu></s>";
+/// let output = parse_donut_output(sequence);
+/// // {"menu": {"nm": "Latte", "price": "4.50"}}
+/// ```
+pub fn parse_donut_output(sequence: &str) -> Value {
+    let sequence = sequence.replace("</s>", "").replace("<pad>", "");
+    let task_prompt_pattern = Regex::new(r"<.*?>").unwrap();
+    let sequence = task_prompt_pattern.replace(&sequence, "");
+    token_sequence_to_json(sequence.trim(), false)
+}
+
+fn token_sequence_to_json(sequence: &str, is_inner_value: bool) -> Value {
+    let start_token_pattern = Regex::new(r"(?i)<s_(.*?)>").unwrap();
+    let mut output = Map::new();
+    let mut tokens = sequence.to_string();
+
+    while !tokens.is_empty() {
+        let (start_token, key) = match start_token_pattern.captures(&tokens) {
+            Some(captures) => (captures[0].to_string(), captures[1].to_string()),
+            None => break,
+        };
+        let end_token_pattern =
+            Regex::new(&format!(r"(?i)</s_{}>", regex::escape(key.as_str()))).unwrap();
+        let end_token = match end_token_pattern.find(&tokens) {
+            Some(end_token) => end_token.as_str().to_string(),
+            None => {
+                tokens = tokens.replace(start_token.as_str(), "");
+                continue;
+            }
+        };
+
+        let content_pattern = Regex::new(&format!(
+            r"(?is){}(.*?){}",
+            regex::escape(start_token.as_str()),
+            regex::escape(end_token.as_str())
+        ))
+        .unwrap();
+        if let Some(captures) = content_pattern.captures(&tokens) {
+            let content = captures[1].trim();
+            if content.contains("<s_") && content.contains("</s_") {
+                // Non-leaf node: nested fields
+                if let Value::Array(mut values) = token_sequence_to_json(content, true) {
+                    if !values.is_empty() {
+                        let value = if values.len() == 1 {
+                            values.remove(0)
+                        } else {
+                            Value::Array(values)
+                        };
+                        output.insert(key, value);
+                    }
+                }
+            } else {
+                // Leaf node: single value or list of values, unwrapping categorical tokens (e.g. `<yes/>`)
+                let mut values = content
+                    .split("<sep/>")
+                    .map(|leaf| {
+                        let leaf = leaf.trim();
+                        if leaf.len() > 3
+                            && leaf.starts_with('<')
+                            && leaf.ends_with("/>")
+                            && !leaf[1..leaf.len() - 2].contains(&['<', '>', '/'][..])
+                        {
+                            Value::String(leaf[1..leaf.len() - 2].to_string())
+                        } else {
+                            Value::String(leaf.to_string())
+                        }
+                    })
+                    .collect::<Vec<Value>>();
+                let value = if values.len() == 1 {
+                    values.remove(0)
+                } else {
+                    Value::Array(values)
+                };
+                output.insert(key, value);
+            }
+        }
+
+        let end_position = tokens.find(end_token.as_str()).unwrap() + end_token.len();
+        tokens = tokens[end_position..].trim().to_string();
+        if let Some(remaining_tokens) = tokens.strip_prefix("<sep/>") {
+            // List of objects
+            let mut values = vec![Value::Object(output)];
+            if let Value::Array(next_values) = token_sequence_to_json(remaining_tokens, true) {
+                values.extend(next_values);
+            }
+            return Value::Array(values);
+        }
+    }
+
+    match (output.is_empty(), is_inner_value) {
+        (false, true) => Value::Array(vec![Value::Object(output)]),
+        (false, false) => Value::Object(output),
+        (true, true) => Value::Array(vec![]),
+        (true, false) => json!({ "text_sequence": tokens }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_nested_output() {
+        let sequence = "<s_cord-v2><s_menu><s_nm>Latte</s_nm><s_cnt>2</s_cnt><sep/><s_nm>Bagel</s_nm><s_cnt>1</s_cnt></s_menu><s_total><s_total_price>9.00</s_total_price></s_total></s><pad>";
+        assert_eq!(
+            parse_donut_output(sequence),
+            json!({
+                "menu": [{"nm": "Latte", "cnt": "2"}, {"nm": "Bagel", "cnt": "1"}],
+                "total": {"total_price": "9.00"}
+            })
+        );
+        assert_eq!(
+            parse_donut_output("<s_rvlcdip><s_class><invoice/></s_class></s>"),
+            json!({"class": "invoice"})
+        );
+        assert_eq!(
+            parse_donut_output("<s_synthdog>plain text</s>"),
+            json!({"text_sequence": "plain text"})
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! # Donut (Kim et al.)
+//!
+//! Implementation of the Donut document understanding model ([OCR-free Document Understanding Transformer](https://arxiv.org/abs/2111.15664) Kim, Hong, Yim et al., 2022).
+//! Donut reads document images directly, without a separate OCR step: a Swin transformer encoder extracts the image features,
+//! and a MBART decoder generates a token sequence describing the document content, for example the fields of a receipt or invoice.
+//! The image encoder is implemented in the `swin::DonutSwinModel` struct and the full vision encoder-decoder model in `donut_model::DonutModel`.
+//!
+//! The decoder is prompted with a task token (e.g. `<s_cord-v2>` for receipt parsing, see `CORD_V2_TASK_PROMPT` and `build_docvqa_task_prompt`) and generates
+//! a sequence of `<s_{field}>...</s_{field}>` tokens that is converted to JSON with `parse_donut_output`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers) `VisionEncoderDecoderConfig`
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the
+//!   weights from [NAVER Clova](https://huggingface.co/naver-clova-ix) to the `.ot` format, for example with `python utils/convert_model.py path/to/donut/pytorch_model.bin`.
+//! - The tokenizer (a XLM-RoBERTa SentencePiece tokenizer extended with the field tokens) should be loaded from a `tokenizer.json` file and a special tokens map using the `hf-tokenizers` feature
+//!   (see `TokenizerOption::from_hf_tokenizer_file`).
+//!
+//! Images are prepared for the encoder with `prepare_image` (resizing, padding and normalization).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device, Tensor};
+//! # use std::path::PathBuf;
+//! use rust_bert::donut::{
+//!     parse_donut_output, prepare_image, DonutConfig, DonutModel, CORD_V2_TASK_PROMPT,
+//! };
+//! use rust_bert::pipelines::common::TokenizerOption;
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+//!     "path/to/tokenizer.json",
+//!     "path/to/special_tokens_map.json",
+//! )?;
+//! let config = DonutConfig::from_file(config_path);
+//! let donut_model = DonutModel::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! let image = tch::vision::image::load("path/to/receipt.png")?;
+//! let pixel_values = prepare_image(&image, &config.encoder.image_size)
+//!     .unsqueeze(0)
+//!     .to_device(device);
+//! let prompt = Tensor::from_slice(&tokenizer.convert_tokens_to_ids(&[CORD_V2_TASK_PROMPT]))
+//!     .unsqueeze(0)
+//!     .to_device(device);
+//!
+//! let output_ids = donut_model.generate(&pixel_values, &prompt, 768)?;
+//! let sequence = tokenizer.decode(&Vec::<i64>::try_from(output_ids.get(0))?, false, false);
+//! let receipt = parse_donut_output(&sequence);
+//! # Ok(())
+//! # }
+//! ```
+
+mod donut_model;
+mod swin;
+
+pub use donut_model::{
+    build_docvqa_task_prompt, parse_donut_output, prepare_image, DonutConfig, DonutConfigResources,
+    DonutModel, DonutModelOutput, DonutSpecialMap, DonutSwinConfig, DonutVocabResources,
+    CORD_V2_TASK_PROMPT,
+};
+pub use swin::{
+    DonutSwinAttention, DonutSwinEmbeddings, DonutSwinLayer, DonutSwinModel, DonutSwinModelOutput,
+    DonutSwinPatchMerging, DonutSwinStage,
+};
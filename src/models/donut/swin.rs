@@ -0,0 +1,769 @@
+// Copyright 2022 NAVER Corp., The Microsoft Research Asia Swin Transformer and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::donut::donut_model::DonutSwinConfig;
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::Init;
+use tch::{nn, Tensor};
+
+/// Splits a (*batch size*, *height*, *width*, *channels*) tensor into non-overlapping windows
+/// of shape (*window size*, *window size*, *channels*)
+fn window_partition(input: &Tensor, window_size: i64) -> Tensor {
+    let (batch_size, height, width, num_channels) = input.size4().unwrap();
+    input
+        .view([
+            batch_size,
+            height / window_size,
+            window_size,
+            width / window_size,
+            window_size,
+            num_channels,
+        ])
+        .permute([0, 1, 3, 2, 4, 5])
+        .contiguous()
+        .view([-1, window_size, window_size, num_channels])
+}
+
+/// Merges windows back to a (*batch size*, *height*, *width*, *channels*) tensor
+fn window_reverse(windows: &Tensor, window_size: i64, height: i64, width: i64) -> Tensor {
+    let num_channels = *windows.size().last().unwrap();
+    windows
+        .view([
+            -1,
+            height / window_size,
+            width / window_size,
+            window_size,
+            window_size,
+            num_channels,
+        ])
+        .permute([0, 1, 3, 2, 4, 5])
+        .contiguous()
+        .view([-1, height, width, num_channels])
+}
+
+/// Stochastic depth: randomly drops the residual branch of samples during training
+struct DropPath {
+    drop_prob: f64,
+}
+
+impl DropPath {
+    fn forward_t(&self, input: &Tensor, train: bool) -> Tensor {
+        if !train || self.drop_prob == 0.0 {
+            return input.shallow_clone();
+        }
+        let keep_prob = 1.0 - self.drop_prob;
+        let mut shape = vec![1; input.dim()];
+        shape[0] = input.size()[0];
+        let random_tensor =
+            (Tensor::rand(shape.as_slice(), (input.kind(), input.device())) + keep_prob).floor();
+        input / keep_prob * random_tensor
+    }
+}
+
+/// # Donut Swin patch embeddings
+/// Splits the image in patches and projects them to the embedding dimension.
+pub struct DonutSwinEmbeddings {
+    projection: nn::Conv2D,
+    norm: nn::LayerNorm,
+    dropout: Dropout,
+    patch_size: i64,
+}
+
+impl DonutSwinEmbeddings {
+    pub fn new<'p, P>(p: P, config: &DonutSwinConfig) -> DonutSwinEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let conv_config = nn::ConvConfig {
+            stride: config.patch_size,
+            ..Default::default()
+        };
+        let projection = nn::conv2d(
+            p / "patch_embeddings" / "projection",
+            config.num_channels,
+            config.embed_dim,
+            config.patch_size,
+            conv_config,
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let norm = nn::layer_norm(p / "norm", vec![config.embed_dim], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        DonutSwinEmbeddings {
+            projection,
+            norm,
+            dropout,
+            patch_size: config.patch_size,
+        }
+    }
+
+    /// Returns the patch embeddings of shape (*batch size*, *num patches*, *embed dim*) and the (*height*, *width*) of the patches grid
+    pub fn forward_t(&self, pixel_values: &Tensor, train: bool) -> (Tensor, (i64, i64)) {
+        let (_, _, height, width) = pixel_values.size4().unwrap();
+        let pad_bottom = (self.patch_size - height % self.patch_size) % self.patch_size;
+        let pad_right = (self.patch_size - width % self.patch_size) % self.patch_size;
+        let pixel_values = if pad_bottom > 0 || pad_right > 0 {
+            pixel_values.pad([0, pad_right, 0, pad_bottom], "constant", 0.0)
+        } else {
+            pixel_values.shallow_clone()
+        };
+
+        let embeddings = pixel_values.apply(&self.projection);
+        let (_, _, output_height, output_width) = embeddings.size4().unwrap();
+        let embeddings = embeddings
+            .flatten(2, -1)
+            .transpose(1, 2)
+            .apply(&self.norm)
+            .apply_t(&self.dropout, train);
+
+        (embeddings, (output_height, output_width))
+    }
+}
+
+/// # Donut Swin window attention
+/// Multi-head self-attention computed within local windows, with a learned relative position bias.
+pub struct DonutSwinAttention {
+    query: nn::Linear,
+    key: nn::Linear,
+    value: nn::Linear,
+    dense: nn::Linear,
+    relative_position_bias_table: Tensor,
+    relative_position_index: Tensor,
+    num_attention_heads: i64,
+    attention_head_size: i64,
+    attention_dropout: Dropout,
+    dropout: Dropout,
+    output_attentions: bool,
+}
+
+impl DonutSwinAttention {
+    pub fn new<'p, P>(
+        p: P,
+        config: &DonutSwinConfig,
+        dim: i64,
+        num_heads: i64,
+        window_size: i64,
+    ) -> DonutSwinAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let p_self = p / "self";
+
+        let linear_config = nn::LinearConfig {
+            bias: config.qkv_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let query = nn::linear(&p_self / "query", dim, dim, linear_config);
+        let key = nn::linear(&p_self / "key", dim, dim, linear_config);
+        let value = nn::linear(&p_self / "value", dim, dim, linear_config);
+        let dense = nn::linear(p / "output" / "dense", dim, dim, Default::default());
+
+        let relative_position_bias_table = p_self.var(
+            "relative_position_bias_table",
+            &[(2 * window_size - 1) * (2 * window_size - 1), num_heads],
+            Init::Const(0.),
+        );
+
+        let window_area = window_size * window_size;
+        let mut relative_position_index = Vec::with_capacity((window_area * window_area) as usize);
+        for position_i in 0..window_area {
+            for position_j in 0..window_area {
+                let relative_row = position_i / window_size - position_j / window_size;
+                let relative_column = position_i % window_size - position_j % window_size;
+                relative_position_index.push(
+                    (relative_row + window_size - 1) * (2 * window_size - 1)
+                        + relative_column
+                        + window_size
+                        - 1,
+                );
+            }
+        }
+        let relative_position_index =
+            Tensor::from_slice(relative_position_index.as_slice()).to_device(p.device());
+
+        DonutSwinAttention {
+            query,
+            key,
+            value,
+            dense,
+            relative_position_bias_table,
+            relative_position_index,
+            num_attention_heads: num_heads,
+            attention_head_size: dim / num_heads,
+            attention_dropout: Dropout::new(config.attention_probs_dropout_prob),
+            dropout: Dropout::new(config.hidden_dropout_prob),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    fn split_heads(&self, x: Tensor, batch_size: i64, sequence_length: i64) -> Tensor {
+        x.view([
+            batch_size,
+            sequence_length,
+            self.num_attention_heads,
+            self.attention_head_size,
+        ])
+        .permute([0, 2, 1, 3])
+    }
+
+    /// Attention within windows of shape (*batch size x num windows*, *window area*, *dim*).
+    /// The optional attention mask of shape (*num windows*, *window area*, *window area*) masks the tokens
+    /// of windows originating from non-adjacent regions after the cyclic shift.
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (batch_size, sequence_length, _) = hidden_states.size3().unwrap();
+
+        let query = self.split_heads(
+            hidden_states.apply(&self.query),
+            batch_size,
+            sequence_length,
+        );
+        let key = self.split_heads(hidden_states.apply(&self.key), batch_size, sequence_length);
+        let value = self.split_heads(
+            hidden_states.apply(&self.value),
+            batch_size,
+            sequence_length,
+        );
+
+        let relative_position_bias = self
+            .relative_position_bias_table
+            .index_select(0, &self.relative_position_index)
+            .view([sequence_length, sequence_length, -1])
+            .permute([2, 0, 1]);
+
+        let mut attention_scores = attention_scores(&query, &key.transpose(-1, -2))
+            / (self.attention_head_size as f64).sqrt()
+            + relative_position_bias.unsqueeze(0);
+
+        if let Some(mask) = attention_mask {
+            let num_windows = mask.size()[0];
+            attention_scores = (attention_scores.view([
+                batch_size / num_windows,
+                num_windows,
+                self.num_attention_heads,
+                sequence_length,
+                sequence_length,
+            ]) + mask.unsqueeze(1).unsqueeze(0))
+            .view([
+                -1,
+                self.num_attention_heads,
+                sequence_length,
+                sequence_length,
+            ]);
+        }
+
+        let attention_probs = attention_scores
+            .softmax(-1, attention_scores.kind())
+            .apply_t(&self.attention_dropout, train);
+        let context = attention_output(&attention_probs, &value)
+            .permute([0, 2, 1, 3])
+            .contiguous()
+            .view([batch_size, sequence_length, -1]);
+        let output = context.apply(&self.dense).apply_t(&self.dropout, train);
+
+        let attention_probs = if self.output_attentions {
+            Some(attention_probs)
+        } else {
+            None
+        };
+        (output, attention_probs)
+    }
+}
+
+/// # Donut Swin transformer block
+/// (Shifted) window attention followed by a feed-forward layer, both with pre-normalization.
+pub struct DonutSwinLayer {
+    layernorm_before: nn::LayerNorm,
+    attention: DonutSwinAttention,
+    layernorm_after: nn::LayerNorm,
+    intermediate: nn::Linear,
+    output: nn::Linear,
+    activation: TensorFunction,
+    dropout: Dropout,
+    drop_path: DropPath,
+    window_size: i64,
+    shift_size: i64,
+}
+
+impl DonutSwinLayer {
+    pub fn new<'p, P>(
+        p: P,
+        config: &DonutSwinConfig,
+        dim: i64,
+        num_heads: i64,
+        input_resolution: (i64, i64),
+        shift_size: i64,
+        drop_path_rate: f64,
+    ) -> DonutSwinLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        // Windows cannot be larger than the feature map: the attention then covers the full map without shift
+        let min_resolution = input_resolution.0.min(input_resolution.1);
+        let (window_size, shift_size) = if min_resolution <= config.window_size {
+            (min_resolution, 0)
+        } else {
+            (config.window_size, shift_size)
+        };
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let layernorm_before = nn::layer_norm(p / "layernorm_before", vec![dim], layer_norm_config);
+        let attention =
+            DonutSwinAttention::new(p / "attention", config, dim, num_heads, window_size);
+        let layernorm_after = nn::layer_norm(p / "layernorm_after", vec![dim], layer_norm_config);
+
+        let intermediate_size = (config.mlp_ratio * dim as f64) as i64;
+        let intermediate = nn::linear(
+            p / "intermediate" / "dense",
+            dim,
+            intermediate_size,
+            Default::default(),
+        );
+        let output = nn::linear(
+            p / "output" / "dense",
+            intermediate_size,
+            dim,
+            Default::default(),
+        );
+
+        DonutSwinLayer {
+            layernorm_before,
+            attention,
+            layernorm_after,
+            intermediate,
+            output,
+            activation: config.hidden_act.get_function(),
+            dropout: Dropout::new(config.hidden_dropout_prob),
+            drop_path: DropPath {
+                drop_prob: drop_path_rate,
+            },
+            window_size,
+            shift_size,
+        }
+    }
+
+    fn get_attention_mask(&self, height: i64, width: i64, reference: &Tensor) -> Tensor {
+        let image_mask = Tensor::zeros(
+            [1, height, width, 1],
+            (reference.kind(), reference.device()),
+        );
+        let slices = |size: i64| {
+            [
+                (0, size - self.window_size),
+                (size - self.window_size, size - self.shift_size),
+                (size - self.shift_size, size),
+            ]
+        };
+        let mut count = 0i64;
+        for (height_start, height_end) in slices(height) {
+            for (width_start, width_end) in slices(width) {
+                let mut region = image_mask.slice(1, height_start, height_end, 1).slice(
+                    2,
+                    width_start,
+                    width_end,
+                    1,
+                );
+                let _ = region.fill_(count);
+                count += 1;
+            }
+        }
+
+        let mask_windows = window_partition(&image_mask, self.window_size)
+            .view([-1, self.window_size * self.window_size]);
+        (mask_windows.unsqueeze(1) - mask_windows.unsqueeze(2))
+            .ne(0)
+            .to_kind(reference.kind())
+            * -100.0
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        input_dimensions: (i64, i64),
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (height, width) = input_dimensions;
+        let (batch_size, _, num_channels) = hidden_states.size3().unwrap();
+        // The relative position bias is sized for the window: smaller feature maps are padded without shift
+        let shift_size = if height.min(width) <= self.window_size {
+            0
+        } else {
+            self.shift_size
+        };
+
+        let attention_input = hidden_states.apply(&self.layernorm_before).view([
+            batch_size,
+            height,
+            width,
+            num_channels,
+        ]);
+        let pad_right = (self.window_size - width % self.window_size) % self.window_size;
+        let pad_bottom = (self.window_size - height % self.window_size) % self.window_size;
+        let attention_input = if pad_right > 0 || pad_bottom > 0 {
+            attention_input.pad([0, 0, 0, pad_right, 0, pad_bottom], "constant", 0.0)
+        } else {
+            attention_input
+        };
+        let (height_pad, width_pad) = (height + pad_bottom, width + pad_right);
+
+        let shifted_input = if shift_size > 0 {
+            attention_input.roll([-shift_size, -shift_size], [1, 2])
+        } else {
+            attention_input
+        };
+        let input_windows = window_partition(&shifted_input, self.window_size).view([
+            -1,
+            self.window_size * self.window_size,
+            num_channels,
+        ]);
+        let attention_mask = if shift_size > 0 {
+            Some(self.get_attention_mask(height_pad, width_pad, &input_windows))
+        } else {
+            None
+        };
+
+        let (attention_windows, attention_weights) =
+            self.attention
+                .forward_t(&input_windows, attention_mask.as_ref(), train);
+        let attention_windows =
+            attention_windows.view([-1, self.window_size, self.window_size, num_channels]);
+        let shifted_windows =
+            window_reverse(&attention_windows, self.window_size, height_pad, width_pad);
+        let attention_output = if shift_size > 0 {
+            shifted_windows.roll([shift_size, shift_size], [1, 2])
+        } else {
+            shifted_windows
+        };
+        let attention_output = if pad_right > 0 || pad_bottom > 0 {
+            attention_output
+                .slice(1, 0, height, 1)
+                .slice(2, 0, width, 1)
+                .contiguous()
+        } else {
+            attention_output
+        };
+        let hidden_states = hidden_states
+            + self.drop_path.forward_t(
+                &attention_output.view([batch_size, height * width, num_channels]),
+                train,
+            );
+
+        let layer_output = (self.activation.get_fn())(
+            &hidden_states
+                .apply(&self.layernorm_after)
+                .apply(&self.intermediate),
+        )
+        .apply(&self.output)
+        .apply_t(&self.dropout, train);
+        let hidden_states = &hidden_states + self.drop_path.forward_t(&layer_output, train);
+
+        (hidden_states, attention_weights)
+    }
+}
+
+/// # Donut Swin patch merging
+/// Concatenates the features of groups of 2x2 neighbouring patches and projects them to twice the input dimension.
+pub struct DonutSwinPatchMerging {
+    reduction: nn::Linear,
+    norm: nn::LayerNorm,
+}
+
+impl DonutSwinPatchMerging {
+    pub fn new<'p, P>(p: P, config: &DonutSwinConfig, dim: i64) -> DonutSwinPatchMerging
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: false,
+            ..Default::default()
+        };
+        let reduction = nn::linear(p / "reduction", 4 * dim, 2 * dim, linear_config);
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let norm = nn::layer_norm(p / "norm", vec![4 * dim], layer_norm_config);
+
+        DonutSwinPatchMerging { reduction, norm }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor, input_dimensions: (i64, i64)) -> Tensor {
+        let (height, width) = input_dimensions;
+        let (batch_size, _, num_channels) = hidden_states.size3().unwrap();
+
+        let hidden_states = hidden_states.view([batch_size, height, width, num_channels]);
+        let hidden_states = if height % 2 == 1 || width % 2 == 1 {
+            hidden_states.pad([0, 0, 0, width % 2, 0, height % 2], "constant", 0.0)
+        } else {
+            hidden_states
+        };
+
+        let hidden_states_0 = hidden_states.slice(1, 0, None, 2).slice(2, 0, None, 2);
+        let hidden_states_1 = hidden_states.slice(1, 1, None, 2).slice(2, 0, None, 2);
+        let hidden_states_2 = hidden_states.slice(1, 0, None, 2).slice(2, 1, None, 2);
+        let hidden_states_3 = hidden_states.slice(1, 1, None, 2).slice(2, 1, None, 2);
+
+        Tensor::cat(
+            &[
+                hidden_states_0,
+                hidden_states_1,
+                hidden_states_2,
+                hidden_states_3,
+            ],
+            -1,
+        )
+        .view([batch_size, -1, 4 * num_channels])
+        .apply(&self.norm)
+        .apply(&self.reduction)
+    }
+}
+
+/// # Donut Swin stage
+/// Sequence of Swin blocks alternating regular and shifted windows, followed by an optional patch merging layer.
+pub struct DonutSwinStage {
+    blocks: Vec<DonutSwinLayer>,
+    downsample: Option<DonutSwinPatchMerging>,
+}
+
+impl DonutSwinStage {
+    pub fn new<'p, P>(
+        p: P,
+        config: &DonutSwinConfig,
+        stage_index: usize,
+        input_resolution: (i64, i64),
+        drop_path_rates: &[f64],
+    ) -> DonutSwinStage
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dim = config.embed_dim * 2_i64.pow(stage_index as u32);
+        let p_blocks = p / "blocks";
+        let blocks = (0..config.depths[stage_index])
+            .map(|block_index| {
+                let shift_size = if block_index % 2 == 0 {
+                    0
+                } else {
+                    config.window_size / 2
+                };
+                DonutSwinLayer::new(
+                    &p_blocks / block_index,
+                    config,
+                    dim,
+                    config.num_heads[stage_index],
+                    input_resolution,
+                    shift_size,
+                    drop_path_rates[block_index as usize],
+                )
+            })
+            .collect::<Vec<DonutSwinLayer>>();
+
+        let downsample = if stage_index < config.depths.len() - 1 {
+            Some(DonutSwinPatchMerging::new(p / "downsample", config, dim))
+        } else {
+            None
+        };
+
+        DonutSwinStage { blocks, downsample }
+    }
+
+    /// Returns the stage output and its (*height*, *width*) dimensions, with the attention weights of each block if required
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        input_dimensions: (i64, i64),
+        train: bool,
+    ) -> (Tensor, (i64, i64), Vec<Tensor>) {
+        let mut hidden_state = hidden_states.shallow_clone();
+        let mut attentions = vec![];
+        for block in &self.blocks {
+            let (block_output, attention_weights) =
+                block.forward_t(&hidden_state, input_dimensions, train);
+            hidden_state = block_output;
+            if let Some(attention_weights) = attention_weights {
+                attentions.push(attention_weights);
+            }
+        }
+
+        match &self.downsample {
+            Some(downsample) => {
+                let output_dimensions =
+                    ((input_dimensions.0 + 1) / 2, (input_dimensions.1 + 1) / 2);
+                (
+                    downsample.forward(&hidden_state, input_dimensions),
+                    output_dimensions,
+                    attentions,
+                )
+            }
+            None => (hidden_state, input_dimensions, attentions),
+        }
+    }
+}
+
+/// # Donut Swin encoder
+/// Hierarchical vision transformer used as the image encoder of Donut.
+/// It is made of the following blocks:
+/// - `embeddings`: patch embeddings
+/// - `stages`: sequence of Swin stages, each halving the spatial resolution (except for the last one)
+pub struct DonutSwinModel {
+    embeddings: DonutSwinEmbeddings,
+    stages: Vec<DonutSwinStage>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl DonutSwinModel {
+    /// Build a new `DonutSwinModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Swin encoder
+    /// * `config` - `DonutSwinConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::donut::{DonutSwinConfig, DonutSwinModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = DonutSwinConfig::from_file(config_path);
+    /// let swin_model = DonutSwinModel::new(&p.root() / "encoder", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &DonutSwinConfig) -> DonutSwinModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = DonutSwinEmbeddings::new(p / "embeddings", config);
+
+        let total_depth: i64 = config.depths.iter().sum();
+        let drop_path_rates = (0..total_depth)
+            .map(|block_index| {
+                if total_depth > 1 {
+                    config.drop_path_rate * block_index as f64 / (total_depth - 1) as f64
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<f64>>();
+
+        let grid_size = (
+            config.image_size[0] / config.patch_size,
+            config.image_size[1] / config.patch_size,
+        );
+        let p_layers = p / "encoder" / "layers";
+        let mut stages = Vec::with_capacity(config.depths.len());
+        let mut depth_offset = 0;
+        for (stage_index, depth) in config.depths.iter().enumerate() {
+            let scale = 2_i64.pow(stage_index as u32);
+            stages.push(DonutSwinStage::new(
+                &p_layers / stage_index as i64,
+                config,
+                stage_index,
+                (grid_size.0 / scale, grid_size.1 / scale),
+                &drop_path_rates[depth_offset..depth_offset + *depth as usize],
+            ));
+            depth_offset += *depth as usize;
+        }
+
+        DonutSwinModel {
+            embeddings,
+            stages,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel_values` - Normalized images of shape (*batch size*, *num channels*, *height*, *width*) (see `prepare_image`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `DonutSwinModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *num patches*, *hidden_size*) for the last stage (the number of patches is divided by 4 with every stage but the last)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_stages + 1* with the embeddings and stages outputs
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_blocks* with shape (*batch size x num windows*, *num heads*, *window area*, *window area*)
+    pub fn forward_t(&self, pixel_values: &Tensor, train: bool) -> DonutSwinModelOutput {
+        let (mut hidden_state, mut dimensions) = self.embeddings.forward_t(pixel_values, train);
+
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![hidden_state.shallow_clone()])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        for stage in &self.stages {
+            let (stage_output, output_dimensions, attentions) =
+                stage.forward_t(&hidden_state, dimensions, train);
+            hidden_state = stage_output;
+            dimensions = output_dimensions;
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.shallow_clone());
+            };
+            if let Some(all_attentions) = all_attentions.borrow_mut() {
+                all_attentions.extend(attentions);
+            };
+        }
+
+        DonutSwinModelOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// Container for the Donut Swin encoder output.
+pub struct DonutSwinModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for the embeddings and all stages of the model
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all blocks of the model
+    pub all_attentions: Option<Vec<Tensor>>,
+}
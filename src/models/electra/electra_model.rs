@@ -16,6 +16,7 @@ use crate::bert::BertConfig;
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::weight_tying::TiedWeights;
 use crate::electra::embeddings::ElectraEmbeddings;
 use crate::{bert::encoder::BertEncoder, common::activations::TensorFunction};
 use crate::{Config, RustBertError};
@@ -523,6 +524,12 @@ pub struct ElectraForMaskedLM {
 
 /// Defines the implementation of the ElectraForMaskedLM.
 impl ElectraForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "electra.embeddings.word_embeddings.weight",
+        "generator_lm_head.weight",
+    );
+
     /// Build a new `ElectraForMaskedLM`
     ///
     /// # Arguments
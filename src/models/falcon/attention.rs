@@ -0,0 +1,310 @@
+// Copyright 2023 The Technology Innovation Institute and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::position_embeddings::{apply_rotary_pos_emb, RotaryEmbedding};
+use crate::common::precision::{attention_output, attention_scores};
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::falcon::falcon_model::FalconConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for Falcon attention layers
+/// Stores the cached value of key and value, before expansion to the number of query heads
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self.prev_value.narrow(-2, 0, length),
+        }
+    }
+}
+
+/// Builds the boolean mask of the future key positions each query may not attend to, of shape
+/// (*query_length*, *key_length*). Queries are assumed to be the last `query_length` positions of the keys.
+pub(crate) fn build_causal_mask(query_length: i64, key_length: i64, device: Device) -> Tensor {
+    let query_positions =
+        Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+            .unsqueeze(-1);
+    let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+    (query_positions - key_positions).lt(0)
+}
+
+/// # Rotary position embeddings
+/// Falcon models without ALiBi use the shared rotary position embeddings (non-interleaved layout) over the full head dimension.
+pub type FalconRotaryEmbedding = RotaryEmbedding;
+
+/// Repeats the key and value heads to match the number of query heads (multi-query and grouped-query attention)
+fn repeat_kv(hidden_states: &Tensor, num_repetitions: i64) -> Tensor {
+    if num_repetitions == 1 {
+        return hidden_states.shallow_clone();
+    }
+    let (batch_size, num_key_value_heads, sequence_length, head_dim) =
+        hidden_states.size4().unwrap();
+    hidden_states
+        .unsqueeze(2)
+        .expand(
+            [
+                batch_size,
+                num_key_value_heads,
+                num_repetitions,
+                sequence_length,
+                head_dim,
+            ],
+            false,
+        )
+        .reshape([
+            batch_size,
+            num_key_value_heads * num_repetitions,
+            sequence_length,
+            head_dim,
+        ])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Layout of the fused query, key and value projection
+enum FusedQKVLayout {
+    /// `num_kv_heads` groups of query heads, each followed by its key and value heads (Falcon-40B, Falcon-180B)
+    GroupedQuery,
+    /// All query heads followed by a single key head and a single value head (Falcon-7B)
+    MultiQuery,
+    /// Interleaved query, key and value for each head
+    MultiHead,
+}
+
+/// # Falcon self-attention
+/// Causal attention with a fused query, key and value projection. Falcon-7B uses multi-query attention (a single key
+/// and value head), the larger models grouped-query attention with `num_kv_heads` key and value heads. Positions are
+/// encoded with rotary embeddings, or with ALiBi attention biases if `alibi` is set in the configuration.
+pub struct FalconAttention {
+    query_key_value: QuantizableLinear,
+    dense: QuantizableLinear,
+    attention_dropout: Dropout,
+    layout: FusedQKVLayout,
+    num_heads: i64,
+    num_kv_heads: i64,
+    head_dim: i64,
+    use_cache: bool,
+    output_attentions: bool,
+}
+
+impl FalconAttention {
+    pub fn new<'p, P>(p: P, config: &FalconConfig) -> FalconAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let num_heads = config.num_attention_heads;
+        let head_dim = config.head_dim();
+        let (layout, num_kv_heads) = if config.new_decoder_architecture.unwrap_or(false) {
+            (
+                FusedQKVLayout::GroupedQuery,
+                config.num_kv_heads.unwrap_or(num_heads),
+            )
+        } else if config.multi_query.unwrap_or(true) {
+            (FusedQKVLayout::MultiQuery, 1)
+        } else {
+            (FusedQKVLayout::MultiHead, num_heads)
+        };
+        assert_eq!(
+            num_heads % num_kv_heads,
+            0,
+            "Number of attention heads not a multiple of the number of key/value heads"
+        );
+
+        let linear_config = nn::LinearConfig {
+            bias: config.bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let query_key_value = nn::linear(
+            p / "query_key_value",
+            config.hidden_size,
+            (num_heads + 2 * num_kv_heads) * head_dim,
+            linear_config,
+        );
+        let dense = nn::linear(
+            p / "dense",
+            num_heads * head_dim,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let attention_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+
+        FalconAttention {
+            query_key_value: query_key_value.into(),
+            dense: dense.into(),
+            attention_dropout,
+            layout,
+            num_heads,
+            num_kv_heads,
+            head_dim,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.query_key_value.quantize_int4(config)?;
+        self.dense.quantize_int4(config)
+    }
+
+    /// Splits the fused projection into query, key and value tensors of shape
+    /// (*batch size*, *num heads*, *sequence_length*, *head_dim*), keys and values keeping `num_kv_heads` heads
+    fn split_heads(&self, fused_qkv: &Tensor) -> (Tensor, Tensor, Tensor) {
+        let (batch_size, sequence_length, _) = fused_qkv.size3().unwrap();
+        let (query, key, value) = match self.layout {
+            FusedQKVLayout::GroupedQuery => {
+                let group_size = self.num_heads / self.num_kv_heads;
+                let qkv = fused_qkv.view([
+                    batch_size,
+                    sequence_length,
+                    self.num_kv_heads,
+                    group_size + 2,
+                    self.head_dim,
+                ]);
+                (
+                    qkv.narrow(3, 0, group_size).reshape([
+                        batch_size,
+                        sequence_length,
+                        self.num_heads,
+                        self.head_dim,
+                    ]),
+                    qkv.select(3, group_size),
+                    qkv.select(3, group_size + 1),
+                )
+            }
+            FusedQKVLayout::MultiQuery => {
+                let qkv = fused_qkv.view([
+                    batch_size,
+                    sequence_length,
+                    self.num_heads + 2,
+                    self.head_dim,
+                ]);
+                (
+                    qkv.narrow(2, 0, self.num_heads),
+                    qkv.narrow(2, self.num_heads, 1),
+                    qkv.narrow(2, self.num_heads + 1, 1),
+                )
+            }
+            FusedQKVLayout::MultiHead => {
+                let qkv = fused_qkv.view([
+                    batch_size,
+                    sequence_length,
+                    self.num_heads,
+                    3,
+                    self.head_dim,
+                ]);
+                (qkv.select(3, 0), qkv.select(3, 1), qkv.select(3, 2))
+            }
+        };
+        (
+            query.transpose(1, 2),
+            key.transpose(1, 2),
+            value.transpose(1, 2),
+        )
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        position_bias: FalconPositionBias,
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (batch_size, sequence_length, _) = hidden_states.size3().unwrap();
+
+        let (query, key, value) = self.split_heads(&hidden_states.apply(&self.query_key_value));
+        let (query, mut key) = match position_bias {
+            FalconPositionBias::Rotary(cos, sin) => (
+                apply_rotary_pos_emb(&query, cos, sin),
+                apply_rotary_pos_emb(&key, cos, sin),
+            ),
+            FalconPositionBias::Alibi(_) => (query, key),
+        };
+        let mut value = value;
+
+        if let Some(layer_past) = layer_past {
+            key = Tensor::cat(&[&layer_past.prev_key, &key], -2);
+            value = Tensor::cat(&[&layer_past.prev_value, &value], -2);
+        }
+
+        let present = self.use_cache.then(|| LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        });
+
+        let num_repetitions = self.num_heads / self.num_kv_heads;
+        let key = repeat_kv(&key, num_repetitions);
+        let value = repeat_kv(&value, num_repetitions);
+
+        let mut attention_scores = attention_scores(&query, &key.transpose(-1, -2));
+        if let FalconPositionBias::Alibi(alibi) = position_bias {
+            attention_scores = attention_scores + alibi;
+        }
+        attention_scores = attention_scores / (self.head_dim as f64).sqrt();
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores + attention_mask;
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_output(&attention_weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.dense);
+
+        let attention_weights = self.output_attentions.then_some(attention_weights);
+
+        (attention_output, present, attention_weights)
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Position information provided to the attention layers: rotary embeddings cosine and sine tables, or ALiBi biases
+pub enum FalconPositionBias<'a> {
+    /// Cosine and sine tables of the rotary embeddings
+    Rotary(&'a Tensor, &'a Tensor),
+    /// ALiBi attention biases of shape (*1*, *num_heads*, *query_length*, *key_length*)
+    Alibi(&'a Tensor),
+}
@@ -0,0 +1,871 @@
+// Copyright 2023 The Technology Innovation Institute and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::kind::get_min;
+use crate::common::mps;
+use crate::common::position_embeddings::AlibiBias;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::falcon::attention::{
+    build_causal_mask, FalconPositionBias, FalconRotaryEmbedding, LayerState,
+};
+use crate::falcon::transformer::FalconDecoderLayer;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, LanguageGenerator};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Device, Kind, Tensor};
+
+/// # Falcon Pretrained model weight files
+pub struct FalconModelResources;
+
+/// # Falcon Pretrained model config files
+pub struct FalconConfigResources;
+
+/// # Falcon Pretrained model vocab files
+pub struct FalconVocabResources;
+
+/// # Falcon Pretrained model special tokens map files
+pub struct FalconSpecialMap;
+
+impl FalconModelResources {
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-rw-1b>.
+    pub const FALCON_RW_1B: (&'static str, &'static str) = (
+        "falcon-rw-1b/model",
+        "https://huggingface.co/tiiuae/falcon-rw-1b/resolve/main/model.safetensors",
+    );
+}
+
+impl FalconConfigResources {
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-7b-instruct>.
+    pub const FALCON_7B_INSTRUCT: (&'static str, &'static str) = (
+        "falcon-7b-instruct/config",
+        "https://huggingface.co/tiiuae/falcon-7b-instruct/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-rw-1b>.
+    pub const FALCON_RW_1B: (&'static str, &'static str) = (
+        "falcon-rw-1b/config",
+        "https://huggingface.co/tiiuae/falcon-rw-1b/resolve/main/config.json",
+    );
+}
+
+impl FalconVocabResources {
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-7b-instruct>.
+    pub const FALCON_7B_INSTRUCT: (&'static str, &'static str) = (
+        "falcon-7b-instruct/tokenizer",
+        "https://huggingface.co/tiiuae/falcon-7b-instruct/resolve/main/tokenizer.json",
+    );
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-rw-1b>.
+    pub const FALCON_RW_1B: (&'static str, &'static str) = (
+        "falcon-rw-1b/tokenizer",
+        "https://huggingface.co/tiiuae/falcon-rw-1b/resolve/main/tokenizer.json",
+    );
+}
+
+impl FalconSpecialMap {
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-7b-instruct>.
+    pub const FALCON_7B_INSTRUCT: (&'static str, &'static str) = (
+        "falcon-7b-instruct/special",
+        "https://huggingface.co/tiiuae/falcon-7b-instruct/resolve/main/special_tokens_map.json",
+    );
+    /// Shared under Apache 2.0 license by the Technology Innovation Institute at <https://huggingface.co/tiiuae/falcon-rw-1b>.
+    pub const FALCON_RW_1B: (&'static str, &'static str) = (
+        "falcon-rw-1b/special",
+        "https://huggingface.co/tiiuae/falcon-rw-1b/resolve/main/special_tokens_map.json",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Falcon model configuration
+/// Defines the Falcon model architecture (e.g. number of layers, hidden layer size, attention variant...).
+/// Both the Transformers configuration names and the original `RefinedWeb` names (`n_layer`, `n_head`, `n_head_kv`) are accepted.
+pub struct FalconConfig {
+    pub vocab_size: i64,
+    #[serde(alias = "n_embed")]
+    pub hidden_size: i64,
+    #[serde(alias = "n_layer")]
+    pub num_hidden_layers: i64,
+    #[serde(alias = "n_head")]
+    pub num_attention_heads: i64,
+    #[serde(alias = "n_head_kv")]
+    pub num_kv_heads: Option<i64>,
+    pub ffn_hidden_size: Option<i64>,
+    pub activation: Option<Activation>,
+    pub max_position_embeddings: Option<i64>,
+    pub initializer_range: Option<f64>,
+    pub layer_norm_epsilon: Option<f64>,
+    pub rope_theta: Option<f64>,
+    pub alibi: Option<bool>,
+    pub multi_query: Option<bool>,
+    pub new_decoder_architecture: Option<bool>,
+    pub parallel_attn: Option<bool>,
+    pub num_ln_in_parallel_attn: Option<i64>,
+    pub bias: Option<bool>,
+    pub hidden_dropout: Option<f64>,
+    pub attention_dropout: Option<f64>,
+    pub tie_word_embeddings: Option<bool>,
+    pub use_cache: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    pub forced_bos_token_id: Option<i64>,
+    pub forced_eos_token_id: Option<i64>,
+}
+
+impl Config for FalconConfig {}
+
+impl Default for FalconConfig {
+    fn default() -> Self {
+        FalconConfig {
+            vocab_size: 65024,
+            hidden_size: 4544,
+            num_hidden_layers: 32,
+            num_attention_heads: 71,
+            num_kv_heads: None,
+            ffn_hidden_size: None,
+            activation: Some(Activation::gelu),
+            max_position_embeddings: Some(2048),
+            initializer_range: Some(0.02),
+            layer_norm_epsilon: Some(1e-5),
+            rope_theta: Some(10000.0),
+            alibi: Some(false),
+            multi_query: Some(true),
+            new_decoder_architecture: Some(false),
+            parallel_attn: Some(true),
+            num_ln_in_parallel_attn: None,
+            bias: Some(false),
+            hidden_dropout: Some(0.0),
+            attention_dropout: Some(0.0),
+            tie_word_embeddings: Some(true),
+            use_cache: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            bos_token_id: Some(11),
+            eos_token_id: Some(11),
+            pad_token_id: None,
+            decoder_start_token_id: None,
+            forced_bos_token_id: None,
+            forced_eos_token_id: None,
+        }
+    }
+}
+
+impl FalconConfig {
+    /// Dimension of each attention head (`hidden_size / num_attention_heads`)
+    pub fn head_dim(&self) -> i64 {
+        self.hidden_size / self.num_attention_heads
+    }
+
+    /// Dimension of the MLP hidden layer (`4 * hidden_size` unless set explicitly)
+    pub fn ffn_hidden_size(&self) -> i64 {
+        self.ffn_hidden_size.unwrap_or(4 * self.hidden_size)
+    }
+
+    /// Maximum sequence length supported by the model (2048 unless set explicitly)
+    pub fn max_position_embeddings(&self) -> i64 {
+        self.max_position_embeddings.unwrap_or(2048)
+    }
+}
+
+/// # Falcon Base model
+/// Base architecture for Falcon models. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `word_embeddings`: `token` embeddings
+/// - `h`: Decoder made of a vector of layers. Each layer is made of a multi-query (Falcon-7B) or grouped-query (new decoder
+///   architecture) attention layer with rotary position embeddings or ALiBi biases, layer normalizations and an MLP,
+///   the attention and MLP usually running in parallel.
+/// - `ln_f`: Final layer normalization
+pub struct FalconModel {
+    word_embeddings: nn::Embedding,
+    h: Vec<FalconDecoderLayer>,
+    ln_f: nn::LayerNorm,
+    rotary_embedding: Option<FalconRotaryEmbedding>,
+    alibi: Option<AlibiBias>,
+    use_cache: bool,
+    output_hidden_states: bool,
+    output_attentions: bool,
+}
+
+impl FalconModel {
+    /// Build a new `FalconModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Falcon model
+    /// * `config` - `FalconConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::falcon::{FalconConfig, FalconModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = FalconConfig::from_file(config_path);
+    /// let falcon: FalconModel = FalconModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &FalconConfig) -> FalconModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "transformer";
+
+        let word_embeddings = embedding(
+            &p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let mut h: Vec<FalconDecoderLayer> = vec![];
+        let layers_path = &p / "h";
+        for layer_index in 0..config.num_hidden_layers {
+            h.push(FalconDecoderLayer::new(&layers_path / layer_index, config));
+        }
+
+        let ln_f = nn::layer_norm(
+            &p / "ln_f",
+            vec![config.hidden_size],
+            nn::LayerNormConfig {
+                eps: config.layer_norm_epsilon.unwrap_or(1e-5),
+                ..Default::default()
+            },
+        );
+
+        let (rotary_embedding, alibi) = if config.alibi.unwrap_or(false) {
+            (None, Some(AlibiBias::new(config.num_attention_heads)))
+        } else {
+            (
+                Some(FalconRotaryEmbedding::new(
+                    config.head_dim(),
+                    config.rope_theta.unwrap_or(10000.0),
+                    p.device(),
+                )),
+                None,
+            )
+        };
+
+        FalconModel {
+            word_embeddings,
+            h,
+            ln_f,
+            rotary_embedding,
+            alibi,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the decoder layers to int4 in place.
+    /// The embeddings and normalization layers are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.h.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - Optional vector of length *num_hidden_layers* containing the past keys and values of each layer of shape (*batch size*, *number of key/value heads*, *past_sequence_length*, *hidden size per head*). When provided, these are concatenated with the current input keys and values.
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*) used by the rotary embeddings. If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `FalconModelOutput` containing:
+    ///   - `output` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *num_hidden_layers* containing the past keys and values of each layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *number of heads*, *sequence_length*, *past_sequence_length + sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::falcon::{FalconConfig, FalconModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = FalconConfig::from_file(config_path);
+    /// # let falcon_model: FalconModel = FalconModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     falcon_model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             None,
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<FalconModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+
+        let (layer_past, past_length) = match layer_past {
+            Some(value) => {
+                if value.len() != self.h.len() {
+                    return Err(RustBertError::ValueError(format!(
+                        "Past activations vector length ({}) must be equal to the number of layers ({})",
+                        value.len(),
+                        self.h.len()
+                    )));
+                } else {
+                    let past_length = value
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|layer_state| layer_state.prev_key.size()[2])
+                        .unwrap_or(0);
+                    (value, past_length)
+                }
+            }
+            None => {
+                let mut out = Vec::with_capacity(self.h.len());
+                out.resize_with(self.h.len(), || None);
+                (out, 0)
+            }
+        };
+        let key_length = past_length + sequence_length;
+
+        let mut masked_positions = build_causal_mask(sequence_length, key_length, device).view([
+            1,
+            1,
+            sequence_length,
+            key_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            masked_positions =
+                masked_positions.logical_or(&attention_mask.view([batch_size, 1, 1, -1]).eq(0));
+        }
+        let kind = input_embeddings.kind();
+        let attention_mask = Tensor::zeros(masked_positions.size(), (kind, device))
+            .masked_fill(&masked_positions, get_min(kind)?);
+
+        let position_tables = self.rotary_embedding.as_ref().map(|rotary_embedding| {
+            let position_ids = match position_ids {
+                Some(value) => value.shallow_clone(),
+                None => Tensor::arange_start(past_length, key_length, (Kind::Int64, device))
+                    .unsqueeze(0)
+                    .expand([batch_size, sequence_length], true),
+            };
+            rotary_embedding.forward(&position_ids, kind)
+        });
+        let alibi = self.alibi.as_ref().map(|alibi| {
+            alibi
+                .forward(sequence_length, key_length, device)
+                .to_kind(kind)
+        });
+        let position_bias = match (&position_tables, &alibi) {
+            (Some((cos, sin)), _) => FalconPositionBias::Rotary(cos, sin),
+            (None, Some(alibi)) => FalconPositionBias::Alibi(alibi),
+            (None, None) => unreachable!(),
+        };
+
+        let mut hidden_state = input_embeddings.shallow_clone();
+
+        let mut all_presents: Option<Vec<Option<LayerState>>> = self.use_cache.then(Vec::new);
+        let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
+        let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
+
+        for (layer, past) in self.h.iter().zip(layer_past) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let temp = layer.forward_t(
+                &hidden_state,
+                Some(&attention_mask),
+                position_bias,
+                past.as_ref(),
+                train,
+            );
+            hidden_state = temp.0;
+            if let Some(presents) = all_presents.borrow_mut() {
+                presents.push(temp.1);
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(temp.2.unwrap());
+            };
+        }
+
+        let output = hidden_state.apply(&self.ln_f);
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(output.copy());
+        };
+
+        Ok(FalconModelOutput {
+            output,
+            cache: all_presents,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # Falcon Language Modeling head
+/// Falcon model with a decoding head (linear layer without bias). The weights of the linear layer are tied to the word
+/// embeddings unless `tie_word_embeddings` is set to false in the configuration.
+/// It is made of the following blocks:
+/// - `transformer`: Base FalconModel
+/// - `lm_head`: Optional linear layer projecting the hidden states to the vocabulary (untied weights only)
+pub struct FalconForCausalLM {
+    transformer: FalconModel,
+    lm_head: Option<QuantizableLinear>,
+}
+
+impl FalconForCausalLM {
+    /// Build a new `FalconForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Falcon model
+    /// * `config` - `FalconConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::falcon::{FalconConfig, FalconForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = FalconConfig::from_file(config_path);
+    /// let falcon: FalconForCausalLM = FalconForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &FalconConfig) -> FalconForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let transformer = FalconModel::new(p, config);
+        let lm_head = if config.tie_word_embeddings.unwrap_or(true) {
+            None
+        } else {
+            Some(
+                nn::linear(
+                    p / "lm_head",
+                    config.hidden_size,
+                    config.vocab_size,
+                    nn::LinearConfig {
+                        bias: false,
+                        ..Default::default()
+                    },
+                )
+                .into(),
+            )
+        };
+
+        FalconForCausalLM {
+            transformer,
+            lm_head,
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place. An untied language model head
+    /// is only quantized if `quantize_lm_head` is set in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.transformer.quantize_int4(config)?;
+        if config.quantize_lm_head {
+            if let Some(lm_head) = self.lm_head.as_mut() {
+                lm_head.quantize_int4(config)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - `Cache` containing the past keys and values of each layer (`Cache::FalconCache` or `Cache::None`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `cache` - `Cache::FalconCache` containing the past keys and values of each layer
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::FalconCache(layer_past) => self.transformer.forward_t(
+                input_ids,
+                layer_past,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            Cache::None => self.transformer.forward_t(
+                input_ids,
+                None,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with Falcon Model".into(),
+                ));
+            }
+        }?;
+
+        let lm_logits = match &self.lm_head {
+            Some(lm_head) => base_model_output.output.apply(lm_head),
+            None => base_model_output
+                .output
+                .linear::<Tensor>(&self.transformer.word_embeddings.ws, None),
+        };
+
+        Ok(LMModelOutput {
+            lm_logits,
+            cache: Cache::FalconCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
+        })
+    }
+}
+
+/// Container for the Falcon model output.
+pub struct FalconModelOutput {
+    /// Hidden state of the last layer of the decoder
+    pub output: Tensor,
+    /// Cached attention layers keys and values if the model is used for generation
+    pub cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the Falcon architecture
+pub struct FalconGenerator {
+    model: FalconForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl FalconGenerator {
+    /// Build a new `FalconGenerator`. Falcon tokenizers are not available from the `rust_tokenizers` crate: the
+    /// tokenizer should be loaded from a `tokenizer.json` file (see `TokenizerOption::from_hf_tokenizer_file`)
+    /// and the generator created with `new_with_tokenizer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    pub fn new(generate_config: GenerateConfig) -> Result<FalconGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Falcon,
+            vocab_path.to_str().unwrap(),
+            None,
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    /// Build a new `FalconGenerator` with a tokenizer
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer, usually loaded from a `tokenizer.json` file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::falcon::{
+    ///     FalconConfigResources, FalconGenerator, FalconSpecialMap, FalconVocabResources,
+    /// };
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::{LocalResource, RemoteResource, ResourceProvider};
+    /// use std::path::PathBuf;
+    ///
+    /// let vocab_resource = RemoteResource::from_pretrained(FalconVocabResources::FALCON_7B_INSTRUCT);
+    /// let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+    ///     vocab_resource.get_local_path()?,
+    ///     RemoteResource::from_pretrained(FalconSpecialMap::FALCON_7B_INSTRUCT).get_local_path()?,
+    /// )?;
+    /// let generate_config = GenerateConfig {
+    ///     model_type: ModelType::Falcon,
+    ///     model_resource: ModelResource::Torch(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     })),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(
+    ///         FalconConfigResources::FALCON_7B_INSTRUCT,
+    ///     )),
+    ///     vocab_resource: Box::new(vocab_resource),
+    ///     merges_resource: None,
+    ///     max_length: Some(128),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..Default::default()
+    /// };
+    /// let falcon_generator = FalconGenerator::new_with_tokenizer(generate_config, tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<FalconGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate()?;
+        let mut var_store = nn::VarStore::new(device);
+
+        let config = FalconConfig::from_file(config_path);
+        let model = FalconForCausalLM::new(var_store.root(), &config);
+        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+
+        let bos_token_id = tokenizer.get_bos_id().or(config.bos_token_id);
+        let eos_token_ids = tokenizer
+            .get_eos_id()
+            .or(config.eos_token_id)
+            .map(|id| vec![id]);
+        let pad_token_id = tokenizer.get_pad_id().or(config.pad_token_id);
+        let max_position_embeddings = config.max_position_embeddings();
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = config.decoder_start_token_id;
+
+        Ok(FalconGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+
+    /// Quantize the weights of the model to int4 in place (see `FalconForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+}
+
+impl PrivateLanguageGenerator for FalconGenerator {
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn _get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        &mut self.tokenizer
+    }
+    fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+    fn get_var_store_mut(&mut self) -> Result<&mut nn::VarStore, RustBertError> {
+        Ok(&mut self.var_store)
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> Option<i64> {
+        Some(self.max_position_embeddings)
+    }
+
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        self.model.forward_t(
+            input_ids,
+            layer_past,
+            attention_mask,
+            position_ids,
+            input_embeds,
+            train,
+        )
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        // Positions are counted from the first non-padding token of each (left-padded) prompt
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::FalconCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::FalconCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::FalconCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::FalconCache(None),
+            },
+            _ => panic!("Cache type incompatible with Falcon"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::FalconCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for Falcon model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator for FalconGenerator {}
@@ -0,0 +1,65 @@
+//! # Falcon (Almazrouei et al.)
+//!
+//! Implementation of the Falcon decoder architecture ([The Falcon Series of Open Language Models](https://arxiv.org/abs/2311.16867) Almazrouei, Alobeidli, Alshamsi et al., 2023).
+//! Falcon is a decoder-only model computing the attention and MLP blocks of each layer in parallel from the same input.
+//! Falcon-7B uses multi-query attention (a single key and value head shared by all query heads), while the larger models
+//! (new decoder architecture) use grouped-query attention with separate layer normalizations for the attention and MLP blocks.
+//! Positions are encoded with rotary embeddings, or with ALiBi attention biases for checkpoints setting `alibi` in their configuration.
+//! The base model is implemented in the `falcon_model::FalconModel` struct, the language modeling head in `falcon_model::FalconForCausalLM`
+//! and the text generation utilities in `falcon_model::FalconGenerator`, also available through the `TextGenerationModel` pipeline with `ModelType::Falcon`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights to the `.ot` format, for example with `python utils/convert_model.py path/to/falcon/model.safetensors`.
+//! - The BPE tokenizer should be loaded from a `tokenizer.json` file and a special tokens map using the `hf-tokenizers` feature
+//!   (see `TokenizerOption::from_hf_tokenizer_file`).
+//!
+//! Pretrained configuration and tokenizer files are available for Falcon-7B-Instruct and Falcon-RW-1B (ALiBi positions),
+//! with the weights of Falcon-RW-1B in `FalconModelResources`.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::falcon::{FalconConfig, FalconForCausalLM};
+//! use rust_bert::pipelines::common::TokenizerOption;
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+//!     "path/to/tokenizer.json",
+//!     "path/to/special_tokens_map.json",
+//! )?;
+//! let config = FalconConfig::from_file(config_path);
+//! let falcon_model = FalconForCausalLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod falcon_model;
+mod transformer;
+
+pub use attention::{FalconAttention, FalconPositionBias, FalconRotaryEmbedding, LayerState};
+pub use falcon_model::{
+    FalconConfig, FalconConfigResources, FalconForCausalLM, FalconGenerator, FalconModel,
+    FalconModelOutput, FalconModelResources, FalconSpecialMap, FalconVocabResources,
+};
+pub use transformer::{FalconDecoderLayer, FalconMLP};
@@ -0,0 +1,187 @@
+// Copyright 2023 The Technology Innovation Institute and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::{Activation, TensorFunction};
+use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::falcon::attention::{FalconAttention, FalconPositionBias, LayerState};
+use crate::falcon::falcon_model::FalconConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+/// # Falcon feed-forward block
+/// Two-layer MLP: `dense_4h_to_h(activation(dense_h_to_4h(x)))`
+pub struct FalconMLP {
+    dense_h_to_4h: QuantizableLinear,
+    dense_4h_to_h: QuantizableLinear,
+    activation: TensorFunction,
+}
+
+impl FalconMLP {
+    pub fn new<'p, P>(p: P, config: &FalconConfig) -> FalconMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let ffn_hidden_size = config.ffn_hidden_size();
+        let dense_h_to_4h = nn::linear(
+            p / "dense_h_to_4h",
+            config.hidden_size,
+            ffn_hidden_size,
+            linear_config,
+        );
+        let dense_4h_to_h = nn::linear(
+            p / "dense_4h_to_h",
+            ffn_hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let activation = config.activation.unwrap_or(Activation::gelu).get_function();
+
+        FalconMLP {
+            dense_h_to_4h: dense_h_to_4h.into(),
+            dense_4h_to_h: dense_4h_to_h.into(),
+            activation,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.dense_h_to_4h.quantize_int4(config)?;
+        self.dense_4h_to_h.quantize_int4(config)
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (self.activation.get_fn())(&hidden_states.apply(&self.dense_h_to_4h))
+            .apply(&self.dense_4h_to_h)
+    }
+}
+
+/// # Falcon decoder layer
+/// The attention and MLP blocks either run in parallel from the same input (`parallel_attn`, new decoder architecture),
+/// or sequentially as in GPT-2. Parallel blocks of the new decoder architecture normalize the inputs of the attention
+/// and MLP with separate layer normalizations (`ln_attn` and `ln_mlp`) unless `num_ln_in_parallel_attn` is set to 1.
+pub struct FalconDecoderLayer {
+    attention_layernorm: nn::LayerNorm,
+    mlp_layernorm: Option<nn::LayerNorm>,
+    self_attention: FalconAttention,
+    mlp: FalconMLP,
+    hidden_dropout: Dropout,
+    parallel: bool,
+}
+
+impl FalconDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &FalconConfig) -> FalconDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let new_decoder_architecture = config.new_decoder_architecture.unwrap_or(false);
+        let parallel = new_decoder_architecture || config.parallel_attn.unwrap_or(true);
+        let separate_layer_norms =
+            new_decoder_architecture && config.num_ln_in_parallel_attn.unwrap_or(2) == 2;
+
+        let (attention_layernorm, mlp_layernorm) = if separate_layer_norms {
+            (
+                nn::layer_norm(p / "ln_attn", vec![config.hidden_size], layer_norm_config),
+                Some(nn::layer_norm(
+                    p / "ln_mlp",
+                    vec![config.hidden_size],
+                    layer_norm_config,
+                )),
+            )
+        } else {
+            let input_layernorm = nn::layer_norm(
+                p / "input_layernorm",
+                vec![config.hidden_size],
+                layer_norm_config,
+            );
+            let post_attention_layernorm = (!parallel).then(|| {
+                nn::layer_norm(
+                    p / "post_attention_layernorm",
+                    vec![config.hidden_size],
+                    layer_norm_config,
+                )
+            });
+            (input_layernorm, post_attention_layernorm)
+        };
+        let self_attention = FalconAttention::new(p / "self_attention", config);
+        let mlp = FalconMLP::new(p / "mlp", config);
+        let hidden_dropout = Dropout::new(config.hidden_dropout.unwrap_or(0.0));
+
+        FalconDecoderLayer {
+            attention_layernorm,
+            mlp_layernorm,
+            self_attention,
+            mlp,
+            hidden_dropout,
+            parallel,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attention.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        position_bias: FalconPositionBias,
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let attention_input = hidden_states.apply(&self.attention_layernorm);
+        let (attention_output, present, attention_weights) = self.self_attention.forward_t(
+            &attention_input,
+            attention_mask,
+            position_bias,
+            layer_past,
+            train,
+        );
+
+        let hidden_states = if self.parallel {
+            let mlp_input = match &self.mlp_layernorm {
+                Some(mlp_layernorm) => hidden_states.apply(mlp_layernorm),
+                None => attention_input,
+            };
+            let mlp_output = self.mlp.forward(&mlp_input) + attention_output;
+            hidden_states + mlp_output.apply_t(&self.hidden_dropout, train)
+        } else {
+            let hidden_states = hidden_states + attention_output;
+            let mlp_output = self
+                .mlp
+                .forward(&hidden_states.apply(self.mlp_layernorm.as_ref().unwrap()));
+            &hidden_states + mlp_output.apply_t(&self.hidden_dropout, train)
+        };
+
+        (hidden_states, present, attention_weights)
+    }
+}
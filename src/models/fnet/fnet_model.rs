@@ -11,9 +11,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::activations::{TensorFunction, _tanh};
+use crate::common::activations::{_tanh, TensorFunction};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::weight_tying::TiedWeights;
 use crate::fnet::embeddings::FNetEmbeddings;
 use crate::fnet::encoder::FNetEncoder;
 use crate::{Activation, Config, RustBertError};
@@ -364,6 +365,13 @@ pub struct FNetForMaskedLM {
 }
 
 impl FNetForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "fnet.embeddings.word_embeddings.weight",
+        "cls.predictions.decoder.weight",
+    )
+    .with_bias("cls.predictions.decoder.bias", "cls.predictions.bias");
+
     /// Build a new `FNetForMaskedLM`
     ///
     /// # Arguments
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
 use crate::gpt2::gpt2_model::Gpt2Config;
 use std::borrow::Borrow;
 use tch::kind::Kind::Float;
@@ -131,7 +132,7 @@ impl Attention {
         attention_mask: Option<&Tensor>,
         train: bool,
     ) -> (Tensor, Option<Tensor>) {
-        let mut w = query.matmul(key);
+        let mut w = attention_scores(query, key);
         if self.scale {
             w = w / (*value.size().last().unwrap() as f64).sqrt();
         }
@@ -144,7 +145,7 @@ impl Attention {
         }
         w = w.softmax(-1, w.kind()).apply_t(&self.attn_dropout, train);
 
-        let output = w.matmul(value);
+        let output = attention_output(&w, value);
 
         if self.output_attentions {
             (output, Some(w))
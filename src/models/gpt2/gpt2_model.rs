@@ -15,6 +15,7 @@
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::mps;
 use crate::gpt2::transformer::Block;
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
@@ -558,6 +559,7 @@ impl GPT2LMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::GPT2Cache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
         })
     }
 }
@@ -647,7 +649,7 @@ impl GPT2Generator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = Gpt2Config::from_file(config_path);
@@ -760,7 +762,7 @@ impl PrivateLanguageGenerator for GPT2Generator {
         past: Cache,
         attention_mask: Tensor,
     ) -> PreparedInput<'a> {
-        let position_ids = (attention_mask.totype(Kind::Int64).cumsum(-1, Kind::Int64) - 1)
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
             .masked_fill(&attention_mask.eq(0), 1);
 
         match past {
@@ -12,10 +12,14 @@
 
 use crate::common::dropout::Dropout;
 use crate::common::kind::get_min;
+use crate::common::precision::attention_output;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::scratch;
+use crate::common::tensor_parallel::TensorParallelConfig;
 use crate::gpt_j::gpt_j_model::GptJConfig;
+use crate::RustBertError;
 use std::borrow::Borrow;
-use tch::nn::Linear;
-use tch::{nn, IndexOp, Kind, NewAxis, Tensor};
+use tch::{nn, Device, IndexOp, Kind, NewAxis, Tensor};
 
 #[derive(Debug)]
 /// # Cache for GPT-J attention layers
@@ -38,9 +42,17 @@ impl Clone for LayerState {
 
 impl LayerState {
     pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        let new_indices = &new_indices.to_device(self.prev_key.device());
         self.prev_key = self.prev_key.index_select(0, new_indices);
         self.prev_value = self.prev_value.index_select(0, new_indices);
     }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self.prev_value.narrow(-2, 0, length),
+        }
+    }
 }
 
 pub struct GptJAttention {
@@ -48,10 +60,10 @@ pub struct GptJAttention {
     attn_dropout: Dropout,
     resid_dropout: Dropout,
     scale_attn: f32,
-    k_proj: Linear,
-    v_proj: Linear,
-    q_proj: Linear,
-    out_proj: Linear,
+    k_proj: QuantizableLinear,
+    v_proj: QuantizableLinear,
+    q_proj: QuantizableLinear,
+    out_proj: QuantizableLinear,
     output_attentions: bool,
     dim_per_head: i64,
     n_head: i64,
@@ -117,10 +129,10 @@ impl GptJAttention {
             resid_dropout,
             output_attentions,
             scale_attn,
-            k_proj,
-            v_proj,
-            q_proj,
-            out_proj,
+            k_proj: k_proj.into(),
+            v_proj: v_proj.into(),
+            q_proj: q_proj.into(),
+            out_proj: out_proj.into(),
             dim_per_head,
             n_head: config.n_head,
             rotary_dim: config.rotary_dim,
@@ -129,6 +141,30 @@ impl GptJAttention {
         }
     }
 
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.k_proj.quantize_int4(config)?;
+        self.v_proj.quantize_int4(config)?;
+        self.q_proj.quantize_int4(config)?;
+        self.out_proj.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        config.validate(self.n_head, "attention heads")?;
+        self.k_proj
+            .split_columns(&config.devices, self.dim_per_head)?;
+        self.v_proj
+            .split_columns(&config.devices, self.dim_per_head)?;
+        self.q_proj
+            .split_columns(&config.devices, self.dim_per_head)?;
+        self.out_proj.split_rows(&config.devices, self.dim_per_head)
+    }
+
     fn split_heads(
         tensor: &Tensor,
         num_heads: i64,
@@ -170,6 +206,14 @@ impl GptJAttention {
         tensor.view(new_shape.as_slice())
     }
 
+    fn causal_mask(&self, query_length: i64, key_length: i64, device: Device) -> Tensor {
+        self.bias
+            .slice(2, key_length - query_length, key_length, 1)
+            .slice(3, 0, key_length, 1)
+            .to_kind(Kind::Bool)
+            .to_device(device)
+    }
+
     fn attention(
         &self,
         query: &Tensor,
@@ -188,12 +232,7 @@ impl GptJAttention {
         let query_length = query_dims[query_dims.len() - 2];
         let key_length = key_dims[key_dims.len() - 2];
 
-        let causal_mask = &self
-            .bias
-            .slice(2, key_length - query_length, key_length, 1)
-            .slice(3, 0, key_length, 1)
-            .to_kind(Kind::Bool)
-            .to_device(attention_weights.device());
+        let causal_mask = &self.causal_mask(query_length, key_length, attention_weights.device());
 
         let mask_value = get_min(attention_weights.kind()).unwrap();
         let mask_value = Tensor::full(
@@ -214,7 +253,7 @@ impl GptJAttention {
             .to_kind(value.kind())
             .apply_t(&self.attn_dropout, train);
 
-        let attention_output = attention_weights.matmul(value);
+        let attention_output = attention_output(&attention_weights, value);
 
         (attention_output, attention_weights)
     }
@@ -274,14 +313,42 @@ impl GptJAttention {
             prev_value: value.copy(),
         });
 
-        let (attn_output, attn_weights) =
-            self.attention(&query, &key, &value, attention_mask, train);
+        let scratch_output = if train || self.output_attentions {
+            None
+        } else {
+            let (query_length, key_length) = (query.size()[2], key.size()[2]);
+            scratch::causal_attention(
+                &query.to_kind(Kind::Float),
+                &key.to_kind(Kind::Float),
+                &value,
+                &self.causal_mask(query_length, key_length, query.device()),
+                get_min(Kind::Float).unwrap(),
+                self.scale.then_some(self.scale_attn as f64),
+                attention_mask,
+            )
+        };
+
+        let (attn_output, attn_weights) = match scratch_output {
+            Some(merged_heads) => (merged_heads, None),
+            None => {
+                let (attn_output, attn_weights) =
+                    self.attention(&query, &key, &value, attention_mask, train);
+                (
+                    Self::merge_heads(&attn_output, self.n_head, self.dim_per_head),
+                    Some(attn_weights),
+                )
+            }
+        };
 
-        let attn_output = Self::merge_heads(&attn_output, self.n_head, self.dim_per_head)
+        let attn_output = attn_output
             .apply(&self.out_proj)
             .apply_t(&self.resid_dropout, train);
 
-        let attn_weights = self.output_attentions.then_some(attn_weights);
+        let attn_weights = if self.output_attentions {
+            attn_weights
+        } else {
+            None
+        };
 
         (attn_output, present, attn_weights)
     }
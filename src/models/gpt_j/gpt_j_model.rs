@@ -11,9 +11,14 @@
 // limitations under the License.
 
 use crate::common::activations::Activation;
+use crate::common::device_map::{
+    dispatch_var_store, to_layer_placement, DeviceMap, LayerPlacement,
+};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
 use crate::common::kind::get_min;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::TensorParallelConfig;
 use crate::gpt_j::attention::LayerState;
 use crate::gpt_j::transformer::GptJBlock;
 use crate::pipelines::common::{ModelType, TokenizerOption};
@@ -24,7 +29,7 @@ use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, L
 use crate::{Config, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::borrow::{Borrow, BorrowMut};
-use tch::nn::{embedding, Linear};
+use tch::nn::embedding;
 use tch::{nn, Device, Tensor};
 
 /// # GPT-J Pretrained model weight files
@@ -197,6 +202,7 @@ pub struct GptJModel {
     use_cache: bool,
     output_hidden_states: bool,
     output_attentions: bool,
+    layer_placements: Option<Vec<LayerPlacement>>,
 }
 
 impl GptJModel {
@@ -267,7 +273,58 @@ impl GptJModel {
             use_cache,
             output_hidden_states,
             output_attentions,
+            layer_placements: None,
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the transformer blocks to int4 in place.
+    /// The embeddings and layer normalizations are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.h.iter_mut() {
+            layer.quantize_int4(config)?;
         }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the transformer blocks across devices in place (tensor parallelism).
+    /// The embeddings and layer normalizations remain on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        for layer in self.h.iter_mut() {
+            layer.tensor_parallelize(config)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn num_layers(&self) -> usize {
+        self.h.len()
+    }
+
+    /// Sets the devices and precisions the hidden states are moved to before each transformer block, once the weights
+    /// of the blocks have been moved to these placements (see the `device_map` module)
+    pub(crate) fn set_layer_placements(
+        &mut self,
+        layer_placements: Vec<LayerPlacement>,
+    ) -> Result<(), RustBertError> {
+        if layer_placements.len() != self.h.len() {
+            return Err(RustBertError::ValueError(format!(
+                "Expected {} layer placements, got {}",
+                self.h.len(),
+                layer_placements.len()
+            )));
+        }
+        self.layer_placements = Some(layer_placements);
+        Ok(())
     }
 
     /// Forward pass through the model
@@ -405,9 +462,25 @@ impl GptJModel {
         let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
         let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
 
-        for (layer, past) in self.h.iter().zip(layer_past) {
-            let temp =
-                layer.forward_t(&hidden_state, past.as_ref(), attention_mask.as_ref(), train);
+        for (layer_idx, (layer, past)) in self.h.iter().zip(layer_past).enumerate() {
+            let temp = match &self.layer_placements {
+                Some(layer_placements) => {
+                    let (layer_input, layer_attention_mask) = to_layer_placement(
+                        &hidden_state,
+                        attention_mask.as_ref(),
+                        &layer_placements[layer_idx],
+                    )?;
+                    layer.forward_t(
+                        &layer_input,
+                        past.as_ref(),
+                        layer_attention_mask.as_ref(),
+                        train,
+                    )
+                }
+                None => {
+                    layer.forward_t(&hidden_state, past.as_ref(), attention_mask.as_ref(), train)
+                }
+            };
             hidden_state = temp.0;
             if let Some(presents) = all_presents.borrow_mut() {
                 presents.push(temp.1);
@@ -420,7 +493,10 @@ impl GptJModel {
             };
         }
 
-        let output = hidden_state.apply(&self.ln_f);
+        let output = hidden_state
+            .to_device(input_embeddings.device())
+            .to_kind(input_embeddings.kind())
+            .apply(&self.ln_f);
 
         Ok(GptJModelOutput {
             output,
@@ -437,7 +513,7 @@ impl GptJModel {
 /// - `transformer`: Base GptJModel
 pub struct GptJLMHeadModel {
     transformer: GptJModel,
-    lm_head: Linear,
+    lm_head: QuantizableLinear,
 }
 
 impl GptJLMHeadModel {
@@ -481,8 +557,46 @@ impl GptJLMHeadModel {
 
         GptJLMHeadModel {
             transformer,
-            lm_head,
+            lm_head: lm_head.into(),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place. The language model head
+    /// is only quantized if `quantize_lm_head` is set in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.transformer.quantize_int4(config)?;
+        if config.quantize_lm_head {
+            self.lm_head.quantize_int4(config)?;
         }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the model across devices in place (tensor parallelism).
+    /// The language model head remains on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.transformer.tensor_parallelize(config)
+    }
+
+    pub(crate) fn num_layers(&self) -> usize {
+        self.transformer.num_layers()
+    }
+
+    pub(crate) fn set_layer_placements(
+        &mut self,
+        layer_placements: Vec<LayerPlacement>,
+    ) -> Result<(), RustBertError> {
+        self.transformer.set_layer_placements(layer_placements)
     }
 
     pub fn forward_t(
@@ -528,6 +642,7 @@ impl GptJLMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::GPTJCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
         })
     }
 }
@@ -617,7 +732,7 @@ impl GptJGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = GptJConfig::from_file(config_path);
@@ -652,6 +767,39 @@ impl GptJGenerator {
             max_position_embeddings,
         })
     }
+
+    /// Quantize the weights of the model to int4 in place (see `GptJLMHeadModel::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+
+    /// Split the layers of the model across devices in place (see `GptJLMHeadModel::tensor_parallelize`).
+    /// The model should be loaded on the first device of the configuration.
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+
+    /// Partitions the transformer blocks of the model across devices in place (see the `device_map` module). The
+    /// embeddings and language model head are moved to the placement of the embeddings of the map, whose device
+    /// becomes the device of the model. The device map should be applied before quantization or tensor parallelism.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_map` - `DeviceMap` defining the device of each transformer block
+    pub fn set_device_map(&mut self, device_map: &DeviceMap) -> Result<(), RustBertError> {
+        let layer_placements = device_map.layer_placements(self.model.num_layers())?;
+        dispatch_var_store(
+            &mut self.var_store,
+            "transformer.h.",
+            &layer_placements,
+            &device_map.embeddings,
+        )?;
+        self.model.set_layer_placements(layer_placements)
+    }
 }
 
 impl PrivateLanguageGenerator for GptJGenerator {
@@ -735,6 +883,7 @@ impl PrivateLanguageGenerator for GptJGenerator {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::GPTJCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
         })
     }
 
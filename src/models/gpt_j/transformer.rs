@@ -12,15 +12,17 @@
 
 use crate::common::activations::{Activation, TensorFunction};
 use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::{mlp_forward, TensorParallelConfig};
 use crate::gpt_j::attention::{GptJAttention, LayerState};
 use crate::gpt_j::gpt_j_model::GptJConfig;
+use crate::RustBertError;
 use std::borrow::Borrow;
-use tch::nn::Linear;
 use tch::{nn, Tensor};
 
 pub struct GptJMLP {
-    fc_in: Linear,
-    fc_out: Linear,
+    fc_in: QuantizableLinear,
+    fc_out: QuantizableLinear,
     activation: TensorFunction,
     dropout: Dropout,
 }
@@ -69,16 +71,37 @@ impl GptJMLP {
         let dropout = Dropout::new(resid_pdrop);
 
         GptJMLP {
-            fc_in,
-            fc_out,
+            fc_in: fc_in.into(),
+            fc_out: fc_out.into(),
             activation,
             dropout,
         }
     }
 
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.fc_in.quantize_int4(config)?;
+        self.fc_out.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.fc_in.split_columns(&config.devices, 1)?;
+        self.fc_out.split_rows(&config.devices, 1)
+    }
+
     pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
-        let h = (self.activation.get_fn())(&hidden_states.apply(&self.fc_in));
-        h.apply(&self.fc_out).apply_t(&self.dropout, train)
+        mlp_forward(
+            &self.fc_in,
+            &self.fc_out,
+            self.activation.get_fn(),
+            hidden_states,
+        )
+        .apply_t(&self.dropout, train)
     }
 }
 
@@ -109,6 +132,22 @@ impl GptJBlock {
         GptJBlock { ln_1, attn, mlp }
     }
 
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.attn.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.attn.tensor_parallelize(config)?;
+        self.mlp.tensor_parallelize(config)
+    }
+
     pub fn forward_t(
         &self,
         hidden_states: &Tensor,
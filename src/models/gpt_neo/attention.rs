@@ -10,11 +10,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::attention::{Attention, DenseAttention, LocalAttention};
 use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::scratch;
+use crate::common::tensor_parallel::TensorParallelConfig;
 use crate::gpt_neo::gpt_neo_model::AttentionLayerType;
 use crate::gpt_neo::GptNeoConfig;
+use crate::RustBertError;
 use std::borrow::Borrow;
-use tch::{nn, Kind, Tensor};
+use tch::{nn, Kind, Scalar, Tensor};
 
 #[derive(Debug)]
 /// # Cache for GPT-Neo attention layers
@@ -37,22 +42,32 @@ impl Clone for LayerState {
 
 impl LayerState {
     pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        let new_indices = &new_indices.to_device(self.prev_key.device());
         self.prev_key = self.prev_key.index_select(0, new_indices);
         self.prev_value = self
             .prev_value
             .as_ref()
             .map(|value| value.index_select(0, new_indices));
     }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self
+                .prev_value
+                .as_ref()
+                .map(|value| value.narrow(-2, 0, length)),
+        }
+    }
 }
 
 pub struct GptNeoSelfAttention {
-    k_proj: nn::Linear,
-    v_proj: nn::Linear,
-    q_proj: nn::Linear,
-    out_proj: nn::Linear,
-    attention_dropout: Dropout,
+    k_proj: QuantizableLinear,
+    v_proj: QuantizableLinear,
+    q_proj: QuantizableLinear,
+    out_proj: QuantizableLinear,
+    attention: Box<dyn Attention>,
     resid_dropout: Dropout,
-    bias: Tensor,
     num_heads: i64,
     head_dim: i64,
     output_attentions: bool,
@@ -68,18 +83,17 @@ impl GptNeoSelfAttention {
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
-        let max_positions = config.max_position_embeddings;
 
-        let mut bias = Tensor::ones([max_positions, max_positions], (Kind::Uint8, p.device()))
-            .tril(0)
-            .view([1, 1, max_positions, max_positions])
-            .requires_grad_(false);
-
-        if attention_type == &AttentionLayerType::Local {
-            let _ = bias.bitwise_or_tensor_(&bias.tril(-config.window_size));
-        }
-
-        let attention_dropout = Dropout::new(config.attention_dropout);
+        let attention: Box<dyn Attention> = match attention_type {
+            AttentionLayerType::Global => {
+                Box::new(DenseAttention::new(config.attention_dropout, None, true))
+            }
+            AttentionLayerType::Local => Box::new(LocalAttention::new(
+                config.window_size,
+                config.attention_dropout,
+                None,
+            )),
+        };
         let resid_dropout = Dropout::new(config.resid_dropout);
 
         let num_heads = config.num_heads;
@@ -117,19 +131,39 @@ impl GptNeoSelfAttention {
         let output_attentions = config.output_attentions.unwrap_or(false);
 
         GptNeoSelfAttention {
-            k_proj,
-            v_proj,
-            q_proj,
-            out_proj,
-            attention_dropout,
+            k_proj: k_proj.into(),
+            v_proj: v_proj.into(),
+            q_proj: q_proj.into(),
+            out_proj: out_proj.into(),
+            attention,
             resid_dropout,
-            bias,
             num_heads,
             head_dim,
             output_attentions,
         }
     }
 
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.k_proj.quantize_int4(config)?;
+        self.v_proj.quantize_int4(config)?;
+        self.q_proj.quantize_int4(config)?;
+        self.out_proj.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        config.validate(self.num_heads, "attention heads")?;
+        self.k_proj.split_columns(&config.devices, self.head_dim)?;
+        self.v_proj.split_columns(&config.devices, self.head_dim)?;
+        self.q_proj.split_columns(&config.devices, self.head_dim)?;
+        self.out_proj.split_rows(&config.devices, self.head_dim)
+    }
+
     fn split_heads(input_tensor: &Tensor, num_heads: i64, attention_head_size: i64) -> Tensor {
         let mut new_shape = input_tensor.size();
         let _ = new_shape.pop();
@@ -146,47 +180,6 @@ impl GptNeoSelfAttention {
         output_tensor.view(new_shape.as_slice())
     }
 
-    fn attend(
-        &self,
-        query: &Tensor,
-        key: &Tensor,
-        value: &Tensor,
-        attention_mask: Option<&Tensor>,
-        train: bool,
-    ) -> (Tensor, Tensor) {
-        let query = query.to_kind(Kind::Float);
-        let key = key.to_kind(Kind::Float);
-
-        let attention_weights = query.matmul(&key.transpose(-1, -2));
-
-        let query_dims = query.size();
-        let key_dims = key.size();
-        let query_length = query_dims[query_dims.len() - 2];
-        let key_length = key_dims[key_dims.len() - 2];
-
-        let causal_mask = &self
-            .bias
-            .slice(2, key_length - query_length, key_length, 1)
-            .slice(3, 0, key_length, 1)
-            .to_kind(Kind::Bool)
-            .to_device(attention_weights.device());
-
-        let mut attention_weights = attention_weights.where_self(
-            causal_mask,
-            &Tensor::from_slice(&[-1e9f32]).to_device(attention_weights.device()),
-        );
-        if let Some(attention_mask_value) = attention_mask {
-            attention_weights = attention_weights + attention_mask_value;
-        };
-
-        let attention_weights = attention_weights.softmax(-1, attention_weights.kind());
-        let attention_weights = attention_weights
-            .to_kind(value.kind())
-            .apply_t(&self.attention_dropout, train);
-        let attention_output = attention_weights.matmul(value);
-        (attention_output, attention_weights)
-    }
-
     pub fn forward_t(
         &self,
         hidden_states: &Tensor,
@@ -215,19 +208,48 @@ impl GptNeoSelfAttention {
             prev_value: Some(value.copy()),
         });
 
-        let (attention_output, attention_weights) =
-            self.attend(&query, &key, &value, attention_mask, train);
+        // Attentions without a pattern mask are not causal and are not supported by the scratch kernel
+        let scratch_output = if train || self.output_attentions {
+            None
+        } else {
+            let (query_length, key_length) = (query.size()[2], key.size()[2]);
+            self.attention
+                .pattern_mask(query_length, key_length, query.device())
+                .and_then(|pattern_mask| {
+                    scratch::causal_attention(
+                        &query.to_kind(Kind::Float),
+                        &key.to_kind(Kind::Float),
+                        &value,
+                        &pattern_mask,
+                        Scalar::float(-1e9),
+                        None,
+                        attention_mask,
+                    )
+                })
+        };
+
+        let (attention_output, attention_weights) = match scratch_output {
+            Some(merged_heads) => (merged_heads, None),
+            None => {
+                let (attention_output, attention_weights) = self.attention.forward_t(
+                    &query.to_kind(Kind::Float),
+                    &key.to_kind(Kind::Float),
+                    &value,
+                    attention_mask,
+                    self.output_attentions,
+                    train,
+                );
+                (
+                    Self::merge_heads(&attention_output, self.num_heads, self.head_dim),
+                    attention_weights,
+                )
+            }
+        };
 
-        let attention_output = Self::merge_heads(&attention_output, self.num_heads, self.head_dim)
+        let attention_output = attention_output
             .apply(&self.out_proj)
             .apply_t(&self.resid_dropout, train);
 
-        let attention_weights = if self.output_attentions {
-            Some(attention_weights)
-        } else {
-            None
-        };
-
         (attention_output, attention_weights, layer_state)
     }
 }
@@ -12,6 +12,8 @@
 
 use crate::common::activations::TensorFunction;
 use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::{mlp_forward, TensorParallelConfig};
 use crate::gpt_neo::attention::{GptNeoSelfAttention, LayerState};
 use crate::gpt_neo::GptNeoConfig;
 use crate::RustBertError;
@@ -21,8 +23,8 @@ use tch::{nn, Tensor};
 
 #[derive(Debug)]
 pub struct GptNeoMLP {
-    c_fc: nn::Linear,
-    c_proj: nn::Linear,
+    c_fc: QuantizableLinear,
+    c_proj: QuantizableLinear,
     activation_function: TensorFunction,
     dropout: Dropout,
 }
@@ -51,21 +53,39 @@ impl GptNeoMLP {
         let dropout = Dropout::new(config.resid_dropout);
 
         GptNeoMLP {
-            c_fc,
-            c_proj,
+            c_fc: c_fc.into(),
+            c_proj: c_proj.into(),
             activation_function,
             dropout,
         }
     }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.c_fc.quantize_int4(config)?;
+        self.c_proj.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.c_fc.split_columns(&config.devices, 1)?;
+        self.c_proj.split_rows(&config.devices, 1)
+    }
 }
 
 impl ModuleT for GptNeoMLP {
     fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
-        let hidden_states = hidden_states.apply(&self.c_fc);
-        let hidden_states = self.activation_function.get_fn()(&hidden_states);
-        hidden_states
-            .apply(&self.c_proj)
-            .apply_t(&self.dropout, train)
+        mlp_forward(
+            &self.c_fc,
+            &self.c_proj,
+            self.activation_function.get_fn(),
+            hidden_states,
+        )
+        .apply_t(&self.dropout, train)
     }
 }
 
@@ -106,6 +126,22 @@ impl GptNeoBlock {
         }
     }
 
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.attention.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.attention.tensor_parallelize(config)?;
+        self.mlp.tensor_parallelize(config)
+    }
+
     pub fn forward_t(
         &self,
         hidden_states: &Tensor,
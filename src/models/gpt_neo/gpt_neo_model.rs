@@ -10,8 +10,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::device_map::{
+    dispatch_var_store, to_layer_placement, DeviceMap, LayerPlacement,
+};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::mps;
+use crate::common::quantization::Int4QuantizationConfig;
+use crate::common::tensor_parallel::TensorParallelConfig;
 use crate::gpt_neo::decoder::GptNeoBlock;
 use crate::gpt_neo::LayerState;
 use crate::pipelines::common::{ModelType, TokenizerOption};
@@ -199,6 +205,7 @@ pub struct GptNeoModel {
     layer_norm: nn::LayerNorm,
     output_attentions: bool,
     output_hidden_states: bool,
+    layer_placements: Option<Vec<LayerPlacement>>,
 }
 
 impl GptNeoModel {
@@ -273,9 +280,60 @@ impl GptNeoModel {
             layer_norm,
             output_attentions,
             output_hidden_states,
+            layer_placements: None,
         })
     }
 
+    /// Quantize the weights of the linear layers of the transformer blocks to int4 in place.
+    /// The embeddings and layer normalizations are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the transformer blocks across devices in place (tensor parallelism).
+    /// The embeddings and layer normalizations remain on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.tensor_parallelize(config)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Sets the devices and precisions the hidden states are moved to before each transformer block, once the weights
+    /// of the blocks have been moved to these placements (see the `device_map` module)
+    pub(crate) fn set_layer_placements(
+        &mut self,
+        layer_placements: Vec<LayerPlacement>,
+    ) -> Result<(), RustBertError> {
+        if layer_placements.len() != self.layers.len() {
+            return Err(RustBertError::ValueError(format!(
+                "Expected {} layer placements, got {}",
+                self.layers.len(),
+                layer_placements.len()
+            )));
+        }
+        self.layer_placements = Some(layer_placements);
+        Ok(())
+    }
+
     /// Forward pass through the model
     ///
     /// # Arguments
@@ -382,6 +440,7 @@ impl GptNeoModel {
         });
 
         let mut hidden_state = input_embeds + position_embeds;
+        let embeddings_kind = hidden_state.kind();
         if let Some(token_type_ids) = token_type_ids {
             hidden_state = hidden_state + token_type_ids.apply(&self.word_embeddings);
         };
@@ -408,20 +467,27 @@ impl GptNeoModel {
         for ((layer_idx, layer), layer_state) in
             self.layers.iter().enumerate().zip(old_cache.into_iter())
         {
-            let temp = if let Some(x_value) = &x {
-                layer.forward_t(
-                    x_value,
-                    layer_state.as_ref(),
-                    attention_mask.as_ref(),
-                    train,
-                )?
-            } else {
-                layer.forward_t(
-                    &hidden_state,
+            let layer_input = x.as_ref().unwrap_or(&hidden_state);
+            let temp = match &self.layer_placements {
+                Some(layer_placements) => {
+                    let (layer_input, layer_attention_mask) = to_layer_placement(
+                        layer_input,
+                        attention_mask.as_ref(),
+                        &layer_placements[layer_idx],
+                    )?;
+                    layer.forward_t(
+                        &layer_input,
+                        layer_state.as_ref(),
+                        layer_attention_mask.as_ref(),
+                        train,
+                    )?
+                }
+                None => layer.forward_t(
+                    layer_input,
                     layer_state.as_ref(),
                     attention_mask.as_ref(),
                     train,
-                )?
+                )?,
             };
             x = Some(temp.0);
             attention_weights = temp.1;
@@ -436,6 +502,8 @@ impl GptNeoModel {
 
         let hidden_states = x
             .unwrap()
+            .to_device(device)
+            .to_kind(embeddings_kind)
             .apply(&self.layer_norm)
             .view(output_shape.as_slice());
 
@@ -489,6 +557,40 @@ impl GptNeoForCausalLM {
         Ok(GptNeoForCausalLM { transformer })
     }
 
+    /// Quantize the weights of the linear layers of the model to int4 in place. The language model head
+    /// is tied to the word embeddings and is kept in its original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.transformer.quantize_int4(config)
+    }
+
+    /// Split the attention and MLP layers of the model across devices in place (tensor parallelism).
+    /// The language model head remains on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.transformer.tensor_parallelize(config)
+    }
+
+    pub(crate) fn num_layers(&self) -> usize {
+        self.transformer.num_layers()
+    }
+
+    pub(crate) fn set_layer_placements(
+        &mut self,
+        layer_placements: Vec<LayerPlacement>,
+    ) -> Result<(), RustBertError> {
+        self.transformer.set_layer_placements(layer_placements)
+    }
+
     /// Forward pass through the model
     ///
     /// # Arguments
@@ -505,6 +607,7 @@ impl GptNeoForCausalLM {
     ///
     /// * `Result<GptNeoModelLMOutput, RustBertError>` containing:
     ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the last hidden states
     ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
@@ -565,6 +668,7 @@ impl GptNeoForCausalLM {
 
         Ok(GptNeoModelLMOutput {
             lm_logits,
+            hidden_states: base_model_output.hidden_states,
             next_cache: base_model_output.next_cache,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -588,6 +692,8 @@ pub struct GptNeoModelOutput {
 pub struct GptNeoModelLMOutput {
     /// logits
     pub lm_logits: Tensor,
+    /// Last hidden states from the model
+    pub hidden_states: Tensor,
     /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
     pub next_cache: Option<Vec<Option<LayerState>>>,
     /// Hidden states for all intermediate layers
@@ -668,7 +774,7 @@ impl GptNeoGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
         let config = GptNeoConfig::from_file(config_path);
         let model = GptNeoForCausalLM::new(var_store.root(), &config)?;
@@ -696,6 +802,39 @@ impl GptNeoGenerator {
             max_position_embeddings,
         })
     }
+
+    /// Quantize the weights of the model to int4 in place (see `GptNeoForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+
+    /// Split the layers of the model across devices in place (see `GptNeoForCausalLM::tensor_parallelize`).
+    /// The model should be loaded on the first device of the configuration.
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+
+    /// Partitions the transformer blocks of the model across devices in place (see the `device_map` module). The
+    /// embeddings and language model head are moved to the placement of the embeddings of the map, whose device
+    /// becomes the device of the model. The device map should be applied before quantization or tensor parallelism.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_map` - `DeviceMap` defining the device of each transformer block
+    pub fn set_device_map(&mut self, device_map: &DeviceMap) -> Result<(), RustBertError> {
+        let layer_placements = device_map.layer_placements(self.model.num_layers())?;
+        dispatch_var_store(
+            &mut self.var_store,
+            "transformer.h.",
+            &layer_placements,
+            &device_map.embeddings,
+        )?;
+        self.model.set_layer_placements(layer_placements)
+    }
 }
 
 impl PrivateLanguageGenerator for GptNeoGenerator {
@@ -778,6 +917,7 @@ impl PrivateLanguageGenerator for GptNeoGenerator {
         Ok(LMModelOutput {
             lm_logits: base_model_output.lm_logits,
             cache: Cache::GPTNeoCache(base_model_output.next_cache),
+            last_hidden_state: Some(base_model_output.hidden_states),
         })
     }
     fn prepare_inputs_for_generation<'a>(
@@ -787,7 +927,7 @@ impl PrivateLanguageGenerator for GptNeoGenerator {
         past: Cache,
         attention_mask: Tensor,
     ) -> PreparedInput<'a> {
-        let position_ids = (attention_mask.totype(Kind::Int64).cumsum(-1, Kind::Int64) - 1)
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
             .masked_fill(&attention_mask.eq(0), 1);
 
         match past {
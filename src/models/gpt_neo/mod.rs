@@ -70,8 +70,8 @@ mod decoder;
 mod gpt_neo_model;
 
 pub use gpt_neo_model::{
-    GptNeoConfig, GptNeoConfigResources, GptNeoForCausalLM, GptNeoGenerator, GptNeoMergesResources,
-    GptNeoModel, GptNeoModelResources, GptNeoVocabResources,
+    AttentionLayerType, GptNeoConfig, GptNeoConfigResources, GptNeoForCausalLM, GptNeoGenerator,
+    GptNeoMergesResources, GptNeoModel, GptNeoModelResources, GptNeoVocabResources,
 };
 
 pub use attention::LayerState;
@@ -0,0 +1,253 @@
+// Copyright 2023 Jina AI
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::precision::{attention_output, attention_scores};
+use crate::jina_bert::JinaBertConfig;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+#[derive(Debug)]
+pub struct JinaBertSelfAttention {
+    num_attention_heads: i64,
+    attention_head_size: i64,
+    dropout: Dropout,
+    output_attentions: bool,
+    query: nn::Linear,
+    key: nn::Linear,
+    value: nn::Linear,
+}
+
+impl JinaBertSelfAttention {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertSelfAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Hidden size not a multiple of the number of attention heads"
+        );
+        let p = p.borrow();
+
+        let query = nn::linear(
+            p / "query",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let key = nn::linear(
+            p / "key",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let value = nn::linear(
+            p / "value",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let dropout = Dropout::new(config.attention_probs_dropout_prob);
+        let attention_head_size = config.hidden_size / config.num_attention_heads;
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        JinaBertSelfAttention {
+            num_attention_heads: config.num_attention_heads,
+            attention_head_size,
+            dropout,
+            output_attentions,
+            query,
+            key,
+            value,
+        }
+    }
+
+    fn split_heads(&self, x: Tensor, bs: i64) -> Tensor {
+        x.view((bs, -1, self.num_attention_heads, self.attention_head_size))
+            .transpose(1, 2)
+    }
+
+    fn flatten(&self, x: Tensor, bs: i64) -> Tensor {
+        x.transpose(1, 2).contiguous().view((
+            bs,
+            -1,
+            self.num_attention_heads * self.attention_head_size,
+        ))
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_bias: &Tensor,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let bs = hidden_states.size()[0];
+
+        let query_layer = self.split_heads(hidden_states.apply(&self.query), bs);
+        let key_layer = self.split_heads(hidden_states.apply(&self.key), bs);
+        let value_layer = self.split_heads(hidden_states.apply(&self.value), bs);
+        let query_layer: Tensor = query_layer / (self.attention_head_size as f64).sqrt();
+
+        let scores = attention_scores(&query_layer, &key_layer.transpose(-1, -2)) + attention_bias;
+        let weights = scores
+            .softmax(-1, scores.kind())
+            .apply_t(&self.dropout, train);
+        let context = self.flatten(attention_output(&weights, &value_layer), bs);
+
+        if !self.output_attentions {
+            (context, None)
+        } else {
+            (context, Some(weights))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct JinaBertSelfOutput {
+    linear: nn::Linear,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl JinaBertSelfOutput {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertSelfOutput
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm =
+            nn::layer_norm(p / "LayerNorm", vec![config.hidden_size], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        JinaBertSelfOutput {
+            linear,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, input_tensor: &Tensor, train: bool) -> Tensor {
+        let hidden_states: Tensor = input_tensor
+            + hidden_states
+                .apply(&self.linear)
+                .apply_t(&self.dropout, train);
+        hidden_states.apply(&self.layer_norm)
+    }
+}
+
+#[derive(Debug)]
+pub struct JinaBertAttention {
+    _self: JinaBertSelfAttention,
+    output: JinaBertSelfOutput,
+}
+
+impl JinaBertAttention {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let _self = JinaBertSelfAttention::new(p / "self", config);
+        let output = JinaBertSelfOutput::new(p / "output", config);
+        JinaBertAttention { _self, output }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_bias: &Tensor,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (self_output, attention_weights) =
+            self._self.forward_t(hidden_states, attention_bias, train);
+
+        let self_output = self.output.forward_t(&self_output, hidden_states, train);
+        (self_output, attention_weights)
+    }
+}
+
+/// # Gated Linear Unit feed-forward layer
+/// The input is projected to twice the intermediate size, the first half is passed through the
+/// activation and used to gate the second half (GEGLU for a GeLU activation, ReGLU for ReLU).
+pub struct JinaBertGLUMLP {
+    gated_layers: LinearNoBias,
+    wo: nn::Linear,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+    activation: TensorFunction,
+    intermediate_size: i64,
+}
+
+impl JinaBertGLUMLP {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertGLUMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let gated_layers = linear_no_bias(
+            p / "gated_layers",
+            config.hidden_size,
+            config.intermediate_size * 2,
+            Default::default(),
+        );
+        let wo = nn::linear(
+            p / "wo",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm =
+            nn::layer_norm(p / "layernorm", vec![config.hidden_size], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let activation = config.feed_forward_activation().get_function();
+
+        JinaBertGLUMLP {
+            gated_layers,
+            wo,
+            layer_norm,
+            dropout,
+            activation,
+            intermediate_size: config.intermediate_size,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let projected = hidden_states.apply(&self.gated_layers);
+        let gated = projected.narrow(-1, 0, self.intermediate_size);
+        let non_gated = projected.narrow(-1, self.intermediate_size, self.intermediate_size);
+        let output = ((self.activation.get_fn())(&gated) * non_gated)
+            .apply_t(&self.dropout, train)
+            .apply(&self.wo);
+        (output + hidden_states).apply(&self.layer_norm)
+    }
+}
@@ -0,0 +1,473 @@
+// Copyright 2023 Jina AI
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::position_embeddings::AlibiBias;
+use crate::jina_bert::attention::{JinaBertAttention, JinaBertGLUMLP};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use std::collections::HashMap;
+use tch::nn::EmbeddingConfig;
+use tch::{nn, Kind, Tensor};
+
+/// # Jina BERT Pretrained model weight files
+pub struct JinaBertModelResources;
+
+/// # Jina BERT Pretrained model config files
+pub struct JinaBertConfigResources;
+
+/// # Jina BERT Pretrained model vocab files
+pub struct JinaBertVocabResources;
+
+impl JinaBertModelResources {
+    /// Shared under Apache 2.0 license by Jina AI at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/model",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by Jina AI at <https://huggingface.co/jinaai/jina-embeddings-v2-small-en>.
+    pub const JINA_EMBEDDINGS_V2_SMALL_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-small-en/model",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-small-en/resolve/main/model.safetensors",
+    );
+}
+
+impl JinaBertConfigResources {
+    /// Shared under Apache 2.0 license by Jina AI at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/config",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by Jina AI at <https://huggingface.co/jinaai/jina-embeddings-v2-small-en>.
+    pub const JINA_EMBEDDINGS_V2_SMALL_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-small-en/config",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-small-en/resolve/main/config.json",
+    );
+}
+
+impl JinaBertVocabResources {
+    /// Shared under Apache 2.0 license by Jina AI at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/vocab",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/vocab.txt",
+    );
+    /// Shared under Apache 2.0 license by Jina AI at <https://huggingface.co/jinaai/jina-embeddings-v2-small-en>.
+    pub const JINA_EMBEDDINGS_V2_SMALL_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-small-en/vocab",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-small-en/resolve/main/vocab.txt",
+    );
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, Copy, PartialEq, Eq)]
+/// # Feed-forward layer type used by Jina BERT layers
+pub enum JinaBertFeedForwardType {
+    /// GeLU-gated linear unit
+    geglu,
+    /// ReLU-gated linear unit
+    reglu,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Jina BERT model configuration
+/// Defines the Jina BERT model architecture (e.g. number of layers, hidden layer size, label mapping...)
+pub struct JinaBertConfig {
+    pub hidden_act: Activation,
+    pub attention_probs_dropout_prob: f64,
+    pub hidden_dropout_prob: f64,
+    pub hidden_size: i64,
+    pub initializer_range: f32,
+    pub intermediate_size: i64,
+    pub max_position_embeddings: i64,
+    pub num_attention_heads: i64,
+    pub num_hidden_layers: i64,
+    pub type_vocab_size: i64,
+    pub vocab_size: i64,
+    pub layer_norm_eps: Option<f64>,
+    pub pad_token_id: Option<i64>,
+    pub feed_forward_type: Option<JinaBertFeedForwardType>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+}
+
+impl Config for JinaBertConfig {}
+
+impl Default for JinaBertConfig {
+    fn default() -> Self {
+        JinaBertConfig {
+            hidden_act: Activation::gelu,
+            attention_probs_dropout_prob: 0.0,
+            hidden_dropout_prob: 0.1,
+            hidden_size: 768,
+            initializer_range: 0.02,
+            intermediate_size: 3072,
+            max_position_embeddings: 8192,
+            num_attention_heads: 12,
+            num_hidden_layers: 12,
+            type_vocab_size: 2,
+            vocab_size: 30528,
+            layer_norm_eps: Some(1e-12),
+            pad_token_id: Some(0),
+            feed_forward_type: Some(JinaBertFeedForwardType::geglu),
+            output_attentions: None,
+            output_hidden_states: None,
+            id2label: None,
+            label2id: None,
+        }
+    }
+}
+
+impl JinaBertConfig {
+    pub(crate) fn feed_forward_activation(&self) -> Activation {
+        match self.feed_forward_type {
+            Some(JinaBertFeedForwardType::reglu) => Activation::relu,
+            Some(JinaBertFeedForwardType::geglu) | None => Activation::gelu,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// # Jina BERT embeddings
+/// Word and token type embeddings. Positions are encoded through an ALiBi attention bias and
+/// the model therefore does not contain position embeddings.
+pub struct JinaBertEmbeddings {
+    word_embeddings: nn::Embedding,
+    token_type_embeddings: nn::Embedding,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl JinaBertEmbeddings {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embedding_config = EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(0),
+            ..Default::default()
+        };
+        let word_embeddings = nn::embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            embedding_config,
+        );
+        let token_type_embeddings = nn::embedding(
+            p / "token_type_embeddings",
+            config.type_vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm =
+            nn::layer_norm(p / "LayerNorm", vec![config.hidden_size], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        JinaBertEmbeddings {
+            word_embeddings,
+            token_type_embeddings,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let calc_token_type_ids = if token_type_ids.is_none() {
+            Some(Tensor::zeros(input_shape, (Kind::Int64, device)))
+        } else {
+            None
+        };
+        let token_type_embeddings = token_type_ids
+            .unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap())
+            .apply(&self.token_type_embeddings);
+
+        let input_embeddings: Tensor = input_embeddings + token_type_embeddings;
+        Ok(input_embeddings
+            .apply(&self.layer_norm)
+            .apply_t(&self.dropout, train))
+    }
+}
+
+/// # Jina BERT Layer
+/// Post-normalization transformer layer made of an ALiBi self-attention block and a gated linear unit
+/// feed-forward block.
+pub struct JinaBertLayer {
+    attention: JinaBertAttention,
+    mlp: JinaBertGLUMLP,
+}
+
+impl JinaBertLayer {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let attention = JinaBertAttention::new(p / "attention", config);
+        let mlp = JinaBertGLUMLP::new(p / "mlp", config);
+
+        JinaBertLayer { attention, mlp }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_bias: &Tensor,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (attention_output, attention_weights) =
+            self.attention
+                .forward_t(hidden_states, attention_bias, train);
+        let output = self.mlp.forward_t(&attention_output, train);
+        (output, attention_weights)
+    }
+}
+
+/// # Jina BERT Encoder
+/// Stack of `JinaBertLayer`. The ALiBi bias is computed once for the input sequence length and
+/// shared across layers, which allows the model to process sequences longer than seen during training.
+pub struct JinaBertEncoder {
+    output_attentions: bool,
+    output_hidden_states: bool,
+    num_attention_heads: i64,
+    layers: Vec<JinaBertLayer>,
+}
+
+impl JinaBertEncoder {
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "layer";
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        let mut layers: Vec<JinaBertLayer> = vec![];
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(JinaBertLayer::new(&p / layer_index, config));
+        }
+
+        JinaBertEncoder {
+            output_attentions,
+            output_hidden_states,
+            num_attention_heads: config.num_attention_heads,
+            layers,
+        }
+    }
+
+    pub fn forward_t(&self, input: &Tensor, mask: &Tensor, train: bool) -> JinaBertEncoderOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let sequence_length = input.size()[1];
+        let attention_bias = AlibiBias::new(self.num_attention_heads)
+            .forward(sequence_length, sequence_length, input.device())
+            .to_kind(input.kind())
+            + mask;
+
+        let mut hidden_state = input.copy();
+        for layer in &self.layers {
+            let (layer_output, attention_weights) =
+                layer.forward_t(&hidden_state, &attention_bias, train);
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+        }
+
+        JinaBertEncoderOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// # Jina BERT Base model
+/// Base architecture for the Jina embeddings models (BERT with ALiBi positional bias and gated feed-forward layers, supporting
+/// sequences of up to 8192 tokens). It is made of the following blocks:
+/// - `embeddings`: `token` and `segment_id` embeddings
+/// - `encoder`: Encoder (transformer) made of a vector of layers with a symmetric ALiBi attention bias
+pub struct JinaBertModel {
+    embeddings: JinaBertEmbeddings,
+    encoder: JinaBertEncoder,
+}
+
+impl JinaBertModel {
+    /// Build a new `JinaBertModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Jina BERT model
+    /// * `config` - `JinaBertConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::jina_bert::{JinaBertConfig, JinaBertModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = JinaBertConfig::from_file(config_path);
+    /// let jina_bert = JinaBertModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &JinaBertConfig) -> JinaBertModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = JinaBertEmbeddings::new(p / "embeddings", config);
+        let encoder = JinaBertEncoder::new(p / "encoder", config);
+
+        JinaBertModel {
+            embeddings,
+            encoder,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `JinaBertModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::jina_bert::{JinaBertConfig, JinaBertModel};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = JinaBertConfig::from_file(config_path);
+    /// # let jina_bert_model = JinaBertModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (4, 2048);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     jina_bert_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<JinaBertModelOutput, RustBertError> {
+        let embedding_output =
+            self.embeddings
+                .forward_t(input_ids, token_type_ids, input_embeds, train)?;
+
+        let input_shape = &embedding_output.size()[..2];
+        let calc_mask = Tensor::ones(input_shape, (Kind::Int8, embedding_output.device()));
+        let mask = mask.unwrap_or(&calc_mask);
+        if mask.dim() != 2 {
+            return Err(RustBertError::ValueError(
+                "Invalid attention mask dimension, must be 2".into(),
+            ));
+        }
+        let extended_attention_mask = mask.unsqueeze(1).unsqueeze(1);
+        let extended_attention_mask: Tensor =
+            ((extended_attention_mask.ones_like() - extended_attention_mask) * -10000.0)
+                .to_kind(embedding_output.kind());
+
+        let encoder_output =
+            self.encoder
+                .forward_t(&embedding_output, &extended_attention_mask, train);
+
+        Ok(JinaBertModelOutput {
+            hidden_state: encoder_output.hidden_state,
+            all_hidden_states: encoder_output.all_hidden_states,
+            all_attentions: encoder_output.all_attentions,
+        })
+    }
+}
+
+/// # Jina BERT for sentence embeddings
+/// Alias for the base model, used by the sentence embeddings pipeline.
+pub type JinaBertForSentenceEmbeddings = JinaBertModel;
+
+/// Container for the Jina BERT encoder output.
+pub struct JinaBertEncoderOutput {
+    /// Last hidden states from the encoder
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the Jina BERT model output.
+pub struct JinaBertModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
@@ -0,0 +1,60 @@
+//! # Jina BERT: long-context BERT encoder with ALiBi (Günther et al.)
+//!
+//! Implementation of the Jina embeddings v2 encoder ([Jina Embeddings 2: 8192-Token General-Purpose Text Embeddings for Long Documents](https://arxiv.org/abs/2310.19923) Günther et al., 2023).
+//! The architecture follows BERT, with learned position embeddings replaced by a symmetric ALiBi attention bias
+//! ([Press et al., 2021](https://arxiv.org/abs/2108.12409)) and the feed-forward layers replaced by gated linear units.
+//! This allows the model to encode documents of up to 8192 tokens.
+//! The base model is implemented in the `jina_bert_model::JinaBertModel` struct and is primarily intended to be used
+//! through the sentence embeddings pipeline.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). The pretrained safetensors checkpoints are loaded directly without conversion. Alternatively, the Python utility scripts convert the `.bin` weights to the `.ot` format.
+//! - `BertTokenizer` using a `vocab.txt` vocabulary
+//! Pretrained models are available and can be downloaded using RemoteResources.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::jina_bert::{JinaBertConfig, JinaBertModel};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::BertTokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/vocab.txt"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: BertTokenizer =
+//!     BertTokenizer::from_file(vocab_path.to_str().unwrap(), true, true)?;
+//! let config = JinaBertConfig::from_file(config_path);
+//! let jina_bert_model = JinaBertModel::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod jina_bert_model;
+
+pub use attention::{JinaBertAttention, JinaBertGLUMLP, JinaBertSelfAttention};
+pub use jina_bert_model::{
+    JinaBertConfig, JinaBertConfigResources, JinaBertEmbeddings, JinaBertEncoder,
+    JinaBertEncoderOutput, JinaBertFeedForwardType, JinaBertForSentenceEmbeddings, JinaBertLayer,
+    JinaBertModel, JinaBertModelOutput, JinaBertModelResources, JinaBertVocabResources,
+};
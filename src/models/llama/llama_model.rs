@@ -0,0 +1,943 @@
+// Copyright 2023 Meta AI, Mistral AI and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::kind::get_min;
+use crate::common::mps;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::TensorParallelConfig;
+use crate::llama::attention::{build_causal_mask, LayerState, LlamaRotaryEmbedding};
+use crate::llama::transformer::{LlamaDecoderLayer, LlamaRMSNorm};
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, LanguageGenerator};
+use crate::resources::{GgufFile, GgufValue};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Device, Kind, Tensor};
+
+/// # LLaMA Pretrained model weight files
+pub struct LlamaModelResources;
+
+/// # LLaMA Pretrained model config files
+pub struct LlamaConfigResources;
+
+/// # LLaMA Pretrained model vocab files
+pub struct LlamaVocabResources;
+
+/// # LLaMA Pretrained model special tokens map files
+pub struct LlamaSpecialMap;
+
+impl LlamaModelResources {
+    /// Shared under Apache 2.0 license by the TinyLlama project at <https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0>.
+    pub const TINYLLAMA_1_1B_CHAT: (&'static str, &'static str) = (
+        "tinyllama-1.1b-chat/model",
+        "https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0/resolve/main/model.safetensors",
+    );
+}
+
+impl LlamaConfigResources {
+    /// Shared under Apache 2.0 license by the TinyLlama project at <https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0>.
+    pub const TINYLLAMA_1_1B_CHAT: (&'static str, &'static str) = (
+        "tinyllama-1.1b-chat/config",
+        "https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0/resolve/main/config.json",
+    );
+}
+
+impl LlamaVocabResources {
+    /// Shared under Apache 2.0 license by the TinyLlama project at <https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0>.
+    pub const TINYLLAMA_1_1B_CHAT: (&'static str, &'static str) = (
+        "tinyllama-1.1b-chat/tokenizer",
+        "https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0/resolve/main/tokenizer.json",
+    );
+}
+
+impl LlamaSpecialMap {
+    /// Shared under Apache 2.0 license by the TinyLlama project at <https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0>.
+    pub const TINYLLAMA_1_1B_CHAT: (&'static str, &'static str) = (
+        "tinyllama-1.1b-chat/special",
+        "https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0/resolve/main/special_tokens_map.json",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # LLaMA model configuration
+/// Defines the LLaMA model architecture (e.g. number of layers, hidden layer size, number of key/value heads...).
+/// Mistral checkpoints share the same configuration, with a `sliding_window` restricting the attention span.
+pub struct LlamaConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub num_key_value_heads: Option<i64>,
+    pub head_dim: Option<i64>,
+    pub hidden_act: Activation,
+    pub max_position_embeddings: i64,
+    pub initializer_range: Option<f64>,
+    pub rms_norm_eps: Option<f64>,
+    pub rope_theta: Option<f64>,
+    pub sliding_window: Option<i64>,
+    pub attention_bias: Option<bool>,
+    pub mlp_bias: Option<bool>,
+    pub attention_dropout: Option<f64>,
+    pub tie_word_embeddings: Option<bool>,
+    pub use_cache: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    pub forced_bos_token_id: Option<i64>,
+    pub forced_eos_token_id: Option<i64>,
+}
+
+impl Config for LlamaConfig {}
+
+impl Default for LlamaConfig {
+    fn default() -> Self {
+        LlamaConfig {
+            vocab_size: 32000,
+            hidden_size: 4096,
+            intermediate_size: 11008,
+            num_hidden_layers: 32,
+            num_attention_heads: 32,
+            num_key_value_heads: None,
+            head_dim: None,
+            hidden_act: Activation::swish,
+            max_position_embeddings: 4096,
+            initializer_range: Some(0.02),
+            rms_norm_eps: Some(1e-5),
+            rope_theta: Some(10000.0),
+            sliding_window: None,
+            attention_bias: Some(false),
+            mlp_bias: Some(false),
+            attention_dropout: Some(0.0),
+            tie_word_embeddings: Some(false),
+            use_cache: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            bos_token_id: Some(1),
+            eos_token_id: Some(2),
+            pad_token_id: None,
+            decoder_start_token_id: None,
+            forced_bos_token_id: None,
+            forced_eos_token_id: None,
+        }
+    }
+}
+
+impl LlamaConfig {
+    /// Dimension of each attention head (`hidden_size / num_attention_heads` unless set explicitly)
+    pub fn head_dim(&self) -> i64 {
+        self.head_dim
+            .unwrap_or(self.hidden_size / self.num_attention_heads)
+    }
+
+    /// Builds the configuration of a model from the metadata of a GGUF checkpoint of the LLaMA architecture
+    /// (`llama.*` hyper-parameters and `tokenizer.ggml.*` special tokens). The language model head is tied to the
+    /// word embeddings if the checkpoint does not contain an `output.weight` tensor.
+    ///
+    /// # Arguments
+    ///
+    /// * `gguf_file` - `GgufFile` header of the checkpoint
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+    /// use rust_bert::resources::{load_weights_from_file, GgufFile};
+    /// use tch::{nn, Device};
+    ///
+    /// let gguf_file = GgufFile::open("path/to/model-q4_0.gguf")?;
+    /// let config = LlamaConfig::from_gguf(&gguf_file)?;
+    /// let mut vs = nn::VarStore::new(Device::cuda_if_available());
+    /// let model = LlamaForCausalLM::new(vs.root(), &config);
+    /// load_weights_from_file("path/to/model-q4_0.gguf", &mut vs)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_gguf(gguf_file: &GgufFile) -> Result<LlamaConfig, RustBertError> {
+        if gguf_file.architecture() != Some("llama") {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Expected a GGUF checkpoint of the LLaMA architecture, got {}",
+                gguf_file.architecture().unwrap_or("(missing)")
+            )));
+        }
+        let integer = |key: &str| {
+            gguf_file
+                .architecture_value(key)
+                .and_then(GgufValue::as_u64)
+                .map(|value| value as i64)
+        };
+        let required = |key: &str| {
+            integer(key).ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(format!(
+                    "Missing llama.{key} in the GGUF metadata"
+                ))
+            })
+        };
+        let float = |key: &str| {
+            gguf_file
+                .architecture_value(key)
+                .and_then(GgufValue::as_f64)
+        };
+        let token_id = |key: &str| {
+            gguf_file
+                .metadata
+                .get(&format!("tokenizer.ggml.{key}"))
+                .and_then(GgufValue::as_u64)
+                .map(|value| value as i64)
+        };
+
+        let hidden_size = required("embedding_length")?;
+        let num_attention_heads = required("attention.head_count")?;
+        let vocab_size = match integer("vocab_size") {
+            Some(vocab_size) => vocab_size,
+            None => gguf_file
+                .tensor_info("token_embd.weight")
+                .map(|tensor_info| tensor_info.shape[0])
+                .ok_or_else(|| {
+                    RustBertError::InvalidConfigurationError(
+                        "Missing token_embd.weight in the GGUF checkpoint".to_string(),
+                    )
+                })?,
+        };
+        let head_dim = integer("attention.key_length")
+            .filter(|&head_dim| head_dim != hidden_size / num_attention_heads);
+        let defaults = LlamaConfig::default();
+        Ok(LlamaConfig {
+            vocab_size,
+            hidden_size,
+            intermediate_size: required("feed_forward_length")?,
+            num_hidden_layers: required("block_count")?,
+            num_attention_heads,
+            num_key_value_heads: integer("attention.head_count_kv"),
+            head_dim,
+            max_position_embeddings: integer("context_length")
+                .unwrap_or(defaults.max_position_embeddings),
+            rms_norm_eps: float("attention.layer_norm_rms_epsilon").or(defaults.rms_norm_eps),
+            rope_theta: float("rope.freq_base").or(defaults.rope_theta),
+            tie_word_embeddings: Some(gguf_file.tensor_info("output.weight").is_none()),
+            bos_token_id: token_id("bos_token_id").or(defaults.bos_token_id),
+            eos_token_id: token_id("eos_token_id").or(defaults.eos_token_id),
+            pad_token_id: token_id("padding_token_id"),
+            ..defaults
+        })
+    }
+}
+
+/// # LLaMA Base model
+/// Base architecture for LLaMA and Mistral models. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `embed_tokens`: `token` embeddings
+/// - `layers`: Decoder made of a vector of layers. Each layer is made of a grouped-query attention layer with rotary position embeddings,
+///   RMS normalization layers, and a gated MLP (SwiGLU). Layers attend to a sliding window of the previous tokens if `sliding_window` is set.
+/// - `norm`: Final RMS normalization
+pub struct LlamaModel {
+    embed_tokens: nn::Embedding,
+    layers: Vec<LlamaDecoderLayer>,
+    norm: LlamaRMSNorm,
+    rotary_embedding: LlamaRotaryEmbedding,
+    sliding_window: Option<i64>,
+    use_cache: bool,
+    output_hidden_states: bool,
+    output_attentions: bool,
+}
+
+impl LlamaModel {
+    /// Build a new `LlamaModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the LLaMA model
+    /// * `config` - `LlamaConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::llama::{LlamaConfig, LlamaModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = LlamaConfig::from_file(config_path);
+    /// let llama: LlamaModel = LlamaModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "model";
+
+        let embed_tokens = embedding(
+            &p / "embed_tokens",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let mut layers: Vec<LlamaDecoderLayer> = vec![];
+        let layers_path = &p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(LlamaDecoderLayer::new(&layers_path / layer_index, config));
+        }
+
+        let norm = LlamaRMSNorm::new(
+            &p / "norm",
+            config.hidden_size,
+            config.rms_norm_eps.unwrap_or(1e-6),
+        );
+
+        let rotary_embedding = LlamaRotaryEmbedding::new(
+            config.head_dim(),
+            config.rope_theta.unwrap_or(10000.0),
+            p.device(),
+        );
+
+        LlamaModel {
+            embed_tokens,
+            layers,
+            norm,
+            rotary_embedding,
+            sliding_window: config.sliding_window,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the decoder layers to int4 in place.
+    /// The embeddings and normalization layers are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the transformer blocks across devices in place (tensor parallelism).
+    /// The embeddings and normalization layers remain on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.tensor_parallelize(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - Optional vector of length *num_hidden_layers* containing the past keys and values of each layer of shape (*batch size*, *number of key/value heads*, *past_sequence_length*, *hidden size per head*). When provided, these are concatenated with the current input keys and values.
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LlamaModelOutput` containing:
+    ///   - `output` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *num_hidden_layers* containing the past keys and values of each layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *number of heads*, *sequence_length*, *past_sequence_length + sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::llama::{LlamaConfig, LlamaModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = LlamaConfig::from_file(config_path);
+    /// # let llama_model: LlamaModel = LlamaModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     llama_model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             None,
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LlamaModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_tokens)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+
+        let (layer_past, past_length) = match layer_past {
+            Some(value) => {
+                if value.len() != self.layers.len() {
+                    return Err(RustBertError::ValueError(format!(
+                        "Past activations vector length ({}) must be equal to the number of layers ({})",
+                        value.len(),
+                        self.layers.len()
+                    )));
+                } else {
+                    let past_length = value
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|layer_state| layer_state.prev_key.size()[2])
+                        .unwrap_or(0);
+                    (value, past_length)
+                }
+            }
+            None => {
+                let mut out = Vec::with_capacity(self.layers.len());
+                out.resize_with(self.layers.len(), || None);
+                (out, 0)
+            }
+        };
+        let key_length = past_length + sequence_length;
+
+        let position_ids = match position_ids {
+            Some(value) => value.shallow_clone(),
+            None => Tensor::arange_start(past_length, key_length, (Kind::Int64, device))
+                .unsqueeze(0)
+                .expand([batch_size, sequence_length], true),
+        };
+
+        let mut masked_positions =
+            build_causal_mask(sequence_length, key_length, self.sliding_window, device).view([
+                1,
+                1,
+                sequence_length,
+                key_length,
+            ]);
+        if let Some(attention_mask) = attention_mask {
+            masked_positions =
+                masked_positions.logical_or(&attention_mask.view([batch_size, 1, 1, -1]).eq(0));
+        }
+        let kind = input_embeddings.kind();
+        let attention_mask = Tensor::zeros(masked_positions.size(), (kind, device))
+            .masked_fill(&masked_positions, get_min(kind)?);
+
+        let (cos, sin) = self.rotary_embedding.forward(&position_ids, kind);
+
+        let mut hidden_state = input_embeddings.shallow_clone();
+
+        let mut all_presents: Option<Vec<Option<LayerState>>> = self.use_cache.then(Vec::new);
+        let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
+        let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
+
+        for (layer, past) in self.layers.iter().zip(layer_past) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let temp = layer.forward_t(
+                &hidden_state,
+                Some(&attention_mask),
+                (&cos, &sin),
+                past.as_ref(),
+                train,
+            );
+            hidden_state = temp.0;
+            if let Some(presents) = all_presents.borrow_mut() {
+                presents.push(temp.1);
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(temp.2.unwrap());
+            };
+        }
+
+        let output = hidden_state.apply(&self.norm);
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(output.copy());
+        };
+
+        Ok(LlamaModelOutput {
+            output,
+            cache: all_presents,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # LLaMA Language Modeling head
+/// LLaMA model with a decoding head (linear layer without bias). The weights of the linear layer are only tied to the word
+/// embeddings if `tie_word_embeddings` is set in the configuration.
+/// It is made of the following blocks:
+/// - `model`: Base LlamaModel
+/// - `lm_head`: Optional linear layer projecting the hidden states to the vocabulary (untied weights only)
+pub struct LlamaForCausalLM {
+    model: LlamaModel,
+    lm_head: Option<QuantizableLinear>,
+}
+
+impl LlamaForCausalLM {
+    /// Build a new `LlamaForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the LLaMA model
+    /// * `config` - `LlamaConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = LlamaConfig::from_file(config_path);
+    /// let llama: LlamaForCausalLM = LlamaForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = LlamaModel::new(p, config);
+        let lm_head = if config.tie_word_embeddings.unwrap_or(false) {
+            None
+        } else {
+            Some(
+                nn::linear(
+                    p / "lm_head",
+                    config.hidden_size,
+                    config.vocab_size,
+                    nn::LinearConfig {
+                        bias: false,
+                        ..Default::default()
+                    },
+                )
+                .into(),
+            )
+        };
+
+        LlamaForCausalLM { model, lm_head }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place. An untied language model head
+    /// is only quantized if `quantize_lm_head` is set in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)?;
+        if config.quantize_lm_head {
+            if let Some(lm_head) = self.lm_head.as_mut() {
+                lm_head.quantize_int4(config)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the model across devices in place (tensor parallelism).
+    /// The language model head remains on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - `Cache` containing the past keys and values of each layer (`Cache::LlamaCache` or `Cache::None`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `cache` - `Cache::LlamaCache` containing the past keys and values of each layer
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::LlamaCache(layer_past) => self.model.forward_t(
+                input_ids,
+                layer_past,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            Cache::None => self.model.forward_t(
+                input_ids,
+                None,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with LLaMA Model".into(),
+                ));
+            }
+        }?;
+
+        let lm_logits = match &self.lm_head {
+            Some(lm_head) => base_model_output.output.apply(lm_head),
+            None => base_model_output
+                .output
+                .linear::<Tensor>(&self.model.embed_tokens.ws, None),
+        };
+
+        Ok(LMModelOutput {
+            lm_logits,
+            cache: Cache::LlamaCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
+        })
+    }
+}
+
+/// Container for the LLaMA model output.
+pub struct LlamaModelOutput {
+    /// Hidden state of the last layer of the decoder
+    pub output: Tensor,
+    /// Cached attention layers keys and values if the model is used for generation
+    pub cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the LLaMA architecture (including Mistral)
+pub struct LlamaGenerator {
+    model: LlamaForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl LlamaGenerator {
+    /// Build a new `LlamaGenerator`. LLaMA tokenizers are not available from the `rust_tokenizers` crate: the
+    /// tokenizer should be loaded from a `tokenizer.json` file (see `TokenizerOption::from_hf_tokenizer_file`)
+    /// and the generator created with `new_with_tokenizer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    pub fn new(generate_config: GenerateConfig) -> Result<LlamaGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Llama,
+            vocab_path.to_str().unwrap(),
+            None,
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    /// Build a new `LlamaGenerator` with a tokenizer
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer, usually loaded from a `tokenizer.json` file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::llama::{
+    ///     LlamaConfigResources, LlamaGenerator, LlamaSpecialMap, LlamaVocabResources,
+    /// };
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::{LocalResource, RemoteResource, ResourceProvider};
+    /// use std::path::PathBuf;
+    ///
+    /// let vocab_resource = RemoteResource::from_pretrained(LlamaVocabResources::TINYLLAMA_1_1B_CHAT);
+    /// let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+    ///     vocab_resource.get_local_path()?,
+    ///     RemoteResource::from_pretrained(LlamaSpecialMap::TINYLLAMA_1_1B_CHAT).get_local_path()?,
+    /// )?;
+    /// let generate_config = GenerateConfig {
+    ///     model_type: ModelType::Llama,
+    ///     model_resource: ModelResource::Torch(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     })),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(
+    ///         LlamaConfigResources::TINYLLAMA_1_1B_CHAT,
+    ///     )),
+    ///     vocab_resource: Box::new(vocab_resource),
+    ///     merges_resource: None,
+    ///     max_length: Some(128),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..Default::default()
+    /// };
+    /// let llama_generator = LlamaGenerator::new_with_tokenizer(generate_config, tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<LlamaGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate()?;
+        let mut var_store = nn::VarStore::new(device);
+
+        let config = LlamaConfig::from_file(config_path);
+        let model = LlamaForCausalLM::new(var_store.root(), &config);
+        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+
+        let bos_token_id = tokenizer.get_bos_id().or(config.bos_token_id);
+        let eos_token_ids = tokenizer
+            .get_eos_id()
+            .or(config.eos_token_id)
+            .map(|id| vec![id]);
+        let pad_token_id = tokenizer.get_pad_id().or(config.pad_token_id);
+        let max_position_embeddings = config.max_position_embeddings;
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = config.decoder_start_token_id;
+
+        Ok(LlamaGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+
+    /// Quantize the weights of the model to int4 in place (see `LlamaForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+
+    /// Split the layers of the model across devices in place (see `LlamaForCausalLM::tensor_parallelize`).
+    /// The model should be loaded on the first device of the configuration.
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+}
+
+impl PrivateLanguageGenerator for LlamaGenerator {
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn _get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        &mut self.tokenizer
+    }
+    fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+    fn get_var_store_mut(&mut self) -> Result<&mut nn::VarStore, RustBertError> {
+        Ok(&mut self.var_store)
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> Option<i64> {
+        Some(self.max_position_embeddings)
+    }
+
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        self.model.forward_t(
+            input_ids,
+            layer_past,
+            attention_mask,
+            position_ids,
+            input_embeds,
+            train,
+        )
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        // Positions are counted from the first non-padding token of each (left-padded) prompt
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::LlamaCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::LlamaCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::LlamaCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::LlamaCache(None),
+            },
+            _ => panic!("Cache type incompatible with LLaMA"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::LlamaCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for LLaMA model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator for LlamaGenerator {}
@@ -0,0 +1,67 @@
+//! # LLaMA (Touvron et al.) and Mistral (Jiang et al.)
+//!
+//! Implementation of the LLaMA decoder architecture ([LLaMA: Open and Efficient Foundation Language Models](https://arxiv.org/abs/2302.13971) Touvron, Lavril, Izacard et al., 2023
+//! and [Llama 2: Open Foundation and Fine-Tuned Chat Models](https://arxiv.org/abs/2307.09288) Touvron, Martin, Stone et al., 2023).
+//! LLaMA is a decoder-only model using pre-normalization with RMSNorm, SwiGLU feed-forward layers, rotary position embeddings
+//! and grouped-query attention (GQA) for the larger models. Mistral ([Mistral 7B](https://arxiv.org/abs/2310.06825) Jiang, Sablayrolles, Mensch et al., 2023)
+//! shares the architecture, restricting the attention to a sliding window of the previous tokens (`sliding_window` set in the configuration).
+//! The base model is implemented in the `llama_model::LlamaModel` struct, the language modeling head in `llama_model::LlamaForCausalLM`
+//! and the text generation utilities in `llama_model::LlamaGenerator`, also available through the `TextGenerationModel` pipeline with `ModelType::Llama`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights to the `.ot` format, for example with `python utils/convert_model.py path/to/llama/model.safetensors`.
+//!   Quantized GGUF checkpoints (`Q4_0`, `Q4_1` and `Q8_0` formats) can also be loaded with `load_weights_from_file`, and the
+//!   configuration can be read from their metadata with `LlamaConfig::from_gguf`.
+//! - The SentencePiece tokenizer should be loaded from a `tokenizer.json` file and a special tokens map using the `hf-tokenizers` feature
+//!   (see `TokenizerOption::from_hf_tokenizer_file`).
+//!
+//! Pretrained resources for TinyLlama-1.1B-Chat are available in `LlamaModelResources`, `LlamaConfigResources`,
+//! `LlamaVocabResources` and `LlamaSpecialMap`.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+//! use rust_bert::pipelines::common::TokenizerOption;
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+//!     "path/to/tokenizer.json",
+//!     "path/to/special_tokens_map.json",
+//! )?;
+//! let config = LlamaConfig::from_file(config_path);
+//! let llama_model = LlamaForCausalLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod llama_model;
+mod transformer;
+
+pub use attention::{LayerState, LlamaAttention, LlamaRotaryEmbedding};
+pub use llama_model::{
+    LlamaConfig, LlamaConfigResources, LlamaForCausalLM, LlamaGenerator, LlamaModel,
+    LlamaModelOutput, LlamaModelResources, LlamaSpecialMap, LlamaVocabResources,
+};
+pub use transformer::{LlamaDecoderLayer, LlamaMLP, LlamaRMSNorm};
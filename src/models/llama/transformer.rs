@@ -0,0 +1,200 @@
+// Copyright 2023 Meta AI, Mistral AI and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::{gated_mlp_forward, TensorParallelConfig};
+use crate::llama::attention::{LayerState, LlamaAttention};
+use crate::llama::llama_model::LlamaConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::nn::{Init, Module};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Root mean square layer normalization
+/// Scales the hidden states by their root mean square (without centering or bias), computed in full precision.
+pub struct LlamaRMSNorm {
+    weight: Tensor,
+    epsilon: f64,
+}
+
+impl LlamaRMSNorm {
+    pub fn new<'p, P>(p: P, hidden_size: i64, epsilon: f64) -> LlamaRMSNorm
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let weight = p.borrow().var("weight", &[hidden_size], Init::Const(1.0));
+        LlamaRMSNorm { weight, epsilon }
+    }
+}
+
+impl Module for LlamaRMSNorm {
+    fn forward(&self, x: &Tensor) -> Tensor {
+        let input_type = x.kind();
+        let x = x.to_kind(Kind::Float);
+        let variance = x
+            .pow_tensor_scalar(2.0_f64)
+            .mean_dim([-1].as_slice(), true, Kind::Float);
+        let x = (x * (variance + self.epsilon).rsqrt()).to_kind(input_type);
+        &self.weight * x
+    }
+}
+
+/// # LLaMA feed-forward block
+/// Gated MLP (SwiGLU): `down_proj(silu(gate_proj(x)) * up_proj(x))`
+pub struct LlamaMLP {
+    gate_proj: QuantizableLinear,
+    up_proj: QuantizableLinear,
+    down_proj: QuantizableLinear,
+    activation: TensorFunction,
+}
+
+impl LlamaMLP {
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.mlp_bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let gate_proj = nn::linear(
+            p / "gate_proj",
+            config.hidden_size,
+            config.intermediate_size,
+            linear_config,
+        );
+        let up_proj = nn::linear(
+            p / "up_proj",
+            config.hidden_size,
+            config.intermediate_size,
+            linear_config,
+        );
+        let down_proj = nn::linear(
+            p / "down_proj",
+            config.intermediate_size,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let activation = config.hidden_act.get_function();
+
+        LlamaMLP {
+            gate_proj: gate_proj.into(),
+            up_proj: up_proj.into(),
+            down_proj: down_proj.into(),
+            activation,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.gate_proj.quantize_int4(config)?;
+        self.up_proj.quantize_int4(config)?;
+        self.down_proj.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.gate_proj.split_columns(&config.devices, 1)?;
+        self.up_proj.split_columns(&config.devices, 1)?;
+        self.down_proj.split_rows(&config.devices, 1)
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        gated_mlp_forward(
+            &self.gate_proj,
+            &self.up_proj,
+            &self.down_proj,
+            self.activation.get_fn(),
+            hidden_states,
+        )
+    }
+}
+
+pub struct LlamaDecoderLayer {
+    input_layernorm: LlamaRMSNorm,
+    self_attn: LlamaAttention,
+    post_attention_layernorm: LlamaRMSNorm,
+    mlp: LlamaMLP,
+}
+
+impl LlamaDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let epsilon = config.rms_norm_eps.unwrap_or(1e-6);
+        let input_layernorm = LlamaRMSNorm::new(p / "input_layernorm", config.hidden_size, epsilon);
+        let self_attn = LlamaAttention::new(p / "self_attn", config);
+        let post_attention_layernorm =
+            LlamaRMSNorm::new(p / "post_attention_layernorm", config.hidden_size, epsilon);
+        let mlp = LlamaMLP::new(p / "mlp", config);
+
+        LlamaDecoderLayer {
+            input_layernorm,
+            self_attn,
+            post_attention_layernorm,
+            mlp,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attn.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attn.tensor_parallelize(config)?;
+        self.mlp.tensor_parallelize(config)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        rotary: (&Tensor, &Tensor),
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (attention_output, present, attention_weights) = self.self_attn.forward_t(
+            &hidden_states.apply(&self.input_layernorm),
+            attention_mask,
+            rotary,
+            layer_past,
+            train,
+        );
+        let hidden_states = hidden_states + attention_output;
+
+        let feed_forward_output = self
+            .mlp
+            .forward(&hidden_states.apply(&self.post_attention_layernorm));
+        let hidden_states = hidden_states + feed_forward_output;
+
+        (hidden_states, present, attention_weights)
+    }
+}
@@ -12,6 +12,7 @@
 
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::mps;
 use crate::longformer::LongformerConfig;
 use crate::RustBertError;
 use std::borrow::Borrow;
@@ -80,7 +81,7 @@ impl LongformerEmbeddings {
 
     fn create_position_ids_from_input_ids(&self, input_ids: &Tensor) -> Tensor {
         let mask = input_ids.ne(self.pad_token_id);
-        mask.cumsum(1, Kind::Int64) * mask + self.pad_token_id
+        mps::cumsum(&mask, 1, Kind::Int64) * mask + self.pad_token_id
     }
 
     fn create_position_ids_from_input_embeds(&self, inputs_embeds: &Tensor) -> Tensor {
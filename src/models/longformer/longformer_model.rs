@@ -10,9 +10,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::activations::{TensorFunction, _tanh};
+use crate::common::activations::{_tanh, TensorFunction};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::weight_tying::TiedWeights;
 use crate::longformer::embeddings::LongformerEmbeddings;
 use crate::longformer::encoder::LongformerEncoder;
 use crate::{Activation, Config, RustBertError};
@@ -660,6 +661,12 @@ pub struct LongformerForMaskedLM {
 }
 
 impl LongformerForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "longformer.embeddings.word_embeddings.weight",
+        "lm_head.decoder.weight",
+    );
+
     /// Build a new `LongformerForMaskedLM`
     ///
     /// # Arguments
@@ -11,11 +11,10 @@
 // limitations under the License.
 
 use crate::common::dropout::Dropout;
+use crate::common::position_embeddings::relative_position_bucket;
 use crate::longt5::layer_norm::LongT5LayerNorm;
 use crate::longt5::LongT5Config;
-use crate::t5::{
-    get_relative_position_bucket, LayerState as T5layerState, T5Attention, T5LayerCrossAttention,
-};
+use crate::t5::{LayerState as T5layerState, T5Attention, T5LayerCrossAttention};
 use std::borrow::Borrow;
 use tch::nn::LinearConfig;
 use tch::{nn, Device, IndexOp, Kind, Tensor};
@@ -208,7 +207,7 @@ fn compute_bias(
     let context_position = memory_position.narrow(0, block_length, block_length);
     let relative_position = memory_position.unsqueeze(0) - context_position.unsqueeze(-1);
 
-    let rp_bucket = get_relative_position_bucket(
+    let rp_bucket = relative_position_bucket(
         &relative_position,
         !is_decoder,
         relative_attention_num_buckets,
@@ -490,7 +489,7 @@ impl LongT5TransientGlobalAttention {
             .where_scalarother(&side_attention_mask.gt(0), -1e10);
 
         let side_relative_position = make_side_relative_position_ids(mask, self.global_block_size);
-        let side_relative_position_bucket = get_relative_position_bucket(
+        let side_relative_position_bucket = relative_position_bucket(
             &side_relative_position,
             !self.is_decoder,
             self.relative_attention_num_buckets,
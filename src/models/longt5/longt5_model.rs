@@ -155,6 +155,7 @@ impl From<&LongT5Config> for T5Config {
             layer_norm_epsilon: val.layer_norm_epsilon,
             num_heads: val.num_heads,
             num_layers: val.num_layers,
+            num_decoder_layers: val.num_decoder_layers,
             output_past: val.output_past,
             pad_token_id: val.pad_token_id,
             relative_attention_num_buckets: val.relative_attention_num_buckets,
@@ -590,7 +591,7 @@ impl LongT5Generator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = LongT5Config::from_file(config_path);
@@ -708,6 +709,7 @@ impl PrivateLanguageGenerator for LongT5Generator {
         Ok(LMModelOutput {
             lm_logits: base_model_output.decoder_output,
             cache: Cache::LongT5Cache(base_model_output.next_cache),
+            last_hidden_state: None,
         })
     }
 
@@ -10,6 +10,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::mps;
 use std::borrow::Borrow;
 use std::ops::Deref;
 use std::sync::RwLock;
@@ -99,7 +100,8 @@ impl SinusoidalPositionalEmbedding {
         past_key_values_length: i64,
     ) -> Tensor {
         let mask = input_ids.ne(self.padding_idx).to_kind(Kind::Int64);
-        let incremental_indices = (mask.cumsum(1, Kind::Int64) + past_key_values_length) * mask;
+        let incremental_indices =
+            (mps::cumsum(&mask, 1, Kind::Int64) + past_key_values_length) * mask;
         incremental_indices + self.padding_idx
     }
 
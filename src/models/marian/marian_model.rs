@@ -756,7 +756,7 @@ impl MarianGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = BartConfig::from_file(config_path);
@@ -873,6 +873,7 @@ impl PrivateLanguageGenerator for MarianGenerator {
         Ok(LMModelOutput {
             lm_logits: base_model_output.decoder_output,
             cache: Cache::BARTCache(base_model_output.cache),
+            last_hidden_state: None,
         })
     }
 
@@ -882,6 +883,7 @@ impl PrivateLanguageGenerator for MarianGenerator {
         current_length: i64,
         max_length: Option<i64>,
         _forced_bos_token_id: Option<i64>,
+        _forced_eos_token_id: Option<i64>,
     ) {
         let _ = scores.index_fill_(
             1,
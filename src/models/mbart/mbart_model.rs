@@ -795,7 +795,7 @@ impl MBartGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = MBartConfig::from_file(config_path);
@@ -911,6 +911,7 @@ impl PrivateLanguageGenerator for MBartGenerator {
         Ok(LMModelOutput {
             lm_logits: base_model_output.decoder_output,
             cache: Cache::BARTCache(base_model_output.cache),
+            last_hidden_state: None,
         })
     }
 
@@ -61,5 +61,5 @@ pub use mbart_model::{
 };
 
 pub use attention::LayerState;
-pub(crate) use decoder::MBartDecoderLayer;
+pub(crate) use decoder::{MBartDecoder, MBartDecoderLayer};
 pub(crate) use encoder::MBartEncoderLayer;
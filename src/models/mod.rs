@@ -1,27 +1,80 @@
 //! # Torch implementation of language models
 
+#[cfg(feature = "albert")]
 pub mod albert;
+#[cfg(feature = "bart")]
 pub mod bart;
+#[cfg(feature = "bert")]
 pub mod bert;
+#[cfg(feature = "bigbird")]
+pub mod bigbird;
+#[cfg(feature = "bloom")]
+pub mod bloom;
+#[cfg(feature = "deberta")]
 pub mod deberta;
+#[cfg(feature = "deberta-v2")]
 pub mod deberta_v2;
+#[cfg(feature = "distilbert")]
 pub mod distilbert;
+#[cfg(feature = "donut")]
+pub mod donut;
+#[cfg(feature = "electra")]
 pub mod electra;
+#[cfg(feature = "falcon")]
+pub mod falcon;
+#[cfg(feature = "fnet")]
 pub mod fnet;
+#[cfg(feature = "gpt2")]
 pub mod gpt2;
+#[cfg(feature = "gpt-j")]
 pub mod gpt_j;
+#[cfg(feature = "gpt-neo")]
 pub mod gpt_neo;
+#[cfg(feature = "jina-bert")]
+pub mod jina_bert;
+#[cfg(feature = "llama")]
+pub mod llama;
+#[cfg(feature = "longformer")]
 pub mod longformer;
+#[cfg(feature = "longt5")]
 pub mod longt5;
+#[cfg(feature = "m2m-100")]
 pub mod m2m_100;
+#[cfg(feature = "marian")]
 pub mod marian;
+#[cfg(feature = "mbart")]
 pub mod mbart;
+#[cfg(feature = "mobilebert")]
 pub mod mobilebert;
+#[cfg(feature = "modernbert")]
+pub mod modernbert;
+#[cfg(feature = "nllb")]
 pub mod nllb;
+#[cfg(feature = "nomic-bert")]
+pub mod nomic_bert;
+#[cfg(feature = "openai-gpt")]
 pub mod openai_gpt;
+#[cfg(feature = "opt")]
+pub mod opt;
+#[cfg(feature = "pegasus")]
 pub mod pegasus;
+#[cfg(feature = "phi")]
+pub mod phi;
+#[cfg(feature = "prophetnet")]
 pub mod prophetnet;
+#[cfg(feature = "reformer")]
 pub mod reformer;
+#[cfg(feature = "roberta")]
 pub mod roberta;
+#[cfg(feature = "siglip")]
+pub mod siglip;
+#[cfg(feature = "starcoder2")]
+pub mod starcoder2;
+#[cfg(feature = "t5")]
 pub mod t5;
+#[cfg(feature = "tapas")]
+pub mod tapas;
+#[cfg(feature = "whisper")]
+pub mod whisper;
+#[cfg(feature = "xlnet")]
 pub mod xlnet;
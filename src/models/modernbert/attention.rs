@@ -0,0 +1,194 @@
+// Copyright 2024 Answer.AI, LightOn, and contributors, and the HuggingFace Inc. team
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::position_embeddings::apply_rotary_pos_emb;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::modernbert::ModernBertConfig;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+/// Builds the additive attention bias restricting attention to a local window of `window_size` tokens
+/// (`window_size / 2` tokens on each side of the query position), of shape (*sequence_length*, *sequence_length*).
+pub(crate) fn build_sliding_window_bias(
+    sequence_length: i64,
+    window_size: i64,
+    device: Device,
+) -> Tensor {
+    let positions = Tensor::arange(sequence_length, (Kind::Int64, device));
+    let distance = (positions.unsqueeze(0) - positions.unsqueeze(1)).abs();
+    distance.gt(window_size / 2).to_kind(Kind::Float) * -10000.0
+}
+
+#[derive(Debug)]
+/// # ModernBERT self-attention
+/// Multi-head self-attention with a fused query/key/value projection and rotary position embeddings.
+/// Depending on the layer, the attention is either global or restricted to a sliding window (controlled by the attention mask).
+pub struct ModernBertAttention {
+    wqkv: nn::Linear,
+    wo: nn::Linear,
+    attention_dropout: Dropout,
+    output_dropout: Dropout,
+    num_attention_heads: i64,
+    head_dim: i64,
+    output_attentions: bool,
+}
+
+impl ModernBertAttention {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Hidden size not a multiple of the number of attention heads"
+        );
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.attention_bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let wqkv = nn::linear(
+            p / "Wqkv",
+            config.hidden_size,
+            3 * config.hidden_size,
+            linear_config,
+        );
+        let wo = nn::linear(
+            p / "Wo",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let attention_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+        let output_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+
+        ModernBertAttention {
+            wqkv,
+            wo,
+            attention_dropout,
+            output_dropout,
+            num_attention_heads: config.num_attention_heads,
+            head_dim: config.hidden_size / config.num_attention_heads,
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        rotary: (&Tensor, &Tensor),
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (bs, seq_len) = (hidden_states.size()[0], hidden_states.size()[1]);
+        let qkv = hidden_states
+            .apply(&self.wqkv)
+            .view([bs, seq_len, 3, self.num_attention_heads, self.head_dim])
+            .permute([2, 0, 3, 1, 4]);
+        let (cos, sin) = rotary;
+        let query = apply_rotary_pos_emb(&qkv.get(0), cos, sin);
+        let key = apply_rotary_pos_emb(&qkv.get(1), cos, sin);
+        let value = qkv.get(2);
+
+        let scores = attention_scores(
+            &(query / (self.head_dim as f64).sqrt()),
+            &key.transpose(-1, -2),
+        ) + attention_mask;
+        let weights = scores
+            .softmax(-1, Kind::Float)
+            .to_kind(scores.kind())
+            .apply_t(&self.attention_dropout, train);
+        let context = attention_output(&weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([bs, seq_len, self.num_attention_heads * self.head_dim])
+            .apply(&self.wo)
+            .apply_t(&self.output_dropout, train);
+
+        if !self.output_attentions {
+            (context, None)
+        } else {
+            (context, Some(weights))
+        }
+    }
+}
+
+/// # ModernBERT gated feed-forward layer
+/// Computes `Wo(act(input) * gate)`, where `input` and `gate` are the two halves of the `Wi` projection.
+pub struct ModernBertMLP {
+    wi: nn::Linear,
+    wo: nn::Linear,
+    activation: TensorFunction,
+    dropout: Dropout,
+}
+
+impl ModernBertMLP {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.mlp_bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let wi = nn::linear(
+            p / "Wi",
+            config.hidden_size,
+            2 * config.intermediate_size,
+            linear_config,
+        );
+        let wo = nn::linear(
+            p / "Wo",
+            config.intermediate_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let activation = config.hidden_activation.get_function();
+        let dropout = Dropout::new(config.mlp_dropout.unwrap_or(0.0));
+
+        ModernBertMLP {
+            wi,
+            wo,
+            activation,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let projected = hidden_states.apply(&self.wi).chunk(2, -1);
+        let (input, gate) = (&projected[0], &projected[1]);
+        (self.activation.get_fn()(input) * gate)
+            .apply_t(&self.dropout, train)
+            .apply(&self.wo)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sliding_window_bias() {
+        let bias = build_sliding_window_bias(5, 2, Device::Cpu);
+        assert_eq!(bias.size(), vec![5, 5]);
+        assert_eq!(bias.double_value(&[2, 1]), 0.0);
+        assert_eq!(bias.double_value(&[2, 3]), 0.0);
+        assert_eq!(bias.double_value(&[2, 0]), -10000.0);
+        assert_eq!(bias.double_value(&[0, 4]), -10000.0);
+    }
+}
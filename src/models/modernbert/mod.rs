@@ -0,0 +1,58 @@
+//! # ModernBERT (Warner et al.)
+//!
+//! Implementation of the ModernBERT language model ([Smarter, Better, Faster, Longer: A Modern Bidirectional Encoder for Fast, Memory Efficient, and Long Context Finetuning and Inference](https://arxiv.org/abs/2412.13663) Warner, Chaffin, Clavié et al., 2024).
+//! ModernBERT updates the BERT encoder with rotary position embeddings, gated (GeGLU) feed-forward layers, pre-normalization layers without biases
+//! and an alternating global / local (sliding window) attention pattern, supporting sequences of up to 8192 tokens. The model does not use token type embeddings.
+//! The base model is implemented in the `modernbert_model::ModernBertModel` struct. Several language model heads have also been implemented, including:
+//! - Masked language heads: `modernbert_model::ModernBertForMaskedLM`
+//! - Sequence classification: `modernbert_model::ModernBertForSequenceClassification`
+//! - Token classification (e.g. NER, POS tagging): `modernbert_model::ModernBertForTokenClassification`
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). The pretrained safetensors checkpoints are loaded directly without conversion. Alternatively, the Python utility scripts convert the `.bin` weights to the `.ot` format.
+//! - ModernBERT uses a byte-level BPE tokenizer that should be loaded from a `tokenizer.json` file and a special tokens map using the `hf-tokenizers` feature
+//! (see `TokenizerOption::from_hf_tokenizer_file`).
+//! Pretrained models are available and can be downloaded using RemoteResources.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::modernbert::{ModernBertConfig, ModernBertForMaskedLM};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let config = ModernBertConfig::from_file(config_path);
+//! let modernbert_model = ModernBertForMaskedLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod modernbert_model;
+
+pub use attention::{ModernBertAttention, ModernBertMLP};
+pub use modernbert_model::{
+    ModernBertClassifierPooling, ModernBertConfig, ModernBertConfigResources, ModernBertEmbeddings,
+    ModernBertEncoder, ModernBertForMaskedLM, ModernBertForSequenceClassification,
+    ModernBertForTokenClassification, ModernBertLayer, ModernBertLayerNorm,
+    ModernBertMaskedLMOutput, ModernBertModel, ModernBertModelOutput, ModernBertModelResources,
+    ModernBertSequenceClassificationOutput, ModernBertSpecialMap,
+    ModernBertTokenClassificationOutput, ModernBertVocabResources,
+};
@@ -0,0 +1,1048 @@
+// Copyright 2024 Answer.AI, LightOn, and contributors, and the HuggingFace Inc. team
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::{Activation, TensorFunction};
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::position_embeddings::RotaryEmbedding;
+use crate::common::weight_tying::TiedWeights;
+use crate::modernbert::attention::{build_sliding_window_bias, ModernBertAttention, ModernBertMLP};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use std::collections::HashMap;
+use tch::nn::{EmbeddingConfig, Init, Module};
+use tch::{nn, Kind, Tensor};
+
+/// # ModernBERT Pretrained model weight files
+pub struct ModernBertModelResources;
+
+/// # ModernBERT Pretrained model config files
+pub struct ModernBertConfigResources;
+
+/// # ModernBERT Pretrained model vocab files
+pub struct ModernBertVocabResources;
+
+/// # ModernBERT Pretrained model special tokens map files
+pub struct ModernBertSpecialMap;
+
+impl ModernBertModelResources {
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-base>.
+    pub const MODERNBERT_BASE: (&'static str, &'static str) = (
+        "modernbert-base/model",
+        "https://huggingface.co/answerdotai/ModernBERT-base/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-large>.
+    pub const MODERNBERT_LARGE: (&'static str, &'static str) = (
+        "modernbert-large/model",
+        "https://huggingface.co/answerdotai/ModernBERT-large/resolve/main/model.safetensors",
+    );
+}
+
+impl ModernBertConfigResources {
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-base>.
+    pub const MODERNBERT_BASE: (&'static str, &'static str) = (
+        "modernbert-base/config",
+        "https://huggingface.co/answerdotai/ModernBERT-base/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-large>.
+    pub const MODERNBERT_LARGE: (&'static str, &'static str) = (
+        "modernbert-large/config",
+        "https://huggingface.co/answerdotai/ModernBERT-large/resolve/main/config.json",
+    );
+}
+
+impl ModernBertVocabResources {
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-base>.
+    pub const MODERNBERT_BASE: (&'static str, &'static str) = (
+        "modernbert-base/tokenizer",
+        "https://huggingface.co/answerdotai/ModernBERT-base/resolve/main/tokenizer.json",
+    );
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-large>.
+    pub const MODERNBERT_LARGE: (&'static str, &'static str) = (
+        "modernbert-large/tokenizer",
+        "https://huggingface.co/answerdotai/ModernBERT-large/resolve/main/tokenizer.json",
+    );
+}
+
+impl ModernBertSpecialMap {
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-base>.
+    pub const MODERNBERT_BASE: (&'static str, &'static str) = (
+        "modernbert-base/special",
+        "https://huggingface.co/answerdotai/ModernBERT-base/resolve/main/special_tokens_map.json",
+    );
+    /// Shared under Apache 2.0 license by Answer.AI at <https://huggingface.co/answerdotai/ModernBERT-large>.
+    pub const MODERNBERT_LARGE: (&'static str, &'static str) = (
+        "modernbert-large/special",
+        "https://huggingface.co/answerdotai/ModernBERT-large/resolve/main/special_tokens_map.json",
+    );
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, Copy, PartialEq, Eq)]
+/// # Pooling strategy used by ModernBERT sequence classification heads
+pub enum ModernBertClassifierPooling {
+    /// Hidden state of the first (`[CLS]`) token
+    cls,
+    /// Average of the hidden states over non-masked tokens
+    mean,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # ModernBERT model configuration
+/// Defines the ModernBERT model architecture (e.g. number of layers, hidden layer size, label mapping...)
+pub struct ModernBertConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub hidden_activation: Activation,
+    pub max_position_embeddings: i64,
+    pub norm_eps: Option<f64>,
+    pub norm_bias: Option<bool>,
+    pub pad_token_id: Option<i64>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub cls_token_id: Option<i64>,
+    pub sep_token_id: Option<i64>,
+    pub global_rope_theta: f64,
+    pub local_rope_theta: Option<f64>,
+    pub global_attn_every_n_layers: i64,
+    pub local_attention: i64,
+    pub attention_bias: Option<bool>,
+    pub attention_dropout: Option<f64>,
+    pub embedding_dropout: Option<f64>,
+    pub mlp_bias: Option<bool>,
+    pub mlp_dropout: Option<f64>,
+    pub decoder_bias: Option<bool>,
+    pub classifier_pooling: Option<ModernBertClassifierPooling>,
+    pub classifier_dropout: Option<f64>,
+    pub classifier_bias: Option<bool>,
+    pub classifier_activation: Option<Activation>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+}
+
+impl Config for ModernBertConfig {}
+
+impl Default for ModernBertConfig {
+    fn default() -> Self {
+        ModernBertConfig {
+            vocab_size: 50368,
+            hidden_size: 768,
+            intermediate_size: 1152,
+            num_hidden_layers: 22,
+            num_attention_heads: 12,
+            hidden_activation: Activation::gelu,
+            max_position_embeddings: 8192,
+            norm_eps: Some(1e-5),
+            norm_bias: Some(false),
+            pad_token_id: Some(50283),
+            bos_token_id: Some(50281),
+            eos_token_id: Some(50282),
+            cls_token_id: Some(50281),
+            sep_token_id: Some(50282),
+            global_rope_theta: 160000.0,
+            local_rope_theta: Some(10000.0),
+            global_attn_every_n_layers: 3,
+            local_attention: 128,
+            attention_bias: Some(false),
+            attention_dropout: Some(0.0),
+            embedding_dropout: Some(0.0),
+            mlp_bias: Some(false),
+            mlp_dropout: Some(0.0),
+            decoder_bias: Some(true),
+            classifier_pooling: Some(ModernBertClassifierPooling::cls),
+            classifier_dropout: Some(0.0),
+            classifier_bias: Some(false),
+            classifier_activation: Some(Activation::gelu),
+            output_attentions: None,
+            output_hidden_states: None,
+            id2label: None,
+            label2id: None,
+        }
+    }
+}
+
+impl ModernBertConfig {
+    fn get_num_labels(&self) -> Result<i64, RustBertError> {
+        Ok(self
+            .id2label
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "num_labels not provided in configuration".to_string(),
+                )
+            })?
+            .len() as i64)
+    }
+}
+
+#[derive(Debug)]
+/// # ModernBERT layer normalization
+/// Layer normalization with an optional bias term (ModernBERT checkpoints are trained without normalization biases).
+pub struct ModernBertLayerNorm {
+    weight: Tensor,
+    bias: Option<Tensor>,
+    hidden_size: i64,
+    eps: f64,
+}
+
+impl ModernBertLayerNorm {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertLayerNorm
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let weight = p.var("weight", &[config.hidden_size], Init::Const(1.0));
+        let bias = if config.norm_bias.unwrap_or(false) {
+            Some(p.var("bias", &[config.hidden_size], Init::Const(0.0)))
+        } else {
+            None
+        };
+
+        ModernBertLayerNorm {
+            weight,
+            bias,
+            hidden_size: config.hidden_size,
+            eps: config.norm_eps.unwrap_or(1e-5),
+        }
+    }
+}
+
+impl Module for ModernBertLayerNorm {
+    fn forward(&self, x: &Tensor) -> Tensor {
+        x.layer_norm(
+            [self.hidden_size],
+            Some(&self.weight),
+            self.bias.as_ref(),
+            self.eps,
+            true,
+        )
+    }
+}
+
+#[derive(Debug)]
+/// # ModernBERT embeddings
+/// Word embeddings followed by a normalization layer. Positions are encoded with rotary embeddings in the attention
+/// layers, and the model does not use token type (segment) embeddings.
+pub struct ModernBertEmbeddings {
+    tok_embeddings: nn::Embedding,
+    norm: ModernBertLayerNorm,
+    dropout: Dropout,
+}
+
+impl ModernBertEmbeddings {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embedding_config = EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(50283),
+            ..Default::default()
+        };
+        let tok_embeddings = nn::embedding(
+            p / "tok_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            embedding_config,
+        );
+        let norm = ModernBertLayerNorm::new(p / "norm", config);
+        let dropout = Dropout::new(config.embedding_dropout.unwrap_or(0.0));
+
+        ModernBertEmbeddings {
+            tok_embeddings,
+            norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        let (calc_input_embeddings, _, _) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.tok_embeddings)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        Ok(input_embeddings
+            .apply(&self.norm)
+            .apply_t(&self.dropout, train))
+    }
+}
+
+/// # ModernBERT Layer
+/// Pre-normalization transformer layer made of a rotary self-attention block (global or sliding window)
+/// and a gated feed-forward block. The first layer does not normalize its attention input, which is already
+/// normalized by the embeddings.
+pub struct ModernBertLayer {
+    attn_norm: Option<ModernBertLayerNorm>,
+    attn: ModernBertAttention,
+    mlp_norm: ModernBertLayerNorm,
+    mlp: ModernBertMLP,
+    use_local_attention: bool,
+}
+
+impl ModernBertLayer {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig, layer_index: i64) -> ModernBertLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let attn_norm = if layer_index != 0 {
+            Some(ModernBertLayerNorm::new(p / "attn_norm", config))
+        } else {
+            None
+        };
+        let attn = ModernBertAttention::new(p / "attn", config);
+        let mlp_norm = ModernBertLayerNorm::new(p / "mlp_norm", config);
+        let mlp = ModernBertMLP::new(p / "mlp", config);
+        let use_local_attention = layer_index % config.global_attn_every_n_layers != 0;
+
+        ModernBertLayer {
+            attn_norm,
+            attn,
+            mlp_norm,
+            mlp,
+            use_local_attention,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        rotary: (&Tensor, &Tensor),
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let attention_input = match &self.attn_norm {
+            Some(attn_norm) => hidden_states.apply(attn_norm),
+            None => hidden_states.shallow_clone(),
+        };
+        let (attention_output, attention_weights) =
+            self.attn
+                .forward_t(&attention_input, attention_mask, rotary, train);
+        let hidden_states = hidden_states + attention_output;
+        let mlp_output = self
+            .mlp
+            .forward_t(&hidden_states.apply(&self.mlp_norm), train);
+        (hidden_states + mlp_output, attention_weights)
+    }
+}
+
+/// # ModernBERT Encoder
+/// Stack of `ModernBertLayer` followed by a final normalization layer. Every `global_attn_every_n_layers` layer
+/// attends to the full sequence, other layers attend to a local window of `local_attention` tokens.
+pub struct ModernBertEncoder {
+    output_attentions: bool,
+    output_hidden_states: bool,
+    global_rotary_embedding: RotaryEmbedding,
+    local_rotary_embedding: RotaryEmbedding,
+    local_attention: i64,
+    layers: Vec<ModernBertLayer>,
+    final_norm: ModernBertLayerNorm,
+}
+
+impl ModernBertEncoder {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let p_layers = p / "layers";
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        let mut layers: Vec<ModernBertLayer> = vec![];
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(ModernBertLayer::new(
+                &p_layers / layer_index,
+                config,
+                layer_index,
+            ));
+        }
+        let final_norm = ModernBertLayerNorm::new(p / "final_norm", config);
+        let head_dim = config.hidden_size / config.num_attention_heads;
+        let global_rotary_embedding =
+            RotaryEmbedding::new(head_dim, config.global_rope_theta, p.device());
+        let local_rotary_embedding = RotaryEmbedding::new(
+            head_dim,
+            config.local_rope_theta.unwrap_or(config.global_rope_theta),
+            p.device(),
+        );
+
+        ModernBertEncoder {
+            output_attentions,
+            output_hidden_states,
+            global_rotary_embedding,
+            local_rotary_embedding,
+            local_attention: config.local_attention,
+            layers,
+            final_norm,
+        }
+    }
+
+    pub fn forward_t(&self, input: &Tensor, mask: &Tensor, train: bool) -> ModernBertModelOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let (sequence_length, device, kind) = (input.size()[1], input.device(), input.kind());
+        let positions = Tensor::arange(sequence_length, (Kind::Int64, device));
+        let (global_cos, global_sin) = self.global_rotary_embedding.forward(&positions, kind);
+        let (local_cos, local_sin) = self.local_rotary_embedding.forward(&positions, kind);
+        let sliding_window_mask = mask
+            + build_sliding_window_bias(sequence_length, self.local_attention, device)
+                .to_kind(kind);
+
+        let mut hidden_state = input.copy();
+        for layer in &self.layers {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let (layer_output, attention_weights) = if layer.use_local_attention {
+                layer.forward_t(
+                    &hidden_state,
+                    &sliding_window_mask,
+                    (&local_cos, &local_sin),
+                    train,
+                )
+            } else {
+                layer.forward_t(&hidden_state, mask, (&global_cos, &global_sin), train)
+            };
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+        let hidden_state = hidden_state.apply(&self.final_norm);
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(hidden_state.copy());
+        };
+
+        ModernBertModelOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// # ModernBERT Base model
+/// Base architecture for ModernBERT models. Task-specific models will be built from this common base model.
+/// It is made of the following blocks:
+/// - `embeddings`: token embeddings (no position or segment embeddings)
+/// - `encoder`: Encoder (transformer) made of a vector of layers alternating global and local (sliding window)
+///   attention with rotary position embeddings, followed by a final normalization layer
+pub struct ModernBertModel {
+    embeddings: ModernBertEmbeddings,
+    encoder: ModernBertEncoder,
+}
+
+impl ModernBertModel {
+    /// Build a new `ModernBertModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the ModernBERT model
+    /// * `config` - `ModernBertConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::modernbert::{ModernBertConfig, ModernBertModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ModernBertConfig::from_file(config_path);
+    /// let modernbert = ModernBertModel::new(&p.root() / "model", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = ModernBertEmbeddings::new(p / "embeddings", config);
+        let encoder = ModernBertEncoder::new(p, config);
+
+        ModernBertModel {
+            embeddings,
+            encoder,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ModernBertModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::modernbert::{ModernBertConfig, ModernBertModel};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = ModernBertConfig::from_file(config_path);
+    /// # let modernbert_model = ModernBertModel::new(&vs.root() / "model", &config);
+    /// let (batch_size, sequence_length) = (4, 1024);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     modernbert_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<ModernBertModelOutput, RustBertError> {
+        let embedding_output = self.embeddings.forward_t(input_ids, input_embeds, train)?;
+
+        let input_shape = &embedding_output.size()[..2];
+        let calc_mask = Tensor::ones(input_shape, (Kind::Int8, embedding_output.device()));
+        let mask = mask.unwrap_or(&calc_mask);
+        if mask.dim() != 2 {
+            return Err(RustBertError::ValueError(
+                "Invalid attention mask dimension, must be 2".into(),
+            ));
+        }
+        let extended_attention_mask = mask.unsqueeze(1).unsqueeze(1);
+        let extended_attention_mask: Tensor =
+            ((extended_attention_mask.ones_like() - extended_attention_mask) * -10000.0)
+                .to_kind(embedding_output.kind());
+
+        Ok(self
+            .encoder
+            .forward_t(&embedding_output, &extended_attention_mask, train))
+    }
+}
+
+/// # ModernBERT prediction head
+/// Dense layer, activation and normalization applied before the task-specific output layers
+struct ModernBertPredictionHead {
+    dense: nn::Linear,
+    activation: TensorFunction,
+    norm: ModernBertLayerNorm,
+}
+
+impl ModernBertPredictionHead {
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertPredictionHead
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.classifier_bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let activation = config
+            .classifier_activation
+            .unwrap_or(config.hidden_activation)
+            .get_function();
+        let norm = ModernBertLayerNorm::new(p / "norm", config);
+
+        ModernBertPredictionHead {
+            dense,
+            activation,
+            norm,
+        }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        self.activation.get_fn()(&hidden_states.apply(&self.dense)).apply(&self.norm)
+    }
+}
+
+/// # ModernBERT for masked language model
+/// Base ModernBERT model with a masked language model head to predict missing tokens, for example `"Looks like one [MASK] is missing" -> "person"`
+/// It is made of the following blocks:
+/// - `model`: Base ModernBERT model
+/// - `head`: ModernBERT prediction head
+/// - `decoder`: linear layer projecting the hidden states to the vocabulary
+pub struct ModernBertForMaskedLM {
+    model: ModernBertModel,
+    head: ModernBertPredictionHead,
+    decoder: nn::Linear,
+}
+
+impl ModernBertForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights =
+        TiedWeights::new("model.embeddings.tok_embeddings.weight", "decoder.weight");
+
+    /// Build a new `ModernBertForMaskedLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the ModernBERT model
+    /// * `config` - `ModernBertConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::modernbert::{ModernBertConfig, ModernBertForMaskedLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ModernBertConfig::from_file(config_path);
+    /// let modernbert = ModernBertForMaskedLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &ModernBertConfig) -> ModernBertForMaskedLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = ModernBertModel::new(p / "model", config);
+        let head = ModernBertPredictionHead::new(p / "head", config);
+        let linear_config = nn::LinearConfig {
+            bias: config.decoder_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let decoder = nn::linear(
+            p / "decoder",
+            config.hidden_size,
+            config.vocab_size,
+            linear_config,
+        );
+
+        ModernBertForMaskedLM {
+            model,
+            head,
+            decoder,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ModernBertMaskedLMOutput` containing:
+    ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::modernbert::{ModernBertConfig, ModernBertForMaskedLM};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = ModernBertConfig::from_file(config_path);
+    /// # let modernbert_model = ModernBertForMaskedLM::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     modernbert_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<ModernBertMaskedLMOutput, RustBertError> {
+        let model_output = self.model.forward_t(input_ids, mask, input_embeds, train)?;
+
+        let prediction_scores = self
+            .head
+            .forward(&model_output.hidden_state)
+            .apply(&self.decoder);
+
+        Ok(ModernBertMaskedLMOutput {
+            prediction_scores,
+            all_hidden_states: model_output.all_hidden_states,
+            all_attentions: model_output.all_attentions,
+        })
+    }
+}
+
+/// # ModernBERT for sequence classification
+/// Base ModernBERT model with a classifier head to perform sentence or document-level classification
+/// It is made of the following blocks:
+/// - `model`: Base ModernBERT model
+/// - `head`: ModernBERT prediction head applied to the pooled hidden states (`[CLS]` token or mean pooling)
+/// - `dropout`: Dropout layer before the last linear layer
+/// - `classifier`: linear layer mapping from hidden to the number of classes to predict
+pub struct ModernBertForSequenceClassification {
+    model: ModernBertModel,
+    head: ModernBertPredictionHead,
+    dropout: Dropout,
+    classifier: nn::Linear,
+    pooling: ModernBertClassifierPooling,
+}
+
+impl ModernBertForSequenceClassification {
+    /// Build a new `ModernBertForSequenceClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the ModernBERT model
+    /// * `config` - `ModernBertConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::modernbert::{ModernBertConfig, ModernBertForSequenceClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ModernBertConfig::from_file(config_path);
+    /// let modernbert = ModernBertForSequenceClassification::new(&p.root(), &config).unwrap();
+    /// ```
+    pub fn new<'p, P>(
+        p: P,
+        config: &ModernBertConfig,
+    ) -> Result<ModernBertForSequenceClassification, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = ModernBertModel::new(p / "model", config);
+        let head = ModernBertPredictionHead::new(p / "head", config);
+        let dropout = Dropout::new(config.classifier_dropout.unwrap_or(0.0));
+        let classifier = nn::linear(
+            p / "classifier",
+            config.hidden_size,
+            config.get_num_labels()?,
+            Default::default(),
+        );
+        let pooling = config
+            .classifier_pooling
+            .unwrap_or(ModernBertClassifierPooling::cls);
+
+        Ok(ModernBertForSequenceClassification {
+            model,
+            head,
+            dropout,
+            classifier,
+            pooling,
+        })
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ModernBertSequenceClassificationOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::modernbert::{ModernBertConfig, ModernBertForSequenceClassification};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = ModernBertConfig::from_file(config_path);
+    /// # let modernbert_model = ModernBertForSequenceClassification::new(&vs.root(), &config).unwrap();
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     modernbert_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<ModernBertSequenceClassificationOutput, RustBertError> {
+        let model_output = self.model.forward_t(input_ids, mask, input_embeds, train)?;
+
+        let pooled_output = match (self.pooling, mask) {
+            (ModernBertClassifierPooling::mean, Some(mask)) => {
+                let mask = mask.unsqueeze(-1).to_kind(model_output.hidden_state.kind());
+                (&model_output.hidden_state * &mask).sum_dim_intlist(
+                    [1].as_slice(),
+                    false,
+                    mask.kind(),
+                ) / mask.sum_dim_intlist([1].as_slice(), false, mask.kind())
+            }
+            (ModernBertClassifierPooling::mean, None) => model_output.hidden_state.mean_dim(
+                [1].as_slice(),
+                false,
+                model_output.hidden_state.kind(),
+            ),
+            (ModernBertClassifierPooling::cls, _) => model_output.hidden_state.select(1, 0),
+        };
+
+        let logits = self
+            .head
+            .forward(&pooled_output)
+            .apply_t(&self.dropout, train)
+            .apply(&self.classifier);
+
+        Ok(ModernBertSequenceClassificationOutput {
+            logits,
+            all_hidden_states: model_output.all_hidden_states,
+            all_attentions: model_output.all_attentions,
+        })
+    }
+}
+
+/// # ModernBERT for token classification (e.g. NER, POS)
+/// Token-level classifier predicting a label for each token provided. Note that because of wordpiece tokenization, the labels predicted are
+/// not necessarily aligned with words in the sentence.
+/// It is made of the following blocks:
+/// - `model`: Base ModernBERT model
+/// - `head`: ModernBERT prediction head
+/// - `dropout`: Dropout layer before the last token-level predictions layer
+/// - `classifier`: Linear layer for token classification
+pub struct ModernBertForTokenClassification {
+    model: ModernBertModel,
+    head: ModernBertPredictionHead,
+    dropout: Dropout,
+    classifier: nn::Linear,
+}
+
+impl ModernBertForTokenClassification {
+    /// Build a new `ModernBertForTokenClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the ModernBERT model
+    /// * `config` - `ModernBertConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::modernbert::{ModernBertConfig, ModernBertForTokenClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ModernBertConfig::from_file(config_path);
+    /// let modernbert = ModernBertForTokenClassification::new(&p.root(), &config).unwrap();
+    /// ```
+    pub fn new<'p, P>(
+        p: P,
+        config: &ModernBertConfig,
+    ) -> Result<ModernBertForTokenClassification, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = ModernBertModel::new(p / "model", config);
+        let head = ModernBertPredictionHead::new(p / "head", config);
+        let dropout = Dropout::new(config.classifier_dropout.unwrap_or(0.0));
+        let classifier = nn::linear(
+            p / "classifier",
+            config.hidden_size,
+            config.get_num_labels()?,
+            Default::default(),
+        );
+
+        Ok(ModernBertForTokenClassification {
+            model,
+            head,
+            dropout,
+            classifier,
+        })
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ModernBertTokenClassificationOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::modernbert::{ModernBertConfig, ModernBertForTokenClassification};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = ModernBertConfig::from_file(config_path);
+    /// # let modernbert_model = ModernBertForTokenClassification::new(&vs.root(), &config).unwrap();
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     modernbert_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<ModernBertTokenClassificationOutput, RustBertError> {
+        let model_output = self.model.forward_t(input_ids, mask, input_embeds, train)?;
+
+        let logits = self
+            .head
+            .forward(&model_output.hidden_state)
+            .apply_t(&self.dropout, train)
+            .apply(&self.classifier);
+
+        Ok(ModernBertTokenClassificationOutput {
+            logits,
+            all_hidden_states: model_output.all_hidden_states,
+            all_attentions: model_output.all_attentions,
+        })
+    }
+}
+
+/// Container for the ModernBERT model output.
+pub struct ModernBertModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the ModernBERT masked LM model output.
+pub struct ModernBertMaskedLMOutput {
+    /// Logits for the vocabulary items at each sequence position
+    pub prediction_scores: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the ModernBERT sequence classification model output.
+pub struct ModernBertSequenceClassificationOutput {
+    /// Logits for each input (sequence) for each target class
+    pub logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the ModernBERT token classification model output.
+pub struct ModernBertTokenClassificationOutput {
+    /// Logits for each sequence item (token) for each target class
+    pub logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
@@ -0,0 +1,127 @@
+// Copyright 2024 Nomic AI
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::position_embeddings::apply_rotary_pos_emb;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::nomic_bert::NomicBertConfig;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+#[derive(Debug)]
+/// # Nomic BERT self-attention
+/// Multi-head self-attention with a fused query/key/value projection and rotary position embeddings.
+pub struct NomicBertAttention {
+    wqkv: nn::Linear,
+    out_proj: nn::Linear,
+    dropout: Dropout,
+    num_attention_heads: i64,
+    head_dim: i64,
+    output_attentions: bool,
+}
+
+impl NomicBertAttention {
+    pub fn new<'p, P>(p: P, config: &NomicBertConfig) -> NomicBertAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        assert_eq!(
+            config.n_embd % config.n_head,
+            0,
+            "Hidden size not a multiple of the number of attention heads"
+        );
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.qkv_proj_bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let wqkv = nn::linear(p / "Wqkv", config.n_embd, 3 * config.n_embd, linear_config);
+        let out_proj = nn::linear(p / "out_proj", config.n_embd, config.n_embd, linear_config);
+        let dropout = Dropout::new(config.attn_pdrop.unwrap_or(0.0));
+
+        NomicBertAttention {
+            wqkv,
+            out_proj,
+            dropout,
+            num_attention_heads: config.n_head,
+            head_dim: config.n_embd / config.n_head,
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        rotary: (&Tensor, &Tensor),
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (bs, seq_len) = (hidden_states.size()[0], hidden_states.size()[1]);
+        let qkv = hidden_states
+            .apply(&self.wqkv)
+            .view([bs, seq_len, 3, self.num_attention_heads, self.head_dim])
+            .permute([2, 0, 3, 1, 4]);
+        let (cos, sin) = rotary;
+        let query = apply_rotary_pos_emb(&qkv.get(0), cos, sin);
+        let key = apply_rotary_pos_emb(&qkv.get(1), cos, sin);
+        let value = qkv.get(2);
+
+        let scores = attention_scores(
+            &(query / (self.head_dim as f64).sqrt()),
+            &key.transpose(-1, -2),
+        ) + attention_mask;
+        let weights = scores
+            .softmax(-1, scores.kind())
+            .apply_t(&self.dropout, train);
+        let context = attention_output(&weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([bs, seq_len, self.num_attention_heads * self.head_dim])
+            .apply(&self.out_proj);
+
+        if !self.output_attentions {
+            (context, None)
+        } else {
+            (context, Some(weights))
+        }
+    }
+}
+
+/// # Nomic BERT SwiGLU feed-forward layer
+/// Computes `fc2(fc11(x) * silu(fc12(x)))`.
+pub struct NomicBertGatedMLP {
+    fc11: LinearNoBias,
+    fc12: LinearNoBias,
+    fc2: LinearNoBias,
+}
+
+impl NomicBertGatedMLP {
+    pub fn new<'p, P>(p: P, config: &NomicBertConfig) -> NomicBertGatedMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let n_inner = config.n_inner.unwrap_or(4 * config.n_embd);
+
+        let fc11 = linear_no_bias(p / "fc11", config.n_embd, n_inner, Default::default());
+        let fc12 = linear_no_bias(p / "fc12", config.n_embd, n_inner, Default::default());
+        let fc2 = linear_no_bias(p / "fc2", n_inner, config.n_embd, Default::default());
+
+        NomicBertGatedMLP { fc11, fc12, fc2 }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (hidden_states.apply(&self.fc11) * hidden_states.apply(&self.fc12).silu()).apply(&self.fc2)
+    }
+}
@@ -0,0 +1,62 @@
+//! # Nomic BERT: long-context BERT encoder with rotary embeddings (Nussbaum et al.)
+//!
+//! Implementation of the Nomic embed encoder ([Nomic Embed: Training a Reproducible Long Context Text Embedder](https://arxiv.org/abs/2402.01613) Nussbaum et al., 2024).
+//! The architecture follows BERT, with learned position embeddings replaced by rotary position embeddings
+//! and the feed-forward layers replaced by SwiGLU units, allowing the encoding of documents of up to 8192 tokens.
+//! The base model is implemented in the `nomic_bert_model::NomicBertModel` struct and is primarily intended to be used
+//! through the sentence embeddings pipeline.
+//!
+//! Nomic embeddings models are trained with task prefixes that should be prepended to the input text
+//! (e.g. `search_query: ` or `search_document: `), see `pipelines::sentence_embeddings::SentenceEmbeddingsTaskPrefix`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). The pretrained safetensors checkpoints are loaded directly without conversion. Alternatively, the Python utility scripts convert the `.bin` weights to the `.ot` format.
+//! - `BertTokenizer` using a `vocab.txt` vocabulary
+//! Pretrained models are available and can be downloaded using RemoteResources.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::nomic_bert::{NomicBertConfig, NomicBertModel};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::BertTokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/vocab.txt"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: BertTokenizer =
+//!     BertTokenizer::from_file(vocab_path.to_str().unwrap(), true, true)?;
+//! let config = NomicBertConfig::from_file(config_path);
+//! let nomic_bert_model = NomicBertModel::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod nomic_bert_model;
+
+pub use attention::{NomicBertAttention, NomicBertGatedMLP};
+pub use nomic_bert_model::{
+    NomicBertConfig, NomicBertConfigResources, NomicBertEmbeddings, NomicBertForSentenceEmbeddings,
+    NomicBertLayer, NomicBertModel, NomicBertModelOutput, NomicBertModelResources,
+    NomicBertVocabResources,
+};
@@ -0,0 +1,434 @@
+// Copyright 2024 Nomic AI
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::position_embeddings::RotaryEmbedding;
+use crate::nomic_bert::attention::{NomicBertAttention, NomicBertGatedMLP};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use std::collections::HashMap;
+use tch::{nn, Kind, Tensor};
+
+/// # Nomic BERT Pretrained model weight files
+pub struct NomicBertModelResources;
+
+/// # Nomic BERT Pretrained model config files
+pub struct NomicBertConfigResources;
+
+/// # Nomic BERT Pretrained model vocab files
+pub struct NomicBertVocabResources;
+
+impl NomicBertModelResources {
+    /// Shared under Apache 2.0 license by Nomic AI at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/model",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by Nomic AI at <https://huggingface.co/nomic-ai/nomic-embed-text-v1.5>.
+    pub const NOMIC_EMBED_TEXT_V1_5: (&'static str, &'static str) = (
+        "nomic-embed-text-v1.5/model",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1.5/resolve/main/model.safetensors",
+    );
+}
+
+impl NomicBertConfigResources {
+    /// Shared under Apache 2.0 license by Nomic AI at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/config",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by Nomic AI at <https://huggingface.co/nomic-ai/nomic-embed-text-v1.5>.
+    pub const NOMIC_EMBED_TEXT_V1_5: (&'static str, &'static str) = (
+        "nomic-embed-text-v1.5/config",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1.5/resolve/main/config.json",
+    );
+}
+
+impl NomicBertVocabResources {
+    /// Shared under Apache 2.0 license by Nomic AI at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/vocab",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/vocab.txt",
+    );
+    /// Shared under Apache 2.0 license by Nomic AI at <https://huggingface.co/nomic-ai/nomic-embed-text-v1.5>.
+    pub const NOMIC_EMBED_TEXT_V1_5: (&'static str, &'static str) = (
+        "nomic-embed-text-v1.5/vocab",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1.5/resolve/main/vocab.txt",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Nomic BERT model configuration
+/// Defines the Nomic BERT model architecture (e.g. number of layers, hidden layer size, rotary embeddings settings...)
+pub struct NomicBertConfig {
+    pub n_embd: i64,
+    pub n_head: i64,
+    pub n_layer: i64,
+    pub n_inner: Option<i64>,
+    pub n_positions: i64,
+    pub vocab_size: i64,
+    pub type_vocab_size: i64,
+    pub layer_norm_epsilon: Option<f64>,
+    pub rotary_emb_base: Option<f64>,
+    pub rotary_emb_fraction: Option<f64>,
+    pub qkv_proj_bias: Option<bool>,
+    pub embd_pdrop: Option<f64>,
+    pub attn_pdrop: Option<f64>,
+    pub resid_pdrop: Option<f64>,
+    pub pad_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+}
+
+impl Config for NomicBertConfig {}
+
+impl Default for NomicBertConfig {
+    fn default() -> Self {
+        NomicBertConfig {
+            n_embd: 768,
+            n_head: 12,
+            n_layer: 12,
+            n_inner: Some(3072),
+            n_positions: 8192,
+            vocab_size: 30528,
+            type_vocab_size: 2,
+            layer_norm_epsilon: Some(1e-12),
+            rotary_emb_base: Some(1000.0),
+            rotary_emb_fraction: Some(1.0),
+            qkv_proj_bias: Some(false),
+            embd_pdrop: Some(0.0),
+            attn_pdrop: Some(0.0),
+            resid_pdrop: Some(0.0),
+            pad_token_id: Some(0),
+            output_attentions: None,
+            output_hidden_states: None,
+            id2label: None,
+            label2id: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// # Nomic BERT embeddings
+/// Word and token type embeddings. Positions are encoded by rotary embeddings in the attention layers.
+pub struct NomicBertEmbeddings {
+    word_embeddings: nn::Embedding,
+    token_type_embeddings: nn::Embedding,
+}
+
+impl NomicBertEmbeddings {
+    pub fn new<'p, P>(p: P, config: &NomicBertConfig) -> NomicBertEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embedding_config = nn::EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(0),
+            ..Default::default()
+        };
+        let word_embeddings = nn::embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            config.n_embd,
+            embedding_config,
+        );
+        let token_type_embeddings = nn::embedding(
+            p / "token_type_embeddings",
+            config.type_vocab_size,
+            config.n_embd,
+            Default::default(),
+        );
+
+        NomicBertEmbeddings {
+            word_embeddings,
+            token_type_embeddings,
+        }
+    }
+
+    pub fn forward(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+    ) -> Result<Tensor, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let calc_token_type_ids = if token_type_ids.is_none() {
+            Some(Tensor::zeros(input_shape, (Kind::Int64, device)))
+        } else {
+            None
+        };
+        let token_type_embeddings = token_type_ids
+            .unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap())
+            .apply(&self.token_type_embeddings);
+
+        Ok(input_embeddings + token_type_embeddings)
+    }
+}
+
+/// # Nomic BERT Layer
+/// Post-normalization transformer block with rotary self-attention and a SwiGLU feed-forward layer.
+pub struct NomicBertLayer {
+    attn: NomicBertAttention,
+    mlp: NomicBertGatedMLP,
+    norm1: nn::LayerNorm,
+    norm2: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl NomicBertLayer {
+    pub fn new<'p, P>(p: P, config: &NomicBertConfig) -> NomicBertLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let attn = NomicBertAttention::new(p / "attn", config);
+        let mlp = NomicBertGatedMLP::new(p / "mlp", config);
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let norm1 = nn::layer_norm(p / "norm1", vec![config.n_embd], layer_norm_config);
+        let norm2 = nn::layer_norm(p / "norm2", vec![config.n_embd], layer_norm_config);
+        let dropout = Dropout::new(config.resid_pdrop.unwrap_or(0.0));
+
+        NomicBertLayer {
+            attn,
+            mlp,
+            norm1,
+            norm2,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        rotary: (&Tensor, &Tensor),
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (attention_output, attention_weights) =
+            self.attn
+                .forward_t(hidden_states, attention_mask, rotary, train);
+        let hidden_states =
+            (attention_output.apply_t(&self.dropout, train) + hidden_states).apply(&self.norm1);
+        let mlp_output = self.mlp.forward(&hidden_states);
+        let hidden_states =
+            (mlp_output.apply_t(&self.dropout, train) + hidden_states).apply(&self.norm2);
+        (hidden_states, attention_weights)
+    }
+}
+
+/// # Nomic BERT Base model
+/// Base architecture for the Nomic embeddings models (BERT with rotary position embeddings and SwiGLU feed-forward layers,
+/// supporting sequences of up to 8192 tokens). It is made of the following blocks:
+/// - `embeddings`: `token` and `segment_id` embeddings, followed by a layer normalization
+/// - `layers`: vector of transformer layers with rotary self-attention
+pub struct NomicBertModel {
+    embeddings: NomicBertEmbeddings,
+    emb_ln: nn::LayerNorm,
+    emb_dropout: Dropout,
+    layers: Vec<NomicBertLayer>,
+    rotary_embedding: RotaryEmbedding,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl NomicBertModel {
+    /// Build a new `NomicBertModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Nomic BERT model
+    /// * `config` - `NomicBertConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::nomic_bert::{NomicBertConfig, NomicBertModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = NomicBertConfig::from_file(config_path);
+    /// let nomic_bert = NomicBertModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &NomicBertConfig) -> NomicBertModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = NomicBertEmbeddings::new(p / "embeddings", config);
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let emb_ln = nn::layer_norm(p / "emb_ln", vec![config.n_embd], layer_norm_config);
+        let emb_dropout = Dropout::new(config.embd_pdrop.unwrap_or(0.0));
+
+        let p_layers = p / "encoder" / "layers";
+        let mut layers: Vec<NomicBertLayer> = vec![];
+        for layer_index in 0..config.n_layer {
+            layers.push(NomicBertLayer::new(&p_layers / layer_index, config));
+        }
+
+        let head_dim = config.n_embd / config.n_head;
+        let rotary_dim = (head_dim as f64 * config.rotary_emb_fraction.unwrap_or(1.0)) as i64;
+        let rotary_embedding = RotaryEmbedding::new(
+            rotary_dim,
+            config.rotary_emb_base.unwrap_or(1000.0),
+            p.device(),
+        );
+
+        NomicBertModel {
+            embeddings,
+            emb_ln,
+            emb_dropout,
+            layers,
+            rotary_embedding,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). If None set to 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `NomicBertModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::nomic_bert::{NomicBertConfig, NomicBertModel};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = NomicBertConfig::from_file(config_path);
+    /// # let nomic_bert_model = NomicBertModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (4, 2048);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let mask = Tensor::ones(&[batch_size, sequence_length], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     nomic_bert_model
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<NomicBertModelOutput, RustBertError> {
+        let embedding_output = self
+            .embeddings
+            .forward(input_ids, token_type_ids, input_embeds)?
+            .apply(&self.emb_ln)
+            .apply_t(&self.emb_dropout, train);
+
+        let (batch_size, sequence_length) =
+            (embedding_output.size()[0], embedding_output.size()[1]);
+        let device = embedding_output.device();
+        let calc_mask = Tensor::ones([batch_size, sequence_length], (Kind::Int8, device));
+        let mask = mask.unwrap_or(&calc_mask);
+        if mask.dim() != 2 {
+            return Err(RustBertError::ValueError(
+                "Invalid attention mask dimension, must be 2".into(),
+            ));
+        }
+        let extended_attention_mask = mask.unsqueeze(1).unsqueeze(1);
+        let extended_attention_mask: Tensor =
+            ((extended_attention_mask.ones_like() - extended_attention_mask) * -10000.0)
+                .to_kind(embedding_output.kind());
+
+        let (cos, sin) = self.rotary_embedding.forward(
+            &Tensor::arange(sequence_length, (Kind::Int64, device)),
+            embedding_output.kind(),
+        );
+
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let mut hidden_state = embedding_output;
+        for layer in &self.layers {
+            let (layer_output, attention_weights) =
+                layer.forward_t(&hidden_state, &extended_attention_mask, (&cos, &sin), train);
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+        }
+
+        Ok(NomicBertModelOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # Nomic BERT for sentence embeddings
+/// Alias for the base model, used by the sentence embeddings pipeline.
+pub type NomicBertForSentenceEmbeddings = NomicBertModel;
+
+/// Container for the Nomic BERT model output.
+pub struct NomicBertModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
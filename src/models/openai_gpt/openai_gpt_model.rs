@@ -15,6 +15,9 @@
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
 use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::weight_tying::{
+    load_weights_with_tying, weight_tying_policy, TiedWeights, WeightTyingStatus,
+};
 use crate::gpt2::Gpt2Config;
 use crate::openai_gpt::transformer::Block;
 use crate::pipelines::common::{ModelType, TokenizerOption};
@@ -282,6 +285,9 @@ pub struct OpenAIGPTLMHeadModel {
 }
 
 impl OpenAIGPTLMHeadModel {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new("tokens_embed.weight", "lm_head.weight");
+
     /// Build a new `OpenAIGPTLMHeadModel`
     ///
     /// # Arguments
@@ -407,6 +413,7 @@ impl OpenAIGPTLMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::None,
+            last_hidden_state: Some(base_model_output.hidden_state),
         })
     }
 }
@@ -435,6 +442,7 @@ pub struct OpenAIGenerator {
     vocab_size: i64,
     decoder_start_id: Option<i64>,
     max_position_embeddings: i64,
+    weight_tying_status: Vec<WeightTyingStatus>,
 }
 
 impl OpenAIGenerator {
@@ -490,7 +498,7 @@ impl OpenAIGenerator {
         generate_config: GenerateConfig,
         tokenizer: TokenizerOption,
     ) -> Result<OpenAIGenerator, RustBertError> {
-        generate_config.validate();
+        generate_config.validate()?;
 
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
@@ -498,7 +506,12 @@ impl OpenAIGenerator {
         let mut var_store = nn::VarStore::new(device);
         let config = Gpt2Config::from_file(config_path);
         let model = OpenAIGPTLMHeadModel::new(var_store.root(), &config);
-        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+        let weight_tying_status = load_weights_with_tying(
+            &generate_config.model_resource,
+            &mut var_store,
+            &[OpenAIGPTLMHeadModel::TIED_WEIGHTS],
+            weight_tying_policy(),
+        )?;
 
         let bos_token_id = tokenizer.get_bos_id();
         let eos_token_ids = tokenizer.get_eos_id().map(|id| vec![id]);
@@ -520,8 +533,15 @@ impl OpenAIGenerator {
             vocab_size,
             decoder_start_id,
             max_position_embeddings,
+            weight_tying_status,
         })
     }
+
+    /// Returns the tying of the LM head with the input embeddings verified when loading the weights. An LM head
+    /// differing from the input embeddings is reported as `WeightTyingStatus::Untied`.
+    pub fn get_weight_tying_status(&self) -> &[WeightTyingStatus] {
+        &self.weight_tying_status
+    }
 }
 
 impl PrivateLanguageGenerator for OpenAIGenerator {
@@ -0,0 +1,202 @@
+// Copyright 2022 The Fairseq Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::opt::opt_model::OptConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for OPT attention layers
+/// Stores the cached value of key and value
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self.prev_value.narrow(-2, 0, length),
+        }
+    }
+}
+
+/// Builds the boolean mask of the future key positions each query may not attend to, of shape
+/// (*query_length*, *key_length*). Queries are assumed to be the last `query_length` positions of the keys.
+pub(crate) fn build_causal_mask(query_length: i64, key_length: i64, device: Device) -> Tensor {
+    let query_positions =
+        Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+            .unsqueeze(-1);
+    let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+    (query_positions - key_positions).lt(0)
+}
+
+/// # OPT self-attention
+/// Causal multi-head attention with separate query, key and value projections. The queries are scaled by the
+/// inverse square root of the head dimension before computing the attention scores.
+pub struct OptAttention {
+    q_proj: QuantizableLinear,
+    k_proj: QuantizableLinear,
+    v_proj: QuantizableLinear,
+    out_proj: QuantizableLinear,
+    attention_dropout: Dropout,
+    num_heads: i64,
+    head_dim: i64,
+    scaling: f64,
+    use_cache: bool,
+    output_attentions: bool,
+}
+
+impl OptAttention {
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.enable_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let q_proj = nn::linear(
+            p / "q_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let k_proj = nn::linear(
+            p / "k_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let v_proj = nn::linear(
+            p / "v_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let out_proj = nn::linear(
+            p / "out_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let attention_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+        let head_dim = config.hidden_size / config.num_attention_heads;
+
+        OptAttention {
+            q_proj: q_proj.into(),
+            k_proj: k_proj.into(),
+            v_proj: v_proj.into(),
+            out_proj: out_proj.into(),
+            attention_dropout,
+            num_heads: config.num_attention_heads,
+            head_dim,
+            scaling: (head_dim as f64).powf(-0.5),
+            use_cache: config.use_cache.unwrap_or(true),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.q_proj.quantize_int4(config)?;
+        self.k_proj.quantize_int4(config)?;
+        self.v_proj.quantize_int4(config)?;
+        self.out_proj.quantize_int4(config)
+    }
+
+    fn split_heads(&self, x: &Tensor, batch_size: i64, sequence_length: i64) -> Tensor {
+        x.view([batch_size, sequence_length, self.num_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (batch_size, sequence_length, _) = hidden_states.size3().unwrap();
+
+        let query = self.split_heads(
+            &(hidden_states.apply(&self.q_proj) * self.scaling),
+            batch_size,
+            sequence_length,
+        );
+        let mut key = self.split_heads(
+            &hidden_states.apply(&self.k_proj),
+            batch_size,
+            sequence_length,
+        );
+        let mut value = self.split_heads(
+            &hidden_states.apply(&self.v_proj),
+            batch_size,
+            sequence_length,
+        );
+
+        if let Some(layer_past) = layer_past {
+            key = Tensor::cat(&[&layer_past.prev_key, &key], -2);
+            value = Tensor::cat(&[&layer_past.prev_value, &value], -2);
+        }
+
+        let present = self.use_cache.then(|| LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        });
+
+        let mut attention_scores = attention_scores(&query, &key.transpose(-1, -2));
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores + attention_mask;
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_output(&attention_weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.out_proj);
+
+        let attention_weights = self.output_attentions.then_some(attention_weights);
+
+        (attention_output, present, attention_weights)
+    }
+}
@@ -0,0 +1,74 @@
+//! # OPT (Zhang et al.)
+//!
+//! Implementation of the OPT decoder architecture ([OPT: Open Pre-trained Transformer Language Models](https://arxiv.org/abs/2205.01068) Zhang, Roller, Goyal, Artetxe, Chen, Chen, Dewan, Diab, Li, Lin, Mihaylov, Ott, Shleifer, Shuster, Simig, Koura, Sridhar, Wang, Zettlemoyer, 2022).
+//! OPT is a family of decoder-only models released by Meta AI, similar to GPT-3. The model uses learned position embeddings
+//! (offset by 2 positions) and normalizes the inputs of its layers, except OPT-350m which normalizes their outputs and
+//! projects its smaller word embeddings to the hidden size.
+//! The base model is implemented in the `opt_model::OptModel` struct, the language modeling head in `opt_model::OptForCausalLM`
+//! and the text generation utilities in `opt_model::OptGenerator`, also available through the `TextGenerationModel` pipeline
+//! with `ModelType::OPT`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers) (`OPTForCausalLM`). A conversion using the Python utility scripts is required to convert the
+//!   weights to the `.ot` format, for example with `python utils/convert_model.py path/to/opt/pytorch_model.bin`.
+//! - `Gpt2Tokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file. OPT uses the `</s>` token (id 2) as
+//!   BOS and EOS token: the token ids of the model configuration are used for generation.
+//!
+//! Pretrained configuration and vocabulary files for OPT-125m, OPT-350m, OPT-1.3b and OPT-2.7b are available in `OptConfigResources`,
+//! `OptVocabResources` and `OptMergesResources`, and the weights of OPT-125m in `OptModelResources`.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::opt::{OptConfig, OptForCausalLM};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::Gpt2Tokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/vocab.json"),
+//! };
+//! let merges_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/merges.txt"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let merges_path = merges_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_file(
+//!     vocab_path.to_str().unwrap(),
+//!     merges_path.to_str().unwrap(),
+//!     false,
+//! )?;
+//! let config = OptConfig::from_file(config_path);
+//! let opt_model = OptForCausalLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod opt_model;
+mod transformer;
+
+pub use attention::{LayerState, OptAttention};
+pub use opt_model::{
+    OptConfig, OptConfigResources, OptForCausalLM, OptGenerator, OptMergesResources, OptModel,
+    OptModelOutput, OptModelResources, OptVocabResources,
+};
+pub use transformer::OptDecoderLayer;
@@ -0,0 +1,869 @@
+// Copyright 2022 The Fairseq Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::kind::get_min;
+use crate::common::mps;
+use crate::common::quantization::Int4QuantizationConfig;
+use crate::opt::attention::{build_causal_mask, LayerState};
+use crate::opt::transformer::OptDecoderLayer;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, LanguageGenerator};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Device, Kind, Tensor};
+
+/// Offset of the learned position embeddings: the first two rows of the position embedding matrix are not used
+/// by the token positions (legacy of the fairseq padding index).
+const POSITION_OFFSET: i64 = 2;
+
+/// # OPT Pretrained model weight files
+pub struct OptModelResources;
+
+/// # OPT Pretrained model config files
+pub struct OptConfigResources;
+
+/// # OPT Pretrained model vocab files
+pub struct OptVocabResources;
+
+/// # OPT Pretrained model merges files
+pub struct OptMergesResources;
+
+impl OptModelResources {
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-125m>.
+    pub const OPT_125M: (&'static str, &'static str) = (
+        "opt-125m/model",
+        "https://huggingface.co/facebook/opt-125m/resolve/main/model.safetensors",
+    );
+}
+
+impl OptConfigResources {
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-125m>.
+    pub const OPT_125M: (&'static str, &'static str) = (
+        "opt-125m/config",
+        "https://huggingface.co/facebook/opt-125m/resolve/main/config.json",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-350m>.
+    pub const OPT_350M: (&'static str, &'static str) = (
+        "opt-350m/config",
+        "https://huggingface.co/facebook/opt-350m/resolve/main/config.json",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-1.3b>.
+    pub const OPT_1_3B: (&'static str, &'static str) = (
+        "opt-1.3b/config",
+        "https://huggingface.co/facebook/opt-1.3b/resolve/main/config.json",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-2.7b>.
+    pub const OPT_2_7B: (&'static str, &'static str) = (
+        "opt-2.7b/config",
+        "https://huggingface.co/facebook/opt-2.7b/resolve/main/config.json",
+    );
+}
+
+impl OptVocabResources {
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-125m>.
+    pub const OPT_125M: (&'static str, &'static str) = (
+        "opt-125m/vocab",
+        "https://huggingface.co/facebook/opt-125m/resolve/main/vocab.json",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-350m>.
+    pub const OPT_350M: (&'static str, &'static str) = (
+        "opt-350m/vocab",
+        "https://huggingface.co/facebook/opt-350m/resolve/main/vocab.json",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-1.3b>.
+    pub const OPT_1_3B: (&'static str, &'static str) = (
+        "opt-1.3b/vocab",
+        "https://huggingface.co/facebook/opt-1.3b/resolve/main/vocab.json",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-2.7b>.
+    pub const OPT_2_7B: (&'static str, &'static str) = (
+        "opt-2.7b/vocab",
+        "https://huggingface.co/facebook/opt-2.7b/resolve/main/vocab.json",
+    );
+}
+
+impl OptMergesResources {
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-125m>.
+    pub const OPT_125M: (&'static str, &'static str) = (
+        "opt-125m/merges",
+        "https://huggingface.co/facebook/opt-125m/resolve/main/merges.txt",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-350m>.
+    pub const OPT_350M: (&'static str, &'static str) = (
+        "opt-350m/merges",
+        "https://huggingface.co/facebook/opt-350m/resolve/main/merges.txt",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-1.3b>.
+    pub const OPT_1_3B: (&'static str, &'static str) = (
+        "opt-1.3b/merges",
+        "https://huggingface.co/facebook/opt-1.3b/resolve/main/merges.txt",
+    );
+    /// Shared under the OPT-175B license agreement by Meta AI at <https://huggingface.co/facebook/opt-2.7b>.
+    pub const OPT_2_7B: (&'static str, &'static str) = (
+        "opt-2.7b/merges",
+        "https://huggingface.co/facebook/opt-2.7b/resolve/main/merges.txt",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # OPT model configuration
+/// Defines the OPT model architecture (e.g. number of layers, hidden layer size, position of the layer normalizations...)
+pub struct OptConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub ffn_dim: i64,
+    pub max_position_embeddings: i64,
+    pub word_embed_proj_dim: Option<i64>,
+    pub do_layer_norm_before: Option<bool>,
+    #[serde(rename = "_remove_final_layer_norm")]
+    pub remove_final_layer_norm: Option<bool>,
+    pub layer_norm_elementwise_affine: Option<bool>,
+    pub enable_bias: Option<bool>,
+    pub activation_function: Option<Activation>,
+    pub dropout: Option<f64>,
+    pub attention_dropout: Option<f64>,
+    pub init_std: Option<f64>,
+    pub use_cache: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    pub forced_bos_token_id: Option<i64>,
+    pub forced_eos_token_id: Option<i64>,
+}
+
+impl Config for OptConfig {}
+
+impl Default for OptConfig {
+    fn default() -> Self {
+        OptConfig {
+            vocab_size: 50272,
+            hidden_size: 768,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            ffn_dim: 3072,
+            max_position_embeddings: 2048,
+            word_embed_proj_dim: None,
+            do_layer_norm_before: Some(true),
+            remove_final_layer_norm: Some(false),
+            layer_norm_elementwise_affine: Some(true),
+            enable_bias: Some(true),
+            activation_function: Some(Activation::relu),
+            dropout: Some(0.1),
+            attention_dropout: Some(0.0),
+            init_std: Some(0.02),
+            use_cache: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            bos_token_id: Some(2),
+            eos_token_id: Some(2),
+            pad_token_id: Some(1),
+            decoder_start_token_id: None,
+            forced_bos_token_id: None,
+            forced_eos_token_id: None,
+        }
+    }
+}
+
+impl OptConfig {
+    /// Dimension of the word embeddings (smaller than the hidden size for OPT-350m)
+    pub fn word_embed_proj_dim(&self) -> i64 {
+        self.word_embed_proj_dim.unwrap_or(self.hidden_size)
+    }
+}
+
+/// # OPT Base model
+/// Base architecture for OPT models. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `embed_tokens`: `token` embeddings, projected to the hidden size by `project_in` if the embedding dimension differs
+/// - `embed_positions`: learned `position` embeddings, offset by 2
+/// - `layers`: Decoder made of a vector of layers. Each layer is made of a multi-head attention layer, layer
+///   normalizations and a feed-forward network.
+/// - `final_layer_norm`: Final layer normalization, for models normalizing the inputs of the layers
+/// - `project_out`: Projection of the hidden states back to the word embedding dimension, if it differs from the hidden size
+pub struct OptModel {
+    embed_tokens: nn::Embedding,
+    embed_positions: nn::Embedding,
+    project_in: Option<nn::Linear>,
+    project_out: Option<nn::Linear>,
+    layers: Vec<OptDecoderLayer>,
+    final_layer_norm: Option<nn::LayerNorm>,
+    use_cache: bool,
+    output_hidden_states: bool,
+    output_attentions: bool,
+}
+
+impl OptModel {
+    /// Build a new `OptModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the OPT model
+    /// * `config` - `OptConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::opt::{OptConfig, OptModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = OptConfig::from_file(config_path);
+    /// let opt: OptModel = OptModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "decoder";
+
+        let word_embed_proj_dim = config.word_embed_proj_dim();
+        let embed_tokens = embedding(
+            &p / "embed_tokens",
+            config.vocab_size,
+            word_embed_proj_dim,
+            Default::default(),
+        );
+        let embed_positions = embedding(
+            &p / "embed_positions",
+            config.max_position_embeddings + POSITION_OFFSET,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let (project_in, project_out) = if word_embed_proj_dim != config.hidden_size {
+            let linear_no_bias_config = nn::LinearConfig {
+                bias: false,
+                ..Default::default()
+            };
+            (
+                Some(nn::linear(
+                    &p / "project_in",
+                    word_embed_proj_dim,
+                    config.hidden_size,
+                    linear_no_bias_config,
+                )),
+                Some(nn::linear(
+                    &p / "project_out",
+                    config.hidden_size,
+                    word_embed_proj_dim,
+                    linear_no_bias_config,
+                )),
+            )
+        } else {
+            (None, None)
+        };
+
+        let mut layers: Vec<OptDecoderLayer> = vec![];
+        let layers_path = &p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(OptDecoderLayer::new(&layers_path / layer_index, config));
+        }
+
+        let final_layer_norm = (config.do_layer_norm_before.unwrap_or(true)
+            && !config.remove_final_layer_norm.unwrap_or(false))
+        .then(|| {
+            nn::layer_norm(
+                &p / "final_layer_norm",
+                vec![config.hidden_size],
+                nn::LayerNormConfig {
+                    elementwise_affine: config.layer_norm_elementwise_affine.unwrap_or(true),
+                    ..Default::default()
+                },
+            )
+        });
+
+        OptModel {
+            embed_tokens,
+            embed_positions,
+            project_in,
+            project_out,
+            layers,
+            final_layer_norm,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the decoder layers to int4 in place.
+    /// The embeddings, projections and normalization layers are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - Optional vector of length *num_hidden_layers* containing the past keys and values of each layer of shape (*batch size*, *number of heads*, *past_sequence_length*, *hidden size per head*). When provided, these are concatenated with the current input keys and values.
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, positions are counted from the first non-masked token of the attention mask (or from the length of the past input if no mask is provided).
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *word_embed_proj_dim*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `OptModelOutput` containing:
+    ///   - `output` - `Tensor` of shape (*batch size*, *sequence_length*, *word_embed_proj_dim*) representing the activations of the last hidden state
+    ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *num_hidden_layers* containing the past keys and values of each layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *number of heads*, *sequence_length*, *past_sequence_length + sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::opt::{OptConfig, OptModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = OptConfig::from_file(config_path);
+    /// # let opt_model: OptModel = OptModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     opt_model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             None,
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<OptModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_tokens)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+
+        let (layer_past, past_length) = match layer_past {
+            Some(value) => {
+                if value.len() != self.layers.len() {
+                    return Err(RustBertError::ValueError(format!(
+                        "Past activations vector length ({}) must be equal to the number of layers ({})",
+                        value.len(),
+                        self.layers.len()
+                    )));
+                } else {
+                    let past_length = value
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|layer_state| layer_state.prev_key.size()[2])
+                        .unwrap_or(0);
+                    (value, past_length)
+                }
+            }
+            None => {
+                let mut out = Vec::with_capacity(self.layers.len());
+                out.resize_with(self.layers.len(), || None);
+                (out, 0)
+            }
+        };
+        let key_length = past_length + sequence_length;
+
+        let position_ids = match (position_ids, attention_mask) {
+            (Some(value), _) => value.shallow_clone(),
+            // Positions are counted from the first non-padding token of each (left-padded) sequence
+            (None, Some(attention_mask)) => {
+                let attention_mask = attention_mask.to_kind(Kind::Int64);
+                (mps::cumsum(&attention_mask, -1, Kind::Int64) * &attention_mask - 1)
+                    .clamp_min(0)
+                    .narrow(1, past_length, sequence_length)
+            }
+            (None, None) => Tensor::arange_start(past_length, key_length, (Kind::Int64, device))
+                .unsqueeze(0)
+                .expand([batch_size, sequence_length], true),
+        };
+
+        let mut masked_positions = build_causal_mask(sequence_length, key_length, device).view([
+            1,
+            1,
+            sequence_length,
+            key_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            masked_positions =
+                masked_positions.logical_or(&attention_mask.view([batch_size, 1, 1, -1]).eq(0));
+        }
+        let kind = input_embeddings.kind();
+        let attention_mask = Tensor::zeros(masked_positions.size(), (kind, device))
+            .masked_fill(&masked_positions, get_min(kind)?);
+
+        let input_embeddings = match &self.project_in {
+            Some(project_in) => input_embeddings.apply(project_in),
+            None => input_embeddings.shallow_clone(),
+        };
+        let position_embeddings = (position_ids + POSITION_OFFSET).apply(&self.embed_positions);
+        let mut hidden_state = input_embeddings + position_embeddings;
+
+        let mut all_presents: Option<Vec<Option<LayerState>>> = self.use_cache.then(Vec::new);
+        let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
+        let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
+
+        for (layer, past) in self.layers.iter().zip(layer_past) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let temp = layer.forward_t(&hidden_state, Some(&attention_mask), past.as_ref(), train);
+            hidden_state = temp.0;
+            if let Some(presents) = all_presents.borrow_mut() {
+                presents.push(temp.1);
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(temp.2.unwrap());
+            };
+        }
+
+        if let Some(final_layer_norm) = &self.final_layer_norm {
+            hidden_state = hidden_state.apply(final_layer_norm);
+        }
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(hidden_state.copy());
+        };
+        let output = match &self.project_out {
+            Some(project_out) => hidden_state.apply(project_out),
+            None => hidden_state,
+        };
+
+        Ok(OptModelOutput {
+            output,
+            cache: all_presents,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # OPT Language Modeling head
+/// OPT model with a decoding head (linear layer without bias). The weights of the linear layer are tied to the word embeddings.
+/// It is made of the following blocks:
+/// - `model`: Base OptModel
+pub struct OptForCausalLM {
+    model: OptModel,
+}
+
+impl OptForCausalLM {
+    /// Build a new `OptForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the OPT model
+    /// * `config` - `OptConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::opt::{OptConfig, OptForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = OptConfig::from_file(config_path);
+    /// let opt: OptForCausalLM = OptForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let model = OptModel::new(p.borrow() / "model", config);
+
+        OptForCausalLM { model }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place (see `OptModel::quantize_int4`).
+    /// The tied language model head is kept in its original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - `Cache` containing the past keys and values of each layer (`Cache::OptCache` or `Cache::None`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, positions are counted from the first non-masked token of the attention mask.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *word_embed_proj_dim*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `cache` - `Cache::OptCache` containing the past keys and values of each layer
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::OptCache(layer_past) => self.model.forward_t(
+                input_ids,
+                layer_past,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            Cache::None => self.model.forward_t(
+                input_ids,
+                None,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with OPT Model".into(),
+                ));
+            }
+        }?;
+
+        let lm_logits = base_model_output
+            .output
+            .linear::<Tensor>(&self.model.embed_tokens.ws, None);
+
+        Ok(LMModelOutput {
+            lm_logits,
+            cache: Cache::OptCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
+        })
+    }
+}
+
+/// Container for the OPT model output.
+pub struct OptModelOutput {
+    /// Hidden state of the last layer of the decoder, projected to the word embedding dimension
+    pub output: Tensor,
+    /// Cached attention layers keys and values if the model is used for generation
+    pub cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the OPT architecture
+pub struct OptGenerator {
+    model: OptForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl OptGenerator {
+    /// Build a new `OptGenerator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::opt::{OptConfigResources, OptGenerator, OptMergesResources, OptVocabResources};
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType};
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::{LocalResource, RemoteResource};
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_type: ModelType::OPT,
+    ///     model_resource: ModelResource::Torch(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     })),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(OptConfigResources::OPT_125M)),
+    ///     vocab_resource: Box::new(RemoteResource::from_pretrained(OptVocabResources::OPT_125M)),
+    ///     merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+    ///         OptMergesResources::OPT_125M,
+    ///     ))),
+    ///     max_length: Some(64),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..Default::default()
+    /// };
+    /// let opt_generator = OptGenerator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<OptGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let merges_path = generate_config
+            .merges_resource
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "OPT expects a merges resources to be provided".to_string(),
+                )
+            })?
+            .get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::OPT,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    /// Build a new `OptGenerator` with a tokenizer, for example loaded from a `tokenizer.json` file
+    /// (see `TokenizerOption::from_hf_tokenizer_file`)
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer to use for text generation
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<OptGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate()?;
+        let mut var_store = nn::VarStore::new(device);
+
+        let config = OptConfig::from_file(config_path);
+        let model = OptForCausalLM::new(var_store.root(), &config);
+        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+
+        // The special tokens of the GPT-2 vocabulary files (`<|endoftext|>`) are not used by OPT: the token ids of
+        // the model configuration take priority
+        let bos_token_id = config.bos_token_id.or_else(|| tokenizer.get_bos_id());
+        let eos_token_ids = config
+            .eos_token_id
+            .or_else(|| tokenizer.get_eos_id())
+            .map(|id| vec![id]);
+        let pad_token_id = config.pad_token_id.or_else(|| tokenizer.get_pad_id());
+        let max_position_embeddings = config.max_position_embeddings;
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = config.decoder_start_token_id;
+
+        Ok(OptGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+
+    /// Quantize the weights of the model to int4 in place (see `OptForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+}
+
+impl PrivateLanguageGenerator for OptGenerator {
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn _get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        &mut self.tokenizer
+    }
+    fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+    fn get_var_store_mut(&mut self) -> Result<&mut nn::VarStore, RustBertError> {
+        Ok(&mut self.var_store)
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> Option<i64> {
+        Some(self.max_position_embeddings)
+    }
+
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        self.model.forward_t(
+            input_ids,
+            layer_past,
+            attention_mask,
+            position_ids,
+            input_embeds,
+            train,
+        )
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        // Position ids are computed by the model from the attention mask
+        match past {
+            Cache::OptCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: None,
+                        prepared_past: Cache::OptCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: None,
+                        prepared_past: Cache::OptCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: None,
+                prepared_past: Cache::OptCache(None),
+            },
+            _ => panic!("Cache type incompatible with OPT"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::OptCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for OPT model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator for OptGenerator {}
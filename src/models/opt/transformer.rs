@@ -0,0 +1,129 @@
+// Copyright 2022 The Fairseq Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::{Activation, TensorFunction};
+use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::opt::attention::{LayerState, OptAttention};
+use crate::opt::opt_model::OptConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+/// # OPT decoder layer
+/// Self-attention block followed by a two-layer feed-forward network (`fc2(activation(fc1(x)))`). The layer
+/// normalizations are applied to the inputs of the blocks (`do_layer_norm_before`, all checkpoints except OPT-350m)
+/// or to their outputs after the residual connections.
+pub struct OptDecoderLayer {
+    self_attn: OptAttention,
+    self_attn_layer_norm: nn::LayerNorm,
+    fc1: QuantizableLinear,
+    fc2: QuantizableLinear,
+    final_layer_norm: nn::LayerNorm,
+    activation: TensorFunction,
+    dropout: Dropout,
+    do_layer_norm_before: bool,
+}
+
+impl OptDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            elementwise_affine: config.layer_norm_elementwise_affine.unwrap_or(true),
+            ..Default::default()
+        };
+        let linear_config = nn::LinearConfig {
+            bias: config.enable_bias.unwrap_or(true),
+            ..Default::default()
+        };
+
+        let self_attn = OptAttention::new(p / "self_attn", config);
+        let self_attn_layer_norm = nn::layer_norm(
+            p / "self_attn_layer_norm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let fc1 = nn::linear(p / "fc1", config.hidden_size, config.ffn_dim, linear_config);
+        let fc2 = nn::linear(p / "fc2", config.ffn_dim, config.hidden_size, linear_config);
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        let activation = config
+            .activation_function
+            .unwrap_or(Activation::relu)
+            .get_function();
+        let dropout = Dropout::new(config.dropout.unwrap_or(0.1));
+
+        OptDecoderLayer {
+            self_attn,
+            self_attn_layer_norm,
+            fc1: fc1.into(),
+            fc2: fc2.into(),
+            final_layer_norm,
+            activation,
+            dropout,
+            do_layer_norm_before: config.do_layer_norm_before.unwrap_or(true),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attn.quantize_int4(config)?;
+        self.fc1.quantize_int4(config)?;
+        self.fc2.quantize_int4(config)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let attention_input = if self.do_layer_norm_before {
+            hidden_states.apply(&self.self_attn_layer_norm)
+        } else {
+            hidden_states.shallow_clone()
+        };
+        let (attention_output, present, attention_weights) =
+            self.self_attn
+                .forward_t(&attention_input, attention_mask, layer_past, train);
+        let mut hidden_states = hidden_states + attention_output.apply_t(&self.dropout, train);
+        if !self.do_layer_norm_before {
+            hidden_states = hidden_states.apply(&self.self_attn_layer_norm);
+        }
+
+        let mlp_input = if self.do_layer_norm_before {
+            hidden_states.apply(&self.final_layer_norm)
+        } else {
+            hidden_states.shallow_clone()
+        };
+        let mlp_output = (self.activation.get_fn())(&mlp_input.apply(&self.fc1))
+            .apply(&self.fc2)
+            .apply_t(&self.dropout, train);
+        let mut hidden_states = hidden_states + mlp_output;
+        if !self.do_layer_norm_before {
+            hidden_states = hidden_states.apply(&self.final_layer_norm);
+        }
+
+        (hidden_states, present, attention_weights)
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2023 Microsoft and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::position_embeddings::{apply_rotary_pos_emb, RotaryEmbedding};
+use crate::common::precision::{attention_output, attention_scores};
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::phi::phi_model::PhiConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for Phi attention layers
+/// Stores the cached value of key and value, before expansion to the number of query heads
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self.prev_value.narrow(-2, 0, length),
+        }
+    }
+}
+
+/// Builds the boolean mask of the future key positions each query may not attend to, of shape
+/// (*query_length*, *key_length*). Queries are assumed to be the last `query_length` positions of the keys.
+pub(crate) fn build_causal_mask(query_length: i64, key_length: i64, device: Device) -> Tensor {
+    let query_positions =
+        Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+            .unsqueeze(-1);
+    let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+    (query_positions - key_positions).lt(0)
+}
+
+/// # Rotary position embeddings
+/// Phi uses the shared rotary position embeddings (non-interleaved layout) over the first
+/// `partial_rotary_factor * head_dim` dimensions of each head, the remaining dimensions passing through unchanged.
+pub type PhiRotaryEmbedding = RotaryEmbedding;
+
+/// Repeats the key and value heads to match the number of query heads (grouped-query attention)
+fn repeat_kv(hidden_states: &Tensor, num_repetitions: i64) -> Tensor {
+    if num_repetitions == 1 {
+        return hidden_states.shallow_clone();
+    }
+    let (batch_size, num_key_value_heads, sequence_length, head_dim) =
+        hidden_states.size4().unwrap();
+    hidden_states
+        .unsqueeze(2)
+        .expand(
+            [
+                batch_size,
+                num_key_value_heads,
+                num_repetitions,
+                sequence_length,
+                head_dim,
+            ],
+            false,
+        )
+        .reshape([
+            batch_size,
+            num_key_value_heads * num_repetitions,
+            sequence_length,
+            head_dim,
+        ])
+}
+
+/// # Phi self-attention
+/// Causal attention with partial rotary position embeddings and a `dense` output projection. The queries and keys
+/// are optionally normalized per head (`qk_layernorm`) before the rotation, and the attention scores are computed
+/// in full precision.
+pub struct PhiAttention {
+    q_proj: QuantizableLinear,
+    k_proj: QuantizableLinear,
+    v_proj: QuantizableLinear,
+    dense: QuantizableLinear,
+    q_layernorm: Option<nn::LayerNorm>,
+    k_layernorm: Option<nn::LayerNorm>,
+    attention_dropout: Dropout,
+    num_heads: i64,
+    num_key_value_heads: i64,
+    head_dim: i64,
+    use_cache: bool,
+    output_attentions: bool,
+}
+
+impl PhiAttention {
+    pub fn new<'p, P>(p: P, config: &PhiConfig) -> PhiAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Attention hidden states not a multiple of the number of heads"
+        );
+        let num_key_value_heads = config
+            .num_key_value_heads
+            .unwrap_or(config.num_attention_heads);
+        assert_eq!(
+            config.num_attention_heads % num_key_value_heads,
+            0,
+            "Number of attention heads not a multiple of the number of key/value heads"
+        );
+        let head_dim = config.hidden_size / config.num_attention_heads;
+
+        let q_proj = nn::linear(
+            p / "q_proj",
+            config.hidden_size,
+            config.num_attention_heads * head_dim,
+            Default::default(),
+        );
+        let k_proj = nn::linear(
+            p / "k_proj",
+            config.hidden_size,
+            num_key_value_heads * head_dim,
+            Default::default(),
+        );
+        let v_proj = nn::linear(
+            p / "v_proj",
+            config.hidden_size,
+            num_key_value_heads * head_dim,
+            Default::default(),
+        );
+        let dense = nn::linear(
+            p / "dense",
+            config.num_attention_heads * head_dim,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let (q_layernorm, k_layernorm) = if config.qk_layernorm.unwrap_or(false) {
+            let layer_norm_config = nn::LayerNormConfig {
+                eps: config.layer_norm_eps.unwrap_or(1e-5),
+                ..Default::default()
+            };
+            (
+                Some(nn::layer_norm(
+                    p / "q_layernorm",
+                    vec![head_dim],
+                    layer_norm_config,
+                )),
+                Some(nn::layer_norm(
+                    p / "k_layernorm",
+                    vec![head_dim],
+                    layer_norm_config,
+                )),
+            )
+        } else {
+            (None, None)
+        };
+
+        let attention_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+
+        PhiAttention {
+            q_proj: q_proj.into(),
+            k_proj: k_proj.into(),
+            v_proj: v_proj.into(),
+            dense: dense.into(),
+            q_layernorm,
+            k_layernorm,
+            attention_dropout,
+            num_heads: config.num_attention_heads,
+            num_key_value_heads,
+            head_dim,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.q_proj.quantize_int4(config)?;
+        self.k_proj.quantize_int4(config)?;
+        self.v_proj.quantize_int4(config)?;
+        self.dense.quantize_int4(config)
+    }
+
+    fn split_heads(&self, tensor: &Tensor, num_heads: i64) -> Tensor {
+        let (batch_size, sequence_length, _) = tensor.size3().unwrap();
+        tensor
+            .view([batch_size, sequence_length, num_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        rotary: (&Tensor, &Tensor),
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (batch_size, sequence_length, _) = hidden_states.size3().unwrap();
+        let (cos, sin) = rotary;
+
+        let mut query = self.split_heads(&hidden_states.apply(&self.q_proj), self.num_heads);
+        let mut key =
+            self.split_heads(&hidden_states.apply(&self.k_proj), self.num_key_value_heads);
+        let mut value =
+            self.split_heads(&hidden_states.apply(&self.v_proj), self.num_key_value_heads);
+
+        if let Some(q_layernorm) = &self.q_layernorm {
+            query = query.apply(q_layernorm);
+        }
+        if let Some(k_layernorm) = &self.k_layernorm {
+            key = key.apply(k_layernorm);
+        }
+
+        let query = apply_rotary_pos_emb(&query, cos, sin);
+        key = apply_rotary_pos_emb(&key, cos, sin);
+
+        if let Some(layer_past) = layer_past {
+            key = Tensor::cat(&[&layer_past.prev_key, &key], -2);
+            value = Tensor::cat(&[&layer_past.prev_value, &value], -2);
+        }
+
+        let present = self.use_cache.then(|| LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        });
+
+        let num_repetitions = self.num_heads / self.num_key_value_heads;
+        let key = repeat_kv(&key, num_repetitions);
+        let value = repeat_kv(&value, num_repetitions);
+
+        // Phi checkpoints overflow in half precision: the attention scores are computed in full precision
+        let mut attention_scores = attention_scores(
+            &query.to_kind(Kind::Float),
+            &key.to_kind(Kind::Float).transpose(-1, -2),
+        ) / (self.head_dim as f64).sqrt();
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores + attention_mask;
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_output(&attention_weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.dense);
+
+        let attention_weights = self.output_attentions.then_some(attention_weights);
+
+        (attention_output, present, attention_weights)
+    }
+}
@@ -0,0 +1,74 @@
+//! # Phi (Li et al.)
+//!
+//! Implementation of the Phi decoder architecture ([Textbooks Are All You Need II: phi-1.5 technical report](https://arxiv.org/abs/2309.05463) Li, Bubeck, Eldan, Del Giorno, Gunasekar, Lee, 2023).
+//! Phi-1.5 and Phi-2 are small language models released by Microsoft. Each decoder layer computes its attention and
+//! feed-forward (two dense layers) blocks in parallel from a single layer normalization, and rotary position embeddings
+//! are only applied to a fraction of the dimensions of each attention head (`partial_rotary_factor`).
+//! The base model is implemented in the `phi_model::PhiModel` struct, the language modeling head in `phi_model::PhiForCausalLM`
+//! and the text generation utilities in `phi_model::PhiGenerator`, also available through the `TextGenerationModel` and
+//! `ConversationModel` pipelines with `ModelType::Phi`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers) (`PhiForCausalLM`). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights to the `.ot` format, for example with `python utils/convert_model.py path/to/phi/model.safetensors`.
+//! - `Gpt2Tokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file. Phi uses the `<|endoftext|>` token as
+//!   BOS and EOS token.
+//!
+//! Pretrained configuration and vocabulary files for Phi-1.5 and Phi-2 are available in `PhiConfigResources`,
+//! `PhiVocabResources` and `PhiMergesResources`, and the weights of Phi-1.5 in `PhiModelResources`.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::phi::{PhiConfig, PhiForCausalLM};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::Gpt2Tokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/vocab.json"),
+//! };
+//! let merges_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/merges.txt"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let merges_path = merges_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_file(
+//!     vocab_path.to_str().unwrap(),
+//!     merges_path.to_str().unwrap(),
+//!     false,
+//! )?;
+//! let config = PhiConfig::from_file(config_path);
+//! let phi_model = PhiForCausalLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod phi_model;
+mod transformer;
+
+pub use attention::{LayerState, PhiAttention, PhiRotaryEmbedding};
+pub use phi_model::{
+    PhiConfig, PhiConfigResources, PhiForCausalLM, PhiGenerator, PhiMergesResources, PhiModel,
+    PhiModelOutput, PhiModelResources, PhiVocabResources,
+};
+pub use transformer::{PhiDecoderLayer, PhiMLP};
@@ -0,0 +1,814 @@
+// Copyright 2023 Microsoft and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::kind::get_min;
+use crate::common::mps;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::phi::attention::{build_causal_mask, LayerState, PhiRotaryEmbedding};
+use crate::phi::transformer::PhiDecoderLayer;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, LanguageGenerator};
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Device, Kind, Tensor};
+
+/// # Phi Pretrained model weight files
+pub struct PhiModelResources;
+
+/// # Phi Pretrained model config files
+pub struct PhiConfigResources;
+
+/// # Phi Pretrained model vocab files
+pub struct PhiVocabResources;
+
+/// # Phi Pretrained model merges files
+pub struct PhiMergesResources;
+
+impl PhiModelResources {
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-1_5>.
+    pub const PHI_1_5: (&'static str, &'static str) = (
+        "phi-1_5/model",
+        "https://huggingface.co/microsoft/phi-1_5/resolve/main/model.safetensors",
+    );
+}
+
+impl PhiConfigResources {
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-1_5>.
+    pub const PHI_1_5: (&'static str, &'static str) = (
+        "phi-1_5/config",
+        "https://huggingface.co/microsoft/phi-1_5/resolve/main/config.json",
+    );
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-2>.
+    pub const PHI_2: (&'static str, &'static str) = (
+        "phi-2/config",
+        "https://huggingface.co/microsoft/phi-2/resolve/main/config.json",
+    );
+}
+
+impl PhiVocabResources {
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-1_5>.
+    pub const PHI_1_5: (&'static str, &'static str) = (
+        "phi-1_5/vocab",
+        "https://huggingface.co/microsoft/phi-1_5/resolve/main/vocab.json",
+    );
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-2>.
+    pub const PHI_2: (&'static str, &'static str) = (
+        "phi-2/vocab",
+        "https://huggingface.co/microsoft/phi-2/resolve/main/vocab.json",
+    );
+}
+
+impl PhiMergesResources {
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-1_5>.
+    pub const PHI_1_5: (&'static str, &'static str) = (
+        "phi-1_5/merges",
+        "https://huggingface.co/microsoft/phi-1_5/resolve/main/merges.txt",
+    );
+    /// Shared under MIT license by the Microsoft team at <https://huggingface.co/microsoft/phi-2>.
+    pub const PHI_2: (&'static str, &'static str) = (
+        "phi-2/merges",
+        "https://huggingface.co/microsoft/phi-2/resolve/main/merges.txt",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Phi model configuration
+/// Defines the Phi model architecture (e.g. number of layers, hidden layer size, fraction of the head dimensions
+/// encoded with rotary position embeddings...).
+pub struct PhiConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub num_key_value_heads: Option<i64>,
+    pub hidden_act: Activation,
+    pub max_position_embeddings: i64,
+    pub initializer_range: Option<f64>,
+    pub layer_norm_eps: Option<f64>,
+    pub rope_theta: Option<f64>,
+    pub partial_rotary_factor: Option<f64>,
+    pub qk_layernorm: Option<bool>,
+    pub resid_pdrop: Option<f64>,
+    pub embd_pdrop: Option<f64>,
+    pub attention_dropout: Option<f64>,
+    pub use_cache: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    pub forced_bos_token_id: Option<i64>,
+    pub forced_eos_token_id: Option<i64>,
+}
+
+impl Config for PhiConfig {}
+
+impl Default for PhiConfig {
+    fn default() -> Self {
+        PhiConfig {
+            vocab_size: 51200,
+            hidden_size: 2560,
+            intermediate_size: 10240,
+            num_hidden_layers: 32,
+            num_attention_heads: 32,
+            num_key_value_heads: None,
+            hidden_act: Activation::gelu_new,
+            max_position_embeddings: 2048,
+            initializer_range: Some(0.02),
+            layer_norm_eps: Some(1e-5),
+            rope_theta: Some(10000.0),
+            partial_rotary_factor: Some(0.4),
+            qk_layernorm: Some(false),
+            resid_pdrop: Some(0.1),
+            embd_pdrop: Some(0.0),
+            attention_dropout: Some(0.0),
+            use_cache: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            bos_token_id: Some(50256),
+            eos_token_id: Some(50256),
+            pad_token_id: None,
+            decoder_start_token_id: None,
+            forced_bos_token_id: None,
+            forced_eos_token_id: None,
+        }
+    }
+}
+
+impl PhiConfig {
+    /// Number of dimensions of each attention head encoded with rotary position embeddings
+    pub fn rotary_dim(&self) -> i64 {
+        let head_dim = self.hidden_size / self.num_attention_heads;
+        (head_dim as f64 * self.partial_rotary_factor.unwrap_or(0.5)) as i64
+    }
+}
+
+/// # Phi Base model
+/// Base architecture for Phi model. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `embed_tokens`: `token` embeddings
+/// - `layers`: Decoder made of a vector of layers. Each layer is made of an attention layer with partial rotary position embeddings
+///   and a MLP made of two dense layers, computed in parallel from the output of a single layer normalization.
+/// - `final_layernorm`: Final layer normalization
+pub struct PhiModel {
+    embed_tokens: nn::Embedding,
+    layers: Vec<PhiDecoderLayer>,
+    final_layernorm: nn::LayerNorm,
+    rotary_embedding: PhiRotaryEmbedding,
+    dropout: Dropout,
+    use_cache: bool,
+    output_hidden_states: bool,
+    output_attentions: bool,
+}
+
+impl PhiModel {
+    /// Build a new `PhiModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Phi model
+    /// * `config` - `PhiConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::phi::{PhiConfig, PhiModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = PhiConfig::from_file(config_path);
+    /// let phi: PhiModel = PhiModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &PhiConfig) -> PhiModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "model";
+
+        let embed_tokens = embedding(
+            &p / "embed_tokens",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let mut layers: Vec<PhiDecoderLayer> = vec![];
+        let layers_path = &p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(PhiDecoderLayer::new(&layers_path / layer_index, config));
+        }
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let final_layernorm = nn::layer_norm(
+            &p / "final_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        let rotary_embedding = PhiRotaryEmbedding::new(
+            config.rotary_dim(),
+            config.rope_theta.unwrap_or(10000.0),
+            p.device(),
+        );
+        let dropout = Dropout::new(config.embd_pdrop.unwrap_or(0.0));
+
+        PhiModel {
+            embed_tokens,
+            layers,
+            final_layernorm,
+            rotary_embedding,
+            dropout,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the decoder layers to int4 in place.
+    /// The embeddings and layer normalizations are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - Optional vector of length *num_hidden_layers* containing the past keys and values of each layer of shape (*batch size*, *number of key/value heads*, *past_sequence_length*, *hidden size per head*). When provided, these are concatenated with the current input keys and values.
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `PhiModelOutput` containing:
+    ///   - `output` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *num_hidden_layers* containing the past keys and values of each layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *number of heads*, *sequence_length*, *past_sequence_length + sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::phi::{PhiConfig, PhiModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = PhiConfig::from_file(config_path);
+    /// # let phi_model: PhiModel = PhiModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     phi_model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             None,
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<PhiModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_tokens)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+
+        let (layer_past, past_length) = match layer_past {
+            Some(value) => {
+                if value.len() != self.layers.len() {
+                    return Err(RustBertError::ValueError(format!(
+                        "Past activations vector length ({}) must be equal to the number of layers ({})",
+                        value.len(),
+                        self.layers.len()
+                    )));
+                } else {
+                    let past_length = value
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|layer_state| layer_state.prev_key.size()[2])
+                        .unwrap_or(0);
+                    (value, past_length)
+                }
+            }
+            None => {
+                let mut out = Vec::with_capacity(self.layers.len());
+                out.resize_with(self.layers.len(), || None);
+                (out, 0)
+            }
+        };
+        let key_length = past_length + sequence_length;
+
+        let position_ids = match position_ids {
+            Some(value) => value.shallow_clone(),
+            None => Tensor::arange_start(past_length, key_length, (Kind::Int64, device))
+                .unsqueeze(0)
+                .expand([batch_size, sequence_length], true),
+        };
+
+        let mut masked_positions = build_causal_mask(sequence_length, key_length, device).view([
+            1,
+            1,
+            sequence_length,
+            key_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            masked_positions =
+                masked_positions.logical_or(&attention_mask.view([batch_size, 1, 1, -1]).eq(0));
+        }
+        let kind = input_embeddings.kind();
+        let attention_mask = Tensor::zeros(masked_positions.size(), (kind, device))
+            .masked_fill(&masked_positions, get_min(kind)?);
+
+        let (cos, sin) = self.rotary_embedding.forward(&position_ids, kind);
+
+        let mut hidden_state = input_embeddings.apply_t(&self.dropout, train);
+
+        let mut all_presents: Option<Vec<Option<LayerState>>> = self.use_cache.then(Vec::new);
+        let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
+        let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
+
+        for (layer, past) in self.layers.iter().zip(layer_past) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let temp = layer.forward_t(
+                &hidden_state,
+                Some(&attention_mask),
+                (&cos, &sin),
+                past.as_ref(),
+                train,
+            );
+            hidden_state = temp.0;
+            if let Some(presents) = all_presents.borrow_mut() {
+                presents.push(temp.1);
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(temp.2.unwrap());
+            };
+        }
+
+        let output = hidden_state.apply(&self.final_layernorm);
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(output.copy());
+        };
+
+        Ok(PhiModelOutput {
+            output,
+            cache: all_presents,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # Phi Language Modeling head
+/// Phi model with a decoding head (linear layer with bias, not tied to the word embeddings).
+/// It is made of the following blocks:
+/// - `model`: Base PhiModel
+/// - `lm_head`: Linear layer projecting the hidden states to the vocabulary
+pub struct PhiForCausalLM {
+    model: PhiModel,
+    lm_head: QuantizableLinear,
+}
+
+impl PhiForCausalLM {
+    /// Build a new `PhiForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Phi model
+    /// * `config` - `PhiConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::phi::{PhiConfig, PhiForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = PhiConfig::from_file(config_path);
+    /// let phi: PhiForCausalLM = PhiForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &PhiConfig) -> PhiForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = PhiModel::new(p, config);
+        let lm_head = nn::linear(
+            p / "lm_head",
+            config.hidden_size,
+            config.vocab_size,
+            Default::default(),
+        );
+
+        PhiForCausalLM {
+            model,
+            lm_head: lm_head.into(),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place. The language model head
+    /// is only quantized if `quantize_lm_head` is set in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)?;
+        if config.quantize_lm_head {
+            self.lm_head.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - `Cache` containing the past keys and values of each layer (`Cache::PhiCache` or `Cache::None`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `cache` - `Cache::PhiCache` containing the past keys and values of each layer
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::PhiCache(layer_past) => self.model.forward_t(
+                input_ids,
+                layer_past,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            Cache::None => self.model.forward_t(
+                input_ids,
+                None,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with Phi Model".into(),
+                ));
+            }
+        }?;
+
+        let lm_logits = base_model_output.output.apply(&self.lm_head);
+
+        Ok(LMModelOutput {
+            lm_logits,
+            cache: Cache::PhiCache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
+        })
+    }
+}
+
+/// Container for the Phi model output.
+pub struct PhiModelOutput {
+    /// Hidden state of the last layer of the decoder
+    pub output: Tensor,
+    /// Cached attention layers keys and values if the model is used for generation
+    pub cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the Phi architecture
+pub struct PhiGenerator {
+    model: PhiForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl PhiGenerator {
+    /// Build a new `PhiGenerator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::phi::{
+    ///     PhiConfigResources, PhiGenerator, PhiMergesResources, PhiVocabResources,
+    /// };
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType};
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::{LocalResource, RemoteResource};
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_type: ModelType::Phi,
+    ///     model_resource: ModelResource::Torch(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     })),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(
+    ///         PhiConfigResources::PHI_2,
+    ///     )),
+    ///     vocab_resource: Box::new(RemoteResource::from_pretrained(
+    ///         PhiVocabResources::PHI_2,
+    ///     )),
+    ///     merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+    ///         PhiMergesResources::PHI_2,
+    ///     ))),
+    ///     max_length: Some(128),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..Default::default()
+    /// };
+    /// let phi_generator = PhiGenerator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<PhiGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let merges_path = generate_config
+            .merges_resource
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "Phi expects a merges resources to be provided".to_string(),
+                )
+            })?
+            .get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Phi,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<PhiGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate()?;
+        let mut var_store = nn::VarStore::new(device);
+
+        let config = PhiConfig::from_file(config_path);
+        let model = PhiForCausalLM::new(var_store.root(), &config);
+        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+
+        let bos_token_id = tokenizer.get_bos_id().or(config.bos_token_id);
+        let eos_token_ids = tokenizer
+            .get_eos_id()
+            .or(config.eos_token_id)
+            .map(|id| vec![id]);
+        let pad_token_id = tokenizer.get_pad_id();
+        let max_position_embeddings = config.max_position_embeddings;
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = config.decoder_start_token_id;
+
+        Ok(PhiGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+
+    /// Quantize the weights of the model to int4 in place (see `PhiForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+}
+
+impl PrivateLanguageGenerator for PhiGenerator {
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn _get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        &mut self.tokenizer
+    }
+    fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+    fn get_var_store_mut(&mut self) -> Result<&mut nn::VarStore, RustBertError> {
+        Ok(&mut self.var_store)
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> Option<i64> {
+        Some(self.max_position_embeddings)
+    }
+
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        self.model.forward_t(
+            input_ids,
+            layer_past,
+            attention_mask,
+            position_ids,
+            input_embeds,
+            train,
+        )
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        // Positions are counted from the first non-padding token of each (left-padded) prompt
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::PhiCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::PhiCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::PhiCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::PhiCache(None),
+            },
+            _ => panic!("Cache type incompatible with Phi"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::PhiCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for Phi model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator for PhiGenerator {}
@@ -0,0 +1,141 @@
+// Copyright 2023 Microsoft and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::phi::attention::{LayerState, PhiAttention};
+use crate::phi::phi_model::PhiConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+/// # Phi feed-forward network
+/// Two dense layers (`fc2(activation(fc1(x)))`) with biases.
+pub struct PhiMLP {
+    fc1: QuantizableLinear,
+    fc2: QuantizableLinear,
+    activation: TensorFunction,
+}
+
+impl PhiMLP {
+    pub fn new<'p, P>(p: P, config: &PhiConfig) -> PhiMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let fc1 = nn::linear(
+            p / "fc1",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let fc2 = nn::linear(
+            p / "fc2",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let activation = config.hidden_act.get_function();
+
+        PhiMLP {
+            fc1: fc1.into(),
+            fc2: fc2.into(),
+            activation,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.fc1.quantize_int4(config)?;
+        self.fc2.quantize_int4(config)
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (self.activation.get_fn())(&hidden_states.apply(&self.fc1)).apply(&self.fc2)
+    }
+}
+
+/// # Phi decoder layer
+/// Parallel attention and feed-forward blocks sharing a single input layer normalization: both blocks read the same
+/// normalized hidden states and their outputs are added to the residual stream (`x + attn(ln(x)) + mlp(ln(x))`).
+pub struct PhiDecoderLayer {
+    input_layernorm: nn::LayerNorm,
+    self_attn: PhiAttention,
+    mlp: PhiMLP,
+    resid_dropout: Dropout,
+}
+
+impl PhiDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &PhiConfig) -> PhiDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let input_layernorm = nn::layer_norm(
+            p / "input_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let self_attn = PhiAttention::new(p / "self_attn", config);
+        let mlp = PhiMLP::new(p / "mlp", config);
+        let resid_dropout = Dropout::new(config.resid_pdrop.unwrap_or(0.0));
+
+        PhiDecoderLayer {
+            input_layernorm,
+            self_attn,
+            mlp,
+            resid_dropout,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attn.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        rotary: (&Tensor, &Tensor),
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let normalized_hidden_states = hidden_states.apply(&self.input_layernorm);
+        let (attention_output, present, attention_weights) = self.self_attn.forward_t(
+            &normalized_hidden_states,
+            attention_mask,
+            rotary,
+            layer_past,
+            train,
+        );
+        let feed_forward_output = self.mlp.forward(&normalized_hidden_states);
+
+        let hidden_states = hidden_states
+            + attention_output.apply_t(&self.resid_dropout, train)
+            + feed_forward_output.apply_t(&self.resid_dropout, train);
+
+        (hidden_states, present, attention_weights)
+    }
+}
@@ -12,6 +12,7 @@
 
 use crate::common::activations::TensorFunction;
 use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
 use crate::prophetnet::ProphetNetConfig;
 use crate::RustBertError;
 use std::borrow::Borrow;
@@ -202,7 +203,7 @@ impl ProphetNetAttention {
         };
 
         let key_sequence_key = key_states.size()[1];
-        let mut attention_weights = query_states.bmm(&key_states.transpose(1, 2));
+        let mut attention_weights = attention_scores(&query_states, &key_states.transpose(1, 2));
 
         if let Some(attention_mask) = attention_mask {
             attention_weights = attention_weights + attention_mask;
@@ -224,8 +225,7 @@ impl ProphetNetAttention {
             .softmax(-1, attention_weights_reshaped.kind())
             .apply_t(&self.attention_dropout, train);
 
-        let attention_output = attention_probs
-            .bmm(&value_states)
+        let attention_output = attention_output(&attention_probs, &value_states)
             .transpose(0, 1)
             .contiguous()
             .view([sequence_length, batch_size, hidden_size])
@@ -468,7 +468,8 @@ impl ProphetNetNgramAttention {
         };
         let main_sequence_length = sequence_length / (1 + self.ngram);
 
-        let main_attention_weights = main_query_states.bmm(&main_key_states.transpose(1, 2));
+        let main_attention_weights =
+            attention_scores(&main_query_states, &main_key_states.transpose(1, 2));
 
         let main_relative_pos_embeddings = self.get_main_relative_position_embeddings(
             &main_hidden_states,
@@ -485,8 +486,7 @@ impl ProphetNetNgramAttention {
             .softmax(-1, main_attention_weights.kind())
             .apply_t(&self.attention_dropout, train);
 
-        let main_attention_output = main_attention_probas
-            .bmm(&main_value_states)
+        let main_attention_output = attention_output(&main_attention_probas, &main_value_states)
             .transpose(0, 1)
             .contiguous()
             .view([-1, main_sequence_length, batch_size, hidden_size])
@@ -10,6 +10,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::mps;
 use crate::prophetnet::ProphetNetConfig;
 use std::borrow::Borrow;
 use tch::nn::{Embedding, EmbeddingConfig};
@@ -64,7 +65,7 @@ impl ProphetNetPositionalEmbeddings {
                     };
                     let attention_mask =
                         attention_mask.unwrap_or_else(|| calc_attention_mask.as_ref().unwrap());
-                    attention_mask.cumsum(1, Kind::Int64) * attention_mask + self.padding_idx
+                    mps::cumsum(attention_mask, 1, Kind::Int64) * attention_mask + self.padding_idx
                 }
             }
             Some(value) => value.copy(),
@@ -16,6 +16,9 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use tch::{nn, Device, Kind, Tensor};
 
+use crate::common::weight_tying::{
+    load_weights_with_tying, weight_tying_policy, TiedWeights, WeightTyingStatus,
+};
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
     PreparedInput, PrivateLanguageGenerator,
@@ -343,6 +346,10 @@ pub struct ProphetNetForConditionalGeneration {
 }
 
 impl ProphetNetForConditionalGeneration {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights =
+        TiedWeights::new("prophetnet.word_embeddings.weight", "lm_head.weight");
+
     /// Build a new `ProphetNetForConditionalGeneration`
     ///
     /// # Arguments
@@ -850,6 +857,7 @@ pub struct ProphetNetConditionalGenerator {
     vocab_size: i64,
     decoder_start_id: Option<i64>,
     max_position_embeddings: i64,
+    weight_tying_status: Vec<WeightTyingStatus>,
 }
 
 impl ProphetNetConditionalGenerator {
@@ -915,11 +923,16 @@ impl ProphetNetConditionalGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
         let config = ProphetNetConfig::from_file(config_path);
         let model = ProphetNetForConditionalGeneration::new(var_store.root(), &config)?;
-        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+        let weight_tying_status = load_weights_with_tying(
+            &generate_config.model_resource,
+            &mut var_store,
+            &[ProphetNetForConditionalGeneration::TIED_WEIGHTS],
+            weight_tying_policy(),
+        )?;
 
         let bos_token_id = Some(config.bos_token_id);
         let eos_token_ids = Some(vec![config.eos_token_id]);
@@ -941,8 +954,15 @@ impl ProphetNetConditionalGenerator {
             vocab_size,
             decoder_start_id,
             max_position_embeddings,
+            weight_tying_status,
         })
     }
+
+    /// Returns the tying of the LM head with the input embeddings verified when loading the weights. An LM head
+    /// differing from the input embeddings is reported as `WeightTyingStatus::Untied`.
+    pub fn get_weight_tying_status(&self) -> &[WeightTyingStatus] {
+        &self.weight_tying_status
+    }
 }
 
 impl PrivateLanguageGenerator for ProphetNetConditionalGenerator {
@@ -1028,6 +1048,7 @@ impl PrivateLanguageGenerator for ProphetNetConditionalGenerator {
         Ok(LMModelOutput {
             lm_logits: base_model_output.logits,
             cache: Cache::ProphetNetCache(base_model_output.next_decoder_cache),
+            last_hidden_state: None,
         })
     }
 
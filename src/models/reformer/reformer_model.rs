@@ -585,6 +585,7 @@ impl ReformerModelWithLMHead {
     ///
     /// * `ReformerLMModelOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocabulary item
+    ///   - `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *2 x hidden_size*) representing the last hidden states
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer*  containing values for the states and buckets for future use.
@@ -644,6 +645,7 @@ impl ReformerModelWithLMHead {
 
         Ok(ReformerLMModelOutput {
             logits,
+            hidden_states: reformer_output.hidden_states,
             all_hidden_states: reformer_output.all_hidden_states,
             all_attentions: reformer_output.all_attentions,
             next_cache: reformer_output.next_cache,
@@ -985,6 +987,8 @@ impl ReformerForQuestionAnswering {
 pub struct ReformerLMModelOutput {
     /// logits
     pub logits: Tensor,
+    /// Last hidden states from the model
+    pub hidden_states: Tensor,
     /// Hidden states for all intermediate layers
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
@@ -1052,7 +1056,7 @@ impl ReformerGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
         let config = ReformerConfig::from_file(config_path);
         let model = ReformerModelWithLMHead::new(var_store.root(), &config)?;
@@ -1156,6 +1160,7 @@ impl PrivateLanguageGenerator for ReformerGenerator {
         Ok(LMModelOutput {
             lm_logits: output.logits,
             cache: Cache::ReformerCache(output.next_cache),
+            last_hidden_state: Some(output.hidden_states),
         })
     }
 
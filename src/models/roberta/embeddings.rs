@@ -14,6 +14,7 @@
 use crate::bert::{BertConfig, BertEmbedding};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::mps;
 use crate::RustBertError;
 use std::borrow::Borrow;
 use tch::nn::{embedding, EmbeddingConfig};
@@ -34,7 +35,7 @@ pub struct RobertaEmbeddings {
 impl RobertaEmbeddings {
     fn create_position_ids_from_input_ids(&self, x: &Tensor) -> Tensor {
         let mask: Tensor = x.ne(self.padding_index).to_kind(Kind::Int64);
-        mask.cumsum(1, Kind::Int64) * mask + self.padding_index
+        mps::cumsum(&mask, 1, Kind::Int64) * mask + self.padding_index
     }
 
     fn create_position_ids_from_embeddings(&self, x: &Tensor) -> Tensor {
@@ -214,4 +215,8 @@ impl BertEmbedding for RobertaEmbeddings {
             .apply(&self.layer_norm)
             .apply_t(&self.dropout, train))
     }
+
+    fn get_word_embeddings(&self) -> &Tensor {
+        &self.word_embeddings.ws
+    }
 }
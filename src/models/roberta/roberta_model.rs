@@ -15,6 +15,7 @@ use crate::bert::{BertConfig, BertModel};
 use crate::common::activations::_gelu;
 use crate::common::dropout::Dropout;
 use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::weight_tying::TiedWeights;
 use crate::roberta::embeddings::RobertaEmbeddings;
 use crate::RustBertError;
 use std::borrow::Borrow;
@@ -84,6 +85,11 @@ impl RobertaModelResources {
         "codebert-mlm/model",
         "https://huggingface.co/microsoft/codebert-base-mlm/resolve/main/rust_model.ot",
     );
+    /// Shared under Apache 2.0 license by the TransQuest authors at <https://huggingface.co/TransQuest/monotransquest-da-multilingual>.
+    pub const TRANSQUEST_DA_MULTILINGUAL: (&'static str, &'static str) = (
+        "transquest-da-multilingual/model",
+        "https://huggingface.co/TransQuest/monotransquest-da-multilingual/resolve/main/model.safetensors",
+    );
 }
 
 impl RobertaConfigResources {
@@ -137,6 +143,11 @@ impl RobertaConfigResources {
         "codebert-mlm/config",
         "https://huggingface.co/microsoft/codebert-base-mlm/resolve/main/config.json",
     );
+    /// Shared under Apache 2.0 license by the TransQuest authors at <https://huggingface.co/TransQuest/monotransquest-da-multilingual>.
+    pub const TRANSQUEST_DA_MULTILINGUAL: (&'static str, &'static str) = (
+        "transquest-da-multilingual/config",
+        "https://huggingface.co/TransQuest/monotransquest-da-multilingual/resolve/main/config.json",
+    );
 }
 
 impl RobertaVocabResources {
@@ -190,6 +201,11 @@ impl RobertaVocabResources {
         "codebert-mlm/vocab",
         "https://huggingface.co/microsoft/codebert-base-mlm/resolve/main/vocab.json",
     );
+    /// Shared under Apache 2.0 license by the TransQuest authors at <https://huggingface.co/TransQuest/monotransquest-da-multilingual>.
+    pub const TRANSQUEST_DA_MULTILINGUAL: (&'static str, &'static str) = (
+        "transquest-da-multilingual/spiece",
+        "https://huggingface.co/TransQuest/monotransquest-da-multilingual/resolve/main/sentencepiece.bpe.model",
+    );
 }
 
 impl RobertaMergesResources {
@@ -292,6 +308,12 @@ pub struct RobertaForMaskedLM {
 }
 
 impl RobertaForMaskedLM {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights = TiedWeights::new(
+        "roberta.embeddings.word_embeddings.weight",
+        "lm_head.decoder.weight",
+    );
+
     /// Build a new `RobertaForMaskedLM`
     ///
     /// # Arguments
@@ -413,6 +435,12 @@ impl RobertaForMaskedLM {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Quantizes the linear layers of the RoBERTa encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.roberta.quantize_dynamic()
+    }
 }
 
 pub struct RobertaClassificationHead {
@@ -602,6 +630,17 @@ impl RobertaForSequenceClassification {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Word embeddings matrix of shape (*vocab_size*, *hidden_size*)
+    pub(crate) fn get_word_embeddings(&self) -> &Tensor {
+        self.roberta.get_word_embeddings()
+    }
+
+    /// Quantizes the linear layers of the RoBERTa encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.roberta.quantize_dynamic()
+    }
 }
 
 #[allow(rustdoc::invalid_html_tags)]
@@ -749,6 +788,12 @@ impl RobertaForMultipleChoice {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Quantizes the linear layers of the RoBERTa encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.roberta.quantize_dynamic()
+    }
 }
 
 /// # RoBERTa for token classification (e.g. NER, POS)
@@ -905,6 +950,12 @@ impl RobertaForTokenClassification {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Quantizes the linear layers of the RoBERTa encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.roberta.quantize_dynamic()
+    }
 }
 
 /// # RoBERTa for question answering
@@ -1051,6 +1102,12 @@ impl RobertaForQuestionAnswering {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Quantizes the linear layers of the RoBERTa encoder to int8, with a dynamic quantization of the activations. The
+    /// task head is kept in full precision. See the `quantization` module for details.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.roberta.quantize_dynamic()
+    }
 }
 
 /// # RoBERTa for sentence embeddings
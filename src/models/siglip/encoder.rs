@@ -0,0 +1,340 @@
+// Copyright 2024 Google AI and The HuggingFace Team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::{Activation, TensorFunction};
+use crate::common::dropout::Dropout;
+use crate::common::precision::{attention_output, attention_scores};
+use crate::siglip::siglip_model::{SiglipTextConfig, SiglipVisionConfig};
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Tensor};
+
+/// # SigLIP transformer encoder configuration
+/// Shared by the text and vision towers (built from `SiglipTextConfig` or `SiglipVisionConfig`)
+#[derive(Debug, Clone)]
+pub struct SiglipEncoderConfig {
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_attention_heads: i64,
+    pub num_hidden_layers: i64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: f64,
+    pub attention_dropout: f64,
+    pub output_attentions: bool,
+    pub output_hidden_states: bool,
+}
+
+impl From<&SiglipTextConfig> for SiglipEncoderConfig {
+    fn from(config: &SiglipTextConfig) -> Self {
+        SiglipEncoderConfig {
+            hidden_size: config.hidden_size,
+            intermediate_size: config.intermediate_size,
+            num_attention_heads: config.num_attention_heads,
+            num_hidden_layers: config.num_hidden_layers,
+            hidden_act: config.hidden_act,
+            layer_norm_eps: config.layer_norm_eps,
+            attention_dropout: config.attention_dropout,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+}
+
+impl From<&SiglipVisionConfig> for SiglipEncoderConfig {
+    fn from(config: &SiglipVisionConfig) -> Self {
+        SiglipEncoderConfig {
+            hidden_size: config.hidden_size,
+            intermediate_size: config.intermediate_size,
+            num_attention_heads: config.num_attention_heads,
+            num_hidden_layers: config.num_hidden_layers,
+            hidden_act: config.hidden_act,
+            layer_norm_eps: config.layer_norm_eps,
+            attention_dropout: config.attention_dropout,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+}
+
+/// # SigLIP multi-head self-attention
+pub struct SiglipAttention {
+    q_proj: nn::Linear,
+    k_proj: nn::Linear,
+    v_proj: nn::Linear,
+    out_proj: nn::Linear,
+    num_attention_heads: i64,
+    head_dim: i64,
+    dropout: Dropout,
+    output_attentions: bool,
+}
+
+impl SiglipAttention {
+    pub fn new<'p, P>(p: P, config: &SiglipEncoderConfig) -> SiglipAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Hidden size not a multiple of the number of attention heads"
+        );
+        let p = p.borrow();
+
+        let q_proj = nn::linear(
+            p / "q_proj",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let k_proj = nn::linear(
+            p / "k_proj",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let v_proj = nn::linear(
+            p / "v_proj",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let out_proj = nn::linear(
+            p / "out_proj",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        SiglipAttention {
+            q_proj,
+            k_proj,
+            v_proj,
+            out_proj,
+            num_attention_heads: config.num_attention_heads,
+            head_dim: config.hidden_size / config.num_attention_heads,
+            dropout: Dropout::new(config.attention_dropout),
+            output_attentions: config.output_attentions,
+        }
+    }
+
+    fn split_heads(&self, x: Tensor, batch_size: i64) -> Tensor {
+        x.view([batch_size, -1, self.num_attention_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (batch_size, sequence_length, hidden_size) = hidden_states.size3().unwrap();
+
+        let query = self.split_heads(hidden_states.apply(&self.q_proj), batch_size);
+        let key = self.split_heads(hidden_states.apply(&self.k_proj), batch_size);
+        let value = self.split_heads(hidden_states.apply(&self.v_proj), batch_size);
+
+        let mut attention_scores =
+            attention_scores(&query, &key.transpose(-1, -2)) / (self.head_dim as f64).sqrt();
+        if let Some(mask) = attention_mask {
+            attention_scores = attention_scores + mask;
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, attention_scores.kind())
+            .apply_t(&self.dropout, train);
+
+        let context = attention_output(&attention_weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, hidden_size])
+            .apply(&self.out_proj);
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+        (context, attention_weights)
+    }
+}
+
+/// # SigLIP feed-forward layer
+pub struct SiglipMLP {
+    fc1: nn::Linear,
+    fc2: nn::Linear,
+    activation: TensorFunction,
+}
+
+impl SiglipMLP {
+    pub fn new<'p, P>(p: P, config: &SiglipEncoderConfig) -> SiglipMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let fc1 = nn::linear(
+            p / "fc1",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let fc2 = nn::linear(
+            p / "fc2",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        SiglipMLP {
+            fc1,
+            fc2,
+            activation: config.hidden_act.get_function(),
+        }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (self.activation.get_fn())(&hidden_states.apply(&self.fc1)).apply(&self.fc2)
+    }
+}
+
+/// # SigLIP encoder layer
+/// Pre-normalization transformer layer (self-attention and feed-forward blocks)
+pub struct SiglipEncoderLayer {
+    layer_norm1: nn::LayerNorm,
+    self_attn: SiglipAttention,
+    layer_norm2: nn::LayerNorm,
+    mlp: SiglipMLP,
+}
+
+impl SiglipEncoderLayer {
+    pub fn new<'p, P>(p: P, config: &SiglipEncoderConfig) -> SiglipEncoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let layer_norm1 = nn::layer_norm(
+            p / "layer_norm1",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let self_attn = SiglipAttention::new(p / "self_attn", config);
+        let layer_norm2 = nn::layer_norm(
+            p / "layer_norm2",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let mlp = SiglipMLP::new(p / "mlp", config);
+
+        SiglipEncoderLayer {
+            layer_norm1,
+            self_attn,
+            layer_norm2,
+            mlp,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (attention_output, attention_weights) = self.self_attn.forward_t(
+            &hidden_states.apply(&self.layer_norm1),
+            attention_mask,
+            train,
+        );
+        let hidden_states = hidden_states + attention_output;
+        let hidden_states =
+            &hidden_states + self.mlp.forward(&hidden_states.apply(&self.layer_norm2));
+        (hidden_states, attention_weights)
+    }
+}
+
+/// # SigLIP transformer encoder
+pub struct SiglipEncoder {
+    layers: Vec<SiglipEncoderLayer>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl SiglipEncoder {
+    pub fn new<'p, P>(p: P, config: &SiglipEncoderConfig) -> SiglipEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p_layers = p.borrow() / "layers";
+        let layers = (0..config.num_hidden_layers)
+            .map(|layer_index| SiglipEncoderLayer::new(&p_layers / layer_index, config))
+            .collect::<Vec<SiglipEncoderLayer>>();
+
+        SiglipEncoder {
+            layers,
+            output_attentions: config.output_attentions,
+            output_hidden_states: config.output_hidden_states,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_embeds: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> SiglipEncoderOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let mut hidden_state = input_embeds.shallow_clone();
+        for layer in &self.layers {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.shallow_clone());
+            };
+            let (layer_output, attention_weights) =
+                layer.forward_t(&hidden_state, attention_mask, train);
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(hidden_state.shallow_clone());
+        };
+
+        SiglipEncoderOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// Container for the SigLIP encoder output.
+pub struct SiglipEncoderOutput {
+    /// Last hidden states from the encoder
+    pub hidden_state: Tensor,
+    /// Hidden states for the embeddings and all layers of the encoder
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all layers of the encoder
+    pub all_attentions: Option<Vec<Tensor>>,
+}
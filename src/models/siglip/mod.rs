@@ -0,0 +1,75 @@
+//! # SigLIP (Zhai et al.)
+//!
+//! Implementation of the SigLIP image-text model ([Sigmoid Loss for Language Image Pre-Training](https://arxiv.org/abs/2303.15343) Zhai, Mustafa, Kolesnikov, Beyer, 2023).
+//! SigLIP is a CLIP-like dual encoder embedding images and texts in a shared space, trained with a pairwise sigmoid loss instead of the contrastive softmax loss.
+//! It is more accurate than CLIP models of the same size for zero-shot image classification and image-text retrieval.
+//! The text and vision towers are implemented in `siglip_model::SiglipTextTransformer` and `siglip_model::SiglipVisionTransformer`, and combined in `siglip_model::SiglipModel`.
+//!
+//! For zero-shot image classification, the candidate labels are embedded as texts (e.g. "a photo of a cat") and scored independently for the image:
+//! the probability of each label is the sigmoid of the image-text logit.
+//! Ready-to-use pipelines are available in `pipelines::zero_shot_image_classification` and `pipelines::multimodal_embeddings`.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights from [Google](https://huggingface.co/google/siglip-base-patch16-224) to the `.ot` format, for example with `python utils/convert_model.py path/to/siglip/model.safetensors`.
+//! - `T5Tokenizer` using a `spiece.model` SentencePiece model (see `encode_texts` for the text pre-processing)
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, no_grad, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::siglip::{encode_texts, prepare_image, SiglipConfig, SiglipModel};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/spiece.model"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer =
+//!     TokenizerOption::from_file(ModelType::T5, vocab_path.to_str().unwrap(), None, true, None, None)?;
+//! let config = SiglipConfig::from_file(config_path);
+//! let siglip_model = SiglipModel::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! let labels = ["a photo of a cat", "a photo of a dog"];
+//! let input_ids = encode_texts(&tokenizer, &labels, 64, device);
+//! let image = tch::vision::image::load("path/to/image.jpg")?;
+//! let pixel_values = prepare_image(&image, config.vision_config.image_size)
+//!     .unsqueeze(0)
+//!     .to_device(device);
+//!
+//! let output = no_grad(|| siglip_model.forward_t(&input_ids, &pixel_values, None, false));
+//! let label_probabilities = output.logits_per_image.sigmoid();
+//! # Ok(())
+//! # }
+//! ```
+
+mod encoder;
+mod siglip_model;
+
+pub use encoder::{
+    SiglipAttention, SiglipEncoder, SiglipEncoderConfig, SiglipEncoderLayer, SiglipEncoderOutput,
+    SiglipMLP,
+};
+pub use siglip_model::{
+    encode_texts, prepare_image, SiglipConfig, SiglipConfigResources, SiglipModel,
+    SiglipModelOutput, SiglipModelResources, SiglipMultiheadAttentionPoolingHead, SiglipTextConfig,
+    SiglipTextTransformer, SiglipVisionConfig, SiglipVisionTransformer, SiglipVocabResources,
+};
@@ -0,0 +1,659 @@
+// Copyright 2024 Google AI and The HuggingFace Team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bart::_expand_mask;
+use crate::common::activations::Activation;
+use crate::pipelines::common::TokenizerOption;
+use crate::siglip::encoder::{SiglipEncoder, SiglipEncoderConfig, SiglipMLP};
+use crate::Config;
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use tch::nn::{embedding, Init};
+use tch::{nn, Device, Kind, Tensor};
+
+/// # SigLIP Pretrained model weight files
+pub struct SiglipModelResources;
+
+/// # SigLIP Pretrained model config files
+pub struct SiglipConfigResources;
+
+/// # SigLIP Pretrained model vocab files
+pub struct SiglipVocabResources;
+
+impl SiglipModelResources {
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/big_vision>.
+    pub const SIGLIP_BASE_PATCH16_224: (&'static str, &'static str) = (
+        "siglip-base-patch16-224/model",
+        "https://huggingface.co/google/siglip-base-patch16-224/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/big_vision>.
+    pub const SIGLIP_SO400M_PATCH14_384: (&'static str, &'static str) = (
+        "siglip-so400m-patch14-384/model",
+        "https://huggingface.co/google/siglip-so400m-patch14-384/resolve/main/model.safetensors",
+    );
+}
+
+impl SiglipConfigResources {
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/big_vision>.
+    pub const SIGLIP_BASE_PATCH16_224: (&'static str, &'static str) = (
+        "siglip-base-patch16-224/config",
+        "https://huggingface.co/google/siglip-base-patch16-224/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/big_vision>.
+    pub const SIGLIP_SO400M_PATCH14_384: (&'static str, &'static str) = (
+        "siglip-so400m-patch14-384/config",
+        "https://huggingface.co/google/siglip-so400m-patch14-384/resolve/main/config.json",
+    );
+}
+
+impl SiglipVocabResources {
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/big_vision>.
+    pub const SIGLIP_BASE_PATCH16_224: (&'static str, &'static str) = (
+        "siglip-base-patch16-224/spiece",
+        "https://huggingface.co/google/siglip-base-patch16-224/resolve/main/spiece.model",
+    );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/big_vision>.
+    pub const SIGLIP_SO400M_PATCH14_384: (&'static str, &'static str) = (
+        "siglip-so400m-patch14-384/spiece",
+        "https://huggingface.co/google/siglip-so400m-patch14-384/resolve/main/spiece.model",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+// Pretrained configuration files only list the values differing from the defaults
+#[serde(default)]
+/// # SigLIP text tower configuration
+pub struct SiglipTextConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub max_position_embeddings: i64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: f64,
+    pub attention_dropout: f64,
+    pub projection_size: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for SiglipTextConfig {}
+
+impl Default for SiglipTextConfig {
+    fn default() -> Self {
+        SiglipTextConfig {
+            vocab_size: 32000,
+            hidden_size: 768,
+            intermediate_size: 3072,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            max_position_embeddings: 64,
+            hidden_act: Activation::gelu_new,
+            layer_norm_eps: 1e-6,
+            attention_dropout: 0.0,
+            projection_size: None,
+            pad_token_id: Some(1),
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+// Pretrained configuration files only list the values differing from the defaults
+#[serde(default)]
+/// # SigLIP vision tower configuration
+pub struct SiglipVisionConfig {
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub num_channels: i64,
+    pub image_size: i64,
+    pub patch_size: i64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: f64,
+    pub attention_dropout: f64,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for SiglipVisionConfig {}
+
+impl Default for SiglipVisionConfig {
+    fn default() -> Self {
+        SiglipVisionConfig {
+            hidden_size: 768,
+            intermediate_size: 3072,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            num_channels: 3,
+            image_size: 224,
+            patch_size: 16,
+            hidden_act: Activation::gelu_new,
+            layer_norm_eps: 1e-6,
+            attention_dropout: 0.0,
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+/// # SigLIP model configuration
+/// Defines the text and vision towers of the SigLIP model
+pub struct SiglipConfig {
+    #[serde(default)]
+    pub text_config: SiglipTextConfig,
+    #[serde(default)]
+    pub vision_config: SiglipVisionConfig,
+}
+
+impl Config for SiglipConfig {}
+
+/// # SigLIP text transformer
+/// Encodes texts into embeddings pooled from the last token of the sequence.
+/// It is made of the following blocks:
+/// - `token_embedding` and `position_embedding`: learned token and absolute position embeddings
+/// - `encoder`: `SiglipEncoder` (bidirectional transformer)
+/// - `final_layer_norm` and `head`: final normalization and projection of the last token hidden state
+pub struct SiglipTextTransformer {
+    token_embedding: nn::Embedding,
+    position_embedding: nn::Embedding,
+    encoder: SiglipEncoder,
+    final_layer_norm: nn::LayerNorm,
+    head: nn::Linear,
+}
+
+impl SiglipTextTransformer {
+    pub fn new<'p, P>(p: P, config: &SiglipTextConfig) -> SiglipTextTransformer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let encoder_config = SiglipEncoderConfig::from(config);
+
+        let token_embedding = embedding(
+            p / "embeddings" / "token_embedding",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let position_embedding = embedding(
+            p / "embeddings" / "position_embedding",
+            config.max_position_embeddings,
+            config.hidden_size,
+            Default::default(),
+        );
+        let encoder = SiglipEncoder::new(p / "encoder", &encoder_config);
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let head = nn::linear(
+            p / "head",
+            config.hidden_size,
+            config.projection_size.unwrap_or(config.hidden_size),
+            Default::default(),
+        );
+
+        SiglipTextTransformer {
+            token_embedding,
+            position_embedding,
+            encoder,
+            final_layer_norm,
+            head,
+        }
+    }
+
+    /// Returns the last hidden states of shape (*batch size*, *sequence_length*, *hidden_size*) and the pooled
+    /// text embeddings of shape (*batch size*, *projection_size*)
+    pub fn forward_t(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Tensor) {
+        let sequence_length = input_ids.size()[1];
+        let position_ids = Tensor::arange(sequence_length, (Kind::Int64, input_ids.device()));
+        let input_embeds =
+            input_ids.apply(&self.token_embedding) + position_ids.apply(&self.position_embedding);
+
+        let attention_mask =
+            attention_mask.map(|mask| _expand_mask(mask, None, input_embeds.kind()));
+        let hidden_state = self
+            .encoder
+            .forward_t(&input_embeds, attention_mask.as_ref(), train)
+            .hidden_state
+            .apply(&self.final_layer_norm);
+
+        let pooled_output = hidden_state.select(1, -1).apply(&self.head);
+        (hidden_state, pooled_output)
+    }
+}
+
+/// # SigLIP multi-head attention pooling
+/// Pools the image patches using a learned query (probe) attending to all patches.
+pub struct SiglipMultiheadAttentionPoolingHead {
+    probe: Tensor,
+    in_proj_weight: Tensor,
+    in_proj_bias: Tensor,
+    out_proj: nn::Linear,
+    layernorm: nn::LayerNorm,
+    mlp: SiglipMLP,
+    num_attention_heads: i64,
+}
+
+impl SiglipMultiheadAttentionPoolingHead {
+    pub fn new<'p, P>(p: P, config: &SiglipVisionConfig) -> SiglipMultiheadAttentionPoolingHead
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let p_attention = p / "attention";
+        let hidden_size = config.hidden_size;
+
+        let probe = p.var(
+            "probe",
+            &[1, 1, hidden_size],
+            Init::Randn {
+                mean: 0.,
+                stdev: 1.,
+            },
+        );
+        let in_proj_weight = p_attention.var(
+            "in_proj_weight",
+            &[3 * hidden_size, hidden_size],
+            Init::KaimingUniform,
+        );
+        let in_proj_bias = p_attention.var("in_proj_bias", &[3 * hidden_size], Init::Const(0.));
+        let out_proj = nn::linear(
+            &p_attention / "out_proj",
+            hidden_size,
+            hidden_size,
+            Default::default(),
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let layernorm = nn::layer_norm(p / "layernorm", vec![hidden_size], layer_norm_config);
+        let mlp = SiglipMLP::new(p / "mlp", &SiglipEncoderConfig::from(config));
+
+        SiglipMultiheadAttentionPoolingHead {
+            probe,
+            in_proj_weight,
+            in_proj_bias,
+            out_proj,
+            layernorm,
+            mlp,
+            num_attention_heads: config.num_attention_heads,
+        }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        let (batch_size, _, hidden_size) = hidden_states.size3().unwrap();
+        let head_dim = hidden_size / self.num_attention_heads;
+
+        let weights = self.in_proj_weight.chunk(3, 0);
+        let biases = self.in_proj_bias.chunk(3, 0);
+        let split_heads = |x: Tensor| {
+            x.view([batch_size, -1, self.num_attention_heads, head_dim])
+                .transpose(1, 2)
+        };
+        let query = split_heads(
+            self.probe
+                .expand([batch_size, 1, hidden_size], false)
+                .linear(&weights[0], Some(&biases[0])),
+        );
+        let key = split_heads(hidden_states.linear(&weights[1], Some(&biases[1])));
+        let value = split_heads(hidden_states.linear(&weights[2], Some(&biases[2])));
+
+        let attention_weights = (query.matmul(&key.transpose(-1, -2)) / (head_dim as f64).sqrt())
+            .softmax(-1, hidden_states.kind());
+        let hidden_state = attention_weights
+            .matmul(&value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, 1, hidden_size])
+            .apply(&self.out_proj);
+
+        let hidden_state = &hidden_state + self.mlp.forward(&hidden_state.apply(&self.layernorm));
+        hidden_state.select(1, 0)
+    }
+}
+
+/// # SigLIP vision transformer
+/// Encodes images into embeddings pooled with multi-head attention.
+/// It is made of the following blocks:
+/// - `patch_embedding` and `position_embedding`: non-overlapping patch projection and learned absolute position embeddings
+/// - `encoder`: `SiglipEncoder` (bidirectional transformer)
+/// - `post_layernorm` and `head`: final normalization and attention pooling of the patches
+pub struct SiglipVisionTransformer {
+    patch_embedding: nn::Conv2D,
+    position_embedding: nn::Embedding,
+    encoder: SiglipEncoder,
+    post_layernorm: nn::LayerNorm,
+    head: SiglipMultiheadAttentionPoolingHead,
+}
+
+impl SiglipVisionTransformer {
+    pub fn new<'p, P>(p: P, config: &SiglipVisionConfig) -> SiglipVisionTransformer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let encoder_config = SiglipEncoderConfig::from(config);
+
+        let conv_config = nn::ConvConfig {
+            stride: config.patch_size,
+            ..Default::default()
+        };
+        let patch_embedding = nn::conv2d(
+            p / "embeddings" / "patch_embedding",
+            config.num_channels,
+            config.hidden_size,
+            config.patch_size,
+            conv_config,
+        );
+        let num_patches = (config.image_size / config.patch_size).pow(2);
+        let position_embedding = embedding(
+            p / "embeddings" / "position_embedding",
+            num_patches,
+            config.hidden_size,
+            Default::default(),
+        );
+        let encoder = SiglipEncoder::new(p / "encoder", &encoder_config);
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let post_layernorm = nn::layer_norm(
+            p / "post_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let head = SiglipMultiheadAttentionPoolingHead::new(p / "head", config);
+
+        SiglipVisionTransformer {
+            patch_embedding,
+            position_embedding,
+            encoder,
+            post_layernorm,
+            head,
+        }
+    }
+
+    /// Returns the last hidden states of shape (*batch size*, *num patches*, *hidden_size*) and the pooled
+    /// image embeddings of shape (*batch size*, *hidden_size*)
+    pub fn forward_t(&self, pixel_values: &Tensor, train: bool) -> (Tensor, Tensor) {
+        let patch_embeddings = pixel_values
+            .apply(&self.patch_embedding)
+            .flatten(2, -1)
+            .transpose(1, 2);
+        let num_patches = patch_embeddings.size()[1];
+        let position_ids = Tensor::arange(num_patches, (Kind::Int64, pixel_values.device()));
+        let input_embeds = patch_embeddings + position_ids.apply(&self.position_embedding);
+
+        let hidden_state = self
+            .encoder
+            .forward_t(&input_embeds, None, train)
+            .hidden_state
+            .apply(&self.post_layernorm);
+
+        let pooled_output = self.head.forward(&hidden_state);
+        (hidden_state, pooled_output)
+    }
+}
+
+/// # SigLIP model
+/// Dual encoder embedding texts and images in a shared space, trained with a pairwise sigmoid loss.
+/// Unlike CLIP, the image-text scores are independent binary probabilities (sigmoid of the logits) rather than
+/// a softmax over the candidate labels.
+/// It is made of the following blocks:
+/// - `text_model`: `SiglipTextTransformer` text tower
+/// - `vision_model`: `SiglipVisionTransformer` vision tower
+/// - `logit_scale` and `logit_bias`: learned temperature and bias of the image-text similarity
+pub struct SiglipModel {
+    text_model: SiglipTextTransformer,
+    vision_model: SiglipVisionTransformer,
+    logit_scale: Tensor,
+    logit_bias: Tensor,
+}
+
+impl SiglipModel {
+    /// Build a new `SiglipModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the SigLIP model
+    /// * `config` - `SiglipConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::siglip::{SiglipConfig, SiglipModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = SiglipConfig::from_file(config_path);
+    /// let siglip_model = SiglipModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &SiglipConfig) -> SiglipModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let text_model = SiglipTextTransformer::new(p / "text_model", &config.text_config);
+        let vision_model = SiglipVisionTransformer::new(p / "vision_model", &config.vision_config);
+        let logit_scale = p.var("logit_scale", &[1], Init::Const(0.));
+        let logit_bias = p.var("logit_bias", &[1], Init::Const(0.));
+
+        SiglipModel {
+            text_model,
+            vision_model,
+            logit_scale,
+            logit_bias,
+        }
+    }
+
+    /// Computes the (non-normalized) text embeddings
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tokens of shape (*batch size*, *sequence_length*), padded to the maximum length (see `encode_texts`)
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *sequence_length*). The pretrained models are used without mask.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *projection_size*)
+    pub fn get_text_features(&self, input_ids: &Tensor, attention_mask: Option<&Tensor>) -> Tensor {
+        self.text_model
+            .forward_t(input_ids, attention_mask, false)
+            .1
+    }
+
+    /// Computes the (non-normalized) image embeddings
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel_values` - Normalized images of shape (*batch size*, *num channels*, *image size*, *image size*) (see `prepare_image`)
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *hidden_size*)
+    pub fn get_image_features(&self, pixel_values: &Tensor) -> Tensor {
+        self.vision_model.forward_t(pixel_values, false).1
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tokens of shape (*batch size*, *sequence_length*), padded to the maximum length (see `encode_texts`)
+    /// * `pixel_values` - Normalized images of shape (*batch size*, *num channels*, *image size*, *image size*) (see `prepare_image`)
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *sequence_length*).
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `SiglipModelOutput` containing:
+    ///   - `logits_per_image` - `Tensor` of shape (*num images*, *num texts*) image-text similarity logits. The probability of a text matching an image is given by a sigmoid.
+    ///   - `logits_per_text` - `Tensor` of shape (*num texts*, *num images*)
+    ///   - `text_embeds` - `Tensor` of shape (*num texts*, *projection_size*) normalized text embeddings
+    ///   - `image_embeds` - `Tensor` of shape (*num images*, *hidden_size*) normalized image embeddings
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use rust_bert::siglip::{SiglipConfig, SiglipModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = SiglipConfig::from_file(config_path);
+    /// # let siglip_model = SiglipModel::new(&vs.root(), &config);
+    /// let input_ids = Tensor::randint(32000, &[2, 64], (Kind::Int64, device));
+    /// let pixel_values = Tensor::rand(&[1, 3, 224, 224], (Kind::Float, device));
+    ///
+    /// let model_output =
+    ///     no_grad(|| siglip_model.forward_t(&input_ids, &pixel_values, None, false));
+    /// let probabilities = model_output.logits_per_image.sigmoid();
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: &Tensor,
+        pixel_values: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> SiglipModelOutput {
+        let (_, text_embeds) = self.text_model.forward_t(input_ids, attention_mask, train);
+        let (_, image_embeds) = self.vision_model.forward_t(pixel_values, train);
+
+        let text_embeds = &text_embeds / text_embeds.norm_scalaropt_dim(2, [-1], true);
+        let image_embeds = &image_embeds / image_embeds.norm_scalaropt_dim(2, [-1], true);
+
+        let logits_per_text = text_embeds.matmul(&image_embeds.transpose(0, 1))
+            * self.logit_scale.exp()
+            + &self.logit_bias;
+        let logits_per_image = logits_per_text.transpose(0, 1);
+
+        SiglipModelOutput {
+            logits_per_image,
+            logits_per_text,
+            text_embeds,
+            image_embeds,
+        }
+    }
+}
+
+/// Container for the SigLIP model output.
+pub struct SiglipModelOutput {
+    /// Image-text similarity logits of shape (*num images*, *num texts*)
+    pub logits_per_image: Tensor,
+    /// Text-image similarity logits of shape (*num texts*, *num images*)
+    pub logits_per_text: Tensor,
+    /// Normalized text embeddings
+    pub text_embeds: Tensor,
+    /// Normalized image embeddings
+    pub image_embeds: Tensor,
+}
+
+/// Tokenizes texts for the SigLIP text tower.
+/// The texts are lower-cased and stripped from punctuation, tokenized and padded to `max_length` with the
+/// end of sequence token (the text embeddings are pooled from the last position, the pretrained models expect padding to
+/// the maximum length of 64 tokens).
+///
+/// # Arguments
+///
+/// * `tokenizer` - `TokenizerOption` SentencePiece tokenizer (e.g. `ModelType::T5` loaded from a `spiece.model` file with lower casing)
+/// * `texts` - Texts to encode
+/// * `max_length` - Length of the token sequences
+/// * `device` - Device to place the token ids on
+///
+/// # Returns
+///
+/// * `Tensor` of shape (*num texts*, *max_length*)
+pub fn encode_texts<S>(
+    tokenizer: &TokenizerOption,
+    texts: &[S],
+    max_length: usize,
+    device: Device,
+) -> Tensor
+where
+    S: AsRef<str> + Send + Sync,
+{
+    let canonicalized_texts = texts
+        .iter()
+        .map(|text| {
+            text.as_ref()
+                .chars()
+                .filter(|character| !character.is_ascii_punctuation())
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>();
+    let pad_token_id = tokenizer.get_eos_id().unwrap_or(1);
+
+    let token_ids = tokenizer
+        .encode_list(
+            &canonicalized_texts,
+            max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        )
+        .into_iter()
+        .map(|input| {
+            let mut token_ids = input.token_ids;
+            token_ids.resize(max_length, pad_token_id);
+            Tensor::from_slice(&token_ids)
+        })
+        .collect::<Vec<Tensor>>();
+    Tensor::stack(&token_ids, 0).to(device)
+}
+
+/// Prepares an image for the SigLIP vision tower: the image is resized to the model image size and normalized to the [-1, 1] range.
+///
+/// # Arguments
+///
+/// * `image` - Image tensor of shape (*num channels*, *height*, *width*) with values in [0, 255] (e.g. loaded with `tch::vision::image::load`)
+/// * `image_size` - Image size of the model (`SiglipVisionConfig::image_size`)
+///
+/// # Returns
+///
+/// * `Tensor` of shape (*num channels*, *image size*, *image size*)
+pub fn prepare_image(image: &Tensor, image_size: i64) -> Tensor {
+    let resized = image
+        .to_kind(Kind::Float)
+        .unsqueeze(0)
+        .upsample_bicubic2d([image_size, image_size], false, None::<f64>, None::<f64>)
+        .squeeze_dim(0)
+        .clamp(0.0, 255.0);
+    (resized / 255.0 - 0.5) / 0.5
+}
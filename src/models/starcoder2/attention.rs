@@ -0,0 +1,302 @@
+// Copyright 2024 BigCode and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::position_embeddings::{apply_rotary_pos_emb, RotaryEmbedding};
+use crate::common::precision::{attention_output, attention_scores};
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::TensorParallelConfig;
+use crate::starcoder2::starcoder2_model::StarCoder2Config;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Device, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for StarCoder2 attention layers
+/// Stores the cached value of key and value, before expansion to the number of query heads
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+
+    pub(crate) fn truncate(&self, length: i64) -> LayerState {
+        LayerState {
+            prev_key: self.prev_key.narrow(-2, 0, length),
+            prev_value: self.prev_value.narrow(-2, 0, length),
+        }
+    }
+}
+
+/// Builds the boolean mask of the key positions each query may not attend to, of shape (*query_length*, *key_length*).
+/// Queries are assumed to be the last `query_length` positions of the keys. Each query attends to itself and the
+/// previous keys, restricted to the `sliding_window` most recent tokens if provided.
+pub(crate) fn build_causal_mask(
+    query_length: i64,
+    key_length: i64,
+    sliding_window: Option<i64>,
+    device: Device,
+) -> Tensor {
+    let query_positions =
+        Tensor::arange_start(key_length - query_length, key_length, (Kind::Int64, device))
+            .unsqueeze(-1);
+    let key_positions = Tensor::arange(key_length, (Kind::Int64, device)).unsqueeze(0);
+    let distance = query_positions - key_positions;
+    let future_mask = distance.lt(0);
+    match sliding_window {
+        Some(sliding_window) => future_mask.logical_or(&distance.ge(sliding_window)),
+        None => future_mask,
+    }
+}
+
+/// # Rotary position embeddings
+/// StarCoder2 uses the shared rotary position embeddings (non-interleaved layout) over the full head dimension.
+pub type StarCoder2RotaryEmbedding = RotaryEmbedding;
+
+/// Repeats the key and value heads to match the number of query heads (grouped-query attention)
+fn repeat_kv(hidden_states: &Tensor, num_repetitions: i64) -> Tensor {
+    if num_repetitions == 1 {
+        return hidden_states.shallow_clone();
+    }
+    let (batch_size, num_key_value_heads, sequence_length, head_dim) =
+        hidden_states.size4().unwrap();
+    hidden_states
+        .unsqueeze(2)
+        .expand(
+            [
+                batch_size,
+                num_key_value_heads,
+                num_repetitions,
+                sequence_length,
+                head_dim,
+            ],
+            false,
+        )
+        .reshape([
+            batch_size,
+            num_key_value_heads * num_repetitions,
+            sequence_length,
+            head_dim,
+        ])
+}
+
+/// # StarCoder2 self-attention
+/// Causal grouped-query attention with rotary position embeddings: `num_key_value_heads` key and value heads are shared
+/// by groups of query heads.
+pub struct StarCoder2Attention {
+    q_proj: QuantizableLinear,
+    k_proj: QuantizableLinear,
+    v_proj: QuantizableLinear,
+    o_proj: QuantizableLinear,
+    attention_dropout: Dropout,
+    residual_dropout: Dropout,
+    num_heads: i64,
+    num_key_value_heads: i64,
+    head_dim: i64,
+    use_cache: bool,
+    output_attentions: bool,
+}
+
+impl StarCoder2Attention {
+    pub fn new<'p, P>(p: P, config: &StarCoder2Config) -> StarCoder2Attention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Attention hidden states not a multiple of the number of heads"
+        );
+        let num_key_value_heads = config
+            .num_key_value_heads
+            .unwrap_or(config.num_attention_heads);
+        assert_eq!(
+            config.num_attention_heads % num_key_value_heads,
+            0,
+            "Number of attention heads not a multiple of the number of key/value heads"
+        );
+        let head_dim = config.hidden_size / config.num_attention_heads;
+
+        let linear_config = nn::LinearConfig {
+            bias: config.use_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let q_proj = nn::linear(
+            p / "q_proj",
+            config.hidden_size,
+            config.num_attention_heads * head_dim,
+            linear_config,
+        );
+        let k_proj = nn::linear(
+            p / "k_proj",
+            config.hidden_size,
+            num_key_value_heads * head_dim,
+            linear_config,
+        );
+        let v_proj = nn::linear(
+            p / "v_proj",
+            config.hidden_size,
+            num_key_value_heads * head_dim,
+            linear_config,
+        );
+        let o_proj = nn::linear(
+            p / "o_proj",
+            config.num_attention_heads * head_dim,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let attention_dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+        let residual_dropout = Dropout::new(config.residual_dropout.unwrap_or(0.0));
+
+        StarCoder2Attention {
+            q_proj: q_proj.into(),
+            k_proj: k_proj.into(),
+            v_proj: v_proj.into(),
+            o_proj: o_proj.into(),
+            attention_dropout,
+            residual_dropout,
+            num_heads: config.num_attention_heads,
+            num_key_value_heads,
+            head_dim,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.q_proj.quantize_int4(config)?;
+        self.k_proj.quantize_int4(config)?;
+        self.v_proj.quantize_int4(config)?;
+        self.o_proj.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        config.validate(self.num_heads, "attention heads")?;
+        config.validate(self.num_key_value_heads, "key and value heads")?;
+        self.q_proj.split_columns(&config.devices, self.head_dim)?;
+        self.k_proj.split_columns(&config.devices, self.head_dim)?;
+        self.v_proj.split_columns(&config.devices, self.head_dim)?;
+        self.o_proj.split_rows(&config.devices, self.head_dim)
+    }
+
+    fn split_heads(&self, tensor: &Tensor, num_heads: i64) -> Tensor {
+        let (batch_size, sequence_length, _) = tensor.size3().unwrap();
+        tensor
+            .view([batch_size, sequence_length, num_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        rotary: (&Tensor, &Tensor),
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (batch_size, sequence_length, _) = hidden_states.size3().unwrap();
+        let (cos, sin) = rotary;
+
+        let query = self.split_heads(&hidden_states.apply(&self.q_proj), self.num_heads);
+        let key = self.split_heads(&hidden_states.apply(&self.k_proj), self.num_key_value_heads);
+        let value = self.split_heads(&hidden_states.apply(&self.v_proj), self.num_key_value_heads);
+
+        let query = apply_rotary_pos_emb(&query, cos, sin);
+        let mut key = apply_rotary_pos_emb(&key, cos, sin);
+        let mut value = value;
+
+        if let Some(layer_past) = layer_past {
+            key = Tensor::cat(&[&layer_past.prev_key, &key], -2);
+            value = Tensor::cat(&[&layer_past.prev_value, &value], -2);
+        }
+
+        let present = self.use_cache.then(|| LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        });
+
+        let num_repetitions = self.num_heads / self.num_key_value_heads;
+        let key = repeat_kv(&key, num_repetitions);
+        let value = repeat_kv(&value, num_repetitions);
+
+        let mut attention_scores =
+            attention_scores(&query, &key.transpose(-1, -2)) / (self.head_dim as f64).sqrt();
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores + attention_mask;
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_output(&attention_weights, &value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.o_proj)
+            .apply_t(&self.residual_dropout, train);
+
+        let attention_weights = self.output_attentions.then_some(attention_weights);
+
+        (attention_output, present, attention_weights)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn causal_mask_sliding_window() {
+        let mask = build_causal_mask(4, 4, Some(2), Device::Cpu).to_kind(Kind::Int64);
+        assert_eq!(mask.size(), vec![4, 4]);
+        assert_eq!(mask.int64_value(&[2, 2]), 0);
+        assert_eq!(mask.int64_value(&[2, 1]), 0);
+        assert_eq!(mask.int64_value(&[2, 0]), 1);
+        assert_eq!(mask.int64_value(&[1, 2]), 1);
+
+        // Single query attending to a cache of 5 tokens
+        let mask = build_causal_mask(1, 6, Some(3), Device::Cpu).to_kind(Kind::Int64);
+        assert_eq!(mask.size(), vec![1, 6]);
+        assert_eq!(mask.int64_value(&[0, 5]), 0);
+        assert_eq!(mask.int64_value(&[0, 3]), 0);
+        assert_eq!(mask.int64_value(&[0, 2]), 1);
+
+        let mask = build_causal_mask(1, 6, None, Device::Cpu).to_kind(Kind::Int64);
+        assert_eq!(mask.sum(Kind::Int64).int64_value(&[]), 0);
+    }
+}
@@ -0,0 +1,74 @@
+//! # StarCoder2 (Lozhkov et al.)
+//!
+//! Implementation of the StarCoder2 code language model ([StarCoder 2 and The Stack v2: The Next Generation](https://arxiv.org/abs/2402.19173) Lozhkov, Li, Ben Allal et al., 2024).
+//! StarCoder2 is a decoder-only model trained on permissively licensed source code, using grouped-query attention (GQA),
+//! rotary position embeddings and sliding window attention.
+//! The base model is implemented in the `starcoder2_model::StarCoder2Model` struct, the language modeling head in `starcoder2_model::StarCoder2ForCausalLM`
+//! and the text generation utilities in `starcoder2_model::StarCoder2Generator`.
+//!
+//! The model was trained with a fill-in-the-middle objective: the code surrounding a missing span is provided using the
+//! `<fim_prefix>`, `<fim_suffix>` and `<fim_middle>` tokens (see `build_fill_in_the_middle_prompt`), and the model generates the missing span.
+//! The `TextGenerationModel::fill_in_the_middle` method of the text generation pipeline performs the prompt formatting and post-processing.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights from [BigCode](https://huggingface.co/bigcode) to the `.ot` format, for example with `python utils/convert_model.py path/to/starcoder2/model.safetensors`.
+//! - `Gpt2Tokenizer` using a `vocab.json` vocabulary and `merges.txt` merges file
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::starcoder2::{StarCoder2Config, StarCoder2ForCausalLM};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::Gpt2Tokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/vocab.json"),
+//! };
+//! let merges_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/merges.txt"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let merges_path = merges_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_file(
+//!     vocab_path.to_str().unwrap(),
+//!     merges_path.to_str().unwrap(),
+//!     false,
+//! )?;
+//! let config = StarCoder2Config::from_file(config_path);
+//! let starcoder2_model = StarCoder2ForCausalLM::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+mod starcoder2_model;
+mod transformer;
+
+pub use attention::{LayerState, StarCoder2Attention, StarCoder2RotaryEmbedding};
+pub use starcoder2_model::{
+    build_fill_in_the_middle_prompt, StarCoder2Config, StarCoder2ConfigResources,
+    StarCoder2ForCausalLM, StarCoder2Generator, StarCoder2MergesResources, StarCoder2Model,
+    StarCoder2ModelOutput, StarCoder2ModelResources, StarCoder2VocabResources, FIM_MIDDLE, FIM_PAD,
+    FIM_PREFIX, FIM_SUFFIX,
+};
+pub use transformer::{StarCoder2DecoderLayer, StarCoder2MLP};
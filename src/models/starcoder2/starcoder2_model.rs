@@ -0,0 +1,895 @@
+// Copyright 2024 BigCode and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::Activation;
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::kind::get_min;
+use crate::common::mps;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::TensorParallelConfig;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{Cache, GenerateConfig, LMModelOutput, LanguageGenerator};
+use crate::starcoder2::attention::{build_causal_mask, LayerState, StarCoder2RotaryEmbedding};
+use crate::starcoder2::transformer::StarCoder2DecoderLayer;
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Device, Kind, Tensor};
+
+/// Fill-in-the-middle token preceding the code before the span to generate
+pub const FIM_PREFIX: &str = "<fim_prefix>";
+/// Fill-in-the-middle token preceding the code after the span to generate
+pub const FIM_SUFFIX: &str = "<fim_suffix>";
+/// Fill-in-the-middle token after which the missing span is generated
+pub const FIM_MIDDLE: &str = "<fim_middle>";
+/// Fill-in-the-middle padding token
+pub const FIM_PAD: &str = "<fim_pad>";
+
+/// Builds a fill-in-the-middle prompt (prefix-suffix-middle ordering): the model generates the code
+/// to insert between `prefix` and `suffix` after the `<fim_middle>` token.
+pub fn build_fill_in_the_middle_prompt(prefix: &str, suffix: &str) -> String {
+    format!("{FIM_PREFIX}{prefix}{FIM_SUFFIX}{suffix}{FIM_MIDDLE}")
+}
+
+/// # StarCoder2 Pretrained model weight files
+pub struct StarCoder2ModelResources;
+
+/// # StarCoder2 Pretrained model config files
+pub struct StarCoder2ConfigResources;
+
+/// # StarCoder2 Pretrained model vocab files
+pub struct StarCoder2VocabResources;
+
+/// # StarCoder2 Pretrained model merges files
+pub struct StarCoder2MergesResources;
+
+impl StarCoder2ModelResources {
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-3b>.
+    pub const STARCODER2_3B: (&'static str, &'static str) = (
+        "starcoder2-3b/model",
+        "https://huggingface.co/bigcode/starcoder2-3b/resolve/main/model.safetensors",
+    );
+}
+
+impl StarCoder2ConfigResources {
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-3b>.
+    pub const STARCODER2_3B: (&'static str, &'static str) = (
+        "starcoder2-3b/config",
+        "https://huggingface.co/bigcode/starcoder2-3b/resolve/main/config.json",
+    );
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-7b>.
+    pub const STARCODER2_7B: (&'static str, &'static str) = (
+        "starcoder2-7b/config",
+        "https://huggingface.co/bigcode/starcoder2-7b/resolve/main/config.json",
+    );
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-15b>.
+    pub const STARCODER2_15B: (&'static str, &'static str) = (
+        "starcoder2-15b/config",
+        "https://huggingface.co/bigcode/starcoder2-15b/resolve/main/config.json",
+    );
+}
+
+impl StarCoder2VocabResources {
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-3b>.
+    pub const STARCODER2_3B: (&'static str, &'static str) = (
+        "starcoder2-3b/vocab",
+        "https://huggingface.co/bigcode/starcoder2-3b/resolve/main/vocab.json",
+    );
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-7b>.
+    pub const STARCODER2_7B: (&'static str, &'static str) = (
+        "starcoder2-7b/vocab",
+        "https://huggingface.co/bigcode/starcoder2-7b/resolve/main/vocab.json",
+    );
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-15b>.
+    pub const STARCODER2_15B: (&'static str, &'static str) = (
+        "starcoder2-15b/vocab",
+        "https://huggingface.co/bigcode/starcoder2-15b/resolve/main/vocab.json",
+    );
+}
+
+impl StarCoder2MergesResources {
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-3b>.
+    pub const STARCODER2_3B: (&'static str, &'static str) = (
+        "starcoder2-3b/merges",
+        "https://huggingface.co/bigcode/starcoder2-3b/resolve/main/merges.txt",
+    );
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-7b>.
+    pub const STARCODER2_7B: (&'static str, &'static str) = (
+        "starcoder2-7b/merges",
+        "https://huggingface.co/bigcode/starcoder2-7b/resolve/main/merges.txt",
+    );
+    /// Shared under BigCode OpenRAIL-M license by the BigCode project at <https://huggingface.co/bigcode/starcoder2-15b>.
+    pub const STARCODER2_15B: (&'static str, &'static str) = (
+        "starcoder2-15b/merges",
+        "https://huggingface.co/bigcode/starcoder2-15b/resolve/main/merges.txt",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # StarCoder2 model configuration
+/// Defines the StarCoder2 model architecture (e.g. number of layers, hidden layer size, number of key/value heads...).
+pub struct StarCoder2Config {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub num_key_value_heads: Option<i64>,
+    pub hidden_act: Activation,
+    pub max_position_embeddings: i64,
+    pub initializer_range: Option<f64>,
+    pub norm_epsilon: Option<f64>,
+    pub rope_theta: Option<f64>,
+    pub sliding_window: Option<i64>,
+    pub use_bias: Option<bool>,
+    pub attention_dropout: Option<f64>,
+    pub residual_dropout: Option<f64>,
+    pub embedding_dropout: Option<f64>,
+    pub tie_word_embeddings: Option<bool>,
+    pub use_cache: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    pub forced_bos_token_id: Option<i64>,
+    pub forced_eos_token_id: Option<i64>,
+}
+
+impl Config for StarCoder2Config {}
+
+impl Default for StarCoder2Config {
+    fn default() -> Self {
+        StarCoder2Config {
+            vocab_size: 49152,
+            hidden_size: 3072,
+            intermediate_size: 12288,
+            num_hidden_layers: 30,
+            num_attention_heads: 24,
+            num_key_value_heads: Some(2),
+            hidden_act: Activation::gelu_new,
+            max_position_embeddings: 16384,
+            initializer_range: Some(0.018),
+            norm_epsilon: Some(1e-5),
+            rope_theta: Some(999999.4420358813),
+            sliding_window: Some(4096),
+            use_bias: Some(true),
+            attention_dropout: Some(0.1),
+            residual_dropout: Some(0.1),
+            embedding_dropout: Some(0.1),
+            tie_word_embeddings: None,
+            use_cache: None,
+            output_attentions: None,
+            output_hidden_states: None,
+            bos_token_id: Some(0),
+            eos_token_id: Some(0),
+            decoder_start_token_id: None,
+            forced_bos_token_id: None,
+            forced_eos_token_id: None,
+        }
+    }
+}
+
+/// # StarCoder2 Base model
+/// Base architecture for StarCoder2 model. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `embed_tokens`: `token` embeddings
+/// - `layers`: Decoder made of a vector of layers. Each layer is made of a grouped-query attention layer with rotary position embeddings,
+///   layer-normalization layers, and a MLP made of linear layers. Layers attend to a sliding window of the previous tokens if `sliding_window` is set.
+/// - `norm`: Final layer normalization
+pub struct StarCoder2Model {
+    embed_tokens: nn::Embedding,
+    layers: Vec<StarCoder2DecoderLayer>,
+    norm: nn::LayerNorm,
+    rotary_embedding: StarCoder2RotaryEmbedding,
+    dropout: Dropout,
+    sliding_window: Option<i64>,
+    use_cache: bool,
+    output_hidden_states: bool,
+    output_attentions: bool,
+}
+
+impl StarCoder2Model {
+    /// Build a new `StarCoder2Model`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the StarCoder2 model
+    /// * `config` - `StarCoder2Config` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::starcoder2::{StarCoder2Config, StarCoder2Model};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = StarCoder2Config::from_file(config_path);
+    /// let starcoder2: StarCoder2Model = StarCoder2Model::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &StarCoder2Config) -> StarCoder2Model
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "model";
+
+        let embed_tokens = embedding(
+            &p / "embed_tokens",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let mut layers: Vec<StarCoder2DecoderLayer> = vec![];
+        let layers_path = &p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(StarCoder2DecoderLayer::new(
+                &layers_path / layer_index,
+                config,
+            ));
+        }
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.norm_epsilon.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let norm = nn::layer_norm(&p / "norm", vec![config.hidden_size], layer_norm_config);
+
+        let rotary_embedding = StarCoder2RotaryEmbedding::new(
+            config.hidden_size / config.num_attention_heads,
+            config.rope_theta.unwrap_or(10000.0),
+            p.device(),
+        );
+        let dropout = Dropout::new(config.embedding_dropout.unwrap_or(0.0));
+
+        StarCoder2Model {
+            embed_tokens,
+            layers,
+            norm,
+            rotary_embedding,
+            dropout,
+            sliding_window: config.sliding_window,
+            use_cache: config.use_cache.unwrap_or(true),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+            output_attentions: config.output_attentions.unwrap_or(false),
+        }
+    }
+
+    /// Quantize the weights of the linear layers of the decoder layers to int4 in place.
+    /// The embeddings and layer normalizations are kept in their original precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.quantize_int4(config)?;
+        }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the transformer blocks across devices in place (tensor parallelism).
+    /// The embeddings and layer normalizations remain on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        for layer in self.layers.iter_mut() {
+            layer.tensor_parallelize(config)?;
+        }
+        Ok(())
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - Optional vector of length *num_hidden_layers* containing the past keys and values of each layer of shape (*batch size*, *number of key/value heads*, *past_sequence_length*, *hidden size per head*). When provided, these are concatenated with the current input keys and values.
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `StarCoder2ModelOutput` containing:
+    ///   - `output` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `cache` - `Option<Vec<Option<LayerState>>>` of length *num_hidden_layers* containing the past keys and values of each layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *number of heads*, *sequence_length*, *past_sequence_length + sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::starcoder2::{StarCoder2Config, StarCoder2Model};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = StarCoder2Config::from_file(config_path);
+    /// # let starcoder2_model: StarCoder2Model = StarCoder2Model::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     starcoder2_model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             None,
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<StarCoder2ModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_tokens)?;
+
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+
+        let (layer_past, past_length) = match layer_past {
+            Some(value) => {
+                if value.len() != self.layers.len() {
+                    return Err(RustBertError::ValueError(format!(
+                        "Past activations vector length ({}) must be equal to the number of layers ({})",
+                        value.len(),
+                        self.layers.len()
+                    )));
+                } else {
+                    let past_length = value
+                        .iter()
+                        .flatten()
+                        .next()
+                        .map(|layer_state| layer_state.prev_key.size()[2])
+                        .unwrap_or(0);
+                    (value, past_length)
+                }
+            }
+            None => {
+                let mut out = Vec::with_capacity(self.layers.len());
+                out.resize_with(self.layers.len(), || None);
+                (out, 0)
+            }
+        };
+        let key_length = past_length + sequence_length;
+
+        let position_ids = match position_ids {
+            Some(value) => value.shallow_clone(),
+            None => Tensor::arange_start(past_length, key_length, (Kind::Int64, device))
+                .unsqueeze(0)
+                .expand([batch_size, sequence_length], true),
+        };
+
+        let mut masked_positions =
+            build_causal_mask(sequence_length, key_length, self.sliding_window, device).view([
+                1,
+                1,
+                sequence_length,
+                key_length,
+            ]);
+        if let Some(attention_mask) = attention_mask {
+            masked_positions =
+                masked_positions.logical_or(&attention_mask.view([batch_size, 1, 1, -1]).eq(0));
+        }
+        let kind = input_embeddings.kind();
+        let attention_mask = Tensor::zeros(masked_positions.size(), (kind, device))
+            .masked_fill(&masked_positions, get_min(kind)?);
+
+        let (cos, sin) = self.rotary_embedding.forward(&position_ids, kind);
+
+        let mut hidden_state = input_embeddings.apply_t(&self.dropout, train);
+
+        let mut all_presents: Option<Vec<Option<LayerState>>> = self.use_cache.then(Vec::new);
+        let mut all_hidden_states: Option<Vec<Tensor>> = self.output_hidden_states.then(Vec::new);
+        let mut all_attentions: Option<Vec<Tensor>> = self.output_attentions.then(Vec::new);
+
+        for (layer, past) in self.layers.iter().zip(layer_past) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let temp = layer.forward_t(
+                &hidden_state,
+                Some(&attention_mask),
+                (&cos, &sin),
+                past.as_ref(),
+                train,
+            );
+            hidden_state = temp.0;
+            if let Some(presents) = all_presents.borrow_mut() {
+                presents.push(temp.1);
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(temp.2.unwrap());
+            };
+        }
+
+        let output = hidden_state.apply(&self.norm);
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(output.copy());
+        };
+
+        Ok(StarCoder2ModelOutput {
+            output,
+            cache: all_presents,
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # StarCoder2 Language Modeling head
+/// StarCoder2 model with a decoding head (linear layer without bias). The weights of the linear layer are tied to the word
+/// embeddings unless `tie_word_embeddings` is set to false in the configuration.
+/// It is made of the following blocks:
+/// - `model`: Base StarCoder2Model
+/// - `lm_head`: Optional linear layer projecting the hidden states to the vocabulary (untied weights only)
+pub struct StarCoder2ForCausalLM {
+    model: StarCoder2Model,
+    lm_head: Option<QuantizableLinear>,
+}
+
+impl StarCoder2ForCausalLM {
+    /// Build a new `StarCoder2ForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the StarCoder2 model
+    /// * `config` - `StarCoder2Config` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::starcoder2::{StarCoder2Config, StarCoder2ForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = StarCoder2Config::from_file(config_path);
+    /// let starcoder2: StarCoder2ForCausalLM = StarCoder2ForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &StarCoder2Config) -> StarCoder2ForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = StarCoder2Model::new(p, config);
+        let lm_head = if config.tie_word_embeddings.unwrap_or(true) {
+            None
+        } else {
+            Some(
+                nn::linear(
+                    p / "lm_head",
+                    config.hidden_size,
+                    config.vocab_size,
+                    nn::LinearConfig {
+                        bias: false,
+                        ..Default::default()
+                    },
+                )
+                .into(),
+            )
+        };
+
+        StarCoder2ForCausalLM { model, lm_head }
+    }
+
+    /// Quantize the weights of the linear layers of the model to int4 in place. An untied language model head
+    /// is only quantized if `quantize_lm_head` is set in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)?;
+        if config.quantize_lm_head {
+            if let Some(lm_head) = self.lm_head.as_mut() {
+                lm_head.quantize_int4(config)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Split the attention and MLP layers of the model across devices in place (tensor parallelism).
+    /// The language model head remains on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `layer_past` - `Cache` containing the past keys and values of each layer (`Cache::StarCoder2Cache` or `Cache::None`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *past_sequence_length + sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `cache` - `Cache::StarCoder2Cache` containing the past keys and values of each layer
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::StarCoder2Cache(layer_past) => self.model.forward_t(
+                input_ids,
+                layer_past,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            Cache::None => self.model.forward_t(
+                input_ids,
+                None,
+                attention_mask,
+                position_ids,
+                input_embeds,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with StarCoder2 Model".into(),
+                ));
+            }
+        }?;
+
+        let lm_logits = match &self.lm_head {
+            Some(lm_head) => base_model_output.output.apply(lm_head),
+            None => base_model_output
+                .output
+                .linear::<Tensor>(&self.model.embed_tokens.ws, None),
+        };
+
+        Ok(LMModelOutput {
+            lm_logits,
+            cache: Cache::StarCoder2Cache(base_model_output.cache),
+            last_hidden_state: Some(base_model_output.output),
+        })
+    }
+}
+
+/// Container for the StarCoder2 model output.
+pub struct StarCoder2ModelOutput {
+    /// Hidden state of the last layer of the decoder
+    pub output: Tensor,
+    /// Cached attention layers keys and values if the model is used for generation
+    pub cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the StarCoder2 architecture
+pub struct StarCoder2Generator {
+    model: StarCoder2ForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl StarCoder2Generator {
+    /// Build a new `StarCoder2Generator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType};
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::{LocalResource, RemoteResource};
+    /// use rust_bert::starcoder2::{
+    ///     StarCoder2ConfigResources, StarCoder2Generator, StarCoder2MergesResources,
+    ///     StarCoder2VocabResources,
+    /// };
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_type: ModelType::StarCoder2,
+    ///     model_resource: ModelResource::Torch(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     })),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(
+    ///         StarCoder2ConfigResources::STARCODER2_3B,
+    ///     )),
+    ///     vocab_resource: Box::new(RemoteResource::from_pretrained(
+    ///         StarCoder2VocabResources::STARCODER2_3B,
+    ///     )),
+    ///     merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+    ///         StarCoder2MergesResources::STARCODER2_3B,
+    ///     ))),
+    ///     max_length: Some(128),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..Default::default()
+    /// };
+    /// let starcoder2_generator = StarCoder2Generator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<StarCoder2Generator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let merges_path = generate_config
+            .merges_resource
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "StarCoder2 expects a merges resources to be provided".to_string(),
+                )
+            })?
+            .get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::StarCoder2,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        mut tokenizer: TokenizerOption,
+    ) -> Result<StarCoder2Generator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate()?;
+        let mut var_store = nn::VarStore::new(device);
+
+        let config = StarCoder2Config::from_file(config_path);
+        let model = StarCoder2ForCausalLM::new(var_store.root(), &config);
+        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+
+        // Fill-in-the-middle markers are part of the vocabulary and should never be split by the tokenizer
+        tokenizer.add_tokens(&[FIM_PREFIX, FIM_SUFFIX, FIM_MIDDLE, FIM_PAD]);
+
+        let bos_token_id = tokenizer.get_bos_id().or(config.bos_token_id);
+        let eos_token_ids = tokenizer
+            .get_eos_id()
+            .or(config.eos_token_id)
+            .map(|id| vec![id]);
+        let pad_token_id = tokenizer.get_pad_id();
+        let max_position_embeddings = config.max_position_embeddings;
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = config.decoder_start_token_id;
+
+        Ok(StarCoder2Generator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+
+    /// Quantize the weights of the model to int4 in place (see `StarCoder2ForCausalLM::quantize_int4`).
+    /// The model should be moved to its target device and precision before quantization.
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+
+    /// Split the layers of the model across devices in place (see `StarCoder2ForCausalLM::tensor_parallelize`).
+    /// The model should be loaded on the first device of the configuration.
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+}
+
+impl PrivateLanguageGenerator for StarCoder2Generator {
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn _get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        &mut self.tokenizer
+    }
+    fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+    fn get_var_store_mut(&mut self) -> Result<&mut nn::VarStore, RustBertError> {
+        Ok(&mut self.var_store)
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> Option<i64> {
+        Some(self.max_position_embeddings)
+    }
+
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        self.model.forward_t(
+            input_ids,
+            layer_past,
+            attention_mask,
+            position_ids,
+            input_embeds,
+            train,
+        )
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        // Positions are counted from the first non-padding token of each (left-padded) prompt
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::StarCoder2Cache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::StarCoder2Cache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::StarCoder2Cache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::StarCoder2Cache(None),
+            },
+            _ => panic!("Cache type incompatible with StarCoder2"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::StarCoder2Cache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for StarCoder2 model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator for StarCoder2Generator {}
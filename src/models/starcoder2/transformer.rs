@@ -0,0 +1,171 @@
+// Copyright 2024 BigCode and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::quantization::{Int4QuantizationConfig, QuantizableLinear};
+use crate::common::tensor_parallel::{mlp_forward, TensorParallelConfig};
+use crate::starcoder2::attention::{LayerState, StarCoder2Attention};
+use crate::starcoder2::starcoder2_model::StarCoder2Config;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+pub struct StarCoder2MLP {
+    c_fc: QuantizableLinear,
+    c_proj: QuantizableLinear,
+    activation: TensorFunction,
+    dropout: Dropout,
+}
+
+impl StarCoder2MLP {
+    pub fn new<'p, P>(p: P, config: &StarCoder2Config) -> StarCoder2MLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: config.use_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let c_fc = nn::linear(
+            p / "c_fc",
+            config.hidden_size,
+            config.intermediate_size,
+            linear_config,
+        );
+        let c_proj = nn::linear(
+            p / "c_proj",
+            config.intermediate_size,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let activation = config.hidden_act.get_function();
+        let dropout = Dropout::new(config.residual_dropout.unwrap_or(0.0));
+
+        StarCoder2MLP {
+            c_fc: c_fc.into(),
+            c_proj: c_proj.into(),
+            activation,
+            dropout,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.c_fc.quantize_int4(config)?;
+        self.c_proj.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.c_fc.split_columns(&config.devices, 1)?;
+        self.c_proj.split_rows(&config.devices, 1)
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        mlp_forward(
+            &self.c_fc,
+            &self.c_proj,
+            self.activation.get_fn(),
+            hidden_states,
+        )
+        .apply_t(&self.dropout, train)
+    }
+}
+
+pub struct StarCoder2DecoderLayer {
+    input_layernorm: nn::LayerNorm,
+    self_attn: StarCoder2Attention,
+    post_attention_layernorm: nn::LayerNorm,
+    mlp: StarCoder2MLP,
+}
+
+impl StarCoder2DecoderLayer {
+    pub fn new<'p, P>(p: P, config: &StarCoder2Config) -> StarCoder2DecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.norm_epsilon.unwrap_or(1e-5),
+            ..Default::default()
+        };
+        let input_layernorm = nn::layer_norm(
+            p / "input_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let self_attn = StarCoder2Attention::new(p / "self_attn", config);
+        let post_attention_layernorm = nn::layer_norm(
+            p / "post_attention_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let mlp = StarCoder2MLP::new(p / "mlp", config);
+
+        StarCoder2DecoderLayer {
+            input_layernorm,
+            self_attn,
+            post_attention_layernorm,
+            mlp,
+        }
+    }
+
+    pub(crate) fn quantize_int4(
+        &mut self,
+        config: &Int4QuantizationConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attn.quantize_int4(config)?;
+        self.mlp.quantize_int4(config)
+    }
+
+    pub(crate) fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.self_attn.tensor_parallelize(config)?;
+        self.mlp.tensor_parallelize(config)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        rotary: (&Tensor, &Tensor),
+        layer_past: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<LayerState>, Option<Tensor>) {
+        let (attention_output, present, attention_weights) = self.self_attn.forward_t(
+            &hidden_states.apply(&self.input_layernorm),
+            attention_mask,
+            rotary,
+            layer_past,
+            train,
+        );
+        let hidden_states = hidden_states + attention_output;
+
+        let feed_forward_output = self
+            .mlp
+            .forward_t(&hidden_states.apply(&self.post_attention_layernorm), train);
+        let hidden_states = hidden_states + feed_forward_output;
+
+        (hidden_states, present, attention_weights)
+    }
+}
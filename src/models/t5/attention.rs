@@ -11,6 +11,8 @@
 // limitations under the License.
 
 use crate::common::dropout::Dropout;
+use crate::common::position_embeddings::relative_position_bucket;
+use crate::common::precision::{attention_output, attention_scores};
 use crate::t5::layer_norm::T5LayerNorm;
 use crate::t5::T5Config;
 use std::borrow::Borrow;
@@ -43,37 +45,6 @@ impl LayerState {
     }
 }
 
-pub fn get_relative_position_bucket(
-    relative_position: &Tensor,
-    bidirectional: bool,
-    num_buckets: i64,
-    max_distance: i64,
-) -> Tensor {
-    let n = -relative_position;
-    let mut num_buckets = num_buckets;
-    let mut ret = n.zeros_like();
-    let n = if bidirectional {
-        num_buckets /= 2;
-        ret += n.lt(0).to_kind(Kind::Int64) * num_buckets;
-        n.abs()
-    } else {
-        n.max_other(&n.zeros_like())
-    };
-
-    let max_exact = num_buckets / 2;
-    let is_small = n.lt(max_exact);
-
-    let value_if_large: Tensor = ((n.to_kind(Kind::Float) / max_exact as f64).log2()
-        / (max_distance as f64 / max_exact as f64).log2()
-        * (num_buckets - max_exact) as f64)
-        .to_kind(Kind::Int64)
-        + max_exact;
-
-    let value_if_large = value_if_large.min_other(&value_if_large.full_like(num_buckets - 1));
-    ret += n.where_self(&is_small, &value_if_large);
-    ret
-}
-
 #[derive(Debug)]
 pub struct T5Attention {
     is_decoder: bool,
@@ -223,7 +194,7 @@ impl T5Attention {
             None
         };
 
-        let mut scores = Tensor::einsum("bnqd,bnkd->bnqk", &[q, k], None::<i64>);
+        let mut scores = attention_scores(&q, &k.transpose(-1, -2));
 
         let calculated_position_bias = if position_bias.is_none() {
             let mut temp_value = if self.has_relative_attention_bias {
@@ -258,7 +229,7 @@ impl T5Attention {
             .softmax(-1, scores.kind())
             .apply_t(&self.dropout, train);
         let context = self
-            .unshape(attention_weights.matmul(&v), bs)
+            .unshape(attention_output(&attention_weights, &v), bs)
             .apply(&self.output);
 
         let attention_weights = if self.output_attentions {
@@ -281,7 +252,7 @@ impl T5Attention {
         let memory_position = Tensor::arange(k_len, (Kind::Int64, device)).unsqueeze(0);
         let relative_position = memory_position - context_position;
 
-        let rp_bucket = get_relative_position_bucket(
+        let rp_bucket = relative_position_bucket(
             &relative_position,
             self.is_bidirectional,
             self.relative_attention_num_buckets,
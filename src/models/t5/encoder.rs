@@ -17,7 +17,7 @@ use crate::t5::attention::{LayerState, T5LayerCrossAttention, T5LayerSelfAttenti
 use crate::t5::layer_norm::T5LayerNorm;
 use crate::t5::t5_model::FeedForwardProj;
 use crate::t5::T5Config;
-use crate::Activation::{gelu_new, relu};
+use crate::Activation::{gelu_new, relu, swish};
 use crate::RustBertError;
 use std::borrow::{Borrow, BorrowMut};
 use std::convert::TryFrom;
@@ -47,6 +47,7 @@ impl T5DenseActDense {
         let activation_function = match config.feed_forward_proj {
             None | Some(FeedForwardProj::Relu) => relu.get_function(),
             Some(FeedForwardProj::GatedGelu) => gelu_new.get_function(),
+            Some(FeedForwardProj::GatedSilu) => swish.get_function(),
         };
 
         T5DenseActDense {
@@ -89,6 +90,7 @@ impl T5DenseGatedActDense {
         let activation_function = match config.feed_forward_proj {
             None | Some(FeedForwardProj::Relu) => relu.get_function(),
             Some(FeedForwardProj::GatedGelu) => gelu_new.get_function(),
+            Some(FeedForwardProj::GatedSilu) => swish.get_function(),
         };
 
         T5DenseGatedActDense {
@@ -123,7 +125,7 @@ impl T5FeedForwardLayer {
             FeedForwardProj::Relu => {
                 T5FeedForwardLayer::T5DenseActDense(T5DenseActDense::new(p, config))
             }
-            FeedForwardProj::GatedGelu => {
+            FeedForwardProj::GatedGelu | FeedForwardProj::GatedSilu => {
                 T5FeedForwardLayer::T5DenseGatedActDense(T5DenseGatedActDense::new(p, config))
             }
         }
@@ -335,9 +337,14 @@ impl T5Stack {
         let p = p.borrow();
         let dropout = Dropout::new(config.dropout_rate);
 
+        let num_layers = if is_decoder {
+            config.num_decoder_layers.unwrap_or(config.num_layers)
+        } else {
+            config.num_layers
+        };
         let mut blocks: Vec<T5Block> = vec![];
         let p_layers = p / "block";
-        for layer_index in 0..config.num_layers {
+        for layer_index in 0..num_layers {
             blocks.push(T5Block::new(
                 &p_layers / layer_index,
                 config,
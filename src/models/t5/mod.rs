@@ -4,6 +4,10 @@
 //! The base model is implemented in the `t5_model::T5Model` struct. This model includes a language model head: `t5_model::T5ForConditionalGeneration`
 //! implementing the common `generation_utils::LanguageGenerator` trait shared between the models used for generation (see `pipelines` for more information).
 //!
+//! UL2 and Flan-UL2 checkpoints ([UL2: Unifying Language Learning Paradigms](https://arxiv.org/abs/2205.05131) Tay et al., 2022) share the T5 architecture
+//! (with gated feed-forward layers and untied word embeddings) and are loaded with the same modules. UL2 models expect the mode token of the mixture of denoisers
+//! matching the task to be prepended to the input (see `UL2Mode`).
+//!
 //! # Model set-up and pre-trained weights loading
 //!
 //! A full working example (summarization) is provided in `examples/summarization_t5`, run with `cargo run --example summarization_t5`.
@@ -54,12 +58,12 @@ mod layer_norm;
 mod t5_model;
 
 pub use attention::LayerState;
-pub(crate) use attention::{get_relative_position_bucket, T5Attention, T5LayerCrossAttention};
+pub(crate) use attention::{T5Attention, T5LayerCrossAttention};
 pub(crate) use encoder::{T5Block, T5BlockOutput, T5LayerFF, T5StackOutput};
 pub(crate) use layer_norm::T5LayerNorm;
-pub(crate) use t5_model::{FeedForwardProj, TaskSpecificParams};
+pub(crate) use t5_model::TaskSpecificParams;
 pub use t5_model::{
-    T5Config, T5ConfigResources, T5ForConditionalGeneration, T5ForSentenceEmbeddings, T5Generator,
-    T5Model, T5ModelOutput, T5ModelResources, T5Prefix, T5SourceLanguages, T5TargetLanguages,
-    T5VocabResources,
+    FeedForwardProj, T5Config, T5ConfigResources, T5ForConditionalGeneration,
+    T5ForSentenceEmbeddings, T5Generator, T5Model, T5ModelOutput, T5ModelResources, T5Prefix,
+    T5SourceLanguages, T5TargetLanguages, T5VocabResources, UL2Mode,
 };
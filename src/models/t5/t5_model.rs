@@ -78,6 +78,16 @@ impl T5ConfigResources {
         "sentence-t5-base/config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/config.json",
     );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/google-research/tree/master/ul2>.
+    pub const UL2: (&'static str, &'static str) = (
+        "ul2/config",
+        "https://huggingface.co/google/ul2/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/google-research/tree/master/ul2>.
+    pub const FLAN_UL2: (&'static str, &'static str) = (
+        "flan-ul2/config",
+        "https://huggingface.co/google/flan-ul2/resolve/main/config.json",
+    );
 }
 
 impl T5VocabResources {
@@ -96,6 +106,16 @@ impl T5VocabResources {
         "sentence-t5-base/spiece",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/spiece.model",
     );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/google-research/tree/master/ul2>.
+    pub const UL2: (&'static str, &'static str) = (
+        "ul2/spiece",
+        "https://huggingface.co/google/ul2/resolve/main/spiece.model",
+    );
+    /// Shared under Apache 2.0 license by the Google team at <https://github.com/google-research/google-research/tree/master/ul2>.
+    pub const FLAN_UL2: (&'static str, &'static str) = (
+        "flan-ul2/spiece",
+        "https://huggingface.co/google/flan-ul2/resolve/main/spiece.model",
+    );
 }
 
 const T5LANGUAGES: [Language; 3] = [Language::English, Language::French, Language::German];
@@ -110,6 +130,35 @@ impl T5Prefix {
     pub const ENGLISH2GERMAN: Option<&'static str> = Some("translate English to German:");
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// # UL2 mode switching tokens
+/// UL2 checkpoints are pre-trained with a mixture of denoisers, each associated with a paradigm token prepended to the input.
+/// The mode token matching the downstream task should be prepended to the inputs of UL2 models (instruction-tuned Flan-UL2 checkpoints do not require it).
+pub enum UL2Mode {
+    /// Regular span corruption denoising, suited for language understanding tasks (`[NLU]`)
+    NLU,
+    /// Extreme span corruption denoising, suited for open-ended generation (`[NLG]`)
+    NLG,
+    /// Sequential (prefix language modeling) denoising, suited for sequence-to-sequence tasks such as summarization (`[S2S]`)
+    S2S,
+}
+
+impl UL2Mode {
+    /// Returns the mode token for this UL2 mode
+    pub fn token(&self) -> &'static str {
+        match self {
+            UL2Mode::NLU => "[NLU]",
+            UL2Mode::NLG => "[NLG]",
+            UL2Mode::S2S => "[S2S]",
+        }
+    }
+
+    /// Prepends the mode token to an input text
+    pub fn apply(&self, text: &str) -> String {
+        format!("{} {}", self.token(), text)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Copy)]
 #[serde(rename_all = "kebab-case")]
 /// # Options for T5 Feed-forward projection layer
@@ -118,6 +167,8 @@ pub enum FeedForwardProj {
     Relu,
     /// Gated geLU
     GatedGelu,
+    /// Gated SiLU (used by UL2)
+    GatedSilu,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -138,6 +189,7 @@ pub struct T5Config {
     pub layer_norm_epsilon: f64,
     pub num_heads: i64,
     pub num_layers: i64,
+    pub num_decoder_layers: Option<i64>,
     pub output_past: Option<bool>,
     pub pad_token_id: Option<i64>,
     pub relative_attention_num_buckets: i64,
@@ -218,6 +270,7 @@ impl Default for T5Config {
             layer_norm_epsilon: 1e-6,
             num_heads: 8,
             num_layers: 6,
+            num_decoder_layers: None,
             output_past: None,
             pad_token_id: Some(0),
             relative_attention_num_buckets: 32,
@@ -758,7 +811,7 @@ impl T5Generator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = T5Config::from_file(config_path);
@@ -875,6 +928,7 @@ impl PrivateLanguageGenerator for T5Generator {
         Ok(LMModelOutput {
             lm_logits: base_model_output.decoder_output,
             cache: Cache::T5Cache(base_model_output.next_cache),
+            last_hidden_state: None,
         })
     }
     fn encode(&self, input_ids: &Tensor, attention_mask: Option<&Tensor>) -> Option<Tensor> {
@@ -0,0 +1,197 @@
+// Copyright 2020 Google Research and The HuggingFace Inc. team.
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::tapas::tapas_model::TapasConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::nn::{embedding, EmbeddingConfig};
+use tch::{nn, Kind, Tensor};
+
+/// Number of token types of a TAPAS input: segment, column, row, previous answer, column rank, inverse column rank
+/// and numeric relation
+pub const NUM_TAPAS_TOKEN_TYPES: usize = 7;
+
+#[derive(Debug)]
+/// # Embeddings implementation for TAPAS model
+/// Sum of the word, position and of the 7 token type embeddings (segment, column, row, previous answer, column rank,
+/// inverse column rank and numeric relation).
+pub struct TapasEmbeddings {
+    word_embeddings: nn::Embedding,
+    position_embeddings: nn::Embedding,
+    token_type_embeddings: Vec<nn::Embedding>,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+    reset_position_index_per_cell: bool,
+    column_vocab_size: i64,
+    max_position_embeddings: i64,
+}
+
+impl TapasEmbeddings {
+    pub fn new<'p, P>(p: P, config: &TapasConfig) -> TapasEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embedding_config = EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(0),
+            ..Default::default()
+        };
+        let word_embeddings: nn::Embedding = embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            embedding_config,
+        );
+        let position_embeddings: nn::Embedding = embedding(
+            p / "position_embeddings",
+            config.max_position_embeddings,
+            config.hidden_size,
+            Default::default(),
+        );
+        let token_type_embeddings = config
+            .type_vocab_sizes
+            .iter()
+            .enumerate()
+            .map(|(index, type_vocab_size)| {
+                embedding(
+                    p / format!("token_type_embeddings_{index}"),
+                    *type_vocab_size,
+                    config.hidden_size,
+                    Default::default(),
+                )
+            })
+            .collect::<Vec<nn::Embedding>>();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm: nn::LayerNorm =
+            nn::layer_norm(p / "LayerNorm", vec![config.hidden_size], layer_norm_config);
+        let dropout: Dropout = Dropout::new(config.hidden_dropout_prob);
+        TapasEmbeddings {
+            word_embeddings,
+            position_embeddings,
+            token_type_embeddings,
+            layer_norm,
+            dropout,
+            reset_position_index_per_cell: config.reset_position_index_per_cell,
+            column_vocab_size: config.type_vocab_sizes.get(1).copied().unwrap_or(256),
+            max_position_embeddings: config.max_position_embeddings,
+        }
+    }
+
+    /// Forward pass through the embeddings
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `token_type_ids` - Optional token types of shape (*batch size*, *sequence_length*, *7*). If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0 (from the start of each cell if `reset_position_index_per_cell` is set).
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let calc_token_type_ids = if token_type_ids.is_none() {
+            Some(Tensor::zeros(
+                [input_shape[0], input_shape[1], NUM_TAPAS_TOKEN_TYPES as i64],
+                (Kind::Int64, device),
+            ))
+        } else {
+            None
+        };
+        let token_type_ids =
+            token_type_ids.unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap());
+        if token_type_ids.size().last() != Some(&(self.token_type_embeddings.len() as i64)) {
+            return Err(RustBertError::ValueError(format!(
+                "TAPAS token type ids should have {} types on their last dimension, got shape {:?}",
+                self.token_type_embeddings.len(),
+                token_type_ids.size()
+            )));
+        }
+
+        let calc_position_ids = match (position_ids, self.reset_position_index_per_cell) {
+            (Some(_), _) => None,
+            (None, true) => Some(cell_position_ids(
+                token_type_ids,
+                self.column_vocab_size,
+                self.max_position_embeddings,
+            )),
+            (None, false) => Some(
+                Tensor::arange(input_shape[1], (Kind::Int64, device))
+                    .unsqueeze(0)
+                    .expand(&input_shape, true),
+            ),
+        };
+        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
+
+        let mut embeddings = input_embeddings + position_ids.apply(&self.position_embeddings);
+        for (index, token_type_embeddings) in self.token_type_embeddings.iter().enumerate() {
+            embeddings += token_type_ids
+                .select(-1, index as i64)
+                .apply(token_type_embeddings);
+        }
+        Ok(embeddings
+            .apply(&self.layer_norm)
+            .apply_t(&self.dropout, train))
+    }
+}
+
+/// Position ids restarting at 0 at the first token of every cell (identified by its column and row token types),
+/// clamped to the maximum number of positions. The question tokens form a single segment starting at the first
+/// position.
+///
+/// # Arguments
+///
+/// * `token_type_ids` - Token types of shape (*batch size*, *sequence_length*, *7*)
+/// * `column_vocab_size` - Number of column ids (size of the column token type vocabulary)
+/// * `max_position_embeddings` - Maximum number of positions
+///
+/// # Returns
+///
+/// * `Tensor` of shape (*batch size*, *sequence_length*)
+pub fn cell_position_ids(
+    token_type_ids: &Tensor,
+    column_vocab_size: i64,
+    max_position_embeddings: i64,
+) -> Tensor {
+    let (batch_size, sequence_length) = (token_type_ids.size()[0], token_type_ids.size()[1]);
+    let device = token_type_ids.device();
+    let cell_index =
+        token_type_ids.select(-1, 2) * column_vocab_size + token_type_ids.select(-1, 1);
+    let positions = Tensor::arange(sequence_length, (Kind::Int64, device))
+        .unsqueeze(0)
+        .expand([batch_size, sequence_length], true);
+    let num_cells = cell_index.max().int64_value(&[]) + 1;
+    let first_positions = Tensor::full(
+        [batch_size, num_cells],
+        sequence_length,
+        (Kind::Int64, device),
+    )
+    .scatter_reduce(1, &cell_index, &positions, "amin", true);
+    (&positions - first_positions.gather(1, &cell_index, false))
+        .clamp_max(max_position_embeddings - 1)
+}
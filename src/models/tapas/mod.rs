@@ -0,0 +1,59 @@
+//! # TAPAS (Herzig et al.)
+//!
+//! Implementation of the TAPAS model ([TAPAS: Weakly Supervised Table Parsing via Pre-training](https://arxiv.org/abs/2004.02349) Herzig, Nowak, Müller, Piccinno, Eisenschlos, 2020).
+//! TAPAS answers questions over tables by encoding the question followed by the flattened table with a BERT encoder.
+//! The structure of the table is given by 7 token types (segment, column, row, previous answer, column rank, inverse column rank and numeric relation),
+//! of shape (*batch size*, *sequence_length*, *7*). The model selects the answer cells and optionally predicts an operator aggregating them (e.g. `SUM`, `AVERAGE` or `COUNT`).
+//! The base model is implemented in the `tapas_model::TapasModel` struct. The question answering head is implemented in `tapas_model::TapasForQuestionAnswering`.
+//! The `pipelines::table_question_answering::TableQuestionAnsweringModel` pipeline handles the encoding of the tables and the decoding of the answers.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). The pretrained safetensors checkpoints are loaded directly without conversion. Alternatively, the Python utility scripts convert the `.bin` weights to the `.ot` format.
+//! - `BertTokenizer` using a `vocab.txt` vocabulary
+//! Pretrained models are available and can be downloaded using RemoteResources.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device};
+//! # use std::path::PathBuf;
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::tapas::{TapasConfig, TapasForQuestionAnswering};
+//! use rust_bert::Config;
+//! use rust_tokenizers::tokenizer::BertTokenizer;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let vocab_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/vocab.txt"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let vocab_path = vocab_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let tokenizer: BertTokenizer =
+//!     BertTokenizer::from_file(vocab_path.to_str().unwrap(), true, true)?;
+//! let config = TapasConfig::from_file(config_path);
+//! let tapas_model = TapasForQuestionAnswering::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! # Ok(())
+//! # }
+//! ```
+
+mod embeddings;
+mod tapas_model;
+
+pub use embeddings::{cell_position_ids, TapasEmbeddings, NUM_TAPAS_TOKEN_TYPES};
+pub use tapas_model::{
+    TapasConfig, TapasConfigResources, TapasForQuestionAnswering, TapasModel, TapasModelOutput,
+    TapasModelResources, TapasQuestionAnsweringOutput, TapasVocabResources,
+};
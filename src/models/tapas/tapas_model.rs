@@ -0,0 +1,492 @@
+// Copyright 2020 Google Research and The HuggingFace Inc. team.
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bert::{BertConfig, BertEncoder, BertPooler};
+use crate::common::activations::Activation;
+use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::tapas::embeddings::TapasEmbeddings;
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use tch::nn::Init;
+use tch::{nn, Kind, Tensor};
+
+/// # TAPAS Pretrained model weight files
+pub struct TapasModelResources;
+
+/// # TAPAS Pretrained model config files
+pub struct TapasConfigResources;
+
+/// # TAPAS Pretrained model vocab files
+pub struct TapasVocabResources;
+
+impl TapasModelResources {
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/tapas-base-finetuned-wtq>.
+    pub const TAPAS_BASE_WTQ: (&'static str, &'static str) = (
+        "tapas-base-finetuned-wtq/model",
+        "https://huggingface.co/google/tapas-base-finetuned-wtq/resolve/main/model.safetensors",
+    );
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/tapas-base-finetuned-sqa>.
+    pub const TAPAS_BASE_SQA: (&'static str, &'static str) = (
+        "tapas-base-finetuned-sqa/model",
+        "https://huggingface.co/google/tapas-base-finetuned-sqa/resolve/main/model.safetensors",
+    );
+}
+
+impl TapasConfigResources {
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/tapas-base-finetuned-wtq>.
+    pub const TAPAS_BASE_WTQ: (&'static str, &'static str) = (
+        "tapas-base-finetuned-wtq/config",
+        "https://huggingface.co/google/tapas-base-finetuned-wtq/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/tapas-base-finetuned-sqa>.
+    pub const TAPAS_BASE_SQA: (&'static str, &'static str) = (
+        "tapas-base-finetuned-sqa/config",
+        "https://huggingface.co/google/tapas-base-finetuned-sqa/resolve/main/config.json",
+    );
+}
+
+impl TapasVocabResources {
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/tapas-base-finetuned-wtq>.
+    pub const TAPAS_BASE_WTQ: (&'static str, &'static str) = (
+        "tapas-base-finetuned-wtq/vocab",
+        "https://huggingface.co/google/tapas-base-finetuned-wtq/resolve/main/vocab.txt",
+    );
+    /// Shared under Apache 2.0 license by Google at <https://huggingface.co/google/tapas-base-finetuned-sqa>.
+    pub const TAPAS_BASE_SQA: (&'static str, &'static str) = (
+        "tapas-base-finetuned-sqa/vocab",
+        "https://huggingface.co/google/tapas-base-finetuned-sqa/resolve/main/vocab.txt",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # TAPAS model configuration
+/// Defines the TAPAS model architecture (e.g. number of layers, hidden layer size, token types, aggregation operators...)
+pub struct TapasConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub intermediate_size: i64,
+    pub hidden_act: Activation,
+    pub hidden_dropout_prob: f64,
+    pub attention_probs_dropout_prob: f64,
+    pub max_position_embeddings: i64,
+    /// Vocabulary sizes of the 7 token types (segment, column, row, previous answer, column rank, inverse column rank
+    /// and numeric relation)
+    pub type_vocab_sizes: Vec<i64>,
+    pub initializer_range: f64,
+    pub layer_norm_eps: Option<f64>,
+    pub pad_token_id: Option<i64>,
+    /// Restart the position index at every cell of the table
+    pub reset_position_index_per_cell: bool,
+    /// Number of aggregation operators predicted (0 if the model does not predict aggregations)
+    pub num_aggregation_labels: i64,
+    /// Names of the aggregation operators (e.g. `NONE`, `SUM`, `AVERAGE`, `COUNT`)
+    pub aggregation_labels: Option<HashMap<i64, String>>,
+    /// Index of the aggregation operator selecting cells without aggregating them
+    pub no_aggregation_label_index: Option<i64>,
+    /// Temperature dividing the cell selection logits
+    pub temperature: f64,
+    pub select_one_column: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+}
+
+impl Config for TapasConfig {}
+
+impl Default for TapasConfig {
+    fn default() -> Self {
+        TapasConfig {
+            vocab_size: 30522,
+            hidden_size: 768,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            intermediate_size: 3072,
+            hidden_act: Activation::gelu,
+            hidden_dropout_prob: 0.1,
+            attention_probs_dropout_prob: 0.1,
+            max_position_embeddings: 1024,
+            type_vocab_sizes: vec![3, 256, 256, 2, 256, 256, 10],
+            initializer_range: 0.02,
+            layer_norm_eps: Some(1e-12),
+            pad_token_id: Some(0),
+            reset_position_index_per_cell: true,
+            num_aggregation_labels: 0,
+            aggregation_labels: None,
+            no_aggregation_label_index: None,
+            temperature: 1.0,
+            select_one_column: Some(true),
+            output_attentions: None,
+            output_hidden_states: None,
+            id2label: None,
+            label2id: None,
+        }
+    }
+}
+
+impl From<&TapasConfig> for BertConfig {
+    fn from(config: &TapasConfig) -> Self {
+        BertConfig {
+            hidden_act: config.hidden_act,
+            attention_probs_dropout_prob: config.attention_probs_dropout_prob,
+            hidden_dropout_prob: config.hidden_dropout_prob,
+            hidden_size: config.hidden_size,
+            initializer_range: config.initializer_range as f32,
+            intermediate_size: config.intermediate_size,
+            max_position_embeddings: config.max_position_embeddings,
+            num_attention_heads: config.num_attention_heads,
+            num_hidden_layers: config.num_hidden_layers,
+            type_vocab_size: config.type_vocab_sizes.first().copied().unwrap_or(3),
+            vocab_size: config.vocab_size,
+            output_attentions: config.output_attentions,
+            output_hidden_states: config.output_hidden_states,
+            is_decoder: None,
+            id2label: config.id2label.clone(),
+            label2id: config.label2id.clone(),
+        }
+    }
+}
+
+/// # TAPAS Base model
+/// Base architecture for TAPAS models, a BERT encoder for questions over flattened tables
+/// ([TAPAS: Weakly Supervised Table Parsing via Pre-training](https://arxiv.org/abs/2004.02349)).
+/// It is made of the following blocks:
+/// - `embeddings`: `token`, `position` and 7 token type embeddings encoding the structure of the table (column, row, numeric rank...)
+/// - `encoder`: BERT encoder (transformer) made of a vector of layers
+/// - `pooler`: Optional linear layer applied to the first element of the sequence
+pub struct TapasModel {
+    embeddings: TapasEmbeddings,
+    encoder: BertEncoder,
+    pooler: Option<BertPooler>,
+}
+
+impl TapasModel {
+    /// Build a new `TapasModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the TAPAS model
+    /// * `config` - `TapasConfig` object defining the model architecture
+    /// * `add_pooling_layer` - boolean flag indicating if a pooling layer should be added after the encoder
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::tapas::{TapasConfig, TapasModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = TapasConfig::from_file(config_path);
+    /// let tapas = TapasModel::new(&p.root() / "tapas", &config, true);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &TapasConfig, add_pooling_layer: bool) -> TapasModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = TapasEmbeddings::new(p / "embeddings", config);
+        let bert_config = BertConfig::from(config);
+        let encoder = BertEncoder::new(p / "encoder", &bert_config);
+        let pooler = if add_pooling_layer {
+            Some(BertPooler::new(p / "pooler", &bert_config))
+        } else {
+            None
+        };
+        TapasModel {
+            embeddings,
+            encoder,
+            pooler,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional token types of shape (*batch size*, *sequence_length*, *7*): segment (0 for the question, 1 for the table), column (1-based, 0 outside of the table), row (1-based, 0 for the question and header), previous answer, column rank, inverse column rank and numeric relation. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0 (from the start of each cell if `reset_position_index_per_cell` is set).
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `TapasModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `pooled_output` - Optional `Tensor` of shape (*batch size*, *hidden_size*) if the model has a pooling layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::tapas::{TapasConfig, TapasModel};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = TapasConfig::from_file(config_path);
+    /// # let tapas_model = TapasModel::new(&vs.root(), &config, true);
+    /// let (batch_size, sequence_length) = (2, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let token_type_ids = Tensor::zeros(&[batch_size, sequence_length, 7], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     tapas_model
+    ///         .forward_t(Some(&input_tensor), None, Some(&token_type_ids), None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<TapasModelOutput, RustBertError> {
+        let (input_shape, device) =
+            get_shape_and_device_from_ids_embeddings_pair(input_ids, input_embeds)?;
+        let calc_mask = if mask.is_none() {
+            Some(Tensor::ones(input_shape, (Kind::Int64, device)))
+        } else {
+            None
+        };
+        let mask = mask.unwrap_or_else(|| calc_mask.as_ref().unwrap());
+        if mask.dim() != 2 {
+            return Err(RustBertError::ValueError(
+                "Invalid attention mask dimension, must be 2".into(),
+            ));
+        }
+
+        let embedding_output = self.embeddings.forward_t(
+            input_ids,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+        let extended_attention_mask = mask.unsqueeze(1).unsqueeze(1);
+        let extended_attention_mask: Tensor =
+            ((extended_attention_mask.ones_like() - extended_attention_mask) * -10000.0)
+                .to_kind(embedding_output.kind());
+
+        let encoder_output = self.encoder.forward_t(
+            &embedding_output,
+            Some(&extended_attention_mask),
+            None,
+            None,
+            train,
+        );
+        let pooled_output = self
+            .pooler
+            .as_ref()
+            .map(|pooler| pooler.forward(&encoder_output.hidden_state));
+
+        Ok(TapasModelOutput {
+            hidden_state: encoder_output.hidden_state,
+            pooled_output,
+            all_hidden_states: encoder_output.all_hidden_states,
+            all_attentions: encoder_output.all_attentions,
+        })
+    }
+}
+
+/// # TAPAS for table question answering
+/// TAPAS model with a cell selection head and an optional aggregation operator classifier.
+/// It is made of the following blocks:
+/// - `tapas`: Base TapasModel
+/// - `output_weights` and `output_bias`: projection of the hidden states to the cell selection logits of each token
+/// - `aggregation_classifier`: Optional linear layer predicting the aggregation operator from the pooled output
+pub struct TapasForQuestionAnswering {
+    tapas: TapasModel,
+    output_weights: Tensor,
+    output_bias: Tensor,
+    aggregation_classifier: Option<nn::Linear>,
+    temperature: f64,
+}
+
+impl TapasForQuestionAnswering {
+    /// Build a new `TapasForQuestionAnswering`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the TAPAS model
+    /// * `config` - `TapasConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::tapas::{TapasConfig, TapasForQuestionAnswering};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = TapasConfig::from_file(config_path);
+    /// let tapas = TapasForQuestionAnswering::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &TapasConfig) -> TapasForQuestionAnswering
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let tapas = TapasModel::new(p / "tapas", config, true);
+        let output_weights = p.var(
+            "output_weights",
+            &[config.hidden_size],
+            Init::Randn {
+                mean: 0.0,
+                stdev: config.initializer_range,
+            },
+        );
+        let output_bias = p.var("output_bias", &[], Init::Const(0.0));
+        let aggregation_classifier = if config.num_aggregation_labels > 0 {
+            Some(nn::linear(
+                p / "aggregation_classifier",
+                config.hidden_size,
+                config.num_aggregation_labels,
+                Default::default(),
+            ))
+        } else {
+            None
+        };
+
+        TapasForQuestionAnswering {
+            tapas,
+            output_weights,
+            output_bias,
+            aggregation_classifier,
+            temperature: config.temperature,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional token types of shape (*batch size*, *sequence_length*, *7*) (see `TapasModel::forward_t`). If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0 (from the start of each cell if `reset_position_index_per_cell` is set).
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `TapasQuestionAnsweringOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the cell selection logits of each token
+    ///   - `aggregation_logits` - Optional `Tensor` of shape (*batch size*, *num_aggregation_labels*) if the model predicts aggregation operators
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::tapas::{TapasConfig, TapasForQuestionAnswering};
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = TapasConfig::from_file(config_path);
+    /// # let tapas_model = TapasForQuestionAnswering::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (2, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Kind::Int64, device));
+    /// let token_type_ids = Tensor::zeros(&[batch_size, sequence_length, 7], (Kind::Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     tapas_model
+    ///         .forward_t(Some(&input_tensor), None, Some(&token_type_ids), None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<TapasQuestionAnsweringOutput, RustBertError> {
+        let base_model_output = self.tapas.forward_t(
+            input_ids,
+            mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+
+        let logits = (base_model_output.hidden_state.matmul(&self.output_weights)
+            + &self.output_bias)
+            / self.temperature;
+        let aggregation_logits = match (
+            &self.aggregation_classifier,
+            &base_model_output.pooled_output,
+        ) {
+            (Some(aggregation_classifier), Some(pooled_output)) => {
+                Some(pooled_output.apply(aggregation_classifier))
+            }
+            _ => None,
+        };
+
+        Ok(TapasQuestionAnsweringOutput {
+            logits,
+            aggregation_logits,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+/// Container for the TAPAS model output.
+pub struct TapasModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Pooled output (hidden state of the first token after the pooling layer)
+    pub pooled_output: Option<Tensor>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the TAPAS question answering model output.
+pub struct TapasQuestionAnsweringOutput {
+    /// Cell selection logits of each token
+    pub logits: Tensor,
+    /// Logits of the aggregation operators (if predicted by the model)
+    pub aggregation_logits: Option<Tensor>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
@@ -0,0 +1,187 @@
+// Copyright 2022 The OpenAI Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audio pre-processing for the Whisper models: conversion of PCM samples, resampling and log-mel spectrograms.
+
+use std::f64::consts::PI;
+use tch::{Device, Kind, Tensor};
+
+/// Sampling rate of the audio expected by the Whisper models (Hz)
+pub const SAMPLE_RATE: usize = 16000;
+/// Size of the Fourier transform windows
+pub const N_FFT: usize = 400;
+/// Number of samples between two consecutive spectrogram frames
+pub const HOP_LENGTH: usize = 160;
+/// Duration of the audio chunks processed by the model (seconds)
+pub const CHUNK_LENGTH: usize = 30;
+/// Number of samples of an audio chunk
+pub const N_SAMPLES: usize = CHUNK_LENGTH * SAMPLE_RATE;
+/// Number of spectrogram frames of an audio chunk
+pub const N_FRAMES: usize = N_SAMPLES / HOP_LENGTH;
+
+/// Converts signed 16-bit PCM samples to `f32` samples in [-1, 1]
+pub fn pcm_to_f32(samples: &[i16]) -> Vec<f32> {
+    samples
+        .iter()
+        .map(|sample| *sample as f32 / 32768.0)
+        .collect()
+}
+
+/// Resamples mono audio to the 16kHz sampling rate of the model (linear interpolation). Audio should ideally be
+/// recorded or decoded at 16kHz: the linear interpolation does not filter the frequencies above 8kHz.
+///
+/// # Arguments
+///
+/// * `samples` - mono audio samples
+/// * `sample_rate` - sampling rate of the samples (Hz)
+pub fn resample(samples: &[f32], sample_rate: usize) -> Vec<f32> {
+    if (sample_rate == SAMPLE_RATE) | samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = sample_rate as f64 / SAMPLE_RATE as f64;
+    let output_length = ((samples.len() as f64) / ratio).floor() as usize;
+    (0..output_length)
+        .map(|index| {
+            let position = index as f64 * ratio;
+            let left = position.floor() as usize;
+            let right = (left + 1).min(samples.len() - 1);
+            let weight = (position - left as f64) as f32;
+            samples[left] * (1.0 - weight) + samples[right] * weight
+        })
+        .collect()
+}
+
+fn hz_to_mel(frequency: f64) -> f64 {
+    // Slaney mel scale: linear below 1kHz, logarithmic above
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz * 3.0 / 200.0;
+    let log_step = 6.4f64.ln() / 27.0;
+    if frequency >= min_log_hz {
+        min_log_mel + (frequency / min_log_hz).ln() / log_step
+    } else {
+        frequency * 3.0 / 200.0
+    }
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz * 3.0 / 200.0;
+    let log_step = 6.4f64.ln() / 27.0;
+    if mel >= min_log_mel {
+        min_log_hz * (log_step * (mel - min_log_mel)).exp()
+    } else {
+        mel * 200.0 / 3.0
+    }
+}
+
+/// Mel filter bank of shape (*num mel bins*, *N_FFT / 2 + 1*), with Slaney-normalized triangular filters covering
+/// 0 to 8kHz (identical to `librosa.filters.mel(sr=16000, n_fft=400, n_mels=n_mels)`)
+pub fn mel_filters(n_mels: i64) -> Tensor {
+    let num_frequencies = N_FFT / 2 + 1;
+    let max_frequency = SAMPLE_RATE as f64 / 2.0;
+    let fft_frequencies = (0..num_frequencies)
+        .map(|index| index as f64 * max_frequency / (num_frequencies - 1) as f64)
+        .collect::<Vec<f64>>();
+    let max_mel = hz_to_mel(max_frequency);
+    let mel_frequencies = (0..n_mels + 2)
+        .map(|index| mel_to_hz(index as f64 * max_mel / (n_mels + 1) as f64))
+        .collect::<Vec<f64>>();
+
+    let mut weights = Vec::with_capacity(n_mels as usize * num_frequencies);
+    for mel in 0..n_mels as usize {
+        let (lower, center, upper) = (
+            mel_frequencies[mel],
+            mel_frequencies[mel + 1],
+            mel_frequencies[mel + 2],
+        );
+        let normalization = 2.0 / (upper - lower);
+        weights.extend(fft_frequencies.iter().map(|frequency| {
+            let rising = (frequency - lower) / (center - lower);
+            let falling = (upper - frequency) / (upper - center);
+            (rising.min(falling).max(0.0) * normalization) as f32
+        }));
+    }
+    Tensor::from_slice(&weights).view([n_mels, num_frequencies as i64])
+}
+
+/// Computes the log-mel spectrogram of an audio chunk, as expected by the Whisper encoder. The audio is padded
+/// with silence (or truncated) to 30 seconds.
+///
+/// # Arguments
+///
+/// * `samples` - mono audio samples at 16kHz (see `resample`), with values in [-1, 1]
+/// * `n_mels` - number of mel bins of the model (`WhisperConfig::num_mel_bins`)
+/// * `device` - device to compute the spectrogram on
+///
+/// # Returns
+///
+/// * `Tensor` of shape (*num mel bins*, *N_FRAMES*)
+pub fn log_mel_spectrogram(samples: &[f32], n_mels: i64, device: Device) -> Tensor {
+    let mut audio = samples[..samples.len().min(N_SAMPLES)].to_vec();
+    audio.resize(N_SAMPLES, 0.0);
+
+    // Centered frames, with the signal reflected at its boundaries
+    let padding = N_FFT / 2;
+    let mut padded_audio = Vec::with_capacity(N_SAMPLES + 2 * padding);
+    padded_audio.extend(audio[1..=padding].iter().rev());
+    padded_audio.extend(audio.iter());
+    padded_audio.extend(audio[N_SAMPLES - padding - 1..N_SAMPLES - 1].iter().rev());
+
+    let window = (0..N_FFT)
+        .map(|index| (0.5 - 0.5 * (2.0 * PI * index as f64 / N_FFT as f64).cos()) as f32)
+        .collect::<Vec<f32>>();
+    // The overlapping frames are extracted on the CPU, `unfold` not being supported on MPS devices
+    let frames = Tensor::from_slice(&padded_audio)
+        .unfold(0, N_FFT as i64, HOP_LENGTH as i64)
+        .to_device(device)
+        * Tensor::from_slice(&window).to_device(device);
+
+    // Real-valued discrete Fourier transform of the frames
+    let num_frequencies = (N_FFT / 2 + 1) as i64;
+    let angles = Tensor::arange(N_FFT as i64, (Kind::Float, device)).unsqueeze(1)
+        * Tensor::arange(num_frequencies, (Kind::Float, device)).unsqueeze(0)
+        * (2.0 * PI / N_FFT as f64);
+    let real = frames.matmul(&angles.cos());
+    let imaginary = frames.matmul(&angles.sin());
+    // The last frame is dropped to obtain N_FRAMES frames
+    let magnitudes = (real.square() + imaginary.square()).narrow(0, 0, N_FRAMES as i64);
+
+    let mel_spectrogram = mel_filters(n_mels)
+        .to_device(device)
+        .matmul(&magnitudes.transpose(0, 1));
+    let log_spectrogram = mel_spectrogram.clamp_min(1e-10).log10();
+    let log_spectrogram = log_spectrogram.maximum(&(log_spectrogram.max() - 8.0));
+    (log_spectrogram + 4.0) / 4.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mel_scale() {
+        assert!((mel_to_hz(hz_to_mel(440.0)) - 440.0).abs() < 1e-6);
+        assert!((mel_to_hz(hz_to_mel(4000.0)) - 4000.0).abs() < 1e-6);
+        assert_eq!(mel_filters(80).size(), vec![80, 201]);
+    }
+
+    #[test]
+    fn spectrogram_shape() {
+        let samples = (0..SAMPLE_RATE)
+            .map(|index| (2.0 * std::f32::consts::PI * 440.0 * index as f32 / 16000.0).sin())
+            .collect::<Vec<f32>>();
+        let spectrogram = log_mel_spectrogram(&samples, 80, Device::Cpu);
+        assert_eq!(spectrogram.size(), vec![80, N_FRAMES as i64]);
+        assert_eq!(resample(&samples, 8000).len(), 2 * SAMPLE_RATE);
+        assert_eq!(resample(&samples, 32000).len(), SAMPLE_RATE / 2);
+    }
+}
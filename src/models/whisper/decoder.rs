@@ -0,0 +1,299 @@
+// Copyright 2022 The OpenAI Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bart::{_prepare_decoder_attention_mask, BartDecoderOutput, LayerState};
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::whisper::attention::WhisperAttention;
+use crate::whisper::WhisperConfig;
+use crate::Activation;
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Tensor};
+
+pub struct WhisperDecoderLayer {
+    self_attention: WhisperAttention,
+    encoder_attention: WhisperAttention,
+    self_attention_layer_norm: nn::LayerNorm,
+    encoder_attention_layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+    activation_dropout: Dropout,
+    activation: TensorFunction,
+    fc1: nn::Linear,
+    fc2: nn::Linear,
+    final_layer_norm: nn::LayerNorm,
+}
+
+impl WhisperDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &WhisperConfig) -> WhisperDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let output_attention = config.output_attentions.unwrap_or(false);
+        let self_attention = WhisperAttention::new(
+            p / "self_attn",
+            config.d_model,
+            config.decoder_attention_heads,
+            config.attention_dropout,
+            false,
+            true,
+            output_attention,
+        );
+        let encoder_attention = WhisperAttention::new(
+            p / "encoder_attn",
+            config.d_model,
+            config.decoder_attention_heads,
+            config.attention_dropout,
+            true,
+            true,
+            output_attention,
+        );
+        let self_attention_layer_norm = nn::layer_norm(
+            p / "self_attn_layer_norm",
+            vec![config.d_model],
+            Default::default(),
+        );
+        let encoder_attention_layer_norm = nn::layer_norm(
+            p / "encoder_attn_layer_norm",
+            vec![config.d_model],
+            Default::default(),
+        );
+
+        let dropout = Dropout::new(config.dropout);
+        let activation_dropout = Dropout::new(config.activation_dropout);
+        let activation_function = config.activation_function.unwrap_or(Activation::gelu);
+        let activation = activation_function.get_function();
+        let fc1 = nn::linear(
+            p / "fc1",
+            config.d_model,
+            config.decoder_ffn_dim,
+            Default::default(),
+        );
+        let fc2 = nn::linear(
+            p / "fc2",
+            config.decoder_ffn_dim,
+            config.d_model,
+            Default::default(),
+        );
+
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.d_model],
+            Default::default(),
+        );
+
+        WhisperDecoderLayer {
+            self_attention,
+            encoder_attention,
+            self_attention_layer_norm,
+            encoder_attention_layer_norm,
+            dropout,
+            activation_dropout,
+            activation,
+            fc1,
+            fc2,
+            final_layer_norm,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        x: &Tensor,
+        encoder_hidden_states: &Tensor,
+        decoder_attention_mask: Option<&Tensor>,
+        layer_states: (Option<LayerState>, Option<LayerState>),
+        train: bool,
+    ) -> (
+        Tensor,
+        Option<Tensor>,
+        (Option<LayerState>, Option<LayerState>),
+    ) {
+        let output = x.apply(&self.self_attention_layer_norm);
+
+        let (output, attention_weights, new_self_layer_states) = self.self_attention.forward_t(
+            &output,
+            None,
+            decoder_attention_mask,
+            layer_states.0,
+            train,
+        );
+        let output: Tensor = output.apply_t(&self.dropout, train) + x;
+
+        let output1 = output.apply(&self.encoder_attention_layer_norm);
+        let (output1, _, new_encoder_layer_states) = self.encoder_attention.forward_t(
+            &output1,
+            Some(encoder_hidden_states),
+            None,
+            layer_states.1,
+            train,
+        );
+        let output1: Tensor = output1.apply_t(&self.dropout, train) + output;
+
+        let output2 = output1.apply(&self.final_layer_norm);
+        let output2 = (self.activation.get_fn())(&output2.apply(&self.fc1));
+        let output2 = output2
+            .apply_t(&self.activation_dropout, train)
+            .apply(&self.fc2)
+            .apply_t(&self.dropout, train);
+        let output2: Tensor = output2 + output1;
+        (
+            output2,
+            attention_weights,
+            (new_self_layer_states, new_encoder_layer_states),
+        )
+    }
+}
+
+/// # Whisper text decoder
+/// Pre-layer normalization transformer decoder with learned position embeddings, attending to the audio features.
+pub struct WhisperDecoder {
+    embed_positions: nn::Embedding,
+    dropout: Dropout,
+    layer_norm: nn::LayerNorm,
+    layers: Vec<WhisperDecoderLayer>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+    output_past: bool,
+}
+
+impl WhisperDecoder {
+    pub fn new<'p, P>(p: P, config: &WhisperConfig) -> WhisperDecoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let output_past = config.output_past.unwrap_or(true);
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        let embed_positions = embedding(
+            p / "embed_positions",
+            config.max_target_positions,
+            config.d_model,
+            Default::default(),
+        );
+        let dropout = Dropout::new(config.dropout);
+        let layer_norm = nn::layer_norm(p / "layer_norm", vec![config.d_model], Default::default());
+
+        let mut layers: Vec<WhisperDecoderLayer> = vec![];
+        let p_layers = p / "layers";
+        for layer_index in 0..config.decoder_layers {
+            layers.push(WhisperDecoderLayer::new(&p_layers / layer_index, config));
+        }
+
+        WhisperDecoder {
+            embed_positions,
+            dropout,
+            layer_norm,
+            layers,
+            output_attentions,
+            output_hidden_states,
+            output_past,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_ids: &Tensor,
+        encoder_hidden_states: &Tensor,
+        embeddings: &nn::Embedding,
+        old_layer_states: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
+        train: bool,
+    ) -> WhisperDecoderOutput {
+        let past_key_values_length = if let Some(old_layer_states_values) = &old_layer_states {
+            if let Some(old_value_state) = &old_layer_states_values[0].0 {
+                old_value_state.prev_key.size()[2]
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+
+        let sequence_length = *input_ids.size().last().unwrap();
+        let positions = self
+            .embed_positions
+            .ws
+            .narrow(0, past_key_values_length, sequence_length);
+        let x: Tensor = input_ids.apply(embeddings) + positions;
+
+        let decoder_attention_mask = _prepare_decoder_attention_mask(
+            None,
+            input_ids.size().as_slice(),
+            &x,
+            past_key_values_length,
+        );
+
+        let mut hidden_state = x.apply_t(&self.dropout, train);
+
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(Vec::with_capacity(self.layers.len()))
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(Vec::with_capacity(self.layers.len()))
+        } else {
+            None
+        };
+        let mut next_decoder_cache: Option<Vec<(Option<LayerState>, Option<LayerState>)>> =
+            if self.output_past {
+                if old_layer_states.is_some() {
+                    old_layer_states
+                } else {
+                    Some(vec![(None, None); self.layers.len()])
+                }
+            } else {
+                None
+            };
+
+        let mut attention_weights: Option<Tensor>;
+
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            let layer_state = match &next_decoder_cache {
+                Some(values) => values[layer_idx].to_owned(),
+                None => (None, None),
+            };
+            let temp = layer.forward_t(
+                &hidden_state,
+                encoder_hidden_states,
+                decoder_attention_mask.as_ref(),
+                layer_state,
+                train,
+            );
+            hidden_state = temp.0;
+            attention_weights = temp.1;
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.as_ref().copy());
+            };
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(std::mem::take(&mut attention_weights.unwrap()));
+            };
+            if let Some(value) = &mut next_decoder_cache {
+                value[layer_idx] = temp.2
+            };
+        }
+
+        WhisperDecoderOutput {
+            hidden_state: hidden_state.apply(&self.layer_norm),
+            encoder_attention_mask: None,
+            next_decoder_cache,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// Container holding a Whisper decoder output
+pub type WhisperDecoderOutput = BartDecoderOutput;
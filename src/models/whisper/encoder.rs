@@ -0,0 +1,224 @@
+// Copyright 2022 The OpenAI Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bart::BartEncoderOutput;
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::whisper::attention::WhisperAttention;
+use crate::whisper::WhisperConfig;
+use crate::Activation;
+use std::borrow::{Borrow, BorrowMut};
+use tch::nn::embedding;
+use tch::{nn, Tensor};
+
+pub struct WhisperEncoderLayer {
+    self_attention: WhisperAttention,
+    self_attention_layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+    activation_dropout: Dropout,
+    activation: TensorFunction,
+    fc1: nn::Linear,
+    fc2: nn::Linear,
+    final_layer_norm: nn::LayerNorm,
+}
+
+impl WhisperEncoderLayer {
+    pub fn new<'p, P>(p: P, config: &WhisperConfig) -> WhisperEncoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let output_attention = config.output_attentions.unwrap_or(false);
+        let self_attention = WhisperAttention::new(
+            p / "self_attn",
+            config.d_model,
+            config.encoder_attention_heads,
+            config.attention_dropout,
+            false,
+            false,
+            output_attention,
+        );
+        let self_attention_layer_norm = nn::layer_norm(
+            p / "self_attn_layer_norm",
+            vec![config.d_model],
+            Default::default(),
+        );
+
+        let dropout = Dropout::new(config.dropout);
+        let activation_dropout = Dropout::new(config.activation_dropout);
+        let activation_function = config.activation_function.unwrap_or(Activation::gelu);
+        let activation = activation_function.get_function();
+        let fc1 = nn::linear(
+            p / "fc1",
+            config.d_model,
+            config.encoder_ffn_dim,
+            Default::default(),
+        );
+        let fc2 = nn::linear(
+            p / "fc2",
+            config.encoder_ffn_dim,
+            config.d_model,
+            Default::default(),
+        );
+
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.d_model],
+            Default::default(),
+        );
+
+        WhisperEncoderLayer {
+            self_attention,
+            self_attention_layer_norm,
+            dropout,
+            activation_dropout,
+            activation,
+            fc1,
+            fc2,
+            final_layer_norm,
+        }
+    }
+
+    pub fn forward_t(&self, x: &Tensor, train: bool) -> (Tensor, Option<Tensor>) {
+        let output = x.apply(&self.self_attention_layer_norm);
+        let (output, attention_weights, _) = self
+            .self_attention
+            .forward_t(&output, None, None, None, train);
+        let output: Tensor = output.apply_t(&self.dropout, train) + x;
+
+        let output2 = output.apply(&self.final_layer_norm);
+        let output2 = (self.activation.get_fn())(&output2.apply(&self.fc1));
+        let output2 = output2
+            .apply_t(&self.activation_dropout, train)
+            .apply(&self.fc2)
+            .apply_t(&self.dropout, train);
+        (output2 + output, attention_weights)
+    }
+}
+
+/// # Whisper audio encoder
+/// Two convolution layers (the second one with a stride of 2) embed the log-mel spectrogram frames, followed by
+/// sinusoidal position embeddings and pre-layer normalization transformer layers.
+pub struct WhisperEncoder {
+    conv1: nn::Conv1D,
+    conv2: nn::Conv1D,
+    embed_positions: nn::Embedding,
+    dropout: Dropout,
+    layer_norm: nn::LayerNorm,
+    layers: Vec<WhisperEncoderLayer>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl WhisperEncoder {
+    pub fn new<'p, P>(p: P, config: &WhisperConfig) -> WhisperEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        let conv1 = nn::conv1d(
+            p / "conv1",
+            config.num_mel_bins,
+            config.d_model,
+            3,
+            nn::ConvConfig {
+                padding: 1,
+                ..Default::default()
+            },
+        );
+        let conv2 = nn::conv1d(
+            p / "conv2",
+            config.d_model,
+            config.d_model,
+            3,
+            nn::ConvConfig {
+                padding: 1,
+                stride: 2,
+                ..Default::default()
+            },
+        );
+        let embed_positions = embedding(
+            p / "embed_positions",
+            config.max_source_positions,
+            config.d_model,
+            Default::default(),
+        );
+
+        let dropout = Dropout::new(config.dropout);
+        let layer_norm = nn::layer_norm(p / "layer_norm", vec![config.d_model], Default::default());
+
+        let mut layers: Vec<WhisperEncoderLayer> = vec![];
+        let p_layers = p / "layers";
+        for layer_index in 0..config.encoder_layers {
+            layers.push(WhisperEncoderLayer::new(&p_layers / layer_index, config));
+        }
+
+        WhisperEncoder {
+            conv1,
+            conv2,
+            embed_positions,
+            dropout,
+            layer_norm,
+            layers,
+            output_attentions,
+            output_hidden_states,
+        }
+    }
+
+    /// Encodes log-mel spectrograms of shape (*batch size*, *num mel bins*, *num frames*). The number of frames
+    /// must not exceed twice the maximum number of source positions (3000 frames, or 30 seconds, for the pretrained
+    /// models).
+    pub fn forward_t(&self, input_features: &Tensor, train: bool) -> WhisperEncoderOutput {
+        let x = input_features.apply(&self.conv1).gelu("none");
+        let x = x.apply(&self.conv2).gelu("none").permute([0, 2, 1]);
+        let positions = self.embed_positions.ws.narrow(0, 0, x.size()[1]);
+        let mut hidden_state = (x + positions).apply_t(&self.dropout, train);
+
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let mut attention_weights: Option<Tensor>;
+
+        for layer in &self.layers {
+            let temp = layer.forward_t(&hidden_state, train);
+            hidden_state = temp.0;
+            attention_weights = temp.1;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(std::mem::take(&mut attention_weights.unwrap()));
+            };
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.as_ref().copy());
+            };
+        }
+
+        WhisperEncoderOutput {
+            hidden_state: hidden_state.apply(&self.layer_norm),
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// Container holding a Whisper encoder output
+pub type WhisperEncoderOutput = BartEncoderOutput;
@@ -0,0 +1,72 @@
+//! # Whisper (Radford et al.)
+//!
+//! Implementation of the Whisper speech recognition model ([Robust Speech Recognition via Large-Scale Weak Supervision](https://arxiv.org/abs/2212.04356) Radford, Kim, Xu et al., 2022).
+//! Whisper is an encoder-decoder transformer: the encoder reads the log-mel spectrogram of 30 seconds of audio, and the decoder generates the transcription
+//! (or its English translation), optionally with timestamp tokens delimiting the segments of speech. Multilingual models also identify the spoken language.
+//! The base model is implemented in the `whisper_model::WhisperModel` struct and the model with a language model head in `whisper_model::WhisperForConditionalGeneration`.
+//! The audio pre-processing (PCM conversion, resampling and log-mel spectrogram) is available in the `audio` module.
+//! The `AutomaticSpeechRecognitionModel` pipeline (`pipelines::automatic_speech_recognition`) combines these steps for audio of any duration.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). Safetensors checkpoints can be loaded directly without conversion. Alternatively, the Python utility scripts convert the
+//!   weights from [OpenAI](https://huggingface.co/openai/whisper-tiny) to the `.ot` format, for example with `python utils/convert_model.py path/to/whisper/model.safetensors`.
+//! - `Gpt2Tokenizer` using a `vocab.json` vocabulary and `merges.txt` merges file. The special tokens are derived from the configuration (see `WhisperSpecialTokens`).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! #
+//! use tch::{nn, Device, Tensor};
+//! # use std::path::PathBuf;
+//! use rust_bert::whisper::audio::log_mel_spectrogram;
+//! use rust_bert::whisper::{WhisperConfig, WhisperForConditionalGeneration};
+//! use rust_bert::resources::{LocalResource, ResourceProvider};
+//! use rust_bert::Config;
+//!
+//! let config_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/config.json"),
+//! };
+//! let weights_resource = LocalResource {
+//!     local_path: PathBuf::from("path/to/model.ot"),
+//! };
+//! let config_path = config_resource.get_local_path()?;
+//! let weights_path = weights_resource.get_local_path()?;
+//!
+//! let device = Device::cuda_if_available();
+//! let mut vs = nn::VarStore::new(device);
+//! let config = WhisperConfig::from_file(config_path);
+//! let whisper_model = WhisperForConditionalGeneration::new(&vs.root(), &config);
+//! vs.load(weights_path)?;
+//!
+//! let samples: Vec<f32> = vec![0.0; 16000];
+//! let input_features = log_mel_spectrogram(&samples, config.num_mel_bins, device).unsqueeze(0);
+//! let encoder_hidden_states = whisper_model.encode(&input_features);
+//! let special_tokens = whisper_model.get_special_tokens();
+//! let prompt = Tensor::from_slice(&[
+//!     special_tokens.start_of_transcript,
+//!     special_tokens.language_token("en").unwrap(),
+//!     special_tokens.transcribe,
+//!     special_tokens.no_timestamps,
+//! ])
+//! .unsqueeze(0);
+//! let output_ids = whisper_model.generate(&encoder_hidden_states, &prompt, 224, false)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod attention;
+pub mod audio;
+mod decoder;
+mod encoder;
+mod whisper_model;
+
+pub use attention::WhisperAttention;
+pub use decoder::{WhisperDecoder, WhisperDecoderLayer, WhisperDecoderOutput};
+pub use encoder::{WhisperEncoder, WhisperEncoderLayer, WhisperEncoderOutput};
+pub use whisper_model::{
+    WhisperConfig, WhisperConfigResources, WhisperForConditionalGeneration, WhisperMergesResources,
+    WhisperModel, WhisperModelOutput, WhisperSpecialTokens, WhisperVocabResources,
+    WHISPER_LANGUAGES, WHISPER_TIMESTAMP_PRECISION,
+};
@@ -0,0 +1,762 @@
+// Copyright 2022 The OpenAI Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bart::LayerState;
+use crate::common::activations::Activation;
+use crate::whisper::decoder::WhisperDecoder;
+use crate::whisper::encoder::WhisperEncoder;
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::convert::TryFrom;
+use tch::nn::embedding;
+use tch::{nn, Kind, Tensor};
+
+/// # Whisper Pretrained model config files
+pub struct WhisperConfigResources;
+
+/// # Whisper Pretrained model vocab files
+pub struct WhisperVocabResources;
+
+/// # Whisper Pretrained model merges files
+pub struct WhisperMergesResources;
+
+impl WhisperConfigResources {
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_TINY: (&'static str, &'static str) = (
+        "whisper-tiny/config",
+        "https://huggingface.co/openai/whisper-tiny/resolve/main/config.json",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_BASE: (&'static str, &'static str) = (
+        "whisper-base/config",
+        "https://huggingface.co/openai/whisper-base/resolve/main/config.json",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_SMALL: (&'static str, &'static str) = (
+        "whisper-small/config",
+        "https://huggingface.co/openai/whisper-small/resolve/main/config.json",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_TINY_EN: (&'static str, &'static str) = (
+        "whisper-tiny-en/config",
+        "https://huggingface.co/openai/whisper-tiny.en/resolve/main/config.json",
+    );
+}
+
+impl WhisperVocabResources {
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_TINY: (&'static str, &'static str) = (
+        "whisper-tiny/vocab",
+        "https://huggingface.co/openai/whisper-tiny/resolve/main/vocab.json",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_BASE: (&'static str, &'static str) = (
+        "whisper-base/vocab",
+        "https://huggingface.co/openai/whisper-base/resolve/main/vocab.json",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_SMALL: (&'static str, &'static str) = (
+        "whisper-small/vocab",
+        "https://huggingface.co/openai/whisper-small/resolve/main/vocab.json",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_TINY_EN: (&'static str, &'static str) = (
+        "whisper-tiny-en/vocab",
+        "https://huggingface.co/openai/whisper-tiny.en/resolve/main/vocab.json",
+    );
+}
+
+impl WhisperMergesResources {
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_TINY: (&'static str, &'static str) = (
+        "whisper-tiny/merges",
+        "https://huggingface.co/openai/whisper-tiny/resolve/main/merges.txt",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_BASE: (&'static str, &'static str) = (
+        "whisper-base/merges",
+        "https://huggingface.co/openai/whisper-base/resolve/main/merges.txt",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_SMALL: (&'static str, &'static str) = (
+        "whisper-small/merges",
+        "https://huggingface.co/openai/whisper-small/resolve/main/merges.txt",
+    );
+    /// Shared under MIT license by the OpenAI team at <https://github.com/openai/whisper>.
+    pub const WHISPER_TINY_EN: (&'static str, &'static str) = (
+        "whisper-tiny-en/merges",
+        "https://huggingface.co/openai/whisper-tiny.en/resolve/main/merges.txt",
+    );
+}
+
+/// Codes of the languages supported by the multilingual Whisper models, in the order of their language tokens
+/// (`<|en|>`, `<|zh|>`...). The last language (Cantonese) is only available for the large-v3 models.
+pub const WHISPER_LANGUAGES: [&str; 100] = [
+    "en", "zh", "de", "es", "ru", "ko", "fr", "ja", "pt", "tr", "pl", "ca", "nl", "ar", "sv", "it",
+    "id", "hi", "fi", "vi", "he", "uk", "el", "ms", "cs", "ro", "da", "hu", "ta", "no", "th", "ur",
+    "hr", "bg", "lt", "la", "mi", "ml", "cy", "sk", "te", "fa", "lv", "bn", "sr", "az", "sl", "kn",
+    "et", "mk", "br", "eu", "is", "hy", "ne", "mn", "bs", "kk", "sq", "sw", "gl", "mr", "pa", "si",
+    "km", "sn", "yo", "so", "af", "oc", "ka", "be", "tg", "sd", "gu", "am", "yi", "lo", "uz", "fo",
+    "ht", "ps", "tk", "nn", "mt", "sa", "lb", "my", "bo", "tl", "mg", "as", "tt", "haw", "ln",
+    "ha", "ba", "jw", "su", "yue",
+];
+
+/// Number of timestamp tokens (`<|0.00|>` to `<|30.00|>`), placed at the end of the vocabulary
+const NUM_TIMESTAMP_TOKENS: i64 = 1501;
+
+/// Duration in seconds between two consecutive timestamp tokens
+pub const WHISPER_TIMESTAMP_PRECISION: f64 = 0.02;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+// Pretrained configuration files only list the values differing from the defaults
+#[serde(default)]
+/// # Whisper model configuration
+/// Defines the Whisper model architecture (e.g. number of layers, hidden layer size, number of mel bins...)
+pub struct WhisperConfig {
+    pub vocab_size: i64,
+    pub num_mel_bins: i64,
+    pub d_model: i64,
+    pub encoder_layers: i64,
+    pub encoder_attention_heads: i64,
+    pub encoder_ffn_dim: i64,
+    pub decoder_layers: i64,
+    pub decoder_attention_heads: i64,
+    pub decoder_ffn_dim: i64,
+    pub max_source_positions: i64,
+    pub max_target_positions: i64,
+    pub activation_function: Option<Activation>,
+    pub dropout: f64,
+    pub attention_dropout: f64,
+    pub activation_dropout: f64,
+    pub pad_token_id: Option<i64>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub decoder_start_token_id: Option<i64>,
+    /// Tokens never generated (e.g. speaker tags or music notes)
+    pub suppress_tokens: Option<Vec<i64>>,
+    /// Tokens not generated at the first decoding step (e.g. blank space, end of text)
+    pub begin_suppress_tokens: Option<Vec<i64>>,
+    pub output_past: Option<bool>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for WhisperConfig {}
+
+impl Default for WhisperConfig {
+    fn default() -> Self {
+        WhisperConfig {
+            vocab_size: 51865,
+            num_mel_bins: 80,
+            d_model: 384,
+            encoder_layers: 4,
+            encoder_attention_heads: 6,
+            encoder_ffn_dim: 1536,
+            decoder_layers: 4,
+            decoder_attention_heads: 6,
+            decoder_ffn_dim: 1536,
+            max_source_positions: 1500,
+            max_target_positions: 448,
+            activation_function: Some(Activation::gelu),
+            dropout: 0.0,
+            attention_dropout: 0.0,
+            activation_dropout: 0.0,
+            pad_token_id: Some(50257),
+            bos_token_id: Some(50257),
+            eos_token_id: Some(50257),
+            decoder_start_token_id: Some(50258),
+            suppress_tokens: None,
+            begin_suppress_tokens: Some(vec![220, 50257]),
+            output_past: None,
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Whisper special tokens
+/// Identifiers of the special tokens controlling the Whisper decoding, derived from the model configuration.
+/// The special tokens follow the text vocabulary: end of text, start of transcript, language tokens, task tokens,
+/// no speech, no timestamps and the timestamp tokens.
+pub struct WhisperSpecialTokens {
+    /// `<|endoftext|>`
+    pub end_of_text: i64,
+    /// `<|startoftranscript|>`
+    pub start_of_transcript: i64,
+    /// `<|translate|>`
+    pub translate: i64,
+    /// `<|transcribe|>`
+    pub transcribe: i64,
+    /// `<|nospeech|>`
+    pub no_speech: i64,
+    /// `<|notimestamps|>`
+    pub no_timestamps: i64,
+    /// `<|0.00|>`, the first timestamp token
+    pub timestamp_begin: i64,
+    /// Flag indicating if the model is multilingual (English-only models are not prompted with a language token)
+    pub multilingual: bool,
+}
+
+impl WhisperSpecialTokens {
+    /// Derives the special tokens of the model from its configuration
+    pub fn new(config: &WhisperConfig) -> WhisperSpecialTokens {
+        let end_of_text = config.eos_token_id.unwrap_or(50257);
+        let start_of_transcript = config.decoder_start_token_id.unwrap_or(end_of_text + 1);
+        let timestamp_begin = config.vocab_size - NUM_TIMESTAMP_TOKENS;
+        WhisperSpecialTokens {
+            end_of_text,
+            start_of_transcript,
+            translate: timestamp_begin - 6,
+            transcribe: timestamp_begin - 5,
+            no_speech: timestamp_begin - 2,
+            no_timestamps: timestamp_begin - 1,
+            timestamp_begin,
+            multilingual: config.vocab_size >= 51865,
+        }
+    }
+
+    /// Codes of the languages supported by the model
+    pub fn languages(&self) -> &'static [&'static str] {
+        let num_languages = (self.translate - self.start_of_transcript - 1)
+            .clamp(0, WHISPER_LANGUAGES.len() as i64) as usize;
+        &WHISPER_LANGUAGES[..num_languages]
+    }
+
+    /// Token of a language (e.g. `<|fr|>` for `fr`)
+    pub fn language_token(&self, language: &str) -> Option<i64> {
+        self.languages()
+            .iter()
+            .position(|code| *code == language)
+            .map(|index| self.start_of_transcript + 1 + index as i64)
+    }
+
+    /// Language code of a language token
+    pub fn language_code(&self, token: i64) -> Option<&'static str> {
+        let index = token - self.start_of_transcript - 1;
+        if index >= 0 {
+            self.languages().get(index as usize).copied()
+        } else {
+            None
+        }
+    }
+
+    /// Checks if a token is a timestamp token
+    pub fn is_timestamp(&self, token: i64) -> bool {
+        token >= self.timestamp_begin
+    }
+
+    /// Time in seconds of a timestamp token
+    pub fn timestamp_seconds(&self, token: i64) -> f64 {
+        (token - self.timestamp_begin) as f64 * WHISPER_TIMESTAMP_PRECISION
+    }
+}
+
+/// # Whisper base model
+/// Base architecture for the Whisper model. Usually complemented with a task-specific head, such as a language model head.
+/// It is made of the following blocks:
+/// - `encoder`: `WhisperEncoder` audio encoder of the log-mel spectrogram
+/// - `decoder`: `WhisperDecoder` text decoder attending to the audio features
+/// - `embed_tokens`: token embeddings shared with the language model head
+pub struct WhisperModel {
+    pub(crate) encoder: WhisperEncoder,
+    decoder: WhisperDecoder,
+    pub(crate) embed_tokens: nn::Embedding,
+}
+
+impl WhisperModel {
+    /// Build a new `WhisperModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Whisper model
+    /// * `config` - `WhisperConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::whisper::{WhisperConfig, WhisperModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = WhisperConfig::from_file(config_path);
+    /// let whisper_model = WhisperModel::new(&p.root() / "model", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &WhisperConfig) -> WhisperModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let encoder = WhisperEncoder::new(p / "encoder", config);
+        let decoder = WhisperDecoder::new(p / "decoder", config);
+        let embed_tokens = embedding(
+            p / "decoder" / "embed_tokens",
+            config.vocab_size,
+            config.d_model,
+            Default::default(),
+        );
+
+        WhisperModel {
+            encoder,
+            decoder,
+            embed_tokens,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_features` - Optional log-mel spectrograms of shape (*batch size*, *num mel bins*, *num frames*) (see `audio::log_mel_spectrogram`). Must be provided if `encoder_outputs` is not.
+    /// * `encoder_outputs` - Optional pre-computed audio features of shape (*batch size*, *num audio positions*, *hidden_size*), skipping the audio encoding.
+    /// * `decoder_input_ids` - Decoder input tokens of shape (*batch size*, *target_sequence_length*), starting with the decoder prompt.
+    /// * `old_layer_states` - Optional cached key/value states of the decoder from a previous step.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `WhisperModelOutput` containing:
+    ///   - `decoder_output` - `Tensor` of shape (*batch size*, *target_sequence_length*, *hidden_size*) representing the activations of the last decoder hidden state
+    ///   - `encoder_hidden_state` - `Option<Tensor>` audio features if they were calculated
+    ///   - `cache` - `Option<Vec<(Option<LayerState>, Option<LayerState>)>>` of length *n_layer* containing the decoder past keys and values
+    ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    pub fn forward_t(
+        &self,
+        input_features: Option<&Tensor>,
+        encoder_outputs: Option<&Tensor>,
+        decoder_input_ids: &Tensor,
+        old_layer_states: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
+        train: bool,
+    ) -> Result<WhisperModelOutput, RustBertError> {
+        let calculated_encoder_output = match (encoder_outputs, input_features) {
+            (Some(_), _) => None,
+            (None, Some(input_features)) => Some(self.encoder.forward_t(input_features, train)),
+            (None, None) => {
+                return Err(RustBertError::ValueError(
+                    "At least one of input_features or encoder_outputs must be provided".into(),
+                ));
+            }
+        };
+        let encoder_hidden_states = match encoder_outputs {
+            Some(encoder_outputs) => encoder_outputs,
+            None => &calculated_encoder_output.as_ref().unwrap().hidden_state,
+        };
+
+        let decoder_output = self.decoder.forward_t(
+            decoder_input_ids,
+            encoder_hidden_states,
+            &self.embed_tokens,
+            old_layer_states,
+            train,
+        );
+
+        Ok(WhisperModelOutput {
+            decoder_output: decoder_output.hidden_state,
+            encoder_hidden_state: calculated_encoder_output.map(|output| output.hidden_state),
+            cache: decoder_output.next_decoder_cache,
+            all_decoder_hidden_states: decoder_output.all_hidden_states,
+            all_decoder_attentions: decoder_output.all_attentions,
+        })
+    }
+}
+
+/// # Whisper model for conditional generation
+/// Whisper model with a language model head (tied to the token embeddings) generating transcriptions or translations
+/// of the audio. It is made of the following blocks:
+/// - `base_model`: `WhisperModel` base Whisper encoder-decoder model
+/// - `special_tokens`: `WhisperSpecialTokens` identifiers of the decoding control tokens
+pub struct WhisperForConditionalGeneration {
+    base_model: WhisperModel,
+    special_tokens: WhisperSpecialTokens,
+    suppress_tokens: Vec<i64>,
+    begin_suppress_tokens: Vec<i64>,
+    max_target_positions: i64,
+}
+
+impl WhisperForConditionalGeneration {
+    /// Build a new `WhisperForConditionalGeneration`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the Whisper model
+    /// * `config` - `WhisperConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::whisper::{WhisperConfig, WhisperForConditionalGeneration};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = WhisperConfig::from_file(config_path);
+    /// let whisper_model = WhisperForConditionalGeneration::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &WhisperConfig) -> WhisperForConditionalGeneration
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let base_model = WhisperModel::new(p / "model", config);
+
+        WhisperForConditionalGeneration {
+            base_model,
+            special_tokens: WhisperSpecialTokens::new(config),
+            suppress_tokens: config.suppress_tokens.clone().unwrap_or_default(),
+            begin_suppress_tokens: config.begin_suppress_tokens.clone().unwrap_or_default(),
+            max_target_positions: config.max_target_positions,
+        }
+    }
+
+    /// Returns the special tokens of the model
+    pub fn get_special_tokens(&self) -> &WhisperSpecialTokens {
+        &self.special_tokens
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_features` - Optional log-mel spectrograms of shape (*batch size*, *num mel bins*, *num frames*) (see `audio::log_mel_spectrogram`). Must be provided if `encoder_outputs` is not.
+    /// * `encoder_outputs` - Optional pre-computed audio features of shape (*batch size*, *num audio positions*, *hidden_size*), skipping the audio encoding.
+    /// * `decoder_input_ids` - Decoder input tokens of shape (*batch size*, *target_sequence_length*), starting with the decoder prompt.
+    /// * `old_layer_states` - Optional cached key/value states of the decoder from a previous step.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `WhisperModelOutput` containing:
+    ///   - `decoder_output` - `Tensor` of shape (*batch size*, *target_sequence_length*, *vocab_size*) representing the logits for each vocabulary item and position
+    ///   - `encoder_hidden_state` - `Option<Tensor>` audio features if they were calculated
+    ///   - `cache` - `Option<Vec<(Option<LayerState>, Option<LayerState>)>>` of length *n_layer* containing the decoder past keys and values
+    ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use rust_bert::whisper::{WhisperConfig, WhisperForConditionalGeneration};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = WhisperConfig::from_file(config_path);
+    /// # let whisper_model = WhisperForConditionalGeneration::new(&vs.root(), &config);
+    /// let input_features = Tensor::rand(&[1, 80, 3000], (Kind::Float, device));
+    /// let decoder_input_ids = Tensor::from_slice(&[50258i64]).unsqueeze(0);
+    ///
+    /// let model_output = no_grad(|| {
+    ///     whisper_model.forward_t(Some(&input_features), None, &decoder_input_ids, None, false)
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_features: Option<&Tensor>,
+        encoder_outputs: Option<&Tensor>,
+        decoder_input_ids: &Tensor,
+        old_layer_states: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
+        train: bool,
+    ) -> Result<WhisperModelOutput, RustBertError> {
+        let base_model_output = self.base_model.forward_t(
+            input_features,
+            encoder_outputs,
+            decoder_input_ids,
+            old_layer_states,
+            train,
+        )?;
+        let lm_logits = base_model_output
+            .decoder_output
+            .linear::<Tensor>(&self.base_model.embed_tokens.ws, None);
+        Ok(WhisperModelOutput {
+            decoder_output: lm_logits,
+            ..base_model_output
+        })
+    }
+
+    /// Encodes log-mel spectrograms of shape (*batch size*, *num mel bins*, *num frames*) into audio features of
+    /// shape (*batch size*, *num audio positions*, *hidden_size*)
+    pub fn encode(&self, input_features: &Tensor) -> Tensor {
+        tch::no_grad(|| {
+            self.base_model
+                .encoder
+                .forward_t(input_features, false)
+                .hidden_state
+        })
+    }
+
+    /// Language identification from the audio features: returns the probabilities of the languages supported by the
+    /// model (in the order of `WhisperSpecialTokens::languages`) as a `Tensor` of shape (*batch size*, *num languages*).
+    ///
+    /// # Arguments
+    ///
+    /// * `encoder_hidden_states` - audio features of shape (*batch size*, *num audio positions*, *hidden_size*) (see `encode`)
+    pub fn detect_language(&self, encoder_hidden_states: &Tensor) -> Result<Tensor, RustBertError> {
+        let num_languages = self.special_tokens.languages().len() as i64;
+        if !self.special_tokens.multilingual || num_languages == 0 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Language identification requires a multilingual Whisper model".to_string(),
+            ));
+        }
+        tch::no_grad(|| {
+            let batch_size = encoder_hidden_states.size()[0];
+            let decoder_input_ids = Tensor::full(
+                [batch_size, 1],
+                self.special_tokens.start_of_transcript,
+                (Kind::Int64, encoder_hidden_states.device()),
+            );
+            let logits = self
+                .forward_t(
+                    None,
+                    Some(encoder_hidden_states),
+                    &decoder_input_ids,
+                    None,
+                    false,
+                )?
+                .decoder_output
+                .select(1, -1);
+            Ok(logits
+                .narrow(
+                    -1,
+                    self.special_tokens.start_of_transcript + 1,
+                    num_languages,
+                )
+                .softmax(-1, Kind::Float))
+        })
+    }
+
+    /// Generates token sequences for a batch of audio features using greedy decoding.
+    /// When timestamps are enabled, the generation follows the Whisper timestamp rules: the text segments are
+    /// delimited by pairs of increasing timestamp tokens, starting with a timestamp token of at most 1 second.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoder_hidden_states` - audio features of shape (*batch size*, *num audio positions*, *hidden_size*) (see `encode`)
+    /// * `decoder_prompt` - prompt tokens of shape (1, *prompt_length*) shared by all inputs, or (*batch size*, *prompt_length*),
+    ///   for example `<|startoftranscript|><|en|><|transcribe|><|notimestamps|>`
+    /// * `max_new_tokens` - maximum number of generated tokens (in addition to the prompt)
+    /// * `timestamps` - flag indicating if timestamp tokens should be generated (the prompt must not contain `<|notimestamps|>`)
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *sequence_length*) containing the generated tokens (without the prompt),
+    ///   padded with end of text tokens after the end of each sequence.
+    pub fn generate(
+        &self,
+        encoder_hidden_states: &Tensor,
+        decoder_prompt: &Tensor,
+        max_new_tokens: i64,
+        timestamps: bool,
+    ) -> Result<Tensor, RustBertError> {
+        tch::no_grad(|| {
+            let batch_size = encoder_hidden_states.size()[0];
+            let prompt_length = decoder_prompt.size()[1];
+            let max_new_tokens = max_new_tokens.min(self.max_target_positions - prompt_length);
+            let device = encoder_hidden_states.device();
+
+            let mut step_input_ids = decoder_prompt
+                .expand([batch_size, prompt_length], false)
+                .contiguous()
+                .to_device(device);
+            let mut generated: Vec<Vec<i64>> = vec![vec![]; batch_size as usize];
+            let mut finished = vec![false; batch_size as usize];
+            let mut cache = None;
+
+            for _ in 0..max_new_tokens.max(0) {
+                let output = self.forward_t(
+                    None,
+                    Some(encoder_hidden_states),
+                    &step_input_ids,
+                    cache,
+                    false,
+                )?;
+                cache = output.cache;
+
+                let next_token_logits = output.decoder_output.select(1, -1).to_kind(Kind::Float);
+                for (row, tokens) in generated.iter().enumerate() {
+                    self.suppress_logits(&next_token_logits.get(row as i64), tokens, timestamps);
+                }
+                let next_tokens = Vec::<i64>::try_from(next_token_logits.argmax(-1, false))?;
+
+                for (row, next_token) in next_tokens.iter().enumerate() {
+                    if finished[row] {
+                        generated[row].push(self.special_tokens.end_of_text);
+                    } else {
+                        generated[row].push(*next_token);
+                        finished[row] = *next_token == self.special_tokens.end_of_text;
+                    }
+                }
+                if finished.iter().all(|finished| *finished) {
+                    break;
+                }
+                step_input_ids = Tensor::from_slice(
+                    &generated
+                        .iter()
+                        .map(|tokens| *tokens.last().unwrap())
+                        .collect::<Vec<i64>>(),
+                )
+                .unsqueeze(-1)
+                .to_device(device);
+            }
+
+            let length = generated.first().map_or(0, |tokens| tokens.len()) as i64;
+            Ok(Tensor::from_slice(&generated.concat())
+                .view([batch_size, length])
+                .to_device(device))
+        })
+    }
+
+    /// Masks the logits of the tokens that cannot be generated at the next step, given the previously generated
+    /// tokens (excluding the prompt)
+    fn suppress_logits(&self, logits: &Tensor, generated: &[i64], timestamps: bool) {
+        let special_tokens = &self.special_tokens;
+        let vocab_size = logits.size()[0];
+        let timestamp_begin = special_tokens.timestamp_begin;
+        let suppress_range = |start: i64, end: i64| {
+            let (start, end) = (start.clamp(0, vocab_size), end.clamp(0, vocab_size));
+            if end > start {
+                let _ = logits
+                    .narrow(0, start, end - start)
+                    .fill_(f64::NEG_INFINITY);
+            }
+        };
+        let suppress_tokens = |tokens: &[i64]| {
+            let tokens = tokens
+                .iter()
+                .copied()
+                .filter(|token| (*token >= 0) & (*token < vocab_size))
+                .collect::<Vec<i64>>();
+            if !tokens.is_empty() {
+                let _ = logits.shallow_clone().index_fill_(
+                    0,
+                    &Tensor::from_slice(&tokens).to_device(logits.device()),
+                    f64::NEG_INFINITY,
+                );
+            }
+        };
+
+        suppress_tokens(&self.suppress_tokens);
+        suppress_tokens(&[special_tokens.no_timestamps]);
+        if generated.is_empty() {
+            suppress_tokens(&self.begin_suppress_tokens);
+        }
+        if !timestamps {
+            suppress_range(timestamp_begin, vocab_size);
+            return;
+        }
+
+        let last_was_timestamp = generated
+            .last()
+            .map_or(false, |token| special_tokens.is_timestamp(*token));
+        let penultimate_was_timestamp =
+            generated.len() < 2 || special_tokens.is_timestamp(generated[generated.len() - 2]);
+        if last_was_timestamp {
+            if penultimate_was_timestamp {
+                // A segment was closed: the next token is text
+                suppress_range(timestamp_begin, vocab_size);
+            } else {
+                // A segment is open: the next token is a closing timestamp or the end of text
+                suppress_range(0, special_tokens.end_of_text);
+            }
+        }
+
+        // Timestamps are non-decreasing, and strictly increasing after a closed segment
+        if let Some(last_timestamp) = generated
+            .iter()
+            .rev()
+            .find(|token| special_tokens.is_timestamp(**token))
+        {
+            let first_allowed = if last_was_timestamp && !penultimate_was_timestamp {
+                *last_timestamp
+            } else {
+                *last_timestamp + 1
+            };
+            suppress_range(timestamp_begin, first_allowed);
+        }
+
+        if generated.is_empty() {
+            // The transcription starts with a timestamp of at most 1 second
+            suppress_range(0, timestamp_begin);
+            let max_initial_timestamp = (1.0 / WHISPER_TIMESTAMP_PRECISION).round() as i64;
+            suppress_range(timestamp_begin + max_initial_timestamp + 1, vocab_size);
+        }
+
+        // A timestamp is generated if the total probability of the timestamps exceeds the probability of any text token
+        let log_probabilities = logits.log_softmax(-1, Kind::Float);
+        let timestamp_log_probability = log_probabilities
+            .narrow(0, timestamp_begin, vocab_size - timestamp_begin)
+            .logsumexp([0], false)
+            .double_value(&[]);
+        let max_text_log_probability = log_probabilities
+            .narrow(0, 0, timestamp_begin)
+            .max()
+            .double_value(&[]);
+        if timestamp_log_probability > max_text_log_probability {
+            suppress_range(0, timestamp_begin);
+        }
+    }
+}
+
+/// Container for the Whisper model output.
+pub struct WhisperModelOutput {
+    /// Last decoder layer hidden state, or logits for the vocabulary items at each sequence position for the model
+    /// with a language model head
+    pub decoder_output: Tensor,
+    /// Audio features of the encoder if they were calculated (not provided as an input)
+    pub encoder_hidden_state: Option<Tensor>,
+    /// Cached decoder attention layers keys and values
+    pub cache: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
+    /// Hidden states for all layers of the decoder
+    pub all_decoder_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all layers of the decoder
+    pub all_decoder_attentions: Option<Vec<Tensor>>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn special_tokens() {
+        let special_tokens = WhisperSpecialTokens::new(&WhisperConfig::default());
+        assert_eq!(special_tokens.timestamp_begin, 50364);
+        assert_eq!(special_tokens.no_timestamps, 50363);
+        assert_eq!(special_tokens.transcribe, 50359);
+        assert_eq!(special_tokens.translate, 50358);
+        assert_eq!(special_tokens.languages().len(), 99);
+        assert_eq!(special_tokens.language_token("en"), Some(50259));
+        assert_eq!(special_tokens.language_token("fr"), Some(50265));
+        assert_eq!(special_tokens.language_code(50260), Some("zh"));
+        assert!((special_tokens.timestamp_seconds(50364 + 150) - 3.0).abs() < 1e-9);
+
+        let english_only = WhisperSpecialTokens::new(&WhisperConfig {
+            vocab_size: 51864,
+            eos_token_id: Some(50256),
+            decoder_start_token_id: Some(50257),
+            ..Default::default()
+        });
+        assert!(!english_only.multilingual);
+        assert_eq!(english_only.timestamp_begin, 50363);
+        assert_eq!(english_only.transcribe, 50358);
+    }
+}
@@ -15,6 +15,9 @@
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::summary::{SequenceSummary, SummaryConfig, SummaryType};
+use crate::common::weight_tying::{
+    load_weights_with_tying, weight_tying_policy, TiedWeights, WeightTyingStatus,
+};
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
     PreparedInput, PrivateLanguageGenerator,
@@ -663,6 +666,10 @@ pub struct XLNetLMHeadModel {
 }
 
 impl XLNetLMHeadModel {
+    /// Input embeddings and LM head variables tied in the checkpoints, for a model created at the root of its `VarStore`
+    pub const TIED_WEIGHTS: TiedWeights =
+        TiedWeights::new("transformer.word_embedding.weight", "lm_loss.weight");
+
     /// Build a new `XLNetLMHeadModel`
     ///
     /// # Arguments
@@ -787,6 +794,7 @@ impl XLNetLMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::XLNetCache(base_model_output.next_cache),
+            last_hidden_state: Some(base_model_output.hidden_state),
         })
     }
 }
@@ -1505,6 +1513,7 @@ pub struct XLNetGenerator {
     vocab_size: i64,
     decoder_start_id: Option<i64>,
     max_position_embeddings: i64,
+    weight_tying_status: Vec<WeightTyingStatus>,
 }
 
 impl XLNetGenerator {
@@ -1555,12 +1564,17 @@ impl XLNetGenerator {
         let config_path = generate_config.config_resource.get_local_path()?;
         let device = generate_config.device;
 
-        generate_config.validate();
+        generate_config.validate()?;
         let mut var_store = nn::VarStore::new(device);
 
         let config = XLNetConfig::from_file(config_path);
         let model = XLNetLMHeadModel::new(var_store.root(), &config);
-        crate::resources::load_weights(&generate_config.model_resource, &mut var_store)?;
+        let weight_tying_status = load_weights_with_tying(
+            &generate_config.model_resource,
+            &mut var_store,
+            &[XLNetLMHeadModel::TIED_WEIGHTS],
+            weight_tying_policy(),
+        )?;
 
         let bos_token_id = Some(config.bos_token_id);
         let eos_token_ids = Some(vec![config.eos_token_id]);
@@ -1583,8 +1597,15 @@ impl XLNetGenerator {
             vocab_size,
             decoder_start_id,
             max_position_embeddings,
+            weight_tying_status,
         })
     }
+
+    /// Returns the tying of the LM head with the input embeddings verified when loading the weights. An LM head
+    /// differing from the input embeddings is reported as `WeightTyingStatus::Untied`.
+    pub fn get_weight_tying_status(&self) -> &[WeightTyingStatus] {
+        &self.weight_tying_status
+    }
 }
 
 impl PrivateLanguageGenerator for XLNetGenerator {
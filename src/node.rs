@@ -0,0 +1,232 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Node.js bindings
+//! Exposes the sentence embeddings and text generation pipelines to JavaScript through [N-API](https://napi.rs),
+//! behind the `node` feature. The predictions run on the libuv thread pool and return promises, so that they do not
+//! block the event loop. Text generation can stream the generated text to a callback as it is produced.
+//!
+//! The Node.js addon is the crate compiled as a dynamic library, renamed with a `.node` extension:
+//!
+//! ```bash
+//! cargo rustc --release --features node --crate-type cdylib
+//! cp target/release/librust_bert.so rust_bert.node
+//! ```
+//!
+//! ```javascript
+//! const { SentenceEmbeddingsModel, TextGenerationModel } = require("./rust_bert.node");
+//!
+//! const embeddings = new SentenceEmbeddingsModel();
+//! const vectors = await embeddings.encode(["This is an example sentence"]);
+//!
+//! const generator = new TextGenerationModel(64, false, "cpu");
+//! const output = await generator.generateStream("The dog", (error, text) => process.stdout.write(text));
+//! ```
+//!
+//! The models are loaded when the pipelines are created, which blocks the event loop: pipelines should be created
+//! once when the application starts. Predictions submitted concurrently to the same pipeline are processed one at a time.
+
+use crate::pipelines::sentence_embeddings::{
+    SentenceEmbeddingsBuilder, SentenceEmbeddingsModel, SentenceEmbeddingsModelType,
+};
+use crate::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+use crate::settings::{self, default_device};
+use crate::RustBertError;
+use napi::bindgen_prelude::{AsyncTask, Env, Error, Result, Task};
+use napi::threadsafe_function::{
+    ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::JsFunction;
+use napi_derive::napi;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tch::Device;
+
+impl From<RustBertError> for Error {
+    fn from(error: RustBertError) -> Self {
+        Error::from_reason(error.to_string())
+    }
+}
+
+/// Parses a device name ("cpu", "cuda", "cuda:<index>", "mps" or "auto"), defaulting to the configured default device
+fn parse_device(device: Option<String>) -> Result<Device> {
+    match device {
+        None => Ok(default_device()),
+        Some(device) => Ok(settings::parse_device(&device)?),
+    }
+}
+
+fn lock<T>(model: &Mutex<T>) -> Result<MutexGuard<'_, T>> {
+    model
+        .lock()
+        .map_err(|_| Error::from_reason("The model is unusable after a failed prediction"))
+}
+
+/// Sentence embeddings pipeline (default: all-MiniLM-L12-v2)
+#[napi(js_name = "SentenceEmbeddingsModel")]
+pub struct JsSentenceEmbeddingsModel {
+    model: Arc<Mutex<SentenceEmbeddingsModel>>,
+}
+
+#[napi]
+impl JsSentenceEmbeddingsModel {
+    /// Loads the default remote model, or a local model directory (as created by sentence-transformers)
+    #[napi(constructor)]
+    pub fn new(model_dir: Option<String>, device: Option<String>) -> Result<Self> {
+        let device = parse_device(device)?;
+        let model = match model_dir {
+            Some(model_dir) => SentenceEmbeddingsBuilder::local(model_dir)
+                .with_device(device)
+                .create_model()?,
+            None => SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+                .with_device(device)
+                .create_model()?,
+        };
+        Ok(JsSentenceEmbeddingsModel {
+            model: Arc::new(Mutex::new(model)),
+        })
+    }
+
+    /// Computes the embeddings of the texts
+    #[napi(ts_return_type = "Promise<number[][]>")]
+    pub fn encode(&self, texts: Vec<String>) -> AsyncTask<EncodeTask> {
+        AsyncTask::new(EncodeTask {
+            model: self.model.clone(),
+            texts,
+        })
+    }
+}
+
+/// Background computation of sentence embeddings
+pub struct EncodeTask {
+    model: Arc<Mutex<SentenceEmbeddingsModel>>,
+    texts: Vec<String>,
+}
+
+#[napi]
+impl Task for EncodeTask {
+    type Output = Vec<Vec<f32>>;
+    type JsValue = Vec<Vec<f64>>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(lock(&self.model)?.encode(&self.texts)?)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output
+            .into_iter()
+            .map(|embedding| embedding.into_iter().map(f64::from).collect())
+            .collect())
+    }
+}
+
+/// Text generation pipeline (default: GPT2)
+#[napi(js_name = "TextGenerationModel")]
+pub struct JsTextGenerationModel {
+    model: Arc<Mutex<TextGenerationModel>>,
+}
+
+#[napi]
+impl JsTextGenerationModel {
+    #[napi(constructor)]
+    pub fn new(
+        max_length: Option<i64>,
+        do_sample: Option<bool>,
+        device: Option<String>,
+    ) -> Result<Self> {
+        let default_config = TextGenerationConfig::default();
+        let config = TextGenerationConfig {
+            max_length: max_length.or(default_config.max_length),
+            do_sample: do_sample.unwrap_or(default_config.do_sample),
+            device: parse_device(device)?,
+            ..default_config
+        };
+        Ok(JsTextGenerationModel {
+            model: Arc::new(Mutex::new(TextGenerationModel::new(config)?)),
+        })
+    }
+
+    /// Generates texts continuing the prompts
+    #[napi(ts_return_type = "Promise<string[]>")]
+    pub fn generate(&self, texts: Vec<String>) -> AsyncTask<GenerateTask> {
+        AsyncTask::new(GenerateTask {
+            model: self.model.clone(),
+            texts,
+        })
+    }
+
+    /// Generates a text continuing the prompt, calling `callback(error, text)` with each new piece of generated text.
+    /// The promise resolves to the complete generated text.
+    #[napi(ts_return_type = "Promise<string>")]
+    pub fn generate_stream(
+        &self,
+        prompt: String,
+        #[napi(ts_arg_type = "(error: null | Error, text: string) => void")] callback: JsFunction,
+    ) -> Result<AsyncTask<GenerateStreamTask>> {
+        let callback: ThreadsafeFunction<String> = callback
+            .create_threadsafe_function(0, |context: ThreadSafeCallContext<String>| {
+                Ok(vec![context.value])
+            })?;
+        Ok(AsyncTask::new(GenerateStreamTask {
+            model: self.model.clone(),
+            prompt,
+            callback,
+        }))
+    }
+}
+
+/// Background text generation
+pub struct GenerateTask {
+    model: Arc<Mutex<TextGenerationModel>>,
+    texts: Vec<String>,
+}
+
+#[napi]
+impl Task for GenerateTask {
+    type Output = Vec<String>;
+    type JsValue = Vec<String>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(lock(&self.model)?.generate(&self.texts, None))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Background text generation, streaming the generated text to a JavaScript callback
+pub struct GenerateStreamTask {
+    model: Arc<Mutex<TextGenerationModel>>,
+    prompt: String,
+    callback: ThreadsafeFunction<String>,
+}
+
+#[napi]
+impl Task for GenerateStreamTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let callback = &self.callback;
+        Ok(
+            lock(&self.model)?.generate_stream(&self.prompt, None, |text| {
+                callback.call(
+                    Ok(text.to_string()),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                );
+            }),
+        )
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
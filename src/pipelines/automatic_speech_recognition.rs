@@ -0,0 +1,454 @@
+// Copyright 2019-2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Automatic speech recognition pipeline
+//! Transcribes audio with a Whisper model, or translates it to English. The audio is split into chunks of 30 seconds
+//! that are transcribed in a single batch. The spoken language is identified from the first chunk for multilingual
+//! models, unless it is provided in the configuration.
+//!
+//! The pipeline expects mono audio samples at 16kHz with values in [-1, 1]. Signed 16-bit PCM samples can be converted
+//! with `whisper::audio::pcm_to_f32`, and audio at other sampling rates resampled with `whisper::audio::resample`.
+//! The transcriptions contain the segments of speech, with their start and end times when timestamps are enabled.
+//!
+//! The model weights must be converted to the `.ot` format (see the `whisper` module).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::automatic_speech_recognition::{
+//!     AutomaticSpeechRecognitionConfig, AutomaticSpeechRecognitionModel,
+//! };
+//! use rust_bert::pipelines::common::ModelResource;
+//! use rust_bert::resources::{LocalResource, RemoteResource};
+//! use rust_bert::whisper::audio::pcm_to_f32;
+//! use rust_bert::whisper::{
+//!     WhisperConfigResources, WhisperMergesResources, WhisperVocabResources,
+//! };
+//! use std::path::PathBuf;
+//!
+//! let mut config = AutomaticSpeechRecognitionConfig::new(
+//!     ModelResource::Torch(Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/whisper-tiny/model.ot"),
+//!     })),
+//!     RemoteResource::from_pretrained(WhisperConfigResources::WHISPER_TINY),
+//!     RemoteResource::from_pretrained(WhisperVocabResources::WHISPER_TINY),
+//!     RemoteResource::from_pretrained(WhisperMergesResources::WHISPER_TINY),
+//! );
+//! config.return_timestamps = true;
+//! let speech_recognition_model = AutomaticSpeechRecognitionModel::new(config)?;
+//!
+//! let pcm_samples: Vec<i16> = vec![0; 16000 * 5];
+//! let transcription = speech_recognition_model.transcribe(&pcm_to_f32(&pcm_samples))?;
+//! println!("[{}] {}", transcription.language, transcription.text);
+//! for segment in transcription.segments {
+//!     println!("{:.2}s - {:.2}s: {}", segment.start, segment.end, segment.text);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::mps;
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+use crate::resources::ResourceProvider;
+use crate::whisper::audio::{log_mel_spectrogram, N_SAMPLES, SAMPLE_RATE};
+use crate::whisper::{WhisperConfig, WhisperForConditionalGeneration, WhisperSpecialTokens};
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use tch::nn::VarStore;
+use tch::{Device, Tensor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Speech recognition task
+pub enum SpeechRecognitionTask {
+    /// Transcription in the spoken language
+    Transcribe,
+    /// Translation to English
+    Translate,
+}
+
+/// # Configuration for AutomaticSpeechRecognitionModel
+/// Contains information regarding the model to load, the decoding settings and device to place the model on.
+pub struct AutomaticSpeechRecognitionConfig {
+    /// Model weights resource
+    pub model_resource: ModelResource,
+    /// Config resource
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource
+    pub merges_resource: Box<dyn ResourceProvider + Send>,
+    /// Language code of the audio (e.g. `en`). The language is identified by multilingual models if not provided (default: None)
+    pub language: Option<String>,
+    /// Transcription or translation to English (default: `Transcribe`)
+    pub task: SpeechRecognitionTask,
+    /// Flag indicating if the segments timestamps should be generated (default: false)
+    pub return_timestamps: bool,
+    /// Maximum number of tokens generated for each chunk of 30 seconds (default: 224)
+    pub max_new_tokens: i64,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl AutomaticSpeechRecognitionConfig {
+    /// Instantiate a new automatic speech recognition configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * model_resource - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config_resource - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab_resource - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.json)
+    /// * merges_resource - The `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt)
+    pub fn new<RC, RV, RM>(
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: RM,
+    ) -> AutomaticSpeechRecognitionConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+        RM: ResourceProvider + Send + 'static,
+    {
+        AutomaticSpeechRecognitionConfig {
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: Box::new(merges_resource),
+            language: None,
+            task: SpeechRecognitionTask::Transcribe,
+            return_timestamps: false,
+            max_new_tokens: 224,
+            device: default_device(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Segment of a transcription
+pub struct TranscriptionSegment {
+    /// Start time of the segment (seconds)
+    pub start: f64,
+    /// End time of the segment (seconds)
+    pub end: f64,
+    /// Text of the segment
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Transcription of an audio input
+pub struct Transcription {
+    /// Full transcription (or translation)
+    pub text: String,
+    /// Language code of the audio
+    pub language: String,
+    /// Probability of the language (1.0 if the language was provided)
+    pub language_probability: f64,
+    /// Segments of the transcription. Without timestamps, each chunk of 30 seconds forms a segment.
+    pub segments: Vec<TranscriptionSegment>,
+}
+
+/// # AutomaticSpeechRecognitionModel to transcribe audio
+pub struct AutomaticSpeechRecognitionModel {
+    tokenizer: TokenizerOption,
+    model: WhisperForConditionalGeneration,
+    num_mel_bins: i64,
+    language: Option<String>,
+    task: SpeechRecognitionTask,
+    return_timestamps: bool,
+    max_new_tokens: i64,
+    device: Device,
+    var_store: VarStore,
+}
+
+impl AutomaticSpeechRecognitionModel {
+    /// Build a new `AutomaticSpeechRecognitionModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `AutomaticSpeechRecognitionConfig` object containing the resource references (model, vocabulary, configuration), decoding settings and device placement (CPU/GPU)
+    pub fn new(
+        config: AutomaticSpeechRecognitionConfig,
+    ) -> Result<AutomaticSpeechRecognitionModel, RustBertError> {
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let merges_path = config.merges_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::GPT2,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        let device = config.device;
+        let weights_path = config.model_resource.get_torch_local_path()?;
+        let mut var_store = VarStore::new(device);
+        let whisper_config = WhisperConfig::from_file(config.config_resource.get_local_path()?);
+        let model = WhisperForConditionalGeneration::new(var_store.root(), &whisper_config);
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+
+        let special_tokens = model.get_special_tokens();
+        if let Some(language) = &config.language {
+            if special_tokens.multilingual && special_tokens.language_token(language).is_none() {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Language {language} is not supported by the model"
+                )));
+            }
+            if !special_tokens.multilingual && (language != "en") {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "English-only models can only transcribe English audio".to_string(),
+                ));
+            }
+        }
+        if !special_tokens.multilingual && (config.task == SpeechRecognitionTask::Translate) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "English-only models do not support translation".to_string(),
+            ));
+        }
+
+        Ok(AutomaticSpeechRecognitionModel {
+            tokenizer,
+            model,
+            num_mel_bins: whisper_config.num_mel_bins,
+            language: config.language,
+            task: config.task,
+            return_timestamps: config.return_timestamps,
+            max_new_tokens: config.max_new_tokens,
+            device,
+            var_store,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Get a reference to the underlying Whisper model.
+    pub fn get_model(&self) -> &WhisperForConditionalGeneration {
+        &self.model
+    }
+
+    /// Get a reference to the model var store.
+    pub fn get_var_store(&self) -> &VarStore {
+        &self.var_store
+    }
+
+    /// Identifies the spoken language from the first 30 seconds of audio (multilingual models only)
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - mono audio samples at 16kHz, with values in [-1, 1]
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(String, f64)>` language codes and their probabilities, sorted by decreasing probability
+    pub fn detect_language(&self, samples: &[f32]) -> Result<Vec<(String, f64)>, RustBertError> {
+        let input_features =
+            log_mel_spectrogram(samples, self.num_mel_bins, self.device).unsqueeze(0);
+        let encoder_hidden_states = self.model.encode(&input_features);
+        self.language_probabilities(&encoder_hidden_states)
+    }
+
+    /// Transcribes audio (or translates it to English)
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - mono audio samples at 16kHz, with values in [-1, 1]
+    ///
+    /// # Returns
+    ///
+    /// * `Transcription` containing the text, language and segments of the audio
+    pub fn transcribe(&self, samples: &[f32]) -> Result<Transcription, RustBertError> {
+        if samples.is_empty() {
+            return Err(RustBertError::ValueError(
+                "Cannot transcribe an empty audio input".to_string(),
+            ));
+        }
+        let chunks = samples.chunks(N_SAMPLES).collect::<Vec<&[f32]>>();
+        let input_features = Tensor::stack(
+            &chunks
+                .iter()
+                .map(|chunk| log_mel_spectrogram(chunk, self.num_mel_bins, self.device))
+                .collect::<Vec<Tensor>>(),
+            0,
+        );
+        let encoder_hidden_states = self.model.encode(&input_features);
+
+        let special_tokens = self.model.get_special_tokens();
+        let (language, language_probability) = match &self.language {
+            Some(language) => (language.clone(), 1.0),
+            None if special_tokens.multilingual => self
+                .language_probabilities(&encoder_hidden_states.narrow(0, 0, 1))?
+                .swap_remove(0),
+            None => ("en".to_string(), 1.0),
+        };
+
+        let prompt = Tensor::from_slice(&self.prompt(special_tokens, &language)).unsqueeze(0);
+        let output_ids = self.model.generate(
+            &encoder_hidden_states,
+            &prompt,
+            self.max_new_tokens,
+            self.return_timestamps,
+        )?;
+
+        let mut segments = vec![];
+        for (chunk_index, chunk) in chunks.iter().enumerate() {
+            let offset = chunk_index as f64 * (N_SAMPLES / SAMPLE_RATE) as f64;
+            let duration = chunk.len() as f64 / SAMPLE_RATE as f64;
+            let tokens = Vec::<i64>::try_from(output_ids.get(chunk_index as i64))?;
+            segments.extend(self.segments(special_tokens, &tokens, offset, duration));
+        }
+        let text = segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        Ok(Transcription {
+            text,
+            language,
+            language_probability,
+            segments,
+        })
+    }
+
+    /// Transcribes a batch of audio inputs (or translates them to English)
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - mono audio samples at 16kHz, with values in [-1, 1], for each input
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Transcription>` containing the text, language and segments of each input
+    pub fn predict<S>(&self, inputs: &[S]) -> Result<Vec<Transcription>, RustBertError>
+    where
+        S: AsRef<[f32]>,
+    {
+        inputs
+            .iter()
+            .map(|samples| self.transcribe(samples.as_ref()))
+            .collect()
+    }
+
+    fn language_probabilities(
+        &self,
+        encoder_hidden_states: &Tensor,
+    ) -> Result<Vec<(String, f64)>, RustBertError> {
+        let probabilities = self.model.detect_language(encoder_hidden_states)?;
+        let probabilities = Vec::<f64>::try_from(mps::to_double(&probabilities.get(0)))?;
+        let mut languages = self
+            .model
+            .get_special_tokens()
+            .languages()
+            .iter()
+            .zip(probabilities)
+            .map(|(language, probability)| (language.to_string(), probability))
+            .collect::<Vec<(String, f64)>>();
+        languages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        Ok(languages)
+    }
+
+    fn prompt(&self, special_tokens: &WhisperSpecialTokens, language: &str) -> Vec<i64> {
+        let mut prompt = vec![special_tokens.start_of_transcript];
+        if special_tokens.multilingual {
+            if let Some(language_token) = special_tokens.language_token(language) {
+                prompt.push(language_token);
+            }
+            prompt.push(match self.task {
+                SpeechRecognitionTask::Transcribe => special_tokens.transcribe,
+                SpeechRecognitionTask::Translate => special_tokens.translate,
+            });
+        }
+        if !self.return_timestamps {
+            prompt.push(special_tokens.no_timestamps);
+        }
+        prompt
+    }
+
+    /// Splits the generated tokens of a chunk into segments delimited by the timestamp tokens
+    fn segments(
+        &self,
+        special_tokens: &WhisperSpecialTokens,
+        tokens: &[i64],
+        offset: f64,
+        duration: f64,
+    ) -> Vec<TranscriptionSegment> {
+        let mut segments = vec![];
+        let mut start = None;
+        let mut text_tokens = vec![];
+        for token in tokens {
+            if *token == special_tokens.end_of_text {
+                break;
+            }
+            if special_tokens.is_timestamp(*token) {
+                let time = special_tokens.timestamp_seconds(*token);
+                match start {
+                    Some(start_time) if !text_tokens.is_empty() => {
+                        segments.push((start_time, time, std::mem::take(&mut text_tokens)));
+                        start = None;
+                    }
+                    _ => start = Some(time),
+                }
+            } else if *token < special_tokens.end_of_text {
+                text_tokens.push(*token);
+            }
+        }
+        if !text_tokens.is_empty() {
+            segments.push((start.unwrap_or(0.0), duration, text_tokens));
+        }
+
+        segments
+            .into_iter()
+            .filter_map(|(start, end, text_tokens)| {
+                let text = self.tokenizer.decode(&text_tokens, true, false);
+                let text = text.trim();
+                if text.is_empty() {
+                    None
+                } else {
+                    Some(TranscriptionSegment {
+                        start: offset + start,
+                        end: offset + end.min(duration),
+                        text: text.to_string(),
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = AutomaticSpeechRecognitionConfig::new(
+            ModelResource::Torch(Box::new(crate::resources::LocalResource {
+                local_path: "path/to/model.ot".into(),
+            })),
+            crate::resources::LocalResource {
+                local_path: "path/to/config.json".into(),
+            },
+            crate::resources::LocalResource {
+                local_path: "path/to/vocab.json".into(),
+            },
+            crate::resources::LocalResource {
+                local_path: "path/to/merges.txt".into(),
+            },
+        );
+        let _: Box<dyn Send> = Box::new(AutomaticSpeechRecognitionModel::new(config));
+    }
+}
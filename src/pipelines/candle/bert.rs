@@ -0,0 +1,359 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bert::BertConfig;
+use crate::{Activation, RustBertError};
+use candle_core::{DType, Device, Module, Tensor};
+use candle_nn::{embedding, layer_norm, linear, Embedding, LayerNorm, Linear, VarBuilder};
+use std::collections::HashMap;
+use std::path::Path;
+
+const LAYER_NORM_EPS: f64 = 1e-12;
+
+/// Loads the tensors of a safetensors checkpoint on a device, renaming the legacy layer normalization parameters
+/// (`gamma` and `beta`) to the names expected by the model. Returns the `VarBuilder` of the BERT encoder (prefixed by
+/// `bert.` for checkpoints of a model with a head) and the `VarBuilder` of the full checkpoint.
+pub(crate) fn load_safetensors(
+    weights_path: &Path,
+    device: &Device,
+) -> Result<(VarBuilder<'static>, VarBuilder<'static>), RustBertError> {
+    let tensors = candle_core::safetensors::load(weights_path, device)?
+        .into_iter()
+        .map(|(name, tensor)| {
+            (
+                name.replace("gamma", "weight").replace("beta", "bias"),
+                tensor,
+            )
+        })
+        .collect::<HashMap<String, Tensor>>();
+    let has_prefix = tensors
+        .keys()
+        .any(|name| name.starts_with("bert.embeddings."));
+    let var_builder = VarBuilder::from_tensors(tensors, DType::F32, device);
+    let encoder_var_builder = if has_prefix {
+        var_builder.pp("bert")
+    } else {
+        var_builder.clone()
+    };
+    Ok((encoder_var_builder, var_builder))
+}
+
+fn activation(hidden_states: &Tensor, activation: Activation) -> candle_core::Result<Tensor> {
+    match activation {
+        Activation::gelu => hidden_states.gelu_erf(),
+        Activation::gelu_new => hidden_states.gelu(),
+        Activation::relu => hidden_states.relu(),
+        Activation::swish => candle_nn::ops::silu(hidden_states),
+        Activation::tanh => hidden_states.tanh(),
+        Activation::identity => Ok(hidden_states.clone()),
+        // x * tanh(softplus(x))
+        Activation::mish => {
+            hidden_states.mul(&hidden_states.exp()?.affine(1.0, 1.0)?.log()?.tanh()?)
+        }
+    }
+}
+
+struct BertEmbeddings {
+    word_embeddings: Embedding,
+    position_embeddings: Embedding,
+    token_type_embeddings: Embedding,
+    layer_norm: LayerNorm,
+}
+
+impl BertEmbeddings {
+    fn new(var_builder: VarBuilder, config: &BertConfig) -> candle_core::Result<BertEmbeddings> {
+        let hidden_size = config.hidden_size as usize;
+        Ok(BertEmbeddings {
+            word_embeddings: embedding(
+                config.vocab_size as usize,
+                hidden_size,
+                var_builder.pp("word_embeddings"),
+            )?,
+            position_embeddings: embedding(
+                config.max_position_embeddings as usize,
+                hidden_size,
+                var_builder.pp("position_embeddings"),
+            )?,
+            token_type_embeddings: embedding(
+                config.type_vocab_size as usize,
+                hidden_size,
+                var_builder.pp("token_type_embeddings"),
+            )?,
+            layer_norm: layer_norm(hidden_size, LAYER_NORM_EPS, var_builder.pp("LayerNorm"))?,
+        })
+    }
+
+    fn forward(&self, input_ids: &Tensor, token_type_ids: &Tensor) -> candle_core::Result<Tensor> {
+        let (_, sequence_length) = input_ids.dims2()?;
+        let position_ids = Tensor::arange(0u32, sequence_length as u32, input_ids.device())?;
+        let embeddings = self
+            .word_embeddings
+            .forward(input_ids)?
+            .broadcast_add(&self.position_embeddings.forward(&position_ids)?)?
+            .broadcast_add(&self.token_type_embeddings.forward(token_type_ids)?)?;
+        self.layer_norm.forward(&embeddings)
+    }
+}
+
+struct BertLayer {
+    query: Linear,
+    key: Linear,
+    value: Linear,
+    attention_output: Linear,
+    attention_layer_norm: LayerNorm,
+    intermediate: Linear,
+    output: Linear,
+    output_layer_norm: LayerNorm,
+    activation: Activation,
+    num_attention_heads: usize,
+    head_dim: usize,
+}
+
+impl BertLayer {
+    fn new(var_builder: VarBuilder, config: &BertConfig) -> candle_core::Result<BertLayer> {
+        let hidden_size = config.hidden_size as usize;
+        let intermediate_size = config.intermediate_size as usize;
+        let attention = var_builder.pp("attention");
+        let self_attention = attention.pp("self");
+        Ok(BertLayer {
+            query: linear(hidden_size, hidden_size, self_attention.pp("query"))?,
+            key: linear(hidden_size, hidden_size, self_attention.pp("key"))?,
+            value: linear(hidden_size, hidden_size, self_attention.pp("value"))?,
+            attention_output: linear(hidden_size, hidden_size, attention.pp("output.dense"))?,
+            attention_layer_norm: layer_norm(
+                hidden_size,
+                LAYER_NORM_EPS,
+                attention.pp("output.LayerNorm"),
+            )?,
+            intermediate: linear(
+                hidden_size,
+                intermediate_size,
+                var_builder.pp("intermediate.dense"),
+            )?,
+            output: linear(
+                intermediate_size,
+                hidden_size,
+                var_builder.pp("output.dense"),
+            )?,
+            output_layer_norm: layer_norm(
+                hidden_size,
+                LAYER_NORM_EPS,
+                var_builder.pp("output.LayerNorm"),
+            )?,
+            activation: config.hidden_act,
+            num_attention_heads: config.num_attention_heads as usize,
+            head_dim: hidden_size / config.num_attention_heads as usize,
+        })
+    }
+
+    /// Splits the last dimension in attention heads: [batch, sequence, hidden] -> [batch, heads, sequence, head_dim]
+    fn split_heads(&self, hidden_states: &Tensor) -> candle_core::Result<Tensor> {
+        let (batch_size, sequence_length, _) = hidden_states.dims3()?;
+        hidden_states
+            .reshape((
+                batch_size,
+                sequence_length,
+                self.num_attention_heads,
+                self.head_dim,
+            ))?
+            .transpose(1, 2)?
+            .contiguous()
+    }
+
+    fn forward(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+    ) -> candle_core::Result<Tensor> {
+        let (batch_size, sequence_length, hidden_size) = hidden_states.dims3()?;
+        let query = self.split_heads(&self.query.forward(hidden_states)?)?;
+        let key = self.split_heads(&self.key.forward(hidden_states)?)?;
+        let value = self.split_heads(&self.value.forward(hidden_states)?)?;
+
+        let scores = query
+            .matmul(&key.t()?.contiguous()?)?
+            .affine(1.0 / (self.head_dim as f64).sqrt(), 0.0)?
+            .broadcast_add(attention_mask)?;
+        let probabilities = candle_nn::ops::softmax_last_dim(&scores)?;
+        let context = probabilities.matmul(&value)?.transpose(1, 2)?.reshape((
+            batch_size,
+            sequence_length,
+            hidden_size,
+        ))?;
+
+        let attention_output = self
+            .attention_layer_norm
+            .forward(&(self.attention_output.forward(&context)? + hidden_states)?)?;
+        let intermediate = activation(
+            &self.intermediate.forward(&attention_output)?,
+            self.activation,
+        )?;
+        self.output_layer_norm
+            .forward(&(self.output.forward(&intermediate)? + attention_output)?)
+    }
+}
+
+/// # BERT encoder running on candle tensors
+/// Embeddings and transformer layers of a BERT model, loaded from a safetensors checkpoint with the names of the
+/// Hugging Face checkpoints (`embeddings.*`, `encoder.layer.{i}.*`).
+pub struct CandleBertModel {
+    embeddings: BertEmbeddings,
+    layers: Vec<BertLayer>,
+    device: Device,
+}
+
+impl CandleBertModel {
+    /// Build a new `CandleBertModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `var_builder` - `VarBuilder` of the model weights (e.g. the first element returned by loading a checkpoint)
+    /// * `config` - `BertConfig` object defining the model architecture
+    pub fn new(
+        var_builder: VarBuilder,
+        config: &BertConfig,
+    ) -> Result<CandleBertModel, RustBertError> {
+        if config.is_decoder.unwrap_or(false) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The candle BERT model does not support decoder configurations".to_string(),
+            ));
+        }
+        let embeddings = BertEmbeddings::new(var_builder.pp("embeddings"), config)?;
+        let layers = (0..config.num_hidden_layers)
+            .map(|layer_index| {
+                BertLayer::new(
+                    var_builder.pp(format!("encoder.layer.{layer_index}")),
+                    config,
+                )
+            })
+            .collect::<candle_core::Result<Vec<BertLayer>>>()?;
+        Ok(CandleBertModel {
+            embeddings,
+            layers,
+            device: var_builder.device().clone(),
+        })
+    }
+
+    /// Device the model weights are placed on
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - input token ids of shape (*batch size*, *sequence_length*), with a `u32` data type
+    /// * `token_type_ids` - token type (segment) ids of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - mask of shape (*batch size*, *sequence_length*): positions with a mask of 0 are ignored
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` - hidden states of the last layer of shape (*batch size*, *sequence_length*, *hidden_size*)
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: &Tensor,
+    ) -> Result<Tensor, RustBertError> {
+        let (batch_size, sequence_length) = attention_mask.dims2()?;
+        // Masked positions get a large negative value added to their attention scores
+        let attention_mask = attention_mask
+            .to_dtype(DType::F32)?
+            .affine(10000.0, -10000.0)?
+            .reshape((batch_size, 1, 1, sequence_length))?;
+        let mut hidden_states = self.embeddings.forward(input_ids, token_type_ids)?;
+        for layer in &self.layers {
+            hidden_states = layer.forward(&hidden_states, &attention_mask)?;
+        }
+        Ok(hidden_states)
+    }
+}
+
+/// # BERT for sequence classification running on candle tensors
+/// BERT encoder with a pooling layer (dense layer with a tanh activation applied to the first token) and a linear
+/// classification head, loaded from the checkpoints of `BertForSequenceClassification` models.
+pub struct CandleBertForSequenceClassification {
+    bert: CandleBertModel,
+    pooler: Linear,
+    classifier: Linear,
+}
+
+impl CandleBertForSequenceClassification {
+    /// Build a new `CandleBertForSequenceClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_path` - path to the safetensors checkpoint of the model
+    /// * `config` - `BertConfig` object defining the model architecture and labels
+    /// * `device` - candle device to load the weights on
+    pub fn new(
+        weights_path: &Path,
+        config: &BertConfig,
+        device: &Device,
+    ) -> Result<CandleBertForSequenceClassification, RustBertError> {
+        let num_labels = config
+            .id2label
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "num_labels not provided in configuration".to_string(),
+                )
+            })?
+            .len();
+        let hidden_size = config.hidden_size as usize;
+        let (encoder_var_builder, var_builder) = load_safetensors(weights_path, device)?;
+        let bert = CandleBertModel::new(encoder_var_builder.clone(), config)?;
+        let pooler = linear(
+            hidden_size,
+            hidden_size,
+            encoder_var_builder.pp("pooler.dense"),
+        )?;
+        let classifier = linear(hidden_size, num_labels, var_builder.pp("classifier"))?;
+        Ok(CandleBertForSequenceClassification {
+            bert,
+            pooler,
+            classifier,
+        })
+    }
+
+    /// Device the model weights are placed on
+    pub fn device(&self) -> &Device {
+        self.bert.device()
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - input token ids of shape (*batch size*, *sequence_length*), with a `u32` data type
+    /// * `token_type_ids` - token type (segment) ids of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - mask of shape (*batch size*, *sequence_length*): positions with a mask of 0 are ignored
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` - logits of shape (*batch size*, *num_labels*)
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: &Tensor,
+    ) -> Result<Tensor, RustBertError> {
+        let hidden_states = self
+            .bert
+            .forward(input_ids, token_type_ids, attention_mask)?;
+        let pooled_output = self
+            .pooler
+            .forward(&hidden_states.narrow(1, 0, 1)?.squeeze(1)?)?
+            .tanh()?;
+        Ok(self.classifier.forward(&pooled_output)?)
+    }
+}
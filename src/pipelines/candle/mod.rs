@@ -0,0 +1,45 @@
+//! # Candle BERT classifier (experimental)
+//!
+//! This module allows running BERT-class encoders with the pure Rust [candle](https://github.com/huggingface/candle)
+//! tensor library instead of libtorch. In order to use it, the corresponding optional feature (`candle`) should be
+//! turned on. This will include the optional `candle-core` and `candle-nn` dependencies.
+//!
+//! The models are loaded from safetensors checkpoints following the Hugging Face naming conventions (for example the
+//! `model.safetensors` file of a model repository), and use the same configuration and vocabulary files as their
+//! Pytorch counterparts. The following are available:
+//! - `CandleBertModel`: BERT encoder returning the hidden states of the last layer,
+//! - `CandleBertForSequenceClassification`: BERT encoder with pooling and classification head,
+//! - `CandleSequenceClassificationModel`: sequence classification pipeline built on the above, returning the same
+//! `Label` objects as `SequenceClassificationModel`.
+//!
+//! The backend is selected per pipeline at build time by using these types in place of their libtorch counterparts.
+//! It is experimental and limited to the BERT encoders: `tch` remains a required dependency of the crate, and
+//! libtorch is still needed when building with the `candle` feature.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::candle::{
+//!     CandleSequenceClassificationConfig, CandleSequenceClassificationModel,
+//! };
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//!
+//! let config = CandleSequenceClassificationConfig::new(
+//!     LocalResource::from(PathBuf::from("path/to/model.safetensors")),
+//!     LocalResource::from(PathBuf::from("path/to/config.json")),
+//!     LocalResource::from(PathBuf::from("path/to/vocab.txt")),
+//!     true,
+//! );
+//! let model = CandleSequenceClassificationModel::new(config)?;
+//! let output = model.predict(&["This movie was a delight to watch."])?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod bert;
+mod sequence_classification;
+
+pub use bert::{CandleBertForSequenceClassification, CandleBertModel};
+pub use sequence_classification::{
+    CandleSequenceClassificationConfig, CandleSequenceClassificationModel,
+};
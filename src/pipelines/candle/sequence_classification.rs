@@ -0,0 +1,218 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bert::BertConfig;
+use crate::pipelines::candle::bert::CandleBertForSequenceClassification;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::sequence_classification::Label;
+use crate::resources::ResourceProvider;
+use crate::{Config, RustBertError};
+use candle_core::{Device, Tensor, D};
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use std::collections::HashMap;
+
+/// # Configuration for CandleSequenceClassificationModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct CandleSequenceClassificationConfig {
+    /// Model weights resource, in the safetensors format (e.g. model.safetensors)
+    pub weights_resource: Box<dyn ResourceProvider + Send>,
+    /// Config resource
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization)
+    pub strip_accents: Option<bool>,
+    /// Device to place the model on (default: CPU)
+    pub device: Device,
+}
+
+impl CandleSequenceClassificationConfig {
+    /// Instantiate a new candle sequence classification configuration for a BERT model.
+    ///
+    /// # Arguments
+    ///
+    /// * weights - The `ResourceProvider` pointing to the safetensors checkpoint to load (e.g. model.safetensors)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g. vocab.txt)
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RW, RC, RV>(
+        weights_resource: RW,
+        config_resource: RC,
+        vocab_resource: RV,
+        lower_case: bool,
+    ) -> CandleSequenceClassificationConfig
+    where
+        RW: ResourceProvider + Send + 'static,
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        CandleSequenceClassificationConfig {
+            weights_resource: Box::new(weights_resource),
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            lower_case,
+            strip_accents: None,
+            device: Device::Cpu,
+        }
+    }
+}
+
+/// # Sequence classification model running on candle tensors
+/// Counterpart of `SequenceClassificationModel` for BERT checkpoints that does not execute the model with libtorch.
+pub struct CandleSequenceClassificationModel {
+    tokenizer: TokenizerOption,
+    model: CandleBertForSequenceClassification,
+    label_mapping: HashMap<i64, String>,
+    max_length: usize,
+}
+
+impl CandleSequenceClassificationModel {
+    /// Build a new `CandleSequenceClassificationModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `CandleSequenceClassificationConfig` object containing the resource references and device
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::candle::{
+    ///     CandleSequenceClassificationConfig, CandleSequenceClassificationModel,
+    /// };
+    /// use rust_bert::resources::LocalResource;
+    /// use std::path::PathBuf;
+    ///
+    /// let config = CandleSequenceClassificationConfig::new(
+    ///     LocalResource::from(PathBuf::from("path/to/model.safetensors")),
+    ///     LocalResource::from(PathBuf::from("path/to/config.json")),
+    ///     LocalResource::from(PathBuf::from("path/to/vocab.txt")),
+    ///     true,
+    /// );
+    /// let model = CandleSequenceClassificationModel::new(config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        config: CandleSequenceClassificationConfig,
+    ) -> Result<CandleSequenceClassificationModel, RustBertError> {
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_path.to_str().unwrap(),
+            None,
+            config.lower_case,
+            config.strip_accents,
+            None,
+        )?;
+        let model_config = BertConfig::from_file(config.config_resource.get_local_path()?);
+        let label_mapping = model_config.id2label.clone().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "`id2label` mapping not provided in configuration".to_string(),
+            )
+        })?;
+        let weights_path = config.weights_resource.get_local_path()?;
+        let model =
+            CandleBertForSequenceClassification::new(&weights_path, &model_config, &config.device)?;
+        Ok(CandleSequenceClassificationModel {
+            tokenizer,
+            model,
+            label_mapping,
+            max_length: model_config.max_position_embeddings as usize,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Classify texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input texts
+    pub fn predict<S>(&self, input: &[S]) -> Result<Vec<Label>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let tokenized_input = self.tokenizer.encode_list(
+            input,
+            self.max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let sequence_length = tokenized_input
+            .iter()
+            .map(|input| input.token_ids.len())
+            .max()
+            .unwrap();
+        let pad_id = self.tokenizer.get_pad_id().unwrap_or(0) as u32;
+        let mut input_ids = Vec::with_capacity(input.len() * sequence_length);
+        let mut token_type_ids = Vec::with_capacity(input.len() * sequence_length);
+        let mut attention_mask = Vec::with_capacity(input.len() * sequence_length);
+        for input in &tokenized_input {
+            let padding = sequence_length - input.token_ids.len();
+            input_ids.extend(input.token_ids.iter().map(|id| *id as u32));
+            input_ids.extend(std::iter::repeat(pad_id).take(padding));
+            token_type_ids.extend(input.segment_ids.iter().map(|id| *id as u32));
+            token_type_ids.extend(std::iter::repeat(0).take(padding));
+            attention_mask.extend(std::iter::repeat(1u32).take(input.token_ids.len()));
+            attention_mask.extend(std::iter::repeat(0).take(padding));
+        }
+        let shape = (input.len(), sequence_length);
+        let device = self.model.device();
+        let logits = self.model.forward(
+            &Tensor::from_vec(input_ids, shape, device)?,
+            &Tensor::from_vec(token_type_ids, shape, device)?,
+            &Tensor::from_vec(attention_mask, shape, device)?,
+        )?;
+        let probabilities = candle_nn::ops::softmax(&logits, D::Minus1)?.to_vec2::<f32>()?;
+
+        Ok(probabilities
+            .into_iter()
+            .enumerate()
+            .map(|(sentence_idx, probabilities)| {
+                let (id, score) =
+                    probabilities
+                        .iter()
+                        .enumerate()
+                        .fold((0, f32::MIN), |best, (id, score)| {
+                            if *score > best.1 {
+                                (id, *score)
+                            } else {
+                                best
+                            }
+                        });
+                let id = id as i64;
+                Label {
+                    text: self
+                        .label_mapping
+                        .get(&id)
+                        .cloned()
+                        .unwrap_or_else(|| format!("LABEL_{id}")),
+                    score: score as f64,
+                    id,
+                    sentence: sentence_idx,
+                }
+            })
+            .collect())
+    }
+}
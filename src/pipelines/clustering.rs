@@ -0,0 +1,492 @@
+// Copyright 2019-present, the HuggingFace Inc. team
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Text clustering and topic discovery
+//! Groups documents with similar meaning by clustering their sentence embeddings. Two algorithms are available:
+//! - k-means (spherical, using cosine similarity) when the number of clusters is known in advance
+//! - community detection, grouping documents whose similarity with a community center exceeds a threshold.
+//!   Documents that do not belong to any community large enough are left unassigned.
+//!
+//! The clustering functions work on any list of `Embedding`, for example generated with a `SentenceEmbeddingsModel`.
+//! The `TopicModel` combines a sentence embeddings model, a clustering algorithm and the keyword extraction pipeline
+//! to label each cluster with its most representative keywords.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::clustering::TopicModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let topic_model = TopicModel::new(Default::default())?;
+//!
+//! let input = [
+//!     "The striker scored twice in the second half.",
+//!     "The goalkeeper saved a penalty in the final minutes.",
+//!     "The home team won the league after a late goal.",
+//!     "The central bank raised interest rates by a quarter point.",
+//!     "Inflation slowed for a third consecutive month.",
+//!     "Bond yields fell after the rate decision.",
+//! ];
+//! let topics = topic_model.discover_topics(&input)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::pipelines::keywords_extraction::{
+    Keyword, KeywordExtractionConfig, KeywordExtractionModel,
+};
+use crate::pipelines::sentence_embeddings::Embedding;
+use crate::RustBertError;
+use std::cmp::Ordering;
+
+/// # Cluster of embeddings
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    /// Indices of the embeddings belonging to the cluster
+    pub members: Vec<usize>,
+    /// Normalized centroid of the cluster
+    pub centroid: Embedding,
+}
+
+/// # Clustering algorithm variants
+#[derive(Debug, Clone)]
+pub enum ClusteringAlgorithm {
+    /// Spherical k-means, assigning each embedding to the centroid with the highest cosine similarity.
+    /// Centroids are initialized deterministically with the farthest-point heuristic.
+    KMeans {
+        /// Number of clusters to create
+        num_clusters: usize,
+        /// Maximum number of assignment / update iterations
+        max_iterations: usize,
+    },
+    /// Community detection: a community is made of all embeddings with a cosine similarity to a central embedding
+    /// above `threshold`. Communities are selected greedily by decreasing size and may not overlap.
+    CommunityDetection {
+        /// Minimum cosine similarity with the community center
+        threshold: f32,
+        /// Minimum number of members for a community to be returned
+        min_community_size: usize,
+    },
+}
+
+impl ClusteringAlgorithm {
+    /// Cluster a list of embeddings.
+    ///
+    /// # Arguments
+    ///
+    /// * `embeddings` - slice of embeddings sharing the same dimension
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Cluster>, RustBertError>` clusters sorted by decreasing size
+    pub fn cluster(&self, embeddings: &[Embedding]) -> Result<Vec<Cluster>, RustBertError> {
+        match *self {
+            ClusteringAlgorithm::KMeans {
+                num_clusters,
+                max_iterations,
+            } => kmeans(embeddings, num_clusters, max_iterations),
+            ClusteringAlgorithm::CommunityDetection {
+                threshold,
+                min_community_size,
+            } => community_detection(embeddings, threshold, min_community_size),
+        }
+    }
+}
+
+fn normalize(embedding: &[f32]) -> Embedding {
+    let norm = embedding
+        .iter()
+        .map(|value| value * value)
+        .sum::<f32>()
+        .sqrt();
+    if norm > 0.0 {
+        embedding.iter().map(|value| value / norm).collect()
+    } else {
+        embedding.to_vec()
+    }
+}
+
+fn dot(left: &[f32], right: &[f32]) -> f32 {
+    left.iter().zip(right).map(|(l, r)| l * r).sum()
+}
+
+fn normalize_embeddings(embeddings: &[Embedding]) -> Result<Vec<Embedding>, RustBertError> {
+    let dim = embeddings.first().map_or(0, |embedding| embedding.len());
+    if embeddings.iter().any(|embedding| embedding.len() != dim) {
+        return Err(RustBertError::ValueError(
+            "All embeddings must have the same dimension".to_string(),
+        ));
+    }
+    Ok(embeddings
+        .iter()
+        .map(|embedding| normalize(embedding))
+        .collect())
+}
+
+fn centroid(embeddings: &[Embedding], members: &[usize]) -> Embedding {
+    let mut sum = vec![0f32; embeddings[members[0]].len()];
+    for &member in members {
+        for (total, value) in sum.iter_mut().zip(&embeddings[member]) {
+            *total += value;
+        }
+    }
+    normalize(&sum)
+}
+
+fn sort_clusters(clusters: &mut [Cluster]) {
+    clusters.sort_by(|left, right| {
+        right
+            .members
+            .len()
+            .cmp(&left.members.len())
+            .then(left.members[0].cmp(&right.members[0]))
+    });
+}
+
+/// Cluster embeddings with spherical k-means (cosine similarity).
+///
+/// # Arguments
+///
+/// * `embeddings` - slice of embeddings sharing the same dimension
+/// * `num_clusters` - number of clusters, must be between 1 and the number of embeddings
+/// * `max_iterations` - maximum number of assignment / update iterations
+///
+/// # Returns
+///
+/// * `Result<Vec<Cluster>, RustBertError>` non-empty clusters sorted by decreasing size
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::clustering::kmeans;
+///
+/// let embeddings = vec![vec![1.0, 0.1], vec![0.9, 0.0], vec![0.0, 1.0], vec![0.1, 0.8]];
+/// let clusters = kmeans(&embeddings, 2, 100)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn kmeans(
+    embeddings: &[Embedding],
+    num_clusters: usize,
+    max_iterations: usize,
+) -> Result<Vec<Cluster>, RustBertError> {
+    if num_clusters == 0 || num_clusters > embeddings.len() {
+        return Err(RustBertError::ValueError(format!(
+            "The number of clusters ({num_clusters}) must be between 1 and the number of embeddings ({})",
+            embeddings.len()
+        )));
+    }
+    let embeddings = normalize_embeddings(embeddings)?;
+
+    // Farthest-point initialization: each new centroid is the embedding least similar to the existing centroids
+    let mut centroids = vec![embeddings[0].clone()];
+    let mut max_similarities = embeddings
+        .iter()
+        .map(|embedding| dot(embedding, &centroids[0]))
+        .collect::<Vec<f32>>();
+    while centroids.len() < num_clusters {
+        let (next_index, _) = max_similarities
+            .iter()
+            .enumerate()
+            .min_by(|(_, left), (_, right)| left.partial_cmp(right).unwrap_or(Ordering::Equal))
+            .unwrap();
+        let next_centroid = embeddings[next_index].clone();
+        for (similarity, embedding) in max_similarities.iter_mut().zip(&embeddings) {
+            *similarity = similarity.max(dot(embedding, &next_centroid));
+        }
+        centroids.push(next_centroid);
+    }
+
+    let mut assignments = vec![usize::MAX; embeddings.len()];
+    for _ in 0..max_iterations.max(1) {
+        let mut changed = false;
+        for (assignment, embedding) in assignments.iter_mut().zip(&embeddings) {
+            let (closest, _) = centroids
+                .iter()
+                .map(|centroid| dot(embedding, centroid))
+                .enumerate()
+                .max_by(|(_, left), (_, right)| left.partial_cmp(right).unwrap_or(Ordering::Equal))
+                .unwrap();
+            if *assignment != closest {
+                *assignment = closest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        for (cluster_index, cluster_centroid) in centroids.iter_mut().enumerate() {
+            let members = assignments
+                .iter()
+                .enumerate()
+                .filter_map(|(index, &assignment)| (assignment == cluster_index).then_some(index))
+                .collect::<Vec<usize>>();
+            // Empty clusters keep their previous centroid
+            if !members.is_empty() {
+                *cluster_centroid = centroid(&embeddings, &members);
+            }
+        }
+    }
+
+    let mut clusters = centroids
+        .into_iter()
+        .enumerate()
+        .filter_map(|(cluster_index, centroid)| {
+            let members = assignments
+                .iter()
+                .enumerate()
+                .filter_map(|(index, &assignment)| (assignment == cluster_index).then_some(index))
+                .collect::<Vec<usize>>();
+            (!members.is_empty()).then_some(Cluster { members, centroid })
+        })
+        .collect::<Vec<Cluster>>();
+    sort_clusters(&mut clusters);
+    Ok(clusters)
+}
+
+/// Cluster embeddings with community detection. Each embedding with at least `min_community_size` neighbours
+/// (including itself) with a cosine similarity above `threshold` is the center of a candidate community.
+/// Candidate communities are selected by decreasing size, skipping communities overlapping an already selected one.
+/// Embeddings that do not belong to any selected community are not assigned to a cluster.
+///
+/// # Arguments
+///
+/// * `embeddings` - slice of embeddings sharing the same dimension
+/// * `threshold` - minimum cosine similarity with the community center
+/// * `min_community_size` - minimum number of members for a community
+///
+/// # Returns
+///
+/// * `Result<Vec<Cluster>, RustBertError>` communities sorted by decreasing size
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::clustering::community_detection;
+///
+/// let embeddings = vec![vec![1.0, 0.1], vec![0.9, 0.0], vec![0.0, 1.0], vec![0.1, 0.8]];
+/// let clusters = community_detection(&embeddings, 0.9, 2)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn community_detection(
+    embeddings: &[Embedding],
+    threshold: f32,
+    min_community_size: usize,
+) -> Result<Vec<Cluster>, RustBertError> {
+    let embeddings = normalize_embeddings(embeddings)?;
+
+    let mut candidates = embeddings
+        .iter()
+        .map(|center| {
+            let mut neighbours = embeddings
+                .iter()
+                .enumerate()
+                .map(|(index, embedding)| (index, dot(center, embedding)))
+                .filter(|(_, similarity)| *similarity >= threshold)
+                .collect::<Vec<(usize, f32)>>();
+            neighbours.sort_by(|(_, left), (_, right)| {
+                right.partial_cmp(left).unwrap_or(Ordering::Equal)
+            });
+            neighbours
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect::<Vec<usize>>()
+        })
+        .filter(|members| members.len() >= min_community_size.max(1))
+        .collect::<Vec<Vec<usize>>>();
+    candidates.sort_by_key(|members| std::cmp::Reverse(members.len()));
+
+    let mut assigned = vec![false; embeddings.len()];
+    let mut clusters = Vec::new();
+    for members in candidates {
+        if members.iter().any(|&member| assigned[member]) {
+            continue;
+        }
+        for &member in &members {
+            assigned[member] = true;
+        }
+        let centroid = centroid(&embeddings, &members);
+        clusters.push(Cluster { members, centroid });
+    }
+    sort_clusters(&mut clusters);
+    Ok(clusters)
+}
+
+/// # Topic discovered by a `TopicModel`
+#[derive(Debug, Clone)]
+pub struct Topic {
+    /// Indices of the input documents belonging to the topic
+    pub members: Vec<usize>,
+    /// Keywords labeling the topic, extracted from the concatenation of the member documents
+    pub keywords: Vec<Keyword>,
+}
+
+/// # Configuration for topic discovery
+pub struct TopicModelConfig<'a> {
+    /// `KeywordExtractionConfig` for the keyword extraction pipeline labeling the clusters. Its sentence embeddings
+    /// model is also used to embed the documents for clustering.
+    pub keyword_extraction_config: KeywordExtractionConfig<'a>,
+    /// `ClusteringAlgorithm` used to group the documents
+    pub algorithm: ClusteringAlgorithm,
+}
+
+impl<'a> TopicModelConfig<'a> {
+    /// Instantiate a new topic discovery configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword_extraction_config` - `KeywordExtractionConfig` for the keyword extraction pipeline labeling the clusters
+    /// * `algorithm` - `ClusteringAlgorithm` used to group the documents
+    pub fn new(
+        keyword_extraction_config: KeywordExtractionConfig<'a>,
+        algorithm: ClusteringAlgorithm,
+    ) -> TopicModelConfig<'a> {
+        TopicModelConfig {
+            keyword_extraction_config,
+            algorithm,
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+impl Default for TopicModelConfig<'_> {
+    fn default() -> Self {
+        TopicModelConfig::new(
+            KeywordExtractionConfig {
+                ngram_range: (1, 2),
+                ..Default::default()
+            },
+            ClusteringAlgorithm::CommunityDetection {
+                threshold: 0.5,
+                min_community_size: 2,
+            },
+        )
+    }
+}
+
+/// # TopicModel for unsupervised topic discovery over a set of documents
+pub struct TopicModel<'a> {
+    keyword_extraction_model: KeywordExtractionModel<'a>,
+    algorithm: ClusteringAlgorithm,
+}
+
+impl<'a> TopicModel<'a> {
+    /// Build a new `TopicModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TopicModelConfig` object containing the keyword extraction configuration and clustering algorithm
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::clustering::TopicModel;
+    ///
+    /// let topic_model = TopicModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: TopicModelConfig<'a>) -> Result<TopicModel<'a>, RustBertError> {
+        let keyword_extraction_model =
+            KeywordExtractionModel::new(config.keyword_extraction_config)?;
+        Ok(TopicModel {
+            keyword_extraction_model,
+            algorithm: config.algorithm,
+        })
+    }
+
+    /// Get a reference to the underlying keyword extraction model.
+    pub fn get_keyword_extraction_model(&self) -> &KeywordExtractionModel<'a> {
+        &self.keyword_extraction_model
+    }
+
+    /// Discover topics in a set of documents. The documents are embedded and clustered, and each cluster
+    /// is labeled with the keywords extracted from the concatenation of its documents (truncated to the maximum
+    /// length of the sentence embeddings model).
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - slice of string-like documents
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Topic>, RustBertError>` topics sorted by decreasing number of documents
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::clustering::TopicModel;
+    ///
+    /// let topic_model = TopicModel::new(Default::default())?;
+    /// let input = [
+    ///     "The striker scored twice in the second half.",
+    ///     "The goalkeeper saved a penalty in the final minutes.",
+    ///     "The central bank raised interest rates by a quarter point.",
+    ///     "Inflation slowed for a third consecutive month.",
+    /// ];
+    /// let topics = topic_model.discover_topics(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn discover_topics<S>(&self, inputs: &[S]) -> Result<Vec<Topic>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let embeddings = self
+            .keyword_extraction_model
+            .sentence_embeddings_model
+            .encode(inputs)?;
+        let clusters = self.algorithm.cluster(&embeddings)?;
+        if clusters.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let cluster_texts = clusters
+            .iter()
+            .map(|cluster| {
+                cluster
+                    .members
+                    .iter()
+                    .map(|&member| inputs[member].as_ref())
+                    .collect::<Vec<&str>>()
+                    .join("\n")
+            })
+            .collect::<Vec<String>>();
+        let keywords = self.keyword_extraction_model.predict(&cluster_texts)?;
+
+        Ok(clusters
+            .into_iter()
+            .zip(keywords)
+            .map(|(cluster, keywords)| Topic {
+                members: cluster.members,
+                keywords,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = TopicModelConfig::default();
+        let _: Box<dyn Send> = Box::new(TopicModel::new(config));
+    }
+}
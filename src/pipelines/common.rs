@@ -16,32 +16,78 @@
 //! generic pipelines. The model component is defined in the generic pipeline itself as the
 //! pre-processing, forward pass and postprocessing differs between pipelines while basic config and
 //! tokenization objects don't.
+#[cfg(feature = "albert")]
 use crate::albert::AlbertConfig;
+#[cfg(feature = "bart")]
 use crate::bart::BartConfig;
+#[cfg(feature = "bert")]
 use crate::bert::BertConfig;
-use crate::common::error::RustBertError;
+#[cfg(feature = "bigbird")]
+use crate::bigbird::BigBirdConfig;
+#[cfg(feature = "bloom")]
+use crate::bloom::BloomConfig;
+use crate::common::error::{InputError, RustBertError};
+#[cfg(feature = "deberta")]
 use crate::deberta::DebertaConfig;
+#[cfg(feature = "deberta-v2")]
 use crate::deberta_v2::DebertaV2Config;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertConfig;
+#[cfg(feature = "electra")]
 use crate::electra::ElectraConfig;
+#[cfg(feature = "falcon")]
+use crate::falcon::FalconConfig;
+#[cfg(feature = "fnet")]
 use crate::fnet::FNetConfig;
+#[cfg(feature = "gpt2")]
 use crate::gpt2::Gpt2Config;
+#[cfg(feature = "gpt-j")]
 use crate::gpt_j::GptJConfig;
+#[cfg(feature = "gpt-neo")]
 use crate::gpt_neo::GptNeoConfig;
+#[cfg(feature = "jina-bert")]
+use crate::jina_bert::JinaBertConfig;
+#[cfg(feature = "llama")]
+use crate::llama::LlamaConfig;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerConfig;
+#[cfg(feature = "longt5")]
 use crate::longt5::LongT5Config;
+#[cfg(feature = "m2m-100")]
 use crate::m2m_100::M2M100Config;
+#[cfg(feature = "marian")]
 use crate::marian::MarianConfig;
+#[cfg(feature = "mbart")]
 use crate::mbart::MBartConfig;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertConfig;
+#[cfg(feature = "modernbert")]
+use crate::modernbert::ModernBertConfig;
+#[cfg(feature = "nomic-bert")]
+use crate::nomic_bert::NomicBertConfig;
+#[cfg(feature = "openai-gpt")]
 use crate::openai_gpt::OpenAiGptConfig;
+#[cfg(feature = "opt")]
+use crate::opt::OptConfig;
+#[cfg(feature = "pegasus")]
 use crate::pegasus::PegasusConfig;
+#[cfg(feature = "phi")]
+use crate::phi::PhiConfig;
 use crate::pipelines::translation::Language;
+#[cfg(feature = "prophetnet")]
 use crate::prophetnet::ProphetNetConfig;
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerConfig;
 use crate::resources::{Resource, ResourceProvider};
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaConfig;
+#[cfg(feature = "starcoder2")]
+use crate::starcoder2::StarCoder2Config;
+#[cfg(feature = "t5")]
 use crate::t5::T5Config;
+#[cfg(feature = "tapas")]
+use crate::tapas::TapasConfig;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetConfig;
 use crate::Config;
 use rust_tokenizers::tokenizer::{
@@ -158,6 +204,24 @@ pub(crate) fn get_device(_model_resource: ModelResource, device: Device) -> Devi
     device
 }
 
+/// Checks that the label ids of a classification head are the contiguous range `0..num_labels`, so that every
+/// output of the head maps to a label.
+pub(crate) fn check_label_mapping(
+    label_mapping: &HashMap<i64, String>,
+) -> Result<(), RustBertError> {
+    let num_labels = label_mapping.len();
+    let max_label_id = label_mapping.keys().copied().max().unwrap_or(-1);
+    let min_label_id = label_mapping.keys().copied().min().unwrap_or(0);
+    if num_labels == 0 || min_label_id < 0 || max_label_id as usize >= num_labels {
+        return Err(InputError::LabelCountMismatch {
+            num_labels,
+            max_label_id,
+        }
+        .into());
+    }
+    Ok(())
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
 /// # Identifies the type of model
 pub enum ModelType {
@@ -187,6 +251,10 @@ pub enum ModelType {
     Reformer,
     ProphetNet,
     Longformer,
+    #[serde(alias = "big_bird")]
+    BigBird,
+    #[serde(alias = "tapas")]
+    Tapas,
     Pegasus,
     GPTNeo,
     MBart,
@@ -194,6 +262,24 @@ pub enum ModelType {
     #[serde(alias = "m2m100")]
     NLLB,
     FNet,
+    #[serde(alias = "jina_bert")]
+    JinaBert,
+    #[serde(alias = "nomic_bert")]
+    NomicBert,
+    #[serde(alias = "modernbert")]
+    ModernBert,
+    #[serde(alias = "starcoder2")]
+    StarCoder2,
+    #[serde(alias = "llama", alias = "mistral")]
+    Llama,
+    #[serde(alias = "falcon", alias = "RefinedWeb", alias = "RefinedWebModel")]
+    Falcon,
+    #[serde(alias = "bloom")]
+    Bloom,
+    #[serde(alias = "opt")]
+    OPT,
+    #[serde(alias = "phi")]
+    Phi,
     #[cfg(feature = "onnx")]
     ONNX,
 }
@@ -201,53 +287,110 @@ pub enum ModelType {
 /// # Abstraction that holds a model configuration, can be of any of the supported models
 pub enum ConfigOption {
     /// Bart configuration
+    #[cfg(feature = "bart")]
     Bart(BartConfig),
     /// Bert configuration
+    #[cfg(feature = "bert")]
     Bert(BertConfig),
     /// DistilBert configuration
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertConfig),
     /// DeBERTa configuration
+    #[cfg(feature = "deberta")]
     Deberta(DebertaConfig),
     /// DeBERTa V2 configuration
+    #[cfg(feature = "deberta-v2")]
     DebertaV2(DebertaV2Config),
     /// Electra configuration
+    #[cfg(feature = "electra")]
     Electra(ElectraConfig),
     /// Marian configuration
+    #[cfg(feature = "marian")]
     Marian(MarianConfig),
     /// MobileBert configuration
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertConfig),
     /// OpenAI GPT configuration
+    #[cfg(feature = "openai-gpt")]
     OpenAiGpt(OpenAiGptConfig),
     /// T5 configuration
+    #[cfg(feature = "t5")]
     T5(T5Config),
     /// LongT5 configuration
+    #[cfg(feature = "longt5")]
     LongT5(LongT5Config),
     /// Albert configuration
+    #[cfg(feature = "albert")]
     Albert(AlbertConfig),
     /// XLNet configuration
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetConfig),
     /// GPT2 configuration
+    #[cfg(feature = "gpt2")]
     GPT2(Gpt2Config),
     /// GPT-J configuration
+    #[cfg(feature = "gpt-j")]
     GPTJ(GptJConfig),
     /// Reformer configuration
+    #[cfg(feature = "reformer")]
     Reformer(ReformerConfig),
     /// RoBERTa configuration
+    #[cfg(feature = "roberta")]
     Roberta(RobertaConfig),
     /// ProphetNet configuration
+    #[cfg(feature = "prophetnet")]
     ProphetNet(ProphetNetConfig),
     /// Longformer configuration
+    #[cfg(feature = "longformer")]
     Longformer(LongformerConfig),
+    /// BigBird configuration
+    #[cfg(feature = "bigbird")]
+    BigBird(BigBirdConfig),
+    /// TAPAS configuration
+    #[cfg(feature = "tapas")]
+    Tapas(TapasConfig),
     /// Pegasus configuration
+    #[cfg(feature = "pegasus")]
     Pegasus(PegasusConfig),
     /// GPT-Neo configuration
+    #[cfg(feature = "gpt-neo")]
     GPTNeo(GptNeoConfig),
     /// MBart configuration
+    #[cfg(feature = "mbart")]
     MBart(MBartConfig),
     /// M2M100 configuration
+    #[cfg(feature = "m2m-100")]
     M2M100(M2M100Config),
     /// FNet configuration
+    #[cfg(feature = "fnet")]
     FNet(FNetConfig),
+    /// Jina BERT configuration
+    #[cfg(feature = "jina-bert")]
+    JinaBert(JinaBertConfig),
+    /// Nomic BERT configuration
+    #[cfg(feature = "nomic-bert")]
+    NomicBert(NomicBertConfig),
+    /// ModernBERT configuration
+    #[cfg(feature = "modernbert")]
+    ModernBert(ModernBertConfig),
+    /// StarCoder2 configuration
+    #[cfg(feature = "starcoder2")]
+    StarCoder2(StarCoder2Config),
+    /// LLaMA (and Mistral) configuration
+    #[cfg(feature = "llama")]
+    Llama(LlamaConfig),
+    /// BLOOM configuration
+    #[cfg(feature = "bloom")]
+    Bloom(BloomConfig),
+    /// OPT configuration
+    #[cfg(feature = "opt")]
+    OPT(OptConfig),
+    /// Phi configuration
+    #[cfg(feature = "phi")]
+    Phi(PhiConfig),
+    /// Falcon configuration
+    #[cfg(feature = "falcon")]
+    Falcon(FalconConfig),
     /// ONNX Model configuration
     #[cfg(feature = "onnx")]
     ONNX(ONNXModelConfig),
@@ -302,105 +445,197 @@ impl ConfigOption {
     /// Interface method to load a configuration from file
     pub fn from_file<P: AsRef<Path>>(model_type: ModelType, path: P) -> Self {
         match model_type {
+            #[cfg(feature = "bart")]
             ModelType::Bart => ConfigOption::Bart(BartConfig::from_file(path)),
+            #[cfg(feature = "bert")]
             ModelType::Bert => ConfigOption::Bert(BertConfig::from_file(path)),
+            #[cfg(feature = "deberta")]
             ModelType::Deberta => ConfigOption::Deberta(DebertaConfig::from_file(path)),
+            #[cfg(feature = "deberta-v2")]
             ModelType::DebertaV2 => ConfigOption::DebertaV2(DebertaV2Config::from_file(path)),
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => ConfigOption::DistilBert(DistilBertConfig::from_file(path)),
+            #[cfg(feature = "electra")]
             ModelType::Electra => ConfigOption::Electra(ElectraConfig::from_file(path)),
+            #[cfg(feature = "marian")]
             ModelType::Marian => ConfigOption::Marian(MarianConfig::from_file(path)),
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => ConfigOption::MobileBert(MobileBertConfig::from_file(path)),
+            #[cfg(feature = "t5")]
             ModelType::T5 => ConfigOption::T5(T5Config::from_file(path)),
+            #[cfg(feature = "longt5")]
             ModelType::LongT5 => ConfigOption::LongT5(LongT5Config::from_file(path)),
+            #[cfg(feature = "albert")]
             ModelType::Albert => ConfigOption::Albert(AlbertConfig::from_file(path)),
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => ConfigOption::XLNet(XLNetConfig::from_file(path)),
+            #[cfg(feature = "gpt2")]
             ModelType::GPT2 => ConfigOption::GPT2(Gpt2Config::from_file(path)),
+            #[cfg(feature = "gpt-j")]
             ModelType::GPTJ => ConfigOption::GPTJ(GptJConfig::from_file(path)),
+            #[cfg(feature = "gpt-neo")]
             ModelType::GPTNeo => ConfigOption::GPTNeo(GptNeoConfig::from_file(path)),
+            #[cfg(feature = "openai-gpt")]
             ModelType::OpenAiGpt => ConfigOption::OpenAiGpt(OpenAiGptConfig::from_file(path)),
+            #[cfg(feature = "reformer")]
             ModelType::Reformer => ConfigOption::Reformer(ReformerConfig::from_file(path)),
+            #[cfg(feature = "prophetnet")]
             ModelType::ProphetNet => ConfigOption::ProphetNet(ProphetNetConfig::from_file(path)),
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => ConfigOption::Longformer(LongformerConfig::from_file(path)),
+            #[cfg(feature = "bigbird")]
+            ModelType::BigBird => ConfigOption::BigBird(BigBirdConfig::from_file(path)),
+            #[cfg(feature = "tapas")]
+            ModelType::Tapas => ConfigOption::Tapas(TapasConfig::from_file(path)),
+            #[cfg(feature = "pegasus")]
             ModelType::Pegasus => ConfigOption::Pegasus(PegasusConfig::from_file(path)),
+            #[cfg(feature = "roberta")]
             ModelType::Roberta | ModelType::XLMRoberta => {
                 ConfigOption::Roberta(RobertaConfig::from_file(path))
             }
+            #[cfg(feature = "mbart")]
             ModelType::MBart => ConfigOption::MBart(MBartConfig::from_file(path)),
+            #[cfg(feature = "m2m-100")]
             ModelType::M2M100 | ModelType::NLLB => {
                 ConfigOption::M2M100(M2M100Config::from_file(path))
             }
+            #[cfg(feature = "fnet")]
             ModelType::FNet => ConfigOption::FNet(FNetConfig::from_file(path)),
+            #[cfg(feature = "jina-bert")]
+            ModelType::JinaBert => ConfigOption::JinaBert(JinaBertConfig::from_file(path)),
+            #[cfg(feature = "nomic-bert")]
+            ModelType::NomicBert => ConfigOption::NomicBert(NomicBertConfig::from_file(path)),
+            #[cfg(feature = "modernbert")]
+            ModelType::ModernBert => ConfigOption::ModernBert(ModernBertConfig::from_file(path)),
+            #[cfg(feature = "starcoder2")]
+            ModelType::StarCoder2 => ConfigOption::StarCoder2(StarCoder2Config::from_file(path)),
+            #[cfg(feature = "llama")]
+            ModelType::Llama => ConfigOption::Llama(LlamaConfig::from_file(path)),
+            #[cfg(feature = "bloom")]
+            ModelType::Bloom => ConfigOption::Bloom(BloomConfig::from_file(path)),
+            #[cfg(feature = "opt")]
+            ModelType::OPT => ConfigOption::OPT(OptConfig::from_file(path)),
+            #[cfg(feature = "phi")]
+            ModelType::Phi => ConfigOption::Phi(PhiConfig::from_file(path)),
+            #[cfg(feature = "falcon")]
+            ModelType::Falcon => ConfigOption::Falcon(FalconConfig::from_file(path)),
             #[cfg(feature = "onnx")]
             ModelType::ONNX => ConfigOption::ONNX(ONNXModelConfig::from_file(path)),
+            #[allow(unreachable_patterns)]
+            _ => panic!(
+                "Support for {model_type:?} models is not enabled, please activate the corresponding cargo feature"
+            ),
         }
     }
 
     pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "bert")]
             Self::Bert(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "deberta")]
             Self::Deberta(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "electra")]
             Self::Electra(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "marian")]
             Self::Marian(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "albert")]
             Self::Albert(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "xlnet")]
             Self::XLNet(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "longformer")]
             Self::Longformer(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(config) => config
+                .id2label
+                .as_ref()
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "tapas")]
+            Self::Tapas(config) => config
+                .id2label
+                .as_ref()
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "fnet")]
             Self::FNet(config) => config
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(config) => config
+                .id2label
+                .as_ref()
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(config) => config
+                .id2label
+                .as_ref()
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(config) => config
+                .id2label
+                .as_ref()
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "roberta")]
             Self::Roberta(config) => config
                 .id2label
                 .as_ref()
@@ -410,103 +645,314 @@ impl ConfigOption {
                 .id2label
                 .as_ref()
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "t5")]
             Self::T5(_) => panic!("T5 does not use a label mapping"),
+            #[cfg(feature = "longt5")]
             Self::LongT5(_) => panic!("LongT5 does not use a label mapping"),
+            #[cfg(feature = "openai-gpt")]
             Self::OpenAiGpt(_) => panic!("OpenAI GPT does not use a label mapping"),
+            #[cfg(feature = "gpt2")]
             Self::GPT2(_) => panic!("GPT2 does not use a label mapping"),
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(_) => panic!("GPT-J does not use a label mapping"),
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(_) => panic!("GPT-Neo does not use a label mapping"),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(_) => panic!("StarCoder2 does not use a label mapping"),
+            #[cfg(feature = "llama")]
+            Self::Llama(_) => panic!("LLaMA does not use a label mapping"),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(_) => panic!("BLOOM does not use a label mapping"),
+            #[cfg(feature = "opt")]
+            Self::OPT(_) => panic!("OPT does not use a label mapping"),
+            #[cfg(feature = "phi")]
+            Self::Phi(_) => panic!("Phi does not use a label mapping"),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(_) => panic!("Falcon does not use a label mapping"),
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(_) => panic!("Pegasus does not use a label mapping"),
         }
     }
 
     pub fn get_max_len(&self) -> Option<i64> {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "bert")]
             Self::Bert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "deberta")]
             Self::Deberta(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "electra")]
             Self::Electra(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "marian")]
             Self::Marian(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "t5")]
             Self::T5(_) => None,
+            #[cfg(feature = "longt5")]
             Self::LongT5(_) => None,
+            #[cfg(feature = "albert")]
             Self::Albert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => None,
+            #[cfg(feature = "gpt2")]
             Self::GPT2(config) => Some(config.n_positions),
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(config) => Some(config.n_positions),
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "longformer")]
             Self::Longformer(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "tapas")]
+            Self::Tapas(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "openai-gpt")]
             Self::OpenAiGpt(config) => Some(config.n_positions),
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "fnet")]
             Self::FNet(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(config) => Some(config.n_positions),
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "llama")]
+            Self::Llama(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(config) => config.max_position_embeddings,
+            #[cfg(feature = "opt")]
+            Self::OPT(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "phi")]
+            Self::Phi(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(config) => Some(config.max_position_embeddings()),
+            #[cfg(feature = "roberta")]
             Self::Roberta(config) => Some(config.max_position_embeddings),
             #[cfg(feature = "onnx")]
             Self::ONNX(config) => config.max_position_embeddings,
         }
     }
 
+    /// Enables the output of the attention weights of every layer, for the models supporting it
+    pub(crate) fn enable_output_attentions(&mut self) -> Result<(), RustBertError> {
+        match self {
+            #[cfg(feature = "bert")]
+            Self::Bert(config) => config.output_attentions = Some(true),
+            #[cfg(feature = "roberta")]
+            Self::Roberta(config) => config.output_attentions = Some(true),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(config) => config.output_attentions = Some(true),
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "Attention outputs are only supported for BERT, RoBERTa and DistilBERT models"
+                        .to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_vocab_size(&self) -> i64 {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => config.vocab_size,
+            #[cfg(feature = "bert")]
             Self::Bert(config) => config.vocab_size,
+            #[cfg(feature = "deberta")]
             Self::Deberta(config) => config.vocab_size,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(config) => config.vocab_size,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(config) => config.vocab_size,
+            #[cfg(feature = "electra")]
             Self::Electra(config) => config.vocab_size,
+            #[cfg(feature = "marian")]
             Self::Marian(config) => config.vocab_size,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(config) => config.vocab_size,
+            #[cfg(feature = "t5")]
             Self::T5(config) => config.vocab_size,
+            #[cfg(feature = "longt5")]
             Self::LongT5(config) => config.vocab_size,
+            #[cfg(feature = "albert")]
             Self::Albert(config) => config.vocab_size,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(config) => config.vocab_size,
+            #[cfg(feature = "gpt2")]
             Self::GPT2(config) => config.vocab_size,
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(config) => config.vocab_size,
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => config.vocab_size,
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => config.vocab_size,
+            #[cfg(feature = "longformer")]
             Self::Longformer(config) => config.vocab_size,
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(config) => config.vocab_size,
+            #[cfg(feature = "tapas")]
+            Self::Tapas(config) => config.vocab_size,
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(config) => config.vocab_size,
+            #[cfg(feature = "openai-gpt")]
             Self::OpenAiGpt(config) => config.vocab_size,
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(config) => config.vocab_size,
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => config.vocab_size,
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(config) => config.vocab_size,
+            #[cfg(feature = "fnet")]
             Self::FNet(config) => config.vocab_size,
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(config) => config.vocab_size,
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(config) => config.vocab_size,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(config) => config.vocab_size,
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(config) => config.vocab_size,
+            #[cfg(feature = "llama")]
+            Self::Llama(config) => config.vocab_size,
+            #[cfg(feature = "bloom")]
+            Self::Bloom(config) => config.vocab_size,
+            #[cfg(feature = "opt")]
+            Self::OPT(config) => config.vocab_size,
+            #[cfg(feature = "phi")]
+            Self::Phi(config) => config.vocab_size,
+            #[cfg(feature = "falcon")]
+            Self::Falcon(config) => config.vocab_size,
+            #[cfg(feature = "roberta")]
             Self::Roberta(config) => config.vocab_size,
             #[cfg(feature = "onnx")]
             Self::ONNX(config) => config.vocab_size,
         }
     }
 
+    /// Returns the number of token types (segments) supported by the embeddings of the model, or `None` for
+    /// architectures without a token type vocabulary (e.g. DistilBERT or decoder models).
+    pub fn get_type_vocab_size(&self) -> Option<i64> {
+        match self {
+            #[cfg(feature = "bert")]
+            Self::Bert(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "roberta")]
+            Self::Roberta(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "albert")]
+            Self::Albert(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "deberta")]
+            Self::Deberta(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "deberta-v2")]
+            Self::DebertaV2(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "electra")]
+            Self::Electra(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "mobilebert")]
+            Self::MobileBert(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "longformer")]
+            Self::Longformer(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "tapas")]
+            Self::Tapas(config) => config.type_vocab_sizes.first().copied(),
+            #[cfg(feature = "fnet")]
+            Self::FNet(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(config) => Some(config.type_vocab_size),
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(config) => Some(config.type_vocab_size),
+            _ => None,
+        }
+    }
+
     pub fn get_decoder_start_token_id(&self) -> Option<i64> {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => config.decoder_start_token_id,
+            #[cfg(feature = "bert")]
             Self::Bert(_) => None,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => None,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => None,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => None,
+            #[cfg(feature = "electra")]
             Self::Electra(_) => None,
+            #[cfg(feature = "marian")]
             Self::Marian(config) => config.decoder_start_token_id,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => None,
+            #[cfg(feature = "t5")]
             Self::T5(config) => config.decoder_start_token_id,
+            #[cfg(feature = "longt5")]
             Self::LongT5(config) => config.decoder_start_token_id,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => None,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => None,
+            #[cfg(feature = "gpt2")]
             Self::GPT2(config) => config.decoder_start_token_id,
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(config) => config.decoder_start_token_id,
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => config.decoder_start_token_id,
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => config.decoder_start_token_id,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => None,
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(_) => None,
+            #[cfg(feature = "tapas")]
+            Self::Tapas(_) => None,
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(config) => config.decoder_start_token_id,
+            #[cfg(feature = "openai-gpt")]
             Self::OpenAiGpt(config) => config.decoder_start_token_id,
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(config) => config.decoder_start_token_id,
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => config.decoder_start_token_id,
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(config) => config.decoder_start_token_id,
+            #[cfg(feature = "fnet")]
             Self::FNet(config) => config.decoder_start_token_id,
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(_) => None,
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(_) => None,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => None,
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(config) => config.decoder_start_token_id,
+            #[cfg(feature = "llama")]
+            Self::Llama(config) => config.decoder_start_token_id,
+            #[cfg(feature = "bloom")]
+            Self::Bloom(config) => config.decoder_start_token_id,
+            #[cfg(feature = "opt")]
+            Self::OPT(config) => config.decoder_start_token_id,
+            #[cfg(feature = "phi")]
+            Self::Phi(config) => config.decoder_start_token_id,
+            #[cfg(feature = "falcon")]
+            Self::Falcon(config) => config.decoder_start_token_id,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => None,
             #[cfg(feature = "onnx")]
             Self::ONNX(config) => config.decoder_start_token_id,
@@ -515,29 +961,75 @@ impl ConfigOption {
 
     pub fn get_forced_bos_token_id(&self) -> Option<i64> {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => config.forced_bos_token_id,
+            #[cfg(feature = "bert")]
             Self::Bert(_) => None,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => None,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => None,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => None,
+            #[cfg(feature = "electra")]
             Self::Electra(_) => None,
+            #[cfg(feature = "marian")]
             Self::Marian(config) => config.forced_bos_token_id,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => None,
+            #[cfg(feature = "t5")]
             Self::T5(config) => config.forced_bos_token_id,
+            #[cfg(feature = "longt5")]
             Self::LongT5(config) => config.forced_bos_token_id,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => None,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => None,
+            #[cfg(feature = "gpt2")]
             Self::GPT2(config) => config.forced_bos_token_id,
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(config) => config.forced_bos_token_id,
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => config.forced_bos_token_id,
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => config.forced_bos_token_id,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => None,
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(_) => None,
+            #[cfg(feature = "tapas")]
+            Self::Tapas(_) => None,
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(config) => config.forced_bos_token_id,
+            #[cfg(feature = "openai-gpt")]
             Self::OpenAiGpt(config) => config.forced_bos_token_id,
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(config) => config.forced_bos_token_id,
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => config.forced_bos_token_id,
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(config) => config.forced_bos_token_id,
+            #[cfg(feature = "fnet")]
             Self::FNet(_) => None,
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(_) => None,
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(_) => None,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => None,
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(config) => config.forced_bos_token_id,
+            #[cfg(feature = "llama")]
+            Self::Llama(config) => config.forced_bos_token_id,
+            #[cfg(feature = "bloom")]
+            Self::Bloom(config) => config.forced_bos_token_id,
+            #[cfg(feature = "opt")]
+            Self::OPT(config) => config.forced_bos_token_id,
+            #[cfg(feature = "phi")]
+            Self::Phi(config) => config.forced_bos_token_id,
+            #[cfg(feature = "falcon")]
+            Self::Falcon(config) => config.forced_bos_token_id,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => None,
             #[cfg(feature = "onnx")]
             Self::ONNX(config) => config.forced_bos_token_id,
@@ -546,29 +1038,75 @@ impl ConfigOption {
 
     pub fn get_forced_eos_token_id(&self) -> Option<i64> {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => config.forced_eos_token_id,
+            #[cfg(feature = "bert")]
             Self::Bert(_) => None,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => None,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => None,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => None,
+            #[cfg(feature = "electra")]
             Self::Electra(_) => None,
+            #[cfg(feature = "marian")]
             Self::Marian(config) => config.forced_eos_token_id,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => None,
+            #[cfg(feature = "t5")]
             Self::T5(config) => config.forced_eos_token_id,
+            #[cfg(feature = "longt5")]
             Self::LongT5(config) => config.forced_eos_token_id,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => None,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => None,
+            #[cfg(feature = "gpt2")]
             Self::GPT2(config) => config.forced_eos_token_id,
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(config) => config.forced_eos_token_id,
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => config.forced_eos_token_id,
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => config.forced_eos_token_id,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => None,
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(_) => None,
+            #[cfg(feature = "tapas")]
+            Self::Tapas(_) => None,
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(config) => config.forced_eos_token_id,
+            #[cfg(feature = "openai-gpt")]
             Self::OpenAiGpt(config) => config.forced_eos_token_id,
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(config) => config.forced_eos_token_id,
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => config.forced_eos_token_id,
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(config) => config.forced_eos_token_id,
+            #[cfg(feature = "fnet")]
             Self::FNet(_) => None,
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(_) => None,
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(_) => None,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => None,
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(config) => config.forced_eos_token_id,
+            #[cfg(feature = "llama")]
+            Self::Llama(config) => config.forced_eos_token_id,
+            #[cfg(feature = "bloom")]
+            Self::Bloom(config) => config.forced_eos_token_id,
+            #[cfg(feature = "opt")]
+            Self::OPT(config) => config.forced_eos_token_id,
+            #[cfg(feature = "phi")]
+            Self::Phi(config) => config.forced_eos_token_id,
+            #[cfg(feature = "falcon")]
+            Self::Falcon(config) => config.forced_eos_token_id,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => None,
             #[cfg(feature = "onnx")]
             Self::ONNX(config) => config.forced_eos_token_id,
@@ -576,12 +1114,16 @@ impl ConfigOption {
     }
 }
 
+#[cfg(feature = "bert")]
 impl TryFrom<&ConfigOption> for BertConfig {
     type Error = RustBertError;
 
     fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
         match config {
-            ConfigOption::Bert(config) | ConfigOption::Roberta(config) => Ok(config.clone()),
+            #[cfg(feature = "bert")]
+            ConfigOption::Bert(config) => Ok(config.clone()),
+            #[cfg(feature = "roberta")]
+            ConfigOption::Roberta(config) => Ok(config.clone()),
             _ => Err(RustBertError::InvalidConfigurationError(
                 "You can only supply a BertConfig for Bert or a RobertaConfig for Roberta!"
                     .to_string(),
@@ -590,6 +1132,7 @@ impl TryFrom<&ConfigOption> for BertConfig {
     }
 }
 
+#[cfg(feature = "distilbert")]
 impl TryFrom<&ConfigOption> for DistilBertConfig {
     type Error = RustBertError;
 
@@ -604,6 +1147,7 @@ impl TryFrom<&ConfigOption> for DistilBertConfig {
     }
 }
 
+#[cfg(feature = "albert")]
 impl TryFrom<&ConfigOption> for AlbertConfig {
     type Error = RustBertError;
 
@@ -618,6 +1162,7 @@ impl TryFrom<&ConfigOption> for AlbertConfig {
     }
 }
 
+#[cfg(feature = "t5")]
 impl TryFrom<&ConfigOption> for T5Config {
     type Error = RustBertError;
 
@@ -632,6 +1177,51 @@ impl TryFrom<&ConfigOption> for T5Config {
     }
 }
 
+#[cfg(feature = "jina-bert")]
+impl TryFrom<&ConfigOption> for JinaBertConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::JinaBert(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a JinaBertConfig for Jina BERT!".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "nomic-bert")]
+impl TryFrom<&ConfigOption> for NomicBertConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::NomicBert(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a NomicBertConfig for Nomic BERT!".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "modernbert")]
+impl TryFrom<&ConfigOption> for ModernBertConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::ModernBert(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a ModernBertConfig for ModernBERT!".to_string(),
+            ))
+        }
+    }
+}
+
 impl TokenizerOption {
     /// Interface method to load a tokenizer from file
     pub fn from_file(
@@ -649,7 +1239,10 @@ impl TokenizerOption {
             ModelType::Bert
             | ModelType::DistilBert
             | ModelType::Electra
-            | ModelType::MobileBert => {
+            | ModelType::MobileBert
+            | ModelType::JinaBert
+            | ModelType::NomicBert
+            | ModelType::Tapas => {
                 if add_prefix_space.is_some() {
                     return Err(RustBertError::InvalidConfigurationError(
                         format!("Optional input `add_prefix_space` set to value {} but cannot be used by {:?}",
@@ -770,7 +1363,7 @@ impl TokenizerOption {
                 }
                 TokenizerOption::XLMRoberta(XLMRobertaTokenizer::from_file(vocab_path, lower_case)?)
             }
-            ModelType::Albert => {
+            ModelType::Albert | ModelType::BigBird => {
                 if strip_accents.is_some() {
                     return Err(RustBertError::InvalidConfigurationError(format!(
                         "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
@@ -812,13 +1405,16 @@ impl TokenizerOption {
                 }
                 TokenizerOption::Reformer(ReformerTokenizer::from_file(vocab_path, lower_case)?)
             }
-            ModelType::GPT2 | ModelType::GPTNeo | ModelType::GPTJ => {
-                TokenizerOption::GPT2(Gpt2Tokenizer::from_file(
-                    vocab_path,
-                    merges_path.expect("No merges specified!"),
-                    lower_case,
-                )?)
-            }
+            ModelType::GPT2
+            | ModelType::GPTNeo
+            | ModelType::GPTJ
+            | ModelType::StarCoder2
+            | ModelType::OPT
+            | ModelType::Phi => TokenizerOption::GPT2(Gpt2Tokenizer::from_file(
+                vocab_path,
+                merges_path.expect("No merges specified!"),
+                lower_case,
+            )?),
             ModelType::OpenAiGpt => TokenizerOption::OpenAiGpt(OpenAiGptTokenizer::from_file(
                 vocab_path,
                 merges_path.expect("No merges specified!"),
@@ -913,6 +1509,26 @@ impl TokenizerOption {
                 lower_case,
                 strip_accents.unwrap_or(false),
             )?),
+            ModelType::ModernBert => Err(RustBertError::InvalidConfigurationError(
+                "ModernBERT tokenizers should be loaded from a `tokenizer.json` file using \
+                `TokenizerOption::from_hf_tokenizer_file` (requires the `hf-tokenizers` feature)."
+                    .to_string(),
+            ))?,
+            ModelType::Llama => Err(RustBertError::InvalidConfigurationError(
+                "LLaMA tokenizers should be loaded from a `tokenizer.json` file using \
+                `TokenizerOption::from_hf_tokenizer_file` (requires the `hf-tokenizers` feature)."
+                    .to_string(),
+            ))?,
+            ModelType::Falcon => Err(RustBertError::InvalidConfigurationError(
+                "Falcon tokenizers should be loaded from a `tokenizer.json` file using \
+                `TokenizerOption::from_hf_tokenizer_file` (requires the `hf-tokenizers` feature)."
+                    .to_string(),
+            ))?,
+            ModelType::Bloom => Err(RustBertError::InvalidConfigurationError(
+                "BLOOM tokenizers should be loaded from a `tokenizer.json` file using \
+                `TokenizerOption::from_hf_tokenizer_file` (requires the `hf-tokenizers` feature)."
+                    .to_string(),
+            ))?,
             #[cfg(feature = "onnx")]
             ModelType::ONNX => Err(RustBertError::InvalidConfigurationError(
                 "Default Tokenizer not defined for generic ONNX models.".to_string(),
@@ -1572,19 +2188,33 @@ impl TokenizerOption {
         supported_source_languages: &HashSet<Language>,
         supported_target_languages: &HashSet<Language>,
     ) -> Result<(Option<String>, Option<i64>), RustBertError> {
+        let unsupported_language =
+            |language: &Language, supported_languages: &HashSet<Language>| {
+                let mut supported = supported_languages
+                    .iter()
+                    .map(|language| language.to_string())
+                    .collect::<Vec<String>>();
+                supported.sort();
+                RustBertError::from(InputError::UnsupportedLanguage {
+                    language: language.to_string(),
+                    supported,
+                })
+            };
         if let Some(source_language) = source_language {
             if !supported_source_languages.contains(source_language) {
-                return Err(RustBertError::ValueError(format!(
-                        "{source_language} not in list of supported languages: {supported_source_languages:?}",
-                    )));
+                return Err(unsupported_language(
+                    source_language,
+                    supported_source_languages,
+                ));
             }
         }
 
         if let Some(target_language) = target_language {
             if !supported_target_languages.contains(target_language) {
-                return Err(RustBertError::ValueError(format!(
-                        "{target_language} not in list of supported languages: {supported_target_languages:?}"
-                    )));
+                return Err(unsupported_language(
+                    target_language,
+                    supported_target_languages,
+                ));
             }
         }
 
@@ -2294,6 +2924,62 @@ impl TokenizerOption {
         )
     }
 
+    /// Encodes an input made of several segments, for models with more than two token types.
+    /// The first two segments are combined with the special tokens of the tokenizer (e.g. `[CLS] A [SEP] B [SEP]`),
+    /// each following segment is appended followed by a separator token. The tokens of the n-th segment
+    /// (and its separator) are assigned the token type n. The input is not truncated.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments` - texts of the segments
+    ///
+    /// # Returns
+    /// * `EncodedInput` containing the token ids and token type ids of the input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::FNet,
+    ///     "path/to/spiece.model",
+    ///     None,
+    ///     false,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// let input = tokenizer.encode_segments(&["Title", "Question", "Answer"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_segments(&self, segments: &[&str]) -> Result<EncodedInput, RustBertError> {
+        let mut encoded_segments = segments.iter().map(|segment| {
+            let tokens = self.tokenize_with_offsets(segment);
+            TokenIdsWithOffsets {
+                ids: self.convert_tokens_to_ids(&tokens.tokens),
+                offsets: tokens.offsets,
+                reference_offsets: tokens.reference_offsets,
+                masks: tokens.masks,
+            }
+        });
+        let first_segment = encoded_segments.next().ok_or(InputError::EmptyInput)?;
+        let second_segment = encoded_segments.next();
+        let mut encoded_input: EncodedInput = self
+            .build_input_with_special_tokens(first_segment, second_segment)
+            .into();
+
+        let sep_id = self.get_sep_id();
+        let token_type_ids = encoded_input.token_type_ids.get_or_insert_with(Vec::new);
+        for (segment_id, segment) in (2i64..).zip(encoded_segments) {
+            let segment_length = segment.ids.len() + usize::from(sep_id.is_some());
+            encoded_input.input_ids.extend(segment.ids);
+            encoded_input.input_ids.extend(sep_id);
+            token_type_ids.extend(std::iter::repeat(segment_id).take(segment_length));
+        }
+        Ok(encoded_input)
+    }
+
     /// Interface method
     pub fn add_extra_ids(&mut self, num_extra_ids: i64) {
         match *self {
@@ -2348,3 +3034,230 @@ impl TokenizerOption {
         }
     }
 }
+
+/// # Pre-encoded input for the pipelines
+/// Token ids obtained from an earlier tokenization (e.g. cached or received over the wire), allowing the
+/// pipelines to skip the tokenization step. The ids should already contain the special tokens expected by the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodedInput {
+    /// Token ids of the input
+    pub input_ids: Vec<i64>,
+    /// Optional attention mask (1 for tokens to attend to, 0 for masked tokens). Defaults to attending all tokens.
+    pub attention_mask: Option<Vec<i64>>,
+    /// Optional token type (segment) ids. Defaults to 0 for all tokens.
+    pub token_type_ids: Option<Vec<i64>>,
+}
+
+impl EncodedInput {
+    /// Create a new `EncodedInput` from token ids, attending all tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - token ids of the input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::common::EncodedInput;
+    ///
+    /// let input = EncodedInput::new(vec![101, 7592, 2088, 102]).with_attention_mask(vec![1, 1, 1, 0]);
+    /// ```
+    pub fn new(input_ids: Vec<i64>) -> EncodedInput {
+        EncodedInput {
+            input_ids,
+            attention_mask: None,
+            token_type_ids: None,
+        }
+    }
+
+    /// Sets a pre-computed attention mask
+    pub fn with_attention_mask(mut self, attention_mask: Vec<i64>) -> EncodedInput {
+        self.attention_mask = Some(attention_mask);
+        self
+    }
+
+    /// Sets pre-computed token type ids
+    pub fn with_token_type_ids(mut self, token_type_ids: Vec<i64>) -> EncodedInput {
+        self.token_type_ids = Some(token_type_ids);
+        self
+    }
+
+    /// Pads a batch of encoded inputs to the longest input and stacks them into tensors.
+    /// Returns the input ids, attention mask and token type ids, of shape (*batch size*, *sequence_length*).
+    /// Padding positions are filled with `pad_id`, masked and assigned the last token type of the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - batch of encoded inputs
+    /// * `pad_id` - padding token id
+    /// * `max_length` - maximum length of the inputs: longer inputs are truncated
+    /// * `device` - device the tensors are created on
+    pub fn pad_batch(
+        inputs: &[EncodedInput],
+        pad_id: i64,
+        max_length: Option<usize>,
+        device: Device,
+    ) -> Result<(Tensor, Tensor, Tensor), RustBertError> {
+        if inputs.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        for (index, input) in inputs.iter().enumerate() {
+            if input.input_ids.is_empty() {
+                return Err(RustBertError::ValueError(format!(
+                    "The encoded input {index} is empty"
+                )));
+            }
+            let mismatch = [
+                ("attention mask", &input.attention_mask),
+                ("token type ids", &input.token_type_ids),
+            ]
+            .iter()
+            .find(|(_, values)| {
+                values
+                    .as_deref()
+                    .map_or(false, |values| values.len() != input.input_ids.len())
+            });
+            if let Some((name, _)) = mismatch {
+                return Err(RustBertError::ValueError(format!(
+                    "The {name} of the encoded input {index} does not match the length of its input ids ({})",
+                    input.input_ids.len()
+                )));
+            }
+        }
+
+        let max_len = inputs
+            .iter()
+            .map(|input| input.input_ids.len())
+            .max()
+            .unwrap();
+        let max_len = max_length.map_or(max_len, |max_length| max_len.min(max_length));
+        let pad = |values: &[i64], pad_value: i64| -> Tensor {
+            let mut values = values[..values.len().min(max_len)].to_vec();
+            values.resize(max_len, pad_value);
+            Tensor::from_slice(&values)
+        };
+
+        let mut input_ids = Vec::with_capacity(inputs.len());
+        let mut attention_masks = Vec::with_capacity(inputs.len());
+        let mut token_type_ids = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            input_ids.push(pad(&input.input_ids, pad_id));
+            attention_masks.push(match &input.attention_mask {
+                Some(attention_mask) => pad(attention_mask, 0),
+                None => pad(&vec![1; input.input_ids.len()], 0),
+            });
+            token_type_ids.push(match &input.token_type_ids {
+                Some(token_type_ids) => pad(token_type_ids, *token_type_ids.last().unwrap()),
+                None => pad(&[], 0),
+            });
+        }
+
+        Ok((
+            Tensor::stack(&input_ids, 0).to(device),
+            Tensor::stack(&attention_masks, 0).to(device),
+            Tensor::stack(&token_type_ids, 0).to(device),
+        ))
+    }
+}
+
+impl From<TokenizedInput> for EncodedInput {
+    fn from(tokenized_input: TokenizedInput) -> Self {
+        EncodedInput {
+            input_ids: tokenized_input.token_ids,
+            attention_mask: None,
+            token_type_ids: Some(
+                tokenized_input
+                    .segment_ids
+                    .into_iter()
+                    .map(i64::from)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// # Token type (segment) ids strategy for the pipelines
+/// Controls the token type ids passed to the model for sentence pairs (e.g. question answering, NLI or reranking).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenTypeStrategy {
+    /// Use the segment ids built by the tokenizer for models with at least two token types, and set all token types to 0
+    /// for models trained without segment embeddings (e.g. RoBERTa with a single token type or DeBERTa) (default)
+    Auto,
+    /// Always use the segment ids built by the tokenizer (or provided with the encoded inputs)
+    Segments,
+    /// Set all token types to 0
+    Zeros,
+}
+
+impl Default for TokenTypeStrategy {
+    fn default() -> Self {
+        TokenTypeStrategy::Auto
+    }
+}
+
+impl TokenTypeStrategy {
+    /// Resolves the strategy for a model configuration, validating that the model supports segment ids if they are
+    /// explicitly requested.
+    pub(crate) fn resolve(
+        self,
+        model_config: &ConfigOption,
+    ) -> Result<TokenTypeIdsHandler, RustBertError> {
+        let type_vocab_size = model_config.get_type_vocab_size();
+        let use_segment_ids = match self {
+            TokenTypeStrategy::Auto => type_vocab_size.map_or(true, |size| size > 1),
+            TokenTypeStrategy::Segments => {
+                if let Some(size) = type_vocab_size.filter(|size| *size < 2) {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Segment ids were requested but the model only supports {size} token type(s)"
+                    )));
+                }
+                true
+            }
+            TokenTypeStrategy::Zeros => false,
+        };
+        Ok(TokenTypeIdsHandler {
+            use_segment_ids,
+            type_vocab_size,
+        })
+    }
+}
+
+/// Applies a resolved `TokenTypeStrategy` to the token type ids of a batch
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TokenTypeIdsHandler {
+    use_segment_ids: bool,
+    type_vocab_size: Option<i64>,
+}
+
+impl TokenTypeIdsHandler {
+    /// Returns the token type ids to pass to the model, of the same shape as the segment ids provided
+    pub(crate) fn apply(&self, token_type_ids: Tensor) -> Tensor {
+        if self.use_segment_ids {
+            token_type_ids
+        } else {
+            token_type_ids.zeros_like()
+        }
+    }
+
+    /// Checks that the token type ids of pre-encoded inputs are supported by the model embeddings
+    pub(crate) fn validate(&self, inputs: &[EncodedInput]) -> Result<(), RustBertError> {
+        if !self.use_segment_ids {
+            return Ok(());
+        }
+        if let Some(type_vocab_size) = self.type_vocab_size {
+            for (index, input) in inputs.iter().enumerate() {
+                let invalid_id = input.token_type_ids.as_deref().and_then(|token_type_ids| {
+                    token_type_ids
+                        .iter()
+                        .find(|id| **id < 0 || **id >= type_vocab_size)
+                });
+                if let Some(invalid_id) = invalid_id {
+                    return Err(RustBertError::ValueError(format!(
+                        "The encoded input {index} has a token type id {invalid_id} while the model supports {type_vocab_size} token types"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -13,6 +13,9 @@
 
 //! # Multi-turn dialogue
 //! Conversation model based on Microsoft's [DialoGPT](https://github.com/microsoft/DialoGPT).
+//! BLOOMZ checkpoints can also be used with `ModelType::Bloom` (tokenizer loaded from a `tokenizer.json` file,
+//! see `ConversationModel::new_with_tokenizer`).
+//! Phi-1.5 and Phi-2 checkpoints can be used with `ModelType::Phi`.
 //! This pipeline allows the generation of single or multi-turn conversations between a human and a model.
 //! The DialoGPT's page states that
 //! > The human evaluation results indicate that the response generated from DialoGPT is comparable to human response quality
@@ -53,17 +56,26 @@
 //! # Disclaimer
 //! The authors of this repository are not responsible for any generation
 //! from the 3rd party utilization of the pretrained system.
+#[cfg(feature = "bloom")]
+use crate::bloom::BloomGenerator;
 use crate::common::error::RustBertError;
+#[cfg(all(feature = "remote", feature = "gpt2"))]
+use crate::common::settings::default_device;
+#[cfg(feature = "gpt2")]
 use crate::gpt2::GPT2Generator;
+#[cfg(feature = "phi")]
+use crate::phi::PhiGenerator;
 use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator, NoRepeatNgramScope};
+use crate::pipelines::summarization::SummarizationModel;
 use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tch::{Device, Kind, Tensor};
 use uuid::Uuid;
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "gpt2"))]
 use crate::{
     gpt2::{Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources},
     resources::RemoteResource,
@@ -115,9 +127,23 @@ pub struct ConversationConfig {
     pub diversity_penalty: Option<f64>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Strategy used to fit the conversation history in the maximum context length (default: `Truncate`)
+    pub history_truncation: HistoryTruncationStrategy,
 }
 
-#[cfg(feature = "remote")]
+/// # Strategy used to fit the conversation history in the maximum context length
+/// Turns evicted from the context may be summarized into a rolling memory using `ConversationModel::summarize_history`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryTruncationStrategy {
+    /// Truncates the context at the token level, cutting at the first turn boundary that fits the maximum length
+    /// if it exists (a turn may be cut in the middle otherwise)
+    Truncate,
+    /// Drops the oldest turns (user input and generated response) until the context fits the maximum length.
+    /// Turns are never cut in the middle
+    DropOldestTurns,
+}
+
+#[cfg(all(feature = "remote", feature = "gpt2"))]
 impl Default for ConversationConfig {
     fn default() -> ConversationConfig {
         ConversationConfig {
@@ -149,7 +175,8 @@ impl Default for ConversationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            history_truncation: HistoryTruncationStrategy::Truncate,
         }
     }
 }
@@ -173,18 +200,31 @@ impl From<ConversationConfig> for GenerateConfig {
             repetition_penalty: config.repetition_penalty,
             length_penalty: config.length_penalty,
             no_repeat_ngram_size: config.no_repeat_ngram_size,
+            no_repeat_ngram_scope: NoRepeatNgramScope::FullSequence,
+            encoder_no_repeat_ngram_size: 0,
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            penalty_alpha: None,
+            typical_p: None,
+            eta_cutoff: None,
+            epsilon_cutoff: None,
             device: config.device,
+            encoder_cache: None,
+            stop_sequences: Vec::new(),
+            stopping_criteria: Vec::new(),
+            logits_processors: Vec::new(),
+            bad_word_ids: Vec::new(),
+            sequence_bias: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Data structure keeping track of a conversation in the system. It contains past user inputs and
 /// generated answers, a history of the tokens generated and a placeholder for new user inputs to be
-/// processed by the system if submitted for prediction
+/// processed by the system if submitted for prediction.
+/// The conversation can be serialized (e.g. to JSON) to persist a chat session and resume it later.
 pub struct Conversation {
     /// Past user inputs that have already been processed
     pub past_user_inputs: Vec<String>,
@@ -194,6 +234,15 @@ pub struct Conversation {
     pub new_user_input: Option<String>,
     ///  History of the tokens passed as an input and generated so far used as context for next turn generation
     pub history: Vec<Vec<i64>>,
+    /// Optional system prompt (persona) always kept at the start of the context, it is not evicted when the history is truncated
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Optional rolling memory summarizing the oldest turns of the conversation, kept at the start of the context
+    #[serde(default)]
+    pub memory: Option<String>,
+    /// Number of past turns summarized in the memory (their token ids are removed from the history)
+    #[serde(default)]
+    pub summarized_turns: usize,
 }
 
 impl Conversation {
@@ -216,6 +265,9 @@ impl Conversation {
             generated_responses: vec![],
             new_user_input: Some(text.to_string()),
             history: vec![],
+            system_prompt: None,
+            memory: None,
+            summarized_turns: 0,
         }
     }
 
@@ -234,9 +286,31 @@ impl Conversation {
             generated_responses: vec![],
             new_user_input: None,
             history: vec![],
+            system_prompt: None,
+            memory: None,
+            summarized_turns: 0,
         }
     }
 
+    /// Sets the system prompt (persona) of the conversation. The system prompt is prepended to the context
+    /// for every turn and is not evicted when the history is truncated to fit the maximum context length.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - `&str` with the system prompt
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::conversation::Conversation;
+    ///
+    /// let mut conversation = Conversation::new("Hi there!");
+    /// conversation.set_system_prompt("I am a friendly movie critic.");
+    /// ```
+    pub fn set_system_prompt(&mut self, text: &str) {
+        self.system_prompt = Some(text.to_string());
+    }
+
     /// Adds a new user input to the conversation. This method returns an error if an unprocessed
     /// user input already exists
     ///
@@ -460,7 +534,7 @@ impl Conversation {
 
 /// Data structure allowing the management of conversations and main input to the dialogue model.
 /// It contains a `HashMap` of conversations with `UUID` keys
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ConversationManager {
     conversations: HashMap<Uuid, Conversation>,
 }
@@ -606,6 +680,33 @@ impl ConversationManager {
         self.add(conversation)
     }
 
+    /// Creates a conversation with a system prompt (persona) and add it to the conversation manager
+    ///
+    /// # Arguments
+    ///
+    /// * `system_prompt` - `&str` string slice with the system prompt, kept at the start of the context
+    /// * `text` - `&str` string slice with an original user input
+    ///
+    /// # Returns
+    ///
+    /// * `Uuid` for the conversation created
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::conversation::{Conversation, ConversationManager};
+    ///
+    /// let mut conversation_manager = ConversationManager::new();
+    ///
+    /// let conversation_id = conversation_manager
+    ///     .create_with_system_prompt("I am a friendly movie critic.", "Hi there!");
+    /// ```
+    pub fn create_with_system_prompt(&mut self, system_prompt: &str, text: &str) -> Uuid {
+        let mut conversation = Conversation::new(text);
+        conversation.set_system_prompt(system_prompt);
+        self.add(conversation)
+    }
+
     /// Adds an existing conversation to the conversation manager
     ///
     /// # Arguments
@@ -659,6 +760,38 @@ impl ConversationManager {
         self.conversations.remove(uuid)
     }
 
+    /// Registers a previously persisted conversation with its original UUID, allowing to resume
+    /// a chat session. If a conversation with the same UUID exists, it is replaced and returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `uuid` - `Uuid` of the conversation to restore
+    /// * `conversation` - `Conversation` to restore (e.g. deserialized from a database)
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Conversation>` conversation previously registered with this UUID
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{Conversation, ConversationManager};
+    ///
+    /// let mut conversation_manager = ConversationManager::new();
+    /// let conversation_id = conversation_manager.create("Hi there!");
+    /// let serialized = serde_json::to_string(conversation_manager.get(&conversation_id).unwrap())?;
+    ///
+    /// let mut restored_manager = ConversationManager::new();
+    /// let conversation: Conversation = serde_json::from_str(&serialized)?;
+    /// restored_manager.restore(conversation_id, conversation);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restore(&mut self, uuid: Uuid, conversation: Conversation) -> Option<Conversation> {
+        self.conversations.insert(uuid, conversation)
+    }
+
     /// Clear all conversations from the conversation manager, and returns the conversations and their
     /// former UUID.
     ///
@@ -695,15 +828,29 @@ impl Default for ConversationManager {
 /// # Abstraction that holds one particular conversation model, for any of the supported models
 pub enum ConversationOption {
     /// Conversation based on GPT2 model
+    #[cfg(feature = "gpt2")]
     GPT2(GPT2Generator),
+    /// Conversation based on BLOOM model (BLOOMZ checkpoints)
+    #[cfg(feature = "bloom")]
+    Bloom(BloomGenerator),
+    /// Conversation based on Phi model
+    #[cfg(feature = "phi")]
+    Phi(PhiGenerator),
 }
 
 impl ConversationOption {
     pub fn new(config: ConversationConfig) -> Result<Self, RustBertError> {
         match config.model_type {
+            #[cfg(feature = "gpt2")]
             ModelType::GPT2 => Ok(ConversationOption::GPT2(GPT2Generator::new(config.into())?)),
+            #[cfg(feature = "bloom")]
+            ModelType::Bloom => Ok(ConversationOption::Bloom(BloomGenerator::new(
+                config.into(),
+            )?)),
+            #[cfg(feature = "phi")]
+            ModelType::Phi => Ok(ConversationOption::Phi(PhiGenerator::new(config.into())?)),
             _ => Err(RustBertError::InvalidConfigurationError(
-                "GPT2 is currently the only supported model for conversation generation"
+                "GPT2, BLOOM and Phi are the only supported models for conversation generation"
                     .to_string(),
             )),
         }
@@ -714,43 +861,87 @@ impl ConversationOption {
         tokenizer: TokenizerOption,
     ) -> Result<Self, RustBertError> {
         match config.model_type {
+            #[cfg(feature = "gpt2")]
             ModelType::GPT2 => Ok(ConversationOption::GPT2(GPT2Generator::new_with_tokenizer(
                 config.into(),
                 tokenizer,
             )?)),
+            #[cfg(feature = "bloom")]
+            ModelType::Bloom => Ok(ConversationOption::Bloom(
+                BloomGenerator::new_with_tokenizer(config.into(), tokenizer)?,
+            )),
+            #[cfg(feature = "phi")]
+            ModelType::Phi => Ok(ConversationOption::Phi(PhiGenerator::new_with_tokenizer(
+                config.into(),
+                tokenizer,
+            )?)),
             _ => Err(RustBertError::InvalidConfigurationError(
-                "GPT2 is currently the only supported model for conversation generation"
+                "GPT2, BLOOM and Phi are the only supported models for conversation generation"
                     .to_string(),
             )),
         }
     }
 
     pub fn get_eos_id(&self) -> Result<i64, RustBertError> {
-        match self {
-            Self::GPT2(model_ref) => {
+        match *self {
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => {
                 Ok(*model_ref.get_eos_ids().as_ref().unwrap().first().unwrap())
             }
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => model_ref
+                .get_eos_ids()
+                .and_then(|eos_ids| eos_ids.first().copied())
+                .ok_or_else(|| {
+                    RustBertError::InvalidConfigurationError(
+                        "BLOOM conversation model requires an end of sequence token".to_string(),
+                    )
+                }),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => model_ref
+                .get_eos_ids()
+                .and_then(|eos_ids| eos_ids.first().copied())
+                .ok_or_else(|| {
+                    RustBertError::InvalidConfigurationError(
+                        "Phi conversation model requires an end of sequence token".to_string(),
+                    )
+                }),
         }
     }
 
     /// Get a reference to the model tokenizer.
     pub fn get_tokenizer(&self) -> &TokenizerOption {
-        match self {
-            Self::GPT2(model_ref) => model_ref._get_tokenizer(),
+        match *self {
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => model_ref._get_tokenizer(),
         }
     }
 
     /// Get a mutable reference to the model tokenizer.
     pub fn get_tokenizer_mut(&mut self) -> &TokenizerOption {
-        match self {
-            Self::GPT2(model_ref) => model_ref._get_tokenizer_mut(),
+        match *self {
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref._get_tokenizer_mut(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref._get_tokenizer_mut(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref._get_tokenizer_mut(),
         }
     }
 
     /// Returns the `ModelType` for this ConversationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "gpt2")]
             Self::GPT2(_) => ModelType::GPT2,
+            #[cfg(feature = "bloom")]
+            Self::Bloom(_) => ModelType::Bloom,
+            #[cfg(feature = "phi")]
+            Self::Phi(_) => ModelType::Phi,
         }
     }
 
@@ -761,11 +952,24 @@ impl ConversationOption {
         attention_mask: Option<Tensor>,
     ) -> Vec<Vec<i64>> {
         match *self {
+            #[cfg(feature = "gpt2")]
             Self::GPT2(ref model) => model
                 .generate_from_ids_and_past(input_ids, attention_mask, None)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model) => model
+                .generate_from_ids_and_past(input_ids, attention_mask, None)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model) => model
+                .generate_from_ids_and_past(input_ids, attention_mask, None)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
         }
     }
 }
@@ -776,6 +980,7 @@ pub struct ConversationModel {
     model: ConversationOption,
     eos_token_id: i64,
     max_allowed_context_length: Option<i64>,
+    history_truncation: HistoryTruncationStrategy,
     device: Device,
 }
 
@@ -803,12 +1008,14 @@ impl ConversationModel {
             .max_length
             .map(|max_length| max_length - conversation_config.min_length_for_response);
         let device = conversation_config.device;
+        let history_truncation = conversation_config.history_truncation;
         let model = ConversationOption::new(conversation_config)?;
         let eos_token_id = model.get_eos_id()?;
         Ok(ConversationModel {
             model,
             eos_token_id,
             max_allowed_context_length: max_allowed_length,
+            history_truncation,
             device,
         })
     }
@@ -846,12 +1053,14 @@ impl ConversationModel {
             .max_length
             .map(|max_length| max_length - conversation_config.min_length_for_response);
         let device = conversation_config.device;
+        let history_truncation = conversation_config.history_truncation;
         let model = ConversationOption::new_with_tokenizer(conversation_config, tokenizer)?;
         let eos_token_id = model.get_eos_id()?;
         Ok(ConversationModel {
             model,
             eos_token_id,
             max_allowed_context_length: max_allowed_length,
+            history_truncation,
             device,
         })
     }
@@ -891,14 +1100,33 @@ impl ConversationModel {
                 .map(|c| c.new_user_input.as_ref().unwrap().as_str())
                 .collect::<Vec<&str>>();
 
-            let history = active_conversations
+            let system_prompts = active_conversations
                 .iter()
-                .map(|c| c.history.iter().flatten().copied().collect())
+                .map(|c| {
+                    let prefix_texts = c
+                        .system_prompt
+                        .iter()
+                        .chain(c.memory.iter())
+                        .map(|text| text.as_str())
+                        .collect::<Vec<&str>>();
+                    self.encode_prompts(&prefix_texts)
+                        .into_iter()
+                        .flatten()
+                        .collect()
+                })
                 .collect::<Vec<Vec<i64>>>();
 
             let prompt_ids = self.encode_prompts(texts.as_ref());
+
+            let history = active_conversations
+                .iter()
+                .zip(system_prompts.iter().zip(prompt_ids.iter()))
+                .map(|(c, (system_prompt, input))| {
+                    self.select_history(&c.history, system_prompt.len() + input.len())
+                })
+                .collect::<Vec<Vec<i64>>>();
             let (input_tensor, attention_mask) =
-                self.concat_input_history(prompt_ids.as_ref(), history);
+                self.concat_input_history(prompt_ids.as_ref(), history, system_prompts);
             let input_length = *input_tensor.size().last().unwrap() as usize;
             let mut generated = self
                 .model
@@ -962,12 +1190,38 @@ impl ConversationModel {
         removed_tokens
     }
 
+    fn select_history(&self, history: &[Vec<i64>], reserved_length: usize) -> Vec<i64> {
+        // Selects the history tokens to use as a context, according to the history truncation strategy
+        match (self.history_truncation, self.max_allowed_context_length) {
+            (HistoryTruncationStrategy::DropOldestTurns, Some(max_allowed_context_length)) => {
+                let budget = (max_allowed_context_length as usize).saturating_sub(reserved_length);
+                let mut history_length = 0;
+                let mut kept_turns = 0;
+                for turn in history.rchunks(2) {
+                    let turn_length = turn.iter().map(|ids| ids.len()).sum::<usize>();
+                    if history_length + turn_length > budget {
+                        break;
+                    }
+                    history_length += turn_length;
+                    kept_turns += turn.len();
+                }
+                history[history.len() - kept_turns..]
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .collect()
+            }
+            _ => history.iter().flatten().copied().collect(),
+        }
+    }
+
     fn concat_input_history(
         &self,
         inputs: &[Vec<i64>],
         history: Vec<Vec<i64>>,
+        system_prompts: Vec<Vec<i64>>,
     ) -> (Tensor, Tensor) {
-        // Concatenates the history token indices with new user input
+        // Concatenates the history token indices with new user input, prefixed by the system prompt
         let pad_token = self
             .model
             .get_tokenizer()
@@ -979,6 +1233,11 @@ impl ConversationModel {
             history.len(),
             "Length of inputs should equal length of history"
         );
+        assert_eq!(
+            inputs.len(),
+            system_prompts.len(),
+            "Length of inputs should equal length of system prompts"
+        );
 
         let mut concatenated_inputs = Vec::with_capacity(inputs.len());
         for (input, history) in inputs.iter().zip(history.iter()) {
@@ -988,22 +1247,33 @@ impl ConversationModel {
             concatenated_inputs.push(concatenated_element);
         }
 
+        // The system prompt is excluded from the truncation and always kept at the start of the context
         let truncated_concatenated_inputs = concatenated_inputs
             .iter()
-            .map(|input| match self.max_allowed_context_length {
-                Some(max_allowed_context_length)
-                    if input.len() > max_allowed_context_length as usize =>
-                {
-                    let start = self.get_truncated_input_index(
-                        input,
-                        max_allowed_context_length as usize,
-                        pad_token,
-                    );
-                    &input[start..]
-                }
-                _ => input.as_slice(),
+            .zip(system_prompts.iter())
+            .map(|(input, system_prompt)| {
+                let truncated_input = match self.max_allowed_context_length {
+                    Some(max_allowed_context_length) => {
+                        let max_input_length = (max_allowed_context_length as usize)
+                            .saturating_sub(system_prompt.len())
+                            .max(1);
+                        if input.len() > max_input_length {
+                            let start =
+                                self.get_truncated_input_index(input, max_input_length, pad_token);
+                            &input[start..]
+                        } else {
+                            input.as_slice()
+                        }
+                    }
+                    None => input.as_slice(),
+                };
+                let mut truncated_element =
+                    Vec::with_capacity(system_prompt.len() + truncated_input.len());
+                truncated_element.extend_from_slice(system_prompt);
+                truncated_element.extend_from_slice(truncated_input);
+                truncated_element
             })
-            .collect::<Vec<&[i64]>>();
+            .collect::<Vec<Vec<i64>>>();
 
         let max_len = truncated_concatenated_inputs
             .iter()
@@ -1057,6 +1327,113 @@ impl ConversationModel {
         *eos_indices.first().unwrap_or(&(start_length - max_length))
     }
 
+    /// Summarizes the oldest turns of a conversation into a rolling memory, kept at the start of the context
+    /// (after the system prompt if any). The token ids of the summarized turns are removed from the history,
+    /// freeing context for the next turns. The previous memory is included in the text to summarize.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation` - `&mut Conversation` conversation to summarize
+    /// * `summarization_model` - `&SummarizationModel` model used to summarize the oldest turns
+    /// * `keep_last_turns` - number of most recent turns (user input and generated response) to keep as is
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{ConversationManager, ConversationModel};
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    /// let model = ConversationModel::new(Default::default())?;
+    /// let summarization_model = SummarizationModel::new(Default::default())?;
+    ///
+    /// let mut conversation_manager = ConversationManager::new();
+    /// let conversation_id =
+    ///     conversation_manager.create("Going to the movies tonight - any suggestions?");
+    /// let _ = model.generate_responses(&mut conversation_manager);
+    ///
+    /// let conversation = conversation_manager.get(&conversation_id).unwrap();
+    /// model.summarize_history(conversation, &summarization_model, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summarize_history(
+        &self,
+        conversation: &mut Conversation,
+        summarization_model: &SummarizationModel,
+        keep_last_turns: usize,
+    ) {
+        let num_turns = conversation
+            .past_user_inputs
+            .len()
+            .min(conversation.generated_responses.len());
+        let end_turn = num_turns.saturating_sub(keep_last_turns);
+        if end_turn <= conversation.summarized_turns {
+            return;
+        }
+
+        let mut text = conversation.memory.clone().unwrap_or_default();
+        for (user_input, generated_response) in conversation.past_user_inputs
+            [conversation.summarized_turns..end_turn]
+            .iter()
+            .zip(conversation.generated_responses[conversation.summarized_turns..end_turn].iter())
+        {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(user_input);
+            text.push('\n');
+            text.push_str(generated_response);
+        }
+
+        conversation.memory = summarization_model.summarize(&[text]).pop();
+        let num_summarized_ids =
+            (2 * (end_turn - conversation.summarized_turns)).min(conversation.history.len());
+        conversation.history.drain(..num_summarized_ids);
+        conversation.summarized_turns = end_turn;
+    }
+
+    /// Re-encodes the history of a restored conversation from its past user inputs and generated responses.
+    /// The context (and therefore the model cache) for the next turn is rebuilt from the history token ids:
+    /// this should be called when resuming a conversation that was persisted without its token ids, or
+    /// with token ids produced by a different tokenizer. Turns already summarized in the memory are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation` - `&mut Conversation` restored conversation to re-prime
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{Conversation, ConversationModel};
+    /// let model = ConversationModel::new(Default::default())?;
+    ///
+    /// let mut conversation: Conversation = serde_json::from_str(
+    ///     r#"{"past_user_inputs": ["Going to the movies tonight - any suggestions?"],
+    ///         "generated_responses": ["The Big Lebowski"],
+    ///         "new_user_input": "Is it an action movie?",
+    ///         "history": []}"#,
+    /// )?;
+    /// model.restore_history(&mut conversation);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restore_history(&self, conversation: &mut Conversation) {
+        let mut texts = Vec::with_capacity(
+            conversation.past_user_inputs.len() + conversation.generated_responses.len(),
+        );
+        for (user_input, generated_response) in conversation
+            .past_user_inputs
+            .iter()
+            .zip(conversation.generated_responses.iter())
+            .skip(conversation.summarized_turns)
+        {
+            texts.push(user_input.as_str());
+            texts.push(generated_response.as_str());
+        }
+        conversation.history = self.encode_prompts(&texts);
+    }
+
     /// Encodes prompts into Vectors of indices to be processed by the model. This method may be used to
     /// initialize the history of a conversation with a prior state.
     ///
@@ -0,0 +1,426 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Data-to-text pipeline
+//! Verbalizes structured records (attribute/value pairs or simple tables) with a sequence-to-sequence model.
+//! The records are linearized into a flat text (e.g. `name: Alimentum | food: Italian | area: city centre`)
+//! by a `Linearizer`, optionally prefixed with a task prefix for T5-style models, and passed to the generator.
+//! Any encoder-decoder model supported by the summarization pipeline can be used. The pipeline is meant to be
+//! used with a model fine-tuned on a data-to-text dataset (e.g. WebNLG, E2E or ToTTo) using the same linearization.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::data_to_text::{DataRecord, DataToTextModel};
+//! # fn main() -> anyhow::Result<()> {
+//! let data_to_text_model = DataToTextModel::new(Default::default())?;
+//!
+//! let record = DataRecord::from_pairs(&[
+//!     ("name", "Alimentum"),
+//!     ("food", "Italian"),
+//!     ("area", "city centre"),
+//! ]);
+//! let output = data_to_text_model.verbalize(&[record]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A `FaithfulnessModel` can be attached to check that the generated texts are supported by the records
+//! (see `DataToTextModel::verbalize_with_faithfulness`).
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::faithfulness::{FaithfulnessModel, ScoredSummary};
+use crate::pipelines::summarization::{SummarizationConfig, SummarizationOption};
+use serde::{Deserialize, Serialize};
+
+#[cfg(all(feature = "remote", feature = "t5"))]
+use crate::{
+    pipelines::common::{ModelResource, ModelType},
+    resources::RemoteResource,
+    t5::{T5ConfigResources, T5ModelResources, T5VocabResources},
+};
+
+/// # Structured record to verbalize
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataRecord {
+    /// Attribute/value pairs describing a single entity
+    KeyValue(Vec<(String, String)>),
+    /// Table with a header and rows of cells (one cell per header column)
+    Table {
+        /// Optional title of the table
+        title: Option<String>,
+        /// Column names
+        header: Vec<String>,
+        /// Rows of cells
+        rows: Vec<Vec<String>>,
+    },
+}
+
+impl DataRecord {
+    /// Creates a record from attribute/value pairs
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - Attribute/value pairs, in the order they should be linearized
+    pub fn from_pairs<K, V>(pairs: &[(K, V)]) -> DataRecord
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        DataRecord::KeyValue(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.as_ref().to_string(), value.as_ref().to_string()))
+                .collect(),
+        )
+    }
+
+    /// Creates a table record, checking that all rows have one cell per header column
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - Optional title of the table
+    /// * `header` - Column names
+    /// * `rows` - Rows of cells
+    pub fn from_table<H, C>(
+        title: Option<&str>,
+        header: &[H],
+        rows: &[Vec<C>],
+    ) -> Result<DataRecord, RustBertError>
+    where
+        H: AsRef<str>,
+        C: AsRef<str>,
+    {
+        if let Some(row) = rows.iter().find(|row| row.len() != header.len()) {
+            return Err(RustBertError::ValueError(format!(
+                "Table row with {} cells does not match the header with {} columns",
+                row.len(),
+                header.len()
+            )));
+        }
+        Ok(DataRecord::Table {
+            title: title.map(str::to_string),
+            header: header
+                .iter()
+                .map(|column| column.as_ref().to_string())
+                .collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|cell| cell.as_ref().to_string()).collect())
+                .collect(),
+        })
+    }
+
+    /// Attribute/value pairs of the record, each table cell being paired with its column name
+    fn facts(&self) -> Vec<Vec<(&str, &str)>> {
+        match self {
+            DataRecord::KeyValue(pairs) => vec![pairs
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect()],
+            DataRecord::Table { header, rows, .. } => rows
+                .iter()
+                .map(|row| {
+                    header
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(column, cell)| (column.as_str(), cell.as_str()))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    fn title(&self) -> Option<&str> {
+        match self {
+            DataRecord::KeyValue(_) => None,
+            DataRecord::Table { title, .. } => title.as_deref(),
+        }
+    }
+}
+
+/// # Linearization of structured records
+/// Flattens records into the text passed to the model. Must match the linearization used when fine-tuning it.
+/// Attribute/value pairs are linearized as `key<key_value_separator>value`, joined by `field_separator`. Table rows
+/// are joined by `row_separator`, after the title of the table (if any) followed by `title_separator`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Linearizer {
+    /// Separator between an attribute and its value (default: `": "`)
+    pub key_value_separator: String,
+    /// Separator between the attribute/value pairs (default: `" | "`)
+    pub field_separator: String,
+    /// Separator between the rows of a table (default: `" || "`)
+    pub row_separator: String,
+    /// Separator between the title of a table and its rows (default: `" || "`)
+    pub title_separator: String,
+}
+
+impl Default for Linearizer {
+    fn default() -> Linearizer {
+        Linearizer {
+            key_value_separator: ": ".to_string(),
+            field_separator: " | ".to_string(),
+            row_separator: " || ".to_string(),
+            title_separator: " || ".to_string(),
+        }
+    }
+}
+
+impl Linearizer {
+    /// Linearizes a record
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Record to linearize
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::data_to_text::{DataRecord, Linearizer};
+    ///
+    /// let record = DataRecord::from_pairs(&[("name", "Alimentum"), ("food", "Italian")]);
+    /// assert_eq!(
+    ///     Linearizer::default().linearize(&record),
+    ///     "name: Alimentum | food: Italian"
+    /// );
+    /// ```
+    pub fn linearize(&self, record: &DataRecord) -> String {
+        let rows = record
+            .facts()
+            .iter()
+            .map(|pairs| {
+                pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}{}{}", key, self.key_value_separator, value))
+                    .collect::<Vec<String>>()
+                    .join(&self.field_separator)
+            })
+            .collect::<Vec<String>>()
+            .join(&self.row_separator);
+        match record.title() {
+            Some(title) => format!("{}{}{}", title, self.title_separator, rows),
+            None => rows,
+        }
+    }
+}
+
+/// Renders a record as simple statements (`key: value.`), used as premise to check the faithfulness of the
+/// generated texts
+fn record_statements(record: &DataRecord) -> String {
+    let mut statements = vec![];
+    if let Some(title) = record.title() {
+        statements.push(format!("{title}."));
+    }
+    for pairs in record.facts() {
+        statements.push(
+            pairs
+                .iter()
+                .map(|(key, value)| format!("{key}: {value}."))
+                .collect::<Vec<String>>()
+                .join(" "),
+        );
+    }
+    statements.join("\n")
+}
+
+/// # Configuration for DataToTextModel
+/// Contains the configuration of the sequence-to-sequence model and the linearization of the records.
+pub struct DataToTextConfig {
+    /// Configuration of the sequence-to-sequence model (any model supported by the summarization pipeline)
+    pub generation_config: SummarizationConfig,
+    /// Optional task prefix prepended to the linearized records (e.g. for T5-style models)
+    pub prefix: Option<String>,
+    /// Linearization of the records, matching the one used to fine-tune the model
+    pub linearizer: Linearizer,
+}
+
+impl DataToTextConfig {
+    /// Instantiate a new data-to-text configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `SummarizationConfig` of the sequence-to-sequence model to load
+    /// * `prefix` - Optional task prefix prepended to the linearized records
+    pub fn new(generation_config: SummarizationConfig, prefix: Option<&str>) -> DataToTextConfig {
+        DataToTextConfig {
+            generation_config,
+            prefix: prefix.map(str::to_string),
+            linearizer: Linearizer::default(),
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "t5"))]
+impl Default for DataToTextConfig {
+    /// Provides a default T5 configuration, to be replaced by a model fine-tuned for data-to-text generation
+    fn default() -> DataToTextConfig {
+        let mut generation_config = SummarizationConfig::new(
+            ModelType::T5,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                T5ModelResources::T5_BASE,
+            ))),
+            RemoteResource::from_pretrained(T5ConfigResources::T5_BASE),
+            RemoteResource::from_pretrained(T5VocabResources::T5_BASE),
+            None,
+        );
+        generation_config.min_length = 0;
+        generation_config.max_length = Some(64);
+        DataToTextConfig::new(generation_config, Some("data to text: "))
+    }
+}
+
+/// # DataToTextModel to verbalize structured records
+pub struct DataToTextModel {
+    model: SummarizationOption,
+    prefix: Option<String>,
+    linearizer: Linearizer,
+    faithfulness: Option<(FaithfulnessModel, f64)>,
+}
+
+impl DataToTextModel {
+    /// Build a new `DataToTextModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `DataToTextConfig` object containing the sequence-to-sequence model configuration and the
+    ///   linearization of the records
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::data_to_text::DataToTextModel;
+    ///
+    /// let data_to_text_model = DataToTextModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: DataToTextConfig) -> Result<DataToTextModel, RustBertError> {
+        let model = SummarizationOption::new(config.generation_config)?;
+        Ok(DataToTextModel {
+            model,
+            prefix: config.prefix,
+            linearizer: config.linearizer,
+            faithfulness: None,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.model.get_tokenizer_mut()
+    }
+
+    /// Attach a faithfulness model checking the texts generated by `verbalize_with_faithfulness`.
+    ///
+    /// # Arguments
+    ///
+    /// * `faithfulness_model` - `FaithfulnessModel` (NLI model) scoring the generated texts against the records
+    /// * `threshold` - Generated sentences with an entailment probability below this threshold are flagged
+    pub fn set_faithfulness_model(
+        &mut self,
+        faithfulness_model: FaithfulnessModel,
+        threshold: f64,
+    ) {
+        self.faithfulness = Some((faithfulness_model, threshold));
+    }
+
+    /// Linearizes a record into the text passed to the model (excluding the task prefix)
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Record to linearize
+    pub fn linearize(&self, record: &DataRecord) -> String {
+        self.linearizer.linearize(record)
+    }
+
+    /// Verbalizes structured records
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - `&[DataRecord]` Records to verbalize
+    ///
+    /// # Returns
+    /// * `Vec<String>` Generated texts, in the order of the records
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::data_to_text::{DataRecord, DataToTextModel};
+    ///
+    /// let data_to_text_model = DataToTextModel::new(Default::default())?;
+    /// let record = DataRecord::from_table(
+    ///     Some("Tallest buildings"),
+    ///     &["name", "height"],
+    ///     &[vec!["Burj Khalifa", "828 m"], vec!["Merdeka 118", "679 m"]],
+    /// )?;
+    /// let output = data_to_text_model.verbalize(&[record]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn verbalize(&self, records: &[DataRecord]) -> Vec<String> {
+        let texts = records
+            .iter()
+            .map(|record| {
+                let linearized = self.linearizer.linearize(record);
+                match &self.prefix {
+                    Some(prefix) => format!("{prefix}{linearized}"),
+                    None => linearized,
+                }
+            })
+            .collect::<Vec<String>>();
+        self.model.generate(Some(&texts))
+    }
+
+    /// Verbalizes structured records and checks the faithfulness of the generated texts to the records with the
+    /// attached faithfulness model, flagging the generated sentences likely to be hallucinations. The records are
+    /// rendered as simple statements (`key: value.`) used as premises. Requires a faithfulness model set with
+    /// `set_faithfulness_model`.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - `&[DataRecord]` Records to verbalize
+    ///
+    /// # Returns
+    /// * `Vec<ScoredSummary>` Generated texts with their faithfulness scores
+    pub fn verbalize_with_faithfulness(
+        &self,
+        records: &[DataRecord],
+    ) -> Result<Vec<ScoredSummary>, RustBertError> {
+        let (faithfulness_model, threshold) = self.faithfulness.as_ref().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "No faithfulness model attached, set one with `set_faithfulness_model`".to_string(),
+            )
+        })?;
+        let texts = self.verbalize(records);
+        let sources = records
+            .iter()
+            .map(record_statements)
+            .collect::<Vec<String>>();
+        faithfulness_model.check(&sources, &texts, *threshold)
+    }
+}
+
+#[cfg(all(test, feature = "remote", feature = "t5"))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = DataToTextConfig::default();
+        let _: Box<dyn Send> = Box::new(DataToTextModel::new(config));
+    }
+}
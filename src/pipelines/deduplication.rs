@@ -0,0 +1,484 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Near-duplicate detection
+//! Streaming deduplication of documents combining two signals:
+//! - lexical near-duplicates, using MinHash signatures over word shingles indexed with locality sensitive hashing (LSH).
+//!   The Jaccard similarity between a new document and the LSH candidates is estimated from their signatures.
+//! - semantic near-duplicates (optional), using the cosine similarity between sentence embeddings.
+//!
+//! Documents are processed in batches by a `Deduplicator` that keeps an index of the unique documents seen so far.
+//! The memory footprint can be bounded by setting a maximum number of indexed documents, the oldest documents
+//! being evicted first.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::deduplication::Deduplicator;
+//! # fn main() -> anyhow::Result<()> {
+//! let mut deduplicator = Deduplicator::new(Default::default())?;
+//!
+//! let input = [
+//!     "The quick brown fox jumps over the lazy dog near the river bank.",
+//!     "The quick brown fox jumps over the lazy dog near the river bank!",
+//!     "A completely different sentence about the stock market.",
+//! ];
+//! let output = deduplicator.deduplicate(&input)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::deduplication::{DeduplicationResult, DuplicateMatch};
+//! let output = [
+//!     DeduplicationResult { id: 0, duplicate_of: None },
+//!     DeduplicationResult {
+//!         id: 1,
+//!         duplicate_of: Some(DuplicateMatch { id: 0, jaccard: 1.0, similarity: None }),
+//!     },
+//!     DeduplicationResult { id: 2, duplicate_of: None },
+//! ]
+//! # ;
+//! ```
+
+use crate::pipelines::sentence_embeddings::{
+    Embedding, SentenceEmbeddingsConfig, SentenceEmbeddingsModel,
+};
+use crate::RustBertError;
+use std::collections::{HashMap, VecDeque};
+
+const MERSENNE_PRIME: u64 = (1 << 61) - 1;
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+fn fnv_hash(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+/// # MinHash signature generator
+/// Documents are lower-cased and split into alphanumeric words, and shingles are built from `shingle_size`
+/// consecutive words. The signature contains the minimum of each of the `num_permutations`
+/// hash functions over the shingles of the document. Signatures are deterministic across runs.
+#[derive(Debug, Clone)]
+pub struct MinHasher {
+    shingle_size: usize,
+    permutations: Vec<(u64, u64)>,
+}
+
+impl MinHasher {
+    /// Create a new `MinHasher`
+    ///
+    /// # Arguments
+    ///
+    /// * `num_permutations` - number of hash functions (length of the signatures)
+    /// * `shingle_size` - number of consecutive words in a shingle
+    pub fn new(num_permutations: usize, shingle_size: usize) -> MinHasher {
+        let mut state = 42u64;
+        let permutations = (0..num_permutations)
+            .map(|_| {
+                (
+                    1 + splitmix64(&mut state) % (MERSENNE_PRIME - 1),
+                    splitmix64(&mut state) % MERSENNE_PRIME,
+                )
+            })
+            .collect();
+        MinHasher {
+            shingle_size: shingle_size.max(1),
+            permutations,
+        }
+    }
+
+    /// Returns the number of hash functions (length of the signatures)
+    pub fn num_permutations(&self) -> usize {
+        self.permutations.len()
+    }
+
+    fn shingle_hashes(&self, text: &str) -> Vec<u64> {
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<String>>();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        words
+            .windows(self.shingle_size.min(words.len()))
+            .map(|shingle| {
+                fnv_hash(shingle.iter().enumerate().flat_map(|(index, word)| {
+                    (index > 0).then_some(b' ').into_iter().chain(word.bytes())
+                }))
+            })
+            .collect()
+    }
+
+    /// Compute the MinHash signature of a text
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - input text
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<u64>` signature of length `num_permutations`
+    pub fn signature(&self, text: &str) -> Vec<u64> {
+        let shingle_hashes = self.shingle_hashes(text);
+        self.permutations
+            .iter()
+            .map(|&(a, b)| {
+                shingle_hashes
+                    .iter()
+                    .map(|&hash| {
+                        ((a as u128 * (hash % MERSENNE_PRIME) as u128 + b as u128)
+                            % MERSENNE_PRIME as u128) as u64
+                    })
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+}
+
+/// Estimate the Jaccard similarity of two documents from their MinHash signatures, as the fraction of
+/// matching signature values.
+pub fn estimate_jaccard(left: &[u64], right: &[u64]) -> f32 {
+    if left.is_empty() || left.len() != right.len() {
+        return 0.0;
+    }
+    let matches = left.iter().zip(right).filter(|(l, r)| l == r).count();
+    matches as f32 / left.len() as f32
+}
+
+/// # Configuration for the near-duplicate detection
+pub struct DeduplicationConfig {
+    /// Number of MinHash permutations (length of the signatures)
+    pub num_permutations: usize,
+    /// Number of LSH bands, must divide `num_permutations`. More bands increase the recall of the candidate search.
+    pub num_bands: usize,
+    /// Number of consecutive words in a shingle
+    pub shingle_size: usize,
+    /// Minimum estimated Jaccard similarity for two documents to be considered near-duplicates
+    pub jaccard_threshold: f32,
+    /// Optional `SentenceEmbeddingsConfig` for semantic deduplication. If provided, documents with a cosine similarity
+    /// above `embedding_threshold` with an indexed document are also considered near-duplicates.
+    pub sentence_embeddings_config: Option<SentenceEmbeddingsConfig>,
+    /// Minimum cosine similarity for two documents to be considered semantic near-duplicates
+    pub embedding_threshold: f32,
+    /// Optional maximum number of unique documents kept in the index. The oldest documents are evicted first.
+    pub max_documents: Option<usize>,
+}
+
+impl Default for DeduplicationConfig {
+    fn default() -> Self {
+        DeduplicationConfig {
+            num_permutations: 128,
+            num_bands: 32,
+            shingle_size: 3,
+            jaccard_threshold: 0.8,
+            sentence_embeddings_config: None,
+            embedding_threshold: 0.95,
+            max_documents: None,
+        }
+    }
+}
+
+/// # Indexed document matching a new document
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateMatch {
+    /// Identifier of the matching indexed document
+    pub id: u64,
+    /// Estimated Jaccard similarity with the matching document
+    pub jaccard: f32,
+    /// Cosine similarity of the embeddings, if semantic deduplication is enabled
+    pub similarity: Option<f32>,
+}
+
+/// # Deduplication output for a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeduplicationResult {
+    /// Identifier assigned to the document (position in the stream of processed documents)
+    pub id: u64,
+    /// Best matching indexed document if the document is a near-duplicate
+    pub duplicate_of: Option<DuplicateMatch>,
+}
+
+impl DeduplicationResult {
+    /// Returns true if the document is a near-duplicate of an indexed document
+    pub fn is_duplicate(&self) -> bool {
+        self.duplicate_of.is_some()
+    }
+}
+
+struct IndexedDocument {
+    signature: Vec<u64>,
+    band_hashes: Vec<u64>,
+    embedding: Option<Embedding>,
+}
+
+/// # Streaming near-duplicate detector
+pub struct Deduplicator {
+    min_hasher: MinHasher,
+    num_bands: usize,
+    jaccard_threshold: f32,
+    sentence_embeddings_model: Option<SentenceEmbeddingsModel>,
+    embedding_threshold: f32,
+    max_documents: Option<usize>,
+    documents: HashMap<u64, IndexedDocument>,
+    buckets: HashMap<(usize, u64), Vec<u64>>,
+    insertion_order: VecDeque<u64>,
+    next_id: u64,
+}
+
+impl Deduplicator {
+    /// Build a new `Deduplicator`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `DeduplicationConfig` object containing the MinHash and LSH parameters, and the optional sentence embeddings configuration
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::deduplication::Deduplicator;
+    ///
+    /// let deduplicator = Deduplicator::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: DeduplicationConfig) -> Result<Deduplicator, RustBertError> {
+        if config.num_bands == 0
+            || config.num_permutations == 0
+            || config.num_permutations % config.num_bands != 0
+        {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The number of bands ({}) must be a non-zero divisor of the number of permutations ({})",
+                config.num_bands, config.num_permutations
+            )));
+        }
+        let sentence_embeddings_model = config
+            .sentence_embeddings_config
+            .map(SentenceEmbeddingsModel::new)
+            .transpose()?;
+        Ok(Deduplicator {
+            min_hasher: MinHasher::new(config.num_permutations, config.shingle_size),
+            num_bands: config.num_bands,
+            jaccard_threshold: config.jaccard_threshold,
+            sentence_embeddings_model,
+            embedding_threshold: config.embedding_threshold,
+            max_documents: config.max_documents,
+            documents: HashMap::new(),
+            buckets: HashMap::new(),
+            insertion_order: VecDeque::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Returns the number of unique documents currently indexed
+    pub fn num_indexed_documents(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Clear the index of unique documents. Identifiers keep increasing.
+    pub fn clear(&mut self) {
+        self.documents.clear();
+        self.buckets.clear();
+        self.insertion_order.clear();
+    }
+
+    fn band_hashes(&self, signature: &[u64]) -> Vec<u64> {
+        signature
+            .chunks(signature.len() / self.num_bands)
+            .map(|band| fnv_hash(band.iter().flat_map(|value| value.to_le_bytes())))
+            .collect()
+    }
+
+    fn find_duplicate(
+        &self,
+        signature: &[u64],
+        band_hashes: &[u64],
+        embedding: Option<&Embedding>,
+    ) -> Option<DuplicateMatch> {
+        let mut best_match: Option<DuplicateMatch> = None;
+        let mut update = |candidate: DuplicateMatch| {
+            let score = candidate.similarity.unwrap_or(candidate.jaccard);
+            match &best_match {
+                Some(current) if current.similarity.unwrap_or(current.jaccard) >= score => {}
+                _ => best_match = Some(candidate),
+            }
+        };
+
+        for (band_index, band_hash) in band_hashes.iter().enumerate() {
+            if let Some(candidates) = self.buckets.get(&(band_index, *band_hash)) {
+                for candidate_id in candidates {
+                    let candidate = &self.documents[candidate_id];
+                    let jaccard = estimate_jaccard(signature, &candidate.signature);
+                    if jaccard >= self.jaccard_threshold {
+                        update(DuplicateMatch {
+                            id: *candidate_id,
+                            jaccard,
+                            similarity: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(embedding) = embedding {
+            for (candidate_id, candidate) in &self.documents {
+                if let Some(candidate_embedding) = &candidate.embedding {
+                    let similarity = cosine_similarity(embedding, candidate_embedding);
+                    if similarity >= self.embedding_threshold {
+                        update(DuplicateMatch {
+                            id: *candidate_id,
+                            jaccard: estimate_jaccard(signature, &candidate.signature),
+                            similarity: Some(similarity),
+                        });
+                    }
+                }
+            }
+        }
+        best_match
+    }
+
+    fn insert(&mut self, id: u64, document: IndexedDocument) {
+        for (band_index, band_hash) in document.band_hashes.iter().enumerate() {
+            self.buckets
+                .entry((band_index, *band_hash))
+                .or_default()
+                .push(id);
+        }
+        self.documents.insert(id, document);
+        self.insertion_order.push_back(id);
+
+        if let Some(max_documents) = self.max_documents {
+            while self.documents.len() > max_documents {
+                let evicted_id = match self.insertion_order.pop_front() {
+                    Some(evicted_id) => evicted_id,
+                    None => break,
+                };
+                if let Some(evicted) = self.documents.remove(&evicted_id) {
+                    for (band_index, band_hash) in evicted.band_hashes.iter().enumerate() {
+                        let key = (band_index, *band_hash);
+                        if let Some(bucket) = self.buckets.get_mut(&key) {
+                            bucket.retain(|&bucket_id| bucket_id != evicted_id);
+                            if bucket.is_empty() {
+                                self.buckets.remove(&key);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Deduplicate a batch of documents against the index of unique documents seen so far. Documents are processed
+    /// in order, so that a document may be a duplicate of an earlier document of the same batch. Unique documents
+    /// are added to the index, duplicates are not.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - slice of string-like documents
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<DeduplicationResult>, RustBertError>` deduplication output for each input document
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::deduplication::Deduplicator;
+    ///
+    /// let mut deduplicator = Deduplicator::new(Default::default())?;
+    /// let input = [
+    ///     "The quick brown fox jumps over the lazy dog near the river bank.",
+    ///     "The quick brown fox jumps over the lazy dog near the river bank!",
+    /// ];
+    /// let unique_documents = deduplicator
+    ///     .deduplicate(&input)?
+    ///     .iter()
+    ///     .zip(input)
+    ///     .filter_map(|(result, document)| (!result.is_duplicate()).then_some(document))
+    ///     .collect::<Vec<&str>>();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deduplicate<S>(
+        &mut self,
+        inputs: &[S],
+    ) -> Result<Vec<DeduplicationResult>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let embeddings = match &self.sentence_embeddings_model {
+            Some(model) if !inputs.is_empty() => Some(model.encode(inputs)?),
+            _ => None,
+        };
+
+        let mut output = Vec::with_capacity(inputs.len());
+        for (index, input) in inputs.iter().enumerate() {
+            let id = self.next_id;
+            self.next_id += 1;
+
+            let signature = self.min_hasher.signature(input.as_ref());
+            let band_hashes = self.band_hashes(&signature);
+            let embedding = embeddings
+                .as_ref()
+                .map(|embeddings| embeddings[index].clone());
+            let duplicate_of = self.find_duplicate(&signature, &band_hashes, embedding.as_ref());
+            if duplicate_of.is_none() {
+                self.insert(
+                    id,
+                    IndexedDocument {
+                        signature,
+                        band_hashes,
+                        embedding,
+                    },
+                );
+            }
+            output.push(DeduplicationResult { id, duplicate_of });
+        }
+        Ok(output)
+    }
+}
+
+fn cosine_similarity(left: &[f32], right: &[f32]) -> f32 {
+    let dot = left.iter().zip(right).map(|(l, r)| l * r).sum::<f32>();
+    let norm = left.iter().map(|v| v * v).sum::<f32>().sqrt()
+        * right.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        dot / norm
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = DeduplicationConfig::default();
+        let _: Box<dyn Send> = Box::new(Deduplicator::new(config));
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Ensemble of classification models
+//! Combines the predictions of several sequence classification models sharing the same labels (e.g. models fine-tuned
+//! from different checkpoints) into a single classifier. Each model is calibrated with a temperature, and the
+//! calibrated predictions are averaged with a weight per model, either in probability space (mixture of the predicted
+//! distributions) or in logit space (weighted geometric mean of the predicted distributions).
+//!
+//! The temperatures and weights default to 1 and uniform weights, and can be learned on a labelled validation set
+//! with `fit`, minimizing the negative log-likelihood of the ensemble predictions.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::ensemble::{ClassificationEnsemble, EnsembleFitConfig, EnsembleSpace};
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let models = vec![
+//!     SequenceClassificationModel::new(Default::default())?,
+//!     SequenceClassificationModel::new(Default::default())?,
+//! ];
+//! let mut ensemble = ClassificationEnsemble::new(models, EnsembleSpace::Probability)?;
+//!
+//! let validation_texts = ["A wonderful film.", "A waste of time."];
+//! let validation_labels = [1, 0];
+//! ensemble.fit(&validation_texts, &validation_labels, EnsembleFitConfig::default())?;
+//!
+//! let output = ensemble.predict(&["An instant classic."]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::mps;
+use crate::pipelines::sequence_classification::{Label, SequenceClassificationModel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tch::nn::OptimizerConfig;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Space in which the predictions of the models are averaged
+pub enum EnsembleSpace {
+    /// Weighted average of the label probabilities of the models
+    Probability,
+    /// Weighted average of the label log-probabilities of the models, normalized with a softmax
+    Logit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Configuration for the fitting of the ensemble temperatures and weights
+pub struct EnsembleFitConfig {
+    /// Number of optimization steps (default: 200)
+    pub iterations: usize,
+    /// Learning rate of the Adam optimizer (default: 0.05)
+    pub learning_rate: f64,
+    /// Learn a temperature per model in addition to the weights of the models (default: true)
+    pub fit_temperatures: bool,
+}
+
+impl Default for EnsembleFitConfig {
+    fn default() -> EnsembleFitConfig {
+        EnsembleFitConfig {
+            iterations: 200,
+            learning_rate: 0.05,
+            fit_temperatures: true,
+        }
+    }
+}
+
+/// # ClassificationEnsemble combining the predictions of several sequence classification models
+pub struct ClassificationEnsemble {
+    models: Vec<SequenceClassificationModel>,
+    space: EnsembleSpace,
+    weights: Vec<f64>,
+    temperatures: Vec<f64>,
+}
+
+impl ClassificationEnsemble {
+    /// Build a new `ClassificationEnsemble` with uniform weights and unit temperatures
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - `Vec<SequenceClassificationModel>` models to combine. The models must have the same label mapping
+    ///   and more than one output logit.
+    /// * `space` - `EnsembleSpace` in which the predictions are averaged
+    pub fn new(
+        models: Vec<SequenceClassificationModel>,
+        space: EnsembleSpace,
+    ) -> Result<ClassificationEnsemble, RustBertError> {
+        let first_model = models.first().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "An ensemble requires at least one model".to_string(),
+            )
+        })?;
+        if first_model.num_labels() < 2 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Ensembles of models with a single output logit are not supported".to_string(),
+            ));
+        }
+        if let Some(index) = models
+            .iter()
+            .position(|model| model.get_label_mapping() != first_model.get_label_mapping())
+        {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The labels of model {index} do not match the labels of the first model"
+            )));
+        }
+        let num_models = models.len();
+        Ok(ClassificationEnsemble {
+            models,
+            space,
+            weights: vec![1.0 / num_models as f64; num_models],
+            temperatures: vec![1.0; num_models],
+        })
+    }
+
+    /// Get a reference to the models of the ensemble
+    pub fn get_models(&self) -> &[SequenceClassificationModel] {
+        &self.models
+    }
+
+    /// Returns the weights of the models (summing to 1)
+    pub fn get_weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Returns the calibration temperatures of the models
+    pub fn get_temperatures(&self) -> &[f64] {
+        &self.temperatures
+    }
+
+    /// Sets the weights of the models. The weights are normalized to sum to 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - non-negative weight of each model
+    pub fn set_weights(&mut self, weights: &[f64]) -> Result<(), RustBertError> {
+        let total_weight: f64 = weights.iter().sum();
+        if (weights.len() != self.models.len())
+            | weights.iter().any(|weight| *weight < 0.0)
+            | (total_weight <= 0.0)
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Expected {} non-negative weights with a positive sum",
+                self.models.len()
+            )));
+        }
+        self.weights = weights.iter().map(|weight| weight / total_weight).collect();
+        Ok(())
+    }
+
+    /// Sets the calibration temperatures of the models (the logits of each model are divided by its temperature)
+    ///
+    /// # Arguments
+    ///
+    /// * `temperatures` - positive temperature of each model
+    pub fn set_temperatures(&mut self, temperatures: &[f64]) -> Result<(), RustBertError> {
+        if (temperatures.len() != self.models.len())
+            | temperatures.iter().any(|temperature| *temperature <= 0.0)
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Expected {} positive temperatures",
+                self.models.len()
+            )));
+        }
+        self.temperatures = temperatures.to_vec();
+        Ok(())
+    }
+
+    /// Returns the mapping from label ids to label names shared by the models
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        self.models[0].get_label_mapping()
+    }
+
+    /// Returns the number of labels of the models
+    pub fn num_labels(&self) -> usize {
+        self.models[0].num_labels()
+    }
+
+    /// Learns the temperatures and weights of the models on labelled examples (e.g. a validation set not used to
+    /// train the models) by minimizing the negative log-likelihood of the ensemble predictions.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` texts of the examples
+    /// * `labels` - `&[i64]` label id of each example
+    /// * `fit_config` - `EnsembleFitConfig` optimization settings
+    ///
+    /// # Returns
+    ///
+    /// * `f64` negative log-likelihood of the examples with the learned temperatures and weights
+    pub fn fit(
+        &mut self,
+        input: &[&str],
+        labels: &[i64],
+        fit_config: EnsembleFitConfig,
+    ) -> Result<f64, RustBertError> {
+        if input.is_empty() | (input.len() != labels.len()) {
+            return Err(RustBertError::ValueError(
+                "Expected a non-empty set of examples with one label per example".to_string(),
+            ));
+        }
+        let num_labels = self.num_labels() as i64;
+        if let Some(label) = labels
+            .iter()
+            .find(|label| (**label < 0) | (**label >= num_labels))
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Label {label} is not a label of the models"
+            )));
+        }
+        let logits = self.logits(input);
+        let labels = Tensor::from_slice(labels);
+
+        let vs = nn::VarStore::new(Device::Cpu);
+        let log_temperatures = vs.root().var_copy(
+            "log_temperatures",
+            &Tensor::from_slice(&self.temperatures)
+                .log()
+                .to_kind(Kind::Float),
+        );
+        let weight_logits = vs.root().var_copy(
+            "weight_logits",
+            &Tensor::from_slice(&self.weights)
+                .clamp_min(1e-12)
+                .log()
+                .to_kind(Kind::Float),
+        );
+        let mut optimizer = nn::Adam::default().build(&vs, fit_config.learning_rate)?;
+        for _ in 0..fit_config.iterations {
+            let temperatures = if fit_config.fit_temperatures {
+                log_temperatures.exp()
+            } else {
+                log_temperatures.exp().detach()
+            };
+            let log_probabilities = combine(
+                &logits,
+                &temperatures,
+                &weight_logits.softmax(-1, Kind::Float),
+                self.space,
+            );
+            let loss = log_probabilities.nll_loss(&labels);
+            optimizer.backward_step(&loss);
+        }
+
+        self.temperatures = Vec::<f64>::try_from(mps::to_double(&log_temperatures.exp()))?;
+        self.weights =
+            Vec::<f64>::try_from(mps::to_double(&weight_logits.softmax(-1, Kind::Float)))?;
+        let loss = no_grad(|| self.log_probabilities(&logits).nll_loss(&labels));
+        Ok(loss.double_value(&[]))
+    }
+
+    /// Classify texts with the ensemble
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing the most likely label of each input text, with its ensemble probability
+    pub fn predict<'a, S>(&self, input: S) -> Vec<Label>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        self.predict_scores(input.as_ref())
+            .into_iter()
+            .map(|labels| {
+                labels
+                    .into_iter()
+                    .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Scores all labels for each text with the ensemble
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing the ensemble probabilities of all labels (ordered by label id) for each input text
+    pub fn predict_scores(&self, input: &[&str]) -> Vec<Vec<Label>> {
+        if input.is_empty() {
+            return vec![];
+        }
+        let logits = self.logits(input);
+        let probabilities = no_grad(|| self.log_probabilities(&logits).exp());
+        let label_mapping = self.get_label_mapping();
+        (0..input.len())
+            .map(|sentence| {
+                probabilities
+                    .get(sentence as i64)
+                    .iter::<f64>()
+                    .unwrap()
+                    .enumerate()
+                    .map(|(id, score)| Label {
+                        text: label_mapping
+                            .get(&(id as i64))
+                            .cloned()
+                            .unwrap_or_else(|| format!("LABEL_{id}")),
+                        score,
+                        id: id as i64,
+                        sentence,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Logits of all models, of shape (*number of models*, *batch size*, *number of labels*), on the CPU
+    fn logits(&self, input: &[&str]) -> Tensor {
+        let logits = self
+            .models
+            .iter()
+            .map(|model| {
+                let (input_ids, token_type_ids) =
+                    model
+                        .get_tokenizer()
+                        .tokenize_and_pad(input, model.max_length, model.device);
+                let mask = match model.get_tokenizer().get_pad_id() {
+                    Some(pad_id) => input_ids.ne(pad_id).to_kind(Kind::Int64),
+                    None => input_ids.ones_like(),
+                };
+                no_grad(|| {
+                    model
+                        .sequence_classifier
+                        .forward_t(
+                            Some(&input_ids),
+                            Some(&mask),
+                            Some(&token_type_ids),
+                            None,
+                            None,
+                            false,
+                        )
+                        .to_kind(Kind::Float)
+                        .to(Device::Cpu)
+                })
+            })
+            .collect::<Vec<Tensor>>();
+        Tensor::stack(&logits, 0)
+    }
+
+    fn log_probabilities(&self, logits: &Tensor) -> Tensor {
+        combine(
+            logits,
+            &Tensor::from_slice(&self.temperatures).to_kind(Kind::Float),
+            &Tensor::from_slice(&self.weights).to_kind(Kind::Float),
+            self.space,
+        )
+    }
+}
+
+/// Combines the logits of the models (*number of models*, *batch size*, *number of labels*) calibrated with the
+/// temperatures and averaged with the weights of the models, returning the log-probabilities of the ensemble
+fn combine(
+    logits: &Tensor,
+    temperatures: &Tensor,
+    weights: &Tensor,
+    space: EnsembleSpace,
+) -> Tensor {
+    let log_probabilities = (logits / temperatures.view([-1, 1, 1])).log_softmax(-1, Kind::Float);
+    let weights = weights.view([-1, 1, 1]);
+    match space {
+        EnsembleSpace::Probability => (log_probabilities.exp() * weights)
+            .sum_dim_intlist([0].as_slice(), false, Kind::Float)
+            .clamp_min(1e-12)
+            .log(),
+        EnsembleSpace::Logit => (log_probabilities * weights)
+            .sum_dim_intlist([0].as_slice(), false, Kind::Float)
+            .log_softmax(-1, Kind::Float),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let model = SequenceClassificationModel::new(Default::default()).unwrap();
+        let _: Box<dyn Send> = Box::new(ClassificationEnsemble::new(
+            vec![model],
+            EnsembleSpace::Probability,
+        ));
+    }
+
+    #[test]
+    fn combined_probabilities() {
+        // Two models, one example, two labels
+        let logits = Tensor::from_slice(&[2.0f32, 0.0, 0.0, 2.0]).view([2, 1, 2]);
+        let temperatures = Tensor::from_slice(&[1.0f32, 1.0]);
+        let weights = Tensor::from_slice(&[0.5f32, 0.5]);
+        for space in [EnsembleSpace::Probability, EnsembleSpace::Logit] {
+            let probabilities = combine(&logits, &temperatures, &weights, space).exp();
+            // Symmetric predictions average to a uniform distribution
+            assert!((probabilities.double_value(&[0, 0]) - 0.5).abs() < 1e-6);
+            assert!((probabilities.double_value(&[0, 1]) - 0.5).abs() < 1e-6);
+        }
+
+        // A single model with a high temperature predicts a flatter distribution
+        let weights = Tensor::from_slice(&[1.0f32, 0.0]);
+        let temperatures = Tensor::from_slice(&[4.0f32, 1.0]);
+        let probabilities =
+            combine(&logits, &temperatures, &weights, EnsembleSpace::Probability).exp();
+        let expected = 1.0 / (1.0 + (-0.5f64).exp());
+        assert!((probabilities.double_value(&[0, 0]) - expected).abs() < 1e-6);
+    }
+}
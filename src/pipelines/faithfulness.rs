@@ -0,0 +1,449 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Faithfulness scoring pipeline
+//! Checks that summaries (or any generated text) are supported by their source document with a natural language
+//! inference (NLI) model: each sentence of the summary is used as a hypothesis against the source document (split in
+//! chunks fitting the model), and scored with the highest entailment probability over the chunks, in the style of
+//! SummaC. Sentences with an entailment probability below a threshold are flagged as likely hallucinations.
+//! By default, the dependencies for this model will be downloaded for a BART model fine-tuned on MNLI.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::faithfulness::FaithfulnessModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let faithfulness_model = FaithfulnessModel::new(Default::default())?;
+//!
+//! let source = "The Eiffel tower was completed in 1889. It is located in Paris.";
+//! let summary = "The Eiffel tower, in Paris, was completed in 1889. It is 500 meters tall.";
+//! let output = faithfulness_model.check(&[source], &[summary], 0.5)?;
+//! let hallucinations = output[0]
+//!     .sentences
+//!     .iter()
+//!     .filter(|sentence| sentence.is_hallucination);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A `FaithfulnessModel` can also be attached to a `SummarizationModel` to score the summaries as they are
+//! generated (see `SummarizationModel::summarize_with_faithfulness`).
+
+use crate::common::error::RustBertError;
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenTypeStrategy, TokenizerOption};
+use crate::pipelines::nli::find_label_id;
+use crate::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::pipelines::translation::split_sentences;
+use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use tch::Device;
+
+#[cfg(all(feature = "remote", feature = "bart"))]
+use crate::{
+    bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources},
+    resources::RemoteResource,
+};
+
+/// Number of (source chunk, summary sentence) pairs scored in a batch
+const BATCH_SIZE: usize = 16;
+
+/// # Faithfulness of a summary sentence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentenceFaithfulness {
+    /// Sentence of the summary
+    pub sentence: String,
+    /// Highest probability of the sentence being entailed by a chunk of the source document
+    pub entailment: f64,
+    /// Probability of the sentence being contradicted by the source chunk with the highest entailment
+    pub contradiction: f64,
+    /// Flag set when the entailment probability is below the threshold: the sentence is likely not supported by
+    /// the source document
+    pub is_hallucination: bool,
+}
+
+/// # Summary scored for faithfulness to its source document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredSummary {
+    /// Summary text
+    pub text: String,
+    /// Faithfulness of the summary: lowest entailment probability of its sentences (1 for empty summaries)
+    pub score: f64,
+    /// Faithfulness of each sentence of the summary
+    pub sentences: Vec<SentenceFaithfulness>,
+}
+
+impl ScoredSummary {
+    /// Checks if any sentence of the summary is flagged as a hallucination
+    pub fn has_hallucinations(&self) -> bool {
+        self.sentences
+            .iter()
+            .any(|sentence| sentence.is_hallucination)
+    }
+}
+
+/// # Configuration for FaithfulnessModel
+/// Contains information regarding the NLI model to load and device to place the model on.
+pub struct FaithfulnessConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: pretrained BART model fine-tuned on MNLI)
+    pub model_resource: ModelResource,
+    /// Config resource (default: pretrained BART model fine-tuned on MNLI)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: pretrained BART model fine-tuned on MNLI)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (default: pretrained BART model fine-tuned on MNLI)
+    pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Maximum number of tokens of the source chunks used as premises
+    pub max_premise_tokens: usize,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl FaithfulnessConfig {
+    /// Instantiate a new faithfulness scoring configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RC, RV>(
+        model_type: ModelType,
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: Option<RV>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> FaithfulnessConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        FaithfulnessConfig {
+            model_type,
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: merges_resource.map(|r| Box::new(r) as Box<_>),
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            max_premise_tokens: 400,
+            device: default_device(),
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bart"))]
+impl Default for FaithfulnessConfig {
+    /// Provides a default BART model fine-tuned on MNLI (English)
+    fn default() -> FaithfulnessConfig {
+        FaithfulnessConfig::new(
+            ModelType::Bart,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                BartModelResources::BART_MNLI,
+            ))),
+            RemoteResource::from_pretrained(BartConfigResources::BART_MNLI),
+            RemoteResource::from_pretrained(BartVocabResources::BART_MNLI),
+            Some(RemoteResource::from_pretrained(
+                BartMergesResources::BART_MNLI,
+            )),
+            false,
+            None,
+            None,
+        )
+    }
+}
+
+impl From<FaithfulnessConfig> for SequenceClassificationConfig {
+    fn from(config: FaithfulnessConfig) -> Self {
+        SequenceClassificationConfig {
+            model_type: config.model_type,
+            model_resource: config.model_resource,
+            config_resource: config.config_resource,
+            vocab_resource: config.vocab_resource,
+            merges_resource: config.merges_resource,
+            lower_case: config.lower_case,
+            strip_accents: config.strip_accents,
+            add_prefix_space: config.add_prefix_space,
+            device: config.device,
+            kind: None,
+            token_type_strategy: TokenTypeStrategy::default(),
+        }
+    }
+}
+
+/// # FaithfulnessModel to detect hallucinations in summaries
+pub struct FaithfulnessModel {
+    sequence_classification_model: SequenceClassificationModel,
+    entailment_id: i64,
+    contradiction_id: Option<i64>,
+    max_premise_tokens: usize,
+}
+
+impl FaithfulnessModel {
+    /// Build a new `FaithfulnessModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `faithfulness_config` - `FaithfulnessConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::faithfulness::FaithfulnessModel;
+    ///
+    /// let faithfulness_model = FaithfulnessModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        faithfulness_config: FaithfulnessConfig,
+    ) -> Result<FaithfulnessModel, RustBertError> {
+        let max_premise_tokens = faithfulness_config.max_premise_tokens;
+        let sequence_classification_model =
+            SequenceClassificationModel::new(faithfulness_config.into())?;
+        FaithfulnessModel::from_sequence_classification_model(
+            sequence_classification_model,
+            max_premise_tokens,
+        )
+    }
+
+    /// Build a new `FaithfulnessModel` with a provided tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `faithfulness_config` - `FaithfulnessConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer to use for faithfulness scoring.
+    pub fn new_with_tokenizer(
+        faithfulness_config: FaithfulnessConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<FaithfulnessModel, RustBertError> {
+        let max_premise_tokens = faithfulness_config.max_premise_tokens;
+        let sequence_classification_model =
+            SequenceClassificationModel::new_with_tokenizer(faithfulness_config.into(), tokenizer)?;
+        FaithfulnessModel::from_sequence_classification_model(
+            sequence_classification_model,
+            max_premise_tokens,
+        )
+    }
+
+    fn from_sequence_classification_model(
+        sequence_classification_model: SequenceClassificationModel,
+        max_premise_tokens: usize,
+    ) -> Result<FaithfulnessModel, RustBertError> {
+        let entailment_id =
+            find_label_id(&sequence_classification_model, "entail").ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "The model configuration must define an entailment label (e.g. `ENTAILMENT`)"
+                        .to_string(),
+                )
+            })?;
+        let contradiction_id = find_label_id(&sequence_classification_model, "contradict");
+        Ok(FaithfulnessModel {
+            sequence_classification_model,
+            entailment_id,
+            contradiction_id,
+            max_premise_tokens,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.sequence_classification_model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.sequence_classification_model.get_tokenizer_mut()
+    }
+
+    /// Scores the faithfulness of summaries to their source documents, flagging the summary sentences with an
+    /// entailment probability below the threshold as likely hallucinations
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - Source documents
+    /// * `summaries` - Summaries of the source documents, in the same order
+    /// * `threshold` - Summary sentences with an entailment probability below this threshold are flagged
+    ///
+    /// # Returns
+    /// * `Vec<ScoredSummary>` Scored summaries, in the input order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::faithfulness::FaithfulnessModel;
+    ///
+    /// let faithfulness_model = FaithfulnessModel::new(Default::default())?;
+    /// let output = faithfulness_model.check(
+    ///     &["The Eiffel tower was completed in 1889. It is located in Paris."],
+    ///     &["The Eiffel tower was completed in 1889 in Rome."],
+    ///     0.5,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check<S, T>(
+        &self,
+        sources: &[S],
+        summaries: &[T],
+        threshold: f64,
+    ) -> Result<Vec<ScoredSummary>, RustBertError>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        if sources.len() != summaries.len() {
+            return Err(RustBertError::ValueError(format!(
+                "The number of summaries ({}) must match the number of source documents ({})",
+                summaries.len(),
+                sources.len()
+            )));
+        }
+        Ok(sources
+            .iter()
+            .zip(summaries.iter())
+            .map(|(source, summary)| {
+                self.check_summary(source.as_ref(), summary.as_ref(), threshold)
+            })
+            .collect())
+    }
+
+    fn check_summary(&self, source: &str, summary: &str, threshold: f64) -> ScoredSummary {
+        let premises = self
+            .split_premises(source)
+            .into_iter()
+            .map(|range| &source[range])
+            .collect::<Vec<&str>>();
+        let hypotheses = split_sentences(summary, None)
+            .into_iter()
+            .map(|range| &summary[range])
+            .collect::<Vec<&str>>();
+
+        let pairs = hypotheses
+            .iter()
+            .flat_map(|hypothesis| premises.iter().map(move |premise| (*premise, *hypothesis)))
+            .collect::<Vec<(&str, &str)>>();
+        let scores = pairs
+            .chunks(BATCH_SIZE)
+            .flat_map(|batch| {
+                self.sequence_classification_model
+                    .predict_pairs_scores(batch)
+            })
+            .collect::<Vec<_>>();
+
+        let sentences = if premises.is_empty() {
+            hypotheses
+                .iter()
+                .map(|hypothesis| SentenceFaithfulness {
+                    sentence: hypothesis.to_string(),
+                    entailment: 0f64,
+                    contradiction: 0f64,
+                    is_hallucination: true,
+                })
+                .collect::<Vec<SentenceFaithfulness>>()
+        } else {
+            hypotheses
+                .iter()
+                .zip(scores.chunks(premises.len()))
+                .map(|(hypothesis, premise_scores)| {
+                    let (entailment, contradiction) = premise_scores
+                        .iter()
+                        .map(|labels| {
+                            let score = |id: i64| {
+                                labels
+                                    .iter()
+                                    .find(|label| label.id == id)
+                                    .map_or(0f64, |label| label.score)
+                            };
+                            (
+                                score(self.entailment_id),
+                                self.contradiction_id.map_or(0f64, score),
+                            )
+                        })
+                        .fold(
+                            (0f64, 0f64),
+                            |best, scores| {
+                                if scores.0 > best.0 {
+                                    scores
+                                } else {
+                                    best
+                                }
+                            },
+                        );
+                    SentenceFaithfulness {
+                        sentence: hypothesis.to_string(),
+                        entailment,
+                        contradiction,
+                        is_hallucination: entailment < threshold,
+                    }
+                })
+                .collect::<Vec<SentenceFaithfulness>>()
+        };
+        let score = sentences
+            .iter()
+            .map(|sentence| sentence.entailment)
+            .fold(1f64, f64::min);
+        ScoredSummary {
+            text: summary.to_string(),
+            score,
+            sentences,
+        }
+    }
+
+    /// Splits a source document into chunks of consecutive sentences of at most `max_premise_tokens` tokens
+    fn split_premises(&self, source: &str) -> Vec<Range<usize>> {
+        let tokenizer = self.sequence_classification_model.get_tokenizer();
+        let mut chunks: Vec<(Range<usize>, usize)> = vec![];
+        for sentence in split_sentences(source, None) {
+            let length = tokenizer.tokenize(&source[sentence.clone()]).len();
+            match chunks.last_mut() {
+                Some((chunk, chunk_length))
+                    if *chunk_length + length <= self.max_premise_tokens =>
+                {
+                    chunk.end = sentence.end;
+                    *chunk_length += length;
+                }
+                _ => chunks.push((sentence, length)),
+            }
+        }
+        chunks.into_iter().map(|(chunk, _)| chunk).collect()
+    }
+}
+
+#[cfg(all(test, feature = "remote", feature = "bart"))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = FaithfulnessConfig::default();
+        let _: Box<dyn Send> = Box::new(FaithfulnessModel::new(config));
+    }
+}
@@ -66,34 +66,79 @@
 //! # ;
 //! ```
 
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::BufReader;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use tch::kind::Kind::Int64;
-use tch::{no_grad, Device, Tensor};
+use tch::{no_grad, Device, Kind, Tensor};
 
+#[cfg(feature = "bart")]
 use crate::bart::LayerState as BartLayerState;
+#[cfg(feature = "bloom")]
+use crate::bloom::LayerState as BloomLayerState;
+use crate::common::precision::set_var_store_kind;
 use crate::common::resources::ResourceProvider;
+use crate::common::snapshot;
+#[cfg(feature = "falcon")]
+use crate::falcon::LayerState as FalconLayerState;
+#[cfg(feature = "gpt-j")]
 use crate::gpt_j::LayerState as GPTJLayerState;
+#[cfg(feature = "gpt-neo")]
 use crate::gpt_neo::LayerState as GPTNeoLayerState;
+#[cfg(feature = "llama")]
+use crate::llama::LayerState as LlamaLayerState;
+#[cfg(feature = "opt")]
+use crate::opt::LayerState as OptLayerState;
+#[cfg(feature = "phi")]
+use crate::phi::LayerState as PhiLayerState;
 use crate::pipelines::generation_utils::private_generation_utils::{
-    InternalGenerateOptions, PrivateLanguageGenerator,
+    InternalGenerateOptions, PrivateLanguageGenerator, StoppingConditions,
 };
+#[cfg(feature = "prophetnet")]
 use crate::prophetnet::LayerState as ProphetNetLayerState;
+#[cfg(feature = "reformer")]
 use crate::reformer::LayerState as ReformerLayerState;
+#[cfg(feature = "starcoder2")]
+use crate::starcoder2::LayerState as StarCoder2LayerState;
+#[cfg(feature = "t5")]
 use crate::t5::LayerState as T5LayerState;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::LayerState as XLNetLayerState;
 
 use self::ordered_float::OrderedFloat;
 use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
 
 extern crate ordered_float;
+#[cfg(all(feature = "remote", feature = "gpt2"))]
+use crate::common::settings::default_device;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::ONNXLayerCache;
 use crate::RustBertError;
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "gpt2"))]
 use crate::{
     gpt2::{Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources},
     resources::RemoteResource,
 };
 
+/// # Scope of the n-gram repetition blocking
+/// Sets the tokens considered when blocking repeated n-grams with `no_repeat_ngram_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoRepeatNgramScope {
+    /// N-grams of the full sequence are blocked, including the prompt of decoder-only models
+    FullSequence,
+    /// Only n-grams of the generated continuation are blocked: repeating n-grams of the prompt is allowed
+    Generated,
+}
+
 /// # Configuration for text generation
 pub struct GenerateConfig {
     /// Model type used for generation
@@ -128,17 +173,42 @@ pub struct GenerateConfig {
     pub length_penalty: f64,
     /// Number of allowed repetitions of n-grams. Values higher than 0 turn on this feature (default: 3)
     pub no_repeat_ngram_size: i64,
+    /// Tokens considered for the n-gram repetition blocking: the full sequence or the generated continuation only (default: FullSequence)
+    pub no_repeat_ngram_scope: NoRepeatNgramScope,
+    /// Size of the n-grams of the encoder input that may not be repeated in the generated output (encoder-decoder models only). Values higher than 0 turn on this feature (default: 0)
+    pub encoder_no_repeat_ngram_size: i64,
     /// Number of sequences to return for each prompt text (default: 1)
     pub num_return_sequences: i64,
     /// Number of beam groups for diverse beam generation. If provided and higher than 1, will split the beams into beam subgroups leading to more diverse generation.
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Degeneration penalty for [contrastive search, Su et al.](https://arxiv.org/abs/2202.06417). If provided with greedy decoding (`do_sample` false and `num_beams` 1) and `top_k` higher than 1, selects the next token among the `top_k` most likely candidates, penalizing candidates whose hidden state is similar to the context. Only supported by decoder-only models returning their hidden states, other models fall back to greedy decoding (default: None)
+    pub penalty_alpha: Option<f64>,
+    /// Typical_p value for [locally typical sampling, Meister et al.](https://arxiv.org/abs/2202.00666). Keep the tokens whose information content is the closest to the entropy of the distribution until their cumulative probability reaches typical_p (default: None)
+    pub typical_p: Option<f64>,
+    /// Eta cutoff for [eta sampling, Hewitt et al.](https://arxiv.org/abs/2210.15191). Remove the tokens with a probability lower than `min(eta_cutoff, sqrt(eta_cutoff) * exp(-entropy))`. Suggested values range from 3e-4 to 2e-3 (default: None)
+    pub eta_cutoff: Option<f64>,
+    /// Epsilon cutoff for [epsilon sampling, Hewitt et al.](https://arxiv.org/abs/2210.15191). Remove the tokens with a probability lower than epsilon_cutoff. Suggested values range from 3e-4 to 9e-4 (default: None)
+    pub epsilon_cutoff: Option<f64>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Optional cache of the encoder outputs for repeated inputs (encoder-decoder models only, default: None)
+    pub encoder_cache: Option<EncoderOutputCache>,
+    /// Sequences of text stopping the generation of a sequence once generated (e.g. `"\n\n"`). The stop sequence is kept in the output (default: empty)
+    pub stop_sequences: Vec<String>,
+    /// Custom stopping criteria evaluated for each sequence after every decoding step (default: empty)
+    pub stopping_criteria: Vec<Box<dyn StoppingCriteria>>,
+    /// Logits processors modifying the scores of the next token at every decoding step, applied in order (default: empty)
+    pub logits_processors: Vec<Box<dyn LogitsProcessor>>,
+    /// List of bad word ids (may be a sequence of word ids) that will be banned during the generation (default: empty)
+    pub bad_word_ids: Vec<Vec<i64>>,
+    /// Biases added to the score of the last token of token sequences when the preceding tokens of the sequence were
+    /// generated (single token sequences are always biased). Negative values discourage the sequence, `f64::NEG_INFINITY` bans it. The sequences cannot be empty (default: empty)
+    pub sequence_bias: Vec<(Vec<i64>, f64)>,
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "gpt2"))]
 impl Default for GenerateConfig {
     fn default() -> GenerateConfig {
         GenerateConfig {
@@ -162,16 +232,158 @@ impl Default for GenerateConfig {
             repetition_penalty: 1.0,
             length_penalty: 1.0,
             no_repeat_ngram_size: 3,
+            no_repeat_ngram_scope: NoRepeatNgramScope::FullSequence,
+            encoder_no_repeat_ngram_size: 0,
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
-            device: Device::cuda_if_available(),
+            penalty_alpha: None,
+            typical_p: None,
+            eta_cutoff: None,
+            epsilon_cutoff: None,
+            device: default_device(),
+            encoder_cache: None,
+            stop_sequences: Vec::new(),
+            stopping_criteria: Vec::new(),
+            logits_processors: Vec::new(),
+            bad_word_ids: Vec::new(),
+            sequence_bias: Vec::new(),
+        }
+    }
+}
+
+/// # Cache of encoder outputs for encoder-decoder generation
+/// Stores the encoder hidden states of the most recent input batches, keyed by a hash of the input ids
+/// and attention mask. Generating from a batch identical to a cached one (e.g. retries or repeated
+/// translations of the same texts) skips the encoder forward pass.
+///
+/// The cache is cheap to clone: clones share the same storage. Cached outputs are tied to the weights of
+/// the model that computed them: the cache should be cleared if the model is modified (e.g. converted to
+/// half precision) and should not be shared across models.
+#[derive(Clone, Debug)]
+pub struct EncoderOutputCache {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<EncoderCacheEntry>>>,
+}
+
+#[derive(Debug)]
+struct EncoderCacheEntry {
+    key: u64,
+    input_ids: Tensor,
+    attention_mask: Tensor,
+    encoder_output: Tensor,
+}
+
+impl EncoderOutputCache {
+    /// Create a new cache storing the encoder outputs of up to `capacity` input batches.
+    /// The least recently used entries are evicted first.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::generation_utils::EncoderOutputCache;
+    /// use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
+    ///
+    /// let summarization_config = SummarizationConfig {
+    ///     encoder_cache: Some(EncoderOutputCache::new(64)),
+    ///     ..Default::default()
+    /// };
+    /// let summarization_model = SummarizationModel::new(summarization_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(capacity: usize) -> EncoderOutputCache {
+        EncoderOutputCache {
+            capacity,
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// Returns the maximum number of cached input batches
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cached input batches
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns true if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached encoder outputs
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn hash_key(input_ids: &Tensor, attention_mask: &Tensor) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        input_ids.size().hash(&mut hasher);
+        format!("{:?}", input_ids.device()).hash(&mut hasher);
+        Vec::<i64>::try_from(input_ids.flatten(0, -1))
+            .ok()?
+            .hash(&mut hasher);
+        Vec::<i64>::try_from(attention_mask.to_kind(Int64).flatten(0, -1))
+            .ok()?
+            .hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Returns the cached encoder output for the inputs, computing and storing it with `encode` if missing
+    pub(crate) fn get_or_insert_with<F>(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        encode: F,
+    ) -> Tensor
+    where
+        F: FnOnce() -> Tensor,
+    {
+        if self.capacity == 0 {
+            return encode();
         }
+        let key = match Self::hash_key(input_ids, attention_mask) {
+            Some(key) => key,
+            None => return encode(),
+        };
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let position = entries.iter().position(|entry| {
+                entry.key == key
+                    && entry.input_ids.size() == input_ids.size()
+                    && entry.input_ids.device() == input_ids.device()
+                    && entry.input_ids.equal(input_ids)
+                    && entry.attention_mask.equal(attention_mask)
+            });
+            if let Some(position) = position {
+                let entry = entries.remove(position).unwrap();
+                let encoder_output = entry.encoder_output.shallow_clone();
+                entries.push_back(entry);
+                return encoder_output;
+            }
+        }
+        // The lock is released while encoding so that concurrent generations are not serialized
+        let encoder_output = encode();
+        let mut entries = self.entries.lock().unwrap();
+        while entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(EncoderCacheEntry {
+            key,
+            input_ids: input_ids.shallow_clone(),
+            attention_mask: attention_mask.shallow_clone(),
+            encoder_output: encoder_output.shallow_clone(),
+        });
+        encoder_output
     }
 }
 
 impl GenerateConfig {
-    pub(crate) fn validate(&self) {
+    pub(crate) fn validate(&self) -> Result<(), RustBertError> {
         assert!(self.temperature > 0f64, "temperature must positive");
         assert!(
             (self.top_p >= 0f64) & (self.top_p <= 1f64),
@@ -216,25 +428,423 @@ impl GenerateConfig {
                 )
             }
         }
+        validate_sequence_bias(&self.sequence_bias)
+    }
+}
+
+/// # Named presets of generation settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GenerationPreset {
+    /// Beam search with n-gram repetition blocking, for factual outputs (e.g. summaries, answers)
+    Precise,
+    /// Nucleus sampling with a moderate repetition penalty, for general purpose text generation
+    Balanced,
+    /// Nucleus sampling with a higher temperature, for varied and original outputs
+    Creative,
+    /// Greedy decoding: the same input always produces the same output
+    Deterministic,
+}
+
+impl FromStr for GenerationPreset {
+    type Err = RustBertError;
+
+    fn from_str(preset: &str) -> Result<Self, Self::Err> {
+        match preset.trim().to_lowercase().as_str() {
+            "precise" => Ok(GenerationPreset::Precise),
+            "balanced" => Ok(GenerationPreset::Balanced),
+            "creative" => Ok(GenerationPreset::Creative),
+            "deterministic" => Ok(GenerationPreset::Deterministic),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Unknown generation preset {preset}, expected one of precise, balanced, creative or deterministic"
+            ))),
+        }
+    }
+}
+
+/// # Generation settings of a `generation_config.json` file
+/// Recommended generation settings shipped with a model (as saved by the Transformers library). Settings absent
+/// from the file are left unchanged when applied to a `GenerateConfig`. Settings without an equivalent in
+/// `GenerateConfig` (e.g. special token ids or `max_new_tokens`) are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationConfigFile {
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub do_sample: Option<bool>,
+    /// Either a boolean or `"never"` (equivalent to `false`)
+    pub early_stopping: Option<serde_json::Value>,
+    pub num_beams: Option<i64>,
+    pub temperature: Option<f64>,
+    pub top_k: Option<i64>,
+    pub top_p: Option<f64>,
+    pub repetition_penalty: Option<f64>,
+    pub length_penalty: Option<f64>,
+    pub no_repeat_ngram_size: Option<i64>,
+    pub encoder_no_repeat_ngram_size: Option<i64>,
+    pub num_return_sequences: Option<i64>,
+    pub num_beam_groups: Option<i64>,
+    pub diversity_penalty: Option<f64>,
+    pub penalty_alpha: Option<f64>,
+    pub typical_p: Option<f64>,
+    pub eta_cutoff: Option<f64>,
+    pub epsilon_cutoff: Option<f64>,
+}
+
+impl GenerationConfigFile {
+    /// Reads the generation settings from a `generation_config.json` file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the `generation_config.json` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<GenerationConfigFile, RustBertError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!(
+                "Invalid generation configuration file {}: {error}",
+                path.display()
+            ))
+        })
+    }
+}
+
+/// Name of the file storing the recommended generation settings of a model
+pub const GENERATION_CONFIG_FILE: &str = "generation_config.json";
+
+impl GenerateConfig {
+    /// Default configuration tuned for factual outputs (see `GenerationPreset::Precise`)
+    #[cfg(all(feature = "remote", feature = "gpt2"))]
+    pub fn precise() -> GenerateConfig {
+        GenerateConfig::default().with_preset(GenerationPreset::Precise)
+    }
+
+    /// Default configuration tuned for general purpose generation (see `GenerationPreset::Balanced`)
+    #[cfg(all(feature = "remote", feature = "gpt2"))]
+    pub fn balanced() -> GenerateConfig {
+        GenerateConfig::default().with_preset(GenerationPreset::Balanced)
+    }
+
+    /// Default configuration tuned for varied outputs (see `GenerationPreset::Creative`)
+    #[cfg(all(feature = "remote", feature = "gpt2"))]
+    pub fn creative() -> GenerateConfig {
+        GenerateConfig::default().with_preset(GenerationPreset::Creative)
+    }
+
+    /// Default configuration with greedy decoding (see `GenerationPreset::Deterministic`)
+    #[cfg(all(feature = "remote", feature = "gpt2"))]
+    pub fn deterministic() -> GenerateConfig {
+        GenerateConfig::default().with_preset(GenerationPreset::Deterministic)
+    }
+
+    /// Applies the decoding settings of a preset, keeping the resources, length limits and device of the configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `preset` - `GenerationPreset` to apply
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, GenerationPreset};
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     max_length: Some(128),
+    ///     ..Default::default()
+    /// }
+    /// .with_preset(GenerationPreset::Creative);
+    /// ```
+    pub fn with_preset(self, preset: GenerationPreset) -> GenerateConfig {
+        match preset {
+            GenerationPreset::Precise => GenerateConfig {
+                do_sample: false,
+                num_beams: 4,
+                early_stopping: true,
+                temperature: 1.0,
+                top_k: 0,
+                top_p: 1.0,
+                repetition_penalty: 1.0,
+                length_penalty: 1.0,
+                no_repeat_ngram_size: 3,
+                num_return_sequences: 1,
+                num_beam_groups: None,
+                diversity_penalty: None,
+                penalty_alpha: None,
+                typical_p: None,
+                eta_cutoff: None,
+                epsilon_cutoff: None,
+                ..self
+            },
+            GenerationPreset::Balanced => GenerateConfig {
+                do_sample: true,
+                num_beams: 1,
+                early_stopping: false,
+                temperature: 1.0,
+                top_k: 50,
+                top_p: 0.9,
+                repetition_penalty: 1.1,
+                length_penalty: 1.0,
+                no_repeat_ngram_size: 0,
+                num_return_sequences: 1,
+                num_beam_groups: None,
+                diversity_penalty: None,
+                penalty_alpha: None,
+                typical_p: None,
+                eta_cutoff: None,
+                epsilon_cutoff: None,
+                ..self
+            },
+            GenerationPreset::Creative => GenerateConfig {
+                do_sample: true,
+                num_beams: 1,
+                early_stopping: false,
+                temperature: 1.3,
+                top_k: 0,
+                top_p: 0.95,
+                repetition_penalty: 1.2,
+                length_penalty: 1.0,
+                no_repeat_ngram_size: 0,
+                num_return_sequences: 1,
+                num_beam_groups: None,
+                diversity_penalty: None,
+                penalty_alpha: None,
+                typical_p: None,
+                eta_cutoff: None,
+                epsilon_cutoff: None,
+                ..self
+            },
+            GenerationPreset::Deterministic => GenerateConfig {
+                do_sample: false,
+                num_beams: 1,
+                early_stopping: false,
+                temperature: 1.0,
+                top_k: 0,
+                top_p: 1.0,
+                repetition_penalty: 1.0,
+                length_penalty: 1.0,
+                no_repeat_ngram_size: 0,
+                num_return_sequences: 1,
+                num_beam_groups: None,
+                diversity_penalty: None,
+                penalty_alpha: None,
+                typical_p: None,
+                eta_cutoff: None,
+                epsilon_cutoff: None,
+                ..self
+            },
+        }
+    }
+
+    /// Applies the settings of a `GenerationConfigFile`, overriding the settings present in the file
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `GenerationConfigFile` recommended generation settings of the model
+    pub fn with_generation_config(
+        self,
+        generation_config: &GenerationConfigFile,
+    ) -> GenerateConfig {
+        let early_stopping = match &generation_config.early_stopping {
+            Some(serde_json::Value::Bool(early_stopping)) => *early_stopping,
+            Some(serde_json::Value::String(_)) => false,
+            _ => self.early_stopping,
+        };
+        GenerateConfig {
+            min_length: generation_config.min_length.unwrap_or(self.min_length),
+            max_length: generation_config.max_length.or(self.max_length),
+            do_sample: generation_config.do_sample.unwrap_or(self.do_sample),
+            early_stopping,
+            num_beams: generation_config.num_beams.unwrap_or(self.num_beams),
+            temperature: generation_config.temperature.unwrap_or(self.temperature),
+            top_k: generation_config.top_k.unwrap_or(self.top_k),
+            top_p: generation_config.top_p.unwrap_or(self.top_p),
+            repetition_penalty: generation_config
+                .repetition_penalty
+                .unwrap_or(self.repetition_penalty),
+            length_penalty: generation_config
+                .length_penalty
+                .unwrap_or(self.length_penalty),
+            no_repeat_ngram_size: generation_config
+                .no_repeat_ngram_size
+                .unwrap_or(self.no_repeat_ngram_size),
+            encoder_no_repeat_ngram_size: generation_config
+                .encoder_no_repeat_ngram_size
+                .unwrap_or(self.encoder_no_repeat_ngram_size),
+            num_return_sequences: generation_config
+                .num_return_sequences
+                .unwrap_or(self.num_return_sequences),
+            num_beam_groups: generation_config.num_beam_groups.or(self.num_beam_groups),
+            diversity_penalty: generation_config
+                .diversity_penalty
+                .or(self.diversity_penalty),
+            penalty_alpha: generation_config.penalty_alpha.or(self.penalty_alpha),
+            typical_p: generation_config.typical_p.or(self.typical_p),
+            eta_cutoff: generation_config.eta_cutoff.or(self.eta_cutoff),
+            epsilon_cutoff: generation_config.epsilon_cutoff.or(self.epsilon_cutoff),
+            ..self
+        }
+    }
+
+    /// Applies the recommended settings of the model from the `generation_config.json` file located next to the
+    /// model configuration file, if present. The configuration is returned unchanged if there is no such file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    ///
+    /// let generate_config = GenerateConfig::default().with_recommended_defaults()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_recommended_defaults(self) -> Result<GenerateConfig, RustBertError> {
+        let config_path = self.config_resource.get_local_path()?;
+        let generation_config_path = match config_path.parent() {
+            Some(model_dir) => model_dir.join(GENERATION_CONFIG_FILE),
+            None => return Ok(self),
+        };
+        if !generation_config_path.is_file() {
+            return Ok(self);
+        }
+        let generation_config = GenerationConfigFile::from_file(generation_config_path)?;
+        Ok(self.with_generation_config(&generation_config))
     }
 }
 
 #[derive(Debug)]
 pub enum Cache {
     GPT2Cache(Option<Vec<Tensor>>),
+    #[cfg(feature = "bart")]
     BARTCache(Option<Vec<(Option<BartLayerState>, Option<BartLayerState>)>>),
+    #[cfg(feature = "t5")]
     T5Cache(Option<Vec<(Option<T5LayerState>, Option<T5LayerState>)>>),
+    #[cfg(feature = "longt5")]
     LongT5Cache(Option<Vec<(Option<T5LayerState>, Option<T5LayerState>)>>),
+    #[cfg(feature = "xlnet")]
     XLNetCache(Option<Vec<Option<XLNetLayerState>>>),
+    #[cfg(feature = "reformer")]
     ReformerCache(Option<Vec<Option<ReformerLayerState>>>),
+    #[cfg(feature = "prophetnet")]
     ProphetNetCache(Option<Vec<(Option<ProphetNetLayerState>, Option<ProphetNetLayerState>)>>),
+    #[cfg(feature = "gpt-neo")]
     GPTNeoCache(Option<Vec<Option<GPTNeoLayerState>>>),
+    #[cfg(feature = "gpt-j")]
     GPTJCache(Option<Vec<Option<GPTJLayerState>>>),
+    #[cfg(feature = "starcoder2")]
+    StarCoder2Cache(Option<Vec<Option<StarCoder2LayerState>>>),
+    #[cfg(feature = "llama")]
+    LlamaCache(Option<Vec<Option<LlamaLayerState>>>),
+    #[cfg(feature = "bloom")]
+    BloomCache(Option<Vec<Option<BloomLayerState>>>),
+    #[cfg(feature = "opt")]
+    OptCache(Option<Vec<Option<OptLayerState>>>),
+    #[cfg(feature = "phi")]
+    PhiCache(Option<Vec<Option<PhiLayerState>>>),
+    #[cfg(feature = "falcon")]
+    FalconCache(Option<Vec<Option<FalconLayerState>>>),
     #[cfg(feature = "onnx")]
     ONNXCache(ONNXLayerCache),
     None,
 }
 
+impl Cache {
+    /// Keeps the cached keys and values of the first `length` positions of the sequence (used to roll back tokens
+    /// rejected during assisted generation, or to reuse the cache of a shared context). The truncated cache holds
+    /// views of the original keys and values. Returns `None` if the cache does not hold the keys and values of every
+    /// layer or cannot be truncated, in which case the sequence needs to be processed again without cache.
+    pub(crate) fn truncate(&self, length: i64) -> Option<Cache> {
+        match self {
+            Cache::GPT2Cache(Some(layer_past)) => Some(Cache::GPT2Cache(Some(
+                layer_past
+                    .iter()
+                    .map(|past| past.narrow(-2, 0, length))
+                    .collect(),
+            ))),
+            #[cfg(feature = "gpt-neo")]
+            Cache::GPTNeoCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::GPTNeoCache(Some(layer_states))),
+            #[cfg(feature = "gpt-j")]
+            Cache::GPTJCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::GPTJCache(Some(layer_states))),
+            #[cfg(feature = "starcoder2")]
+            Cache::StarCoder2Cache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::StarCoder2Cache(Some(layer_states))),
+            #[cfg(feature = "llama")]
+            Cache::LlamaCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::LlamaCache(Some(layer_states))),
+            #[cfg(feature = "bloom")]
+            Cache::BloomCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::BloomCache(Some(layer_states))),
+            #[cfg(feature = "opt")]
+            Cache::OptCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::OptCache(Some(layer_states))),
+            #[cfg(feature = "phi")]
+            Cache::PhiCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::PhiCache(Some(layer_states))),
+            #[cfg(feature = "falcon")]
+            Cache::FalconCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|layer_state| {
+                    layer_state
+                        .as_ref()
+                        .map(|state| Some(state.truncate(length)))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|layer_states| Cache::FalconCache(Some(layer_states))),
+            _ => None,
+        }
+    }
+}
+
 pub(crate) mod private_generation_utils {
     use rust_tokenizers::TokenIdsWithOffsets;
     use std::cmp::{max, min};
@@ -247,11 +857,13 @@ pub(crate) mod private_generation_utils {
 
     use crate::pipelines::common::TokenizerOption;
     use crate::pipelines::generation_utils::{
-        BeamHypotheses, Cache, GenerateConfig, LMModelOutput, PrefixAllowedFunction,
+        BeamHypotheses, Cache, GenerateConfig, LMModelOutput, LogitsProcessor, PhrasalConstraint,
+        PrefixAllowedFunction, StoppingCriteria, TokenCallback, TokenTrie,
     };
 
     use super::ordered_float::OrderedFloat;
     use crate::common::kind::{get_negative_infinity, get_positive_infinity};
+    use crate::common::mps;
     use crate::RustBertError;
 
     pub struct InternalGenerateOptions<'a> {
@@ -263,6 +875,9 @@ pub(crate) mod private_generation_utils {
         pub top_p: f64,
         pub repetition_penalty: f64,
         pub no_repeat_ngram_size: i64,
+        pub no_repeat_ngram_start: i64,
+        pub encoder_no_repeat_ngram_size: i64,
+        pub encoder_input_ids: Option<Vec<Vec<i64>>>,
         pub pad_token_id: Option<i64>,
         pub eos_token_ids: Option<Vec<i64>>,
         pub num_return_sequences: i64,
@@ -271,8 +886,106 @@ pub(crate) mod private_generation_utils {
         pub length_penalty: f64,
         pub num_beam_groups: Option<i64>,
         pub diversity_penalty: Option<f64>,
+        pub penalty_alpha: Option<f64>,
+        pub typical_p: Option<f64>,
+        pub eta_cutoff: Option<f64>,
+        pub epsilon_cutoff: Option<f64>,
         pub forced_bos_token_id: Option<i64>,
+        pub forced_eos_token_id: Option<i64>,
         pub bad_word_ids: Option<&'a Vec<Vec<i64>>>,
+        pub constraints: Option<&'a [PhrasalConstraint]>,
+        pub allowed_outputs: Option<&'a TokenTrie>,
+        pub stopping_conditions: StoppingConditions<'a>,
+        pub logits_processors: Vec<&'a dyn LogitsProcessor>,
+    }
+
+    pub struct StoppingConditions<'a> {
+        stop_sequences: Vec<&'a str>,
+        stopping_criteria: Vec<&'a dyn StoppingCriteria>,
+        stop_sequence_window: usize,
+    }
+
+    impl<'a> StoppingConditions<'a> {
+        pub fn new(
+            stop_sequences: Vec<&'a str>,
+            stopping_criteria: Vec<&'a dyn StoppingCriteria>,
+        ) -> StoppingConditions<'a> {
+            let stop_sequences = stop_sequences
+                .into_iter()
+                .filter(|stop_sequence| !stop_sequence.is_empty())
+                .collect::<Vec<&str>>();
+            // Tokens span at least one byte: a stop sequence completed by the last token is contained in the
+            // decoding of as many trailing tokens as it has bytes, plus one for a token partially overlapping it.
+            let stop_sequence_window = stop_sequences
+                .iter()
+                .map(|stop_sequence| stop_sequence.len() + 1)
+                .max()
+                .unwrap_or(0);
+            StoppingConditions {
+                stop_sequences,
+                stopping_criteria,
+                stop_sequence_window,
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.stop_sequences.is_empty() & self.stopping_criteria.is_empty()
+        }
+
+        /// Flags the sequences of the batch meeting a stopping condition. Sequences marked as finished in
+        /// `unfinished_sequences` (if provided) are not evaluated.
+        pub fn stopped_sequences(
+            &self,
+            tokenizer: &TokenizerOption,
+            input_ids: &Tensor,
+            unfinished_sequences: Option<&Tensor>,
+            start_length: i64,
+        ) -> Vec<bool> {
+            let input_ids = input_ids.to_device(Device::Cpu);
+            let unfinished_sequences = unfinished_sequences.map(|unfinished_sequences| {
+                Vec::<i64>::try_from(unfinished_sequences.to_device(Device::Cpu)).unwrap()
+            });
+            let input_size = input_ids.size();
+            let generated_length = max(input_size[1] - start_length, 0) as usize;
+            (0..input_size[0])
+                .map(|sequence_index| {
+                    if let Some(unfinished_sequences) = &unfinished_sequences {
+                        if unfinished_sequences[sequence_index as usize] == 0 {
+                            return false;
+                        }
+                    }
+                    let token_ids = input_ids
+                        .get(sequence_index)
+                        .iter::<i64>()
+                        .unwrap()
+                        .collect::<Vec<i64>>();
+                    self.should_stop(tokenizer, &token_ids, generated_length)
+                })
+                .collect()
+        }
+
+        fn should_stop(
+            &self,
+            tokenizer: &TokenizerOption,
+            token_ids: &[i64],
+            generated_length: usize,
+        ) -> bool {
+            if self
+                .stopping_criteria
+                .iter()
+                .any(|criterion| criterion.should_stop(token_ids, generated_length))
+            {
+                return true;
+            }
+            if self.stop_sequences.is_empty() | (generated_length == 0) {
+                return false;
+            }
+            let window = min(generated_length, self.stop_sequence_window);
+            let text = tokenizer.decode(&token_ids[token_ids.len() - window..], true, false);
+            self.stop_sequences
+                .iter()
+                .any(|stop_sequence| text.contains(stop_sequence))
+        }
     }
 
     pub struct PreparedInput<'a> {
@@ -329,6 +1042,7 @@ pub(crate) mod private_generation_utils {
             current_length: i64,
             max_length: Option<i64>,
             forced_bos_token_id: Option<i64>,
+            forced_eos_token_id: Option<i64>,
         ) {
             if current_length == 1 {
                 if let Some(forced_bos_token_id) =
@@ -341,7 +1055,9 @@ pub(crate) mod private_generation_utils {
                     );
                 }
             } else if let Some(max_length) = max_length {
-                if let Some(forced_eos_token_id) = self.get_forced_eos_token_id() {
+                if let Some(forced_eos_token_id) =
+                    forced_eos_token_id.or(self.get_forced_eos_token_id())
+                {
                     if current_length == max_length - 1 {
                         force_token_id_generation(
                             scores,
@@ -353,6 +1069,27 @@ pub(crate) mod private_generation_utils {
             }
         }
 
+        /// Returns true if the next token is forced by `prepare_scores_for_generation` (forced beginning or end of
+        /// sequence token). The constraints on the generated sequences are not applied to the forced tokens.
+        fn is_forced_token_step(
+            &self,
+            current_length: i64,
+            max_length: Option<i64>,
+            forced_bos_token_id: Option<i64>,
+            forced_eos_token_id: Option<i64>,
+        ) -> bool {
+            if current_length == 1 {
+                forced_bos_token_id
+                    .or(self.get_forced_bos_token_id())
+                    .is_some()
+            } else {
+                max_length.map_or(false, |max_length| current_length == max_length - 1)
+                    && forced_eos_token_id
+                        .or(self.get_forced_eos_token_id())
+                        .is_some()
+            }
+        }
+
         fn encode(&self, _input_ids: &Tensor, _attention_mask: Option<&Tensor>) -> Option<Tensor> {
             None
         }
@@ -507,10 +1244,12 @@ pub(crate) mod private_generation_utils {
             &self,
             input_ids: &Tensor,
             no_repeat_ngram_size: i64,
+            no_repeat_ngram_start: i64,
             cur_len: i64,
         ) -> Vec<Vec<i64>> {
             //        Ported from hugging face's transformers and fairseq (https://github.com/pytorch/fairseq/blob/master/fairseq/sequence_generator.py)
-            if cur_len + 1 < no_repeat_ngram_size {
+            // N-grams starting before `no_repeat_ngram_start` (e.g. in the prompt) are not blocked
+            if cur_len + 1 < no_repeat_ngram_start + no_repeat_ngram_size {
                 vec![vec![]]
             } else {
                 let input_ids = input_ids.to(Device::Cpu);
@@ -519,7 +1258,8 @@ pub(crate) mod private_generation_utils {
                 for hypothesis_index in 0..num_hypothesis {
                     let hypothesis_input_ids = input_ids.get(hypothesis_index);
                     let mut generated_ngram: HashMap<Vec<i64>, Vec<i64>> = HashMap::new();
-                    let input: Vec<i64> = (0..hypothesis_input_ids.size1().unwrap()).collect();
+                    let input: Vec<i64> =
+                        (no_repeat_ngram_start..hypothesis_input_ids.size1().unwrap()).collect();
                     let hypothesis_input_ids = hypothesis_input_ids
                         .iter::<i64>()
                         .unwrap()
@@ -549,6 +1289,40 @@ pub(crate) mod private_generation_utils {
             }
         }
 
+        fn get_encoder_banned_tokens(
+            &self,
+            input_ids: &Tensor,
+            encoder_input_ids: &[Vec<i64>],
+            encoder_no_repeat_ngram_size: i64,
+            cur_len: i64,
+        ) -> Vec<Vec<i64>> {
+            // Bans the tokens completing an n-gram of the encoder input from the last generated tokens
+            let num_hypothesis = *input_ids.size().first().unwrap();
+            if cur_len + 1 < encoder_no_repeat_ngram_size {
+                return vec![vec![]; num_hypothesis as usize];
+            }
+            let input_ids = input_ids.to(Device::Cpu);
+            let num_hypothesis_per_input = num_hypothesis as usize / encoder_input_ids.len();
+            let prefix_length = encoder_no_repeat_ngram_size as usize - 1;
+            let mut banned_tokens: Vec<Vec<i64>> = Vec::with_capacity(num_hypothesis as usize);
+            for hypothesis_index in 0..num_hypothesis {
+                let hypothesis_input_ids = input_ids
+                    .get(hypothesis_index)
+                    .iter::<i64>()
+                    .unwrap()
+                    .collect::<Vec<i64>>();
+                let query = &hypothesis_input_ids[cur_len as usize - prefix_length..];
+                let hypothesis_banned_tokens = encoder_input_ids
+                    [hypothesis_index as usize / num_hypothesis_per_input]
+                    .windows(encoder_no_repeat_ngram_size as usize)
+                    .filter(|ngram| &ngram[..prefix_length] == query)
+                    .map(|ngram| *ngram.last().unwrap())
+                    .collect::<Vec<i64>>();
+                banned_tokens.push(hypothesis_banned_tokens);
+            }
+            banned_tokens
+        }
+
         fn top_k_top_p_filtering(
             &self,
             logits: &mut Tensor,
@@ -561,7 +1335,7 @@ pub(crate) mod private_generation_utils {
             let vocab_size = *logits.size().last().unwrap();
             if top_k > 0 {
                 let top_k = vocab_size - min(max(top_k, min_tokens_to_keep), vocab_size);
-                let (_, indices_to_remove) = logits.topk(top_k, -1, false, false);
+                let (_, indices_to_remove) = mps::topk(logits, top_k, -1, false, false);
                 for index in 0..*logits.size().first().unwrap() {
                     let _ = logits.get(index).index_fill_(
                         0,
@@ -609,6 +1383,71 @@ pub(crate) mod private_generation_utils {
             }
         }
 
+        fn typical_eta_epsilon_filtering(
+            &self,
+            logits: &mut Tensor,
+            typical_p: Option<f64>,
+            eta_cutoff: Option<f64>,
+            epsilon_cutoff: Option<f64>,
+            min_tokens_to_keep: i64,
+        ) {
+            let vocab_size = *logits.size().last().unwrap();
+            let min_tokens_to_keep = min(max(min_tokens_to_keep, 1), vocab_size);
+            let entropy = |log_probabilities: &Tensor, probabilities: &Tensor| -> Tensor {
+                (-log_probabilities * probabilities)
+                    .masked_fill(&probabilities.eq(0.0), 0.0)
+                    .sum_dim_intlist([-1].as_slice(), true, Kind::Float)
+            };
+            // Removes the tokens flagged by `mask`, keeping at least the `min_tokens_to_keep` most likely tokens
+            let remove_tokens = |logits: &mut Tensor, mask: Tensor| {
+                let (top_logits, _) = mps::topk(logits, min_tokens_to_keep, -1, true, true);
+                let min_top_logits = top_logits.narrow(-1, min_tokens_to_keep - 1, 1);
+                let indices_to_remove = mask.logical_and(&logits.lt_tensor(&min_top_logits));
+                let _ = logits.masked_fill_(&indices_to_remove, f64::NEG_INFINITY);
+            };
+
+            if let Some(typical_p) = typical_p.filter(|typical_p| *typical_p < 1f64) {
+                //        Locally typical sampling introduced by Meister et al. (https://arxiv.org/abs/2202.00666)
+                let log_probabilities = logits.log_softmax(-1, Kind::Float);
+                let probabilities = log_probabilities.exp();
+                let shifted_scores =
+                    (-&log_probabilities - entropy(&log_probabilities, &probabilities)).abs();
+                let (sorted_scores, sorted_indices) = shifted_scores.sort(-1, false);
+                let last_index = probabilities
+                    .gather(-1, &sorted_indices, false)
+                    .cumsum(-1, Kind::Float)
+                    .lt(typical_p)
+                    .sum_dim_intlist([-1].as_slice(), true, Kind::Int64)
+                    .clamp_max(vocab_size - 1);
+                let sorted_indices_to_remove =
+                    sorted_scores.gt_tensor(&sorted_scores.gather(-1, &last_index, false));
+                let _ = sorted_indices_to_remove
+                    .slice(-1, 0, min_tokens_to_keep, 1)
+                    .fill_(0);
+                let indices_to_remove = sorted_indices_to_remove.scatter(
+                    -1,
+                    &sorted_indices,
+                    &sorted_indices_to_remove,
+                );
+                let _ = logits.masked_fill_(&indices_to_remove, f64::NEG_INFINITY);
+            }
+            if let Some(epsilon_cutoff) =
+                epsilon_cutoff.filter(|epsilon_cutoff| *epsilon_cutoff > 0f64)
+            {
+                //        Epsilon sampling introduced by Hewitt et al. (https://arxiv.org/abs/2210.15191)
+                let probabilities = logits.softmax(-1, Kind::Float);
+                remove_tokens(logits, probabilities.lt(epsilon_cutoff));
+            }
+            if let Some(eta_cutoff) = eta_cutoff.filter(|eta_cutoff| *eta_cutoff > 0f64) {
+                //        Eta sampling introduced by Hewitt et al. (https://arxiv.org/abs/2210.15191)
+                let log_probabilities = logits.log_softmax(-1, Kind::Float);
+                let probabilities = log_probabilities.exp();
+                let eta = (-entropy(&log_probabilities, &probabilities)).exp() * eta_cutoff.sqrt();
+                let eta = eta.clamp_max(eta_cutoff);
+                remove_tokens(logits, probabilities.lt_tensor(&eta));
+            }
+        }
+
         fn run_hamming_diversity_penalty(
             &self,
             scores: &mut Tensor,
@@ -668,6 +1507,137 @@ pub(crate) mod private_generation_utils {
             let _ = scores.subtract_(&mask);
         }
 
+        /// Enforces phrasal constraints on the scores of the next tokens. While a sequence does not satisfy all the
+        /// constraints, the end of sequence tokens are banned. Once the beginning of a constraint has been generated,
+        /// the constraint is completed. The generation of an unsatisfied constraint is started when the model would
+        /// otherwise end the sequence, or when the remaining length is just sufficient to generate the constraints.
+        fn apply_phrasal_constraints(
+            &self,
+            constraints: &[PhrasalConstraint],
+            input_ids: &Tensor,
+            prompt_length: i64,
+            current_length: i64,
+            gen_opt: &InternalGenerateOptions,
+            scores: &mut Tensor,
+        ) {
+            if self.is_forced_token_step(
+                current_length,
+                gen_opt.max_length,
+                gen_opt.forced_bos_token_id,
+                gen_opt.forced_eos_token_id,
+            ) {
+                return;
+            }
+            let forced_bos_token_id = gen_opt
+                .forced_bos_token_id
+                .or(self.get_forced_bos_token_id());
+            let eos_token_ids = gen_opt.eos_token_ids.as_deref().unwrap_or(&[]);
+            for idx in 0..scores.size()[0] {
+                let generated =
+                    Vec::<i64>::try_from(input_ids.get(idx).slice(0, prompt_length, None, 1))
+                        .unwrap();
+                let generated = strip_forced_bos_token(&generated, forced_bos_token_id);
+                let unsatisfied = constraints
+                    .iter()
+                    .filter(|constraint| !constraint.is_satisfied(generated))
+                    .collect::<Vec<&PhrasalConstraint>>();
+                if unsatisfied.is_empty() {
+                    continue;
+                }
+
+                let mut allowed_tokens = unsatisfied
+                    .iter()
+                    .filter_map(|constraint| constraint.next_tokens(generated))
+                    .flatten()
+                    .collect::<Vec<i64>>();
+                if allowed_tokens.is_empty() {
+                    // One position is kept for the end of sequence token
+                    let remaining_length = gen_opt
+                        .max_length
+                        .map(|max_length| max_length - current_length - 1);
+                    let required_length = unsatisfied
+                        .iter()
+                        .map(|constraint| constraint.min_length())
+                        .sum::<i64>();
+                    let predicted_token = scores.get(idx).argmax(-1, false).int64_value(&[]);
+                    if eos_token_ids.contains(&predicted_token)
+                        || remaining_length.map_or(false, |remaining_length| {
+                            remaining_length <= required_length
+                        })
+                    {
+                        allowed_tokens = unsatisfied
+                            .iter()
+                            .flat_map(|constraint| constraint.first_tokens())
+                            .collect();
+                    }
+                }
+
+                if allowed_tokens.is_empty() {
+                    if !eos_token_ids.is_empty() {
+                        let _ = scores.get(idx).index_fill_(
+                            0,
+                            &Tensor::from_slice(eos_token_ids).to(scores.device()),
+                            f64::NEG_INFINITY,
+                        );
+                    }
+                } else {
+                    let mask = scores.get(idx).full_like(f64::NEG_INFINITY);
+                    let _ = mask.index_fill_(
+                        0,
+                        &Tensor::from_slice(allowed_tokens.as_slice()).to(scores.device()),
+                        0,
+                    );
+                    let _ = scores.get(idx).add_(&mask);
+                }
+            }
+        }
+
+        /// Restricts the next tokens to the continuations of the sequences of a trie. The end of sequence tokens are
+        /// allowed once a complete sequence of the trie has been generated. Sequences that are not in the trie (e.g.
+        /// finished sequences followed by padding) are left unchanged. The forced beginning of sequence token (e.g.
+        /// for BART models) is not part of the trie sequences.
+        fn apply_token_trie(
+            &self,
+            trie: &TokenTrie,
+            input_ids: &Tensor,
+            prompt_length: i64,
+            current_length: i64,
+            gen_opt: &InternalGenerateOptions,
+            scores: &mut Tensor,
+        ) {
+            if self.is_forced_token_step(
+                current_length,
+                gen_opt.max_length,
+                gen_opt.forced_bos_token_id,
+                gen_opt.forced_eos_token_id,
+            ) {
+                return;
+            }
+            let forced_bos_token_id = gen_opt
+                .forced_bos_token_id
+                .or(self.get_forced_bos_token_id());
+            let eos_token_ids = gen_opt.eos_token_ids.as_deref().unwrap_or(&[]);
+            for idx in 0..scores.size()[0] {
+                let generated =
+                    Vec::<i64>::try_from(input_ids.get(idx).slice(0, prompt_length, None, 1))
+                        .unwrap();
+                let generated = strip_forced_bos_token(&generated, forced_bos_token_id);
+                let mut allowed_tokens = trie.allowed_tokens(generated);
+                if trie.is_complete(generated) {
+                    allowed_tokens.extend_from_slice(eos_token_ids);
+                }
+                if !allowed_tokens.is_empty() {
+                    let mask = scores.get(idx).full_like(f64::NEG_INFINITY);
+                    let _ = mask.index_fill_(
+                        0,
+                        &Tensor::from_slice(allowed_tokens.as_slice()).to(scores.device()),
+                        0,
+                    );
+                    let _ = scores.get(idx).add_(&mask);
+                }
+            }
+        }
+
         fn split_bad_word_ids<'a>(
             &self,
             bad_word_ids: Option<&'a Vec<Vec<i64>>>,
@@ -816,6 +1786,115 @@ pub(crate) mod private_generation_utils {
             }
         }
 
+        /// Contrastive search step: evaluates the `top_k` most likely next tokens of each sequence and selects the
+        /// candidate maximizing its probability minus the degeneration penalty (maximum cosine similarity between the
+        /// hidden state of the candidate and the hidden states of the context). Returns the selected tokens and the
+        /// model output for these tokens, to be used by the next decoding step.
+        fn contrastive_search_step(
+            &self,
+            input_ids: &Tensor,
+            attention_mask: &Tensor,
+            mut past: Cache,
+            context_hidden_states: &Tensor,
+            next_token_logits: &Tensor,
+            top_k: i64,
+            penalty_alpha: f64,
+        ) -> (Tensor, LMModelOutput) {
+            let batch_size = *input_ids.size().first().unwrap();
+            let device = input_ids.device();
+            let probabilities = next_token_logits.softmax(-1, Kind::Float);
+            let (top_k_probabilities, top_k_ids) = mps::topk(&probabilities, top_k, -1, true, true);
+
+            // Evaluate the candidates of all sequences in a single forward pass
+            let expanded_indices = Tensor::arange(batch_size, (Kind::Int64, device))
+                .view((-1, 1))
+                .repeat([1, top_k])
+                .view(-1);
+            let _ = self.reorder_cache(&mut past, None, &expanded_indices);
+            let candidate_input_ids = Tensor::cat(
+                &[
+                    input_ids.index_select(0, &expanded_indices),
+                    top_k_ids.view((-1, 1)),
+                ],
+                -1,
+            );
+            let candidate_attention_mask = Tensor::cat(
+                &[
+                    attention_mask.index_select(0, &expanded_indices),
+                    Tensor::ones([batch_size * top_k, 1], (Kind::Int64, device)),
+                ],
+                -1,
+            );
+            let prepared_input = self.prepare_inputs_for_generation(
+                candidate_input_ids,
+                None,
+                past,
+                candidate_attention_mask,
+            );
+            let candidate_output = self
+                .forward_t(
+                    prepared_input.prepared_input.as_ref(),
+                    prepared_input.prepared_past,
+                    prepared_input.prepared_attention_mask.as_ref(),
+                    None,
+                    prepared_input.prepared_position_ids.as_ref(),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .unwrap();
+            let candidate_hidden_states = candidate_output
+                .last_hidden_state
+                .as_ref()
+                .expect(
+                    "The model returned the hidden states of the context but not of the candidates",
+                )
+                .select(1, -1)
+                .to_kind(Kind::Float)
+                .view((batch_size, top_k, -1));
+            let candidate_hidden_states = &candidate_hidden_states
+                / candidate_hidden_states
+                    .norm_scalaropt_dim(2, [-1], true)
+                    .clamp_min(1e-12);
+            let context_hidden_states = context_hidden_states.to_kind(Kind::Float);
+            let context_hidden_states = &context_hidden_states
+                / context_hidden_states
+                    .norm_scalaropt_dim(2, [-1], true)
+                    .clamp_min(1e-12);
+
+            // Padding positions of the context are excluded from the degeneration penalty
+            let (degeneration_penalty, _) = candidate_hidden_states
+                .matmul(&context_hidden_states.transpose(1, 2))
+                .masked_fill(&attention_mask.eq(0).unsqueeze(1), f64::NEG_INFINITY)
+                .max_dim(-1, false);
+            let candidate_scores = (&top_k_probabilities * (1.0 - penalty_alpha)
+                - degeneration_penalty * penalty_alpha)
+                .masked_fill(&top_k_probabilities.eq(0.0), f64::NEG_INFINITY);
+            let selected_candidates = candidate_scores.argmax(-1, false);
+            let next_token = top_k_ids
+                .gather(1, &selected_candidates.unsqueeze(-1), false)
+                .squeeze_dim(1);
+
+            let selected_indices =
+                Tensor::arange(batch_size, (Kind::Int64, device)) * top_k + selected_candidates;
+            let LMModelOutput {
+                lm_logits,
+                mut cache,
+                last_hidden_state,
+            } = candidate_output;
+            let _ = self.reorder_cache(&mut cache, None, &selected_indices);
+            (
+                next_token,
+                LMModelOutput {
+                    lm_logits: lm_logits.index_select(0, &selected_indices),
+                    cache,
+                    last_hidden_state: last_hidden_state
+                        .map(|hidden_states| hidden_states.index_select(0, &selected_indices)),
+                },
+            )
+        }
+
         fn generate_no_beam_search(
             &self,
             input_ids: Tensor,
@@ -826,6 +1905,7 @@ pub(crate) mod private_generation_utils {
             gen_opt: InternalGenerateOptions,
             prefix_allowed_tokens_fn: Option<PrefixAllowedFunction>,
             output_scores: bool,
+            token_callback: Option<TokenCallback>,
         ) -> GeneratedOutputWithScores {
             let mut unfinished_sentences =
                 Tensor::ones([batch_size], (Kind::Int64, self.get_device()));
@@ -841,29 +1921,49 @@ pub(crate) mod private_generation_utils {
             let mut current_length = cur_len;
             let mut token_scores_output: Option<Vec<Tensor>> =
                 if output_scores { Some(vec![]) } else { None };
+            let mut penalty_alpha = gen_opt
+                .penalty_alpha
+                .filter(|_| !gen_opt.do_sample & (gen_opt.top_k > 1));
+            // Output of the model for the tokens selected by contrastive search and hidden states of the context
+            let mut contrastive_output: Option<LMModelOutput> = None;
+            let mut context_hidden_states: Option<Tensor> = None;
 
             loop {
-                let prepared_input = self.prepare_inputs_for_generation(
-                    input_ids.copy(),
-                    encoder_outputs.as_ref(),
-                    past,
-                    attention_mask.copy(),
-                );
-                let temp = self
-                    .forward_t(
-                        prepared_input.prepared_input.as_ref(),
-                        prepared_input.prepared_past,
-                        prepared_input.prepared_attention_mask.as_ref(),
-                        None,
-                        prepared_input.prepared_position_ids.as_ref(),
-                        None,
-                        prepared_input.prepared_encoder_output,
-                        prepared_input.prepared_decoder_input.as_ref(),
-                        false,
-                    )
-                    .unwrap();
+                let temp = match contrastive_output.take() {
+                    Some(contrastive_output) => contrastive_output,
+                    None => {
+                        let prepared_input = self.prepare_inputs_for_generation(
+                            input_ids.copy(),
+                            encoder_outputs.as_ref(),
+                            past,
+                            attention_mask.copy(),
+                        );
+                        self.forward_t(
+                            prepared_input.prepared_input.as_ref(),
+                            prepared_input.prepared_past,
+                            prepared_input.prepared_attention_mask.as_ref(),
+                            None,
+                            prepared_input.prepared_position_ids.as_ref(),
+                            None,
+                            prepared_input.prepared_encoder_output,
+                            prepared_input.prepared_decoder_input.as_ref(),
+                            false,
+                        )
+                        .unwrap()
+                    }
+                };
                 outputs = temp.lm_logits;
                 past = temp.cache;
+                if penalty_alpha.is_some() & context_hidden_states.is_none() {
+                    // Models not returning the hidden states of all the positions of the context (e.g. XLNet
+                    // returning the hidden states of the predicted position) fall back to greedy decoding
+                    context_hidden_states = temp
+                        .last_hidden_state
+                        .filter(|hidden_states| hidden_states.size()[1] == input_ids.size()[1]);
+                    if context_hidden_states.is_none() {
+                        penalty_alpha = None;
+                    }
+                }
 
                 let mut next_token_logits = outputs.select(1, -1);
                 // Reduce probability for repeated inputs
@@ -901,6 +2001,25 @@ pub(crate) mod private_generation_utils {
                     let banned_tokens = self.get_banned_tokens(
                         &input_ids,
                         gen_opt.no_repeat_ngram_size,
+                        gen_opt.no_repeat_ngram_start,
+                        current_length,
+                    );
+                    for (batch_index, index_banned_token) in
+                        (0..banned_tokens.len() as i64).zip(banned_tokens)
+                    {
+                        let _ = next_token_logits.get(batch_index).index_fill_(
+                            0,
+                            &Tensor::from_slice(&index_banned_token)
+                                .to_device(next_token_logits.device()),
+                            f64::NEG_INFINITY,
+                        );
+                    }
+                }
+                if let Some(encoder_input_ids) = gen_opt.encoder_input_ids.as_ref() {
+                    let banned_tokens = self.get_encoder_banned_tokens(
+                        &input_ids,
+                        encoder_input_ids,
+                        gen_opt.encoder_no_repeat_ngram_size,
                         current_length,
                     );
                     for (batch_index, index_banned_token) in
@@ -935,11 +2054,40 @@ pub(crate) mod private_generation_utils {
                     );
                 }
 
+                // Enforce the inclusion of the phrasal constraints
+                if let Some(constraints) = gen_opt.constraints {
+                    self.apply_phrasal_constraints(
+                        constraints,
+                        &input_ids,
+                        cur_len,
+                        current_length,
+                        &gen_opt,
+                        &mut next_token_logits,
+                    );
+                }
+
+                // Restrict the generation to the allowed outputs
+                if let Some(allowed_outputs) = gen_opt.allowed_outputs {
+                    self.apply_token_trie(
+                        allowed_outputs,
+                        &input_ids,
+                        cur_len,
+                        current_length,
+                        &gen_opt,
+                        &mut next_token_logits,
+                    );
+                }
+
+                for logits_processor in gen_opt.logits_processors.iter() {
+                    logits_processor.process(&input_ids, cur_len, &mut next_token_logits);
+                }
+
                 self.prepare_scores_for_generation(
                     &mut next_token_logits,
                     current_length,
                     gen_opt.max_length,
                     gen_opt.forced_bos_token_id,
+                    gen_opt.forced_eos_token_id,
                 );
 
                 // Top-k and top-p sampling
@@ -953,8 +2101,36 @@ pub(crate) mod private_generation_utils {
                         gen_opt.top_p,
                         1,
                     );
+                    self.typical_eta_epsilon_filtering(
+                        &mut next_token_logits,
+                        gen_opt.typical_p,
+                        gen_opt.eta_cutoff,
+                        gen_opt.epsilon_cutoff,
+                        1,
+                    );
                     let probabilities = next_token_logits.softmax(-1, next_token_logits.kind());
                     probabilities.multinomial(1, false).squeeze_dim(1)
+                } else if let Some(penalty_alpha) = penalty_alpha {
+                    let context = context_hidden_states.as_ref().unwrap();
+                    let (next_token, output) = self.contrastive_search_step(
+                        &input_ids,
+                        &attention_mask,
+                        mem::replace(&mut past, Cache::None),
+                        context,
+                        &next_token_logits,
+                        gen_opt.top_k,
+                        penalty_alpha,
+                    );
+                    let selected_hidden_states = output
+                        .last_hidden_state
+                        .as_ref()
+                        .unwrap()
+                        .select(1, -1)
+                        .unsqueeze(1);
+                    context_hidden_states =
+                        Some(Tensor::cat(&[context, &selected_hidden_states], 1));
+                    contrastive_output = Some(output);
+                    next_token
                 } else {
                     next_token_logits.argmax(-1, false)
                 };
@@ -971,14 +2147,32 @@ pub(crate) mod private_generation_utils {
                 };
 
                 // Add tokens to unfinished sentences
-                let tokens_to_add = match &gen_opt.eos_token_ids {
-                    Some(_) => {
+                let tokens_to_add = match gen_opt.pad_token_id {
+                    Some(pad_token_id)
+                        if gen_opt.eos_token_ids.is_some()
+                            | !gen_opt.stopping_conditions.is_empty() =>
+                    {
                         next_token * &unfinished_sentences
-                            - gen_opt.pad_token_id.unwrap() * (&unfinished_sentences - 1)
+                            - pad_token_id * (&unfinished_sentences - 1)
                     }
-                    None => next_token,
+                    _ => next_token,
                 };
 
+                if let Some(token_callback) = token_callback {
+                    let tokens =
+                        Vec::<i64>::try_from(tokens_to_add.to_device(Device::Cpu)).unwrap();
+                    let unfinished =
+                        Vec::<i64>::try_from(unfinished_sentences.to_device(Device::Cpu)).unwrap();
+                    for (sequence_index, (token, _)) in tokens
+                        .iter()
+                        .zip(unfinished.iter())
+                        .enumerate()
+                        .filter(|(_, (_, unfinished))| **unfinished > 0)
+                    {
+                        token_callback(sequence_index, *token);
+                    }
+                }
+
                 input_ids = Tensor::cat(&[input_ids, tokens_to_add.unsqueeze(-1)], -1);
                 if gen_opt.eos_token_ids.is_some() {
                     for eos_token_id in gen_opt.eos_token_ids.as_ref().unwrap() {
@@ -993,9 +2187,26 @@ pub(crate) mod private_generation_utils {
                         );
                         unfinished_sentences = -unfinished_sentences * (sentence_with_eos - 1);
                     }
-                    if i64::try_from(unfinished_sentences.max()).unwrap() == 0 {
-                        break;
-                    }
+                }
+                if !gen_opt.stopping_conditions.is_empty() {
+                    let stopped_sentences =
+                        Tensor::from_slice(&gen_opt.stopping_conditions.stopped_sequences(
+                            self._get_tokenizer(),
+                            &input_ids,
+                            Some(&unfinished_sentences),
+                            cur_len,
+                        ))
+                        .to_device(unfinished_sentences.device());
+                    let _ = sentence_lengths.masked_fill_(
+                        &stopped_sentences.to_device(sentence_lengths.device()),
+                        current_length + 1,
+                    );
+                    unfinished_sentences = unfinished_sentences.masked_fill(&stopped_sentences, 0);
+                }
+                if (gen_opt.eos_token_ids.is_some() | !gen_opt.stopping_conditions.is_empty())
+                    && i64::try_from(unfinished_sentences.max()).unwrap() == 0
+                {
+                    break;
                 }
                 if !self.is_encoder_decoder() {
                     attention_mask = Tensor::cat(
@@ -1191,6 +2402,7 @@ pub(crate) mod private_generation_utils {
                         current_length,
                         gen_opt.max_length,
                         gen_opt.forced_bos_token_id,
+                        gen_opt.forced_eos_token_id,
                     );
 
                     let mut scores = next_token_logits.log_softmax(-1, next_token_logits.kind());
@@ -1228,6 +2440,25 @@ pub(crate) mod private_generation_utils {
                         let banned_tokens = self.get_banned_tokens(
                             group_input_ids.as_ref().unwrap_or(&input_ids),
                             gen_opt.no_repeat_ngram_size,
+                            gen_opt.no_repeat_ngram_start,
+                            current_length,
+                        );
+                        for (batch_index, index_banned_token) in
+                            (0..banned_tokens.len() as i64).zip(banned_tokens)
+                        {
+                            let _ = scores.get(batch_index).index_fill_(
+                                0,
+                                &Tensor::from_slice(&index_banned_token)
+                                    .to_device(next_token_logits.device()),
+                                f64::NEG_INFINITY,
+                            );
+                        }
+                    }
+                    if let Some(encoder_input_ids) = gen_opt.encoder_input_ids.as_ref() {
+                        let banned_tokens = self.get_encoder_banned_tokens(
+                            group_input_ids.as_ref().unwrap_or(&input_ids),
+                            encoder_input_ids,
+                            gen_opt.encoder_no_repeat_ngram_size,
                             current_length,
                         );
                         for (batch_index, index_banned_token) in
@@ -1265,6 +2496,38 @@ pub(crate) mod private_generation_utils {
                         )
                     }
 
+                    // Enforce the inclusion of the phrasal constraints
+                    if let Some(constraints) = gen_opt.constraints {
+                        self.apply_phrasal_constraints(
+                            constraints,
+                            group_input_ids.as_ref().unwrap_or(&input_ids),
+                            cur_len,
+                            current_length,
+                            &gen_opt,
+                            &mut scores,
+                        );
+                    }
+
+                    // Restrict the generation to the allowed outputs
+                    if let Some(allowed_outputs) = gen_opt.allowed_outputs {
+                        self.apply_token_trie(
+                            allowed_outputs,
+                            group_input_ids.as_ref().unwrap_or(&input_ids),
+                            cur_len,
+                            current_length,
+                            &gen_opt,
+                            &mut scores,
+                        );
+                    }
+
+                    for logits_processor in gen_opt.logits_processors.iter() {
+                        logits_processor.process(
+                            group_input_ids.as_ref().unwrap_or(&input_ids),
+                            cur_len,
+                            &mut scores,
+                        );
+                    }
+
                     let mut next_scores: Tensor = &scores
                         + (if num_beam_groups > 1 {
                             beam_scores
@@ -1282,6 +2545,13 @@ pub(crate) mod private_generation_utils {
                             gen_opt.top_p,
                             2,
                         );
+                        self.typical_eta_epsilon_filtering(
+                            &mut next_scores,
+                            gen_opt.typical_p,
+                            gen_opt.eta_cutoff,
+                            gen_opt.epsilon_cutoff,
+                            2,
+                        );
                         let _scores = next_scores
                             .contiguous()
                             .view((batch_size, group_size * vocab_size));
@@ -1296,13 +2566,13 @@ pub(crate) mod private_generation_utils {
                         let _scores = next_scores
                             .contiguous()
                             .view((batch_size, group_size * vocab_size));
-                        _scores.topk(2 * group_size, 1, true, true)
+                        mps::topk(&_scores, 2 * group_size, 1, true, true)
                     };
 
                     let eos_token_ids = gen_opt.eos_token_ids.as_ref();
                     let beam_ids_tensor = &next_tokens.divide_scalar_mode(vocab_size, "floor");
                     let effective_beam_ids_tensor =
-                        (&next_tokens.ones_like().cumsum(0, Kind::Int64) - 1) * group_size
+                        (&mps::cumsum(&next_tokens.ones_like(), 0, Kind::Int64) - 1) * group_size
                             + beam_ids_tensor;
                     let token_id_tensor = &next_tokens - beam_ids_tensor * vocab_size;
                     let (max_scores, _) = next_scores.max_dim(1, false);
@@ -1310,8 +2580,7 @@ pub(crate) mod private_generation_utils {
                     if let Some(eos_token_id) = eos_token_ids {
                         eos_mask -= token_id_tensor.eq(eos_token_id[0]).to_kind(Kind::Int64);
                     }
-                    let eos_mask2 = eos_mask
-                        .cumsum(1, Kind::Int64)
+                    let eos_mask2 = mps::cumsum(&eos_mask, 1, Kind::Int64)
                         .le(group_size)
                         .to_kind(Kind::Bool)
                         .logical_and(&eos_mask);
@@ -1414,6 +2683,39 @@ pub(crate) mod private_generation_utils {
                     -1,
                 );
 
+                // Beams meeting a stopping condition are saved as hypotheses and not extended further
+                if !gen_opt.stopping_conditions.is_empty() {
+                    let stopped_beams = gen_opt.stopping_conditions.stopped_sequences(
+                        self._get_tokenizer(),
+                        &input_ids,
+                        None,
+                        cur_len,
+                    );
+                    for (effective_beam_id, _) in stopped_beams
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, stopped)| **stopped)
+                    {
+                        let batch_index = effective_beam_id / gen_opt.num_beams as usize;
+                        if done[batch_index] {
+                            continue;
+                        }
+                        let effective_beam_id = effective_beam_id as i64;
+                        let saved_beam_scores =
+                            saved_beam_scores.as_ref().map(|step_wise_scores| {
+                                Tensor::stack(step_wise_scores, 1)
+                                    .get(effective_beam_id)
+                                    .copy()
+                            });
+                        hypotheses[batch_index].add(
+                            input_ids.get(effective_beam_id).copy(),
+                            beam_scores.double_value(&[effective_beam_id]),
+                            saved_beam_scores,
+                        );
+                        let _ = beam_scores.get(effective_beam_id).fill_(-1e9);
+                    }
+                }
+
                 current_length += 1;
                 if let Some(max_length) = gen_opt.max_length {
                     if current_length >= max_length {
@@ -1586,6 +2888,18 @@ pub(crate) mod private_generation_utils {
         }
     }
 
+    /// Removes the forced beginning of sequence token from the start of generated tokens
+    fn strip_forced_bos_token(generated: &[i64], forced_bos_token_id: Option<i64>) -> &[i64] {
+        match (forced_bos_token_id, generated.split_first()) {
+            (Some(forced_bos_token_id), Some((&first_token, rest)))
+                if first_token == forced_bos_token_id =>
+            {
+                rest
+            }
+            _ => generated,
+        }
+    }
+
     pub fn force_token_id_generation(scores: &mut Tensor, token_ids: &[i64], vocab_size: i64) {
         let impossible_tokens: Vec<i64> = (0..vocab_size)
             .filter(|pos| !token_ids.contains(pos))
@@ -1616,12 +2930,400 @@ pub struct GeneratedIndicesOutput {
     pub token_scores: Option<Vec<f64>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TokenTrieNode {
+    children: BTreeMap<i64, usize>,
+    is_complete: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Prefix tree of token sequences
+/// Closed set of allowed outputs for constrained generation (e.g. entity names for entity retrieval, or labels
+/// for classification by generation), set with `GenerateOptions::allowed_outputs`. At each step, generation is
+/// restricted to the tokens continuing one of the sequences of the trie, and may end once a complete sequence has
+/// been generated.
+pub struct TokenTrie {
+    nodes: Vec<TokenTrieNode>,
+}
+
+impl TokenTrie {
+    /// Creates a new trie from sequences of token ids
+    ///
+    /// # Arguments
+    ///
+    /// * `sequences` - Allowed sequences of token ids
+    pub fn new(sequences: &[Vec<i64>]) -> Result<TokenTrie, RustBertError> {
+        if sequences.is_empty() || sequences.iter().any(|sequence| sequence.is_empty()) {
+            return Err(RustBertError::ValueError(
+                "The allowed outputs must contain at least one non-empty sequence of tokens"
+                    .to_string(),
+            ));
+        }
+        let mut trie = TokenTrie {
+            nodes: vec![TokenTrieNode {
+                children: BTreeMap::new(),
+                is_complete: false,
+            }],
+        };
+        for sequence in sequences {
+            trie.insert(sequence);
+        }
+        Ok(trie)
+    }
+
+    /// Creates a new trie from a closed set of allowed output texts. The texts are tokenized as provided: outputs
+    /// generated after a prompt may require a leading space for tokenizers encoding white spaces (e.g. GPT2).
+    ///
+    /// # Arguments
+    ///
+    /// * `tokenizer` - Tokenizer of the generation model
+    /// * `outputs` - Allowed outputs
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::generation_utils::TokenTrie;
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let trie = TokenTrie::from_strings(model.get_tokenizer(), &[" positive", " negative"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_strings<S: AsRef<str>>(
+        tokenizer: &TokenizerOption,
+        outputs: &[S],
+    ) -> Result<TokenTrie, RustBertError> {
+        let sequences = outputs
+            .iter()
+            .map(|output| tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(output.as_ref())))
+            .collect::<Vec<Vec<i64>>>();
+        TokenTrie::new(&sequences)
+    }
+
+    /// Adds a sequence of token ids to the trie
+    pub fn insert(&mut self, sequence: &[i64]) {
+        let mut node = 0;
+        for token in sequence {
+            node = match self.nodes[node].children.get(token) {
+                Some(&child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(TokenTrieNode {
+                        children: BTreeMap::new(),
+                        is_complete: false,
+                    });
+                    self.nodes[node].children.insert(*token, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].is_complete = true;
+    }
+
+    fn find(&self, prefix: &[i64]) -> Option<&TokenTrieNode> {
+        let mut node = &self.nodes[0];
+        for token in prefix {
+            node = &self.nodes[*node.children.get(token)?];
+        }
+        Some(node)
+    }
+
+    /// Returns the tokens continuing the sequences of the trie starting with a prefix (empty if the prefix is not in
+    /// the trie)
+    pub fn allowed_tokens(&self, prefix: &[i64]) -> Vec<i64> {
+        self.find(prefix)
+            .map(|node| node.children.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Checks if a sequence of tokens is one of the sequences of the trie
+    pub fn is_complete(&self, sequence: &[i64]) -> bool {
+        self.find(sequence).map_or(false, |node| node.is_complete)
+    }
+}
+
 pub type PrefixAllowedFunction<'a> = &'a dyn Fn(i64, &Tensor) -> Vec<i64>;
 /// Type alias for a function defining allowed tokens based on current tokens generated.
 /// This function should take a `batch_id` and associated tensor of already generated tokens and
 /// should return a vector of allowed tokens. This is useful for controlled generation, i.e.
 /// deterministic generation of a token continuation if a sequence of token occurs.
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Phrasal constraint for lexically constrained decoding
+/// Disjunctive constraint satisfied when the generated sequence contains at least one of its alternatives (sequences of
+/// token ids), for example the tokenized variants of a phrase. Generated sequences are guaranteed to satisfy the
+/// constraints as long as the maximum length leaves enough room to generate them.
+pub struct PhrasalConstraint {
+    alternatives: Vec<Vec<i64>>,
+}
+
+impl PhrasalConstraint {
+    /// Creates a new constraint from alternative sequences of token ids
+    ///
+    /// # Arguments
+    ///
+    /// * `alternatives` - Sequences of token ids, one of which must be generated
+    pub fn new(alternatives: Vec<Vec<i64>>) -> Result<PhrasalConstraint, RustBertError> {
+        if alternatives.is_empty()
+            || alternatives
+                .iter()
+                .any(|alternative| alternative.is_empty())
+        {
+            return Err(RustBertError::ValueError(
+                "Phrasal constraints require at least one non-empty sequence of tokens".to_string(),
+            ));
+        }
+        Ok(PhrasalConstraint { alternatives })
+    }
+
+    /// Creates a new constraint from alternative phrases, one of which must be generated. Each phrase is tokenized
+    /// with and without a leading space, so that the phrase can be generated at the beginning or in the middle of a
+    /// sentence.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokenizer` - Tokenizer of the generation model
+    /// * `phrases` - Alternative phrases
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::generation_utils::PhrasalConstraint;
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let constraint = PhrasalConstraint::from_phrases(model.get_tokenizer(), &["Rust", "rust"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_phrases<S: AsRef<str>>(
+        tokenizer: &TokenizerOption,
+        phrases: &[S],
+    ) -> Result<PhrasalConstraint, RustBertError> {
+        let mut alternatives: Vec<Vec<i64>> = Vec::with_capacity(2 * phrases.len());
+        for phrase in phrases {
+            let phrase = phrase.as_ref().trim();
+            for variant in [phrase.to_string(), format!(" {phrase}")].iter() {
+                let token_ids = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(variant));
+                if !token_ids.is_empty() && !alternatives.contains(&token_ids) {
+                    alternatives.push(token_ids);
+                }
+            }
+        }
+        PhrasalConstraint::new(alternatives)
+    }
+
+    /// Returns the alternative sequences of token ids of the constraint
+    pub fn alternatives(&self) -> &[Vec<i64>] {
+        &self.alternatives
+    }
+
+    /// Checks if a sequence of generated tokens satisfies the constraint
+    pub fn is_satisfied(&self, generated: &[i64]) -> bool {
+        self.alternatives.iter().any(|alternative| {
+            generated
+                .windows(alternative.len())
+                .any(|window| window == alternative.as_slice())
+        })
+    }
+
+    /// Tokens continuing the alternatives partially generated at the end of the sequence, if any
+    pub(crate) fn next_tokens(&self, generated: &[i64]) -> Option<Vec<i64>> {
+        let next_tokens = self
+            .alternatives
+            .iter()
+            .filter_map(|alternative| {
+                (1..alternative.len())
+                    .rev()
+                    .find(|&length| generated.ends_with(&alternative[..length]))
+                    .map(|length| alternative[length])
+            })
+            .collect::<Vec<i64>>();
+        if next_tokens.is_empty() {
+            None
+        } else {
+            Some(next_tokens)
+        }
+    }
+
+    pub(crate) fn first_tokens(&self) -> impl Iterator<Item = i64> + '_ {
+        self.alternatives.iter().map(|alternative| alternative[0])
+    }
+
+    pub(crate) fn min_length(&self) -> i64 {
+        self.alternatives
+            .iter()
+            .map(|alternative| alternative.len() as i64)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// Type alias for a function receiving the generated tokens as they are produced.
+/// The function is called at each decoding step with the index of the sequence in the batch and the
+/// token generated for this sequence (including the EOS token), until the sequence is finished.
+pub type TokenCallback<'a> = &'a dyn Fn(usize, i64);
+
+/// # Stopping criterion for text generation
+/// Evaluated for each unfinished sequence after every decoding step: the generation of the sequence stops (as if an
+/// EOS token had been generated) when the criterion is met. Implemented for closures taking the token ids of the
+/// sequence and the number of generated tokens.
+///
+/// ```no_run
+/// use rust_bert::pipelines::generation_utils::StoppingCriteria;
+///
+/// // Stops the generation after 3 consecutive identical tokens
+/// let repetition_criterion = |token_ids: &[i64], generated_length: usize| {
+///     generated_length >= 3 && token_ids[token_ids.len() - 3..].windows(2).all(|w| w[0] == w[1])
+/// };
+/// assert!(repetition_criterion.should_stop(&[1, 2, 2, 2], 3));
+/// ```
+pub trait StoppingCriteria: Send + Sync {
+    /// Checks if the generation of a sequence should stop
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `&[i64]` token ids of the sequence, starting with the prompt for decoder-only models and with the decoder start token for encoder-decoder models
+    /// * `generated_length` - `usize` number of tokens generated so far (at the end of `token_ids`)
+    ///
+    /// # Returns
+    /// * `bool` flag indicating if the generation of the sequence should stop
+    fn should_stop(&self, token_ids: &[i64], generated_length: usize) -> bool;
+}
+
+impl<F> StoppingCriteria for F
+where
+    F: Fn(&[i64], usize) -> bool + Send + Sync,
+{
+    fn should_stop(&self, token_ids: &[i64], generated_length: usize) -> bool {
+        self(token_ids, generated_length)
+    }
+}
+
+/// # Logits processor for text generation
+/// Modifies the scores of the next token of all sequences of the batch at every decoding step, after the built-in
+/// constraints (bad words, n-gram blocking, prefix-allowed tokens, phrasal constraints and allowed outputs) have been
+/// applied. The scores are raw logits for greedy decoding and sampling, and log-probabilities for beam search:
+/// processors masking tokens should set their scores to negative infinity rather than rescaling them.
+///
+/// `GrammarLogitsProcessor` in `pipelines::structured_generation` restricts the output to a grammar
+/// (e.g. JSON matching a schema).
+///
+/// ```no_run
+/// use rust_bert::pipelines::generation_utils::LogitsProcessor;
+/// use tch::Tensor;
+///
+/// // Bans a token from the generated sequences
+/// struct BanToken(i64);
+///
+/// impl LogitsProcessor for BanToken {
+///     fn process(&self, _input_ids: &Tensor, _start_length: i64, scores: &mut Tensor) {
+///         let _ = scores.select(1, self.0).fill_(f64::NEG_INFINITY);
+///     }
+/// }
+/// ```
+pub trait LogitsProcessor: Send + Sync {
+    /// Modifies the next token scores in place
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - `Tensor` of shape (*batch size*, *sequence length*) with the token ids of the sequences (for beam search, one row per beam)
+    /// * `start_length` - `i64` position of the first generated token in `input_ids` (the prompt for decoder-only models and the decoder start token for encoder-decoder models precede it)
+    /// * `scores` - `Tensor` of shape (*batch size*, *vocabulary size*) with the next token scores to modify
+    fn process(&self, input_ids: &Tensor, start_length: i64, scores: &mut Tensor);
+}
+
+#[derive(Debug, Clone)]
+/// # Logits processor biasing token sequences
+/// Adds a bias to the score of the last token of each token sequence if the preceding tokens of the sequence are the
+/// last tokens of the input (tokens of single token sequences are always biased). Positive biases encourage the
+/// sequences and negative biases discourage them: a bias of `f64::NEG_INFINITY` bans the sequence.
+///
+/// ```no_run
+/// use rust_bert::pipelines::generation_utils::SequenceBiasLogitsProcessor;
+/// # fn main() -> anyhow::Result<()> {
+/// let sequence_bias = SequenceBiasLogitsProcessor::new(vec![(vec![3290], -5.0), (vec![464, 3290], 2.0)])?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SequenceBiasLogitsProcessor {
+    sequence_bias: Vec<(Vec<i64>, f64)>,
+    longest_sequence: usize,
+}
+
+impl SequenceBiasLogitsProcessor {
+    /// Creates a new `SequenceBiasLogitsProcessor`
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence_bias` - token sequences and the bias added to the score of their last token
+    pub fn new(
+        sequence_bias: Vec<(Vec<i64>, f64)>,
+    ) -> Result<SequenceBiasLogitsProcessor, RustBertError> {
+        validate_sequence_bias(&sequence_bias)?;
+        let longest_sequence = sequence_bias
+            .iter()
+            .map(|(sequence, _)| sequence.len())
+            .max()
+            .unwrap_or(0);
+        Ok(SequenceBiasLogitsProcessor {
+            sequence_bias,
+            longest_sequence,
+        })
+    }
+}
+
+fn validate_sequence_bias(sequence_bias: &[(Vec<i64>, f64)]) -> Result<(), RustBertError> {
+    if sequence_bias
+        .iter()
+        .any(|(sequence, _)| sequence.is_empty())
+    {
+        return Err(RustBertError::ValueError(
+            "Biased token sequences cannot be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl LogitsProcessor for SequenceBiasLogitsProcessor {
+    fn process(&self, input_ids: &Tensor, _start_length: i64, scores: &mut Tensor) {
+        if self.sequence_bias.is_empty() {
+            return;
+        }
+        let (batch_size, vocab_size) = scores.size2().unwrap();
+        let previous_tokens = (0..batch_size)
+            .map(|sequence_index| {
+                let token_ids = input_ids.get(sequence_index);
+                let length = *token_ids.size().last().unwrap();
+                let window = min(self.longest_sequence as i64 - 1, length);
+                Vec::<i64>::try_from(token_ids.slice(0, length - window, length, 1)).unwrap()
+            })
+            .collect::<Vec<Vec<i64>>>();
+
+        let mut bias = vec![0f64; (batch_size * vocab_size) as usize];
+        for (sequence, sequence_bias) in self.sequence_bias.iter() {
+            let (prefix, token) = sequence.split_at(sequence.len() - 1);
+            let token = token[0];
+            if (token < 0) | (token >= vocab_size) {
+                continue;
+            }
+            for (sequence_index, previous_tokens) in previous_tokens.iter().enumerate() {
+                if previous_tokens.ends_with(prefix) {
+                    bias[sequence_index * vocab_size as usize + token as usize] += sequence_bias;
+                }
+            }
+        }
+        let bias = Tensor::from_slice(&bias)
+            .view([batch_size, vocab_size])
+            .to_kind(scores.kind())
+            .to_device(scores.device());
+        let _ = scores.g_add_(&bias);
+    }
+}
+
 #[derive(Clone, Copy, Default)]
 /// # Generation options for text generation.
 /// When provided to a `generate` method, these options will take priority over the `GenerateConfig` used to create the
@@ -1657,18 +3359,59 @@ pub struct GenerateOptions<'a> {
     pub length_penalty: Option<f64>,
     /// Number of allowed repetitions of n-grams. Values higher than 0 turn on this feature
     pub no_repeat_ngram_size: Option<i64>,
+    /// Tokens considered for the n-gram repetition blocking, replacing the `no_repeat_ngram_scope` of the `GenerateConfig`
+    pub no_repeat_ngram_scope: Option<NoRepeatNgramScope>,
+    /// Size of the n-grams of the encoder input that may not be repeated (encoder-decoder models only), replacing the
+    /// `encoder_no_repeat_ngram_size` of the `GenerateConfig`
+    pub encoder_no_repeat_ngram_size: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups
     pub diversity_penalty: Option<f64>,
-    /// Decoder start token id
+    /// Degeneration penalty for contrastive search (used with greedy decoding and `top_k` higher than 1). Only supported
+    /// by decoder-only models returning their hidden states, other models fall back to greedy decoding
+    pub penalty_alpha: Option<f64>,
+    /// Typical_p value for locally typical sampling
+    pub typical_p: Option<f64>,
+    /// Eta cutoff for eta sampling
+    pub eta_cutoff: Option<f64>,
+    /// Epsilon cutoff for epsilon sampling
+    pub epsilon_cutoff: Option<f64>,
+    /// Decoder start token id, replacing the `decoder_start_token_id` of the model configuration
     pub decoder_start_token_id: Option<i64>,
-    /// Forced first token generated
+    /// Forced first token generated, replacing the `forced_bos_token_id` of the model configuration
     pub forced_bos_token_id: Option<i64>,
+    /// Forced last token generated when the maximum length is reached, replacing the `forced_eos_token_id` of the
+    /// model configuration
+    pub forced_eos_token_id: Option<i64>,
     /// Function to control the generation process. The function should take a `batch_id` (i64) and a tensor of token_ids already generated and returns a `Vec<i64>` of allowed tokens.
     pub prefix_allowed_tokens_fn: Option<PrefixAllowedFunction<'a>>,
-    /// List of bad word ids (may be a sequence of word ids) that will be banned during the generation
+    /// List of bad word ids (may be a sequence of word ids) that will be banned during the generation, replacing the `bad_word_ids` of the `GenerateConfig`
     pub bad_word_ids: Option<&'a Vec<Vec<i64>>>,
+    /// Phrasal constraints the generated sequences must satisfy (lexically constrained decoding)
+    pub constraints: Option<&'a [PhrasalConstraint]>,
+    /// Closed set of allowed outputs: the generated sequences are restricted to the sequences of the trie
+    pub allowed_outputs: Option<&'a TokenTrie>,
     /// Flag indicating if text generation scores should be returned
     pub output_scores: bool,
+    /// Function called with each token as it is generated, e.g. to stream the output. Tokens are only final when
+    /// decoding with greedy search or sampling: the function is not called for beam search (`num_beams` > 1).
+    pub token_callback: Option<TokenCallback<'a>>,
+    /// Sequences of text stopping the generation of a sequence once generated, replacing the `stop_sequences` of the `GenerateConfig`
+    pub stop_sequences: Option<&'a [&'a str]>,
+    /// Stopping criteria evaluated in addition to the `stopping_criteria` of the `GenerateConfig`
+    pub stopping_criteria: Option<&'a [&'a dyn StoppingCriteria]>,
+    /// Logits processors applied after the `logits_processors` of the `GenerateConfig`
+    pub logits_processors: Option<&'a [&'a dyn LogitsProcessor]>,
+    /// Biases of token sequences, replacing the `sequence_bias` of the `GenerateConfig`. Empty sequences are
+    /// rejected by `validate` and ignored by the `generate` methods.
+    pub sequence_bias: Option<&'a [(Vec<i64>, f64)]>,
+}
+
+impl GenerateOptions<'_> {
+    /// Checks the options before they are passed to a `generate` method. Returns an error if a biased token
+    /// sequence is empty.
+    pub fn validate(&self) -> Result<(), RustBertError> {
+        self.sequence_bias.map_or(Ok(()), validate_sequence_bias)
+    }
 }
 
 macro_rules! unpack_config {
@@ -1975,18 +3718,86 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
         let repetition_penalty = unpack_config!(repetition_penalty, generate_options, config);
         let length_penalty = unpack_config!(length_penalty, generate_options, config);
         let no_repeat_ngram_size = unpack_config!(no_repeat_ngram_size, generate_options, config);
+        let no_repeat_ngram_scope = unpack_config!(no_repeat_ngram_scope, generate_options, config);
+        let encoder_no_repeat_ngram_size =
+            unpack_config!(encoder_no_repeat_ngram_size, generate_options, config);
         let num_beam_groups = generate_options.map_or(config.num_beam_groups, |opts| {
             opts.num_beam_groups.or(config.num_beam_groups)
         });
         let diversity_penalty = generate_options.map_or(config.diversity_penalty, |opts| {
             opts.diversity_penalty.or(config.diversity_penalty)
         });
+        // Contrastive search is only supported by decoder-only models, encoder-decoder models use greedy decoding
+        let penalty_alpha = generate_options
+            .map_or(config.penalty_alpha, |opts| {
+                opts.penalty_alpha.or(config.penalty_alpha)
+            })
+            .filter(|_| !self.is_encoder_decoder());
+        let typical_p =
+            generate_options.map_or(config.typical_p, |opts| opts.typical_p.or(config.typical_p));
+        let eta_cutoff = generate_options.map_or(config.eta_cutoff, |opts| {
+            opts.eta_cutoff.or(config.eta_cutoff)
+        });
+        let epsilon_cutoff = generate_options.map_or(config.epsilon_cutoff, |opts| {
+            opts.epsilon_cutoff.or(config.epsilon_cutoff)
+        });
         let decoder_start_token_id = generate_options.and_then(|opts| opts.decoder_start_token_id);
         let forced_bos_token_id = generate_options.and_then(|opts| opts.forced_bos_token_id);
-        let bad_word_ids = generate_options.and_then(|opts| opts.bad_word_ids);
+        let forced_eos_token_id = generate_options.and_then(|opts| opts.forced_eos_token_id);
+        let bad_word_ids = generate_options
+            .and_then(|opts| opts.bad_word_ids)
+            .or_else(|| Some(&config.bad_word_ids).filter(|ids| !ids.is_empty()));
+        let constraints = generate_options.and_then(|opts| opts.constraints);
+        let allowed_outputs = generate_options.and_then(|opts| opts.allowed_outputs);
         let prefix_allowed_tokens_fn =
             generate_options.and_then(|opts| opts.prefix_allowed_tokens_fn);
         let output_scores = generate_options.map_or(false, |opts| opts.output_scores);
+        let token_callback = generate_options.and_then(|opts| opts.token_callback);
+        let stop_sequences = match generate_options.and_then(|opts| opts.stop_sequences) {
+            Some(stop_sequences) => stop_sequences.to_vec(),
+            None => config.stop_sequences.iter().map(String::as_str).collect(),
+        };
+        let mut stopping_criteria = config
+            .stopping_criteria
+            .iter()
+            .map(|criterion| criterion.as_ref())
+            .collect::<Vec<&dyn StoppingCriteria>>();
+        if let Some(extra_stopping_criteria) =
+            generate_options.and_then(|opts| opts.stopping_criteria)
+        {
+            stopping_criteria.extend(extra_stopping_criteria.iter().copied());
+        }
+        let stopping_conditions = StoppingConditions::new(stop_sequences, stopping_criteria);
+        let sequence_bias = generate_options
+            .and_then(|opts| opts.sequence_bias)
+            .unwrap_or(&config.sequence_bias);
+        // The configuration is validated by the generator, the empty sequences of the options are ignored
+        let sequence_bias = sequence_bias
+            .iter()
+            .filter(|(sequence, _)| !sequence.is_empty())
+            .cloned()
+            .collect::<Vec<(Vec<i64>, f64)>>();
+        let sequence_bias_processor = if sequence_bias.is_empty() {
+            None
+        } else {
+            SequenceBiasLogitsProcessor::new(sequence_bias).ok()
+        };
+        // The sequence bias is applied to the scores of the model, before the other logits processors
+        let mut logits_processors = sequence_bias_processor
+            .iter()
+            .map(|processor| processor as &dyn LogitsProcessor)
+            .chain(
+                config
+                    .logits_processors
+                    .iter()
+                    .map(|processor| processor.as_ref()),
+            )
+            .collect::<Vec<&dyn LogitsProcessor>>();
+        if let Some(extra_logits_processors) =
+            generate_options.and_then(|opts| opts.logits_processors)
+        {
+            logits_processors.extend(extra_logits_processors.iter().copied());
+        }
 
         let pad_token_id = match self.get_pad_id() {
             Some(value) => Some(value),
@@ -2029,8 +3840,33 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
             },
         };
 
+        let encoder_input_ids = (self.is_encoder_decoder() && encoder_no_repeat_ngram_size > 0)
+            .then(|| {
+                let input_ids = input_ids.to(Device::Cpu);
+                let attention_mask = attention_mask.to_device(Device::Cpu).to_kind(Int64);
+                (0..batch_size)
+                    .map(|batch_index| {
+                        input_ids
+                            .get(batch_index)
+                            .iter::<i64>()
+                            .unwrap()
+                            .zip(attention_mask.get(batch_index).iter::<i64>().unwrap())
+                            .filter(|(_, mask)| *mask != 0)
+                            .map(|(token_id, _)| token_id)
+                            .collect::<Vec<i64>>()
+                    })
+                    .collect::<Vec<Vec<i64>>>()
+            });
+
         let encoder_outputs = if self.is_encoder_decoder() {
-            let encoder_outputs = self.encode(&input_ids, Some(&attention_mask)).unwrap();
+            let encoder_outputs = match &config.encoder_cache {
+                Some(encoder_cache) => {
+                    encoder_cache.get_or_insert_with(&input_ids, &attention_mask, || {
+                        self.encode(&input_ids, Some(&attention_mask)).unwrap()
+                    })
+                }
+                None => self.encode(&input_ids, Some(&attention_mask)).unwrap(),
+            };
             let expanded_batch_indices = Tensor::arange(batch_size, (Int64, input_ids.device()))
                 .view((-1, 1))
                 .repeat([1, num_beams * effective_batch_mult])
@@ -2105,6 +3941,11 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
             This would lead to an infinite generation loop. Please provide a `max_length` or `max_new_tokens`")
         }
 
+        let no_repeat_ngram_start = match no_repeat_ngram_scope {
+            NoRepeatNgramScope::FullSequence => 0,
+            NoRepeatNgramScope::Generated => cur_len,
+        };
+
         let gen_opt = InternalGenerateOptions {
             min_length,
             max_length,
@@ -2114,6 +3955,9 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
             top_p,
             repetition_penalty,
             no_repeat_ngram_size,
+            no_repeat_ngram_start,
+            encoder_no_repeat_ngram_size,
+            encoder_input_ids,
             pad_token_id,
             eos_token_ids,
             num_return_sequences,
@@ -2122,8 +3966,17 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
             length_penalty,
             num_beam_groups,
             diversity_penalty,
+            penalty_alpha,
+            typical_p,
+            eta_cutoff,
+            epsilon_cutoff,
             forced_bos_token_id,
+            forced_eos_token_id,
             bad_word_ids,
+            constraints,
+            allowed_outputs,
+            stopping_conditions,
+            logits_processors,
         };
 
         let generated_output_with_scores = no_grad(|| {
@@ -2148,6 +4001,7 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
                     gen_opt,
                     prefix_allowed_tokens_fn,
                     output_scores,
+                    token_callback,
                 )
             }
         });
@@ -2182,6 +4036,513 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
         output
     }
 
+    /// Scores continuations of a prompt by their log-likelihood under the model, e.g. to rank a set of candidate
+    /// answers or label words. For encoder-decoder models, the prompt is the encoder input and the continuation is
+    /// scored as the start of the decoded sequence. The continuations are scored in a single forward pass each,
+    /// without special tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - `&str` prompt (context) preceding the continuations. May not be empty for decoder-only models.
+    /// * `continuations` - `&[&str]` continuations to score
+    ///
+    /// # Returns
+    /// * `Vec<f64>` Sum of the log-probabilities of the tokens of each continuation given the prompt
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::LanguageGenerator;
+    ///
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
+    /// let scores =
+    ///     gpt2_generator.score_continuations("The capital of France is", &[" Paris", " London"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn score_continuations<S>(
+        &self,
+        prompt: &str,
+        continuations: &[S],
+    ) -> Result<Vec<f64>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        let tokenizer = self._get_tokenizer();
+        let device = self.get_device();
+        let prompt_ids = if self.is_encoder_decoder() {
+            tokenizer
+                .encode_list(
+                    &[prompt],
+                    self.get_max_positions_embeddings()
+                        .map_or(usize::MAX, |max_length| max_length as usize),
+                    &TruncationStrategy::LongestFirst,
+                    0,
+                )
+                .remove(0)
+                .token_ids
+        } else {
+            tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(prompt))
+        };
+        if prompt_ids.is_empty() {
+            return Err(RustBertError::ValueError(
+                "The prompt of the continuations to score may not be empty".to_string(),
+            ));
+        }
+        let encoder_input = Tensor::from_slice(&prompt_ids).unsqueeze(0).to(device);
+        let encoder_output = if self.is_encoder_decoder() {
+            no_grad(|| self.encode(&encoder_input, None))
+        } else {
+            None
+        };
+        let decoder_start_id = self.get_decoder_start_id().or_else(|| self.get_bos_id());
+
+        continuations
+            .iter()
+            .map(|continuation| {
+                let continuation_ids =
+                    tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(continuation.as_ref()));
+                if continuation_ids.is_empty() {
+                    return Err(RustBertError::ValueError(format!(
+                        "The continuation {} does not contain any token",
+                        continuation.as_ref()
+                    )));
+                }
+                // Logits at a position predict the token at the next position
+                let (output, offset) = if self.is_encoder_decoder() {
+                    let decoder_start_id = decoder_start_id.ok_or_else(|| {
+                        RustBertError::ValueError(
+                            "The model requires a decoder start token to score continuations"
+                                .to_string(),
+                        )
+                    })?;
+                    let decoder_ids = [&[decoder_start_id], continuation_ids.as_slice()].concat();
+                    let decoder_input = Tensor::from_slice(&decoder_ids).unsqueeze(0).to(device);
+                    let output = no_grad(|| {
+                        self.forward_t(
+                            Some(&encoder_input),
+                            Cache::None,
+                            None,
+                            None,
+                            None,
+                            None,
+                            encoder_output.as_ref(),
+                            Some(&decoder_input),
+                            false,
+                        )
+                    })?;
+                    (output, 0)
+                } else {
+                    let input_ids = [prompt_ids.as_slice(), continuation_ids.as_slice()].concat();
+                    let input = Tensor::from_slice(&input_ids).unsqueeze(0).to(device);
+                    let output = no_grad(|| {
+                        self.forward_t(
+                            Some(&input),
+                            Cache::None,
+                            None,
+                            None,
+                            None,
+                            None,
+                            None,
+                            None,
+                            false,
+                        )
+                    })?;
+                    (output, prompt_ids.len() - 1)
+                };
+                let log_probabilities = output
+                    .lm_logits
+                    .get(0)
+                    .log_softmax(-1, Kind::Float)
+                    .to(Device::Cpu);
+                Ok(continuation_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(position, token_id)| {
+                        log_probabilities.double_value(&[(offset + position) as i64, *token_id])
+                    })
+                    .sum())
+            })
+            .collect()
+    }
+
+    /// Scores the tokens of a sequence by their log-probability given the previous tokens under a decoder-only model,
+    /// e.g. to measure the information carried by each token. The first token is scored after the beginning of
+    /// sequence token of the model if it has one. The sequence is scored in a single forward pass and should not
+    /// exceed the maximum number of positions of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `&[i64]` ids of the tokens to score
+    ///
+    /// # Returns
+    /// * `Vec<Option<f64>>` Log-probability of each token (`None` for the first token of models without a beginning of
+    /// sequence token)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::LanguageGenerator;
+    ///
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
+    /// let tokenizer = gpt2_generator.get_tokenizer();
+    /// let token_ids = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize("The capital of France is Paris"));
+    /// let log_probabilities = gpt2_generator.score_token_ids(&token_ids)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn score_token_ids(&self, token_ids: &[i64]) -> Result<Vec<Option<f64>>, RustBertError> {
+        if self.is_encoder_decoder() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Scoring tokens requires a decoder-only model".to_string(),
+            ));
+        }
+        if token_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let bos_id = self.get_bos_id();
+        let offset = usize::from(bos_id.is_some());
+        let input_ids = bos_id
+            .into_iter()
+            .chain(token_ids.iter().copied())
+            .collect::<Vec<i64>>();
+        let input = Tensor::from_slice(&input_ids)
+            .unsqueeze(0)
+            .to(self.get_device());
+        let output = no_grad(|| {
+            self.forward_t(
+                Some(&input),
+                Cache::None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+        })?;
+        let log_probabilities = output
+            .lm_logits
+            .get(0)
+            .log_softmax(-1, Kind::Float)
+            .to(Device::Cpu);
+        // Logits at a position predict the token at the next position
+        Ok(token_ids
+            .iter()
+            .enumerate()
+            .map(|(position, token_id)| {
+                let input_position = position + offset;
+                (input_position > 0).then(|| {
+                    log_probabilities.double_value(&[input_position as i64 - 1, *token_id])
+                })
+            })
+            .collect())
+    }
+
+    /// Generate text with greedy decoding, assisted by a smaller draft model (speculative decoding). At each step the
+    /// draft model proposes `num_draft_tokens` tokens, that are verified by the model in a single forward pass. The
+    /// longest prefix of the proposal matching the greedy predictions of the model is accepted, followed by the
+    /// prediction of the model at the first mismatch. The cached keys and values of both models are rolled back to the
+    /// accepted tokens. The generated sequences are identical to the greedy decoding of the model (up to numerical
+    /// differences), while the model is called once for several tokens when the draft model predicts them correctly.
+    ///
+    /// Both models must be decoder-only models sharing the same vocabulary (e.g. `gpt2` and `distilgpt2`). Prompts are
+    /// processed one at a time. Only the `max_length`, `max_new_tokens` and `min_length` settings are used: the
+    /// decoding is always greedy, without sampling, beam search, repetition penalty or n-gram blocking.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - Text prompts to complete
+    /// * `draft_model` - Smaller `LanguageGenerator` proposing the tokens
+    /// * `num_draft_tokens` - Number of tokens proposed by the draft model at each step
+    /// * `generate_options` - `Option<GenerateOptions>` Optional set of generate options. If not (or partially) provided, will use the settings provided when creating the generator
+    ///
+    /// # Returns
+    /// * `Vec<GeneratedTextOutput>` Vector of length *number_of_prompts* containing the generated texts (without score).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::{
+    ///     GPT2Generator, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources,
+    ///     Gpt2VocabResources,
+    /// };
+    /// use rust_bert::pipelines::generation_utils::{
+    ///     GenerateConfig, GenerateOptions, LanguageGenerator,
+    /// };
+    /// use rust_bert::resources::RemoteResource;
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
+    /// let draft_generator = GPT2Generator::new(GenerateConfig {
+    ///     model_resource: rust_bert::pipelines::common::ModelResource::Torch(Box::new(
+    ///         RemoteResource::from_pretrained(Gpt2ModelResources::DISTIL_GPT2),
+    ///     )),
+    ///     config_resource: Box::new(RemoteResource::from_pretrained(
+    ///         Gpt2ConfigResources::DISTIL_GPT2,
+    ///     )),
+    ///     vocab_resource: Box::new(RemoteResource::from_pretrained(
+    ///         Gpt2VocabResources::DISTIL_GPT2,
+    ///     )),
+    ///     merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+    ///         Gpt2MergesResources::DISTIL_GPT2,
+    ///     ))),
+    ///     ..Default::default()
+    /// })?;
+    ///
+    /// let generate_options = GenerateOptions {
+    ///     max_new_tokens: Some(32),
+    ///     ..Default::default()
+    /// };
+    /// let output = gpt2_generator.generate_assisted(
+    ///     &["The dog"],
+    ///     &draft_generator,
+    ///     4,
+    ///     Some(generate_options),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_assisted<S, D>(
+        &self,
+        prompt_texts: &[S],
+        draft_model: &D,
+        num_draft_tokens: i64,
+        generate_options: Option<GenerateOptions>,
+    ) -> Result<Vec<GeneratedTextOutput>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+        D: LanguageGenerator,
+    {
+        if self.is_encoder_decoder() | draft_model.is_encoder_decoder() {
+            return Err(RustBertError::ValueError(
+                "Assisted generation is only supported for decoder-only models".into(),
+            ));
+        }
+        if self.get_vocab_size() != draft_model.get_vocab_size() {
+            return Err(RustBertError::ValueError(format!(
+                "The draft model vocabulary size ({}) does not match the model vocabulary size ({})",
+                draft_model.get_vocab_size(),
+                self.get_vocab_size()
+            )));
+        }
+        if num_draft_tokens < 1 {
+            return Err(RustBertError::ValueError(
+                "At least one draft token must be proposed at each step".into(),
+            ));
+        }
+        let config = self.get_config();
+        let eos_token_ids = self.get_eos_ids().cloned();
+        let min_length = unpack_config!(min_length, generate_options, config);
+        let (max_length, max_new_tokens) = match generate_options {
+            Some(generate_options) => {
+                match (generate_options.max_length, generate_options.max_new_tokens) {
+                    (None, None) => (config.max_length, None),
+                    (max_length, max_new_tokens) => (max_length, max_new_tokens),
+                }
+            }
+            None => (config.max_length, None),
+        };
+        if max_length.is_none() & max_new_tokens.is_none() & eos_token_ids.is_none() {
+            return Err(RustBertError::ValueError(
+                "No maximum length given for a model without an EOS token. \
+                Please provide a `max_length` or `max_new_tokens`"
+                    .into(),
+            ));
+        }
+        let pad_token_id = self
+            .get_pad_id()
+            .or_else(|| eos_token_ids.as_ref().map(|eos_ids| eos_ids[0]));
+
+        let mut output = Vec::with_capacity(prompt_texts.len());
+        for prompt_text in prompt_texts {
+            let input_ids = self.encode_prompt_text(&[prompt_text], max_length, pad_token_id);
+            let mut sequence = Vec::<i64>::try_from(input_ids.get(0))?;
+            if sequence.is_empty() {
+                sequence.push(self.get_bos_id().ok_or_else(|| {
+                    RustBertError::ValueError(
+                        "A model with a BOS token must be used to start generation with an empty input"
+                            .into(),
+                    )
+                })?);
+            }
+            let max_length = match (max_length, max_new_tokens) {
+                (Some(max_length), _) => Some(max_length as usize),
+                (None, Some(max_new_tokens)) => Some(sequence.len() + max_new_tokens as usize),
+                (None, None) => None,
+            };
+            no_grad(|| {
+                assisted_greedy_search(
+                    self,
+                    draft_model,
+                    &mut sequence,
+                    num_draft_tokens as usize,
+                    max_length,
+                    min_length,
+                    eos_token_ids.as_deref(),
+                )
+            })?;
+            output.push(GeneratedTextOutput {
+                text: self._get_tokenizer().decode(&sequence, true, true),
+                score: None,
+            });
+        }
+        Ok(output)
+    }
+
+    /// Generates continuations of several prompts sharing a common context (e.g. the sections of a document planned
+    /// by an outline, or several questions about the same text). The context is processed once and its cached keys
+    /// and values are reused by every prompt, so that only the prompt and the generated tokens are processed for each
+    /// continuation. The context and the prompts are tokenized separately.
+    ///
+    /// The model must be a decoder-only model. Prompts are processed one at a time, with greedy decoding or sampling
+    /// (`do_sample`, `temperature`, `top_k` and `top_p`), stopping at `max_length` (counting the context, prompt and
+    /// generated tokens), `max_new_tokens`, an EOS token or one of the `stop_sequences`. Beam search, repetition
+    /// penalty and n-gram blocking are not used.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Text shared by all prompts, preceding each prompt
+    /// * `prompt_texts` - Text prompts to complete, following the context
+    /// * `generate_options` - `Option<GenerateOptions>` Optional set of generate options. If not (or partially) provided, will use the settings provided when creating the generator
+    ///
+    /// # Returns
+    /// * `Vec<GeneratedTextOutput>` Vector of length *number_of_prompts* containing the generated continuations, without the context and prompt (without score).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateOptions, LanguageGenerator};
+    ///
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
+    /// let generate_options = GenerateOptions {
+    ///     max_new_tokens: Some(32),
+    ///     ..Default::default()
+    /// };
+    /// let output = gpt2_generator.generate_with_shared_context(
+    ///     "The history of the Eiffel tower.",
+    ///     &[" The construction", " The inauguration"],
+    ///     Some(generate_options),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_with_shared_context<S>(
+        &self,
+        context: &str,
+        prompt_texts: &[S],
+        generate_options: Option<GenerateOptions>,
+    ) -> Result<Vec<GeneratedTextOutput>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        if self.is_encoder_decoder() {
+            return Err(RustBertError::ValueError(
+                "Generation with a shared context is only supported for decoder-only models".into(),
+            ));
+        }
+        let config = self.get_config();
+        let eos_token_ids = self.get_eos_ids().cloned();
+        let min_length = unpack_config!(min_length, generate_options, config);
+        let do_sample = unpack_config!(do_sample, generate_options, config);
+        let temperature = unpack_config!(temperature, generate_options, config);
+        let top_k = unpack_config!(top_k, generate_options, config);
+        let top_p = unpack_config!(top_p, generate_options, config);
+        if do_sample & (temperature <= 0.0) {
+            return Err(RustBertError::ValueError(format!(
+                "The sampling temperature should be positive, got {temperature}"
+            )));
+        }
+        let (max_length, max_new_tokens) = match generate_options {
+            Some(generate_options) => {
+                match (generate_options.max_length, generate_options.max_new_tokens) {
+                    (None, None) => (config.max_length, None),
+                    (max_length, max_new_tokens) => (max_length, max_new_tokens),
+                }
+            }
+            None => (config.max_length, None),
+        };
+        if max_length.is_none() & max_new_tokens.is_none() & eos_token_ids.is_none() {
+            return Err(RustBertError::ValueError(
+                "No maximum length given for a model without an EOS token. \
+                Please provide a `max_length` or `max_new_tokens`"
+                    .into(),
+            ));
+        }
+        let stop_sequences: Vec<String> =
+            match generate_options.and_then(|options| options.stop_sequences) {
+                Some(stop_sequences) => stop_sequences.iter().map(|s| s.to_string()).collect(),
+                None => config.stop_sequences.clone(),
+            };
+        let pad_token_id = self
+            .get_pad_id()
+            .or_else(|| eos_token_ids.as_ref().map(|eos_ids| eos_ids[0]));
+
+        let context_ids = self.encode_prompt_text(&[context], None, pad_token_id);
+        let mut context_ids = Vec::<i64>::try_from(context_ids.get(0))?;
+        if context_ids.is_empty() {
+            if let Some(bos_token_id) = self.get_bos_id() {
+                context_ids.push(bos_token_id);
+            }
+        }
+        let search_settings = SharedContextSearchSettings {
+            min_length,
+            eos_token_ids: eos_token_ids.as_deref(),
+            stop_sequences: &stop_sequences,
+            do_sample,
+            temperature,
+            top_k,
+            top_p,
+        };
+
+        no_grad(|| {
+            let context_cache = if context_ids.is_empty() {
+                Cache::None
+            } else {
+                assisted_forward(self, &context_ids, Cache::None, 0)?.1
+            };
+            let tokenizer = self._get_tokenizer();
+            let mut output = Vec::with_capacity(prompt_texts.len());
+            for prompt_text in prompt_texts {
+                let prompt_ids =
+                    tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(prompt_text.as_ref()));
+                let mut sequence = context_ids.clone();
+                sequence.extend(prompt_ids);
+                if sequence.is_empty() {
+                    return Err(RustBertError::ValueError(
+                        "A model with a BOS token must be used to start generation with an empty input"
+                            .into(),
+                    ));
+                }
+                let prompt_length = sequence.len();
+                let max_length = match (max_length, max_new_tokens) {
+                    (Some(max_length), _) => Some(max_length as usize),
+                    (None, Some(max_new_tokens)) => Some(prompt_length + max_new_tokens as usize),
+                    (None, None) => None,
+                };
+                // At least one position is processed to obtain the logits of the first generated token
+                let (cache, cached_length) =
+                    rollback_cache(&context_cache, context_ids.len(), prompt_length - 1);
+                let text = shared_context_search(
+                    self,
+                    &mut sequence,
+                    cache,
+                    cached_length,
+                    max_length,
+                    &search_settings,
+                )?;
+                output.push(GeneratedTextOutput { text, score: None });
+            }
+            Ok(output)
+        })
+    }
+
     /// Returns a reference to the text generator's tokenizer
     ///
     /// # Returns
@@ -2236,10 +4597,239 @@ pub trait LanguageGenerator: PrivateLanguageGenerator {
         Ok(())
     }
 
+    /// Casts the weights of the model to a floating point precision (`Kind::Float`, `Kind::Double`, `Kind::Half` or
+    /// `Kind::BFloat16`). The attention of the model is accumulated in half precision unless it is run within
+    /// `precision::with_attention_fp32_accumulation` (as done by the pipelines).
+    fn set_kind(&mut self, kind: Kind) -> Result<(), RustBertError> {
+        set_var_store_kind(self.get_var_store_mut()?, kind)
+    }
+
     fn set_device(&mut self, device: Device) -> Result<(), RustBertError> {
         self.get_var_store_mut()?.set_device(device);
         Ok(())
     }
+
+    /// Saves the weights of the model, in their current precision, to a snapshot file (see the `snapshot` module)
+    fn save_snapshot(&mut self, path: &Path) -> Result<(), RustBertError> {
+        snapshot::save_snapshot(self.get_var_store_mut()?, path)
+    }
+
+    /// Restores the weights and precision of the model from a snapshot file (see the `snapshot` module)
+    fn load_snapshot(&mut self, path: &Path) -> Result<(), RustBertError> {
+        snapshot::load_snapshot(self.get_var_store_mut()?, path)
+    }
+}
+
+/// Runs a decoder-only model on the positions of `sequence` not stored in its cache, returning the logits of these
+/// positions with shape (*new tokens*, *vocab_size*) and the updated cache
+fn assisted_forward<T: PrivateLanguageGenerator + ?Sized>(
+    model: &T,
+    sequence: &[i64],
+    cache: Cache,
+    cached_length: usize,
+) -> Result<(Tensor, Cache), RustBertError> {
+    let device = model.get_device();
+    let input_ids = Tensor::from_slice(&sequence[cached_length..])
+        .to_device(device)
+        .unsqueeze(0);
+    let attention_mask = Tensor::ones([1, sequence.len() as i64], (Int64, device));
+    let position_ids =
+        Tensor::arange_start(cached_length as i64, sequence.len() as i64, (Int64, device))
+            .unsqueeze(0);
+    let output = model.forward_t(
+        Some(&input_ids),
+        cache,
+        Some(&attention_mask),
+        None,
+        Some(&position_ids),
+        None,
+        None,
+        None,
+        false,
+    )?;
+    Ok((output.lm_logits.squeeze_dim(0), output.cache))
+}
+
+/// Greedy predictions for logits of shape (*positions*, *vocab_size*), the first row predicting the token at
+/// `first_position`. EOS tokens are not predicted before `min_length`.
+fn greedy_predictions(
+    logits: Tensor,
+    first_position: usize,
+    min_length: i64,
+    eos_token_ids: Option<&[i64]>,
+) -> Result<Vec<i64>, RustBertError> {
+    if let Some(eos_token_ids) = eos_token_ids {
+        let masked_rows = (min_length - first_position as i64).clamp(0, logits.size()[0]);
+        if masked_rows > 0 {
+            let eos_token_ids = Tensor::from_slice(eos_token_ids).to_device(logits.device());
+            let _ =
+                logits
+                    .narrow(0, 0, masked_rows)
+                    .index_fill_(1, &eos_token_ids, f64::NEG_INFINITY);
+        }
+    }
+    Ok(Vec::<i64>::try_from(logits.argmax(-1, false))?)
+}
+
+/// Keeps the cache of the first `length` positions, or resets it if it cannot be truncated
+fn rollback_cache(cache: &Cache, cached_length: usize, length: usize) -> (Cache, usize) {
+    match cache.truncate(min(cached_length, length) as i64) {
+        Some(cache) => (cache, min(cached_length, length)),
+        None => (Cache::None, 0),
+    }
+}
+
+/// Extends `sequence` with the greedy decoding of `model`, using the tokens proposed by `draft_model`
+fn assisted_greedy_search<T, D>(
+    model: &T,
+    draft_model: &D,
+    sequence: &mut Vec<i64>,
+    num_draft_tokens: usize,
+    max_length: Option<usize>,
+    min_length: i64,
+    eos_token_ids: Option<&[i64]>,
+) -> Result<(), RustBertError>
+where
+    T: PrivateLanguageGenerator + ?Sized,
+    D: PrivateLanguageGenerator + ?Sized,
+{
+    let is_eos = |token: &i64| eos_token_ids.map_or(false, |eos_ids| eos_ids.contains(token));
+    let (mut cache, mut cached_length) = (Cache::None, 0);
+    let (mut draft_cache, mut draft_cached_length) = (Cache::None, 0);
+    while max_length.map_or(true, |max_length| sequence.len() < max_length) {
+        let current_length = sequence.len();
+        // The model always adds one token after the accepted draft tokens
+        let num_proposals = max_length.map_or(num_draft_tokens, |max_length| {
+            min(num_draft_tokens, max_length - current_length - 1)
+        });
+
+        let mut proposals = sequence.clone();
+        while proposals.len() < current_length + num_proposals {
+            let (logits, new_cache) =
+                assisted_forward(draft_model, &proposals, draft_cache, draft_cached_length)?;
+            draft_cache = new_cache;
+            draft_cached_length = proposals.len();
+            let token = greedy_predictions(
+                logits.get(-1).unsqueeze(0),
+                proposals.len(),
+                min_length,
+                eos_token_ids,
+            )?[0];
+            proposals.push(token);
+            if is_eos(&token) {
+                break;
+            }
+        }
+        let num_proposals = proposals.len() - current_length;
+
+        let (logits, new_cache) = assisted_forward(model, &proposals, cache, cached_length)?;
+        let predictions = greedy_predictions(
+            logits.narrow(
+                0,
+                (current_length - 1 - cached_length) as i64,
+                num_proposals as i64 + 1,
+            ),
+            current_length,
+            min_length,
+            eos_token_ids,
+        )?;
+        let num_accepted = proposals[current_length..]
+            .iter()
+            .zip(predictions.iter())
+            .take_while(|(proposal, prediction)| proposal == prediction)
+            .count();
+        sequence.extend_from_slice(&predictions[..=num_accepted]);
+
+        if let Some(eos_position) = sequence[current_length..].iter().position(is_eos) {
+            sequence.truncate(current_length + eos_position + 1);
+            break;
+        }
+        let (new_cache, new_cached_length) =
+            rollback_cache(&new_cache, proposals.len(), current_length + num_accepted);
+        cache = new_cache;
+        cached_length = new_cached_length;
+        let (new_draft_cache, new_draft_cached_length) = rollback_cache(
+            &draft_cache,
+            draft_cached_length,
+            current_length + num_accepted,
+        );
+        draft_cache = new_draft_cache;
+        draft_cached_length = new_draft_cached_length;
+    }
+    Ok(())
+}
+
+/// Decoding settings of `shared_context_search`
+struct SharedContextSearchSettings<'a> {
+    min_length: i64,
+    eos_token_ids: Option<&'a [i64]>,
+    stop_sequences: &'a [String],
+    do_sample: bool,
+    temperature: f64,
+    top_k: i64,
+    top_p: f64,
+}
+
+/// Extends `sequence` by greedy decoding or sampling, starting from the cache of its first `cached_length` positions.
+/// Returns the decoded text generated, truncated before the first stop sequence.
+fn shared_context_search<T: PrivateLanguageGenerator + ?Sized>(
+    model: &T,
+    sequence: &mut Vec<i64>,
+    mut cache: Cache,
+    mut cached_length: usize,
+    max_length: Option<usize>,
+    settings: &SharedContextSearchSettings,
+) -> Result<String, RustBertError> {
+    let tokenizer = model._get_tokenizer();
+    let prompt_length = sequence.len();
+    while max_length.map_or(true, |max_length| sequence.len() < max_length) {
+        let (logits, new_cache) = assisted_forward(model, sequence, cache, cached_length)?;
+        cache = new_cache;
+        cached_length = sequence.len();
+        let mut logits = logits.get(-1).unsqueeze(0).to_kind(Kind::Float);
+        let token = if settings.do_sample {
+            if let Some(eos_token_ids) = settings.eos_token_ids {
+                if (sequence.len() as i64) < settings.min_length {
+                    let eos_token_ids =
+                        Tensor::from_slice(eos_token_ids).to_device(logits.device());
+                    let _ = logits.index_fill_(1, &eos_token_ids, f64::NEG_INFINITY);
+                }
+            }
+            let mut logits = logits / settings.temperature;
+            model.top_k_top_p_filtering(&mut logits, settings.top_k, settings.top_p, 1);
+            logits
+                .softmax(-1, Kind::Float)
+                .multinomial(1, false)
+                .int64_value(&[0, 0])
+        } else {
+            greedy_predictions(
+                logits,
+                sequence.len(),
+                settings.min_length,
+                settings.eos_token_ids,
+            )?[0]
+        };
+        sequence.push(token);
+        if settings
+            .eos_token_ids
+            .map_or(false, |eos_ids| eos_ids.contains(&token))
+        {
+            break;
+        }
+        if !settings.stop_sequences.is_empty() {
+            let mut generated_text = tokenizer.decode(&sequence[prompt_length..], true, true);
+            if let Some(stop_position) = settings
+                .stop_sequences
+                .iter()
+                .filter_map(|stop_sequence| generated_text.find(stop_sequence.as_str()))
+                .min()
+            {
+                generated_text.truncate(stop_position);
+                return Ok(generated_text);
+            }
+        }
+    }
+    Ok(tokenizer.decode(&sequence[prompt_length..], true, true))
 }
 
 #[derive(Debug)]
@@ -2355,4 +4945,7 @@ pub struct LMModelOutput {
     pub lm_logits: Tensor,
     /// cached state for improved efficiency during decoding
     pub cache: Cache,
+    /// Last hidden states of the decoder for each position (for the predicted position for XLNet), if provided by the
+    /// model (required for contrastive search)
+    pub last_hidden_state: Option<Tensor>,
 }
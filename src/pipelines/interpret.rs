@@ -0,0 +1,687 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Explanation of classification predictions
+//! Attributes the prediction of a sequence classification model to the tokens of its input. Three attribution methods
+//! are available:
+//! - attention rollout (Abnar & Zuidema, 2020): the attention weights of the layers, averaged over the heads and
+//!   combined with the residual connections, are multiplied from the first to the last layer. The importance of a token
+//!   is the attention flowing from the first (classification) token to this token.
+//! - gradient x input: the gradient of the logit of the explained label with respect to the input embeddings,
+//!   multiplied by the input embeddings and summed over the hidden dimension.
+//! - integrated gradients (Sundararajan et al., 2017): the gradients are averaged along the straight line from a zero
+//!   embedding baseline to the input embeddings, and multiplied by the input embeddings.
+//!
+//! The token importances are aligned to the character offsets of the tokens in the input text (special tokens are
+//! omitted). The explanations are available for BERT, RoBERTa, XLM-RoBERTa and DistilBERT models.
+//!
+//! The `OcclusionExplainer` provides model-agnostic explanations for any sequence classification model (including
+//! ONNX models): the importance of a token is the decrease of the probability of the label when the token (or a span
+//! of tokens containing it) is occluded. The occluded inputs are classified in batches.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::interpret::{AttributionMethod, SequenceClassificationExplainer};
+//! # fn main() -> anyhow::Result<()> {
+//! let explainer = SequenceClassificationExplainer::new(Default::default())?;
+//!
+//! let input = ["The movie was great, although a bit long."];
+//! let output = explainer.explain(&input, AttributionMethod::IntegratedGradients { steps: 32 })?;
+//! for attribution in &output[0].attributions {
+//!     println!("{}: {:.3}", attribution.text, attribution.score);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::mps;
+use crate::common::precision::with_attention_fp32_accumulation;
+use crate::pipelines::common::EncodedInput;
+use crate::pipelines::sequence_classification::{
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::{Offset, TokenizedInput};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tch::{no_grad, with_grad, Device, Kind, Tensor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Method used to attribute a prediction to the input tokens
+pub enum AttributionMethod {
+    /// Attention rollout from the first (classification) token
+    AttentionRollout,
+    /// Gradient of the label logit with respect to the input embeddings, multiplied by the input embeddings
+    GradientXInput,
+    /// Integrated gradients from a zero embedding baseline, approximated with `steps` interpolation steps (processed
+    /// as a single batch)
+    IntegratedGradients { steps: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Importance of an input token for a prediction
+pub struct TokenAttribution {
+    /// Text of the token in the input
+    pub text: String,
+    /// Character offsets of the token in the input
+    pub offset: Offset,
+    /// Importance of the token (the sign indicates if the token supports or opposes the label for gradient methods)
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Explanation of a classification prediction
+pub struct Explanation {
+    /// Label explained
+    pub label: Label,
+    /// Importance of the input tokens, in the order of the input
+    pub attributions: Vec<TokenAttribution>,
+}
+
+/// Computes the attention rollout of a stack of attention weights, averaged over the heads. The identity is added to
+/// the attention of each layer to account for the residual connections.
+///
+/// # Arguments
+///
+/// * `attentions` - Attention weights of every layer, from the first to the last layer, each of shape
+///   (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+///
+/// # Returns
+///
+/// * `Tensor` of shape (*batch size*, *sequence_length*, *sequence_length*), the row `i` containing the attention
+///   flowing from position `i` to each input position
+pub fn attention_rollout(attentions: &[Tensor]) -> Result<Tensor, RustBertError> {
+    let mut rollout: Option<Tensor> = None;
+    for attention in attentions {
+        let attention = attention.mean_dim([1].as_slice(), false, Kind::Float);
+        let sequence_length = *attention.size().last().unwrap();
+        let attention = attention + Tensor::eye(sequence_length, (Kind::Float, attention.device()));
+        let attention = &attention / attention.sum_dim_intlist([-1].as_slice(), true, Kind::Float);
+        rollout = Some(match rollout {
+            Some(rollout) => attention.matmul(&rollout),
+            None => attention,
+        });
+    }
+    rollout.ok_or_else(|| {
+        RustBertError::ValueError("At least one attention layer is required".to_string())
+    })
+}
+
+/// # SequenceClassificationExplainer to explain the predictions of a sequence classification model
+pub struct SequenceClassificationExplainer {
+    model: SequenceClassificationModel,
+}
+
+impl SequenceClassificationExplainer {
+    /// Build a new `SequenceClassificationExplainer`. The model is loaded with the output of the attention weights
+    /// enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `SequenceClassificationConfig` object containing the resource references (model, vocabulary,
+    ///   configuration) and device placement (CPU/GPU). Only Torch BERT, RoBERTa, XLM-RoBERTa and DistilBERT models are
+    ///   supported.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::interpret::SequenceClassificationExplainer;
+    ///
+    /// let explainer = SequenceClassificationExplainer::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        config: SequenceClassificationConfig,
+    ) -> Result<SequenceClassificationExplainer, RustBertError> {
+        let model = SequenceClassificationModel::new_with_attention_outputs(config)?;
+        Ok(SequenceClassificationExplainer { model })
+    }
+
+    /// Get a reference to the underlying classification model
+    pub fn get_model(&self) -> &SequenceClassificationModel {
+        &self.model
+    }
+
+    /// Explains the predicted label of each input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify and explain
+    /// * `method` - `AttributionMethod` used to compute the token importances
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Explanation>` containing the predicted label and the token importances for each input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::interpret::{AttributionMethod, SequenceClassificationExplainer};
+    ///
+    /// let explainer = SequenceClassificationExplainer::new(Default::default())?;
+    /// let input = ["The movie was great, although a bit long."];
+    /// let output = explainer.explain(&input, AttributionMethod::AttentionRollout)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain<'a, S>(
+        &self,
+        input: S,
+        method: AttributionMethod,
+    ) -> Result<Vec<Explanation>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        input
+            .as_ref()
+            .iter()
+            .enumerate()
+            .map(|(sentence, text)| self.explain_text(text, sentence, None, method))
+            .collect()
+    }
+
+    /// Explains a given label (not necessarily the predicted label) for an input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Text to explain
+    /// * `label_id` - Id of the label to explain
+    /// * `method` - `AttributionMethod` used to compute the token importances
+    ///
+    /// # Returns
+    ///
+    /// * `Explanation` containing the label (with its score) and the token importances
+    pub fn explain_label(
+        &self,
+        input: &str,
+        label_id: i64,
+        method: AttributionMethod,
+    ) -> Result<Explanation, RustBertError> {
+        self.explain_text(input, 0, Some(label_id), method)
+    }
+
+    fn explain_text(
+        &self,
+        text: &str,
+        sentence: usize,
+        label_id: Option<i64>,
+        method: AttributionMethod,
+    ) -> Result<Explanation, RustBertError> {
+        let tokenized_input = self
+            .model
+            .get_tokenizer()
+            .encode_list(
+                &[text],
+                self.model.max_length,
+                &TruncationStrategy::LongestFirst,
+                0,
+            )
+            .pop()
+            .unwrap();
+        let device = self.model.device;
+        let input_ids = Tensor::from_slice(&tokenized_input.token_ids)
+            .to_device(device)
+            .unsqueeze(0);
+        let token_type_ids = Tensor::from_slice(&tokenized_input.segment_ids)
+            .to_kind(Kind::Int64)
+            .to_device(device)
+            .unsqueeze(0);
+        let classifier = &self.model.sequence_classifier;
+
+        let (logits, all_attentions) =
+            with_attention_fp32_accumulation(self.model.attention_fp32_accumulation, || {
+                no_grad(|| {
+                    classifier.forward_with_attentions(
+                        Some(&input_ids),
+                        None,
+                        Some(&token_type_ids),
+                        None,
+                        false,
+                    )
+                })
+            })?;
+        let label = label_from_probabilities(
+            self.model.get_label_mapping(),
+            &probabilities(&logits).get(0),
+            sentence,
+            label_id,
+        )?;
+
+        let scores = match method {
+            AttributionMethod::AttentionRollout => no_grad(|| attention_rollout(&all_attentions))?
+                .get(0)
+                .get(0),
+            AttributionMethod::GradientXInput => {
+                let input_embeds = self.input_embeddings(&input_ids)?;
+                let gradients = self.gradients(&input_embeds, &token_type_ids, label.id);
+                (gradients * &input_embeds)
+                    .sum_dim_intlist([-1].as_slice(), false, Kind::Float)
+                    .get(0)
+            }
+            AttributionMethod::IntegratedGradients { steps } => {
+                if steps == 0 {
+                    return Err(RustBertError::ValueError(
+                        "Integrated gradients require at least one step".to_string(),
+                    ));
+                }
+                let input_embeds = self.input_embeddings(&input_ids)?;
+                // Midpoint approximation of the path integral from the zero baseline
+                let alphas =
+                    (Tensor::arange(steps as i64, (Kind::Float, device)) + 0.5) / steps as f64;
+                let scaled_embeds =
+                    alphas.view([-1, 1, 1]).to_kind(input_embeds.kind()) * &input_embeds;
+                let gradients = self.gradients(
+                    &scaled_embeds,
+                    &token_type_ids.expand([steps as i64, -1], true),
+                    label.id,
+                );
+                (gradients.mean_dim([0].as_slice(), true, Kind::Float) * &input_embeds)
+                    .sum_dim_intlist([-1].as_slice(), false, Kind::Float)
+                    .get(0)
+            }
+        };
+        let scores = Vec::<f64>::try_from(mps::to_double(&scores))?;
+
+        Ok(Explanation {
+            label,
+            attributions: token_attributions(text, &tokenized_input, &scores),
+        })
+    }
+
+    fn input_embeddings(&self, input_ids: &Tensor) -> Result<Tensor, RustBertError> {
+        let word_embeddings = self.model.sequence_classifier.get_word_embeddings()?;
+        Ok(no_grad(|| {
+            word_embeddings
+                .index_select(0, &input_ids.view([-1]))
+                .unsqueeze(0)
+        }))
+    }
+
+    /// Gradient of the sum of the label logits with respect to the input embeddings
+    fn gradients(&self, input_embeds: &Tensor, token_type_ids: &Tensor, label_id: i64) -> Tensor {
+        with_attention_fp32_accumulation(self.model.attention_fp32_accumulation, || {
+            with_grad(|| {
+                let input_embeds = input_embeds.detach().set_requires_grad(true);
+                let logits = self.model.sequence_classifier.forward_t(
+                    None,
+                    None,
+                    Some(token_type_ids),
+                    None,
+                    Some(&input_embeds),
+                    false,
+                );
+                let label_logits = logits.select(1, label_id).sum(Kind::Float);
+                Tensor::run_backward(&[label_logits], &[&input_embeds], false, false)
+                    .pop()
+                    .unwrap()
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Occlusion of the input tokens
+pub enum OcclusionStrategy {
+    /// The occluded tokens are replaced by the mask token of the tokenizer
+    MaskToken,
+    /// The occluded tokens are removed from the input
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Configuration for occlusion explanations
+pub struct OcclusionConfig {
+    /// Number of consecutive tokens occluded together. For spans longer than one token, the importance of a token
+    /// is averaged over the spans containing it (default: 1)
+    pub span_length: usize,
+    /// Occlusion of the tokens (default: `OcclusionStrategy::MaskToken`)
+    pub strategy: OcclusionStrategy,
+    /// Maximum number of occluded inputs classified in a single forward pass (default: 32)
+    pub batch_size: usize,
+}
+
+impl Default for OcclusionConfig {
+    fn default() -> OcclusionConfig {
+        OcclusionConfig {
+            span_length: 1,
+            strategy: OcclusionStrategy::MaskToken,
+            batch_size: 32,
+        }
+    }
+}
+
+/// # OcclusionExplainer to explain the predictions of any sequence classification model
+/// The importance of a token is the decrease of the probability of the explained label when the token is occluded.
+/// Negative importances indicate tokens opposing the label.
+pub struct OcclusionExplainer {
+    model: SequenceClassificationModel,
+    config: OcclusionConfig,
+}
+
+impl OcclusionExplainer {
+    /// Build a new `OcclusionExplainer` from a sequence classification model
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `SequenceClassificationModel` to explain
+    /// * `config` - `OcclusionConfig` occlusion settings
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::interpret::{OcclusionConfig, OcclusionExplainer};
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let model = SequenceClassificationModel::new(Default::default())?;
+    /// let explainer = OcclusionExplainer::new(model, OcclusionConfig::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        model: SequenceClassificationModel,
+        config: OcclusionConfig,
+    ) -> Result<OcclusionExplainer, RustBertError> {
+        if (config.span_length == 0) | (config.batch_size == 0) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The occlusion span length and batch size must be at least 1".to_string(),
+            ));
+        }
+        if (config.strategy == OcclusionStrategy::MaskToken)
+            & model.get_tokenizer().get_mask_id().is_none()
+        {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The tokenizer of the model has no mask token, use `OcclusionStrategy::Remove`"
+                    .to_string(),
+            ));
+        }
+        Ok(OcclusionExplainer { model, config })
+    }
+
+    /// Get a reference to the underlying classification model
+    pub fn get_model(&self) -> &SequenceClassificationModel {
+        &self.model
+    }
+
+    /// Explains the predicted label of each input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify and explain
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Explanation>` containing the predicted label and the token importances for each input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::interpret::{OcclusionConfig, OcclusionExplainer};
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let model = SequenceClassificationModel::new(Default::default())?;
+    /// let explainer = OcclusionExplainer::new(model, OcclusionConfig::default())?;
+    /// let input = ["The movie was great, although a bit long."];
+    /// let output = explainer.explain(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain<'a, S>(&self, input: S) -> Result<Vec<Explanation>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        input
+            .as_ref()
+            .iter()
+            .enumerate()
+            .map(|(sentence, text)| self.explain_text(text, sentence, None))
+            .collect()
+    }
+
+    /// Explains a given label (not necessarily the predicted label) for an input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Text to explain
+    /// * `label_id` - Id of the label to explain
+    ///
+    /// # Returns
+    ///
+    /// * `Explanation` containing the label (with its score) and the token importances
+    pub fn explain_label(&self, input: &str, label_id: i64) -> Result<Explanation, RustBertError> {
+        self.explain_text(input, 0, Some(label_id))
+    }
+
+    fn explain_text(
+        &self,
+        text: &str,
+        sentence: usize,
+        label_id: Option<i64>,
+    ) -> Result<Explanation, RustBertError> {
+        let tokenizer = self.model.get_tokenizer();
+        let tokenized_input = tokenizer
+            .encode_list(
+                &[text],
+                self.model.max_length,
+                &TruncationStrategy::LongestFirst,
+                0,
+            )
+            .pop()
+            .unwrap();
+        let token_type_ids = tokenized_input
+            .segment_ids
+            .iter()
+            .map(|segment_id| *segment_id as i64)
+            .collect::<Vec<i64>>();
+        let spans = occlusion_spans(&tokenized_input, self.config.span_length);
+
+        // The first input is the original input, followed by an occluded input for each span
+        let mut inputs = vec![EncodedInput::new(tokenized_input.token_ids.clone())
+            .with_token_type_ids(token_type_ids.clone())];
+        for span in spans.iter() {
+            let occluded_input = match self.config.strategy {
+                OcclusionStrategy::MaskToken => {
+                    let mask_id = tokenizer.get_mask_id().unwrap();
+                    let mut input_ids = tokenized_input.token_ids.clone();
+                    for position in span.iter() {
+                        input_ids[*position] = mask_id;
+                    }
+                    EncodedInput::new(input_ids).with_token_type_ids(token_type_ids.clone())
+                }
+                OcclusionStrategy::Remove => {
+                    let (input_ids, segment_ids): (Vec<i64>, Vec<i64>) = tokenized_input
+                        .token_ids
+                        .iter()
+                        .zip(token_type_ids.iter())
+                        .enumerate()
+                        .filter(|(position, _)| !span.contains(position))
+                        .map(|(_, (input_id, segment_id))| (*input_id, *segment_id))
+                        .unzip();
+                    EncodedInput::new(input_ids).with_token_type_ids(segment_ids)
+                }
+            };
+            inputs.push(occluded_input);
+        }
+
+        let pad_id = tokenizer.get_pad_id().unwrap_or(0);
+        let mut all_probabilities = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(self.config.batch_size) {
+            let (input_ids, mask, token_type_ids) =
+                EncodedInput::pad_batch(batch, pad_id, None, self.model.device)?;
+            all_probabilities.push(with_attention_fp32_accumulation(
+                self.model.attention_fp32_accumulation,
+                || {
+                    no_grad(|| {
+                        probabilities(&self.model.sequence_classifier.forward_t(
+                            Some(&input_ids),
+                            Some(&mask),
+                            Some(&token_type_ids),
+                            None,
+                            None,
+                            false,
+                        ))
+                        .to(Device::Cpu)
+                    })
+                },
+            ));
+        }
+        let all_probabilities = Tensor::cat(&all_probabilities, 0);
+        let label = label_from_probabilities(
+            self.model.get_label_mapping(),
+            &all_probabilities.get(0),
+            sentence,
+            label_id,
+        )?;
+        let label_probabilities =
+            Vec::<f64>::try_from(mps::to_double(&all_probabilities.select(1, label.id)))?;
+
+        let mut score_sums = vec![0f64; tokenized_input.token_ids.len()];
+        let mut span_counts = vec![0usize; tokenized_input.token_ids.len()];
+        for (span, occluded_probability) in spans.iter().zip(label_probabilities[1..].iter()) {
+            for position in span.iter() {
+                score_sums[*position] += label.score - occluded_probability;
+                span_counts[*position] += 1;
+            }
+        }
+        let scores = score_sums
+            .iter()
+            .zip(span_counts.iter())
+            .map(|(score_sum, span_count)| score_sum / (*span_count).max(1) as f64)
+            .collect::<Vec<f64>>();
+
+        Ok(Explanation {
+            label,
+            attributions: token_attributions(text, &tokenized_input, &scores),
+        })
+    }
+}
+
+/// Spans of `span_length` consecutive tokens occluded, excluding the special tokens
+fn occlusion_spans(tokenized_input: &TokenizedInput, span_length: usize) -> Vec<Vec<usize>> {
+    let positions = tokenized_input
+        .token_offsets
+        .iter()
+        .enumerate()
+        .filter(|(_, offset)| offset.is_some())
+        .map(|(position, _)| position)
+        .collect::<Vec<usize>>();
+    if positions.is_empty() {
+        vec![]
+    } else if positions.len() <= span_length {
+        vec![positions]
+    } else {
+        positions
+            .windows(span_length)
+            .map(|span| span.to_vec())
+            .collect()
+    }
+}
+
+/// Probabilities of the labels. Single-logit heads (e.g. distilled cross-encoders) output a relevance score.
+fn probabilities(logits: &Tensor) -> Tensor {
+    if logits.size()[1] == 1 {
+        logits.sigmoid().to_kind(Kind::Float)
+    } else {
+        logits.softmax(-1, Kind::Float)
+    }
+}
+
+/// Label explained, predicted from the probabilities of the labels if not provided
+fn label_from_probabilities(
+    label_mapping: &HashMap<i64, String>,
+    probabilities: &Tensor,
+    sentence: usize,
+    label_id: Option<i64>,
+) -> Result<Label, RustBertError> {
+    let id = match label_id {
+        Some(label_id) => label_id,
+        None => probabilities.argmax(-1, false).int64_value(&[]),
+    };
+    let text = label_mapping
+        .get(&id)
+        .ok_or_else(|| {
+            RustBertError::ValueError(format!("Label {id} is not a label of the model"))
+        })?
+        .clone();
+    Ok(Label {
+        text,
+        score: probabilities.double_value(&[id]),
+        id,
+        sentence,
+    })
+}
+
+/// Aligns the token scores to the character offsets of the tokens in the input, skipping special tokens
+fn token_attributions(
+    text: &str,
+    tokenized_input: &TokenizedInput,
+    scores: &[f64],
+) -> Vec<TokenAttribution> {
+    tokenized_input
+        .token_offsets
+        .iter()
+        .zip(scores.iter())
+        .filter_map(|(offset, score)| {
+            offset.map(|offset| TokenAttribution {
+                text: text
+                    .chars()
+                    .skip(offset.begin as usize)
+                    .take((offset.end - offset.begin) as usize)
+                    .collect(),
+                offset,
+                score: *score,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = SequenceClassificationConfig::default();
+        let _: Box<dyn Send> = Box::new(SequenceClassificationExplainer::new(config));
+    }
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test_occlusion() {
+        let model = SequenceClassificationModel::new(Default::default()).unwrap();
+        let _: Box<dyn Send> = Box::new(OcclusionExplainer::new(model, Default::default()));
+    }
+
+    #[test]
+    fn rollout_of_uniform_attention() {
+        let uniform = Tensor::full([1, 2, 3, 3], 1.0 / 3.0, (Kind::Float, Device::Cpu));
+        let rollout = attention_rollout(&[uniform.copy(), uniform]).unwrap();
+        assert_eq!(rollout.size(), vec![1, 3, 3]);
+        // Each row of the rollout is a probability distribution
+        assert!(rollout
+            .sum_dim_intlist([-1].as_slice(), false, Kind::Float)
+            .allclose(
+                &Tensor::ones([1, 3], (Kind::Float, Device::Cpu)),
+                1e-5,
+                1e-8,
+                false
+            ));
+        // The residual connection keeps more attention on the position itself
+        assert!(rollout.double_value(&[0, 0, 0]) > rollout.double_value(&[0, 0, 1]));
+        assert!(attention_rollout(&[]).is_err());
+    }
+}
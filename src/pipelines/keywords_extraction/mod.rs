@@ -3,4 +3,8 @@ mod scorer;
 mod stopwords;
 mod tokenizer;
 
-pub use pipeline::{Keyword, KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType};
+pub use pipeline::{
+    Keyword, KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType,
+    NOUN_PHRASE_POS_PATTERN,
+};
+pub use stopwords::ENGLISH_STOPWORDS;
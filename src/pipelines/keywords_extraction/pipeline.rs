@@ -22,7 +22,8 @@ use crate::pipelines::common::TokenizerOption;
 /// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 /// SOFTWARE.
 use crate::pipelines::keywords_extraction::tokenizer::StopWordsTokenizer;
-#[cfg(feature = "remote")]
+use crate::pipelines::pos_tagging::{POSConfig, POSModel};
+#[cfg(all(feature = "remote", feature = "bert"))]
 use crate::pipelines::sentence_embeddings::SentenceEmbeddingsModelType;
 use crate::pipelines::sentence_embeddings::{
     SentenceEmbeddingsConfig, SentenceEmbeddingsModel, SentenceEmbeddingsSentenceBertConfig,
@@ -34,6 +35,10 @@ use rust_tokenizers::Offset;
 use std::borrow::Cow;
 use std::cmp::min;
 use std::collections::{HashMap, HashSet};
+use std::iter;
+
+/// Part of speech pattern (Penn Treebank tags) matching noun phrases: optional adjectives followed by nouns
+pub const NOUN_PHRASE_POS_PATTERN: &str = r"^(JJ[RS]? )*(NN[PS]{0,2} )*NN[PS]{0,2}$";
 
 /// # Keyword generated by a `KeywordExtractionModel`
 #[derive(Debug, Clone)]
@@ -89,9 +94,20 @@ pub struct KeywordExtractionConfig<'a> {
     /// identify a global optimum for the ranker criterion, but are more likely to include sets that are less relevant to the
     /// input document. Larger values also have a higher computational and memory cost (N<sup>2</sup> scale)
     pub max_sum_candidates: Option<usize>,
+    /// Optional `POSConfig` of a part of speech tagging model used to filter the keyword candidates
+    /// matching `candidate_pos_pattern`.
+    pub pos_config: Option<POSConfig>,
+    /// Optional regex pattern matched against the space-separated part of speech tags of the words of a candidate
+    /// (e.g. `JJ NN` for "neural network"). Only used if `pos_config` is provided, defaults to `NOUN_PHRASE_POS_PATTERN`.
+    pub candidate_pos_pattern: Option<Regex>,
+    /// Optional seed keywords describing the domain of the documents. The document embeddings are biased towards
+    /// the average embedding of the seed keywords, favouring domain-specific keywords.
+    pub seed_keywords: Option<&'a [&'a str]>,
+    /// Optional weight of the seed keywords embedding in the biased document embedding, defaults to 0.25.
+    pub seed_keywords_weight: Option<f64>,
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bert"))]
 impl Default for KeywordExtractionConfig<'_> {
     fn default() -> Self {
         let sentence_embeddings_config =
@@ -107,6 +123,10 @@ impl Default for KeywordExtractionConfig<'_> {
             num_keywords: 5,
             diversity: None,
             max_sum_candidates: None,
+            pos_config: None,
+            candidate_pos_pattern: None,
+            seed_keywords: None,
+            seed_keywords_weight: None,
         }
     }
 }
@@ -125,6 +145,9 @@ pub struct KeywordExtractionModel<'a> {
     num_keywords: usize,
     diversity: Option<f64>,
     max_sum_candidates: Option<usize>,
+    pos_model: Option<(POSModel, Regex)>,
+    seed_keywords: Option<&'a [&'a str]>,
+    seed_keywords_weight: f64,
 }
 
 impl<'a> KeywordExtractionModel<'a> {
@@ -172,6 +195,16 @@ impl<'a> KeywordExtractionModel<'a> {
             do_lower_case,
             config.tokenizer_forbidden_ngram_chars,
         );
+        let pos_model = match config.pos_config {
+            Some(pos_config) => {
+                let pattern = match config.candidate_pos_pattern {
+                    Some(pattern) => pattern,
+                    None => Regex::new(NOUN_PHRASE_POS_PATTERN).unwrap(),
+                };
+                Some((POSModel::new(pos_config)?, pattern))
+            }
+            None => None,
+        };
         Ok(Self {
             sentence_embeddings_model,
             tokenizer,
@@ -180,6 +213,9 @@ impl<'a> KeywordExtractionModel<'a> {
             num_keywords: config.num_keywords,
             diversity: config.diversity,
             max_sum_candidates: config.max_sum_candidates,
+            pos_model,
+            seed_keywords: config.seed_keywords,
+            seed_keywords_weight: config.seed_keywords_weight.unwrap_or(0.25),
         })
     }
 
@@ -222,14 +258,65 @@ impl<'a> KeywordExtractionModel<'a> {
     where
         S: AsRef<str> + Send + Sync,
     {
-        let words = self.tokenizer.tokenize_list(inputs, self.ngram_range);
+        let mut words = self.tokenizer.tokenize_list(inputs, self.ngram_range);
+        if let Some((pos_model, pos_pattern)) = &self.pos_model {
+            let pos_tags = pos_model.predict_with_offsets(inputs);
+            for ((document_words, document_tags), text) in
+                words.iter_mut().zip(pos_tags).zip(inputs.iter())
+            {
+                // Part of speech offsets are character offsets, keyword offsets are byte offsets
+                let text = text.as_ref();
+                let byte_positions = text
+                    .char_indices()
+                    .map(|(position, _)| position)
+                    .chain(iter::once(text.len()))
+                    .collect::<Vec<usize>>();
+                let document_tags = document_tags
+                    .into_iter()
+                    .filter_map(|(tag, offset)| {
+                        offset.map(|offset| {
+                            (
+                                byte_positions[offset.begin as usize],
+                                byte_positions[offset.end as usize],
+                                tag.label,
+                            )
+                        })
+                    })
+                    .collect::<Vec<(usize, usize, String)>>();
+                document_words.retain(|_, offsets| {
+                    offsets.iter().any(|offset| {
+                        let candidate_tags = document_tags
+                            .iter()
+                            .filter(|(begin, end, _)| {
+                                *begin < offset.end as usize && *end > offset.begin as usize
+                            })
+                            .map(|(_, _, label)| label.as_str())
+                            .collect::<Vec<&str>>();
+                        !candidate_tags.is_empty()
+                            && pos_pattern.is_match(&candidate_tags.join(" "))
+                    })
+                });
+            }
+        }
         let (flat_word_list, document_boundaries) =
             KeywordExtractionModel::flatten_word_list(&words);
+        if flat_word_list.is_empty() {
+            return Ok(vec![vec![]; inputs.len()]);
+        }
 
-        let document_embeddings = self
+        let mut document_embeddings = self
             .sentence_embeddings_model
             .encode_as_tensor(inputs)?
             .embeddings;
+        if let Some(seed_keywords) = self.seed_keywords.filter(|seeds| !seeds.is_empty()) {
+            let seed_embedding = self
+                .sentence_embeddings_model
+                .encode_as_tensor(seed_keywords)?
+                .embeddings
+                .mean_dim([0].as_slice(), true, document_embeddings.kind());
+            document_embeddings = document_embeddings * (1.0 - self.seed_keywords_weight)
+                + seed_embedding * self.seed_keywords_weight;
+        }
 
         let word_embeddings = self
             .sentence_embeddings_model
@@ -245,6 +332,10 @@ impl<'a> KeywordExtractionModel<'a> {
                 .embeddings
                 .slice(0, start as i64, end as i64, 1);
             let num_keywords = min(self.num_keywords, word_embeddings.size()[0] as usize);
+            if num_keywords == 0 {
+                output_keywords.push(document_keywords);
+                continue;
+            }
             let local_top_word_indices = self.scorer_type.score_keywords(
                 document_embedding,
                 word_embeddings,
@@ -20,6 +20,7 @@
 /// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 /// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 /// SOFTWARE.
+use crate::common::mps;
 use crate::pipelines::keywords_extraction::KeywordScorerType;
 use std::cmp::{max, min};
 use std::convert::TryFrom;
@@ -78,7 +79,7 @@ fn cosine_similarity_score(
 ) -> Vec<(usize, f32)> {
     let similarities = cosine_similarity(Some(&document_embedding), &word_embeddings).view([-1]);
 
-    let (top_scores, top_keywords) = similarities.topk(num_keywords as i64, 0, true, false);
+    let (top_scores, top_keywords) = mps::topk(&similarities, num_keywords as i64, 0, true, false);
     top_scores
         .iter::<f64>()
         .unwrap()
@@ -144,8 +145,13 @@ fn max_sum_score(
     let word_document_similarities =
         cosine_similarity(Some(&document_embedding), &word_embeddings).view([-1]);
     let word_similarities = cosine_similarity(None, &word_embeddings);
-    let (_, top_keywords) =
-        word_document_similarities.topk(max_sum_candidates as i64, 0, true, false);
+    let (_, top_keywords) = mps::topk(
+        &word_document_similarities,
+        max_sum_candidates as i64,
+        0,
+        true,
+        false,
+    );
 
     let keyword_combinations = top_keywords.combinations(num_keywords as i64, false);
     let (mut best_score, mut best_combination) = (None, None);
@@ -28,7 +28,8 @@
 /// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 /// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-pub(crate) const ENGLISH_STOPWORDS: [&str; 318] = [
+/// Default list of English stopwords excluded from the keyword candidates. Can be extended to build custom stopword lists.
+pub const ENGLISH_STOPWORDS: [&str; 318] = [
     "a",
     "about",
     "above",
@@ -0,0 +1,441 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Lexical substitution pipeline
+//! Proposes in-context substitutes for a target word using the head of a masked language model, e.g. for writing
+//! assistants (synonym suggestions) or data augmentation. Each substitute is scored on:
+//! - fluency: probability of the substitute in place of the masked target word,
+//! - similarity: probability of the substitute at the position of the (visible) target word, excluding the target
+//!   word itself. Predictions of the model for an unmasked word favor words close in meaning to this word.
+//!
+//! The substitutes are ranked by the weighted geometric mean of both scores. Substitutes are whole words
+//! corresponding to a single token of the model vocabulary.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::lexical_substitution::{
+//!     LexicalSubstitutionModel, SubstitutionTarget,
+//! };
+//!
+//! let model = LexicalSubstitutionModel::new(Default::default())?;
+//! let targets = [SubstitutionTarget::from_marked(
+//!     "The movie was [[great]], I enjoyed it a lot.",
+//! )?];
+//! let output = model.substitute(&targets)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::mps;
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::masked_language::{MaskedLanguageConfig, MaskedLanguageModel};
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::TokenizedInput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::ops::Range;
+use tch::{Device, Kind, Tensor};
+
+/// Opening marker of the target word for `SubstitutionTarget::from_marked`
+pub const TARGET_START_MARKER: &str = "[[";
+/// Closing marker of the target word for `SubstitutionTarget::from_marked`
+pub const TARGET_END_MARKER: &str = "]]";
+
+/// Number of predictions of the masked language model considered per substitute to return
+const CANDIDATE_POOL_FACTOR: usize = 5;
+
+/// # Text with a target word to substitute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubstitutionTarget {
+    /// Text containing the target word
+    pub text: String,
+    /// Byte range of the target word in the text
+    pub span: Range<usize>,
+}
+
+impl SubstitutionTarget {
+    /// Build a new `SubstitutionTarget`. Returns an error if the span is empty or not a valid range of the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text containing the target word
+    /// * `span` - Byte range of the target word in the text
+    pub fn new<S: Into<String>>(
+        text: S,
+        span: Range<usize>,
+    ) -> Result<SubstitutionTarget, RustBertError> {
+        let text = text.into();
+        match text.get(span.clone()) {
+            Some(target) if !target.trim().is_empty() => Ok(SubstitutionTarget { text, span }),
+            _ => Err(RustBertError::ValueError(format!(
+                "Invalid target span {span:?} for the text {text}"
+            ))),
+        }
+    }
+
+    /// Build a new `SubstitutionTarget` from a text with the target word between markers (e.g. `The movie was
+    /// [[great]].`). The markers are removed from the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text containing the marked target word
+    pub fn from_marked(text: &str) -> Result<SubstitutionTarget, RustBertError> {
+        let start = text.find(TARGET_START_MARKER);
+        let end = text.find(TARGET_END_MARKER);
+        match (start, end) {
+            (Some(start), Some(end)) if start < end => {
+                let target_start = start + TARGET_START_MARKER.len();
+                let unmarked_text = format!(
+                    "{}{}{}",
+                    &text[..start],
+                    &text[target_start..end],
+                    &text[end + TARGET_END_MARKER.len()..]
+                );
+                SubstitutionTarget::new(unmarked_text, start..end - TARGET_START_MARKER.len())
+            }
+            _ => Err(RustBertError::ValueError(format!(
+                "The text {text} does not contain a target word between {TARGET_START_MARKER} and {TARGET_END_MARKER}"
+            ))),
+        }
+    }
+
+    /// Returns the target word
+    pub fn target(&self) -> &str {
+        &self.text[self.span.clone()]
+    }
+}
+
+/// # Substitute proposed for a target word
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Substitution {
+    /// Substitute word
+    pub text: String,
+    /// Probability of the substitute in place of the masked target word
+    pub fluency: f64,
+    /// Probability of the substitute at the position of the target word, excluding the target word
+    pub similarity: f64,
+    /// Combined score used to rank the substitutes
+    pub score: f64,
+}
+
+/// # Configuration for LexicalSubstitutionModel
+pub struct LexicalSubstitutionConfig {
+    /// `MaskedLanguageConfig` defining the masked language model to use
+    pub masked_language_config: MaskedLanguageConfig,
+    /// Number of substitutes to return for each target (default: 10)
+    pub num_candidates: usize,
+    /// Weight of the similarity in the combined score, between 0 (fluency only) and 1 (similarity only) (default: 0.5)
+    pub similarity_weight: f64,
+}
+
+impl LexicalSubstitutionConfig {
+    /// Instantiate a new lexical substitution configuration with the default settings
+    ///
+    /// # Arguments
+    ///
+    /// * `masked_language_config` - `MaskedLanguageConfig` defining the masked language model to use
+    pub fn new(masked_language_config: MaskedLanguageConfig) -> LexicalSubstitutionConfig {
+        LexicalSubstitutionConfig {
+            masked_language_config,
+            num_candidates: 10,
+            similarity_weight: 0.5,
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+impl Default for LexicalSubstitutionConfig {
+    /// Provides a BERT masked language model
+    fn default() -> LexicalSubstitutionConfig {
+        LexicalSubstitutionConfig::new(MaskedLanguageConfig::default())
+    }
+}
+
+/// # LexicalSubstitutionModel to propose in-context substitutes for a word
+pub struct LexicalSubstitutionModel {
+    masked_language_model: MaskedLanguageModel,
+    num_candidates: usize,
+    similarity_weight: f64,
+}
+
+impl LexicalSubstitutionModel {
+    /// Build a new `LexicalSubstitutionModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `LexicalSubstitutionConfig` object containing the masked language model configuration and the scoring settings
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::lexical_substitution::LexicalSubstitutionModel;
+    ///
+    /// let model = LexicalSubstitutionModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        config: LexicalSubstitutionConfig,
+    ) -> Result<LexicalSubstitutionModel, RustBertError> {
+        if !(0.0..=1.0).contains(&config.similarity_weight) {
+            return Err(RustBertError::ValueError(format!(
+                "The similarity weight should be between 0 and 1, got {}",
+                config.similarity_weight
+            )));
+        }
+        let masked_language_model = MaskedLanguageModel::new(config.masked_language_config)?;
+        Ok(LexicalSubstitutionModel {
+            masked_language_model,
+            num_candidates: config.num_candidates,
+            similarity_weight: config.similarity_weight,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.masked_language_model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.masked_language_model.get_tokenizer_mut()
+    }
+
+    /// Proposes substitutes for target words in context
+    ///
+    /// # Arguments
+    ///
+    /// * `targets` - `&[SubstitutionTarget]` texts and target words to substitute
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Substitution>>` substitutes for each target, sorted by decreasing score
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::lexical_substitution::{
+    ///     LexicalSubstitutionModel, SubstitutionTarget,
+    /// };
+    ///
+    /// let model = LexicalSubstitutionModel::new(Default::default())?;
+    /// let text = "She was very happy with the results.";
+    /// let targets = [SubstitutionTarget::new(text, 13..18)?];
+    /// let output = model.substitute(&targets)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn substitute(
+        &self,
+        targets: &[SubstitutionTarget],
+    ) -> Result<Vec<Vec<Substitution>>, RustBertError> {
+        if targets.is_empty() {
+            return Ok(vec![]);
+        }
+        let tokenizer = self.get_tokenizer();
+        let (mask_value, mask_id) = match (tokenizer.get_mask_value(), tokenizer.get_mask_id()) {
+            (Some(mask_value), Some(mask_id)) => (mask_value.to_string(), mask_id),
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "Tokenizer does not have a mask token, please use a tokenizer/model with a mask token.".into(),
+                ))
+            }
+        };
+
+        // Each target is scored with the target word masked (fluency) and visible (similarity)
+        let texts = targets
+            .iter()
+            .flat_map(|target| {
+                let masked_text = format!(
+                    "{}{}{}",
+                    &target.text[..target.span.start],
+                    mask_value,
+                    &target.text[target.span.end..]
+                );
+                vec![masked_text, target.text.clone()]
+            })
+            .collect::<Vec<String>>();
+        let tokenized_input = tokenizer.encode_list(
+            &texts,
+            self.masked_language_model.get_max_length(),
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let (input_ids, token_type_ids) = pad_tokenized_input(tokenizer, &tokenized_input)?;
+        let output = self
+            .masked_language_model
+            .forward_ids(&input_ids, &token_type_ids)
+            .to(Device::Cpu);
+
+        targets
+            .iter()
+            .enumerate()
+            .map(|(target_index, target)| {
+                let masked_input = &tokenized_input[2 * target_index];
+                let unmasked_input = &tokenized_input[2 * target_index + 1];
+                let mask_position = masked_input
+                    .token_ids
+                    .iter()
+                    .position(|token_id| *token_id == mask_id)
+                    .ok_or_else(|| truncated_target_error(target))?;
+
+                let char_start = target.text[..target.span.start].chars().count() as u32;
+                let char_end = char_start + target.target().chars().count() as u32;
+                let target_positions = unmasked_input
+                    .token_offsets
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, offset)| {
+                        offset.as_ref().map_or(false, |offset| {
+                            offset.begin < char_end && offset.end > char_start
+                        })
+                    })
+                    .map(|(position, _)| position as i64)
+                    .collect::<Vec<i64>>();
+                if target_positions.is_empty() {
+                    return Err(truncated_target_error(target));
+                }
+                let target_token_ids = target_positions
+                    .iter()
+                    .map(|position| unmasked_input.token_ids[*position as usize])
+                    .collect::<Vec<i64>>();
+
+                let fluency_probabilities = output
+                    .get(2 * target_index as i64)
+                    .get(mask_position as i64)
+                    .softmax(-1, Kind::Float);
+                let similarity_probabilities = output
+                    .get(2 * target_index as i64 + 1)
+                    .index_select(0, &Tensor::from_slice(&target_positions))
+                    .softmax(-1, Kind::Float)
+                    .mean_dim([0].as_slice(), false, Kind::Float);
+                // The predictions for a visible word are dominated by the word itself
+                let target_probability = similarity_probabilities
+                    .index_select(0, &Tensor::from_slice(&target_token_ids))
+                    .sum(Kind::Float)
+                    .double_value(&[]);
+                let similarity_normalization = (1.0 - target_probability).max(f64::EPSILON);
+
+                let pool_size = (self.num_candidates * CANDIDATE_POOL_FACTOR)
+                    .min(fluency_probabilities.size()[0] as usize);
+                let (pool_probabilities, pool_ids) =
+                    mps::topk(&fluency_probabilities, pool_size as i64, -1, true, true);
+                let pool_probabilities = Vec::<f64>::try_from(pool_probabilities)?;
+                let pool_ids = Vec::<i64>::try_from(pool_ids)?;
+
+                let white_space = if target.text[..target.span.start].ends_with(char::is_whitespace)
+                {
+                    " "
+                } else {
+                    ""
+                };
+                let mut seen_words = HashSet::new();
+                seen_words.insert(target.target().trim().to_lowercase());
+                let mut substitutions = vec![];
+                for (token_id, fluency) in pool_ids.into_iter().zip(pool_probabilities) {
+                    let word = tokenizer
+                        .decode(&[token_id], true, false)
+                        .trim()
+                        .to_string();
+                    if !is_whole_word(tokenizer, &word, white_space, token_id)
+                        || !seen_words.insert(word.to_lowercase())
+                    {
+                        continue;
+                    }
+                    let similarity = (similarity_probabilities.double_value(&[token_id])
+                        / similarity_normalization)
+                        .min(1.0);
+                    let score = fluency.powf(1.0 - self.similarity_weight)
+                        * similarity.powf(self.similarity_weight);
+                    substitutions.push(Substitution {
+                        text: word,
+                        fluency,
+                        similarity,
+                        score,
+                    });
+                }
+                substitutions.sort_by(|a, b| b.score.total_cmp(&a.score));
+                substitutions.truncate(self.num_candidates);
+                Ok(substitutions)
+            })
+            .collect()
+    }
+}
+
+/// Checks if a token is a word (not a sub-word or punctuation) as it would be tokenized in place of the target
+fn is_whole_word(
+    tokenizer: &TokenizerOption,
+    word: &str,
+    white_space: &str,
+    token_id: i64,
+) -> bool {
+    word.starts_with(char::is_alphabetic)
+        && word
+            .chars()
+            .all(|character| character.is_alphabetic() || character == '-' || character == '\'')
+        && tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(&format!("{white_space}{word}")))
+            == [token_id]
+}
+
+fn truncated_target_error(target: &SubstitutionTarget) -> RustBertError {
+    RustBertError::ValueError(format!(
+        "The target word {} was truncated from the model input",
+        target.target()
+    ))
+}
+
+fn pad_tokenized_input(
+    tokenizer: &TokenizerOption,
+    tokenized_input: &[TokenizedInput],
+) -> Result<(Tensor, Tensor), RustBertError> {
+    let pad_id = tokenizer.get_pad_id().ok_or_else(|| {
+        RustBertError::InvalidConfigurationError(
+            "The tokenizer used for lexical substitution should contain a PAD id".into(),
+        )
+    })?;
+    let max_len = tokenized_input
+        .iter()
+        .map(|input| input.token_ids.len())
+        .max()
+        .unwrap_or(0);
+    let (input_ids, token_type_ids): (Vec<Tensor>, Vec<Tensor>) = tokenized_input
+        .iter()
+        .map(|input| {
+            let mut token_ids = input.token_ids.clone();
+            token_ids.resize(max_len, pad_id);
+            let mut segment_ids = input.segment_ids.clone();
+            segment_ids.resize(max_len, *segment_ids.last().unwrap_or(&0));
+            (
+                Tensor::from_slice(&token_ids),
+                Tensor::from_slice(&segment_ids).to_kind(Kind::Int64),
+            )
+        })
+        .unzip();
+    Ok((
+        Tensor::stack(&input_ids, 0),
+        Tensor::stack(&token_type_ids, 0),
+    ))
+}
+
+#[cfg(all(test, feature = "remote", feature = "bert"))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = LexicalSubstitutionConfig::default();
+        let _: Box<dyn Send> = Box::new(LexicalSubstitutionModel::new(config));
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Outline-then-write long-form generation
+//! Generates long documents (e.g. articles or reports) in two stages: the model first writes an outline of the
+//! document with one section heading per line, then expands every section. All sections are written after the same
+//! context (the topic and the complete outline), processed once by the model and whose cached keys and values are
+//! reused for every section (see `LanguageGenerator::generate_with_shared_context`).
+//!
+//! The prompts are built from templates, where `{topic}`, `{outline}` and `{heading}` are replaced by the topic of the
+//! document, the generated outline (one `- heading` line per section) and the heading of the section written.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::long_form::LongFormConfig;
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//!
+//! let model = TextGenerationModel::new(Default::default())?;
+//! let output = model.generate_long_form("The history of the bicycle", &LongFormConfig::default())?;
+//! for section in &output.sections {
+//!     println!("{}: {}", section.heading, section.text);
+//! }
+//! println!("{}", output.document());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+
+/// # Configuration for outline-then-write long-form generation
+/// The sampling settings are those of the text generation model.
+#[derive(Debug, Clone)]
+pub struct LongFormConfig {
+    /// Prompt generating the outline (`{topic}` is replaced by the topic of the document)
+    pub outline_template: String,
+    /// Context shared by all sections (`{topic}` and `{outline}` are replaced by the topic and the outline)
+    pub context_template: String,
+    /// Prompt starting a section, following the shared context (`{heading}` is replaced by the section heading)
+    pub section_template: String,
+    /// Maximum number of sections kept from the outline
+    pub max_sections: usize,
+    /// Maximum number of tokens generated for the outline
+    pub outline_max_new_tokens: Option<i64>,
+    /// Maximum number of tokens generated for each section
+    pub section_max_new_tokens: Option<i64>,
+    /// Sequences ending the text of a section (e.g. the start of the next section heading)
+    pub stop_sequences: Vec<String>,
+}
+
+impl Default for LongFormConfig {
+    fn default() -> LongFormConfig {
+        LongFormConfig {
+            outline_template: "Write the outline of an article about {topic}, with one section title per line.\nOutline:\n".to_string(),
+            context_template: "Article about {topic}.\nOutline:\n{outline}\n\n".to_string(),
+            section_template: "## {heading}\n".to_string(),
+            max_sections: 8,
+            outline_max_new_tokens: Some(128),
+            section_max_new_tokens: Some(256),
+            stop_sequences: vec!["\n## ".to_string()],
+        }
+    }
+}
+
+impl LongFormConfig {
+    pub(crate) fn validate(&self) -> Result<(), RustBertError> {
+        if self.max_sections < 1 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "At least one section should be generated, got a maximum of 0 sections".to_string(),
+            ));
+        }
+        if !self.section_template.contains("{heading}") {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The section template should contain a `{heading}` placeholder".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn outline_prompt(&self, topic: &str) -> String {
+        self.outline_template.replace("{topic}", topic)
+    }
+
+    pub(crate) fn shared_context(&self, topic: &str, headings: &[String]) -> String {
+        let outline = headings
+            .iter()
+            .map(|heading| format!("- {heading}"))
+            .collect::<Vec<String>>()
+            .join("\n");
+        self.context_template
+            .replace("{topic}", topic)
+            .replace("{outline}", &outline)
+    }
+
+    pub(crate) fn section_prompt(&self, heading: &str) -> String {
+        self.section_template.replace("{heading}", heading)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Section of a generated document
+pub struct LongFormSection {
+    /// Heading of the section, from the outline
+    pub heading: String,
+    /// Generated text of the section
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Output of outline-then-write long-form generation
+pub struct LongFormOutput {
+    /// Section headings of the generated outline
+    pub outline: Vec<String>,
+    /// Generated sections, in the order of the outline
+    pub sections: Vec<LongFormSection>,
+}
+
+impl LongFormOutput {
+    /// Returns the complete document, with a markdown heading before the text of each section
+    pub fn document(&self) -> String {
+        self.sections
+            .iter()
+            .map(|section| format!("## {}\n\n{}", section.heading, section.text))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+}
+
+/// Extracts the section headings of a generated outline: one heading per non-empty line, without list markers
+/// (bullets, numbers or markdown heading markers). Duplicated headings are removed.
+pub(crate) fn parse_outline(outline: &str, max_sections: usize) -> Vec<String> {
+    let mut headings: Vec<String> = Vec::new();
+    for line in outline.lines() {
+        let heading = line
+            .trim()
+            .trim_start_matches(|c: char| matches!(c, '-' | '*' | '•' | '#'))
+            .trim_start();
+        let numbering = heading
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(heading.len());
+        let heading = match heading[numbering..].chars().next() {
+            Some(')') | Some(':') if numbering > 0 => &heading[numbering + 1..],
+            _ if heading[..numbering].contains('.') => &heading[numbering..],
+            _ => heading,
+        }
+        .trim()
+        .trim_end_matches(':')
+        .trim();
+        if !heading.is_empty() && !headings.iter().any(|previous| previous == heading) {
+            headings.push(heading.to_string());
+            if headings.len() == max_sections {
+                break;
+            }
+        }
+    }
+    headings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn outline_parsing() {
+        let outline = "1. Introduction\n2) Early designs:\n\n- The safety bicycle\n* Introduction\n## 3.1 Modern bicycles\n10 years ago";
+        assert_eq!(
+            parse_outline(outline, 8),
+            vec![
+                "Introduction",
+                "Early designs",
+                "The safety bicycle",
+                "Modern bicycles",
+                "10 years ago"
+            ]
+        );
+        assert_eq!(
+            parse_outline(outline, 2),
+            vec!["Introduction", "Early designs"]
+        );
+        assert!(parse_outline("\n  \n-\n", 8).is_empty());
+    }
+
+    #[test]
+    fn prompt_templates() {
+        let config = LongFormConfig::default();
+        let headings = vec!["Origins".to_string(), "Today".to_string()];
+        assert_eq!(
+            config.shared_context("bicycles", &headings),
+            "Article about bicycles.\nOutline:\n- Origins\n- Today\n\n"
+        );
+        assert_eq!(config.section_prompt("Origins"), "## Origins\n");
+        assert!(config.validate().is_ok());
+
+        let config = LongFormConfig {
+            section_template: "Section:".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -46,28 +46,39 @@
 //! }
 //! ```
 //!
+#[cfg(feature = "bert")]
 use crate::bert::BertForMaskedLM;
 use crate::common::error::RustBertError;
+use crate::common::weight_tying::{
+    load_weights_with_tying, weight_tying_policy, TiedWeights, WeightTyingStatus,
+};
+#[cfg(feature = "deberta")]
 use crate::deberta::DebertaForMaskedLM;
+#[cfg(feature = "deberta-v2")]
 use crate::deberta_v2::DebertaV2ForMaskedLM;
+#[cfg(feature = "fnet")]
 use crate::fnet::FNetForMaskedLM;
+#[cfg(feature = "modernbert")]
+use crate::modernbert::ModernBertForMaskedLM;
 use crate::pipelines::common::{
     get_device, ConfigOption, ModelResource, ModelType, TokenizerOption,
 };
-use crate::resources::ResourceProvider;
+use crate::resources::{LocalResource, ResourceProvider};
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForMaskedLM;
 use std::convert::TryFrom;
 
+use crate::common::settings::default_device;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::{config::ONNXEnvironmentConfig, ONNXEncoder};
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bert"))]
 use crate::{
     bert::{BertConfigResources, BertModelResources, BertVocabResources},
     resources::RemoteResource,
 };
 use tch::nn::VarStore;
-use tch::{no_grad, Device, Tensor};
+use tch::{no_grad, Device, Kind, Tensor};
 
 #[derive(Debug, Clone)]
 /// Output container for masked language model pipeline.
@@ -142,11 +153,11 @@ impl MaskedLanguageConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             mask_token: mask_token.into(),
-            device: Device::cuda_if_available(),
+            device: default_device(),
         }
     }
 }
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bert"))]
 impl Default for MaskedLanguageConfig {
     /// Provides a BERT language model
     fn default() -> MaskedLanguageConfig {
@@ -170,17 +181,26 @@ impl Default for MaskedLanguageConfig {
 /// # Abstraction that holds one particular masked language model, for any of the supported models
 pub enum MaskedLanguageOption {
     /// Bert for Masked Language
+    #[cfg(feature = "bert")]
     Bert(BertForMaskedLM),
     /// DeBERTa for Masked Language
+    #[cfg(feature = "deberta")]
     Deberta(DebertaForMaskedLM),
     /// DeBERTa V2 for Masked Language
+    #[cfg(feature = "deberta-v2")]
     DebertaV2(DebertaV2ForMaskedLM),
     /// Roberta for Masked Language
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForMaskedLM),
     /// XLMRoberta for Masked Language
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForMaskedLM),
     /// FNet for Masked Language
+    #[cfg(feature = "fnet")]
     FNet(FNetForMaskedLM),
+    /// ModernBERT for Masked Language
+    #[cfg(feature = "modernbert")]
+    ModernBert(ModernBertForMaskedLM),
     /// ONNX model for Masked Language
     #[cfg(feature = "onnx")]
     ONNX(ONNXEncoder),
@@ -193,14 +213,24 @@ impl MaskedLanguageOption {
     /// * `MaskedLanguageConfig` - Masked language model pipeline configuration. The type of model created will be inferred from the
     ///     `ModelResources` (Torch or ONNX) and `ModelType` (Architecture for Torch models) variants provided and
     pub fn new(config: &MaskedLanguageConfig) -> Result<Self, RustBertError> {
+        Self::new_with_weight_tying_status(config).map(|(model, _)| model)
+    }
+
+    /// Instantiate a new masked language model of the supplied type, along with the tying of its LM head with the
+    /// input embeddings verified when loading the Torch weights (empty for ONNX models).
+    pub(crate) fn new_with_weight_tying_status(
+        config: &MaskedLanguageConfig,
+    ) -> Result<(Self, Vec<WeightTyingStatus>), RustBertError> {
         match config.model_resource {
             ModelResource::Torch(_) => Self::new_torch(config),
             #[cfg(feature = "onnx")]
-            ModelResource::ONNX(_) => Self::new_onnx(config),
+            ModelResource::ONNX(_) => Ok((Self::new_onnx(config)?, Vec::new())),
         }
     }
 
-    fn new_torch(config: &MaskedLanguageConfig) -> Result<Self, RustBertError> {
+    fn new_torch(
+        config: &MaskedLanguageConfig,
+    ) -> Result<(Self, Vec<WeightTyingStatus>), RustBertError> {
         let device = config.device;
         let weights_path = config.model_resource.get_torch_local_path()?;
         let mut var_store = VarStore::new(device);
@@ -208,6 +238,7 @@ impl MaskedLanguageOption {
             &ConfigOption::from_file(config.model_type, config.config_resource.get_local_path()?);
         let model_type = config.model_type;
         let model = match model_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(MaskedLanguageOption::Bert(BertForMaskedLM::new(
@@ -220,6 +251,7 @@ impl MaskedLanguageOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta")]
             ModelType::Deberta => {
                 if let ConfigOption::Deberta(config) = model_config {
                     Ok(MaskedLanguageOption::Deberta(DebertaForMaskedLM::new(
@@ -232,6 +264,7 @@ impl MaskedLanguageOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta-v2")]
             ModelType::DebertaV2 => {
                 if let ConfigOption::DebertaV2(config) = model_config {
                     Ok(MaskedLanguageOption::DebertaV2(DebertaV2ForMaskedLM::new(
@@ -244,6 +277,7 @@ impl MaskedLanguageOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
                 if let ConfigOption::Roberta(config) = model_config {
                     Ok(MaskedLanguageOption::Roberta(RobertaForMaskedLM::new(
@@ -256,6 +290,7 @@ impl MaskedLanguageOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(MaskedLanguageOption::XLMRoberta(RobertaForMaskedLM::new(
@@ -268,6 +303,7 @@ impl MaskedLanguageOption {
                     ))
                 }
             }
+            #[cfg(feature = "fnet")]
             ModelType::FNet => {
                 if let ConfigOption::FNet(config) = model_config {
                     Ok(MaskedLanguageOption::FNet(FNetForMaskedLM::new(
@@ -280,12 +316,29 @@ impl MaskedLanguageOption {
                     ))
                 }
             }
+            #[cfg(feature = "modernbert")]
+            ModelType::ModernBert => {
+                if let ConfigOption::ModernBert(config) = model_config {
+                    Ok(MaskedLanguageOption::ModernBert(
+                        ModernBertForMaskedLM::new(var_store.root(), config),
+                    ))
+                } else {
+                    Err(RustBertError::InvalidConfigurationError(
+                        "You can only supply a ModernBertConfig for ModernBERT!".to_string(),
+                    ))
+                }
+            }
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Masked Language is not implemented for {model_type:?}!",
             ))),
         }?;
-        var_store.load(weights_path)?;
-        Ok(model)
+        let weight_tying_status = load_weights_with_tying(
+            &LocalResource::from(weights_path),
+            &mut var_store,
+            model.tied_weights(),
+            weight_tying_policy(),
+        )?;
+        Ok((model, weight_tying_status))
     }
 
     #[cfg(feature = "onnx")]
@@ -306,15 +359,43 @@ impl MaskedLanguageOption {
             &onnx_config,
         )?))
     }
+    /// Input embeddings and LM head variables tied in the checkpoints of the model
+    fn tied_weights(&self) -> &'static [TiedWeights] {
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(_) => &[BertForMaskedLM::TIED_WEIGHTS],
+            #[cfg(feature = "deberta")]
+            Self::Deberta(_) => &[DebertaForMaskedLM::TIED_WEIGHTS],
+            #[cfg(feature = "deberta-v2")]
+            Self::DebertaV2(_) => &[DebertaV2ForMaskedLM::TIED_WEIGHTS],
+            #[cfg(feature = "roberta")]
+            Self::Roberta(_) | Self::XLMRoberta(_) => &[RobertaForMaskedLM::TIED_WEIGHTS],
+            #[cfg(feature = "fnet")]
+            Self::FNet(_) => &[FNetForMaskedLM::TIED_WEIGHTS],
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => &[ModernBertForMaskedLM::TIED_WEIGHTS],
+            #[cfg(feature = "onnx")]
+            Self::ONNX(_) => &[],
+        }
+    }
+
     /// Returns the `ModelType` for this MaskedLanguageOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => ModelType::Deberta,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => ModelType::DebertaV2,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::Roberta,
+            #[cfg(feature = "fnet")]
             Self::FNet(_) => ModelType::FNet,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => ModelType::ModernBert,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
         }
@@ -333,6 +414,7 @@ impl MaskedLanguageOption {
         train: bool,
     ) -> Tensor {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -348,6 +430,7 @@ impl MaskedLanguageOption {
                     .prediction_scores
             }
 
+            #[cfg(feature = "deberta")]
             Self::Deberta(ref model) => {
                 model
                     .forward_t(
@@ -361,6 +444,7 @@ impl MaskedLanguageOption {
                     .expect("Error in Deberta forward_t")
                     .logits
             }
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(ref model) => {
                 model
                     .forward_t(
@@ -375,6 +459,7 @@ impl MaskedLanguageOption {
                     .logits
             }
 
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -389,12 +474,20 @@ impl MaskedLanguageOption {
                     )
                     .prediction_scores
             }
+            #[cfg(feature = "fnet")]
             Self::FNet(ref model) => {
                 model
                     .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
                     .expect("Error in FNet forward pass.")
                     .prediction_scores
             }
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(ref model) => {
+                model
+                    .forward_t(input_ids, mask, input_embeds, train)
+                    .expect("Error in ModernBERT forward pass.")
+                    .prediction_scores
+            }
             #[cfg(feature = "onnx")]
             Self::ONNX(ref model) => {
                 let attention_mask = input_ids.unwrap().ones_like();
@@ -421,6 +514,7 @@ pub struct MaskedLanguageModel {
     mask_token: Option<String>,
     device: Device,
     max_length: usize,
+    weight_tying_status: Vec<WeightTyingStatus>,
 }
 
 impl MaskedLanguageModel {
@@ -488,7 +582,8 @@ impl MaskedLanguageModel {
         config: MaskedLanguageConfig,
         tokenizer: TokenizerOption,
     ) -> Result<MaskedLanguageModel, RustBertError> {
-        let language_encode = MaskedLanguageOption::new(&config)?;
+        let (language_encode, weight_tying_status) =
+            MaskedLanguageOption::new_with_weight_tying_status(&config)?;
         let config_path = config.config_resource.get_local_path()?;
         let model_config = ConfigOption::from_file(config.model_type, config_path);
         let max_length = model_config
@@ -504,9 +599,16 @@ impl MaskedLanguageModel {
             mask_token,
             device,
             max_length,
+            weight_tying_status,
         })
     }
 
+    /// Returns the tying of the LM head with the input embeddings verified when loading the weights. An LM head
+    /// differing from the input embeddings is reported as `WeightTyingStatus::Untied`.
+    pub fn get_weight_tying_status(&self) -> &[WeightTyingStatus] {
+        &self.weight_tying_status
+    }
+
     /// Get a reference to the model tokenizer.
     pub fn get_tokenizer(&self) -> &TokenizerOption {
         &self.tokenizer
@@ -573,8 +675,88 @@ impl MaskedLanguageModel {
     where
         S: AsRef<[&'a str]>,
     {
+        let (mask_token_mask, output) = self.forward_masked(input.as_ref())?;
+
+        let mut output_tokens = Vec::with_capacity(input.as_ref().len());
+        for input_id in 0..input.as_ref().len() as i64 {
+            let mut sequence_tokens = vec![];
+            let sequence_mask = mask_token_mask.get(input_id);
+            if bool::try_from(sequence_mask.any())? {
+                let mask_scores = output
+                    .get(input_id)
+                    .index_select(0, &sequence_mask.argwhere().squeeze_dim(1));
+                let (token_scores, token_ids) = mask_scores.max_dim(1, false);
+                for (id, score) in token_ids.iter::<i64>()?.zip(token_scores.iter::<f64>()?) {
+                    let text = self.tokenizer.decode(&[id], false, true);
+                    sequence_tokens.push(MaskedToken { text, id, score });
+                }
+            }
+            output_tokens.push(sequence_tokens);
+        }
+        Ok(output_tokens)
+    }
+
+    /// Scores candidate tokens for the first masked token of each input, e.g. to compare label words filling a
+    /// cloze-style prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts containing (at least) one masked token.
+    /// * `candidate_ids` - `&[i64]` Token ids of the candidates to score.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` containing the log-probability of each candidate token at the first masked position of each input text
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::masked_language::MaskedLanguageModel;
+    /// let mask_language_model = MaskedLanguageModel::new(Default::default())?;
+    ///
+    /// let tokenizer = mask_language_model.get_tokenizer();
+    /// let candidate_ids = tokenizer.convert_tokens_to_ids(&["great", "terrible"]);
+    /// let output =
+    ///     mask_language_model.score_mask_candidates(&["The movie was [MASK]."], &candidate_ids)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn score_mask_candidates<'a, S>(
+        &self,
+        input: S,
+        candidate_ids: &[i64],
+    ) -> Result<Vec<Vec<f64>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let (mask_token_mask, output) = self.forward_masked(input.as_ref())?;
+        let candidate_ids = Tensor::from_slice(candidate_ids).to(output.device());
+
+        let mut output_scores = Vec::with_capacity(input.as_ref().len());
+        for input_id in 0..input.as_ref().len() as i64 {
+            let sequence_mask = mask_token_mask.get(input_id);
+            if !bool::try_from(sequence_mask.any())? {
+                return Err(RustBertError::ValueError(format!(
+                    "The input {} does not contain a masked token",
+                    input.as_ref()[input_id as usize]
+                )));
+            }
+            let mask_position = i64::try_from(sequence_mask.argwhere().get(0).get(0))?;
+            let scores = output
+                .get(input_id)
+                .get(mask_position)
+                .log_softmax(-1, Kind::Float)
+                .index_select(0, &candidate_ids);
+            output_scores.push(Vec::<f64>::try_from(scores)?);
+        }
+        Ok(output_scores)
+    }
+
+    /// Tokenizes the input texts and runs the model, returning the mask of the masked tokens and the prediction scores
+    fn forward_masked(&self, input: &[&str]) -> Result<(Tensor, Tensor), RustBertError> {
         let (input_ids, token_type_ids) = if let Some(mask_token) = &self.mask_token {
-            let input_with_replaced_mask = self.replace_mask_token(input.as_ref(), mask_token)?;
+            let input_with_replaced_mask = self.replace_mask_token(input, mask_token)?;
             self.tokenizer.tokenize_and_pad(
                 input_with_replaced_mask
                     .iter()
@@ -586,7 +768,7 @@ impl MaskedLanguageModel {
             )
         } else {
             self.tokenizer
-                .tokenize_and_pad(input.as_ref(), self.max_length, self.device)
+                .tokenize_and_pad(input, self.max_length, self.device)
         };
 
         // get the position of mask_token in input texts
@@ -610,24 +792,28 @@ impl MaskedLanguageModel {
                 false,
             )
         });
+        Ok((mask_token_mask, output))
+    }
 
-        let mut output_tokens = Vec::with_capacity(input.as_ref().len());
-        for input_id in 0..input.as_ref().len() as i64 {
-            let mut sequence_tokens = vec![];
-            let sequence_mask = mask_token_mask.get(input_id);
-            if bool::try_from(sequence_mask.any())? {
-                let mask_scores = output
-                    .get(input_id)
-                    .index_select(0, &sequence_mask.argwhere().squeeze_dim(1));
-                let (token_scores, token_ids) = mask_scores.max_dim(1, false);
-                for (id, score) in token_ids.iter::<i64>()?.zip(token_scores.iter::<f64>()?) {
-                    let text = self.tokenizer.decode(&[id], false, true);
-                    sequence_tokens.push(MaskedToken { text, id, score });
-                }
-            }
-            output_tokens.push(sequence_tokens);
-        }
-        Ok(output_tokens)
+    /// Runs the model on padded token ids, returning the prediction scores
+    pub(crate) fn forward_ids(&self, input_ids: &Tensor, token_type_ids: &Tensor) -> Tensor {
+        no_grad(|| {
+            self.language_encode.forward_t(
+                Some(&input_ids.to(self.device)),
+                None,
+                Some(&token_type_ids.to(self.device)),
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+        })
+    }
+
+    /// Maximum number of tokens of the inputs of the model
+    pub(crate) fn get_max_length(&self) -> usize {
+        self.max_length
     }
 }
 #[cfg(test)]
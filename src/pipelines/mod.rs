@@ -524,25 +524,62 @@
 //! }
 //! ```
 
+pub mod clustering;
 pub mod common;
 pub mod conversation;
+pub mod data_to_text;
+pub mod deduplication;
+pub mod ensemble;
+pub mod faithfulness;
 pub mod generation_utils;
+pub mod interpret;
 pub mod keywords_extraction;
+pub mod lexical_substitution;
+pub mod long_form;
 pub mod masked_language;
 pub mod ner;
+pub mod nli;
+pub mod pii;
 pub mod pos_tagging;
+pub mod prompt_classification;
+pub mod prompt_compression;
+pub mod prompts;
 pub mod question_answering;
+pub mod reranking;
+pub mod resume_extraction;
+pub mod review_mining;
+pub mod safety;
+pub mod self_consistency;
 pub mod sentence_embeddings;
 pub mod sentiment;
 pub mod sequence_classification;
+pub mod structured_generation;
+pub mod structured_output;
 pub mod summarization;
 pub mod text_generation;
+pub mod text_to_sql;
 pub mod token_classification;
+pub mod tool_calling;
 pub mod translation;
 pub mod zero_shot_classification;
 
+#[cfg(feature = "whisper")]
+pub mod automatic_speech_recognition;
+
+#[cfg(feature = "tapas")]
+pub mod table_question_answering;
+
+#[cfg(feature = "siglip")]
+pub mod multimodal_embeddings;
+
+#[cfg(feature = "siglip")]
+pub mod zero_shot_image_classification;
+
 #[cfg(feature = "onnx")]
 pub mod onnx;
 
+#[cfg(feature = "candle")]
+pub mod candle;
+
 #[cfg(feature = "hf-tokenizers")]
 pub mod hf_tokenizers;
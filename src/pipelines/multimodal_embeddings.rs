@@ -0,0 +1,326 @@
+// Copyright 2024 Google AI and The HuggingFace Team. All rights reserved.
+// Copyright 2019-2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Multimodal embeddings pipeline
+//! Embeds texts and images in a shared space with a SigLIP dual encoder, for example for text-to-image or
+//! image-to-image retrieval. The embeddings are normalized: the similarity of a text and an image is given by the
+//! dot product of their embeddings.
+//!
+//! Images are expected as tensors of shape (*num channels*, *height*, *width*) with values in [0, 255], as
+//! loaded by `tch::vision::image::load`. They are resized to the image size of the model and normalized by the pipeline.
+//! By default, the dependencies for this model will be downloaded for the SigLIP base model (patch size 16, 224x224 images).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::multimodal_embeddings::MultimodalEmbeddingsModel;
+//!
+//! let embeddings_model = MultimodalEmbeddingsModel::new(Default::default())?;
+//!
+//! let text_embeddings = embeddings_model.encode_texts(&["a photo of a cat", "a photo of a dog"])?;
+//! let image = tch::vision::image::load("path/to/image.jpg")?;
+//! let image_embeddings = embeddings_model.encode_images(&[image])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::{InputError, RustBertError};
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+use crate::pipelines::sentence_embeddings::Embedding;
+use crate::resources::ResourceProvider;
+use crate::siglip::{encode_texts, prepare_image, SiglipConfig, SiglipModel};
+use crate::Config;
+use std::convert::TryFrom;
+use tch::nn::VarStore;
+use tch::{no_grad, Device, Tensor};
+
+#[cfg(feature = "remote")]
+use crate::{
+    resources::RemoteResource,
+    siglip::{SiglipConfigResources, SiglipModelResources, SiglipVocabResources},
+};
+
+/// # Configuration for MultimodalEmbeddingsModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct MultimodalEmbeddingsConfig {
+    /// Model weights resource (default: SigLIP base patch16 224)
+    pub model_resource: ModelResource,
+    /// Config resource (default: SigLIP base patch16 224)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: SigLIP base patch16 224)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Length of the token sequences of the texts. The pretrained models expect sequences of 64 tokens (default: 64)
+    pub max_length: usize,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl MultimodalEmbeddingsConfig {
+    /// Instantiate a new multimodal embeddings configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * model_resource - The `ResourceProvider` pointing to the model to load (e.g.  model.safetensors)
+    /// * config_resource - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab_resource - The `ResourceProvider` pointing to the tokenizer's SentencePiece model to load (e.g.  spiece.model)
+    pub fn new<RC, RV>(
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+    ) -> MultimodalEmbeddingsConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        MultimodalEmbeddingsConfig {
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            max_length: 64,
+            device: default_device(),
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Default for MultimodalEmbeddingsConfig {
+    /// Provides a default SigLIP base model (patch size 16, 224x224 images)
+    fn default() -> MultimodalEmbeddingsConfig {
+        MultimodalEmbeddingsConfig::new(
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                SiglipModelResources::SIGLIP_BASE_PATCH16_224,
+            ))),
+            RemoteResource::from_pretrained(SiglipConfigResources::SIGLIP_BASE_PATCH16_224),
+            RemoteResource::from_pretrained(SiglipVocabResources::SIGLIP_BASE_PATCH16_224),
+        )
+    }
+}
+
+/// # MultimodalEmbeddingsModel to embed texts and images in a shared space
+pub struct MultimodalEmbeddingsModel {
+    tokenizer: TokenizerOption,
+    model: SiglipModel,
+    image_size: i64,
+    num_channels: i64,
+    max_length: usize,
+    device: Device,
+    var_store: VarStore,
+}
+
+impl MultimodalEmbeddingsModel {
+    /// Build a new `MultimodalEmbeddingsModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `MultimodalEmbeddingsConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::multimodal_embeddings::MultimodalEmbeddingsModel;
+    ///
+    /// let embeddings_model = MultimodalEmbeddingsModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        config: MultimodalEmbeddingsConfig,
+    ) -> Result<MultimodalEmbeddingsModel, RustBertError> {
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::T5,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+
+        let device = config.device;
+        let weights_path = config.model_resource.get_torch_local_path()?;
+        let mut var_store = VarStore::new(device);
+        let siglip_config = SiglipConfig::from_file(config.config_resource.get_local_path()?);
+        let model = SiglipModel::new(var_store.root(), &siglip_config);
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+
+        Ok(MultimodalEmbeddingsModel {
+            tokenizer,
+            model,
+            image_size: siglip_config.vision_config.image_size,
+            num_channels: siglip_config.vision_config.num_channels,
+            max_length: config.max_length,
+            device,
+            var_store,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Get a reference to the underlying SigLIP model.
+    pub fn get_model(&self) -> &SiglipModel {
+        &self.model
+    }
+
+    /// Get a reference to the model var store.
+    pub fn get_var_store(&self) -> &VarStore {
+        &self.var_store
+    }
+
+    /// Tokenizes texts for the text tower of the model
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Texts to tokenize
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of token ids of shape (*num texts*, *max_length*)
+    pub fn tokenize<S>(&self, texts: &[S]) -> Result<Tensor, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        if texts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        Ok(encode_texts(
+            &self.tokenizer,
+            texts,
+            self.max_length,
+            self.device,
+        ))
+    }
+
+    /// Resizes and normalizes images for the vision tower of the model
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Image tensors of shape (*num channels*, *height*, *width*) with values in [0, 255]
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of pixel values of shape (*num images*, *num channels*, *image size*, *image size*)
+    pub fn prepare_images(&self, images: &[Tensor]) -> Result<Tensor, RustBertError> {
+        if images.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        let pixel_values = images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let size = image.size();
+                if size.len() != 3 || size[0] != self.num_channels {
+                    return Err(RustBertError::ValueError(format!(
+                        "Image {index} has shape {size:?}, images of shape ({}, height, width) are expected",
+                        self.num_channels
+                    )));
+                }
+                Ok(prepare_image(&image.to_device(self.device), self.image_size))
+            })
+            .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+        Ok(Tensor::stack(&pixel_values, 0))
+    }
+
+    /// Computes the normalized embeddings of texts
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Texts to embed
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*num texts*, *projection_size*)
+    pub fn encode_texts_as_tensor<S>(&self, texts: &[S]) -> Result<Tensor, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let input_ids = self.tokenize(texts)?;
+        let embeddings = no_grad(|| self.model.get_text_features(&input_ids, None));
+        Ok(normalize(&embeddings))
+    }
+
+    /// Computes the normalized embeddings of images
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Image tensors of shape (*num channels*, *height*, *width*) with values in [0, 255]
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*num images*, *hidden_size*)
+    pub fn encode_images_as_tensor(&self, images: &[Tensor]) -> Result<Tensor, RustBertError> {
+        let pixel_values = self.prepare_images(images)?;
+        let embeddings = no_grad(|| self.model.get_image_features(&pixel_values));
+        Ok(normalize(&embeddings))
+    }
+
+    /// Computes the normalized embeddings of texts
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Texts to embed
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Embedding>` containing the embedding of each text
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::multimodal_embeddings::MultimodalEmbeddingsModel;
+    ///
+    /// let embeddings_model = MultimodalEmbeddingsModel::new(Default::default())?;
+    /// let embeddings = embeddings_model.encode_texts(&["a photo of a cat"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_texts<S>(&self, texts: &[S]) -> Result<Vec<Embedding>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        Ok(Vec::try_from(self.encode_texts_as_tensor(texts)?)?)
+    }
+
+    /// Computes the normalized embeddings of images
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Image tensors of shape (*num channels*, *height*, *width*) with values in [0, 255]
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Embedding>` containing the embedding of each image
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::multimodal_embeddings::MultimodalEmbeddingsModel;
+    ///
+    /// let embeddings_model = MultimodalEmbeddingsModel::new(Default::default())?;
+    /// let image = tch::vision::image::load("path/to/image.jpg")?;
+    /// let embeddings = embeddings_model.encode_images(&[image])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_images(&self, images: &[Tensor]) -> Result<Vec<Embedding>, RustBertError> {
+        Ok(Vec::try_from(self.encode_images_as_tensor(images)?)?)
+    }
+}
+
+fn normalize(embeddings: &Tensor) -> Tensor {
+    embeddings / embeddings.norm_scalaropt_dim(2, [-1], true)
+}
@@ -77,6 +77,22 @@
 //! # ;
 //! ```
 //!
+//! Long documents (e.g. contracts or clinical notes) can be processed with `predict_long_documents`: the documents
+//! are split in chunks fitting the model along the detected section boundaries (headings, paragraph and line breaks,
+//! sentence ends) rather than at arbitrary token positions. Consecutive chunks overlap, and the entities predicted
+//! in the overlapping regions are merged, keeping the longest entity so that an entity cut at the end of a chunk is
+//! recovered from the next chunk.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::ner::{LongDocumentConfig, NERModel};
+//! # fn main() -> anyhow::Result<()> {
+//! let ner_model = NERModel::new(Default::default())?;
+//! # let contract = "";
+//! let output = ner_model.predict_long_documents(&[contract], &LongDocumentConfig::default());
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! To run the pipeline for another language, change the NERModel configuration from its default:
 //!
 //! ```no_run
@@ -132,7 +148,8 @@ use crate::pipelines::common::TokenizerOption;
 use crate::pipelines::token_classification::{
     Token, TokenClassificationConfig, TokenClassificationModel,
 };
-use rust_tokenizers::Offset;
+use regex::Regex;
+use rust_tokenizers::{Mask, Offset};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +165,30 @@ pub struct Entity {
     pub offset: Offset,
 }
 
+/// # Configuration of the chunking of long documents
+/// Long documents are split in chunks of at most `max_chunk_tokens` tokens. Each chunk ends at the strongest
+/// boundary (section heading, paragraph break, line break, sentence end, word) found after `min_chunk_fraction`
+/// of the maximum chunk length, and starts `overlap_tokens` tokens before the end of the previous chunk.
+#[derive(Debug, Clone)]
+pub struct LongDocumentConfig {
+    /// Maximum number of tokens of a chunk. Defaults to the maximum input length of the model if `None`.
+    pub max_chunk_tokens: Option<usize>,
+    /// Number of tokens shared by consecutive chunks (default: 32)
+    pub overlap_tokens: usize,
+    /// Minimum length of a chunk, as a fraction of the maximum chunk length (default: 0.5)
+    pub min_chunk_fraction: f64,
+}
+
+impl Default for LongDocumentConfig {
+    fn default() -> Self {
+        LongDocumentConfig {
+            max_chunk_tokens: None,
+            overlap_tokens: 32,
+            min_chunk_fraction: 0.5,
+        }
+    }
+}
+
 //type alias for some backward compatibility
 type NERConfig = TokenClassificationConfig;
 
@@ -334,6 +375,98 @@ impl NERModel {
         entities
     }
 
+    /// Extract full entities from long documents split in chunks along section boundaries (see `LongDocumentConfig`).
+    /// The entities of all the chunks are merged: among overlapping entities predicted by consecutive chunks, the
+    /// longest entity (and for identical spans, the entity with the highest score) is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of documents to extract entities from.
+    /// * `config` - `LongDocumentConfig` chunking settings
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` consolidated entities of each document, with offsets in the documents, sorted by position
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::{LongDocumentConfig, NERModel};
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["HISTORY OF PRESENT ILLNESS:\nSeen by Dr. John Smith at Boston General Hospital..."];
+    /// let config = LongDocumentConfig {
+    ///     overlap_tokens: 64,
+    ///     ..Default::default()
+    /// };
+    /// let output = ner_model.predict_long_documents(&input, &config);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_long_documents<S>(
+        &self,
+        input: &[S],
+        config: &LongDocumentConfig,
+    ) -> Vec<Vec<Entity>>
+    where
+        S: AsRef<str>,
+    {
+        let max_content_length = self.token_classification_model.max_content_length();
+        let max_tokens = config
+            .max_chunk_tokens
+            .map_or(max_content_length, |max_tokens| {
+                max_tokens.min(max_content_length)
+            })
+            .max(1);
+        let min_tokens = ((max_tokens as f64 * config.min_chunk_fraction) as usize).max(1);
+        // Overlaps at least as long as the shortest chunks would prevent the chunking from progressing
+        let overlap_tokens = config.overlap_tokens.min(min_tokens - 1);
+        let heading_pattern = heading_pattern();
+
+        let mut chunks = Vec::new();
+        for (document_index, document) in input.iter().enumerate() {
+            let tokens = self
+                .get_tokenizer()
+                .tokenize_with_offsets(document.as_ref());
+            let characters = document.as_ref().chars().collect::<Vec<char>>();
+            let boundaries = boundary_strengths(
+                &characters,
+                &tokens.offsets,
+                &tokens.masks,
+                &heading_pattern,
+            );
+            for (begin, end) in chunk_ranges(
+                &tokens.offsets,
+                &boundaries,
+                max_tokens,
+                min_tokens,
+                overlap_tokens,
+            ) {
+                let text = characters[begin as usize..end as usize]
+                    .iter()
+                    .collect::<String>();
+                chunks.push((document_index, begin, text));
+            }
+        }
+
+        let chunk_texts = chunks
+            .iter()
+            .map(|(_, _, text)| text.as_str())
+            .collect::<Vec<&str>>();
+        let mut entities = vec![Vec::new(); input.len()];
+        for ((document_index, begin, _), chunk_entities) in
+            chunks.iter().zip(self.predict_full_entities(&chunk_texts))
+        {
+            entities[*document_index].extend(chunk_entities.into_iter().map(|mut entity| {
+                entity.offset.begin += begin;
+                entity.offset.end += begin;
+                entity
+            }));
+        }
+        entities.into_iter().map(merge_chunk_entities).collect()
+    }
+
     fn consolidate_entities(tokens: &[Token]) -> Vec<Entity> {
         let mut entities: Vec<Entity> = Vec::new();
 
@@ -352,6 +485,169 @@ impl NERModel {
     }
 }
 
+/// Boundary strength before a token, used to place the ends of the chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Boundary {
+    /// Inside a word: never used as a chunk end unless no other boundary is available
+    None,
+    Word,
+    Sentence,
+    Line,
+    Paragraph,
+    Section,
+}
+
+/// Pattern of the numbered section headings (e.g. `1.`, `2.3`, `IV.`, `§ 4`, `Article 7`, `Section 2`)
+fn heading_pattern() -> Regex {
+    Regex::new(
+        r"(?i)^(?:\d+(?:\.\d+)*\.?|[ivxlc]+\.|[a-z]\.|§+ *\d+|(?:article|section|chapter|part|schedule|annex|exhibit)\b)",
+    )
+    .unwrap()
+}
+
+/// Returns true if a line is a section heading: a short line in capitals (e.g. `ASSESSMENT AND PLAN`), terminated
+/// by a colon, or starting with a numbering. A short title in capitals followed by a colon and some text
+/// (e.g. `HISTORY OF PRESENT ILLNESS: ...`) is also a heading.
+fn is_section_heading(line: &str, heading_pattern: &Regex) -> bool {
+    let line = line.trim();
+    let (title, colon_terminated) = match line.find(':') {
+        Some(position) => (&line[..position], line[position + 1..].trim().is_empty()),
+        None => (line, false),
+    };
+    let num_words = title.split_whitespace().count();
+    if num_words == 0 || num_words > 8 {
+        return false;
+    }
+    let letters = title
+        .chars()
+        .filter(|character| character.is_alphabetic())
+        .collect::<Vec<char>>();
+    let capitalized =
+        letters.len() >= 3 && letters.iter().all(|character| character.is_uppercase());
+    colon_terminated || capitalized || (title == line && heading_pattern.is_match(title))
+}
+
+/// Strength of the boundary before each token, depending on the text separating the token from the previous one
+fn boundary_strengths(
+    characters: &[char],
+    offsets: &[Option<Offset>],
+    masks: &[Mask],
+    heading_pattern: &Regex,
+) -> Vec<Boundary> {
+    let mut previous_end = None;
+    offsets
+        .iter()
+        .zip(masks)
+        .map(|(offset, mask)| {
+            let offset = match offset {
+                Some(offset) => offset,
+                None => return Boundary::None,
+            };
+            let boundary = match previous_end {
+                None => Boundary::Section,
+                Some(_) if *mask == Mask::Continuation => Boundary::None,
+                Some(previous_end) => {
+                    let (begin, previous_end) = (offset.begin as usize, previous_end as usize);
+                    let separator = &characters[previous_end.min(begin)..begin];
+                    let line_breaks = separator
+                        .iter()
+                        .filter(|&&character| character == '\n')
+                        .count();
+                    if line_breaks > 0 {
+                        let line = characters[begin..]
+                            .iter()
+                            .take_while(|&&character| character != '\n')
+                            .collect::<String>();
+                        if is_section_heading(&line, heading_pattern) {
+                            Boundary::Section
+                        } else if line_breaks > 1 {
+                            Boundary::Paragraph
+                        } else {
+                            Boundary::Line
+                        }
+                    } else if separator.is_empty() {
+                        Boundary::None
+                    } else if previous_end > 0
+                        && matches!(characters[previous_end - 1], '.' | '!' | '?' | ';')
+                    {
+                        Boundary::Sentence
+                    } else {
+                        Boundary::Word
+                    }
+                }
+            };
+            previous_end = Some(offset.end);
+            boundary
+        })
+        .collect()
+}
+
+/// Splits a tokenized document in overlapping chunks of at most `max_tokens` tokens, returning the character
+/// ranges of the chunks. A chunk ends before the strongest (and then latest) boundary between `min_tokens` and
+/// `max_tokens`, and the next chunk starts `overlap_tokens` tokens earlier, at the beginning of a word.
+fn chunk_ranges(
+    offsets: &[Option<Offset>],
+    boundaries: &[Boundary],
+    max_tokens: usize,
+    min_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<(u32, u32)> {
+    let num_tokens = offsets.len();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < num_tokens {
+        let end = if start + max_tokens >= num_tokens {
+            num_tokens
+        } else {
+            (start + min_tokens..=start + max_tokens)
+                .max_by_key(|&position| (boundaries[position], position))
+                .unwrap_or(start + max_tokens)
+        };
+        let begin_offset = offsets[start..end].iter().flatten().next();
+        let end_offset = offsets[start..end].iter().flatten().last();
+        if let (Some(begin_offset), Some(end_offset)) = (begin_offset, end_offset) {
+            chunks.push((begin_offset.begin, end_offset.end));
+        }
+        if end == num_tokens {
+            break;
+        }
+        let mut next_start = end.saturating_sub(overlap_tokens).max(start + 1);
+        while next_start > start + 1 && boundaries[next_start] == Boundary::None {
+            next_start -= 1;
+        }
+        start = next_start;
+    }
+    chunks
+}
+
+/// Merges the entities predicted by overlapping chunks, keeping the longest entity (then the entity with the
+/// highest score) among overlapping entities
+fn merge_chunk_entities(mut entities: Vec<Entity>) -> Vec<Entity> {
+    entities.sort_by(|a, b| {
+        a.offset
+            .begin
+            .cmp(&b.offset.begin)
+            .then((b.offset.end - b.offset.begin).cmp(&(a.offset.end - a.offset.begin)))
+            .then(b.score.total_cmp(&a.score))
+    });
+    let mut merged: Vec<Entity> = Vec::with_capacity(entities.len());
+    for entity in entities {
+        match merged.last_mut() {
+            Some(previous) if entity.offset.begin < previous.offset.end => {
+                let (length, previous_length) = (
+                    entity.offset.end - entity.offset.begin,
+                    previous.offset.end - previous.offset.begin,
+                );
+                if length > previous_length {
+                    *previous = entity;
+                }
+            }
+            _ => merged.push(entity),
+        }
+    }
+    merged
+}
+
 struct EntityBuilder<'a> {
     previous_node: Option<(usize, Tag, &'a str)>,
 }
@@ -466,4 +762,77 @@ mod test {
         let config = NERConfig::default();
         let _: Box<dyn Send> = Box::new(NERModel::new(config));
     }
+
+    /// Whitespace tokenization of a text, returning the offsets and masks of the tokens
+    fn whitespace_tokens(text: &str) -> (Vec<Option<Offset>>, Vec<Mask>) {
+        let mut offsets = Vec::new();
+        let mut begin = None;
+        for (position, character) in text.chars().chain([' ']).enumerate() {
+            match (character.is_whitespace(), begin) {
+                (false, None) => begin = Some(position as u32),
+                (true, Some(token_begin)) => {
+                    offsets.push(Some(Offset {
+                        begin: token_begin,
+                        end: position as u32,
+                    }));
+                    begin = None;
+                }
+                _ => {}
+            }
+        }
+        let masks = vec![Mask::None; offsets.len()];
+        (offsets, masks)
+    }
+
+    #[test]
+    fn section_aware_chunking() {
+        let text = "1. Parties\nThis agreement is made by Acme Corporation and John Smith.\nThe seller agrees to deliver the goods.\n\nTERMINATION\nEither party may terminate this agreement";
+        let characters = text.chars().collect::<Vec<char>>();
+        let (offsets, masks) = whitespace_tokens(text);
+        let boundaries = boundary_strengths(&characters, &offsets, &masks, &heading_pattern());
+        assert_eq!(boundaries[0], Boundary::Section);
+        assert_eq!(boundaries[2], Boundary::Line);
+        assert_eq!(boundaries[9], Boundary::Word);
+        assert_eq!(boundaries[12], Boundary::Line);
+        assert_eq!(boundaries[19], Boundary::Section);
+
+        let chunks = chunk_ranges(&offsets, &boundaries, 16, 8, 3)
+            .into_iter()
+            .map(|(begin, end)| {
+                characters[begin as usize..end as usize]
+                    .iter()
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            chunks,
+            vec![
+                "1. Parties\nThis agreement is made by Acme Corporation and John Smith.",
+                "and John Smith.\nThe seller agrees to deliver the goods.",
+                "deliver the goods.\n\nTERMINATION\nEither party may terminate this agreement",
+            ]
+        );
+    }
+
+    #[test]
+    fn chunk_entities_merging() {
+        let entity = |word: &str, begin: u32, score: f64| Entity {
+            word: word.to_string(),
+            score,
+            label: "PER".to_string(),
+            offset: Offset {
+                begin,
+                end: begin + word.len() as u32,
+            },
+        };
+        let merged = merge_chunk_entities(vec![
+            entity("John", 10, 0.9),
+            entity("Acme", 0, 0.8),
+            entity("John Smith", 10, 0.7),
+            entity("Acme", 0, 0.9),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].score, 0.9);
+        assert_eq!(merged[1].word, "John Smith");
+    }
 }
@@ -0,0 +1,427 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Natural language inference pipeline
+//! Predicts whether a hypothesis is entailed by, neutral to, or contradicted by a premise, e.g. for fact-checking
+//! or consistency checks. The label names are read from the model configuration (`ENTAILMENT`, `NEUTRAL` and
+//! `CONTRADICTION` for models trained on MNLI). Binary models (`ENTAILMENT` and `NOT_ENTAILMENT`) are also supported, the
+//! probability of non-entailment being reported as neutral.
+//! By default, the dependencies for this model will be downloaded for a BART model fine-tuned on MNLI.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::nli::{NliInput, NliModel};
+//! # fn main() -> anyhow::Result<()> {
+//! let nli_model = NliModel::new(Default::default())?;
+//!
+//! let input = [
+//!     NliInput::new("A man is playing a guitar on stage.", "A man is performing music."),
+//!     NliInput::new("A man is playing a guitar on stage.", "The stage is empty."),
+//! ];
+//! let output = nli_model.predict(&input, 16)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::nli::{NliLabel, NliPrediction};
+//! let output = [
+//!     NliPrediction {
+//!         label: NliLabel::Entailment,
+//!         entailment: 0.9746,
+//!         neutral: 0.0236,
+//!         contradiction: 0.0018,
+//!     },
+//!     NliPrediction {
+//!         label: NliLabel::Contradiction,
+//!         entailment: 0.0004,
+//!         neutral: 0.0015,
+//!         contradiction: 0.9981,
+//!     },
+//! ]
+//! # ;
+//! ```
+
+use crate::common::error::{InputError, RustBertError};
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenTypeStrategy, TokenizerOption};
+use crate::pipelines::sequence_classification::{
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
+use tch::Device;
+
+#[cfg(all(feature = "remote", feature = "bart"))]
+use crate::{
+    bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources},
+    resources::RemoteResource,
+};
+
+/// # Premise and hypothesis pair
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NliInput {
+    /// Premise (e.g. source document or evidence)
+    pub premise: String,
+    /// Hypothesis (e.g. claim to verify)
+    pub hypothesis: String,
+}
+
+impl NliInput {
+    /// Creates a new premise and hypothesis pair
+    pub fn new<P: Into<String>, H: Into<String>>(premise: P, hypothesis: H) -> NliInput {
+        NliInput {
+            premise: premise.into(),
+            hypothesis: hypothesis.into(),
+        }
+    }
+}
+
+/// # Relation between a premise and a hypothesis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NliLabel {
+    /// The hypothesis follows from the premise
+    Entailment,
+    /// The hypothesis is neither entailed nor contradicted by the premise
+    Neutral,
+    /// The hypothesis contradicts the premise
+    Contradiction,
+}
+
+/// # Natural language inference prediction
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NliPrediction {
+    /// Most likely relation
+    pub label: NliLabel,
+    /// Probability of entailment
+    pub entailment: f64,
+    /// Probability of a neutral relation (probability of non-entailment for binary models)
+    pub neutral: f64,
+    /// Probability of contradiction (0 for models without a contradiction label)
+    pub contradiction: f64,
+}
+
+/// # Configuration for NliModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct NliConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: pretrained BART model fine-tuned on MNLI)
+    pub model_resource: ModelResource,
+    /// Config resource (default: pretrained BART model fine-tuned on MNLI)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: pretrained BART model fine-tuned on MNLI)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (default: pretrained BART model fine-tuned on MNLI)
+    pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+    /// Token type ids passed to the model for the sentence pairs (default: depending on the architecture)
+    pub token_type_strategy: TokenTypeStrategy,
+}
+
+impl NliConfig {
+    /// Instantiate a new natural language inference configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RC, RV>(
+        model_type: ModelType,
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: Option<RV>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> NliConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        NliConfig {
+            model_type,
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: merges_resource.map(|r| Box::new(r) as Box<_>),
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            device: default_device(),
+            token_type_strategy: TokenTypeStrategy::default(),
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bart"))]
+impl Default for NliConfig {
+    /// Provides a default BART model fine-tuned on MNLI (English)
+    fn default() -> NliConfig {
+        NliConfig::new(
+            ModelType::Bart,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                BartModelResources::BART_MNLI,
+            ))),
+            RemoteResource::from_pretrained(BartConfigResources::BART_MNLI),
+            RemoteResource::from_pretrained(BartVocabResources::BART_MNLI),
+            Some(RemoteResource::from_pretrained(
+                BartMergesResources::BART_MNLI,
+            )),
+            false,
+            None,
+            None,
+        )
+    }
+}
+
+impl From<NliConfig> for SequenceClassificationConfig {
+    fn from(config: NliConfig) -> Self {
+        SequenceClassificationConfig {
+            model_type: config.model_type,
+            model_resource: config.model_resource,
+            config_resource: config.config_resource,
+            vocab_resource: config.vocab_resource,
+            merges_resource: config.merges_resource,
+            lower_case: config.lower_case,
+            strip_accents: config.strip_accents,
+            add_prefix_space: config.add_prefix_space,
+            device: config.device,
+            kind: None,
+            token_type_strategy: config.token_type_strategy,
+        }
+    }
+}
+
+/// Finds the id of a label of a NLI model from the beginning of its name (e.g. "entail" for `ENTAILMENT`)
+pub(crate) fn find_label_id(model: &SequenceClassificationModel, name: &str) -> Option<i64> {
+    model
+        .get_label_mapping()
+        .iter()
+        .find(|(_, label)| label.to_lowercase().starts_with(name))
+        .map(|(id, _)| *id)
+}
+
+/// # NliModel for natural language inference
+pub struct NliModel {
+    sequence_classification_model: SequenceClassificationModel,
+    entailment_id: i64,
+    neutral_id: Option<i64>,
+    contradiction_id: Option<i64>,
+}
+
+impl NliModel {
+    /// Build a new `NliModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `nli_config` - `NliConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::nli::NliModel;
+    ///
+    /// let nli_model = NliModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(nli_config: NliConfig) -> Result<NliModel, RustBertError> {
+        let sequence_classification_model = SequenceClassificationModel::new(nli_config.into())?;
+        NliModel::from_sequence_classification_model(sequence_classification_model)
+    }
+
+    /// Build a new `NliModel` with a provided tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `nli_config` - `NliConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer to use for natural language inference.
+    pub fn new_with_tokenizer(
+        nli_config: NliConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<NliModel, RustBertError> {
+        let sequence_classification_model =
+            SequenceClassificationModel::new_with_tokenizer(nli_config.into(), tokenizer)?;
+        NliModel::from_sequence_classification_model(sequence_classification_model)
+    }
+
+    fn from_sequence_classification_model(
+        sequence_classification_model: SequenceClassificationModel,
+    ) -> Result<NliModel, RustBertError> {
+        let entailment_id =
+            find_label_id(&sequence_classification_model, "entail").ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "The model configuration must define an entailment label (e.g. `ENTAILMENT`)"
+                        .to_string(),
+                )
+            })?;
+        // Binary models predict the probability of non-entailment (`NOT_ENTAILMENT`), reported as neutral
+        let neutral_id = find_label_id(&sequence_classification_model, "neutral")
+            .or_else(|| find_label_id(&sequence_classification_model, "not"))
+            .or_else(|| find_label_id(&sequence_classification_model, "non"));
+        let contradiction_id = find_label_id(&sequence_classification_model, "contradict");
+        Ok(NliModel {
+            sequence_classification_model,
+            entailment_id,
+            neutral_id,
+            contradiction_id,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.sequence_classification_model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.sequence_classification_model.get_tokenizer_mut()
+    }
+
+    /// Predicts the relation between premises and hypotheses
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - `&[NliInput]` Array of premise and hypothesis pairs
+    /// * `batch_size` - Maximum number of pairs scored in a batch
+    ///
+    /// # Returns
+    /// * `Vec<NliPrediction>` Prediction for each pair, in the input order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::nli::{NliInput, NliLabel, NliModel};
+    ///
+    /// let nli_model = NliModel::new(Default::default())?;
+    /// let output = nli_model.predict(
+    ///     &[NliInput::new(
+    ///         "The company reported a loss of $3 million.",
+    ///         "The company was profitable.",
+    ///     )],
+    ///     16,
+    /// )?;
+    /// let is_contradiction = output[0].label == NliLabel::Contradiction;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict(
+        &self,
+        inputs: &[NliInput],
+        batch_size: usize,
+    ) -> Result<Vec<NliPrediction>, RustBertError> {
+        let pairs = inputs
+            .iter()
+            .map(|input| (input.premise.as_str(), input.hypothesis.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        self.predict_pairs(&pairs, batch_size)
+    }
+
+    /// Predicts the relation between (premise, hypothesis) pairs
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - `&[(&str, &str)]` Array of (premise, hypothesis) pairs
+    /// * `batch_size` - Maximum number of pairs scored in a batch
+    ///
+    /// # Returns
+    /// * `Vec<NliPrediction>` Prediction for each pair, in the input order
+    pub fn predict_pairs(
+        &self,
+        pairs: &[(&str, &str)],
+        batch_size: usize,
+    ) -> Result<Vec<NliPrediction>, RustBertError> {
+        if pairs.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        if batch_size == 0 {
+            return Err(RustBertError::ValueError(
+                "The batch size must be positive".to_string(),
+            ));
+        }
+        Ok(pairs
+            .chunks(batch_size)
+            .flat_map(|batch| {
+                self.sequence_classification_model
+                    .predict_pairs_scores(batch)
+            })
+            .map(|labels| self.to_prediction(&labels))
+            .collect())
+    }
+
+    /// Probability of entailment of each hypothesis by its premise, e.g. to rank evidence
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - `&[(&str, &str)]` Array of (premise, hypothesis) pairs
+    /// * `batch_size` - Maximum number of pairs scored in a batch
+    pub fn entailment_scores(
+        &self,
+        pairs: &[(&str, &str)],
+        batch_size: usize,
+    ) -> Result<Vec<f64>, RustBertError> {
+        Ok(self
+            .predict_pairs(pairs, batch_size)?
+            .into_iter()
+            .map(|prediction| prediction.entailment)
+            .collect())
+    }
+
+    fn to_prediction(&self, labels: &[Label]) -> NliPrediction {
+        let score = |id: Option<i64>| {
+            id.and_then(|id| labels.iter().find(|label| label.id == id))
+                .map_or(0f64, |label| label.score)
+        };
+        let entailment = score(Some(self.entailment_id));
+        let neutral = score(self.neutral_id);
+        let contradiction = score(self.contradiction_id);
+        let label = if entailment >= neutral && entailment >= contradiction {
+            NliLabel::Entailment
+        } else if contradiction >= neutral {
+            NliLabel::Contradiction
+        } else {
+            NliLabel::Neutral
+        };
+        NliPrediction {
+            label,
+            entailment,
+            neutral,
+            contradiction,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "remote", feature = "bart"))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = NliConfig::default();
+        let _: Box<dyn Send> = Box::new(NliModel::new(config));
+    }
+}
@@ -113,6 +113,10 @@ impl ONNXDecoder {
             Cache::None
         };
 
-        Ok(LMModelOutput { lm_logits, cache })
+        Ok(LMModelOutput {
+            lm_logits,
+            cache,
+            last_hidden_state: None,
+        })
     }
 }
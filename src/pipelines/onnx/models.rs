@@ -1,3 +1,4 @@
+use crate::common::mps;
 use crate::pipelines::common::{ConfigOption, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
     PreparedInput, PrivateLanguageGenerator,
@@ -502,7 +503,7 @@ impl PrivateLanguageGenerator for ONNXCausalGenerator {
         past: Cache,
         attention_mask: Tensor,
     ) -> PreparedInput<'a> {
-        let position_ids = (attention_mask.totype(Kind::Int64).cumsum(-1, Kind::Int64) - 1)
+        let position_ids = (mps::cumsum(&attention_mask.totype(Kind::Int64), -1, Kind::Int64) - 1)
             .masked_fill(&attention_mask.eq(0), 1);
 
         match (past, self.use_past) {
@@ -0,0 +1,514 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # PII detection and redaction pipeline
+//! Detects personally identifiable information (PII) in texts and returns the redacted texts with the
+//! metadata of the detected spans. Two kinds of detectors are combined:
+//! - a named entity recognition model, detecting person names, organizations and locations
+//! - regular expressions, detecting emails, phone numbers, credit card numbers (Luhn-validated), IBANs,
+//! US social security numbers and IP addresses. Custom patterns can be added with `RegexDetector`.
+//!
+//! Overlapping detections are merged, keeping the longest span.
+//! By default, the NER dependencies are downloaded for the default NER pipeline (BERT cased large model finetuned on CoNNL03).
+//!
+//! ```no_run
+//! use rust_bert::pipelines::pii::PiiRedactionModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let pii_model = PiiRedactionModel::new(Default::default())?;
+//!
+//! let input = ["My name is Amy, contact me at amy@example.com or +1 415 555 0132."];
+//! let output = pii_model.redact(&input);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # let output =
+//! "My name is [PERSON], contact me at [EMAIL] or [PHONE_NUMBER]."
+//! # ;
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::ner::NERModel;
+use crate::pipelines::token_classification::TokenClassificationConfig;
+use regex::Regex;
+use rust_tokenizers::Offset;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// # Type of personally identifiable information
+pub enum PiiEntityType {
+    /// Person name
+    Person,
+    /// Organization name
+    Organization,
+    /// Location (address, city...)
+    Location,
+    /// Email address
+    Email,
+    /// Phone number
+    PhoneNumber,
+    /// Credit card number
+    CreditCard,
+    /// International bank account number
+    Iban,
+    /// US social security number
+    Ssn,
+    /// IPv4 address
+    IpAddress,
+    /// Custom entity type (e.g. detected with a custom regular expression)
+    Custom(String),
+}
+
+impl fmt::Display for PiiEntityType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PiiEntityType::Person => "PERSON",
+            PiiEntityType::Organization => "ORGANIZATION",
+            PiiEntityType::Location => "LOCATION",
+            PiiEntityType::Email => "EMAIL",
+            PiiEntityType::PhoneNumber => "PHONE_NUMBER",
+            PiiEntityType::CreditCard => "CREDIT_CARD",
+            PiiEntityType::Iban => "IBAN",
+            PiiEntityType::Ssn => "SSN",
+            PiiEntityType::IpAddress => "IP_ADDRESS",
+            PiiEntityType::Custom(name) => name,
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Detector having found a PII span
+pub enum PiiSource {
+    /// Named entity recognition model
+    Ner,
+    /// Regular expression
+    Regex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # PII span detected in a text
+pub struct PiiSpan {
+    /// Type of the information detected
+    pub entity_type: PiiEntityType,
+    /// Original text of the span
+    pub text: String,
+    /// Character offsets of the span in the original text
+    pub offset: Offset,
+    /// Confidence score (1.0 for regular expressions)
+    pub score: f64,
+    /// Detector having found the span
+    pub source: PiiSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Output of the PII redaction pipeline
+pub struct PiiRedactionOutput {
+    /// Redacted text
+    pub text: String,
+    /// PII spans detected in the original text, sorted by position
+    pub spans: Vec<PiiSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// # Replacement of the PII spans in the redacted text
+pub enum RedactionStrategy {
+    /// Replaces the span by its entity type, e.g. `[EMAIL]`
+    EntityType,
+    /// Replaces every character of the span by a masking character, preserving the text length
+    Mask(char),
+    /// Replaces the span by a fixed text
+    Fixed(String),
+}
+
+/// # Regular expression PII detector
+#[derive(Debug, Clone)]
+pub struct RegexDetector {
+    entity_type: PiiEntityType,
+    pattern: Regex,
+    validator: Option<fn(&str) -> bool>,
+}
+
+impl RegexDetector {
+    /// Build a new `RegexDetector`
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_type` - `PiiEntityType` of the spans detected
+    /// * `pattern` - Regular expression matching the spans
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::pii::{PiiEntityType, RegexDetector};
+    ///
+    /// let detector = RegexDetector::new(
+    ///     PiiEntityType::Custom("EMPLOYEE_ID".to_string()),
+    ///     r"\bEMP-\d{6}\b",
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(entity_type: PiiEntityType, pattern: &str) -> Result<RegexDetector, RustBertError> {
+        let pattern = Regex::new(pattern).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!("Invalid PII pattern: {error}"))
+        })?;
+        Ok(RegexDetector {
+            entity_type,
+            pattern,
+            validator: None,
+        })
+    }
+
+    /// Adds a validation function filtering the matches of the regular expression (e.g. checksum verification)
+    pub fn with_validator(mut self, validator: fn(&str) -> bool) -> RegexDetector {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Returns the default detectors for emails, phone numbers, credit card numbers, IBANs, US social security numbers and IPv4 addresses
+    pub fn default_detectors() -> Vec<RegexDetector> {
+        vec![
+            RegexDetector::new(
+                PiiEntityType::Email,
+                r"(?i)\b[a-z0-9._%+\-]+@[a-z0-9.\-]+\.[a-z]{2,}\b",
+            )
+            .unwrap(),
+            RegexDetector::new(PiiEntityType::Iban, r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b")
+                .unwrap(),
+            RegexDetector::new(PiiEntityType::CreditCard, r"\b(?:\d[ \-]?){12,18}\d\b")
+                .unwrap()
+                .with_validator(luhn_check),
+            RegexDetector::new(PiiEntityType::Ssn, r"\b\d{3}-\d{2}-\d{4}\b").unwrap(),
+            RegexDetector::new(
+                PiiEntityType::IpAddress,
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+            )
+            .unwrap(),
+            RegexDetector::new(
+                PiiEntityType::PhoneNumber,
+                r"(?:\+\d{1,3}[ .\-]?)?(?:\(\d{1,4}\)[ .\-]?)?\b\d{2,4}(?:[ .\-]\d{2,4}){2,4}\b",
+            )
+            .unwrap()
+            .with_validator(phone_number_check),
+        ]
+    }
+
+    /// Returns the `PiiEntityType` of the spans detected
+    pub fn entity_type(&self) -> &PiiEntityType {
+        &self.entity_type
+    }
+
+    /// Detects the spans matching the regular expression (and validation function, if any) in a text
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - text to process
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<PiiSpan>` detected spans, with character offsets in the text
+    pub fn detect(&self, text: &str) -> Vec<PiiSpan> {
+        self.pattern
+            .find_iter(text)
+            .filter(|found| match self.validator {
+                Some(validator) => validator(found.as_str()),
+                None => true,
+            })
+            .map(|found| PiiSpan {
+                entity_type: self.entity_type.clone(),
+                text: found.as_str().to_string(),
+                offset: Offset {
+                    begin: text[..found.start()].chars().count() as u32,
+                    end: text[..found.end()].chars().count() as u32,
+                },
+                score: 1.0,
+                source: PiiSource::Regex,
+            })
+            .collect()
+    }
+}
+
+fn phone_number_check(number: &str) -> bool {
+    // Excludes sequences with too few or too many digits, and ISO dates (e.g. 2023-06-03)
+    let num_digits = number.chars().filter(char::is_ascii_digit).count();
+    let is_date = number.len() == 10
+        && number
+            .char_indices()
+            .all(|(position, character)| match position {
+                4 | 7 => character == '-',
+                _ => character.is_ascii_digit(),
+            });
+    (7..=15).contains(&num_digits) && !is_date
+}
+
+fn luhn_check(number: &str) -> bool {
+    let digits = number
+        .chars()
+        .filter_map(|character| character.to_digit(10))
+        .collect::<Vec<u32>>();
+    if digits.len() < 13 {
+        return false;
+    }
+    let checksum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(position, &digit)| {
+            if position % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    checksum % 10 == 0
+}
+
+/// # Configuration for PiiRedactionModel
+/// Contains the configuration of the NER model (if any), the regular expression detectors and the redaction strategy.
+pub struct PiiRedactionConfig {
+    /// Configuration of the NER model used to detect names, organizations and locations. No NER model is used if `None`
+    pub ner_config: Option<TokenClassificationConfig>,
+    /// Regular expression detectors (default: `RegexDetector::default_detectors()`)
+    pub regex_detectors: Vec<RegexDetector>,
+    /// Entity types of the NER model to redact (default: person, organization and location)
+    pub ner_entity_types: Vec<PiiEntityType>,
+    /// Minimum score of the NER entities to redact (default: 0.5)
+    pub min_ner_score: f64,
+    /// Redaction strategy (default: `EntityType`)
+    pub redaction_strategy: RedactionStrategy,
+}
+
+impl PiiRedactionConfig {
+    /// Instantiate a new PII redaction configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `ner_config` - Optional `TokenClassificationConfig` for the NER model (only regular expressions are used if `None`)
+    /// * `regex_detectors` - `Vec<RegexDetector>` regular expression detectors
+    pub fn new(
+        ner_config: Option<TokenClassificationConfig>,
+        regex_detectors: Vec<RegexDetector>,
+    ) -> PiiRedactionConfig {
+        PiiRedactionConfig {
+            ner_config,
+            regex_detectors,
+            ner_entity_types: vec![
+                PiiEntityType::Person,
+                PiiEntityType::Organization,
+                PiiEntityType::Location,
+            ],
+            min_ner_score: 0.5,
+            redaction_strategy: RedactionStrategy::EntityType,
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+impl Default for PiiRedactionConfig {
+    /// Provides the default NER model combined with the default regular expression detectors
+    fn default() -> PiiRedactionConfig {
+        PiiRedactionConfig::new(
+            Some(TokenClassificationConfig::default()),
+            RegexDetector::default_detectors(),
+        )
+    }
+}
+
+/// # PiiRedactionModel to detect and redact personally identifiable information
+pub struct PiiRedactionModel {
+    ner_model: Option<NERModel>,
+    regex_detectors: Vec<RegexDetector>,
+    ner_entity_types: Vec<PiiEntityType>,
+    min_ner_score: f64,
+    redaction_strategy: RedactionStrategy,
+}
+
+impl PiiRedactionModel {
+    /// Build a new `PiiRedactionModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `pii_config` - `PiiRedactionConfig` object containing the NER model configuration, detectors and redaction strategy
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::pii::PiiRedactionModel;
+    ///
+    /// let pii_model = PiiRedactionModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(pii_config: PiiRedactionConfig) -> Result<PiiRedactionModel, RustBertError> {
+        let ner_model = pii_config.ner_config.map(NERModel::new).transpose()?;
+        Ok(PiiRedactionModel {
+            ner_model,
+            regex_detectors: pii_config.regex_detectors,
+            ner_entity_types: pii_config.ner_entity_types,
+            min_ner_score: pii_config.min_ner_score,
+            redaction_strategy: pii_config.redaction_strategy,
+        })
+    }
+
+    /// Detects the PII spans in texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to process.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<PiiSpan>>` non-overlapping PII spans for each input text, sorted by position
+    pub fn detect<S>(&self, input: &[S]) -> Vec<Vec<PiiSpan>>
+    where
+        S: AsRef<str>,
+    {
+        let mut spans = input
+            .iter()
+            .map(|text| {
+                self.regex_detectors
+                    .iter()
+                    .flat_map(|detector| detector.detect(text.as_ref()))
+                    .collect::<Vec<PiiSpan>>()
+            })
+            .collect::<Vec<Vec<PiiSpan>>>();
+
+        if let Some(ner_model) = &self.ner_model {
+            for (text_spans, entities) in
+                spans.iter_mut().zip(ner_model.predict_full_entities(input))
+            {
+                text_spans.extend(entities.into_iter().filter_map(|entity| {
+                    let entity_type = ner_entity_type(&entity.label)?;
+                    if self.ner_entity_types.contains(&entity_type)
+                        && entity.score >= self.min_ner_score
+                    {
+                        Some(PiiSpan {
+                            entity_type,
+                            text: entity.word,
+                            offset: entity.offset,
+                            score: entity.score,
+                            source: PiiSource::Ner,
+                        })
+                    } else {
+                        None
+                    }
+                }));
+            }
+        }
+
+        spans.into_iter().map(remove_overlapping_spans).collect()
+    }
+
+    /// Redacts the PII spans in texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to redact.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<PiiRedactionOutput>` redacted texts and detected spans
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::pii::PiiRedactionModel;
+    ///
+    /// let pii_model = PiiRedactionModel::new(Default::default())?;
+    /// let output = pii_model.redact(&["Contact me at amy@example.com"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn redact<S>(&self, input: &[S]) -> Vec<PiiRedactionOutput>
+    where
+        S: AsRef<str>,
+    {
+        input
+            .iter()
+            .zip(self.detect(input))
+            .map(|(text, spans)| PiiRedactionOutput {
+                text: self.redact_spans(text.as_ref(), &spans),
+                spans,
+            })
+            .collect()
+    }
+
+    fn redact_spans(&self, text: &str, spans: &[PiiSpan]) -> String {
+        let characters = text.chars().collect::<Vec<char>>();
+        let mut redacted = String::with_capacity(text.len());
+        let mut position = 0;
+        for span in spans {
+            let (begin, end) = (span.offset.begin as usize, span.offset.end as usize);
+            redacted.extend(&characters[position..begin]);
+            match &self.redaction_strategy {
+                RedactionStrategy::EntityType => {
+                    redacted.push_str(&format!("[{}]", span.entity_type))
+                }
+                RedactionStrategy::Mask(mask) => {
+                    redacted.push_str(&mask.to_string().repeat(end - begin))
+                }
+                RedactionStrategy::Fixed(replacement) => redacted.push_str(replacement),
+            }
+            position = end;
+        }
+        redacted.extend(&characters[position..]);
+        redacted
+    }
+}
+
+fn ner_entity_type(label: &str) -> Option<PiiEntityType> {
+    let label = label
+        .trim_start_matches("B-")
+        .trim_start_matches("I-")
+        .to_uppercase();
+    match label.as_str() {
+        "PER" | "PERSON" => Some(PiiEntityType::Person),
+        "ORG" | "ORGANIZATION" => Some(PiiEntityType::Organization),
+        "LOC" | "LOCATION" | "GPE" => Some(PiiEntityType::Location),
+        _ => None,
+    }
+}
+
+fn remove_overlapping_spans(mut spans: Vec<PiiSpan>) -> Vec<PiiSpan> {
+    // Keeps the longest span among overlapping spans
+    spans.sort_by(|a, b| {
+        a.offset
+            .begin
+            .cmp(&b.offset.begin)
+            .then((b.offset.end - b.offset.begin).cmp(&(a.offset.end - a.offset.begin)))
+    });
+    let mut output: Vec<PiiSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match output.last_mut() {
+            Some(previous) if span.offset.begin < previous.offset.end => {
+                if span.offset.end - span.offset.begin > previous.offset.end - previous.offset.begin
+                {
+                    *previous = span;
+                }
+            }
+            _ => output.push(span),
+        }
+    }
+    output
+}
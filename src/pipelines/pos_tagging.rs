@@ -83,22 +83,19 @@
 
 use crate::common::error::RustBertError;
 use crate::pipelines::token_classification::{TokenClassificationConfig, TokenClassificationModel};
+use rust_tokenizers::Offset;
 use serde::{Deserialize, Serialize};
 
 use crate::pipelines::common::TokenizerOption;
-#[cfg(feature = "remote")]
-use {
-    crate::{
-        mobilebert::{
-            MobileBertConfigResources, MobileBertModelResources, MobileBertVocabResources,
-        },
-        pipelines::{
-            common::{ModelResource, ModelType},
-            token_classification::LabelAggregationOption,
-        },
-        resources::RemoteResource,
+#[cfg(all(feature = "remote", feature = "mobilebert"))]
+use crate::{
+    common::settings::default_device,
+    mobilebert::{MobileBertConfigResources, MobileBertModelResources, MobileBertVocabResources},
+    pipelines::{
+        common::{ModelResource, ModelType},
+        token_classification::LabelAggregationOption,
     },
-    tch::Device,
+    resources::RemoteResource,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -117,7 +114,7 @@ pub struct POSConfig {
     token_classification_config: TokenClassificationConfig,
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "mobilebert"))]
 impl Default for POSConfig {
     /// Provides a Part of speech tagging model (English)
     fn default() -> POSConfig {
@@ -137,7 +134,8 @@ impl Default for POSConfig {
                 lower_case: true,
                 strip_accents: Some(true),
                 add_prefix_space: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
+                kind: None,
                 label_aggregation_function: LabelAggregationOption::First,
                 batch_size: 64,
             },
@@ -259,6 +257,25 @@ impl POSModel {
     /// # }
     /// ```
     pub fn predict<S>(&self, input: &[S]) -> Vec<Vec<POSTag>>
+    where
+        S: AsRef<str>,
+    {
+        self.predict_with_offsets(input)
+            .into_iter()
+            .map(|sequence_tags| sequence_tags.into_iter().map(|(tag, _)| tag).collect())
+            .collect::<Vec<Vec<POSTag>>>()
+    }
+
+    /// Extract Part of Speech tags from a text, with the character offsets of the words tagged
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to tag.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<(POSTag, Option<Offset>)>>` containing Part of Speech tags and word offsets for the inputs provided
+    pub fn predict_with_offsets<S>(&self, input: &[S]) -> Vec<Vec<(POSTag, Option<Offset>)>>
     where
         S: AsRef<str>,
     {
@@ -277,14 +294,19 @@ impl POSModel {
                         };
                         token
                     })
-                    .map(|token| POSTag {
-                        word: token.text,
-                        score: token.score,
-                        label: token.label,
+                    .map(|token| {
+                        (
+                            POSTag {
+                                word: token.text,
+                                score: token.score,
+                                label: token.label,
+                            },
+                            token.offset,
+                        )
                     })
-                    .collect::<Vec<POSTag>>()
+                    .collect::<Vec<(POSTag, Option<Offset>)>>()
             })
-            .collect::<Vec<Vec<POSTag>>>()
+            .collect::<Vec<Vec<(POSTag, Option<Offset>)>>>()
     }
 
     fn is_punctuation(string: &str) -> bool {
@@ -0,0 +1,346 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Prompt-based few-shot classification
+//! Classifies texts with a language model by scoring label words (verbalizers) filling a prompt pattern, in the
+//! style of Pattern-Exploiting Training (PET, [Schick and Schütze, 2021](https://arxiv.org/abs/2001.07676)).
+//! Labelled exemplars can be prepended to the prompt (in-context few-shot learning). This provides an alternative
+//! to the NLI-based zero-shot classification pipeline when a generative or masked language model is already loaded.
+//!
+//! The pattern is a prompt template with a `{text}` variable for the input text and a `{label}` variable for the
+//! label word. Two scoring modes are available:
+//! - with a text generation model (`PromptClassifier::classify_with_generator`), the label words are scored by their
+//!   log-likelihood as a continuation of the prompt up to the `{label}` variable. The pattern should end with the label.
+//! - with a masked language model (`PromptClassifier::classify_with_masked_lm`), the `{label}` variable is replaced
+//!   by a mask token and the label words are scored at this position. Each label word should be a single token.
+//!
+//! The probabilities of the label words of a label are summed, and normalized across labels.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::prompt_classification::{PromptClassifier, Verbalizer};
+//! use rust_bert::pipelines::prompts::PromptTemplate;
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//!
+//! let model = TextGenerationModel::new(Default::default())?;
+//! let classifier = PromptClassifier::new(
+//!     PromptTemplate::new("Review: {text}\nSentiment: {label}")?,
+//!     vec![
+//!         Verbalizer::new("positive", &["positive", "great"]),
+//!         Verbalizer::new("negative", &["negative", "terrible"]),
+//!     ],
+//! )?
+//! .with_example("I loved every minute of it.", "positive")?
+//! .with_example("A complete waste of time.", "negative")?;
+//!
+//! let output = classifier.classify_with_generator(&model, &["The plot was dull and predictable."])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::{InputError, RustBertError};
+use crate::pipelines::masked_language::MaskedLanguageModel;
+use crate::pipelines::prompts::PromptTemplate;
+use crate::pipelines::sequence_classification::Label;
+use crate::pipelines::text_generation::TextGenerationModel;
+
+const TEXT_VARIABLE: &str = "text";
+const LABEL_VARIABLE: &str = "label";
+
+/// # Label and the words representing it in the prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verbalizer {
+    /// Label returned by the classifier
+    pub label: String,
+    /// Words filling the prompt for this label. The first word is used to format the few-shot examples.
+    pub words: Vec<String>,
+}
+
+impl Verbalizer {
+    /// Build a new `Verbalizer`
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Label returned by the classifier
+    /// * `words` - Words filling the prompt for this label
+    pub fn new<L, W>(label: L, words: &[W]) -> Verbalizer
+    where
+        L: Into<String>,
+        W: AsRef<str>,
+    {
+        Verbalizer {
+            label: label.into(),
+            words: words.iter().map(|word| word.as_ref().to_string()).collect(),
+        }
+    }
+}
+
+/// # Prompt-based classifier
+/// Holds the pattern, verbalizers and few-shot examples. The classifier does not own a model: it scores the
+/// label words with the text generation or masked language model passed at classification time.
+#[derive(Debug, Clone)]
+pub struct PromptClassifier {
+    pattern: PromptTemplate,
+    verbalizers: Vec<Verbalizer>,
+    examples: Vec<String>,
+    separator: String,
+}
+
+impl PromptClassifier {
+    /// Build a new `PromptClassifier` without examples. Returns an error if the pattern does not contain exactly
+    /// the `{text}` and `{label}` variables (the label appearing once), or if a label has no words.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - `PromptTemplate` with `{text}` and `{label}` variables
+    /// * `verbalizers` - `Vec<Verbalizer>` labels and their words
+    pub fn new(
+        pattern: PromptTemplate,
+        verbalizers: Vec<Verbalizer>,
+    ) -> Result<PromptClassifier, RustBertError> {
+        let mut variables = pattern.variables().to_vec();
+        variables.sort();
+        if variables != [LABEL_VARIABLE, TEXT_VARIABLE] {
+            return Err(RustBertError::ValueError(format!(
+                "The classification pattern should contain the {{{TEXT_VARIABLE}}} and {{{LABEL_VARIABLE}}} variables only, got {variables:?}"
+            )));
+        }
+        pattern.format_around(LABEL_VARIABLE, &[(TEXT_VARIABLE, "")])?;
+        if verbalizers.is_empty() {
+            return Err(InputError::NoCandidateLabels.into());
+        }
+        if let Some(verbalizer) = verbalizers
+            .iter()
+            .find(|verbalizer| verbalizer.words.is_empty())
+        {
+            return Err(RustBertError::ValueError(format!(
+                "No words provided for the label {}",
+                verbalizer.label
+            )));
+        }
+        Ok(PromptClassifier {
+            pattern,
+            verbalizers,
+            examples: vec![],
+            separator: "\n\n".to_string(),
+        })
+    }
+
+    /// Sets the separator inserted between the examples and the query (default: blank line)
+    pub fn with_separator(mut self, separator: &str) -> PromptClassifier {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Adds a labelled example to the prompt, formatted with the first word of its label.
+    /// Returns an error if the label is not one of the verbalizer labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Example text
+    /// * `label` - Label of the example
+    pub fn with_example(
+        mut self,
+        text: &str,
+        label: &str,
+    ) -> Result<PromptClassifier, RustBertError> {
+        self.add_example(text, label)?;
+        Ok(self)
+    }
+
+    /// Adds a labelled example to the prompt, formatted with the first word of its label.
+    /// Returns an error if the label is not one of the verbalizer labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Example text
+    /// * `label` - Label of the example
+    pub fn add_example(&mut self, text: &str, label: &str) -> Result<(), RustBertError> {
+        let verbalizer = self
+            .verbalizers
+            .iter()
+            .find(|verbalizer| verbalizer.label == label)
+            .ok_or_else(|| RustBertError::ValueError(format!("Unknown example label {label}")))?;
+        let example = self.pattern.format(&[
+            (TEXT_VARIABLE, text),
+            (LABEL_VARIABLE, verbalizer.words[0].as_str()),
+        ])?;
+        self.examples.push(example);
+        Ok(())
+    }
+
+    /// Returns the number of examples of the prompt
+    pub fn num_examples(&self) -> usize {
+        self.examples.len()
+    }
+
+    /// Classifies texts by scoring the label words as continuations of the prompt with a text generation model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `TextGenerationModel` scoring the label words
+    /// * `texts` - `&[&str]` Array of texts to classify
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Label>>` probabilities of all labels (in the order of the verbalizers) for each input text
+    pub fn classify_with_generator<S>(
+        &self,
+        model: &TextGenerationModel,
+        texts: &[S],
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if texts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        texts
+            .iter()
+            .enumerate()
+            .map(|(sentence, text)| {
+                let (context, _) = self.prompt_parts(text.as_ref())?;
+                // Trailing white spaces are part of the label word tokens for BPE tokenizers (e.g. GPT2)
+                let prompt = context.trim_end();
+                let white_space = &context[prompt.len()..];
+                let log_probabilities = self
+                    .verbalizers
+                    .iter()
+                    .map(|verbalizer| {
+                        let continuations = verbalizer
+                            .words
+                            .iter()
+                            .map(|word| format!("{white_space}{word}"))
+                            .collect::<Vec<String>>();
+                        model.score_continuations(prompt, &continuations)
+                    })
+                    .collect::<Result<Vec<Vec<f64>>, RustBertError>>()?;
+                Ok(self.to_labels(&log_probabilities, sentence))
+            })
+            .collect()
+    }
+
+    /// Classifies texts by scoring the label words at the position of the label in the prompt with a masked
+    /// language model. Returns an error if a label word does not correspond to a single token.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `MaskedLanguageModel` scoring the label words
+    /// * `texts` - `&[&str]` Array of texts to classify
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Label>>` probabilities of all labels (in the order of the verbalizers) for each input text
+    pub fn classify_with_masked_lm<S>(
+        &self,
+        model: &MaskedLanguageModel,
+        texts: &[S],
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if texts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        let tokenizer = model.get_tokenizer();
+        let mask_token = tokenizer.get_mask_value().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "Tokenizer does not have a mask token, please use a tokenizer/model with a mask token."
+                    .into(),
+            )
+        })?;
+        let mut inputs = Vec::with_capacity(texts.len());
+        let mut white_space = "";
+        for text in texts {
+            let (context, continuation) = self.prompt_parts(text.as_ref())?;
+            if context.ends_with(char::is_whitespace) {
+                white_space = " ";
+            }
+            inputs.push(format!("{context}{mask_token}{continuation}"));
+        }
+
+        // Label words are tokenized as they would appear in the pattern (preceded by a white space for BPE
+        // tokenizers, e.g. RoBERTa)
+        let mut candidate_ids = vec![];
+        let mut word_counts = Vec::with_capacity(self.verbalizers.len());
+        for verbalizer in &self.verbalizers {
+            for word in &verbalizer.words {
+                let tokens = tokenizer.tokenize(&format!("{white_space}{word}"));
+                if tokens.len() != 1 {
+                    return Err(RustBertError::ValueError(format!(
+                        "The label word {word} should be a single token for masked language models, got {tokens:?}"
+                    )));
+                }
+                candidate_ids.extend(tokenizer.convert_tokens_to_ids(&tokens));
+            }
+            word_counts.push(verbalizer.words.len());
+        }
+
+        let scores = model.score_mask_candidates(
+            inputs.iter().map(String::as_str).collect::<Vec<&str>>(),
+            &candidate_ids,
+        )?;
+        Ok(scores
+            .into_iter()
+            .enumerate()
+            .map(|(sentence, scores)| {
+                let mut remaining = scores.as_slice();
+                let log_probabilities = word_counts
+                    .iter()
+                    .map(|count| {
+                        let (label_scores, rest) = remaining.split_at(*count);
+                        remaining = rest;
+                        label_scores.to_vec()
+                    })
+                    .collect::<Vec<Vec<f64>>>();
+                self.to_labels(&log_probabilities, sentence)
+            })
+            .collect())
+    }
+
+    /// Formats the prompt for an input text, returning the parts before and after the label
+    fn prompt_parts(&self, text: &str) -> Result<(String, String), RustBertError> {
+        let (context, continuation) = self
+            .pattern
+            .format_around(LABEL_VARIABLE, &[(TEXT_VARIABLE, text)])?;
+        let mut parts = self.examples.clone();
+        parts.push(context);
+        Ok((parts.join(&self.separator), continuation))
+    }
+
+    /// Sums the probabilities of the words of each label and normalizes them across labels
+    fn to_labels(&self, log_probabilities: &[Vec<f64>], sentence: usize) -> Vec<Label> {
+        let max_log_probability = log_probabilities
+            .iter()
+            .flatten()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let label_scores = log_probabilities
+            .iter()
+            .map(|word_log_probabilities| {
+                word_log_probabilities
+                    .iter()
+                    .map(|log_probability| (log_probability - max_log_probability).exp())
+                    .sum::<f64>()
+            })
+            .collect::<Vec<f64>>();
+        let total = label_scores.iter().sum::<f64>();
+        self.verbalizers
+            .iter()
+            .zip(label_scores)
+            .enumerate()
+            .map(|(id, (verbalizer, score))| Label {
+                text: verbalizer.label.clone(),
+                score: score / total,
+                id: id as i64,
+                sentence,
+            })
+            .collect()
+    }
+}
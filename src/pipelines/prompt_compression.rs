@@ -0,0 +1,398 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Prompt compression
+//! Compresses a long context (e.g. retrieved evidence) into a shorter text before feeding it to a generation model
+//! with a small context window. The compression is selection-based: a small causal language model (the scorer)
+//! computes the self-information of every token of the context (its negative log-probability given the previous
+//! tokens), and the most informative words are kept, in their original order, until the token budget is reached.
+//! Predictable words (function words, repeated phrases) are dropped first.
+//!
+//! The importance of a word is the mean self-information of its tokens. Contexts longer than the scorer context
+//! window are scored in overlapping windows. Terms listed in the configuration (e.g. entities of the question) are
+//! always kept.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::prompt_compression::PromptCompressionConfig;
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//!
+//! let scorer = TextGenerationModel::new(Default::default())?;
+//! let config = PromptCompressionConfig {
+//!     keep_ratio: 0.4,
+//!     preserved_terms: vec!["Eiffel".to_string()],
+//!     ..Default::default()
+//! };
+//! let compressed = scorer.compress_prompt(
+//!     "The Eiffel Tower is a wrought-iron lattice tower on the Champ de Mars in Paris, France. \
+//!      It is named after the engineer Gustave Eiffel, whose company designed and built the tower.",
+//!     &config,
+//! )?;
+//! println!("{} ({:.2})", compressed.text, compressed.compression_ratio());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use rust_tokenizers::Offset;
+use std::cmp::Ordering;
+
+/// # Configuration for prompt compression
+#[derive(Debug, Clone)]
+pub struct PromptCompressionConfig {
+    /// Fraction of the context tokens to keep, in ]0, 1]
+    pub keep_ratio: f64,
+    /// Maximum number of context tokens to keep, taking precedence over `keep_ratio` if lower
+    pub max_tokens: Option<usize>,
+    /// Terms always kept (case-insensitive, compared to the words of the context without surrounding punctuation)
+    pub preserved_terms: Vec<String>,
+    /// Number of tokens scored in a single forward pass of the scorer (default: maximum number of positions of the
+    /// scorer)
+    pub window_tokens: Option<usize>,
+}
+
+impl Default for PromptCompressionConfig {
+    fn default() -> PromptCompressionConfig {
+        PromptCompressionConfig {
+            keep_ratio: 0.5,
+            max_tokens: None,
+            preserved_terms: vec![],
+            window_tokens: None,
+        }
+    }
+}
+
+impl PromptCompressionConfig {
+    pub(crate) fn validate(&self) -> Result<(), RustBertError> {
+        if !(self.keep_ratio > 0.0 && self.keep_ratio <= 1.0) {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The ratio of tokens to keep should be in ]0, 1], got {}",
+                self.keep_ratio
+            )));
+        }
+        if self.window_tokens.map_or(false, |window| window < 2) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The scoring window should contain at least 2 tokens".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Number of context tokens kept for a context of `num_tokens` tokens
+    pub(crate) fn token_budget(&self, num_tokens: usize) -> usize {
+        let budget = (num_tokens as f64 * self.keep_ratio).ceil() as usize;
+        self.max_tokens
+            .map_or(budget, |max_tokens| budget.min(max_tokens))
+    }
+
+    pub(crate) fn is_preserved(&self, word: &str) -> bool {
+        let word = word.trim_matches(|character: char| !character.is_alphanumeric());
+        self.preserved_terms
+            .iter()
+            .any(|term| term.to_lowercase() == word.to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Compressed context
+pub struct CompressedPrompt {
+    /// Compressed text, made of the words kept separated by a space (or a line break if the words were separated by
+    /// a line break in the original context)
+    pub text: String,
+    /// Number of tokens of the original context
+    pub original_tokens: usize,
+    /// Number of tokens of the words kept
+    pub compressed_tokens: usize,
+}
+
+impl CompressedPrompt {
+    /// Fraction of the original tokens kept
+    pub fn compression_ratio(&self) -> f64 {
+        if self.original_tokens == 0 {
+            1.0
+        } else {
+            self.compressed_tokens as f64 / self.original_tokens as f64
+        }
+    }
+}
+
+/// Whitespace-separated word of the context, with the tokens it is made of
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Word {
+    pub(crate) text: String,
+    /// Character offsets of the word in the context
+    pub(crate) begin: usize,
+    pub(crate) end: usize,
+    pub(crate) num_tokens: usize,
+    /// Mean self-information of the tokens of the word (infinite if a token could not be scored)
+    pub(crate) importance: f64,
+}
+
+/// Scores sequences longer than the scorer window in windows overlapping by half a window: every token after the
+/// first window is scored with at least half a window of context.
+pub(crate) fn windowed_log_probabilities<F>(
+    token_ids: &[i64],
+    window: usize,
+    mut score: F,
+) -> Result<Vec<Option<f64>>, RustBertError>
+where
+    F: FnMut(&[i64]) -> Result<Vec<Option<f64>>, RustBertError>,
+{
+    if token_ids.len() <= window {
+        return score(token_ids);
+    }
+    let stride = (window / 2).max(1);
+    let mut log_probabilities = Vec::with_capacity(token_ids.len());
+    let mut start = 0;
+    loop {
+        let end = (start + window).min(token_ids.len());
+        let window_log_probabilities = score(&token_ids[start..end])?;
+        // Tokens before the last position of the previous window were already scored
+        let skipped = log_probabilities.len() - start;
+        log_probabilities.extend(window_log_probabilities.into_iter().skip(skipped));
+        if end == token_ids.len() {
+            break;
+        }
+        start += stride;
+    }
+    Ok(log_probabilities)
+}
+
+/// Groups the tokens of a context into whitespace-separated words. A token belongs to the word of its first
+/// non-whitespace character, tokens without offset belong to the word of the previous token.
+pub(crate) fn group_words(
+    text: &str,
+    offsets: &[Option<Offset>],
+    log_probabilities: &[Option<f64>],
+) -> Vec<Word> {
+    let characters = text.chars().collect::<Vec<char>>();
+    let mut word_indices = vec![None; characters.len()];
+    let mut words: Vec<Word> = Vec::new();
+    for (position, character) in characters.iter().enumerate() {
+        if character.is_whitespace() {
+            continue;
+        }
+        match words.last_mut() {
+            Some(word) if word.end == position => word.end += 1,
+            _ => words.push(Word {
+                text: String::new(),
+                begin: position,
+                end: position + 1,
+                num_tokens: 0,
+                importance: 0.0,
+            }),
+        }
+        word_indices[position] = Some(words.len() - 1);
+    }
+
+    let mut information = vec![0.0; words.len()];
+    let mut current_word = None;
+    for (offset, log_probability) in offsets.iter().zip(log_probabilities) {
+        if let Some(offset) = offset {
+            if let Some(word_index) = (offset.begin as usize..offset.end as usize)
+                .find_map(|position| word_indices.get(position).copied().flatten())
+            {
+                current_word = Some(word_index);
+            }
+        }
+        if let Some(word_index) = current_word {
+            words[word_index].num_tokens += 1;
+            information[word_index] += log_probability.map_or(f64::INFINITY, |value| -value);
+        }
+    }
+
+    words
+        .into_iter()
+        .zip(information)
+        .filter(|(word, _)| word.num_tokens > 0)
+        .map(|(mut word, information)| {
+            word.text = characters[word.begin..word.end].iter().collect();
+            word.importance = information / word.num_tokens as f64;
+            word
+        })
+        .collect()
+}
+
+/// Selects the most important words fitting in the token budget, after the `forced` words. Returns the indices of
+/// the words selected, in their original order.
+pub(crate) fn select_words(words: &[Word], budget: usize, forced: &[bool]) -> Vec<usize> {
+    let mut selected = forced.to_vec();
+    let mut num_tokens = words
+        .iter()
+        .zip(forced)
+        .filter(|(_, forced)| **forced)
+        .map(|(word, _)| word.num_tokens)
+        .sum::<usize>();
+    let mut candidates = (0..words.len())
+        .filter(|index| !forced[*index])
+        .collect::<Vec<usize>>();
+    candidates.sort_by(|index_1, index_2| {
+        words[*index_2]
+            .importance
+            .partial_cmp(&words[*index_1].importance)
+            .unwrap_or(Ordering::Equal)
+            .then(index_1.cmp(index_2))
+    });
+    for index in candidates {
+        if num_tokens + words[index].num_tokens <= budget {
+            selected[index] = true;
+            num_tokens += words[index].num_tokens;
+        }
+    }
+    (0..words.len()).filter(|index| selected[*index]).collect()
+}
+
+/// Joins the selected words, keeping a line break between words separated by a line break in the context
+pub(crate) fn join_words(text: &str, words: &[Word], selected: &[usize]) -> String {
+    let characters = text.chars().collect::<Vec<char>>();
+    let mut output = String::new();
+    let mut previous_end = None;
+    for index in selected {
+        let word = &words[*index];
+        if let Some(previous_end) = previous_end {
+            if characters[previous_end..word.begin].contains(&'\n') {
+                output.push('\n');
+            } else {
+                output.push(' ');
+            }
+        }
+        output.push_str(&word.text);
+        previous_end = Some(word.end);
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn word(text: &str, num_tokens: usize, importance: f64) -> Word {
+        Word {
+            text: text.to_string(),
+            begin: 0,
+            end: 0,
+            num_tokens,
+            importance,
+        }
+    }
+
+    #[test]
+    fn word_grouping() {
+        let text = "The tower,\nin Paris";
+        // GPT2-like tokens: "The", " tower", ",", "\n", "in", " Par", "is"
+        let offsets = [
+            (0, 3),
+            (3, 9),
+            (9, 10),
+            (10, 11),
+            (11, 13),
+            (13, 17),
+            (17, 19),
+        ]
+        .iter()
+        .map(|(begin, end)| {
+            Some(Offset {
+                begin: *begin,
+                end: *end,
+            })
+        })
+        .collect::<Vec<Option<Offset>>>();
+        let log_probabilities = [
+            None,
+            Some(-4.0),
+            Some(-2.0),
+            Some(-1.0),
+            Some(-1.0),
+            Some(-6.0),
+            Some(-2.0),
+        ];
+        let words = group_words(text, &offsets, &log_probabilities);
+
+        assert_eq!(
+            words
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["The", "tower,", "in", "Paris"]
+        );
+        assert_eq!(words[0].importance, f64::INFINITY);
+        // The line break token is attached to the previous word
+        assert_eq!(words[1].num_tokens, 3);
+        assert!((words[1].importance - 7.0 / 3.0).abs() < 1e-9);
+        assert_eq!(words[3].num_tokens, 2);
+        assert!((words[3].importance - 4.0).abs() < 1e-9);
+        assert_eq!(
+            join_words(text, &words, &[0, 1, 2, 3]),
+            "The tower,\nin Paris"
+        );
+        assert_eq!(join_words(text, &words, &[0, 3]), "The\nParis");
+    }
+
+    #[test]
+    fn word_selection() {
+        let words = vec![
+            word("the", 1, 0.5),
+            word("Eiffel", 2, 8.0),
+            word("tower", 1, 3.0),
+            word("is", 1, 0.2),
+            word("tall", 1, 2.0),
+        ];
+        let config = PromptCompressionConfig {
+            keep_ratio: 0.5,
+            preserved_terms: vec!["is".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_preserved("Is,"));
+        let forced = words
+            .iter()
+            .map(|word| config.is_preserved(&word.text))
+            .collect::<Vec<bool>>();
+        let budget = config.token_budget(6);
+        assert_eq!(budget, 3);
+        // "is" is forced (1 token), "Eiffel" (2 tokens) fits, "tower" does not fit anymore
+        assert_eq!(select_words(&words, budget, &forced), vec![1, 3]);
+        assert_eq!(select_words(&words, 4, &[false; 5]), vec![1, 2, 4]);
+
+        assert!(PromptCompressionConfig {
+            keep_ratio: 0.0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn windowed_scoring() {
+        let token_ids = (0..10).collect::<Vec<i64>>();
+        let mut windows = Vec::new();
+        let log_probabilities = windowed_log_probabilities(&token_ids, 4, |ids| {
+            windows.push(ids.to_vec());
+            Ok(ids.iter().map(|id| Some(-(*id as f64))).collect())
+        })
+        .unwrap();
+        assert_eq!(
+            windows,
+            vec![
+                vec![0, 1, 2, 3],
+                vec![2, 3, 4, 5],
+                vec![4, 5, 6, 7],
+                vec![6, 7, 8, 9]
+            ]
+        );
+        assert_eq!(
+            log_probabilities,
+            token_ids
+                .iter()
+                .map(|id| Some(-(*id as f64)))
+                .collect::<Vec<Option<f64>>>()
+        );
+    }
+}
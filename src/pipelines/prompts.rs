@@ -0,0 +1,406 @@
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Prompt templates
+//! Typed prompt templates with named variables (`{variable}`, literal braces are escaped as `{{` and `}}`),
+//! few-shot prompts built from an example template and a list of examples, and model-specific
+//! instruction formats. Templates can be used to build the inputs of the text generation pipeline,
+//! or the label hypotheses of the zero-shot classification pipeline.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::prompts::{FewShotPromptTemplate, PromptFormat, PromptTemplate};
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//!
+//! let prompt = FewShotPromptTemplate::new(
+//!     PromptTemplate::new("Word: {word}\nAntonym: {antonym}")?,
+//!     PromptTemplate::new("Word: {word}\nAntonym:")?,
+//! )
+//! .with_prefix("Give the antonym of every input.")
+//! .with_example(&[("word", "happy"), ("antonym", "sad")])?
+//! .with_example(&[("word", "tall"), ("antonym", "short")])?;
+//!
+//! let input = PromptFormat::Plain.apply(None, &prompt.format(&[("word", "big")])?);
+//! let model = TextGenerationModel::new(Default::default())?;
+//! let output = model.generate(&[input], None);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::zero_shot_classification::ZeroShotTemplate;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplateSegment {
+    Text(String),
+    Variable(String),
+}
+
+/// # Prompt template
+/// Template text with named variables delimited by braces, e.g. `Translate to {language}: {text}`.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    segments: Vec<TemplateSegment>,
+    variables: Vec<String>,
+}
+
+impl PromptTemplate {
+    /// Build a new `PromptTemplate`. Returns an error if the template contains unbalanced braces or empty variable names.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Template text, with variables delimited by braces
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::prompts::PromptTemplate;
+    ///
+    /// let template = PromptTemplate::new("Translate to {language}: {text}")?;
+    /// let prompt = template.format(&[("language", "French"), ("text", "Hello")])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(template: &str) -> Result<PromptTemplate, RustBertError> {
+        let mut segments = vec![];
+        let mut variables: Vec<String> = vec![];
+        let mut text = String::new();
+        let mut characters = template.chars().peekable();
+        while let Some(character) = characters.next() {
+            match character {
+                '{' if characters.peek() == Some(&'{') => {
+                    characters.next();
+                    text.push('{');
+                }
+                '}' if characters.peek() == Some(&'}') => {
+                    characters.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match characters.next() {
+                            Some('}') => break,
+                            Some('{') | None => {
+                                return Err(RustBertError::ValueError(format!(
+                                    "Unbalanced braces in prompt template: {template}"
+                                )))
+                            }
+                            Some(character) => name.push(character),
+                        }
+                    }
+                    let name = name.trim().to_string();
+                    if name.is_empty() {
+                        return Err(RustBertError::ValueError(format!(
+                            "Empty variable name in prompt template: {template}"
+                        )));
+                    }
+                    if !text.is_empty() {
+                        segments.push(TemplateSegment::Text(std::mem::take(&mut text)));
+                    }
+                    if !variables.contains(&name) {
+                        variables.push(name.clone());
+                    }
+                    segments.push(TemplateSegment::Variable(name));
+                }
+                '}' => {
+                    return Err(RustBertError::ValueError(format!(
+                        "Unbalanced braces in prompt template: {template}"
+                    )))
+                }
+                character => text.push(character),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(TemplateSegment::Text(text));
+        }
+        Ok(PromptTemplate {
+            segments,
+            variables,
+        })
+    }
+
+    /// Returns the names of the variables of the template, in order of first appearance
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Formats the template with the values provided for its variables.
+    /// Returns an error if a variable is missing or if a value is provided for an unknown variable.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - `&[(&str, &str)]` pairs of variable names and values
+    pub fn format(&self, values: &[(&str, &str)]) -> Result<String, RustBertError> {
+        for (name, _) in values {
+            if !self.variables.iter().any(|variable| variable == name) {
+                return Err(RustBertError::ValueError(format!(
+                    "Unknown prompt template variable {name}"
+                )));
+            }
+        }
+        Self::format_segments(&self.segments, values)
+    }
+
+    /// Formats the parts of the template before and after a variable appearing once in the template, e.g. to
+    /// score the values of this variable given the rest of the prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `variable` - Name of the variable separating the two parts
+    /// * `values` - `&[(&str, &str)]` pairs of variable names and values for the other variables
+    pub(crate) fn format_around(
+        &self,
+        variable: &str,
+        values: &[(&str, &str)],
+    ) -> Result<(String, String), RustBertError> {
+        let is_variable = |segment: &TemplateSegment| matches!(segment, TemplateSegment::Variable(name) if name == variable);
+        if self
+            .segments
+            .iter()
+            .filter(|segment| is_variable(segment))
+            .count()
+            != 1
+        {
+            return Err(RustBertError::ValueError(format!(
+                "The prompt template variable {variable} should appear exactly once"
+            )));
+        }
+        let position = self.segments.iter().position(is_variable).unwrap();
+        Ok((
+            Self::format_segments(&self.segments[..position], values)?,
+            Self::format_segments(&self.segments[position + 1..], values)?,
+        ))
+    }
+
+    fn format_segments(
+        segments: &[TemplateSegment],
+        values: &[(&str, &str)],
+    ) -> Result<String, RustBertError> {
+        let mut output = String::new();
+        for segment in segments {
+            match segment {
+                TemplateSegment::Text(text) => output.push_str(text),
+                TemplateSegment::Variable(variable) => {
+                    let value = values
+                        .iter()
+                        .find(|(name, _)| name == variable)
+                        .map(|(_, value)| *value)
+                        .ok_or_else(|| {
+                            RustBertError::ValueError(format!(
+                                "Missing value for prompt template variable {variable}"
+                            ))
+                        })?;
+                    output.push_str(value);
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Converts a template with a single variable into a label template for the zero-shot classification
+    /// pipeline (e.g. `This example is about {label}.`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::prompts::PromptTemplate;
+    ///
+    /// let template = PromptTemplate::new("This review is {label}.")?.to_zero_shot_template()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_zero_shot_template(&self) -> Result<ZeroShotTemplate, RustBertError> {
+        if self.variables.len() != 1 {
+            return Err(RustBertError::ValueError(format!(
+                "Zero-shot templates should contain exactly one variable, got {}",
+                self.variables.len()
+            )));
+        }
+        let template = self.clone();
+        Ok(Box::new(move |label: &str| {
+            template
+                .format(&[(template.variables[0].as_str(), label)])
+                .unwrap()
+        }))
+    }
+}
+
+/// # Few-shot prompt template
+/// Prompt made of an optional prefix (instructions), a list of examples formatted with an example template
+/// and a suffix template formatted with the query values. The parts are joined with a separator (default: blank line).
+#[derive(Debug, Clone)]
+pub struct FewShotPromptTemplate {
+    prefix: Option<String>,
+    example_template: PromptTemplate,
+    examples: Vec<String>,
+    suffix: PromptTemplate,
+    separator: String,
+}
+
+impl FewShotPromptTemplate {
+    /// Build a new `FewShotPromptTemplate` without examples
+    ///
+    /// # Arguments
+    ///
+    /// * `example_template` - `PromptTemplate` used to format the examples
+    /// * `suffix` - `PromptTemplate` formatted with the query values
+    pub fn new(example_template: PromptTemplate, suffix: PromptTemplate) -> FewShotPromptTemplate {
+        FewShotPromptTemplate {
+            prefix: None,
+            example_template,
+            examples: vec![],
+            suffix,
+            separator: "\n\n".to_string(),
+        }
+    }
+
+    /// Sets the prefix (instructions) of the prompt
+    pub fn with_prefix(mut self, prefix: &str) -> FewShotPromptTemplate {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Sets the separator inserted between the prefix, examples and suffix
+    pub fn with_separator(mut self, separator: &str) -> FewShotPromptTemplate {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Adds an example to the prompt. Returns an error if the example does not provide all the example template variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - `&[(&str, &str)]` pairs of variable names and values for the example template
+    pub fn with_example(
+        mut self,
+        values: &[(&str, &str)],
+    ) -> Result<FewShotPromptTemplate, RustBertError> {
+        self.add_example(values)?;
+        Ok(self)
+    }
+
+    /// Adds an example to the prompt. Returns an error if the example does not provide all the example template variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - `&[(&str, &str)]` pairs of variable names and values for the example template
+    pub fn add_example(&mut self, values: &[(&str, &str)]) -> Result<(), RustBertError> {
+        let example = self.example_template.format(values)?;
+        self.examples.push(example);
+        Ok(())
+    }
+
+    /// Returns the number of examples of the prompt
+    pub fn num_examples(&self) -> usize {
+        self.examples.len()
+    }
+
+    /// Formats the prompt with the query values provided for the suffix template variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - `&[(&str, &str)]` pairs of variable names and values for the suffix template
+    pub fn format(&self, values: &[(&str, &str)]) -> Result<String, RustBertError> {
+        let mut parts = Vec::with_capacity(self.examples.len() + 2);
+        if let Some(prefix) = &self.prefix {
+            parts.push(prefix.clone());
+        }
+        parts.extend(self.examples.iter().cloned());
+        parts.push(self.suffix.format(values)?);
+        Ok(parts.join(&self.separator))
+    }
+
+    /// Formats a batch of queries, e.g. to be passed to the text generation pipeline
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_values` - `&[Vec<(&str, &str)>]` values of the suffix template variables for each query
+    pub fn format_batch(
+        &self,
+        batch_values: &[Vec<(&str, &str)>],
+    ) -> Result<Vec<String>, RustBertError> {
+        batch_values
+            .iter()
+            .map(|values| self.format(values))
+            .collect()
+    }
+
+    /// Returns the variables of the suffix template, to be provided when formatting the prompt
+    pub fn variables(&self) -> &[String] {
+        self.suffix.variables()
+    }
+}
+
+/// # Model-specific instruction format
+/// Wraps an (optional) system prompt and a user prompt following the conventions of instruction-tuned models.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptFormat {
+    /// No formatting, the system prompt is prepended to the user prompt
+    Plain,
+    /// ChatML format (`<|im_start|>user ... <|im_end|>`), e.g. Qwen and Hermes models
+    ChatML,
+    /// Llama 2 chat format (`[INST] <<SYS>> ... <</SYS>> ... [/INST]`)
+    Llama2,
+    /// Mistral instruct format (`[INST] ... [/INST]`, system prompt merged in the user instruction)
+    Mistral,
+    /// Zephyr format (`<|system|>`, `<|user|>` and `<|assistant|>` roles)
+    Zephyr,
+    /// Alpaca format (`### Instruction:` and `### Response:` sections)
+    Alpaca,
+}
+
+impl PromptFormat {
+    /// Formats a prompt for generation, ending with the marker of the assistant turn
+    ///
+    /// # Arguments
+    ///
+    /// * `system_prompt` - Optional system prompt
+    /// * `prompt` - User prompt
+    pub fn apply(&self, system_prompt: Option<&str>, prompt: &str) -> String {
+        match self {
+            PromptFormat::Plain => match system_prompt {
+                Some(system_prompt) => format!("{system_prompt}\n\n{prompt}"),
+                None => prompt.to_string(),
+            },
+            PromptFormat::ChatML => {
+                let system = system_prompt
+                    .map(|system_prompt| format!("<|im_start|>system\n{system_prompt}<|im_end|>\n"))
+                    .unwrap_or_default();
+                format!("{system}<|im_start|>user\n{prompt}<|im_end|>\n<|im_start|>assistant\n")
+            }
+            PromptFormat::Llama2 => match system_prompt {
+                Some(system_prompt) => {
+                    format!("[INST] <<SYS>>\n{system_prompt}\n<</SYS>>\n\n{prompt} [/INST]")
+                }
+                None => format!("[INST] {prompt} [/INST]"),
+            },
+            PromptFormat::Mistral => match system_prompt {
+                Some(system_prompt) => format!("[INST] {system_prompt}\n\n{prompt} [/INST]"),
+                None => format!("[INST] {prompt} [/INST]"),
+            },
+            PromptFormat::Zephyr => {
+                let system = system_prompt
+                    .map(|system_prompt| format!("<|system|>\n{system_prompt}</s>\n"))
+                    .unwrap_or_default();
+                format!("{system}<|user|>\n{prompt}</s>\n<|assistant|>\n")
+            }
+            PromptFormat::Alpaca => match system_prompt {
+                Some(system_prompt) => {
+                    format!("{system_prompt}\n\n### Instruction:\n{prompt}\n\n### Response:\n")
+                }
+                None => format!("### Instruction:\n{prompt}\n\n### Response:\n"),
+            },
+        }
+    }
+}
@@ -43,36 +43,57 @@
 //! # ;
 //! ```
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForQuestionAnswering;
+#[cfg(feature = "bert")]
 use crate::bert::BertForQuestionAnswering;
+#[cfg(feature = "bigbird")]
+use crate::bigbird::BigBirdForQuestionAnswering;
 use crate::common::error::RustBertError;
+use crate::common::mps;
+use crate::common::precision::{
+    is_half_precision, set_var_store_kind, with_attention_fp32_accumulation,
+};
+#[cfg(feature = "deberta")]
 use crate::deberta::DebertaForQuestionAnswering;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertForQuestionAnswering;
+#[cfg(feature = "fnet")]
 use crate::fnet::FNetForQuestionAnswering;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerForQuestionAnswering;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForQuestionAnswering;
 use crate::pipelines::common::{
-    get_device, ConfigOption, ModelResource, ModelType, TokenizerOption,
+    get_device, ConfigOption, ModelResource, ModelType, TokenTypeIdsHandler, TokenTypeStrategy,
+    TokenizerOption,
 };
+use crate::pipelines::translation::split_sentences;
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerForQuestionAnswering;
 use crate::resources::ResourceProvider;
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForQuestionAnswering;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForQuestionAnswering;
 use rust_tokenizers::{Offset, TokenIdsWithOffsets, TokenizedInput};
 use serde::{Deserialize, Serialize};
 use std::cmp::min;
 use std::collections::HashMap;
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
 use tch::kind::Kind::Float;
 use tch::nn::VarStore;
 use tch::{no_grad, Device, Kind, Tensor};
 
+use crate::common::settings::default_device;
+#[cfg(feature = "deberta-v2")]
 use crate::deberta_v2::DebertaV2ForQuestionAnswering;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::{config::ONNXEnvironmentConfig, ONNXEncoder};
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "distilbert"))]
 use crate::{
     distilbert::{DistilBertConfigResources, DistilBertModelResources, DistilBertVocabResources},
     resources::RemoteResource,
@@ -116,6 +137,142 @@ impl PartialEq for Answer {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// # Span of a question answering context
+pub struct ContextSpan {
+    /// Start position of the span (character offset in the context)
+    pub start: usize,
+    /// End position of the span (character offset in the context)
+    pub end: usize,
+    /// Text of the span
+    pub text: String,
+}
+
+impl ContextSpan {
+    fn new(context: &str, range: Range<usize>) -> ContextSpan {
+        ContextSpan {
+            start: range.start,
+            end: range.end,
+            text: context
+                .chars()
+                .skip(range.start)
+                .take(range.end - range.start)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Answer with the context sentences supporting it
+pub struct AnswerEvidence {
+    /// Extracted answer
+    pub answer: Answer,
+    /// Sentence(s) of the context containing the answer
+    pub evidence: ContextSpan,
+    /// Evidence extended with the neighboring sentences of the context
+    pub surrounding_context: ContextSpan,
+}
+
+impl AnswerEvidence {
+    /// Returns the surrounding context with the answer enclosed between markers (e.g. `<b>` and `</b>`)
+    ///
+    /// # Arguments
+    ///
+    /// * `open` - Marker inserted before the answer
+    /// * `close` - Marker inserted after the answer
+    pub fn highlight(&self, open: &str, close: &str) -> String {
+        let characters = self.surrounding_context.text.chars().collect::<Vec<char>>();
+        let start = (self.answer.start.max(self.surrounding_context.start)
+            - self.surrounding_context.start)
+            .min(characters.len());
+        let end = (self.answer.end.max(self.surrounding_context.start)
+            - self.surrounding_context.start)
+            .clamp(start, characters.len());
+        let mut highlighted =
+            String::with_capacity(self.surrounding_context.text.len() + open.len() + close.len());
+        highlighted.extend(&characters[..start]);
+        highlighted.push_str(open);
+        highlighted.extend(&characters[start..end]);
+        highlighted.push_str(close);
+        highlighted.extend(&characters[end..]);
+        highlighted
+    }
+}
+
+/// Character ranges of the sentences of a text
+fn sentence_character_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut byte_position = 0;
+    let mut char_position = 0;
+    for range in split_sentences(text, None) {
+        let start = char_position + text[byte_position..range.start].chars().count();
+        let end = start + text[range.clone()].chars().count();
+        ranges.push(start..end);
+        byte_position = range.end;
+        char_position = end;
+    }
+    ranges
+}
+
+fn answer_evidence(
+    context: &str,
+    sentences: &[Range<usize>],
+    answer: Answer,
+    context_sentences: usize,
+) -> AnswerEvidence {
+    let first = sentences
+        .iter()
+        .position(|sentence| sentence.end > answer.start)
+        .unwrap_or_else(|| sentences.len().saturating_sub(1));
+    let last = sentences
+        .iter()
+        .rposition(|sentence| sentence.start < answer.end)
+        .unwrap_or(first)
+        .max(first);
+    let (evidence, surrounding_context) = match (sentences.get(first), sentences.get(last)) {
+        (Some(first_sentence), Some(last_sentence)) => {
+            let context_first = &sentences[first.saturating_sub(context_sentences)];
+            let context_last = &sentences[(last + context_sentences).min(sentences.len() - 1)];
+            (
+                ContextSpan::new(context, first_sentence.start..last_sentence.end),
+                ContextSpan::new(context, context_first.start..context_last.end),
+            )
+        }
+        _ => {
+            let span = ContextSpan::new(context, 0..context.chars().count());
+            (span.clone(), span)
+        }
+    };
+    AnswerEvidence {
+        answer,
+        evidence,
+        surrounding_context,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Answer extracted from one of multiple contexts
+pub struct ContextAnswer {
+    /// Extracted answer, with a score normalized across all contexts
+    pub answer: Answer,
+    /// Index of the context the answer was extracted from
+    pub context_index: usize,
+}
+
+/// Answer with the log normalizer of the span probabilities of its feature (`log_offset`), to recover its
+/// span logits
+struct AnswerCandidate {
+    answer: Answer,
+    log_offset: f64,
+}
+
+#[derive(Default)]
+struct ExampleCandidates {
+    candidates: Vec<AnswerCandidate>,
+    /// Log of the total unnormalized probability of the valid spans of each feature
+    log_normalizers: Vec<f64>,
+}
+
 fn remove_duplicates<T: PartialEq + Clone>(vector: &mut Vec<T>) -> &mut Vec<T> {
     let mut potential_duplicates = vec![];
     vector.retain(|item| {
@@ -142,6 +299,8 @@ pub struct QuestionAnsweringConfig {
     pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
     /// Model type
     pub model_type: ModelType,
     /// Flag indicating if the model expects a lower casing of the input
@@ -158,6 +317,8 @@ pub struct QuestionAnsweringConfig {
     pub max_query_length: usize,
     /// Maximum length for the answer
     pub max_answer_length: usize,
+    /// Token type ids passed to the model for the question and context segments (default: depending on the architecture)
+    pub token_type_strategy: TokenTypeStrategy,
 }
 
 impl QuestionAnsweringConfig {
@@ -194,11 +355,13 @@ impl QuestionAnsweringConfig {
             lower_case,
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
             max_seq_length: 384,
             doc_stride: 128,
             max_query_length: 64,
             max_answer_length: 15,
+            token_type_strategy: TokenTypeStrategy::default(),
         }
     }
 
@@ -243,16 +406,18 @@ impl QuestionAnsweringConfig {
             lower_case,
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
             max_seq_length: max_seq_length.into().unwrap_or(384),
             doc_stride: doc_stride.into().unwrap_or(128),
             max_query_length: max_query_length.into().unwrap_or(64),
             max_answer_length: max_answer_length.into().unwrap_or(15),
+            token_type_strategy: TokenTypeStrategy::default(),
         }
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "distilbert"))]
 impl Default for QuestionAnsweringConfig {
     fn default() -> QuestionAnsweringConfig {
         QuestionAnsweringConfig {
@@ -266,7 +431,8 @@ impl Default for QuestionAnsweringConfig {
                 DistilBertVocabResources::DISTIL_BERT_SQUAD,
             )),
             merges_resource: None,
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
             model_type: ModelType::DistilBert,
             lower_case: false,
             add_prefix_space: None,
@@ -275,6 +441,7 @@ impl Default for QuestionAnsweringConfig {
             doc_stride: 128,
             max_query_length: 64,
             max_answer_length: 15,
+            token_type_strategy: TokenTypeStrategy::default(),
         }
     }
 }
@@ -283,28 +450,43 @@ impl Default for QuestionAnsweringConfig {
 /// # Abstraction that holds one particular question answering model, for any of the supported models
 pub enum QuestionAnsweringOption {
     /// Bert for Question Answering
+    #[cfg(feature = "bert")]
     Bert(BertForQuestionAnswering),
     /// DeBERTa for Question Answering
+    #[cfg(feature = "deberta")]
     Deberta(DebertaForQuestionAnswering),
     /// DeBERTa V2 for Question Answering
+    #[cfg(feature = "deberta-v2")]
     DebertaV2(DebertaV2ForQuestionAnswering),
     /// DistilBert for Question Answering
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertForQuestionAnswering),
     /// MobileBert for Question Answering
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForQuestionAnswering),
     /// Roberta for Question Answering
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForQuestionAnswering),
     /// XLMRoberta for Question Answering
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForQuestionAnswering),
     /// Albert for Question Answering
+    #[cfg(feature = "albert")]
     Albert(AlbertForQuestionAnswering),
     /// XLNet for Question Answering
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForQuestionAnswering),
     /// Reformer for Question Answering
+    #[cfg(feature = "reformer")]
     Reformer(ReformerForQuestionAnswering),
     /// Longformer for Question Answering
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForQuestionAnswering),
+    /// BigBird for Question Answering
+    #[cfg(feature = "bigbird")]
+    BigBird(BigBirdForQuestionAnswering),
     /// FNet for Question Answering
+    #[cfg(feature = "fnet")]
     FNet(FNetForQuestionAnswering),
     /// ONNX model for Question Answering
     #[cfg(feature = "onnx")]
@@ -336,6 +518,7 @@ impl QuestionAnsweringOption {
         );
         let model_type = config.model_type;
         let model = match model_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(QuestionAnsweringOption::Bert(
@@ -347,6 +530,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta")]
             ModelType::Deberta => {
                 if let ConfigOption::Deberta(config) = model_config {
                     Ok(QuestionAnsweringOption::Deberta(
@@ -358,6 +542,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta-v2")]
             ModelType::DebertaV2 => {
                 if let ConfigOption::DebertaV2(config) = model_config {
                     Ok(QuestionAnsweringOption::DebertaV2(
@@ -369,6 +554,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => {
                 if let ConfigOption::DistilBert(ref mut config) = model_config {
                     config.sinusoidal_pos_embds = false;
@@ -381,6 +567,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
                 if let ConfigOption::MobileBert(config) = model_config {
                     Ok(QuestionAnsweringOption::MobileBert(
@@ -392,6 +579,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
                 if let ConfigOption::Roberta(config) = model_config {
                     Ok(QuestionAnsweringOption::Roberta(
@@ -403,6 +591,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(QuestionAnsweringOption::XLMRoberta(
@@ -414,6 +603,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
                 if let ConfigOption::Albert(config) = model_config {
                     Ok(QuestionAnsweringOption::Albert(
@@ -425,6 +615,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
                 if let ConfigOption::XLNet(config) = model_config {
                     Ok(QuestionAnsweringOption::XLNet(
@@ -436,6 +627,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "reformer")]
             ModelType::Reformer => {
                 if let ConfigOption::Reformer(config) = model_config {
                     Ok(QuestionAnsweringOption::Reformer(
@@ -447,6 +639,7 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
                 if let ConfigOption::Longformer(config) = model_config {
                     Ok(QuestionAnsweringOption::Longformer(
@@ -458,6 +651,19 @@ impl QuestionAnsweringOption {
                     ))
                 }
             }
+            #[cfg(feature = "bigbird")]
+            ModelType::BigBird => {
+                if let ConfigOption::BigBird(config) = model_config {
+                    Ok(QuestionAnsweringOption::BigBird(
+                        BigBirdForQuestionAnswering::new(var_store.root(), config),
+                    ))
+                } else {
+                    Err(RustBertError::InvalidConfigurationError(
+                        "You can only supply a BigBirdConfig for BigBird!".to_string(),
+                    ))
+                }
+            }
+            #[cfg(feature = "fnet")]
             ModelType::FNet => {
                 if let ConfigOption::FNet(config) = model_config {
                     Ok(QuestionAnsweringOption::FNet(
@@ -473,7 +679,10 @@ impl QuestionAnsweringOption {
                 "QuestionAnswering not implemented for {model_type:?}!",
             ))),
         }?;
-        var_store.load(weights_path)?;
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+        if let Some(kind) = config.kind {
+            set_var_store_kind(&mut var_store, kind)?;
+        }
         Ok(model)
     }
 
@@ -496,20 +705,53 @@ impl QuestionAnsweringOption {
         )?))
     }
 
+    /// Quantizes the linear layers of the model to int8 for CPU inference (see the `quantization` module). Only
+    /// available for BERT, DistilBERT and RoBERTa models.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref mut model_ref) => model_ref.quantize_dynamic(),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref mut model_ref) => model_ref.quantize_dynamic(),
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref mut model_ref) | Self::XLMRoberta(ref mut model_ref) => {
+                model_ref.quantize_dynamic()
+            }
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Dynamic int8 quantization not implemented for {:?}",
+                self.model_type()
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this SequenceClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => ModelType::Deberta,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => ModelType::DebertaV2,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::XLMRoberta,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "reformer")]
             Self::Reformer(_) => ModelType::Reformer,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(_) => ModelType::BigBird,
+            #[cfg(feature = "fnet")]
             Self::FNet(_) => ModelType::FNet,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
@@ -522,66 +764,95 @@ impl QuestionAnsweringOption {
         input_ids: Option<&Tensor>,
         mask: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
-        _token_type_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
         train: bool,
     ) -> (Tensor, Tensor) {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
-                let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
+                let outputs =
+                    model.forward_t(input_ids, mask, token_type_ids, None, input_embeds, train);
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "deberta")]
             Self::Deberta(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, None, None, input_embeds, train)
+                    .forward_t(input_ids, mask, token_type_ids, None, input_embeds, train)
                     .expect("Error in Deberta forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, None, None, input_embeds, train)
+                    .forward_t(input_ids, mask, token_type_ids, None, input_embeds, train)
                     .expect("Error in Deberta V2 forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(ref model) => {
                 let outputs = model
                     .forward_t(input_ids, mask, input_embeds, train)
                     .expect("Error in distilbert forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, None, None, input_embeds, mask, train)
+                    .forward_t(input_ids, token_type_ids, None, input_embeds, mask, train)
                     .expect("Error in mobilebert forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
-                let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
+                let outputs =
+                    model.forward_t(input_ids, mask, token_type_ids, None, input_embeds, train);
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
-                let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
+                let outputs =
+                    model.forward_t(input_ids, mask, token_type_ids, None, input_embeds, train);
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
-                let outputs =
-                    model.forward_t(input_ids, mask, None, None, None, None, input_embeds, train);
+                let outputs = model.forward_t(
+                    input_ids,
+                    mask,
+                    None,
+                    None,
+                    None,
+                    token_type_ids,
+                    input_embeds,
+                    train,
+                );
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "reformer")]
             Self::Reformer(ref model) => {
                 let outputs = model
                     .forward_t(input_ids, None, None, mask, None, train)
                     .expect("Error in reformer forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, None, None, None, None, train)
+                    .forward_t(input_ids, mask, None, token_type_ids, None, None, train)
                     .expect("Error in reformer forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(ref model) => {
+                let outputs = model
+                    .forward_t(input_ids, mask, token_type_ids, None, input_embeds, train)
+                    .expect("Error in BigBird forward pass");
+                (outputs.start_logits, outputs.end_logits)
+            }
+            #[cfg(feature = "fnet")]
             Self::FNet(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, None, None, None, train)
+                    .forward_t(input_ids, token_type_ids, None, None, train)
                     .expect("Error in fnet forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
@@ -591,7 +862,7 @@ impl QuestionAnsweringOption {
                     .forward(
                         input_ids,
                         mask.map(|tensor| tensor.to_kind(Kind::Int64)).as_ref(),
-                        _token_type_ids,
+                        token_type_ids,
                         None,
                         input_embeds,
                     )
@@ -613,6 +884,8 @@ pub struct QuestionAnsweringModel {
     max_answer_len: usize,
     qa_model: QuestionAnsweringOption,
     device: Device,
+    token_type_ids_handler: TokenTypeIdsHandler,
+    attention_fp32_accumulation: bool,
 }
 
 impl QuestionAnsweringModel {
@@ -690,6 +963,14 @@ impl QuestionAnsweringModel {
         let sep_idx = tokenizer
             .get_sep_id()
             .expect("The Tokenizer used for Question Answering should contain a SEP id");
+        let config_path = question_answering_config.config_resource.get_local_path()?;
+        let token_type_ids_handler =
+            question_answering_config
+                .token_type_strategy
+                .resolve(&ConfigOption::from_file(
+                    question_answering_config.model_type,
+                    config_path,
+                ))?;
 
         if question_answering_config.max_seq_length
             < (question_answering_config.max_query_length
@@ -719,6 +1000,10 @@ impl QuestionAnsweringModel {
             max_answer_len: question_answering_config.max_answer_length,
             qa_model,
             device,
+            token_type_ids_handler,
+            attention_fp32_accumulation: question_answering_config
+                .kind
+                .map_or(false, is_half_precision),
         })
     }
 
@@ -732,6 +1017,13 @@ impl QuestionAnsweringModel {
         &mut self.tokenizer
     }
 
+    /// Quantizes the linear layers of the model to int8, with a dynamic quantization of the activations, for faster
+    /// CPU inference. Only available for BERT, DistilBERT and RoBERTa models placed on the CPU (see the `quantization`
+    /// module).
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.qa_model.quantize_dynamic()
+    }
+
     /// Perform extractive question answering given a list of `QaInputs`
     ///
     /// # Arguments
@@ -775,6 +1067,129 @@ impl QuestionAnsweringModel {
         top_k: i64,
         batch_size: usize,
     ) -> Vec<Vec<Answer>> {
+        self.predict_candidates(qa_inputs, top_k, batch_size)
+            .into_iter()
+            .map(|example_candidates| {
+                let mut answers = example_candidates
+                    .candidates
+                    .into_iter()
+                    .map(|candidate| candidate.answer)
+                    .collect::<Vec<Answer>>();
+                remove_duplicates(&mut answers)
+                    .sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+                answers.truncate(top_k as usize);
+                answers
+            })
+            .collect()
+    }
+
+    /// Perform extractive question answering for a question over multiple contexts (e.g. passages retrieved
+    /// from a document collection) in a single call, returning a ranked list of answers across all contexts.
+    ///
+    /// The scores of the answers are normalized jointly over all contexts: the score of an answer is the
+    /// softmax of its span logits (sum of the start and end logits) over all valid spans of all contexts.
+    /// Unlike the scores returned by `predict` (normalized independently for each context), they can be compared
+    /// across contexts, and a context without a plausible answer does not produce high-confidence answers.
+    /// Long contexts split into several windows contribute the spans of all their windows.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - Question to answer
+    /// * `contexts` - Contexts containing the potential answers
+    /// * `top_k` - Number of answers to return across all contexts
+    /// * `batch_size` - maximum batch size for the model forward pass.
+    ///
+    /// # Returns
+    /// * `Vec<ContextAnswer>` Answers ranked by decreasing score, with the index of the context they were extracted from.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::QuestionAnsweringModel;
+    ///
+    /// let qa_model = QuestionAnsweringModel::new(Default::default())?;
+    ///
+    /// let contexts = [
+    ///     "Eric is a software engineer.",
+    ///     "Amy lives in Amsterdam and works as a teacher.",
+    /// ];
+    /// let answers = qa_model.predict_multi_context("Where does Amy live ?", &contexts, 3, 32);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_multi_context<S>(
+        &self,
+        question: &str,
+        contexts: &[S],
+        top_k: usize,
+        batch_size: usize,
+    ) -> Vec<ContextAnswer>
+    where
+        S: AsRef<str>,
+    {
+        let qa_inputs = contexts
+            .iter()
+            .map(|context| QaInput {
+                question: question.to_string(),
+                context: context.as_ref().to_string(),
+            })
+            .collect::<Vec<QaInput>>();
+        let example_candidates = self.predict_candidates(&qa_inputs, top_k as i64, batch_size);
+
+        let log_normalizers = example_candidates
+            .iter()
+            .flat_map(|example| example.log_normalizers.iter().copied())
+            .collect::<Vec<f64>>();
+        let max_log_normalizer = log_normalizers
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if !max_log_normalizer.is_finite() {
+            return vec![];
+        }
+        let global_log_normalizer = max_log_normalizer
+            + log_normalizers
+                .iter()
+                .map(|log_normalizer| (log_normalizer - max_log_normalizer).exp())
+                .sum::<f64>()
+                .ln();
+
+        let mut answers = vec![];
+        for (context_index, example) in example_candidates.into_iter().enumerate() {
+            let mut context_answers: Vec<ContextAnswer> = vec![];
+            for candidate in example.candidates {
+                let score = (candidate.answer.score.ln() + candidate.log_offset
+                    - global_log_normalizer)
+                    .exp();
+                // The same span may be extracted from overlapping windows: keep its best score
+                match context_answers
+                    .iter_mut()
+                    .find(|previous| previous.answer == candidate.answer)
+                {
+                    Some(previous) => previous.answer.score = previous.answer.score.max(score),
+                    None => context_answers.push(ContextAnswer {
+                        answer: Answer {
+                            score,
+                            ..candidate.answer
+                        },
+                        context_index,
+                    }),
+                }
+            }
+            answers.extend(context_answers);
+        }
+        answers.sort_by(|a, b| b.answer.score.partial_cmp(&a.answer.score).unwrap());
+        answers.truncate(top_k);
+        answers
+    }
+
+    fn predict_candidates(
+        &self,
+        qa_inputs: &[QaInput],
+        top_k: i64,
+        batch_size: usize,
+    ) -> Vec<ExampleCandidates> {
         let mut features: Vec<QaFeature> = qa_inputs
             .iter()
             .enumerate()
@@ -789,99 +1204,166 @@ impl QuestionAnsweringModel {
             })
             .collect();
 
-        let mut example_top_k_answers_map: HashMap<usize, Vec<Answer>> = HashMap::new();
+        let mut example_candidates_map: HashMap<usize, ExampleCandidates> = HashMap::new();
         let mut start = 0usize;
         let len_features = features.len();
 
         while start < len_features {
             let end = start + min(len_features - start, batch_size);
             let batch_features = &mut features[start..end];
-            no_grad(|| {
-                let (input_ids, attention_masks, token_type_ids) =
-                    self.pad_features(batch_features);
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                no_grad(|| {
+                    let (input_ids, attention_masks, token_type_ids) =
+                        self.pad_features(batch_features);
 
-                let (start_logits, end_logits) = self.qa_model.forward_t(
-                    Some(&input_ids),
-                    Some(&attention_masks),
-                    None,
-                    Some(&token_type_ids),
-                    false,
-                );
+                    let (start_logits, end_logits) = self.qa_model.forward_t(
+                        Some(&input_ids),
+                        Some(&attention_masks),
+                        None,
+                        Some(&token_type_ids),
+                        false,
+                    );
 
-                let start_logits = start_logits.detach();
-                let end_logits = end_logits.detach();
-                let example_index_to_feature_end_position: Vec<(usize, i64)> = batch_features
-                    .iter()
-                    .enumerate()
-                    .map(|(feature_index, feature)| {
-                        (feature.example_index as usize, feature_index as i64 + 1)
-                    })
-                    .collect();
-
-                let mut feature_id_start = 0;
-
-                for (example_id, max_feature_id) in example_index_to_feature_end_position {
-                    let mut answers: Vec<Answer> = vec![];
-                    let example = &qa_inputs[example_id];
-                    for feature_idx in feature_id_start..max_feature_id {
-                        let feature = &batch_features[feature_idx as usize];
-                        let p_mask = (Tensor::from_slice(&feature.p_mask) - 1)
-                            .abs()
-                            .to_device(start_logits.device())
-                            .eq(0);
-
-                        let start = start_logits.get(feature_idx).masked_fill(&p_mask, -10000);
-                        let end = end_logits.get(feature_idx).masked_fill(&p_mask, -10000);
-
-                        let start = start.exp() / start.exp().sum(Float);
-                        let end = end.exp() / end.exp().sum(Float);
-
-                        let (starts, ends, scores) = self.decode(&start, &end, top_k);
-
-                        for idx in 0..starts.len() {
-                            let start_pos = feature.offsets[starts[idx] as usize]
-                                .unwrap_or(Offset { begin: 0, end: 0 })
-                                .begin as usize;
-                            let end_pos = feature.offsets[ends[idx] as usize]
-                                .unwrap_or(Offset { begin: 0, end: 0 })
-                                .end as usize;
-                            let answer = example
-                                .context
-                                .chars()
-                                .take(end_pos)
-                                .skip(start_pos)
-                                .collect::<String>();
-
-                            answers.push(Answer {
-                                score: scores[idx],
-                                start: start_pos,
-                                end: end_pos,
-                                answer,
-                            });
+                    let start_logits = start_logits.detach();
+                    let end_logits = end_logits.detach();
+                    let example_index_to_feature_end_position: Vec<(usize, i64)> = batch_features
+                        .iter()
+                        .enumerate()
+                        .map(|(feature_index, feature)| {
+                            (feature.example_index as usize, feature_index as i64 + 1)
+                        })
+                        .collect();
+
+                    let mut feature_id_start = 0;
+
+                    for (example_id, max_feature_id) in example_index_to_feature_end_position {
+                        let mut candidates: Vec<AnswerCandidate> = vec![];
+                        let mut log_normalizers: Vec<f64> = vec![];
+                        let example = &qa_inputs[example_id];
+                        for feature_idx in feature_id_start..max_feature_id {
+                            let feature = &batch_features[feature_idx as usize];
+                            let p_mask = (Tensor::from_slice(&feature.p_mask) - 1)
+                                .abs()
+                                .to_device(start_logits.device())
+                                .eq(0);
+
+                            let start = start_logits.get(feature_idx).masked_fill(&p_mask, -10000);
+                            let end = end_logits.get(feature_idx).masked_fill(&p_mask, -10000);
+
+                            let log_offset = start.logsumexp([0], false).double_value(&[])
+                                + end.logsumexp([0], false).double_value(&[]);
+
+                            let start = start.exp() / start.exp().sum(Float);
+                            let end = end.exp() / end.exp().sum(Float);
+
+                            let (starts, ends, scores, valid_mass) =
+                                self.decode(&start, &end, top_k);
+                            log_normalizers.push(log_offset + valid_mass.ln());
+
+                            for idx in 0..starts.len() {
+                                let start_pos = feature.offsets[starts[idx] as usize]
+                                    .unwrap_or(Offset { begin: 0, end: 0 })
+                                    .begin as usize;
+                                let end_pos = feature.offsets[ends[idx] as usize]
+                                    .unwrap_or(Offset { begin: 0, end: 0 })
+                                    .end as usize;
+                                let answer = example
+                                    .context
+                                    .chars()
+                                    .take(end_pos)
+                                    .skip(start_pos)
+                                    .collect::<String>();
+
+                                candidates.push(AnswerCandidate {
+                                    answer: Answer {
+                                        score: scores[idx],
+                                        start: start_pos,
+                                        end: end_pos,
+                                        answer,
+                                    },
+                                    log_offset,
+                                });
+                            }
                         }
+                        feature_id_start = max_feature_id;
+                        let example_candidates = example_candidates_map
+                            .entry(example_id)
+                            .or_insert_with(ExampleCandidates::default);
+                        example_candidates.candidates.extend(candidates);
+                        example_candidates.log_normalizers.extend(log_normalizers);
                     }
-                    feature_id_start = max_feature_id;
-                    let example_answers = example_top_k_answers_map
-                        .entry(example_id)
-                        .or_insert_with(Vec::new);
-                    example_answers.extend(answers);
-                }
+                })
             });
             start = end;
         }
-        let mut all_answers = vec![];
-        for example_id in 0..qa_inputs.len() {
-            if let Some(answers) = example_top_k_answers_map.get_mut(&example_id) {
-                remove_duplicates(answers).sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-                all_answers.push(answers[..min(answers.len(), top_k as usize)].to_vec());
-            } else {
-                all_answers.push(vec![]);
-            }
-        }
-        all_answers
+        (0..qa_inputs.len())
+            .map(|example_id| {
+                example_candidates_map
+                    .remove(&example_id)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Perform extractive question answering given a list of `QaInputs`, returning the answers with the
+    /// sentence(s) of the context containing them and the neighboring sentences, e.g. to display the evidence
+    /// supporting an answer.
+    ///
+    /// # Arguments
+    ///
+    /// * `qa_inputs` - `&[QaInput]` Array of Question Answering inputs (context and question pairs)
+    /// * `top_k` - return the top-k answers for each QaInput. Set to 1 to return only the best answer.
+    /// * `batch_size` - maximum batch size for the model forward pass.
+    /// * `context_sentences` - number of sentences before and after the evidence included in the surrounding context.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<AnswerEvidence>>` Vector (same length as `qa_inputs`) of vectors (each of length `top_k`) containing the extracted answers and their evidence.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
+    ///
+    /// let qa_model = QuestionAnsweringModel::new(Default::default())?;
+    ///
+    /// let qa_input = QaInput {
+    ///     question: String::from("Where does Amy live ?"),
+    ///     context: String::from("Amy is a teacher. She lives in Amsterdam. She has two cats."),
+    /// };
+    /// let answers = qa_model.predict_with_evidence(&[qa_input], 1, 32, 1);
+    /// let highlighted = answers[0][0].highlight("<b>", "</b>");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_evidence(
+        &self,
+        qa_inputs: &[QaInput],
+        top_k: i64,
+        batch_size: usize,
+        context_sentences: usize,
+    ) -> Vec<Vec<AnswerEvidence>> {
+        qa_inputs
+            .iter()
+            .zip(self.predict(qa_inputs, top_k, batch_size))
+            .map(|(qa_input, answers)| {
+                let sentences = sentence_character_ranges(&qa_input.context);
+                answers
+                    .into_iter()
+                    .map(|answer| {
+                        answer_evidence(&qa_input.context, &sentences, answer, context_sentences)
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
-    fn decode(&self, start: &Tensor, end: &Tensor, top_k: i64) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
+    fn decode(
+        &self,
+        start: &Tensor,
+        end: &Tensor,
+        top_k: i64,
+    ) -> (Vec<i64>, Vec<i64>, Vec<f64>, f64) {
         let outer = start.unsqueeze(-1).matmul(&end.unsqueeze(0));
         let start_dim = start.size()[0];
         let end_dim = end.size()[0];
@@ -905,7 +1387,10 @@ impl QuestionAnsweringModel {
             start.push(flat_index / start_dim);
             end.push(flat_index % end_dim);
         }
-        (start, end, scores)
+        let valid_mass = mps::to_double(&candidates)
+            .sum(Kind::Double)
+            .double_value(&[]);
+        (start, end, scores, valid_mass)
     }
 
     fn generate_features(
@@ -1032,9 +1517,11 @@ impl QuestionAnsweringModel {
 
         let input_ids = Tensor::stack(&padded_input_ids, 0).to(self.device);
         let attention_masks = Tensor::stack(&attention_masks, 0).to(self.device);
-        let token_type_ids = Tensor::stack(&padded_token_type_ids, 0)
-            .to(self.device)
-            .to_kind(Kind::Int64);
+        let token_type_ids = self.token_type_ids_handler.apply(
+            Tensor::stack(&padded_token_type_ids, 0)
+                .to(self.device)
+                .to_kind(Kind::Int64),
+        );
         (input_ids, attention_masks, token_type_ids)
     }
 
@@ -0,0 +1,311 @@
+// Copyright 2019-present, the HuggingFace Inc. team, The Google AI Language Team and Facebook, Inc.
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Reranking pipeline
+//! Scores the relevance of a set of passages for a query using a cross-encoder, and returns the passages
+//! sorted by decreasing relevance. This is typically used as a second stage after a fast retrieval step
+//! (e.g. dense retrieval with sentence embeddings).
+//! By default, the dependencies for this model will be downloaded for a compact MiniLM-L6 cross-encoder
+//! distilled on MS MARCO, suitable for low-latency CPU inference.
+//! Customized BERT-like models can be loaded by overwriting the resources in the configuration.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::reranking::RerankingModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let reranking_model = RerankingModel::new(Default::default())?;
+//!
+//! let query = "How many people live in Berlin?";
+//! let passages = [
+//!     "New York City is famous for the Metropolitan Museum of Art.",
+//!     "Berlin has a population of 3,520,031 registered inhabitants in an area of 891.82 square kilometers.",
+//! ];
+//! let output = reranking_model.rerank(query, &passages);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::reranking::RankedPassage;
+//! let output = [
+//!     RankedPassage { index: 1, score: 0.9998 },
+//!     RankedPassage { index: 0, score: 0.0000 },
+//! ]
+//! # ;
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenTypeStrategy, TokenizerOption};
+use crate::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
+use tch::Device;
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+use crate::{
+    bert::{BertConfigResources, BertModelResources, BertVocabResources},
+    resources::RemoteResource,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Passage scored by a `RerankingModel`
+pub struct RankedPassage {
+    /// Index of the passage in the input
+    pub index: usize,
+    /// Relevance score of the passage for the query
+    pub score: f64,
+}
+
+/// # Configuration for RerankingModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct RerankingConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: MiniLM-L6 cross-encoder trained on MS MARCO)
+    pub model_resource: ModelResource,
+    /// Config resource (default: MiniLM-L6 cross-encoder trained on MS MARCO)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: MiniLM-L6 cross-encoder trained on MS MARCO)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+    /// Token type ids passed to the model for the sentence pairs (default: depending on the architecture)
+    pub token_type_strategy: TokenTypeStrategy,
+}
+
+impl RerankingConfig {
+    /// Instantiate a new reranking configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RC, RV>(
+        model_type: ModelType,
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: Option<RV>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> RerankingConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        RerankingConfig {
+            model_type,
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: merges_resource.map(|r| Box::new(r) as Box<_>),
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            device: default_device(),
+            token_type_strategy: TokenTypeStrategy::default(),
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+impl Default for RerankingConfig {
+    /// Provides a default MiniLM-L6 cross-encoder trained on MS MARCO (English)
+    fn default() -> RerankingConfig {
+        RerankingConfig::new(
+            ModelType::Bert,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                BertModelResources::MS_MARCO_MINI_LM_L6_V2,
+            ))),
+            RemoteResource::from_pretrained(BertConfigResources::MS_MARCO_MINI_LM_L6_V2),
+            RemoteResource::from_pretrained(BertVocabResources::MS_MARCO_MINI_LM_L6_V2),
+            None,
+            true,
+            None,
+            None,
+        )
+    }
+}
+
+impl From<RerankingConfig> for SequenceClassificationConfig {
+    fn from(config: RerankingConfig) -> Self {
+        SequenceClassificationConfig {
+            model_type: config.model_type,
+            model_resource: config.model_resource,
+            config_resource: config.config_resource,
+            vocab_resource: config.vocab_resource,
+            merges_resource: config.merges_resource,
+            lower_case: config.lower_case,
+            strip_accents: config.strip_accents,
+            add_prefix_space: config.add_prefix_space,
+            device: config.device,
+            kind: None,
+            token_type_strategy: config.token_type_strategy,
+        }
+    }
+}
+
+/// # RerankingModel to sort passages by relevance for a query
+pub struct RerankingModel {
+    sequence_classification_model: SequenceClassificationModel,
+}
+
+impl RerankingModel {
+    /// Build a new `RerankingModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `reranking_config` - `RerankingConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::reranking::RerankingModel;
+    ///
+    /// let reranking_model = RerankingModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(reranking_config: RerankingConfig) -> Result<RerankingModel, RustBertError> {
+        let sequence_classification_model =
+            SequenceClassificationModel::new(reranking_config.into())?;
+        Ok(RerankingModel {
+            sequence_classification_model,
+        })
+    }
+
+    /// Build a new `RerankingModel` with a provided tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `reranking_config` - `RerankingConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer to use for reranking.
+    pub fn new_with_tokenizer(
+        reranking_config: RerankingConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<RerankingModel, RustBertError> {
+        let sequence_classification_model =
+            SequenceClassificationModel::new_with_tokenizer(reranking_config.into(), tokenizer)?;
+        Ok(RerankingModel {
+            sequence_classification_model,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.sequence_classification_model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.sequence_classification_model.get_tokenizer_mut()
+    }
+
+    /// Scores the relevance of each passage for the query, preserving the input order.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query text
+    /// * `passages` - Candidate passages to score
+    ///
+    /// # Returns
+    /// * `Vec<f64>` Relevance score for each passage. Single-logit cross-encoders return a sigmoid-normalized score,
+    ///   models with a classification head return the probability of their last label.
+    pub fn score<S>(&self, query: &str, passages: &[S]) -> Vec<f64>
+    where
+        S: AsRef<str>,
+    {
+        let pairs = passages
+            .iter()
+            .map(|passage| (query, passage.as_ref()))
+            .collect::<Vec<(&str, &str)>>();
+        self.sequence_classification_model
+            .predict_pairs(&pairs)
+            .into_iter()
+            .map(|label| {
+                // Binary relevance classifiers predict the positive (last) class with probability `score`
+                if label.id == 0 && self.sequence_classification_model.num_labels() == 2 {
+                    1.0 - label.score
+                } else {
+                    label.score
+                }
+            })
+            .collect()
+    }
+
+    /// Reranks passages by decreasing relevance for the query
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query text
+    /// * `passages` - Candidate passages to rerank
+    ///
+    /// # Returns
+    /// * `Vec<RankedPassage>` Passages indices and scores, sorted by decreasing relevance
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::reranking::RerankingModel;
+    ///
+    /// let reranking_model = RerankingModel::new(Default::default())?;
+    /// let ranked_passages = reranking_model.rerank(
+    ///     "How many people live in Berlin?",
+    ///     &["Berlin is well known for its museums.", "Berlin has 3.5M inhabitants."],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rerank<S>(&self, query: &str, passages: &[S]) -> Vec<RankedPassage>
+    where
+        S: AsRef<str>,
+    {
+        let mut ranked_passages = self
+            .score(query, passages)
+            .into_iter()
+            .enumerate()
+            .map(|(index, score)| RankedPassage { index, score })
+            .collect::<Vec<RankedPassage>>();
+        ranked_passages.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        ranked_passages
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = RerankingConfig::default();
+        let _: Box<dyn Send> = Box::new(RerankingModel::new(config));
+    }
+}
@@ -0,0 +1,892 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Resume and job posting extraction pipeline
+//! Extracts structured information from resumes and job postings by combining composable extraction primitives:
+//! - a `SectionSegmenter`, splitting the document into sections (experience, education, skills...) based on a
+//! dictionary of headings
+//! - `NormalizationDictionary` objects, matching skills, job titles and degrees and mapping their aliases to a
+//! canonical form (e.g. `k8s` to `Kubernetes`)
+//! - `RegexDetector` objects from the PII pipeline, detecting contact details (emails and phone numbers)
+//! - an optional named entity recognition model, run on each section and detecting person names, organizations and
+//! locations
+//!
+//! Every extracted field references the section it was found in. Dictionary and regular expression matches take
+//! precedence over the overlapping NER entities.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::resume_extraction::{FieldType, ResumeExtractionModel};
+//! # fn main() -> anyhow::Result<()> {
+//! let extraction_model = ResumeExtractionModel::new(Default::default())?;
+//!
+//! let input = ["Amy Smith\namy@example.com\n\nExperience\nSenior SWE at Acme, Paris\n\nSkills\nRust, k8s, PostgreSQL"];
+//! let output = extraction_model.extract(&input);
+//! let skills = output[0].normalized_values(&FieldType::Skill);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # let skills =
+//! ["Rust", "Kubernetes", "PostgreSQL"]
+//! # ;
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::ner::NERModel;
+use crate::pipelines::pii::{PiiEntityType, RegexDetector};
+use crate::pipelines::token_classification::TokenClassificationConfig;
+use rust_tokenizers::Offset;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// # Type of a document section
+pub enum SectionKind {
+    /// Text preceding the first heading (usually the name and contact details of a resume)
+    Header,
+    /// Summary or profile of the candidate, or description of the company
+    Summary,
+    /// Work experience
+    Experience,
+    /// Education
+    Education,
+    /// Skills
+    Skills,
+    /// Certifications
+    Certifications,
+    /// Projects
+    Projects,
+    /// Spoken languages
+    Languages,
+    /// Responsibilities of a job posting
+    Responsibilities,
+    /// Requirements of a job posting
+    Requirements,
+    /// Benefits of a job posting
+    Benefits,
+    /// Custom section type
+    Custom(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Section of a document
+pub struct Section {
+    /// Type of the section
+    pub kind: SectionKind,
+    /// Heading of the section, as written in the document (`None` for the header)
+    pub heading: Option<String>,
+    /// Text of the section, excluding the heading
+    pub text: String,
+    /// Character offsets of the section text in the document
+    pub offset: Offset,
+}
+
+/// # Segmentation of documents into sections
+/// Lines matching a heading of the dictionary (ignoring the case, surrounding punctuation and a trailing colon)
+/// start a new section. A heading followed by a colon may be followed by the section text on the same line
+/// (e.g. `Skills: Rust, Python`).
+#[derive(Debug, Clone)]
+pub struct SectionSegmenter {
+    headings: HashMap<String, SectionKind>,
+    max_heading_words: usize,
+}
+
+impl SectionSegmenter {
+    /// Build a new `SectionSegmenter` without any heading
+    ///
+    /// # Arguments
+    ///
+    /// * `max_heading_words` - Maximum number of words of a heading line
+    pub fn new(max_heading_words: usize) -> SectionSegmenter {
+        SectionSegmenter {
+            headings: HashMap::new(),
+            max_heading_words,
+        }
+    }
+
+    /// Adds a heading to the dictionary, replacing the section type of an existing identical heading
+    ///
+    /// # Arguments
+    ///
+    /// * `heading` - Heading text (case-insensitive)
+    /// * `kind` - `SectionKind` of the sections starting with this heading
+    pub fn add_heading(&mut self, heading: &str, kind: SectionKind) {
+        self.headings.insert(normalize_term(heading), kind);
+    }
+
+    /// Splits a document into sections. Empty sections are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Document to segment
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Section>` sections of the document, sorted by position
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::resume_extraction::SectionSegmenter;
+    ///
+    /// let sections = SectionSegmenter::default().segment("Amy Smith\nEducation\nMSc, ETH Zurich");
+    /// ```
+    pub fn segment(&self, text: &str) -> Vec<Section> {
+        let characters = text.chars().collect::<Vec<char>>();
+        let mut sections = Vec::new();
+        let mut current: (SectionKind, Option<String>, usize) = (SectionKind::Header, None, 0);
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            let line_length = line.chars().count();
+            if let Some((kind, heading, body_start)) = self.heading(line) {
+                push_section(&mut sections, &characters, current, line_start);
+                current = (kind, Some(heading), line_start + body_start);
+            }
+            line_start += line_length;
+        }
+        push_section(&mut sections, &characters, current, characters.len());
+        sections
+    }
+
+    /// Returns the section type, heading and character position of the section text in the line if the line is a
+    /// heading
+    fn heading(&self, line: &str) -> Option<(SectionKind, String, usize)> {
+        let (heading, body_start) = match line.find(':') {
+            Some(position) => (&line[..position], line[..=position].chars().count()),
+            None => (line, line.chars().count()),
+        };
+        if heading.split_whitespace().count() > self.max_heading_words {
+            return None;
+        }
+        let kind = self.headings.get(&normalize_term(heading))?;
+        Some((kind.clone(), heading.trim().to_string(), body_start))
+    }
+}
+
+impl Default for SectionSegmenter {
+    /// Provides a segmenter with the common headings of resumes and job postings (in English)
+    fn default() -> SectionSegmenter {
+        let mut segmenter = SectionSegmenter::new(5);
+        let headings = [
+            (
+                SectionKind::Summary,
+                &[
+                    "summary",
+                    "profile",
+                    "professional summary",
+                    "about me",
+                    "objective",
+                    "about us",
+                    "about the company",
+                    "about the role",
+                ][..],
+            ),
+            (
+                SectionKind::Experience,
+                &[
+                    "experience",
+                    "work experience",
+                    "professional experience",
+                    "employment history",
+                    "work history",
+                    "career history",
+                ],
+            ),
+            (
+                SectionKind::Education,
+                &["education", "academic background", "qualifications"],
+            ),
+            (
+                SectionKind::Skills,
+                &[
+                    "skills",
+                    "technical skills",
+                    "core competencies",
+                    "competencies",
+                    "technologies",
+                    "tech stack",
+                ],
+            ),
+            (
+                SectionKind::Certifications,
+                &["certifications", "certificates", "licenses"],
+            ),
+            (
+                SectionKind::Projects,
+                &["projects", "personal projects", "selected projects"],
+            ),
+            (SectionKind::Languages, &["languages", "spoken languages"]),
+            (
+                SectionKind::Responsibilities,
+                &[
+                    "responsibilities",
+                    "what you will do",
+                    "what you'll do",
+                    "your role",
+                    "the role",
+                ],
+            ),
+            (
+                SectionKind::Requirements,
+                &[
+                    "requirements",
+                    "qualifications required",
+                    "what we are looking for",
+                    "what we're looking for",
+                    "who you are",
+                    "nice to have",
+                ],
+            ),
+            (
+                SectionKind::Benefits,
+                &["benefits", "perks", "what we offer", "why join us"],
+            ),
+        ];
+        for (kind, section_headings) in headings {
+            for heading in section_headings {
+                segmenter.add_heading(heading, kind.clone());
+            }
+        }
+        segmenter
+    }
+}
+
+fn push_section(
+    sections: &mut Vec<Section>,
+    characters: &[char],
+    (kind, heading, begin): (SectionKind, Option<String>, usize),
+    end: usize,
+) {
+    let mut begin = begin;
+    let mut end = end;
+    while begin < end && characters[begin].is_whitespace() {
+        begin += 1;
+    }
+    while end > begin && characters[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    if begin < end {
+        sections.push(Section {
+            kind,
+            heading,
+            text: characters[begin..end].iter().collect(),
+            offset: Offset {
+                begin: begin as u32,
+                end: end as u32,
+            },
+        });
+    }
+}
+
+/// Lower cases a term and removes the surrounding punctuation and redundant whitespaces
+fn normalize_term(term: &str) -> String {
+    term.trim_matches(|character: char| !character.is_alphanumeric())
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// # Normalization dictionary
+/// Maps the aliases of terms (case-insensitive) to their canonical form, e.g. `k8s` and `kubernetes` to
+/// `Kubernetes`. The canonical form of a term is also one of its aliases.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizationDictionary {
+    aliases: HashMap<String, String>,
+}
+
+impl NormalizationDictionary {
+    /// Build a new empty `NormalizationDictionary`
+    pub fn new() -> NormalizationDictionary {
+        Default::default()
+    }
+
+    /// Adds a term and its aliases to the dictionary. Aliases already present are re-assigned to this term.
+    ///
+    /// # Arguments
+    ///
+    /// * `canonical` - Canonical form of the term
+    /// * `aliases` - Alternative spellings and abbreviations of the term
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::resume_extraction::NormalizationDictionary;
+    ///
+    /// let mut skills = NormalizationDictionary::new();
+    /// skills.add("Kubernetes", &["k8s"]);
+    /// assert_eq!(skills.normalize("K8s"), Some("Kubernetes"));
+    /// ```
+    pub fn add(&mut self, canonical: &str, aliases: &[&str]) {
+        for alias in std::iter::once(&canonical).chain(aliases) {
+            let alias = alias.trim().to_lowercase();
+            if !alias.is_empty() {
+                self.aliases.insert(alias, canonical.to_string());
+            }
+        }
+    }
+
+    /// Returns the canonical form of a term, `None` if the term is not in the dictionary
+    pub fn normalize(&self, term: &str) -> Option<&str> {
+        self.aliases
+            .get(&term.trim().to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Returns true if the dictionary contains no term
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Finds the occurrences of the dictionary terms in a text. Matches are case-insensitive and must not be
+    /// preceded or followed by an alphanumeric character. The longest match is kept among overlapping matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to search
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(String, Offset)>` canonical form and character offsets of the matches, sorted by position
+    pub fn find_all(&self, text: &str) -> Vec<(String, Offset)> {
+        // Lower casing character by character keeps the character offsets of the text
+        let characters = text
+            .chars()
+            .map(|character| character.to_lowercase().next().unwrap_or(character))
+            .collect::<Vec<char>>();
+        let mut matches = Vec::new();
+        for (alias, canonical) in &self.aliases {
+            let alias = alias.chars().collect::<Vec<char>>();
+            if alias.len() > characters.len() {
+                continue;
+            }
+            for begin in 0..=characters.len() - alias.len() {
+                let end = begin + alias.len();
+                if characters[begin..end] == alias[..]
+                    && !(alias[0].is_alphanumeric()
+                        && begin > 0
+                        && characters[begin - 1].is_alphanumeric())
+                    && !(alias[alias.len() - 1].is_alphanumeric()
+                        && end < characters.len()
+                        && characters[end].is_alphanumeric())
+                {
+                    matches.push((
+                        canonical.clone(),
+                        Offset {
+                            begin: begin as u32,
+                            end: end as u32,
+                        },
+                    ));
+                }
+            }
+        }
+        matches.sort_by(|(_, a), (_, b)| {
+            a.begin
+                .cmp(&b.begin)
+                .then((b.end - b.begin).cmp(&(a.end - a.begin)))
+        });
+        let mut output: Vec<(String, Offset)> = Vec::with_capacity(matches.len());
+        for (canonical, offset) in matches {
+            match output.last() {
+                Some((_, previous)) if offset.begin < previous.end => {}
+                _ => output.push((canonical, offset)),
+            }
+        }
+        output
+    }
+
+    /// Returns a dictionary of common technical and professional skills
+    pub fn default_skills() -> NormalizationDictionary {
+        let mut dictionary = NormalizationDictionary::new();
+        for (canonical, aliases) in [
+            ("Rust", &["rustlang"][..]),
+            ("Python", &["python3"]),
+            ("Java", &[]),
+            ("JavaScript", &["js", "ecmascript"]),
+            ("TypeScript", &[]),
+            ("C++", &["cpp"]),
+            ("C#", &["csharp"]),
+            ("SQL", &[]),
+            ("PostgreSQL", &["postgres", "psql"]),
+            ("MySQL", &[]),
+            ("MongoDB", &["mongo"]),
+            ("Docker", &[]),
+            ("Kubernetes", &["k8s"]),
+            ("Amazon Web Services", &["aws"]),
+            ("Google Cloud Platform", &["gcp", "google cloud"]),
+            ("Microsoft Azure", &["azure"]),
+            ("Linux", &[]),
+            ("Git", &[]),
+            ("React", &["reactjs", "react.js"]),
+            ("Node.js", &["nodejs"]),
+            (
+                "Machine Learning",
+                &["ml", "machine-learning", "statistical learning"],
+            ),
+            ("Deep Learning", &[]),
+            ("Natural Language Processing", &["nlp"]),
+            ("Computer Vision", &[]),
+            ("PyTorch", &[]),
+            ("TensorFlow", &[]),
+            ("Data Analysis", &["data analytics"]),
+            ("Project Management", &[]),
+            ("Agile", &["scrum", "kanban"]),
+            ("Continuous Integration", &["ci/cd"]),
+        ] {
+            dictionary.add(canonical, aliases);
+        }
+        dictionary
+    }
+
+    /// Returns a dictionary of common job titles
+    pub fn default_job_titles() -> NormalizationDictionary {
+        let mut dictionary = NormalizationDictionary::new();
+        for (canonical, aliases) in [
+            (
+                "Software Engineer",
+                &[
+                    "swe",
+                    "software developer",
+                    "software development engineer",
+                    "programmer",
+                ][..],
+            ),
+            (
+                "Senior Software Engineer",
+                &[
+                    "senior swe",
+                    "senior software developer",
+                    "sr. software engineer",
+                ],
+            ),
+            ("Data Scientist", &[]),
+            ("Data Engineer", &[]),
+            ("Machine Learning Engineer", &["ml engineer", "mle"]),
+            (
+                "DevOps Engineer",
+                &["site reliability engineer", "sre", "devops"],
+            ),
+            (
+                "Frontend Developer",
+                &["front-end developer", "frontend engineer"],
+            ),
+            (
+                "Backend Developer",
+                &["back-end developer", "backend engineer"],
+            ),
+            (
+                "Full Stack Developer",
+                &["full-stack developer", "fullstack developer"],
+            ),
+            ("Product Manager", &["product owner"]),
+            ("Project Manager", &[]),
+            ("Engineering Manager", &[]),
+            ("Chief Technology Officer", &["cto"]),
+            ("Research Scientist", &["research engineer"]),
+            ("Business Analyst", &[]),
+            ("UX Designer", &["ui/ux designer", "product designer"]),
+        ] {
+            dictionary.add(canonical, aliases);
+        }
+        dictionary
+    }
+
+    /// Returns a dictionary of common academic degrees
+    pub fn default_degrees() -> NormalizationDictionary {
+        let mut dictionary = NormalizationDictionary::new();
+        for (canonical, aliases) in [
+            (
+                "Bachelor",
+                &["bachelor's degree", "bsc", "b.sc.", "b.a.", "b.s."][..],
+            ),
+            (
+                "Master",
+                &["master's degree", "msc", "m.sc.", "m.a.", "m.s.", "meng"],
+            ),
+            ("MBA", &["master of business administration"]),
+            ("PhD", &["ph.d.", "doctorate", "doctor of philosophy"]),
+        ] {
+            dictionary.add(canonical, aliases);
+        }
+        dictionary
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// # Type of an extracted field
+pub enum FieldType {
+    /// Skill (dictionary)
+    Skill,
+    /// Job title (dictionary)
+    JobTitle,
+    /// Academic degree (dictionary)
+    Degree,
+    /// Email address (regular expression)
+    Email,
+    /// Phone number (regular expression)
+    PhoneNumber,
+    /// Person name (NER)
+    Person,
+    /// Organization (NER)
+    Organization,
+    /// Location (NER)
+    Location,
+    /// Custom field type (e.g. detected with a custom regular expression)
+    Custom(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Extractor having found a field
+pub enum ExtractionSource {
+    /// Normalization dictionary
+    Dictionary,
+    /// Regular expression
+    Regex,
+    /// Named entity recognition model
+    Ner,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Field extracted from a document
+pub struct ExtractedField {
+    /// Type of the field
+    pub field_type: FieldType,
+    /// Original text of the field
+    pub text: String,
+    /// Canonical form of the field (for the dictionary matches)
+    pub normalized: Option<String>,
+    /// Character offsets of the field in the document
+    pub offset: Offset,
+    /// Type of the section containing the field
+    pub section: SectionKind,
+    /// Confidence score (1.0 for dictionaries and regular expressions)
+    pub score: f64,
+    /// Extractor having found the field
+    pub source: ExtractionSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Output of the resume extraction pipeline
+pub struct ResumeExtraction {
+    /// Sections of the document
+    pub sections: Vec<Section>,
+    /// Fields extracted from the document, sorted by position
+    pub fields: Vec<ExtractedField>,
+}
+
+impl ResumeExtraction {
+    /// Returns the fields of a given type
+    pub fn fields_of_type(&self, field_type: &FieldType) -> impl Iterator<Item = &ExtractedField> {
+        let field_type = field_type.clone();
+        self.fields
+            .iter()
+            .filter(move |field| field.field_type == field_type)
+    }
+
+    /// Returns the distinct values of the fields of a given type, in order of first occurrence. The canonical form
+    /// is used for the dictionary matches.
+    pub fn normalized_values(&self, field_type: &FieldType) -> Vec<&str> {
+        let mut values: Vec<&str> = Vec::new();
+        for field in self.fields_of_type(field_type) {
+            let value = field.normalized.as_deref().unwrap_or(&field.text);
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        values
+    }
+
+    /// Returns the first section of a given type, if any
+    pub fn section(&self, kind: &SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|section| &section.kind == kind)
+    }
+}
+
+/// # Configuration for ResumeExtractionModel
+/// Contains the configuration of the NER model (if any), the section segmenter, the normalization dictionaries and
+/// the regular expression detectors.
+pub struct ResumeExtractionConfig {
+    /// Configuration of the NER model used to detect names, organizations and locations. No NER model is used if `None`
+    pub ner_config: Option<TokenClassificationConfig>,
+    /// Section segmenter (default: `SectionSegmenter::default()`)
+    pub segmenter: SectionSegmenter,
+    /// Skills dictionary (default: `NormalizationDictionary::default_skills()`)
+    pub skills: NormalizationDictionary,
+    /// Job titles dictionary (default: `NormalizationDictionary::default_job_titles()`)
+    pub job_titles: NormalizationDictionary,
+    /// Degrees dictionary (default: `NormalizationDictionary::default_degrees()`)
+    pub degrees: NormalizationDictionary,
+    /// Regular expression detectors (default: email and phone number detectors of the PII pipeline)
+    pub regex_detectors: Vec<RegexDetector>,
+    /// Minimum score of the NER entities to extract (default: 0.5)
+    pub min_ner_score: f64,
+}
+
+impl ResumeExtractionConfig {
+    /// Instantiate a new resume extraction configuration with the default segmenter, dictionaries and detectors.
+    ///
+    /// # Arguments
+    ///
+    /// * `ner_config` - Optional `TokenClassificationConfig` for the NER model (no NER entities are extracted if `None`)
+    pub fn new(ner_config: Option<TokenClassificationConfig>) -> ResumeExtractionConfig {
+        ResumeExtractionConfig {
+            ner_config,
+            segmenter: SectionSegmenter::default(),
+            skills: NormalizationDictionary::default_skills(),
+            job_titles: NormalizationDictionary::default_job_titles(),
+            degrees: NormalizationDictionary::default_degrees(),
+            regex_detectors: RegexDetector::default_detectors()
+                .into_iter()
+                .filter(|detector| {
+                    matches!(
+                        detector.entity_type(),
+                        PiiEntityType::Email | PiiEntityType::PhoneNumber
+                    )
+                })
+                .collect(),
+            min_ner_score: 0.5,
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+impl Default for ResumeExtractionConfig {
+    /// Provides the default NER model combined with the default segmenter, dictionaries and detectors
+    fn default() -> ResumeExtractionConfig {
+        ResumeExtractionConfig::new(Some(TokenClassificationConfig::default()))
+    }
+}
+
+/// # ResumeExtractionModel to extract structured information from resumes and job postings
+pub struct ResumeExtractionModel {
+    ner_model: Option<NERModel>,
+    segmenter: SectionSegmenter,
+    dictionaries: Vec<(FieldType, NormalizationDictionary)>,
+    regex_detectors: Vec<RegexDetector>,
+    min_ner_score: f64,
+}
+
+impl ResumeExtractionModel {
+    /// Build a new `ResumeExtractionModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `extraction_config` - `ResumeExtractionConfig` object containing the NER model configuration, segmenter,
+    /// dictionaries and detectors
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::resume_extraction::ResumeExtractionModel;
+    ///
+    /// let extraction_model = ResumeExtractionModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        extraction_config: ResumeExtractionConfig,
+    ) -> Result<ResumeExtractionModel, RustBertError> {
+        let ner_model = extraction_config
+            .ner_config
+            .map(NERModel::new)
+            .transpose()?;
+        Ok(ResumeExtractionModel {
+            ner_model,
+            segmenter: extraction_config.segmenter,
+            dictionaries: vec![
+                (FieldType::Skill, extraction_config.skills),
+                (FieldType::JobTitle, extraction_config.job_titles),
+                (FieldType::Degree, extraction_config.degrees),
+            ],
+            regex_detectors: extraction_config.regex_detectors,
+            min_ner_score: extraction_config.min_ner_score,
+        })
+    }
+
+    /// Extracts the sections and fields of documents
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of resumes or job postings to process.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<ResumeExtraction>` sections and fields of each document
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::resume_extraction::ResumeExtractionModel;
+    ///
+    /// let extraction_model = ResumeExtractionModel::new(Default::default())?;
+    /// let output = extraction_model.extract(&["Requirements: 3+ years of Python and AWS"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract<S>(&self, input: &[S]) -> Vec<ResumeExtraction>
+    where
+        S: AsRef<str>,
+    {
+        let mut outputs = input
+            .iter()
+            .map(|text| self.extract_rule_based(text.as_ref()))
+            .collect::<Vec<ResumeExtraction>>();
+
+        if let Some(ner_model) = &self.ner_model {
+            // The NER model processes sections rather than documents, limiting truncation for long documents
+            let sections = outputs
+                .iter()
+                .enumerate()
+                .flat_map(|(document_index, output)| {
+                    output
+                        .sections
+                        .iter()
+                        .map(move |section| (document_index, section))
+                })
+                .collect::<Vec<(usize, &Section)>>();
+            let section_texts = sections
+                .iter()
+                .map(|(_, section)| section.text.as_str())
+                .collect::<Vec<&str>>();
+            let entities = ner_model.predict_full_entities(&section_texts);
+            let mut ner_fields = vec![Vec::new(); outputs.len()];
+            for ((document_index, section), section_entities) in sections.iter().zip(entities) {
+                ner_fields[*document_index].extend(section_entities.into_iter().filter_map(
+                    |entity| {
+                        let field_type = ner_field_type(&entity.label)?;
+                        if entity.score < self.min_ner_score {
+                            return None;
+                        }
+                        Some(ExtractedField {
+                            field_type,
+                            text: entity.word,
+                            normalized: None,
+                            offset: Offset {
+                                begin: section.offset.begin + entity.offset.begin,
+                                end: section.offset.begin + entity.offset.end,
+                            },
+                            section: section.kind.clone(),
+                            score: entity.score,
+                            source: ExtractionSource::Ner,
+                        })
+                    },
+                ));
+            }
+            for (output, fields) in outputs.iter_mut().zip(ner_fields) {
+                for field in fields {
+                    if !output
+                        .fields
+                        .iter()
+                        .any(|existing| overlaps(&existing.offset, &field.offset))
+                    {
+                        output.fields.push(field);
+                    }
+                }
+                output.fields.sort_by_key(|field| field.offset.begin);
+            }
+        }
+        outputs
+    }
+
+    /// Segments a document and extracts the dictionary and regular expression fields
+    fn extract_rule_based(&self, text: &str) -> ResumeExtraction {
+        let sections = self.segmenter.segment(text);
+        let mut fields = Vec::new();
+        for section in &sections {
+            let mut section_fields = Vec::new();
+            for detector in &self.regex_detectors {
+                section_fields.extend(detector.detect(&section.text).into_iter().map(|span| {
+                    ExtractedField {
+                        field_type: regex_field_type(&span.entity_type),
+                        text: span.text,
+                        normalized: None,
+                        offset: span.offset,
+                        section: section.kind.clone(),
+                        score: span.score,
+                        source: ExtractionSource::Regex,
+                    }
+                }));
+            }
+            let characters = section.text.chars().collect::<Vec<char>>();
+            // The longest match is kept among the overlapping matches of the different dictionaries
+            let mut matches = self
+                .dictionaries
+                .iter()
+                .flat_map(|(field_type, dictionary)| {
+                    dictionary
+                        .find_all(&section.text)
+                        .into_iter()
+                        .map(move |(canonical, offset)| (field_type, canonical, offset))
+                })
+                .collect::<Vec<(&FieldType, String, Offset)>>();
+            matches.sort_by(|(_, _, a), (_, _, b)| {
+                a.begin
+                    .cmp(&b.begin)
+                    .then((b.end - b.begin).cmp(&(a.end - a.begin)))
+            });
+            for (field_type, canonical, offset) in matches {
+                if section_fields
+                    .iter()
+                    .any(|field: &ExtractedField| overlaps(&field.offset, &offset))
+                {
+                    continue;
+                }
+                section_fields.push(ExtractedField {
+                    field_type: field_type.clone(),
+                    text: characters[offset.begin as usize..offset.end as usize]
+                        .iter()
+                        .collect(),
+                    normalized: Some(canonical),
+                    offset,
+                    section: section.kind.clone(),
+                    score: 1.0,
+                    source: ExtractionSource::Dictionary,
+                });
+            }
+            fields.extend(section_fields.into_iter().map(|mut field| {
+                field.offset.begin += section.offset.begin;
+                field.offset.end += section.offset.begin;
+                field
+            }));
+        }
+        fields.sort_by_key(|field| field.offset.begin);
+        ResumeExtraction { sections, fields }
+    }
+}
+
+fn overlaps(a: &Offset, b: &Offset) -> bool {
+    a.begin < b.end && b.begin < a.end
+}
+
+fn regex_field_type(entity_type: &PiiEntityType) -> FieldType {
+    match entity_type {
+        PiiEntityType::Email => FieldType::Email,
+        PiiEntityType::PhoneNumber => FieldType::PhoneNumber,
+        other => FieldType::Custom(other.to_string()),
+    }
+}
+
+fn ner_field_type(label: &str) -> Option<FieldType> {
+    let label = label
+        .trim_start_matches("B-")
+        .trim_start_matches("I-")
+        .to_uppercase();
+    match label.as_str() {
+        "PER" | "PERSON" => Some(FieldType::Person),
+        "ORG" | "ORGANIZATION" => Some(FieldType::Organization),
+        "LOC" | "LOCATION" | "GPE" => Some(FieldType::Location),
+        _ => None,
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Review mining pipeline (opinion triplet extraction)
+//! Extracts (aspect term, opinion term, polarity) triplets from reviews, e.g. ("battery life", "amazing", positive)
+//! from "The battery life is amazing.". The extraction combines two models:
+//! - a tagging model (token classification), labelling the aspect terms and opinion terms of the review
+//! - a pairing model (sequence pair classification), classifying every (aspect term, opinion term) candidate pair of
+//! a review into a sentiment polarity, or into a label denoting unrelated terms (any label that is not a polarity,
+//! e.g. `none`)
+//!
+//! ```no_run
+//! use rust_bert::pipelines::review_mining::{ReviewMiningConfig, ReviewMiningModel};
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationConfig;
+//! use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+//! # fn main() -> anyhow::Result<()> {
+//! # let tagging_config = TokenClassificationConfig::default();
+//! # let pairing_config = SequenceClassificationConfig::default();
+//! let review_mining_model =
+//!     ReviewMiningModel::new(ReviewMiningConfig::new(tagging_config, pairing_config))?;
+//!
+//! let input = ["The battery life is amazing but the screen is too dim."];
+//! let output = review_mining_model.extract(&input);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::review_mining::OpinionTriplet;
+//! # use rust_bert::pipelines::sentiment::SentimentPolarity;
+//! # use rust_tokenizers::Offset;
+//! # let output =
+//! [[
+//!     OpinionTriplet {
+//!         aspect: String::from("battery life"),
+//!         aspect_offset: Offset { begin: 4, end: 16 },
+//!         opinion: String::from("amazing"),
+//!         opinion_offset: Offset { begin: 20, end: 27 },
+//!         polarity: SentimentPolarity::Positive,
+//!         score: 0.9811,
+//!     },
+//!     OpinionTriplet {
+//!         aspect: String::from("screen"),
+//!         aspect_offset: Offset { begin: 36, end: 42 },
+//!         opinion: String::from("too dim"),
+//!         opinion_offset: Offset { begin: 46, end: 53 },
+//!         polarity: SentimentPolarity::Negative,
+//!         score: 0.9623,
+//!     },
+//! ]]
+//! # ;
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::ner::{Entity, NERModel};
+use crate::pipelines::sentiment::SentimentPolarity;
+use crate::pipelines::sequence_classification::{
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::pipelines::token_classification::TokenClassificationConfig;
+use rust_tokenizers::Offset;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Opinion triplet extracted from a review
+pub struct OpinionTriplet {
+    /// Aspect term (e.g. `battery life`)
+    pub aspect: String,
+    /// Offsets of the aspect term in the review
+    pub aspect_offset: Offset,
+    /// Opinion term expressed about the aspect (e.g. `amazing`)
+    pub opinion: String,
+    /// Offsets of the opinion term in the review
+    pub opinion_offset: Offset,
+    /// Polarity of the opinion
+    pub polarity: SentimentPolarity,
+    /// Confidence score of the pairing model
+    pub score: f64,
+}
+
+/// # Configuration for ReviewMiningModel
+/// Contains the configuration of the tagging and pairing models, and the labels of the tagging model denoting aspect
+/// and opinion terms.
+pub struct ReviewMiningConfig {
+    /// Configuration of the token classification model tagging the aspect and opinion terms
+    pub tagging_config: TokenClassificationConfig,
+    /// Configuration of the sequence classification model classifying the (aspect term, opinion term) pairs
+    pub pairing_config: SequenceClassificationConfig,
+    /// Labels of the tagging model denoting aspect terms, without `B-`/`I-` prefix (case-insensitive, default: `ASP`, `ASPECT`, `AT`)
+    pub aspect_labels: Vec<String>,
+    /// Labels of the tagging model denoting opinion terms, without `B-`/`I-` prefix (case-insensitive, default: `OPN`, `OPINION`, `OT`)
+    pub opinion_labels: Vec<String>,
+    /// Template of the second sequence of the pairing model input (the first one being the review), where `{aspect}`
+    /// and `{opinion}` are replaced by the aspect and opinion terms (default: `{aspect}: {opinion}`)
+    pub pair_template: String,
+    /// Minimum score of the tagged terms (default: 0.5)
+    pub min_tagging_score: f64,
+    /// Minimum score of the polarity predicted by the pairing model (default: 0.5)
+    pub min_pairing_score: f64,
+}
+
+impl ReviewMiningConfig {
+    /// Instantiate a new review mining configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `tagging_config` - `TokenClassificationConfig` for the model tagging the aspect and opinion terms
+    /// * `pairing_config` - `SequenceClassificationConfig` for the model classifying the (aspect term, opinion term) pairs
+    pub fn new(
+        tagging_config: TokenClassificationConfig,
+        pairing_config: SequenceClassificationConfig,
+    ) -> ReviewMiningConfig {
+        ReviewMiningConfig {
+            tagging_config,
+            pairing_config,
+            aspect_labels: vec!["ASP".to_string(), "ASPECT".to_string(), "AT".to_string()],
+            opinion_labels: vec!["OPN".to_string(), "OPINION".to_string(), "OT".to_string()],
+            pair_template: "{aspect}: {opinion}".to_string(),
+            min_tagging_score: 0.5,
+            min_pairing_score: 0.5,
+        }
+    }
+}
+
+/// # ReviewMiningModel to extract opinion triplets from reviews
+pub struct ReviewMiningModel {
+    tagging_model: NERModel,
+    pairing_model: SequenceClassificationModel,
+    aspect_labels: Vec<String>,
+    opinion_labels: Vec<String>,
+    pair_template: String,
+    min_tagging_score: f64,
+    min_pairing_score: f64,
+}
+
+impl ReviewMiningModel {
+    /// Build a new `ReviewMiningModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `review_mining_config` - `ReviewMiningConfig` object containing the tagging and pairing model configurations
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::review_mining::{ReviewMiningConfig, ReviewMiningModel};
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationConfig;
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+    /// # let tagging_config = TokenClassificationConfig::default();
+    /// # let pairing_config = SequenceClassificationConfig::default();
+    ///
+    /// let review_mining_model =
+    ///     ReviewMiningModel::new(ReviewMiningConfig::new(tagging_config, pairing_config))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        review_mining_config: ReviewMiningConfig,
+    ) -> Result<ReviewMiningModel, RustBertError> {
+        let tagging_model = NERModel::new(review_mining_config.tagging_config)?;
+        let pairing_model = SequenceClassificationModel::new(review_mining_config.pairing_config)?;
+        Ok(ReviewMiningModel {
+            tagging_model,
+            pairing_model,
+            aspect_labels: review_mining_config.aspect_labels,
+            opinion_labels: review_mining_config.opinion_labels,
+            pair_template: review_mining_config.pair_template,
+            min_tagging_score: review_mining_config.min_tagging_score,
+            min_pairing_score: review_mining_config.min_pairing_score,
+        })
+    }
+
+    /// Extracts the opinion triplets of reviews. All candidate pairs of the batch are classified together by the
+    /// pairing model.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of reviews to process.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<OpinionTriplet>>` opinion triplets of each review, sorted by position of the aspect term
+    pub fn extract<S>(&self, input: &[S]) -> Vec<Vec<OpinionTriplet>>
+    where
+        S: AsRef<str>,
+    {
+        let candidates = self
+            .tagging_model
+            .predict_full_entities(input)
+            .into_iter()
+            .map(|entities| {
+                candidate_pairs(
+                    &entities,
+                    &self.aspect_labels,
+                    &self.opinion_labels,
+                    self.min_tagging_score,
+                )
+            })
+            .collect::<Vec<Vec<(Entity, Entity)>>>();
+
+        let pair_texts = candidates
+            .iter()
+            .flat_map(|text_candidates| {
+                text_candidates.iter().map(|(aspect, opinion)| {
+                    self.pair_template
+                        .replace("{aspect}", &aspect.word)
+                        .replace("{opinion}", &opinion.word)
+                })
+            })
+            .collect::<Vec<String>>();
+        let pairs = candidates
+            .iter()
+            .zip(input.iter())
+            .flat_map(|(text_candidates, text)| {
+                std::iter::repeat(text.as_ref()).take(text_candidates.len())
+            })
+            .zip(pair_texts.iter().map(String::as_str))
+            .collect::<Vec<(&str, &str)>>();
+        let mut pair_scores = self.pairing_model.predict_pairs_scores(&pairs).into_iter();
+
+        candidates
+            .into_iter()
+            .map(|text_candidates| {
+                text_candidates
+                    .into_iter()
+                    .zip(pair_scores.by_ref())
+                    .filter_map(|((aspect, opinion), labels)| {
+                        let (polarity, score) = pair_polarity(&labels)?;
+                        if score < self.min_pairing_score {
+                            return None;
+                        }
+                        Some(OpinionTriplet {
+                            aspect: aspect.word,
+                            aspect_offset: aspect.offset,
+                            opinion: opinion.word,
+                            opinion_offset: opinion.offset,
+                            polarity,
+                            score,
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Every (aspect term, opinion term) pair of the tagged entities of a review, sorted by position of the aspect term
+fn candidate_pairs(
+    entities: &[Entity],
+    aspect_labels: &[String],
+    opinion_labels: &[String],
+    min_score: f64,
+) -> Vec<(Entity, Entity)> {
+    let has_label = |entity: &Entity, labels: &[String]| {
+        entity.score >= min_score
+            && labels
+                .iter()
+                .any(|label| label.eq_ignore_ascii_case(&entity.label))
+    };
+    let mut aspects = entities
+        .iter()
+        .filter(|entity| has_label(entity, aspect_labels))
+        .collect::<Vec<&Entity>>();
+    aspects.sort_by_key(|entity| entity.offset.begin);
+    let opinions = entities
+        .iter()
+        .filter(|entity| has_label(entity, opinion_labels))
+        .collect::<Vec<&Entity>>();
+
+    aspects
+        .into_iter()
+        .flat_map(|aspect| {
+            opinions
+                .iter()
+                .map(move |opinion| (aspect.clone(), (*opinion).clone()))
+        })
+        .collect()
+}
+
+/// Polarity of the most likely label of the pairing model, `None` if this label denotes unrelated terms
+fn pair_polarity(labels: &[Label]) -> Option<(SentimentPolarity, f64)> {
+    let label = labels
+        .iter()
+        .max_by(|label_a, label_b| label_a.score.total_cmp(&label_b.score))?;
+    SentimentPolarity::from_label(&label.text).map(|polarity| (polarity, label.score))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entity(word: &str, label: &str, begin: u32, score: f64) -> Entity {
+        Entity {
+            word: word.to_string(),
+            score,
+            label: label.to_string(),
+            offset: Offset {
+                begin,
+                end: begin + word.len() as u32,
+            },
+        }
+    }
+
+    #[test]
+    fn candidate_pairing() {
+        let entities = [
+            entity("amazing", "OPN", 20, 0.9),
+            entity("battery life", "ASP", 4, 0.9),
+            entity("screen", "asp", 36, 0.8),
+            entity("too dim", "OPN", 46, 0.3),
+            entity("Acme", "ORG", 60, 0.9),
+        ];
+        let pairs = candidate_pairs(&entities, &["ASP".to_string()], &["OPN".to_string()], 0.5);
+        let pairs = pairs
+            .iter()
+            .map(|(aspect, opinion)| (aspect.word.as_str(), opinion.word.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        assert_eq!(
+            pairs,
+            vec![("battery life", "amazing"), ("screen", "amazing")]
+        );
+    }
+
+    #[test]
+    fn pairing_polarity() {
+        let labels = ["none", "negative", "positive"]
+            .iter()
+            .zip([0.5, 0.1, 0.4])
+            .enumerate()
+            .map(|(id, (text, score))| Label {
+                text: text.to_string(),
+                score,
+                id: id as i64,
+                sentence: 0,
+            })
+            .collect::<Vec<Label>>();
+        assert_eq!(pair_polarity(&labels), None);
+        assert_eq!(
+            pair_polarity(&labels[1..]),
+            Some((SentimentPolarity::Positive, 0.4))
+        );
+    }
+}
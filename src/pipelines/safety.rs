@@ -0,0 +1,522 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Safety filtering pipeline
+//! Screens texts with a safety classifier (toxicity, NSFW or jailbreak / prompt injection detection checkpoints)
+//! and blocks or redacts the texts flagged as unsafe. The classifier can be attached to the text generation
+//! pipeline to screen the prompts before generation and the generated outputs, and used on partial outputs
+//! when streaming.
+//! By default, the dependencies for this model will be downloaded for a BERT model finetuned on the Jigsaw
+//! toxic comments dataset (multi-label toxicity classification). A DeBERTa (v3) prompt injection classifier
+//! is available via `SafetyConfig::prompt_injection`.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::safety::SafetyClassifier;
+//! # fn main() -> anyhow::Result<()> {
+//! let safety_classifier = SafetyClassifier::new(Default::default())?;
+//!
+//! let input = ["Have a great day!", "You are a complete idiot."];
+//! let output = safety_classifier.screen(&input);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::safety::{SafetyAction, ScreenedText};
+//! # use rust_bert::pipelines::sequence_classification::Label;
+//! let output = [
+//!     ScreenedText {
+//!         text: Some(String::from("Have a great day!")),
+//!         flagged_labels: vec![],
+//!         action: None,
+//!     },
+//!     ScreenedText {
+//!         text: None,
+//!         flagged_labels: vec![
+//!             Label { text: String::from("toxic"), score: 0.9873, id: 0, sentence: 1 },
+//!             Label { text: String::from("insult"), score: 0.9512, id: 4, sentence: 1 },
+//!         ],
+//!         action: Some(SafetyAction::Block),
+//!     },
+//! ]
+//! # ;
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::{ModelResource, ModelType, TokenTypeStrategy, TokenizerOption};
+use crate::pipelines::sequence_classification::{
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::pipelines::text_generation::TextGenerationModel;
+use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tch::Device;
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+use crate::bert::{BertConfigResources, BertModelResources, BertVocabResources};
+use crate::common::settings::default_device;
+#[cfg(all(feature = "remote", feature = "deberta-v2"))]
+use crate::deberta_v2::{
+    DebertaV2ConfigResources, DebertaV2ModelResources, DebertaV2VocabResources,
+};
+#[cfg(feature = "remote")]
+use crate::resources::RemoteResource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Action taken for texts flagged as unsafe
+pub enum SafetyAction {
+    /// The text is removed
+    Block,
+    /// The text is replaced by the redaction text of the configuration
+    Redact,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Text screened by a `SafetyClassifier`
+pub struct ScreenedText {
+    /// Text after screening: the original text if it is safe, the redaction text if it was redacted, `None` if it was blocked
+    pub text: Option<String>,
+    /// Labels with a score above their threshold
+    pub flagged_labels: Vec<Label>,
+    /// Action taken if the text was flagged
+    pub action: Option<SafetyAction>,
+}
+
+impl ScreenedText {
+    /// Returns `true` if the text was flagged as unsafe
+    pub fn is_flagged(&self) -> bool {
+        !self.flagged_labels.is_empty()
+    }
+}
+
+/// # Configuration for SafetyClassifier
+/// Contains information regarding the model to load, the thresholds and actions for flagged texts and device to place the model on.
+pub struct SafetyConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: toxic-bert)
+    pub model_resource: ModelResource,
+    /// Config resource (default: toxic-bert)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: toxic-bert)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Flag indicating if the classification head is multi-label (independent sigmoid scores) rather than multi-class (softmax) (default: true)
+    pub multilabel: bool,
+    /// Score above which a label is flagged (default: 0.5)
+    pub threshold: f64,
+    /// Per-label thresholds, overriding the default threshold for the labels provided
+    pub label_thresholds: HashMap<String, f64>,
+    /// Labels that are never flagged (e.g. the `SAFE` class of multi-class classifiers)
+    pub safe_labels: Vec<String>,
+    /// Action taken for flagged texts (default: Block)
+    pub action: SafetyAction,
+    /// Text replacing redacted texts (default: `[REDACTED]`)
+    pub redaction_text: String,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl SafetyConfig {
+    /// Instantiate a new safety classifier configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    /// * multilabel - A `bool` indicating if the classification head is multi-label
+    pub fn new<RC, RV>(
+        model_type: ModelType,
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: Option<RV>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+        multilabel: bool,
+    ) -> SafetyConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        SafetyConfig {
+            model_type,
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: merges_resource.map(|r| Box::new(r) as Box<_>),
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            multilabel,
+            threshold: 0.5,
+            label_thresholds: HashMap::new(),
+            safe_labels: vec![],
+            action: SafetyAction::Block,
+            redaction_text: "[REDACTED]".to_string(),
+            device: default_device(),
+        }
+    }
+
+    /// Provides a DeBERTa (v3) prompt injection / jailbreak classifier, flagging the `INJECTION` class
+    #[cfg(all(feature = "remote", feature = "deberta-v2"))]
+    pub fn prompt_injection() -> SafetyConfig {
+        let mut config = SafetyConfig::new(
+            ModelType::DebertaV2,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                DebertaV2ModelResources::DEBERTA_V3_BASE_PROMPT_INJECTION,
+            ))),
+            RemoteResource::from_pretrained(
+                DebertaV2ConfigResources::DEBERTA_V3_BASE_PROMPT_INJECTION,
+            ),
+            RemoteResource::from_pretrained(
+                DebertaV2VocabResources::DEBERTA_V3_BASE_PROMPT_INJECTION,
+            ),
+            None,
+            false,
+            None,
+            None,
+            false,
+        );
+        config.safe_labels = vec!["SAFE".to_string()];
+        config
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "bert"))]
+impl Default for SafetyConfig {
+    /// Provides a default multi-label toxicity classifier (toxic-bert, English)
+    fn default() -> SafetyConfig {
+        SafetyConfig::new(
+            ModelType::Bert,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                BertModelResources::TOXIC_BERT,
+            ))),
+            RemoteResource::from_pretrained(BertConfigResources::TOXIC_BERT),
+            RemoteResource::from_pretrained(BertVocabResources::TOXIC_BERT),
+            None,
+            true,
+            None,
+            None,
+            true,
+        )
+    }
+}
+
+/// # SafetyClassifier to screen prompts and generated texts
+pub struct SafetyClassifier {
+    sequence_classification_model: SequenceClassificationModel,
+    multilabel: bool,
+    threshold: f64,
+    label_thresholds: HashMap<String, f64>,
+    safe_labels: Vec<String>,
+    action: SafetyAction,
+    redaction_text: String,
+}
+
+impl SafetyClassifier {
+    /// Build a new `SafetyClassifier`
+    ///
+    /// # Arguments
+    ///
+    /// * `safety_config` - `SafetyConfig` object containing the resource references (model, vocabulary, configuration), thresholds, action and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::safety::SafetyClassifier;
+    ///
+    /// let safety_classifier = SafetyClassifier::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(safety_config: SafetyConfig) -> Result<SafetyClassifier, RustBertError> {
+        let (sequence_classification_config, safety_options) = safety_config.split();
+        let sequence_classification_model =
+            SequenceClassificationModel::new(sequence_classification_config)?;
+        Ok(SafetyClassifier::from_parts(
+            sequence_classification_model,
+            safety_options,
+        ))
+    }
+
+    /// Build a new `SafetyClassifier` with a provided tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `safety_config` - `SafetyConfig` object containing the resource references (model, vocabulary, configuration), thresholds, action and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer to use for safety classification.
+    pub fn new_with_tokenizer(
+        safety_config: SafetyConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<SafetyClassifier, RustBertError> {
+        let (sequence_classification_config, safety_options) = safety_config.split();
+        let sequence_classification_model = SequenceClassificationModel::new_with_tokenizer(
+            sequence_classification_config,
+            tokenizer,
+        )?;
+        Ok(SafetyClassifier::from_parts(
+            sequence_classification_model,
+            safety_options,
+        ))
+    }
+
+    fn from_parts(
+        sequence_classification_model: SequenceClassificationModel,
+        safety_options: SafetyOptions,
+    ) -> SafetyClassifier {
+        SafetyClassifier {
+            sequence_classification_model,
+            multilabel: safety_options.multilabel,
+            threshold: safety_options.threshold,
+            label_thresholds: safety_options.label_thresholds,
+            safe_labels: safety_options.safe_labels,
+            action: safety_options.action,
+            redaction_text: safety_options.redaction_text,
+        }
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.sequence_classification_model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.sequence_classification_model.get_tokenizer_mut()
+    }
+
+    /// Returns the labels flagged for each input text (labels with a score above their threshold, excluding safe labels)
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` flagged labels for each input text (empty if the text is safe)
+    pub fn classify(&self, input: &[&str]) -> Vec<Vec<Label>> {
+        self.sequence_classification_model
+            .predict_scores(input, self.multilabel)
+            .into_iter()
+            .map(|labels| {
+                labels
+                    .into_iter()
+                    .filter(|label| {
+                        !self.safe_labels.contains(&label.text)
+                            && label.score
+                                >= *self
+                                    .label_thresholds
+                                    .get(&label.text)
+                                    .unwrap_or(&self.threshold)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Screens texts, applying the configured action (block or redact) to the texts flagged as unsafe.
+    /// This can be used on partial outputs when streaming the generation, to interrupt unsafe generations.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to screen.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<ScreenedText>` screened texts
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::safety::SafetyClassifier;
+    ///
+    /// let safety_classifier = SafetyClassifier::new(Default::default())?;
+    /// let output = safety_classifier.screen(&["Have a great day!"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn screen(&self, input: &[&str]) -> Vec<ScreenedText> {
+        input
+            .iter()
+            .zip(self.classify(input))
+            .map(|(text, flagged_labels)| {
+                if flagged_labels.is_empty() {
+                    ScreenedText {
+                        text: Some(text.to_string()),
+                        flagged_labels,
+                        action: None,
+                    }
+                } else {
+                    let text = match self.action {
+                        SafetyAction::Block => None,
+                        SafetyAction::Redact => Some(self.redaction_text.clone()),
+                    };
+                    ScreenedText {
+                        text,
+                        flagged_labels,
+                        action: Some(self.action),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Generates texts with a text generation model, screening the prompts before generation and/or the generated outputs.
+    /// Prompts flagged as unsafe are not passed to the model, and their output carries the prompt screening result.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `&TextGenerationModel` model used for generation
+    /// * `prompts` - `&[&str]` prompts to generate from
+    /// * `screen_prompts` - `bool` flag indicating if the prompts should be screened
+    /// * `screen_outputs` - `bool` flag indicating if the generated outputs should be screened
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<ScreenedText>` screened outputs for each prompt (a single output per prompt is expected from the model)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::safety::SafetyClassifier;
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let safety_classifier = SafetyClassifier::new(Default::default())?;
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let output = safety_classifier.generate(&model, &["The dog"], true, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate(
+        &self,
+        model: &TextGenerationModel,
+        prompts: &[&str],
+        screen_prompts: bool,
+        screen_outputs: bool,
+    ) -> Result<Vec<ScreenedText>, RustBertError> {
+        let mut outputs: Vec<Option<ScreenedText>> = if screen_prompts {
+            self.screen(prompts)
+                .into_iter()
+                .map(|screened| {
+                    if screened.is_flagged() {
+                        Some(screened)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            vec![None; prompts.len()]
+        };
+
+        let safe_prompts = prompts
+            .iter()
+            .zip(outputs.iter())
+            .filter(|(_, output)| output.is_none())
+            .map(|(prompt, _)| *prompt)
+            .collect::<Vec<&str>>();
+        if !safe_prompts.is_empty() {
+            let generated = model.generate(&safe_prompts, None);
+            if generated.len() != safe_prompts.len() {
+                return Err(RustBertError::ValueError(format!(
+                    "Expected a single output per prompt, got {} outputs for {} prompts",
+                    generated.len(),
+                    safe_prompts.len()
+                )));
+            }
+            let generated = if screen_outputs {
+                let generated = generated.iter().map(String::as_str).collect::<Vec<&str>>();
+                self.screen(&generated)
+            } else {
+                generated
+                    .into_iter()
+                    .map(|text| ScreenedText {
+                        text: Some(text),
+                        flagged_labels: vec![],
+                        action: None,
+                    })
+                    .collect()
+            };
+            let mut generated = generated.into_iter();
+            for output in outputs.iter_mut().filter(|output| output.is_none()) {
+                *output = generated.next();
+            }
+        }
+        Ok(outputs.into_iter().flatten().collect())
+    }
+}
+
+struct SafetyOptions {
+    multilabel: bool,
+    threshold: f64,
+    label_thresholds: HashMap<String, f64>,
+    safe_labels: Vec<String>,
+    action: SafetyAction,
+    redaction_text: String,
+}
+
+impl SafetyConfig {
+    fn split(self) -> (SequenceClassificationConfig, SafetyOptions) {
+        (
+            SequenceClassificationConfig {
+                model_type: self.model_type,
+                model_resource: self.model_resource,
+                config_resource: self.config_resource,
+                vocab_resource: self.vocab_resource,
+                merges_resource: self.merges_resource,
+                lower_case: self.lower_case,
+                strip_accents: self.strip_accents,
+                add_prefix_space: self.add_prefix_space,
+                device: self.device,
+                kind: None,
+                token_type_strategy: TokenTypeStrategy::default(),
+            },
+            SafetyOptions {
+                multilabel: self.multilabel,
+                threshold: self.threshold,
+                label_thresholds: self.label_thresholds,
+                safe_labels: self.safe_labels,
+                action: self.action,
+                redaction_text: self.redaction_text,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = SafetyConfig::default();
+        let _: Box<dyn Send> = Box::new(SafetyClassifier::new(config));
+    }
+}
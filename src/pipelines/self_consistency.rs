@@ -0,0 +1,238 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Self-consistency decoding
+//! Answers reasoning prompts by sampling several reasoning chains and returning the most frequent final answer
+//! ([Wang et al., 2022](https://arxiv.org/abs/2203.11171)). The final answer of each chain is extracted by an
+//! `AnswerExtractor`, for example a regular expression (`RegexAnswerExtractor`) or a closure parsing the generated
+//! text. Chains without an answer do not take part in the vote.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::self_consistency::{RegexAnswerExtractor, SelfConsistencyConfig};
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//!
+//! let model = TextGenerationModel::new(Default::default())?;
+//! let extractor = RegexAnswerExtractor::new(r"The answer is (\-?[0-9]+)")?;
+//!
+//! let output = model.generate_self_consistent(
+//!     "Q: Roger has 5 tennis balls. He buys 2 cans of 3 tennis balls. How many tennis balls does he have now?\nA:",
+//!     &extractor,
+//!     &SelfConsistencyConfig::default(),
+//! )?;
+//! println!("{:?} ({:.0}% agreement)", output.answer, output.agreement * 100.0);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// # Extraction of the final answer of a generated reasoning chain
+/// Implemented for closures `Fn(&str) -> Option<String>` and regular expressions (`RegexAnswerExtractor`).
+pub trait AnswerExtractor {
+    /// Returns the final answer of a generated text, or `None` if the text does not contain an answer
+    fn extract(&self, text: &str) -> Option<String>;
+}
+
+impl<F> AnswerExtractor for F
+where
+    F: Fn(&str) -> Option<String>,
+{
+    fn extract(&self, text: &str) -> Option<String> {
+        self(text)
+    }
+}
+
+/// # Answer extraction with a regular expression
+/// The answer is the first capture group of the last match in the text (the whole match if the expression has no
+/// capture group), trimmed of surrounding white spaces. The last match is used as reasoning chains may mention
+/// intermediate results before concluding.
+#[derive(Debug, Clone)]
+pub struct RegexAnswerExtractor {
+    pattern: Regex,
+}
+
+impl RegexAnswerExtractor {
+    /// Build a new `RegexAnswerExtractor`. Returns an error if the regular expression is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Regular expression matching the final answer
+    pub fn new(pattern: &str) -> Result<RegexAnswerExtractor, RustBertError> {
+        let pattern = Regex::new(pattern).map_err(|error| {
+            RustBertError::ValueError(format!("Invalid answer extraction pattern: {error}"))
+        })?;
+        Ok(RegexAnswerExtractor { pattern })
+    }
+}
+
+impl AnswerExtractor for RegexAnswerExtractor {
+    fn extract(&self, text: &str) -> Option<String> {
+        let captures = self.pattern.captures_iter(text).last()?;
+        let answer = captures.get(1).or_else(|| captures.get(0))?;
+        Some(answer.as_str().trim().to_string())
+    }
+}
+
+/// # Configuration for self-consistency decoding
+/// Sampling settings of the reasoning chains. The other generation settings are those of the text generation model.
+#[derive(Debug, Clone)]
+pub struct SelfConsistencyConfig {
+    /// Number of reasoning chains sampled
+    pub num_samples: i64,
+    /// Sampling temperature
+    pub temperature: f64,
+    /// Top-k sampling (0 to disable)
+    pub top_k: i64,
+    /// Top-p (nucleus) sampling
+    pub top_p: f64,
+    /// Maximum number of tokens generated for each chain
+    pub max_new_tokens: Option<i64>,
+}
+
+impl Default for SelfConsistencyConfig {
+    fn default() -> SelfConsistencyConfig {
+        SelfConsistencyConfig {
+            num_samples: 10,
+            temperature: 0.7,
+            top_k: 40,
+            top_p: 1.0,
+            max_new_tokens: Some(256),
+        }
+    }
+}
+
+impl SelfConsistencyConfig {
+    pub(crate) fn validate(&self) -> Result<(), RustBertError> {
+        if self.num_samples < 1 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "At least one reasoning chain should be sampled, got {}",
+                self.num_samples
+            )));
+        }
+        if self.temperature <= 0.0 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The sampling temperature should be positive, got {}",
+                self.temperature
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Output of self-consistency decoding
+pub struct SelfConsistencyOutput {
+    /// Majority-vote answer, `None` if no reasoning chain contains an answer. Ties are broken in favour of the
+    /// answer appearing first.
+    pub answer: Option<String>,
+    /// Number of reasoning chains agreeing with the majority-vote answer
+    pub votes: usize,
+    /// Fraction of the reasoning chains containing an answer that agree with the majority-vote answer
+    pub agreement: f64,
+    /// Distinct answers and their number of votes, by decreasing number of votes
+    pub answer_counts: Vec<(String, usize)>,
+    /// Generated reasoning chains
+    pub chains: Vec<String>,
+    /// Answer extracted from each reasoning chain
+    pub chain_answers: Vec<Option<String>>,
+}
+
+/// Extracts the answers of the reasoning chains and aggregates them by majority vote
+pub(crate) fn aggregate_answers<E>(chains: Vec<String>, extractor: &E) -> SelfConsistencyOutput
+where
+    E: AnswerExtractor + ?Sized,
+{
+    let chain_answers = chains
+        .iter()
+        .map(|chain| extractor.extract(chain))
+        .collect::<Vec<Option<String>>>();
+
+    let mut first_positions: HashMap<&str, usize> = HashMap::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (position, answer) in chain_answers.iter().flatten().enumerate() {
+        first_positions.entry(answer.as_str()).or_insert(position);
+        *counts.entry(answer.as_str()).or_insert(0) += 1;
+    }
+    let mut answer_counts = counts.into_iter().collect::<Vec<(&str, usize)>>();
+    answer_counts
+        .sort_by_key(|(answer, count)| (std::cmp::Reverse(*count), first_positions[answer]));
+    let answer_counts = answer_counts
+        .into_iter()
+        .map(|(answer, count)| (answer.to_string(), count))
+        .collect::<Vec<(String, usize)>>();
+
+    let num_answered = chain_answers.iter().flatten().count();
+    let (answer, votes) = match answer_counts.first() {
+        Some((answer, votes)) => (Some(answer.clone()), *votes),
+        None => (None, 0),
+    };
+    let agreement = if num_answered > 0 {
+        votes as f64 / num_answered as f64
+    } else {
+        0.0
+    };
+
+    SelfConsistencyOutput {
+        answer,
+        votes,
+        agreement,
+        answer_counts,
+        chains,
+        chain_answers,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn regex_extraction_uses_last_match() {
+        let extractor = RegexAnswerExtractor::new(r"answer is (\d+)").unwrap();
+        assert_eq!(
+            extractor.extract("The answer is 5 apples... no, the answer is 11."),
+            Some("11".to_string())
+        );
+        assert_eq!(extractor.extract("I do not know."), None);
+        assert!(RegexAnswerExtractor::new(r"(unclosed").is_err());
+    }
+
+    #[test]
+    fn majority_vote_aggregation() {
+        let chains = ["A: 11", "A: 12", "no idea", "A: 12", "A: 11", "A: 11"]
+            .iter()
+            .map(|chain| chain.to_string())
+            .collect::<Vec<String>>();
+        let extractor =
+            |text: &str| -> Option<String> { text.strip_prefix("A: ").map(str::to_string) };
+        let output = aggregate_answers(chains, &extractor);
+
+        assert_eq!(output.answer.as_deref(), Some("11"));
+        assert_eq!(output.votes, 3);
+        assert!((output.agreement - 0.6).abs() < 1e-9);
+        assert_eq!(
+            output.answer_counts,
+            vec![("11".to_string(), 3), ("12".to_string(), 2)]
+        );
+        assert_eq!(output.chain_answers[2], None);
+
+        // Ties are broken by the first answer
+        let output = aggregate_answers(vec!["A: 2".into(), "A: 1".into()], &extractor);
+        assert_eq!(output.answer.as_deref(), Some("2"));
+
+        let output = aggregate_answers(vec!["none".into()], &extractor);
+        assert_eq!(output.answer, None);
+        assert_eq!(output.agreement, 0.0);
+    }
+}
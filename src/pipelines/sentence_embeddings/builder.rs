@@ -1,9 +1,12 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 use tch::Device;
 
-use crate::pipelines::common::ModelType;
+use crate::common::settings::default_device;
+#[cfg(feature = "onnx")]
+use crate::pipelines::common::ONNXModelResources;
+use crate::pipelines::common::{ModelResource, ModelType};
 use crate::pipelines::sentence_embeddings::{
     SentenceEmbeddingsConfig, SentenceEmbeddingsModel, SentenceEmbeddingsModulesConfig,
 };
@@ -33,6 +36,8 @@ impl<T> SentenceEmbeddingsBuilder<T> {
 
 pub struct Local {
     model_dir: PathBuf,
+    #[cfg(feature = "onnx")]
+    onnx_model: Option<PathBuf>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,17 +47,45 @@ struct ModelConfig {
 
 impl Config for ModelConfig {}
 
+/// Weights file of a model directory: converted `rust_model.ot` weights if present, and otherwise the
+/// `model.safetensors` checkpoint of the directory
+fn weights_file(dir: &Path) -> PathBuf {
+    let torch_weights = dir.join("rust_model.ot");
+    let safetensors_weights = dir.join("model.safetensors");
+    if !torch_weights.exists() && safetensors_weights.exists() {
+        safetensors_weights
+    } else {
+        torch_weights
+    }
+}
+
 impl SentenceEmbeddingsBuilder<Local> {
     pub fn local<P: Into<PathBuf>>(model_dir: P) -> Self {
         Self {
-            device: Device::cuda_if_available(),
+            device: default_device(),
             inner: Local {
                 model_dir: model_dir.into(),
+                #[cfg(feature = "onnx")]
+                onnx_model: None,
             },
         }
     }
 
+    /// Runs the transformer from an ONNX graph of the model directory instead of the Torch weights, e.g.
+    /// `onnx/model_quantized.onnx` for a quantized export. The pooling, dense and normalization layers are unchanged.
+    #[cfg(feature = "onnx")]
+    pub fn onnx_model<P: Into<PathBuf>>(mut self, onnx_model: P) -> Self {
+        self.inner.onnx_model = Some(onnx_model.into());
+        self
+    }
+
     pub fn create_model(self) -> Result<SentenceEmbeddingsModel, RustBertError> {
+        SentenceEmbeddingsModel::new(self.into_config()?)
+    }
+
+    /// Returns the `SentenceEmbeddingsConfig` pointing to the files of the local model directory,
+    /// e.g. to create a `KeywordExtractionModel` from a local model
+    pub fn into_config(self) -> Result<SentenceEmbeddingsConfig, RustBertError> {
         let model_dir = self.inner.model_dir;
 
         let modules_config = model_dir.join("modules.json");
@@ -60,7 +93,17 @@ impl SentenceEmbeddingsBuilder<Local> {
 
         let transformer_config = model_dir.join("config.json");
         let transformer_type = ModelConfig::from_file(&transformer_config).model_type;
-        let transformer_weights = model_dir.join("rust_model.ot");
+        let torch_weights = weights_file(&model_dir);
+        #[cfg(feature = "onnx")]
+        let transformer_weights = match &self.inner.onnx_model {
+            Some(onnx_model) => ModelResource::ONNX(ONNXModelResources {
+                encoder_resource: Some(model_dir.join(onnx_model).into()),
+                ..Default::default()
+            }),
+            None => ModelResource::Torch(torch_weights.into()),
+        };
+        #[cfg(not(feature = "onnx"))]
+        let transformer_weights = ModelResource::Torch(torch_weights.into());
 
         let pooling_config = model_dir
             .join(&modules.pooling_module().path)
@@ -71,7 +114,7 @@ impl SentenceEmbeddingsBuilder<Local> {
             .map(|m| {
                 (
                     Some(model_dir.join(&m.path).join("config.json")),
-                    Some(model_dir.join(&m.path).join("rust_model.ot")),
+                    Some(weights_file(&model_dir.join(&m.path))),
                 )
             })
             .unwrap_or((None, None));
@@ -79,7 +122,10 @@ impl SentenceEmbeddingsBuilder<Local> {
         let tokenizer_config = model_dir.join("tokenizer_config.json");
         let sentence_bert_config = model_dir.join("sentence_bert_config.json");
         let (tokenizer_vocab, tokenizer_merges) = match transformer_type {
-            ModelType::Bert | ModelType::DistilBert => (model_dir.join("vocab.txt"), None),
+            ModelType::Bert
+            | ModelType::DistilBert
+            | ModelType::JinaBert
+            | ModelType::NomicBert => (model_dir.join("vocab.txt"), None),
             ModelType::Roberta => (
                 model_dir.join("vocab.json"),
                 Some(model_dir.join("merges.txt")),
@@ -93,11 +139,11 @@ impl SentenceEmbeddingsBuilder<Local> {
             }
         };
 
-        let config = SentenceEmbeddingsConfig {
+        Ok(SentenceEmbeddingsConfig {
             modules_config_resource: modules_config.into(),
             transformer_type,
             transformer_config_resource: transformer_config.into(),
-            transformer_weights_resource: transformer_weights.into(),
+            transformer_weights_resource: transformer_weights,
             pooling_config_resource: pooling_config.into(),
             dense_config_resource: dense_config.map(|r| r.into()),
             dense_weights_resource: dense_weights.map(|r| r.into()),
@@ -106,9 +152,7 @@ impl SentenceEmbeddingsBuilder<Local> {
             tokenizer_vocab_resource: tokenizer_vocab.into(),
             tokenizer_merges_resource: tokenizer_merges.map(|r| r.into()),
             device: self.device,
-        };
-
-        SentenceEmbeddingsModel::new(config)
+        })
     }
 }
 
@@ -121,7 +165,7 @@ pub struct Remote {
 impl SentenceEmbeddingsBuilder<Remote> {
     pub fn remote(model_type: SentenceEmbeddingsModelType) -> Self {
         Self {
-            device: Device::cuda_if_available(),
+            device: default_device(),
             inner: Remote {
                 config: SentenceEmbeddingsConfig::from(model_type),
             },
@@ -139,7 +183,18 @@ impl SentenceEmbeddingsBuilder<Remote> {
     }
 
     pub fn transformer_weights(mut self, resource: RemoteResource) -> Self {
-        self.inner.config.transformer_weights_resource = Box::new(resource);
+        self.inner.config.transformer_weights_resource = ModelResource::Torch(Box::new(resource));
+        self
+    }
+
+    /// Runs the transformer from an ONNX graph (e.g. a quantized export) instead of the Torch weights.
+    /// The pooling, dense and normalization layers are unchanged.
+    #[cfg(feature = "onnx")]
+    pub fn onnx_model(mut self, resource: RemoteResource) -> Self {
+        self.inner.config.transformer_weights_resource = ModelResource::ONNX(ONNXModelResources {
+            encoder_resource: Some(Box::new(resource)),
+            ..Default::default()
+        });
         self
     }
 
@@ -1,15 +1,34 @@
 use serde::{Deserialize, Serialize};
 use tch::Device;
 
-use crate::pipelines::common::ModelType;
+use crate::pipelines::common::{ModelResource, ModelType};
 use crate::resources::ResourceProvider;
 use crate::{Config, RustBertError};
 
+#[cfg(all(feature = "remote", feature = "albert"))]
+use crate::albert::{AlbertConfigResources, AlbertModelResources, AlbertVocabResources};
+#[cfg(all(feature = "remote", feature = "bert"))]
+use crate::bert::{BertConfigResources, BertModelResources, BertVocabResources};
+#[cfg(feature = "remote")]
+use crate::common::settings::default_device;
+#[cfg(all(feature = "remote", feature = "distilbert"))]
+use crate::distilbert::{
+    DistilBertConfigResources, DistilBertModelResources, DistilBertVocabResources,
+};
+#[cfg(all(feature = "remote", feature = "jina-bert"))]
+use crate::jina_bert::{JinaBertConfigResources, JinaBertModelResources, JinaBertVocabResources};
+#[cfg(all(feature = "remote", feature = "nomic-bert"))]
+use crate::nomic_bert::{
+    NomicBertConfigResources, NomicBertModelResources, NomicBertVocabResources,
+};
+#[cfg(all(feature = "remote", feature = "roberta"))]
+use crate::roberta::{
+    RobertaConfigResources, RobertaMergesResources, RobertaModelResources, RobertaVocabResources,
+};
+#[cfg(all(feature = "remote", feature = "t5"))]
+use crate::t5::{T5ConfigResources, T5ModelResources, T5VocabResources};
 #[cfg(feature = "remote")]
 use crate::{
-    albert::{AlbertConfigResources, AlbertModelResources, AlbertVocabResources},
-    bert::{BertConfigResources, BertModelResources, BertVocabResources},
-    distilbert::{DistilBertConfigResources, DistilBertModelResources, DistilBertVocabResources},
     pipelines::sentence_embeddings::resources::{
         SentenceEmbeddingsConfigResources, SentenceEmbeddingsModelType,
         SentenceEmbeddingsModulesConfigResources, SentenceEmbeddingsPoolingConfigResources,
@@ -19,11 +38,6 @@ use crate::{
         SentenceEmbeddingsDenseConfigResources, SentenceEmbeddingsDenseResources,
     },
     resources::RemoteResource,
-    roberta::{
-        RobertaConfigResources, RobertaMergesResources, RobertaModelResources,
-        RobertaVocabResources,
-    },
-    t5::{T5ConfigResources, T5ModelResources, T5VocabResources},
 };
 
 /// # Configuration for sentence embeddings
@@ -37,8 +51,9 @@ pub struct SentenceEmbeddingsConfig {
     pub transformer_type: ModelType,
     /// Transformer model configuration resource
     pub transformer_config_resource: Box<dyn ResourceProvider + Send>,
-    /// Transformer weights resource
-    pub transformer_weights_resource: Box<dyn ResourceProvider + Send>,
+    /// Transformer weights resource: Torch weights, or an ONNX graph (e.g. a quantized export) whose encoder
+    /// outputs the `last_hidden_state`
+    pub transformer_weights_resource: ModelResource,
     /// Pooling layer configuration resource
     pub pooling_config_resource: Box<dyn ResourceProvider + Send>,
     /// Optional dense layer configuration resource
@@ -61,6 +76,7 @@ pub struct SentenceEmbeddingsConfig {
 impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
     fn from(model_type: SentenceEmbeddingsModelType) -> Self {
         match model_type {
+            #[cfg(feature = "distilbert")]
             SentenceEmbeddingsModelType::DistiluseBaseMultilingualCased => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
@@ -69,8 +85,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     DistilBertConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    DistilBertModelResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(
+                        DistilBertModelResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
+                    ),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
@@ -91,9 +109,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     DistilBertVocabResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
                 )),
                 tokenizer_merges_resource: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
             },
 
+            #[cfg(feature = "bert")]
             SentenceEmbeddingsModelType::BertBaseNliMeanTokens => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::BERT_BASE_NLI_MEAN_TOKENS,
@@ -102,8 +121,8 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     BertConfigResources::BERT_BASE_NLI_MEAN_TOKENS,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    BertModelResources::BERT_BASE_NLI_MEAN_TOKENS,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(BertModelResources::BERT_BASE_NLI_MEAN_TOKENS),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::BERT_BASE_NLI_MEAN_TOKENS,
@@ -120,9 +139,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     BertVocabResources::BERT_BASE_NLI_MEAN_TOKENS,
                 )),
                 tokenizer_merges_resource: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
             },
 
+            #[cfg(feature = "bert")]
             SentenceEmbeddingsModelType::AllMiniLmL12V2 => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::ALL_MINI_LM_L12_V2,
@@ -131,8 +151,8 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     BertConfigResources::ALL_MINI_LM_L12_V2,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    BertModelResources::ALL_MINI_LM_L12_V2,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(BertModelResources::ALL_MINI_LM_L12_V2),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::ALL_MINI_LM_L12_V2,
@@ -149,9 +169,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     BertVocabResources::ALL_MINI_LM_L12_V2,
                 )),
                 tokenizer_merges_resource: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
             },
 
+            #[cfg(feature = "bert")]
             SentenceEmbeddingsModelType::AllMiniLmL6V2 => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::ALL_MINI_LM_L6_V2,
@@ -160,8 +181,8 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     BertConfigResources::ALL_MINI_LM_L6_V2,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    BertModelResources::ALL_MINI_LM_L6_V2,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(BertModelResources::ALL_MINI_LM_L6_V2),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::ALL_MINI_LM_L6_V2,
@@ -178,9 +199,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     BertVocabResources::ALL_MINI_LM_L6_V2,
                 )),
                 tokenizer_merges_resource: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
             },
 
+            #[cfg(feature = "roberta")]
             SentenceEmbeddingsModelType::AllDistilrobertaV1 => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::ALL_DISTILROBERTA_V1,
@@ -189,8 +211,8 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     RobertaConfigResources::ALL_DISTILROBERTA_V1,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    RobertaModelResources::ALL_DISTILROBERTA_V1,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(RobertaModelResources::ALL_DISTILROBERTA_V1),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::ALL_DISTILROBERTA_V1,
@@ -209,9 +231,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 tokenizer_merges_resource: Some(Box::new(RemoteResource::from_pretrained(
                     RobertaMergesResources::ALL_DISTILROBERTA_V1,
                 ))),
-                device: Device::cuda_if_available(),
+                device: default_device(),
             },
 
+            #[cfg(feature = "albert")]
             SentenceEmbeddingsModelType::ParaphraseAlbertSmallV2 => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::PARAPHRASE_ALBERT_SMALL_V2,
@@ -220,8 +243,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     AlbertConfigResources::PARAPHRASE_ALBERT_SMALL_V2,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    AlbertModelResources::PARAPHRASE_ALBERT_SMALL_V2,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(
+                        AlbertModelResources::PARAPHRASE_ALBERT_SMALL_V2,
+                    ),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::PARAPHRASE_ALBERT_SMALL_V2,
@@ -238,9 +263,10 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     AlbertVocabResources::PARAPHRASE_ALBERT_SMALL_V2,
                 )),
                 tokenizer_merges_resource: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
             },
 
+            #[cfg(feature = "t5")]
             SentenceEmbeddingsModelType::SentenceT5Base => SentenceEmbeddingsConfig {
                 modules_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsModulesConfigResources::SENTENCE_T5_BASE,
@@ -249,8 +275,8 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 transformer_config_resource: Box::new(RemoteResource::from_pretrained(
                     T5ConfigResources::SENTENCE_T5_BASE,
                 )),
-                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
-                    T5ModelResources::SENTENCE_T5_BASE,
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(T5ModelResources::SENTENCE_T5_BASE),
                 )),
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::SENTENCE_T5_BASE,
@@ -271,7 +297,69 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     T5VocabResources::SENTENCE_T5_BASE,
                 )),
                 tokenizer_merges_resource: None,
-                device: Device::cuda_if_available(),
+                device: default_device(),
+            },
+
+            #[cfg(feature = "jina-bert")]
+            SentenceEmbeddingsModelType::JinaEmbeddingsV2BaseEn => SentenceEmbeddingsConfig {
+                modules_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsModulesConfigResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                )),
+                transformer_type: ModelType::JinaBert,
+                transformer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    JinaBertConfigResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                )),
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(
+                        JinaBertModelResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                    ),
+                )),
+                pooling_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsPoolingConfigResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                )),
+                dense_config_resource: None,
+                dense_weights_resource: None,
+                sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsConfigResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                )),
+                tokenizer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsTokenizerConfigResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                )),
+                tokenizer_vocab_resource: Box::new(RemoteResource::from_pretrained(
+                    JinaBertVocabResources::JINA_EMBEDDINGS_V2_BASE_EN,
+                )),
+                tokenizer_merges_resource: None,
+                device: default_device(),
+            },
+
+            #[cfg(feature = "nomic-bert")]
+            SentenceEmbeddingsModelType::NomicEmbedTextV1 => SentenceEmbeddingsConfig {
+                modules_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsModulesConfigResources::NOMIC_EMBED_TEXT_V1,
+                )),
+                transformer_type: ModelType::NomicBert,
+                transformer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    NomicBertConfigResources::NOMIC_EMBED_TEXT_V1,
+                )),
+                transformer_weights_resource: ModelResource::Torch(Box::new(
+                    RemoteResource::from_pretrained(NomicBertModelResources::NOMIC_EMBED_TEXT_V1),
+                )),
+                pooling_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsPoolingConfigResources::NOMIC_EMBED_TEXT_V1,
+                )),
+                dense_config_resource: None,
+                dense_weights_resource: None,
+                sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsConfigResources::NOMIC_EMBED_TEXT_V1,
+                )),
+                tokenizer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsTokenizerConfigResources::NOMIC_EMBED_TEXT_V1,
+                )),
+                tokenizer_vocab_resource: Box::new(RemoteResource::from_pretrained(
+                    NomicBertVocabResources::NOMIC_EMBED_TEXT_V1,
+                )),
+                tokenizer_merges_resource: None,
+                device: default_device(),
             },
         }
     }
@@ -138,7 +138,7 @@ impl Dense {
 
         let activation = dense_conf.activation_function.get_function();
 
-        vs_dense.load(dense_weights)?;
+        crate::resources::load_weights_from_file(dense_weights, &mut vs_dense)?;
 
         Ok(Dense {
             linear,
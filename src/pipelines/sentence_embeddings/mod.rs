@@ -27,12 +27,32 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! With the `onnx` feature, the transformer can also run from an ONNX graph, for example a quantized export
+//! of the model, while the pooling, dense and normalization layers are applied as for Torch models:
+//!
+//! ```no_run
+//! # #[cfg(feature = "onnx")]
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sentence_embeddings::SentenceEmbeddingsBuilder;
+//!
+//! let model = SentenceEmbeddingsBuilder::local("local/path/to/all-MiniLM-L6-v2")
+//!     .onnx_model("onnx/model_qint8_avx512.onnx")
+//!     .create_model()?;
+//!
+//! let embeddings = model.encode(&["This is an example sentence"])?;
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "onnx"))]
+//! # fn main() {}
+//! ```
 
 pub mod builder;
 mod config;
 pub mod layers;
 mod pipeline;
 mod resources;
+pub mod sharding;
 
 pub use builder::SentenceEmbeddingsBuilder;
 pub use config::{
@@ -42,7 +62,7 @@ pub use config::{
 };
 pub use pipeline::{
     SentenceEmbeddingsModel, SentenceEmbeddingsModelOutput, SentenceEmbeddingsOption,
-    SentenceEmbeddingsTokenizerOutput,
+    SentenceEmbeddingsTaskPrefix, SentenceEmbeddingsTokenizerOutput,
 };
 
 pub use resources::{
@@ -4,32 +4,60 @@ use std::convert::{TryFrom, TryInto};
 use rust_tokenizers::tokenizer::TruncationStrategy;
 use tch::{nn, Tensor};
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForSentenceEmbeddings;
+#[cfg(feature = "bert")]
 use crate::bert::BertForSentenceEmbeddings;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertForSentenceEmbeddings;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+#[cfg(feature = "jina-bert")]
+use crate::jina_bert::JinaBertForSentenceEmbeddings;
+#[cfg(feature = "nomic-bert")]
+use crate::nomic_bert::NomicBertForSentenceEmbeddings;
+use crate::pipelines::common::{
+    ConfigOption, EncodedInput, ModelResource, ModelType, TokenizerOption,
+};
+#[cfg(feature = "onnx")]
+use crate::pipelines::onnx::{config::ONNXEnvironmentConfig, ONNXEncoder};
+use crate::pipelines::pii::PiiRedactionModel;
 use crate::pipelines::sentence_embeddings::layers::{Dense, DenseConfig, Pooling, PoolingConfig};
 use crate::pipelines::sentence_embeddings::{
     AttentionHead, AttentionLayer, AttentionOutput, Embedding, SentenceEmbeddingsConfig,
     SentenceEmbeddingsModulesConfig, SentenceEmbeddingsSentenceBertConfig,
     SentenceEmbeddingsTokenizerConfig,
 };
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForSentenceEmbeddings;
+#[cfg(feature = "t5")]
 use crate::t5::T5ForSentenceEmbeddings;
 use crate::{Config, RustBertError};
 
 /// # Abstraction that holds one particular sentence embeddings model, for any of the supported models
 pub enum SentenceEmbeddingsOption {
     /// Bert for Sentence Embeddings
+    #[cfg(feature = "bert")]
     Bert(BertForSentenceEmbeddings),
     /// DistilBert for Sentence Embeddings
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertForSentenceEmbeddings),
     /// Roberta for Sentence Embeddings
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForSentenceEmbeddings),
     /// Albert for Sentence Embeddings
+    #[cfg(feature = "albert")]
     Albert(AlbertForSentenceEmbeddings),
     /// T5 for Sentence Embeddings
+    #[cfg(feature = "t5")]
     T5(T5ForSentenceEmbeddings),
+    /// Jina BERT for Sentence Embeddings
+    #[cfg(feature = "jina-bert")]
+    JinaBert(JinaBertForSentenceEmbeddings),
+    /// Nomic BERT for Sentence Embeddings
+    #[cfg(feature = "nomic-bert")]
+    NomicBert(NomicBertForSentenceEmbeddings),
+    /// ONNX encoder for Sentence Embeddings
+    #[cfg(feature = "onnx")]
+    ONNX(ONNXEncoder),
 }
 
 impl SentenceEmbeddingsOption {
@@ -51,18 +79,32 @@ impl SentenceEmbeddingsOption {
         use SentenceEmbeddingsOption::*;
 
         let option = match transformer_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => Bert(BertForSentenceEmbeddings::new(p, &(config.try_into()?))),
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => DistilBert(DistilBertForSentenceEmbeddings::new(
                 p,
                 &(config.try_into()?),
             )),
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => Roberta(RobertaForSentenceEmbeddings::new_with_optional_pooler(
                 p,
                 &(config.try_into()?),
                 false,
             )),
+            #[cfg(feature = "albert")]
             ModelType::Albert => Albert(AlbertForSentenceEmbeddings::new(p, &(config.try_into()?))),
+            #[cfg(feature = "t5")]
             ModelType::T5 => T5(T5ForSentenceEmbeddings::new(p, &(config.try_into()?))),
+            #[cfg(feature = "jina-bert")]
+            ModelType::JinaBert => {
+                JinaBert(JinaBertForSentenceEmbeddings::new(p, &(config.try_into()?)))
+            }
+            #[cfg(feature = "nomic-bert")]
+            ModelType::NomicBert => NomicBert(NomicBertForSentenceEmbeddings::new(
+                p,
+                &(config.try_into()?),
+            )),
             _ => {
                 return Err(RustBertError::InvalidConfigurationError(format!(
                     "Unsupported transformer model {transformer_type:?} for Sentence Embeddings"
@@ -73,14 +115,41 @@ impl SentenceEmbeddingsOption {
         Ok(option)
     }
 
+    /// Instantiate a new sentence embeddings transformer from an ONNX graph (e.g. a quantized export of the
+    /// transformer). The graph must output the `last_hidden_state` of the encoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `ModelResource::ONNX` pointing to the encoder graph
+    /// * `device` - Device to run the ONNX session on
+    #[cfg(feature = "onnx")]
+    pub fn new_onnx(
+        model_resource: &ModelResource,
+        device: tch::Device,
+    ) -> Result<Self, RustBertError> {
+        let onnx_config = ONNXEnvironmentConfig::from_device(device);
+        let environment = onnx_config.get_environment()?;
+        let encoder_file = model_resource.get_onnx_local_paths()?.encoder_path.ok_or(
+            RustBertError::InvalidConfigurationError(
+                "An encoder file must be provided for sentence embeddings ONNX models.".to_string(),
+            ),
+        )?;
+        Ok(Self::ONNX(ONNXEncoder::new(
+            encoder_file,
+            &environment,
+            &onnx_config,
+        )?))
+    }
+
     /// Interface method to forward() of the particular transformer models.
     pub fn forward(
         &self,
         tokens_ids: &Tensor,
         tokens_masks: &Tensor,
     ) -> Result<(Tensor, Option<Vec<Tensor>>), RustBertError> {
-        match self {
-            Self::Bert(transformer) => transformer
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref transformer) => transformer
                 .forward_t(
                     Some(tokens_ids),
                     Some(tokens_masks),
@@ -97,7 +166,8 @@ impl SentenceEmbeddingsOption {
                         transformer_output.all_attentions,
                     )
                 }),
-            Self::DistilBert(transformer) => transformer
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref transformer) => transformer
                 .forward_t(Some(tokens_ids), Some(tokens_masks), None, false)
                 .map(|transformer_output| {
                     (
@@ -105,7 +175,8 @@ impl SentenceEmbeddingsOption {
                         transformer_output.all_attentions,
                     )
                 }),
-            Self::Roberta(transformer) => transformer
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref transformer) => transformer
                 .forward_t(
                     Some(tokens_ids),
                     Some(tokens_masks),
@@ -122,7 +193,8 @@ impl SentenceEmbeddingsOption {
                         transformer_output.all_attentions,
                     )
                 }),
-            Self::Albert(transformer) => transformer
+            #[cfg(feature = "albert")]
+            Self::Albert(ref transformer) => transformer
                 .forward_t(
                     Some(tokens_ids),
                     Some(tokens_masks),
@@ -145,7 +217,44 @@ impl SentenceEmbeddingsOption {
                         }),
                     )
                 }),
-            Self::T5(transformer) => transformer.forward(tokens_ids, tokens_masks),
+            #[cfg(feature = "t5")]
+            Self::T5(ref transformer) => transformer.forward(tokens_ids, tokens_masks),
+            #[cfg(feature = "jina-bert")]
+            Self::JinaBert(ref transformer) => transformer
+                .forward_t(Some(tokens_ids), Some(tokens_masks), None, None, false)
+                .map(|transformer_output| {
+                    (
+                        transformer_output.hidden_state,
+                        transformer_output.all_attentions,
+                    )
+                }),
+            #[cfg(feature = "nomic-bert")]
+            Self::NomicBert(ref transformer) => transformer
+                .forward_t(Some(tokens_ids), Some(tokens_masks), None, None, false)
+                .map(|transformer_output| {
+                    (
+                        transformer_output.hidden_state,
+                        transformer_output.all_attentions,
+                    )
+                }),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref transformer) => {
+                // Inputs that are not expected by the graph (e.g. token type ids) are ignored
+                let token_type_ids = tokens_ids.zeros_like();
+                let output = transformer.forward(
+                    Some(tokens_ids),
+                    Some(tokens_masks),
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                )?;
+                let hidden_state = output.last_hidden_state.ok_or_else(|| {
+                    RustBertError::InvalidConfigurationError(
+                        "The ONNX graph does not output a `last_hidden_state`".to_string(),
+                    )
+                })?;
+                Ok((hidden_state, None))
+            }
         }
     }
 }
@@ -168,6 +277,7 @@ pub struct SentenceEmbeddingsModel {
     dense_layer: Option<Dense>,
     normalize_embeddings: bool,
     embeddings_dim: i64,
+    pii_redaction_model: Option<PiiRedactionModel>,
 }
 
 impl SentenceEmbeddingsModel {
@@ -247,14 +357,29 @@ impl SentenceEmbeddingsModel {
         );
 
         // Setup transformer
-        let mut var_store = nn::VarStore::new(device);
         let transformer_config = ConfigOption::from_file(
             transformer_type,
             transformer_config_resource.get_local_path()?,
         );
-        let transformer =
-            SentenceEmbeddingsOption::new(transformer_type, var_store.root(), &transformer_config)?;
-        crate::resources::load_weights(&transformer_weights_resource, &mut var_store)?;
+        let (transformer, var_store) = match transformer_weights_resource {
+            ModelResource::Torch(ref weights_resource) => {
+                let mut var_store = nn::VarStore::new(device);
+                let transformer = SentenceEmbeddingsOption::new(
+                    transformer_type,
+                    var_store.root(),
+                    &transformer_config,
+                )?;
+                crate::resources::load_weights(weights_resource, &mut var_store)?;
+                (transformer, var_store)
+            }
+            // The ONNX outputs are placed on the CPU, the pooling and dense layers run on the same device
+            #[cfg(feature = "onnx")]
+            ModelResource::ONNX(_) => (
+                SentenceEmbeddingsOption::new_onnx(&transformer_weights_resource, device)?,
+                nn::VarStore::new(tch::Device::Cpu),
+            ),
+        };
+        let device = var_store.device();
 
         // Setup pooling layer
         let pooling_config = PoolingConfig::from_file(pooling_config_resource.get_local_path()?);
@@ -288,6 +413,7 @@ impl SentenceEmbeddingsModel {
             dense_layer,
             normalize_embeddings,
             embeddings_dim,
+            pii_redaction_model: None,
         })
     }
 
@@ -306,22 +432,75 @@ impl SentenceEmbeddingsModel {
         self.tokenizer_truncation_strategy = truncation_strategy;
     }
 
+    /// Sets a PII redaction model applied to the texts before encoding, so that the embeddings do not carry
+    /// the identifiers found in the texts (e.g. to build a retrieval index over a sensitive corpus). The entity
+    /// type redaction strategy is recommended: the placeholders (e.g. `[EMAIL]`) preserve the meaning of the texts.
+    /// Pre-encoded inputs (`encode_from_ids`) are not redacted.
+    ///
+    /// # Arguments
+    ///
+    /// * `pii_redaction_model` - Optional `PiiRedactionModel`, disabling the redaction if `None`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::pii::{PiiRedactionConfig, PiiRedactionModel, RegexDetector};
+    /// use rust_bert::pipelines::sentence_embeddings::{
+    ///     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+    /// };
+    ///
+    /// let mut model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL6V2)
+    ///     .create_model()?;
+    /// let pii_model = PiiRedactionModel::new(PiiRedactionConfig::new(
+    ///     None,
+    ///     RegexDetector::default_detectors(),
+    /// ))?;
+    /// model.set_pii_redaction_model(Some(pii_model));
+    ///
+    /// let embeddings = model.encode(&["Contact me at amy@example.com"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_pii_redaction_model(&mut self, pii_redaction_model: Option<PiiRedactionModel>) {
+        self.pii_redaction_model = pii_redaction_model;
+    }
+
+    /// Get a reference to the PII redaction model applied before encoding, if any.
+    pub fn get_pii_redaction_model(&self) -> Option<&PiiRedactionModel> {
+        self.pii_redaction_model.as_ref()
+    }
+
     /// Return the embedding output dimension
     pub fn get_embedding_dim(&self) -> Result<i64, RustBertError> {
         Ok(self.embeddings_dim)
     }
 
-    /// Tokenizes the inputs
+    /// Tokenizes the inputs, after redaction of their PII spans if a PII redaction model is set
     pub fn tokenize<S>(&self, inputs: &[S]) -> SentenceEmbeddingsTokenizerOutput
     where
         S: AsRef<str> + Send + Sync,
     {
-        let tokenized_input = self.tokenizer.encode_list(
-            inputs,
-            self.sentence_bert_config.max_seq_length,
-            &self.tokenizer_truncation_strategy,
-            0,
-        );
+        let tokenized_input = if let Some(pii_redaction_model) = &self.pii_redaction_model {
+            let redacted_inputs = pii_redaction_model
+                .redact(inputs)
+                .into_iter()
+                .map(|output| output.text)
+                .collect::<Vec<String>>();
+            self.tokenizer.encode_list(
+                &redacted_inputs,
+                self.sentence_bert_config.max_seq_length,
+                &self.tokenizer_truncation_strategy,
+                0,
+            )
+        } else {
+            self.tokenizer.encode_list(
+                inputs,
+                self.sentence_bert_config.max_seq_length,
+                &self.tokenizer_truncation_strategy,
+                0,
+            )
+        };
 
         let max_len = tokenized_input
             .iter()
@@ -383,12 +562,66 @@ impl SentenceEmbeddingsModel {
         }
         let tokens_ids = Tensor::stack(&tokens_ids, 0).to(self.var_store.device());
         let tokens_masks = Tensor::stack(&tokens_masks, 0).to(self.var_store.device());
+        self.embed(&tokens_ids, &tokens_masks)
+    }
 
+    /// Computes sentence embeddings for pre-encoded inputs, skipping the tokenization step. Outputs `Tensor`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - slice of pre-encoded inputs, including the special tokens expected by the model. Inputs
+    /// longer than the maximum sequence length of the model are truncated.
+    pub fn encode_from_ids_as_tensor(
+        &self,
+        inputs: &[EncodedInput],
+    ) -> Result<SentenceEmbeddingsModelOutput, RustBertError> {
+        let pad_token_id = self.tokenizer.get_pad_id().unwrap_or(0);
+        let (tokens_ids, tokens_masks, _) = EncodedInput::pad_batch(
+            inputs,
+            pad_token_id,
+            Some(self.sentence_bert_config.max_seq_length),
+            self.var_store.device(),
+        )?;
+        self.embed(&tokens_ids, &tokens_masks)
+    }
+
+    /// Computes sentence embeddings for pre-encoded inputs, skipping the tokenization step
+    /// (e.g. for token ids cached or received from a remote tokenization service).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::EncodedInput;
+    /// use rust_bert::pipelines::sentence_embeddings::{
+    ///     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+    /// };
+    ///
+    /// let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+    ///     .create_model()?;
+    /// let embeddings = model.encode_from_ids(&[EncodedInput::new(vec![101, 7592, 2088, 102])])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_from_ids(
+        &self,
+        inputs: &[EncodedInput],
+    ) -> Result<Vec<Embedding>, RustBertError> {
+        let SentenceEmbeddingsModelOutput { embeddings, .. } =
+            self.encode_from_ids_as_tensor(inputs)?;
+        Ok(Vec::try_from(embeddings)?)
+    }
+
+    fn embed(
+        &self,
+        tokens_ids: &Tensor,
+        tokens_masks: &Tensor,
+    ) -> Result<SentenceEmbeddingsModelOutput, RustBertError> {
         let (tokens_embeddings, all_attentions) =
-            tch::no_grad(|| self.transformer.forward(&tokens_ids, &tokens_masks))?;
+            tch::no_grad(|| self.transformer.forward(tokens_ids, tokens_masks))?;
 
         let mean_pool =
-            tch::no_grad(|| self.pooling_layer.forward(tokens_embeddings, &tokens_masks));
+            tch::no_grad(|| self.pooling_layer.forward(tokens_embeddings, tokens_masks));
         let maybe_linear = if let Some(dense_layer) = &self.dense_layer {
             tch::no_grad(|| dense_layer.forward(&mean_pool))
         } else {
@@ -419,39 +652,92 @@ impl SentenceEmbeddingsModel {
         Ok(Vec::try_from(embeddings)?)
     }
 
+    /// Computes sentence embeddings for inputs prepended with a task prefix.
+    ///
+    /// Some embeddings models (e.g. Nomic embed) are trained with task-specific instructions that
+    /// should be prepended to the text, such as `search_query: ` for queries and `search_document: ` for
+    /// the documents to retrieve.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - slice of input texts to embed
+    /// * `prefix` - prefix to prepend to every input, e.g. a `SentenceEmbeddingsTaskPrefix`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_embeddings::{
+    ///     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType, SentenceEmbeddingsTaskPrefix,
+    /// };
+    ///
+    /// let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::NomicEmbedTextV1)
+    ///     .create_model()?;
+    /// let query_embeddings =
+    ///     model.encode_with_prefix(&["What is TSNE?"], SentenceEmbeddingsTaskPrefix::SearchQuery)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_with_prefix<S, P>(
+        &self,
+        inputs: &[S],
+        prefix: P,
+    ) -> Result<Vec<Embedding>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+        P: AsRef<str>,
+    {
+        let prefix = prefix.as_ref();
+        let prefixed_inputs = inputs
+            .iter()
+            .map(|input| format!("{}{}", prefix, input.as_ref()))
+            .collect::<Vec<String>>();
+        self.encode(&prefixed_inputs)
+    }
+
     fn nb_layers(&self) -> usize {
         use SentenceEmbeddingsOption::*;
         match (&self.transformer, &self.transformer_config) {
+            #[cfg(feature = "bert")]
             (Bert(_), ConfigOption::Bert(conf)) => conf.num_hidden_layers as usize,
-            (Bert(_), _) => unreachable!(),
+            #[cfg(feature = "distilbert")]
             (DistilBert(_), ConfigOption::DistilBert(conf)) => conf.n_layers as usize,
-            (DistilBert(_), _) => unreachable!(),
+            #[cfg(feature = "roberta")]
             (Roberta(_), ConfigOption::Bert(conf)) => conf.num_hidden_layers as usize,
-            (Roberta(_), _) => unreachable!(),
+            #[cfg(feature = "albert")]
             (Albert(_), ConfigOption::Albert(conf)) => conf.num_hidden_layers as usize,
-            (Albert(_), _) => unreachable!(),
+            #[cfg(feature = "t5")]
             (T5(_), ConfigOption::T5(conf)) => conf.num_layers as usize,
-            (T5(_), _) => unreachable!(),
+            #[cfg(feature = "jina-bert")]
+            (JinaBert(_), ConfigOption::JinaBert(conf)) => conf.num_hidden_layers as usize,
+            #[cfg(feature = "nomic-bert")]
+            (NomicBert(_), ConfigOption::NomicBert(conf)) => conf.n_layer as usize,
+            _ => unreachable!(),
         }
     }
 
     fn nb_heads(&self) -> usize {
         use SentenceEmbeddingsOption::*;
         match (&self.transformer, &self.transformer_config) {
+            #[cfg(feature = "bert")]
             (Bert(_), ConfigOption::Bert(conf)) => conf.num_attention_heads as usize,
-            (Bert(_), _) => unreachable!(),
+            #[cfg(feature = "distilbert")]
             (DistilBert(_), ConfigOption::DistilBert(conf)) => conf.n_heads as usize,
-            (DistilBert(_), _) => unreachable!(),
+            #[cfg(feature = "roberta")]
             (Roberta(_), ConfigOption::Roberta(conf)) => conf.num_attention_heads as usize,
-            (Roberta(_), _) => unreachable!(),
+            #[cfg(feature = "albert")]
             (Albert(_), ConfigOption::Albert(conf)) => conf.num_attention_heads as usize,
-            (Albert(_), _) => unreachable!(),
+            #[cfg(feature = "t5")]
             (T5(_), ConfigOption::T5(conf)) => conf.num_heads as usize,
-            (T5(_), _) => unreachable!(),
+            #[cfg(feature = "jina-bert")]
+            (JinaBert(_), ConfigOption::JinaBert(conf)) => conf.num_attention_heads as usize,
+            #[cfg(feature = "nomic-bert")]
+            (NomicBert(_), ConfigOption::NomicBert(conf)) => conf.n_head as usize,
+            _ => unreachable!(),
         }
     }
 
-    /// Computes sentence embeddings, also outputs `AttentionOutput`s.
+    /// Computes sentence embeddings, also outputs `AttentionOutput`s. Attentions are not available for ONNX models.
     pub fn encode_with_attention<S>(
         &self,
         inputs: &[S],
@@ -503,3 +789,29 @@ pub struct SentenceEmbeddingsModelOutput {
     pub embeddings: Tensor,
     pub all_attentions: Option<Vec<Tensor>>,
 }
+
+/// # Task prefixes used by instruction-aware embeddings models
+/// Nomic embed models expect every input to start with a prefix describing the task, and queries and
+/// documents of a retrieval task should be encoded with different prefixes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SentenceEmbeddingsTaskPrefix {
+    /// Query of a retrieval task (`search_query: `)
+    SearchQuery,
+    /// Document of a retrieval task (`search_document: `)
+    SearchDocument,
+    /// Clustering of texts (`clustering: `)
+    Clustering,
+    /// Features for a downstream classifier (`classification: `)
+    Classification,
+}
+
+impl AsRef<str> for SentenceEmbeddingsTaskPrefix {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::SearchQuery => "search_query: ",
+            Self::SearchDocument => "search_document: ",
+            Self::Clustering => "clustering: ",
+            Self::Classification => "classification: ",
+        }
+    }
+}
@@ -17,13 +17,24 @@ pub struct SentenceEmbeddingsConfigResources;
 pub struct SentenceEmbeddingsTokenizerConfigResources;
 
 pub enum SentenceEmbeddingsModelType {
+    #[cfg(feature = "distilbert")]
     DistiluseBaseMultilingualCased,
+    #[cfg(feature = "bert")]
     BertBaseNliMeanTokens,
+    #[cfg(feature = "bert")]
     AllMiniLmL12V2,
+    #[cfg(feature = "bert")]
     AllMiniLmL6V2,
+    #[cfg(feature = "roberta")]
     AllDistilrobertaV1,
+    #[cfg(feature = "albert")]
     ParaphraseAlbertSmallV2,
+    #[cfg(feature = "t5")]
     SentenceT5Base,
+    #[cfg(feature = "jina-bert")]
+    JinaEmbeddingsV2BaseEn,
+    #[cfg(feature = "nomic-bert")]
+    NomicEmbedTextV1,
 }
 
 impl SentenceEmbeddingsModulesConfigResources {
@@ -62,6 +73,16 @@ impl SentenceEmbeddingsModulesConfigResources {
         "sentence-t5-base/sbert-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/modules.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/sbert-config",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/modules.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/sbert-config",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/modules.json",
+    );
 }
 
 impl SentenceEmbeddingsDenseResources {
@@ -126,6 +147,16 @@ impl SentenceEmbeddingsPoolingConfigResources {
         "sentence-t5-base/sbert-pooling-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/1_Pooling/config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/sbert-pooling-config",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/1_Pooling/config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/sbert-pooling-config",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/1_Pooling/config.json",
+    );
 }
 
 impl SentenceEmbeddingsConfigResources {
@@ -164,6 +195,16 @@ impl SentenceEmbeddingsConfigResources {
         "sentence-t5-base/sbert-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/sentence_bert_config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/sbert-config",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/sentence_bert_config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/sbert-config",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/sentence_bert_config.json",
+    );
 }
 
 impl SentenceEmbeddingsTokenizerConfigResources {
@@ -202,4 +243,14 @@ impl SentenceEmbeddingsTokenizerConfigResources {
         "sentence-t5-base/tokenizer-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/tokenizer_config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/jinaai/jina-embeddings-v2-base-en>.
+    pub const JINA_EMBEDDINGS_V2_BASE_EN: (&'static str, &'static str) = (
+        "jina-embeddings-v2-base-en/tokenizer-config",
+        "https://huggingface.co/jinaai/jina-embeddings-v2-base-en/resolve/main/tokenizer_config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/nomic-ai/nomic-embed-text-v1>.
+    pub const NOMIC_EMBED_TEXT_V1: (&'static str, &'static str) = (
+        "nomic-embed-text-v1/tokenizer-config",
+        "https://huggingface.co/nomic-ai/nomic-embed-text-v1/resolve/main/tokenizer_config.json",
+    );
 }
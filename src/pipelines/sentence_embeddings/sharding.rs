@@ -0,0 +1,449 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Sharded corpus encoding
+//! Splits the encoding of a large corpus across several worker processes, possibly running on different machines
+//! sharing a file system. The corpus is divided into contiguous shards described by a manifest stored in an output
+//! directory. Each worker claims pending shards, encodes the sentences of the shard with its own
+//! `SentenceEmbeddingsModel` and writes the embeddings to a shard file. Once all shards are encoded, the shards are
+//! merged into a single file of embeddings that is memory-mapped when loaded.
+//!
+//! Embeddings are stored as raw little-endian `f32` values, row-major (one embedding of `embedding_dim` values
+//! after the other). The merged file can also be memory-mapped by other tools (e.g. `numpy.memmap`).
+//!
+//! Shard claims rely on the atomic creation of lock files in the output directory. A worker interrupted while
+//! encoding a shard leaves a lock file without a completed shard: the shard can be made available again with
+//! `release_shard`.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::sentence_embeddings::sharding::ShardedEncodingCoordinator;
+//! use rust_bert::pipelines::sentence_embeddings::{
+//!     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+//! };
+//! # fn main() -> anyhow::Result<()> {
+//! # fn read_corpus_lines(range: std::ops::Range<usize>) -> Vec<String> { vec![] }
+//! let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+//!     .create_model()?;
+//!
+//! // Run once, by the coordinator
+//! let coordinator = ShardedEncodingCoordinator::create(
+//!     "path/to/shared/output",
+//!     1_000_000_000,
+//!     1_000,
+//!     model.get_embedding_dim()?,
+//! )?;
+//!
+//! // Run by each worker
+//! let worker = ShardedEncodingCoordinator::open("path/to/shared/output")?;
+//! while let Some(shard) = worker.claim_shard()? {
+//!     let sentences = read_corpus_lines(worker.shard_range(shard));
+//!     worker.encode_shard(&model, shard, sentences, 256)?;
+//! }
+//!
+//! // Run by the coordinator once all shards are encoded
+//! let embeddings = coordinator.merge()?;
+//! let embeddings_tensor = embeddings.load()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tch::{Device, Kind, Tensor};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const MERGED_FILE: &str = "embeddings.f32";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Description of a sharded corpus encoding job
+pub struct ShardedEncodingManifest {
+    /// Number of sentences of the corpus
+    pub corpus_size: usize,
+    /// Number of shards the corpus is split into
+    pub num_shards: usize,
+    /// Dimension of the embeddings
+    pub embedding_dim: i64,
+}
+
+impl ShardedEncodingManifest {
+    /// Range of the corpus sentences of a shard. The shards are contiguous and their sizes differ by at most one.
+    pub fn shard_range(&self, shard: usize) -> Range<usize> {
+        let shard_size = self.corpus_size / self.num_shards;
+        let remainder = self.corpus_size % self.num_shards;
+        let start = shard * shard_size + shard.min(remainder);
+        let end = start + shard_size + usize::from(shard < remainder);
+        start..end
+    }
+}
+
+/// # Coordinator of a sharded corpus encoding job
+/// Shared by the coordinator (creating the job and merging the shards) and the workers (encoding the shards).
+pub struct ShardedEncodingCoordinator {
+    output_dir: PathBuf,
+    manifest: ShardedEncodingManifest,
+}
+
+impl ShardedEncodingCoordinator {
+    /// Creates a new encoding job in an output directory, writing its manifest. If the directory already contains
+    /// the manifest of an identical job, the existing job is opened so that an interrupted job can be resumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_dir` - directory storing the manifest and the shards, accessible to all workers
+    /// * `corpus_size` - number of sentences of the corpus
+    /// * `num_shards` - number of shards the corpus is split into
+    /// * `embedding_dim` - dimension of the embeddings of the model used by the workers
+    pub fn create<P: AsRef<Path>>(
+        output_dir: P,
+        corpus_size: usize,
+        num_shards: usize,
+        embedding_dim: i64,
+    ) -> Result<ShardedEncodingCoordinator, RustBertError> {
+        if (num_shards == 0) | (num_shards > corpus_size) | (embedding_dim <= 0) {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Cannot split a corpus of {corpus_size} sentences in {num_shards} shards of embeddings of dimension {embedding_dim}"
+            )));
+        }
+        let manifest = ShardedEncodingManifest {
+            corpus_size,
+            num_shards,
+            embedding_dim,
+        };
+        let output_dir = output_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&output_dir)?;
+        let manifest_path = output_dir.join(MANIFEST_FILE);
+        if manifest_path.exists() {
+            let coordinator = Self::open(&output_dir)?;
+            if coordinator.manifest != manifest {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "{} already contains a different encoding job",
+                    output_dir.display()
+                )));
+            }
+            return Ok(coordinator);
+        }
+        let manifest_json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| RustBertError::IOError(e.to_string()))?;
+        write_atomically(&manifest_path, manifest_json.as_bytes())?;
+        Ok(ShardedEncodingCoordinator {
+            output_dir,
+            manifest,
+        })
+    }
+
+    /// Opens an existing encoding job from its output directory (e.g. in a worker process)
+    ///
+    /// # Arguments
+    ///
+    /// * `output_dir` - directory storing the manifest and the shards
+    pub fn open<P: AsRef<Path>>(
+        output_dir: P,
+    ) -> Result<ShardedEncodingCoordinator, RustBertError> {
+        let output_dir = output_dir.as_ref().to_path_buf();
+        let manifest_file = File::open(output_dir.join(MANIFEST_FILE))?;
+        let manifest: ShardedEncodingManifest =
+            serde_json::from_reader(BufReader::new(manifest_file)).map_err(|e| {
+                RustBertError::InvalidConfigurationError(format!(
+                    "Invalid encoding job manifest: {e}"
+                ))
+            })?;
+        Ok(ShardedEncodingCoordinator {
+            output_dir,
+            manifest,
+        })
+    }
+
+    /// Returns the manifest of the job
+    pub fn manifest(&self) -> &ShardedEncodingManifest {
+        &self.manifest
+    }
+
+    /// Range of the corpus sentences of a shard
+    pub fn shard_range(&self, shard: usize) -> Range<usize> {
+        self.manifest.shard_range(shard)
+    }
+
+    /// Checks if a shard has been fully encoded
+    pub fn is_shard_complete(&self, shard: usize) -> bool {
+        let expected_size = self.shard_range(shard).len() as u64
+            * self.manifest.embedding_dim as u64
+            * std::mem::size_of::<f32>() as u64;
+        fs::metadata(self.shard_path(shard))
+            .map(|metadata| metadata.len() == expected_size)
+            .unwrap_or(false)
+    }
+
+    /// Returns the shards that have not been fully encoded yet
+    pub fn pending_shards(&self) -> Vec<usize> {
+        (0..self.manifest.num_shards)
+            .filter(|shard| !self.is_shard_complete(*shard))
+            .collect()
+    }
+
+    /// Claims the next shard neither encoded nor claimed by another worker. Returns `None` when all shards are
+    /// claimed.
+    pub fn claim_shard(&self) -> Result<Option<usize>, RustBertError> {
+        for shard in self.pending_shards() {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.lock_path(shard))
+            {
+                Ok(_) => return Ok(Some(shard)),
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Releases the claim on a shard, for example after the failure of the worker encoding it
+    pub fn release_shard(&self, shard: usize) -> Result<(), RustBertError> {
+        match fs::remove_file(self.lock_path(shard)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Encodes the sentences of a shard and writes their embeddings to the shard file. The shard file is only
+    /// published once all sentences are encoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `SentenceEmbeddingsModel` producing embeddings of the dimension of the job
+    /// * `shard` - index of the shard
+    /// * `sentences` - sentences of the shard, in order (the corpus sentences in `shard_range(shard)`)
+    /// * `batch_size` - number of sentences encoded by each forward pass of the model
+    pub fn encode_shard<I, S>(
+        &self,
+        model: &SentenceEmbeddingsModel,
+        shard: usize,
+        sentences: I,
+        batch_size: usize,
+    ) -> Result<(), RustBertError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str> + Send + Sync,
+    {
+        if shard >= self.manifest.num_shards {
+            return Err(RustBertError::ValueError(format!(
+                "Shard {shard} does not exist, the job has {} shards",
+                self.manifest.num_shards
+            )));
+        }
+        if model.get_embedding_dim()? != self.manifest.embedding_dim {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The model embeddings have dimension {}, expected {}",
+                model.get_embedding_dim()?,
+                self.manifest.embedding_dim
+            )));
+        }
+        let batch_size = batch_size.max(1);
+        let expected_count = self.shard_range(shard).len();
+        let temporary_path = self.shard_path(shard).with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+
+        let mut count = 0;
+        let mut sentences = sentences.into_iter();
+        loop {
+            let batch = sentences.by_ref().take(batch_size).collect::<Vec<S>>();
+            if batch.is_empty() {
+                break;
+            }
+            count += batch.len();
+            if count > expected_count {
+                break;
+            }
+            let embeddings = model.encode_as_tensor(batch.as_slice())?.embeddings;
+            let values = Vec::<f32>::try_from(
+                embeddings
+                    .to_kind(Kind::Float)
+                    .to(Device::Cpu)
+                    .contiguous()
+                    .view(-1),
+            )?;
+            for value in values {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        if count != expected_count {
+            drop(writer);
+            fs::remove_file(&temporary_path)?;
+            return Err(RustBertError::ValueError(if count > expected_count {
+                format!("Shard {shard} contains {expected_count} sentences, received more")
+            } else {
+                format!("Shard {shard} contains {expected_count} sentences, received {count}")
+            }));
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        drop(writer);
+        fs::rename(&temporary_path, self.shard_path(shard))?;
+        Ok(())
+    }
+
+    /// Merges the encoded shards, in order, into a single file of embeddings. The shard files are removed once
+    /// merged. Returns an error if some shards are not encoded yet.
+    pub fn merge(&self) -> Result<ShardedEmbeddings, RustBertError> {
+        let merged = ShardedEmbeddings {
+            path: self.output_dir.join(MERGED_FILE),
+            num_embeddings: self.manifest.corpus_size,
+            embedding_dim: self.manifest.embedding_dim,
+        };
+        let pending_shards = self.pending_shards();
+        if !pending_shards.is_empty() {
+            if merged.is_complete() {
+                return Ok(merged);
+            }
+            return Err(RustBertError::ValueError(format!(
+                "{} shards are not encoded yet (e.g. shard {})",
+                pending_shards.len(),
+                pending_shards[0]
+            )));
+        }
+
+        let temporary_path = merged.path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+        for shard in 0..self.manifest.num_shards {
+            let mut shard_file = File::open(self.shard_path(shard))?;
+            std::io::copy(&mut shard_file, &mut writer)?;
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        drop(writer);
+        fs::rename(&temporary_path, &merged.path)?;
+
+        for shard in 0..self.manifest.num_shards {
+            fs::remove_file(self.shard_path(shard))?;
+            self.release_shard(shard)?;
+        }
+        Ok(merged)
+    }
+
+    fn shard_path(&self, shard: usize) -> PathBuf {
+        self.output_dir.join(format!("shard-{shard:06}.f32"))
+    }
+
+    fn lock_path(&self, shard: usize) -> PathBuf {
+        self.output_dir.join(format!("shard-{shard:06}.lock"))
+    }
+}
+
+/// # Embeddings of a corpus, stored in a single file
+pub struct ShardedEmbeddings {
+    path: PathBuf,
+    num_embeddings: usize,
+    embedding_dim: i64,
+}
+
+impl ShardedEmbeddings {
+    /// Path of the file storing the embeddings (raw little-endian `f32` values, row-major)
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of embeddings
+    pub fn num_embeddings(&self) -> usize {
+        self.num_embeddings
+    }
+
+    /// Dimension of the embeddings
+    pub fn embedding_dim(&self) -> i64 {
+        self.embedding_dim
+    }
+
+    /// Memory-maps the embeddings as a `Tensor` of shape (*number of embeddings*, *embedding dimension*). The
+    /// embeddings are read from the file as they are accessed rather than loaded in memory, and modifications to the
+    /// tensor are written to the file.
+    pub fn load(&self) -> Result<Tensor, RustBertError> {
+        let num_values = self.num_embeddings as i64 * self.embedding_dim;
+        let path = self.path.to_str().ok_or_else(|| {
+            RustBertError::IOError(format!("Invalid path {}", self.path.display()))
+        })?;
+        Ok(
+            Tensor::f_from_file(path, true, num_values, (Kind::Float, Device::Cpu))?
+                .view([self.num_embeddings as i64, self.embedding_dim]),
+        )
+    }
+
+    fn is_complete(&self) -> bool {
+        let expected_size = self.num_embeddings as u64
+            * self.embedding_dim as u64
+            * std::mem::size_of::<f32>() as u64;
+        fs::metadata(&self.path)
+            .map(|metadata| metadata.len() == expected_size)
+            .unwrap_or(false)
+    }
+}
+
+/// Writes a file through a temporary file renamed once written, so that readers never observe a partial file
+fn write_atomically(path: &Path, content: &[u8]) -> Result<(), RustBertError> {
+    let temporary_path = path.with_extension("tmp");
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shard_ranges() {
+        let manifest = ShardedEncodingManifest {
+            corpus_size: 10,
+            num_shards: 3,
+            embedding_dim: 4,
+        };
+        let ranges = (0..3)
+            .map(|shard| manifest.shard_range(shard))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![0..4, 4..7, 7..10]);
+    }
+
+    #[test]
+    fn merged_shards_are_file_backed() -> anyhow::Result<()> {
+        let output_dir = tempfile::tempdir()?;
+        let coordinator = ShardedEncodingCoordinator::create(output_dir.path(), 5, 2, 2)?;
+        for shard in 0..2 {
+            let content = coordinator
+                .shard_range(shard)
+                .flat_map(|index| [index as f32, -(index as f32)])
+                .flat_map(f32::to_le_bytes)
+                .collect::<Vec<u8>>();
+            write_atomically(&coordinator.shard_path(shard), &content)?;
+        }
+
+        let merged = coordinator.merge()?;
+        // The shard files are removed once merged
+        assert_eq!(coordinator.pending_shards(), vec![0, 1]);
+        let embeddings = merged.load()?;
+        assert_eq!(embeddings.size(), vec![5, 2]);
+        assert_eq!(
+            Vec::<f32>::try_from(embeddings.view(-1))?,
+            vec![0.0, -0.0, 1.0, -1.0, 2.0, -2.0, 3.0, -3.0, 4.0, -4.0]
+        );
+
+        // Writes to the tensor reach the file as it is mapped in shared mode
+        let _ = embeddings.get(0).fill_(7.0);
+        let content = fs::read(merged.path())?;
+        assert_eq!(&content[..4], &7f32.to_le_bytes());
+        Ok(())
+    }
+}
@@ -53,19 +53,50 @@
 //! ]
 //! # ;
 //! ```
+//!
+//! Aspect-based sentiment analysis (`SentimentModel::predict_aspects`) returns the sentiment expressed towards each
+//! of a list of aspects of a text (e.g. the food and the service of a restaurant review), which a single
+//! document-level sentiment does not capture. It requires a model classifying (text, aspect) pairs, for example a
+//! DeBERTa model fine-tuned for aspect-based sentiment analysis (`AspectSentimentMode::SentencePair`), or a natural
+//! language inference model scoring a hypothesis built for each aspect and polarity (`AspectSentimentMode::Nli`).
 
-use crate::common::error::RustBertError;
+use crate::common::error::{InputError, RustBertError};
 use crate::pipelines::common::TokenizerOption;
 use crate::pipelines::sequence_classification::{
-    SequenceClassificationConfig, SequenceClassificationModel,
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
 };
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// Enum with the possible sentiment polarities. Note that the pre-trained SST2 model does not include neutral sentiment.
 pub enum SentimentPolarity {
     Positive,
     Negative,
+    Neutral,
+}
+
+impl SentimentPolarity {
+    /// Polarity of a classification label (e.g. `POSITIVE`, `Negative` or `neutral`), if the label denotes a polarity
+    pub fn from_label(label: &str) -> Option<SentimentPolarity> {
+        let label = label.trim().to_lowercase();
+        if label.starts_with("pos") {
+            Some(SentimentPolarity::Positive)
+        } else if label.starts_with("neg") {
+            Some(SentimentPolarity::Negative)
+        } else if label.starts_with("neu") {
+            Some(SentimentPolarity::Neutral)
+        } else {
+            None
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            SentimentPolarity::Positive => "positive",
+            SentimentPolarity::Negative => "negative",
+            SentimentPolarity::Neutral => "neutral",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,6 +108,47 @@ pub struct Sentiment {
     pub score: f64,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Sentiment expressed towards an aspect of a text.
+pub struct AspectSentiment {
+    /// Aspect of the text
+    pub aspect: String,
+    /// Polarity of the sentiment towards the aspect
+    pub polarity: SentimentPolarity,
+    /// Confidence score
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// # Prompting of the model for aspect-based sentiment analysis
+pub enum AspectSentimentMode {
+    /// The model classifies (text, aspect) pairs, with labels denoting sentiment polarities (e.g. `Positive`,
+    /// `Negative` and `Neutral`), as models fine-tuned for aspect-based sentiment analysis
+    SentencePair,
+    /// The model is a natural language inference model scoring the entailment of a hypothesis built for each aspect
+    /// and polarity by the template, where `{aspect}` and `{polarity}` are replaced by the aspect and the polarity
+    /// name (`positive`, `negative` or `neutral`). The entailment probabilities are normalized over the polarities.
+    Nli {
+        hypothesis_template: String,
+        polarities: Vec<SentimentPolarity>,
+    },
+}
+
+impl AspectSentimentMode {
+    /// Natural language inference prompting with the hypothesis `The sentiment towards {aspect} is {polarity}.` and
+    /// positive, negative and neutral polarities
+    pub fn nli() -> AspectSentimentMode {
+        AspectSentimentMode::Nli {
+            hypothesis_template: "The sentiment towards {aspect} is {polarity}.".to_string(),
+            polarities: vec![
+                SentimentPolarity::Positive,
+                SentimentPolarity::Negative,
+                SentimentPolarity::Neutral,
+            ],
+        }
+    }
+}
+
 pub type SentimentConfig = SequenceClassificationConfig;
 
 /// # SentimentClassifier to perform sentiment analysis
@@ -200,7 +272,183 @@ impl SentimentModel {
         }
         sentiments
     }
+
+    /// Extract the sentiment expressed towards each aspect of a batch of texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract the sentiments from.
+    /// * `aspects` - `&[&str]` Aspects to extract the sentiment of, for every text.
+    /// * `mode` - `&AspectSentimentMode` prompting of the model (sentence pair classification or natural language inference).
+    ///
+    /// # Returns
+    /// * `Vec<Vec<AspectSentiment>>` Sentiment towards each aspect (in the order provided) for each text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::bart::{
+    ///     BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources,
+    /// };
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType};
+    /// use rust_bert::pipelines::sentiment::{AspectSentimentMode, SentimentConfig, SentimentModel};
+    /// use rust_bert::resources::RemoteResource;
+    ///
+    /// // Natural language inference model (BART fine-tuned on MNLI)
+    /// let config = SentimentConfig::new(
+    ///     ModelType::Bart,
+    ///     ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+    ///         BartModelResources::BART_MNLI,
+    ///     ))),
+    ///     RemoteResource::from_pretrained(BartConfigResources::BART_MNLI),
+    ///     RemoteResource::from_pretrained(BartVocabResources::BART_MNLI),
+    ///     Some(RemoteResource::from_pretrained(BartMergesResources::BART_MNLI)),
+    ///     false,
+    ///     None,
+    ///     None,
+    /// );
+    /// let sentiment_model = SentimentModel::new(config)?;
+    ///
+    /// let output = sentiment_model.predict_aspects(
+    ///     &["The pasta was delicious but the waiter was rude."],
+    ///     &["food", "service"],
+    ///     &AspectSentimentMode::nli(),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_aspects(
+        &self,
+        input: &[&str],
+        aspects: &[&str],
+        mode: &AspectSentimentMode,
+    ) -> Result<Vec<Vec<AspectSentiment>>, RustBertError> {
+        if aspects.is_empty() {
+            return Err(InputError::NoCandidateLabels.into());
+        }
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let scores = match mode {
+            AspectSentimentMode::SentencePair => {
+                let pairs = input
+                    .iter()
+                    .flat_map(|text| aspects.iter().map(move |aspect| (*text, *aspect)))
+                    .collect::<Vec<(&str, &str)>>();
+                let label_scores = self
+                    .sequence_classification_model
+                    .predict_pairs_scores(&pairs);
+                label_scores
+                    .iter()
+                    .map(|labels| pair_polarity(labels))
+                    .collect::<Result<Vec<(SentimentPolarity, f64)>, RustBertError>>()?
+            }
+            AspectSentimentMode::Nli {
+                hypothesis_template,
+                polarities,
+            } => {
+                if polarities.is_empty() {
+                    return Err(RustBertError::InvalidConfigurationError(
+                        "At least one polarity should be scored for natural language inference"
+                            .into(),
+                    ));
+                }
+                let entailment_id = self
+                    .sequence_classification_model
+                    .get_label_mapping()
+                    .iter()
+                    .find(|(_, label)| label.to_lowercase().starts_with("entail"))
+                    .map(|(id, _)| *id)
+                    .ok_or_else(|| {
+                        RustBertError::InvalidConfigurationError(
+                            "The model configuration does not define an entailment label (natural language inference model expected)".into(),
+                        )
+                    })?;
+                let hypotheses = aspects
+                    .iter()
+                    .flat_map(|aspect| {
+                        polarities.iter().map(move |polarity| {
+                            hypothesis_template
+                                .replace("{aspect}", aspect)
+                                .replace("{polarity}", polarity.name())
+                        })
+                    })
+                    .collect::<Vec<String>>();
+                let pairs = input
+                    .iter()
+                    .flat_map(|text| {
+                        hypotheses
+                            .iter()
+                            .map(move |hypothesis| (*text, hypothesis.as_str()))
+                    })
+                    .collect::<Vec<(&str, &str)>>();
+                let entailment_scores = self
+                    .sequence_classification_model
+                    .predict_pairs_scores(&pairs)
+                    .iter()
+                    .map(|labels| labels[entailment_id as usize].score)
+                    .collect::<Vec<f64>>();
+                entailment_scores
+                    .chunks(polarities.len())
+                    .map(|scores| nli_polarity(polarities, scores))
+                    .collect()
+            }
+        };
+
+        Ok(scores
+            .chunks(aspects.len())
+            .map(|text_scores| {
+                aspects
+                    .iter()
+                    .zip(text_scores)
+                    .map(|(aspect, (polarity, score))| AspectSentiment {
+                        aspect: aspect.to_string(),
+                        polarity: *polarity,
+                        score: *score,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+/// Most likely polarity among the labels of a sentence pair classification, with its probability
+fn pair_polarity(labels: &[Label]) -> Result<(SentimentPolarity, f64), RustBertError> {
+    labels
+        .iter()
+        .filter_map(|label| {
+            SentimentPolarity::from_label(&label.text).map(|polarity| (polarity, label.score))
+        })
+        .max_by(|(_, score_a), (_, score_b)| score_a.total_cmp(score_b))
+        .ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(format!(
+                "The model labels ({}) do not denote sentiment polarities",
+                labels
+                    .iter()
+                    .map(|label| label.text.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ))
+        })
+}
+
+/// Most likely polarity given the entailment probabilities of the hypotheses of each polarity, normalized over the
+/// polarities
+fn nli_polarity(
+    polarities: &[SentimentPolarity],
+    entailment_scores: &[f64],
+) -> (SentimentPolarity, f64) {
+    let total = entailment_scores.iter().sum::<f64>();
+    let (polarity, score) = polarities
+        .iter()
+        .zip(entailment_scores)
+        .max_by(|(_, score_a), (_, score_b)| score_a.total_cmp(score_b))
+        .unwrap();
+    let score = if total > 0.0 { score / total } else { 0.0 };
+    (*polarity, score)
 }
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -211,4 +459,30 @@ mod test {
         let config = SentimentConfig::default();
         let _: Box<dyn Send> = Box::new(SentimentModel::new(config));
     }
+
+    #[test]
+    fn aspect_polarity_aggregation() {
+        let labels = ["Negative", "Neutral", "Positive"]
+            .iter()
+            .zip([0.1, 0.3, 0.6])
+            .enumerate()
+            .map(|(id, (text, score))| Label {
+                text: text.to_string(),
+                score,
+                id: id as i64,
+                sentence: 0,
+            })
+            .collect::<Vec<Label>>();
+        assert_eq!(
+            pair_polarity(&labels).unwrap(),
+            (SentimentPolarity::Positive, 0.6)
+        );
+        assert!(pair_polarity(&labels[..0]).is_err());
+
+        let polarities = [SentimentPolarity::Positive, SentimentPolarity::Negative];
+        let (polarity, score) = nli_polarity(&polarities, &[0.2, 0.6]);
+        assert_eq!(polarity, SentimentPolarity::Negative);
+        assert!((score - 0.75).abs() < 1e-9);
+        assert_eq!(SentimentPolarity::from_label("LABEL_0"), None);
+    }
 }
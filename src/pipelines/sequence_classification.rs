@@ -58,31 +58,55 @@
 //! ]
 //! # ;
 //! ```
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForSequenceClassification;
+#[cfg(feature = "bart")]
 use crate::bart::BartForSequenceClassification;
+#[cfg(feature = "bert")]
 use crate::bert::BertForSequenceClassification;
+#[cfg(feature = "bigbird")]
+use crate::bigbird::BigBirdForSequenceClassification;
 use crate::common::error::RustBertError;
+use crate::common::mps;
+use crate::common::precision::{
+    is_half_precision, set_var_store_kind, with_attention_fp32_accumulation,
+};
+#[cfg(feature = "deberta")]
 use crate::deberta::DebertaForSequenceClassification;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertModelClassifier;
+#[cfg(feature = "fnet")]
 use crate::fnet::FNetForSequenceClassification;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerForSequenceClassification;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForSequenceClassification;
+#[cfg(feature = "modernbert")]
+use crate::modernbert::ModernBertForSequenceClassification;
 use crate::pipelines::common::{
-    get_device, ConfigOption, ModelResource, ModelType, TokenizerOption,
+    check_label_mapping, get_device, ConfigOption, EncodedInput, ModelResource, ModelType,
+    TokenTypeIdsHandler, TokenTypeStrategy, TokenizerOption,
 };
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerForSequenceClassification;
 use crate::resources::ResourceProvider;
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForSequenceClassification;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForSequenceClassification;
+use rust_tokenizers::tokenizer::TruncationStrategy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use tch::nn::VarStore;
 use tch::{no_grad, Device, Kind, Tensor};
 
+use crate::common::settings::default_device;
+#[cfg(feature = "deberta-v2")]
 use crate::deberta_v2::DebertaV2ForSequenceClassification;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::{config::ONNXEnvironmentConfig, ONNXEncoder};
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "distilbert"))]
 use crate::{
     distilbert::{DistilBertConfigResources, DistilBertModelResources, DistilBertVocabResources},
     resources::RemoteResource,
@@ -123,6 +147,10 @@ pub struct SequenceClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
+    /// Token type ids passed to the model for sentence pairs and pre-encoded inputs (default: depending on the architecture)
+    pub token_type_strategy: TokenTypeStrategy,
 }
 
 impl SequenceClassificationConfig {
@@ -159,12 +187,14 @@ impl SequenceClassificationConfig {
             lower_case,
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
+            token_type_strategy: TokenTypeStrategy::default(),
         }
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "distilbert"))]
 impl Default for SequenceClassificationConfig {
     /// Provides a defaultSST-2 sentiment analysis model (English)
     fn default() -> SequenceClassificationConfig {
@@ -187,31 +217,50 @@ impl Default for SequenceClassificationConfig {
 /// # Abstraction that holds one particular sequence classification model, for any of the supported models
 pub enum SequenceClassificationOption {
     /// Bert for Sequence Classification
+    #[cfg(feature = "bert")]
     Bert(BertForSequenceClassification),
     /// DeBERTa for Sequence Classification
+    #[cfg(feature = "deberta")]
     Deberta(DebertaForSequenceClassification),
     /// DeBERTa V2 for Sequence Classification
+    #[cfg(feature = "deberta-v2")]
     DebertaV2(DebertaV2ForSequenceClassification),
     /// DistilBert for Sequence Classification
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertModelClassifier),
     /// MobileBert for Sequence Classification
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForSequenceClassification),
     /// Roberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForSequenceClassification),
     /// XLMRoberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForSequenceClassification),
     /// Albert for Sequence Classification
+    #[cfg(feature = "albert")]
     Albert(AlbertForSequenceClassification),
     /// XLNet for Sequence Classification
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForSequenceClassification),
     /// Bart for Sequence Classification
+    #[cfg(feature = "bart")]
     Bart(BartForSequenceClassification),
     /// Reformer for Sequence Classification
+    #[cfg(feature = "reformer")]
     Reformer(ReformerForSequenceClassification),
     /// Longformer for Sequence Classification
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForSequenceClassification),
+    /// BigBird for Sequence Classification
+    #[cfg(feature = "bigbird")]
+    BigBird(BigBirdForSequenceClassification),
     /// FNet for Sequence Classification
+    #[cfg(feature = "fnet")]
     FNet(FNetForSequenceClassification),
+    /// ModernBERT for Sequence Classification
+    #[cfg(feature = "modernbert")]
+    ModernBert(ModernBertForSequenceClassification),
     /// ONNX Model for Sequence Classification
     #[cfg(feature = "onnx")]
     ONNX(ONNXEncoder),
@@ -226,20 +275,42 @@ impl SequenceClassificationOption {
     ///     `ModelResources` (Torch or ONNX) and `ModelType` (Architecture for Torch models) variants provided and
     pub fn new(config: &SequenceClassificationConfig) -> Result<Self, RustBertError> {
         match config.model_resource {
-            ModelResource::Torch(_) => Self::new_torch(config),
+            ModelResource::Torch(_) => Self::new_torch(config, false),
             #[cfg(feature = "onnx")]
             ModelResource::ONNX(_) => Self::new_onnx(config),
         }
     }
 
-    fn new_torch(config: &SequenceClassificationConfig) -> Result<Self, RustBertError> {
+    /// Instantiate a new sequence classification model returning the attention weights of every layer (see
+    /// `forward_with_attentions`). Only available for Torch BERT, RoBERTa and DistilBERT models.
+    pub(crate) fn new_with_attention_outputs(
+        config: &SequenceClassificationConfig,
+    ) -> Result<Self, RustBertError> {
+        match config.model_resource {
+            ModelResource::Torch(_) => Self::new_torch(config, true),
+            #[cfg(feature = "onnx")]
+            ModelResource::ONNX(_) => Err(RustBertError::InvalidConfigurationError(
+                "Attention outputs are not available for ONNX models".to_string(),
+            )),
+        }
+    }
+
+    fn new_torch(
+        config: &SequenceClassificationConfig,
+        output_attentions: bool,
+    ) -> Result<Self, RustBertError> {
         let device = config.device;
         let weights_path = config.model_resource.get_torch_local_path()?;
         let mut var_store = VarStore::new(device);
-        let model_config =
-            &ConfigOption::from_file(config.model_type, config.config_resource.get_local_path()?);
+        let mut model_config =
+            ConfigOption::from_file(config.model_type, config.config_resource.get_local_path()?);
+        if output_attentions {
+            model_config.enable_output_attentions()?;
+        }
+        let model_config = &model_config;
         let model_type = config.model_type;
         let model = match model_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(Self::Bert(
@@ -251,6 +322,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta")]
             ModelType::Deberta => {
                 if let ConfigOption::Deberta(config) = model_config {
                     Ok(Self::Deberta(
@@ -262,6 +334,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta-v2")]
             ModelType::DebertaV2 => {
                 if let ConfigOption::DebertaV2(config) = model_config {
                     Ok(Self::DebertaV2(
@@ -273,6 +346,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => {
                 if let ConfigOption::DistilBert(config) = model_config {
                     Ok(Self::DistilBert(
@@ -284,6 +358,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
                 if let ConfigOption::MobileBert(config) = model_config {
                     Ok(Self::MobileBert(
@@ -295,6 +370,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
                 if let ConfigOption::Roberta(config) = model_config {
                     Ok(Self::Roberta(
@@ -306,6 +382,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
                 if let ConfigOption::Roberta(config) = model_config {
                     Ok(Self::XLMRoberta(
@@ -317,6 +394,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
                 if let ConfigOption::Albert(config) = model_config {
                     Ok(Self::Albert(
@@ -328,6 +406,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
                 if let ConfigOption::XLNet(config) = model_config {
                     Ok(Self::XLNet(
@@ -339,6 +418,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "bart")]
             ModelType::Bart => {
                 if let ConfigOption::Bart(config) = model_config {
                     Ok(Self::Bart(
@@ -350,6 +430,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "reformer")]
             ModelType::Reformer => {
                 if let ConfigOption::Reformer(config) = model_config {
                     Ok(Self::Reformer(
@@ -361,6 +442,7 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
                 if let ConfigOption::Longformer(config) = model_config {
                     Ok(Self::Longformer(
@@ -372,6 +454,19 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "bigbird")]
+            ModelType::BigBird => {
+                if let ConfigOption::BigBird(config) = model_config {
+                    Ok(Self::BigBird(
+                        BigBirdForSequenceClassification::new(var_store.root(), config)?,
+                    ))
+                } else {
+                    Err(RustBertError::InvalidConfigurationError(
+                        "You can only supply a BigBirdConfig for BigBird!".to_string(),
+                    ))
+                }
+            }
+            #[cfg(feature = "fnet")]
             ModelType::FNet => {
                 if let ConfigOption::FNet(config) = model_config {
                     Ok(Self::FNet(
@@ -383,6 +478,18 @@ impl SequenceClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "modernbert")]
+            ModelType::ModernBert => {
+                if let ConfigOption::ModernBert(config) = model_config {
+                    Ok(Self::ModernBert(
+                        ModernBertForSequenceClassification::new(var_store.root(), config)?,
+                    ))
+                } else {
+                    Err(RustBertError::InvalidConfigurationError(
+                        "You can only supply a ModernBertConfig for ModernBERT!".to_string(),
+                    ))
+                }
+            }
             #[cfg(feature = "onnx")]
             ModelType::ONNX => Err(RustBertError::InvalidConfigurationError(
                 "A `ModelType::ONNX` ModelType was provided in the configuration with `ModelResources::TORCH`, these are incompatible".to_string(),
@@ -391,7 +498,10 @@ impl SequenceClassificationOption {
                 "Sequence Classification not implemented for {model_type:?}!",
             ))),
         }?;
-        var_store.load(weights_path)?;
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+        if let Some(kind) = config.kind {
+            set_var_store_kind(&mut var_store, kind)?;
+        }
         Ok(model)
     }
 
@@ -415,22 +525,58 @@ impl SequenceClassificationOption {
         )?))
     }
 
+    /// Quantizes the linear layers of the model to int8 for CPU inference (see the `quantization` module). Only
+    /// available for BERT, DistilBERT and RoBERTa models.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref mut model_ref) => model_ref.quantize_dynamic(),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref mut model_ref) => model_ref.quantize_dynamic(),
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref mut model_ref) | Self::XLMRoberta(ref mut model_ref) => {
+                model_ref.quantize_dynamic()
+            }
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Dynamic int8 quantization not implemented for {:?}",
+                self.model_type()
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this SequenceClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => ModelType::Deberta,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => ModelType::DebertaV2,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::Roberta,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "bart")]
             Self::Bart(_) => ModelType::Bart,
+            #[cfg(feature = "reformer")]
             Self::Reformer(_) => ModelType::Reformer,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(_) => ModelType::BigBird,
+            #[cfg(feature = "fnet")]
             Self::FNet(_) => ModelType::FNet,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => ModelType::ModernBert,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
         }
@@ -447,6 +593,7 @@ impl SequenceClassificationOption {
         train: bool,
     ) -> Tensor {
         match *self {
+            #[cfg(feature = "bart")]
             Self::Bart(ref model) => {
                 model
                     .forward_t(
@@ -459,6 +606,7 @@ impl SequenceClassificationOption {
                     )
                     .decoder_output
             }
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -471,6 +619,7 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "deberta")]
             Self::Deberta(ref model) => {
                 model
                     .forward_t(
@@ -484,6 +633,7 @@ impl SequenceClassificationOption {
                     .expect("Error in Deberta forward_t")
                     .logits
             }
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(ref model) => {
                 model
                     .forward_t(
@@ -497,18 +647,21 @@ impl SequenceClassificationOption {
                     .expect("Error in Deberta V2 forward_t")
                     .logits
             }
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(ref model) => {
                 model
                     .forward_t(input_ids, mask, input_embeds, train)
                     .expect("Error in distilbert forward_t")
                     .logits
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 model
                     .forward_t(input_ids, None, None, input_embeds, mask, train)
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -521,6 +674,7 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 model
                     .forward_t(
@@ -533,6 +687,7 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 model
                     .forward_t(
@@ -547,12 +702,14 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "reformer")]
             Self::Reformer(ref model) => {
                 model
                     .forward_t(input_ids, None, None, mask, None, train)
                     .expect("Error in Reformer forward pass.")
                     .logits
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 model
                     .forward_t(
@@ -567,12 +724,34 @@ impl SequenceClassificationOption {
                     .expect("Error in Longformer forward pass.")
                     .logits
             }
+            #[cfg(feature = "bigbird")]
+            Self::BigBird(ref model) => {
+                model
+                    .forward_t(
+                        input_ids,
+                        mask,
+                        token_type_ids,
+                        position_ids,
+                        input_embeds,
+                        train,
+                    )
+                    .expect("Error in BigBird forward pass.")
+                    .logits
+            }
+            #[cfg(feature = "fnet")]
             Self::FNet(ref model) => {
                 model
                     .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
                     .expect("Error in FNet forward pass.")
                     .logits
             }
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(ref model) => {
+                model
+                    .forward_t(input_ids, mask, input_embeds, train)
+                    .expect("Error in ModernBERT forward pass.")
+                    .logits
+            }
             #[cfg(feature = "onnx")]
             Self::ONNX(ref model) => {
                 let attention_mask = input_ids.unwrap().ones_like();
@@ -590,15 +769,82 @@ impl SequenceClassificationOption {
             }
         }
     }
+
+    /// Word embeddings matrix of the model, of shape (*vocab_size*, *hidden_size*). Only available for BERT, RoBERTa and
+    /// DistilBERT models.
+    pub(crate) fn get_word_embeddings(&self) -> Result<&Tensor, RustBertError> {
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref model) => Ok(model.get_word_embeddings()),
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
+                Ok(model.get_word_embeddings())
+            }
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref model) => Ok(model.get_word_embeddings()),
+            #[allow(unreachable_patterns)]
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Input embeddings are not available for {:?} models",
+                self.model_type()
+            ))),
+        }
+    }
+
+    /// Forward pass returning the logits and the attention weights of every layer, each of shape
+    /// (*batch size*, *num_heads*, *sequence_length*, *sequence_length*). The model must have been created with
+    /// `new_with_attention_outputs`.
+    pub(crate) fn forward_with_attentions(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<(Tensor, Vec<Tensor>), RustBertError> {
+        let (logits, all_attentions) = match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref model) => {
+                let output =
+                    model.forward_t(input_ids, mask, token_type_ids, None, input_embeds, train);
+                (output.logits, output.all_attentions)
+            }
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
+                let output =
+                    model.forward_t(input_ids, mask, token_type_ids, None, input_embeds, train);
+                (output.logits, output.all_attentions)
+            }
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref model) => {
+                let output = model.forward_t(input_ids, mask, input_embeds, train)?;
+                (output.logits, output.all_attentions)
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Attention outputs are not available for {:?} models",
+                    self.model_type()
+                )))
+            }
+        };
+        let all_attentions = all_attentions.ok_or_else(|| {
+            RustBertError::ValueError(
+                "The model was not created with attention outputs enabled".to_string(),
+            )
+        })?;
+        Ok((logits, all_attentions))
+    }
 }
 
 /// # SequenceClassificationModel for Classification (e.g. Sentiment Analysis)
 pub struct SequenceClassificationModel {
     tokenizer: TokenizerOption,
-    sequence_classifier: SequenceClassificationOption,
+    pub(crate) sequence_classifier: SequenceClassificationOption,
     label_mapping: HashMap<i64, String>,
-    device: Device,
-    max_length: usize,
+    pub(crate) device: Device,
+    pub(crate) max_length: usize,
+    token_type_ids_handler: TokenTypeIdsHandler,
+    pub(crate) attention_fp32_accumulation: bool,
 }
 
 impl SequenceClassificationModel {
@@ -621,21 +867,7 @@ impl SequenceClassificationModel {
     pub fn new(
         config: SequenceClassificationConfig,
     ) -> Result<SequenceClassificationModel, RustBertError> {
-        let vocab_path = config.vocab_resource.get_local_path()?;
-        let merges_path = config
-            .merges_resource
-            .as_ref()
-            .map(|resource| resource.get_local_path())
-            .transpose()?;
-
-        let tokenizer = TokenizerOption::from_file(
-            config.model_type,
-            vocab_path.to_str().unwrap(),
-            merges_path.as_deref().map(|path| path.to_str().unwrap()),
-            config.lower_case,
-            config.strip_accents,
-            config.add_prefix_space,
-        )?;
+        let tokenizer = Self::tokenizer_from_config(&config)?;
         Self::new_with_tokenizer(config, tokenizer)
     }
 
@@ -668,22 +900,65 @@ impl SequenceClassificationModel {
         config: SequenceClassificationConfig,
         tokenizer: TokenizerOption,
     ) -> Result<SequenceClassificationModel, RustBertError> {
-        let config_path = config.config_resource.get_local_path()?;
         let sequence_classifier = SequenceClassificationOption::new(&config)?;
+        Self::new_with_classifier(config, tokenizer, sequence_classifier)
+    }
+
+    /// Build a new `SequenceClassificationModel` returning the attention weights of every layer, used by the
+    /// `interpret` module. Only available for Torch BERT, RoBERTa and DistilBERT models.
+    pub(crate) fn new_with_attention_outputs(
+        config: SequenceClassificationConfig,
+    ) -> Result<SequenceClassificationModel, RustBertError> {
+        let tokenizer = Self::tokenizer_from_config(&config)?;
+        let sequence_classifier =
+            SequenceClassificationOption::new_with_attention_outputs(&config)?;
+        Self::new_with_classifier(config, tokenizer, sequence_classifier)
+    }
+
+    fn tokenizer_from_config(
+        config: &SequenceClassificationConfig,
+    ) -> Result<TokenizerOption, RustBertError> {
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let merges_path = config
+            .merges_resource
+            .as_ref()
+            .map(|resource| resource.get_local_path())
+            .transpose()?;
 
+        TokenizerOption::from_file(
+            config.model_type,
+            vocab_path.to_str().unwrap(),
+            merges_path.as_deref().map(|path| path.to_str().unwrap()),
+            config.lower_case,
+            config.strip_accents,
+            config.add_prefix_space,
+        )
+    }
+
+    fn new_with_classifier(
+        config: SequenceClassificationConfig,
+        tokenizer: TokenizerOption,
+        sequence_classifier: SequenceClassificationOption,
+    ) -> Result<SequenceClassificationModel, RustBertError> {
+        let config_path = config.config_resource.get_local_path()?;
         let model_config = ConfigOption::from_file(config.model_type, config_path);
         let max_length = model_config
             .get_max_len()
             .map(|v| v as usize)
             .unwrap_or(usize::MAX);
         let label_mapping = model_config.get_label_mapping().clone();
+        check_label_mapping(&label_mapping)?;
+        let token_type_ids_handler = config.token_type_strategy.resolve(&model_config)?;
         let device = get_device(config.model_resource, config.device);
+        let attention_fp32_accumulation = config.kind.map_or(false, is_half_precision);
         Ok(SequenceClassificationModel {
             tokenizer,
             sequence_classifier,
             label_mapping,
             device,
             max_length,
+            token_type_ids_handler,
+            attention_fp32_accumulation,
         })
     }
 
@@ -696,6 +971,24 @@ impl SequenceClassificationModel {
     pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
         &mut self.tokenizer
     }
+
+    /// Quantizes the linear layers of the model to int8, with a dynamic quantization of the activations, for faster
+    /// CPU inference. Only available for BERT, DistilBERT and RoBERTa models placed on the CPU (see the `quantization`
+    /// module).
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.sequence_classifier.quantize_dynamic()
+    }
+
+    /// Returns the number of labels of the classification head
+    pub fn num_labels(&self) -> usize {
+        self.label_mapping.len()
+    }
+
+    /// Returns the mapping from label ids to label names of the classification head
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        &self.label_mapping
+    }
+
     /// Classify texts
     ///
     /// # Arguments
@@ -729,16 +1022,25 @@ impl SequenceClassificationModel {
         let (input_ids, token_type_ids) =
             self.tokenizer
                 .tokenize_and_pad(input.as_ref(), self.max_length, self.device);
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(&input_ids),
-                None,
-                Some(&token_type_ids),
-                None,
-                None,
-                false,
-            );
-            output.softmax(-1, Kind::Float).detach().to(Device::Cpu)
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                let output = self.sequence_classifier.forward_t(
+                    Some(&input_ids),
+                    None,
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                );
+                // Single-logit heads (e.g. distilled cross-encoders) output a relevance score
+                if output.size()[1] == 1 {
+                    output.sigmoid().to_kind(Kind::Float)
+                } else {
+                    output.softmax(-1, Kind::Float)
+                }
+                .detach()
+                .to(Device::Cpu)
+            })
         });
         let label_indices = output.as_ref().argmax(-1, true).squeeze_dim(1);
         let scores = output
@@ -765,6 +1067,366 @@ impl SequenceClassificationModel {
         labels
     }
 
+    /// Classify pairs of texts (e.g. query/passage relevance or premise/hypothesis entailment)
+    ///
+    /// The two texts of each pair are encoded jointly as a single sequence separated by the tokenizer's
+    /// separator token, as expected by cross-encoder models.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of text pairs to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input text pairs
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = [(
+    ///     "How many people live in Berlin?",
+    ///     "Berlin has a population of 3,520,031 registered inhabitants.",
+    /// )];
+    /// let output = sequence_classification_model.predict_pairs(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_pairs(&self, input: &[(&str, &str)]) -> Vec<Label> {
+        if input.is_empty() {
+            return vec![];
+        }
+        let output = self.forward_pairs(input);
+        let output = if output.size()[1] == 1 {
+            output.sigmoid()
+        } else {
+            output.softmax(-1, Kind::Float)
+        };
+        let label_indices = output.as_ref().argmax(-1, true).squeeze_dim(1);
+        let scores = output
+            .gather(1, &label_indices.unsqueeze(-1), false)
+            .squeeze_dim(1);
+        let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
+        let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
+
+        label_indices
+            .into_iter()
+            .zip(scores)
+            .enumerate()
+            .map(|(sentence_idx, (id, score))| Label {
+                text: self
+                    .label_mapping
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("LABEL_{id}")),
+                score,
+                id,
+                sentence: sentence_idx,
+            })
+            .collect()
+    }
+
+    /// Predicts the scores of all labels for pairs of texts, encoded as a single sequence with a separator token
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of text pairs to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing the scores of all labels (ordered by label id, normalized over the labels) for
+    /// each text pair
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = [(
+    ///     "How many people live in Berlin?",
+    ///     "Berlin has a population of 3,520,031 registered inhabitants.",
+    /// )];
+    /// let output = sequence_classification_model.predict_pairs_scores(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_pairs_scores(&self, input: &[(&str, &str)]) -> Vec<Vec<Label>> {
+        if input.is_empty() {
+            return vec![];
+        }
+        let output = self.forward_pairs(input);
+        let output = if output.size()[1] == 1 {
+            output.sigmoid()
+        } else {
+            output.softmax(-1, Kind::Float)
+        };
+
+        (0..output.size()[0])
+            .map(|sentence_idx| {
+                output
+                    .get(sentence_idx)
+                    .iter::<f64>()
+                    .unwrap()
+                    .enumerate()
+                    .map(|(id, score)| Label {
+                        text: self
+                            .label_mapping
+                            .get(&(id as i64))
+                            .cloned()
+                            .unwrap_or_else(|| format!("LABEL_{id}")),
+                        score,
+                        id: id as i64,
+                        sentence: sentence_idx as usize,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Predicts the raw output of a regression head (single output, e.g. a quality or similarity score) for pairs of
+    /// texts, encoded as a single sequence with a separator token.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of text pairs to score.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<f64>` containing the regression output for each text pair
+    pub fn regress_pairs(&self, input: &[(&str, &str)]) -> Result<Vec<f64>, RustBertError> {
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let output = self.forward_pairs(input);
+        let num_outputs = output.size()[1];
+        if num_outputs != 1 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Regression requires a model with a single output, the model has {num_outputs} outputs"
+            )));
+        }
+        Ok(Vec::<f64>::try_from(mps::to_double(
+            &output.squeeze_dim(1),
+        ))?)
+    }
+
+    /// Returns the logits of the classification head for a non-empty batch of text pairs
+    fn forward_pairs(&self, input: &[(&str, &str)]) -> Tensor {
+        let mut tokenized_input = self.tokenizer.encode_pair_list(
+            input,
+            self.max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let max_len = tokenized_input
+            .iter()
+            .map(|input| input.token_ids.len())
+            .max()
+            .unwrap();
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .expect("The Tokenizer used for sequence classification should contain a PAD id");
+        let input_ids = tokenized_input
+            .iter_mut()
+            .map(|input| {
+                input.token_ids.resize(max_len, pad_id);
+                Tensor::from_slice(&(input.token_ids))
+            })
+            .collect::<Vec<_>>();
+        let token_type_ids = tokenized_input
+            .iter_mut()
+            .map(|input| {
+                input
+                    .segment_ids
+                    .resize(max_len, *input.segment_ids.last().unwrap_or(&0));
+                Tensor::from_slice(&(input.segment_ids))
+            })
+            .collect::<Vec<_>>();
+        let input_ids = Tensor::stack(input_ids.as_slice(), 0).to(self.device);
+        let token_type_ids = self.token_type_ids_handler.apply(
+            Tensor::stack(token_type_ids.as_slice(), 0)
+                .to(self.device)
+                .to_kind(Kind::Int64),
+        );
+        let mask = input_ids.ne(pad_id).to_kind(Kind::Int64);
+
+        with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                self.sequence_classifier
+                    .forward_t(
+                        Some(&input_ids),
+                        Some(&mask),
+                        Some(&token_type_ids),
+                        None,
+                        None,
+                        false,
+                    )
+                    .to_kind(Kind::Float)
+                    .detach()
+                    .to(Device::Cpu)
+            })
+        })
+    }
+
+    /// Classify pre-encoded inputs, skipping the tokenization step (e.g. for token ids cached
+    /// or received from a remote tokenization service)
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[EncodedInput]` Array of pre-encoded inputs to classify, including the special tokens
+    /// expected by the model. Inputs longer than the model maximum length are truncated.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Label>, RustBertError>` containing labels for the inputs
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::common::EncodedInput;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     EncodedInput::new(vec![101, 2307, 3185, 999, 102]),
+    ///     EncodedInput::new(vec![101, 6659, 102, 0]).with_attention_mask(vec![1, 1, 1, 0]),
+    /// ];
+    /// let output = sequence_classification_model.predict_from_ids(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_from_ids(&self, input: &[EncodedInput]) -> Result<Vec<Label>, RustBertError> {
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        self.token_type_ids_handler.validate(input)?;
+        let pad_id = self.tokenizer.get_pad_id().unwrap_or(0);
+        let (input_ids, mask, token_type_ids) =
+            EncodedInput::pad_batch(input, pad_id, Some(self.max_length), self.device)?;
+        let token_type_ids = self.token_type_ids_handler.apply(token_type_ids);
+
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                let output = self.sequence_classifier.forward_t(
+                    Some(&input_ids),
+                    Some(&mask),
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                );
+                if output.size()[1] == 1 {
+                    output.sigmoid().to_kind(Kind::Float)
+                } else {
+                    output.softmax(-1, Kind::Float)
+                }
+                .detach()
+                .to(Device::Cpu)
+            })
+        });
+        let label_indices = output.as_ref().argmax(-1, true).squeeze_dim(1);
+        let scores = output
+            .gather(1, &label_indices.unsqueeze(-1), false)
+            .squeeze_dim(1);
+        let label_indices = label_indices.iter::<i64>()?.collect::<Vec<i64>>();
+        let scores = scores.iter::<f64>()?.collect::<Vec<f64>>();
+
+        Ok(label_indices
+            .into_iter()
+            .zip(scores)
+            .enumerate()
+            .map(|(sentence_idx, (id, score))| Label {
+                text: self
+                    .label_mapping
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("LABEL_{id}")),
+                score,
+                id,
+                sentence: sentence_idx,
+            })
+            .collect())
+    }
+
+    /// Scores all labels for each text
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `multilabel` - `bool` flag indicating if the labels scores should be computed independently (sigmoid, for multi-label
+    /// classification heads) or normalized over the labels (softmax)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing the scores of all labels (ordered by label id) for each input text
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Probably my all-time favorite movie."];
+    /// let output = sequence_classification_model.predict_scores(&input, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_scores(&self, input: &[&str], multilabel: bool) -> Vec<Vec<Label>> {
+        if input.is_empty() {
+            return vec![];
+        }
+        let (input_ids, token_type_ids) =
+            self.tokenizer
+                .tokenize_and_pad(input.as_ref(), self.max_length, self.device);
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                let output = self.sequence_classifier.forward_t(
+                    Some(&input_ids),
+                    None,
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                );
+                if multilabel || output.size()[1] == 1 {
+                    output.sigmoid().to_kind(Kind::Float)
+                } else {
+                    output.softmax(-1, Kind::Float)
+                }
+                .detach()
+                .to(Device::Cpu)
+            })
+        });
+
+        (0..output.size()[0])
+            .map(|sentence_idx| {
+                output
+                    .get(sentence_idx)
+                    .iter::<f64>()
+                    .unwrap()
+                    .enumerate()
+                    .map(|(id, score)| Label {
+                        text: self
+                            .label_mapping
+                            .get(&(id as i64))
+                            .cloned()
+                            .unwrap_or_else(|| format!("LABEL_{id}")),
+                        score,
+                        id: id as i64,
+                        sentence: sentence_idx as usize,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
     /// Multi-label classification of texts
     ///
     /// # Arguments
@@ -800,16 +1462,18 @@ impl SequenceClassificationModel {
         let (input_ids, token_type_ids) =
             self.tokenizer
                 .tokenize_and_pad(input.as_ref(), self.max_length, self.device);
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(&input_ids),
-                None,
-                Some(&token_type_ids),
-                None,
-                None,
-                false,
-            );
-            output.sigmoid().detach().to(Device::Cpu)
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                let output = self.sequence_classifier.forward_t(
+                    Some(&input_ids),
+                    None,
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                );
+                output.sigmoid().detach().to(Device::Cpu)
+            })
         });
         let label_indices = output.as_ref().ge(threshold).nonzero();
 
@@ -0,0 +1,1042 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Grammar-constrained generation
+//! Logits processor restricting the output of text generation models (e.g. GPT2, GPT-Neo or T5) to a grammar, for
+//! example to extract structured data as JSON matching a schema. At each decoding step, only the tokens continuing the
+//! generated text into a valid prefix of the grammar are allowed, and the end of sequence token is only allowed once
+//! the generated text is complete.
+//!
+//! Grammars implement the `TextGrammar` trait, consuming the generated text incrementally. The module provides a
+//! JSON grammar (`JsonGrammar`), optionally following a JSON schema, and an implementation for the SQL grammar of the
+//! text-to-SQL pipeline. Custom context-free grammars can be used by implementing the trait.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::generation_utils::{GenerateOptions, LogitsProcessor};
+//! use rust_bert::pipelines::structured_generation::{
+//!     GrammarLogitsProcessor, JsonGrammar, JsonSchema,
+//! };
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//! # fn main() -> anyhow::Result<()> {
+//! let model = TextGenerationModel::new(Default::default())?;
+//!
+//! let schema = JsonSchema::from_json(
+//!     r#"{
+//!         "type": "object",
+//!         "properties": {
+//!             "name": {"type": "string"},
+//!             "age": {"type": "integer"}
+//!         },
+//!         "required": ["name", "age"]
+//!     }"#,
+//! )?;
+//! let json_processor = GrammarLogitsProcessor::new(JsonGrammar::new(&schema), model.get_tokenizer());
+//! let logits_processors: [&dyn LogitsProcessor; 1] = [&json_processor];
+//! let generate_options = GenerateOptions {
+//!     logits_processors: Some(&logits_processors),
+//!     ..Default::default()
+//! };
+//! let output = model.generate_indices_with_options(
+//!     &["John Smith is a 42 year old engineer. As JSON:"],
+//!     None,
+//!     generate_options,
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::generation_utils::LogitsProcessor;
+use crate::pipelines::text_to_sql::SqlGrammar;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tch::{Device, Tensor};
+
+/// # Grammar consuming text incrementally
+/// The state of the grammar is advanced with pieces of text (the decoded generated tokens). Used by the
+/// `GrammarLogitsProcessor` to check the continuations of the generated text for every token of the vocabulary.
+pub trait TextGrammar: Send + Sync {
+    /// State of the grammar after consuming a text
+    type State: Clone;
+
+    /// Returns the state of the grammar before consuming any text
+    fn initial_state(&self) -> Self::State;
+
+    /// Advances the state of the grammar with a piece of text
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - State of the grammar, updated in place
+    /// * `text` - Text to consume
+    ///
+    /// # Returns
+    /// * `bool` flag indicating if the text consumed so far is still a valid prefix of the grammar. The state should not be advanced further once invalid.
+    fn advance(&self, state: &mut Self::State, text: &str) -> bool;
+
+    /// Checks if the text consumed so far is complete (the generation may stop)
+    fn is_accepting(&self, state: &Self::State) -> bool;
+
+    /// Checks if a text is a valid prefix of the grammar
+    fn accepts_prefix(&self, text: &str) -> bool {
+        let mut state = self.initial_state();
+        self.advance(&mut state, text)
+    }
+
+    /// Checks if a text is complete and valid for the grammar
+    fn accepts(&self, text: &str) -> bool {
+        let mut state = self.initial_state();
+        self.advance(&mut state, text) && self.is_accepting(&state)
+    }
+}
+
+impl TextGrammar for SqlGrammar {
+    type State = String;
+
+    fn initial_state(&self) -> String {
+        String::new()
+    }
+
+    fn advance(&self, state: &mut String, text: &str) -> bool {
+        state.push_str(text);
+        self.is_valid_prefix(state)
+    }
+
+    fn is_accepting(&self, state: &String) -> bool {
+        self.is_complete(state)
+    }
+}
+
+/// # Property of a JSON object schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonProperty {
+    /// Name of the property
+    pub name: String,
+    /// Schema of the property value
+    pub schema: JsonSchema,
+    /// Flag indicating if the property must be present
+    pub required: bool,
+}
+
+/// # Subset of JSON schema supported for constrained generation
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonSchema {
+    /// Any JSON value
+    Any,
+    /// Object with a set of properties, generated in any order and at most once each. Additional properties (with
+    /// any value) are only allowed if `additional_properties` is true.
+    Object {
+        properties: Vec<JsonProperty>,
+        additional_properties: bool,
+    },
+    /// Array of values matching a schema
+    Array(Box<JsonSchema>),
+    /// String
+    String,
+    /// Number (integer or decimal, with an optional exponent)
+    Number,
+    /// Integer
+    Integer,
+    /// Boolean
+    Boolean,
+    /// `null`
+    Null,
+    /// One of a list of values
+    Enum(Vec<Value>),
+}
+
+impl JsonSchema {
+    /// Parses a JSON schema. The following keywords are supported: `type` (a single type), `properties`,
+    /// `required`, `additionalProperties` (boolean, `false` by default when `properties` are given), `items`, `enum`
+    /// and `const`. Other keywords are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - JSON schema serialized as a string
+    ///
+    /// # Returns
+    /// * `JsonSchema` schema used for constrained generation
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::structured_generation::JsonSchema;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let schema = JsonSchema::from_json(r#"{"type": "array", "items": {"type": "number"}}"#)?;
+    /// assert_eq!(schema, JsonSchema::Array(Box::new(JsonSchema::Number)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_json(schema: &str) -> Result<JsonSchema, RustBertError> {
+        let schema: Value = serde_json::from_str(schema).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!("Invalid JSON schema: {error}"))
+        })?;
+        JsonSchema::from_value(&schema)
+    }
+
+    /// Converts a JSON schema value (see `from_json` for the supported keywords)
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - JSON schema
+    ///
+    /// # Returns
+    /// * `JsonSchema` schema used for constrained generation
+    pub fn from_value(schema: &Value) -> Result<JsonSchema, RustBertError> {
+        let unsupported = |reason: &str| {
+            RustBertError::InvalidConfigurationError(format!(
+                "Unsupported JSON schema ({reason}): {schema}"
+            ))
+        };
+        let schema = match schema {
+            Value::Bool(true) => return Ok(JsonSchema::Any),
+            Value::Object(schema) => schema,
+            _ => return Err(unsupported("expected an object")),
+        };
+        if let Some(values) = schema.get("enum") {
+            return match values.as_array() {
+                Some(values) if !values.is_empty() => Ok(JsonSchema::Enum(values.clone())),
+                _ => Err(unsupported("`enum` should be a non-empty array")),
+            };
+        }
+        if let Some(value) = schema.get("const") {
+            return Ok(JsonSchema::Enum(vec![value.clone()]));
+        }
+        let schema_type = match schema.get("type") {
+            Some(Value::String(schema_type)) => schema_type.as_str(),
+            Some(_) => return Err(unsupported("`type` should be a single type")),
+            None if schema.contains_key("properties") => "object",
+            None if schema.contains_key("items") => "array",
+            None => return Ok(JsonSchema::Any),
+        };
+        Ok(match schema_type {
+            "object" => {
+                let required = match schema.get("required") {
+                    Some(Value::Array(required)) => required
+                        .iter()
+                        .map(|name| {
+                            name.as_str()
+                                .ok_or_else(|| unsupported("`required` should list names"))
+                        })
+                        .collect::<Result<Vec<&str>, RustBertError>>()?,
+                    Some(_) => return Err(unsupported("`required` should be an array")),
+                    None => Vec::new(),
+                };
+                let properties = match schema.get("properties") {
+                    Some(Value::Object(properties)) => properties
+                        .iter()
+                        .map(|(name, property_schema)| {
+                            if name.contains(|c: char| c == '"' || c == '\\' || c < ' ') {
+                                return Err(unsupported("property names should not need escaping"));
+                            }
+                            Ok(JsonProperty {
+                                name: name.clone(),
+                                schema: JsonSchema::from_value(property_schema)?,
+                                required: required.contains(&name.as_str()),
+                            })
+                        })
+                        .collect::<Result<Vec<JsonProperty>, RustBertError>>()?,
+                    Some(_) => return Err(unsupported("`properties` should be an object")),
+                    None => Vec::new(),
+                };
+                let additional_properties = match schema.get("additionalProperties") {
+                    Some(Value::Bool(additional_properties)) => *additional_properties,
+                    Some(_) => {
+                        return Err(unsupported("`additionalProperties` should be a boolean"))
+                    }
+                    None => properties.is_empty(),
+                };
+                if !additional_properties
+                    && required
+                        .iter()
+                        .any(|name| !properties.iter().any(|property| property.name == *name))
+                {
+                    return Err(unsupported("required properties should be declared"));
+                }
+                JsonSchema::Object {
+                    properties,
+                    additional_properties,
+                }
+            }
+            "array" => JsonSchema::Array(Box::new(match schema.get("items") {
+                Some(items) => JsonSchema::from_value(items)?,
+                None => JsonSchema::Any,
+            })),
+            "string" => JsonSchema::String,
+            "number" => JsonSchema::Number,
+            "integer" => JsonSchema::Integer,
+            "boolean" => JsonSchema::Boolean,
+            "null" => JsonSchema::Null,
+            _ => return Err(unsupported("unknown type")),
+        })
+    }
+
+    /// Validates a JSON value against the schema
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - JSON value to validate
+    ///
+    /// # Returns
+    /// * `Result<(), String>` describing the first mismatch found and its location in the value (e.g. `$.age`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::structured_generation::JsonSchema;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let schema = JsonSchema::from_json(r#"{"type": "array", "items": {"type": "integer"}}"#)?;
+    /// assert!(schema.validate(&serde_json::json!([1, 2])).is_ok());
+    /// assert_eq!(
+    ///     schema.validate(&serde_json::json!([1, "2"])),
+    ///     Err("$[1]: expected an integer".to_string())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate(&self, value: &Value) -> Result<(), String> {
+        self.validate_at(value, "$")
+    }
+
+    fn validate_at(&self, value: &Value, path: &str) -> Result<(), String> {
+        let mismatch = |expected: &str| Err(format!("{path}: expected {expected}"));
+        match (self, value) {
+            (JsonSchema::Any, _)
+            | (JsonSchema::String, Value::String(_))
+            | (JsonSchema::Number, Value::Number(_))
+            | (JsonSchema::Boolean, Value::Bool(_))
+            | (JsonSchema::Null, Value::Null) => Ok(()),
+            (JsonSchema::Integer, Value::Number(number)) if number.is_i64() || number.is_u64() => {
+                Ok(())
+            }
+            (
+                JsonSchema::Object {
+                    properties,
+                    additional_properties,
+                },
+                Value::Object(object),
+            ) => {
+                for property in properties {
+                    match object.get(&property.name) {
+                        Some(property_value) => property
+                            .schema
+                            .validate_at(property_value, &format!("{path}.{}", property.name))?,
+                        None if property.required => {
+                            return Err(format!(
+                                "{path}: missing required property `{}`",
+                                property.name
+                            ));
+                        }
+                        None => {}
+                    }
+                }
+                if !additional_properties {
+                    if let Some(name) = object
+                        .keys()
+                        .find(|name| !properties.iter().any(|property| &property.name == *name))
+                    {
+                        return Err(format!("{path}: unexpected property `{name}`"));
+                    }
+                }
+                Ok(())
+            }
+            (JsonSchema::Array(items), Value::Array(values)) => {
+                for (index, item) in values.iter().enumerate() {
+                    items.validate_at(item, &format!("{path}[{index}]"))?;
+                }
+                Ok(())
+            }
+            (JsonSchema::Enum(values), _) => {
+                if values.contains(value) {
+                    Ok(())
+                } else {
+                    let values = values
+                        .iter()
+                        .map(Value::to_string)
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    mismatch(&format!("one of {values}"))
+                }
+            }
+            (JsonSchema::Object { .. }, _) => mismatch("an object"),
+            (JsonSchema::Array(_), _) => mismatch("an array"),
+            (JsonSchema::String, _) => mismatch("a string"),
+            (JsonSchema::Number, _) => mismatch("a number"),
+            (JsonSchema::Integer, _) => mismatch("an integer"),
+            (JsonSchema::Boolean, _) => mismatch("a boolean"),
+            (JsonSchema::Null, _) => mismatch("null"),
+        }
+    }
+}
+
+/// Maximum number of consecutive whitespace characters between JSON tokens, preventing the generation from
+/// degenerating into whitespace
+const MAX_WHITESPACE_RUN: usize = 16;
+const KEYWORD_LITERALS: [&str; 3] = ["true", "false", "null"];
+const ANY_NODE: usize = 0;
+const FREE_OBJECT_NODE: usize = 1;
+
+#[derive(Debug)]
+struct PropertyNode {
+    name: String,
+    node: usize,
+    required: bool,
+}
+
+#[derive(Debug)]
+enum SchemaNode {
+    Any,
+    Object {
+        properties: Vec<PropertyNode>,
+        additional_properties: bool,
+    },
+    Array(usize),
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Null,
+    Enum(Vec<String>),
+}
+
+/// # JSON grammar
+/// Accepts a single JSON value (surrounded by optional whitespace), optionally matching a schema.
+///
+/// ```no_run
+/// use rust_bert::pipelines::structured_generation::{JsonGrammar, JsonSchema, TextGrammar};
+///
+/// let grammar = JsonGrammar::new(&JsonSchema::Array(Box::new(JsonSchema::Integer)));
+/// assert!(grammar.accepts("[1, 2, 3]"));
+/// assert!(grammar.accepts_prefix("[1, 2"));
+/// assert!(!grammar.accepts_prefix("[1.5"));
+/// ```
+#[derive(Debug)]
+pub struct JsonGrammar {
+    nodes: Vec<SchemaNode>,
+    root: usize,
+}
+
+impl JsonGrammar {
+    /// Creates a JSON grammar for values matching a schema (use `JsonSchema::Any` for any JSON value)
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - Schema of the values accepted
+    pub fn new(schema: &JsonSchema) -> JsonGrammar {
+        let mut nodes = vec![
+            SchemaNode::Any,
+            SchemaNode::Object {
+                properties: Vec::new(),
+                additional_properties: true,
+            },
+        ];
+        let root = JsonGrammar::compile(schema, &mut nodes);
+        JsonGrammar { nodes, root }
+    }
+
+    fn compile(schema: &JsonSchema, nodes: &mut Vec<SchemaNode>) -> usize {
+        let node = match schema {
+            JsonSchema::Any => return ANY_NODE,
+            JsonSchema::Object {
+                properties,
+                additional_properties,
+            } => SchemaNode::Object {
+                properties: properties
+                    .iter()
+                    .map(|property| PropertyNode {
+                        name: property.name.clone(),
+                        node: JsonGrammar::compile(&property.schema, nodes),
+                        required: property.required,
+                    })
+                    .collect(),
+                additional_properties: *additional_properties,
+            },
+            JsonSchema::Array(items) => SchemaNode::Array(JsonGrammar::compile(items, nodes)),
+            JsonSchema::String => SchemaNode::String,
+            JsonSchema::Number => SchemaNode::Number,
+            JsonSchema::Integer => SchemaNode::Integer,
+            JsonSchema::Boolean => SchemaNode::Boolean,
+            JsonSchema::Null => SchemaNode::Null,
+            JsonSchema::Enum(values) => {
+                SchemaNode::Enum(values.iter().map(Value::to_string).collect())
+            }
+        };
+        nodes.push(node);
+        nodes.len() - 1
+    }
+
+    fn literals(&self, literals: Literals) -> Vec<&str> {
+        match literals {
+            Literals::Keywords => KEYWORD_LITERALS.to_vec(),
+            Literals::Booleans => KEYWORD_LITERALS[..2].to_vec(),
+            Literals::Null => KEYWORD_LITERALS[2..].to_vec(),
+            Literals::Enum(node) => match &self.nodes[node] {
+                SchemaNode::Enum(values) => values.iter().map(String::as_str).collect(),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    fn object_properties(&self, node: usize) -> (&[PropertyNode], bool) {
+        match &self.nodes[node] {
+            SchemaNode::Object {
+                properties,
+                additional_properties,
+            } => (properties.as_slice(), *additional_properties),
+            _ => (&[], true),
+        }
+    }
+}
+
+impl TextGrammar for JsonGrammar {
+    type State = JsonGrammarState;
+
+    fn initial_state(&self) -> JsonGrammarState {
+        JsonGrammarState {
+            stack: vec![Frame::Value(self.root)],
+            whitespace_run: 0,
+        }
+    }
+
+    fn advance(&self, state: &mut JsonGrammarState, text: &str) -> bool {
+        text.chars().all(|character| state.feed(self, character))
+    }
+
+    fn is_accepting(&self, state: &JsonGrammarState) -> bool {
+        match state.stack.as_slice() {
+            [] => true,
+            [Frame::Number { step, .. }] => step.is_complete(),
+            [Frame::Literal { literals, text }] => {
+                self.literals(*literals).contains(&text.as_str())
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Literals {
+    Keywords,
+    Booleans,
+    Null,
+    Enum(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StringStep {
+    Character,
+    Escape,
+    Unicode(u8),
+}
+
+enum StringTransition {
+    Open(StringStep),
+    Closed,
+    Invalid,
+}
+
+impl StringStep {
+    fn next(self, character: char) -> StringTransition {
+        match self {
+            StringStep::Character => match character {
+                '"' => StringTransition::Closed,
+                '\\' => StringTransition::Open(StringStep::Escape),
+                character if character < ' ' => StringTransition::Invalid,
+                _ => StringTransition::Open(StringStep::Character),
+            },
+            StringStep::Escape => match character {
+                '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => {
+                    StringTransition::Open(StringStep::Character)
+                }
+                'u' => StringTransition::Open(StringStep::Unicode(0)),
+                _ => StringTransition::Invalid,
+            },
+            StringStep::Unicode(digits) if character.is_ascii_hexdigit() => {
+                StringTransition::Open(if digits == 3 {
+                    StringStep::Character
+                } else {
+                    StringStep::Unicode(digits + 1)
+                })
+            }
+            StringStep::Unicode(_) => StringTransition::Invalid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NumberStep {
+    Sign,
+    Zero,
+    Integer,
+    Point,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl NumberStep {
+    fn start(character: char) -> Option<NumberStep> {
+        match character {
+            '-' => Some(NumberStep::Sign),
+            '0' => Some(NumberStep::Zero),
+            '1'..='9' => Some(NumberStep::Integer),
+            _ => None,
+        }
+    }
+
+    fn next(self, character: char, integer: bool) -> Option<NumberStep> {
+        match (self, character) {
+            (NumberStep::Sign, '0') => Some(NumberStep::Zero),
+            (NumberStep::Sign, '1'..='9') | (NumberStep::Integer, '0'..='9') => {
+                Some(NumberStep::Integer)
+            }
+            (NumberStep::Zero, '.') | (NumberStep::Integer, '.') if !integer => {
+                Some(NumberStep::Point)
+            }
+            (NumberStep::Zero, 'e' | 'E')
+            | (NumberStep::Integer, 'e' | 'E')
+            | (NumberStep::Fraction, 'e' | 'E')
+                if !integer =>
+            {
+                Some(NumberStep::Exponent)
+            }
+            (NumberStep::Point, '0'..='9') | (NumberStep::Fraction, '0'..='9') => {
+                Some(NumberStep::Fraction)
+            }
+            (NumberStep::Exponent, '+' | '-') => Some(NumberStep::ExponentSign),
+            (NumberStep::Exponent, '0'..='9')
+            | (NumberStep::ExponentSign, '0'..='9')
+            | (NumberStep::ExponentDigits, '0'..='9') => Some(NumberStep::ExponentDigits),
+            _ => None,
+        }
+    }
+
+    fn is_complete(self) -> bool {
+        matches!(
+            self,
+            NumberStep::Zero
+                | NumberStep::Integer
+                | NumberStep::Fraction
+                | NumberStep::ExponentDigits
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ObjectStep {
+    Start,
+    KeyStart,
+    Key { text: String, step: StringStep },
+    Colon(usize),
+    AfterValue,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ArrayStep {
+    Start,
+    AfterValue,
+}
+
+#[derive(Debug, Clone)]
+enum Frame {
+    Value(usize),
+    Object {
+        node: usize,
+        seen: Vec<bool>,
+        step: ObjectStep,
+    },
+    Array {
+        items: usize,
+        step: ArrayStep,
+    },
+    String(StringStep),
+    Number {
+        step: NumberStep,
+        integer: bool,
+    },
+    Literal {
+        literals: Literals,
+        text: String,
+    },
+}
+
+fn is_json_whitespace(character: char) -> bool {
+    matches!(character, ' ' | '\t' | '\n' | '\r')
+}
+
+/// State of a `JsonGrammar`: stack of the JSON values being generated
+#[derive(Debug, Clone)]
+pub struct JsonGrammarState {
+    stack: Vec<Frame>,
+    whitespace_run: usize,
+}
+
+impl JsonGrammarState {
+    fn skip_whitespace(&mut self) -> bool {
+        self.whitespace_run += 1;
+        self.whitespace_run <= MAX_WHITESPACE_RUN
+    }
+
+    fn feed(&mut self, grammar: &JsonGrammar, character: char) -> bool {
+        let whitespace = is_json_whitespace(character);
+        if !whitespace {
+            self.whitespace_run = 0;
+        }
+        // Numbers and literals are only closed by the character following them, which is then fed to the
+        // enclosing value
+        loop {
+            let frame = match self.stack.pop() {
+                Some(frame) => frame,
+                None => return whitespace && self.skip_whitespace(),
+            };
+            match frame {
+                Frame::Value(node) => {
+                    if whitespace {
+                        self.stack.push(Frame::Value(node));
+                        return self.skip_whitespace();
+                    }
+                    return self.open_value(grammar, node, character);
+                }
+                Frame::Object { node, seen, step } => {
+                    return self.feed_object(grammar, node, seen, step, character);
+                }
+                Frame::Array { items, step } => {
+                    if whitespace {
+                        self.stack.push(Frame::Array { items, step });
+                        return self.skip_whitespace();
+                    }
+                    return match (step, character) {
+                        (_, ']') => true,
+                        (ArrayStep::Start, _) => {
+                            self.stack.push(Frame::Array {
+                                items,
+                                step: ArrayStep::AfterValue,
+                            });
+                            self.open_value(grammar, items, character)
+                        }
+                        (ArrayStep::AfterValue, ',') => {
+                            self.stack.push(Frame::Array {
+                                items,
+                                step: ArrayStep::AfterValue,
+                            });
+                            self.stack.push(Frame::Value(items));
+                            true
+                        }
+                        _ => false,
+                    };
+                }
+                Frame::String(step) => {
+                    return match step.next(character) {
+                        StringTransition::Open(step) => {
+                            self.stack.push(Frame::String(step));
+                            true
+                        }
+                        StringTransition::Closed => true,
+                        StringTransition::Invalid => false,
+                    };
+                }
+                Frame::Number { step, integer } => match step.next(character, integer) {
+                    Some(step) => {
+                        self.stack.push(Frame::Number { step, integer });
+                        return true;
+                    }
+                    None if step.is_complete() => continue,
+                    None => return false,
+                },
+                Frame::Literal { literals, mut text } => {
+                    let candidates = grammar.literals(literals);
+                    let complete = candidates.contains(&text.as_str());
+                    text.push(character);
+                    if candidates
+                        .iter()
+                        .any(|candidate| candidate.starts_with(text.as_str()))
+                    {
+                        self.stack.push(Frame::Literal { literals, text });
+                        return true;
+                    }
+                    if !complete {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    fn open_value(&mut self, grammar: &JsonGrammar, node: usize, character: char) -> bool {
+        match &grammar.nodes[node] {
+            SchemaNode::Any => match character {
+                '{' => self.open_object(grammar, FREE_OBJECT_NODE),
+                '[' => {
+                    self.stack.push(Frame::Array {
+                        items: ANY_NODE,
+                        step: ArrayStep::Start,
+                    });
+                    true
+                }
+                '"' => {
+                    self.stack.push(Frame::String(StringStep::Character));
+                    true
+                }
+                '-' | '0'..='9' => self.open_number(character, false),
+                _ => self.open_literal(grammar, Literals::Keywords, character),
+            },
+            SchemaNode::Object { .. } if character == '{' => self.open_object(grammar, node),
+            SchemaNode::Array(items) if character == '[' => {
+                self.stack.push(Frame::Array {
+                    items: *items,
+                    step: ArrayStep::Start,
+                });
+                true
+            }
+            SchemaNode::String if character == '"' => {
+                self.stack.push(Frame::String(StringStep::Character));
+                true
+            }
+            SchemaNode::Number => self.open_number(character, false),
+            SchemaNode::Integer => self.open_number(character, true),
+            SchemaNode::Boolean => self.open_literal(grammar, Literals::Booleans, character),
+            SchemaNode::Null => self.open_literal(grammar, Literals::Null, character),
+            SchemaNode::Enum(_) => self.open_literal(grammar, Literals::Enum(node), character),
+            _ => false,
+        }
+    }
+
+    fn open_object(&mut self, grammar: &JsonGrammar, node: usize) -> bool {
+        let (properties, _) = grammar.object_properties(node);
+        self.stack.push(Frame::Object {
+            node,
+            seen: vec![false; properties.len()],
+            step: ObjectStep::Start,
+        });
+        true
+    }
+
+    fn open_number(&mut self, character: char, integer: bool) -> bool {
+        match NumberStep::start(character) {
+            Some(step) => {
+                self.stack.push(Frame::Number { step, integer });
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn open_literal(&mut self, grammar: &JsonGrammar, literals: Literals, character: char) -> bool {
+        let text = character.to_string();
+        if grammar
+            .literals(literals)
+            .iter()
+            .any(|candidate| candidate.starts_with(text.as_str()))
+        {
+            self.stack.push(Frame::Literal { literals, text });
+            true
+        } else {
+            false
+        }
+    }
+
+    fn feed_object(
+        &mut self,
+        grammar: &JsonGrammar,
+        node: usize,
+        mut seen: Vec<bool>,
+        step: ObjectStep,
+        character: char,
+    ) -> bool {
+        let (properties, additional_properties) = grammar.object_properties(node);
+        if is_json_whitespace(character) && !matches!(step, ObjectStep::Key { .. }) {
+            self.stack.push(Frame::Object { node, seen, step });
+            return self.skip_whitespace();
+        }
+        let required_seen = |seen: &[bool]| {
+            properties
+                .iter()
+                .zip(seen.iter())
+                .all(|(property, seen)| !property.required || *seen)
+        };
+        let can_add_key = |seen: &[bool]| additional_properties || seen.iter().any(|seen| !*seen);
+        let step = match step {
+            ObjectStep::Start if character == '}' => return required_seen(&seen),
+            ObjectStep::Start | ObjectStep::KeyStart if character == '"' => {
+                if !can_add_key(&seen) {
+                    return false;
+                }
+                ObjectStep::Key {
+                    text: String::new(),
+                    step: StringStep::Character,
+                }
+            }
+            ObjectStep::Key { mut text, step } => match step.next(character) {
+                StringTransition::Closed => {
+                    match properties.iter().position(|property| property.name == text) {
+                        Some(index) if !seen[index] => {
+                            seen[index] = true;
+                            ObjectStep::Colon(properties[index].node)
+                        }
+                        None if additional_properties => ObjectStep::Colon(ANY_NODE),
+                        _ => return false,
+                    }
+                }
+                StringTransition::Open(step) => {
+                    text.push(character);
+                    if !additional_properties
+                        && !properties.iter().zip(seen.iter()).any(|(property, seen)| {
+                            !*seen && property.name.starts_with(text.as_str())
+                        })
+                    {
+                        return false;
+                    }
+                    ObjectStep::Key { text, step }
+                }
+                StringTransition::Invalid => return false,
+            },
+            ObjectStep::Colon(value_node) if character == ':' => {
+                self.stack.push(Frame::Object {
+                    node,
+                    seen,
+                    step: ObjectStep::AfterValue,
+                });
+                self.stack.push(Frame::Value(value_node));
+                return true;
+            }
+            ObjectStep::AfterValue if character == ',' => {
+                if !can_add_key(&seen) {
+                    return false;
+                }
+                ObjectStep::KeyStart
+            }
+            ObjectStep::AfterValue if character == '}' => return required_seen(&seen),
+            _ => return false,
+        };
+        self.stack.push(Frame::Object { node, seen, step });
+        true
+    }
+}
+
+/// Text decoded for each token of the vocabulary when continuing a sequence (including its leading space, if any)
+pub(crate) fn token_continuations(tokenizer: &TokenizerOption) -> Vec<(i64, String)> {
+    let anchor = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize("a"));
+    let anchor_text = tokenizer.decode(&anchor, true, false);
+    (0..tokenizer.get_vocab_size())
+        .filter_map(|token_id| {
+            let mut token_ids = anchor.clone();
+            token_ids.push(token_id);
+            let text = tokenizer.decode(&token_ids, true, false);
+            text.strip_prefix(anchor_text.as_str())
+                .filter(|continuation| !continuation.is_empty())
+                .map(|continuation| (token_id, continuation.to_string()))
+        })
+        .collect()
+}
+
+/// # Logits processor constraining the generation to a grammar
+/// Only allows the tokens continuing the generated text into a valid prefix of the grammar. The end of sequence token
+/// is allowed once the generated text is complete, and is the only token allowed if no continuation is valid. The
+/// generated text is rebuilt from the decoded continuations of the tokens, the prompt is not constrained.
+///
+/// The continuations of all tokens of the vocabulary are checked at every decoding step, grammars should therefore
+/// advance their state efficiently.
+pub struct GrammarLogitsProcessor<G: TextGrammar> {
+    grammar: G,
+    token_continuations: Vec<(i64, String)>,
+    token_texts: HashMap<i64, String>,
+    eos_token_ids: Vec<i64>,
+}
+
+impl<G: TextGrammar> GrammarLogitsProcessor<G> {
+    /// Creates a grammar logits processor
+    ///
+    /// # Arguments
+    ///
+    /// * `grammar` - Grammar the generated text should follow
+    /// * `tokenizer` - Tokenizer of the model generating the text
+    pub fn new(grammar: G, tokenizer: &TokenizerOption) -> GrammarLogitsProcessor<G> {
+        let token_continuations = token_continuations(tokenizer);
+        let token_texts = token_continuations.iter().cloned().collect();
+        GrammarLogitsProcessor {
+            grammar,
+            token_continuations,
+            token_texts,
+            eos_token_ids: tokenizer.get_eos_id().into_iter().collect(),
+        }
+    }
+
+    /// Returns the grammar followed by the generated text
+    pub fn grammar(&self) -> &G {
+        &self.grammar
+    }
+
+    fn allowed_tokens(&self, generated_ids: &[i64], allowed: &mut [bool]) {
+        let allow_eos = |allowed: &mut [bool]| {
+            for eos_token_id in self.eos_token_ids.iter() {
+                if let Some(flag) = allowed.get_mut(*eos_token_id as usize) {
+                    *flag = true;
+                }
+            }
+        };
+        let mut state = self.grammar.initial_state();
+        for token_id in generated_ids {
+            if self.eos_token_ids.contains(token_id) {
+                allow_eos(allowed);
+                return;
+            }
+            let text = self
+                .token_texts
+                .get(token_id)
+                .map(String::as_str)
+                .unwrap_or_default();
+            if !self.grammar.advance(&mut state, text) {
+                allow_eos(allowed);
+                return;
+            }
+        }
+        let mut valid_continuation = false;
+        for (token_id, continuation) in self.token_continuations.iter() {
+            if let Some(flag) = allowed.get_mut(*token_id as usize) {
+                let mut continued_state = state.clone();
+                if self.grammar.advance(&mut continued_state, continuation) {
+                    *flag = true;
+                    valid_continuation = true;
+                }
+            }
+        }
+        if !valid_continuation | self.grammar.is_accepting(&state) {
+            allow_eos(allowed);
+        }
+    }
+}
+
+impl<G: TextGrammar> LogitsProcessor for GrammarLogitsProcessor<G> {
+    fn process(&self, input_ids: &Tensor, start_length: i64, scores: &mut Tensor) {
+        let input_ids = input_ids.to_device(Device::Cpu);
+        let input_size = input_ids.size();
+        let vocab_size = scores.size()[1];
+        let mut allowed = vec![false; (input_size[0] * vocab_size) as usize];
+        for (sequence_index, sequence_allowed) in
+            allowed.chunks_mut(vocab_size as usize).enumerate()
+        {
+            let generated_ids = Vec::<i64>::try_from(input_ids.get(sequence_index as i64).slice(
+                0,
+                start_length.min(input_size[1]),
+                input_size[1],
+                1,
+            ))
+            .unwrap();
+            self.allowed_tokens(&generated_ids, sequence_allowed);
+        }
+        let allowed = Tensor::from_slice(&allowed)
+            .view([input_size[0], vocab_size])
+            .to_device(scores.device());
+        let _ = scores.masked_fill_(&allowed.logical_not(), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,391 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Structured output with automatic retry
+//! Parses the output of text generation models into typed values, and generates again when the output is invalid.
+//! The output is parsed by an `OutputParser`, for example a JSON parser deserializing the first JSON value of the
+//! generated text into a Rust type after checking it against a JSON schema (`JsonOutputParser`), or a closure. When
+//! parsing fails, the prompt is extended with the invalid output and the parsing error (following the
+//! `retry_template` of the `StructuredOutputConfig`) and the model generates a new answer, up to `max_retries` times.
+//!
+//! Unlike grammar-constrained generation (see the `structured_generation` module), the model is free to generate
+//! any text, which makes it possible to validate constraints that are not expressed by a grammar (e.g. value ranges
+//! or consistency between fields).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::structured_generation::JsonSchema;
+//! use rust_bert::pipelines::structured_output::{JsonOutputParser, StructuredOutputConfig};
+//! use rust_bert::pipelines::text_generation::TextGenerationModel;
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Person {
+//!     name: String,
+//!     age: u32,
+//! }
+//!
+//! let model = TextGenerationModel::new(Default::default())?;
+//! let schema = JsonSchema::from_json(
+//!     r#"{
+//!         "type": "object",
+//!         "properties": {"name": {"type": "string"}, "age": {"type": "integer"}},
+//!         "required": ["name", "age"]
+//!     }"#,
+//! )?;
+//! let parser = JsonOutputParser::<Person>::new().with_schema(schema);
+//!
+//! let output = model.generate_structured(
+//!     "John Smith is a 42 year old engineer. As JSON with a name and an age:",
+//!     &parser,
+//!     &StructuredOutputConfig::default(),
+//! )?;
+//! match output.value {
+//!     Some(person) => println!("{:?}", person),
+//!     None => println!("No valid output after {} attempts", output.attempts.len()),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::structured_generation::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// # Parser of generated texts into typed values
+/// Implemented for closures `Fn(&str) -> Result<T, String>` and JSON values (`JsonOutputParser`). The error message
+/// is shown to the model when generating again, and should describe the problem in plain words.
+pub trait OutputParser {
+    /// Type of the parsed values
+    type Output;
+
+    /// Parses a generated text, returning a description of the problem if the text is invalid
+    fn parse(&self, text: &str) -> Result<Self::Output, String>;
+}
+
+impl<F, T> OutputParser for F
+where
+    F: Fn(&str) -> Result<T, String>,
+{
+    type Output = T;
+
+    fn parse(&self, text: &str) -> Result<T, String> {
+        self(text)
+    }
+}
+
+/// # Parser of JSON values
+/// Parses the first JSON object or array of the generated text (ignoring any text before and after it), optionally
+/// validates it against a `JsonSchema` and deserializes it into `T`. If the text contains no object or array, the
+/// whole text is parsed as a JSON value. Use `serde_json::Value` as `T` to keep the value untyped.
+pub struct JsonOutputParser<T> {
+    schema: Option<JsonSchema>,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonOutputParser<T>
+where
+    T: DeserializeOwned,
+{
+    /// Build a new `JsonOutputParser`, without schema validation
+    pub fn new() -> JsonOutputParser<T> {
+        JsonOutputParser {
+            schema: None,
+            _output: PhantomData,
+        }
+    }
+
+    /// Validates the parsed values against a JSON schema before deserializing them. The schema errors locate the
+    /// invalid field (e.g. `$.age: expected an integer`), which helps the model to fix its output.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - `JsonSchema` the parsed values must follow
+    pub fn with_schema(mut self, schema: JsonSchema) -> JsonOutputParser<T> {
+        self.schema = Some(schema);
+        self
+    }
+}
+
+impl<T> Default for JsonOutputParser<T>
+where
+    T: DeserializeOwned,
+{
+    fn default() -> JsonOutputParser<T> {
+        JsonOutputParser::new()
+    }
+}
+
+impl<T> OutputParser for JsonOutputParser<T>
+where
+    T: DeserializeOwned,
+{
+    type Output = T;
+
+    fn parse(&self, text: &str) -> Result<T, String> {
+        let value = extract_json(text)?;
+        if let Some(schema) = &self.schema {
+            schema.validate(&value)?;
+        }
+        serde_json::from_value(value).map_err(|error| error.to_string())
+    }
+}
+
+/// Returns the first JSON object or array of a text that can be parsed, the error of the first candidate otherwise
+fn extract_json(text: &str) -> Result<Value, String> {
+    let mut first_error = None;
+    for (start, _) in text.match_indices(|c: char| c == '{' || c == '[') {
+        match serde_json::Deserializer::from_str(&text[start..])
+            .into_iter::<Value>()
+            .next()
+        {
+            Some(Ok(value)) => return Ok(value),
+            Some(Err(error)) => {
+                first_error.get_or_insert_with(|| format!("invalid JSON: {error}"));
+            }
+            None => {}
+        }
+    }
+    match first_error {
+        Some(error) => Err(error),
+        None => serde_json::from_str(text.trim())
+            .map_err(|_| "the output does not contain a JSON value".to_string()),
+    }
+}
+
+/// # Configuration for structured output generation
+/// Retry settings. The generation settings are those of the text generation model.
+#[derive(Debug, Clone)]
+pub struct StructuredOutputConfig {
+    /// Maximum number of generations after the first one when the output is invalid
+    pub max_retries: usize,
+    /// Prompt of the retries: `{prompt}` is replaced by the original prompt, `{output}` by the last invalid output
+    /// and `{error}` by its parsing error
+    pub retry_template: String,
+    /// Maximum number of tokens generated for each attempt
+    pub max_new_tokens: Option<i64>,
+}
+
+impl Default for StructuredOutputConfig {
+    fn default() -> StructuredOutputConfig {
+        StructuredOutputConfig {
+            max_retries: 2,
+            retry_template: "{prompt}{output}\n\nThe answer above is invalid ({error}). Answer again, fixing the error:\n".to_string(),
+            max_new_tokens: Some(256),
+        }
+    }
+}
+
+impl StructuredOutputConfig {
+    pub(crate) fn validate(&self) -> Result<(), RustBertError> {
+        if !self.retry_template.contains("{prompt}") {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The retry template should contain a `{prompt}` placeholder".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn retry_prompt(&self, prompt: &str, output: &str, error: &str) -> String {
+        self.retry_template
+            .replace("{output}", output)
+            .replace("{error}", error)
+            .replace("{prompt}", prompt)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Generation attempt of structured output
+pub struct StructuredOutputAttempt {
+    /// Prompt of the attempt
+    pub prompt: String,
+    /// Generated text
+    pub text: String,
+    /// Parsing error of the generated text, `None` if the text is valid
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Output of structured output generation
+pub struct StructuredOutput<T> {
+    /// Parsed value of the first valid attempt, `None` if all attempts failed
+    pub value: Option<T>,
+    /// Attempts in generation order. On failure, the errors of the attempts describe why the outputs are invalid.
+    pub attempts: Vec<StructuredOutputAttempt>,
+}
+
+impl<T> StructuredOutput<T> {
+    /// Returns `true` if a valid value was generated
+    pub fn is_valid(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Returns the parsed value, or an error with the parsing error of the last attempt
+    pub fn into_result(self) -> Result<T, RustBertError> {
+        match self.value {
+            Some(value) => Ok(value),
+            None => Err(RustBertError::ValueError(format!(
+                "No valid output after {} attempts, last error: {}",
+                self.attempts.len(),
+                self.attempts
+                    .last()
+                    .and_then(|attempt| attempt.error.as_deref())
+                    .unwrap_or("no attempt")
+            ))),
+        }
+    }
+}
+
+/// Generates and parses outputs until a valid one is found or the retries are exhausted. `generate` returns the
+/// continuation of a prompt.
+pub(crate) fn generate_with_retries<P, G>(
+    prompt: &str,
+    parser: &P,
+    config: &StructuredOutputConfig,
+    mut generate: G,
+) -> Result<StructuredOutput<P::Output>, RustBertError>
+where
+    P: OutputParser + ?Sized,
+    G: FnMut(&str) -> Result<String, RustBertError>,
+{
+    let mut attempts: Vec<StructuredOutputAttempt> = Vec::with_capacity(config.max_retries + 1);
+    let mut attempt_prompt = prompt.to_string();
+    for _ in 0..=config.max_retries {
+        let text = generate(&attempt_prompt)?;
+        match parser.parse(&text) {
+            Ok(value) => {
+                attempts.push(StructuredOutputAttempt {
+                    prompt: attempt_prompt,
+                    text,
+                    error: None,
+                });
+                return Ok(StructuredOutput {
+                    value: Some(value),
+                    attempts,
+                });
+            }
+            Err(error) => {
+                let next_prompt = config.retry_prompt(prompt, &text, &error);
+                attempts.push(StructuredOutputAttempt {
+                    prompt: std::mem::replace(&mut attempt_prompt, next_prompt),
+                    text,
+                    error: Some(error),
+                });
+            }
+        }
+    }
+    Ok(StructuredOutput {
+        value: None,
+        attempts,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    fn person_schema() -> JsonSchema {
+        JsonSchema::from_json(
+            r#"{
+                "type": "object",
+                "properties": {"name": {"type": "string"}, "age": {"type": "integer"}},
+                "required": ["name", "age"]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn json_parsing() {
+        let parser = JsonOutputParser::<Person>::new().with_schema(person_schema());
+        assert_eq!(
+            parser.parse(r#"Sure! {"name": "John", "age": 42} is the answer. {"other": 1}"#),
+            Ok(Person {
+                name: "John".to_string(),
+                age: 42
+            })
+        );
+        assert_eq!(
+            parser.parse(r#"{"name": "John", "age": "42"}"#),
+            Err("$.age: expected an integer".to_string())
+        );
+        assert_eq!(
+            parser.parse(r#"{"name": "John"}"#),
+            Err("$: missing required property `age`".to_string())
+        );
+        assert!(parser
+            .parse(r#"{"name": "John", "age": 4"#)
+            .unwrap_err()
+            .starts_with("invalid JSON"));
+        assert!(parser.parse("John is 42").is_err());
+
+        let parser = JsonOutputParser::<Vec<i64>>::new();
+        assert_eq!(
+            parser.parse("[note] The values are [1, 2, 3]"),
+            Ok(vec![1, 2, 3])
+        );
+        let parser = JsonOutputParser::<f64>::new();
+        assert_eq!(parser.parse(" 3.5 "), Ok(3.5));
+    }
+
+    #[test]
+    fn retries_with_error_feedback() {
+        let parser = |text: &str| -> Result<u32, String> {
+            text.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("`{}` is not a number", text.trim()))
+        };
+        let config = StructuredOutputConfig {
+            max_retries: 2,
+            retry_template: "{prompt}{output} -> {error}\n".to_string(),
+            max_new_tokens: None,
+        };
+
+        let mut outputs = vec![" 12", " twelve"];
+        let mut prompts = vec![];
+        let output = generate_with_retries("Q:", &parser, &config, |prompt| {
+            prompts.push(prompt.to_string());
+            Ok(outputs.pop().unwrap().to_string())
+        })
+        .unwrap();
+        assert_eq!(output.value, Some(12));
+        assert_eq!(output.attempts.len(), 2);
+        assert_eq!(
+            output.attempts[0].error.as_deref(),
+            Some("`twelve` is not a number")
+        );
+        assert_eq!(
+            prompts,
+            vec!["Q:", "Q: twelve -> `twelve` is not a number\n"]
+        );
+
+        let output =
+            generate_with_retries("Q:", &parser, &config, |_| Ok("none".to_string())).unwrap();
+        assert!(!output.is_valid());
+        assert_eq!(output.attempts.len(), 3);
+        assert!(output.into_result().is_err());
+
+        let config = StructuredOutputConfig {
+            retry_template: "Try again".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -53,6 +53,13 @@
 //! ```
 //! (New sample credits: [WikiNews](https://en.wikinews.org/wiki/Astronomers_find_water_vapour_in_atmosphere_of_exoplanet_K2-18b))
 //!
+//! Unbounded inputs such as live transcripts can be summarized incrementally with a `StreamingSummarizer`,
+//! which folds the incoming text chunks into a rolling summary.
+//!
+//! Inputs longer than the model context can be summarized with `summarize_extract_then_abstract`: the most salient
+//! sentences of each text are first selected by an unsupervised extractive scorer (`score_sentences`), and only these
+//! sentences are passed to the abstractive model.
+//!
 //! Example output: \
 //! ```no_run
 //! # let output =
@@ -62,21 +69,35 @@
 //! # ;
 //! ```
 
-use tch::Device;
+use tch::{Device, Kind};
 
+#[cfg(feature = "bart")]
 use crate::bart::BartGenerator;
 use crate::common::error::RustBertError;
+use crate::common::precision::{is_half_precision, with_attention_fp32_accumulation};
+#[cfg(feature = "pegasus")]
 use crate::pegasus::PegasusConditionalGenerator;
 use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::faithfulness::{FaithfulnessModel, ScoredSummary};
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{
+    EncoderOutputCache, GenerateConfig, GenerateOptions, LanguageGenerator, NoRepeatNgramScope,
+};
+use crate::pipelines::translation::{split_sentences, Language};
+#[cfg(feature = "prophetnet")]
 use crate::prophetnet::ProphetNetConditionalGenerator;
 use crate::resources::ResourceProvider;
+#[cfg(feature = "t5")]
 use crate::t5::T5Generator;
+use std::collections::HashMap;
+use std::ops::Range;
 
+use crate::common::settings::default_device;
+#[cfg(feature = "longt5")]
 use crate::longt5::LongT5Generator;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::ONNXConditionalGenerator;
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bart"))]
 use crate::{
     bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources},
     resources::RemoteResource,
@@ -118,6 +139,10 @@ pub struct SummarizationConfig {
     pub length_penalty: f64,
     /// Number of allowed repetitions of n-grams. Values higher than 0 turn on this feature (default: 3)
     pub no_repeat_ngram_size: i64,
+    /// Tokens considered for the n-gram repetition blocking: the full sequence or the generated continuation only (default: FullSequence)
+    pub no_repeat_ngram_scope: NoRepeatNgramScope,
+    /// Size of the n-grams of the input text that may not be repeated in the summary. Values higher than 0 turn on this feature (default: 0)
+    pub encoder_no_repeat_ngram_size: i64,
     /// Number of sequences to return for each prompt text (default: 1)
     pub num_return_sequences: i64,
     /// Number of beam groups for diverse beam generation. If provided and higher than 1, will split the beams into beam subgroups leading to more diverse generation.
@@ -126,6 +151,14 @@ pub struct SummarizationConfig {
     pub diversity_penalty: Option<f64>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
+    /// Optional cache of the encoder outputs for repeated inputs (default: None)
+    pub encoder_cache: Option<EncoderOutputCache>,
+    /// List of bad word ids (may be a sequence of word ids) that will be banned during the generation (default: empty)
+    pub bad_word_ids: Vec<Vec<i64>>,
+    /// Biases added to the score of the last token of token sequences when the preceding tokens of the sequence were generated (single token sequences are always biased). Negative values discourage the sequence, `f64::NEG_INFINITY` bans it (default: empty)
+    pub sequence_bias: Vec<(Vec<i64>, f64)>,
 }
 
 impl SummarizationConfig {
@@ -166,15 +199,21 @@ impl SummarizationConfig {
             repetition_penalty: 1.0,
             length_penalty: 1.0,
             no_repeat_ngram_size: 3,
+            no_repeat_ngram_scope: NoRepeatNgramScope::FullSequence,
+            encoder_no_repeat_ngram_size: 0,
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
+            encoder_cache: None,
+            bad_word_ids: Vec::new(),
+            sequence_bias: Vec::new(),
         }
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bart"))]
 impl Default for SummarizationConfig {
     fn default() -> SummarizationConfig {
         SummarizationConfig::new(
@@ -210,10 +249,22 @@ impl From<SummarizationConfig> for GenerateConfig {
             repetition_penalty: config.repetition_penalty,
             length_penalty: config.length_penalty,
             no_repeat_ngram_size: config.no_repeat_ngram_size,
+            no_repeat_ngram_scope: config.no_repeat_ngram_scope,
+            encoder_no_repeat_ngram_size: config.encoder_no_repeat_ngram_size,
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            penalty_alpha: None,
+            typical_p: None,
+            eta_cutoff: None,
+            epsilon_cutoff: None,
             device: config.device,
+            encoder_cache: config.encoder_cache,
+            stop_sequences: Vec::new(),
+            stopping_criteria: Vec::new(),
+            logits_processors: Vec::new(),
+            bad_word_ids: config.bad_word_ids,
+            sequence_bias: config.sequence_bias,
         }
     }
 }
@@ -221,14 +272,19 @@ impl From<SummarizationConfig> for GenerateConfig {
 /// # Abstraction that holds one particular summarization model, for any of the supported models
 pub enum SummarizationOption {
     /// Summarizer based on BART model
+    #[cfg(feature = "bart")]
     Bart(BartGenerator),
     /// Summarizer based on T5 model
+    #[cfg(feature = "t5")]
     T5(T5Generator),
     /// Summarizer based on LongT5 model
+    #[cfg(feature = "longt5")]
     LongT5(LongT5Generator),
     /// Summarizer based on ProphetNet model
+    #[cfg(feature = "prophetnet")]
     ProphetNet(ProphetNetConditionalGenerator),
     /// Summarizer based on Pegasus model
+    #[cfg(feature = "pegasus")]
     Pegasus(PegasusConditionalGenerator),
     /// Summarizer based on ONNX model
     #[cfg(feature = "onnx")]
@@ -242,16 +298,21 @@ impl SummarizationOption {
             (_, &ModelResource::ONNX(_)) => Ok(SummarizationOption::ONNX(
                 ONNXConditionalGenerator::new(config.into(), None, None)?,
             )),
+            #[cfg(feature = "bart")]
             (ModelType::Bart, _) => Ok(SummarizationOption::Bart(BartGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "t5")]
             (ModelType::T5, _) => Ok(SummarizationOption::T5(T5Generator::new(config.into())?)),
+            #[cfg(feature = "longt5")]
             (ModelType::LongT5, _) => Ok(SummarizationOption::LongT5(LongT5Generator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "prophetnet")]
             (ModelType::ProphetNet, _) => Ok(SummarizationOption::ProphetNet(
                 ProphetNetConditionalGenerator::new(config.into())?,
             )),
+            #[cfg(feature = "pegasus")]
             (ModelType::Pegasus, _) => Ok(SummarizationOption::Pegasus(
                 PegasusConditionalGenerator::new(config.into())?,
             )),
@@ -271,19 +332,24 @@ impl SummarizationOption {
             (_, &ModelResource::ONNX(_)) => Ok(SummarizationOption::ONNX(
                 ONNXConditionalGenerator::new_with_tokenizer(config.into(), tokenizer, None, None)?,
             )),
+            #[cfg(feature = "bart")]
             (ModelType::Bart, _) => Ok(SummarizationOption::Bart(
                 BartGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "t5")]
             (ModelType::T5, _) => Ok(SummarizationOption::T5(T5Generator::new_with_tokenizer(
                 config.into(),
                 tokenizer,
             )?)),
+            #[cfg(feature = "longt5")]
             (ModelType::LongT5, _) => Ok(SummarizationOption::LongT5(
                 LongT5Generator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "prophetnet")]
             (ModelType::ProphetNet, _) => Ok(SummarizationOption::ProphetNet(
                 ProphetNetConditionalGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "pegasus")]
             (ModelType::Pegasus, _) => Ok(SummarizationOption::Pegasus(
                 PegasusConditionalGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
@@ -297,76 +363,147 @@ impl SummarizationOption {
     /// Returns the `ModelType` for this SummarizationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bart")]
             Self::Bart(_) => ModelType::Bart,
+            #[cfg(feature = "t5")]
             Self::T5(_) => ModelType::T5,
+            #[cfg(feature = "longt5")]
             Self::LongT5(_) => ModelType::LongT5,
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(_) => ModelType::ProphetNet,
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(_) => ModelType::Pegasus,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
         }
     }
 
+    /// Returns the maximum number of positions (context window) of the model, if limited
+    pub fn get_max_positions_embeddings(&self) -> Option<i64> {
+        match *self {
+            #[cfg(feature = "bart")]
+            Self::Bart(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "longt5")]
+            Self::LongT5(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model_ref) => model_ref.get_max_positions_embeddings(),
+        }
+    }
+
     /// Interface method to access tokenizer
     pub fn get_tokenizer(&self) -> &TokenizerOption {
-        match self {
-            Self::Bart(model_ref) => model_ref.get_tokenizer(),
-            Self::T5(model_ref) => model_ref.get_tokenizer(),
-            Self::LongT5(model_ref) => model_ref.get_tokenizer(),
-            Self::ProphetNet(model_ref) => model_ref.get_tokenizer(),
-            Self::Pegasus(model_ref) => model_ref.get_tokenizer(),
+        match *self {
+            #[cfg(feature = "bart")]
+            Self::Bart(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "longt5")]
+            Self::LongT5(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(ref model_ref) => model_ref.get_tokenizer(),
             #[cfg(feature = "onnx")]
-            Self::ONNX(model_ref) => model_ref.get_tokenizer(),
+            Self::ONNX(ref model_ref) => model_ref.get_tokenizer(),
         }
     }
 
     /// Interface method to access tokenizer
     pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
-        match self {
-            Self::Bart(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::T5(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::LongT5(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::ProphetNet(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::Pegasus(model_ref) => model_ref.get_tokenizer_mut(),
+        match *self {
+            #[cfg(feature = "bart")]
+            Self::Bart(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "longt5")]
+            Self::LongT5(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+        }
+    }
+
+    /// Interface method to cast the weights of the model to a floating point precision
+    pub fn set_kind(&mut self, kind: Kind) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "bart")]
+            Self::Bart(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "longt5")]
+            Self::LongT5(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(ref mut model_ref) => model_ref.set_kind(kind),
             #[cfg(feature = "onnx")]
-            Self::ONNX(model_ref) => model_ref.get_tokenizer_mut(),
+            Self::ONNX(_) => Err(RustBertError::OrtError(
+                "Type casting not supported for ONNX models.".to_string(),
+            )),
         }
     }
 
     /// Interface method to generate() of the particular models.
     pub fn generate<S>(&self, prompt_texts: Option<&[S]>) -> Vec<String>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        self.generate_with_options(prompt_texts, None)
+    }
+
+    /// Interface method to generate() of the particular models, with `GenerateOptions` overriding the
+    /// generation configuration of the model.
+    pub fn generate_with_options<S>(
+        &self,
+        prompt_texts: Option<&[S]>,
+        generate_options: Option<GenerateOptions>,
+    ) -> Vec<String>
     where
         S: AsRef<str> + Send + Sync,
     {
         match *self {
+            #[cfg(feature = "bart")]
             Self::Bart(ref model) => model
-                .generate(prompt_texts, None)
+                .generate(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "t5")]
             Self::T5(ref model) => model
-                .generate(prompt_texts, None)
+                .generate(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "longt5")]
             Self::LongT5(ref model) => model
-                .generate(prompt_texts, None)
+                .generate(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(ref model) => model
-                .generate(prompt_texts, None)
+                .generate(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(ref model) => model
-                .generate(prompt_texts, None)
+                .generate(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
             #[cfg(feature = "onnx")]
             Self::ONNX(ref model) => model
-                .generate(prompt_texts, None)
+                .generate(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
@@ -374,10 +511,23 @@ impl SummarizationOption {
     }
 }
 
+/// # Configuration of the extractive step of extract-then-abstract summarization
+#[derive(Debug, Clone, Default)]
+pub struct SalientSentencesConfig {
+    /// Maximum number of tokens of the selected sentences. Defaults to the maximum input length of the model (default: None)
+    pub max_tokens: Option<usize>,
+    /// Maximum number of sentences selected (default: None)
+    pub max_sentences: Option<usize>,
+    /// Language of the texts, used to split them into sentences (default: None)
+    pub language: Option<Language>,
+}
+
 /// # SummarizationModel to perform summarization
 pub struct SummarizationModel {
     model: SummarizationOption,
     prefix: Option<String>,
+    faithfulness: Option<(FaithfulnessModel, f64)>,
+    attention_fp32_accumulation: bool,
 }
 
 impl SummarizationModel {
@@ -401,12 +551,22 @@ impl SummarizationModel {
         summarization_config: SummarizationConfig,
     ) -> Result<SummarizationModel, RustBertError> {
         let prefix = match summarization_config.model_type {
+            #[cfg(feature = "t5")]
             ModelType::T5 => Some("summarize: ".to_string()),
             _ => None,
         };
-        let model = SummarizationOption::new(summarization_config)?;
+        let kind = summarization_config.kind;
+        let mut model = SummarizationOption::new(summarization_config)?;
+        if let Some(kind) = kind {
+            model.set_kind(kind)?;
+        }
 
-        Ok(SummarizationModel { model, prefix })
+        Ok(SummarizationModel {
+            model,
+            prefix,
+            faithfulness: None,
+            attention_fp32_accumulation: kind.map_or(false, is_half_precision),
+        })
     }
 
     /// Build a new `SummarizationModel` with a provided tokenizer.
@@ -440,12 +600,22 @@ impl SummarizationModel {
         tokenizer: TokenizerOption,
     ) -> Result<SummarizationModel, RustBertError> {
         let prefix = match summarization_config.model_type {
+            #[cfg(feature = "t5")]
             ModelType::T5 => Some("summarize: ".to_string()),
             _ => None,
         };
-        let model = SummarizationOption::new_with_tokenizer(summarization_config, tokenizer)?;
+        let kind = summarization_config.kind;
+        let mut model = SummarizationOption::new_with_tokenizer(summarization_config, tokenizer)?;
+        if let Some(kind) = kind {
+            model.set_kind(kind)?;
+        }
 
-        Ok(SummarizationModel { model, prefix })
+        Ok(SummarizationModel {
+            model,
+            prefix,
+            faithfulness: None,
+            attention_fp32_accumulation: kind.map_or(false, is_half_precision),
+        })
     }
 
     /// Get a reference to the model tokenizer.
@@ -458,6 +628,100 @@ impl SummarizationModel {
         self.model.get_tokenizer_mut()
     }
 
+    /// Set the prefix prepended to the texts to summarize. T5 models default to `"summarize: "`,
+    /// UL2 checkpoints additionally expect the mode token of their mixture of denoisers.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - `Option<String>` prefix to prepend to each input text (no prefix if `None`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    /// use rust_bert::t5::UL2Mode;
+    ///
+    /// let mut summarization_model = SummarizationModel::new(Default::default())?;
+    /// summarization_model.set_prefix(Some(UL2Mode::S2S.apply("summarize: ")));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_prefix(&mut self, prefix: Option<String>) {
+        self.prefix = prefix;
+    }
+
+    /// Attach a faithfulness model scoring the summaries generated by `summarize_with_faithfulness`.
+    ///
+    /// # Arguments
+    ///
+    /// * `faithfulness_model` - `FaithfulnessModel` (NLI model) scoring the summaries against the source texts
+    /// * `threshold` - Summary sentences with an entailment probability below this threshold are flagged as likely
+    /// hallucinations
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::faithfulness::FaithfulnessModel;
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    ///
+    /// let mut summarization_model = SummarizationModel::new(Default::default())?;
+    /// summarization_model.set_faithfulness_model(FaithfulnessModel::new(Default::default())?, 0.5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_faithfulness_model(
+        &mut self,
+        faithfulness_model: FaithfulnessModel,
+        threshold: f64,
+    ) {
+        self.faithfulness = Some((faithfulness_model, threshold));
+    }
+
+    /// Summarize texts provided and scores the faithfulness of the summaries to the texts with the attached
+    /// faithfulness model, flagging the summary sentences likely to be hallucinations. Requires a faithfulness model
+    /// set with `set_faithfulness_model`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to summarize.
+    ///
+    /// # Returns
+    /// * `Vec<ScoredSummary>` Summaries with their faithfulness scores
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::faithfulness::FaithfulnessModel;
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    ///
+    /// let mut summarization_model = SummarizationModel::new(Default::default())?;
+    /// summarization_model.set_faithfulness_model(FaithfulnessModel::new(Default::default())?, 0.5);
+    ///
+    /// let input = ["The Eiffel tower was completed in 1889. It is located in Paris, France."];
+    /// let output = summarization_model.summarize_with_faithfulness(&input)?;
+    /// let flagged = output.iter().filter(|summary| summary.has_hallucinations());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summarize_with_faithfulness<S>(
+        &self,
+        texts: &[S],
+    ) -> Result<Vec<ScoredSummary>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let (faithfulness_model, threshold) = self.faithfulness.as_ref().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "No faithfulness model attached, set one with `set_faithfulness_model`".to_string(),
+            )
+        })?;
+        let summaries = self.summarize(texts);
+        faithfulness_model.check(texts, &summaries, *threshold)
+    }
+
     /// Summarize texts provided
     ///
     /// # Arguments
@@ -506,23 +770,402 @@ impl SummarizationModel {
     where
         S: AsRef<str> + Send + Sync,
     {
-        match &self.prefix {
-            None => self.model.generate(Some(texts)),
+        self.generate(texts, None)
+    }
+
+    /// Summarize texts provided, with generation options overriding the configuration of the model for this call
+    /// (e.g. the `decoder_start_token_id`, `forced_bos_token_id` and `forced_eos_token_id` of fine-tuned models
+    /// whose configuration is missing or incorrect).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to summarize.
+    /// * `generate_options` - `GenerateOptions` overriding the generation settings of the model
+    ///
+    /// # Returns
+    /// * `Vec<String>` Summarized texts
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::generation_utils::GenerateOptions;
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    /// let model = SummarizationModel::new(Default::default())?;
+    ///
+    /// let input = ["The Eiffel tower was completed in 1889. It is located in Paris, France."];
+    /// let generate_options = GenerateOptions {
+    ///     decoder_start_token_id: Some(2),
+    ///     forced_bos_token_id: Some(0),
+    ///     forced_eos_token_id: Some(2),
+    ///     ..Default::default()
+    /// };
+    /// let output = model.summarize_with_options(&input, generate_options);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summarize_with_options<S>(
+        &self,
+        texts: &[S],
+        generate_options: GenerateOptions,
+    ) -> Vec<String>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        self.generate(texts, Some(generate_options))
+    }
+
+    /// Selects the most salient sentences of texts, so that they fit in the input of the model. The sentences are
+    /// scored with `score_sentences` and selected by decreasing salience until the token budget is exhausted. The
+    /// selected sentences are returned in their original order. Texts fitting in the budget are returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract sentences from.
+    /// * `config` - `SalientSentencesConfig` defining the token budget and the language of the texts
+    ///
+    /// # Returns
+    /// * `Vec<String>` Selected sentences of each text
+    pub fn extract_salient_sentences<S>(
+        &self,
+        texts: &[S],
+        config: &SalientSentencesConfig,
+    ) -> Vec<String>
+    where
+        S: AsRef<str>,
+    {
+        let tokenizer = self.get_tokenizer();
+        let max_tokens = config.max_tokens.unwrap_or_else(|| {
+            let prefix_length = self
+                .prefix
+                .as_ref()
+                .map_or(0, |prefix| tokenizer.tokenize(prefix).len());
+            // Keeps room for the special tokens added by the tokenizer
+            self.model
+                .get_max_positions_embeddings()
+                .map_or(512, |max_positions| max_positions as usize)
+                .saturating_sub(prefix_length + 2)
+        });
+        texts
+            .iter()
+            .map(|text| {
+                let text = text.as_ref();
+                let sentences = split_sentences(text, config.language);
+                let lengths = sentences
+                    .iter()
+                    .map(|sentence| tokenizer.tokenize(&text[sentence.clone()]).len())
+                    .collect::<Vec<usize>>();
+                if lengths.iter().sum::<usize>() <= max_tokens
+                    && sentences.len() <= config.max_sentences.unwrap_or(usize::MAX)
+                {
+                    return text.to_string();
+                }
+                let scores = score_sentences(text, &sentences);
+                select_sentences(&scores, &lengths, max_tokens, config.max_sentences)
+                    .into_iter()
+                    .map(|index| &text[sentences[index].clone()])
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    /// Summarize long texts in two steps: the most salient sentences of each text are selected with
+    /// `extract_salient_sentences` and the selected sentences are summarized by the model. Compared to summarizing
+    /// every part of a long text and merging the partial summaries, the model runs once per text and the summary is
+    /// only conditioned on the salient content of the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to summarize.
+    /// * `config` - `SalientSentencesConfig` defining the token budget and the language of the texts
+    ///
+    /// # Returns
+    /// * `Vec<String>` Summarized texts
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::{SalientSentencesConfig, SummarizationModel};
+    /// let model = SummarizationModel::new(Default::default())?;
+    ///
+    /// let input = ["A very long report. It contains many sentences. Only some of them are relevant."];
+    /// let config = SalientSentencesConfig {
+    ///     max_tokens: Some(256),
+    ///     ..Default::default()
+    /// };
+    /// let output = model.summarize_extract_then_abstract(&input, &config);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summarize_extract_then_abstract<S>(
+        &self,
+        texts: &[S],
+        config: &SalientSentencesConfig,
+    ) -> Vec<String>
+    where
+        S: AsRef<str>,
+    {
+        let extracts = self.extract_salient_sentences(texts, config);
+        self.summarize(&extracts)
+    }
+
+    fn generate<S>(&self, texts: &[S], generate_options: Option<GenerateOptions>) -> Vec<String>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        with_attention_fp32_accumulation(self.attention_fp32_accumulation, || match &self.prefix {
+            None => self
+                .model
+                .generate_with_options(Some(texts), generate_options),
             Some(prefix) => {
                 let texts = texts
                     .iter()
                     .map(|text| format!("{}{}", prefix, text.as_ref()))
                     .collect::<Vec<String>>();
-                self.model.generate(Some(&texts))
+                self.model
+                    .generate_with_options(Some(&texts), generate_options)
             }
+        })
+    }
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Scores the salience of the sentences of a text, as the cosine similarity between the TF-IDF vector of each
+/// sentence and the TF-IDF vector of the whole text (the sentences being the documents of the inverse document
+/// frequency). Words frequent in the text but concentrated in a few sentences are weighted up, and words spread
+/// over every sentence (e.g. function words) weighted down.
+///
+/// # Arguments
+///
+/// * `text` - Text to score
+/// * `sentences` - Byte ranges of the sentences of the text, for example from `split_sentences`
+///
+/// # Returns
+/// * `Vec<f64>` Salience score (between 0 and 1) of each sentence
+pub fn score_sentences(text: &str, sentences: &[Range<usize>]) -> Vec<f64> {
+    let sentence_counts = sentences
+        .iter()
+        .map(|sentence| {
+            let mut counts: HashMap<String, f64> = HashMap::new();
+            for word in words(&text[sentence.clone()]) {
+                *counts.entry(word).or_insert(0.0) += 1.0;
+            }
+            counts
+        })
+        .collect::<Vec<HashMap<String, f64>>>();
+
+    let mut document_frequencies: HashMap<&str, f64> = HashMap::new();
+    let mut text_counts: HashMap<&str, f64> = HashMap::new();
+    for counts in &sentence_counts {
+        for (word, count) in counts {
+            *document_frequencies.entry(word.as_str()).or_insert(0.0) += 1.0;
+            *text_counts.entry(word.as_str()).or_insert(0.0) += count;
         }
     }
+    let num_sentences = sentences.len() as f64;
+    let idf = |word: &str| (1.0 + num_sentences / document_frequencies[word]).ln();
+    let text_vector = text_counts
+        .iter()
+        .map(|(word, count)| (*word, count * idf(word)))
+        .collect::<HashMap<&str, f64>>();
+    let text_norm = text_vector
+        .values()
+        .map(|value| value * value)
+        .sum::<f64>()
+        .sqrt();
+
+    sentence_counts
+        .iter()
+        .map(|counts| {
+            let mut dot_product = 0.0;
+            let mut sentence_norm = 0.0;
+            for (word, count) in counts {
+                let value = count * idf(word);
+                dot_product += value * text_vector[word.as_str()];
+                sentence_norm += value * value;
+            }
+            if sentence_norm > 0.0 && text_norm > 0.0 {
+                dot_product / (sentence_norm.sqrt() * text_norm)
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Selects sentences by decreasing score while they fit in the token budget, and returns their indices in the
+/// original order. The best sentence is always selected, even if it exceeds the budget.
+fn select_sentences(
+    scores: &[f64],
+    lengths: &[usize],
+    max_tokens: usize,
+    max_sentences: Option<usize>,
+) -> Vec<usize> {
+    let mut ranking = (0..scores.len()).collect::<Vec<usize>>();
+    ranking.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]).then(a.cmp(b)));
+    let max_sentences = max_sentences.unwrap_or(usize::MAX).max(1);
+    let mut selected = Vec::new();
+    let mut num_tokens = 0;
+    for index in ranking {
+        if selected.len() >= max_sentences {
+            break;
+        }
+        if selected.is_empty() || num_tokens + lengths[index] <= max_tokens {
+            num_tokens += lengths[index];
+            selected.push(index);
+        }
+    }
+    selected.sort_unstable();
+    selected
+}
+
+/// # Incremental summarizer for unbounded inputs
+/// Maintains a rolling summary of a stream of text chunks (e.g. a live transcript). The chunks are buffered until
+/// they reach `fold_length` tokens, and then folded into the summary: the current summary and the buffered text
+/// are summarized together into the new summary. The summary therefore stays bounded by the maximum length of the
+/// summarization model, regardless of the length of the stream.
+pub struct StreamingSummarizer<'a> {
+    model: &'a SummarizationModel,
+    fold_length: usize,
+    summary: String,
+    pending: Vec<String>,
+    pending_length: usize,
+}
+
+impl<'a> StreamingSummarizer<'a> {
+    /// Build a new `StreamingSummarizer`
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `SummarizationModel` used to fold the text chunks into the summary
+    /// * `fold_length` - Number of tokens buffered before they are folded into the summary. The buffered text and
+    /// the summary should fit in the maximum input length of the model, the end of longer inputs is truncated.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::{SummarizationModel, StreamingSummarizer};
+    ///
+    /// let model = SummarizationModel::new(Default::default())?;
+    /// let mut summarizer = StreamingSummarizer::new(&model, 512);
+    /// for chunk in &["First part of a live transcript.", "Second part of the transcript."] {
+    ///     summarizer.push(chunk);
+    /// }
+    /// summarizer.flush();
+    /// let summary = summarizer.current_summary();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(model: &'a SummarizationModel, fold_length: usize) -> StreamingSummarizer<'a> {
+        StreamingSummarizer {
+            model,
+            fold_length,
+            summary: String::new(),
+            pending: Vec::new(),
+            pending_length: 0,
+        }
+    }
+
+    /// Adds a chunk of text to the stream, folding the buffered text into the summary once it reaches
+    /// `fold_length` tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - Text chunk to append to the stream
+    ///
+    /// # Returns
+    /// * `bool` indicating if the summary was updated
+    pub fn push<S: AsRef<str>>(&mut self, chunk: S) -> bool {
+        let chunk = chunk.as_ref().trim();
+        if chunk.is_empty() {
+            return false;
+        }
+        self.pending_length += self.model.get_tokenizer().tokenize(chunk).len();
+        self.pending.push(chunk.to_string());
+        if self.pending_length >= self.fold_length {
+            self.flush()
+        } else {
+            false
+        }
+    }
+
+    /// Folds the buffered text into the summary, regardless of its length (e.g. at the end of the stream).
+    ///
+    /// # Returns
+    /// * `bool` indicating if the summary was updated (`false` if no text was buffered)
+    pub fn flush(&mut self) -> bool {
+        if self.pending.is_empty() {
+            return false;
+        }
+        let mut input = self.summary.clone();
+        for chunk in self.pending.drain(..) {
+            if !input.is_empty() {
+                input.push(' ');
+            }
+            input.push_str(&chunk);
+        }
+        self.pending_length = 0;
+        if let Some(summary) = self.model.summarize(&[input]).pop() {
+            self.summary = summary.trim().to_string();
+        }
+        true
+    }
+
+    /// Returns the current summary of the stream. The text buffered since the last fold is not yet reflected
+    /// (see `flush`).
+    pub fn current_summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Returns the number of tokens buffered since the last fold.
+    pub fn pending_length(&self) -> usize {
+        self.pending_length
+    }
+
+    /// Clears the summary and the buffered text, e.g. to start summarizing a new stream.
+    pub fn reset(&mut self) {
+        self.summary.clear();
+        self.pending.clear();
+        self.pending_length = 0;
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn sentence_scoring() {
+        let text = "The probe reached Mars in June. It was a sunny day. \
+The probe sent images of Mars and of the moons of Mars.";
+        let sentences = split_sentences(text, None);
+        assert_eq!(sentences.len(), 3);
+        let scores = score_sentences(text, &sentences);
+        assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+        assert!(scores[2] > scores[0]);
+        assert!(scores[0] > scores[1]);
+        assert!(score_sentences("", &[]).is_empty());
+    }
+
+    #[test]
+    fn sentence_selection() {
+        let scores = [0.2, 0.9, 0.5, 0.7];
+        let lengths = [10, 20, 5, 30];
+        assert_eq!(select_sentences(&scores, &lengths, 30, None), [1, 2]);
+        assert_eq!(select_sentences(&scores, &lengths, 60, None), [1, 2, 3]);
+        assert_eq!(select_sentences(&scores, &lengths, 100, Some(2)), [1, 3]);
+        assert_eq!(select_sentences(&scores, &lengths, 5, None), [1]);
+        assert!(select_sentences(&[], &[], 10, None).is_empty());
+    }
+
     #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
     fn test() {
@@ -0,0 +1,814 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Table question answering pipeline
+//! Answers natural language questions over a table of strings with a TAPAS model. The model selects the cells
+//! answering the question and, for models fine-tuned with aggregation (e.g. on WikiTableQuestions), an operator
+//! aggregating these cells (`SUM`, `AVERAGE` or `COUNT`). By default, the dependencies for this model will be
+//! downloaded for a TAPAS base model fine-tuned on WikiTableQuestions.
+//!
+//! The question is followed by the flattened table (header, then rows) in the model input. Rows are dropped from the
+//! end of the table if the input exceeds the maximum length. Numeric columns are ranked, and the numbers of the
+//! question are compared to the cells of the table, as expected by TAPAS models.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::table_question_answering::{Table, TableQuestionAnsweringModel};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let model = TableQuestionAnsweringModel::new(Default::default())?;
+//!
+//! let table = Table::new(
+//!     &["Actor", "Age", "Number of movies"],
+//!     &[
+//!         vec!["Brad Pitt", "59", "87"],
+//!         vec!["Leonardo DiCaprio", "48", "53"],
+//!         vec!["George Clooney", "61", "69"],
+//!     ],
+//! )?;
+//! let questions = [
+//!     "How many movies has George Clooney played in?",
+//!     "What is the total number of movies?",
+//! ];
+//! let answers = model.predict(&table, &questions)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::table_question_answering::TableAnswer;
+//! # let output =
+//! [
+//!     TableAnswer {
+//!         answer: String::from("69"),
+//!         coordinates: vec![(2, 2)],
+//!         cells: vec![String::from("69")],
+//!         aggregator: None,
+//!     },
+//!     TableAnswer {
+//!         answer: String::from("SUM > 87, 53, 69"),
+//!         coordinates: vec![(0, 2), (1, 2), (2, 2)],
+//!         cells: vec![
+//!             String::from("87"),
+//!             String::from("53"),
+//!             String::from("69"),
+//!         ],
+//!         aggregator: Some(String::from("SUM")),
+//!     },
+//! ]
+//! # ;
+//! ```
+
+use crate::common::error::{InputError, RustBertError};
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+use crate::resources::ResourceProvider;
+use crate::tapas::{TapasConfig, TapasForQuestionAnswering, NUM_TAPAS_TOKEN_TYPES};
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use tch::nn::VarStore;
+use tch::{no_grad, Device, Kind, Tensor};
+
+#[cfg(feature = "remote")]
+use crate::{
+    resources::RemoteResource,
+    tapas::{TapasConfigResources, TapasModelResources, TapasVocabResources},
+};
+
+const SEGMENT: usize = 0;
+const COLUMN: usize = 1;
+const ROW: usize = 2;
+const COLUMN_RANK: usize = 4;
+const INVERSE_COLUMN_RANK: usize = 5;
+const NUMERIC_RELATION: usize = 6;
+
+// Numeric relations between a number of the question and the value of a cell
+const RELATION_EQUAL: i64 = 1;
+const RELATION_LOWER: i64 = 2;
+const RELATION_GREATER: i64 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// # Table of strings queried by the table question answering pipeline
+pub struct Table {
+    /// Column names
+    pub header: Vec<String>,
+    /// Rows of the table, with one cell per column
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Build a new `Table` from its header and rows
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Column names
+    /// * `rows` - Rows of the table, each containing one cell per column
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::table_question_answering::Table;
+    ///
+    /// let table = Table::new(
+    ///     &["City", "Population"],
+    ///     &[vec!["Paris", "2,102,650"], vec!["Lyon", "522,250"]],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new<S: AsRef<str>>(header: &[S], rows: &[Vec<S>]) -> Result<Table, RustBertError> {
+        let table = Table {
+            header: header
+                .iter()
+                .map(|cell| cell.as_ref().to_string())
+                .collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|cell| cell.as_ref().to_string()).collect())
+                .collect(),
+        };
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Number of columns of the table
+    pub fn num_columns(&self) -> usize {
+        self.header.len()
+    }
+
+    /// Number of rows of the table (excluding the header)
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn validate(&self) -> Result<(), RustBertError> {
+        if self.header.is_empty() {
+            return Err(RustBertError::ValueError(
+                "The table should contain at least one column".to_string(),
+            ));
+        }
+        if let Some((index, row)) = self
+            .rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.len() != self.header.len())
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Row {index} of the table has {} cells, expected one cell per column ({})",
+                row.len(),
+                self.header.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// # Configuration for TableQuestionAnsweringModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct TableQuestionAnsweringConfig {
+    /// Model weights resource (default: pretrained TAPAS base model on WikiTableQuestions)
+    pub model_resource: ModelResource,
+    /// Config resource (default: pretrained TAPAS base model on WikiTableQuestions)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: pretrained TAPAS base model on WikiTableQuestions)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Flag indicating if the model expects a lower casing of the input
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization)
+    pub strip_accents: Option<bool>,
+    /// Maximum number of tokens of the question and flattened table (default: 512)
+    pub max_length: usize,
+    /// Minimum average probability of the tokens of a cell for the cell to be selected (default: 0.5)
+    pub cell_threshold: f64,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl TableQuestionAnsweringConfig {
+    /// Instantiate a new table question answering configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * model_resource - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config_resource - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab_resource - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt)
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RC, RV>(
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        lower_case: bool,
+    ) -> TableQuestionAnsweringConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        TableQuestionAnsweringConfig {
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            lower_case,
+            strip_accents: None,
+            max_length: 512,
+            cell_threshold: 0.5,
+            device: default_device(),
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Default for TableQuestionAnsweringConfig {
+    fn default() -> TableQuestionAnsweringConfig {
+        TableQuestionAnsweringConfig::new(
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                TapasModelResources::TAPAS_BASE_WTQ,
+            ))),
+            RemoteResource::from_pretrained(TapasConfigResources::TAPAS_BASE_WTQ),
+            RemoteResource::from_pretrained(TapasVocabResources::TAPAS_BASE_WTQ),
+            true,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Answer to a question over a table
+pub struct TableAnswer {
+    /// Answer text: the selected cells, preceded by the aggregation operator if any (e.g. `SUM > 87, 53`)
+    pub answer: String,
+    /// Coordinates (row index, column index) of the selected cells, the first row following the header
+    pub coordinates: Vec<(usize, usize)>,
+    /// Text of the selected cells
+    pub cells: Vec<String>,
+    /// Aggregation operator to apply to the selected cells (None if the cells are the answer)
+    pub aggregator: Option<String>,
+}
+
+impl TableAnswer {
+    fn new(
+        coordinates: Vec<(usize, usize)>,
+        cells: Vec<String>,
+        aggregator: Option<String>,
+    ) -> TableAnswer {
+        let answer = match &aggregator {
+            Some(aggregator) => format!("{aggregator} > {}", cells.join(", ")),
+            None => cells.join(", "),
+        };
+        TableAnswer {
+            answer,
+            coordinates,
+            cells,
+            aggregator,
+        }
+    }
+
+    /// Applies the aggregation operator (`SUM`, `AVERAGE` or `COUNT`) to the selected cells. Returns None if the
+    /// answer is not aggregated, for other operators, or if `SUM` or `AVERAGE` is applied to non-numeric cells.
+    pub fn aggregated_value(&self) -> Option<f64> {
+        let values = || {
+            self.cells
+                .iter()
+                .map(|cell| parse_number(cell))
+                .collect::<Option<Vec<f64>>>()
+        };
+        match self.aggregator.as_deref() {
+            Some("COUNT") => Some(self.cells.len() as f64),
+            Some("SUM") => values().map(|values| values.iter().sum()),
+            Some("AVERAGE") if !self.cells.is_empty() => {
+                values().map(|values| values.iter().sum::<f64>() / values.len() as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+struct EncodedTable {
+    input_ids: Vec<i64>,
+    token_type_ids: Vec<[i64; NUM_TAPAS_TOKEN_TYPES]>,
+}
+
+/// # TableQuestionAnsweringModel to answer questions over tables
+pub struct TableQuestionAnsweringModel {
+    tokenizer: TokenizerOption,
+    model: TapasForQuestionAnswering,
+    cls_id: i64,
+    sep_id: i64,
+    pad_id: i64,
+    empty_id: i64,
+    aggregation_labels: HashMap<i64, String>,
+    no_aggregation_label_index: i64,
+    max_length: usize,
+    max_columns: usize,
+    max_rows: usize,
+    max_rank: i64,
+    cell_threshold: f64,
+    device: Device,
+    var_store: VarStore,
+}
+
+impl TableQuestionAnsweringModel {
+    /// Build a new `TableQuestionAnsweringModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TableQuestionAnsweringConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::table_question_answering::TableQuestionAnsweringModel;
+    ///
+    /// let model = TableQuestionAnsweringModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        config: TableQuestionAnsweringConfig,
+    ) -> Result<TableQuestionAnsweringModel, RustBertError> {
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Tapas,
+            vocab_path.to_str().unwrap(),
+            None,
+            config.lower_case,
+            config.strip_accents,
+            None,
+        )?;
+
+        let device = config.device;
+        let weights_path = config.model_resource.get_torch_local_path()?;
+        let mut var_store = VarStore::new(device);
+        let tapas_config = TapasConfig::from_file(config.config_resource.get_local_path()?);
+        if tapas_config.type_vocab_sizes.len() != NUM_TAPAS_TOKEN_TYPES {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "TAPAS models expect {NUM_TAPAS_TOKEN_TYPES} token types, got {} type vocabulary sizes",
+                tapas_config.type_vocab_sizes.len()
+            )));
+        }
+        let model = TapasForQuestionAnswering::new(var_store.root(), &tapas_config);
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+
+        let special_token_ids = tokenizer.convert_tokens_to_ids(&["[CLS]", "[EMPTY]"]);
+        let sep_id = tokenizer
+            .get_sep_id()
+            .expect("The Tokenizer used for table question answering should contain a SEP id");
+        let pad_id = tokenizer
+            .get_pad_id()
+            .expect("The Tokenizer used for table question answering should contain a PAD id");
+
+        Ok(TableQuestionAnsweringModel {
+            tokenizer,
+            model,
+            cls_id: special_token_ids[0],
+            sep_id,
+            pad_id,
+            empty_id: special_token_ids[1],
+            aggregation_labels: tapas_config.aggregation_labels.unwrap_or_default(),
+            no_aggregation_label_index: tapas_config.no_aggregation_label_index.unwrap_or(0),
+            max_length: config.max_length,
+            max_columns: tapas_config.type_vocab_sizes[COLUMN] as usize - 1,
+            max_rows: tapas_config.type_vocab_sizes[ROW] as usize - 1,
+            max_rank: tapas_config.type_vocab_sizes[COLUMN_RANK] - 1,
+            cell_threshold: config.cell_threshold,
+            device,
+            var_store,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Get a reference to the model var store.
+    pub fn get_var_store(&self) -> &VarStore {
+        &self.var_store
+    }
+
+    /// Answers questions over a table
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - `Table` queried by the questions
+    /// * `questions` - `&[&str]` Array of questions, processed in a single batch
+    ///
+    /// # Returns
+    /// * `Vec<TableAnswer>` containing the selected cells and aggregation operator for each question
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::table_question_answering::{Table, TableQuestionAnsweringModel};
+    ///
+    /// let model = TableQuestionAnsweringModel::new(Default::default())?;
+    /// let table = Table::new(
+    ///     &["Repository", "Stars"],
+    ///     &[vec!["Transformers", "36542"], vec!["Datasets", "4512"]],
+    /// )?;
+    /// let answers = model.predict(&table, &["How many stars does Datasets have?"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<S>(
+        &self,
+        table: &Table,
+        questions: &[S],
+    ) -> Result<Vec<TableAnswer>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        table.validate()?;
+        if questions.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        let encodings = questions
+            .iter()
+            .enumerate()
+            .map(|(index, question)| self.encode(table, question.as_ref(), index))
+            .collect::<Result<Vec<EncodedTable>, RustBertError>>()?;
+
+        let sequence_length = encodings
+            .iter()
+            .map(|encoding| encoding.input_ids.len())
+            .max()
+            .unwrap();
+        let mut input_ids = Vec::with_capacity(encodings.len() * sequence_length);
+        let mut attention_mask = Vec::with_capacity(encodings.len() * sequence_length);
+        let mut token_type_ids =
+            Vec::with_capacity(encodings.len() * sequence_length * NUM_TAPAS_TOKEN_TYPES);
+        for encoding in &encodings {
+            let padding = sequence_length - encoding.input_ids.len();
+            input_ids.extend_from_slice(&encoding.input_ids);
+            input_ids.extend(std::iter::repeat(self.pad_id).take(padding));
+            attention_mask.extend(std::iter::repeat(1i64).take(encoding.input_ids.len()));
+            attention_mask.extend(std::iter::repeat(0i64).take(padding));
+            for token_types in &encoding.token_type_ids {
+                token_type_ids.extend_from_slice(token_types);
+            }
+            token_type_ids.extend(std::iter::repeat(0i64).take(padding * NUM_TAPAS_TOKEN_TYPES));
+        }
+        let batch_size = encodings.len() as i64;
+        let input_ids = Tensor::from_slice(&input_ids)
+            .view([batch_size, sequence_length as i64])
+            .to(self.device);
+        let attention_mask = Tensor::from_slice(&attention_mask)
+            .view([batch_size, sequence_length as i64])
+            .to(self.device);
+        let token_type_ids = Tensor::from_slice(&token_type_ids)
+            .view([
+                batch_size,
+                sequence_length as i64,
+                NUM_TAPAS_TOKEN_TYPES as i64,
+            ])
+            .to(self.device);
+
+        let output = no_grad(|| {
+            self.model.forward_t(
+                Some(&input_ids),
+                Some(&attention_mask),
+                Some(&token_type_ids),
+                None,
+                None,
+                false,
+            )
+        })?;
+        let probabilities = output
+            .logits
+            .sigmoid()
+            .to(Device::Cpu)
+            .to_kind(Kind::Double);
+        let aggregation_ids = output
+            .aggregation_logits
+            .map(|logits| Vec::<i64>::try_from(logits.argmax(-1, false).to(Device::Cpu)))
+            .transpose()?;
+
+        let mut answers = Vec::with_capacity(encodings.len());
+        for (index, encoding) in encodings.iter().enumerate() {
+            let token_probabilities = Vec::<f64>::try_from(
+                probabilities
+                    .get(index as i64)
+                    .narrow(0, 0, encoding.input_ids.len() as i64),
+            )?;
+            let coordinates = cell_probabilities(&token_probabilities, &encoding.token_type_ids)
+                .into_iter()
+                .filter(|(_, probability)| *probability > self.cell_threshold)
+                .map(|(coordinates, _)| coordinates)
+                .collect::<Vec<(usize, usize)>>();
+            let cells = coordinates
+                .iter()
+                .map(|(row, column)| table.rows[*row][*column].clone())
+                .collect();
+            let aggregator = aggregation_ids
+                .as_ref()
+                .and_then(|aggregation_ids| self.aggregator_label(aggregation_ids[index]));
+            answers.push(TableAnswer::new(coordinates, cells, aggregator));
+        }
+        Ok(answers)
+    }
+
+    fn aggregator_label(&self, aggregation_id: i64) -> Option<String> {
+        if aggregation_id == self.no_aggregation_label_index {
+            None
+        } else {
+            self.aggregation_labels.get(&aggregation_id).cloned()
+        }
+    }
+
+    fn encode(
+        &self,
+        table: &Table,
+        question: &str,
+        index: usize,
+    ) -> Result<EncodedTable, RustBertError> {
+        if table.num_columns() > self.max_columns {
+            return Err(RustBertError::ValueError(format!(
+                "The table has {} columns, the model supports at most {} columns",
+                table.num_columns(),
+                self.max_columns
+            )));
+        }
+
+        let mut input_ids = vec![self.cls_id];
+        input_ids.extend(
+            self.tokenizer
+                .convert_tokens_to_ids(&self.tokenizer.tokenize(question)),
+        );
+        input_ids.push(self.sep_id);
+        let mut token_type_ids = vec![[0i64; NUM_TAPAS_TOKEN_TYPES]; input_ids.len()];
+
+        for (column, cell) in table.header.iter().enumerate() {
+            let mut token_types = [0i64; NUM_TAPAS_TOKEN_TYPES];
+            token_types[SEGMENT] = 1;
+            token_types[COLUMN] = column as i64 + 1;
+            let cell_ids = self.cell_ids(cell);
+            token_type_ids.extend(std::iter::repeat(token_types).take(cell_ids.len()));
+            input_ids.extend(cell_ids);
+        }
+        if input_ids.len() > self.max_length {
+            return Err(InputError::ContextTooLong {
+                index,
+                length: input_ids.len(),
+                max_length: self.max_length,
+            }
+            .into());
+        }
+
+        let question_values = question_numbers(question);
+        let column_values = (0..table.num_columns())
+            .map(|column| {
+                table
+                    .rows
+                    .iter()
+                    .map(|row| parse_number(&row[column]))
+                    .collect::<Vec<Option<f64>>>()
+            })
+            .collect::<Vec<Vec<Option<f64>>>>();
+        let column_ranks = column_values
+            .iter()
+            .map(|values| column_ranks(values))
+            .collect::<Vec<Vec<(i64, i64)>>>();
+
+        for (row_index, row) in table.rows.iter().take(self.max_rows).enumerate() {
+            let mut row_ids = Vec::new();
+            let mut row_token_type_ids = Vec::new();
+            for (column, cell) in row.iter().enumerate() {
+                let (rank, inverse_rank) = column_ranks[column][row_index];
+                let mut token_types = [0i64; NUM_TAPAS_TOKEN_TYPES];
+                token_types[SEGMENT] = 1;
+                token_types[COLUMN] = column as i64 + 1;
+                token_types[ROW] = row_index as i64 + 1;
+                token_types[COLUMN_RANK] = rank.min(self.max_rank);
+                token_types[INVERSE_COLUMN_RANK] = inverse_rank.min(self.max_rank);
+                token_types[NUMERIC_RELATION] =
+                    numeric_relation(&question_values, column_values[column][row_index]);
+                let cell_ids = self.cell_ids(cell);
+                row_token_type_ids.extend(std::iter::repeat(token_types).take(cell_ids.len()));
+                row_ids.extend(cell_ids);
+            }
+            if input_ids.len() + row_ids.len() > self.max_length {
+                break;
+            }
+            input_ids.extend(row_ids);
+            token_type_ids.extend(row_token_type_ids);
+        }
+
+        Ok(EncodedTable {
+            input_ids,
+            token_type_ids,
+        })
+    }
+
+    fn cell_ids(&self, cell: &str) -> Vec<i64> {
+        let tokens = self.tokenizer.tokenize(cell);
+        if tokens.is_empty() {
+            vec![self.empty_id]
+        } else {
+            self.tokenizer.convert_tokens_to_ids(&tokens)
+        }
+    }
+}
+
+/// Parses the numeric value of a cell or question word, ignoring thousands separators, currency symbols and
+/// percentage signs.
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text
+        .trim()
+        .trim_start_matches(|c: char| matches!(c, '$' | '€' | '£'))
+        .trim_end_matches('%')
+        .replace(',', "");
+    if text.is_empty() {
+        return None;
+    }
+    text.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+/// Numbers mentioned in a question
+fn question_numbers(question: &str) -> Vec<f64> {
+    question
+        .split_whitespace()
+        .filter_map(|word| {
+            parse_number(word.trim_matches(|c: char| {
+                !(c.is_alphanumeric() || matches!(c, '.' | '-' | '$' | '€' | '£' | '%'))
+            }))
+        })
+        .collect()
+}
+
+/// Dense ranks (starting at 1) and inverse ranks of the values of a column. The ranks are only set for numeric
+/// columns (where all cells are numbers), and are 0 for the other columns.
+fn column_ranks(values: &[Option<f64>]) -> Vec<(i64, i64)> {
+    let mut sorted_values = values.iter().flatten().copied().collect::<Vec<f64>>();
+    if sorted_values.len() < values.len() {
+        return vec![(0, 0); values.len()];
+    }
+    sorted_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted_values.dedup();
+    let max_rank = sorted_values.len() as i64;
+    values
+        .iter()
+        .flatten()
+        .map(|value| {
+            let rank = sorted_values
+                .iter()
+                .position(|sorted_value| sorted_value == value)
+                .unwrap() as i64
+                + 1;
+            (rank, max_rank - rank + 1)
+        })
+        .collect()
+}
+
+/// Set of relations between the numbers of the question and the value of a cell (bit flags for equal, lower and
+/// greater question values)
+fn numeric_relation(question_values: &[f64], cell_value: Option<f64>) -> i64 {
+    let cell_value = match cell_value {
+        Some(cell_value) => cell_value,
+        None => return 0,
+    };
+    question_values.iter().fold(0, |relations, question_value| {
+        relations
+            | if *question_value == cell_value {
+                RELATION_EQUAL
+            } else if *question_value < cell_value {
+                RELATION_LOWER
+            } else {
+                RELATION_GREATER
+            }
+    })
+}
+
+/// Average selection probability of the tokens of each cell of the table (excluding the header), sorted by row and
+/// column.
+fn cell_probabilities(
+    token_probabilities: &[f64],
+    token_type_ids: &[[i64; NUM_TAPAS_TOKEN_TYPES]],
+) -> Vec<((usize, usize), f64)> {
+    let mut cells: BTreeMap<(usize, usize), (f64, usize)> = BTreeMap::new();
+    for (probability, token_types) in token_probabilities.iter().zip(token_type_ids) {
+        if token_types[SEGMENT] == 1 && token_types[ROW] > 0 && token_types[COLUMN] > 0 {
+            let cell = cells
+                .entry((
+                    token_types[ROW] as usize - 1,
+                    token_types[COLUMN] as usize - 1,
+                ))
+                .or_insert((0.0, 0));
+            cell.0 += probability;
+            cell.1 += 1;
+        }
+    }
+    cells
+        .into_iter()
+        .map(|(coordinates, (sum, count))| (coordinates, sum / count as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn numeric_features() {
+        assert_eq!(parse_number(" 2,102,650 "), Some(2102650.0));
+        assert_eq!(parse_number("$3.5"), Some(3.5));
+        assert_eq!(parse_number("12%"), Some(12.0));
+        assert_eq!(parse_number("Paris"), None);
+        assert_eq!(parse_number(""), None);
+        assert_eq!(
+            question_numbers("Which actors are older than 50 and have more than 60 movies?"),
+            vec![50.0, 60.0]
+        );
+
+        let values = [Some(59.0), Some(48.0), None, Some(59.0)];
+        assert_eq!(column_ranks(&values), vec![(0, 0), (0, 0), (0, 0), (0, 0)]);
+        let values = [Some(59.0), Some(48.0), Some(61.0), Some(59.0)];
+        assert_eq!(column_ranks(&values), vec![(2, 2), (1, 3), (3, 1), (2, 2)]);
+
+        assert_eq!(numeric_relation(&[50.0], Some(59.0)), RELATION_LOWER);
+        assert_eq!(
+            numeric_relation(&[50.0, 59.0], Some(59.0)),
+            RELATION_LOWER | RELATION_EQUAL
+        );
+        assert_eq!(numeric_relation(&[60.0], Some(48.0)), RELATION_GREATER);
+        assert_eq!(numeric_relation(&[60.0], None), 0);
+    }
+
+    #[test]
+    fn cell_selection() {
+        let token_type = |segment: i64, column: i64, row: i64| {
+            let mut token_types = [0; NUM_TAPAS_TOKEN_TYPES];
+            token_types[SEGMENT] = segment;
+            token_types[COLUMN] = column;
+            token_types[ROW] = row;
+            token_types
+        };
+        // Question, header cell, then a 2 x 1 table with a cell of 2 tokens
+        let token_type_ids = [
+            token_type(0, 0, 0),
+            token_type(0, 0, 0),
+            token_type(1, 1, 0),
+            token_type(1, 1, 1),
+            token_type(1, 1, 1),
+            token_type(1, 1, 2),
+        ];
+        let token_probabilities = [0.9, 0.9, 0.9, 0.8, 0.4, 0.1];
+        let cells = cell_probabilities(&token_probabilities, &token_type_ids);
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[0].0, (0, 0));
+        assert!((cells[0].1 - 0.6).abs() < 1e-9);
+        assert_eq!(cells[1].0, (1, 0));
+        assert!((cells[1].1 - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn answer_aggregation() {
+        let answer = TableAnswer::new(
+            vec![(0, 2), (1, 2)],
+            vec!["87".to_string(), "53".to_string()],
+            Some("SUM".to_string()),
+        );
+        assert_eq!(answer.answer, "SUM > 87, 53");
+        assert_eq!(answer.aggregated_value(), Some(140.0));
+
+        let answer = TableAnswer {
+            aggregator: Some("AVERAGE".to_string()),
+            ..answer
+        };
+        assert_eq!(answer.aggregated_value(), Some(70.0));
+
+        let answer = TableAnswer::new(
+            vec![(0, 0), (2, 0)],
+            vec!["Brad Pitt".to_string(), "George Clooney".to_string()],
+            Some("COUNT".to_string()),
+        );
+        assert_eq!(answer.aggregated_value(), Some(2.0));
+        let answer = TableAnswer {
+            aggregator: Some("SUM".to_string()),
+            ..answer
+        };
+        assert_eq!(answer.aggregated_value(), None);
+
+        let answer = TableAnswer::new(vec![(2, 2)], vec!["69".to_string()], None);
+        assert_eq!(answer.answer, "69");
+        assert_eq!(answer.aggregated_value(), None);
+    }
+
+    #[test]
+    fn table_validation() {
+        assert!(Table::new(&["Actor", "Age"], &[vec!["Brad Pitt", "59"]]).is_ok());
+        assert!(Table::new(&["Actor", "Age"], &[vec!["Brad Pitt"]]).is_err());
+        let header: [&str; 0] = [];
+        assert!(Table::new(&header, &[]).is_err());
+    }
+}
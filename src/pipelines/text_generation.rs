@@ -19,6 +19,13 @@
 //! - OpenAI GPT
 //! - OpenAI GPT2
 //! - GPT-Neo
+//! - GPT-J
+//! - StarCoder2 (including fill-in-the-middle code completion, see `TextGenerationModel::fill_in_the_middle`)
+//! - LLaMA and Mistral (tokenizer loaded from a `tokenizer.json` file, see `TextGenerationModel::new_with_tokenizer`)
+//! - Falcon (tokenizer loaded from a `tokenizer.json` file, see `TextGenerationModel::new_with_tokenizer`)
+//! - BLOOM and BLOOMZ (tokenizer loaded from a `tokenizer.json` file, see `TextGenerationModel::new_with_tokenizer`)
+//! - OPT
+//! - Phi
 //! - XLNet
 //! - Reformer
 //!
@@ -31,23 +38,67 @@
 //!
 //! Customized text generation models models can be loaded by overwriting the resources in the configuration.
 //! The dependencies will be downloaded to the user's home directory, e.g. under ~/.cache/.rustbert/gpt2
-use tch::Device;
+use std::cell::RefCell;
+use std::path::Path;
+use tch::{Device, Kind};
 
-use crate::common::error::RustBertError;
+#[cfg(feature = "bloom")]
+use crate::bloom::BloomGenerator;
+use crate::common::device_map::DeviceMap;
+use crate::common::error::{InputError, RustBertError};
+use crate::common::placement::{AutoPlacementConfig, Placement};
+use crate::common::precision::{is_half_precision, with_attention_fp32_accumulation};
+use crate::common::quantization::Int4QuantizationConfig;
+use crate::common::snapshot;
+use crate::common::tensor_parallel::TensorParallelConfig;
+#[cfg(feature = "falcon")]
+use crate::falcon::FalconGenerator;
+#[cfg(feature = "gpt2")]
 use crate::gpt2::GPT2Generator;
+#[cfg(feature = "gpt-j")]
 use crate::gpt_j::GptJGenerator;
+#[cfg(feature = "gpt-neo")]
 use crate::gpt_neo::GptNeoGenerator;
+#[cfg(feature = "llama")]
+use crate::llama::LlamaGenerator;
+#[cfg(feature = "openai-gpt")]
 use crate::openai_gpt::OpenAIGenerator;
+#[cfg(feature = "opt")]
+use crate::opt::OptGenerator;
+#[cfg(feature = "phi")]
+use crate::phi::PhiGenerator;
 use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
-use crate::pipelines::generation_utils::{GenerateConfig, GenerateOptions, LanguageGenerator};
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{
+    GenerateConfig, GenerateOptions, GeneratedTextOutput, LanguageGenerator, NoRepeatNgramScope,
+    PhrasalConstraint, TokenTrie,
+};
+use crate::pipelines::long_form::{parse_outline, LongFormConfig, LongFormOutput, LongFormSection};
+use crate::pipelines::prompt_compression::{
+    group_words, join_words, select_words, windowed_log_probabilities, CompressedPrompt,
+    PromptCompressionConfig,
+};
+use crate::pipelines::self_consistency::{
+    aggregate_answers, AnswerExtractor, SelfConsistencyConfig, SelfConsistencyOutput,
+};
+use crate::pipelines::sequence_classification::Label;
+use crate::pipelines::structured_output::{
+    generate_with_retries, OutputParser, StructuredOutput, StructuredOutputConfig,
+};
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerGenerator;
-use crate::resources::ResourceProvider;
+use crate::resources::{LocalResource, ResourceProvider};
+#[cfg(feature = "starcoder2")]
+use crate::starcoder2::{build_fill_in_the_middle_prompt, StarCoder2Generator, FIM_MIDDLE};
+#[cfg(feature = "t5")]
 use crate::t5::T5Generator;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetGenerator;
 
+use crate::common::settings::default_device;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::ONNXCausalGenerator;
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "gpt2"))]
 use crate::{
     gpt2::{Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources},
     resources::RemoteResource,
@@ -89,14 +140,34 @@ pub struct TextGenerationConfig {
     pub length_penalty: f64,
     /// Number of allowed repetitions of n-grams. Values higher than 0 turn on this feature and will prevent repeats of n-grams with a length equal or greater to this value (default: 0)
     pub no_repeat_ngram_size: i64,
+    /// Tokens considered for the n-gram repetition blocking: the full sequence or the generated continuation only, allowing repeats of n-grams of the prompt (default: FullSequence)
+    pub no_repeat_ngram_scope: NoRepeatNgramScope,
     /// Number of sequences to return for each prompt text (default: 1)
     pub num_return_sequences: i64,
     /// Number of beam groups for diverse beam generation. If provided and higher than 1, will split the beams into beam subgroups leading to more diverse generation.
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Degeneration penalty for contrastive search. If provided with greedy decoding (`do_sample` false and `num_beams` 1) and `top_k` higher than 1, selects the next token among the `top_k` most likely candidates, penalizing candidates whose hidden state is similar to the context (default: None)
+    pub penalty_alpha: Option<f64>,
+    /// Typical_p value for locally typical sampling. Keep the tokens whose information content is the closest to the entropy of the distribution until their cumulative probability reaches typical_p (default: None)
+    pub typical_p: Option<f64>,
+    /// Eta cutoff for eta sampling. Remove the tokens with a probability lower than `min(eta_cutoff, sqrt(eta_cutoff) * exp(-entropy))` (default: None)
+    pub eta_cutoff: Option<f64>,
+    /// Epsilon cutoff for epsilon sampling. Remove the tokens with a probability lower than epsilon_cutoff (default: None)
+    pub epsilon_cutoff: Option<f64>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
+    /// Devices the transformer blocks are partitioned across for models that do not fit on a single device (see the `device_map` module). The devices and precisions of the embeddings and of each group of blocks are set by the map. The model is loaded on `device` before being dispatched, and the device of the embeddings becomes the device of the model (default: None)
+    pub device_map: Option<DeviceMap>,
+    /// Sequences of text stopping the generation of a sequence once generated (e.g. `"\n\n"`). The stop sequence is kept in the output (default: empty)
+    pub stop_sequences: Vec<String>,
+    /// List of bad word ids (may be a sequence of word ids) that will be banned during the generation (default: empty)
+    pub bad_word_ids: Vec<Vec<i64>>,
+    /// Biases added to the score of the last token of token sequences when the preceding tokens of the sequence were generated (single token sequences are always biased). Negative values discourage the sequence, `f64::NEG_INFINITY` bans it (default: empty)
+    pub sequence_bias: Vec<(Vec<i64>, f64)>,
 }
 
 impl TextGenerationConfig {
@@ -137,15 +208,25 @@ impl TextGenerationConfig {
             repetition_penalty: 1.0,
             length_penalty: 1.0,
             no_repeat_ngram_size: 0,
+            no_repeat_ngram_scope: NoRepeatNgramScope::FullSequence,
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
-            device: Device::cuda_if_available(),
+            penalty_alpha: None,
+            typical_p: None,
+            eta_cutoff: None,
+            epsilon_cutoff: None,
+            device: default_device(),
+            kind: None,
+            device_map: None,
+            stop_sequences: Vec::new(),
+            bad_word_ids: Vec::new(),
+            sequence_bias: Vec::new(),
         }
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "gpt2"))]
 impl Default for TextGenerationConfig {
     fn default() -> TextGenerationConfig {
         TextGenerationConfig::new(
@@ -181,10 +262,22 @@ impl From<TextGenerationConfig> for GenerateConfig {
             repetition_penalty: config.repetition_penalty,
             length_penalty: config.length_penalty,
             no_repeat_ngram_size: config.no_repeat_ngram_size,
+            no_repeat_ngram_scope: config.no_repeat_ngram_scope,
+            encoder_no_repeat_ngram_size: 0,
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            penalty_alpha: config.penalty_alpha,
+            typical_p: config.typical_p,
+            eta_cutoff: config.eta_cutoff,
+            epsilon_cutoff: config.epsilon_cutoff,
             device: config.device,
+            encoder_cache: None,
+            stop_sequences: config.stop_sequences,
+            stopping_criteria: Vec::new(),
+            logits_processors: Vec::new(),
+            bad_word_ids: config.bad_word_ids,
+            sequence_bias: config.sequence_bias,
         }
     }
 }
@@ -192,18 +285,43 @@ impl From<TextGenerationConfig> for GenerateConfig {
 /// # Abstraction that holds one particular text generation model, for any of the supported models
 pub enum TextGenerationOption {
     /// Text Generator based on GPT2 model
+    #[cfg(feature = "gpt2")]
     GPT2(GPT2Generator),
     /// Text Generator based on GPT model
+    #[cfg(feature = "openai-gpt")]
     GPT(OpenAIGenerator),
     /// Text Generator based on GPT-Neo model
+    #[cfg(feature = "gpt-neo")]
     GPTNeo(GptNeoGenerator),
     /// Text Generator based on GPT-J model
+    #[cfg(feature = "gpt-j")]
     GPTJ(GptJGenerator),
+    /// Text Generator based on StarCoder2 model
+    #[cfg(feature = "starcoder2")]
+    StarCoder2(StarCoder2Generator),
+    /// Text Generator based on LLaMA model (including Mistral)
+    #[cfg(feature = "llama")]
+    Llama(LlamaGenerator),
+    /// Text Generator based on BLOOM model (including BLOOMZ)
+    #[cfg(feature = "bloom")]
+    Bloom(BloomGenerator),
+    /// Text Generator based on OPT model
+    #[cfg(feature = "opt")]
+    OPT(OptGenerator),
+    /// Text Generator based on Phi model
+    #[cfg(feature = "phi")]
+    Phi(PhiGenerator),
+    /// Text Generator based on Falcon model
+    #[cfg(feature = "falcon")]
+    Falcon(FalconGenerator),
     /// Text Generator based on XLNet model
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetGenerator),
     /// Text Generator based on Reformer model
+    #[cfg(feature = "reformer")]
     Reformer(ReformerGenerator),
     /// Text Generator based on T5 model
+    #[cfg(feature = "t5")]
     T5(T5Generator),
     /// ONNX model for text generation
     #[cfg(feature = "onnx")]
@@ -217,24 +335,51 @@ impl TextGenerationOption {
             (_, &ModelResource::ONNX(_)) => Ok(TextGenerationOption::ONNX(
                 ONNXCausalGenerator::new(config.into(), None, None)?,
             )),
+            #[cfg(feature = "gpt2")]
             (ModelType::GPT2, _) => Ok(TextGenerationOption::GPT2(GPT2Generator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "openai-gpt")]
             (ModelType::OpenAiGpt, _) => Ok(TextGenerationOption::GPT(OpenAIGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "xlnet")]
             (ModelType::XLNet, _) => Ok(TextGenerationOption::XLNet(XLNetGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "reformer")]
             (ModelType::Reformer, _) => Ok(TextGenerationOption::Reformer(ReformerGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "gpt-neo")]
             (ModelType::GPTNeo, _) => Ok(TextGenerationOption::GPTNeo(GptNeoGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "gpt-j")]
             (ModelType::GPTJ, _) => Ok(TextGenerationOption::GPTJ(GptJGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "starcoder2")]
+            (ModelType::StarCoder2, _) => Ok(TextGenerationOption::StarCoder2(
+                StarCoder2Generator::new(config.into())?,
+            )),
+            #[cfg(feature = "llama")]
+            (ModelType::Llama, _) => Ok(TextGenerationOption::Llama(LlamaGenerator::new(
+                config.into(),
+            )?)),
+            #[cfg(feature = "bloom")]
+            (ModelType::Bloom, _) => Ok(TextGenerationOption::Bloom(BloomGenerator::new(
+                config.into(),
+            )?)),
+            #[cfg(feature = "opt")]
+            (ModelType::OPT, _) => Ok(TextGenerationOption::OPT(OptGenerator::new(config.into())?)),
+            #[cfg(feature = "phi")]
+            (ModelType::Phi, _) => Ok(TextGenerationOption::Phi(PhiGenerator::new(config.into())?)),
+            #[cfg(feature = "falcon")]
+            (ModelType::Falcon, _) => Ok(TextGenerationOption::Falcon(FalconGenerator::new(
+                config.into(),
+            )?)),
+            #[cfg(feature = "t5")]
             (ModelType::T5, _) => Ok(TextGenerationOption::T5(T5Generator::new(config.into())?)),
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Text generation not implemented for {:?}!",
@@ -252,24 +397,57 @@ impl TextGenerationOption {
             (_, &ModelResource::ONNX(_)) => Ok(TextGenerationOption::ONNX(
                 ONNXCausalGenerator::new_with_tokenizer(config.into(), tokenizer, None, None)?,
             )),
+            #[cfg(feature = "gpt2")]
             (ModelType::GPT2, _) => Ok(TextGenerationOption::GPT2(
                 GPT2Generator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "openai-gpt")]
             (ModelType::OpenAiGpt, _) => Ok(TextGenerationOption::GPT(
                 OpenAIGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "xlnet")]
             (ModelType::XLNet, _) => Ok(TextGenerationOption::XLNet(
                 XLNetGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "reformer")]
             (ModelType::Reformer, _) => Ok(TextGenerationOption::Reformer(
                 ReformerGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "gpt-neo")]
             (ModelType::GPTNeo, _) => Ok(TextGenerationOption::GPTNeo(
                 GptNeoGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "gpt-j")]
             (ModelType::GPTJ, _) => Ok(TextGenerationOption::GPTJ(
                 GptJGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "starcoder2")]
+            (ModelType::StarCoder2, _) => Ok(TextGenerationOption::StarCoder2(
+                StarCoder2Generator::new_with_tokenizer(config.into(), tokenizer)?,
+            )),
+            #[cfg(feature = "llama")]
+            (ModelType::Llama, _) => Ok(TextGenerationOption::Llama(
+                LlamaGenerator::new_with_tokenizer(config.into(), tokenizer)?,
+            )),
+            #[cfg(feature = "bloom")]
+            (ModelType::Bloom, _) => Ok(TextGenerationOption::Bloom(
+                BloomGenerator::new_with_tokenizer(config.into(), tokenizer)?,
+            )),
+            #[cfg(feature = "opt")]
+            (ModelType::OPT, _) => Ok(TextGenerationOption::OPT(OptGenerator::new_with_tokenizer(
+                config.into(),
+                tokenizer,
+            )?)),
+            #[cfg(feature = "phi")]
+            (ModelType::Phi, _) => Ok(TextGenerationOption::Phi(PhiGenerator::new_with_tokenizer(
+                config.into(),
+                tokenizer,
+            )?)),
+            #[cfg(feature = "falcon")]
+            (ModelType::Falcon, _) => Ok(TextGenerationOption::Falcon(
+                FalconGenerator::new_with_tokenizer(config.into(), tokenizer)?,
+            )),
+            #[cfg(feature = "t5")]
             (ModelType::T5, _) => Ok(TextGenerationOption::T5(T5Generator::new_with_tokenizer(
                 config.into(),
                 tokenizer,
@@ -284,12 +462,31 @@ impl TextGenerationOption {
     /// Returns the `ModelType` for this TextGenerationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "openai-gpt")]
             Self::GPT(_) => ModelType::OpenAiGpt,
+            #[cfg(feature = "gpt2")]
             Self::GPT2(_) => ModelType::GPT2,
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(_) => ModelType::GPTNeo,
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(_) => ModelType::GPTJ,
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(_) => ModelType::StarCoder2,
+            #[cfg(feature = "llama")]
+            Self::Llama(_) => ModelType::Llama,
+            #[cfg(feature = "bloom")]
+            Self::Bloom(_) => ModelType::Bloom,
+            #[cfg(feature = "opt")]
+            Self::OPT(_) => ModelType::OPT,
+            #[cfg(feature = "phi")]
+            Self::Phi(_) => ModelType::Phi,
+            #[cfg(feature = "falcon")]
+            Self::Falcon(_) => ModelType::Falcon,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "reformer")]
             Self::Reformer(_) => ModelType::Reformer,
+            #[cfg(feature = "t5")]
             Self::T5(_) => ModelType::T5,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
@@ -297,31 +494,103 @@ impl TextGenerationOption {
     }
     /// Interface method to access tokenizer
     pub fn get_tokenizer(&self) -> &TokenizerOption {
-        match self {
-            Self::GPT(model_ref) => model_ref.get_tokenizer(),
-            Self::GPT2(model_ref) => model_ref.get_tokenizer(),
-            Self::GPTNeo(model_ref) => model_ref.get_tokenizer(),
-            Self::GPTJ(model_ref) => model_ref.get_tokenizer(),
-            Self::XLNet(model_ref) => model_ref.get_tokenizer(),
-            Self::Reformer(model_ref) => model_ref.get_tokenizer(),
-            Self::T5(model_ref) => model_ref.get_tokenizer(),
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref model_ref) => model_ref.get_tokenizer(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.get_tokenizer(),
             #[cfg(feature = "onnx")]
-            Self::ONNX(model_ref) => model_ref.get_tokenizer(),
+            Self::ONNX(ref model_ref) => model_ref.get_tokenizer(),
         }
     }
 
     /// Interface method to access tokenizer
     pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
-        match self {
-            Self::GPT(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::GPT2(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::GPTNeo(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::GPTJ(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::XLNet(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::Reformer(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::T5(model_ref) => model_ref.get_tokenizer_mut(),
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+        }
+    }
+
+    /// Returns the maximum number of positions (context window) of the model, if limited
+    pub fn get_max_positions_embeddings(&self) -> Option<i64> {
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.get_max_positions_embeddings(),
             #[cfg(feature = "onnx")]
-            Self::ONNX(model_ref) => model_ref.get_tokenizer_mut(),
+            Self::ONNX(ref model_ref) => model_ref.get_max_positions_embeddings(),
         }
     }
 
@@ -335,42 +604,101 @@ impl TextGenerationOption {
     where
         S: AsRef<str> + Send + Sync,
     {
-        let generate_options = Some(GenerateOptions {
-            min_length,
-            max_length,
-            ..Default::default()
-        });
+        self.generate_indices_with_options(
+            prompt_texts,
+            GenerateOptions {
+                min_length,
+                max_length,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Interface method to generate() of the particular models, with `GenerateOptions` overriding the
+    /// generation configuration of the model.
+    pub fn generate_indices_with_options<S>(
+        &self,
+        prompt_texts: Option<&[S]>,
+        generate_options: GenerateOptions,
+    ) -> Vec<Vec<i64>>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let generate_options = Some(generate_options);
         match *self {
+            #[cfg(feature = "openai-gpt")]
             Self::GPT(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "gpt2")]
             Self::GPT2(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "gpt-j")]
             Self::GPTJ(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "reformer")]
             Self::Reformer(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "t5")]
             Self::T5(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
@@ -385,15 +713,188 @@ impl TextGenerationOption {
         }
     }
 
+    /// Interface method to score_continuations() of the particular models.
+    pub fn score_continuations<S>(
+        &self,
+        prompt: &str,
+        continuations: &[S],
+    ) -> Result<Vec<f64>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            // XLNet relies on permutation masks prepared during generation to prevent attending to the scored tokens
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(_) => Err(RustBertError::InvalidConfigurationError(
+                "Scoring continuations is not supported for XLNet models".to_string(),
+            )),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model_ref) => model_ref.score_continuations(prompt, continuations),
+        }
+    }
+
+    /// Interface method to score_token_ids() of the particular models.
+    pub fn score_token_ids(&self, token_ids: &[i64]) -> Result<Vec<Option<f64>>, RustBertError> {
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref model_ref) => model_ref.score_token_ids(token_ids),
+            // XLNet relies on permutation masks prepared during generation to prevent attending to the scored tokens
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(_) => Err(RustBertError::InvalidConfigurationError(
+                "Scoring tokens is not supported for XLNet models".to_string(),
+            )),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.score_token_ids(token_ids),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model_ref) => model_ref.score_token_ids(token_ids),
+        }
+    }
+
+    /// Interface method to generate_with_shared_context() of the particular models.
+    pub fn generate_with_shared_context<S>(
+        &self,
+        context: &str,
+        prompt_texts: &[S],
+        generate_options: GenerateOptions,
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let generate_options = Some(generate_options);
+        let output: Result<Vec<GeneratedTextOutput>, RustBertError> = match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "llama")]
+            Self::Llama(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "opt")]
+            Self::OPT(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "phi")]
+            Self::Phi(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            // XLNet relies on permutation masks prepared during generation to predict the next token
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(_) => Err(RustBertError::InvalidConfigurationError(
+                "Generation with a shared context is not supported for XLNet models".to_string(),
+            )),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model_ref) => {
+                model_ref.generate_with_shared_context(context, prompt_texts, generate_options)
+            }
+        };
+        Ok(output?.into_iter().map(|output| output.text).collect())
+    }
+
     pub fn half(&mut self) -> Result<(), RustBertError> {
-        match self {
-            Self::GPT(model_ref) => model_ref.half(),
-            Self::GPT2(model_ref) => model_ref.half(),
-            Self::GPTNeo(model_ref) => model_ref.half(),
-            Self::GPTJ(model_ref) => model_ref.half(),
-            Self::XLNet(model_ref) => model_ref.half(),
-            Self::Reformer(model_ref) => model_ref.half(),
-            Self::T5(model_ref) => model_ref.half(),
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.half(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.half(),
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => Err(RustBertError::OrtError(
                 "Type casting not supported for ONNX models.".to_string(),
@@ -402,14 +903,68 @@ impl TextGenerationOption {
     }
 
     pub fn float(&mut self) -> Result<(), RustBertError> {
-        match self {
-            Self::GPT(model_ref) => model_ref.float(),
-            Self::GPT2(model_ref) => model_ref.float(),
-            Self::GPTNeo(model_ref) => model_ref.float(),
-            Self::GPTJ(model_ref) => model_ref.float(),
-            Self::XLNet(model_ref) => model_ref.float(),
-            Self::Reformer(model_ref) => model_ref.float(),
-            Self::T5(model_ref) => model_ref.float(),
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.float(),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(_) => Err(RustBertError::OrtError(
+                "Type casting not supported for ONNX models.".to_string(),
+            )),
+        }
+    }
+
+    pub fn set_kind(&mut self, kind: Kind) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.set_kind(kind),
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => Err(RustBertError::OrtError(
                 "Type casting not supported for ONNX models.".to_string(),
@@ -417,21 +972,243 @@ impl TextGenerationOption {
         }
     }
 
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.quantize_int4(config),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.quantize_int4(config),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Int4 quantization not supported for {:?}",
+                self.model_type()
+            ))),
+        }
+    }
+
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.tensor_parallelize(config),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.tensor_parallelize(config),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.tensor_parallelize(config),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.tensor_parallelize(config),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Tensor parallelism not supported for {:?}",
+                self.model_type()
+            ))),
+        }
+    }
+
+    pub fn set_device_map(&mut self, device_map: &DeviceMap) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.set_device_map(device_map),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.set_device_map(device_map),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Device maps not supported for {:?}",
+                self.model_type()
+            ))),
+        }
+    }
+
     pub fn set_device(&mut self, device: Device) -> Result<(), RustBertError> {
-        match self {
-            Self::GPT(model_ref) => model_ref.set_device(device),
-            Self::GPT2(model_ref) => model_ref.set_device(device),
-            Self::GPTNeo(model_ref) => model_ref.set_device(device),
-            Self::GPTJ(model_ref) => model_ref.set_device(device),
-            Self::XLNet(model_ref) => model_ref.set_device(device),
-            Self::Reformer(model_ref) => model_ref.set_device(device),
-            Self::T5(model_ref) => model_ref.set_device(device),
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.set_device(device),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.set_device(device),
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => Err(RustBertError::OrtError(
                 "Device assignment not supported for ONNX models.".to_string(),
             )),
         }
     }
+
+    pub fn save_snapshot(&mut self, path: &Path) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.save_snapshot(path),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(_) => Err(RustBertError::OrtError(
+                "Snapshots not supported for ONNX models.".to_string(),
+            )),
+        }
+    }
+
+    pub fn load_snapshot(&mut self, path: &Path) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "gpt-j")]
+            Self::GPTJ(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "starcoder2")]
+            Self::StarCoder2(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "llama")]
+            Self::Llama(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "bloom")]
+            Self::Bloom(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "opt")]
+            Self::OPT(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "phi")]
+            Self::Phi(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "falcon")]
+            Self::Falcon(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.load_snapshot(path),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(_) => Err(RustBertError::OrtError(
+                "Snapshots not supported for ONNX models.".to_string(),
+            )),
+        }
+    }
+}
+
+/// Decodes the tokens of a sequence as they are generated, passing the new text to a callback.
+/// New tokens are decoded along with the tokens preceding them (from `prefix_offset`) and only the text they add is
+/// emitted, so that text already emitted is never decoded again. Tokenization spaces are not cleaned up, as the
+/// cleanup could rewrite text already emitted. Tokens ending with a partial UTF-8 character are held back.
+struct TextStreamer<F: FnMut(&str)> {
+    callback: F,
+    token_ids: Vec<i64>,
+    prefix_offset: usize,
+    read_offset: usize,
+}
+
+impl<F: FnMut(&str)> TextStreamer<F> {
+    fn new(callback: F) -> Self {
+        TextStreamer {
+            callback,
+            token_ids: Vec::new(),
+            prefix_offset: 0,
+            read_offset: 0,
+        }
+    }
+
+    fn push(&mut self, tokenizer: &TokenizerOption, token_id: i64) {
+        self.token_ids.push(token_id);
+        self.emit(tokenizer, false);
+    }
+
+    fn finish(mut self, tokenizer: &TokenizerOption) {
+        self.emit(tokenizer, true);
+    }
+
+    fn emit(&mut self, tokenizer: &TokenizerOption, flush: bool) {
+        let prefix_text = tokenizer.decode(
+            &self.token_ids[self.prefix_offset..self.read_offset],
+            true,
+            false,
+        );
+        let text = tokenizer.decode(&self.token_ids[self.prefix_offset..], true, false);
+        if text.len() <= prefix_text.len() || (!flush && text.ends_with('\u{FFFD}')) {
+            return;
+        }
+        if let Some(new_text) = text.get(prefix_text.len()..) {
+            (self.callback)(new_text);
+            self.prefix_offset = self.read_offset;
+            self.read_offset = self.token_ids.len();
+        }
+    }
+}
+
+/// # Aggregation of the token log-probabilities of a verbalized answer
+/// Used by `TextGenerationModel::classify_answers` to score answers made of a different number of tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnswerScoring {
+    /// Log-likelihood of the answer (sum of the log-probabilities of its tokens)
+    Sum,
+    /// Log-likelihood of the answer divided by its number of tokens, not favouring shorter answers
+    Mean,
+}
+
+impl Default for AnswerScoring {
+    fn default() -> Self {
+        AnswerScoring::Sum
+    }
+}
+
+/// Normalizes answer scores (log-space) to probabilities summing to one
+fn answer_probabilities(scores: &[f64]) -> Vec<f64> {
+    let max_score = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let exp_scores = scores
+        .iter()
+        .map(|score| (score - max_score).exp())
+        .collect::<Vec<f64>>();
+    let total = exp_scores.iter().sum::<f64>();
+    exp_scores.iter().map(|score| score / total).collect()
 }
 
 /// # TextGenerationModel to generate texts from a prompt
@@ -441,6 +1218,7 @@ pub struct TextGenerationModel {
     prefix_length: Option<i64>,
     min_length: i64,
     max_length: Option<i64>,
+    attention_fp32_accumulation: bool,
 }
 
 impl TextGenerationModel {
@@ -466,16 +1244,27 @@ impl TextGenerationModel {
     ) -> Result<TextGenerationModel, RustBertError> {
         let (prefix, min_length, max_length) =
             TextGenerationModel::get_prefix_min_max_length(&generation_config);
-        let model = TextGenerationOption::new(generation_config)?;
+        let kind = generation_config.kind;
+        let device_map = generation_config.device_map.clone();
+        let mut model = TextGenerationOption::new(generation_config)?;
+        if let Some(kind) = kind {
+            model.set_kind(kind)?;
+        }
+        if let Some(device_map) = &device_map {
+            model.set_device_map(device_map)?;
+        }
         let prefix_length = prefix
             .as_ref()
             .map(|prefix| model.get_tokenizer().tokenize(prefix).len() as i64);
+        let attention_fp32_accumulation = kind.map_or(false, is_half_precision)
+            || device_map.map_or(false, |device_map| device_map.has_half_precision());
         Ok(TextGenerationModel {
             model,
             prefix,
             prefix_length,
             min_length,
             max_length,
+            attention_fp32_accumulation,
         })
     }
 
@@ -511,23 +1300,86 @@ impl TextGenerationModel {
     ) -> Result<TextGenerationModel, RustBertError> {
         let (prefix, min_length, max_length) =
             TextGenerationModel::get_prefix_min_max_length(&generation_config);
-        let model = TextGenerationOption::new_with_tokenizer(generation_config, tokenizer)?;
+        let kind = generation_config.kind;
+        let device_map = generation_config.device_map.clone();
+        let mut model = TextGenerationOption::new_with_tokenizer(generation_config, tokenizer)?;
+        if let Some(kind) = kind {
+            model.set_kind(kind)?;
+        }
+        if let Some(device_map) = &device_map {
+            model.set_device_map(device_map)?;
+        }
         let prefix_length = prefix
             .as_ref()
             .map(|prefix| model.get_tokenizer().tokenize(prefix).len() as i64);
+        let attention_fp32_accumulation = kind.map_or(false, is_half_precision)
+            || device_map.map_or(false, |device_map| device_map.has_half_precision());
         Ok(TextGenerationModel {
             model,
             prefix,
             prefix_length,
             min_length,
             max_length,
+            attention_fp32_accumulation,
         })
     }
 
+    /// Build a new `TextGenerationModel` placed on a device and in a precision selected automatically from the
+    /// size of the checkpoint and the memory available on the system (see `Placement::auto`). The `device` of the
+    /// configuration is ignored. To inspect the selected placement, call `Placement::auto` and
+    /// `TextGenerationModel::new_with_placement` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration) and generation options
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let generation_model = TextGenerationModel::new_auto(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_auto(
+        generation_config: TextGenerationConfig,
+    ) -> Result<TextGenerationModel, RustBertError> {
+        let placement = Placement::auto(
+            &generation_config.model_resource,
+            &AutoPlacementConfig::default(),
+        )?;
+        TextGenerationModel::new_with_placement(generation_config, &placement)
+    }
+
+    /// Build a new `TextGenerationModel` with a given placement (device, precision and loading strategy).
+    /// The `device` of the configuration is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration) and generation options
+    /// * `placement` - `Placement` of the model, for example obtained from `Placement::auto`
+    pub fn new_with_placement(
+        mut generation_config: TextGenerationConfig,
+        placement: &Placement,
+    ) -> Result<TextGenerationModel, RustBertError> {
+        generation_config.device = placement.load_device();
+        let mut model = TextGenerationModel::new(generation_config)?;
+        if placement.kind == Kind::Half {
+            model.half()?;
+        }
+        if placement.load_device() != placement.device {
+            model.set_device(placement.device)?;
+        }
+        Ok(model)
+    }
+
     fn get_prefix_min_max_length(
         generation_config: &TextGenerationConfig,
     ) -> (Option<String>, i64, Option<i64>) {
         let prefix = match generation_config.model_type {
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => Some(
                 "In 1991, the remains of Russian Tsar Nicholas II and his family \
 (except for Alexei and Maria) are discovered. \
@@ -558,17 +1410,113 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
     }
 
     pub fn half(&mut self) -> Result<(), RustBertError> {
-        self.model.half()
+        self.model.half()?;
+        self.attention_fp32_accumulation = true;
+        Ok(())
     }
 
     pub fn float(&mut self) -> Result<(), RustBertError> {
-        self.model.float()
+        self.model.float()?;
+        self.attention_fp32_accumulation = false;
+        Ok(())
+    }
+
+    /// Casts the weights of the model to a floating point precision (see `TextGenerationConfig::kind`)
+    pub fn set_kind(&mut self, kind: Kind) -> Result<(), RustBertError> {
+        self.model.set_kind(kind)?;
+        self.attention_fp32_accumulation = is_half_precision(kind);
+        Ok(())
+    }
+
+    /// Quantize the weights of the model to int4 in place, for the models supporting weight-only quantization
+    /// (GPT-Neo, GPT-J, StarCoder2, LLaMA, Falcon, BLOOM, OPT and Phi). The model should be moved to its target device and precision before quantization.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `Int4QuantizationConfig` defining the quantization group size
+    pub fn quantize_int4(&mut self, config: &Int4QuantizationConfig) -> Result<(), RustBertError> {
+        self.model.quantize_int4(config)
+    }
+
+    /// Split the attention and MLP layers of the model across several devices in place (tensor parallelism), for
+    /// GPT-Neo, GPT-J, StarCoder2 and LLaMA models. The model should be loaded on the first device of the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TensorParallelConfig` defining the devices to split the layers across
+    pub fn tensor_parallelize(
+        &mut self,
+        config: &TensorParallelConfig,
+    ) -> Result<(), RustBertError> {
+        self.model.tensor_parallelize(config)
+    }
+
+    /// Partitions the transformer blocks of the model across devices in place (see the `device_map` module).
+    /// Supported for GPT-Neo and GPT-J models.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_map` - `DeviceMap` defining the device of each transformer block
+    pub fn set_device_map(&mut self, device_map: &DeviceMap) -> Result<(), RustBertError> {
+        self.model.set_device_map(device_map)?;
+        self.attention_fp32_accumulation |= device_map.has_half_precision();
+        Ok(())
     }
 
     pub fn set_device(&mut self, device: Device) -> Result<(), RustBertError> {
         self.model.set_device(device)
     }
 
+    /// Saves the weights of the initialized model (e.g. after conversion to half precision) to a snapshot file
+    /// with a `.safetensors` extension, allowing a fast restart with `TextGenerationModel::from_snapshot`.
+    /// Models split with tensor parallelism or quantized to int4 cannot be snapshotted.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the snapshot file to write
+    pub fn save_snapshot<P: AsRef<Path>>(&mut self, path: P) -> Result<(), RustBertError> {
+        self.model.save_snapshot(path.as_ref())
+    }
+
+    /// Build a new `TextGenerationModel` restoring its weights and precision from a snapshot file
+    /// saved with `TextGenerationModel::save_snapshot`. The model resource of the configuration is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `GenerateConfig` object of the snapshotted model. The model resource is replaced by the snapshot.
+    /// * `path` - path of the snapshot file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let generation_model =
+    ///     TextGenerationModel::from_snapshot(Default::default(), "path/to/snapshot.safetensors")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_snapshot<P: AsRef<Path>>(
+        mut generation_config: TextGenerationConfig,
+        path: P,
+    ) -> Result<TextGenerationModel, RustBertError> {
+        let path = path.as_ref();
+        let kind = snapshot::snapshot_kind(path)?;
+        generation_config.model_resource =
+            ModelResource::Torch(Box::new(LocalResource::from(path.to_path_buf())));
+        let mut model = TextGenerationModel::new(generation_config)?;
+        match kind {
+            Kind::Half => model.half()?,
+            Kind::Float => {}
+            _ => {
+                model.model.load_snapshot(path)?;
+                model.attention_fp32_accumulation = is_half_precision(kind);
+            }
+        }
+        Ok(model)
+    }
+
     /// Generate texts from provided prompts
     ///
     /// # Arguments
@@ -599,27 +1547,24 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
     where
         S: AsRef<str> + Send + Sync,
     {
-        let (prefix, prefix_length) = match (prefix.into(), &self.prefix) {
-            (Some(query_prefix), _) => (
-                Some(query_prefix),
-                Some(self.model.get_tokenizer().tokenize(query_prefix).len() as i64),
-            ),
-            (None, Some(pipeline_prefix)) => (Some(pipeline_prefix.as_str()), self.prefix_length),
-            (None, None) => (None, None),
-        };
+        let (prefix, prefix_length) = self.resolve_prefix(prefix.into());
         let generated_indices = match (prefix, prefix_length) {
-            (None, _) => self.model.generate_indices(Some(texts), None, None),
+            (None, _) => with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model.generate_indices(Some(texts), None, None)
+            }),
             (Some(prefix), Some(prefix_length)) => {
                 let texts = texts
                     .as_ref()
                     .iter()
                     .map(|text| format!("{} {}", prefix, text.as_ref()))
                     .collect::<Vec<String>>();
-                self.model.generate_indices(
-                    Some(&texts),
-                    Some(self.min_length + prefix_length),
-                    self.max_length.map(|max_length| max_length + prefix_length),
-                )
+                with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                    self.model.generate_indices(
+                        Some(&texts),
+                        Some(self.min_length + prefix_length),
+                        self.max_length.map(|max_length| max_length + prefix_length),
+                    )
+                })
             }
             _ => panic!("Prefix length not defined but prefix provided!"),
         };
@@ -634,16 +1579,881 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
         }
         output
     }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
 
-    #[test]
-    #[ignore] // no need to run, compilation is enough to verify it is Send
-    fn test() {
-        let config = TextGenerationConfig::default();
-        let _: Box<dyn Send> = Box::new(TextGenerationModel::new(config));
+    /// Generate texts based on prompts, validating the inputs first. Returns an error instead of panicking when no
+    /// prompt is provided or when a prompt is longer than the context window of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - `&[&str]` Array of texts to use as prompts for generation.
+    /// * `prefix` - `impl Into<Option<&str>>`: Optional string to pass as a prefix for generation. Will be excluded from generated sequences.
+    ///
+    /// # Returns
+    /// * `Vec<String>` Vector of generated strings based on the prompts of length *n_samples x num_return_sequences*.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    ///
+    /// let output = model.try_generate(&["The dog", "The cat was"], None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_generate<'a, S>(
+        &self,
+        texts: &[S],
+        prefix: impl Into<Option<&'a str>>,
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let prefix = prefix.into();
+        if texts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        if let Some(max_positions) = self.model.get_max_positions_embeddings() {
+            let (_, prefix_length) = self.resolve_prefix(prefix);
+            let tokenizer = self.model.get_tokenizer();
+            for (index, text) in texts.iter().enumerate() {
+                let length =
+                    tokenizer.tokenize(text.as_ref()).len() + prefix_length.unwrap_or(0) as usize;
+                if length > max_positions as usize {
+                    return Err(InputError::ContextTooLong {
+                        index,
+                        length,
+                        max_length: max_positions as usize,
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(self.generate(texts, prefix))
+    }
+
+    /// Generate text based on a prompt, passing the generated text to a callback as it is produced
+    /// (e.g. to display the output of an interactive application). The text is generated with greedy decoding or
+    /// sampling (depending on the configuration of the model) as beam search does not produce final tokens until
+    /// the search completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - `&str` prompt to continue
+    /// * `prefix` - `impl Into<Option<&str>>`: Optional string to pass as a prefix for generation. Will be excluded from generated sequences.
+    /// * `callback` - `FnMut(&str)` called with each new piece of generated text. The pieces are decoded without
+    ///   cleaning up the tokenization spaces (e.g. before punctuation), which may differ from the returned text.
+    ///
+    /// # Returns
+    /// * `String` Generated text, as returned by `generate`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    /// use std::io::Write;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    ///
+    /// let output = model.generate_stream("The dog", None, |text| {
+    ///     print!("{text}");
+    ///     std::io::stdout().flush().unwrap();
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_stream<'a, F>(
+        &self,
+        text: &str,
+        prefix: impl Into<Option<&'a str>>,
+        callback: F,
+    ) -> String
+    where
+        F: FnMut(&str),
+    {
+        let (prefix, prefix_length) = self.resolve_prefix(prefix.into());
+        let prompt = match prefix {
+            Some(prefix) => format!("{} {}", prefix, text),
+            None => text.to_string(),
+        };
+        let tokenizer = self.model.get_tokenizer();
+        let streamer = RefCell::new(TextStreamer::new(callback));
+        let token_callback = |sequence_index: usize, token_id: i64| {
+            if sequence_index == 0 {
+                streamer.borrow_mut().push(tokenizer, token_id);
+            }
+        };
+        let generate_options = GenerateOptions {
+            min_length: prefix_length.map(|prefix_length| self.min_length + prefix_length),
+            max_length: prefix_length.and_then(|prefix_length| {
+                self.max_length.map(|max_length| max_length + prefix_length)
+            }),
+            num_beams: Some(1),
+            num_return_sequences: Some(1),
+            token_callback: Some(&token_callback),
+            ..Default::default()
+        };
+        let generated_indices =
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_indices_with_options(Some(&[prompt][..]), generate_options)
+            });
+        streamer.into_inner().finish(tokenizer);
+        generated_indices
+            .first()
+            .map(|generated_sequence| {
+                tokenizer.decode(
+                    &generated_sequence[prefix_length.unwrap_or(0) as usize..],
+                    true,
+                    true,
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Generate texts based on prompts, guaranteeing that each generated sequence contains all the phrases provided
+    /// (lexically constrained decoding), e.g. product names in generated ad copy. The phrases are generated as long
+    /// as the maximum length leaves enough room for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - `&[&str]` Array of texts to use as prompts for generation.
+    /// * `phrases` - `&[&str]` Phrases that must appear in each generated sequence.
+    ///
+    /// # Returns
+    /// * `Vec<String>` Vector of generated strings based on the prompts of length *n_samples x num_return_sequences*.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    ///
+    /// let output = model.generate_with_constraints(&["Introducing our new phone,"], &["Galaxy"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_with_constraints<S, P>(
+        &self,
+        texts: &[S],
+        phrases: &[P],
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+        P: AsRef<str>,
+    {
+        if texts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        let tokenizer = self.model.get_tokenizer();
+        let constraints = phrases
+            .iter()
+            .map(|phrase| PhrasalConstraint::from_phrases(tokenizer, &[phrase.as_ref()]))
+            .collect::<Result<Vec<PhrasalConstraint>, RustBertError>>()?;
+        let generate_options = GenerateOptions {
+            constraints: Some(&constraints),
+            ..Default::default()
+        };
+        let generated_indices =
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_indices_with_options(Some(texts), generate_options)
+            });
+        Ok(generated_indices
+            .into_iter()
+            .map(|generated_sequence| tokenizer.decode(&generated_sequence, true, true))
+            .collect())
+    }
+
+    /// Generate outputs restricted to a closed set of allowed outputs (e.g. entity names for entity retrieval, or
+    /// labels for classification by generation). The allowed outputs are compiled to a token trie continuing the
+    /// prompts: they are tokenized as provided and may require a leading space for tokenizers encoding white spaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - `&[&str]` Array of texts to use as prompts for generation.
+    /// * `allowed_outputs` - `&[&str]` Closed set of allowed outputs.
+    ///
+    /// # Returns
+    /// * `Vec<String>` Allowed output generated for each prompt (*n_samples x num_return_sequences*).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    ///
+    /// let output = model.generate_from_set(
+    ///     &["The capital city of France is"],
+    ///     &[" Paris", " London", " Berlin"],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_from_set<S, O>(
+        &self,
+        texts: &[S],
+        allowed_outputs: &[O],
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+        O: AsRef<str>,
+    {
+        if texts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        let tokenizer = self.model.get_tokenizer();
+        let output_ids = allowed_outputs
+            .iter()
+            .map(|output| tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(output.as_ref())))
+            .collect::<Vec<Vec<i64>>>();
+        let trie = TokenTrie::new(&output_ids)?;
+        let prompt_length = texts
+            .iter()
+            .map(|text| tokenizer.tokenize(text.as_ref()).len())
+            .max()
+            .unwrap_or(0);
+        let output_length = output_ids.iter().map(Vec::len).max().unwrap_or(0);
+        let generate_options = GenerateOptions {
+            min_length: Some(0),
+            max_length: Some((prompt_length + output_length + 1) as i64),
+            allowed_outputs: Some(&trie),
+            ..Default::default()
+        };
+        let generated_indices =
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_indices_with_options(Some(texts), generate_options)
+            });
+
+        // The generated output is the longest allowed output ending the sequence, ignoring the end of sequence and
+        // padding tokens
+        let special_tokens = [tokenizer.get_eos_id(), tokenizer.get_pad_id()];
+        Ok(generated_indices
+            .iter()
+            .map(|generated_sequence| {
+                let mut end = generated_sequence.len();
+                while end > 0 && special_tokens.contains(&Some(generated_sequence[end - 1])) {
+                    end -= 1;
+                }
+                let generated_sequence = &generated_sequence[..end];
+                output_ids
+                    .iter()
+                    .zip(allowed_outputs.iter())
+                    .filter(|(ids, _)| generated_sequence.ends_with(ids))
+                    .max_by_key(|(ids, _)| ids.len())
+                    .map(|(_, output)| output.as_ref().to_string())
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
+
+    /// Scores continuations of a prompt by their log-likelihood under the model (sum of the log-probabilities of
+    /// their tokens), e.g. to rank candidate answers. The pipeline prefix is not applied to the prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - `&str` prompt preceding the continuations.
+    /// * `continuations` - `&[&str]` continuations to score.
+    ///
+    /// # Returns
+    /// * `Vec<f64>` Log-likelihood of each continuation given the prompt
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let scores = model.score_continuations("The capital city of France is", &[" Paris", " London"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn score_continuations<S>(
+        &self,
+        prompt: &str,
+        continuations: &[S],
+    ) -> Result<Vec<f64>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            self.model.score_continuations(prompt, continuations)
+        })
+    }
+
+    /// Scores the tokens of a sequence by their log-probability given the previous tokens. Only decoder-only models
+    /// are supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `&[i64]` ids of the tokens to score, not exceeding the maximum number of positions of the model
+    ///
+    /// # Returns
+    /// * `Vec<Option<f64>>` Log-probability of each token (`None` for the first token of models without a beginning of
+    /// sequence token)
+    pub fn score_token_ids(&self, token_ids: &[i64]) -> Result<Vec<Option<f64>>, RustBertError> {
+        with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            self.model.score_token_ids(token_ids)
+        })
+    }
+
+    /// Classifies prompts by scoring a fixed set of verbalized answers (e.g. "yes"/"no" or option letters) as their
+    /// continuation, and normalizing the scores of the answers to probabilities. White spaces ending a prompt are
+    /// moved to the start of the answers, as they are part of the answer tokens for BPE tokenizers (e.g. GPT2).
+    /// The pipeline prefix is not applied to the prompts.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompts` - `&[&str]` Array of prompts, each ending where the answer should start.
+    /// * `answers` - `&[&str]` Verbalized answers to score.
+    /// * `scoring` - `AnswerScoring` aggregation of the log-probabilities of the tokens of an answer.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Label>>` probabilities of all answers (in the order provided) for each prompt
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::{AnswerScoring, TextGenerationModel};
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let output = model.classify_answers(
+    ///     &["Question: is Paris the capital city of France?\nAnswer: "],
+    ///     &["yes", "no"],
+    ///     AnswerScoring::Sum,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn classify_answers<S, A>(
+        &self,
+        prompts: &[S],
+        answers: &[A],
+        scoring: AnswerScoring,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<str>,
+        A: AsRef<str>,
+    {
+        if prompts.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        if answers.is_empty() {
+            return Err(InputError::NoCandidateLabels.into());
+        }
+        let tokenizer = self.model.get_tokenizer();
+        prompts
+            .iter()
+            .enumerate()
+            .map(|(sentence, prompt)| {
+                let prompt = prompt.as_ref();
+                let context = prompt.trim_end();
+                let white_space = &prompt[context.len()..];
+                let continuations = answers
+                    .iter()
+                    .map(|answer| format!("{white_space}{}", answer.as_ref()))
+                    .collect::<Vec<String>>();
+                let mut scores =
+                    with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                        self.model.score_continuations(context, &continuations)
+                    })?;
+                if scoring == AnswerScoring::Mean {
+                    for (score, continuation) in scores.iter_mut().zip(continuations.iter()) {
+                        *score /= tokenizer.tokenize(continuation).len() as f64;
+                    }
+                }
+                Ok(answer_probabilities(&scores)
+                    .into_iter()
+                    .zip(answers.iter())
+                    .enumerate()
+                    .map(|(id, (score, answer))| Label {
+                        text: answer.as_ref().to_string(),
+                        score,
+                        id: id as i64,
+                        sentence,
+                    })
+                    .collect())
+            })
+            .collect()
+    }
+
+    /// Answers a reasoning prompt by self-consistency: samples several reasoning chains, extracts their final answer
+    /// and returns the majority-vote answer with agreement statistics. The sampling settings are given by the
+    /// `SelfConsistencyConfig`, the other generation settings being those of the pipeline. The answers are extracted from
+    /// the generated continuations only (excluding the prompt, that may contain few-shot examples). The pipeline prefix
+    /// is not applied to the prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - `&str` reasoning prompt.
+    /// * `extractor` - `AnswerExtractor` returning the final answer of a reasoning chain (e.g. `RegexAnswerExtractor`).
+    /// * `config` - `SelfConsistencyConfig` number of reasoning chains and sampling settings.
+    ///
+    /// # Returns
+    /// * `SelfConsistencyOutput` majority-vote answer, votes, agreement and generated reasoning chains
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::self_consistency::{RegexAnswerExtractor, SelfConsistencyConfig};
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let extractor = RegexAnswerExtractor::new(r"The answer is (\-?[0-9]+)")?;
+    /// let config = SelfConsistencyConfig {
+    ///     num_samples: 20,
+    ///     ..Default::default()
+    /// };
+    /// let output = model.generate_self_consistent(
+    ///     "Q: A farmer has 15 sheep and buys 8 more. How many sheep does he have?\nA:",
+    ///     &extractor,
+    ///     &config,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_self_consistent<E>(
+        &self,
+        prompt: &str,
+        extractor: &E,
+        config: &SelfConsistencyConfig,
+    ) -> Result<SelfConsistencyOutput, RustBertError>
+    where
+        E: AnswerExtractor + ?Sized,
+    {
+        if prompt.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        config.validate()?;
+        let generate_options = GenerateOptions {
+            max_new_tokens: config.max_new_tokens,
+            do_sample: Some(true),
+            temperature: Some(config.temperature),
+            top_k: Some(config.top_k),
+            top_p: Some(config.top_p),
+            num_beams: Some(1),
+            num_return_sequences: Some(config.num_samples),
+            ..Default::default()
+        };
+        let chains = self.generate_continuations(prompt, generate_options);
+
+        Ok(aggregate_answers(chains, extractor))
+    }
+
+    /// Generates a structured output: the generated text is parsed by an `OutputParser` (e.g. a `JsonOutputParser`
+    /// deserializing JSON checked against a schema), and the model generates again if it is invalid, with a prompt
+    /// extended with the invalid output and the parsing error. The parser is applied to the generated continuation
+    /// only. The generation settings are those of the pipeline, and the pipeline prefix is not applied to the
+    /// prompts.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - `&str` prompt describing the expected output.
+    /// * `parser` - `OutputParser` validating and parsing the generated text.
+    /// * `config` - `StructuredOutputConfig` number of retries and prompt template of the retries.
+    ///
+    /// # Returns
+    /// * `StructuredOutput` parsed value of the first valid output (`None` if all attempts failed) and attempts
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::structured_output::{JsonOutputParser, StructuredOutputConfig};
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let parser = JsonOutputParser::<Vec<String>>::new();
+    /// let output = model.generate_structured(
+    ///     "The three primary colors, as a JSON list:",
+    ///     &parser,
+    ///     &StructuredOutputConfig::default(),
+    /// )?;
+    /// let colors = output.into_result()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_structured<P>(
+        &self,
+        prompt: &str,
+        parser: &P,
+        config: &StructuredOutputConfig,
+    ) -> Result<StructuredOutput<P::Output>, RustBertError>
+    where
+        P: OutputParser + ?Sized,
+    {
+        if prompt.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        config.validate()?;
+        generate_with_retries(prompt, parser, config, |attempt_prompt| {
+            let generate_options = GenerateOptions {
+                max_new_tokens: config.max_new_tokens,
+                num_return_sequences: Some(1),
+                ..Default::default()
+            };
+            Ok(self
+                .generate_continuations(attempt_prompt, generate_options)
+                .into_iter()
+                .next()
+                .unwrap_or_default())
+        })
+    }
+
+    fn generate_continuations(
+        &self,
+        prompt: &str,
+        generate_options: GenerateOptions,
+    ) -> Vec<String> {
+        let tokenizer = self.model.get_tokenizer();
+        let generated_indices =
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_indices_with_options(Some(&[prompt]), generate_options)
+            });
+
+        // Decoder-only models return the prompt followed by the generated tokens
+        let prompt_ids = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(prompt));
+        generated_indices
+            .iter()
+            .map(|generated_sequence| {
+                let start = if generated_sequence.starts_with(&prompt_ids) {
+                    prompt_ids.len()
+                } else {
+                    0
+                };
+                tokenizer.decode(&generated_sequence[start..], true, true)
+            })
+            .collect()
+    }
+
+    /// Generates a long document (e.g. an article or report) about a topic in two stages: the model first writes an
+    /// outline with one section heading per line, then expands every section. The sections are written after a
+    /// context made of the topic and the complete outline, whose cached keys and values are computed once and reused
+    /// for every section. The pipeline prefix is not applied to the prompts.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - `&str` topic of the document.
+    /// * `config` - `&LongFormConfig` prompt templates and generation lengths of the outline and sections.
+    ///
+    /// # Returns
+    /// * `LongFormOutput` generated outline and sections
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::long_form::LongFormConfig;
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    /// let output = model.generate_long_form("The history of the bicycle", &LongFormConfig::default())?;
+    /// println!("{}", output.document());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_long_form(
+        &self,
+        topic: &str,
+        config: &LongFormConfig,
+    ) -> Result<LongFormOutput, RustBertError> {
+        if topic.trim().is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        config.validate()?;
+
+        let outline_options = GenerateOptions {
+            max_new_tokens: config.outline_max_new_tokens,
+            ..Default::default()
+        };
+        let outline = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            self.model.generate_with_shared_context(
+                &config.outline_prompt(topic),
+                &[""],
+                outline_options,
+            )
+        })?;
+        let headings = parse_outline(&outline[0], config.max_sections);
+        if headings.is_empty() {
+            return Err(RustBertError::ValueError(
+                "The generated outline does not contain any section heading".to_string(),
+            ));
+        }
+
+        let context = config.shared_context(topic, &headings);
+        if let Some(max_positions) = self.model.get_max_positions_embeddings() {
+            let context_length = self.model.get_tokenizer().tokenize(&context).len();
+            if context_length + config.section_max_new_tokens.unwrap_or(0) as usize
+                > max_positions as usize
+            {
+                return Err(InputError::ContextTooLong {
+                    index: 0,
+                    length: context_length,
+                    max_length: max_positions as usize,
+                }
+                .into());
+            }
+        }
+        let section_prompts = headings
+            .iter()
+            .map(|heading| config.section_prompt(heading))
+            .collect::<Vec<String>>();
+        let stop_sequences = config
+            .stop_sequences
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        let section_options = GenerateOptions {
+            max_new_tokens: config.section_max_new_tokens,
+            stop_sequences: Some(&stop_sequences),
+            ..Default::default()
+        };
+        let section_texts =
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_with_shared_context(&context, &section_prompts, section_options)
+            })?;
+
+        let sections = headings
+            .iter()
+            .zip(section_texts)
+            .map(|(heading, text)| LongFormSection {
+                heading: heading.clone(),
+                text: text.trim().to_string(),
+            })
+            .collect();
+        Ok(LongFormOutput {
+            outline: headings,
+            sections,
+        })
+    }
+
+    fn resolve_prefix<'a>(&'a self, prefix: Option<&'a str>) -> (Option<&'a str>, Option<i64>) {
+        match (prefix, &self.prefix) {
+            (Some(query_prefix), _) => (
+                Some(query_prefix),
+                Some(self.model.get_tokenizer().tokenize(query_prefix).len() as i64),
+            ),
+            (None, Some(pipeline_prefix)) => (Some(pipeline_prefix.as_str()), self.prefix_length),
+            (None, None) => (None, None),
+        }
+    }
+
+    /// Compresses a long context into a shorter text, keeping the most informative words under this model (used as
+    /// the scorer, typically a small model) until the token budget of the configuration is reached. See the
+    /// `prompt_compression` module for details. Only decoder-only models are supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - `&str` context to compress (e.g. retrieved evidence)
+    /// * `config` - `PromptCompressionConfig` with the token budget and the terms to preserve
+    ///
+    /// # Returns
+    /// * `CompressedPrompt` compressed text and number of tokens before and after compression
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::prompt_compression::PromptCompressionConfig;
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let scorer = TextGenerationModel::new(Default::default())?;
+    /// let compressed = scorer.compress_prompt(
+    ///     "The Eiffel Tower is a wrought-iron lattice tower on the Champ de Mars in Paris, France.",
+    ///     &PromptCompressionConfig::default(),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compress_prompt(
+        &self,
+        context: &str,
+        config: &PromptCompressionConfig,
+    ) -> Result<CompressedPrompt, RustBertError> {
+        if context.trim().is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        config.validate()?;
+        let tokenizer = self.model.get_tokenizer();
+        let tokens = tokenizer.tokenize_with_offsets(context);
+        let token_ids = tokenizer.convert_tokens_to_ids(&tokens.tokens);
+        // One position is kept for the beginning of sequence token prepended by the scoring
+        let window = config
+            .window_tokens
+            .or_else(|| {
+                self.model
+                    .get_max_positions_embeddings()
+                    .map(|max_positions| (max_positions as usize).saturating_sub(1).max(2))
+            })
+            .unwrap_or(512);
+        let log_probabilities = windowed_log_probabilities(&token_ids, window, |token_ids| {
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model.score_token_ids(token_ids)
+            })
+        })?;
+
+        let words = group_words(context, &tokens.offsets, &log_probabilities);
+        let forced = words
+            .iter()
+            .map(|word| config.is_preserved(&word.text))
+            .collect::<Vec<bool>>();
+        let selected = select_words(&words, config.token_budget(token_ids.len()), &forced);
+        Ok(CompressedPrompt {
+            text: join_words(context, &words, &selected),
+            original_tokens: token_ids.len(),
+            compressed_tokens: selected.iter().map(|index| words[*index].num_tokens).sum(),
+        })
+    }
+
+    /// Generate the code missing between a prefix and a suffix (fill-in-the-middle). Requires a model trained
+    /// with a fill-in-the-middle objective and the associated special tokens (e.g. StarCoder2).
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - `&[(&str, &str)]` Array of (prefix, suffix) pairs surrounding the code to generate.
+    ///
+    /// # Returns
+    /// * `Vec<String>` Generated middle spans (`num_return_sequences` for each input)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelResource, ModelType};
+    /// use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+    /// use rust_bert::resources::{LocalResource, RemoteResource};
+    /// use rust_bert::starcoder2::{
+    ///     StarCoder2ConfigResources, StarCoder2MergesResources, StarCoder2VocabResources,
+    /// };
+    /// use std::path::PathBuf;
+    ///
+    /// let generation_config = TextGenerationConfig {
+    ///     max_length: Some(64),
+    ///     do_sample: false,
+    ///     num_beams: 1,
+    ///     ..TextGenerationConfig::new(
+    ///         ModelType::StarCoder2,
+    ///         ModelResource::Torch(Box::new(LocalResource {
+    ///             local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///         })),
+    ///         RemoteResource::from_pretrained(StarCoder2ConfigResources::STARCODER2_3B),
+    ///         RemoteResource::from_pretrained(StarCoder2VocabResources::STARCODER2_3B),
+    ///         Some(RemoteResource::from_pretrained(
+    ///             StarCoder2MergesResources::STARCODER2_3B,
+    ///         )),
+    ///     )
+    /// };
+    /// let model = TextGenerationModel::new(generation_config)?;
+    ///
+    /// let inputs = [("def fibonacci(n):\n    ", "\n    return fibonacci(n - 1) + fibonacci(n - 2)")];
+    /// let middle = model.fill_in_the_middle(&inputs)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "starcoder2")]
+    pub fn fill_in_the_middle<S>(&self, inputs: &[(S, S)]) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        let tokenizer = self.model.get_tokenizer();
+        if tokenizer.convert_tokens_to_ids(&[FIM_MIDDLE])[0] == tokenizer.get_unk_id() {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The tokenizer vocabulary does not contain the fill-in-the-middle token {FIM_MIDDLE}"
+            )));
+        }
+
+        let prompts = inputs
+            .iter()
+            .map(|(prefix, suffix)| {
+                build_fill_in_the_middle_prompt(prefix.as_ref(), suffix.as_ref())
+            })
+            .collect::<Vec<String>>();
+        // Prompts are left-padded to the longest prompt and excluded from the generated sequences
+        let prompt_length = prompts
+            .iter()
+            .map(|prompt| tokenizer.tokenize(prompt).len() as i64)
+            .max()
+            .unwrap_or(0);
+        let generated_indices =
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model.generate_indices(
+                    Some(&prompts),
+                    Some(self.min_length + prompt_length),
+                    self.max_length.map(|max_length| max_length + prompt_length),
+                )
+            });
+
+        Ok(generated_indices
+            .into_iter()
+            .map(|generated_sequence| {
+                tokenizer.decode(&generated_sequence[prompt_length as usize..], true, true)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text_streaming() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let vocab_path = dir.path().join("vocab.txt");
+        std::fs::write(
+            &vocab_path,
+            "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nhello\n##s\nit\n'\ns\n.\n",
+        )?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+        // Cleaning up the tokenization spaces around `'` would rewrite the text emitted for the previous tokens
+        let token_ids =
+            tokenizer.convert_tokens_to_ids(&["hello", "##s", "it", "'", "s", ".", "[SEP]"]);
+
+        let mut pieces = Vec::new();
+        let mut streamer = TextStreamer::new(|text: &str| pieces.push(text.to_string()));
+        for &token_id in &token_ids {
+            streamer.push(&tokenizer, token_id);
+        }
+        streamer.finish(&tokenizer);
+
+        assert_eq!(pieces, ["hello", "s", " it", " '", " s", " ."]);
+        assert_eq!(pieces.concat(), tokenizer.decode(&token_ids, true, false));
+        assert_eq!(tokenizer.decode(&token_ids, true, true), "hellos it's.");
+        Ok(())
+    }
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = TextGenerationConfig::default();
+        let _: Box<dyn Send> = Box::new(TextGenerationModel::new(config));
+    }
+
+    #[test]
+    fn answer_probabilities_normalization() {
+        let probabilities = answer_probabilities(&[-1.0, -2.0, -1000.0]);
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((probabilities[0] / probabilities[1] - 1f64.exp()).abs() < 1e-9);
+        assert!(probabilities[2] < 1e-12);
     }
 }
@@ -0,0 +1,1203 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Text-to-SQL pipeline
+//! Generates SQL queries answering natural language questions over a database schema. The question and a
+//! description of the schema are formatted into a prompt (by default `{question} | {schema}`, with the schema
+//! serialized as `table : column , column | ...`) and passed to a text generation model, typically a T5 model
+//! fine-tuned on a text-to-SQL dataset such as Spider.
+//!
+//! The generation is constrained by a SQL grammar: at each decoding step, only the tokens continuing the generated
+//! text into a valid prefix of a query are allowed, and the end of sequence token is only allowed once the query is
+//! complete. The grammar covers the `SELECT` queries commonly generated for analytics (joins, filters, aggregations,
+//! grouping, ordering, limits, set operations and sub-queries) and is schema aware: tables and columns must be
+//! declared in the schema.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::text_to_sql::{SqlSchema, TextToSqlModel};
+//! # fn main() -> anyhow::Result<()> {
+//! let text_to_sql_model = TextToSqlModel::new(Default::default())?;
+//!
+//! let schema = SqlSchema::new()
+//!     .with_table("singer", &["singer_id", "name", "country", "age"])
+//!     .with_table("concert", &["concert_id", "singer_id", "year"]);
+//! let output = text_to_sql_model.generate(&["How many singers are from France?"], &schema)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The grammar can also be used on its own to validate queries (see `SqlGrammar`).
+
+use crate::common::error::{InputError, RustBertError};
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::generation_utils::GenerateOptions;
+use crate::pipelines::prompts::PromptTemplate;
+use crate::pipelines::structured_generation::token_continuations;
+use crate::pipelines::text_generation::{TextGenerationConfig, TextGenerationOption};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use tch::Tensor;
+
+#[cfg(all(feature = "remote", feature = "t5"))]
+use crate::{
+    pipelines::common::{ModelResource, ModelType},
+    resources::RemoteResource,
+    t5::{T5ConfigResources, T5ModelResources, T5VocabResources},
+};
+
+/// Default prompt, following the serialization of the Spider text-to-SQL models
+pub const DEFAULT_TEXT_TO_SQL_PROMPT: &str = "{question} | {schema}";
+
+const KEYWORDS: [&str; 37] = [
+    "select",
+    "distinct",
+    "from",
+    "where",
+    "group",
+    "by",
+    "having",
+    "order",
+    "asc",
+    "desc",
+    "limit",
+    "offset",
+    "as",
+    "join",
+    "inner",
+    "left",
+    "right",
+    "full",
+    "outer",
+    "cross",
+    "natural",
+    "on",
+    "and",
+    "or",
+    "not",
+    "in",
+    "like",
+    "between",
+    "is",
+    "null",
+    "true",
+    "false",
+    "union",
+    "intersect",
+    "except",
+    "all",
+    "exists",
+];
+
+const FUNCTIONS: [&str; 15] = [
+    "count", "sum", "avg", "min", "max", "abs", "round", "length", "lower", "upper", "substr",
+    "coalesce", "ifnull", "date", "strftime",
+];
+
+const BINARY_OPERATORS: [&str; 13] = [
+    "=", "!=", "<>", "<", ">", "<=", ">=", "+", "-", "*", "/", "%", "||",
+];
+
+const COLUMN_CONSTRAINTS: [&str; 7] = [
+    "primary",
+    "foreign",
+    "unique",
+    "constraint",
+    "check",
+    "key",
+    "index",
+];
+
+/// # Table of a database schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SqlTable {
+    /// Name of the table
+    pub name: String,
+    /// Names of the columns of the table
+    pub columns: Vec<String>,
+}
+
+/// # Database schema
+/// Tables and columns the generated queries may refer to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SqlSchema {
+    /// Tables of the schema
+    pub tables: Vec<SqlTable>,
+}
+
+impl SqlSchema {
+    /// Creates an empty schema
+    pub fn new() -> SqlSchema {
+        SqlSchema { tables: vec![] }
+    }
+
+    /// Adds a table to the schema
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the table
+    /// * `columns` - Names of the columns of the table
+    pub fn with_table<S>(mut self, name: &str, columns: &[S]) -> SqlSchema
+    where
+        S: AsRef<str>,
+    {
+        self.tables.push(SqlTable {
+            name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|column| column.as_ref().to_string())
+                .collect(),
+        });
+        self
+    }
+
+    /// Reads the tables and columns of `CREATE TABLE` statements. Table constraints (e.g. `PRIMARY KEY (...)` or
+    /// `FOREIGN KEY (...)`) are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `ddl` - SQL statements creating the tables of the schema
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_to_sql::SqlSchema;
+    ///
+    /// let schema = SqlSchema::from_ddl(
+    ///     "CREATE TABLE singer (singer_id INTEGER PRIMARY KEY, name TEXT, age INTEGER);",
+    /// )?;
+    /// assert_eq!(schema.describe(), "singer : singer_id , name , age");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_ddl(ddl: &str) -> Result<SqlSchema, RustBertError> {
+        let lowercase = ddl.to_ascii_lowercase();
+        let mut schema = SqlSchema::new();
+        let mut position = 0;
+        while let Some(offset) = lowercase[position..].find("create table") {
+            let name_start = position + offset + "create table".len();
+            let body_start = ddl[name_start..]
+                .find('(')
+                .map(|offset| name_start + offset)
+                .ok_or_else(|| {
+                    RustBertError::ValueError(
+                        "Missing column definitions in CREATE TABLE statement".to_string(),
+                    )
+                })?;
+            let name = ddl[name_start..body_start].trim();
+            let name = if name.to_ascii_lowercase().starts_with("if not exists") {
+                name["if not exists".len()..].trim()
+            } else {
+                name
+            };
+
+            let mut depth = 0;
+            let mut body_end = None;
+            for (offset, character) in ddl[body_start..].char_indices() {
+                match character {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            body_end = Some(body_start + offset);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let body_end = body_end.ok_or_else(|| {
+                RustBertError::ValueError(format!(
+                    "Unbalanced parentheses in CREATE TABLE statement for {name}"
+                ))
+            })?;
+
+            let columns = split_top_level(&ddl[body_start + 1..body_end])
+                .into_iter()
+                .filter_map(|definition| {
+                    let column = definition.split_whitespace().next()?;
+                    if COLUMN_CONSTRAINTS.contains(&column.to_ascii_lowercase().as_str()) {
+                        None
+                    } else {
+                        Some(unquote(column).to_string())
+                    }
+                })
+                .collect::<Vec<String>>();
+            schema = schema.with_table(unquote(name), &columns);
+            position = body_end;
+        }
+        if schema.tables.is_empty() {
+            return Err(RustBertError::ValueError(
+                "No CREATE TABLE statement found in the schema definition".to_string(),
+            ));
+        }
+        Ok(schema)
+    }
+
+    /// Serializes the schema for the prompt, as `table : column , column | table : column`
+    pub fn describe(&self) -> String {
+        self.tables
+            .iter()
+            .map(|table| format!("{} : {}", table.name, table.columns.join(" , ")))
+            .collect::<Vec<String>>()
+            .join(" | ")
+    }
+}
+
+/// Splits a list of definitions at the commas outside of parentheses
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (offset, character) in text.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(text[start..offset].trim());
+                start = offset + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(text[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn unquote(name: &str) -> &str {
+    name.trim_matches(|character| matches!(character, '"' | '`' | '[' | ']' | '\''))
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word)
+}
+
+/// SQL token. Words are lower cased.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SqlToken {
+    Word(String),
+    Number,
+    Literal,
+    /// String literal missing its closing quote (only at the end of a text)
+    OpenLiteral,
+    Symbol(String),
+}
+
+/// Splits a SQL text into tokens. The last token is returned separately with its character position if it may be
+/// continued by the following text (e.g. a word or a number). Returns `None` if the text contains invalid characters.
+fn lex(text: &str) -> Option<(Vec<SqlToken>, Option<(SqlToken, usize)>)> {
+    let characters = text.chars().collect::<Vec<char>>();
+    let is_word_character = |character: char| character.is_alphanumeric() || character == '_';
+    let mut tokens = vec![];
+    let mut index = 0;
+    while index < characters.len() {
+        let character = characters[index];
+        let start = index;
+        if character.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        let token = if character.is_alphabetic() || character == '_' {
+            while index < characters.len() && is_word_character(characters[index]) {
+                index += 1;
+            }
+            SqlToken::Word(
+                characters[start..index]
+                    .iter()
+                    .collect::<String>()
+                    .to_lowercase(),
+            )
+        } else if character.is_ascii_digit() {
+            let mut seen_decimal_point = false;
+            while index < characters.len()
+                && (characters[index].is_ascii_digit()
+                    || (characters[index] == '.' && !seen_decimal_point))
+            {
+                seen_decimal_point |= characters[index] == '.';
+                index += 1;
+            }
+            if index < characters.len() && is_word_character(characters[index]) {
+                return None;
+            }
+            SqlToken::Number
+        } else if character == '\'' {
+            index += 1;
+            loop {
+                match characters.get(index) {
+                    None => return Some((tokens, Some((SqlToken::OpenLiteral, start)))),
+                    Some('\'') if characters.get(index + 1) == Some(&'\'') => index += 2,
+                    Some('\'') => {
+                        index += 1;
+                        break;
+                    }
+                    Some(_) => index += 1,
+                }
+            }
+            SqlToken::Literal
+        } else {
+            let next = characters.get(index + 1).copied();
+            let symbol = match (character, next) {
+                ('<', Some('='))
+                | ('<', Some('>'))
+                | ('>', Some('='))
+                | ('!', Some('='))
+                | ('|', Some('|')) => {
+                    format!("{character}{}", next.unwrap())
+                }
+                ('!', None) | ('|', None) => character.to_string(),
+                ('!', _) | ('|', _) => return None,
+                _ if "()<>=,;.*+-/%".contains(character) => character.to_string(),
+                _ => return None,
+            };
+            index += symbol.chars().count();
+            SqlToken::Symbol(symbol)
+        };
+        let is_extendable = match &token {
+            SqlToken::Word(_) | SqlToken::Number | SqlToken::Literal => true,
+            SqlToken::Symbol(symbol) => matches!(symbol.as_str(), "<" | ">" | "!" | "|"),
+            SqlToken::OpenLiteral => false,
+        };
+        if is_extendable && index == characters.len() {
+            return Some((tokens, Some((token, start))));
+        }
+        tokens.push(token);
+    }
+    Some((tokens, None))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Clause {
+    Select,
+    From,
+    Where,
+    GroupBy,
+    Having,
+    OrderBy,
+    Limit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Frame {
+    /// Parenthesized expression, function arguments or list of values
+    Group,
+    /// Sub-query, with the clause of the enclosing query
+    Subquery { clause: Clause, in_from: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Phase {
+    Start,
+    AfterSetOperator,
+    SelectStart,
+    Operand {
+        subquery: bool,
+    },
+    AfterOperand,
+    AfterIdentifier {
+        value: bool,
+        function: bool,
+        table: Option<usize>,
+    },
+    AfterQualifier {
+        table: Option<usize>,
+    },
+    AfterNegation,
+    ExpectParenthesis,
+    FunctionArguments,
+    AfterAs,
+    AfterAlias,
+    ExpectBy(Clause),
+    Table,
+    AfterTable,
+    AfterJoinType,
+    LimitValue,
+    AfterLimit,
+    OffsetValue,
+    AfterDirection,
+    Done,
+    End,
+}
+
+/// State of the parser after a sequence of complete tokens
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ParserState {
+    phase: Phase,
+    clause: Clause,
+    stack: Vec<Frame>,
+    aliases: Vec<String>,
+}
+
+impl ParserState {
+    fn new() -> ParserState {
+        ParserState {
+            phase: Phase::Start,
+            clause: Clause::Select,
+            stack: vec![],
+            aliases: vec![],
+        }
+    }
+
+    fn in_group(&self) -> bool {
+        self.stack.last() == Some(&Frame::Group)
+    }
+
+    fn is_final(&self) -> bool {
+        let is_complete_phase = match self.phase {
+            Phase::AfterIdentifier { value, .. } => value,
+            Phase::AfterOperand
+            | Phase::AfterTable
+            | Phase::AfterDirection
+            | Phase::AfterLimit
+            | Phase::Done => true,
+            Phase::End => return self.stack.is_empty(),
+            _ => false,
+        };
+        is_complete_phase && self.stack.is_empty() && self.clause != Clause::Select
+    }
+}
+
+/// Words that may start the next token in a parser state
+#[derive(Debug, Clone)]
+struct AllowedWords {
+    words: Vec<String>,
+    any_identifier: bool,
+}
+
+/// # Schema-aware SQL grammar
+/// Validates (prefixes of) SQL queries against a subset of the SQL grammar covering `SELECT` queries, and checks that
+/// the tables and columns used belong to the schema. Keywords and names are case insensitive. Columns may be
+/// qualified by a table name or by an alias, possibly declared later in the query (e.g. `SELECT T1.name FROM singer
+/// AS T1`).
+#[derive(Debug, Clone)]
+pub struct SqlGrammar {
+    tables: Vec<String>,
+    columns: Vec<Vec<String>>,
+    all_columns: Vec<String>,
+    words: Vec<String>,
+}
+
+impl SqlGrammar {
+    /// Creates a grammar for queries over a schema
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - Schema of the database queried
+    pub fn new(schema: &SqlSchema) -> SqlGrammar {
+        let tables = schema
+            .tables
+            .iter()
+            .map(|table| table.name.to_lowercase())
+            .collect::<Vec<String>>();
+        let columns = schema
+            .tables
+            .iter()
+            .map(|table| {
+                table
+                    .columns
+                    .iter()
+                    .map(|column| column.to_lowercase())
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<Vec<String>>>();
+        let mut all_columns = columns.concat();
+        all_columns.sort();
+        all_columns.dedup();
+        let mut words = KEYWORDS
+            .iter()
+            .chain(FUNCTIONS.iter())
+            .map(|word| word.to_string())
+            .chain(tables.iter().cloned())
+            .chain(all_columns.iter().cloned())
+            .collect::<Vec<String>>();
+        words.sort();
+        words.dedup();
+        SqlGrammar {
+            tables,
+            columns,
+            all_columns,
+            words,
+        }
+    }
+
+    /// Checks if a text is a valid prefix of a query (i.e. can be continued into a valid query)
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - Text to check
+    pub fn is_valid_prefix(&self, sql: &str) -> bool {
+        self.accepts_continuation(&ParserState::new(), sql, &mut HashMap::new())
+    }
+
+    /// Checks if a text is a complete and valid query
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - Text to check
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::text_to_sql::{SqlGrammar, SqlSchema};
+    ///
+    /// let schema = SqlSchema::new().with_table("singer", &["singer_id", "name", "country"]);
+    /// let grammar = SqlGrammar::new(&schema);
+    ///
+    /// assert!(grammar.is_complete("SELECT count(*) FROM singer WHERE country = 'France'"));
+    /// assert!(!grammar.is_complete("SELECT count(*) FROM singer WHERE"));
+    /// assert!(!grammar.is_complete("SELECT title FROM singer"));
+    /// ```
+    pub fn is_complete(&self, sql: &str) -> bool {
+        self.is_complete_from(&ParserState::new(), sql)
+    }
+
+    /// Parses the complete tokens of a prefix, returning the parser state and the text of the last token if it may
+    /// be continued
+    fn parse_prefix(&self, sql: &str) -> Option<(ParserState, String)> {
+        let (tokens, partial) = lex(sql)?;
+        let mut state = ParserState::new();
+        for token in tokens.iter() {
+            if !self.advance(&mut state, token) {
+                return None;
+            }
+        }
+        let partial = partial.map_or_else(String::new, |(_, start)| {
+            sql.chars().skip(start).collect::<String>()
+        });
+        Some((state, partial))
+    }
+
+    fn is_complete_from(&self, state: &ParserState, text: &str) -> bool {
+        let (mut tokens, partial) = match lex(text) {
+            Some(lexed) => lexed,
+            None => return false,
+        };
+        if let Some((token, _)) = partial {
+            tokens.push(token);
+        }
+        let mut state = state.clone();
+        tokens.iter().all(|token| self.advance(&mut state, token)) && state.is_final()
+    }
+
+    /// Checks if a text continuing a parser state can be continued into a valid query
+    fn accepts_continuation(
+        &self,
+        state: &ParserState,
+        text: &str,
+        cache: &mut HashMap<ParserState, AllowedWords>,
+    ) -> bool {
+        let (tokens, partial) = match lex(text) {
+            Some(lexed) => lexed,
+            None => return false,
+        };
+        let mut state = state.clone();
+        if !tokens.iter().all(|token| self.advance(&mut state, token)) {
+            return false;
+        }
+        match partial {
+            None => true,
+            Some((SqlToken::Word(prefix), _)) => {
+                let allowed = cache
+                    .entry(state.clone())
+                    .or_insert_with(|| self.allowed_words(&state));
+                allowed.any_identifier || allowed.words.iter().any(|word| word.starts_with(&prefix))
+            }
+            Some((SqlToken::OpenLiteral, _)) => self.accepts(&state, &SqlToken::Literal),
+            Some((SqlToken::Symbol(symbol), _)) => {
+                let candidates: &[&str] = match symbol.as_str() {
+                    "<" => &["<", "<=", "<>"],
+                    ">" => &[">", ">="],
+                    "!" => &["!="],
+                    "|" => &["||"],
+                    _ => &[],
+                };
+                candidates
+                    .iter()
+                    .any(|candidate| self.accepts(&state, &SqlToken::Symbol(candidate.to_string())))
+            }
+            Some((token, _)) => self.accepts(&state, &token),
+        }
+    }
+
+    fn accepts(&self, state: &ParserState, token: &SqlToken) -> bool {
+        self.advance(&mut state.clone(), token)
+    }
+
+    fn allowed_words(&self, state: &ParserState) -> AllowedWords {
+        let words = self
+            .words
+            .iter()
+            .chain(state.aliases.iter())
+            .filter(|word| self.accepts(state, &SqlToken::Word(word.to_string())))
+            .cloned()
+            .collect();
+        // Identifiers outside of the schema are accepted for aliases and column qualifiers
+        let any_identifier = self.accepts(state, &SqlToken::Word("#".to_string()));
+        AllowedWords {
+            words,
+            any_identifier,
+        }
+    }
+
+    fn table_index(&self, word: &str) -> Option<usize> {
+        self.tables.iter().position(|table| table == word)
+    }
+
+    fn is_column(&self, word: &str, table: Option<usize>) -> bool {
+        match table {
+            Some(table) => self.columns[table].iter().any(|column| column == word),
+            None => self.all_columns.iter().any(|column| column == word),
+        }
+    }
+
+    fn advance(&self, state: &mut ParserState, token: &SqlToken) -> bool {
+        let word = match token {
+            SqlToken::Word(word) => Some(word.as_str()),
+            _ => None,
+        };
+        let symbol = match token {
+            SqlToken::Symbol(symbol) => Some(symbol.as_str()),
+            _ => None,
+        };
+        let phase = state.phase;
+        let next_phase = match phase {
+            Phase::Start | Phase::AfterSetOperator if word == Some("select") => {
+                state.clause = Clause::Select;
+                Some(Phase::SelectStart)
+            }
+            Phase::AfterSetOperator if word == Some("all") => Some(Phase::Start),
+            Phase::Start | Phase::AfterSetOperator => None,
+            Phase::SelectStart if word == Some("distinct") => {
+                Some(Phase::Operand { subquery: false })
+            }
+            Phase::SelectStart => return self.advance_operand(state, token, false),
+            Phase::Operand { subquery } => return self.advance_operand(state, token, subquery),
+            Phase::FunctionArguments => match (word, symbol) {
+                (Some("distinct"), _) => Some(Phase::Operand { subquery: false }),
+                (_, Some("*")) => Some(Phase::AfterOperand),
+                (_, Some(")")) => {
+                    state.stack.pop();
+                    Some(Phase::AfterOperand)
+                }
+                _ => return self.advance_operand(state, token, false),
+            },
+            Phase::AfterIdentifier {
+                value,
+                function,
+                table,
+            } => match symbol {
+                Some(".") => Some(Phase::AfterQualifier { table }),
+                Some("(") if function => {
+                    state.stack.push(Frame::Group);
+                    Some(Phase::FunctionArguments)
+                }
+                _ if value => return self.advance_after_operand(state, token),
+                _ => None,
+            },
+            Phase::AfterQualifier { table } => match token {
+                SqlToken::Symbol(symbol) if symbol == "*" => Some(Phase::AfterOperand),
+                SqlToken::Word(word) if self.is_column(word, table) => Some(Phase::AfterOperand),
+                _ => None,
+            },
+            Phase::AfterOperand => return self.advance_after_operand(state, token),
+            Phase::AfterNegation => match word {
+                Some("like") | Some("between") => Some(Phase::Operand { subquery: false }),
+                Some("in") => Some(Phase::ExpectParenthesis),
+                _ => None,
+            },
+            Phase::ExpectParenthesis => match symbol {
+                Some("(") => {
+                    state.stack.push(Frame::Group);
+                    Some(Phase::Operand { subquery: true })
+                }
+                _ => None,
+            },
+            Phase::AfterAs => match word {
+                Some(alias) if !is_keyword(alias) => {
+                    state.aliases.push(alias.to_string());
+                    if state.clause == Clause::From {
+                        Some(Phase::AfterTable)
+                    } else {
+                        Some(Phase::AfterAlias)
+                    }
+                }
+                _ => None,
+            },
+            Phase::AfterAlias => match symbol {
+                Some(",") => Some(Phase::Operand { subquery: false }),
+                _ => return self.advance_clause(state, token),
+            },
+            Phase::ExpectBy(clause) => match word {
+                Some("by") => {
+                    state.clause = clause;
+                    Some(Phase::Operand { subquery: false })
+                }
+                _ => None,
+            },
+            Phase::Table => match token {
+                SqlToken::Word(table) if self.table_index(table).is_some() => {
+                    Some(Phase::AfterTable)
+                }
+                SqlToken::Symbol(symbol) if symbol == "(" => {
+                    state.stack.push(Frame::Subquery {
+                        clause: Clause::From,
+                        in_from: true,
+                    });
+                    Some(Phase::Start)
+                }
+                _ => None,
+            },
+            Phase::AfterTable => match (word, symbol) {
+                (Some("as"), _) => Some(Phase::AfterAs),
+                (Some("on"), _) => Some(Phase::Operand { subquery: false }),
+                (Some(alias), _) if !is_keyword(alias) => {
+                    state.aliases.push(alias.to_string());
+                    Some(Phase::AfterTable)
+                }
+                (_, Some(",")) => Some(Phase::Table),
+                _ => return self.advance_clause(state, token),
+            },
+            Phase::AfterJoinType => match word {
+                Some("outer") => Some(Phase::AfterJoinType),
+                Some("join") => Some(Phase::Table),
+                _ => None,
+            },
+            Phase::LimitValue => match token {
+                SqlToken::Number => Some(Phase::AfterLimit),
+                _ => None,
+            },
+            Phase::AfterLimit => match (word, symbol) {
+                (Some("offset"), _) | (_, Some(",")) => Some(Phase::OffsetValue),
+                _ => return self.advance_end_of_query(state, token),
+            },
+            Phase::OffsetValue => match token {
+                SqlToken::Number => Some(Phase::Done),
+                _ => None,
+            },
+            Phase::AfterDirection => match symbol {
+                Some(",") => Some(Phase::Operand { subquery: false }),
+                _ => return self.advance_clause(state, token),
+            },
+            Phase::Done => return self.advance_end_of_query(state, token),
+            Phase::End => None,
+        };
+        match next_phase {
+            Some(phase) => {
+                state.phase = phase;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn advance_operand(&self, state: &mut ParserState, token: &SqlToken, subquery: bool) -> bool {
+        let next_phase = match token {
+            SqlToken::Word(word) if word == "select" && subquery => {
+                if let Some(frame) = state.stack.last_mut() {
+                    *frame = Frame::Subquery {
+                        clause: state.clause,
+                        in_from: false,
+                    };
+                }
+                state.clause = Clause::Select;
+                Some(Phase::SelectStart)
+            }
+            SqlToken::Word(word) if word == "not" => Some(Phase::Operand { subquery: false }),
+            SqlToken::Word(word) if word == "exists" => Some(Phase::ExpectParenthesis),
+            SqlToken::Word(word) if matches!(word.as_str(), "null" | "true" | "false") => {
+                Some(Phase::AfterOperand)
+            }
+            SqlToken::Word(word) if is_keyword(word) => None,
+            SqlToken::Word(word) => Some(Phase::AfterIdentifier {
+                value: self.is_column(word, None) || state.aliases.contains(word),
+                function: FUNCTIONS.contains(&word.as_str()),
+                table: self.table_index(word),
+            }),
+            SqlToken::Number | SqlToken::Literal => Some(Phase::AfterOperand),
+            SqlToken::Symbol(symbol) => match symbol.as_str() {
+                "(" => {
+                    state.stack.push(Frame::Group);
+                    Some(Phase::Operand { subquery: true })
+                }
+                "-" | "+" => Some(Phase::Operand { subquery: false }),
+                "*" if state.clause == Clause::Select && !state.in_group() => {
+                    Some(Phase::AfterOperand)
+                }
+                _ => None,
+            },
+            SqlToken::OpenLiteral => None,
+        };
+        match next_phase {
+            Some(phase) => {
+                state.phase = phase;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn advance_after_operand(&self, state: &mut ParserState, token: &SqlToken) -> bool {
+        let in_group = state.in_group();
+        let next_phase = match token {
+            SqlToken::Symbol(symbol) if BINARY_OPERATORS.contains(&symbol.as_str()) => {
+                Some(Phase::Operand { subquery: false })
+            }
+            SqlToken::Word(word) => match word.as_str() {
+                "and" | "or" | "like" | "is" | "between" => {
+                    Some(Phase::Operand { subquery: false })
+                }
+                "in" => Some(Phase::ExpectParenthesis),
+                "not" => Some(Phase::AfterNegation),
+                "as" if !in_group && state.clause == Clause::Select => Some(Phase::AfterAs),
+                "asc" | "desc" if !in_group && state.clause == Clause::OrderBy => {
+                    Some(Phase::AfterDirection)
+                }
+                _ if in_group => None,
+                _ => return self.advance_clause(state, token),
+            },
+            SqlToken::Symbol(symbol) if symbol == "," => match state.clause {
+                _ if in_group => Some(Phase::Operand { subquery: false }),
+                Clause::Select | Clause::GroupBy | Clause::OrderBy => {
+                    Some(Phase::Operand { subquery: false })
+                }
+                Clause::From => Some(Phase::Table),
+                _ => None,
+            },
+            SqlToken::Symbol(symbol) if symbol == ")" && in_group => {
+                state.stack.pop();
+                Some(Phase::AfterOperand)
+            }
+            _ if in_group => None,
+            _ => return self.advance_clause(state, token),
+        };
+        match next_phase {
+            Some(phase) => {
+                state.phase = phase;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Transitions to the next clause of the query, or to the end of the query
+    fn advance_clause(&self, state: &mut ParserState, token: &SqlToken) -> bool {
+        let word = match token {
+            SqlToken::Word(word) => word.as_str(),
+            _ => return self.advance_end_of_query(state, token),
+        };
+        let (clause, phase) = match (state.clause, word) {
+            (Clause::Select, "from") => (Clause::From, Phase::Table),
+            (Clause::From, "join") => (Clause::From, Phase::Table),
+            (Clause::From, "inner")
+            | (Clause::From, "left")
+            | (Clause::From, "right")
+            | (Clause::From, "full")
+            | (Clause::From, "cross")
+            | (Clause::From, "natural") => (Clause::From, Phase::AfterJoinType),
+            (Clause::From, "where") => (Clause::Where, Phase::Operand { subquery: false }),
+            (Clause::From, "group") | (Clause::Where, "group") => {
+                (state.clause, Phase::ExpectBy(Clause::GroupBy))
+            }
+            (Clause::GroupBy, "having") => (Clause::Having, Phase::Operand { subquery: false }),
+            (Clause::From, "order")
+            | (Clause::Where, "order")
+            | (Clause::GroupBy, "order")
+            | (Clause::Having, "order") => (state.clause, Phase::ExpectBy(Clause::OrderBy)),
+            (Clause::From, "limit")
+            | (Clause::Where, "limit")
+            | (Clause::GroupBy, "limit")
+            | (Clause::Having, "limit")
+            | (Clause::OrderBy, "limit") => (Clause::Limit, Phase::LimitValue),
+            (Clause::From, "union")
+            | (Clause::From, "intersect")
+            | (Clause::From, "except")
+            | (Clause::Where, "union")
+            | (Clause::Where, "intersect")
+            | (Clause::Where, "except")
+            | (Clause::GroupBy, "union")
+            | (Clause::GroupBy, "intersect")
+            | (Clause::GroupBy, "except")
+            | (Clause::Having, "union")
+            | (Clause::Having, "intersect")
+            | (Clause::Having, "except") => (Clause::Select, Phase::AfterSetOperator),
+            _ => return false,
+        };
+        state.clause = clause;
+        state.phase = phase;
+        true
+    }
+
+    /// Closes a sub-query or ends the query with a semicolon
+    fn advance_end_of_query(&self, state: &mut ParserState, token: &SqlToken) -> bool {
+        if state.clause == Clause::Select {
+            return false;
+        }
+        match token {
+            SqlToken::Symbol(symbol) if symbol == ")" => match state.stack.last().copied() {
+                Some(Frame::Subquery { clause, in_from }) => {
+                    state.stack.pop();
+                    state.clause = clause;
+                    state.phase = if in_from {
+                        Phase::AfterTable
+                    } else {
+                        Phase::AfterOperand
+                    };
+                    true
+                }
+                _ => false,
+            },
+            SqlToken::Symbol(symbol) if symbol == ";" && state.stack.is_empty() => {
+                state.phase = Phase::End;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// # Configuration for TextToSqlModel
+/// Contains the text generation model configuration and the prompt formatting the questions.
+pub struct TextToSqlConfig {
+    /// Configuration of the text generation model (e.g. a T5 model fine-tuned for text-to-SQL)
+    pub generation_config: TextGenerationConfig,
+    /// Prompt template with a `{question}` and a `{schema}` variable (default: `{question} | {schema}`)
+    pub prompt_template: String,
+    /// Maximum number of tokens of the generated queries (default: 128)
+    pub max_sql_tokens: i64,
+}
+
+impl TextToSqlConfig {
+    /// Instantiate a new text-to-SQL configuration with the default prompt template
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `TextGenerationConfig` of the text generation model to load
+    pub fn new(generation_config: TextGenerationConfig) -> TextToSqlConfig {
+        TextToSqlConfig {
+            generation_config,
+            prompt_template: DEFAULT_TEXT_TO_SQL_PROMPT.to_string(),
+            max_sql_tokens: 128,
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "t5"))]
+impl Default for TextToSqlConfig {
+    /// Provides a default T5 configuration, to be replaced by a model fine-tuned for text-to-SQL
+    fn default() -> TextToSqlConfig {
+        let mut generation_config = TextGenerationConfig::new(
+            ModelType::T5,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                T5ModelResources::T5_BASE,
+            ))),
+            RemoteResource::from_pretrained(T5ConfigResources::T5_BASE),
+            RemoteResource::from_pretrained(T5VocabResources::T5_BASE),
+            None,
+        );
+        generation_config.min_length = 0;
+        generation_config.num_beams = 4;
+        generation_config.do_sample = false;
+        generation_config.no_repeat_ngram_size = 0;
+        TextToSqlConfig::new(generation_config)
+    }
+}
+
+/// # TextToSqlModel to generate SQL queries from natural language questions
+pub struct TextToSqlModel {
+    model: TextGenerationOption,
+    prompt_template: PromptTemplate,
+    max_sql_tokens: i64,
+    token_continuations: Vec<(i64, String)>,
+}
+
+impl TextToSqlModel {
+    /// Build a new `TextToSqlModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TextToSqlConfig` object containing the text generation model configuration and the prompt
+    ///   template
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_to_sql::TextToSqlModel;
+    ///
+    /// let text_to_sql_model = TextToSqlModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: TextToSqlConfig) -> Result<TextToSqlModel, RustBertError> {
+        let prompt_template = PromptTemplate::new(&config.prompt_template)?;
+        let mut variables = prompt_template.variables().to_vec();
+        variables.sort();
+        if variables != ["question", "schema"] {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The text-to-SQL prompt template must have a {{question}} and a {{schema}} variable, got {:?}",
+                prompt_template.variables()
+            )));
+        }
+        let model = TextGenerationOption::new(config.generation_config)?;
+        let token_continuations = token_continuations(model.get_tokenizer());
+        Ok(TextToSqlModel {
+            model,
+            prompt_template,
+            max_sql_tokens: config.max_sql_tokens,
+            token_continuations,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Generates SQL queries answering questions over a database schema, with a decoding constrained by the SQL
+    /// grammar of the schema. Queries reaching the maximum number of tokens may be incomplete, which can be checked
+    /// with `SqlGrammar::is_complete`.
+    ///
+    /// # Arguments
+    ///
+    /// * `questions` - Natural language questions
+    /// * `schema` - Schema of the database queried
+    ///
+    /// # Returns
+    /// * `Vec<String>` Generated queries (*n_questions x num_return_sequences*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_to_sql::{SqlSchema, TextToSqlModel};
+    ///
+    /// let text_to_sql_model = TextToSqlModel::new(Default::default())?;
+    /// let schema = SqlSchema::from_ddl(
+    ///     "CREATE TABLE singer (singer_id INTEGER PRIMARY KEY, name TEXT, country TEXT, age INTEGER);",
+    /// )?;
+    /// let output = text_to_sql_model.generate(&["What is the average age of singers?"], &schema)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate<S>(
+        &self,
+        questions: &[S],
+        schema: &SqlSchema,
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if questions.is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        let description = schema.describe();
+        let prompts = questions
+            .iter()
+            .map(|question| {
+                self.prompt_template.format(&[
+                    ("question", question.as_ref()),
+                    ("schema", description.as_str()),
+                ])
+            })
+            .collect::<Result<Vec<String>, RustBertError>>()?;
+
+        let grammar = SqlGrammar::new(schema);
+        let tokenizer = self.model.get_tokenizer();
+        // The sequences passed at the first decoding step only contain the (padded) prompts, or the decoder
+        // start token for encoder-decoder models: the generated query starts after them.
+        let start_length: Cell<Option<usize>> = Cell::new(None);
+        let cache = RefCell::new(HashMap::new());
+        let allowed_tokens = |_batch_id: i64, token_ids: &Tensor| -> Vec<i64> {
+            let token_ids = token_ids.iter::<i64>().unwrap().collect::<Vec<i64>>();
+            let start = match start_length.get() {
+                Some(start) => start,
+                None => {
+                    start_length.set(Some(token_ids.len()));
+                    token_ids.len()
+                }
+            };
+            let sql = tokenizer.decode(&token_ids[start.min(token_ids.len())..], true, false);
+            self.allowed_tokens(&grammar, &sql, &mut cache.borrow_mut())
+        };
+        let generate_options = GenerateOptions {
+            max_new_tokens: Some(self.max_sql_tokens),
+            prefix_allowed_tokens_fn: Some(&allowed_tokens),
+            ..Default::default()
+        };
+        let generated = self
+            .model
+            .generate_indices_with_options(Some(&prompts), generate_options);
+
+        let start = start_length.get().unwrap_or(0);
+        Ok(generated
+            .iter()
+            .map(|token_ids| {
+                tokenizer
+                    .decode(&token_ids[start.min(token_ids.len())..], true, false)
+                    .trim()
+                    .to_string()
+            })
+            .collect())
+    }
+
+    /// Tokens continuing the query generated so far into a valid query prefix, and the end of sequence token if the
+    /// query is complete
+    fn allowed_tokens(
+        &self,
+        grammar: &SqlGrammar,
+        sql: &str,
+        cache: &mut HashMap<ParserState, AllowedWords>,
+    ) -> Vec<i64> {
+        let eos_token_id = self.model.get_tokenizer().get_eos_id();
+        let mut allowed = match grammar.parse_prefix(sql) {
+            Some((state, partial)) => {
+                let mut allowed = self
+                    .token_continuations
+                    .iter()
+                    .filter(|(_, continuation)| {
+                        grammar.accepts_continuation(
+                            &state,
+                            &format!("{partial}{continuation}"),
+                            cache,
+                        )
+                    })
+                    .map(|(token_id, _)| *token_id)
+                    .collect::<Vec<i64>>();
+                if grammar.is_complete_from(&state, &partial) {
+                    allowed.extend(eos_token_id);
+                }
+                allowed
+            }
+            None => vec![],
+        };
+        // Dead end (e.g. tokens missing from the vocabulary): end the sequence
+        if allowed.is_empty() {
+            allowed.extend(eos_token_id);
+        }
+        if allowed.is_empty() {
+            allowed = (0..self.model.get_tokenizer().get_vocab_size()).collect();
+        }
+        allowed
+    }
+}
+
+#[cfg(all(test, feature = "remote", feature = "t5"))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = TextToSqlConfig::default();
+        let _: Box<dyn Send> = Box::new(TextToSqlModel::new(config));
+    }
+}
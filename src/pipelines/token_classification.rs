@@ -112,20 +112,35 @@
 //! # ;
 //! ```
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForTokenClassification;
+#[cfg(feature = "bert")]
 use crate::bert::BertForTokenClassification;
 use crate::common::error::RustBertError;
+use crate::common::precision::{
+    is_half_precision, set_var_store_kind, with_attention_fp32_accumulation,
+};
+#[cfg(feature = "deberta")]
 use crate::deberta::DebertaForTokenClassification;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertForTokenClassification;
+#[cfg(feature = "electra")]
 use crate::electra::ElectraForTokenClassification;
+#[cfg(feature = "fnet")]
 use crate::fnet::FNetForTokenClassification;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerForTokenClassification;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForTokenClassification;
+#[cfg(feature = "modernbert")]
+use crate::modernbert::ModernBertForTokenClassification;
 use crate::pipelines::common::{
-    get_device, ConfigOption, ModelResource, ModelType, TokenizerOption,
+    check_label_mapping, get_device, ConfigOption, ModelResource, ModelType, TokenizerOption,
 };
 use crate::resources::ResourceProvider;
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForTokenClassification;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForTokenClassification;
 use ordered_float::OrderedFloat;
 use rust_tokenizers::{
@@ -138,10 +153,12 @@ use std::collections::HashMap;
 use tch::nn::VarStore;
 use tch::{no_grad, Device, Kind, Tensor};
 
+use crate::common::settings::default_device;
+#[cfg(feature = "deberta-v2")]
 use crate::deberta_v2::DebertaV2ForTokenClassification;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::{config::ONNXEnvironmentConfig, ONNXEncoder};
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bert"))]
 use crate::{
     bert::{BertConfigResources, BertModelResources, BertVocabResources},
     resources::RemoteResource,
@@ -242,6 +259,8 @@ pub struct TokenClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
     /// Sub-tokens aggregation method (default: `LabelAggregationOption::First`)
     pub label_aggregation_function: LabelAggregationOption,
     /// Batch size for predictions
@@ -283,14 +302,15 @@ impl TokenClassificationConfig {
             lower_case,
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
             label_aggregation_function,
             batch_size: 64,
         }
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bert"))]
 impl Default for TokenClassificationConfig {
     /// Provides a default CoNLL-2003 NER model (English)
     fn default() -> TokenClassificationConfig {
@@ -314,29 +334,44 @@ impl Default for TokenClassificationConfig {
 /// # Abstraction that holds one particular token sequence classifier model, for any of the supported models
 pub enum TokenClassificationOption {
     /// Bert for Token Classification
+    #[cfg(feature = "bert")]
     Bert(BertForTokenClassification),
     /// DeBERTa for Token Classification
+    #[cfg(feature = "deberta")]
     Deberta(DebertaForTokenClassification),
     /// DeBERTa V2 for Token Classification
+    #[cfg(feature = "deberta-v2")]
     DebertaV2(DebertaV2ForTokenClassification),
     /// DistilBert for Token Classification
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertForTokenClassification),
     /// MobileBert for Token Classification
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForTokenClassification),
     /// Roberta for Token Classification
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForTokenClassification),
     /// XLM Roberta for Token Classification
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForTokenClassification),
     /// Electra for Token Classification
+    #[cfg(feature = "electra")]
     Electra(ElectraForTokenClassification),
     /// Albert for Token Classification
+    #[cfg(feature = "albert")]
     Albert(AlbertForTokenClassification),
     /// XLNet for Token Classification
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForTokenClassification),
     /// Longformer for Token Classification
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForTokenClassification),
     /// FNet for Token Classification
+    #[cfg(feature = "fnet")]
     FNet(FNetForTokenClassification),
+    /// ModernBERT for Token Classification
+    #[cfg(feature = "modernbert")]
+    ModernBert(ModernBertForTokenClassification),
     /// ONNX model for Token Classification
     #[cfg(feature = "onnx")]
     ONNX(ONNXEncoder),
@@ -365,6 +400,7 @@ impl TokenClassificationOption {
             &ConfigOption::from_file(config.model_type, config.config_resource.get_local_path()?);
         let model_type = config.model_type;
         let model = match model_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(Self::Bert(
@@ -376,6 +412,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta")]
             ModelType::Deberta => {
                 if let ConfigOption::Deberta(config) = model_config {
                     Ok(Self::Deberta(
@@ -387,6 +424,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta-v2")]
             ModelType::DebertaV2 => {
                 if let ConfigOption::DebertaV2(config) = model_config {
                     Ok(Self::DebertaV2(
@@ -398,6 +436,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => {
                 if let ConfigOption::DistilBert(config) = model_config {
                     Ok(Self::DistilBert(
@@ -409,6 +448,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
                 if let ConfigOption::MobileBert(config) = model_config {
                     Ok(Self::MobileBert(
@@ -420,6 +460,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
                 if let ConfigOption::Roberta(config) = model_config {
                     Ok(Self::Roberta(
@@ -431,6 +472,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
                 if let ConfigOption::Roberta(config) = model_config {
                     Ok(Self::XLMRoberta(
@@ -442,6 +484,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "electra")]
             ModelType::Electra => {
                 if let ConfigOption::Electra(config) = model_config {
                     Ok(Self::Electra(
@@ -453,6 +496,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
                 if let ConfigOption::Albert(config) = model_config {
                     Ok(Self::Albert(
@@ -464,6 +508,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
                 if let ConfigOption::XLNet(config) = model_config {
                     Ok(Self::XLNet(
@@ -475,6 +520,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
                 if let ConfigOption::Longformer(config) = model_config {
                     Ok(Self::Longformer(
@@ -486,6 +532,7 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "fnet")]
             ModelType::FNet => {
                 if let ConfigOption::FNet(config) = model_config {
                     Ok(Self::FNet(
@@ -497,6 +544,18 @@ impl TokenClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "modernbert")]
+            ModelType::ModernBert => {
+                if let ConfigOption::ModernBert(config) = model_config {
+                    Ok(Self::ModernBert(
+                        ModernBertForTokenClassification::new(var_store.root(), config)?,
+                    ))
+                } else {
+                    Err(RustBertError::InvalidConfigurationError(
+                        "You can only supply a ModernBertConfig for ModernBERT!".to_string(),
+                    ))
+                }
+            }
             #[cfg(feature = "onnx")]
             ModelType::ONNX => Err(RustBertError::InvalidConfigurationError(
                 "A `ModelType::ONNX` ModelType was provided in the configuration with `ModelResources::TORCH`, these are incompatible".to_string(),
@@ -505,7 +564,10 @@ impl TokenClassificationOption {
                 "Token classification not implemented for {model_type:?}!"
             ))),
         }?;
-        var_store.load(weights_path)?;
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+        if let Some(kind) = config.kind {
+            set_var_store_kind(&mut var_store, kind)?;
+        }
         Ok(model)
     }
 
@@ -529,21 +591,54 @@ impl TokenClassificationOption {
         )?))
     }
 
+    /// Quantizes the linear layers of the model to int8 for CPU inference (see the `quantization` module). Only
+    /// available for BERT, DistilBERT and RoBERTa models.
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref mut model_ref) => model_ref.quantize_dynamic(),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref mut model_ref) => model_ref.quantize_dynamic(),
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref mut model_ref) | Self::XLMRoberta(ref mut model_ref) => {
+                model_ref.quantize_dynamic()
+            }
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Dynamic int8 quantization not implemented for {:?}",
+                self.model_type()
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this TokenClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => ModelType::Deberta,
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(_) => ModelType::DebertaV2,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::XLMRoberta,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "electra")]
             Self::Electra(_) => ModelType::Electra,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
+            #[cfg(feature = "fnet")]
             Self::FNet(_) => ModelType::FNet,
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(_) => ModelType::ModernBert,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
         }
@@ -559,6 +654,7 @@ impl TokenClassificationOption {
         train: bool,
     ) -> Tensor {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -571,6 +667,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "deberta")]
             Self::Deberta(ref model) => {
                 model
                     .forward_t(
@@ -584,6 +681,7 @@ impl TokenClassificationOption {
                     .expect("Error in DeBERTa forward_t")
                     .logits
             }
+            #[cfg(feature = "deberta-v2")]
             Self::DebertaV2(ref model) => {
                 model
                     .forward_t(
@@ -597,18 +695,21 @@ impl TokenClassificationOption {
                     .expect("Error in DeBERTa V2 forward_t")
                     .logits
             }
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(ref model) => {
                 model
                     .forward_t(input_ids, mask, input_embeds, train)
                     .expect("Error in distilbert forward_t")
                     .logits
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 model
                     .forward_t(input_ids, None, None, input_embeds, mask, train)
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -621,6 +722,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "electra")]
             Self::Electra(ref model) => {
                 model
                     .forward_t(
@@ -633,6 +735,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 model
                     .forward_t(
@@ -645,6 +748,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 model
                     .forward_t(
@@ -659,6 +763,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 model
                     .forward_t(
@@ -673,12 +778,20 @@ impl TokenClassificationOption {
                     .expect("Error in longformer forward_t")
                     .logits
             }
+            #[cfg(feature = "fnet")]
             Self::FNet(ref model) => {
                 model
                     .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
                     .expect("Error in fnet forward_t")
                     .logits
             }
+            #[cfg(feature = "modernbert")]
+            Self::ModernBert(ref model) => {
+                model
+                    .forward_t(input_ids, mask, input_embeds, train)
+                    .expect("Error in modernbert forward_t")
+                    .logits
+            }
             #[cfg(feature = "onnx")]
             Self::ONNX(ref model) => model
                 .forward(input_ids, mask, token_type_ids, position_ids, input_embeds)
@@ -698,6 +811,7 @@ pub struct TokenClassificationModel {
     label_aggregation_function: LabelAggregationOption,
     max_length: usize,
     batch_size: usize,
+    attention_fp32_accumulation: bool,
 }
 
 impl TokenClassificationModel {
@@ -778,6 +892,7 @@ impl TokenClassificationModel {
             .map(|v| v as usize)
             .unwrap_or(usize::MAX);
         let label_mapping = model_config.get_label_mapping().clone();
+        check_label_mapping(&label_mapping)?;
         let batch_size = config.batch_size;
         let device = get_device(config.model_resource, config.device);
         Ok(TokenClassificationModel {
@@ -788,6 +903,7 @@ impl TokenClassificationModel {
             label_aggregation_function,
             max_length,
             batch_size,
+            attention_fp32_accumulation: config.kind.map_or(false, is_half_precision),
         })
     }
 
@@ -801,20 +917,16 @@ impl TokenClassificationModel {
         &mut self.tokenizer
     }
 
-    fn generate_features<S>(&self, input: S, example_index: usize) -> Vec<InputFeature>
-    where
-        S: AsRef<str>,
-    {
-        let tokenized_input = self.tokenizer.tokenize_with_offsets(input.as_ref());
-        let encoded_input = TokenIdsWithOffsets {
-            ids: self
-                .tokenizer
-                .convert_tokens_to_ids(&tokenized_input.tokens),
-            offsets: tokenized_input.offsets,
-            reference_offsets: tokenized_input.reference_offsets,
-            masks: tokenized_input.masks,
-        };
+    /// Quantizes the linear layers of the model to int8, with a dynamic quantization of the activations, for faster
+    /// CPU inference. Only available for BERT, DistilBERT and RoBERTa models placed on the CPU (see the `quantization`
+    /// module).
+    pub fn quantize_dynamic(&mut self) -> Result<(), RustBertError> {
+        self.token_sequence_classifier.quantize_dynamic()
+    }
 
+    /// Maximum number of tokens of an input processed in a single pass, excluding the special tokens added by the
+    /// tokenizer. Longer inputs are split in overlapping spans.
+    pub(crate) fn max_content_length(&self) -> usize {
         let sequence_added_tokens = self
             .tokenizer
             .build_input_with_special_tokens(
@@ -828,8 +940,24 @@ impl TokenClassificationModel {
             )
             .token_ids
             .len();
+        self.max_length - sequence_added_tokens
+    }
 
-        let max_content_length = self.max_length - sequence_added_tokens;
+    fn generate_features<S>(&self, input: S, example_index: usize) -> Vec<InputFeature>
+    where
+        S: AsRef<str>,
+    {
+        let tokenized_input = self.tokenizer.tokenize_with_offsets(input.as_ref());
+        let encoded_input = TokenIdsWithOffsets {
+            ids: self
+                .tokenizer
+                .convert_tokens_to_ids(&tokenized_input.tokens),
+            offsets: tokenized_input.offsets,
+            reference_offsets: tokenized_input.reference_offsets,
+            masks: tokenized_input.masks,
+        };
+
+        let max_content_length = self.max_content_length();
         let doc_stride = self.max_length / 4;
 
         let mut spans: Vec<InputFeature> = vec![];
@@ -972,60 +1100,62 @@ impl TokenClassificationModel {
         while start < len_features {
             let end = start + min(len_features - start, self.batch_size);
 
-            no_grad(|| {
-                let batch_features = &mut features[start..end];
-                let (input_ids, attention_masks, token_type_ids) =
-                    self.pad_features(batch_features);
-                let output = self.token_sequence_classifier.forward_t(
-                    Some(&input_ids),
-                    Some(&attention_masks),
-                    Some(&token_type_ids),
-                    None,
-                    None,
-                    false,
-                );
-                let score = output.exp()
-                    / output
-                        .exp()
-                        .sum_dim_intlist([-1].as_slice(), true, Kind::Float);
-                let label_indices = score.argmax(-1, true);
-                for sentence_idx in 0..label_indices.size()[0] {
-                    let labels = label_indices.get(sentence_idx);
-                    let feature = &features[sentence_idx as usize];
-                    let sentence_reference_flag = &feature.reference_feature;
-                    let original_chars = input[feature.example_index]
-                        .as_ref()
-                        .chars()
-                        .collect::<Vec<char>>();
-                    let mut word_idx: u16 = 0;
-                    for position_idx in sentence_reference_flag
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, flag)| **flag)
-                        .map(|(pos, _)| pos)
-                    {
-                        let mask = feature.mask[position_idx];
-                        if (mask == Mask::Special) & (!return_special) {
-                            continue;
-                        }
-                        if !(mask == Mask::Continuation) {
-                            word_idx += 1;
+            with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                no_grad(|| {
+                    let batch_features = &mut features[start..end];
+                    let (input_ids, attention_masks, token_type_ids) =
+                        self.pad_features(batch_features);
+                    let output = self.token_sequence_classifier.forward_t(
+                        Some(&input_ids),
+                        Some(&attention_masks),
+                        Some(&token_type_ids),
+                        None,
+                        None,
+                        false,
+                    );
+                    let score = output.exp()
+                        / output
+                            .exp()
+                            .sum_dim_intlist([-1].as_slice(), true, Kind::Float);
+                    let label_indices = score.argmax(-1, true);
+                    for sentence_idx in 0..label_indices.size()[0] {
+                        let labels = label_indices.get(sentence_idx);
+                        let feature = &features[sentence_idx as usize];
+                        let sentence_reference_flag = &feature.reference_feature;
+                        let original_chars = input[feature.example_index]
+                            .as_ref()
+                            .chars()
+                            .collect::<Vec<char>>();
+                        let mut word_idx: u16 = 0;
+                        for position_idx in sentence_reference_flag
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, flag)| **flag)
+                            .map(|(pos, _)| pos)
+                        {
+                            let mask = feature.mask[position_idx];
+                            if (mask == Mask::Special) & (!return_special) {
+                                continue;
+                            }
+                            if !(mask == Mask::Continuation) {
+                                word_idx += 1;
+                            }
+                            let token = {
+                                self.decode_token(
+                                    &original_chars,
+                                    feature,
+                                    &input_ids,
+                                    &labels,
+                                    &score,
+                                    sentence_idx,
+                                    position_idx as i64,
+                                    word_idx,
+                                )
+                            };
+                            example_tokens_map[feature.example_index].push(token);
                         }
-                        let token = {
-                            self.decode_token(
-                                &original_chars,
-                                feature,
-                                &input_ids,
-                                &labels,
-                                &score,
-                                sentence_idx,
-                                position_idx as i64,
-                                word_idx,
-                            )
-                        };
-                        example_tokens_map[feature.example_index].push(token);
                     }
-                }
+                })
             });
             start = end;
         }
@@ -0,0 +1,481 @@
+// Copyright 2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Tool (function) calling for conversations
+//! Declares tools with a JSON schema describing their parameters, renders them into the system prompt
+//! of a conversation following a model prompt convention, parses and validates the tool calls generated
+//! by the model and feeds the tool results back into the conversation.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::conversation::{ConversationManager, ConversationModel};
+//! use rust_bert::pipelines::tool_calling::{Tool, ToolFormat, ToolResult, ToolSet};
+//! use serde::Deserialize;
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize)]
+//! struct WeatherArguments {
+//!     city: String,
+//! }
+//!
+//! let tools = ToolSet::new(
+//!     vec![Tool::new(
+//!         "get_weather",
+//!         "Get the current weather in a given city",
+//!         json!({
+//!             "type": "object",
+//!             "properties": {"city": {"type": "string"}},
+//!             "required": ["city"]
+//!         }),
+//!     )],
+//!     ToolFormat::Hermes,
+//! );
+//!
+//! let conversation_model = ConversationModel::new(Default::default())?;
+//! let mut conversation_manager = ConversationManager::new();
+//! let conversation_id = conversation_manager.create("What is the weather like in Paris?");
+//! tools.attach(conversation_manager.get(&conversation_id).unwrap());
+//!
+//! let output = conversation_model.generate_responses(&mut conversation_manager);
+//! let tool_calls = tools.parse(output.get(&conversation_id).unwrap())?;
+//! for tool_call in tool_calls {
+//!     let arguments: WeatherArguments = tool_call.parse_arguments()?;
+//!     let result = ToolResult::new(&tool_call.name, json!({"temperature": 21, "city": arguments.city}));
+//!     tools.add_tool_results(conversation_manager.get(&conversation_id).unwrap(), &[result])?;
+//! }
+//! let output = conversation_model.generate_responses(&mut conversation_manager);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::conversation::Conversation;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// # Tool declaration
+/// Tool (function) that can be called by the model. The parameters are described by a JSON schema object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tool {
+    /// Name of the tool, used by the model to refer to it
+    pub name: String,
+    /// Description of the tool, helping the model decide when to call it
+    pub description: String,
+    /// JSON schema of the tool parameters (`object` type with `properties` and `required` fields)
+    pub parameters: Value,
+}
+
+impl Tool {
+    /// Build a new `Tool`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the tool
+    /// * `description` - Description of the tool
+    /// * `parameters` - JSON schema of the tool parameters
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::tool_calling::Tool;
+    /// use serde_json::json;
+    ///
+    /// let tool = Tool::new(
+    ///     "get_weather",
+    ///     "Get the current weather in a given city",
+    ///     json!({
+    ///         "type": "object",
+    ///         "properties": {"city": {"type": "string"}},
+    ///         "required": ["city"]
+    ///     }),
+    /// );
+    /// ```
+    pub fn new(name: &str, description: &str, parameters: Value) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+        }
+    }
+
+    /// Validates the arguments of a tool call against the parameters schema of the tool.
+    /// Checks the presence of required parameters, rejects unknown parameters and verifies
+    /// the JSON type of the parameters declared in the schema.
+    pub fn validate(&self, tool_call: &ToolCall) -> Result<(), RustBertError> {
+        let arguments = tool_call.arguments.as_object().ok_or_else(|| {
+            RustBertError::ValueError(format!(
+                "Arguments of the call to {} must be a JSON object",
+                self.name
+            ))
+        })?;
+        let properties = self.parameters.get("properties").and_then(Value::as_object);
+
+        if let Some(required) = self.parameters.get("required").and_then(Value::as_array) {
+            for parameter in required.iter().filter_map(Value::as_str) {
+                if !arguments.contains_key(parameter) {
+                    return Err(RustBertError::ValueError(format!(
+                        "Missing required argument {parameter} in the call to {}",
+                        self.name
+                    )));
+                }
+            }
+        }
+
+        if let Some(properties) = properties {
+            for (parameter, value) in arguments {
+                let schema = properties.get(parameter).ok_or_else(|| {
+                    RustBertError::ValueError(format!(
+                        "Unknown argument {parameter} in the call to {}",
+                        self.name
+                    ))
+                })?;
+                if let Some(expected_type) = schema.get("type").and_then(Value::as_str) {
+                    if !matches_json_type(value, expected_type) {
+                        return Err(RustBertError::ValueError(format!(
+                            "Argument {parameter} in the call to {} should be of type {expected_type}",
+                            self.name
+                        )));
+                    }
+                }
+                if let Some(allowed_values) = schema.get("enum").and_then(Value::as_array) {
+                    if !allowed_values.contains(value) {
+                        return Err(RustBertError::ValueError(format!(
+                            "Argument {parameter} in the call to {} is not one of the allowed values",
+                            self.name
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn matches_json_type(value: &Value, expected_type: &str) -> bool {
+    match expected_type {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// # Tool call generated by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Name of the tool called
+    pub name: String,
+    /// Arguments of the call (JSON object)
+    #[serde(default, alias = "parameters")]
+    pub arguments: Value,
+}
+
+impl ToolCall {
+    /// Deserializes the arguments of the call into a typed structure
+    ///
+    /// # Returns
+    ///
+    /// * `T` typed arguments of the tool call
+    pub fn parse_arguments<T>(&self) -> Result<T, RustBertError>
+    where
+        T: DeserializeOwned,
+    {
+        serde_json::from_value(self.arguments.clone()).map_err(|error| {
+            RustBertError::ValueError(format!(
+                "Invalid arguments for the call to {}: {error}",
+                self.name
+            ))
+        })
+    }
+}
+
+/// # Result of a tool execution, fed back to the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    /// Name of the tool executed
+    pub name: String,
+    /// Output of the tool
+    pub content: Value,
+}
+
+impl ToolResult {
+    /// Build a new `ToolResult`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the tool executed
+    /// * `content` - JSON output of the tool
+    pub fn new(name: &str, content: Value) -> ToolResult {
+        ToolResult {
+            name: name.to_string(),
+            content,
+        }
+    }
+}
+
+/// # Prompt convention used to declare tools, and for the tool calls and results
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolFormat {
+    /// Tools declared as a JSON list, tool calls generated as bare JSON objects
+    /// `{"name": ..., "arguments": ...}` and results returned as JSON objects
+    Json,
+    /// Hermes / Qwen convention: tools declared between `<tools></tools>` tags, calls generated between
+    /// `<tool_call></tool_call>` tags and results returned between `<tool_response></tool_response>` tags
+    Hermes,
+    /// Mistral convention: tools declared between `[AVAILABLE_TOOLS]` and `[/AVAILABLE_TOOLS]`, calls generated as
+    /// a JSON list after the `[TOOL_CALLS]` marker and results returned between `[TOOL_RESULTS]` and `[/TOOL_RESULTS]`
+    Mistral,
+}
+
+/// # Set of tools available to the model
+pub struct ToolSet {
+    tools: Vec<Tool>,
+    format: ToolFormat,
+}
+
+impl ToolSet {
+    /// Build a new `ToolSet`
+    ///
+    /// # Arguments
+    ///
+    /// * `tools` - Tools available to the model
+    /// * `format` - `ToolFormat` prompt convention of the model
+    pub fn new(tools: Vec<Tool>, format: ToolFormat) -> ToolSet {
+        ToolSet { tools, format }
+    }
+
+    /// Returns the tools available to the model
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Renders the tools declaration as a system prompt, following the prompt convention of the model
+    pub fn render_prompt(&self) -> String {
+        let declarations = self
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": tool.parameters,
+                    }
+                })
+            })
+            .collect::<Vec<Value>>();
+
+        match self.format {
+            ToolFormat::Json => format!(
+                "You have access to the following tools:\n{}\nTo call a tool, respond with a JSON object \
+                 with the following format: {{\"name\": <tool name>, \"arguments\": <arguments object>}}",
+                Value::Array(declarations)
+            ),
+            ToolFormat::Hermes => {
+                let declarations = declarations
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!(
+                    "You are a function calling AI model. You are provided with function signatures \
+                     within <tools></tools> XML tags. You may call one or more functions to assist \
+                     with the user query. Here are the available tools: <tools>\n{declarations}\n</tools>\n\
+                     For each function call return a json object with function name and arguments within \
+                     <tool_call></tool_call> XML tags:\n<tool_call>\n{{\"name\": <function-name>, \
+                     \"arguments\": <args-json-object>}}\n</tool_call>"
+                )
+            }
+            ToolFormat::Mistral => format!(
+                "[AVAILABLE_TOOLS]{}[/AVAILABLE_TOOLS]",
+                Value::Array(declarations)
+            ),
+        }
+    }
+
+    /// Declares the tools in the system prompt of a conversation. An existing system prompt
+    /// is preserved and the tools declaration is appended to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation` - `&mut Conversation` conversation to declare the tools in
+    pub fn attach(&self, conversation: &mut Conversation) {
+        let system_prompt = match &conversation.system_prompt {
+            Some(system_prompt) => format!("{system_prompt}\n{}", self.render_prompt()),
+            None => self.render_prompt(),
+        };
+        conversation.set_system_prompt(&system_prompt);
+    }
+
+    /// Parses the tool calls from a model output and validates them against the declared tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Text generated by the model
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<ToolCall>` tool calls found in the output (empty if the model answered without calling a tool)
+    pub fn parse(&self, output: &str) -> Result<Vec<ToolCall>, RustBertError> {
+        let tool_calls = parse_tool_calls(output, self.format)?;
+        for tool_call in &tool_calls {
+            let tool = self
+                .tools
+                .iter()
+                .find(|tool| tool.name == tool_call.name)
+                .ok_or_else(|| {
+                    RustBertError::ValueError(format!("Unknown tool {}", tool_call.name))
+                })?;
+            tool.validate(tool_call)?;
+        }
+        Ok(tool_calls)
+    }
+
+    /// Renders tool results following the prompt convention of the model
+    pub fn render_tool_results(&self, results: &[ToolResult]) -> String {
+        let results = results
+            .iter()
+            .map(|result| json!({"name": result.name, "content": result.content}))
+            .collect::<Vec<Value>>();
+        match self.format {
+            ToolFormat::Json => results
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<String>>()
+                .join("\n"),
+            ToolFormat::Hermes => results
+                .iter()
+                .map(|result| format!("<tool_response>\n{result}\n</tool_response>"))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            ToolFormat::Mistral => {
+                format!("[TOOL_RESULTS]{}[/TOOL_RESULTS]", Value::Array(results))
+            }
+        }
+    }
+
+    /// Feeds tool results back into a conversation, as the next input to process by the model.
+    /// Returns an error if an unprocessed user input already exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation` - `&mut Conversation` conversation in which the tools were called
+    /// * `results` - `&[ToolResult]` results of the tool calls
+    pub fn add_tool_results(
+        &self,
+        conversation: &mut Conversation,
+        results: &[ToolResult],
+    ) -> Result<(), RustBertError> {
+        conversation.add_user_input(&self.render_tool_results(results))
+    }
+}
+
+/// Parses the tool calls from a model output following a prompt convention.
+/// The output is scanned for JSON objects (or lists of objects) containing a `name` field.
+///
+/// # Arguments
+///
+/// * `output` - Text generated by the model
+/// * `format` - `ToolFormat` prompt convention of the model
+///
+/// # Returns
+///
+/// * `Vec<ToolCall>` tool calls found in the output
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::pipelines::tool_calling::{parse_tool_calls, ToolFormat};
+///
+/// let output = r#"<tool_call>{"name": "get_weather", "arguments": {"city": "Paris"}}</tool_call>"#;
+/// let tool_calls = parse_tool_calls(output, ToolFormat::Hermes).unwrap();
+/// ```
+pub fn parse_tool_calls(output: &str, format: ToolFormat) -> Result<Vec<ToolCall>, RustBertError> {
+    let segments: Vec<&str> = match format {
+        ToolFormat::Json => vec![output],
+        ToolFormat::Hermes => output
+            .split("<tool_call>")
+            .skip(1)
+            .map(|segment| segment.split("</tool_call>").next().unwrap_or(segment))
+            .collect(),
+        ToolFormat::Mistral => output
+            .split_once("[TOOL_CALLS]")
+            .map(|(_, calls)| vec![calls])
+            .unwrap_or_default(),
+    };
+
+    let mut tool_calls = vec![];
+    for segment in segments {
+        for value in extract_json_values(segment) {
+            let values = match value {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                if value.get("name").is_some() {
+                    let tool_call: ToolCall = serde_json::from_value(value).map_err(|error| {
+                        RustBertError::ValueError(format!("Invalid tool call: {error}"))
+                    })?;
+                    tool_calls.push(tool_call);
+                }
+            }
+        }
+    }
+    Ok(tool_calls)
+}
+
+fn extract_json_values(text: &str) -> Vec<Value> {
+    // Scans the text for balanced JSON objects or arrays, skipping the content of strings
+    let mut values = vec![];
+    let mut depth = 0usize;
+    let mut start = None;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (position, character) in text.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if character == '\\' {
+                escaped = true;
+            } else if character == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match character {
+            '"' if depth > 0 => in_string = true,
+            '{' | '[' => {
+                if depth == 0 {
+                    start = Some(position);
+                }
+                depth += 1;
+            }
+            '}' | ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(start) = start.take() {
+                        if let Ok(value) = serde_json::from_str(&text[start..=position]) {
+                            values.push(value);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    values
+}
@@ -0,0 +1,277 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::translation::Language;
+use std::ops::Range;
+
+/// Sentence terminators that do not require a following white space (e.g. Chinese or Japanese full stops)
+const CJK_TERMINATORS: [char; 6] = ['。', '！', '？', '｡', '．', '︒'];
+/// Sentence terminators followed by a white space at the end of a sentence
+const TERMINATORS: [char; 9] = ['.', '!', '?', '…', '‼', '।', '॥', '؟', '۔'];
+/// Closing quotes and brackets belonging to the sentence they end
+const CLOSING_PUNCTUATION: [char; 9] = ['"', '\'', '”', '’', '»', '›', ')', ']', '」'];
+/// Clause separators used to split sentences too long for the model
+const CLAUSE_SEPARATORS: [char; 7] = [',', ';', ':', '，', '；', '：', '、'];
+
+/// Maximum number of tokens of the translated segments for models without a limited context window
+pub(crate) const DEFAULT_MAX_SEGMENT_TOKENS: usize = 256;
+
+const COMMON_ABBREVIATIONS: [&str; 8] = ["dr", "prof", "st", "etc", "ca", "cf", "vs", "nr"];
+const ENGLISH_ABBREVIATIONS: [&str; 18] = [
+    "mr", "mrs", "ms", "jr", "sr", "e.g", "i.e", "inc", "ltd", "co", "corp", "fig", "approx",
+    "dept", "est", "mt", "jan", "feb",
+];
+const FRENCH_ABBREVIATIONS: [&str; 8] = ["m", "mme", "mlle", "mm", "pr", "p.ex", "av", "env"];
+const GERMAN_ABBREVIATIONS: [&str; 10] = [
+    "z.b", "bzw", "usw", "vgl", "str", "hr", "fr", "bspw", "ggf", "u.a",
+];
+const SPANISH_ABBREVIATIONS: [&str; 8] = ["sr", "sra", "srta", "dra", "ud", "uds", "p.ej", "av"];
+const ITALIAN_ABBREVIATIONS: [&str; 6] = ["sig", "sigg", "dott", "ing", "avv", "pag"];
+const PORTUGUESE_ABBREVIATIONS: [&str; 5] = ["sr", "sra", "dra", "pág", "av"];
+const DUTCH_ABBREVIATIONS: [&str; 5] = ["dhr", "mevr", "bijv", "o.a", "blz"];
+
+fn language_abbreviations(language: Option<Language>) -> &'static [&'static str] {
+    match language.and_then(|language| language.get_iso_639_1_code()) {
+        Some("en") => &ENGLISH_ABBREVIATIONS,
+        Some("fr") => &FRENCH_ABBREVIATIONS,
+        Some("de") => &GERMAN_ABBREVIATIONS,
+        Some("es") => &SPANISH_ABBREVIATIONS,
+        Some("it") => &ITALIAN_ABBREVIATIONS,
+        Some("pt") => &PORTUGUESE_ABBREVIATIONS,
+        Some("nl") => &DUTCH_ABBREVIATIONS,
+        _ => &[],
+    }
+}
+
+/// Checks if the word preceding a full stop is an abbreviation or an initial (e.g. "Dr." or "J.")
+fn is_abbreviation(word: &str, language: Option<Language>) -> bool {
+    let word = word
+        .trim_start_matches(|character: char| !character.is_alphanumeric())
+        .trim_end_matches('.')
+        .to_lowercase();
+    if word.chars().count() == 1 && word.chars().all(char::is_alphabetic) {
+        return true;
+    }
+    COMMON_ABBREVIATIONS.contains(&word.as_str())
+        || language_abbreviations(language).contains(&word.as_str())
+        || (language.is_none() && ENGLISH_ABBREVIATIONS.contains(&word.as_str()))
+}
+
+/// Splits a text into sentences. Returns the byte ranges of the sentences in the text, excluding the white spaces
+/// between sentences: the text between the ranges can be kept as is to preserve the formatting of the text when
+/// replacing the sentences (e.g. by their translation). Line breaks always end a sentence.
+///
+/// The splitting is rule based: sentences end with a terminating punctuation followed by a white space (or with a
+/// terminating punctuation not requiring a white space, such as Chinese or Japanese full stops), except for the
+/// abbreviations of the language provided and for full stops followed by a lower case word.
+///
+/// # Arguments
+///
+/// * `text` - Text to split
+/// * `language` - Optional language of the text, used to recognize abbreviations
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::pipelines::translation::{split_sentences, Language};
+///
+/// let text = "Dr. Smith arrived. He was late!";
+/// let sentences = split_sentences(text, Some(Language::English))
+///     .into_iter()
+///     .map(|range| &text[range])
+///     .collect::<Vec<&str>>();
+/// assert_eq!(sentences, ["Dr. Smith arrived.", "He was late!"]);
+/// ```
+pub fn split_sentences(text: &str, language: Option<Language>) -> Vec<Range<usize>> {
+    let mut sentences = vec![];
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        split_line(
+            text,
+            line_start,
+            line_start + line.len(),
+            language,
+            &mut sentences,
+        );
+        line_start += line.len();
+    }
+    sentences
+}
+
+fn split_line(
+    text: &str,
+    start: usize,
+    end: usize,
+    language: Option<Language>,
+    sentences: &mut Vec<Range<usize>>,
+) {
+    let line = &text[start..end];
+    let characters = line.char_indices().collect::<Vec<(usize, char)>>();
+    let mut sentence_start: Option<usize> = None;
+    let mut index = 0;
+    while index < characters.len() {
+        let (offset, character) = characters[index];
+        if sentence_start.is_none() {
+            if !character.is_whitespace() {
+                sentence_start = Some(offset);
+            }
+            index += 1;
+            continue;
+        }
+        let is_cjk_terminator = CJK_TERMINATORS.contains(&character);
+        if !is_cjk_terminator && !TERMINATORS.contains(&character) {
+            index += 1;
+            continue;
+        }
+
+        // Extend the sentence over repeated terminators and closing punctuation
+        let mut next = index + 1;
+        while next < characters.len()
+            && (TERMINATORS.contains(&characters[next].1)
+                || CJK_TERMINATORS.contains(&characters[next].1)
+                || CLOSING_PUNCTUATION.contains(&characters[next].1))
+        {
+            next += 1;
+        }
+        let sentence_end = characters
+            .get(next)
+            .map_or(line.len(), |(offset, _)| *offset);
+        let next_word = line[sentence_end..].trim_start();
+        let is_boundary = if is_cjk_terminator {
+            true
+        } else if next < characters.len() && !characters[next].1.is_whitespace() {
+            false
+        } else if next_word.is_empty() {
+            true
+        } else if next_word.starts_with(char::is_lowercase) {
+            false
+        } else if character == '.' && next == index + 1 {
+            let sentence = &line[sentence_start.unwrap()..offset];
+            let word = sentence
+                .rsplit(char::is_whitespace)
+                .next()
+                .unwrap_or(sentence);
+            !is_abbreviation(word, language)
+        } else {
+            true
+        };
+
+        if is_boundary {
+            sentences.push(start + sentence_start.unwrap()..start + sentence_end);
+            sentence_start = None;
+        }
+        index = next;
+    }
+    if let Some(sentence_start) = sentence_start {
+        let sentence = line[sentence_start..].trim_end();
+        if !sentence.is_empty() {
+            sentences.push(start + sentence_start..start + sentence_start + sentence.len());
+        }
+    }
+}
+
+/// Splits the segments longer than `max_tokens` tokens at clause separators, and at word boundaries if a clause is
+/// still too long. The pieces are packed greedily into segments of at most `max_tokens` tokens.
+pub(crate) fn split_long_segments(
+    text: &str,
+    segments: Vec<Range<usize>>,
+    tokenizer: &TokenizerOption,
+    max_tokens: usize,
+) -> Vec<Range<usize>> {
+    let token_count = |range: &Range<usize>| tokenizer.tokenize(&text[range.clone()]).len();
+    let mut output = Vec::with_capacity(segments.len());
+    for segment in segments {
+        if token_count(&segment) <= max_tokens {
+            output.push(segment);
+            continue;
+        }
+        for clause in pack(
+            split_at(text, segment, |character| {
+                CLAUSE_SEPARATORS.contains(&character)
+            }),
+            &token_count,
+            max_tokens,
+        ) {
+            if token_count(&clause) <= max_tokens {
+                output.push(clause);
+            } else {
+                output.extend(pack(
+                    split_at(text, clause, |_| false),
+                    &token_count,
+                    max_tokens,
+                ));
+            }
+        }
+    }
+    output
+}
+
+/// Splits a range of text after the separators followed by a white space (or at white spaces if no separator
+/// matches), excluding the white spaces from the pieces.
+fn split_at<F>(text: &str, range: Range<usize>, is_separator: F) -> Vec<Range<usize>>
+where
+    F: Fn(char) -> bool,
+{
+    let is_word_split = !text[range.clone()].chars().any(&is_separator);
+    let mut pieces = vec![];
+    let mut piece_start: Option<usize> = None;
+    let mut previous: Option<char> = None;
+    for (offset, character) in text[range.clone()].char_indices() {
+        let offset = range.start + offset;
+        if character.is_whitespace() {
+            if let Some(start) = piece_start {
+                if is_word_split || previous.map_or(false, &is_separator) {
+                    pieces.push(start..offset);
+                    piece_start = None;
+                }
+            }
+        } else if piece_start.is_none() {
+            piece_start = Some(offset);
+        }
+        previous = Some(character);
+    }
+    if let Some(start) = piece_start {
+        let end = range.start + text[range.clone()].trim_end().len();
+        pieces.push(start..end);
+    }
+    pieces
+}
+
+/// Packs consecutive pieces of text into ranges of at most `max_tokens` tokens (a single piece may exceed the limit)
+fn pack<F>(pieces: Vec<Range<usize>>, token_count: &F, max_tokens: usize) -> Vec<Range<usize>>
+where
+    F: Fn(&Range<usize>) -> usize,
+{
+    let mut packed: Vec<Range<usize>> = vec![];
+    for piece in pieces {
+        match packed.last_mut() {
+            Some(last) if token_count(&(last.start..piece.end)) <= max_tokens => {
+                last.end = piece.end
+            }
+            _ => packed.push(piece),
+        }
+    }
+    packed
+}
+
+/// Replaces the segments of a text by their translation, keeping the text between the segments unchanged
+pub(crate) fn reassemble(text: &str, segments: &[Range<usize>], translations: &[String]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut position = 0;
+    for (segment, translation) in segments.iter().zip(translations.iter()) {
+        output.push_str(&text[position..segment.start]);
+        output.push_str(translation);
+        position = segment.end;
+    }
+    output.push_str(&text[position..]);
+    output
+}
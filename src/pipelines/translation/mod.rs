@@ -87,10 +87,62 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! The quality of translations can be estimated without reference translation by a `QualityEstimationModel`
+//! (cross-encoder with a regression head, in the style of COMET-QE). The model can be attached to a `TranslationModel`
+//! to flag low-confidence translations for human review:
+//!
+//! ```no_run
+//! use rust_bert::pipelines::translation::{
+//!     Language, QualityEstimationModel, TranslationModelBuilder,
+//! };
+//! fn main() -> anyhow::Result<()> {
+//!     let mut model = TranslationModelBuilder::new()
+//!         .with_source_languages(vec![Language::English])
+//!         .with_target_languages(vec![Language::Spanish])
+//!         .create_model()?;
+//!     model.set_quality_estimation(QualityEstimationModel::new(Default::default())?, 0.5);
+//!
+//!     let output = model.translate_with_quality_estimation(
+//!         &["The dog did not wake up."],
+//!         None,
+//!         Language::Spanish,
+//!     )?;
+//!     for translation in output.iter().filter(|translation| translation.needs_review) {
+//!         println!("To review: {} ({:.2})", translation.text, translation.score);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Documents longer than the context window of the model can be translated with `translate_documents`: the documents
+//! are split into sentences, translated in batches and reassembled, preserving the line breaks and white spaces
+//! between sentences.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+//! fn main() -> anyhow::Result<()> {
+//!     let model = TranslationModelBuilder::new()
+//!         .with_source_languages(vec![Language::English])
+//!         .with_target_languages(vec![Language::Spanish])
+//!         .create_model()?;
+//!
+//!     let document = std::fs::read_to_string("path/to/document.txt")?;
+//!     let output = model.translate_documents(&[document], Language::English, Language::Spanish, 16)?;
+//!     println!("{}", output[0]);
+//!     Ok(())
+//! }
+//! ```
 
+mod document;
+mod quality_estimation;
 mod translation_builder;
 mod translation_pipeline;
 
-pub use translation_pipeline::{Language, TranslationConfig, TranslationModel, TranslationOption};
+pub use document::split_sentences;
+pub use quality_estimation::{QualityEstimationConfig, QualityEstimationModel, ScoredTranslation};
+pub use translation_pipeline::{
+    Language, TranslationConfig, TranslationHypothesis, TranslationModel, TranslationOption,
+};
 
 pub use translation_builder::TranslationModelBuilder;
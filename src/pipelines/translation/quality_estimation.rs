@@ -0,0 +1,289 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::error::RustBertError;
+use crate::common::settings::default_device;
+use crate::pipelines::common::{ModelResource, ModelType, TokenTypeStrategy, TokenizerOption};
+use crate::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
+use tch::Device;
+
+#[cfg(all(feature = "remote", feature = "roberta"))]
+use crate::{
+    resources::RemoteResource,
+    roberta::{RobertaConfigResources, RobertaModelResources, RobertaVocabResources},
+};
+
+/// # Translation scored by a quality estimation model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredTranslation {
+    /// Translated text
+    pub text: String,
+    /// Estimated quality of the translation (higher is better)
+    pub score: f64,
+    /// Flag set when the score is below the review threshold: the translation should be reviewed by a human
+    pub needs_review: bool,
+}
+
+/// # Configuration for QualityEstimationModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct QualityEstimationConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: TransQuest multilingual direct assessment model)
+    pub model_resource: ModelResource,
+    /// Config resource (default: TransQuest multilingual direct assessment model)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: TransQuest multilingual direct assessment model)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl QualityEstimationConfig {
+    /// Instantiate a new quality estimation configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RC, RV>(
+        model_type: ModelType,
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: Option<RV>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> QualityEstimationConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        QualityEstimationConfig {
+            model_type,
+            model_resource,
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: merges_resource.map(|r| Box::new(r) as Box<_>),
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            device: default_device(),
+        }
+    }
+}
+
+#[cfg(all(feature = "remote", feature = "roberta"))]
+impl Default for QualityEstimationConfig {
+    /// Provides a default multilingual XLM-RoBERTa quality estimation model (TransQuest, trained on direct assessments)
+    fn default() -> QualityEstimationConfig {
+        QualityEstimationConfig::new(
+            ModelType::XLMRoberta,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                RobertaModelResources::TRANSQUEST_DA_MULTILINGUAL,
+            ))),
+            RemoteResource::from_pretrained(RobertaConfigResources::TRANSQUEST_DA_MULTILINGUAL),
+            RemoteResource::from_pretrained(RobertaVocabResources::TRANSQUEST_DA_MULTILINGUAL),
+            None,
+            false,
+            None,
+            None,
+        )
+    }
+}
+
+impl From<QualityEstimationConfig> for SequenceClassificationConfig {
+    fn from(config: QualityEstimationConfig) -> Self {
+        SequenceClassificationConfig {
+            model_type: config.model_type,
+            model_resource: config.model_resource,
+            config_resource: config.config_resource,
+            vocab_resource: config.vocab_resource,
+            merges_resource: config.merges_resource,
+            lower_case: config.lower_case,
+            strip_accents: config.strip_accents,
+            add_prefix_space: config.add_prefix_space,
+            device: config.device,
+            kind: None,
+            token_type_strategy: TokenTypeStrategy::default(),
+        }
+    }
+}
+
+/// # QualityEstimationModel to score translations without reference
+/// Cross-encoder with a regression head predicting the quality of a translation from the source text and the
+/// translation, in the style of COMET-QE / TransQuest. The scale of the scores depends on the training data of the
+/// model (for direct assessment models, z-normalized human ratings where higher is better).
+pub struct QualityEstimationModel {
+    sequence_classification_model: SequenceClassificationModel,
+}
+
+impl QualityEstimationModel {
+    /// Build a new `QualityEstimationModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `quality_estimation_config` - `QualityEstimationConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::QualityEstimationModel;
+    ///
+    /// let quality_estimation_model = QualityEstimationModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        quality_estimation_config: QualityEstimationConfig,
+    ) -> Result<QualityEstimationModel, RustBertError> {
+        let sequence_classification_model =
+            SequenceClassificationModel::new(quality_estimation_config.into())?;
+        Ok(QualityEstimationModel {
+            sequence_classification_model,
+        })
+    }
+
+    /// Build a new `QualityEstimationModel` with a provided tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `quality_estimation_config` - `QualityEstimationConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` tokenizer to use for quality estimation.
+    pub fn new_with_tokenizer(
+        quality_estimation_config: QualityEstimationConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<QualityEstimationModel, RustBertError> {
+        let sequence_classification_model = SequenceClassificationModel::new_with_tokenizer(
+            quality_estimation_config.into(),
+            tokenizer,
+        )?;
+        Ok(QualityEstimationModel {
+            sequence_classification_model,
+        })
+    }
+
+    /// Get a reference to the model tokenizer.
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.sequence_classification_model.get_tokenizer()
+    }
+
+    /// Get a mutable reference to the model tokenizer.
+    pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
+        self.sequence_classification_model.get_tokenizer_mut()
+    }
+
+    /// Estimates the quality of translations from their source texts
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - Source texts
+    /// * `translations` - Translations of the source texts, in the same order
+    ///
+    /// # Returns
+    /// * `Vec<f64>` Estimated quality of each translation (higher is better)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::QualityEstimationModel;
+    ///
+    /// let quality_estimation_model = QualityEstimationModel::new(Default::default())?;
+    /// let scores = quality_estimation_model.score(
+    ///     &["The cat sits on the mat."],
+    ///     &["Le chat est assis sur le tapis."],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn score<S, T>(&self, sources: &[S], translations: &[T]) -> Result<Vec<f64>, RustBertError>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        if sources.len() != translations.len() {
+            return Err(RustBertError::ValueError(format!(
+                "The number of translations ({}) must match the number of source texts ({})",
+                translations.len(),
+                sources.len()
+            )));
+        }
+        let pairs = sources
+            .iter()
+            .zip(translations.iter())
+            .map(|(source, translation)| (source.as_ref(), translation.as_ref()))
+            .collect::<Vec<(&str, &str)>>();
+        self.sequence_classification_model.regress_pairs(&pairs)
+    }
+
+    /// Scores translations and flags the translations with a score below the review threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - Source texts
+    /// * `translations` - Translations of the source texts, in the same order
+    /// * `review_threshold` - Translations scoring below this threshold are flagged for review
+    ///
+    /// # Returns
+    /// * `Vec<ScoredTranslation>` Scored translations, in the input order
+    pub fn flag<S, T>(
+        &self,
+        sources: &[S],
+        translations: &[T],
+        review_threshold: f64,
+    ) -> Result<Vec<ScoredTranslation>, RustBertError>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        let scores = self.score(sources, translations)?;
+        Ok(translations
+            .iter()
+            .zip(scores)
+            .map(|(translation, score)| ScoredTranslation {
+                text: translation.as_ref().to_string(),
+                score,
+                needs_review: score < review_threshold,
+            })
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "remote", feature = "roberta"))]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = QualityEstimationConfig::default();
+        let _: Box<dyn Send> = Box::new(QualityEstimationModel::new(config));
+    }
+}
@@ -38,7 +38,7 @@ enum ModelSize {
 /// Otherwise a M2M100 multi-lingual model will be returned.
 ///
 /// The options for the builder are provided with dedicated "builder function", the call to `create_model()` creates a model
-/// from the builder.
+/// from the builder. Creating a model requires the `remote`, `marian`, `mbart` and `m2m-100` features.
 ///
 /// # Example
 ///
@@ -325,7 +325,12 @@ impl TranslationModelBuilder {
     ///     Ok(())
     /// }
     /// ```
-    #[cfg(feature = "remote")]
+    #[cfg(all(
+        feature = "remote",
+        feature = "marian",
+        feature = "mbart",
+        feature = "m2m-100"
+    ))]
     pub fn create_model(&self) -> Result<TranslationModel, RustBertError> {
         let device = self.device.unwrap_or_else(Device::cuda_if_available);
 
@@ -392,7 +397,12 @@ impl TranslationModelBuilder {
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(
+    feature = "remote",
+    feature = "marian",
+    feature = "mbart",
+    feature = "m2m-100"
+))]
 mod model_fetchers {
     use super::*;
     use crate::{
@@ -11,23 +11,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use tch::Device;
+use tch::{Device, Kind};
 
 use crate::common::error::RustBertError;
+use crate::common::precision::{is_half_precision, with_attention_fp32_accumulation};
+#[cfg(feature = "m2m-100")]
 use crate::m2m_100::M2M100Generator;
+#[cfg(feature = "marian")]
 use crate::marian::MarianGenerator;
+#[cfg(feature = "mbart")]
 use crate::mbart::MBartGenerator;
+#[cfg(feature = "nllb")]
 use crate::nllb::NLLBGenerator;
 use crate::pipelines::common::{ModelResource, ModelType, TokenizerOption};
-use crate::pipelines::generation_utils::{GenerateConfig, GenerateOptions, LanguageGenerator};
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{
+    EncoderOutputCache, GenerateConfig, GenerateOptions, GeneratedTextOutput, LanguageGenerator,
+    NoRepeatNgramScope, PhrasalConstraint,
+};
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::ONNXConditionalGenerator;
+use crate::pipelines::translation::document::{
+    reassemble, split_long_segments, DEFAULT_MAX_SEGMENT_TOKENS,
+};
+use crate::pipelines::translation::{split_sentences, QualityEstimationModel, ScoredTranslation};
 use crate::resources::ResourceProvider;
+#[cfg(feature = "t5")]
 use crate::t5::T5Generator;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::{Debug, Display};
+use std::ops::Range;
 
 /// Language
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -974,10 +989,14 @@ pub struct TranslationConfig {
     pub num_return_sequences: i64,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
     /// Number of beam groups for diverse beam generation. If provided and higher than 1, will split the beams into beam subgroups leading to more diverse generation.
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Optional cache of the encoder outputs for repeated inputs (default: None)
+    pub encoder_cache: Option<EncoderOutputCache>,
 }
 
 impl TranslationConfig {
@@ -1051,6 +1070,7 @@ impl TranslationConfig {
             source_languages: source_languages.as_ref().iter().cloned().collect(),
             target_languages: target_languages.as_ref().iter().cloned().collect(),
             device,
+            kind: None,
             min_length: 0,
             max_length: Some(512),
             do_sample: false,
@@ -1065,6 +1085,7 @@ impl TranslationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            encoder_cache: None,
         }
     }
 }
@@ -1088,10 +1109,22 @@ impl From<TranslationConfig> for GenerateConfig {
             repetition_penalty: config.repetition_penalty,
             length_penalty: config.length_penalty,
             no_repeat_ngram_size: config.no_repeat_ngram_size,
+            no_repeat_ngram_scope: NoRepeatNgramScope::FullSequence,
+            encoder_no_repeat_ngram_size: 0,
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            penalty_alpha: None,
+            typical_p: None,
+            eta_cutoff: None,
+            epsilon_cutoff: None,
             device: config.device,
+            encoder_cache: config.encoder_cache,
+            stop_sequences: Vec::new(),
+            stopping_criteria: Vec::new(),
+            logits_processors: Vec::new(),
+            bad_word_ids: Vec::new(),
+            sequence_bias: Vec::new(),
         }
     }
 }
@@ -1100,14 +1133,19 @@ impl From<TranslationConfig> for GenerateConfig {
 /// # Abstraction that holds one particular translation model, for any of the supported models
 pub enum TranslationOption {
     /// Translator based on Marian model
+    #[cfg(feature = "marian")]
     Marian(MarianGenerator),
     /// Translator based on T5 model
+    #[cfg(feature = "t5")]
     T5(T5Generator),
     /// Translator based on MBart50 model
+    #[cfg(feature = "mbart")]
     MBart(MBartGenerator),
     /// Translator based on M2M100 model
+    #[cfg(feature = "m2m-100")]
     M2M100(M2M100Generator),
     /// Translator based on NLLB model
+    #[cfg(feature = "nllb")]
     NLLB(NLLBGenerator),
     /// Translator based on ONNX model
     #[cfg(feature = "onnx")]
@@ -1121,16 +1159,21 @@ impl TranslationOption {
             (_, &ModelResource::ONNX(_)) => Ok(TranslationOption::ONNX(
                 ONNXConditionalGenerator::new(config.into(), None, None)?,
             )),
+            #[cfg(feature = "marian")]
             (ModelType::Marian, _) => Ok(TranslationOption::Marian(MarianGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "t5")]
             (ModelType::T5, _) => Ok(TranslationOption::T5(T5Generator::new(config.into())?)),
+            #[cfg(feature = "mbart")]
             (ModelType::MBart, _) => Ok(TranslationOption::MBart(MBartGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "m2m-100")]
             (ModelType::M2M100, _) => Ok(TranslationOption::M2M100(M2M100Generator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "nllb")]
             (ModelType::NLLB, _) => Ok(TranslationOption::NLLB(NLLBGenerator::new(config.into())?)),
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Translation not implemented for {:?}!",
@@ -1148,19 +1191,24 @@ impl TranslationOption {
             (_, &ModelResource::ONNX(_)) => Ok(TranslationOption::ONNX(
                 ONNXConditionalGenerator::new_with_tokenizer(config.into(), tokenizer, None, None)?,
             )),
+            #[cfg(feature = "marian")]
             (ModelType::Marian, _) => Ok(TranslationOption::Marian(
                 MarianGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "t5")]
             (ModelType::T5, _) => Ok(TranslationOption::T5(T5Generator::new_with_tokenizer(
                 config.into(),
                 tokenizer,
             )?)),
+            #[cfg(feature = "mbart")]
             (ModelType::MBart, _) => Ok(TranslationOption::MBart(
                 MBartGenerator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "m2m-100")]
             (ModelType::M2M100, _) => Ok(TranslationOption::M2M100(
                 M2M100Generator::new_with_tokenizer(config.into(), tokenizer)?,
             )),
+            #[cfg(feature = "nllb")]
             (ModelType::NLLB, _) => Ok(TranslationOption::NLLB(NLLBGenerator::new_with_tokenizer(
                 config.into(),
                 tokenizer,
@@ -1175,23 +1223,51 @@ impl TranslationOption {
     /// Returns the `ModelType` for this TranslationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "marian")]
             Self::Marian(_) => ModelType::Marian,
+            #[cfg(feature = "t5")]
             Self::T5(_) => ModelType::T5,
+            #[cfg(feature = "mbart")]
             Self::MBart(_) => ModelType::MBart,
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(_) => ModelType::M2M100,
+            #[cfg(feature = "nllb")]
             Self::NLLB(_) => ModelType::NLLB,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
         }
     }
 
+    /// Returns the maximum number of positions (context window) of the model, if limited
+    pub fn get_max_positions_embeddings(&self) -> Option<i64> {
+        match *self {
+            #[cfg(feature = "marian")]
+            Self::Marian(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "mbart")]
+            Self::MBart(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref model_ref) => model_ref.get_max_positions_embeddings(),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model_ref) => model_ref.get_max_positions_embeddings(),
+        }
+    }
+
     /// Returns the `Tokenizer` for this TranslationOption
     pub fn get_tokenizer(&self) -> &TokenizerOption {
-        match self {
+        match *self {
+            #[cfg(feature = "marian")]
             Self::Marian(ref generator) => generator.get_tokenizer(),
+            #[cfg(feature = "t5")]
             Self::T5(ref generator) => generator.get_tokenizer(),
+            #[cfg(feature = "mbart")]
             Self::MBart(ref generator) => generator.get_tokenizer(),
+            #[cfg(feature = "m2m-100")]
             Self::M2M100(ref generator) => generator.get_tokenizer(),
+            #[cfg(feature = "nllb")]
             Self::NLLB(ref generator) => generator.get_tokenizer(),
             #[cfg(feature = "onnx")]
             Self::ONNX(ref generator) => generator.get_tokenizer(),
@@ -1200,16 +1276,42 @@ impl TranslationOption {
 
     /// Interface method to access tokenizer
     pub fn get_tokenizer_mut(&mut self) -> &mut TokenizerOption {
-        match self {
-            Self::Marian(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::T5(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::MBart(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::M2M100(model_ref) => model_ref.get_tokenizer_mut(),
-            Self::NLLB(model_ref) => model_ref.get_tokenizer_mut(),
+        match *self {
+            #[cfg(feature = "marian")]
+            Self::Marian(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "mbart")]
+            Self::MBart(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref mut model_ref) => model_ref.get_tokenizer_mut(),
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref mut model_ref) => model_ref.get_tokenizer_mut(),
             #[cfg(feature = "onnx")]
-            Self::ONNX(model_ref) => model_ref.get_tokenizer_mut(),
+            Self::ONNX(ref mut model_ref) => model_ref.get_tokenizer_mut(),
         }
     }
+
+    /// Interface method to cast the weights of the model to a floating point precision
+    pub fn set_kind(&mut self, kind: Kind) -> Result<(), RustBertError> {
+        match *self {
+            #[cfg(feature = "marian")]
+            Self::Marian(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "t5")]
+            Self::T5(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "mbart")]
+            Self::MBart(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref mut model_ref) => model_ref.set_kind(kind),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(_) => Err(RustBertError::OrtError(
+                "Type casting not supported for ONNX models.".to_string(),
+            )),
+        }
+    }
+
     /// Interface method to generate() of the particular models.
     pub fn generate<S>(
         &self,
@@ -1220,16 +1322,19 @@ impl TranslationOption {
         S: AsRef<str> + Send + Sync,
     {
         match *self {
+            #[cfg(feature = "marian")]
             Self::Marian(ref model) => model
                 .generate(prompt_texts, None)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "t5")]
             Self::T5(ref model) => model
                 .generate(prompt_texts, None)
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "mbart")]
             Self::MBart(ref model) => {
                 let generate_options = GenerateOptions {
                     forced_bos_token_id,
@@ -1241,7 +1346,20 @@ impl TranslationOption {
                     .map(|output| output.text)
                     .collect()
             }
-            Self::M2M100(ref model) | Self::NLLB(ref model) => {
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref model) => {
+                let generate_options = GenerateOptions {
+                    forced_bos_token_id,
+                    ..Default::default()
+                };
+                model
+                    .generate(prompt_texts, Some(generate_options))
+                    .into_iter()
+                    .map(|output| output.text)
+                    .collect()
+            }
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref model) => {
                 let generate_options = GenerateOptions {
                     forced_bos_token_id,
                     ..Default::default()
@@ -1267,6 +1385,127 @@ impl TranslationOption {
             }
         }
     }
+
+    /// Interface method to generate() of the particular models, with `GenerateOptions` overriding the
+    /// generation configuration of the model.
+    pub fn generate_with_options<S>(
+        &self,
+        prompt_texts: Option<&[S]>,
+        generate_options: GenerateOptions,
+    ) -> Vec<String>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let generate_options = Some(generate_options);
+        let outputs = match *self {
+            #[cfg(feature = "marian")]
+            Self::Marian(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "mbart")]
+            Self::MBart(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model) => model.generate(prompt_texts, generate_options),
+        };
+        outputs.into_iter().map(|output| output.text).collect()
+    }
+
+    /// Interface method to generate the `num_hypotheses` best beam search hypotheses for each input, with their scores.
+    /// The beam size is increased to `num_hypotheses` if the configured beam size is smaller.
+    pub fn generate_n_best<S>(
+        &self,
+        prompt_texts: Option<&[S]>,
+        forced_bos_token_id: Option<i64>,
+        num_hypotheses: i64,
+    ) -> Vec<GeneratedTextOutput>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let generate_options = |num_beams: i64| GenerateOptions {
+            num_beams: Some(num_beams.max(num_hypotheses)),
+            num_return_sequences: Some(num_hypotheses),
+            forced_bos_token_id,
+            output_scores: true,
+            ..Default::default()
+        };
+        match *self {
+            #[cfg(feature = "marian")]
+            Self::Marian(ref model) => model.generate(
+                prompt_texts,
+                Some(generate_options(model.get_config().num_beams)),
+            ),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model) => model.generate(
+                prompt_texts,
+                Some(generate_options(model.get_config().num_beams)),
+            ),
+            #[cfg(feature = "mbart")]
+            Self::MBart(ref model) => model.generate(
+                prompt_texts,
+                Some(generate_options(model.get_config().num_beams)),
+            ),
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref model) => model.generate(
+                prompt_texts,
+                Some(generate_options(model.get_config().num_beams)),
+            ),
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref model) => model.generate(
+                prompt_texts,
+                Some(generate_options(model.get_config().num_beams)),
+            ),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model) => model.generate(
+                prompt_texts,
+                Some(generate_options(model.get_config().num_beams)),
+            ),
+        }
+    }
+
+    /// Interface method to generate() of the particular models, with phrasal constraints the translations must satisfy.
+    pub fn generate_with_constraints<S>(
+        &self,
+        prompt_texts: Option<&[S]>,
+        forced_bos_token_id: Option<i64>,
+        constraints: &[PhrasalConstraint],
+    ) -> Vec<String>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let generate_options = Some(GenerateOptions {
+            forced_bos_token_id,
+            constraints: Some(constraints),
+            ..Default::default()
+        });
+        let outputs = match *self {
+            #[cfg(feature = "marian")]
+            Self::Marian(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "t5")]
+            Self::T5(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "mbart")]
+            Self::MBart(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "m2m-100")]
+            Self::M2M100(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "nllb")]
+            Self::NLLB(ref model) => model.generate(prompt_texts, generate_options),
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model) => model.generate(prompt_texts, generate_options),
+        };
+        outputs.into_iter().map(|output| output.text).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Translation hypothesis returned by `TranslationModel::translate_n_best`
+pub struct TranslationHypothesis {
+    /// Translated text
+    pub text: String,
+    /// Log-likelihood of the hypothesis, normalized by the length penalty (higher is better)
+    pub score: f64,
 }
 
 /// # TranslationModel to perform translation
@@ -1274,6 +1513,8 @@ pub struct TranslationModel {
     model: TranslationOption,
     supported_source_languages: HashSet<Language>,
     supported_target_languages: HashSet<Language>,
+    quality_estimation: Option<(QualityEstimationModel, f64)>,
+    attention_fp32_accumulation: bool,
 }
 
 impl TranslationModel {
@@ -1324,12 +1565,18 @@ impl TranslationModel {
         let supported_source_languages = translation_config.source_languages.clone();
         let supported_target_languages = translation_config.target_languages.clone();
 
-        let model = TranslationOption::new(translation_config)?;
+        let kind = translation_config.kind;
+        let mut model = TranslationOption::new(translation_config)?;
+        if let Some(kind) = kind {
+            model.set_kind(kind)?;
+        }
 
         Ok(TranslationModel {
             model,
             supported_source_languages,
             supported_target_languages,
+            quality_estimation: None,
+            attention_fp32_accumulation: kind.map_or(false, is_half_precision),
         })
     }
 
@@ -1394,12 +1641,18 @@ impl TranslationModel {
         let supported_source_languages = translation_config.source_languages.clone();
         let supported_target_languages = translation_config.target_languages.clone();
 
-        let model = TranslationOption::new_with_tokenizer(translation_config, tokenizer)?;
+        let kind = translation_config.kind;
+        let mut model = TranslationOption::new_with_tokenizer(translation_config, tokenizer)?;
+        if let Some(kind) = kind {
+            model.set_kind(kind)?;
+        }
 
         Ok(TranslationModel {
             model,
             supported_source_languages,
             supported_target_languages,
+            quality_estimation: None,
+            attention_fp32_accumulation: kind.map_or(false, is_half_precision),
         })
     }
 
@@ -1413,6 +1666,20 @@ impl TranslationModel {
         self.model.get_tokenizer_mut()
     }
 
+    /// Attaches a quality estimation model scoring the translations of `translate_with_quality_estimation`.
+    ///
+    /// # Arguments
+    ///
+    /// * `quality_estimation_model` - `QualityEstimationModel` scoring the translations from their source texts
+    /// * `review_threshold` - Translations scoring below this threshold are flagged for review
+    pub fn set_quality_estimation(
+        &mut self,
+        quality_estimation_model: QualityEstimationModel,
+        review_threshold: f64,
+    ) {
+        self.quality_estimation = Some((quality_estimation_model, review_threshold));
+    }
+
     /// Translates texts provided
     ///
     /// # Arguments
@@ -1486,11 +1753,481 @@ impl TranslationModel {
                     .iter()
                     .map(|v| format!("{}{}", value, v.as_ref()))
                     .collect::<Vec<String>>();
-                self.model.generate(Some(&texts), forced_bos_token_id)
+                with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                    self.model.generate(Some(&texts), forced_bos_token_id)
+                })
             }
-            None => self.model.generate(Some(texts), forced_bos_token_id),
+            None => with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model.generate(Some(texts), forced_bos_token_id)
+            }),
         })
     }
+
+    /// Translates texts with their own source and target languages in a single call. The texts are grouped by
+    /// language pair and each group is translated as a batch, the translations are returned in the input order.
+    ///
+    /// # Arguments
+    /// * `inputs` - `&[(&str, Option<Language>, Option<Language>)]` Array of (text, source language, target language)
+    /// to translate. The languages are optional for models with a single source or target language.
+    ///
+    /// # Returns
+    /// * `Vec<String>` Translated texts, in the input order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::ModelType;
+    /// use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+    ///
+    /// let model = TranslationModelBuilder::new()
+    ///     .with_model_type(ModelType::M2M100)
+    ///     .with_source_languages(vec![Language::English, Language::French])
+    ///     .with_target_languages(vec![Language::German, Language::Spanish])
+    ///     .create_model()?;
+    ///
+    /// let output = model.translate_batch(&[
+    ///     ("This is a sentence to be translated", Some(Language::English), Some(Language::German)),
+    ///     ("Ceci est une phrase à traduire", Some(Language::French), Some(Language::Spanish)),
+    ///     ("This is another sentence", Some(Language::English), Some(Language::German)),
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_batch<S>(
+        &self,
+        inputs: &[(S, Option<Language>, Option<Language>)],
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let mut language_pairs: Vec<(Option<Language>, Option<Language>)> = vec![];
+        let mut groups: HashMap<(Option<Language>, Option<Language>), Vec<usize>> = HashMap::new();
+        for (index, (_, source_language, target_language)) in inputs.iter().enumerate() {
+            let language_pair = (*source_language, *target_language);
+            groups
+                .entry(language_pair)
+                .or_insert_with(|| {
+                    language_pairs.push(language_pair);
+                    vec![]
+                })
+                .push(index);
+        }
+
+        let mut output = vec![String::new(); inputs.len()];
+        for language_pair in language_pairs {
+            let indices = &groups[&language_pair];
+            let texts = indices
+                .iter()
+                .map(|&index| inputs[index].0.as_ref())
+                .collect::<Vec<&str>>();
+            let translations = self.translate(&texts, language_pair.0, language_pair.1)?;
+            for (&index, translation) in indices.iter().zip(translations) {
+                output[index] = translation;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Translates documents of any length: the documents are split into sentences (splitting further the sentences
+    /// exceeding the context window of the model), the sentences are translated in batches and the translations are
+    /// reassembled in the original order, preserving the line breaks and white spaces between sentences.
+    ///
+    /// # Arguments
+    /// * `documents` - `&[&str]` Array of documents to translate.
+    /// * `source_language` - Language of the documents (optional for models with a single source language), also
+    /// used to split the sentences
+    /// * `target_language` - Language to translate to (optional for models with a single target language)
+    /// * `batch_size` - Maximum number of sentences translated in a batch
+    ///
+    /// # Returns
+    /// * `Vec<String>` Translated documents
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+    ///
+    /// let model = TranslationModelBuilder::new()
+    ///     .with_source_languages(vec![Language::English])
+    ///     .with_target_languages(vec![Language::French])
+    ///     .create_model()?;
+    ///
+    /// let document = "This is the first paragraph. It has two sentences.\n\nThis is the second paragraph.";
+    /// let output = model.translate_documents(&[document], Language::English, Language::French, 16)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_documents<S>(
+        &self,
+        documents: &[S],
+        source_language: impl Into<Option<Language>>,
+        target_language: impl Into<Option<Language>>,
+        batch_size: usize,
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        if batch_size == 0 {
+            return Err(RustBertError::ValueError(
+                "The batch size must be positive".to_string(),
+            ));
+        }
+        let source_language = source_language.into();
+        let target_language = target_language.into();
+        // Keep room for the special tokens and language prefix of the model
+        let max_tokens = self
+            .model
+            .get_max_positions_embeddings()
+            .map_or(DEFAULT_MAX_SEGMENT_TOKENS, |max_positions| {
+                (max_positions as usize).saturating_sub(8).max(1)
+            });
+
+        let segments = documents
+            .iter()
+            .map(|document| {
+                let document = document.as_ref();
+                split_long_segments(
+                    document,
+                    split_sentences(document, source_language),
+                    self.model.get_tokenizer(),
+                    max_tokens,
+                )
+            })
+            .collect::<Vec<Vec<Range<usize>>>>();
+        let sentences = documents
+            .iter()
+            .zip(segments.iter())
+            .flat_map(|(document, segments)| {
+                segments
+                    .iter()
+                    .map(move |segment| &document.as_ref()[segment.clone()])
+            })
+            .collect::<Vec<&str>>();
+
+        let mut translations = Vec::with_capacity(sentences.len());
+        for batch in sentences.chunks(batch_size) {
+            translations.extend(self.translate(batch, source_language, target_language)?);
+        }
+
+        let mut translations = translations.as_slice();
+        Ok(documents
+            .iter()
+            .zip(segments.iter())
+            .map(|(document, segments)| {
+                let (document_translations, remaining) = translations.split_at(segments.len());
+                translations = remaining;
+                reassemble(document.as_ref(), segments, document_translations)
+            })
+            .collect())
+    }
+
+    /// Translates texts provided, returning the `num_hypotheses` best beam search hypotheses for each input with their
+    /// scores (e.g. for reranking or post-editing). The beam size is increased to `num_hypotheses` if the configured
+    /// beam size is smaller.
+    ///
+    /// # Arguments
+    /// * `texts` - `&[&str]` Array of texts to translate.
+    /// * `source_language` - Language of the texts (optional for models with a single source language)
+    /// * `target_language` - Language to translate to (optional for models with a single target language)
+    /// * `num_hypotheses` - Number of hypotheses to return for each input
+    ///
+    /// # Returns
+    /// * `Vec<Vec<TranslationHypothesis>>` Hypotheses for each input, sorted by decreasing score
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+    ///
+    /// let model = TranslationModelBuilder::new()
+    ///     .with_source_languages(vec![Language::English])
+    ///     .with_target_languages(vec![Language::French])
+    ///     .create_model()?;
+    ///
+    /// let hypotheses = model.translate_n_best(
+    ///     &["This is a sentence to be translated"],
+    ///     None,
+    ///     Language::French,
+    ///     5,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_n_best<S>(
+        &self,
+        texts: &[S],
+        source_language: impl Into<Option<Language>>,
+        target_language: impl Into<Option<Language>>,
+        num_hypotheses: usize,
+    ) -> Result<Vec<Vec<TranslationHypothesis>>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        if num_hypotheses == 0 {
+            return Err(RustBertError::ValueError(
+                "At least one hypothesis must be requested".to_string(),
+            ));
+        }
+        let (prefix, forced_bos_token_id) =
+            self.model.get_tokenizer().get_prefix_and_forced_bos_id(
+                source_language.into().as_ref(),
+                target_language.into().as_ref(),
+                &self.supported_source_languages,
+                &self.supported_target_languages,
+            )?;
+
+        let outputs = match prefix {
+            Some(value) => {
+                let texts = texts
+                    .iter()
+                    .map(|v| format!("{}{}", value, v.as_ref()))
+                    .collect::<Vec<String>>();
+                with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                    self.model.generate_n_best(
+                        Some(&texts),
+                        forced_bos_token_id,
+                        num_hypotheses as i64,
+                    )
+                })
+            }
+            None => with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_n_best(Some(texts), forced_bos_token_id, num_hypotheses as i64)
+            }),
+        };
+        Ok(outputs
+            .chunks(num_hypotheses)
+            .map(|hypotheses| {
+                let mut hypotheses = hypotheses
+                    .iter()
+                    .map(|output| TranslationHypothesis {
+                        text: output.text.clone(),
+                        score: output.score.unwrap_or(f64::NEG_INFINITY),
+                    })
+                    .collect::<Vec<TranslationHypothesis>>();
+                hypotheses.sort_by(|a, b| b.score.total_cmp(&a.score));
+                hypotheses
+            })
+            .collect())
+    }
+
+    /// Translates texts provided, with generation options overriding the configuration of the model for this call.
+    /// The `decoder_start_token_id`, `forced_bos_token_id` and `forced_eos_token_id` of the options take priority over
+    /// the model configuration and the target language token, e.g. for fine-tuned models whose configuration is
+    /// missing or incorrect.
+    ///
+    /// # Arguments
+    /// * `texts` - `&[&str]` Array of texts to translate.
+    /// * `source_language` - Language of the texts (optional for models with a single source language)
+    /// * `target_language` - Language to translate to (optional for models with a single target language)
+    /// * `generate_options` - `GenerateOptions` overriding the generation settings of the model
+    ///
+    /// # Returns
+    /// * `Vec<String>` Translated texts
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::ModelType;
+    /// use rust_bert::pipelines::generation_utils::GenerateOptions;
+    /// use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+    ///
+    /// let model = TranslationModelBuilder::new()
+    ///     .with_model_type(ModelType::MBart)
+    ///     .with_source_languages(vec![Language::English])
+    ///     .with_target_languages(vec![Language::French])
+    ///     .create_model()?;
+    ///
+    /// let generate_options = GenerateOptions {
+    ///     decoder_start_token_id: Some(2),
+    ///     forced_eos_token_id: Some(2),
+    ///     ..Default::default()
+    /// };
+    /// let output = model.translate_with_options(
+    ///     &["This is a sentence to be translated"],
+    ///     Language::English,
+    ///     Language::French,
+    ///     generate_options,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_with_options<S>(
+        &self,
+        texts: &[S],
+        source_language: impl Into<Option<Language>>,
+        target_language: impl Into<Option<Language>>,
+        generate_options: GenerateOptions,
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let (prefix, forced_bos_token_id) =
+            self.model.get_tokenizer().get_prefix_and_forced_bos_id(
+                source_language.into().as_ref(),
+                target_language.into().as_ref(),
+                &self.supported_source_languages,
+                &self.supported_target_languages,
+            )?;
+        let generate_options = GenerateOptions {
+            forced_bos_token_id: generate_options.forced_bos_token_id.or(forced_bos_token_id),
+            ..generate_options
+        };
+
+        Ok(match prefix {
+            Some(value) => {
+                let texts = texts
+                    .iter()
+                    .map(|v| format!("{}{}", value, v.as_ref()))
+                    .collect::<Vec<String>>();
+                with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                    self.model
+                        .generate_with_options(Some(&texts), generate_options)
+                })
+            }
+            None => with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_with_options(Some(texts), generate_options)
+            }),
+        })
+    }
+
+    /// Translates texts provided, guaranteeing that each translation contains all the phrases provided (lexically
+    /// constrained decoding), e.g. to enforce the translation of terminology or product names.
+    ///
+    /// # Arguments
+    /// * `texts` - `&[&str]` Array of texts to translate.
+    /// * `source_language` - Language of the texts (optional for models with a single source language)
+    /// * `target_language` - Language to translate to (optional for models with a single target language)
+    /// * `phrases` - `&[&str]` Phrases (in the target language) that must appear in each translation
+    ///
+    /// # Returns
+    /// * `Vec<String>` Translated texts
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+    ///
+    /// let model = TranslationModelBuilder::new()
+    ///     .with_source_languages(vec![Language::English])
+    ///     .with_target_languages(vec![Language::French])
+    ///     .create_model()?;
+    ///
+    /// let output = model.translate_with_constraints(
+    ///     &["The new phone has a great camera."],
+    ///     None,
+    ///     Language::French,
+    ///     &["smartphone"],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_with_constraints<S, P>(
+        &self,
+        texts: &[S],
+        source_language: impl Into<Option<Language>>,
+        target_language: impl Into<Option<Language>>,
+        phrases: &[P],
+    ) -> Result<Vec<String>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+        P: AsRef<str>,
+    {
+        let (prefix, forced_bos_token_id) =
+            self.model.get_tokenizer().get_prefix_and_forced_bos_id(
+                source_language.into().as_ref(),
+                target_language.into().as_ref(),
+                &self.supported_source_languages,
+                &self.supported_target_languages,
+            )?;
+        let constraints = phrases
+            .iter()
+            .map(|phrase| {
+                PhrasalConstraint::from_phrases(self.model.get_tokenizer(), &[phrase.as_ref()])
+            })
+            .collect::<Result<Vec<PhrasalConstraint>, RustBertError>>()?;
+
+        Ok(match prefix {
+            Some(value) => {
+                let texts = texts
+                    .iter()
+                    .map(|v| format!("{}{}", value, v.as_ref()))
+                    .collect::<Vec<String>>();
+                with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                    self.model.generate_with_constraints(
+                        Some(&texts),
+                        forced_bos_token_id,
+                        &constraints,
+                    )
+                })
+            }
+            None => with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+                self.model
+                    .generate_with_constraints(Some(texts), forced_bos_token_id, &constraints)
+            }),
+        })
+    }
+
+    /// Translates texts and scores the translations with the attached quality estimation model, flagging the
+    /// low-confidence translations for human review. Requires a quality estimation model set with
+    /// `set_quality_estimation`.
+    ///
+    /// # Arguments
+    /// * `texts` - `&[&str]` Array of texts to translate.
+    /// * `source_language` - Language of the texts (optional for models with a single source language)
+    /// * `target_language` - Language to translate to (optional for models with a single target language)
+    ///
+    /// # Returns
+    /// * `Vec<ScoredTranslation>` Translated texts with their estimated quality
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{
+    ///     Language, QualityEstimationModel, TranslationModelBuilder,
+    /// };
+    ///
+    /// let mut model = TranslationModelBuilder::new()
+    ///     .with_source_languages(vec![Language::English])
+    ///     .with_target_languages(vec![Language::French])
+    ///     .create_model()?;
+    /// model.set_quality_estimation(QualityEstimationModel::new(Default::default())?, 0.5);
+    ///
+    /// let output = model.translate_with_quality_estimation(
+    ///     &["This is a sentence to be translated"],
+    ///     None,
+    ///     Language::French,
+    /// )?;
+    /// let to_review = output.iter().filter(|translation| translation.needs_review);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_with_quality_estimation<S>(
+        &self,
+        texts: &[S],
+        source_language: impl Into<Option<Language>>,
+        target_language: impl Into<Option<Language>>,
+    ) -> Result<Vec<ScoredTranslation>, RustBertError>
+    where
+        S: AsRef<str> + Send + Sync,
+    {
+        let (quality_estimation_model, review_threshold) =
+            self.quality_estimation.as_ref().ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "No quality estimation model attached, set one with `set_quality_estimation`"
+                        .to_string(),
+                )
+            })?;
+        let translations = self.translate(texts, source_language, target_language)?;
+        quality_estimation_model.flag(texts, &translations, *review_threshold)
+    }
 }
 
 #[cfg(test)]
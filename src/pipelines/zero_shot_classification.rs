@@ -98,25 +98,43 @@
 //! .to_vec();
 //! ```
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForSequenceClassification;
+#[cfg(feature = "bart")]
 use crate::bart::BartForSequenceClassification;
+#[cfg(feature = "bert")]
 use crate::bert::BertForSequenceClassification;
+#[cfg(feature = "deberta")]
 use crate::deberta::DebertaForSequenceClassification;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertModelClassifier;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerForSequenceClassification;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForSequenceClassification;
-use crate::pipelines::common::{ConfigOption, ModelResource, ModelType, TokenizerOption};
+use crate::pipelines::common::{
+    ConfigOption, ModelResource, ModelType, TokenTypeIdsHandler, TokenTypeStrategy, TokenizerOption,
+};
 use crate::pipelines::sequence_classification::Label;
 use crate::resources::ResourceProvider;
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForSequenceClassification;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForSequenceClassification;
-use crate::RustBertError;
+use crate::{InputError, RustBertError};
 use rust_tokenizers::tokenizer::TruncationStrategy;
 use rust_tokenizers::TokenizedInput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
 
+use crate::common::precision::{
+    is_half_precision, set_var_store_kind, with_attention_fp32_accumulation,
+};
+use crate::common::settings::default_device;
 #[cfg(feature = "onnx")]
 use crate::pipelines::onnx::{config::ONNXEnvironmentConfig, ONNXEncoder};
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bart"))]
 use crate::{
     bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources},
     resources::RemoteResource,
@@ -146,6 +164,10 @@ pub struct ZeroShotClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Precision the weights are cast to once loaded, `Kind::Half` or `Kind::BFloat16` for half precision inference, the attention being then accumulated in single precision (see the `precision` module). The precision of the checkpoint is kept if `None` (default: None)
+    pub kind: Option<Kind>,
+    /// Token type ids passed to the model for the input and label hypothesis pairs (default: depending on the architecture)
+    pub token_type_strategy: TokenTypeStrategy,
 }
 
 impl ZeroShotClassificationConfig {
@@ -182,12 +204,14 @@ impl ZeroShotClassificationConfig {
             lower_case,
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
+            token_type_strategy: TokenTypeStrategy::default(),
         }
     }
 }
 
-#[cfg(feature = "remote")]
+#[cfg(all(feature = "remote", feature = "bart"))]
 impl Default for ZeroShotClassificationConfig {
     /// Provides a default zero-shot classification model (English)
     fn default() -> ZeroShotClassificationConfig {
@@ -208,7 +232,9 @@ impl Default for ZeroShotClassificationConfig {
             lower_case: false,
             strip_accents: None,
             add_prefix_space: None,
-            device: Device::cuda_if_available(),
+            device: default_device(),
+            kind: None,
+            token_type_strategy: TokenTypeStrategy::default(),
         }
     }
 }
@@ -219,24 +245,34 @@ impl Default for ZeroShotClassificationConfig {
 /// to contradiction and the last logit corresponding to entailment.
 pub enum ZeroShotClassificationOption {
     /// Bart for Sequence Classification
+    #[cfg(feature = "bart")]
     Bart(BartForSequenceClassification),
     /// DeBERTa for Sequence Classification
+    #[cfg(feature = "deberta")]
     Deberta(DebertaForSequenceClassification),
     /// Bert for Sequence Classification
+    #[cfg(feature = "bert")]
     Bert(BertForSequenceClassification),
     /// DistilBert for Sequence Classification
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertModelClassifier),
     /// MobileBert for Sequence Classification
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForSequenceClassification),
     /// Roberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForSequenceClassification),
     /// XLMRoberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForSequenceClassification),
     /// Albert for Sequence Classification
+    #[cfg(feature = "albert")]
     Albert(AlbertForSequenceClassification),
     /// XLNet for Sequence Classification
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForSequenceClassification),
     /// Longformer for Sequence Classification
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForSequenceClassification),
     /// ONNX model for Sequence Classification
     #[cfg(feature = "onnx")]
@@ -266,6 +302,7 @@ impl ZeroShotClassificationOption {
             &ConfigOption::from_file(config.model_type, config.config_resource.get_local_path()?);
         let model_type = config.model_type;
         let model = match model_type {
+            #[cfg(feature = "bart")]
             ModelType::Bart => {
                 if let ConfigOption::Bart(config) = model_config {
                     Ok(Self::Bart(
@@ -277,6 +314,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "deberta")]
             ModelType::Deberta => {
                 if let ConfigOption::Deberta(config) = model_config {
                     Ok(Self::Deberta(
@@ -288,6 +326,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(Self::Bert(
@@ -299,6 +338,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => {
                 if let ConfigOption::DistilBert(config) = model_config {
                     Ok(Self::DistilBert(
@@ -310,6 +350,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
                 if let ConfigOption::MobileBert(config) = model_config {
                     Ok(Self::MobileBert(
@@ -321,6 +362,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(Self::Roberta(
@@ -332,6 +374,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
                 if let ConfigOption::Bert(config) = model_config {
                     Ok(Self::XLMRoberta(
@@ -343,6 +386,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
                 if let ConfigOption::Albert(config) = model_config {
                     Ok(Self::Albert(
@@ -354,6 +398,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
                 if let ConfigOption::XLNet(config) = model_config {
                     Ok(Self::XLNet(
@@ -365,6 +410,7 @@ impl ZeroShotClassificationOption {
                     ))
                 }
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
                 if let ConfigOption::Longformer(config) = model_config {
                     Ok(Self::Longformer(
@@ -384,7 +430,10 @@ impl ZeroShotClassificationOption {
                 "Zero shot classification not implemented for {model_type:?}!",
             ))),
         }?;
-        var_store.load(weights_path)?;
+        crate::resources::load_weights_from_file(weights_path, &mut var_store)?;
+        if let Some(kind) = config.kind {
+            set_var_store_kind(&mut var_store, kind)?;
+        }
         Ok(model)
     }
 
@@ -411,15 +460,25 @@ impl ZeroShotClassificationOption {
     /// Returns the `ModelType` for this SequenceClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bart")]
             Self::Bart(_) => ModelType::Bart,
+            #[cfg(feature = "deberta")]
             Self::Deberta(_) => ModelType::Deberta,
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::Roberta,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
             #[cfg(feature = "onnx")]
             Self::ONNX(_) => ModelType::ONNX,
@@ -437,6 +496,7 @@ impl ZeroShotClassificationOption {
         train: bool,
     ) -> Tensor {
         match *self {
+            #[cfg(feature = "bart")]
             Self::Bart(ref model) => {
                 model
                     .forward_t(
@@ -449,6 +509,7 @@ impl ZeroShotClassificationOption {
                     )
                     .decoder_output
             }
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -461,6 +522,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "deberta")]
             Self::Deberta(ref model) => {
                 model
                     .forward_t(
@@ -474,18 +536,21 @@ impl ZeroShotClassificationOption {
                     .expect("Error in DeBERTa forward_t")
                     .logits
             }
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(ref model) => {
                 model
                     .forward_t(input_ids, mask, input_embeds, train)
                     .expect("Error in distilbert forward_t")
                     .logits
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 model
                     .forward_t(input_ids, None, None, input_embeds, mask, train)
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -498,6 +563,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 model
                     .forward_t(
@@ -510,6 +576,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 model
                     .forward_t(
@@ -524,6 +591,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 model
                     .forward_t(
@@ -573,11 +641,70 @@ pub type ZeroShotTemplate = Box<dyn Fn(&str) -> String>;
 /// }
 /// ```
 
+/// # Options for zero-shot classification with custom hypotheses
+/// Hypothesis templates contain a `{}` placeholder replaced by the label (templates without placeholder are used
+/// as is, e.g. for hand-written hypotheses).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZeroShotOptions {
+    /// Hypothesis templates shared by all labels, ensembled by averaging their scores (default: `"This example is about {}."`)
+    pub templates: Vec<String>,
+    /// Hypothesis templates of specific labels, replacing the shared templates for these labels
+    pub label_templates: HashMap<String, Vec<String>>,
+    /// Subtract the scores of a content-free (empty) input from the scores of the inputs (contextual calibration)
+    pub calibrate: bool,
+    /// Score the labels independently (multi-label classification)
+    pub multilabel: bool,
+}
+
+impl Default for ZeroShotOptions {
+    fn default() -> Self {
+        ZeroShotOptions {
+            templates: vec!["This example is about {}.".to_string()],
+            label_templates: HashMap::new(),
+            calibrate: false,
+            multilabel: false,
+        }
+    }
+}
+
+impl ZeroShotOptions {
+    /// Sets the hypothesis templates of a label, replacing the shared templates for this label
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Label using the templates
+    /// * `templates` - Hypothesis templates for the label
+    pub fn with_label_templates<L: Into<String>>(
+        mut self,
+        label: L,
+        templates: Vec<String>,
+    ) -> Self {
+        self.label_templates.insert(label.into(), templates);
+        self
+    }
+
+    /// Hypotheses of a label from its templates
+    fn hypotheses(&self, label: &str) -> Result<Vec<String>, RustBertError> {
+        let templates = self.label_templates.get(label).unwrap_or(&self.templates);
+        if templates.is_empty() {
+            return Err(RustBertError::ValueError(format!(
+                "No hypothesis template provided for the label {label}"
+            )));
+        }
+        Ok(templates
+            .iter()
+            .map(|template| template.replace("{}", label))
+            .collect())
+    }
+}
+
 /// # ZeroShotClassificationModel for Zero Shot Classification
 pub struct ZeroShotClassificationModel {
     tokenizer: TokenizerOption,
     zero_shot_classifier: ZeroShotClassificationOption,
     device: Device,
+    token_type_ids_handler: TokenTypeIdsHandler,
+    attention_fp32_accumulation: bool,
 }
 
 impl ZeroShotClassificationModel {
@@ -649,11 +776,16 @@ impl ZeroShotClassificationModel {
     ) -> Result<ZeroShotClassificationModel, RustBertError> {
         let device = config.device;
         let zero_shot_classifier = ZeroShotClassificationOption::new(&config)?;
+        let model_config =
+            ConfigOption::from_file(config.model_type, config.config_resource.get_local_path()?);
+        let token_type_ids_handler = config.token_type_strategy.resolve(&model_config)?;
 
         Ok(ZeroShotClassificationModel {
             tokenizer,
             zero_shot_classifier,
             device,
+            token_type_ids_handler,
+            attention_fp32_accumulation: config.kind.map_or(false, is_half_precision),
         })
     }
 
@@ -678,6 +810,12 @@ impl ZeroShotClassificationModel {
         S: AsRef<[&'a str]>,
         T: AsRef<[&'a str]>,
     {
+        if inputs.as_ref().is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        if labels.as_ref().is_empty() {
+            return Err(InputError::NoCandidateLabels.into());
+        }
         let label_sentences: Vec<String> = match template {
             Some(function) => labels
                 .as_ref()
@@ -701,8 +839,16 @@ impl ZeroShotClassificationModel {
             })
             .collect::<Vec<(&str, &str)>>();
 
+        self.encode_pairs(&text_pair_list, max_len)
+    }
+
+    fn encode_pairs(
+        &self,
+        text_pair_list: &[(&str, &str)],
+        max_len: usize,
+    ) -> Result<(Tensor, Tensor, Tensor), RustBertError> {
         let mut tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
-            text_pair_list.as_ref(),
+            text_pair_list,
             max_len,
             &TruncationStrategy::LongestFirst,
             0,
@@ -735,9 +881,11 @@ impl ZeroShotClassificationModel {
             .collect::<Vec<_>>();
 
         let input_ids = Tensor::stack(input_ids.as_slice(), 0).to(self.device);
-        let token_type_ids = Tensor::stack(token_type_ids.as_slice(), 0)
-            .to(self.device)
-            .to_kind(Kind::Int64);
+        let token_type_ids = self.token_type_ids_handler.apply(
+            Tensor::stack(token_type_ids.as_slice(), 0)
+                .to(self.device)
+                .to_kind(Kind::Int64),
+        );
         let mask = input_ids
             .ne(self
                 .tokenizer
@@ -817,16 +965,18 @@ impl ZeroShotClassificationModel {
         let (input_tensor, mask, token_type_ids) =
             self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length)?;
 
-        let output = no_grad(|| {
-            let output = self.zero_shot_classifier.forward_t(
-                Some(&input_tensor),
-                Some(&mask),
-                Some(&token_type_ids),
-                None,
-                None,
-                false,
-            );
-            output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                let output = self.zero_shot_classifier.forward_t(
+                    Some(&input_tensor),
+                    Some(&mask),
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                );
+                output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
+            })
         });
 
         let scores = output.softmax(1, Float).select(-1, -1);
@@ -959,16 +1109,18 @@ impl ZeroShotClassificationModel {
         let (input_tensor, mask, token_type_ids) =
             self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length)?;
 
-        let output = no_grad(|| {
-            let output = self.zero_shot_classifier.forward_t(
-                Some(&input_tensor),
-                Some(&mask),
-                Some(&token_type_ids),
-                None,
-                None,
-                false,
-            );
-            output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                let output = self.zero_shot_classifier.forward_t(
+                    Some(&input_tensor),
+                    Some(&mask),
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                );
+                output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
+            })
         });
         let scores = output.slice(-1, 0, 3, 2).softmax(-1, Float).select(-1, -1);
 
@@ -994,6 +1146,170 @@ impl ZeroShotClassificationModel {
         }
         Ok(output_labels)
     }
+
+    /// Zero shot classification with custom hypothesis templates, template ensembling and contextual calibration.
+    /// The entailment scores are averaged over the hypotheses of each label. With calibration, the scores of a
+    /// content-free (empty) input are subtracted from the scores of the inputs, correcting the bias of the model
+    /// towards some labels or templates.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `options` - `ZeroShotOptions` hypothesis templates, calibration and multi-label settings.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Label>>, RustBertError>` containing the scores of all labels (in the order of the labels provided) for each input text:
+    /// probabilities over the labels for single-label classification, independent probabilities for multi-label classification.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::{
+    ///     ZeroShotClassificationModel, ZeroShotOptions,
+    /// };
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let options = ZeroShotOptions {
+    ///     templates: vec![
+    ///         "This example is about {}.".to_string(),
+    ///         "The topic of this text is {}.".to_string(),
+    ///     ],
+    ///     calibrate: true,
+    ///     ..Default::default()
+    /// }
+    /// .with_label_templates("sports", vec!["This text is about a sports event.".to_string()]);
+    ///
+    /// let output = sequence_classification_model.predict_with_options(
+    ///     &["Who are you voting for in 2020?"],
+    ///     &["politics", "economics", "sports"],
+    ///     &options,
+    ///     128,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_options<'a, S, T>(
+        &self,
+        inputs: S,
+        labels: T,
+        options: &ZeroShotOptions,
+        max_length: usize,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+        T: AsRef<[&'a str]>,
+    {
+        let labels = labels.as_ref();
+        if inputs.as_ref().is_empty() {
+            return Err(InputError::EmptyInput.into());
+        }
+        if labels.is_empty() {
+            return Err(InputError::NoCandidateLabels.into());
+        }
+        let hypotheses = labels
+            .iter()
+            .map(|label| options.hypotheses(label))
+            .collect::<Result<Vec<Vec<String>>, RustBertError>>()?;
+
+        // The content-free input used for calibration is scored with the inputs
+        let mut premises = inputs.as_ref().to_vec();
+        if options.calibrate {
+            premises.push("");
+        }
+        let text_pair_list = premises
+            .iter()
+            .flat_map(|premise| {
+                hypotheses.iter().flat_map(move |label_hypotheses| {
+                    label_hypotheses
+                        .iter()
+                        .map(move |hypothesis| (*premise, hypothesis.as_str()))
+                })
+            })
+            .collect::<Vec<(&str, &str)>>();
+
+        let (input_tensor, mask, token_type_ids) =
+            self.encode_pairs(&text_pair_list, max_length)?;
+        let output = with_attention_fp32_accumulation(self.attention_fp32_accumulation, || {
+            no_grad(|| {
+                self.zero_shot_classifier.forward_t(
+                    Some(&input_tensor),
+                    Some(&mask),
+                    Some(&token_type_ids),
+                    None,
+                    None,
+                    false,
+                )
+            })
+        })
+        .to(Device::Cpu)
+        .to_kind(Kind::Double);
+        // Entailment logit for single-label classification, entailment vs. contradiction log-odds for multi-label
+        let pair_scores = if options.multilabel {
+            output.select(-1, -1) - output.select(-1, 0)
+        } else {
+            output.select(-1, -1)
+        };
+        let pair_scores = Vec::<f64>::try_from(pair_scores)?;
+
+        let mut pair_scores = pair_scores.as_slice();
+        let mut premise_scores = premises
+            .iter()
+            .map(|_| {
+                hypotheses
+                    .iter()
+                    .map(|label_hypotheses| {
+                        let (scores, remaining) = pair_scores.split_at(label_hypotheses.len());
+                        pair_scores = remaining;
+                        scores.iter().sum::<f64>() / scores.len() as f64
+                    })
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<Vec<f64>>>();
+        if options.calibrate {
+            let content_free_scores = premise_scores.pop().unwrap();
+            for scores in premise_scores.iter_mut() {
+                for (score, content_free_score) in scores.iter_mut().zip(&content_free_scores) {
+                    *score -= content_free_score;
+                }
+            }
+        }
+
+        Ok(premise_scores
+            .into_iter()
+            .enumerate()
+            .map(|(sentence_idx, scores)| {
+                let probabilities = if options.multilabel {
+                    scores
+                        .iter()
+                        .map(|score| 1f64 / (1f64 + (-score).exp()))
+                        .collect::<Vec<f64>>()
+                } else {
+                    let max_score = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                    let exp_scores = scores
+                        .iter()
+                        .map(|score| (score - max_score).exp())
+                        .collect::<Vec<f64>>();
+                    let sum = exp_scores.iter().sum::<f64>();
+                    exp_scores.into_iter().map(|score| score / sum).collect()
+                };
+                probabilities
+                    .into_iter()
+                    .enumerate()
+                    .map(|(label_index, score)| Label {
+                        text: labels[label_index].to_string(),
+                        score,
+                        id: label_index as i64,
+                        sentence: sentence_idx,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
 }
 #[cfg(test)]
 mod test {
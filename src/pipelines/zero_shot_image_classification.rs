@@ -0,0 +1,260 @@
+// Copyright 2024 Google AI and The HuggingFace Team. All rights reserved.
+// Copyright 2019-2024 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Zero-shot image classification pipeline
+//! Scores a set of candidate labels for images with a SigLIP dual encoder, without training on the labels. Each label
+//! is turned into a caption with a hypothesis template (by default `This is a photo of {}.`) and scored against the image.
+//! SigLIP scores the labels independently: the score of a label is the sigmoid of its image-text logit, and the scores
+//! of an image do not sum to one (several labels or none can match an image).
+//!
+//! Images are expected as tensors of shape (*num channels*, *height*, *width*) with values in [0, 255], as
+//! loaded by `tch::vision::image::load`. They are resized to the image size of the model and normalized by the pipeline.
+//! By default, the dependencies for this model will be downloaded for the SigLIP base model (patch size 16, 224x224 images).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::zero_shot_image_classification::ZeroShotImageClassificationModel;
+//!
+//! let classification_model = ZeroShotImageClassificationModel::new(Default::default())?;
+//!
+//! let image = tch::vision::image::load("path/to/image.jpg")?;
+//! let candidate_labels = ["a cat", "a dog", "a car"];
+//! let output = classification_model.predict(&[image], &candidate_labels)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::zero_shot_image_classification::ImageLabel;
+//! let output = [[
+//!     ImageLabel {
+//!         text: String::from("a cat"),
+//!         score: 0.9304,
+//!         id: 0,
+//!         image: 0,
+//!     },
+//!     ImageLabel {
+//!         text: String::from("a dog"),
+//!         score: 0.0012,
+//!         id: 1,
+//!         image: 0,
+//!     },
+//!     ImageLabel {
+//!         text: String::from("a car"),
+//!         score: 0.0000,
+//!         id: 2,
+//!         image: 0,
+//!     },
+//! ]]
+//! # ;
+//! ```
+
+use crate::common::error::{InputError, RustBertError};
+use crate::pipelines::common::ModelResource;
+use crate::pipelines::multimodal_embeddings::{
+    MultimodalEmbeddingsConfig, MultimodalEmbeddingsModel,
+};
+use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
+use tch::{no_grad, Device, Tensor};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Label scored for an image by a `ZeroShotImageClassificationModel`
+pub struct ImageLabel {
+    /// Label String representation
+    pub text: String,
+    /// Probability of the label matching the image
+    pub score: f64,
+    /// Index of the label in the candidate labels
+    pub id: i64,
+    /// Index of the image in the input
+    pub image: usize,
+}
+
+/// # Configuration for ZeroShotImageClassificationModel
+/// Contains information regarding the model to load, the hypothesis template and device to place the model on.
+pub struct ZeroShotImageClassificationConfig {
+    /// Model weights resource (default: SigLIP base patch16 224)
+    pub model_resource: ModelResource,
+    /// Config resource (default: SigLIP base patch16 224)
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (default: SigLIP base patch16 224)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Template turning a label into a caption, the `{}` placeholder being replaced by the label (default: `"This is a photo of {}."`)
+    pub hypothesis_template: String,
+    /// Length of the token sequences of the captions. The pretrained models expect sequences of 64 tokens (default: 64)
+    pub max_length: usize,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl ZeroShotImageClassificationConfig {
+    /// Instantiate a new zero-shot image classification configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * model_resource - The `ResourceProvider` pointing to the model to load (e.g.  model.safetensors)
+    /// * config_resource - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab_resource - The `ResourceProvider` pointing to the tokenizer's SentencePiece model to load (e.g.  spiece.model)
+    pub fn new<RC, RV>(
+        model_resource: ModelResource,
+        config_resource: RC,
+        vocab_resource: RV,
+    ) -> ZeroShotImageClassificationConfig
+    where
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        MultimodalEmbeddingsConfig::new(model_resource, config_resource, vocab_resource).into()
+    }
+}
+
+impl From<MultimodalEmbeddingsConfig> for ZeroShotImageClassificationConfig {
+    fn from(config: MultimodalEmbeddingsConfig) -> Self {
+        ZeroShotImageClassificationConfig {
+            model_resource: config.model_resource,
+            config_resource: config.config_resource,
+            vocab_resource: config.vocab_resource,
+            hypothesis_template: "This is a photo of {}.".to_string(),
+            max_length: config.max_length,
+            device: config.device,
+        }
+    }
+}
+
+impl From<ZeroShotImageClassificationConfig> for MultimodalEmbeddingsConfig {
+    fn from(config: ZeroShotImageClassificationConfig) -> Self {
+        MultimodalEmbeddingsConfig {
+            model_resource: config.model_resource,
+            config_resource: config.config_resource,
+            vocab_resource: config.vocab_resource,
+            max_length: config.max_length,
+            device: config.device,
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Default for ZeroShotImageClassificationConfig {
+    /// Provides a default SigLIP base model (patch size 16, 224x224 images)
+    fn default() -> ZeroShotImageClassificationConfig {
+        MultimodalEmbeddingsConfig::default().into()
+    }
+}
+
+/// # ZeroShotImageClassificationModel to score candidate labels for images
+pub struct ZeroShotImageClassificationModel {
+    embeddings_model: MultimodalEmbeddingsModel,
+    hypothesis_template: String,
+}
+
+impl ZeroShotImageClassificationModel {
+    /// Build a new `ZeroShotImageClassificationModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `ZeroShotImageClassificationConfig` object containing the resource references (model, vocabulary, configuration), hypothesis template and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_image_classification::ZeroShotImageClassificationModel;
+    ///
+    /// let classification_model = ZeroShotImageClassificationModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        config: ZeroShotImageClassificationConfig,
+    ) -> Result<ZeroShotImageClassificationModel, RustBertError> {
+        let hypothesis_template = config.hypothesis_template.clone();
+        let embeddings_model = MultimodalEmbeddingsModel::new(config.into())?;
+        Ok(ZeroShotImageClassificationModel {
+            embeddings_model,
+            hypothesis_template,
+        })
+    }
+
+    /// Get a reference to the underlying multimodal embeddings model.
+    pub fn get_embeddings_model(&self) -> &MultimodalEmbeddingsModel {
+        &self.embeddings_model
+    }
+
+    /// Scores the candidate labels for images
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Image tensors of shape (*num channels*, *height*, *width*) with values in [0, 255]
+    /// * `candidate_labels` - Labels to score for each image, turned into captions with the hypothesis template
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<ImageLabel>>` containing the scored labels of each image, sorted by decreasing score
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_image_classification::ZeroShotImageClassificationModel;
+    ///
+    /// let classification_model = ZeroShotImageClassificationModel::new(Default::default())?;
+    /// let image = tch::vision::image::load("path/to/image.jpg")?;
+    /// let output = classification_model.predict(&[image], &["a cat", "a dog"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<S>(
+        &self,
+        images: &[Tensor],
+        candidate_labels: &[S],
+    ) -> Result<Vec<Vec<ImageLabel>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if candidate_labels.is_empty() {
+            return Err(InputError::NoCandidateLabels.into());
+        }
+        let pixel_values = self.embeddings_model.prepare_images(images)?;
+        let captions = candidate_labels
+            .iter()
+            .map(|label| self.hypothesis_template.replace("{}", label.as_ref()))
+            .collect::<Vec<String>>();
+        let input_ids = self.embeddings_model.tokenize(&captions)?;
+
+        let scores = no_grad(|| {
+            self.embeddings_model
+                .get_model()
+                .forward_t(&input_ids, &pixel_values, None, false)
+                .logits_per_image
+                .sigmoid()
+        });
+
+        Ok((0..images.len())
+            .map(|image| {
+                let mut labels = candidate_labels
+                    .iter()
+                    .enumerate()
+                    .map(|(id, label)| ImageLabel {
+                        text: label.as_ref().to_string(),
+                        score: scores.double_value(&[image as i64, id as i64]),
+                        id: id as i64,
+                        image,
+                    })
+                    .collect::<Vec<ImageLabel>>();
+                labels.sort_by(|label, other| other.score.total_cmp(&label.score));
+                labels
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright 2019-2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Python bindings
+//! Exposes the main pipelines to Python through [PyO3](https://pyo3.rs), behind the `python` feature:
+//! text generation, sentence embeddings, named entity recognition, question answering and summarization.
+//! The pipelines are created with their default pretrained models, or from a local directory for sentence embeddings.
+//!
+//! The Python extension module can be built with [maturin](https://www.maturin.rs) from the root of the repository
+//! (see `pyproject.toml`), which compiles the crate as a dynamic library with the `python` feature enabled:
+//!
+//! ```bash
+//! maturin develop --release
+//! ```
+//!
+//! ```python
+//! import rust_bert
+//!
+//! ner = rust_bert.NERModel(device="cpu")
+//! entities = ner.predict(["My name is Amy. I live in Paris."])
+//! print([(entity.word, entity.label) for entity in entities[0]])
+//!
+//! embeddings = rust_bert.SentenceEmbeddingsModel()
+//! vectors = embeddings.encode(["This is an example sentence"])
+//! ```
+//!
+//! The pipelines release the GIL while running, so that other Python threads are not blocked by the model forward passes.
+
+use crate::pipelines::ner::{Entity, NERModel};
+use crate::pipelines::question_answering::{
+    Answer, QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
+};
+use crate::pipelines::sentence_embeddings::{
+    SentenceEmbeddingsBuilder, SentenceEmbeddingsModel, SentenceEmbeddingsModelType,
+};
+use crate::pipelines::summarization::{SummarizationConfig, SummarizationModel};
+use crate::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+use crate::pipelines::token_classification::TokenClassificationConfig;
+use crate::settings::{self, default_device};
+use crate::RustBertError;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use tch::Device;
+
+impl From<RustBertError> for PyErr {
+    fn from(error: RustBertError) -> Self {
+        match error {
+            RustBertError::ValueError(_)
+            | RustBertError::InvalidConfigurationError(_)
+            | RustBertError::InputError(_) => PyValueError::new_err(error.to_string()),
+            _ => PyRuntimeError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// Parses a device name ("cpu", "cuda", "cuda:<index>", "mps" or "auto"), defaulting to the configured default device
+fn parse_device(device: Option<&str>) -> PyResult<Device> {
+    match device {
+        None => Ok(default_device()),
+        Some(device) => Ok(settings::parse_device(device)?),
+    }
+}
+
+/// Named entity extracted by `NERModel`
+#[pyclass(name = "Entity", module = "rust_bert")]
+#[derive(Clone)]
+pub struct PyEntity {
+    /// Text of the entity
+    #[pyo3(get)]
+    pub word: String,
+    /// Confidence score
+    #[pyo3(get)]
+    pub score: f64,
+    /// Entity label (e.g. ORG, LOC...)
+    #[pyo3(get)]
+    pub label: String,
+    /// Start character offset of the entity
+    #[pyo3(get)]
+    pub start: u32,
+    /// End character offset of the entity
+    #[pyo3(get)]
+    pub end: u32,
+}
+
+#[pymethods]
+impl PyEntity {
+    fn __repr__(&self) -> String {
+        format!(
+            "Entity(word={:?}, label={:?}, score={:.4}, start={}, end={})",
+            self.word, self.label, self.score, self.start, self.end
+        )
+    }
+}
+
+impl From<Entity> for PyEntity {
+    fn from(entity: Entity) -> Self {
+        PyEntity {
+            word: entity.word,
+            score: entity.score,
+            label: entity.label,
+            start: entity.offset.begin,
+            end: entity.offset.end,
+        }
+    }
+}
+
+/// Answer extracted by `QuestionAnsweringModel`
+#[pyclass(name = "Answer", module = "rust_bert")]
+#[derive(Clone)]
+pub struct PyAnswer {
+    /// Text of the answer span
+    #[pyo3(get)]
+    pub answer: String,
+    /// Confidence score
+    #[pyo3(get)]
+    pub score: f64,
+    /// Start character position of the answer span in the context
+    #[pyo3(get)]
+    pub start: usize,
+    /// End character position of the answer span in the context
+    #[pyo3(get)]
+    pub end: usize,
+}
+
+#[pymethods]
+impl PyAnswer {
+    fn __repr__(&self) -> String {
+        format!(
+            "Answer(answer={:?}, score={:.4}, start={}, end={})",
+            self.answer, self.score, self.start, self.end
+        )
+    }
+}
+
+impl From<Answer> for PyAnswer {
+    fn from(answer: Answer) -> Self {
+        PyAnswer {
+            answer: answer.answer,
+            score: answer.score,
+            start: answer.start,
+            end: answer.end,
+        }
+    }
+}
+
+/// Text generation pipeline (default: GPT2)
+#[pyclass(name = "TextGenerationModel", module = "rust_bert")]
+pub struct PyTextGenerationModel {
+    model: TextGenerationModel,
+}
+
+#[pymethods]
+impl PyTextGenerationModel {
+    #[new]
+    #[pyo3(signature = (max_length = None, do_sample = None, num_beams = None, device = None))]
+    fn new(
+        max_length: Option<i64>,
+        do_sample: Option<bool>,
+        num_beams: Option<i64>,
+        device: Option<&str>,
+    ) -> PyResult<Self> {
+        let default_config = TextGenerationConfig::default();
+        let config = TextGenerationConfig {
+            max_length: max_length.or(default_config.max_length),
+            do_sample: do_sample.unwrap_or(default_config.do_sample),
+            num_beams: num_beams.unwrap_or(default_config.num_beams),
+            device: parse_device(device)?,
+            ..default_config
+        };
+        Ok(PyTextGenerationModel {
+            model: TextGenerationModel::new(config)?,
+        })
+    }
+
+    /// Generates texts continuing the prompts, with an optional prefix excluded from the outputs
+    #[pyo3(signature = (texts, prefix = None))]
+    fn generate(&self, py: Python<'_>, texts: Vec<String>, prefix: Option<&str>) -> Vec<String> {
+        py.allow_threads(|| self.model.generate(&texts, prefix))
+    }
+}
+
+/// Sentence embeddings pipeline (default: all-MiniLM-L12-v2)
+#[pyclass(name = "SentenceEmbeddingsModel", module = "rust_bert")]
+pub struct PySentenceEmbeddingsModel {
+    model: SentenceEmbeddingsModel,
+}
+
+#[pymethods]
+impl PySentenceEmbeddingsModel {
+    /// Loads the default remote model, or a local model directory (as created by sentence-transformers)
+    #[new]
+    #[pyo3(signature = (model_dir = None, device = None))]
+    fn new(model_dir: Option<String>, device: Option<&str>) -> PyResult<Self> {
+        let device = parse_device(device)?;
+        let model = match model_dir {
+            Some(model_dir) => SentenceEmbeddingsBuilder::local(model_dir)
+                .with_device(device)
+                .create_model()?,
+            None => SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+                .with_device(device)
+                .create_model()?,
+        };
+        Ok(PySentenceEmbeddingsModel { model })
+    }
+
+    /// Computes the embeddings of the texts
+    fn encode(&self, py: Python<'_>, texts: Vec<String>) -> PyResult<Vec<Vec<f32>>> {
+        Ok(py.allow_threads(|| self.model.encode(&texts))?)
+    }
+
+    /// Dimension of the embeddings
+    #[getter]
+    fn embedding_dim(&self) -> PyResult<i64> {
+        Ok(self.model.get_embedding_dim()?)
+    }
+}
+
+/// Named entity recognition pipeline (default: BERT fine-tuned on CoNLL-2003)
+#[pyclass(name = "NERModel", module = "rust_bert")]
+pub struct PyNERModel {
+    model: NERModel,
+}
+
+#[pymethods]
+impl PyNERModel {
+    #[new]
+    #[pyo3(signature = (device = None))]
+    fn new(device: Option<&str>) -> PyResult<Self> {
+        let config = TokenClassificationConfig {
+            device: parse_device(device)?,
+            ..Default::default()
+        };
+        Ok(PyNERModel {
+            model: NERModel::new(config)?,
+        })
+    }
+
+    /// Extracts the entities of each text, one entity per token
+    fn predict(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<PyEntity>> {
+        convert_entities(py.allow_threads(|| self.model.predict(&texts)))
+    }
+
+    /// Extracts the entities of each text, merging the tokens of multi-token entities
+    fn predict_full_entities(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<PyEntity>> {
+        convert_entities(py.allow_threads(|| self.model.predict_full_entities(&texts)))
+    }
+}
+
+fn convert_entities(entities: Vec<Vec<Entity>>) -> Vec<Vec<PyEntity>> {
+    entities
+        .into_iter()
+        .map(|entities| entities.into_iter().map(PyEntity::from).collect())
+        .collect()
+}
+
+/// Extractive question answering pipeline (default: DistilBERT fine-tuned on SQuAD)
+#[pyclass(name = "QuestionAnsweringModel", module = "rust_bert")]
+pub struct PyQuestionAnsweringModel {
+    model: QuestionAnsweringModel,
+}
+
+#[pymethods]
+impl PyQuestionAnsweringModel {
+    #[new]
+    #[pyo3(signature = (device = None))]
+    fn new(device: Option<&str>) -> PyResult<Self> {
+        let config = QuestionAnsweringConfig {
+            device: parse_device(device)?,
+            ..Default::default()
+        };
+        Ok(PyQuestionAnsweringModel {
+            model: QuestionAnsweringModel::new(config)?,
+        })
+    }
+
+    /// Answers each (question, context) pair, returning the `top_k` best answers
+    #[pyo3(signature = (questions, contexts, top_k = 1, batch_size = 32))]
+    fn predict(
+        &self,
+        py: Python<'_>,
+        questions: Vec<String>,
+        contexts: Vec<String>,
+        top_k: i64,
+        batch_size: usize,
+    ) -> PyResult<Vec<Vec<PyAnswer>>> {
+        if questions.len() != contexts.len() {
+            return Err(PyValueError::new_err(format!(
+                "Got {} questions for {} contexts",
+                questions.len(),
+                contexts.len()
+            )));
+        }
+        let qa_inputs = questions
+            .into_iter()
+            .zip(contexts)
+            .map(|(question, context)| QaInput { question, context })
+            .collect::<Vec<QaInput>>();
+        Ok(py
+            .allow_threads(|| self.model.predict(&qa_inputs, top_k, batch_size))
+            .into_iter()
+            .map(|answers| answers.into_iter().map(PyAnswer::from).collect())
+            .collect())
+    }
+}
+
+/// Abstractive summarization pipeline (default: BART fine-tuned on CNN/DailyMail)
+#[pyclass(name = "SummarizationModel", module = "rust_bert")]
+pub struct PySummarizationModel {
+    model: SummarizationModel,
+}
+
+#[pymethods]
+impl PySummarizationModel {
+    #[new]
+    #[pyo3(signature = (min_length = None, max_length = None, num_beams = None, device = None))]
+    fn new(
+        min_length: Option<i64>,
+        max_length: Option<i64>,
+        num_beams: Option<i64>,
+        device: Option<&str>,
+    ) -> PyResult<Self> {
+        let default_config = SummarizationConfig::default();
+        let config = SummarizationConfig {
+            min_length: min_length.unwrap_or(default_config.min_length),
+            max_length: max_length.or(default_config.max_length),
+            num_beams: num_beams.unwrap_or(default_config.num_beams),
+            device: parse_device(device)?,
+            ..default_config
+        };
+        Ok(PySummarizationModel {
+            model: SummarizationModel::new(config)?,
+        })
+    }
+
+    /// Summarizes the texts
+    fn summarize(&self, py: Python<'_>, texts: Vec<String>) -> Vec<String> {
+        py.allow_threads(|| self.model.summarize(&texts))
+    }
+}
+
+/// Python extension module
+#[pymodule]
+fn rust_bert(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyTextGenerationModel>()?;
+    module.add_class::<PySentenceEmbeddingsModel>()?;
+    module.add_class::<PyNERModel>()?;
+    module.add_class::<PyQuestionAnsweringModel>()?;
+    module.add_class::<PySummarizationModel>()?;
+    module.add_class::<PyEntity>()?;
+    module.add_class::<PyAnswer>()?;
+    Ok(())
+}
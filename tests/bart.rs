@@ -1,14 +1,21 @@
 use rust_bert::bart::{
-    BartConfig, BartConfigResources, BartMergesResources, BartModel, BartModelResources,
-    BartVocabResources,
+    BartConfig, BartConfigResources, BartGenerator, BartMergesResources, BartModel,
+    BartModelResources, BartVocabResources,
+};
+use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::faithfulness::{FaithfulnessConfig, FaithfulnessModel};
+use rust_bert::pipelines::generation_utils::{
+    EncoderOutputCache, GenerateConfig, GenerateOptions, LanguageGenerator, TokenTrie,
+};
+use rust_bert::pipelines::nli::{NliConfig, NliInput, NliLabel, NliModel};
+use rust_bert::pipelines::summarization::{
+    StreamingSummarizer, SummarizationConfig, SummarizationModel,
 };
-use rust_bert::pipelines::common::ModelResource;
-use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
 use rust_bert::pipelines::zero_shot_classification::{
-    ZeroShotClassificationConfig, ZeroShotClassificationModel,
+    ZeroShotClassificationConfig, ZeroShotClassificationModel, ZeroShotOptions,
 };
 use rust_bert::resources::{RemoteResource, ResourceProvider};
-use rust_bert::{Config, RustBertError};
+use rust_bert::{Config, InputError, RustBertError};
 use rust_tokenizers::tokenizer::{RobertaTokenizer, Tokenizer, TruncationStrategy};
 use tch::{nn, Device, Tensor};
 
@@ -137,6 +144,55 @@ about exoplanets like K2-18b."];
     Ok(())
 }
 
+#[test]
+fn bart_summarization_encoder_cache() -> anyhow::Result<()> {
+    let encoder_cache = EncoderOutputCache::new(2);
+    let summarization_config = SummarizationConfig {
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        ))),
+        num_beams: 1,
+        min_length: 8,
+        max_length: Some(32),
+        device: Device::Cpu,
+        encoder_cache: Some(encoder_cache.clone()),
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+
+    let input = ["The presence of water vapour was confirmed in the atmosphere of K2-18b, a planet \
+circling a star in the constellation Leo. This is the first such discovery in a planet in its star's habitable zone."];
+    let other_input = [
+        "K2-18b was first identified in 2015 by the Kepler space telescope. It is about \
+110 light-years from Earth and larger but less dense.",
+    ];
+
+    let output = model.summarize(&input);
+    assert_eq!(encoder_cache.len(), 1);
+    let cached_output = model.summarize(&input);
+    assert_eq!(encoder_cache.len(), 1);
+    assert_eq!(output, cached_output);
+
+    let _ = model.summarize(&other_input);
+    assert_eq!(encoder_cache.len(), 2);
+    let _ = model.summarize(&["A third document evicting the least recently used entry."]);
+    assert_eq!(encoder_cache.len(), 2);
+
+    encoder_cache.clear();
+    assert!(encoder_cache.is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn bart_summarization_beam_search() -> anyhow::Result<()> {
     let config_resource = Box::new(RemoteResource::from_pretrained(
@@ -198,6 +254,189 @@ about exoplanets like K2-18b."];
     Ok(())
 }
 
+#[test]
+fn bart_summarization_with_options() -> anyhow::Result<()> {
+    let summarization_config = SummarizationConfig {
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        ))),
+        num_beams: 1,
+        min_length: 10,
+        max_length: Some(40),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+
+    let input = ["The presence of water vapour was confirmed in the atmosphere of K2-18b, a planet \
+circling a star in the constellation Leo. This is the first such discovery in a planet in its star's \
+habitable zone, not too hot and not too cold for liquid water to exist."];
+    let forced_token_id = model
+        .get_tokenizer()
+        .convert_tokens_to_ids(&["ĠAstronomers"])[0];
+    let generate_options = GenerateOptions {
+        decoder_start_token_id: Some(2),
+        forced_bos_token_id: Some(forced_token_id),
+        ..Default::default()
+    };
+    let output = model.summarize_with_options(&input, generate_options);
+
+    assert_eq!(output.len(), 1);
+    assert!(output[0].starts_with(" Astronomers"));
+
+    Ok(())
+}
+
+#[test]
+fn bart_generation_from_set() -> anyhow::Result<()> {
+    let generate_config = GenerateConfig {
+        model_type: ModelType::Bart,
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        ))),
+        min_length: 0,
+        max_length: Some(16),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    //    Entity retrieval (GENRE): the outputs are entity names, generated after the forced BOS token of the model
+    let entities = [" Albert Einstein", " Isaac Newton", " Marie Curie"];
+    let trie = TokenTrie::from_strings(model.get_tokenizer(), &entities)?;
+    let input =
+        ["The theory of general relativity was published by the German-born physicist in 1915."];
+
+    for num_beams in [1, 3] {
+        let generate_options = GenerateOptions {
+            num_beams: Some(num_beams),
+            allowed_outputs: Some(&trie),
+            output_scores: true,
+            ..Default::default()
+        };
+        let output = model.generate_indices(Some(&input), Some(generate_options));
+
+        assert_eq!(output.len(), 1);
+        let text = model.get_tokenizer().decode(&output[0].indices, true, true);
+        assert!(
+            entities.iter().any(|entity| entity.trim() == text.trim()),
+            "{text}"
+        );
+        assert!(output[0].score.unwrap().is_finite());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bart_contrastive_search_fallback() -> anyhow::Result<()> {
+    let generate_config = GenerateConfig {
+        model_type: ModelType::Bart,
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        ))),
+        max_length: Some(24),
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    //    Contrastive search is not supported by encoder-decoder models, which fall back to greedy decoding
+    let input = [
+        "The presence of water vapour was confirmed in the atmosphere of K2-18b, a planet \
+circling a star in the constellation Leo.",
+    ];
+    let contrastive_options = GenerateOptions {
+        penalty_alpha: Some(0.6),
+        top_k: Some(4),
+        ..Default::default()
+    };
+    let contrastive_output = model.generate_indices(Some(&input), Some(contrastive_options));
+    let greedy_output = model.generate_indices(Some(&input), None);
+
+    assert_eq!(contrastive_output.len(), 1);
+    assert_eq!(contrastive_output[0].indices, greedy_output[0].indices);
+
+    Ok(())
+}
+
+#[test]
+fn bart_streaming_summarization() -> anyhow::Result<()> {
+    let summarization_config = SummarizationConfig {
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        ))),
+        num_beams: 1,
+        min_length: 10,
+        max_length: Some(60),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+    let mut summarizer = StreamingSummarizer::new(&model, 64);
+
+    let chunks = [
+        "The presence of water vapour was confirmed in the atmosphere of K2-18b, a planet circling a star in the constellation Leo.",
+        "This is the first such discovery in a planet in its star's habitable zone, not too hot and not too cold for liquid water to exist.",
+        "The Montreal team used data from the NASA's Hubble telescope to assess changes in the light coming from K2-18b's star.",
+        "K2-18b was first identified in 2015 by the Kepler space telescope. It is about 110 light-years from Earth.",
+    ];
+    let mut folds = 0;
+    for chunk in chunks.iter() {
+        if summarizer.push(chunk) {
+            folds += 1;
+            assert_eq!(summarizer.pending_length(), 0);
+        }
+    }
+    assert!(folds >= 1);
+    summarizer.flush();
+
+    assert_eq!(summarizer.pending_length(), 0);
+    assert!(summarizer.current_summary().contains("K2-18b"));
+    assert!(!summarizer.flush());
+
+    summarizer.reset();
+    assert!(summarizer.current_summary().is_empty());
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn bart_zero_shot_classification() -> anyhow::Result<()> {
@@ -251,7 +490,7 @@ fn bart_zero_shot_classification_try_error() -> anyhow::Result<()> {
     );
 
     let output_is_error = match output {
-        Err(RustBertError::ValueError(_)) => true,
+        Err(RustBertError::InputError(InputError::EmptyInput)) => true,
         _ => unreachable!(),
     };
     assert!(output_is_error);
@@ -326,10 +565,159 @@ fn bart_zero_shot_classification_multilabel_try_error() -> anyhow::Result<()> {
     );
 
     let output_is_error = match output {
-        Err(RustBertError::ValueError(_)) => true,
+        Err(RustBertError::InputError(InputError::EmptyInput)) => true,
         _ => unreachable!(),
     };
     assert!(output_is_error);
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_templates() -> anyhow::Result<()> {
+    //    Set-up model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    let input_sentence = "Who are you voting for in 2020?";
+    let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+
+    // The default template with multi-label scoring matches `predict_multilabel`
+    let reference = sequence_classification_model.predict_multilabel(
+        [input_sentence, input_sequence_2],
+        candidate_labels,
+        None,
+        128,
+    )?;
+    let output = sequence_classification_model.predict_with_options(
+        [input_sentence, input_sequence_2],
+        candidate_labels,
+        &ZeroShotOptions {
+            multilabel: true,
+            ..Default::default()
+        },
+        128,
+    )?;
+    for (reference_labels, labels) in reference.iter().zip(output.iter()) {
+        for (reference_label, label) in reference_labels.iter().zip(labels.iter()) {
+            assert_eq!(reference_label.text, label.text);
+            assert!((reference_label.score - label.score).abs() < 1e-4);
+        }
+    }
+
+    // Ensembled and calibrated single-label prediction
+    let options = ZeroShotOptions {
+        templates: vec![
+            "This example is about {}.".to_string(),
+            "The topic of this text is {}.".to_string(),
+        ],
+        calibrate: true,
+        ..Default::default()
+    }
+    .with_label_templates(
+        "sports",
+        vec!["This text is about a sports event.".to_string()],
+    );
+    let output = sequence_classification_model.predict_with_options(
+        [input_sentence, input_sequence_2],
+        candidate_labels,
+        &options,
+        128,
+    )?;
+    assert_eq!(output.len(), 2);
+    for labels in output.iter() {
+        assert_eq!(labels.len(), candidate_labels.len());
+        assert!((labels.iter().map(|label| label.score).sum::<f64>() - 1.0).abs() < 1e-6);
+    }
+    assert_eq!(output[0][0].text, "politics");
+    assert!(output[0][0].score > 0.5);
+    assert!(output[1][2].score > output[1][1].score);
+    assert!(output[1][2].score > output[1][3].score);
+
+    let no_template = ZeroShotOptions {
+        templates: vec![],
+        ..Default::default()
+    };
+    assert!(matches!(
+        sequence_classification_model.predict_with_options(
+            [input_sentence],
+            candidate_labels,
+            &no_template,
+            128
+        ),
+        Err(RustBertError::ValueError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_faithfulness() -> anyhow::Result<()> {
+    //    Set-up model
+    let faithfulness_config = FaithfulnessConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let faithfulness_model = FaithfulnessModel::new(faithfulness_config)?;
+
+    let source = "The Eiffel tower was completed in 1889 for the World's Fair. It is located in Paris, on the Champ de Mars.";
+    let summary = "The Eiffel tower, located in Paris, was completed in 1889. It was built for the Olympic Games.";
+
+    let output = faithfulness_model.check(&[source], &[summary], 0.5)?;
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].sentences.len(), 2);
+    assert!(!output[0].sentences[0].is_hallucination);
+    assert!(output[0].sentences[1].is_hallucination);
+    assert!(output[0].has_hallucinations());
+    assert!((output[0].score - output[0].sentences[1].entailment).abs() < 1e-9);
+
+    assert!(faithfulness_model
+        .check(&[source], &[summary, summary], 0.5)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_nli() -> anyhow::Result<()> {
+    //    Set-up model
+    let nli_config = NliConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let nli_model = NliModel::new(nli_config)?;
+
+    let premise = "A man is playing a guitar on stage.";
+    let output = nli_model.predict(
+        &[
+            NliInput::new(premise, "A man is performing music."),
+            NliInput::new(premise, "The stage is empty."),
+            NliInput::new(premise, "The man is a famous musician."),
+        ],
+        2,
+    )?;
+
+    assert_eq!(output.len(), 3);
+    assert_eq!(output[0].label, NliLabel::Entailment);
+    assert_eq!(output[1].label, NliLabel::Contradiction);
+    assert_eq!(output[2].label, NliLabel::Neutral);
+    for prediction in output {
+        assert!(
+            (prediction.entailment + prediction.neutral + prediction.contradiction - 1.0).abs()
+                < 1e-4
+        );
+    }
+
+    let no_input: [(&str, &str); 0] = [];
+    assert!(matches!(
+        nli_model.predict_pairs(&no_input, 2),
+        Err(RustBertError::InputError(InputError::EmptyInput))
+    ));
+    Ok(())
+}
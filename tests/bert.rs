@@ -2,16 +2,22 @@ extern crate anyhow;
 extern crate dirs;
 
 use rust_bert::bert::{
-    BertConfig, BertConfigResources, BertForMaskedLM, BertForMultipleChoice,
-    BertForQuestionAnswering, BertForSequenceClassification, BertForTokenClassification,
-    BertModelResources, BertVocabResources,
+    BertConfig, BertConfigResources, BertForEarlyExitSequenceClassification, BertForMaskedLM,
+    BertForMultipleChoice, BertForQuestionAnswering, BertForSequenceClassification,
+    BertForTokenClassification, BertModelResources, BertVocabResources,
 };
 use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::lexical_substitution::{
+    LexicalSubstitutionConfig, LexicalSubstitutionModel, SubstitutionTarget,
+};
 use rust_bert::pipelines::masked_language::{MaskedLanguageConfig, MaskedLanguageModel};
 use rust_bert::pipelines::ner::NERModel;
 use rust_bert::pipelines::question_answering::{
     QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
 };
+use rust_bert::pipelines::reranking::{RerankingConfig, RerankingModel};
+use rust_bert::pipelines::safety::{SafetyAction, SafetyClassifier, SafetyConfig};
+use rust_bert::pruning::PruningConfig;
 use rust_bert::resources::{RemoteResource, ResourceProvider};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
@@ -101,6 +107,96 @@ fn bert_masked_lm() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_pruning() -> anyhow::Result<()> {
+    //    Resources paths
+    let config_resource = RemoteResource::from_pretrained(BertConfigResources::BERT);
+    let vocab_resource = RemoteResource::from_pretrained(BertVocabResources::BERT);
+    let weights_resource = RemoteResource::from_pretrained(BertModelResources::BERT);
+    let config_path = config_resource.get_local_path()?;
+    let vocab_path = vocab_resource.get_local_path()?;
+    let weights_path = weights_resource.get_local_path()?;
+
+    //    Set-up masked LM model
+    let device = Device::Cpu;
+    let mut vs = nn::VarStore::new(device);
+    let tokenizer: BertTokenizer =
+        BertTokenizer::from_file(vocab_path.to_str().unwrap(), true, true)?;
+    let config = BertConfig::from_file(config_path);
+    let mut bert_model = BertForMaskedLM::new(vs.root(), &config);
+    vs.load(weights_path)?;
+
+    //    Heads and neurons without contribution to the output: their output weights are zeroed
+    let head_size = config.hidden_size / config.num_attention_heads;
+    no_grad(|| {
+        let variables = vs.variables();
+        let _ = variables["bert.encoder.layer.0.attention.output.dense.weight"]
+            .narrow(1, 3 * head_size, head_size)
+            .zero_();
+        let _ = variables["bert.encoder.layer.5.output.dense.weight"]
+            .narrow(1, 0, 512)
+            .zero_();
+    });
+
+    let tokenized_input = tokenizer.encode_list(
+        &["Looks like one [MASK] is missing"],
+        128,
+        &TruncationStrategy::LongestFirst,
+        0,
+    );
+    let input_tensor = Tensor::from_slice(&tokenized_input[0].token_ids)
+        .unsqueeze(0)
+        .to(device);
+    let forward = |model: &BertForMaskedLM| {
+        no_grad(|| {
+            model
+                .forward_t(
+                    Some(&input_tensor),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .prediction_scores
+        })
+    };
+    let original_output = forward(&bert_model);
+
+    let pruning_config = PruningConfig {
+        heads: HashMap::from([(0, vec![3])]),
+        intermediate_neurons: HashMap::from([(5, (0..512).collect())]),
+    };
+    bert_model.prune(&pruning_config)?;
+    assert_eq!(
+        vs.variables()["bert.encoder.layer.0.attention.self.query.weight"].size(),
+        vec![config.hidden_size - head_size, config.hidden_size]
+    );
+    assert_eq!(
+        vs.variables()["bert.encoder.layer.5.intermediate.dense.weight"].size(),
+        vec![config.intermediate_size - 512, config.hidden_size]
+    );
+
+    let pruned_output = forward(&bert_model);
+    assert!(pruned_output.allclose(&original_output, 1e-4, 1e-4, false));
+
+    //    Invalid pruning configurations
+    let all_heads = PruningConfig {
+        heads: HashMap::from([(1, (0..config.num_attention_heads).collect())]),
+        ..Default::default()
+    };
+    assert!(bert_model.prune(&all_heads).is_err());
+    let missing_layer = PruningConfig {
+        heads: HashMap::from([(12, vec![0])]),
+        ..Default::default()
+    };
+    assert!(bert_model.prune(&missing_layer).is_err());
+
+    Ok(())
+}
+
 #[test]
 fn bert_masked_lm_pipeline() -> anyhow::Result<()> {
     //    Set-up model
@@ -143,6 +239,50 @@ fn bert_masked_lm_pipeline() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_lexical_substitution() -> anyhow::Result<()> {
+    //    Set-up model
+    let config = LexicalSubstitutionConfig {
+        num_candidates: 5,
+        ..LexicalSubstitutionConfig::new(MaskedLanguageConfig {
+            device: Device::Cpu,
+            ..Default::default()
+        })
+    };
+    let model = LexicalSubstitutionModel::new(config)?;
+
+    let target = SubstitutionTarget::from_marked("She was very [[happy]] with the results.")?;
+    assert_eq!(target.text, "She was very happy with the results.");
+    assert_eq!(target.target(), "happy");
+    assert!(SubstitutionTarget::from_marked("No marked word").is_err());
+    assert!(SubstitutionTarget::new("Short text", 5..50).is_err());
+
+    let output = model.substitute(&[
+        target,
+        SubstitutionTarget::new("The old car broke down on the highway.", 8..11)?,
+    ])?;
+
+    assert_eq!(output.len(), 2);
+    for substitutions in output.iter() {
+        assert!(!substitutions.is_empty());
+        assert!(substitutions.len() <= 5);
+        assert!(substitutions
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+        assert!(substitutions.iter().all(|substitution| {
+            (0.0..=1.0).contains(&substitution.fluency)
+                && (0.0..=1.0).contains(&substitution.similarity)
+        }));
+    }
+    assert!(output[0]
+        .iter()
+        .all(|substitution| substitution.text != "happy"));
+    assert!(output[1]
+        .iter()
+        .all(|substitution| substitution.text != "car"));
+    Ok(())
+}
+
 #[test]
 fn bert_for_sequence_classification() -> anyhow::Result<()> {
     //    Resources paths
@@ -205,6 +345,53 @@ fn bert_for_sequence_classification() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_for_early_exit_sequence_classification() -> anyhow::Result<()> {
+    //    Resources paths
+    let config_resource = RemoteResource::from_pretrained(BertConfigResources::BERT);
+    let config_path = config_resource.get_local_path()?;
+
+    //    Set-up model
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let mut config = BertConfig::from_file(config_path);
+    let mut dummy_label_mapping = HashMap::new();
+    dummy_label_mapping.insert(0, String::from("Positive"));
+    dummy_label_mapping.insert(1, String::from("Negative"));
+    config.id2label = Some(dummy_label_mapping);
+    config.num_hidden_layers = 4;
+    let mut bert_model = BertForEarlyExitSequenceClassification::new(vs.root(), &config, 0.0)?;
+
+    let input_tensor =
+        Tensor::from_slice(&[101i64, 2023, 2003, 1037, 3231, 102, 101, 2307, 102, 0, 0, 0])
+            .view([2, 6])
+            .to(device);
+    let mask = input_tensor.ne(0).to_kind(tch::Kind::Int64);
+
+    //    No early exit: all inputs go through the full model
+    let model_output = no_grad(|| {
+        bert_model.forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
+    })?;
+    assert_eq!(model_output.logits.size(), &[2, 2]);
+    assert_eq!(model_output.exit_layers, vec![4, 4]);
+
+    //    The maximum entropy for 2 classes is ln(2): all inputs exit after the first layer
+    bert_model.set_entropy_threshold(1.0);
+    let early_output = no_grad(|| {
+        bert_model.forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
+    })?;
+    assert_eq!(early_output.logits.size(), &[2, 2]);
+    assert_eq!(early_output.exit_layers, vec![1, 1]);
+
+    //    Training mode returns the logits of every exit head
+    let train_output =
+        bert_model.forward_t(Some(&input_tensor), Some(&mask), None, None, None, true)?;
+    assert_eq!(train_output.exit_layers, vec![4, 4]);
+    assert_eq!(train_output.all_exit_logits.unwrap().len(), 4);
+
+    Ok(())
+}
+
 #[test]
 fn bert_for_multiple_choice() -> anyhow::Result<()> {
     //    Resources paths
@@ -486,3 +673,96 @@ fn bert_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn mini_lm_question_answering() -> anyhow::Result<()> {
+    //    Set-up question answering model
+    let config = QuestionAnsweringConfig {
+        model_type: ModelType::Bert,
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BertModelResources::MINI_LM_SQUAD2,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            BertConfigResources::MINI_LM_SQUAD2,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            BertVocabResources::MINI_LM_SQUAD2,
+        )),
+        lower_case: true,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+
+    let qa_model = QuestionAnsweringModel::new(config)?;
+
+    //    Define input
+    let question = String::from("Where does Amy live ?");
+    let context = String::from("Amy lives in Amsterdam");
+    let qa_input = QaInput { question, context };
+
+    let answers = qa_model.predict(&[qa_input], 1, 32);
+
+    assert_eq!(answers.len(), 1usize);
+    assert_eq!(answers[0].len(), 1usize);
+    assert_eq!(answers[0][0].answer, "Amsterdam");
+
+    Ok(())
+}
+
+#[test]
+fn mini_lm_reranking() -> anyhow::Result<()> {
+    //    Set-up model
+    let config = RerankingConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let reranking_model = RerankingModel::new(config)?;
+
+    //    Define input
+    let query = "How many people live in Berlin?";
+    let passages = [
+        "New York City is famous for the Metropolitan Museum of Art.",
+        "Berlin has a population of 3,520,031 registered inhabitants in an area of 891.82 square kilometers.",
+        "Berlin is well known for its museums.",
+    ];
+
+    //    Run model
+    let scores = reranking_model.score(query, &passages);
+    let ranked_passages = reranking_model.rerank(query, &passages);
+
+    assert_eq!(scores.len(), 3);
+    assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+    assert_eq!(ranked_passages.len(), 3);
+    assert_eq!(ranked_passages[0].index, 1);
+    assert_eq!(ranked_passages[2].index, 0);
+    assert!(ranked_passages[0].score >= ranked_passages[1].score);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn toxic_bert_safety_screening() -> anyhow::Result<()> {
+    //    Set-up model
+    let config = SafetyConfig {
+        action: SafetyAction::Redact,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let safety_classifier = SafetyClassifier::new(config)?;
+
+    //    Define input
+    let input = ["Have a great day!", "You are a complete idiot."];
+
+    //    Run model
+    let output = safety_classifier.screen(&input);
+
+    assert_eq!(output.len(), 2);
+    assert!(!output[0].is_flagged());
+    assert_eq!(output[0].text.as_deref(), Some("Have a great day!"));
+    assert!(output[1].is_flagged());
+    assert_eq!(output[1].text.as_deref(), Some("[REDACTED]"));
+    assert_eq!(output[1].action, Some(SafetyAction::Redact));
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use rust_bert::bigbird::{
+    BigBirdConfig, BigBirdConfigResources, BigBirdForQuestionAnswering,
+    BigBirdForSequenceClassification, BigBirdModelResources, BigBirdSelfAttention,
+    BigBirdVocabResources,
+};
+use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::question_answering::{
+    QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
+};
+use rust_bert::resources::RemoteResource;
+use std::collections::HashMap;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+/// Dense attention over the key blocks of the block-sparse pattern. At inference, the random block of the middle
+/// query blocks points to the first block, which is attended to twice.
+fn reference_attention(
+    vs: &nn::VarStore,
+    hidden_states: &Tensor,
+    config: &BigBirdConfig,
+) -> Tensor {
+    let variables = vs.variables();
+    let (num_heads, head_dim) = (
+        config.num_attention_heads,
+        config.hidden_size / config.num_attention_heads,
+    );
+    let project = |name: &str| {
+        hidden_states
+            .linear(
+                &variables[&format!("{name}.weight")],
+                Some(&variables[&format!("{name}.bias")]),
+            )
+            .view([1, -1, num_heads, head_dim])
+            .transpose(1, 2)
+    };
+    let (query, key, value) = (project("query"), project("key"), project("value"));
+
+    let sequence_length = hidden_states.size()[1];
+    let num_blocks = sequence_length / config.block_size;
+    let mut bias = vec![-10000f32; (sequence_length * sequence_length) as usize];
+    for query_position in 0..sequence_length {
+        let query_block = query_position / config.block_size;
+        for key_position in 0..sequence_length {
+            let key_block = key_position / config.block_size;
+            let value = if query_block == 0 || query_block == num_blocks - 1 {
+                Some(0.0)
+            } else if key_block == 0 {
+                Some(((1 + config.num_random_blocks) as f32).ln())
+            } else if key_block == num_blocks - 1 || (key_block - query_block).abs() <= 1 {
+                Some(0.0)
+            } else {
+                None
+            };
+            if let Some(value) = value {
+                bias[(query_position * sequence_length + key_position) as usize] = value;
+            }
+        }
+    }
+    let bias = Tensor::from_slice(&bias).view([sequence_length, sequence_length]);
+
+    let scores = query.matmul(&key.transpose(-1, -2)) / (head_dim as f64).sqrt() + bias;
+    scores
+        .softmax(-1, Kind::Float)
+        .matmul(&value)
+        .transpose(1, 2)
+        .contiguous()
+        .view([1, sequence_length, -1])
+}
+
+#[test]
+fn bigbird_block_sparse_attention_pattern() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = common::tiny_bigbird_config();
+    let attention = BigBirdSelfAttention::new(vs.root(), &config);
+
+    let hidden_states = Tensor::randn([1, 40, 16], (Kind::Float, device));
+    let mask = Tensor::ones([1, 40], (Kind::Int64, device));
+    let (context, attention_weights) =
+        no_grad(|| attention.forward_t(&hidden_states, &mask, true, false));
+    let expected_context = no_grad(|| reference_attention(&vs, &hidden_states, &config));
+
+    assert_eq!(context.size(), vec![1, 40, 16]);
+    let max_difference = (context - expected_context).abs().max().double_value(&[]);
+    assert!(max_difference < 1e-5);
+
+    // Second query block: first, sliding window (of which the first block) and last blocks
+    let attention_weights = attention_weights.unwrap();
+    assert_eq!(attention_weights.size(), vec![1, 2, 40, 40]);
+    assert!(attention_weights.double_value(&[0, 0, 5, 10]) > 0.0);
+    assert!(attention_weights.double_value(&[0, 0, 5, 38]) > 0.0);
+    assert_eq!(attention_weights.double_value(&[0, 0, 5, 14]), 0.0);
+
+    Ok(())
+}
+
+#[test]
+fn bigbird_random_attention_blocks() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = common::tiny_bigbird_config();
+    let attention = BigBirdSelfAttention::new(vs.root(), &config);
+
+    let hidden_states = Tensor::randn([1, 40, 16], (Kind::Float, device));
+    let mask = Tensor::ones([1, 40], (Kind::Int64, device));
+    let (_, attention_weights) = no_grad(|| attention.forward_t(&hidden_states, &mask, true, true));
+    let attention_weights = attention_weights.unwrap();
+
+    let row_sums = attention_weights.sum_dim_intlist([-1].as_slice(), false, Kind::Float);
+    assert!((row_sums - 1.0).abs().max().double_value(&[]) < 1e-5);
+
+    // Number of key blocks attended by each query block
+    let attended_blocks = attention_weights
+        .view([2, 10, 4, 10, 4])
+        .select(2, 0)
+        .sum_dim_intlist([-1].as_slice(), false, Kind::Float)
+        .gt(0.0)
+        .sum_dim_intlist([-1].as_slice(), false, Kind::Int64);
+    for head in 0..2 {
+        let counts = (0..10)
+            .map(|block| attended_blocks.int64_value(&[head, block]))
+            .collect::<Vec<i64>>();
+        assert_eq!(counts, vec![10, 5, 6, 6, 6, 6, 6, 6, 5, 10]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bigbird_sequence_classification_forward() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = BigBirdConfig {
+        id2label: Some(HashMap::from([
+            (0, "negative".to_string()),
+            (1, "neutral".to_string()),
+            (2, "positive".to_string()),
+        ])),
+        ..common::tiny_bigbird_config()
+    };
+    let model = BigBirdForSequenceClassification::new(vs.root(), &config)?;
+
+    let named_variables = vs.variables();
+    assert!(named_variables.contains_key("bert.encoder.layer.1.attention.self.query.weight"));
+    assert!(named_variables.contains_key("bert.pooler.dense.weight"));
+    assert_eq!(
+        named_variables["classifier.out_proj.weight"].size(),
+        vec![3, 16]
+    );
+
+    // Long input: block-sparse attention, padded to a multiple of the block size
+    let input_ids = Tensor::randint(100, [2, 42], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(output.logits.size(), vec![2, 3]);
+    let hidden_states = output.all_hidden_states.unwrap();
+    assert_eq!(hidden_states.len(), 3);
+    assert_eq!(hidden_states[2].size(), vec![2, 42, 16]);
+    let attentions = output.all_attentions.unwrap();
+    assert_eq!(attentions[0].size(), vec![2, 2, 42, 42]);
+    assert_eq!(attentions[0].double_value(&[0, 0, 20, 4]), 0.0);
+
+    // Short input: full attention
+    let input_ids = Tensor::randint(100, [2, 12], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(output.logits.size(), vec![2, 3]);
+    let attentions = output.all_attentions.unwrap();
+    assert_eq!(attentions[0].size(), vec![2, 2, 12, 12]);
+    assert!(attentions[0].double_value(&[0, 0, 6, 0]) > 0.0);
+    assert!(attentions[0].double_value(&[0, 0, 6, 11]) > 0.0);
+
+    Ok(())
+}
+
+#[test]
+fn bigbird_question_answering_masks_question() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = BigBirdConfig {
+        output_attentions: None,
+        output_hidden_states: None,
+        ..common::tiny_bigbird_config()
+    };
+    let model = BigBirdForQuestionAnswering::new(vs.root(), &config);
+
+    let named_variables = vs.variables();
+    assert!(!named_variables.contains_key("bert.pooler.dense.weight"));
+    assert_eq!(
+        named_variables["qa_classifier.qa_outputs.weight"].size(),
+        vec![2, 16]
+    );
+
+    // Question of 6 tokens (up to the separator token), followed by the context
+    let input_ids = Tensor::randint_low(4, 100, [1, 50], (Kind::Int64, device));
+    let _ = input_ids.narrow(1, 5, 1).fill_(3);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    assert_eq!(output.start_logits.size(), vec![1, 50]);
+    assert_eq!(output.end_logits.size(), vec![1, 50]);
+    assert!(output.start_logits.double_value(&[0, 0]) > -1e3);
+    for position in 1..6 {
+        assert!(output.start_logits.double_value(&[0, position]) < -1e5);
+        assert!(output.end_logits.double_value(&[0, position]) < -1e5);
+    }
+    assert!(output.start_logits.double_value(&[0, 6]) > -1e3);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bigbird_for_question_answering() -> anyhow::Result<()> {
+    //    Set-up Question Answering model
+    let config = QuestionAnsweringConfig::new(
+        ModelType::BigBird,
+        ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            BigBirdModelResources::BIGBIRD_BASE_TRIVIA_ITC,
+        ))),
+        RemoteResource::from_pretrained(BigBirdConfigResources::BIGBIRD_BASE_TRIVIA_ITC),
+        RemoteResource::from_pretrained(BigBirdVocabResources::BIGBIRD_BASE_TRIVIA_ITC),
+        None,
+        false,
+        None,
+        None,
+    );
+
+    let qa_model = QuestionAnsweringModel::new(config)?;
+
+    //    Define input
+    let question = String::from("Where does Amy live ?");
+    let context = String::from("Amy lives in Amsterdam");
+    let qa_input = QaInput { question, context };
+
+    //    Get answer
+    let answers = qa_model.predict(&[qa_input], 1, 32);
+
+    assert_eq!(answers.len(), 1usize);
+    assert_eq!(answers[0].len(), 1usize);
+    assert_eq!(answers[0][0].answer.trim(), "Amsterdam");
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+#[cfg(feature = "candle")]
+mod tests {
+    use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+    use rust_bert::pipelines::candle::{
+        CandleBertForSequenceClassification, CandleSequenceClassificationConfig,
+        CandleSequenceClassificationModel,
+    };
+    use rust_bert::resources::LocalResource;
+    use rust_bert::Config;
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use tch::{nn, no_grad, Device, Kind, Tensor};
+
+    const VOCAB: [&str; 12] = [
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "movie", "was", "great", "awful",
+        "not", ".",
+    ];
+
+    fn tiny_config() -> BertConfig {
+        BertConfig {
+            hidden_size: 16,
+            intermediate_size: 32,
+            num_attention_heads: 4,
+            num_hidden_layers: 2,
+            max_position_embeddings: 32,
+            vocab_size: VOCAB.len() as i64,
+            id2label: Some(HashMap::from([
+                (0, "NEGATIVE".to_string()),
+                (1, "POSITIVE".to_string()),
+            ])),
+            ..Default::default()
+        }
+    }
+
+    /// Saves a randomly initialized libtorch model in the safetensors format, with its configuration and vocabulary
+    fn write_model(
+        dir: &tempfile::TempDir,
+    ) -> anyhow::Result<(
+        BertForSequenceClassification,
+        CandleSequenceClassificationConfig,
+    )> {
+        let config = tiny_config();
+        let vs = nn::VarStore::new(Device::Cpu);
+        let model = BertForSequenceClassification::new(vs.root(), &config)?;
+        let tensors = vs
+            .variables()
+            .into_iter()
+            .collect::<Vec<(String, Tensor)>>();
+        Tensor::write_safetensors(&tensors, dir.path().join("model.safetensors"))?;
+        std::fs::write(
+            dir.path().join("config.json"),
+            serde_json::to_string(&config)?,
+        )?;
+        std::fs::write(dir.path().join("vocab.txt"), VOCAB.join("\n"))?;
+        let candle_config = CandleSequenceClassificationConfig::new(
+            LocalResource::from(dir.path().join("model.safetensors")),
+            LocalResource::from(dir.path().join("config.json")),
+            LocalResource::from(dir.path().join("vocab.txt")),
+            true,
+        );
+        Ok((model, candle_config))
+    }
+
+    #[test]
+    fn candle_bert_matches_libtorch() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (model, _) = write_model(&dir)?;
+        let candle_model = CandleBertForSequenceClassification::new(
+            &dir.path().join("model.safetensors"),
+            &BertConfig::from_file(dir.path().join("config.json")),
+            &candle_core::Device::Cpu,
+        )?;
+
+        let input_ids: [[i64; 6]; 2] = [[2, 5, 6, 7, 8, 3], [2, 6, 7, 9, 3, 0]];
+        let mask: [[i64; 6]; 2] = [[1, 1, 1, 1, 1, 1], [1, 1, 1, 1, 1, 0]];
+        let expected_logits = no_grad(|| {
+            model
+                .forward_t(
+                    Some(&Tensor::from_slice2(&input_ids)),
+                    Some(&Tensor::from_slice2(&mask)),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .logits
+        });
+        let expected_logits = Vec::<Vec<f32>>::try_from(expected_logits.to_kind(Kind::Float))?;
+
+        let as_candle = |values: [[i64; 6]; 2]| {
+            let values = values
+                .iter()
+                .flatten()
+                .map(|value| *value as u32)
+                .collect::<Vec<u32>>();
+            candle_core::Tensor::from_vec(values, (2, 6), &candle_core::Device::Cpu)
+        };
+        let logits = candle_model
+            .forward(
+                &as_candle(input_ids)?,
+                &as_candle([[0; 6]; 2])?,
+                &as_candle(mask)?,
+            )?
+            .to_vec2::<f32>()?;
+
+        for (row, expected_row) in logits.iter().zip(expected_logits.iter()) {
+            for (value, expected_value) in row.iter().zip(expected_row.iter()) {
+                assert!((value - expected_value).abs() < 1e-4);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn candle_sequence_classification() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (_, config) = write_model(&dir)?;
+        let model = CandleSequenceClassificationModel::new(config)?;
+
+        let output = model.predict(&["The movie was great.", "The movie was not awful"])?;
+        assert_eq!(output.len(), 2);
+        for (sentence_idx, label) in output.iter().enumerate() {
+            assert_eq!(label.sentence, sentence_idx);
+            assert!(["NEGATIVE", "POSITIVE"].contains(&label.text.as_str()));
+            assert!((0.5..=1.0).contains(&label.score));
+        }
+        assert!(model.predict::<&str>(&[])?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use rust_bert::resources::{inspect_checkpoint, CheckpointFormat, ShapeMismatch};
+use tch::nn::{Init, VarStore};
+use tch::{Device, Kind, Tensor};
+
+fn var_store() -> VarStore {
+    let vs = VarStore::new(Device::Cpu);
+    let root = vs.root();
+    let _ = (&root / "layer").var("weight", &[4, 3], Init::Const(0.));
+    let _ = (&root / "layer").var("bias", &[4], Init::Const(0.));
+    let _ = (&root / "layer_norm").var("weight", &[4], Init::Const(1.));
+    vs
+}
+
+/// Checkpoint missing `layer.bias`, with a mis-shaped `layer.weight` and an additional `pooler.weight`
+fn checkpoint_tensors() -> Vec<(String, Tensor)> {
+    vec![
+        (
+            "layer.weight".to_string(),
+            Tensor::zeros([3, 4], (Kind::Float, Device::Cpu)),
+        ),
+        (
+            "layer_norm.weight".to_string(),
+            Tensor::ones([4], (Kind::Half, Device::Cpu)),
+        ),
+        (
+            "pooler.weight".to_string(),
+            Tensor::zeros([2, 2], (Kind::Float, Device::Cpu)),
+        ),
+    ]
+}
+
+#[test]
+fn checkpoint_inspection() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let safetensors_path = dir.path().join("model.safetensors");
+    Tensor::write_safetensors(&checkpoint_tensors(), &safetensors_path)?;
+    let torch_path = dir.path().join("model.ot");
+    Tensor::save_multi(&checkpoint_tensors(), &torch_path)?;
+
+    for (path, format) in [
+        (&safetensors_path, CheckpointFormat::Safetensors),
+        (&torch_path, CheckpointFormat::Torch),
+    ] {
+        let checkpoint = inspect_checkpoint(path)?;
+        assert_eq!(checkpoint.format, format);
+        assert_eq!(
+            checkpoint
+                .tensors
+                .iter()
+                .map(|tensor| tensor.name.as_str())
+                .collect::<Vec<&str>>(),
+            ["layer.weight", "layer_norm.weight", "pooler.weight"]
+        );
+        let layer_norm = checkpoint.tensor("layer_norm.weight").unwrap();
+        assert_eq!(layer_norm.shape, [4]);
+        assert_eq!(layer_norm.dtype, "F16");
+        assert_eq!(checkpoint.num_parameters(), 20);
+
+        let report = checkpoint.compatibility_report(&var_store());
+        assert!(!report.is_compatible());
+        assert_eq!(report.missing, ["layer.bias"]);
+        assert_eq!(report.unexpected, ["pooler.weight"]);
+        assert_eq!(
+            report.mismatched,
+            [ShapeMismatch {
+                name: "layer.weight".to_string(),
+                expected_shape: vec![4, 3],
+                checkpoint_shape: vec![3, 4],
+            }]
+        );
+        assert!(report.to_string().contains("layer.bias"));
+    }
+    Ok(())
+}
+
+#[test]
+fn compatible_checkpoint() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.safetensors");
+    let vs = var_store();
+    let mut tensors = vs
+        .variables()
+        .into_iter()
+        .collect::<Vec<(String, Tensor)>>();
+    // Checkpoints saved with a model head are matched with the base model
+    for (name, _) in tensors.iter_mut() {
+        *name = format!("model.{name}");
+    }
+    Tensor::write_safetensors(&tensors, &path)?;
+
+    let report = inspect_checkpoint(&path)?.compatibility_report(&vs);
+    assert!(report.is_compatible());
+    assert!(report.unexpected.is_empty());
+    assert!(inspect_checkpoint(dir.path().join("missing.safetensors")).is_err());
+    Ok(())
+}
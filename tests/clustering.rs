@@ -0,0 +1,75 @@
+use rust_bert::pipelines::clustering::{
+    community_detection, kmeans, ClusteringAlgorithm, TopicModel,
+};
+
+fn embeddings() -> Vec<Vec<f32>> {
+    vec![
+        vec![1.0, 0.1, 0.0],
+        vec![0.0, 1.0, 0.1],
+        vec![0.9, 0.0, 0.1],
+        vec![0.1, 0.9, 0.0],
+        vec![0.0, 0.1, 1.0],
+        vec![1.0, 0.0, 0.0],
+    ]
+}
+
+#[test]
+fn kmeans_clustering() -> anyhow::Result<()> {
+    let clusters = kmeans(&embeddings(), 3, 100)?;
+
+    assert_eq!(clusters.len(), 3);
+    assert_eq!(clusters[0].members, [0, 2, 5]);
+    assert_eq!(clusters[1].members, [1, 3]);
+    assert_eq!(clusters[2].members, [4]);
+    let norm = clusters[0]
+        .centroid
+        .iter()
+        .map(|value| value * value)
+        .sum::<f32>();
+    assert!((norm - 1.0).abs() < 1e-5);
+
+    assert!(kmeans(&embeddings(), 0, 100).is_err());
+    assert!(kmeans(&embeddings(), 7, 100).is_err());
+    assert!(kmeans(&[vec![1.0, 0.0], vec![1.0]], 1, 100).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn community_detection_clustering() -> anyhow::Result<()> {
+    let clusters = community_detection(&embeddings(), 0.9, 2)?;
+
+    assert_eq!(clusters.len(), 2);
+    assert_eq!(clusters[0].members, [0, 5, 2]);
+    assert_eq!(clusters[1].members, [1, 3]);
+
+    let algorithm = ClusteringAlgorithm::CommunityDetection {
+        threshold: 0.9,
+        min_community_size: 3,
+    };
+    let clusters = algorithm.cluster(&embeddings())?;
+    assert_eq!(clusters.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn topic_discovery() -> anyhow::Result<()> {
+    let topic_model = TopicModel::new(Default::default())?;
+
+    let input = [
+        "The striker scored twice in the second half of the football match.",
+        "The goalkeeper saved a penalty in the final minutes of the football match.",
+        "The central bank raised interest rates to fight inflation.",
+        "Inflation slowed after the central bank decision on interest rates.",
+    ];
+    let topics = topic_model.discover_topics(&input)?;
+
+    assert_eq!(topics.len(), 2);
+    assert_eq!(topics[0].members.len(), 2);
+    assert_eq!(topics[1].members.len(), 2);
+    assert_eq!(topics[0].keywords.len(), 5);
+
+    Ok(())
+}
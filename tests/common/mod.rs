@@ -0,0 +1,154 @@
+//! Randomly initialized configurations shared by the integration tests checking model behaviour
+//! that does not depend on pretrained weights (attention masks, padding).
+#![allow(dead_code)]
+
+use rust_bert::bigbird::BigBirdConfig;
+use rust_bert::bloom::BloomConfig;
+use rust_bert::donut::{DonutConfig, DonutSwinConfig};
+use rust_bert::jina_bert::JinaBertConfig;
+use rust_bert::llama::LlamaConfig;
+use rust_bert::mbart::MBartConfig;
+use rust_bert::modernbert::ModernBertConfig;
+use rust_bert::opt::OptConfig;
+use rust_bert::starcoder2::StarCoder2Config;
+use rust_bert::tapas::TapasConfig;
+
+pub fn tiny_bigbird_config() -> BigBirdConfig {
+    BigBirdConfig {
+        vocab_size: 100,
+        hidden_size: 16,
+        num_hidden_layers: 2,
+        num_attention_heads: 2,
+        intermediate_size: 32,
+        max_position_embeddings: 128,
+        block_size: 4,
+        num_random_blocks: 1,
+        sep_token_id: Some(3),
+        hidden_dropout_prob: 0.0,
+        attention_probs_dropout_prob: 0.0,
+        output_attentions: Some(true),
+        output_hidden_states: Some(true),
+        ..Default::default()
+    }
+}
+
+pub fn tiny_donut_config() -> DonutConfig {
+    DonutConfig {
+        encoder: DonutSwinConfig {
+            image_size: vec![32, 32],
+            embed_dim: 8,
+            depths: vec![2, 2],
+            num_heads: vec![2, 4],
+            window_size: 4,
+            drop_path_rate: 0.0,
+            ..Default::default()
+        },
+        decoder: MBartConfig {
+            vocab_size: 50,
+            max_position_embeddings: 32,
+            d_model: 16,
+            decoder_layers: 2,
+            decoder_attention_heads: 2,
+            decoder_ffn_dim: 32,
+            dropout: 0.0,
+            scale_embedding: Some(true),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+pub fn tiny_jina_bert_config() -> JinaBertConfig {
+    JinaBertConfig {
+        hidden_size: 32,
+        intermediate_size: 64,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        vocab_size: 100,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_llama_config() -> LlamaConfig {
+    LlamaConfig {
+        vocab_size: 100,
+        hidden_size: 32,
+        intermediate_size: 64,
+        num_hidden_layers: 2,
+        num_attention_heads: 4,
+        num_key_value_heads: Some(2),
+        max_position_embeddings: 64,
+        output_attentions: Some(true),
+        output_hidden_states: Some(true),
+        ..Default::default()
+    }
+}
+
+pub fn tiny_bloom_config() -> BloomConfig {
+    BloomConfig {
+        vocab_size: 100,
+        hidden_size: 32,
+        n_layer: 2,
+        n_head: 4,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_modernbert_config() -> ModernBertConfig {
+    ModernBertConfig {
+        hidden_size: 32,
+        intermediate_size: 48,
+        num_attention_heads: 4,
+        num_hidden_layers: 4,
+        vocab_size: 100,
+        pad_token_id: Some(0),
+        local_attention: 4,
+        output_attentions: Some(true),
+        ..Default::default()
+    }
+}
+
+pub fn tiny_opt_config() -> OptConfig {
+    OptConfig {
+        vocab_size: 100,
+        hidden_size: 32,
+        num_hidden_layers: 2,
+        num_attention_heads: 4,
+        ffn_dim: 64,
+        max_position_embeddings: 64,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_starcoder2_config() -> StarCoder2Config {
+    StarCoder2Config {
+        vocab_size: 100,
+        hidden_size: 32,
+        intermediate_size: 64,
+        num_hidden_layers: 2,
+        num_attention_heads: 4,
+        num_key_value_heads: Some(2),
+        max_position_embeddings: 64,
+        rope_theta: Some(10000.0),
+        attention_dropout: Some(0.0),
+        residual_dropout: Some(0.0),
+        embedding_dropout: Some(0.0),
+        output_attentions: Some(true),
+        ..Default::default()
+    }
+}
+
+pub fn tiny_tapas_config() -> TapasConfig {
+    TapasConfig {
+        vocab_size: 100,
+        hidden_size: 16,
+        num_hidden_layers: 2,
+        num_attention_heads: 2,
+        intermediate_size: 32,
+        max_position_embeddings: 64,
+        type_vocab_sizes: vec![3, 16, 16, 2, 16, 16, 10],
+        hidden_dropout_prob: 0.0,
+        attention_probs_dropout_prob: 0.0,
+        ..Default::default()
+    }
+}
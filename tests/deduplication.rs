@@ -0,0 +1,74 @@
+use rust_bert::pipelines::deduplication::{
+    estimate_jaccard, DeduplicationConfig, Deduplicator, MinHasher,
+};
+
+#[test]
+fn minhash_signatures() {
+    let min_hasher = MinHasher::new(128, 3);
+
+    let signature = min_hasher.signature("The quick brown fox jumps over the lazy dog.");
+    assert_eq!(signature.len(), 128);
+    assert_eq!(
+        signature,
+        min_hasher.signature("the QUICK brown fox, jumps over the lazy dog!")
+    );
+
+    let other_signature =
+        min_hasher.signature("Central banks raised interest rates again this quarter.");
+    assert_eq!(estimate_jaccard(&signature, &signature), 1.0);
+    assert!(estimate_jaccard(&signature, &other_signature) < 0.2);
+}
+
+#[test]
+fn streaming_deduplication() -> anyhow::Result<()> {
+    let mut deduplicator = Deduplicator::new(Default::default())?;
+
+    let input = [
+        "The quick brown fox jumps over the lazy dog near the river bank.",
+        "The quick brown fox jumps over the lazy dog near the river bank!",
+        "A completely different sentence about the stock market.",
+    ];
+    let output = deduplicator.deduplicate(&input)?;
+
+    assert_eq!(output.len(), 3);
+    assert!(!output[0].is_duplicate());
+    assert_eq!(output[1].duplicate_of.as_ref().unwrap().id, 0);
+    assert_eq!(output[1].duplicate_of.as_ref().unwrap().jaccard, 1.0);
+    assert!(!output[2].is_duplicate());
+    assert_eq!(deduplicator.num_indexed_documents(), 2);
+
+    let output =
+        deduplicator.deduplicate(&["a completely different sentence about the STOCK market"])?;
+    assert_eq!(output[0].id, 3);
+    assert_eq!(output[0].duplicate_of.as_ref().unwrap().id, 2);
+
+    Ok(())
+}
+
+#[test]
+fn bounded_deduplication() -> anyhow::Result<()> {
+    let mut deduplicator = Deduplicator::new(DeduplicationConfig {
+        max_documents: Some(1),
+        ..Default::default()
+    })?;
+
+    let input = [
+        "The quick brown fox jumps over the lazy dog near the river bank.",
+        "A completely different sentence about the stock market.",
+        "The quick brown fox jumps over the lazy dog near the river bank.",
+    ];
+    let output = deduplicator.deduplicate(&input)?;
+
+    // The first document was evicted from the index when the second one was added
+    assert!(output.iter().all(|result| !result.is_duplicate()));
+    assert_eq!(deduplicator.num_indexed_documents(), 1);
+
+    assert!(Deduplicator::new(DeduplicationConfig {
+        num_permutations: 100,
+        num_bands: 32,
+        ..Default::default()
+    })
+    .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use rust_bert::device_map::{DeviceMap, LayerPlacement};
+use rust_bert::gpt_neo::{GptNeoConfig, GptNeoForCausalLM};
+use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+use rust_bert::resources::LocalResource;
+use tch::{nn, Device, Kind};
+
+const VOCAB: [&str; 9] = ["<|endoftext|>", "h", "e", "l", "o", "Ġ", "w", "r", "d"];
+
+/// Saves a randomly initialized GPT-Neo model with its configuration and vocabulary, and returns a pipeline
+/// configuration loading it with the provided device map
+fn generation_config(
+    dir: &tempfile::TempDir,
+    device_map: Option<DeviceMap>,
+) -> anyhow::Result<TextGenerationConfig> {
+    let config = GptNeoConfig {
+        bos_token_id: 0,
+        eos_token_id: 0,
+        vocab_size: VOCAB.len() as i64,
+        num_layers: 3,
+        num_heads: 4,
+        hidden_size: 16,
+        window_size: 4,
+        max_position_embeddings: 32,
+        ..Default::default()
+    };
+    let weights_path = dir.path().join("model.ot");
+    if !weights_path.exists() {
+        let vs = nn::VarStore::new(Device::Cpu);
+        let _ = GptNeoForCausalLM::new(vs.root(), &config)?;
+        vs.save(&weights_path)?;
+        std::fs::write(
+            dir.path().join("config.json"),
+            serde_json::to_string(&config)?,
+        )?;
+        let vocab = VOCAB
+            .iter()
+            .enumerate()
+            .map(|(id, token)| (token.to_string(), id))
+            .collect::<std::collections::HashMap<String, usize>>();
+        std::fs::write(
+            dir.path().join("vocab.json"),
+            serde_json::to_string(&vocab)?,
+        )?;
+        std::fs::write(dir.path().join("merges.txt"), "#version: 0.2\n")?;
+    }
+    Ok(TextGenerationConfig {
+        device: Device::Cpu,
+        device_map,
+        do_sample: false,
+        num_beams: 2,
+        max_length: Some(12),
+        ..TextGenerationConfig::new(
+            ModelType::GPTNeo,
+            ModelResource::Torch(Box::new(LocalResource::from(weights_path))),
+            LocalResource::from(dir.path().join("config.json")),
+            LocalResource::from(dir.path().join("vocab.json")),
+            Some(LocalResource::from(dir.path().join("merges.txt"))),
+        )
+    })
+}
+
+/// Devices of the transformer blocks placed by a device map
+fn layer_devices(device_map: &DeviceMap, num_layers: usize) -> anyhow::Result<Vec<Device>> {
+    Ok(device_map
+        .layer_placements(num_layers)?
+        .iter()
+        .map(|placement| placement.device)
+        .collect())
+}
+
+#[test]
+fn device_map_layer_placements() -> anyhow::Result<()> {
+    let device_map = DeviceMap::new(vec![Device::Cpu, Device::Cuda(0), Device::Cuda(1)]);
+    assert_eq!(device_map.main_device(), Device::Cpu);
+    assert_eq!(
+        layer_devices(&device_map, 5)?,
+        [
+            Device::Cpu,
+            Device::Cpu,
+            Device::Cuda(0),
+            Device::Cuda(0),
+            Device::Cuda(1)
+        ]
+    );
+    assert!(device_map.layer_placements(2).is_err());
+
+    let device_map = DeviceMap::from_layer_counts(vec![(Device::Cuda(0), 1), (Device::Cuda(1), 3)]);
+    assert_eq!(
+        layer_devices(&device_map, 4)?,
+        [
+            Device::Cuda(0),
+            Device::Cuda(1),
+            Device::Cuda(1),
+            Device::Cuda(1)
+        ]
+    );
+    assert!(device_map.layer_placements(5).is_err());
+    assert!(DeviceMap::new(vec![]).layer_placements(4).is_err());
+
+    let device_map = DeviceMap::from_layer_ranges(
+        LayerPlacement::with_kind(Device::Cpu, Kind::Float),
+        vec![
+            (0..1, LayerPlacement::new(Device::Cpu)),
+            (1..3, LayerPlacement::with_kind(Device::Cuda(0), Kind::Half)),
+        ],
+    )?;
+    assert_eq!(device_map.main_device(), Device::Cpu);
+    assert_eq!(
+        device_map.layer_placements(3)?[1..],
+        [LayerPlacement::with_kind(Device::Cuda(0), Kind::Half); 2]
+    );
+    assert!(DeviceMap::from_layer_ranges(
+        LayerPlacement::new(Device::Cpu),
+        vec![(0..2, Device::Cpu.into()), (3..4, Device::Cpu.into())],
+    )
+    .is_err());
+    let int_map = DeviceMap::new(vec![Device::Cpu])
+        .with_embeddings(LayerPlacement::with_kind(Device::Cpu, Kind::Int8));
+    assert!(int_map.layer_placements(3).is_err());
+    Ok(())
+}
+
+#[test]
+fn gpt_neo_device_map() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = ["hello world"];
+
+    let model = TextGenerationModel::new(generation_config(&dir, None)?)?;
+    let mapped_model = TextGenerationModel::new(generation_config(
+        &dir,
+        Some(DeviceMap::from_layer_counts(vec![
+            (Device::Cpu, 1),
+            (Device::Cpu, 2),
+        ])),
+    )?)?;
+    assert_eq!(
+        mapped_model.generate(&input, None),
+        model.generate(&input, None)
+    );
+
+    let invalid_map = DeviceMap::from_layer_counts(vec![(Device::Cpu, 2)]);
+    assert!(TextGenerationModel::new(generation_config(&dir, Some(invalid_map))?).is_err());
+
+    let mixed_precision_map = DeviceMap::from_layer_ranges(
+        LayerPlacement::new(Device::Cpu),
+        vec![
+            (0..2, LayerPlacement::new(Device::Cpu)),
+            (2..3, LayerPlacement::with_kind(Device::Cpu, Kind::BFloat16)),
+        ],
+    )?;
+    let mixed_precision_model =
+        TextGenerationModel::new(generation_config(&dir, Some(mixed_precision_map))?)?;
+    assert_eq!(mixed_precision_model.generate(&input, None).len(), 1);
+    Ok(())
+}
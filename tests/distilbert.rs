@@ -3,8 +3,14 @@ use rust_bert::distilbert::{
     DistilBertForTokenClassification, DistilBertModelMaskedLM, DistilBertModelResources,
     DistilBertVocabResources,
 };
+use rust_bert::pipelines::ensemble::{ClassificationEnsemble, EnsembleFitConfig, EnsembleSpace};
+use rust_bert::pipelines::interpret::{
+    AttributionMethod, OcclusionConfig, OcclusionExplainer, OcclusionStrategy,
+    SequenceClassificationExplainer,
+};
 use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
 use rust_bert::pipelines::sentiment::{SentimentModel, SentimentPolarity};
+use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
 use rust_bert::resources::{RemoteResource, ResourceProvider};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
@@ -39,6 +45,154 @@ fn distilbert_sentiment_classifier() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn distilbert_sentiment_explanations() -> anyhow::Result<()> {
+    //    Set-up explainer
+    let explainer = SequenceClassificationExplainer::new(Default::default())?;
+
+    let input = ["This movie was wonderful and the acting was great."];
+    let methods = [
+        AttributionMethod::AttentionRollout,
+        AttributionMethod::GradientXInput,
+        AttributionMethod::IntegratedGradients { steps: 16 },
+    ];
+    for method in methods.iter() {
+        let output = explainer.explain(&input, *method)?;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].label.text, "POSITIVE");
+        let attributions = &output[0].attributions;
+        // Special tokens are omitted and the tokens are aligned to the input characters
+        assert_eq!(attributions.len(), 10);
+        assert_eq!(attributions[3].text, "wonderful");
+        assert_eq!(attributions[3].offset.begin, 15);
+        assert_eq!(attributions[3].offset.end, 24);
+        assert_eq!(attributions[9].text, ".");
+    }
+
+    let rollout = explainer.explain(&input, AttributionMethod::AttentionRollout)?;
+    assert!(rollout[0]
+        .attributions
+        .iter()
+        .all(|attribution| attribution.score > 0.0));
+
+    let integrated_gradients =
+        explainer.explain(&input, AttributionMethod::IntegratedGradients { steps: 16 })?;
+    let most_important = integrated_gradients[0]
+        .attributions
+        .iter()
+        .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
+        .unwrap();
+    assert!(["wonderful", "great"].contains(&most_important.text.as_str()));
+
+    let negative = explainer.explain_label(input[0], 0, AttributionMethod::GradientXInput)?;
+    assert_eq!(negative.label.text, "NEGATIVE");
+    assert!(negative.label.score < 0.5);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sentiment_occlusion_explanations() -> anyhow::Result<()> {
+    let input = ["This movie was wonderful and the acting was great."];
+    for strategy in [OcclusionStrategy::MaskToken, OcclusionStrategy::Remove] {
+        //    Set-up explainer
+        let model = SequenceClassificationModel::new(Default::default())?;
+        let explainer = OcclusionExplainer::new(
+            model,
+            OcclusionConfig {
+                strategy,
+                batch_size: 4,
+                ..Default::default()
+            },
+        )?;
+
+        let output = explainer.explain(&input)?;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].label.text, "POSITIVE");
+        let attributions = &output[0].attributions;
+        assert_eq!(attributions.len(), 10);
+        assert_eq!(attributions[3].text, "wonderful");
+        let most_important = attributions
+            .iter()
+            .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
+            .unwrap();
+        assert!(["wonderful", "great"].contains(&most_important.text.as_str()));
+    }
+
+    let model = SequenceClassificationModel::new(Default::default())?;
+    let explainer = OcclusionExplainer::new(
+        model,
+        OcclusionConfig {
+            span_length: 3,
+            ..Default::default()
+        },
+    )?;
+    let negative = explainer.explain_label(input[0], 0)?;
+    assert_eq!(negative.label.text, "NEGATIVE");
+    assert_eq!(negative.attributions.len(), 10);
+
+    let model = SequenceClassificationModel::new(Default::default())?;
+    assert!(OcclusionExplainer::new(
+        model,
+        OcclusionConfig {
+            span_length: 0,
+            ..Default::default()
+        },
+    )
+    .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sentiment_ensemble() -> anyhow::Result<()> {
+    let input = ["This movie was wonderful.", "A boring and pointless film."];
+    let single_model = SequenceClassificationModel::new(Default::default())?;
+    let expected = input
+        .iter()
+        .map(|sentence| single_model.predict([*sentence]).remove(0))
+        .collect::<Vec<_>>();
+
+    for space in [EnsembleSpace::Probability, EnsembleSpace::Logit] {
+        //    Set-up ensemble of identical models
+        let models = vec![
+            SequenceClassificationModel::new(Default::default())?,
+            SequenceClassificationModel::new(Default::default())?,
+        ];
+        let mut ensemble = ClassificationEnsemble::new(models, space)?;
+        assert!(ensemble.set_weights(&[1.0]).is_err());
+        ensemble.set_weights(&[3.0, 1.0])?;
+        assert_eq!(ensemble.get_weights(), &[0.75, 0.25]);
+
+        //    Identical models predict the same labels as a single model
+        let output = ensemble.predict(input);
+        assert_eq!(output.len(), 2);
+        for (label, expected_label) in output.iter().zip(expected.iter()) {
+            assert_eq!(label.text, expected_label.text);
+            assert!((label.score - expected_label.score).abs() < 1e-4);
+        }
+        let scores = ensemble.predict_scores(&input);
+        assert_eq!(scores[0].len(), 2);
+        assert!((scores[0][0].score + scores[0][1].score - 1.0).abs() < 1e-4);
+
+        //    Fit temperatures and weights
+        let loss = ensemble.fit(
+            &input,
+            &[1, 0],
+            EnsembleFitConfig {
+                iterations: 20,
+                ..Default::default()
+            },
+        )?;
+        assert!(loss.is_finite());
+        assert!((ensemble.get_weights().iter().sum::<f64>() - 1.0).abs() < 1e-4);
+        assert!(ensemble.get_temperatures().iter().all(|t| *t > 0.0));
+        assert!(ensemble.fit(&input, &[1, 2], Default::default()).is_err());
+    }
+
+    Ok(())
+}
+
 #[test]
 fn distilbert_masked_lm() -> anyhow::Result<()> {
     //    Resources paths
@@ -276,3 +430,58 @@ fn distilbert_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn distilbert_question_answering_evidence() -> anyhow::Result<()> {
+    //    Set-up question answering model
+    let qa_model = QuestionAnsweringModel::new(Default::default())?;
+
+    //    Define input
+    let question = String::from("Where does Amy live ?");
+    let context = String::from("Amy is a teacher. She lives in Amsterdam. She has two cats.");
+    let qa_input = QaInput { question, context };
+
+    let answers = qa_model.predict_with_evidence(&[qa_input], 1, 32, 0);
+
+    assert_eq!(answers.len(), 1usize);
+    assert_eq!(answers[0].len(), 1usize);
+    assert_eq!(answers[0][0].answer.answer, "Amsterdam");
+    assert_eq!(answers[0][0].evidence.text, "She lives in Amsterdam.");
+    assert_eq!(answers[0][0].evidence.start, 18);
+    assert_eq!(answers[0][0].evidence.end, 41);
+    assert_eq!(answers[0][0].surrounding_context, answers[0][0].evidence);
+    assert_eq!(
+        answers[0][0].highlight("<b>", "</b>"),
+        "She lives in <b>Amsterdam</b>."
+    );
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_question_answering_multi_context() -> anyhow::Result<()> {
+    //    Set-up question answering model
+    let qa_model = QuestionAnsweringModel::new(Default::default())?;
+
+    //    Define input
+    let contexts = [
+        "Eric is a software engineer working in The Hague.",
+        "Amy lives in Amsterdam",
+    ];
+
+    let answers = qa_model.predict_multi_context("Where does Amy live ?", &contexts, 3, 32);
+
+    assert_eq!(answers.len(), 3usize);
+    assert_eq!(answers[0].context_index, 1);
+    assert_eq!(answers[0].answer.answer, "Amsterdam");
+    assert!(answers[0].answer.score > answers[1].answer.score);
+    assert!(
+        answers
+            .iter()
+            .map(|answer| answer.answer.score)
+            .sum::<f64>()
+            <= 1.0 + 1e-6
+    );
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use rust_bert::donut::{prepare_image, DonutModel};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn donut_forward_and_generate() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = common::tiny_donut_config();
+    let model = DonutModel::new(vs.root(), &config);
+
+    //    Image dimensions that are not multiples of the patch and window sizes
+    let image = Tensor::randint(256, [3, 30, 34], (Kind::Uint8, device));
+    let pixel_values = prepare_image(&image, &config.encoder.image_size);
+    assert_eq!(pixel_values.size(), vec![3, 32, 32]);
+    let pixel_values = Tensor::stack(&[&pixel_values, &pixel_values], 0).narrow(3, 0, 30);
+
+    let decoder_input_ids = Tensor::from_slice(&[0i64, 5, 7]).unsqueeze(0);
+    let output = no_grad(|| {
+        model.forward_t(
+            Some(&pixel_values),
+            None,
+            &decoder_input_ids.expand([2, 3], false),
+            None,
+            false,
+        )
+    })?;
+
+    // 32x30 image: 8x8 patches (padded width), merged to 4x4 patches of dimension 16
+    assert_eq!(output.encoder_hidden_state.unwrap().size(), vec![2, 16, 16]);
+    assert_eq!(output.lm_logits.size(), vec![2, 3, 50]);
+    assert_eq!(output.cache.unwrap().len(), 2);
+
+    let generated = model.generate(&pixel_values, &decoder_input_ids, 6)?;
+    assert_eq!(generated.size()[0], 2);
+    assert!(generated.size()[1] <= 6);
+    assert_eq!(
+        generated.narrow(1, 0, 3),
+        decoder_input_ids.expand([2, 3], false)
+    );
+
+    Ok(())
+}
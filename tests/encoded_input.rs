@@ -0,0 +1,98 @@
+use rust_bert::bert::BertConfig;
+use rust_bert::distilbert::DistilBertConfig;
+use rust_bert::pipelines::common::{ConfigOption, EncodedInput, ModelType, TokenizerOption};
+use std::fs;
+use tch::{Device, Tensor};
+
+#[test]
+fn encoded_input_pad_batch() -> anyhow::Result<()> {
+    let inputs = [
+        EncodedInput::new(vec![101, 7592, 2088, 102]),
+        EncodedInput::new(vec![101, 7592, 102])
+            .with_attention_mask(vec![1, 1, 1])
+            .with_token_type_ids(vec![0, 1, 1]),
+    ];
+
+    let (input_ids, attention_mask, token_type_ids) =
+        EncodedInput::pad_batch(&inputs, 0, None, Device::Cpu)?;
+
+    assert_eq!(input_ids.size(), vec![2, 4]);
+    assert_eq!(
+        input_ids,
+        Tensor::from_slice(&[101i64, 7592, 2088, 102, 101, 7592, 102, 0]).view([2, 4])
+    );
+    assert_eq!(
+        attention_mask,
+        Tensor::from_slice(&[1i64, 1, 1, 1, 1, 1, 1, 0]).view([2, 4])
+    );
+    assert_eq!(
+        token_type_ids,
+        Tensor::from_slice(&[0i64, 0, 0, 0, 0, 1, 1, 1]).view([2, 4])
+    );
+
+    let (truncated_ids, _, _) = EncodedInput::pad_batch(&inputs, 0, Some(2), Device::Cpu)?;
+    assert_eq!(truncated_ids.size(), vec![2, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn encoded_input_pad_batch_invalid() {
+    assert!(EncodedInput::pad_batch(&[], 0, None, Device::Cpu).is_err());
+    assert!(EncodedInput::pad_batch(&[EncodedInput::new(vec![])], 0, None, Device::Cpu).is_err());
+
+    let mismatched_mask = EncodedInput::new(vec![101, 102]).with_attention_mask(vec![1]);
+    assert!(EncodedInput::pad_batch(&[mismatched_mask], 0, None, Device::Cpu).is_err());
+}
+
+#[test]
+fn encoded_input_multiple_segments() -> anyhow::Result<()> {
+    let model_dir = tempfile::tempdir()?;
+    let vocab_path = model_dir.path().join("vocab.txt");
+    fs::write(
+        &vocab_path,
+        "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\ntitle\nquestion\nanswer\nshort\n",
+    )?;
+    let tokenizer = TokenizerOption::from_file(
+        ModelType::Bert,
+        vocab_path.to_str().unwrap(),
+        None,
+        true,
+        None,
+        None,
+    )?;
+
+    let input = tokenizer.encode_segments(&["Title", "Question", "Short answer"])?;
+    assert_eq!(input.input_ids, vec![2, 5, 3, 6, 3, 8, 7, 3]);
+    assert_eq!(input.token_type_ids, Some(vec![0, 0, 0, 1, 1, 2, 2, 2]));
+
+    let input = tokenizer.encode_segments(&["Title", "Question"])?;
+    assert_eq!(input.token_type_ids, Some(vec![0, 0, 0, 1, 1]));
+    assert!(tokenizer.encode_segments(&[]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn config_type_vocab_size() -> anyhow::Result<()> {
+    let model_dir = tempfile::tempdir()?;
+    let config_path = model_dir.path().join("config.json");
+
+    // RoBERTa checkpoints are trained with a single token type
+    let config = BertConfig {
+        type_vocab_size: 1,
+        ..Default::default()
+    };
+    fs::write(&config_path, serde_json::to_string(&config)?)?;
+    let config = ConfigOption::from_file(ModelType::Roberta, &config_path);
+    assert_eq!(config.get_type_vocab_size(), Some(1));
+
+    fs::write(
+        &config_path,
+        serde_json::to_string(&DistilBertConfig::default())?,
+    )?;
+    let config = ConfigOption::from_file(ModelType::DistilBert, &config_path);
+    assert_eq!(config.get_type_vocab_size(), None);
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use rust_bert::gpt2::GPT2Generator;
+use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+use rust_bert::pipelines::generation_utils::{
+    GenerateConfig, GenerateOptions, GenerationConfigFile, GenerationPreset, GENERATION_CONFIG_FILE,
+};
+use rust_bert::resources::LocalResource;
+use std::fs;
+use std::str::FromStr;
+
+#[test]
+fn generation_presets() -> anyhow::Result<()> {
+    let deterministic = GenerateConfig::deterministic();
+    assert!(!deterministic.do_sample);
+    assert_eq!(deterministic.num_beams, 1);
+
+    let precise = GenerateConfig::precise();
+    assert!(!precise.do_sample);
+    assert!(precise.num_beams > 1);
+
+    let balanced = GenerateConfig::balanced();
+    let creative = GenerateConfig::creative();
+    assert!(balanced.do_sample && creative.do_sample);
+    assert!(creative.temperature > balanced.temperature);
+
+    // Presets keep the length limits of the configuration
+    let config = GenerateConfig {
+        max_length: Some(128),
+        ..Default::default()
+    }
+    .with_preset(GenerationPreset::Creative);
+    assert_eq!(config.max_length, Some(128));
+
+    assert_eq!(
+        GenerationPreset::from_str("Balanced")?,
+        GenerationPreset::Balanced
+    );
+    assert!(GenerationPreset::from_str("fast").is_err());
+    Ok(())
+}
+
+#[test]
+fn generation_config_file() -> anyhow::Result<()> {
+    let model_dir = tempfile::tempdir()?;
+    let config_path = model_dir.path().join("config.json");
+    fs::write(&config_path, "{}")?;
+
+    // Configuration unchanged without a generation configuration file
+    let config = GenerateConfig {
+        config_resource: Box::new(LocalResource::from(config_path.clone())),
+        ..Default::default()
+    }
+    .with_recommended_defaults()?;
+    assert_eq!(config.num_beams, GenerateConfig::default().num_beams);
+
+    fs::write(
+        model_dir.path().join(GENERATION_CONFIG_FILE),
+        r#"{"num_beams": 2, "max_length": 64, "early_stopping": "never", "bos_token_id": 0}"#,
+    )?;
+    let config = GenerateConfig {
+        config_resource: Box::new(LocalResource::from(config_path)),
+        top_p: 0.5,
+        ..Default::default()
+    }
+    .with_recommended_defaults()?;
+    assert_eq!(config.num_beams, 2);
+    assert_eq!(config.max_length, Some(64));
+    assert!(!config.early_stopping);
+    assert_eq!(config.top_p, 0.5);
+
+    fs::write(model_dir.path().join(GENERATION_CONFIG_FILE), "not json")?;
+    assert!(
+        GenerationConfigFile::from_file(model_dir.path().join(GENERATION_CONFIG_FILE)).is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn empty_sequence_bias() -> anyhow::Result<()> {
+    let model_dir = tempfile::tempdir()?;
+    let vocab_path = model_dir.path().join("vocab.json");
+    let merges_path = model_dir.path().join("merges.txt");
+    fs::write(&vocab_path, r#"{"<|endoftext|>": 0, "a": 1}"#)?;
+    fs::write(&merges_path, "#version: 0.2\n")?;
+    let tokenizer = TokenizerOption::from_file(
+        ModelType::GPT2,
+        vocab_path.to_str().unwrap(),
+        Some(merges_path.to_str().unwrap()),
+        false,
+        None,
+        None,
+    )?;
+
+    // The configuration is validated before the model is loaded
+    let sequence_bias = vec![(vec![1], -1.0), (vec![], 1.0)];
+    let generate_config = GenerateConfig {
+        config_resource: Box::new(LocalResource::from(model_dir.path().join("config.json"))),
+        sequence_bias: sequence_bias.clone(),
+        ..Default::default()
+    };
+    assert!(GPT2Generator::new_with_tokenizer(generate_config, tokenizer).is_err());
+
+    let generate_options = GenerateOptions {
+        sequence_bias: Some(&sequence_bias),
+        ..Default::default()
+    };
+    assert!(generate_options.validate().is_err());
+    let generate_options = GenerateOptions {
+        sequence_bias: Some(&sequence_bias[..1]),
+        ..Default::default()
+    };
+    assert!(generate_options.validate().is_ok());
+    Ok(())
+}
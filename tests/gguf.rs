@@ -0,0 +1,272 @@
+mod common;
+
+use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+use rust_bert::resources::{load_weights_from_file, GgmlType, GgufFile};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::path::Path;
+use tch::{nn, no_grad, Device, Tensor};
+
+const GGML_TYPE_F32: u32 = 0;
+const GGML_TYPE_Q4_0: u32 = 2;
+const GGML_TYPE_Q8_0: u32 = 8;
+
+enum Metadata {
+    U32(u32),
+    F32(f32),
+    String(&'static str),
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend((value.len() as u64).to_le_bytes());
+    buffer.extend(value.as_bytes());
+}
+
+fn pad(buffer: &mut Vec<u8>) {
+    while buffer.len() % 32 != 0 {
+        buffer.push(0);
+    }
+}
+
+/// Writes a GGUF (version 3) file with the given metadata and tensors (name, GGML type, Torch shape, data)
+fn write_gguf(
+    path: &Path,
+    metadata: &[(&str, Metadata)],
+    tensors: &[(String, u32, Vec<i64>, Vec<u8>)],
+) -> anyhow::Result<()> {
+    let mut buffer = b"GGUF".to_vec();
+    buffer.extend(3u32.to_le_bytes());
+    buffer.extend((tensors.len() as u64).to_le_bytes());
+    buffer.extend((metadata.len() as u64).to_le_bytes());
+    for (key, value) in metadata {
+        write_string(&mut buffer, key);
+        match value {
+            Metadata::U32(value) => {
+                buffer.extend(4u32.to_le_bytes());
+                buffer.extend(value.to_le_bytes());
+            }
+            Metadata::F32(value) => {
+                buffer.extend(6u32.to_le_bytes());
+                buffer.extend(value.to_le_bytes());
+            }
+            Metadata::String(value) => {
+                buffer.extend(8u32.to_le_bytes());
+                write_string(&mut buffer, value);
+            }
+        }
+    }
+    let mut data = Vec::new();
+    for (name, ggml_type, shape, tensor_data) in tensors {
+        write_string(&mut buffer, name);
+        buffer.extend((shape.len() as u32).to_le_bytes());
+        for dimension in shape.iter().rev() {
+            buffer.extend((*dimension as u64).to_le_bytes());
+        }
+        buffer.extend(ggml_type.to_le_bytes());
+        buffer.extend((data.len() as u64).to_le_bytes());
+        data.extend(tensor_data);
+        pad(&mut data);
+    }
+    pad(&mut buffer);
+    buffer.extend(data);
+    std::fs::File::create(path)?.write_all(&buffer)?;
+    Ok(())
+}
+
+/// Encodes values in the Q8_0 format with a scale of 1/64 (half precision 0x2400)
+fn encode_q8_0(values: &[f32]) -> Vec<u8> {
+    values
+        .chunks(32)
+        .flat_map(|block| {
+            let mut bytes = 0x2400u16.to_le_bytes().to_vec();
+            bytes.extend(block.iter().map(|value| (value * 64.0).round() as i8 as u8));
+            bytes
+        })
+        .collect()
+}
+
+/// Encodes values in the Q4_0 format with a scale of 1/16 (half precision 0x2C00)
+fn encode_q4_0(values: &[f32]) -> Vec<u8> {
+    values
+        .chunks(32)
+        .flat_map(|block| {
+            let quants = block
+                .iter()
+                .map(|value| ((value * 16.0).round() + 8.0) as u8)
+                .collect::<Vec<u8>>();
+            let mut bytes = 0x2C00u16.to_le_bytes().to_vec();
+            bytes.extend((0..16).map(|index| quants[index] | (quants[index + 16] << 4)));
+            bytes
+        })
+        .collect()
+}
+
+fn gguf_tensor_name(variable_name: &str) -> String {
+    match variable_name {
+        "model.embed_tokens.weight" => "token_embd.weight".to_string(),
+        "model.norm.weight" => "output_norm.weight".to_string(),
+        "lm_head.weight" => "output.weight".to_string(),
+        _ => {
+            let name = variable_name.strip_prefix("model.layers.").unwrap();
+            let (layer, name) = name.split_once('.').unwrap();
+            let name = name
+                .replace("self_attn.q_proj", "attn_q")
+                .replace("self_attn.k_proj", "attn_k")
+                .replace("self_attn.v_proj", "attn_v")
+                .replace("self_attn.o_proj", "attn_output")
+                .replace("post_attention_layernorm", "ffn_norm")
+                .replace("input_layernorm", "attn_norm")
+                .replace("mlp.gate_proj", "ffn_gate")
+                .replace("mlp.up_proj", "ffn_up")
+                .replace("mlp.down_proj", "ffn_down");
+            format!("blk.{layer}.{name}")
+        }
+    }
+}
+
+/// Permutation of the rotary dimensions applied by the llama.cpp conversion of the query and key projections
+fn permute_rotary(tensor: &Tensor, num_heads: i64) -> Tensor {
+    let size = tensor.size();
+    tensor
+        .view([num_heads, 2, size[0] / num_heads / 2, size[1]])
+        .transpose(1, 2)
+        .reshape(&size[..])
+}
+
+fn single_layer_config() -> LlamaConfig {
+    LlamaConfig {
+        num_hidden_layers: 1,
+        ..common::tiny_llama_config()
+    }
+}
+
+fn tiny_metadata(architecture: &'static str) -> Vec<(&'static str, Metadata)> {
+    vec![
+        ("general.architecture", Metadata::String(architecture)),
+        ("general.alignment", Metadata::U32(32)),
+        ("llama.embedding_length", Metadata::U32(32)),
+        ("llama.feed_forward_length", Metadata::U32(64)),
+        ("llama.block_count", Metadata::U32(1)),
+        ("llama.attention.head_count", Metadata::U32(4)),
+        ("llama.attention.head_count_kv", Metadata::U32(2)),
+        ("llama.context_length", Metadata::U32(64)),
+        (
+            "llama.attention.layer_norm_rms_epsilon",
+            Metadata::F32(1e-5),
+        ),
+        ("llama.rope.freq_base", Metadata::F32(10000.0)),
+        ("tokenizer.ggml.bos_token_id", Metadata::U32(1)),
+        ("tokenizer.ggml.eos_token_id", Metadata::U32(2)),
+    ]
+}
+
+/// Rounds the weights of a model to values that can be represented exactly in the Q8_0 (linear layers and
+/// embeddings) and Q4_0 (language model head) formats, and returns the corresponding GGUF tensors
+fn quantized_tensors(vs: &nn::VarStore) -> anyhow::Result<Vec<(String, u32, Vec<i64>, Vec<u8>)>> {
+    let mut tensors = Vec::new();
+    for (name, mut variable) in vs.variables() {
+        let (ggml_type, scale, range) = match (name.as_str(), variable.dim()) {
+            ("lm_head.weight", _) => (GGML_TYPE_Q4_0, 16.0, (-8.0, 7.0)),
+            (_, 2) => (GGML_TYPE_Q8_0, 64.0, (-127.0, 127.0)),
+            _ => (GGML_TYPE_F32, 1.0, (0.0, 0.0)),
+        };
+        if ggml_type != GGML_TYPE_F32 {
+            no_grad(|| {
+                let rounded = (&variable * scale).round().clamp(range.0, range.1) / scale;
+                variable.copy_(&rounded);
+            });
+        }
+        let gguf_tensor = if name.contains("q_proj") {
+            permute_rotary(&variable, 4)
+        } else if name.contains("k_proj") {
+            permute_rotary(&variable, 2)
+        } else {
+            variable.shallow_clone()
+        };
+        let values = Vec::<f32>::try_from(gguf_tensor.contiguous().view([-1]))?;
+        let data = match ggml_type {
+            GGML_TYPE_Q4_0 => encode_q4_0(&values),
+            GGML_TYPE_Q8_0 => encode_q8_0(&values),
+            _ => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+        };
+        tensors.push((gguf_tensor_name(&name), ggml_type, variable.size(), data));
+    }
+    Ok(tensors)
+}
+
+#[test]
+fn gguf_llama_weights_loading() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("tiny-llama-q8_0.gguf");
+    let config = single_layer_config();
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = LlamaForCausalLM::new(vs.root(), &config);
+    let tensors = quantized_tensors(&vs)?;
+    write_gguf(&path, &tiny_metadata("llama"), &tensors)?;
+
+    let gguf_file = GgufFile::open(&path)?;
+    assert_eq!(gguf_file.version, 3);
+    assert_eq!(gguf_file.architecture(), Some("llama"));
+    let tensor_info = gguf_file.tensor_info("blk.0.attn_k.weight").unwrap();
+    assert_eq!(tensor_info.shape, vec![16, 32]);
+    assert_eq!(tensor_info.ggml_type, GgmlType::Q8_0);
+
+    let loaded_config = LlamaConfig::from_gguf(&gguf_file)?;
+    assert_eq!(loaded_config.vocab_size, 100);
+    assert_eq!(loaded_config.num_key_value_heads, Some(2));
+    assert_eq!(loaded_config.head_dim(), 8);
+    assert_eq!(loaded_config.tie_word_embeddings, Some(false));
+
+    let mut loaded_vs = nn::VarStore::new(Device::Cpu);
+    let _ = LlamaForCausalLM::new(loaded_vs.root(), &loaded_config);
+    load_weights_from_file(&path, &mut loaded_vs)?;
+
+    let variables = vs.variables();
+    for (name, loaded_variable) in loaded_vs.variables() {
+        assert!(
+            loaded_variable.equal(&variables[&name]),
+            "mismatch for {name}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn gguf_unsupported_checkpoints() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.gguf");
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = LlamaForCausalLM::new(vs.root(), &single_layer_config());
+    let tensors = quantized_tensors(&vs)?;
+
+    write_gguf(&path, &tiny_metadata("gpt2"), &tensors)?;
+    let mut loaded_vs = nn::VarStore::new(Device::Cpu);
+    let _ = LlamaForCausalLM::new(loaded_vs.root(), &single_layer_config());
+    let error = load_weights_from_file(&path, &mut loaded_vs).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Unsupported GGUF architecture gpt2"));
+
+    // k-quants (Q4_K, GGML type 12) are not supported
+    let mut k_quants_tensors = tensors.clone();
+    for tensor in k_quants_tensors.iter_mut() {
+        if tensor.0 == "blk.0.ffn_up.weight" {
+            tensor.1 = 12;
+        }
+    }
+    write_gguf(&path, &tiny_metadata("llama"), &k_quants_tensors)?;
+    let error = load_weights_from_file(&path, &mut loaded_vs).unwrap_err();
+    assert!(error.to_string().contains("Unsupported data type"));
+
+    let missing_tensors = tensors
+        .into_iter()
+        .filter(|tensor| tensor.0 != "output_norm.weight")
+        .collect::<Vec<(String, u32, Vec<i64>, Vec<u8>)>>();
+    write_gguf(&path, &tiny_metadata("llama"), &missing_tensors)?;
+    let error = load_weights_from_file(&path, &mut loaded_vs).unwrap_err();
+    assert!(error.to_string().contains("model.norm.weight"));
+    Ok(())
+}
@@ -4,14 +4,21 @@ use rust_bert::gpt2::{
 };
 use rust_bert::pipelines::common::{ModelResource, ModelType};
 use rust_bert::pipelines::conversation::{
-    ConversationConfig, ConversationManager, ConversationModel,
+    Conversation, ConversationConfig, ConversationManager, ConversationModel,
+    HistoryTruncationStrategy,
 };
 use rust_bert::pipelines::generation_utils::{
-    Cache, GenerateConfig, GenerateOptions, LanguageGenerator,
+    Cache, GenerateConfig, GenerateOptions, LanguageGenerator, LogitsProcessor, NoRepeatNgramScope,
+    SequenceBiasLogitsProcessor, StoppingCriteria,
+};
+use rust_bert::pipelines::prompt_classification::{PromptClassifier, Verbalizer};
+use rust_bert::pipelines::prompts::PromptTemplate;
+use rust_bert::pipelines::structured_generation::{
+    GrammarLogitsProcessor, JsonGrammar, JsonSchema, TextGrammar,
 };
 use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
 use rust_bert::resources::{RemoteResource, ResourceProvider};
-use rust_bert::Config;
+use rust_bert::{Config, InputError, RustBertError};
 use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
 use tch::{nn, Device, Tensor};
 
@@ -129,6 +136,207 @@ fn gpt2_generation_greedy() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn gpt2_generation_streaming() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource = Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource = Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource = Box::new(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource = Box::new(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
+
+    let generate_config = TextGenerationConfig {
+        model_type: ModelType::GPT2,
+        model_resource: ModelResource::Torch(model_resource),
+        config_resource,
+        vocab_resource,
+        merges_resource: Some(merges_resource),
+        max_length: Some(40),
+        do_sample: false,
+        num_beams: 1,
+        temperature: 1.1,
+        repetition_penalty: 1.1,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+
+    let input_context = "The cat";
+    let mut pieces: Vec<String> = Vec::new();
+    let output = model.generate_stream(input_context, None, |text| pieces.push(text.to_string()));
+
+    assert!(pieces.len() > 1);
+    assert_eq!(output, model.generate(&[input_context], None)[0]);
+    assert_eq!(format!("{input_context}{}", pieces.concat()), output);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_truncation_sampling() -> anyhow::Result<()> {
+    //    Resources definition
+    let generate_config = GenerateConfig {
+        max_length: Some(24),
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            Gpt2ModelResources::GPT2,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2)),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2)),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            Gpt2MergesResources::GPT2,
+        ))),
+        do_sample: true,
+        num_beams: 1,
+        top_p: 1.0,
+        no_repeat_ngram_size: 0,
+        num_return_sequences: 3,
+        typical_p: Some(0.9),
+        eta_cutoff: Some(2e-3),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context = "The dog";
+    let output = model.generate(Some(&[input_context]), None);
+    assert_eq!(output.len(), 3);
+    assert!(output
+        .iter()
+        .all(|output| output.text.starts_with(input_context)));
+
+    // An epsilon cutoff higher than the probability of all tokens only keeps the most likely token
+    let greedy_output = model.generate(
+        Some(&[input_context]),
+        Some(GenerateOptions {
+            do_sample: Some(false),
+            num_return_sequences: Some(1),
+            ..Default::default()
+        }),
+    );
+    let epsilon_output = model.generate(
+        Some(&[input_context]),
+        Some(GenerateOptions {
+            typical_p: Some(1.0),
+            eta_cutoff: Some(0.0),
+            epsilon_cutoff: Some(1.0),
+            num_return_sequences: Some(1),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(epsilon_output[0].text, greedy_output[0].text);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_input_validation() -> anyhow::Result<()> {
+    let generate_config = TextGenerationConfig {
+        max_length: Some(40),
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+
+    let no_input: [&str; 0] = [];
+    assert!(matches!(
+        model.try_generate(&no_input, None),
+        Err(RustBertError::InputError(InputError::EmptyInput))
+    ));
+
+    let long_input = "The cat ".repeat(1024);
+    match model.try_generate(&[long_input.as_str()], None) {
+        Err(RustBertError::InputError(InputError::ContextTooLong {
+            index, max_length, ..
+        })) => {
+            assert_eq!(index, 0);
+            assert_eq!(max_length, 1024);
+        }
+        _ => panic!("Expected a context length error"),
+    }
+
+    let output = model.try_generate(&["The cat"], None)?;
+    assert_eq!(output.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_from_set() -> anyhow::Result<()> {
+    let generate_config = TextGenerationConfig {
+        max_length: Some(40),
+        do_sample: false,
+        num_beams: 3,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+
+    let allowed_outputs = [" Paris", " London", " New York City"];
+    let output = model.generate_from_set(
+        &[
+            "The capital city of France is",
+            "The largest city in the United States is",
+        ],
+        &allowed_outputs,
+    )?;
+
+    assert_eq!(output.len(), 2);
+    assert!(output
+        .iter()
+        .all(|generated| allowed_outputs.contains(&generated.as_str())));
+    assert_eq!(output[0], " Paris");
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_prompt_classification() -> anyhow::Result<()> {
+    let model = TextGenerationModel::new(Default::default())?;
+
+    let scores =
+        model.score_continuations("The capital city of France is", &[" Paris", " Banana"])?;
+    assert_eq!(scores.len(), 2);
+    assert!(scores[0] > scores[1]);
+
+    let classifier = PromptClassifier::new(
+        PromptTemplate::new("Review: {text}\nSentiment: {label}")?,
+        vec![
+            Verbalizer::new("positive", &["positive"]),
+            Verbalizer::new("negative", &["negative"]),
+        ],
+    )?
+    .with_example("I loved every minute of it, a wonderful film.", "positive")?
+    .with_example(
+        "A complete waste of time, the acting was awful.",
+        "negative",
+    )?;
+    assert_eq!(classifier.num_examples(), 2);
+
+    let output = classifier.classify_with_generator(
+        &model,
+        &[
+            "What a great movie, I would watch it again!",
+            "Terrible plot and boring characters, I hated it.",
+        ],
+    )?;
+    assert_eq!(output.len(), 2);
+    for labels in output.iter() {
+        assert_eq!(labels.len(), 2);
+        assert!((labels.iter().map(|label| label.score).sum::<f64>() - 1.0).abs() < 1e-6);
+    }
+    assert!(output[0][0].score > output[0][1].score);
+    assert!(output[1][1].score > output[1][0].score);
+
+    assert!(classifier
+        .clone()
+        .with_example("Unlabelled", "neutral")
+        .is_err());
+    assert!(PromptClassifier::new(
+        PromptTemplate::new("Review: {text}")?,
+        vec![Verbalizer::new("positive", &["positive"])]
+    )
+    .is_err());
+    Ok(())
+}
+
 #[test]
 fn gpt2_generation_beam_search() -> anyhow::Result<()> {
     //    Resources definition
@@ -475,6 +683,328 @@ fn gpt2_bad_tokens_greedy() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn gpt2_sequence_bias_greedy() -> anyhow::Result<()> {
+    //    Resources definition
+    let generate_config = || GenerateConfig {
+        max_length: Some(36),
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            Gpt2ModelResources::GPT2,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2)),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2)),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            Gpt2MergesResources::GPT2,
+        ))),
+        do_sample: false,
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config())?;
+
+    let bad_word_ids = model
+        .get_tokenizer()
+        .encode_list(
+            &[" honeybees", " a writer"],
+            512,
+            &TruncationStrategy::DoNotTruncate,
+            0,
+        )
+        .into_iter()
+        .map(|tokenized_input| tokenized_input.token_ids)
+        .collect::<Vec<Vec<i64>>>();
+    let input_context_1 = "Hello, my name is";
+    let expected_output = "Hello, my name is John. I'm a student at the University of California, Berkeley. I've been studying computer science for a year. I have a PhD in computer science";
+
+    // Banning sequences with an infinite negative bias is equivalent to banning bad words
+    let sequence_bias = bad_word_ids
+        .iter()
+        .map(|sequence| (sequence.clone(), f64::NEG_INFINITY))
+        .collect::<Vec<(Vec<i64>, f64)>>();
+    let output = model.generate(
+        Some(&[input_context_1]),
+        Some(GenerateOptions {
+            sequence_bias: Some(&sequence_bias),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].text, expected_output);
+
+    // A positive bias encourages a token
+    let name_ids = model
+        .get_tokenizer()
+        .encode_list(&[" Alice"], 512, &TruncationStrategy::DoNotTruncate, 0)
+        .pop()
+        .unwrap()
+        .token_ids;
+    let positive_bias = vec![(name_ids, 20.0)];
+    let biased_output = model.generate(
+        Some(&[input_context_1]),
+        Some(GenerateOptions {
+            sequence_bias: Some(&positive_bias),
+            max_new_tokens: Some(1),
+            max_length: None,
+            ..Default::default()
+        }),
+    );
+    assert_eq!(biased_output[0].text, "Hello, my name is Alice");
+
+    // Bad words and biases set in the configuration
+    let model = GPT2Generator::new(GenerateConfig {
+        bad_word_ids: bad_word_ids.clone(),
+        ..generate_config()
+    })?;
+    assert_eq!(
+        model.generate(Some(&[input_context_1]), None)[0].text,
+        expected_output
+    );
+    let model = GPT2Generator::new(GenerateConfig {
+        sequence_bias,
+        ..generate_config()
+    })?;
+    assert_eq!(
+        model.generate(Some(&[input_context_1]), None)[0].text,
+        expected_output
+    );
+
+    assert!(SequenceBiasLogitsProcessor::new(vec![(vec![], 1.0)]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_stop_sequences_greedy() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource = Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource = Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource = Box::new(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource = Box::new(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
+
+    let generate_config = GenerateConfig {
+        max_length: Some(36),
+        model_resource: ModelResource::Torch(model_resource),
+        config_resource,
+        vocab_resource,
+        merges_resource: Some(merges_resource),
+        do_sample: false,
+        num_beams: 1,
+        stop_sequences: vec![", and".to_string()],
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context_1 = "Hello, my name is";
+    let output = model.generate(Some(&[input_context_1]), None);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].text, "Hello, my name is John. I'm a writer, and");
+
+    let stop_sequences = ["."];
+    let generate_options = GenerateOptions {
+        stop_sequences: Some(&stop_sequences),
+        ..Default::default()
+    };
+    let output = model.generate(Some(&[input_context_1]), Some(generate_options));
+    assert_eq!(output[0].text, "Hello, my name is John.");
+
+    let max_three_tokens = |_token_ids: &[i64], generated_length: usize| generated_length >= 3;
+    let stopping_criteria: [&dyn StoppingCriteria; 1] = [&max_three_tokens];
+    let generate_options = GenerateOptions {
+        stop_sequences: Some(&[]),
+        stopping_criteria: Some(&stopping_criteria),
+        ..Default::default()
+    };
+    let output = model.generate(Some(&[input_context_1]), Some(generate_options));
+    assert_eq!(output[0].text, "Hello, my name is John. I");
+
+    Ok(())
+}
+
+#[test]
+fn json_grammar_schema() -> anyhow::Result<()> {
+    let schema = JsonSchema::from_json(
+        r#"{
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "tags": {"type": "array", "items": {"enum": ["admin", "user"]}}
+            },
+            "required": ["name", "age"]
+        }"#,
+    )?;
+    let grammar = JsonGrammar::new(&schema);
+
+    assert!(grammar.accepts(r#" {"name": "John \"Jr\"", "age": 42}"#));
+    assert!(grammar.accepts(r#"{"age": -3, "tags": ["user", "admin"], "name": ""}"#));
+    assert!(grammar.accepts_prefix(r#"{"name": "Jo"#));
+    assert!(grammar.accepts_prefix(r#"{"ag"#));
+    assert!(!grammar.accepts(r#"{"name": "John"}"#));
+    assert!(!grammar.accepts_prefix(r#"{"nickname"#));
+    assert!(!grammar.accepts_prefix(r#"{"age": 4.2"#));
+    assert!(!grammar.accepts_prefix(r#"{"age": 42, "age""#));
+    assert!(!grammar.accepts_prefix(r#"{"tags": ["guest"#));
+    assert!(!grammar.accepts_prefix(r#"{"name": "John", "age": 42}}"#));
+
+    let any_grammar = JsonGrammar::new(&JsonSchema::Any);
+    assert!(any_grammar.accepts(r#"[1.5e3, true, null, {"a": {}}]"#));
+    assert!(any_grammar.accepts("0"));
+    assert!(!any_grammar.accepts_prefix("01"));
+    assert!(!any_grammar.accepts_prefix("[1,]"));
+
+    assert!(JsonSchema::from_json(r#"{"type": ["string", "null"]}"#).is_err());
+    Ok(())
+}
+
+#[test]
+fn gpt2_json_constrained_generation() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource = Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource = Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource = Box::new(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource = Box::new(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
+
+    let generate_config = GenerateConfig {
+        max_length: Some(64),
+        model_resource: ModelResource::Torch(model_resource),
+        config_resource,
+        vocab_resource,
+        merges_resource: Some(merges_resource),
+        do_sample: false,
+        num_beams: 1,
+        no_repeat_ngram_size: 0,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let schema = JsonSchema::from_json(
+        r#"{
+            "type": "object",
+            "properties": {
+                "sentiment": {"enum": ["positive", "negative"]},
+                "stars": {"type": "integer"}
+            },
+            "required": ["sentiment", "stars"]
+        }"#,
+    )?;
+    let json_processor =
+        GrammarLogitsProcessor::new(JsonGrammar::new(&schema), model.get_tokenizer());
+    let logits_processors: [&dyn LogitsProcessor; 1] = [&json_processor];
+    let generate_options = GenerateOptions {
+        logits_processors: Some(&logits_processors),
+        ..Default::default()
+    };
+
+    let input_context = "Review: The food was delicious and the staff friendly. Rating as JSON:";
+    let output = model.generate(Some(&[input_context]), Some(generate_options));
+    assert_eq!(output.len(), 1);
+    let generated = output[0].text.strip_prefix(input_context).unwrap();
+    assert!(json_processor.grammar().accepts(generated));
+    let value: serde_json::Value = serde_json::from_str(generated)?;
+    assert!(value["sentiment"].is_string());
+    assert!(value["stars"].is_i64());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_assisted_generation() -> anyhow::Result<()> {
+    //    Resources definition
+    let generate_config = GenerateConfig {
+        max_length: Some(32),
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            Gpt2ModelResources::GPT2,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2)),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2)),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            Gpt2MergesResources::GPT2,
+        ))),
+        do_sample: false,
+        num_beams: 1,
+        no_repeat_ngram_size: 0,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let draft_generate_config = GenerateConfig {
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            Gpt2ModelResources::DISTIL_GPT2,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            Gpt2ConfigResources::DISTIL_GPT2,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            Gpt2VocabResources::DISTIL_GPT2,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            Gpt2MergesResources::DISTIL_GPT2,
+        ))),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let draft_model = GPT2Generator::new(draft_generate_config)?;
+
+    for input_context in ["The dog", "The cat was sleeping on the"] {
+        let greedy_output = model.generate(Some(&[input_context]), None);
+        let assisted_output = model.generate_assisted(&[input_context], &draft_model, 4, None)?;
+        assert_eq!(assisted_output.len(), 1);
+        assert_eq!(assisted_output[0].text, greedy_output[0].text);
+    }
+    assert!(model
+        .generate_assisted(&["The dog"], &draft_model, 0, None)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_with_shared_context() -> anyhow::Result<()> {
+    //    Resources definition
+    let generate_config = GenerateConfig {
+        max_length: Some(32),
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            Gpt2ModelResources::GPT2,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2)),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2)),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            Gpt2MergesResources::GPT2,
+        ))),
+        do_sample: false,
+        num_beams: 1,
+        no_repeat_ngram_size: 0,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    // The cache of the context is reused by every prompt without being modified
+    let output = model.generate_with_shared_context("The dog", &[" was", " is", " was"], None)?;
+    assert_eq!(output.len(), 3);
+    assert_eq!(output[0].text, output[2].text);
+    for (prompt, shared_context_output) in ["The dog was", "The dog is"].iter().zip(output.iter()) {
+        let greedy_output = model.generate(Some(&[*prompt]), None);
+        assert!(!shared_context_output.text.is_empty());
+        assert!(greedy_output[0].text.ends_with(&shared_context_output.text));
+    }
+
+    let generate_options = GenerateOptions {
+        max_new_tokens: Some(16),
+        stop_sequences: Some(&["."]),
+        ..Default::default()
+    };
+    let output =
+        model.generate_with_shared_context("The dog", &[" was"], Some(generate_options))?;
+    assert!(!output[0].text.contains('.'));
+
+    Ok(())
+}
+
 #[test]
 fn gpt2_bad_tokens_beam_search() -> anyhow::Result<()> {
     //    Resources definition
@@ -718,6 +1248,65 @@ fn gpt2_beam_search_token_scores() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn gpt2_no_repeat_ngram_scope_greedy() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource = Box::new(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource = Box::new(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource = Box::new(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource = Box::new(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
+
+    let generate_config = GenerateConfig {
+        max_length: Some(32),
+        model_resource: ModelResource::Torch(model_resource),
+        config_resource,
+        vocab_resource,
+        merges_resource: Some(merges_resource),
+        do_sample: false,
+        num_beams: 1,
+        no_repeat_ngram_size: 2,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context = "The cat sat on the mat. The cat sat on the";
+    let prompt_length = model.get_tokenizer().encode_list(
+        &[input_context],
+        512,
+        &TruncationStrategy::DoNotTruncate,
+        0,
+    )[0]
+    .token_ids
+    .len();
+    let bigrams = |indices: &[i64]| {
+        indices
+            .windows(2)
+            .map(|bigram| bigram.to_vec())
+            .collect::<Vec<Vec<i64>>>()
+    };
+
+    // Bigrams completed by generated tokens do not repeat any bigram of the sequence
+    let output = model.generate_indices(Some(&[input_context]), None);
+    let full_sequence_bigrams = bigrams(&output[0].indices);
+    for position in prompt_length - 1..full_sequence_bigrams.len() {
+        assert!(!full_sequence_bigrams[..position].contains(&full_sequence_bigrams[position]));
+    }
+
+    // Bigrams of the prompt may be repeated, only the generated bigrams are unique
+    let generate_options = GenerateOptions {
+        no_repeat_ngram_scope: Some(NoRepeatNgramScope::Generated),
+        ..Default::default()
+    };
+    let output = model.generate_indices(Some(&[input_context]), Some(generate_options));
+    let generated_bigrams = bigrams(&output[0].indices[prompt_length..]);
+    for position in 0..generated_bigrams.len() {
+        assert!(!generated_bigrams[..position].contains(&generated_bigrams[position]));
+    }
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn dialogpt_single_multi_turn_conversation() -> anyhow::Result<()> {
@@ -755,6 +1344,57 @@ fn dialogpt_single_multi_turn_conversation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_restored_multi_turn_conversation() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
+        do_sample: false,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    // Set-up conversation manager and add a conversation
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_id =
+        conversation_manager.create("Going to the movies tonight - any suggestions?");
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"The Big Lebowski");
+
+    // Persist and restore the conversation in a new conversation manager
+    let serialized = serde_json::to_string(&conversation_manager)?;
+    let mut conversation_manager: ConversationManager = serde_json::from_str(&serialized)?;
+
+    // Turn 2
+    let _ = conversation_manager
+        .get(&conversation_id)
+        .unwrap()
+        .add_user_input("Is it an action movie?");
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"It\'s a comedy.");
+
+    // Restore a conversation persisted without token ids
+    let mut conversation: Conversation = serde_json::from_str(
+        r#"{"past_user_inputs": ["Going to the movies tonight - any suggestions?"],
+            "generated_responses": ["The Big Lebowski"],
+            "new_user_input": "Is it an action movie?",
+            "history": []}"#,
+    )?;
+    conversation_model.restore_history(&mut conversation);
+    assert_eq!(conversation.history.len(), 2);
+
+    let mut conversation_manager = ConversationManager::new();
+    conversation_manager.restore(conversation_id, conversation);
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"It\'s a comedy.");
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn dialogpt_multiple_multi_turn_conversation() -> anyhow::Result<()> {
@@ -846,6 +1486,93 @@ fn dialogpt_multiple_multi_turn_conversation_with_truncation() -> anyhow::Result
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_multi_turn_conversation_drop_oldest_turns() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
+        max_length: Some(36),
+        min_length_for_response: 24,
+        do_sample: false,
+        device: Device::Cpu,
+        history_truncation: HistoryTruncationStrategy::DropOldestTurns,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    // Set-up conversation manager and add a conversation
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_id =
+        conversation_manager.create("Going to the movies tonight - any suggestions?");
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"The Big Lebowski");
+
+    // Turn 2, the first turn does not fit in the context and is dropped entirely
+    let _ = conversation_manager
+        .get(&conversation_id)
+        .unwrap()
+        .add_user_input("Is it an action movie?");
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert!(!output.get(&conversation_id).unwrap().is_empty());
+    assert_eq!(
+        conversation_manager
+            .get(&conversation_id)
+            .unwrap()
+            .history
+            .len(),
+        4
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_multi_turn_conversation_with_system_prompt() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
+        max_length: Some(36),
+        min_length_for_response: 24,
+        do_sample: false,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    // Set-up conversation manager and add a conversation with a persona
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_id = conversation_manager.create_with_system_prompt(
+        "I am a movie critic.",
+        "Going to the movies tonight - any suggestions?",
+    );
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert!(!output.get(&conversation_id).unwrap().is_empty());
+
+    // Turn 2, the history exceeds the maximum context length and gets truncated
+    let _ = conversation_manager
+        .get(&conversation_id)
+        .unwrap()
+        .add_user_input("Is it an action movie?");
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+
+    // The system prompt is not stored in the conversation history
+    let conversation = conversation_manager.get(&conversation_id).unwrap();
+    assert_eq!(
+        conversation.system_prompt.as_deref(),
+        Some("I am a movie critic.")
+    );
+    assert_eq!(conversation.history.len(), 4);
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn dialogpt_multiple_multi_turn_conversation_with_conversation_deletion() -> anyhow::Result<()> {
@@ -1,13 +1,14 @@
 use rust_bert::gpt_neo::{
-    GptNeoConfig, GptNeoConfigResources, GptNeoForCausalLM, GptNeoMergesResources,
-    GptNeoModelResources, GptNeoVocabResources,
+    AttentionLayerType, GptNeoConfig, GptNeoConfigResources, GptNeoForCausalLM, GptNeoGenerator,
+    GptNeoMergesResources, GptNeoModelResources, GptNeoVocabResources,
 };
 use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::generation_utils::{GenerateConfig, GenerateOptions, LanguageGenerator};
 use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
 use rust_bert::resources::{RemoteResource, ResourceProvider};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
-use tch::{nn, Device, Tensor};
+use tch::{nn, no_grad, Device, Tensor};
 
 #[test]
 fn gpt_neo_lm() -> anyhow::Result<()> {
@@ -151,3 +152,128 @@ fn test_generation_gpt_neo() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_contrastive_search_gpt_neo() -> anyhow::Result<()> {
+    //    Resources paths
+    let generate_config = GenerateConfig {
+        model_type: ModelType::GPTNeo,
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            GptNeoModelResources::GPT_NEO_125M,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            GptNeoConfigResources::GPT_NEO_125M,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            GptNeoVocabResources::GPT_NEO_125M,
+        )),
+        merges_resource: Some(Box::new(RemoteResource::from_pretrained(
+            GptNeoMergesResources::GPT_NEO_125M,
+        ))),
+        max_length: Some(32),
+        do_sample: false,
+        num_beams: 1,
+        top_k: 4,
+        no_repeat_ngram_size: 0,
+        penalty_alpha: Some(0.6),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = GptNeoGenerator::new(generate_config)?;
+
+    let input_context_1 = "It was a very nice and sunny";
+    let input_context_2 = "It was a gloom winter night, and";
+    let output = model.generate(Some(&[input_context_1, input_context_2]), None);
+
+    assert_eq!(output.len(), 2);
+    assert!(output[0].text.starts_with(input_context_1));
+    assert!(output[1].text.starts_with(input_context_2));
+    let repeated_output = model.generate(Some(&[input_context_1, input_context_2]), None);
+    assert_eq!(output[0].text, repeated_output[0].text);
+    assert_eq!(output[1].text, repeated_output[1].text);
+
+    // Without degeneration penalty, contrastive search selects the most likely candidate (greedy decoding)
+    let contrastive_output = model.generate(
+        Some(&[input_context_1, input_context_2]),
+        Some(GenerateOptions {
+            penalty_alpha: Some(0.0),
+            ..Default::default()
+        }),
+    );
+    let greedy_output = model.generate(
+        Some(&[input_context_1, input_context_2]),
+        Some(GenerateOptions {
+            top_k: Some(1),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(contrastive_output[0].text, greedy_output[0].text);
+    assert_eq!(contrastive_output[1].text, greedy_output[1].text);
+
+    Ok(())
+}
+
+#[test]
+fn gpt_neo_local_attention_window() -> anyhow::Result<()> {
+    //    Set-up a single local attention layer with a window of 2 tokens
+    let config = GptNeoConfig {
+        attention_layers: vec![AttentionLayerType::Local],
+        attention_types: vec![(vec![AttentionLayerType::Local], 1)],
+        vocab_size: 16,
+        num_layers: 1,
+        num_heads: 2,
+        hidden_size: 8,
+        window_size: 2,
+        max_position_embeddings: 16,
+        ..Default::default()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let gpt_neo_model = GptNeoForCausalLM::new(vs.root(), &config)?;
+
+    //    Forward pass on inputs longer than the window
+    let input_ids = Tensor::from_slice(&[1i64, 2, 3, 4, 5, 6]).unsqueeze(0);
+    let modified_input_ids = Tensor::from_slice(&[7i64, 2, 3, 4, 5, 6]).unsqueeze(0);
+    let forward = |input_ids: &Tensor| {
+        no_grad(|| gpt_neo_model.forward_t(Some(input_ids), None, None, None, None, None, false))
+    };
+    let logits = forward(&input_ids)?.lm_logits;
+    let modified_logits = forward(&modified_input_ids)?.lm_logits;
+
+    //    The first token is only attended to by the tokens within the window
+    let difference = (&logits - &modified_logits).abs().amax([0, 2], false);
+    assert!(difference.double_value(&[0]) > 1e-6);
+    assert!(difference.double_value(&[1]) > 1e-6);
+    assert!(difference.slice(0, 2, 6, 1).max().double_value(&[]) < 1e-6);
+
+    //    The window is enforced when attending to the cached keys and values
+    let model_output = no_grad(|| {
+        gpt_neo_model.forward_t(
+            Some(&input_ids.slice(1, 0, 5, 1)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let last_step_logits = no_grad(|| {
+        gpt_neo_model.forward_t(
+            Some(&input_ids.slice(1, 5, 6, 1)),
+            None,
+            None,
+            None,
+            model_output.next_cache,
+            None,
+            false,
+        )
+    })?
+    .lm_logits;
+    let step_difference = (last_step_logits.select(1, 0) - logits.select(1, 5))
+        .abs()
+        .max()
+        .double_value(&[]);
+    assert!(step_difference < 1e-5);
+
+    Ok(())
+}
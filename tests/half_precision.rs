@@ -0,0 +1,86 @@
+use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+use rust_bert::resources::LocalResource;
+use std::collections::HashMap;
+use tch::{nn, Device, Kind};
+
+const VOCAB: [&str; 12] = [
+    "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "movie", "was", "great", "awful", "not",
+    ".",
+];
+
+/// Saves a randomly initialized model with its configuration and vocabulary, and returns a pipeline configuration
+/// loading it in the provided precision
+fn classification_config(
+    dir: &tempfile::TempDir,
+    kind: Option<Kind>,
+) -> anyhow::Result<SequenceClassificationConfig> {
+    let config = BertConfig {
+        hidden_size: 16,
+        intermediate_size: 32,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        max_position_embeddings: 32,
+        vocab_size: VOCAB.len() as i64,
+        id2label: Some(HashMap::from([
+            (0, "NEGATIVE".to_string()),
+            (1, "POSITIVE".to_string()),
+        ])),
+        ..Default::default()
+    };
+    let weights_path = dir.path().join("model.ot");
+    if !weights_path.exists() {
+        let vs = nn::VarStore::new(Device::Cpu);
+        let _ = BertForSequenceClassification::new(vs.root(), &config)?;
+        vs.save(&weights_path)?;
+        std::fs::write(
+            dir.path().join("config.json"),
+            serde_json::to_string(&config)?,
+        )?;
+        std::fs::write(dir.path().join("vocab.txt"), VOCAB.join("\n"))?;
+    }
+    Ok(SequenceClassificationConfig {
+        device: Device::Cpu,
+        kind,
+        ..SequenceClassificationConfig::new(
+            ModelType::Bert,
+            ModelResource::Torch(Box::new(LocalResource::from(weights_path))),
+            LocalResource::from(dir.path().join("config.json")),
+            LocalResource::from(dir.path().join("vocab.txt")),
+            None,
+            true,
+            None,
+            None,
+        )
+    })
+}
+
+#[test]
+fn bfloat16_sequence_classification() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = ["The movie was great.", "The movie was not awful"];
+
+    let float_model = SequenceClassificationModel::new(classification_config(&dir, None)?)?;
+    let half_model =
+        SequenceClassificationModel::new(classification_config(&dir, Some(Kind::BFloat16))?)?;
+
+    let float_output = float_model.predict(input);
+    let half_output = half_model.predict(input);
+    assert_eq!(half_output.len(), 2);
+    for (float_label, half_label) in float_output.iter().zip(half_output.iter()) {
+        assert!(half_label.score.is_finite());
+        assert!((float_label.score - half_label.score).abs() < 5e-2);
+    }
+    Ok(())
+}
+
+#[test]
+fn invalid_precision() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = classification_config(&dir, Some(Kind::Int64))?;
+    assert!(SequenceClassificationModel::new(config).is_err());
+    Ok(())
+}
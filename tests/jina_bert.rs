@@ -0,0 +1,52 @@
+mod common;
+
+use rust_bert::jina_bert::{JinaBertConfig, JinaBertModel};
+use rust_bert::pipelines::sentence_embeddings::{
+    SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn jina_bert_extrapolates_sequence_length() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = JinaBertConfig {
+        max_position_embeddings: 16,
+        ..common::tiny_jina_bert_config()
+    };
+    let model = JinaBertModel::new(vs.root(), &config);
+
+    // ALiBi does not rely on learned positions, sequences longer than the configured maximum can be encoded
+    let input_ids = Tensor::randint(100, [1, 64], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, false))?;
+    assert_eq!(output.hidden_state.size(), vec![1, 64, 32]);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn jina_embeddings() -> anyhow::Result<()> {
+    let model =
+        SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::JinaEmbeddingsV2BaseEn)
+            .create_model()?;
+
+    let sentences = [
+        "A man is eating food.",
+        "A man is eating a piece of bread.",
+        "The stock market fell sharply today.",
+    ];
+    let embeddings = model.encode_as_tensor(&sentences)?.embeddings;
+    let similarity = |left: i64, right: i64| {
+        embeddings
+            .get(left)
+            .cosine_similarity(&embeddings.get(right), 0, 1e-8)
+            .double_value(&[])
+    };
+
+    assert_eq!(embeddings.size(), vec![3, 768]);
+    assert!(similarity(0, 1) > similarity(0, 2));
+    assert!(similarity(0, 1) > similarity(1, 2));
+
+    Ok(())
+}
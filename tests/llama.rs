@@ -0,0 +1,121 @@
+mod common;
+
+use rust_bert::llama::{LlamaConfig, LlamaModel};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn mistral_sliding_window() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = LlamaConfig {
+        sliding_window: Some(4),
+        ..common::tiny_llama_config()
+    };
+    let model = LlamaModel::new(vs.root(), &config);
+
+    let input_ids = Tensor::randint(100, [1, 8], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    let attentions = output.all_attentions.unwrap();
+    assert!(attentions[1].double_value(&[0, 0, 7, 4]) > 0.0);
+    assert!(attentions[1].double_value(&[0, 0, 7, 3]) < 1e-6);
+
+    Ok(())
+}
+
+#[cfg(feature = "hf-tokenizers")]
+mod pretrained {
+    use rust_bert::llama::{
+        LlamaConfig, LlamaConfigResources, LlamaForCausalLM, LlamaModelResources, LlamaSpecialMap,
+        LlamaVocabResources,
+    };
+    use rust_bert::pipelines::common::TokenizerOption;
+    use rust_bert::pipelines::generation_utils::Cache;
+    use rust_bert::resources::{load_weights, RemoteResource, ResourceProvider};
+    use rust_bert::Config;
+    use rust_tokenizers::tokenizer::TruncationStrategy;
+    use tch::{nn, no_grad, Device, Tensor};
+
+    #[test]
+    #[cfg_attr(not(feature = "all-tests"), ignore)]
+    fn llama_lm() -> anyhow::Result<()> {
+        //    Resources paths
+        let config_resource =
+            RemoteResource::from_pretrained(LlamaConfigResources::TINYLLAMA_1_1B_CHAT);
+        let vocab_resource =
+            RemoteResource::from_pretrained(LlamaVocabResources::TINYLLAMA_1_1B_CHAT);
+        let special_map_resource =
+            RemoteResource::from_pretrained(LlamaSpecialMap::TINYLLAMA_1_1B_CHAT);
+        let weights_resource =
+            RemoteResource::from_pretrained(LlamaModelResources::TINYLLAMA_1_1B_CHAT);
+        let config_path = config_resource.get_local_path()?;
+        let vocab_path = vocab_resource.get_local_path()?;
+        let special_map_path = special_map_resource.get_local_path()?;
+
+        //    Set-up model
+        let device = Device::Cpu;
+        let mut vs = nn::VarStore::new(device);
+        let tokenizer = TokenizerOption::from_hf_tokenizer_file(vocab_path, special_map_path)?;
+        let config = LlamaConfig::from_file(config_path);
+        let llama_model = LlamaForCausalLM::new(vs.root(), &config);
+        load_weights(&weights_resource, &mut vs)?;
+
+        //    Define input
+        let input = ["The capital of France is"];
+        let tokenized_input =
+            tokenizer.encode_list(&input, 128, &TruncationStrategy::LongestFirst, 0);
+        let input_length = tokenized_input[0].token_ids.len() as i64;
+        let input_tensor = Tensor::from_slice(&tokenized_input[0].token_ids)
+            .unsqueeze(0)
+            .to(device);
+
+        //    Forward pass
+        let model_output = no_grad(|| {
+            llama_model.forward_t(Some(&input_tensor), Cache::None, None, None, None, false)
+        })?;
+
+        let next_word_id = model_output
+            .lm_logits
+            .get(0)
+            .get(-1)
+            .argmax(-1, true)
+            .int64_value(&[0]);
+        let next_word = tokenizer.decode(&[next_word_id], true, true);
+
+        // Output
+        assert_eq!(
+            model_output.lm_logits.size(),
+            vec!(1, input_length, config.vocab_size)
+        );
+        assert_eq!(next_word.trim(), "Paris");
+
+        // Cached generation step matches the pretrained logits of the full sequence
+        let prefix_output = no_grad(|| {
+            llama_model.forward_t(
+                Some(&input_tensor.narrow(1, 0, input_length - 1)),
+                Cache::None,
+                None,
+                None,
+                None,
+                false,
+            )
+        })?;
+        let step_output = no_grad(|| {
+            llama_model.forward_t(
+                Some(&input_tensor.narrow(1, input_length - 1, 1)),
+                prefix_output.cache,
+                None,
+                None,
+                None,
+                false,
+            )
+        })?;
+        let max_difference = (step_output.lm_logits.select(1, 0)
+            - model_output.lm_logits.select(1, input_length - 1))
+        .abs()
+        .max()
+        .double_value(&[]);
+        assert!(max_difference < 1e-3);
+        Ok(())
+    }
+}
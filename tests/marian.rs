@@ -4,7 +4,7 @@ use rust_bert::marian::{
 };
 use rust_bert::pipelines::common::{ModelResource, ModelType};
 use rust_bert::pipelines::translation::{
-    Language, TranslationConfig, TranslationModel, TranslationModelBuilder,
+    split_sentences, Language, TranslationConfig, TranslationModel, TranslationModelBuilder,
 };
 use rust_bert::resources::RemoteResource;
 use tch::Device;
@@ -72,3 +72,171 @@ fn test_translation_builder() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+// #[cfg_attr(not(feature = "all-tests"), ignore)]
+fn test_translation_n_best() -> anyhow::Result<()> {
+    let model = TranslationModelBuilder::new()
+        .with_device(Device::cuda_if_available())
+        .with_model_type(ModelType::Marian)
+        .with_source_languages(vec![Language::English])
+        .with_target_languages(vec![Language::French])
+        .create_model()?;
+
+    let input_context_1 = "The quick brown fox jumps over the lazy dog";
+    let input_context_2 = "The dog did not wake up";
+
+    let hypotheses = model.translate_n_best(
+        &[input_context_1, input_context_2],
+        None,
+        Language::French,
+        3,
+    )?;
+    let best = model.translate(&[input_context_1, input_context_2], None, Language::French)?;
+
+    assert_eq!(hypotheses.len(), 2);
+    for (input_hypotheses, best) in hypotheses.iter().zip(best.iter()) {
+        assert_eq!(input_hypotheses.len(), 3);
+        assert!(input_hypotheses
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+        assert!(input_hypotheses
+            .iter()
+            .any(|hypothesis| &hypothesis.text == best));
+    }
+    assert!(model
+        .translate_n_best(&[input_context_1], None, Language::French, 0)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_translation_batch() -> anyhow::Result<()> {
+    let model = TranslationModelBuilder::new()
+        .with_device(Device::cuda_if_available())
+        .with_model_type(ModelType::Marian)
+        .with_source_languages(vec![Language::English])
+        .with_target_languages(vec![Language::French])
+        .create_model()?;
+
+    let input_context_1 = "The quick brown fox jumps over the lazy dog";
+    let input_context_2 = "The dog did not wake up";
+    let input_context_3 = "This is a sentence to be translated";
+
+    let output = model.translate_batch(&[
+        (
+            input_context_1,
+            Some(Language::English),
+            Some(Language::French),
+        ),
+        (input_context_2, None, Some(Language::French)),
+        (
+            input_context_3,
+            Some(Language::English),
+            Some(Language::French),
+        ),
+    ])?;
+    let expected = model.translate(
+        &[input_context_1, input_context_2, input_context_3],
+        None,
+        Language::French,
+    )?;
+
+    assert_eq!(output, expected);
+    assert!(model
+        .translate_batch(&[(input_context_1, Some(Language::German), None)])
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_sentence_splitting() {
+    let text = "Dr. Smith arrived at 10 a.m. today. Was he late?\n\n  \"Yes!\" said J. Doe... Then they left.";
+    let sentences = split_sentences(text, Some(Language::English))
+        .into_iter()
+        .map(|range| &text[range])
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        sentences,
+        [
+            "Dr. Smith arrived at 10 a.m. today.",
+            "Was he late?",
+            "\"Yes!\" said J. Doe...",
+            "Then they left."
+        ]
+    );
+
+    let text = "今日は晴れです。明日は雨です。";
+    let sentences = split_sentences(text, Some(Language::Japanese))
+        .into_iter()
+        .map(|range| &text[range])
+        .collect::<Vec<&str>>();
+    assert_eq!(sentences, ["今日は晴れです。", "明日は雨です。"]);
+}
+
+#[test]
+fn test_translation_documents() -> anyhow::Result<()> {
+    let model = TranslationModelBuilder::new()
+        .with_device(Device::cuda_if_available())
+        .with_model_type(ModelType::Marian)
+        .with_source_languages(vec![Language::English])
+        .with_target_languages(vec![Language::French])
+        .create_model()?;
+
+    let document = "The quick brown fox jumps over the lazy dog. The dog did not wake up.\n\nThis is a sentence to be translated.";
+    let long_document = "The dog did not wake up. ".repeat(200);
+
+    let output = model.translate_documents(
+        &[document, long_document.as_str()],
+        Language::English,
+        Language::French,
+        8,
+    )?;
+    let sentences = model.translate(
+        &[
+            "The quick brown fox jumps over the lazy dog.",
+            "The dog did not wake up.",
+            "This is a sentence to be translated.",
+        ],
+        None,
+        Language::French,
+    )?;
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(
+        output[0],
+        format!("{} {}\n\n{}", sentences[0], sentences[1], sentences[2])
+    );
+    assert_eq!(output[1].matches(sentences[1].as_str()).count(), 200);
+
+    Ok(())
+}
+
+#[test]
+fn test_translation_with_constraints() -> anyhow::Result<()> {
+    let model = TranslationModelBuilder::new()
+        .with_device(Device::cuda_if_available())
+        .with_model_type(ModelType::Marian)
+        .with_source_languages(vec![Language::English])
+        .with_target_languages(vec![Language::French])
+        .create_model()?;
+
+    let input_context_1 = "The quick brown fox jumps over the lazy dog";
+    let input_context_2 = "The dog did not wake up";
+
+    let output = model.translate_with_constraints(
+        &[input_context_1, input_context_2],
+        None,
+        Language::French,
+        &["animal"],
+    )?;
+
+    assert_eq!(output.len(), 2);
+    assert!(output
+        .iter()
+        .all(|translation| translation.contains("animal")));
+
+    Ok(())
+}
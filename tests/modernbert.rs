@@ -0,0 +1,74 @@
+mod common;
+
+use rust_bert::modernbert::ModernBertModel;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn modernbert_local_attention() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let model = ModernBertModel::new(vs.root() / "model", &common::tiny_modernbert_config());
+
+    let input_ids = Tensor::randint(100, [2, 12], (Kind::Int64, device));
+    let mask = Tensor::ones([2, 12], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), Some(&mask), None, false))?;
+
+    // Layers 1 and 2 use sliding window attention: tokens outside the window get no attention weight
+    let all_attentions = output.all_attentions.unwrap();
+    let global_weight = all_attentions[0].double_value(&[0, 0, 0, 11]);
+    let local_weight = all_attentions[1].double_value(&[0, 0, 0, 11]);
+    assert!(global_weight > 0.0);
+    assert!(local_weight < 1e-6);
+
+    Ok(())
+}
+
+#[cfg(feature = "hf-tokenizers")]
+mod pretrained {
+    use rust_bert::modernbert::{
+        ModernBertConfigResources, ModernBertModelResources, ModernBertSpecialMap,
+        ModernBertVocabResources,
+    };
+    use rust_bert::pipelines::common::{ModelResource, ModelType, TokenizerOption};
+    use rust_bert::pipelines::masked_language::{MaskedLanguageConfig, MaskedLanguageModel};
+    use rust_bert::resources::{RemoteResource, ResourceProvider};
+
+    #[test]
+    #[cfg_attr(not(feature = "all-tests"), ignore)]
+    fn modernbert_masked_lm() -> anyhow::Result<()> {
+        //    Resources paths
+        let vocab_resource =
+            RemoteResource::from_pretrained(ModernBertVocabResources::MODERNBERT_BASE);
+        let special_map_resource =
+            RemoteResource::from_pretrained(ModernBertSpecialMap::MODERNBERT_BASE);
+        let tokenizer = TokenizerOption::from_hf_tokenizer_file(
+            vocab_resource.get_local_path()?,
+            special_map_resource.get_local_path()?,
+        )?;
+
+        //    Set-up masked LM model
+        let config = MaskedLanguageConfig::new(
+            ModelType::ModernBert,
+            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+                ModernBertModelResources::MODERNBERT_BASE,
+            ))),
+            RemoteResource::from_pretrained(ModernBertConfigResources::MODERNBERT_BASE),
+            vocab_resource,
+            None,
+            false,
+            None,
+            None,
+            Some(String::from("[MASK]")),
+        );
+        let mask_language_model = MaskedLanguageModel::new_with_tokenizer(config, tokenizer)?;
+
+        //    Run model
+        let output = mask_language_model.predict(["The capital of France is [MASK]."])?;
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].len(), 1);
+        assert_eq!(output[0][0].text.trim(), "Paris");
+
+        Ok(())
+    }
+}
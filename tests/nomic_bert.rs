@@ -0,0 +1,53 @@
+use rust_bert::nomic_bert::{NomicBertConfig, NomicBertModel};
+use rust_bert::pipelines::sentence_embeddings::{
+    SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn nomic_bert_partial_rotary() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = NomicBertConfig {
+        n_embd: 32,
+        n_head: 2,
+        n_layer: 1,
+        n_inner: Some(64),
+        vocab_size: 100,
+        rotary_emb_fraction: Some(0.5),
+        ..Default::default()
+    };
+    let model = NomicBertModel::new(vs.root(), &config);
+
+    let input_ids = Tensor::randint(100, [1, 7], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, false))?;
+    assert_eq!(output.hidden_state.size(), vec![1, 7, 32]);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn nomic_embeddings() -> anyhow::Result<()> {
+    let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::NomicEmbedTextV1)
+        .create_model()?;
+
+    let sentences = [
+        "search_query: A man is eating food.",
+        "search_query: A man is eating a piece of bread.",
+        "search_query: The stock market fell sharply today.",
+    ];
+    let embeddings = model.encode_as_tensor(&sentences)?.embeddings;
+    let similarity = |left: i64, right: i64| {
+        embeddings
+            .get(left)
+            .cosine_similarity(&embeddings.get(right), 0, 1e-8)
+            .double_value(&[])
+    };
+
+    assert_eq!(embeddings.size(), vec![3, 768]);
+    assert!(similarity(0, 1) > similarity(0, 2));
+    assert!(similarity(0, 1) > similarity(1, 2));
+
+    Ok(())
+}
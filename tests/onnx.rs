@@ -9,6 +9,9 @@ mod tests {
     use rust_bert::pipelines::question_answering::{
         QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
     };
+    use rust_bert::pipelines::sentence_embeddings::{
+        SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+    };
     use rust_bert::pipelines::sentiment::{SentimentModel, SentimentPolarity};
     use rust_bert::pipelines::sequence_classification::SequenceClassificationConfig;
     use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
@@ -292,4 +295,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn onnx_quantized_sentence_embeddings() -> anyhow::Result<()> {
+        let torch_model =
+            SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL6V2)
+                .create_model()?;
+        let onnx_model =
+            SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL6V2)
+                .onnx_model(RemoteResource::new(
+                    "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/onnx/model_qint8_avx512.onnx",
+                    "onnx-all-mini-lm-l6-v2-qint8",
+                ))
+                .create_model()?;
+
+        let sentences = ["This is an example sentence", "Each sentence is converted"];
+        let torch_embeddings = torch_model.encode(&sentences)?;
+        let onnx_embeddings = onnx_model.encode(&sentences)?;
+
+        assert_eq!(onnx_embeddings.len(), 2);
+        assert_eq!(onnx_embeddings[0].len(), 384);
+        for (torch_embedding, onnx_embedding) in torch_embeddings.iter().zip(onnx_embeddings.iter())
+        {
+            // Both embeddings are normalized, the dot product is their cosine similarity
+            let similarity: f32 = torch_embedding
+                .iter()
+                .zip(onnx_embedding.iter())
+                .map(|(a, b)| a * b)
+                .sum();
+            assert!(similarity > 0.98);
+        }
+        assert!(onnx_model.encode_with_attention(&sentences).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,137 @@
+mod common;
+
+use rust_bert::opt::{
+    OptConfig, OptConfigResources, OptForCausalLM, OptMergesResources, OptModel, OptModelResources,
+    OptVocabResources,
+};
+use rust_bert::pipelines::generation_utils::Cache;
+use rust_bert::resources::{load_weights, RemoteResource, ResourceProvider};
+use rust_bert::Config;
+use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn opt_left_padding() -> anyhow::Result<()> {
+    // Positions are counted from the first non-padding token: left padding does not change the outputs
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let model = OptModel::new(vs.root(), &common::tiny_opt_config());
+
+    let input_ids = Tensor::randint(100, [1, 5], (Kind::Int64, device));
+    let padded_input_ids = Tensor::cat(
+        &[
+            Tensor::full([1, 3], 1, (Kind::Int64, device)),
+            input_ids.copy(),
+        ],
+        1,
+    );
+    let attention_mask = Tensor::cat(
+        &[
+            Tensor::zeros([1, 3], (Kind::Int64, device)),
+            Tensor::ones([1, 5], (Kind::Int64, device)),
+        ],
+        1,
+    );
+
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    let padded_output = no_grad(|| {
+        model.forward_t(
+            Some(&padded_input_ids),
+            None,
+            Some(&attention_mask),
+            None,
+            None,
+            false,
+        )
+    })?;
+
+    let max_difference = (output.output - padded_output.output.narrow(1, 3, 5))
+        .abs()
+        .max()
+        .double_value(&[]);
+    assert!(max_difference < 1e-4);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn opt_lm() -> anyhow::Result<()> {
+    //    Resources paths
+    let config_resource = RemoteResource::from_pretrained(OptConfigResources::OPT_125M);
+    let vocab_resource = RemoteResource::from_pretrained(OptVocabResources::OPT_125M);
+    let merges_resource = RemoteResource::from_pretrained(OptMergesResources::OPT_125M);
+    let weights_resource = RemoteResource::from_pretrained(OptModelResources::OPT_125M);
+    let config_path = config_resource.get_local_path()?;
+    let vocab_path = vocab_resource.get_local_path()?;
+    let merges_path = merges_resource.get_local_path()?;
+
+    //    Set-up model
+    let device = Device::Cpu;
+    let mut vs = nn::VarStore::new(device);
+    let tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_file(
+        vocab_path.to_str().unwrap(),
+        merges_path.to_str().unwrap(),
+        false,
+    )?;
+    let config = OptConfig::from_file(config_path);
+    let opt_model = OptForCausalLM::new(vs.root(), &config);
+    load_weights(&weights_resource, &mut vs)?;
+
+    //    Define input
+    let input = ["Monday, Tuesday, Wednesday, Thursday,"];
+    let tokenized_input = tokenizer.encode_list(&input, 128, &TruncationStrategy::LongestFirst, 0);
+    // OPT inputs start with the `</s>` BOS token
+    let mut token_ids = vec![config.bos_token_id.unwrap_or(2)];
+    token_ids.extend(&tokenized_input[0].token_ids);
+    let input_length = token_ids.len() as i64;
+    let input_tensor = Tensor::from_slice(&token_ids).unsqueeze(0).to(device);
+
+    //    Forward pass
+    let model_output =
+        no_grad(|| opt_model.forward_t(Some(&input_tensor), Cache::None, None, None, None, false))?;
+
+    let next_word_id = model_output
+        .lm_logits
+        .get(0)
+        .get(-1)
+        .argmax(-1, true)
+        .int64_value(&[0]);
+    let next_word = tokenizer.decode(&[next_word_id], true, true);
+
+    // Output
+    assert_eq!(
+        model_output.lm_logits.size(),
+        vec!(1, input_length, config.vocab_size)
+    );
+    assert_eq!(next_word, String::from(" Friday"));
+
+    // Cached generation step matches the pretrained logits of the full sequence
+    let prefix_output = no_grad(|| {
+        opt_model.forward_t(
+            Some(&input_tensor.narrow(1, 0, input_length - 1)),
+            Cache::None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let step_output = no_grad(|| {
+        opt_model.forward_t(
+            Some(&input_tensor.narrow(1, input_length - 1, 1)),
+            prefix_output.cache,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let max_difference = (step_output.lm_logits.select(1, 0)
+        - model_output.lm_logits.select(1, input_length - 1))
+    .abs()
+    .max()
+    .double_value(&[]);
+    assert!(max_difference < 1e-3);
+    Ok(())
+}
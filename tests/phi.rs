@@ -0,0 +1,91 @@
+use rust_bert::phi::{
+    PhiConfig, PhiConfigResources, PhiForCausalLM, PhiMergesResources, PhiModelResources,
+    PhiVocabResources,
+};
+use rust_bert::pipelines::generation_utils::Cache;
+use rust_bert::resources::{load_weights, RemoteResource, ResourceProvider};
+use rust_bert::Config;
+use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
+use tch::{nn, no_grad, Device, Tensor};
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn phi_lm() -> anyhow::Result<()> {
+    //    Resources paths
+    let config_resource = RemoteResource::from_pretrained(PhiConfigResources::PHI_1_5);
+    let vocab_resource = RemoteResource::from_pretrained(PhiVocabResources::PHI_1_5);
+    let merges_resource = RemoteResource::from_pretrained(PhiMergesResources::PHI_1_5);
+    let weights_resource = RemoteResource::from_pretrained(PhiModelResources::PHI_1_5);
+    let config_path = config_resource.get_local_path()?;
+    let vocab_path = vocab_resource.get_local_path()?;
+    let merges_path = merges_resource.get_local_path()?;
+
+    //    Set-up model
+    let device = Device::Cpu;
+    let mut vs = nn::VarStore::new(device);
+    let tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_file(
+        vocab_path.to_str().unwrap(),
+        merges_path.to_str().unwrap(),
+        false,
+    )?;
+    let config = PhiConfig::from_file(config_path);
+    // Rotary embeddings applied to half of the attention head dimensions
+    assert_eq!(config.partial_rotary_factor, Some(0.5));
+    let phi_model = PhiForCausalLM::new(vs.root(), &config);
+    load_weights(&weights_resource, &mut vs)?;
+
+    //    Define input
+    let input = ["The capital of France is"];
+    let tokenized_input = tokenizer.encode_list(&input, 128, &TruncationStrategy::LongestFirst, 0);
+    let token_ids = &tokenized_input[0].token_ids;
+    let input_length = token_ids.len() as i64;
+    let input_tensor = Tensor::from_slice(token_ids).unsqueeze(0).to(device);
+
+    //    Forward pass
+    let model_output =
+        no_grad(|| phi_model.forward_t(Some(&input_tensor), Cache::None, None, None, None, false))?;
+
+    let next_word_id = model_output
+        .lm_logits
+        .get(0)
+        .get(-1)
+        .argmax(-1, true)
+        .int64_value(&[0]);
+    let next_word = tokenizer.decode(&[next_word_id], true, true);
+
+    // Output
+    assert_eq!(
+        model_output.lm_logits.size(),
+        vec!(1, input_length, config.vocab_size)
+    );
+    assert_eq!(next_word, String::from(" Paris"));
+
+    // Cached generation step matches the pretrained logits of the full sequence
+    let prefix_output = no_grad(|| {
+        phi_model.forward_t(
+            Some(&input_tensor.narrow(1, 0, input_length - 1)),
+            Cache::None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let step_output = no_grad(|| {
+        phi_model.forward_t(
+            Some(&input_tensor.narrow(1, input_length - 1, 1)),
+            prefix_output.cache,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let max_difference = (step_output.lm_logits.select(1, 0)
+        - model_output.lm_logits.select(1, input_length - 1))
+    .abs()
+    .max()
+    .double_value(&[]);
+    assert!(max_difference < 1e-3);
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use rust_bert::pipelines::pii::{
+    PiiEntityType, PiiRedactionConfig, PiiRedactionModel, PiiSource, RedactionStrategy,
+    RegexDetector,
+};
+
+#[test]
+fn pii_regex_redaction() -> anyhow::Result<()> {
+    //    Set-up model without NER
+    let pii_model = PiiRedactionModel::new(PiiRedactionConfig::new(
+        None,
+        RegexDetector::default_detectors(),
+    ))?;
+
+    //    Define input
+    let input = [
+        "Contact me at amy.smith@example.com or +1 415 555 0132, from 192.168.0.1.",
+        "Card 4111 1111 1111 1111, SSN 123-45-6789, meeting on 2023-06-03.",
+        "Invalid card 4111 1111 1111 1112.",
+    ];
+
+    //    Run model
+    let output = pii_model.redact(&input);
+
+    assert_eq!(output.len(), 3);
+    assert_eq!(
+        output[0].text,
+        "Contact me at [EMAIL] or [PHONE_NUMBER], from [IP_ADDRESS]."
+    );
+    assert_eq!(output[0].spans.len(), 3);
+    assert_eq!(output[0].spans[0].entity_type, PiiEntityType::Email);
+    assert_eq!(output[0].spans[0].text, "amy.smith@example.com");
+    assert_eq!(output[0].spans[0].offset.begin, 14);
+    assert_eq!(output[0].spans[0].offset.end, 35);
+    assert_eq!(output[0].spans[0].source, PiiSource::Regex);
+
+    assert_eq!(
+        output[1].text,
+        "Card [CREDIT_CARD], SSN [SSN], meeting on 2023-06-03."
+    );
+    assert_eq!(output[2].text, "Invalid card 4111 1111 1111 1112.");
+
+    Ok(())
+}
+
+#[test]
+fn pii_custom_detector_masking() -> anyhow::Result<()> {
+    let mut config = PiiRedactionConfig::new(
+        None,
+        vec![RegexDetector::new(
+            PiiEntityType::Custom("EMPLOYEE_ID".to_string()),
+            r"\bEMP-\d{6}\b",
+        )?],
+    );
+    config.redaction_strategy = RedactionStrategy::Mask('*');
+    let pii_model = PiiRedactionModel::new(config)?;
+
+    let output = pii_model.redact(&["Employé EMP-123456 arrivé."]);
+    assert_eq!(output[0].text, "Employé ********** arrivé.");
+    assert_eq!(output[0].spans[0].offset.begin, 8);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use rust_bert::placement::{
+    AutoPlacementConfig, DeviceMemory, MemoryInfo, OffloadStrategy, Placement,
+};
+use tch::{Device, Kind};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+fn memory_info(cuda_free_gib: &[u64], ram_gib: u64) -> MemoryInfo {
+    MemoryInfo {
+        cuda_devices: cuda_free_gib
+            .iter()
+            .enumerate()
+            .map(|(index, free_gib)| DeviceMemory {
+                index,
+                free_bytes: Some(free_gib * GIB),
+            })
+            .collect(),
+        available_ram_bytes: Some(ram_gib * GIB),
+    }
+}
+
+#[test]
+fn placement_selects_largest_cuda_device() -> anyhow::Result<()> {
+    let placement = Placement::select(
+        4 * GIB,
+        &memory_info(&[2, 8, 6], 32),
+        &AutoPlacementConfig::default(),
+    )?;
+    assert_eq!(placement.device, Device::Cuda(1));
+    assert_eq!(placement.kind, Kind::Float);
+    assert_eq!(placement.offload, OffloadStrategy::None);
+    assert_eq!(placement.load_device(), Device::Cuda(1));
+    Ok(())
+}
+
+#[test]
+fn placement_falls_back_to_half_precision() -> anyhow::Result<()> {
+    let placement = Placement::select(
+        8 * GIB,
+        &memory_info(&[6], 32),
+        &AutoPlacementConfig::default(),
+    )?;
+    assert_eq!(placement.device, Device::Cuda(0));
+    assert_eq!(placement.kind, Kind::Half);
+    assert_eq!(placement.offload, OffloadStrategy::StageOnCpu);
+    assert_eq!(placement.load_device(), Device::Cpu);
+    Ok(())
+}
+
+#[test]
+fn placement_falls_back_to_cpu() -> anyhow::Result<()> {
+    let config = AutoPlacementConfig {
+        allow_half: false,
+        ..Default::default()
+    };
+    let placement = Placement::select(8 * GIB, &memory_info(&[6], 32), &config)?;
+    assert_eq!(placement.device, Device::Cpu);
+    assert_eq!(placement.kind, Kind::Float);
+    assert_eq!(placement.offload, OffloadStrategy::Cpu);
+
+    let placement = Placement::select(4 * GIB, &memory_info(&[], 32), &config)?;
+    assert_eq!(placement.device, Device::Cpu);
+    Ok(())
+}
+
+#[test]
+fn placement_insufficient_memory() {
+    assert!(Placement::select(
+        64 * GIB,
+        &memory_info(&[16], 32),
+        &AutoPlacementConfig::default()
+    )
+    .is_err());
+
+    let config = AutoPlacementConfig {
+        allow_cpu: false,
+        ..Default::default()
+    };
+    assert!(Placement::select(4 * GIB, &memory_info(&[], 32), &config).is_err());
+}
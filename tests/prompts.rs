@@ -0,0 +1,65 @@
+use rust_bert::pipelines::prompts::{FewShotPromptTemplate, PromptFormat, PromptTemplate};
+
+#[test]
+fn prompt_template_formatting() -> anyhow::Result<()> {
+    let template = PromptTemplate::new("Translate to {language}: {text} {{literal}}")?;
+    assert_eq!(template.variables(), ["language", "text"]);
+    assert_eq!(
+        template.format(&[("text", "Hello"), ("language", "French")])?,
+        "Translate to French: Hello {literal}"
+    );
+
+    assert!(template.format(&[("language", "French")]).is_err());
+    assert!(template
+        .format(&[
+            ("language", "French"),
+            ("text", "Hello"),
+            ("tone", "formal")
+        ])
+        .is_err());
+    assert!(PromptTemplate::new("Unbalanced {language").is_err());
+    assert!(PromptTemplate::new("Empty {}").is_err());
+
+    let zero_shot_template =
+        PromptTemplate::new("This review is {label}.")?.to_zero_shot_template()?;
+    assert_eq!(zero_shot_template("positive"), "This review is positive.");
+    assert!(template.to_zero_shot_template().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn few_shot_prompt_template_formatting() -> anyhow::Result<()> {
+    let prompt = FewShotPromptTemplate::new(
+        PromptTemplate::new("Word: {word}\nAntonym: {antonym}")?,
+        PromptTemplate::new("Word: {word}\nAntonym:")?,
+    )
+    .with_prefix("Give the antonym of every input.")
+    .with_separator("\n")
+    .with_example(&[("word", "happy"), ("antonym", "sad")])?
+    .with_example(&[("word", "tall"), ("antonym", "short")])?;
+
+    assert_eq!(prompt.num_examples(), 2);
+    assert_eq!(
+        prompt.format(&[("word", "big")])?,
+        "Give the antonym of every input.\nWord: happy\nAntonym: sad\nWord: tall\nAntonym: short\nWord: big\nAntonym:"
+    );
+    assert_eq!(
+        prompt
+            .format_batch(&[vec![("word", "big")], vec![("word", "fast")]])?
+            .len(),
+        2
+    );
+
+    Ok(())
+}
+
+#[test]
+fn prompt_formats() {
+    assert_eq!(
+        PromptFormat::ChatML.apply(Some("You are helpful."), "Hi"),
+        "<|im_start|>system\nYou are helpful.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\n"
+    );
+    assert_eq!(PromptFormat::Mistral.apply(None, "Hi"), "[INST] Hi [/INST]");
+    assert_eq!(PromptFormat::Plain.apply(None, "Hi"), "Hi");
+}
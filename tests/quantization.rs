@@ -0,0 +1,153 @@
+use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+use rust_bert::distilbert::{DistilBertConfig, DistilBertModelClassifier};
+use rust_bert::pruning::PruningConfig;
+use rust_bert::quantization::{Int4Linear, Int4Weight, Int8Linear};
+use std::collections::HashMap;
+use tch::nn::{Linear, Module};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn int4_weight_round_trip() -> anyhow::Result<()> {
+    let weight = Tensor::randn([16, 256], (Kind::Float, Device::Cpu));
+    let quantized = Int4Weight::quantize(&weight, 64)?;
+
+    assert_eq!(quantized.packed.size(), [16, 128]);
+    assert_eq!(quantized.packed.kind(), Kind::Uint8);
+    assert_eq!(quantized.scales.size(), [16, 4]);
+    assert_eq!(quantized.size_in_bytes(), 16 * 128 + 2 * 16 * 4 * 4);
+
+    let reconstructed = quantized.dequantize();
+    assert_eq!(reconstructed.size(), weight.size());
+    assert_eq!(reconstructed.kind(), Kind::Float);
+    // The reconstruction error is at most half a quantization step
+    let max_error = (&reconstructed - &weight).abs().max().double_value(&[]);
+    let max_scale = quantized.scales.max().double_value(&[]);
+    assert!(max_error <= max_scale / 2.0 + 1e-5);
+
+    assert!(Int4Weight::quantize(&weight, 48).is_err());
+    assert!(Int4Weight::quantize(&weight, 7).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn int4_linear_forward() -> anyhow::Result<()> {
+    let linear = Linear {
+        ws: Tensor::randn([8, 128], (Kind::Float, Device::Cpu)),
+        bs: Some(Tensor::randn([8], (Kind::Float, Device::Cpu))),
+    };
+    let input = Tensor::randn([2, 3, 128], (Kind::Float, Device::Cpu));
+    let expected = linear.forward(&input);
+    let weight = linear.ws.shallow_clone();
+
+    let quantized_linear = Int4Linear::from_linear(linear, 32)?;
+    let output = quantized_linear.forward(&input);
+
+    assert_eq!(output.size(), [2, 3, 8]);
+    // The storage of the original weight is released
+    assert_eq!(weight.numel(), 0);
+    let relative_error =
+        (&output - &expected).norm().double_value(&[]) / expected.norm().double_value(&[]);
+    assert!(relative_error < 0.2);
+
+    Ok(())
+}
+
+#[test]
+fn int8_linear_forward() -> anyhow::Result<()> {
+    let linear = Linear {
+        ws: Tensor::randn([8, 128], (Kind::Float, Device::Cpu)),
+        bs: Some(Tensor::randn([8], (Kind::Float, Device::Cpu))),
+    };
+    let input = Tensor::randn([2, 3, 128], (Kind::Float, Device::Cpu));
+    let expected = linear.forward(&input);
+    let weight = linear.ws.shallow_clone();
+
+    let quantized_linear = Int8Linear::from_linear(linear)?;
+    let output = quantized_linear.forward(&input);
+
+    assert_eq!(output.size(), [2, 3, 8]);
+    assert_eq!(output.kind(), Kind::Float);
+    assert_eq!(weight.numel(), 0);
+    assert!(quantized_linear.size_in_bytes() < 8 * 128 * 4);
+    let relative_error =
+        (&output - &expected).norm().double_value(&[]) / expected.norm().double_value(&[]);
+    assert!(relative_error < 0.05);
+
+    Ok(())
+}
+
+#[test]
+fn bert_dynamic_quantization() -> anyhow::Result<()> {
+    let config = BertConfig {
+        hidden_size: 32,
+        intermediate_size: 64,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        vocab_size: 100,
+        id2label: Some(HashMap::from([
+            (0, "NEGATIVE".to_string()),
+            (1, "POSITIVE".to_string()),
+        ])),
+        ..Default::default()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let mut model = BertForSequenceClassification::new(vs.root(), &config)?;
+    let input_ids = Tensor::from_slice2(&[[2i64, 15, 27, 3], [2, 41, 3, 0]]);
+    let mask = Tensor::from_slice2(&[[1i64, 1, 1, 1], [1, 1, 1, 0]]);
+    let forward = |model: &BertForSequenceClassification| {
+        no_grad(|| {
+            model
+                .forward_t(Some(&input_ids), Some(&mask), None, None, None, false)
+                .logits
+        })
+    };
+    let expected = forward(&model);
+
+    model.quantize_dynamic()?;
+    let logits = forward(&model);
+
+    assert_eq!(logits.size(), [2, 2]);
+    let max_error = (&logits - &expected).abs().max().double_value(&[]);
+    assert!(max_error < 0.1);
+    // Quantized layers can no longer be rewritten in place
+    let pruning_config = PruningConfig {
+        heads: HashMap::from([(0, vec![1])]),
+        intermediate_neurons: HashMap::new(),
+    };
+    assert!(model.prune(&pruning_config).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_dynamic_quantization() -> anyhow::Result<()> {
+    let config = DistilBertConfig {
+        dim: 32,
+        hidden_dim: 64,
+        n_heads: 4,
+        n_layers: 2,
+        vocab_size: 100,
+        id2label: Some(HashMap::from([
+            (0, "NEGATIVE".to_string()),
+            (1, "POSITIVE".to_string()),
+        ])),
+        ..Default::default()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let mut model = DistilBertModelClassifier::new(vs.root(), &config)?;
+    let input_ids = Tensor::from_slice2(&[[2i64, 15, 27, 3], [2, 41, 3, 0]]);
+    let mask = Tensor::from_slice2(&[[1i64, 1, 1, 1], [1, 1, 1, 0]]);
+    let forward = |model: &DistilBertModelClassifier| -> anyhow::Result<Tensor> {
+        Ok(no_grad(|| model.forward_t(Some(&input_ids), Some(&mask), None, false))?.logits)
+    };
+    let expected = forward(&model)?;
+
+    model.quantize_dynamic()?;
+    let logits = forward(&model)?;
+
+    assert_eq!(logits.size(), [2, 2]);
+    let max_error = (&logits - &expected).abs().max().double_value(&[]);
+    assert!(max_error < 0.1);
+    Ok(())
+}
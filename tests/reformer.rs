@@ -73,6 +73,40 @@ fn test_generation_reformer() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_contrastive_search_reformer() -> anyhow::Result<()> {
+    //    Set-up model
+    let generation_config = TextGenerationConfig {
+        model_type: ModelType::Reformer,
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            ReformerModelResources::CRIME_AND_PUNISHMENT,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(
+            ReformerConfigResources::CRIME_AND_PUNISHMENT,
+        )),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(
+            ReformerVocabResources::CRIME_AND_PUNISHMENT,
+        )),
+        merges_resource: None,
+        max_length: Some(32),
+        do_sample: false,
+        num_beams: 1,
+        top_k: 4,
+        penalty_alpha: Some(0.6),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generation_config)?;
+
+    let input_context = "The really great men must, I think,";
+    let output = model.generate(&[input_context], None);
+
+    assert_eq!(output.len(), 1);
+    assert!(output[0].len() > input_context.len() + 1);
+
+    Ok(())
+}
+
 #[test]
 fn reformer_for_sequence_classification() -> anyhow::Result<()> {
     //    Resources paths
@@ -0,0 +1,101 @@
+use rust_bert::pipelines::resume_extraction::{
+    ExtractionSource, FieldType, NormalizationDictionary, ResumeExtractionConfig,
+    ResumeExtractionModel, SectionKind, SectionSegmenter,
+};
+
+#[test]
+fn resume_section_segmentation() {
+    let resume = "Amy Smith\namy@example.com\n\n## Experience\nSenior SWE at Acme\n\nSkills: Rust, k8s\nEducation:\n\nMSc, ETH Zurich\n";
+    let sections = SectionSegmenter::default().segment(resume);
+
+    let kinds = sections
+        .iter()
+        .map(|section| section.kind.clone())
+        .collect::<Vec<SectionKind>>();
+    assert_eq!(
+        kinds,
+        vec![
+            SectionKind::Header,
+            SectionKind::Experience,
+            SectionKind::Skills,
+            SectionKind::Education
+        ]
+    );
+    assert_eq!(sections[0].heading, None);
+    assert_eq!(sections[0].text, "Amy Smith\namy@example.com");
+    assert_eq!(sections[2].heading.as_deref(), Some("Skills"));
+    assert_eq!(sections[2].text, "Rust, k8s");
+    assert_eq!(sections[3].text, "MSc, ETH Zurich");
+    let characters = resume.chars().collect::<Vec<char>>();
+    for section in &sections {
+        let text = characters[section.offset.begin as usize..section.offset.end as usize]
+            .iter()
+            .collect::<String>();
+        assert_eq!(text, section.text);
+    }
+}
+
+#[test]
+fn resume_rule_based_extraction() -> anyhow::Result<()> {
+    //    Set-up model without NER
+    let mut config = ResumeExtractionConfig::new(None);
+    config.skills.add("Tokio", &["tokio-rs"]);
+    let extraction_model = ResumeExtractionModel::new(config)?;
+
+    //    Define input
+    let input = [
+        "Amy Smith\namy@example.com · +1 415 555 0132\n\nExperience\nSenior SWE at Acme (k8s, C++)\n\nSkills: Rust, tokio-rs, Kubernetes\n\nEducation\nMSc in Computer Science",
+        "About the role\nWe are hiring an ML engineer.\nRequirements\n3+ years of Python and AWS.",
+    ];
+
+    //    Run model
+    let output = extraction_model.extract(&input);
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(
+        output[0].normalized_values(&FieldType::Skill),
+        vec!["Kubernetes", "C++", "Rust", "Tokio"]
+    );
+    assert_eq!(
+        output[0].normalized_values(&FieldType::JobTitle),
+        vec!["Senior Software Engineer"]
+    );
+    assert_eq!(
+        output[0].normalized_values(&FieldType::Degree),
+        vec!["Master"]
+    );
+    let email = output[0].fields_of_type(&FieldType::Email).next().unwrap();
+    assert_eq!(email.text, "amy@example.com");
+    assert_eq!(email.offset.begin, 10);
+    assert_eq!(email.section, SectionKind::Header);
+    assert_eq!(email.source, ExtractionSource::Regex);
+    assert_eq!(output[0].fields_of_type(&FieldType::PhoneNumber).count(), 1);
+
+    let title = output[1]
+        .fields_of_type(&FieldType::JobTitle)
+        .next()
+        .unwrap();
+    assert_eq!(title.text, "ML engineer");
+    assert_eq!(title.section, SectionKind::Summary);
+    assert_eq!(
+        output[1].section(&SectionKind::Requirements).unwrap().text,
+        "3+ years of Python and AWS."
+    );
+    assert_eq!(
+        output[1].normalized_values(&FieldType::Skill),
+        vec!["Python", "Amazon Web Services"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn normalization_dictionary() {
+    let mut dictionary = NormalizationDictionary::new();
+    dictionary.add("Kubernetes", &["k8s"]);
+    assert_eq!(dictionary.normalize(" K8S "), Some("Kubernetes"));
+    assert_eq!(dictionary.normalize("kubernetes"), Some("Kubernetes"));
+    assert_eq!(dictionary.normalize("docker"), None);
+    // Matches within words are ignored
+    assert!(dictionary.find_all("k8sx, xk8s").is_empty());
+}
@@ -0,0 +1,136 @@
+use rust_bert::resources::{load_weights, load_weights_from_file, BufferResource, LocalResource};
+use tch::nn::{Init, VarStore};
+use tch::{Device, Kind, Tensor};
+
+fn var_store() -> VarStore {
+    let vs = VarStore::new(Device::Cpu);
+    let root = vs.root();
+    let _ = (&root / "layer").var("weight", &[4, 3], Init::Const(0.));
+    let _ = (&root / "layer_norm").var("weight", &[4], Init::Const(0.));
+    let _ = (&root / "layer_norm").var("bias", &[4], Init::Const(0.));
+    vs
+}
+
+/// Checkpoint of a model with a head (`bert.` prefix), in half precision and with the legacy layer normalization
+/// names, as found on the Hugging Face Hub
+fn checkpoint_tensors() -> Vec<(String, Tensor)> {
+    vec![
+        (
+            "bert.layer.weight".to_string(),
+            Tensor::randn([4, 3], (Kind::Float, Device::Cpu)).to_kind(Kind::Half),
+        ),
+        (
+            "bert.layer_norm.gamma".to_string(),
+            Tensor::randn([4], (Kind::Float, Device::Cpu)).to_kind(Kind::Half),
+        ),
+        (
+            "bert.layer_norm.beta".to_string(),
+            Tensor::randn([4], (Kind::Float, Device::Cpu)).to_kind(Kind::Half),
+        ),
+        (
+            "classifier.weight".to_string(),
+            Tensor::randn([2, 4], (Kind::Float, Device::Cpu)).to_kind(Kind::Half),
+        ),
+    ]
+}
+
+fn assert_loaded(vs: &VarStore, tensors: &[(String, Tensor)]) {
+    let variables = vs.variables();
+    for (variable_name, tensor_name) in [
+        ("layer.weight", "bert.layer.weight"),
+        ("layer_norm.weight", "bert.layer_norm.gamma"),
+        ("layer_norm.bias", "bert.layer_norm.beta"),
+    ] {
+        let variable = &variables[variable_name];
+        let (_, tensor) = tensors
+            .iter()
+            .find(|(name, _)| name == tensor_name)
+            .unwrap();
+        assert_eq!(variable.kind(), Kind::Float);
+        assert!(variable.equal(&tensor.to_kind(Kind::Float)));
+    }
+}
+
+#[test]
+fn safetensors_weights_loading() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let tensors = checkpoint_tensors();
+    let path = dir.path().join("model.safetensors");
+    Tensor::write_safetensors(&tensors, &path)?;
+
+    let mut vs = var_store();
+    load_weights_from_file(&path, &mut vs)?;
+    assert_loaded(&vs, &tensors);
+
+    // Cached remote resources have no extension: the format is detected from the content
+    let cached_path = dir.path().join("5c2d0c8e7a41");
+    std::fs::copy(&path, &cached_path)?;
+    let mut vs = var_store();
+    load_weights(
+        &LocalResource {
+            local_path: cached_path,
+        },
+        &mut vs,
+    )?;
+    assert_loaded(&vs, &tensors);
+
+    let mut vs = var_store();
+    load_weights(&BufferResource::from(std::fs::read(&path)?), &mut vs)?;
+    assert_loaded(&vs, &tensors);
+
+    Ok(())
+}
+
+#[test]
+fn safetensors_missing_and_mismatched_weights() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.safetensors");
+
+    let tensors = checkpoint_tensors()
+        .into_iter()
+        .filter(|(name, _)| name != "bert.layer_norm.beta")
+        .collect::<Vec<(String, Tensor)>>();
+    Tensor::write_safetensors(&tensors, &path)?;
+    let error = load_weights_from_file(&path, &mut var_store()).unwrap_err();
+    assert!(error.to_string().contains("layer_norm.bias"));
+
+    let mut tensors = checkpoint_tensors();
+    tensors[0].1 = Tensor::randn([3, 4], (Kind::Float, Device::Cpu));
+    Tensor::write_safetensors(&tensors, &path)?;
+    let error = load_weights_from_file(&path, &mut var_store()).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Shape mismatch for layer.weight"));
+
+    Ok(())
+}
+
+#[test]
+fn safetensors_base_model_weights_loading() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.safetensors");
+    // Checkpoint saved from the base model, loaded in a model with a head
+    let tensors = vec![
+        (
+            "h.0.weight".to_string(),
+            Tensor::randn([4, 3], (Kind::Float, Device::Cpu)),
+        ),
+        (
+            "ln_f.weight".to_string(),
+            Tensor::randn([4], (Kind::Float, Device::Cpu)),
+        ),
+    ];
+    Tensor::write_safetensors(&tensors, &path)?;
+
+    let mut vs = VarStore::new(Device::Cpu);
+    let transformer = vs.root() / "transformer";
+    let _ = (&transformer / "h" / "0").var("weight", &[4, 3], Init::Const(0.));
+    let _ = (&transformer / "ln_f").var("weight", &[4], Init::Const(0.));
+    load_weights_from_file(&path, &mut vs)?;
+
+    let variables = vs.variables();
+    assert!(variables["transformer.h.0.weight"].equal(&tensors[0].1));
+    assert!(variables["transformer.ln_f.weight"].equal(&tensors[1].1));
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use rust_bert::gpt_neo::{GptNeoConfig, GptNeoForCausalLM};
+use rust_bert::scratch;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+fn generate_logits(model: &GptNeoForCausalLM, input: &Tensor) -> anyhow::Result<Vec<Tensor>> {
+    let prompt_output = model.forward_t(Some(input), None, None, None, None, None, false)?;
+    let next_token = prompt_output.lm_logits.select(1, -1).argmax(-1, true);
+    let step_output = model.forward_t(
+        Some(&next_token),
+        None,
+        None,
+        None,
+        prompt_output.next_cache,
+        None,
+        false,
+    )?;
+    Ok(vec![prompt_output.lm_logits, step_output.lm_logits])
+}
+
+#[test]
+fn scratch_buffers_attention() -> anyhow::Result<()> {
+    let mut config = GptNeoConfig {
+        num_layers: 2,
+        num_heads: 4,
+        hidden_size: 64,
+        vocab_size: 100,
+        max_position_embeddings: 32,
+        window_size: 4,
+        ..Default::default()
+    };
+    config.attention_layers.truncate(2);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = GptNeoForCausalLM::new(vs.root(), &config)?;
+    let input = Tensor::randint(100, [2, 7], (Kind::Int64, Device::Cpu));
+
+    scratch::set_enabled(false);
+    let expected = no_grad(|| generate_logits(&model, &input))?;
+    assert_eq!(scratch::allocated_bytes(), 0);
+
+    scratch::set_enabled(true);
+    let output = no_grad(|| generate_logits(&model, &input))?;
+    assert!(scratch::allocated_bytes() > 0);
+    for (output, expected) in output.iter().zip(expected.iter()) {
+        assert!(output.allclose(expected, 1e-5, 1e-5, false));
+    }
+
+    scratch::clear();
+    assert_eq!(scratch::allocated_bytes(), 0);
+    Ok(())
+}
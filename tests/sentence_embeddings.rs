@@ -1,6 +1,8 @@
 use rust_bert::pipelines::keywords_extraction::{
     KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType,
 };
+use rust_bert::pipelines::pii::{PiiRedactionConfig, PiiRedactionModel, RegexDetector};
+use rust_bert::pipelines::sentence_embeddings::sharding::ShardedEncodingCoordinator;
 use rust_bert::pipelines::sentence_embeddings::{
     SentenceEmbeddingsBuilder, SentenceEmbeddingsConfig, SentenceEmbeddingsModelType,
 };
@@ -83,6 +85,42 @@ fn sbert_bert_small() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn sbert_pii_redaction() -> anyhow::Result<()> {
+    let mut model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+        .create_model()?;
+    let reference = model.encode(&["contact me at [EMAIL] for details"])?;
+
+    let pii_model = PiiRedactionModel::new(PiiRedactionConfig::new(
+        None,
+        RegexDetector::default_detectors(),
+    ))?;
+    model.set_pii_redaction_model(Some(pii_model));
+    assert!(model.get_pii_redaction_model().is_some());
+
+    let sentences = [
+        "contact me at amy.smith@example.com for details",
+        "contact me at john.doe@example.org for details",
+    ];
+    let embeddings = model.encode(&sentences)?;
+
+    // The identifiers do not affect the embeddings
+    for embedding in embeddings.iter() {
+        assert!(embedding
+            .iter()
+            .zip(reference[0].iter())
+            .all(|(value, reference_value)| (value - reference_value).abs() < 1e-5));
+    }
+
+    model.set_pii_redaction_model(None);
+    let embeddings = model.encode(&sentences)?;
+    assert!(embeddings[0]
+        .iter()
+        .zip(embeddings[1].iter())
+        .any(|(first, second)| (first - second).abs() > 1e-4));
+    Ok(())
+}
+
 #[test]
 fn sbert_distilroberta() -> anyhow::Result<()> {
     let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllDistilrobertaV1)
@@ -356,3 +394,81 @@ fn keyword_extraction_cosine_similarity_n_grams() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn keyword_extraction_pos_filtering_seed_keywords() -> anyhow::Result<()> {
+    let seed_keywords: &[&str] = &["memory management", "compiler"];
+    let keyword_extraction_config = KeywordExtractionConfig {
+        sentence_embeddings_config: SentenceEmbeddingsConfig::from(
+            SentenceEmbeddingsModelType::AllMiniLmL6V2,
+        ),
+        scorer_type: KeywordScorerType::MaximalMarginRelevance,
+        ngram_range: (1, 2),
+        num_keywords: 5,
+        diversity: Some(0.3),
+        pos_config: Some(Default::default()),
+        seed_keywords: Some(seed_keywords),
+        ..Default::default()
+    };
+
+    let keyword_extraction_model = KeywordExtractionModel::new(keyword_extraction_config)?;
+
+    let input = [
+        "Rust is a multi-paradigm, general-purpose programming language. \
+ Rust emphasizes performance, type safety, and concurrency. Rust enforces memory safety—that is, \
+ that all references point to valid memory—without requiring the use of a garbage collector or \
+ reference counting present in other memory-safe languages.",
+    ];
+
+    let keywords = keyword_extraction_model.predict(&input)?;
+
+    assert_eq!(keywords.len(), 1);
+    assert_eq!(keywords[0].len(), 5);
+    // Candidates containing verbs are filtered by the default noun phrase pattern
+    assert!(keywords[0]
+        .iter()
+        .all(|keyword| !keyword.text.contains("enforces") && !keyword.text.contains("emphasizes")));
+
+    Ok(())
+}
+
+#[test]
+fn sbert_sharded_encoding() -> anyhow::Result<()> {
+    let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+        .create_model()?;
+
+    let corpus = [
+        "This is an example sentence",
+        "Each sentence is converted",
+        "The corpus is split in shards",
+        "Shards are encoded by workers",
+        "The shards are merged once encoded",
+    ];
+    let output_dir = tempfile::tempdir()?;
+    let coordinator = ShardedEncodingCoordinator::create(
+        output_dir.path(),
+        corpus.len(),
+        2,
+        model.get_embedding_dim()?,
+    )?;
+    assert!(coordinator.merge().is_err());
+
+    // Each worker opens the job from the shared output directory and encodes the shards it claims
+    let worker = ShardedEncodingCoordinator::open(output_dir.path())?;
+    let mut encoded_shards = vec![];
+    while let Some(shard) = worker.claim_shard()? {
+        worker.encode_shard(&model, shard, &corpus[worker.shard_range(shard)], 2)?;
+        encoded_shards.push(shard);
+    }
+    assert_eq!(encoded_shards, vec![0, 1]);
+    assert!(coordinator.pending_shards().is_empty());
+
+    let embeddings = coordinator.merge()?;
+    assert_eq!(embeddings.num_embeddings(), corpus.len());
+    let embeddings = embeddings.load()?;
+    let expected = model.encode_as_tensor(&corpus)?.embeddings;
+    assert_eq!(embeddings.size(), expected.size());
+    assert!(embeddings.allclose(&expected.to_kind(tch::Kind::Float), 1e-4, 1e-5, false));
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use rust_bert::resources::{RemoteResource, ResourceProvider};
+use rust_bert::settings::{
+    clear_overrides, default_device, parse_device, set_overrides, Settings, SettingsLayer,
+};
+use std::fs;
+use std::path::PathBuf;
+use tch::Device;
+
+#[test]
+fn settings_precedence() -> anyhow::Result<()> {
+    let overrides = SettingsLayer {
+        device: Some("cpu".to_string()),
+        ..Default::default()
+    };
+    let environment = SettingsLayer {
+        device: Some("cuda:1".to_string()),
+        cache_dir: Some(PathBuf::from("/tmp/env_cache")),
+        ..Default::default()
+    };
+    let file = SettingsLayer {
+        cache_dir: Some(PathBuf::from("/tmp/file_cache")),
+        num_threads: Some(4),
+        offline: Some(true),
+        ..Default::default()
+    };
+
+    let settings = Settings::from_layers(vec![overrides, environment, file])?;
+    assert_eq!(settings.device, Device::Cpu);
+    assert_eq!(settings.cache_dir, PathBuf::from("/tmp/env_cache"));
+    assert_eq!(settings.num_threads, Some(4));
+    assert!(settings.offline);
+    assert_eq!(settings.hf_token, None);
+
+    let defaults = Settings::from_layers(vec![])?;
+    assert!(!defaults.offline);
+    assert!(defaults.cache_dir.ends_with(".rustbert"));
+
+    let invalid = SettingsLayer {
+        device: Some("tpu".to_string()),
+        ..Default::default()
+    };
+    assert!(Settings::from_layers(vec![invalid]).is_err());
+    Ok(())
+}
+
+#[test]
+fn settings_file() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let path = directory.path().join("config.json");
+    fs::write(&path, r#"{"device": "cuda:2", "num_threads": 8}"#)?;
+
+    let layer = SettingsLayer::from_file(&path)?;
+    assert_eq!(layer.device.as_deref(), Some("cuda:2"));
+    assert_eq!(layer.num_threads, Some(8));
+    assert_eq!(layer.cache_dir, None);
+
+    fs::write(&path, r#"{"num_threads": "eight"}"#)?;
+    assert!(SettingsLayer::from_file(&path).is_err());
+    Ok(())
+}
+
+#[test]
+fn settings_device_names() -> anyhow::Result<()> {
+    assert_eq!(parse_device("CPU")?, Device::Cpu);
+    assert_eq!(parse_device("cuda")?, Device::Cuda(0));
+    assert_eq!(parse_device("cuda:3")?, Device::Cuda(3));
+    assert_eq!(parse_device("mps")?, Device::Mps);
+    assert!(parse_device("cuda:first").is_err());
+    Ok(())
+}
+
+#[test]
+fn invalid_settings_resource_error() {
+    // The resources cache is created from the settings when the first remote resource of the process is loaded
+    set_overrides(SettingsLayer {
+        device: Some("tpu".to_string()),
+        ..Default::default()
+    });
+    let resource = RemoteResource::new("http://config_json_location", "configs");
+    assert!(resource.get_local_path().is_err());
+    assert_eq!(default_device(), Device::cuda_if_available());
+    clear_overrides();
+}
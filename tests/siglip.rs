@@ -0,0 +1,124 @@
+use rust_bert::pipelines::multimodal_embeddings::MultimodalEmbeddingsModel;
+use rust_bert::pipelines::zero_shot_image_classification::ZeroShotImageClassificationModel;
+use rust_bert::siglip::{
+    prepare_image, SiglipConfig, SiglipModel, SiglipTextConfig, SiglipVisionConfig,
+};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn siglip_forward() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = SiglipConfig {
+        text_config: SiglipTextConfig {
+            vocab_size: 100,
+            hidden_size: 32,
+            intermediate_size: 64,
+            num_hidden_layers: 2,
+            num_attention_heads: 4,
+            max_position_embeddings: 16,
+            ..Default::default()
+        },
+        vision_config: SiglipVisionConfig {
+            hidden_size: 32,
+            intermediate_size: 64,
+            num_hidden_layers: 2,
+            num_attention_heads: 4,
+            image_size: 32,
+            patch_size: 8,
+            ..Default::default()
+        },
+    };
+    let model = SiglipModel::new(vs.root(), &config);
+
+    let input_ids = Tensor::randint(100, [3, 16], (Kind::Int64, device));
+    let image = Tensor::randint(256, [3, 40, 50], (Kind::Uint8, device));
+    let pixel_values = prepare_image(&image, 32);
+    assert_eq!(pixel_values.size(), vec![3, 32, 32]);
+    let pixel_values = Tensor::stack(&[&pixel_values, &pixel_values], 0);
+
+    let output = no_grad(|| model.forward_t(&input_ids, &pixel_values, None, false));
+
+    assert_eq!(output.logits_per_image.size(), vec![2, 3]);
+    assert_eq!(output.logits_per_text.size(), vec![3, 2]);
+    assert_eq!(output.text_embeds.size(), vec![3, 32]);
+    assert_eq!(output.image_embeds.size(), vec![2, 32]);
+    let norms = output.image_embeds.norm_scalaropt_dim(2, [-1], false);
+    assert!((norms - 1.0).abs().max().double_value(&[]) < 1e-5);
+
+    Ok(())
+}
+
+/// Solid color image of shape (3, height, width)
+fn solid_image(rgb: [i64; 3], height: i64, width: i64) -> Tensor {
+    Tensor::from_slice(&rgb)
+        .view([3, 1, 1])
+        .expand([3, height, width], false)
+        .to_kind(Kind::Uint8)
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn siglip_zero_shot_image_classification() -> anyhow::Result<()> {
+    let classification_model = ZeroShotImageClassificationModel::new(Default::default())?;
+
+    let images = [
+        solid_image([255, 0, 0], 300, 200),
+        solid_image([0, 0, 255], 120, 160),
+    ];
+    let candidate_labels = ["a red square", "a blue square", "a dog"];
+    let output = classification_model.predict(&images, &candidate_labels)?;
+
+    assert_eq!(output.len(), 2);
+    for (image, labels) in output.iter().enumerate() {
+        assert_eq!(labels.len(), 3);
+        assert!(labels.iter().all(|label| label.image == image));
+        assert!(labels.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(labels
+            .iter()
+            .all(|label| (0.0..=1.0).contains(&label.score)));
+    }
+    assert_eq!(output[0][0].text, "a red square");
+    assert_eq!(output[1][0].text, "a blue square");
+
+    let empty_labels: [&str; 0] = [];
+    assert!(classification_model
+        .predict(&images, &empty_labels)
+        .is_err());
+    assert!(classification_model
+        .predict(
+            &[Tensor::zeros([32, 32], (Kind::Uint8, Device::Cpu))],
+            &candidate_labels
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn siglip_multimodal_embeddings() -> anyhow::Result<()> {
+    let embeddings_model = MultimodalEmbeddingsModel::new(Default::default())?;
+
+    let text_embeddings =
+        embeddings_model.encode_texts(&["a photo of a red square", "a photo of a dog"])?;
+    let image_embeddings = embeddings_model.encode_images(&[solid_image([255, 0, 0], 224, 224)])?;
+
+    assert_eq!(text_embeddings.len(), 2);
+    assert_eq!(image_embeddings.len(), 1);
+    assert_eq!(text_embeddings[0].len(), image_embeddings[0].len());
+    let dot =
+        |left: &[f32], right: &[f32]| -> f32 { left.iter().zip(right).map(|(l, r)| l * r).sum() };
+    for embedding in text_embeddings.iter().chain(image_embeddings.iter()) {
+        assert!((dot(embedding, embedding) - 1.0).abs() < 1e-4);
+    }
+    assert!(
+        dot(&image_embeddings[0], &text_embeddings[0])
+            > dot(&image_embeddings[0], &text_embeddings[1])
+    );
+
+    let no_texts: [&str; 0] = [];
+    assert!(embeddings_model.encode_texts(&no_texts).is_err());
+
+    Ok(())
+}
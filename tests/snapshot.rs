@@ -0,0 +1,55 @@
+use rust_bert::snapshot::{load_snapshot, save_snapshot, snapshot_kind};
+use tch::nn::{Init, VarStore};
+use tch::{Device, Kind};
+
+fn var_store() -> VarStore {
+    let vs = VarStore::new(Device::Cpu);
+    let root = vs.root();
+    let _ = root.var(
+        "weight",
+        &[4, 3],
+        Init::Randn {
+            mean: 0.,
+            stdev: 1.,
+        },
+    );
+    let _ = (&root / "layer").var(
+        "bias",
+        &[4],
+        Init::Randn {
+            mean: 0.,
+            stdev: 1.,
+        },
+    );
+    vs
+}
+
+#[test]
+fn snapshot_round_trip() -> anyhow::Result<()> {
+    let mut vs = var_store();
+    vs.half();
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("snapshot.safetensors");
+    save_snapshot(&vs, &path)?;
+
+    assert_eq!(snapshot_kind(&path)?, Kind::Half);
+
+    let mut restored_vs = var_store();
+    load_snapshot(&mut restored_vs, &path)?;
+    let variables = vs.variables();
+    let restored_variables = restored_vs.variables();
+    assert_eq!(restored_variables.len(), variables.len());
+    for (name, variable) in variables {
+        let restored_variable = &restored_variables[&name];
+        assert_eq!(restored_variable.kind(), Kind::Half);
+        assert!(restored_variable.equal(&variable));
+    }
+    Ok(())
+}
+
+#[test]
+fn snapshot_invalid_extension() {
+    let vs = var_store();
+    let dir = tempfile::tempdir().unwrap();
+    assert!(save_snapshot(&vs, dir.path().join("snapshot.ot")).is_err());
+}
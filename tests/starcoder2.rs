@@ -0,0 +1,86 @@
+mod common;
+
+use rust_bert::pipelines::generation_utils::Cache;
+use rust_bert::resources::{load_weights, RemoteResource, ResourceProvider};
+use rust_bert::starcoder2::{
+    StarCoder2Config, StarCoder2ConfigResources, StarCoder2ForCausalLM, StarCoder2MergesResources,
+    StarCoder2Model, StarCoder2ModelResources, StarCoder2VocabResources,
+};
+use rust_bert::Config;
+use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn starcoder2_sliding_window() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = StarCoder2Config {
+        sliding_window: Some(4),
+        ..common::tiny_starcoder2_config()
+    };
+    let model = StarCoder2Model::new(vs.root(), &config);
+
+    let input_ids = Tensor::randint(100, [1, 8], (Kind::Int64, device));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    let attentions = output.all_attentions.unwrap();
+    assert!(attentions[0].double_value(&[0, 0, 7, 4]) > 0.0);
+    assert!(attentions[0].double_value(&[0, 0, 7, 3]) < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn starcoder2_lm() -> anyhow::Result<()> {
+    //    Resources paths
+    let config_resource = RemoteResource::from_pretrained(StarCoder2ConfigResources::STARCODER2_3B);
+    let vocab_resource = RemoteResource::from_pretrained(StarCoder2VocabResources::STARCODER2_3B);
+    let merges_resource = RemoteResource::from_pretrained(StarCoder2MergesResources::STARCODER2_3B);
+    let weights_resource = RemoteResource::from_pretrained(StarCoder2ModelResources::STARCODER2_3B);
+    let config_path = config_resource.get_local_path()?;
+    let vocab_path = vocab_resource.get_local_path()?;
+    let merges_path = merges_resource.get_local_path()?;
+
+    //    Set-up model
+    let device = Device::Cpu;
+    let mut vs = nn::VarStore::new(device);
+    let tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_file(
+        vocab_path.to_str().unwrap(),
+        merges_path.to_str().unwrap(),
+        false,
+    )?;
+    let config = StarCoder2Config::from_file(config_path);
+    let starcoder2_model = StarCoder2ForCausalLM::new(vs.root(), &config);
+    load_weights(&weights_resource, &mut vs)?;
+
+    //    Define input
+    let input = ["import numpy as"];
+    let tokenized_input = tokenizer.encode_list(&input, 128, &TruncationStrategy::LongestFirst, 0);
+    let input_length = tokenized_input[0].token_ids.len() as i64;
+    let input_tensor = Tensor::from_slice(&tokenized_input[0].token_ids)
+        .unsqueeze(0)
+        .to(device);
+
+    //    Forward pass
+    let model_output = no_grad(|| {
+        starcoder2_model.forward_t(Some(&input_tensor), Cache::None, None, None, None, false)
+    })?;
+
+    let next_word_id = model_output
+        .lm_logits
+        .get(0)
+        .get(-1)
+        .argmax(-1, true)
+        .int64_value(&[0]);
+    let next_word = tokenizer.decode(&[next_word_id], true, true);
+
+    // Output
+    assert_eq!(
+        model_output.lm_logits.size(),
+        vec!(1, input_length, config.vocab_size)
+    );
+    assert_eq!(next_word, String::from(" np"));
+
+    Ok(())
+}
@@ -1,9 +1,15 @@
 use rust_bert::pipelines::common::{ModelResource, ModelType};
+use rust_bert::pipelines::data_to_text::{DataRecord, DataToTextConfig, DataToTextModel};
 use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
+use rust_bert::pipelines::text_generation::TextGenerationConfig;
+use rust_bert::pipelines::text_to_sql::{SqlGrammar, SqlSchema, TextToSqlConfig, TextToSqlModel};
 use rust_bert::pipelines::translation::{Language, TranslationConfig, TranslationModel};
 use rust_bert::resources::RemoteResource;
-use rust_bert::t5::{T5ConfigResources, T5ModelResources, T5VocabResources};
-use tch::Device;
+use rust_bert::t5::{
+    FeedForwardProj, T5Config, T5ConfigResources, T5ForConditionalGeneration, T5ModelResources,
+    T5VocabResources, UL2Mode,
+};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 #[test]
 fn test_translation_t5() -> anyhow::Result<()> {
@@ -113,3 +119,153 @@ about exoplanets like K2-18b."];
 
     Ok(())
 }
+
+#[test]
+fn test_data_to_text_t5() -> anyhow::Result<()> {
+    //    Set-up data-to-text model
+    let generation_config = SummarizationConfig {
+        model_type: ModelType::T5,
+        model_resource: ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            T5ModelResources::T5_SMALL,
+        ))),
+        config_resource: Box::new(RemoteResource::from_pretrained(T5ConfigResources::T5_SMALL)),
+        vocab_resource: Box::new(RemoteResource::from_pretrained(T5VocabResources::T5_SMALL)),
+        merges_resource: None,
+        min_length: 0,
+        max_length: Some(32),
+        ..Default::default()
+    };
+    let model = DataToTextModel::new(DataToTextConfig::new(
+        generation_config,
+        Some("summarize: "),
+    ))?;
+
+    let record = DataRecord::from_pairs(&[
+        ("name", "Alimentum"),
+        ("food", "Italian"),
+        ("area", "city centre"),
+    ]);
+    assert_eq!(
+        model.linearize(&record),
+        "name: Alimentum | food: Italian | area: city centre"
+    );
+
+    let table = DataRecord::from_table(
+        Some("Tallest buildings"),
+        &["name", "height"],
+        &[vec!["Burj Khalifa", "828 m"], vec!["Merdeka 118", "679 m"]],
+    )?;
+    assert_eq!(
+        model.linearize(&table),
+        "Tallest buildings || name: Burj Khalifa | height: 828 m || name: Merdeka 118 | height: 679 m"
+    );
+    assert!(DataRecord::from_table(None, &["name", "height"], &[vec!["Burj Khalifa"]]).is_err());
+
+    let output = model.verbalize(&[record, table]);
+
+    assert_eq!(output.len(), 2);
+    assert!(output.iter().all(|text| !text.trim().is_empty()));
+
+    Ok(())
+}
+
+#[test]
+fn test_text_to_sql_t5() -> anyhow::Result<()> {
+    let schema = SqlSchema::from_ddl(
+        "CREATE TABLE singer (singer_id INTEGER PRIMARY KEY, name TEXT, country TEXT, age INTEGER);
+        CREATE TABLE IF NOT EXISTS concert (concert_id INTEGER, singer_id INTEGER, year INTEGER,
+        FOREIGN KEY (singer_id) REFERENCES singer(singer_id));",
+    )?;
+    assert_eq!(
+        schema.describe(),
+        "singer : singer_id , name , country , age | concert : concert_id , singer_id , year"
+    );
+
+    //    Check the grammar
+    let grammar = SqlGrammar::new(&schema);
+    assert!(grammar.is_complete("SELECT count(*) FROM singer WHERE country = 'France'"));
+    assert!(grammar.is_complete(
+        "SELECT T1.name FROM singer AS T1 JOIN concert AS T2 ON T1.singer_id = T2.singer_id \
+        GROUP BY T1.singer_id HAVING count(*) > 1 ORDER BY T1.age DESC LIMIT 3;"
+    ));
+    assert!(grammar.is_complete(
+        "SELECT name FROM singer WHERE age > (SELECT avg(age) FROM singer) \
+        AND singer_id NOT IN (SELECT singer_id FROM concert WHERE year = 2014)"
+    ));
+    assert!(grammar.is_valid_prefix("SELECT name FROM singer WHERE coun"));
+    assert!(!grammar.is_complete("SELECT name FROM singer WHERE"));
+    assert!(!grammar.is_valid_prefix("SELECT name FROM album"));
+    assert!(!grammar.is_valid_prefix("SELECT title FROM"));
+
+    //    Set-up text-to-SQL model
+    let mut generation_config = TextGenerationConfig::new(
+        ModelType::T5,
+        ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
+            T5ModelResources::T5_SMALL,
+        ))),
+        RemoteResource::from_pretrained(T5ConfigResources::T5_SMALL),
+        RemoteResource::from_pretrained(T5VocabResources::T5_SMALL),
+        None,
+    );
+    generation_config.min_length = 0;
+    generation_config.do_sample = false;
+    generation_config.num_beams = 2;
+    let mut config = TextToSqlConfig::new(generation_config);
+    config.max_sql_tokens = 32;
+    let model = TextToSqlModel::new(config)?;
+
+    let output = model.generate(&["How many singers are from France?"], &schema)?;
+
+    assert_eq!(output.len(), 1);
+    assert!(grammar.is_valid_prefix(&output[0]));
+
+    Ok(())
+}
+
+#[test]
+fn test_ul2_configuration() -> anyhow::Result<()> {
+    //    Set-up a randomly initialized model with the UL2 configuration options
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = T5Config {
+        d_model: 32,
+        d_ff: 64,
+        d_kv: 8,
+        num_heads: 4,
+        num_layers: 2,
+        num_decoder_layers: Some(1),
+        vocab_size: 100,
+        feed_forward_proj: Some(FeedForwardProj::GatedSilu),
+        tie_word_embeddings: Some(false),
+        output_hidden_states: Some(true),
+        ..Default::default()
+    };
+    let model = T5ForConditionalGeneration::new(vs.root(), &config);
+
+    //    Forward pass
+    let input_ids = Tensor::randint(100, [2, 7], (Kind::Int64, device));
+    let decoder_input_ids = Tensor::randint(100, [2, 3], (Kind::Int64, device));
+    let output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids),
+            None,
+            None,
+            Some(&decoder_input_ids),
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    });
+
+    assert_eq!(output.decoder_output.size(), vec![2, 3, 100]);
+    assert_eq!(output.all_encoder_hidden_states.unwrap().len(), 2);
+    assert_eq!(output.all_decoder_hidden_states.unwrap().len(), 1);
+    assert_eq!(
+        UL2Mode::S2S.apply("summarize: some text"),
+        "[S2S] summarize: some text"
+    );
+
+    Ok(())
+}
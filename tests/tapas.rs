@@ -0,0 +1,152 @@
+mod common;
+
+use rust_bert::resources::{load_weights, RemoteResource, ResourceProvider};
+use rust_bert::tapas::{
+    cell_position_ids, TapasConfig, TapasConfigResources, TapasForQuestionAnswering,
+    TapasModelResources, TapasVocabResources,
+};
+use rust_bert::Config;
+use rust_tokenizers::tokenizer::{BertTokenizer, Tokenizer};
+use std::convert::TryFrom;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+/// Token types of a question of 3 tokens followed by a table with a header of 2 cells and 2 rows. The cell of the
+/// first row and column has 2 tokens.
+fn table_token_types() -> Tensor {
+    // (segment, column, row) of each token
+    let tokens = [
+        (0, 0, 0),
+        (0, 0, 0),
+        (0, 0, 0),
+        (1, 1, 0),
+        (1, 2, 0),
+        (1, 1, 1),
+        (1, 1, 1),
+        (1, 2, 1),
+        (1, 1, 2),
+        (1, 2, 2),
+    ];
+    let token_types = tokens
+        .iter()
+        .flat_map(|(segment, column, row)| [*segment, *column, *row, 0, 0, 0, 0])
+        .collect::<Vec<i64>>();
+    Tensor::from_slice(&token_types).view([1, tokens.len() as i64, 7])
+}
+
+#[test]
+fn tapas_cell_position_ids() {
+    let position_ids = cell_position_ids(&table_token_types(), 16, 64);
+    let position_ids = Vec::<i64>::try_from(position_ids.view([-1])).unwrap();
+    assert_eq!(position_ids, vec![0, 1, 2, 0, 0, 0, 1, 0, 0, 0]);
+
+    let position_ids = cell_position_ids(&table_token_types(), 16, 2);
+    let position_ids = Vec::<i64>::try_from(position_ids.view([-1])).unwrap();
+    assert_eq!(position_ids, vec![0, 1, 1, 0, 0, 0, 1, 0, 0, 0]);
+}
+
+#[test]
+fn tapas_token_types_validation() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let model = TapasForQuestionAnswering::new(vs.root(), &common::tiny_tapas_config());
+
+    let input_ids = Tensor::randint(100, [1, 10], (Kind::Int64, device));
+    let output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids),
+            None,
+            Some(&table_token_types()),
+            None,
+            None,
+            false,
+        )
+    })?;
+    assert_eq!(output.logits.size(), vec![1, 10]);
+
+    // Token types without the 7 TAPAS types are rejected
+    let token_type_ids = Tensor::zeros([1, 10], (Kind::Int64, device));
+    assert!(model
+        .forward_t(
+            Some(&input_ids),
+            None,
+            Some(&token_type_ids),
+            None,
+            None,
+            false
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn tapas_for_question_answering() -> anyhow::Result<()> {
+    //    Resources paths
+    let config_resource = RemoteResource::from_pretrained(TapasConfigResources::TAPAS_BASE_WTQ);
+    let vocab_resource = RemoteResource::from_pretrained(TapasVocabResources::TAPAS_BASE_WTQ);
+    let weights_resource = RemoteResource::from_pretrained(TapasModelResources::TAPAS_BASE_WTQ);
+    let config_path = config_resource.get_local_path()?;
+    let vocab_path = vocab_resource.get_local_path()?;
+
+    //    Set-up model
+    let device = Device::Cpu;
+    let mut vs = nn::VarStore::new(device);
+    let tokenizer = BertTokenizer::from_file(vocab_path.to_str().unwrap(), true, true)?;
+    let config = TapasConfig::from_file(config_path);
+    let tapas_model = TapasForQuestionAnswering::new(vs.root(), &config);
+    load_weights(&weights_resource, &mut vs)?;
+
+    //    Define input: question followed by the flattened table, with the (segment, column, row) of each token
+    let question = "How old is Brad Pitt?";
+    let table = [
+        ["Actors", "Age"],
+        ["Brad Pitt", "56"],
+        ["Leonardo Di Caprio", "45"],
+    ];
+    let mut tokens = vec![String::from("[CLS]")];
+    tokens.extend(tokenizer.tokenize(question));
+    tokens.push(String::from("[SEP]"));
+    let mut token_types = vec![0; tokens.len() * 7];
+    for (row, cells) in table.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            for token in tokenizer.tokenize(cell) {
+                tokens.push(token);
+                token_types.extend([1, column as i64 + 1, row as i64, 0, 0, 0, 0]);
+            }
+        }
+    }
+    let input_tensor = Tensor::from_slice(&tokenizer.convert_tokens_to_ids(&tokens))
+        .unsqueeze(0)
+        .to(device);
+    let token_type_ids = Tensor::from_slice(&token_types)
+        .view([1, tokens.len() as i64, 7])
+        .to(device);
+
+    //    Forward pass
+    let model_output = no_grad(|| {
+        tapas_model.forward_t(
+            Some(&input_tensor),
+            None,
+            Some(&token_type_ids),
+            None,
+            None,
+            false,
+        )
+    })?;
+
+    // The most likely selected token belongs to the age cell of the first row
+    let selected_token = model_output
+        .logits
+        .get(0)
+        .argmax(-1, false)
+        .int64_value(&[]) as usize;
+    assert_eq!(model_output.logits.size(), vec![1, tokens.len() as i64]);
+    assert_eq!(tokens[selected_token], "56");
+    assert_eq!(
+        token_types[selected_token * 7 + 1..selected_token * 7 + 3],
+        [2, 1]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use rust_bert::tensor_parallel::{ColumnParallelLinear, RowParallelLinear, TensorParallelConfig};
+use tch::nn::{Linear, Module};
+use tch::{Device, Kind, Tensor};
+
+fn random_linear(in_dim: i64, out_dim: i64) -> Linear {
+    Linear {
+        ws: Tensor::randn([out_dim, in_dim], (Kind::Float, Device::Cpu)),
+        bs: Some(Tensor::randn([out_dim], (Kind::Float, Device::Cpu))),
+    }
+}
+
+fn copy_linear(linear: &Linear) -> Linear {
+    Linear {
+        ws: linear.ws.copy(),
+        bs: linear.bs.as_ref().map(Tensor::copy),
+    }
+}
+
+#[test]
+fn column_parallel_linear() -> anyhow::Result<()> {
+    let devices = [Device::Cpu, Device::Cpu, Device::Cpu];
+    let linear = random_linear(16, 10);
+    let input = Tensor::randn([2, 3, 16], (Kind::Float, Device::Cpu));
+    let expected = linear.forward(&input);
+
+    let column_linear = ColumnParallelLinear::from_linear(copy_linear(&linear), &devices, 2)?;
+    let shard_sizes = column_linear
+        .shards
+        .iter()
+        .map(|shard| shard.ws.size()[0])
+        .collect::<Vec<i64>>();
+    assert_eq!(shard_sizes, [4, 4, 2]);
+
+    let output = column_linear.forward(&input);
+    assert_eq!(output.size(), [2, 3, 10]);
+    assert!(output.allclose(&expected, 1e-5, 1e-5, false));
+
+    assert!(ColumnParallelLinear::from_linear(copy_linear(&linear), &devices, 3).is_err());
+    assert!(ColumnParallelLinear::from_linear(random_linear(16, 4), &devices, 2).is_err());
+    Ok(())
+}
+
+#[test]
+fn row_parallel_linear() -> anyhow::Result<()> {
+    let devices = [Device::Cpu, Device::Cpu];
+    let linear = random_linear(16, 8);
+    let input = Tensor::randn([2, 3, 16], (Kind::Float, Device::Cpu));
+    let expected = linear.forward(&input);
+
+    let row_linear = RowParallelLinear::from_linear(copy_linear(&linear), &devices, 4)?;
+    assert_eq!(row_linear.shards.len(), 2);
+    assert_eq!(row_linear.shards[0].size(), [8, 8]);
+
+    let output = row_linear.forward(&input);
+    assert_eq!(output.size(), [2, 3, 8]);
+    assert!(output.allclose(&expected, 1e-5, 1e-5, false));
+    Ok(())
+}
+
+#[test]
+fn column_row_parallel_mlp() -> anyhow::Result<()> {
+    let devices = [Device::Cpu, Device::Cpu];
+    let fc_in = random_linear(8, 32);
+    let fc_out = random_linear(32, 8);
+    let input = Tensor::randn([4, 8], (Kind::Float, Device::Cpu));
+    let expected = fc_in.forward(&input).relu().apply(&fc_out);
+
+    let fc_in = ColumnParallelLinear::from_linear(fc_in, &devices, 1)?;
+    let fc_out = RowParallelLinear::from_linear(fc_out, &devices, 1)?;
+    let intermediate = fc_in
+        .forward_shards(&input)
+        .iter()
+        .map(Tensor::relu)
+        .collect::<Vec<Tensor>>();
+    let output = fc_out.forward_shards(&intermediate);
+    assert!(output.allclose(&expected, 1e-4, 1e-4, false));
+    Ok(())
+}
+
+#[test]
+fn tensor_parallel_config() {
+    assert!(TensorParallelConfig::new(vec![]).devices.is_empty());
+    assert_eq!(
+        TensorParallelConfig::all_cuda_devices().devices.len(),
+        tch::Cuda::device_count() as usize
+    );
+}
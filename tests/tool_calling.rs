@@ -0,0 +1,106 @@
+use rust_bert::pipelines::conversation::Conversation;
+use rust_bert::pipelines::tool_calling::{
+    parse_tool_calls, Tool, ToolCall, ToolFormat, ToolResult, ToolSet,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+fn weather_tool_set(format: ToolFormat) -> ToolSet {
+    ToolSet::new(
+        vec![Tool::new(
+            "get_weather",
+            "Get the current weather in a given city",
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": {"type": "string"},
+                    "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]}
+                },
+                "required": ["city"]
+            }),
+        )],
+        format,
+    )
+}
+
+#[derive(Deserialize)]
+struct WeatherArguments {
+    city: String,
+    unit: Option<String>,
+}
+
+#[test]
+fn tool_calls_parsing() -> anyhow::Result<()> {
+    let output = r#"Let me check. <tool_call>
+{"name": "get_weather", "arguments": {"city": "Paris {France}", "unit": "celsius"}}
+</tool_call>"#;
+    let tool_calls = weather_tool_set(ToolFormat::Hermes).parse(output)?;
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].name, "get_weather");
+    let arguments: WeatherArguments = tool_calls[0].parse_arguments()?;
+    assert_eq!(arguments.city, "Paris {France}");
+    assert_eq!(arguments.unit.as_deref(), Some("celsius"));
+
+    let output = r#"[TOOL_CALLS][{"name": "get_weather", "arguments": {"city": "Paris"}}, {"name": "get_weather", "arguments": {"city": "Berlin"}}]"#;
+    let tool_calls = parse_tool_calls(output, ToolFormat::Mistral)?;
+    assert_eq!(tool_calls.len(), 2);
+    assert_eq!(tool_calls[1].arguments, json!({"city": "Berlin"}));
+
+    let output = "The weather is sunny in Paris.";
+    assert!(parse_tool_calls(output, ToolFormat::Json)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn tool_calls_validation() -> anyhow::Result<()> {
+    let tool_set = weather_tool_set(ToolFormat::Json);
+    let tool = &tool_set.tools()[0];
+
+    let missing_argument = ToolCall {
+        name: "get_weather".to_string(),
+        arguments: json!({"unit": "celsius"}),
+    };
+    assert!(tool.validate(&missing_argument).is_err());
+
+    let invalid_value = ToolCall {
+        name: "get_weather".to_string(),
+        arguments: json!({"city": "Paris", "unit": "kelvin"}),
+    };
+    assert!(tool.validate(&invalid_value).is_err());
+
+    let invalid_type = ToolCall {
+        name: "get_weather".to_string(),
+        arguments: json!({"city": 75000}),
+    };
+    assert!(tool.validate(&invalid_type).is_err());
+
+    assert!(tool_set
+        .parse(r#"{"name": "get_time", "arguments": {}}"#)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn tool_results_in_conversation() -> anyhow::Result<()> {
+    let tool_set = weather_tool_set(ToolFormat::Hermes);
+    let mut conversation = Conversation::new_empty();
+    conversation.set_system_prompt("You are a helpful assistant.");
+    tool_set.attach(&mut conversation);
+
+    let system_prompt = conversation.system_prompt.as_ref().unwrap();
+    assert!(system_prompt.starts_with("You are a helpful assistant."));
+    assert!(system_prompt.contains("<tools>"));
+    assert!(system_prompt.contains("get_weather"));
+
+    tool_set.add_tool_results(
+        &mut conversation,
+        &[ToolResult::new("get_weather", json!({"temperature": 21}))],
+    )?;
+    let new_user_input = conversation.new_user_input.as_ref().unwrap();
+    assert!(new_user_input.starts_with("<tool_response>"));
+    assert!(new_user_input.contains(r#""temperature":21"#));
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+use rust_bert::vocabulary::{
+    add_tokens, resize_token_embeddings, VocabularyLearner, VocabularyLearnerConfig,
+};
+use tch::nn::{Init, VarStore};
+use tch::{Device, Kind, Tensor};
+
+const VOCAB: [&str; 15] = [
+    "[PAD]",
+    "[UNK]",
+    "[CLS]",
+    "[SEP]",
+    "[MASK]",
+    "the",
+    "patient",
+    "has",
+    "hyper",
+    "##lip",
+    "##id",
+    "##emia",
+    "and",
+    "##tension",
+    ".",
+];
+
+fn tokenizer(dir: &tempfile::TempDir) -> anyhow::Result<TokenizerOption> {
+    let path = dir.path().join("vocab.txt");
+    std::fs::write(&path, VOCAB.join("\n"))?;
+    Ok(TokenizerOption::from_file(
+        ModelType::Bert,
+        path.to_str().unwrap(),
+        None,
+        true,
+        None,
+        None,
+    )?)
+}
+
+#[test]
+fn vocabulary_learning() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut tokenizer = tokenizer(&dir)?;
+    let corpus = [
+        "The patient has hyperlipidemia and hypertension.",
+        "The patient has hypertension and hyperlipidemia.",
+        "Hyperlipidemia: the patient has hyperlipidemia.",
+    ];
+
+    let learner = VocabularyLearner::new(VocabularyLearnerConfig {
+        min_frequency: 2,
+        min_subwords: 2,
+        ..Default::default()
+    });
+    let candidates = learner.learn(&tokenizer, &corpus);
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[0].token, "hyperlipidemia");
+    assert_eq!(candidates[0].frequency, 3);
+    assert_eq!(candidates[0].subword_ids, vec![8, 9, 10, 11]);
+    assert_eq!(candidates[0].savings(), 9);
+    assert_eq!(candidates[1].token, "hypertension");
+    assert_eq!(candidates[1].savings(), 2);
+
+    let added_tokens = add_tokens(&mut tokenizer, &candidates[..1])?;
+    assert_eq!(added_tokens.len(), 1);
+    assert_eq!(added_tokens[0].id, 15);
+    assert_eq!(
+        tokenizer.tokenize("The patient has hyperlipidemia."),
+        vec!["the", "patient", "has", "hyperlipidemia", "."]
+    );
+    // Tokens already in the vocabulary are skipped
+    assert!(add_tokens(&mut tokenizer, &candidates[..1])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn token_embeddings_resizing() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut tokenizer = tokenizer(&dir)?;
+    let candidates = VocabularyLearner::new(VocabularyLearnerConfig {
+        min_frequency: 1,
+        ..Default::default()
+    })
+    .learn(&tokenizer, &["the patient has hyperlipidemia"]);
+    let added_tokens = add_tokens(&mut tokenizer, &candidates)?;
+
+    let mut vs = VarStore::new(Device::Cpu);
+    let root = vs.root();
+    let embeddings = (&root / "embeddings").var(
+        "weight",
+        &[15, 4],
+        Init::Randn {
+            mean: 0.,
+            stdev: 1.,
+        },
+    );
+    let bias = (&root / "lm_head").var(
+        "bias",
+        &[15],
+        Init::Randn {
+            mean: 0.,
+            stdev: 1.,
+        },
+    );
+    let layer = (&root / "layer").var(
+        "weight",
+        &[4, 4],
+        Init::Randn {
+            mean: 0.,
+            stdev: 1.,
+        },
+    );
+    let original_embeddings = embeddings.copy();
+    let original_bias = bias.copy();
+    let original_layer = layer.copy();
+
+    let new_vocab_size = resize_token_embeddings(&mut vs, 15, &added_tokens)?;
+    assert_eq!(new_vocab_size, 16);
+    let variables = vs.variables();
+    let embeddings = &variables["embeddings.weight"];
+    let bias = &variables["lm_head.bias"];
+    assert_eq!(embeddings.size(), vec![16, 4]);
+    assert_eq!(bias.size(), vec![16]);
+    assert!(embeddings.narrow(0, 0, 15).equal(&original_embeddings));
+    assert!(bias.narrow(0, 0, 15).equal(&original_bias));
+    assert!(variables["layer.weight"].equal(&original_layer));
+
+    let subword_ids = Tensor::from_slice(&[8i64, 9, 10, 11]);
+    let expected_embedding = original_embeddings.index_select(0, &subword_ids).mean_dim(
+        [0].as_slice(),
+        false,
+        Kind::Float,
+    );
+    assert!(embeddings
+        .get(15)
+        .allclose(&expected_embedding, 1e-6, 1e-6, false));
+    let expected_bias =
+        original_bias
+            .index_select(0, &subword_ids)
+            .mean_dim([0].as_slice(), false, Kind::Float);
+    assert!(bias.get(15).allclose(&expected_bias, 1e-6, 1e-6, false));
+
+    // Padded vocabulary: the new token overwrites an unused row
+    let mut vs = VarStore::new(Device::Cpu);
+    let _ = (&vs.root() / "embeddings").var("weight", &[20, 4], Init::Const(0.));
+    assert_eq!(resize_token_embeddings(&mut vs, 20, &added_tokens)?, 20);
+    assert_eq!(vs.variables()["embeddings.weight"].size(), vec![20, 4]);
+
+    // The subwords must be part of the model vocabulary
+    assert!(resize_token_embeddings(&mut vs, 8, &added_tokens).is_err());
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use rust_bert::albert::{AlbertConfig, AlbertForMaskedLM};
+use rust_bert::bert::{BertConfig, BertForMaskedLM};
+use rust_bert::resources::{BufferResource, LocalResource};
+use rust_bert::weight_tying::{
+    load_weights_with_tying, TiedWeights, WeightTyingPolicy, WeightTyingStatus,
+};
+use tch::nn::{Init, VarStore};
+use tch::{no_grad, Device, Kind, Tensor};
+
+const TIED_WEIGHTS: TiedWeights = TiedWeights::new("embeddings.weight", "lm_head.weight");
+
+fn var_store() -> VarStore {
+    let vs = VarStore::new(Device::Cpu);
+    let root = vs.root();
+    let _ = (&root / "embeddings").var("weight", &[5, 4], Init::Const(0.));
+    let _ = (&root / "lm_head").var("weight", &[5, 4], Init::Const(0.));
+    let _ = (&root / "layer").var("weight", &[4, 4], Init::Const(0.));
+    vs
+}
+
+/// Checkpoint with an LM head tied to the input embeddings, or random if `untied_lm_head`
+fn checkpoint_tensors(untied_lm_head: bool) -> Vec<(String, Tensor)> {
+    let embeddings = Tensor::randn([5, 4], (Kind::Float, Device::Cpu));
+    let lm_head = if untied_lm_head {
+        Tensor::randn([5, 4], (Kind::Float, Device::Cpu))
+    } else {
+        embeddings.copy()
+    };
+    vec![
+        (
+            "layer.weight".to_string(),
+            Tensor::randn([4, 4], (Kind::Float, Device::Cpu)),
+        ),
+        ("embeddings.weight".to_string(), embeddings),
+        ("lm_head.weight".to_string(), lm_head),
+    ]
+}
+
+fn lm_head_tied(vs: &VarStore) -> bool {
+    let variables = vs.variables();
+    variables["lm_head.weight"].equal(&variables["embeddings.weight"])
+}
+
+#[test]
+fn tied_and_missing_lm_heads() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.ot");
+    let tensors = checkpoint_tensors(false);
+    Tensor::save_multi(&tensors, &path)?;
+    let mut vs = var_store();
+    let status = load_weights_with_tying(
+        &LocalResource::from(path),
+        &mut vs,
+        &[TIED_WEIGHTS],
+        WeightTyingPolicy::Error,
+    )?;
+    assert_eq!(status, [WeightTyingStatus::Tied]);
+    assert!(lm_head_tied(&vs));
+
+    // Checkpoints saved without the duplicated LM head
+    let path = dir.path().join("model.safetensors");
+    let tensors = checkpoint_tensors(false)
+        .into_iter()
+        .filter(|(name, _)| name != "lm_head.weight")
+        .collect::<Vec<(String, Tensor)>>();
+    Tensor::write_safetensors(&tensors, &path)?;
+    let mut vs = var_store();
+    let status = load_weights_with_tying(
+        &BufferResource::from(std::fs::read(&path)?),
+        &mut vs,
+        &[TIED_WEIGHTS],
+        WeightTyingPolicy::Error,
+    )?;
+    assert_eq!(status, [WeightTyingStatus::MissingLmHead]);
+    assert!(lm_head_tied(&vs));
+    assert!(vs.variables()["layer.weight"].equal(&tensors[0].1));
+
+    // Only the tied LM heads may be missing
+    let tensors = tensors
+        .into_iter()
+        .filter(|(name, _)| name != "layer.weight")
+        .collect::<Vec<(String, Tensor)>>();
+    Tensor::write_safetensors(&tensors, &path)?;
+    let error = load_weights_with_tying(
+        &LocalResource::from(path),
+        &mut var_store(),
+        &[TIED_WEIGHTS],
+        WeightTyingPolicy::Error,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("layer.weight"));
+    Ok(())
+}
+
+#[test]
+fn untied_lm_heads() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.ot");
+    let tensors = checkpoint_tensors(true);
+    Tensor::save_multi(&tensors, &path)?;
+
+    let mut vs = var_store();
+    let status = load_weights_with_tying(
+        &LocalResource::from(path.clone()),
+        &mut vs,
+        &[TIED_WEIGHTS],
+        WeightTyingPolicy::Warn,
+    )?;
+    assert!(matches!(
+        status[0],
+        WeightTyingStatus::Untied {
+            repaired: false,
+            ..
+        }
+    ));
+    assert!(!lm_head_tied(&vs));
+
+    let mut vs = var_store();
+    let status = load_weights_with_tying(
+        &LocalResource::from(path.clone()),
+        &mut vs,
+        &[TIED_WEIGHTS],
+        WeightTyingPolicy::Repair,
+    )?;
+    assert!(matches!(
+        status[0],
+        WeightTyingStatus::Untied { repaired: true, .. }
+    ));
+    assert!(lm_head_tied(&vs));
+
+    assert!(load_weights_with_tying(
+        &LocalResource::from(path),
+        &mut var_store(),
+        &[TIED_WEIGHTS],
+        WeightTyingPolicy::Error,
+    )
+    .is_err());
+    Ok(())
+}
+
+/// Tensors of a model as saved in the safetensors checkpoints of the Hugging Face Hub, without the tied duplicates
+fn hub_checkpoint_tensors(vs: &VarStore, tied_duplicates: &[&str]) -> Vec<(String, Tensor)> {
+    vs.variables()
+        .into_iter()
+        .filter(|(name, _)| !tied_duplicates.contains(&name.as_str()))
+        .collect()
+}
+
+#[test]
+fn bert_masked_lm_tied_head() -> anyhow::Result<()> {
+    let config = BertConfig {
+        hidden_size: 32,
+        intermediate_size: 64,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        vocab_size: 100,
+        ..Default::default()
+    };
+    let tied_weights = BertForMaskedLM::TIED_WEIGHTS;
+    let vs = VarStore::new(Device::Cpu);
+    let model = BertForMaskedLM::new(vs.root(), &config);
+    no_grad(|| {
+        let variables = vs.variables();
+        variables[tied_weights.lm_head].copy_(&variables[tied_weights.input_embeddings]);
+    });
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.safetensors");
+    Tensor::write_safetensors(&hub_checkpoint_tensors(&vs, &[tied_weights.lm_head]), &path)?;
+
+    let mut loaded_vs = VarStore::new(Device::Cpu);
+    let loaded_model = BertForMaskedLM::new(loaded_vs.root(), &config);
+    let status = load_weights_with_tying(
+        &LocalResource::from(path),
+        &mut loaded_vs,
+        &[tied_weights],
+        WeightTyingPolicy::Error,
+    )?;
+    assert_eq!(status, [WeightTyingStatus::MissingLmHead]);
+
+    let input_ids = Tensor::from_slice2(&[[2i64, 15, 27, 3], [2, 41, 3, 0]]);
+    let forward = |model: &BertForMaskedLM| {
+        no_grad(|| {
+            model
+                .forward_t(Some(&input_ids), None, None, None, None, None, None, false)
+                .prediction_scores
+        })
+    };
+    assert!(forward(&loaded_model).allclose(&forward(&model), 1e-5, 1e-6, false));
+    Ok(())
+}
+
+#[test]
+fn albert_masked_lm_tied_head_bias() -> anyhow::Result<()> {
+    let config = AlbertConfig {
+        embedding_size: 8,
+        hidden_size: 16,
+        intermediate_size: 32,
+        num_attention_heads: 2,
+        num_hidden_layers: 1,
+        vocab_size: 50,
+        ..Default::default()
+    };
+    let tied_weights = AlbertForMaskedLM::TIED_WEIGHTS;
+    let (lm_head_bias, checkpoint_bias) = tied_weights.lm_head_bias.unwrap();
+    let vs = VarStore::new(Device::Cpu);
+    let _ = AlbertForMaskedLM::new(vs.root(), &config);
+    let mut tensors = hub_checkpoint_tensors(&vs, &[tied_weights.lm_head, lm_head_bias]);
+    let bias = Tensor::randn([50], (Kind::Float, Device::Cpu));
+    tensors.push((checkpoint_bias.to_string(), bias.copy()));
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("model.safetensors");
+    Tensor::write_safetensors(&tensors, &path)?;
+
+    let mut loaded_vs = VarStore::new(Device::Cpu);
+    let _ = AlbertForMaskedLM::new(loaded_vs.root(), &config);
+    let status = load_weights_with_tying(
+        &LocalResource::from(path),
+        &mut loaded_vs,
+        &[tied_weights],
+        WeightTyingPolicy::Error,
+    )?;
+    assert_eq!(status, [WeightTyingStatus::MissingLmHead]);
+    let variables = loaded_vs.variables();
+    assert!(variables[lm_head_bias].equal(&bias));
+    assert!(variables[tied_weights.lm_head].equal(&variables[tied_weights.input_embeddings]));
+    Ok(())
+}
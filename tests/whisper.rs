@@ -0,0 +1,108 @@
+use rust_bert::whisper::audio::{log_mel_spectrogram, N_FRAMES, SAMPLE_RATE};
+use rust_bert::whisper::{WhisperConfig, WhisperForConditionalGeneration};
+use std::convert::TryFrom;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+fn small_config() -> WhisperConfig {
+    WhisperConfig {
+        d_model: 32,
+        encoder_layers: 2,
+        encoder_attention_heads: 4,
+        encoder_ffn_dim: 64,
+        decoder_layers: 2,
+        decoder_attention_heads: 4,
+        decoder_ffn_dim: 64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn whisper_forward() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = small_config();
+    let model = WhisperForConditionalGeneration::new(vs.root(), &config);
+
+    let samples = (0..2 * SAMPLE_RATE)
+        .map(|index| (2.0 * std::f32::consts::PI * 440.0 * index as f32 / 16000.0).sin())
+        .collect::<Vec<f32>>();
+    let input_features = log_mel_spectrogram(&samples, config.num_mel_bins, device);
+    assert_eq!(
+        input_features.size(),
+        vec![config.num_mel_bins, N_FRAMES as i64]
+    );
+    let input_features = Tensor::stack(&[&input_features, &input_features], 0);
+
+    let encoder_hidden_states = model.encode(&input_features);
+    assert_eq!(encoder_hidden_states.size(), vec![2, 1500, 32]);
+
+    let special_tokens = model.get_special_tokens();
+    let decoder_input_ids = Tensor::from_slice(&[
+        special_tokens.start_of_transcript,
+        special_tokens.language_token("en").unwrap(),
+        special_tokens.transcribe,
+    ])
+    .unsqueeze(0)
+    .expand([2, 3], false);
+    let output = no_grad(|| {
+        model.forward_t(
+            None,
+            Some(&encoder_hidden_states),
+            &decoder_input_ids,
+            None,
+            false,
+        )
+    })?;
+    assert_eq!(output.decoder_output.size(), vec![2, 3, config.vocab_size]);
+
+    let language_probabilities = model.detect_language(&encoder_hidden_states)?;
+    assert_eq!(
+        language_probabilities.size(),
+        vec![2, special_tokens.languages().len() as i64]
+    );
+    let total = language_probabilities.sum_dim_intlist([-1].as_slice(), false, Kind::Double);
+    assert!((total - 1.0).abs().max().double_value(&[]) < 1e-5);
+
+    Ok(())
+}
+
+#[test]
+fn whisper_generation() -> anyhow::Result<()> {
+    let device = Device::Cpu;
+    let vs = nn::VarStore::new(device);
+    let config = small_config();
+    let model = WhisperForConditionalGeneration::new(vs.root(), &config);
+
+    let input_features =
+        log_mel_spectrogram(&[0.0; 16000], config.num_mel_bins, device).unsqueeze(0);
+    let encoder_hidden_states = model.encode(&input_features);
+    let special_tokens = model.get_special_tokens();
+
+    let prompt = Tensor::from_slice(&[
+        special_tokens.start_of_transcript,
+        special_tokens.language_token("fr").unwrap(),
+        special_tokens.transcribe,
+    ])
+    .unsqueeze(0);
+    let output_ids = model.generate(&encoder_hidden_states, &prompt, 5, true)?;
+    assert_eq!(output_ids.size()[0], 1);
+    assert!(output_ids.size()[1] <= 5);
+    let tokens = Vec::<i64>::try_from(output_ids.get(0))?;
+    assert!(special_tokens.is_timestamp(tokens[0]));
+    assert!(special_tokens.timestamp_seconds(tokens[0]) <= 1.0);
+
+    let prompt = Tensor::from_slice(&[
+        special_tokens.start_of_transcript,
+        special_tokens.language_token("en").unwrap(),
+        special_tokens.transcribe,
+        special_tokens.no_timestamps,
+    ])
+    .unsqueeze(0);
+    let output_ids = model.generate(&encoder_hidden_states, &prompt, 5, false)?;
+    let tokens = Vec::<i64>::try_from(output_ids.get(0))?;
+    assert!(tokens
+        .iter()
+        .all(|token| !special_tokens.is_timestamp(*token)));
+
+    Ok(())
+}